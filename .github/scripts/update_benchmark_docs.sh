@@ -21,6 +21,7 @@ BENCHMARK_FILE="$DOCS_DIR/benchmark.md"
 SUMMARY_FILE="$RESULTS_DIR/summary.json"
 TOOL_RESULTS_FILE="$RESULTS_DIR/tool_tests/tools_benchmark_results.json"
 TOOL_SUMMARY_FILE="$RESULTS_DIR/tool_tests/summary.json"
+TOOL_REPORT_FILE="$RESULTS_DIR/tool_tests/tool_report.md"
 
 # Check if results exist
 if [ ! -d "$RESULTS_DIR" ]; then
@@ -240,6 +241,12 @@ if [ -f "$BENCHMARK_FILE" ]; then
     # Insert the new content with test details
     echo "$MARKDOWN_CONTENT$TEST_DETAILS"
 
+    # Append the per-tool accuracy report if the run produced one
+    if [ -f "$TOOL_REPORT_FILE" ]; then
+      echo ""
+      cat "$TOOL_REPORT_FILE"
+    fi
+
     # Read from the end marker to the end of file
     sed -n '/<!-- END_BENCHMARK_RESULTS -->/,$p' "$BENCHMARK_FILE"
   } > "${BENCHMARK_FILE}.tmp"
@@ -4,10 +4,13 @@ import { render } from "ink";
 import App from "./components/App.js";
 import path from "path";
 import { fileURLToPath } from "url";
-import { spawnBackend } from "./services/backend.js";
+import { spawnBackend, TUI_CAPABILITIES } from "./services/backend.js";
 import fs from "fs";
+import { spawnSync } from "child_process";
 import { createRequire } from "module";
 import { BackendService } from "./services/backend.js";
+import { ToolStatusUpdate } from "./types/index.js";
+import { captureMouse, isMouseEnabledFromEnv } from "./utils/mouse.js";
 
 // Parse command line arguments
 const args = [...process.argv.slice(2)];
@@ -27,20 +30,31 @@ Usage: oli [options] [prompt]
 oli - starts an interactive session with model selection by default
 
 Arguments:
-  prompt                          Your prompt (requires -m/--model if specified)
+  prompt                          Your prompt
 
 Options:
-  -p, --print                     Print output to stdout (requires -m/--model)
-  -m, --model <name>              Select a model by name or ID
+  -p, --print, --prompt           Run the prompt without the interface, printing the
+                                  answer to stdout and tool progress to stderr
+  -m, --model <name>              Select a model by name or ID (defaults to the only
+                                  model with an API key in -p mode)
+  --output-format <text|json>     In -p mode, print the answer (text) or a JSON record
+                                  of the turn (default: text)
   -l, --list                      List all available models
+  --accessible                    Screen-reader friendly output (also OLI_ACCESSIBLE=1)
   -h, --help                      Show this help message
   -v, --version                   Show version information
 
+Commands:
+  config check [path]             Check ~/.oli/config.toml (or OLI_CONFIG) for errors
+
 Examples:
   oli                             Start interactive session with model selection
   oli -m gpt-4o                   Start interactive session with specified model
   oli -m gpt-4o "What is TypeScript?"   Run query with specified model
   oli -m gpt-4o -p "Hello world"        Run in non-interactive mode
+  oli -p "fix the failing test" || exit 1   Script with the exit status
+  oli -p --output-format json "run the tests" | jq .usage   Parse the result
+  cat error.log | oli -m gpt-4o -p "explain this failure"   Attach piped input
   oli -l                          List all available models
   `);
   process.exit(0);
@@ -57,17 +71,29 @@ if (args.includes("--version") || args.includes("-v")) {
 // Parse command line args
 let printMode = false;
 let listModels = false;
+let accessible = false;
 let selectedModelName: string | null = null;
+let outputFormat: "text" | "json" = "text";
 let prompt = "";
 
 // Process arguments
 for (let i = 0; i < args.length; i++) {
   const arg = args[i];
 
-  if (arg === "--print" || arg === "-p") {
+  if (arg === "--print" || arg === "--prompt" || arg === "-p") {
     printMode = true;
   } else if (arg === "--list" || arg === "-l") {
     listModels = true;
+  } else if (arg === "--output-format") {
+    const format = args[i + 1];
+    if (format !== "text" && format !== "json") {
+      console.error("Error: --output-format must be text or json");
+      process.exit(1);
+    }
+    outputFormat = format;
+    i++; // Skip the format
+  } else if (arg === "--accessible") {
+    accessible = true;
   } else if (arg === "--model" || arg === "-m") {
     if (i + 1 < args.length) {
       selectedModelName = args[i + 1];
@@ -82,14 +108,17 @@ for (let i = 0; i < args.length; i++) {
   }
 }
 
+// Startup diagnostics go to stderr in print mode, keeping stdout to the answer
+const info = printMode ? console.error : console.log;
+
 // Setup environment for the app
-console.log(`Current directory: ${process.cwd()}`);
-console.log(`Script directory: ${__dirname}`);
+info(`Current directory: ${process.cwd()}`);
+info(`Script directory: ${__dirname}`);
 
 // Check for environment variable first
 const envBackendPath = process.env.BACKEND_BIN_PATH;
 if (envBackendPath) {
-  console.log(`Environment provided backend path: ${envBackendPath}`);
+  info(`Environment provided backend path: ${envBackendPath}`);
 }
 
 // First check if BACKEND_BIN_PATH is set - this is crucial
@@ -97,7 +126,7 @@ const potentialPaths = [];
 
 // Always prioritize environment variable if provided
 if (envBackendPath) {
-  console.log(`Using environment-provided backend path: ${envBackendPath}`);
+  info(`Using environment-provided backend path: ${envBackendPath}`);
   potentialPaths.push(envBackendPath);
 }
 
@@ -122,16 +151,16 @@ let backendPath = potentialPaths[0];
 let backendFound = false;
 
 for (const p of potentialPaths) {
-  console.log(`Checking backend path: ${p}`);
+  info(`Checking backend path: ${p}`);
   try {
     const { accessSync, constants } = fs;
     accessSync(p, constants.X_OK);
     backendPath = p;
     backendFound = true;
-    console.log(`Using backend at: ${backendPath}`);
+    info(`Using backend at: ${backendPath}`);
     break;
   } catch {
-    console.log(`Backend not found at: ${p}`);
+    info(`Backend not found at: ${p}`);
   }
 }
 
@@ -142,8 +171,15 @@ if (!backendFound) {
   process.exit(1);
 }
 
-// Launch the Rust backend as a child process
-const backend = spawnBackend(backendPath);
+// `oli config check` only validates the config file, without starting a session
+if (args[0] === "config") {
+  const result = spawnSync(backendPath, args, { stdio: "inherit" });
+  process.exit(result.status ?? 1);
+}
+
+// Launch the Rust backend as a child process. Print mode has nobody to review
+// edits or answer permission prompts, so only the interactive UI declares it does.
+const backend = spawnBackend(backendPath, printMode ? undefined : TUI_CAPABILITIES);
 
 // Define model interface
 interface Model {
@@ -189,6 +225,48 @@ async function selectModel(backend: BackendService, modelName: string): Promise<
   }
 }
 
+// Largest amount of piped input read in print mode (the backend truncates further)
+const MAX_STDIN_BYTES = 1024 * 1024;
+
+// Read text piped into print mode, e.g. `cat error.log | oli -p "explain"`
+async function readPipedInput(): Promise<string | null> {
+  if (process.stdin.isTTY) {
+    return null;
+  }
+
+  const chunks: Buffer[] = [];
+  let size = 0;
+  for await (const chunk of process.stdin) {
+    const buffer = Buffer.isBuffer(chunk) ? chunk : Buffer.from(chunk);
+    chunks.push(buffer);
+    size += buffer.length;
+    if (size >= MAX_STDIN_BYTES) {
+      break;
+    }
+  }
+
+  const input = Buffer.concat(chunks).toString("utf8");
+  return input.trim() ? input : null;
+}
+
+// Show each finished tool call and progress message of a print-mode run on stderr
+async function reportProgress(backend: BackendService): Promise<void> {
+  backend.on("tool_status", ({ type, execution }: ToolStatusUpdate) => {
+    if (type === "updated" && execution.status !== "running") {
+      const mark = execution.status === "success" ? "⏺" : "✗";
+      console.error(`${mark} ${execution.name}: ${execution.message}`);
+    }
+  });
+  backend.on("processing_progress", (params) => {
+    if (params.message) console.error(params.message);
+  });
+  try {
+    await backend.subscribe("tool_status");
+  } catch {
+    // The answer is still printed without progress
+  }
+}
+
 // Function to format and print models
 function formatAndPrintModels(models: Model[]): void {
   console.log("\nAvailable Models:");
@@ -275,6 +353,16 @@ async function main() {
       process.exit(0);
     }
 
+    // Print mode without -m uses the only model with an API key, if there is one
+    if (printMode && !selectedModelName) {
+      const quickStart = await backend.call("get_quick_start");
+      const model = quickStart.quick_start as { model: string; source: string } | null;
+      if (model) {
+        selectedModelName = model.model;
+        console.error(`Using ${model.model} via ${model.source}; pass -m to choose another model`);
+      }
+    }
+
     // Check for required -m/--model flag for prompt and print mode
     if ((prompt || printMode) && !selectedModelName) {
       console.error("Error: The -m/--model flag is required when using a prompt or -p/--print mode");
@@ -298,19 +386,43 @@ async function main() {
         process.exit(1);
       }
 
+      let exitCode = 0;
       try {
+        await reportProgress(backend);
+        const stdin = await readPipedInput();
         const result = await backend.call("run", {
           prompt,
-          model_index: selectedModelIndex
+          model_index: selectedModelIndex,
+          ...(stdin ? { stdin } : {}),
         });
-        console.log(result.response);
-        backend.kill();
-        process.exit(0);
+        if (outputFormat === "text") {
+          console.log(result.response);
+        }
       } catch (error) {
         console.error("Error:", error instanceof Error ? error.message : String(error));
-        backend.kill();
-        process.exit(1);
+        exitCode = 1;
+      }
+
+      // One JSON record of the turn, whether it succeeded or failed
+      if (outputFormat === "json") {
+        const { record } = await backend.call("get_turn_record");
+        console.log(JSON.stringify(record));
       }
+
+      // Summarize the run to stderr, keeping stdout to the response alone
+      if (exitCode === 0) {
+        try {
+          const summary = await backend.call("summarize_session", {});
+          if (summary.summary) {
+            console.error(`\n${summary.summary}\nAppended to ${summary.path}`);
+          }
+        } catch (error) {
+          console.error("Could not write the session summary:", error instanceof Error ? error.message : String(error));
+        }
+      }
+
+      backend.kill();
+      process.exit(exitCode);
     }
 
     // Handle interactive mode with model and prompt, model only, or default
@@ -354,7 +466,8 @@ function startInteractiveMode(
   // Create the app element with or without model selection
   const props: any = {
     backend: backend,
-    initialPrompt: initialPrompt
+    initialPrompt: initialPrompt,
+    accessible: accessible
   };
 
   // Only pass initialModelIndex if specified (undefined means show model selection)
@@ -367,16 +480,22 @@ function startInteractiveMode(
   // Clear the terminal before rendering anything
   process.stdout.write("\x1B[2J\x1B[H\x1B[J");
 
+  // Read the mouse wheel, unless turned off, keeping its reports out of the input box
+  const stdin =
+    process.stdin.isTTY && isMouseEnabledFromEnv()
+      ? captureMouse(process.stdin, process.stdout)
+      : process.stdin;
+
   // Render the React app with custom options
   const { waitUntilExit } = render(app, {
     // Disable console patching to avoid interference
     patchConsole: false,
     // Use standard IO
-    stdin: process.stdin,
+    stdin,
     stdout: process.stdout,
     stderr: process.stderr,
-    // Allow Ctrl+C to exit
-    exitOnCtrlC: true,
+    // Ctrl+C interrupts a running task first; the app exits when it's idle
+    exitOnCtrlC: false,
   });
 
   // Handle graceful shutdown
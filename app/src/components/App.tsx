@@ -1,27 +1,53 @@
-import React, { useEffect, useState, useCallback, useMemo } from "react";
-import { Box } from "ink";
+import React, { useEffect, useState, useCallback, useMemo, useRef } from "react";
+import { Box, useApp, useInput } from "ink";
 import { BackendService } from "../services/backend.js";
 import ChatInterface from "./ChatInterface.js";
 import ModelSelector from "./ModelSelector.js";
 import StatusBar from "./StatusBar.js";
-// Theme is used by imported components
+import StagingView from "./StagingView.js";
+import RenameView from "./RenameView.js";
+import DiffReviewView from "./DiffReviewView.js";
+import PermissionPrompt, { PermissionAnswer } from "./PermissionPrompt.js";
+import OllamaPullView from "./OllamaPullView.js";
+import { applyTheme, isThemeName } from "../styles/themes.js";
 
-import { AppState, ToolExecution, ToolStatusUpdate, Model } from "../types/index.js";
+import {
+  AppState,
+  AskAnswer,
+  EditReviewRequest,
+  PermissionRequest,
+  IndexStatus,
+  ToolExecution,
+  ToolQueue,
+  ToolStatusUpdate,
+  Model,
+  QuickStart,
+  SessionState,
+} from "../types/index.js";
 import { isCommand } from "../utils/commandUtils.js";
 import {
   executeCommand,
   processUserMessage,
 } from "../utils/commandHandlers.js";
+import { isAccessibleFromEnv } from "../utils/accessibility.js";
+import { DEFAULT_SCROLL_BINDINGS, ScrollBindings } from "../utils/scroll.js";
+import { createMessage } from "../utils/messageUtils.js";
 
 // App props interface
 interface AppProps {
   backend: BackendService;
   initialPrompt?: string | null;
   initialModelIndex?: number;
+  accessible?: boolean;
 }
 
 // Main app component
-const App: React.FC<AppProps> = ({ backend, initialPrompt, initialModelIndex }) => {
+const App: React.FC<AppProps> = ({
+  backend,
+  initialPrompt,
+  initialModelIndex,
+  accessible = false,
+}) => {
   // App state
   const [state, setState] = useState<AppState>({
     models: [],
@@ -32,6 +58,7 @@ const App: React.FC<AppProps> = ({ backend, initialPrompt, initialModelIndex })
     backendConnected: false,
     appMode: initialModelIndex !== undefined ? "chat" : "setup", // Only skip setup if model explicitly provided
     useAgent: true, // Agent mode is always enabled
+    accessible: accessible || isAccessibleFromEnv(),
   });
 
   // Tool executions state - separate to avoid re-rendering the entire app on tool updates
@@ -39,12 +66,103 @@ const App: React.FC<AppProps> = ({ backend, initialPrompt, initialModelIndex })
     Map<string, ToolExecution>
   >(new Map());
 
+  // Tool calls of the current round, from tool_queue notifications
+  const [toolQueue, setToolQueue] = useState<ToolQueue | null>(null);
+
+  // Text of the running task's answer as it is generated, shown until it finishes
+  const [streamingText, setStreamingText] = useState("");
+
+  // Workspace index progress, shown in the status bar for large repositories
+  const [indexStatus, setIndexStatus] = useState<IndexStatus | null>(null);
+
   // Track if initial prompt has been processed
   const [initialPromptProcessed, setInitialPromptProcessed] = useState<boolean>(false);
 
   // UI state
   const [showShortcuts, setShowShortcuts] = useState(false);
 
+  // Draft and scroll position restored from the last session, and the latest values to save
+  const [restoredSession, setRestoredSession] = useState<SessionState | null>(null);
+  const sessionRef = useRef<SessionState>({ draft: "", scroll_offset: 0 });
+  const savedSessionRef = useRef<string>(JSON.stringify(sessionRef.current));
+
+  // Prompts submitted in this and earlier sessions, oldest first
+  const [promptHistory, setPromptHistory] = useState<string[]>([]);
+  const [scrollBindings, setScrollBindings] = useState<ScrollBindings>(
+    DEFAULT_SCROLL_BINDINGS,
+  );
+
+  // The model in OLI_OLLAMA_MODEL when Ollama doesn't have it yet
+  const [missingOllamaModel, setMissingOllamaModel] = useState<string | null>(null);
+
+  // An Edit or Write waiting for the user to review its hunks, and how to answer
+  const [editReview, setEditReview] = useState<{
+    request: EditReviewRequest;
+    respond: (result: Record<string, unknown>) => void;
+    receivedAt: number;
+  } | null>(null);
+
+  // Show edits the backend asks to review before they run
+  useEffect(() => {
+    const handleReviewEdit = (
+      params: EditReviewRequest,
+      respond: (result: Record<string, unknown>) => void,
+    ) => {
+      setEditReview({ request: params, respond, receivedAt: Date.now() });
+    };
+
+    backend.on("oli/reviewEdit", handleReviewEdit);
+    return () => {
+      backend.off("oli/reviewEdit", handleReviewEdit);
+    };
+  }, [backend]);
+
+  // A tool call waiting for the user's permission, and how to answer
+  const [permissionPrompt, setPermissionPrompt] = useState<{
+    request: PermissionRequest;
+    respond: (result: Record<string, unknown>) => void;
+    receivedAt: number;
+  } | null>(null);
+
+  // Ask before running the tool calls the backend needs permission for
+  useEffect(() => {
+    const handleRequestPermission = (
+      params: PermissionRequest,
+      respond: (result: Record<string, unknown>) => void,
+    ) => {
+      setPermissionPrompt({ request: params, respond, receivedAt: Date.now() });
+    };
+
+    backend.on("oli/requestPermission", handleRequestPermission);
+    return () => {
+      backend.off("oli/requestPermission", handleRequestPermission);
+    };
+  }, [backend]);
+
+  const handleSessionChange = useCallback((draft: string, scrollOffset: number) => {
+    sessionRef.current = { draft, scroll_offset: scrollOffset };
+  }, []);
+
+  // Save the draft every few seconds when it has changed
+  useEffect(() => {
+    if (!state.backendConnected) return;
+
+    const timer = setInterval(() => {
+      const serialized = JSON.stringify(sessionRef.current);
+      if (serialized === savedSessionRef.current) return;
+
+      savedSessionRef.current = serialized;
+      backend
+        .call("save_session_state", { ...sessionRef.current })
+        .catch(() => {
+          // Saving is best effort; try again on the next change
+          savedSessionRef.current = "";
+        });
+    }, 5000);
+
+    return () => clearInterval(timer);
+  }, [state.backendConnected, backend]);
+
   // Subscribe to tool status events
   useEffect(() => {
     // Setup tool status subscription when backend is available
@@ -164,6 +282,125 @@ const App: React.FC<AppProps> = ({ backend, initialPrompt, initialModelIndex })
         ...(initialPrompt || initialModelIndex !== undefined ? { appMode: "chat" } : {}),
       }));
 
+      // Skip setup when exactly one provider is usable without it
+      if (!initialPrompt && initialModelIndex === undefined) {
+        try {
+          const result = await backend.call("get_quick_start");
+          const quickStart = result.quick_start as QuickStart | null;
+          setMissingOllamaModel((result.missing_ollama_model as string | null) ?? null);
+          if (quickStart) {
+            setState((prev) => ({
+              ...prev,
+              selectedModel: quickStart.model_index,
+              appMode: "chat",
+              messages: [
+                ...prev.messages,
+                createMessage("system", result.notice as string),
+              ],
+            }));
+          }
+        } catch (error) {
+          // Quick start unavailable, choose a model in setup
+        }
+      }
+
+      // Restore a draft left behind by a crash or restart
+      try {
+        const result = await backend.call("load_session_state");
+        const saved = result.state as SessionState | null;
+        if (saved && !initialPrompt) {
+          setRestoredSession(saved);
+          sessionRef.current = { draft: saved.draft, scroll_offset: saved.scroll_offset };
+          savedSessionRef.current = JSON.stringify(sessionRef.current);
+
+          if (saved.draft.trim()) {
+            setState((prev) => ({
+              ...prev,
+              messages: [
+                ...prev.messages,
+                {
+                  id: `system-draft-${Date.now()}`,
+                  role: "system",
+                  content: "Draft restored",
+                  timestamp: Date.now(),
+                },
+              ],
+            }));
+          }
+        }
+      } catch (error) {
+        // No saved session to restore
+      }
+
+      // Load earlier prompts so Up in the input box can recall them
+      try {
+        const result = await backend.call("get_prompt_history");
+        setPromptHistory((result.entries as string[] | undefined) ?? []);
+      } catch (error) {
+        // Start with an empty history
+      }
+
+      // Warn when another oli instance is already working in this workspace
+      try {
+        const status = await backend.call("get_workspace_status");
+        const warning = status.warning as string | null;
+        if (warning) {
+          setState((prev) => ({
+            ...prev,
+            readOnly: Boolean(status.read_only),
+            messages: [
+              ...prev.messages,
+              {
+                id: `system-workspace-${Date.now()}`,
+                role: "system",
+                content: `[Warning] ${warning}`,
+                timestamp: Date.now(),
+              },
+            ],
+          }));
+        }
+      } catch (error) {
+        // Workspace lock unavailable, nothing to warn about
+      }
+
+      // The theme is chosen in config.toml, which only the backend reads
+      try {
+        const result = await backend.call("get_theme");
+        const theme = result.theme as string;
+        if (isThemeName(theme)) {
+          applyTheme(theme);
+          setState((prev) => ({ ...prev, theme }));
+        }
+      } catch (error) {
+        // Theme unavailable, keep the default
+      }
+
+      // Scroll keys may be rebound in config.toml, which only the backend reads
+      try {
+        const result = await backend.call("get_scroll_bindings");
+        setScrollBindings(result.bindings as ScrollBindings);
+      } catch (error) {
+        // Bindings unavailable, keep the defaults
+      }
+
+      // Presets may be chosen in config.toml before the UI connected
+      try {
+        const result = await backend.call("get_sampling_presets");
+        setState((prev) => ({ ...prev, samplingPresets: result.presets }));
+      } catch (error) {
+        // Presets unavailable, the status bar shows none
+      }
+
+      // Indexing may have started before the UI connected
+      try {
+        const result = await backend.call("get_index_status");
+        if (result.status) {
+          setIndexStatus(result.status as IndexStatus);
+        }
+      } catch (error) {
+        // Index status unavailable, index_progress notifications will follow
+      }
+
       // If an initial model was specified, set it in the backend
       if (initialModelIndex !== undefined && initialModelIndex !== 0) {
         try {
@@ -196,7 +433,8 @@ const App: React.FC<AppProps> = ({ backend, initialPrompt, initialModelIndex })
         // Process the initial prompt with the selected model
         backend.call("run", {
           prompt: initialPrompt,
-          model_index: initialModelIndex
+          model_index: initialModelIndex,
+          stream: true,
         }).catch((error) => {
           // Failed to process initial prompt, but continue
         });
@@ -222,9 +460,15 @@ const App: React.FC<AppProps> = ({ backend, initialPrompt, initialModelIndex })
 
     // Register event listeners for backend notifications
     backend.on("processing_started", (params) => {
+      setToolQueue(null);
+      setStreamingText("");
       setState((prev) => ({
         ...prev,
         isProcessing: true,
+        // Announce state changes as their own lines for screen readers
+        ...(prev.accessible
+          ? { messages: [...prev.messages, createMessage("system", "Working")] }
+          : {}),
         // If agent mode is specified in the event, update state
         ...(params.use_agent !== undefined
           ? { useAgent: params.use_agent }
@@ -232,6 +476,21 @@ const App: React.FC<AppProps> = ({ backend, initialPrompt, initialModelIndex })
       }));
     });
 
+    backend.on("tool_queue", (params) => {
+      setToolQueue(params as ToolQueue);
+    });
+
+    // Deltas of a run carry its task id; query_model deltas are handled by their caller
+    backend.on("content_delta", (params) => {
+      if (params.task_id) {
+        setStreamingText((prev) => prev + (params.delta as string));
+      }
+    });
+
+    backend.on("index_progress", (params) => {
+      setIndexStatus(params as IndexStatus);
+    });
+
     backend.on("processing_progress", (params) => {
       // Add progress message if it's not already in the list
       setState((prev) => {
@@ -256,13 +515,18 @@ const App: React.FC<AppProps> = ({ backend, initialPrompt, initialModelIndex })
     });
 
     backend.on("processing_complete", () => {
+      setStreamingText("");
       setState((prev) => ({
         ...prev,
         isProcessing: false,
+        ...(prev.accessible
+          ? { messages: [...prev.messages, createMessage("system", "Finished")] }
+          : {}),
       }));
     });
 
     backend.on("processing_error", (params) => {
+      setStreamingText("");
       setState((prev) => ({
         ...prev,
         isProcessing: false,
@@ -375,6 +639,27 @@ const App: React.FC<AppProps> = ({ backend, initialPrompt, initialModelIndex })
       if (backend) {
         backend
           .call("set_selected_model", { model_index: index })
+          .then((result) => {
+            // Show Ollama problems (server down, model missing, old version) right away
+            const diagnostics = result.diagnostics as
+              | { healthy: boolean; report: string }
+              | null
+              | undefined;
+            if (diagnostics && !diagnostics.healthy) {
+              setState((prev) => ({
+                ...prev,
+                messages: [
+                  ...prev.messages,
+                  {
+                    id: `system-${Date.now()}`,
+                    role: "system",
+                    content: diagnostics.report,
+                    timestamp: Date.now(),
+                  },
+                ],
+              }));
+            }
+          })
           .catch((error) => {
             console.error("Failed to update selected model on backend:", error);
           });
@@ -393,6 +678,144 @@ const App: React.FC<AppProps> = ({ backend, initialPrompt, initialModelIndex })
     }));
   }, []);
 
+  // Leave the /stage walk-through and report what was staged
+  const handleStagingDone = useCallback((summary: string) => {
+    setState((prev) => ({
+      ...prev,
+      staging: false,
+      messages: [...prev.messages, createMessage("system", summary)],
+    }));
+  }, []);
+
+  // Answer an edit review with the accepted hunks and report the decision
+  const handleEditReviewDone = useCallback(
+    (accepted: string[]) => {
+      if (!editReview) return;
+      const { request, respond } = editReview;
+      respond({ accepted });
+      setEditReview(null);
+
+      const total = request.hunks.length;
+      const summary =
+        accepted.length === total
+          ? `Accepted all ${total} hunks of ${request.path}`
+          : accepted.length === 0
+            ? `Rejected the ${request.tool} of ${request.path}`
+            : `Accepted ${accepted.length} of ${total} hunks of ${request.path}`;
+      setState((prev) => ({
+        ...prev,
+        messages: [...prev.messages, createMessage("system", summary)],
+      }));
+    },
+    [editReview],
+  );
+
+  // Answer a permission prompt and report the decision
+  const handlePermissionDone = useCallback(
+    (answer: PermissionAnswer) => {
+      if (!permissionPrompt) return;
+      const { request, respond } = permissionPrompt;
+      respond({ ...answer });
+      setPermissionPrompt(null);
+
+      const summary = !answer.allow
+        ? `Denied ${request.tool}`
+        : answer.remember === "command"
+          ? `Always allowed this ${request.tool} command`
+          : answer.remember === "tool"
+            ? `Always allowed ${request.tool}`
+            : `Allowed ${request.tool}`;
+      setState((prev) => ({
+        ...prev,
+        messages: [...prev.messages, createMessage("system", summary)],
+      }));
+    },
+    [permissionPrompt],
+  );
+
+  // Start downloading an Ollama model from the setup screen
+  const handlePullModel = useCallback((model: string) => {
+    setState((prev) => ({ ...prev, pullingModel: model }));
+  }, []);
+
+  // Select a model pulled into Ollama, or report why the pull failed
+  const handlePullDone = useCallback(
+    (result: { models: Model[]; modelIndex: number } | null, summary: string) => {
+      if (result) {
+        setMissingOllamaModel(null);
+        backend
+          .call("set_selected_model", { model_index: result.modelIndex })
+          .catch((error) => {
+            console.error("Failed to select the pulled model:", error);
+          });
+      }
+      setState((prev) => ({
+        ...prev,
+        pullingModel: null,
+        ...(result
+          ? { models: result.models, selectedModel: result.modelIndex }
+          : {}),
+        messages: [...prev.messages, createMessage("system", summary)],
+      }));
+    },
+    [backend],
+  );
+
+  // Leave the /rename review and report whether the rename was applied
+  const handleRenameDone = useCallback((summary: string) => {
+    setState((prev) => ({
+      ...prev,
+      renaming: null,
+      messages: [...prev.messages, createMessage("system", summary)],
+    }));
+  }, []);
+
+  // Answer a side question (Ctrl+A) without touching the main conversation
+  const handleAsk = useCallback(
+    async (question: string) =>
+      (await backend.call("ask", { question })) as unknown as AskAnswer,
+    [backend],
+  );
+
+  // Files matching an @-mention typed in the input box
+  const handleSearchFiles = useCallback(
+    async (query: string) => {
+      const result = await backend.call("complete_file_path", { query });
+      return (result.paths as string[] | undefined) ?? [];
+    },
+    [backend],
+  );
+
+  // Remember a submitted prompt here and in the history file
+  const handleRecordPrompt = useCallback(
+    (prompt: string) => {
+      if (!prompt.trim()) return;
+      setPromptHistory((prev) =>
+        prev[prev.length - 1] === prompt ? prev : [...prev, prompt],
+      );
+      backend.call("add_prompt_history", { prompt }).catch(() => {
+        // The prompt stays in this session's history
+      });
+    },
+    [backend],
+  );
+
+  // Add a side question and its answer to the main conversation
+  const handlePromoteAnswer = useCallback(
+    async (answer: AskAnswer) => {
+      await backend.call("promote_ask", { id: answer.id });
+      setState((prev) => ({
+        ...prev,
+        messages: [
+          ...prev.messages,
+          createMessage("user", `(Side question) ${answer.question}`),
+          createMessage("assistant", answer.answer ?? ""),
+        ],
+      }));
+    },
+    [backend],
+  );
+
   // Memoize command execution handler to reduce rerenders
   const handleExecuteCommand = useCallback(
     (command: string) => {
@@ -473,14 +896,18 @@ const App: React.FC<AppProps> = ({ backend, initialPrompt, initialModelIndex })
         onSelect={handleModelSelect}
         onConfirm={handleModelConfirm}
         isLoading={!state.backendConnected || state.models.length === 0}
+        missingOllamaModel={missingOllamaModel}
+        onPull={handlePullModel}
       />
     ),
     [
       state.models,
       state.selectedModel,
       state.backendConnected,
+      missingOllamaModel,
       handleModelSelect,
       handleModelConfirm,
+      handlePullModel,
     ],
   );
 
@@ -525,6 +952,18 @@ const App: React.FC<AppProps> = ({ backend, initialPrompt, initialModelIndex })
     }
   }, [state.isProcessing, backend]);
 
+  // Ctrl+C stops the running task, and exits when nothing is running
+  const { exit } = useApp();
+  useInput((input, key) => {
+    if (key.ctrl && input === "c") {
+      if (state.isProcessing) {
+        handleInterrupt();
+      } else {
+        exit();
+      }
+    }
+  });
+
   // Clean up message history to prevent duplicates
   const filteredMessages = useMemo(() => {
     // Track seen user messages to remove duplicates
@@ -564,12 +1003,36 @@ const App: React.FC<AppProps> = ({ backend, initialPrompt, initialModelIndex })
         onClearHistory={handleClearHistory}
         onExecuteCommand={handleExecuteCommand}
         toolExecutions={toolExecutions}
+        toolQueue={toolQueue}
+        streamingText={streamingText}
+        showTimestamps={state.showTimestamps}
+        accessible={state.accessible}
+        restoredSession={restoredSession}
+        onSessionChange={handleSessionChange}
+        onAsk={handleAsk}
+        onPromoteAnswer={handlePromoteAnswer}
+        onSearchFiles={handleSearchFiles}
+        promptHistory={promptHistory}
+        onRecordPrompt={handleRecordPrompt}
+        scrollBindings={scrollBindings}
       />
     ),
     [
+      handleAsk,
+      handlePromoteAnswer,
+      handleSearchFiles,
+      promptHistory,
+      handleRecordPrompt,
+      scrollBindings,
       filteredMessages,
+      restoredSession,
+      handleSessionChange,
+      state.showTimestamps,
+      state.accessible,
       state.isProcessing,
       toolExecutions,
+      toolQueue,
+      streamingText,
       handleUserInput,
       handleInterrupt,
       showShortcuts,
@@ -586,10 +1049,27 @@ const App: React.FC<AppProps> = ({ backend, initialPrompt, initialModelIndex })
         isProcessing={state.isProcessing}
         backendConnected={state.backendConnected}
         showShortcuts={showShortcuts}
+        accessible={state.accessible}
+        readOnly={state.readOnly}
+        preset={
+          state.samplingPresets?.modes[state.useAgent ? "agent" : "chat"] ??
+          state.samplingPresets?.default ??
+          null
+        }
+        indexStatus={indexStatus}
+        sessionCost={state.sessionCost ?? null}
+        contextPercent={state.contextPercent ?? null}
       />
     ),
     [
       state.models,
+      state.accessible,
+      state.readOnly,
+      state.samplingPresets,
+      state.useAgent,
+      indexStatus,
+      state.sessionCost,
+      state.contextPercent,
       state.selectedModel,
       state.isProcessing,
       state.backendConnected,
@@ -600,15 +1080,52 @@ const App: React.FC<AppProps> = ({ backend, initialPrompt, initialModelIndex })
   // Render with memoized components for better performance
   if (state.appMode === "setup") {
     // Setup mode - directly render the model selector without any container
-    return modelSelectorComponent;
+    return state.pullingModel ? (
+      <OllamaPullView
+        backend={backend}
+        model={state.pullingModel}
+        onDone={handlePullDone}
+      />
+    ) : (
+      modelSelectorComponent
+    );
   }
 
-  // Single column layout with component-based architecture
+  // Single column layout with component-based architecture. Keyed by the theme
+  // so memoized components are drawn again in its colors when it changes.
   return (
-    <Box flexDirection="column" width="100%" height="100%">
+    <Box key={state.theme} flexDirection="column" width="100%" height="100%">
       {/* Chat area */}
       <Box flexGrow={1} flexDirection="column">
-        {chatInterfaceComponent}
+        {permissionPrompt ? (
+          <PermissionPrompt
+            key={permissionPrompt.receivedAt}
+            request={permissionPrompt.request}
+            onDone={handlePermissionDone}
+          />
+        ) : editReview ? (
+          <DiffReviewView
+            key={editReview.receivedAt}
+            request={editReview.request}
+            onDone={handleEditReviewDone}
+          />
+        ) : state.pullingModel ? (
+          <OllamaPullView
+            backend={backend}
+            model={state.pullingModel}
+            onDone={handlePullDone}
+          />
+        ) : state.staging ? (
+          <StagingView backend={backend} onDone={handleStagingDone} />
+        ) : state.renaming ? (
+          <RenameView
+            backend={backend}
+            request={state.renaming}
+            onDone={handleRenameDone}
+          />
+        ) : (
+          chatInterfaceComponent
+        )}
       </Box>
 
       {/* Status bar */}
@@ -0,0 +1,151 @@
+import React, { useEffect, useState } from "react";
+import { Box, Text, useInput } from "ink";
+import TextInput from "ink-text-input";
+import theme from "../styles/themes.js";
+import { AskAnswer } from "../types/index.js";
+
+// Answer lines shown before the answer is cut off
+const MAX_ANSWER_LINES = 20;
+
+// Component props
+interface AskPanelProps {
+  visible: boolean;
+  onAsk: (question: string) => Promise<AskAnswer>;
+  onPromote: (answer: AskAnswer) => Promise<void>;
+  onClose: () => void;
+}
+
+const errorText = (err: unknown): string =>
+  err instanceof Error ? err.message : String(err);
+
+// Side questions answered with read-only tools while the main task runs. Stays
+// mounted while hidden so a question can finish and be read later.
+const AskPanel: React.FC<AskPanelProps> = ({
+  visible,
+  onAsk,
+  onPromote,
+  onClose,
+}) => {
+  const [question, setQuestion] = useState("");
+  const [pending, setPending] = useState<string | null>(null);
+  const [latest, setLatest] = useState<AskAnswer | null>(null);
+  const [unseen, setUnseen] = useState(false);
+  const [notice, setNotice] = useState<string | null>(null);
+  const [promoted, setPromoted] = useState<Set<string>>(new Set());
+
+  // An answer counts as read once the panel is open
+  useEffect(() => {
+    if (visible && unseen) setUnseen(false);
+  }, [visible, unseen]);
+
+  const ask = async (value: string) => {
+    if (value.trim() === "" || pending) return;
+    setQuestion("");
+    setNotice(null);
+    setPending(value);
+    try {
+      setLatest(await onAsk(value));
+    } catch (err) {
+      setNotice(`Error asking: ${errorText(err)}`);
+    }
+    setPending(null);
+    setUnseen(true);
+  };
+
+  const promote = async () => {
+    if (!latest?.answer || promoted.has(latest.id)) return;
+    try {
+      await onPromote(latest);
+      setPromoted((prev) => new Set(prev).add(latest.id));
+      setNotice("Added to the main conversation");
+    } catch (err) {
+      setNotice(`Error adding the answer: ${errorText(err)}`);
+    }
+  };
+
+  useInput(
+    (_input, key) => {
+      if (key.escape) {
+        onClose();
+      } else if (key.tab) {
+        promote();
+      }
+    },
+    { isActive: visible },
+  );
+
+  if (!visible) {
+    return unseen && !pending ? (
+      <Box paddingX={2}>
+        <Text color={theme.palette.aqua}>
+          Side question answered · Ctrl+A to read it
+        </Text>
+      </Box>
+    ) : null;
+  }
+
+  const lines = (latest?.answer ?? "").split("\n");
+  const hiddenLines = lines.length - MAX_ANSWER_LINES;
+
+  return (
+    <Box
+      flexDirection="column"
+      borderStyle="round"
+      borderColor={theme.palette.aqua}
+      paddingX={1}
+      marginX={2}
+    >
+      <Text bold color={theme.palette.aqua}>
+        Ask · read-only tools, separate from the main conversation
+      </Text>
+
+      {latest && (
+        <Box flexDirection="column" marginY={1}>
+          <Text bold>{latest.question}</Text>
+          {latest.answer !== null ? (
+            <>
+              <Text wrap="wrap">{lines.slice(0, MAX_ANSWER_LINES).join("\n")}</Text>
+              {hiddenLines > 0 && (
+                <Text {...theme.styles.text.dimmed}>
+                  … {hiddenLines} more lines (Tab adds all of it)
+                </Text>
+              )}
+            </>
+          ) : (
+            <Text color={theme.palette.red}>Error: {latest.error}</Text>
+          )}
+          <Text {...theme.styles.text.dimmed}>
+            {latest.model} · {(latest.latency_ms / 1000).toFixed(1)}s
+            {promoted.has(latest.id) ? " · added to the main conversation" : ""}
+          </Text>
+        </Box>
+      )}
+
+      {pending && (
+        <Text {...theme.styles.text.dimmed}>Answering "{pending}"...</Text>
+      )}
+      {notice && <Text color={theme.palette.yellow}>{notice}</Text>}
+
+      <Box flexDirection="row">
+        <Text color={theme.palette.aqua} bold>
+          ?
+        </Text>
+        <Box marginLeft={1} flexGrow={1}>
+          <TextInput
+            value={question}
+            onChange={setQuestion}
+            onSubmit={ask}
+            focus={visible}
+            placeholder="Ask a quick question about the codebase..."
+          />
+        </Box>
+      </Box>
+
+      <Text {...theme.styles.text.dimmed}>
+        Enter ask · Tab add the answer to the main conversation · Esc close
+      </Text>
+    </Box>
+  );
+};
+
+export default AskPanel;
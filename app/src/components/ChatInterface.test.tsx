@@ -0,0 +1,154 @@
+import { EventEmitter } from "events";
+import { PassThrough } from "stream";
+import { afterEach, test } from "node:test";
+import assert from "node:assert/strict";
+import React from "react";
+import { render } from "ink";
+import ChatInterface from "./ChatInterface.js";
+import { Message } from "../types/index.js";
+import { mouseEvents } from "../utils/mouse.js";
+import { ScrollBindings } from "../utils/scroll.js";
+
+// A terminal 24 rows high, leaving 16 rows for the messages
+class FakeStdout extends EventEmitter {
+  columns = 100;
+  rows = 24;
+  frames: string[] = [];
+
+  write = (frame: string) => {
+    this.frames.push(frame);
+    return true;
+  };
+
+  lastFrame = () => this.frames[this.frames.length - 1] ?? "";
+}
+
+// Keys typed into the terminal, read by Ink like a raw-mode TTY
+const fakeStdin = () => {
+  const stdin = new PassThrough({ encoding: "utf8" }) as unknown as NodeJS.ReadStream &
+    PassThrough;
+  stdin.isTTY = true;
+  stdin.setRawMode = () => stdin;
+  stdin.ref = () => stdin;
+  stdin.unref = () => stdin;
+  return stdin;
+};
+
+// 40 one-row messages, the eleventh a prompt
+const messages: Message[] = Array.from({ length: 40 }, (_, i) => ({
+  id: `message-${i}`,
+  role: i === 10 ? "user" : "system",
+  content: i === 10 ? "prompt-10-end" : `msg-${i}-end`,
+  timestamp: 0,
+}));
+
+const settle = () => new Promise((resolve) => setTimeout(resolve, 50));
+
+let unmount: (() => void) | null = null;
+afterEach(() => {
+  unmount?.();
+  unmount = null;
+});
+
+const renderChat = async (scrollBindings?: ScrollBindings) => {
+  const stdout = new FakeStdout();
+  const stdin = fakeStdin();
+  const instance = render(
+    <ChatInterface
+      messages={messages}
+      isProcessing={false}
+      onSubmit={() => {}}
+      scrollBindings={scrollBindings}
+    />,
+    {
+      stdout: stdout as unknown as NodeJS.WriteStream,
+      stdin,
+      debug: true,
+      exitOnCtrlC: false,
+      patchConsole: false,
+    },
+  );
+  unmount = instance.unmount;
+  await settle();
+
+  const press = async (keys: string) => {
+    stdin.write(keys);
+    await settle();
+  };
+  // The first and last message on screen
+  const shown = () => {
+    const frame = stdout.lastFrame();
+    const rows = messages
+      .map((message, i) => (frame.includes(message.content) ? i : -1))
+      .filter((i) => i >= 0);
+    return [rows[0], rows[rows.length - 1]];
+  };
+  // Left-click a terminal row, counting from 1 at the top
+  const click = async (y: number) => {
+    mouseEvents.emit("mouse", { kind: "press", button: 0, x: 5, y, shift: false });
+    await settle();
+  };
+  // The screen line showing a message
+  const lineOf = (content: string) =>
+    stdout
+      .lastFrame()
+      .split("\n")
+      .find((line) => line.includes(content)) ?? "";
+  const marked = () => stdout.lastFrame().includes("▎");
+  return { press, shown, click, lineOf, marked };
+};
+
+test("scroll keys move by lines, half pages and prompts of the terminal's height", async () => {
+  const { press, shown } = await renderChat();
+  assert.deepEqual(shown(), [24, 39]);
+
+  await press("\x15"); // Ctrl+U, half of the 16 rows
+  assert.deepEqual(shown(), [16, 31]);
+
+  await press("\x05"); // Ctrl+E, one row
+  assert.deepEqual(shown(), [17, 32]);
+
+  await press("\x04"); // Ctrl+D
+  assert.deepEqual(shown(), [24, 39]);
+
+  await press("\x1b[1;5A"); // Ctrl+Up, to the prompt
+  assert.deepEqual(shown(), [10, 25]);
+
+  await press("\x1b[5~"); // PageUp, a screen but no further than the top
+  assert.deepEqual(shown(), [0, 15]);
+
+  await press("\x1b[1;5B"); // Ctrl+Down, back to the prompt
+  assert.deepEqual(shown(), [10, 25]);
+});
+
+test("scroll keys follow the configured bindings", async () => {
+  const { press, shown } = await renderChat({ "ctrl+k": "line_up" });
+
+  await press("\x15"); // Ctrl+U isn't bound any more
+  assert.deepEqual(shown(), [24, 39]);
+
+  await press("\x0b"); // Ctrl+K
+  assert.deepEqual(shown(), [23, 38]);
+});
+
+test("clicking a message selects it, and clicking it again clears the selection", async () => {
+  const { press, shown, click, lineOf, marked } = await renderChat();
+
+  // The first row below the padding shows message 24
+  await click(2);
+  assert.match(lineOf("msg-24-end"), /▎/);
+  assert.doesNotMatch(lineOf("msg-25-end"), /▎/);
+  await click(2);
+  assert.equal(marked(), false);
+
+  // Rows follow the scroll position
+  await press("\x19"); // Ctrl+Y, one row up
+  assert.deepEqual(shown(), [23, 38]);
+  await click(3);
+  assert.match(lineOf("msg-24-end"), /▎/);
+
+  // Clicks on the padding, or past the messages, leave the selection alone
+  await click(1);
+  await click(40);
+  assert.match(lineOf("msg-24-end"), /▎/);
+});
@@ -1,26 +1,63 @@
 /* eslint-disable @typescript-eslint/no-unused-vars */
-import React, { useState, useEffect, useCallback } from "react";
-import { Box, Text, useInput } from "ink";
+import React, { useState, useEffect, useCallback, useMemo } from "react";
+import { Box, Text, useInput, useStdin, useStdout } from "ink";
 import TextInput from "ink-text-input";
-import theme from "../styles/gruvbox.js";
+import theme from "../styles/themes.js";
 import ShortcutsPanel from "./ShortcutsPanel.js";
 import CommandPalette from "./CommandPalette.js";
+import FilePicker from "./FilePicker.js";
 import ToolStatusIndicator from "./ToolStatusIndicator.js";
 import StatusDisplay from "./StatusDisplay.js";
 import TaskInterruptionHandler from "./TaskInterruptionHandler.js";
+import EvidencePanel from "./EvidencePanel.js";
+import AskPanel from "./AskPanel.js";
 import { isCommand } from "../utils/commandUtils.js";
+import { describeMessage } from "../utils/accessibility.js";
+import {
+  citedEvidence,
+  evidenceLabel,
+  latestMessageWithEvidence,
+  unresolvedCitations,
+} from "../utils/citations.js";
+import {
+  collapsedPreview,
+  collapseThreshold,
+  isCollapsible,
+  latestCollapsibleId,
+} from "../utils/collapse.js";
+import { editInExternalEditor } from "../utils/externalEditor.js";
+import { activeMention, insertMention } from "../utils/mentions.js";
+import { MouseEvent, mouseEvents } from "../utils/mouse.js";
+import {
+  applyScrollAction,
+  DEFAULT_SCROLL_BINDINGS,
+  messageAtRow,
+  ScrollBindings,
+  scrollActionFor,
+  scrollWindow,
+  transcriptLayout,
+  wrappedLines,
+} from "../utils/scroll.js";
 
 // Import types
-import { Message, ToolExecution } from "../types/index.js";
+import {
+  AskAnswer,
+  Message,
+  SessionState,
+  ToolExecution,
+  ToolQueue,
+} from "../types/index.js";
 
 // Messages Display component - memoized to only render when messages change
 interface MessagesDisplayProps {
   visibleMessages: Message[];
   formatMessage: (message: Message) => React.ReactNode;
+  height?: number; // Rows to clip the messages to, when they don't all fit
+  hiddenBelow: number; // Rows of the last message scrolled out below
 }
 
 const MessagesDisplay: React.FC<MessagesDisplayProps> = React.memo(
-  ({ visibleMessages, formatMessage }) => {
+  ({ visibleMessages, formatMessage, height, hiddenBelow }) => {
     return (
       <Box flexDirection="column" flexGrow={1} padding={1}>
         {visibleMessages.length === 0 ? (
@@ -34,12 +71,21 @@ const MessagesDisplay: React.FC<MessagesDisplayProps> = React.memo(
             <Text {...theme.styles.text.highlight}>Ready for input...</Text>
           </Box>
         ) : (
-          <Box flexDirection="column" flexGrow={1}>
-            {visibleMessages.map((message) => (
-              <Box key={message.id} marginY={0}>
-                {formatMessage(message)}
-              </Box>
-            ))}
+          <Box
+            flexDirection="column"
+            flexGrow={1}
+            height={height}
+            overflow="hidden"
+            justifyContent="flex-end"
+          >
+            {/* Pushed down so the rows scrolled past drop below the clipped area */}
+            <Box flexDirection="column" flexShrink={0} marginBottom={-hiddenBelow}>
+              {visibleMessages.map((message) => (
+                <Box key={message.id} marginY={0}>
+                  {formatMessage(message)}
+                </Box>
+              ))}
+            </Box>
           </Box>
         )}
       </Box>
@@ -63,10 +109,15 @@ interface InputAreaProps {
   setHistoryIndex: (value: number) => void;
   filteredCommands: Array<{ value: string; description: string }>;
   selectedIndex: number;
+  fileMatches: string[];
+  fileIndex: number;
+  inputKey: number;
   showShortcuts: boolean;
+  hasCollapsible: boolean;
   onToggleShortcuts?: () => void;
   onExecuteCommand?: (command: string) => void;
   handleCommandSelect: (command: string) => void;
+  handleFileSelect: (path: string) => void;
   handleSubmit: (value: string) => void;
 }
 
@@ -86,10 +137,15 @@ const InputArea: React.FC<InputAreaProps> = React.memo(
     setHistoryIndex,
     filteredCommands,
     selectedIndex,
+    fileMatches,
+    fileIndex,
+    inputKey,
     showShortcuts,
+    hasCollapsible,
     onToggleShortcuts,
     onExecuteCommand,
     handleCommandSelect,
+    handleFileSelect,
     handleSubmit,
   }) => {
     // Handle onChange for input field
@@ -101,6 +157,11 @@ const InputArea: React.FC<InputAreaProps> = React.memo(
           return;
         }
 
+        // Likewise o, which expands a long answer (handled in useInput)
+        if (input === "" && value === "o" && hasCollapsible) {
+          return;
+        }
+
         // Hide shortcuts panel when user starts typing
         if (showShortcuts) {
           onToggleShortcuts?.();
@@ -128,7 +189,7 @@ const InputArea: React.FC<InputAreaProps> = React.memo(
           setShowCommandPalette(false);
         }
       },
-      [input, commandMode, showShortcuts, onToggleShortcuts],
+      [input, commandMode, showShortcuts, hasCollapsible, onToggleShortcuts],
     );
 
     // Handle input submission
@@ -150,6 +211,12 @@ const InputArea: React.FC<InputAreaProps> = React.memo(
           }
         }
 
+        // Enter while the file picker is open completes the mention instead
+        if (fileMatches.length > 0 && activeMention(value) !== null) {
+          handleFileSelect(fileMatches[fileIndex] ?? fileMatches[0]);
+          return;
+        }
+
         // Reset command mode
         if (commandMode) {
           setCommandMode(false);
@@ -190,6 +257,8 @@ const InputArea: React.FC<InputAreaProps> = React.memo(
         showCommandPalette,
         filteredCommands,
         selectedIndex,
+        fileMatches,
+        fileIndex,
         multilineInput,
         setMultilineInput,
         setCommandMode,
@@ -198,6 +267,7 @@ const InputArea: React.FC<InputAreaProps> = React.memo(
         setHistoryIndex,
         setInput,
         handleCommandSelect,
+        handleFileSelect,
         handleSubmit,
         onExecuteCommand,
       ],
@@ -207,7 +277,7 @@ const InputArea: React.FC<InputAreaProps> = React.memo(
       <Box paddingX={2} paddingY={1} flexDirection="column">
         <Box
           borderStyle={commandMode ? "single" : undefined}
-          borderColor={theme.colors.dark.green}
+          borderColor={theme.palette.green}
           paddingX={1}
           paddingY={commandMode ? 1 : 0}
           flexDirection="column"
@@ -221,8 +291,8 @@ const InputArea: React.FC<InputAreaProps> = React.memo(
                   <Text
                     color={
                       commandMode
-                        ? theme.colors.dark.green
-                        : theme.colors.dark.blue
+                        ? theme.palette.green
+                        : theme.palette.blue
                     }
                     bold
                   >
@@ -244,8 +314,8 @@ const InputArea: React.FC<InputAreaProps> = React.memo(
                 <Text
                   color={
                     commandMode
-                      ? theme.colors.dark.green
-                      : theme.colors.dark.blue
+                      ? theme.palette.green
+                      : theme.palette.blue
                   }
                   bold
                 >
@@ -256,7 +326,9 @@ const InputArea: React.FC<InputAreaProps> = React.memo(
               {multilineInput.length > 0 && <Box width={1}></Box>}
 
               <Box marginLeft={1} flexGrow={1}>
+                {/* Remounted after a mention is completed, to put the cursor at the end */}
                 <TextInput
+                  key={inputKey}
                   value={input}
                   onChange={handleInputChange}
                   onSubmit={handleInputSubmit}
@@ -286,8 +358,45 @@ interface ChatInterfaceProps {
   onClearHistory?: () => void;
   onExecuteCommand?: (command: string) => void;
   toolExecutions?: Map<string, ToolExecution>;
+  toolQueue?: ToolQueue | null;
+  streamingText?: string; // The running task's answer so far, streamed by the backend
+  showTimestamps?: boolean;
+  accessible?: boolean;
+  restoredSession?: SessionState | null;
+  onSessionChange?: (draft: string, scrollOffset: number) => void;
+  onAsk?: (question: string) => Promise<AskAnswer>;
+  onPromoteAnswer?: (answer: AskAnswer) => Promise<void>;
+  onSearchFiles?: (query: string) => Promise<string[]>;
+  promptHistory?: string[]; // Prompts submitted earlier, oldest first
+  onRecordPrompt?: (prompt: string) => void;
+  scrollBindings?: ScrollBindings; // Keys for scrolling, as configured in the backend
 }
 
+// Terminal rows assumed when the terminal doesn't report its size
+const DEFAULT_TERMINAL_ROWS = 24;
+
+// Rows kept below the messages for the input box and status lines
+const RESERVED_ROWS = 8;
+
+// Columns taken by the padding around messages, and by the timestamp gutter
+const MESSAGE_PADDING_COLUMNS = 4;
+const TIMESTAMP_COLUMNS = 9;
+
+// Rows scrolled by one notch of the mouse wheel
+const WHEEL_SCROLL_ROWS = 3;
+
+// Terminal row, counting from 1, of the first message row, below the messages
+// area's padding
+const MESSAGES_TOP_ROW = 2;
+
+// Format a message timestamp for the gutter, e.g. "14:03:27"
+const formatTimestamp = (timestamp: number): string => {
+  const date = new Date(timestamp);
+  return [date.getHours(), date.getMinutes(), date.getSeconds()]
+    .map((part) => String(part).padStart(2, "0"))
+    .join(":");
+};
+
 // Chat interface component
 const ChatInterface: React.FC<ChatInterfaceProps> = ({
   messages,
@@ -299,6 +408,18 @@ const ChatInterface: React.FC<ChatInterfaceProps> = ({
   onClearHistory,
   onExecuteCommand,
   toolExecutions = new Map(),
+  toolQueue = null,
+  streamingText = "",
+  showTimestamps = false,
+  accessible = false,
+  restoredSession = null,
+  onSessionChange,
+  onAsk,
+  onPromoteAnswer,
+  onSearchFiles,
+  promptHistory = [],
+  onRecordPrompt,
+  scrollBindings = DEFAULT_SCROLL_BINDINGS,
 }) => {
   const [input, setInput] = useState("");
   const [visibleMessages, setVisibleMessages] = useState<Message[]>([]);
@@ -306,15 +427,261 @@ const ChatInterface: React.FC<ChatInterfaceProps> = ({
   // These are used in the handleInputSubmit callback and useInput hook
   const [commandHistory, setCommandHistory] = useState<string[]>([]);
   const [historyIndex, setHistoryIndex] = useState(-1);
+  // Earlier prompt shown in the input box with Up, counted back from the latest
+  const [promptIndex, setPromptIndex] = useState(-1);
   const [showCommandPalette, setShowCommandPalette] = useState(false);
   const [multilineInput, setMultilineInput] = useState("");
   const [filteredCommands, setFilteredCommands] = useState<
     Array<{ value: string; description: string }>
   >([]);
   const [selectedIndex, setSelectedIndex] = useState(0);
+  // Files matching the @-mention being typed, and the one picked with the arrows
+  const [fileMatches, setFileMatches] = useState<string[]>([]);
+  const [fileIndex, setFileIndex] = useState(0);
+  const [inputKey, setInputKey] = useState(0);
+  // Rows scrolled back from the latest message with the scroll keys
+  const [scrollOffset, setScrollOffset] = useState(0);
+  // Rows of the last visible message scrolled out below the messages area
+  const [hiddenBelow, setHiddenBelow] = useState(0);
+  // Position among the latest answer's cited tool results shown with Ctrl+O
+  const [evidenceCursor, setEvidenceCursor] = useState<number | null>(null);
+  // Long answers the user expanded with o or a click; the rest show a preview
+  const [expandedMessages, setExpandedMessages] = useState<Set<string>>(
+    new Set(),
+  );
+  // Message the user clicked on, marked in the gutter
+  const [selectedMessageId, setSelectedMessageId] = useState<string | null>(
+    null,
+  );
+  // Whether the side-question panel (Ctrl+A) has the keyboard
+  const [asking, setAsking] = useState(false);
+  // Why the last Ctrl+G edit didn't make it back into the input box
+  const [editorError, setEditorError] = useState<string | null>(null);
+  const { setRawMode } = useStdin();
+  const { stdout } = useStdout();
+  const [terminalSize, setTerminalSize] = useState({
+    rows: stdout.rows ?? DEFAULT_TERMINAL_ROWS,
+    columns: stdout.columns ?? 80,
+  });
+  const collapseLines = useMemo(() => collapseThreshold(), []);
+  // The selected answer when it's long, otherwise the latest long answer, is the
+  // one o expands
+  const collapsibleId = useMemo(() => {
+    const selected = visibleMessages.find(
+      (message) => message.id === selectedMessageId,
+    );
+    return selected && isCollapsible(selected, collapseLines)
+      ? selected.id
+      : latestCollapsibleId(visibleMessages, collapseLines);
+  }, [visibleMessages, collapseLines, selectedMessageId]);
+  const evidenceEntries = useMemo(() => {
+    const message = latestMessageWithEvidence(messages);
+    return message ? citedEvidence(message) : [];
+  }, [messages]);
+
+  // Close the evidence panel when a new answer arrives
+  useEffect(() => {
+    setEvidenceCursor(null);
+  }, [evidenceEntries]);
+
+  // Restore the draft and scroll position saved before a crash or restart
+  useEffect(() => {
+    if (!restoredSession) return;
+
+    const lastNewline = restoredSession.draft.lastIndexOf("\n");
+    setMultilineInput(restoredSession.draft.slice(0, lastNewline + 1));
+    setInput(restoredSession.draft.slice(lastNewline + 1));
+    setScrollOffset(restoredSession.scroll_offset);
+  }, [restoredSession]);
+
+  // Report the draft and scroll position so they can be saved periodically
+  useEffect(() => {
+    onSessionChange?.(commandMode ? multilineInput : multilineInput + input, scrollOffset);
+  }, [input, multilineInput, scrollOffset, commandMode, onSessionChange]);
+
+  // Follow the terminal's size so scrolling moves by what fits on screen
+  useEffect(() => {
+    const handleResize = () =>
+      setTerminalSize({
+        rows: stdout.rows ?? DEFAULT_TERMINAL_ROWS,
+        columns: stdout.columns ?? 80,
+      });
+
+    stdout.on("resize", handleResize);
+    return () => {
+      stdout.off("resize", handleResize);
+    };
+  }, [stdout]);
+
+  // Where each message sits, in terminal rows, for scrolling by line and page
+  const viewportRows = Math.max(1, terminalSize.rows - RESERVED_ROWS);
+  const layout = useMemo(() => {
+    const width =
+      terminalSize.columns -
+      MESSAGE_PADDING_COLUMNS -
+      (showTimestamps ? TIMESTAMP_COLUMNS : 0);
+    return transcriptLayout(messages, (message) => {
+      if (accessible) return wrappedLines(describeMessage(message), width);
+      // Prompts are indented past the ">"
+      if (message.role === "user") {
+        return wrappedLines(message.content, width - 2);
+      }
+      if (message.role !== "assistant") {
+        return wrappedLines(message.content, width);
+      }
+
+      // Answers have a blank line above and below, and lines for the collapsed
+      // preview's note, citations and the turn summary
+      const collapsed =
+        isCollapsible(message, collapseLines) &&
+        !expandedMessages.has(message.id)
+          ? collapsedPreview(message.content)
+          : null;
+      return (
+        (collapsed
+          ? wrappedLines(collapsed.preview, width) + 1
+          : wrappedLines(message.content, width)) +
+        2 +
+        (message.evidence?.length ? 1 : 0) +
+        (message.turn_summary ? 1 : 0)
+      );
+    });
+  }, [
+    messages,
+    terminalSize.columns,
+    showTimestamps,
+    accessible,
+    collapseLines,
+    expandedMessages,
+  ]);
+  const scrollView = useMemo(
+    () => ({
+      contentLines: layout.contentLines,
+      viewportRows,
+      promptLines: layout.promptLines,
+    }),
+    [layout, viewportRows],
+  );
+
+  // The mouse wheel scrolls through earlier messages, a few rows at a time
+  useEffect(() => {
+    const maxOffset = Math.max(0, scrollView.contentLines - viewportRows);
+    const handleMouse = (event: MouseEvent) => {
+      if (event.kind === "wheelUp") {
+        setScrollOffset((prev) =>
+          Math.min(maxOffset, prev + WHEEL_SCROLL_ROWS),
+        );
+      } else if (event.kind === "wheelDown") {
+        setScrollOffset((prev) => Math.max(0, prev - WHEEL_SCROLL_ROWS));
+      }
+    };
+
+    mouseEvents.on("mouse", handleMouse);
+    return () => {
+      mouseEvents.off("mouse", handleMouse);
+    };
+  }, [scrollView.contentLines, viewportRows]);
+
+  // Clicking a message selects it, and a long answer expands or collapses too.
+  // Clicking the selected message again clears the selection.
+  useEffect(() => {
+    const handleClick = (event: MouseEvent) => {
+      if (event.kind !== "press" || event.button !== 0) return;
+
+      const index = messageAtRow(
+        layout.messageLines,
+        scrollOffset,
+        viewportRows,
+        event.y - MESSAGES_TOP_ROW,
+      );
+      const message = index === null ? undefined : messages[index];
+      if (!message) return;
+
+      setSelectedMessageId((prev) => (prev === message.id ? null : message.id));
+      if (!accessible && isCollapsible(message, collapseLines)) {
+        setExpandedMessages((prev) => {
+          const next = new Set(prev);
+          if (!next.delete(message.id)) next.add(message.id);
+          return next;
+        });
+      }
+    };
+
+    mouseEvents.on("mouse", handleClick);
+    return () => {
+      mouseEvents.off("mouse", handleClick);
+    };
+  }, [
+    messages,
+    layout,
+    scrollOffset,
+    viewportRows,
+    accessible,
+    collapseLines,
+  ]);
+
+  // Look up the files matching an @-mention as it's typed
+  const mention = commandMode ? null : activeMention(input);
+  useEffect(() => {
+    if (mention === null || !onSearchFiles) {
+      setFileMatches([]);
+      return;
+    }
+
+    let cancelled = false;
+    const debounceTimeout = setTimeout(() => {
+      onSearchFiles(mention)
+        .then((paths) => !cancelled && setFileMatches(paths))
+        .catch(() => !cancelled && setFileMatches([]));
+    }, 50);
+    return () => {
+      cancelled = true;
+      clearTimeout(debounceTimeout);
+    };
+  }, [mention, onSearchFiles]);
+
+  // Complete the mention being typed with the picked file
+  const handleFileSelect = useCallback(
+    (path: string) => {
+      setInput(insertMention(input, path));
+      setFileMatches([]);
+      setInputKey((prev) => prev + 1);
+    },
+    [input],
+  );
 
   // Handle keyboard shortcuts
   useInput((inputChar, key) => {
+    // Ctrl+A to open or close the side-question panel, even while a task runs
+    if (key.ctrl && inputChar === "a" && onAsk && onPromoteAnswer) {
+      setAsking((prev) => !prev);
+      // Keep the draft as it was before the keypress
+      setInput(input);
+      return;
+    }
+
+    // The side-question panel handles its own keys
+    if (asking) return;
+
+    // Ctrl+G to compose the prompt in $EDITOR, which gets the terminal until it exits
+    if (key.ctrl && inputChar === "g" && !commandMode) {
+      setRawMode(false);
+      const result = editInExternalEditor(multilineInput + input);
+      setRawMode(true);
+
+      if ("error" in result) {
+        setEditorError(result.error);
+        setInput(input);
+        return;
+      }
+      // The last line goes in the text box, the lines before it above, as typed with Ctrl+J
+      const lastNewline = result.text.lastIndexOf("\n");
+      setMultilineInput(result.text.slice(0, lastNewline + 1));
+      setInput(result.text.slice(lastNewline + 1));
+      setEditorError(null);
+      return;
+    }
+
     // Handle ? key to toggle shortcuts panel when input is empty
     if (
       inputChar === "?" &&
@@ -332,6 +699,24 @@ const ChatInterface: React.FC<ChatInterfaceProps> = ({
       return;
     }
 
+    // o to expand or collapse the latest long answer when input is empty
+    if (
+      inputChar === "o" &&
+      input === "" &&
+      collapsibleId &&
+      !accessible &&
+      !commandMode &&
+      !multilineInput
+    ) {
+      setExpandedMessages((prev) => {
+        const next = new Set(prev);
+        if (!next.delete(collapsibleId)) next.add(collapsibleId);
+        return next;
+      });
+      setInput("");
+      return;
+    }
+
     // Ctrl+J to insert a newline (a more reliable cross-platform shortcut)
     if (key.ctrl && inputChar === "j" && !commandMode) {
       // Hide shortcuts panel when entering multiline mode
@@ -355,6 +740,21 @@ const ChatInterface: React.FC<ChatInterfaceProps> = ({
       return;
     }
 
+    // Ctrl+O to step through the tool results cited in the latest answer
+    if (key.ctrl && inputChar === "o") {
+      if (evidenceEntries.length === 0) return;
+      setEvidenceCursor((prev) =>
+        prev === null ? 0 : prev + 1 < evidenceEntries.length ? prev + 1 : null,
+      );
+      return;
+    }
+
+    // ESC key to close the evidence panel
+    if (key.escape && evidenceCursor !== null) {
+      setEvidenceCursor(null);
+      return;
+    }
+
     // ESC key to exit command mode
     if (key.escape && commandMode) {
       setCommandMode(false);
@@ -363,12 +763,51 @@ const ChatInterface: React.FC<ChatInterfaceProps> = ({
       return;
     }
 
+    // Scroll keys (Ctrl+E/Y by line, Ctrl+D/U by half a page, PgUp/PgDn,
+    // Ctrl+Up/Down between prompts) move through earlier messages
+    const scrollAction = scrollActionFor(scrollBindings, inputChar, key);
+    if (scrollAction) {
+      setScrollOffset((prev) =>
+        applyScrollAction(scrollAction, prev, scrollView),
+      );
+      // Keep the draft as it was before the keypress
+      setInput(input);
+      return;
+    }
+
     // Ctrl+L to clear history
     if (key.ctrl && inputChar === "l") {
       onClearHistory?.();
       return;
     }
 
+    // Up/Down in an empty input box to step through earlier prompts, like a shell;
+    // once a prompt is recalled they keep stepping until it's submitted
+    const browsingPrompts =
+      promptIndex > -1 || (input === "" && !multilineInput);
+    if (
+      (key.upArrow || key.downArrow) &&
+      browsingPrompts &&
+      !commandMode &&
+      fileMatches.length === 0
+    ) {
+      const newIndex = key.upArrow
+        ? Math.min(promptHistory.length - 1, promptIndex + 1)
+        : Math.max(-1, promptIndex - 1);
+      if (newIndex === promptIndex) return;
+
+      const recalled =
+        newIndex === -1 ? "" : promptHistory[promptHistory.length - 1 - newIndex];
+      // Earlier lines of a multi-line prompt go above the input box, as typed with Ctrl+J
+      const lastNewline = recalled.lastIndexOf("\n");
+      setPromptIndex(newIndex);
+      setMultilineInput(recalled.slice(0, lastNewline + 1));
+      setInput(recalled.slice(lastNewline + 1));
+      // Remount the text box so the cursor lands at the end
+      setInputKey((prev) => prev + 1);
+      return;
+    }
+
     // Handle command mode navigation
     if (commandMode) {
       // Tab for autocomplete is now handled in CommandPalette
@@ -399,14 +838,22 @@ const ChatInterface: React.FC<ChatInterfaceProps> = ({
 
   // Update visible messages when messages change, with debouncing
   useEffect(() => {
-    // Only show the last 20 messages to prevent terminal overflow
+    // Only show the messages that fit on screen, ending scrollOffset rows
+    // before the latest
     // Use setTimeout to debounce frequent updates
     const timer = setTimeout(() => {
-      setVisibleMessages(messages.slice(-20));
+      const maxOffset = Math.max(0, layout.contentLines - viewportRows);
+      const shown = scrollWindow(
+        layout.messageLines,
+        Math.min(scrollOffset, maxOffset),
+        viewportRows,
+      );
+      setVisibleMessages(messages.slice(shown.start, shown.end));
+      setHiddenBelow(shown.hiddenBelow);
     }, 10);
 
     return () => clearTimeout(timer);
-  }, [messages]);
+  }, [messages, layout, scrollOffset, viewportRows]);
 
   // Tool messages are now handled directly by the StatusDisplay component
 
@@ -460,10 +907,13 @@ const ChatInterface: React.FC<ChatInterfaceProps> = ({
 
     // For non-commands and unknown commands, send as normal input to backend
     onSubmit(value);
+    onRecordPrompt?.(value);
+    setPromptIndex(-1);
 
-    // Reset both input states
+    // Reset both input states and jump back to the latest messages
     setInput("");
     setMultilineInput("");
+    setScrollOffset(0);
   };
 
   // Get Gruvbox style for a message based on its role
@@ -486,39 +936,105 @@ const ChatInterface: React.FC<ChatInterfaceProps> = ({
   const formatMessage = (message: Message) => {
     const style = getMessageStyle(message.role);
 
+    // One plain-text line per message, labelled by author rather than by color
+    if (accessible) {
+      return (
+        <Box paddingX={1} flexDirection="row">
+          {showTimestamps && (
+            <Box marginRight={1} flexShrink={0}>
+              <Text>{formatTimestamp(message.timestamp)}</Text>
+            </Box>
+          )}
+          <Text wrap="wrap">{describeMessage(message)}</Text>
+        </Box>
+      );
+    }
+
+    // Long answers show a preview until expanded; the full text is kept for export
+    const collapsed =
+      isCollapsible(message, collapseLines) && !expandedMessages.has(message.id)
+        ? collapsedPreview(message.content)
+        : null;
+
+    // The selection marker takes the place of the left padding
+    const selected = message.id === selectedMessageId;
+
     return (
-      <Box marginY={message.role === "assistant" ? 1 : 0} paddingX={1} flexDirection="column">
-        {message.role === "user" ? (
-          <Box flexDirection="row">
-            <Text color={theme.colors.dark.blue} bold>
-              {">"}
+      <Box
+        marginY={message.role === "assistant" ? 1 : 0}
+        paddingLeft={selected ? 0 : 1}
+        paddingRight={1}
+        flexDirection="row"
+      >
+        {selected && <Text color={theme.palette.blue}>▎</Text>}
+        {showTimestamps && (
+          <Box marginRight={1} flexShrink={0}>
+            <Text {...theme.styles.text.dimmed}>
+              {formatTimestamp(message.timestamp)}
             </Text>
-            <Box marginLeft={1} flexGrow={1}>
+          </Box>
+        )}
+        <Box flexDirection="column" flexGrow={1}>
+          {message.role === "user" ? (
+            <Box flexDirection="row">
+              <Text color={theme.palette.blue} bold>
+                {">"}
+              </Text>
+              <Box marginLeft={1} flexGrow={1}>
+                <Text {...style} wrap="wrap">
+                  {message.content}
+                </Text>
+              </Box>
+            </Box>
+          ) : message.role === "assistant" ? (
+            <Box flexDirection="column" flexGrow={1}>
+              {collapsed ? (
+                <>
+                  <Text {...style} wrap="wrap">
+                    {collapsed.preview}
+                  </Text>
+                  <Text {...theme.styles.text.dimmed}>
+                    … {collapsed.hiddenLines} more lines
+                    {message.id === collapsibleId
+                      ? " (press o to expand)"
+                      : ""}
+                  </Text>
+                </>
+              ) : (
+                <Text {...style} wrap="wrap">
+                  {message.content}
+                </Text>
+              )}
+            </Box>
+          ) : message.role === "tool" &&
+            message.tool_status &&
+            message.tool_data ? (
+            <ToolStatusIndicator
+              status={message.tool_status}
+              data={message.tool_data}
+            />
+          ) : (
+            <Box flexGrow={1}>
               <Text {...style} wrap="wrap">
                 {message.content}
               </Text>
             </Box>
-          </Box>
-        ) : message.role === "assistant" ? (
-          <Box flexGrow={1}>
-            <Text {...style} wrap="wrap">
-              {message.content}
+          )}
+          {message.role === "assistant" && message.evidence?.length ? (
+            <Text {...theme.styles.text.dimmed}>
+              {[
+                ...citedEvidence(message).map(evidenceLabel),
+                ...unresolvedCitations(message).map(
+                  (index) => `[#${index}] (no such tool result)`,
+                ),
+              ].join(" · ")}
+              {"  (Ctrl+O to view)"}
             </Text>
-          </Box>
-        ) : message.role === "tool" &&
-          message.tool_status &&
-          message.tool_data ? (
-          <ToolStatusIndicator
-            status={message.tool_status}
-            data={message.tool_data}
-          />
-        ) : (
-          <Box flexGrow={1}>
-            <Text {...style} wrap="wrap">
-              {message.content}
-            </Text>
-          </Box>
-        )}
+          ) : null}
+          {message.role === "assistant" && message.turn_summary && (
+            <Text {...theme.styles.text.dimmed}>{message.turn_summary}</Text>
+          )}
+        </Box>
       </Box>
     );
   };
@@ -530,13 +1046,39 @@ const ChatInterface: React.FC<ChatInterfaceProps> = ({
       <MessagesDisplay
         visibleMessages={visibleMessages}
         formatMessage={formatMessage}
+        height={
+          scrollView.contentLines > viewportRows ? viewportRows : undefined
+        }
+        hiddenBelow={hiddenBelow}
+      />
+
+      {/* The answer as it is generated; screen readers get the finished answer only */}
+      {isProcessing && streamingText && !accessible && (
+        <Box marginY={1} paddingX={1}>
+          <Text {...getMessageStyle("assistant")} wrap="wrap">
+            {streamingText}
+          </Text>
+        </Box>
+      )}
+
+      {/* Tool result for the selected citation */}
+      <EvidencePanel
+        entry={
+          evidenceCursor !== null
+            ? (evidenceEntries[evidenceCursor] ?? null)
+            : null
+        }
+        position={evidenceCursor ?? 0}
+        total={evidenceEntries.length}
       />
 
       {/* Unified status display - only renders while processing is active */}
       <StatusDisplay
         toolExecutions={toolExecutions}
+        toolQueue={toolQueue}
         isProcessing={isProcessing}
         onInterrupt={onInterrupt || (() => {})}
+        accessible={accessible}
       />
 
       {/* Invisible handler for interruption */}
@@ -545,28 +1087,50 @@ const ChatInterface: React.FC<ChatInterfaceProps> = ({
         onInterrupt={onInterrupt || (() => {})}
       />
 
+      {/* Side questions, answered while the main task keeps running */}
+      {onAsk && onPromoteAnswer && (
+        <AskPanel
+          visible={asking}
+          onAsk={onAsk}
+          onPromote={onPromoteAnswer}
+          onClose={() => setAsking(false)}
+        />
+      )}
+
       {/* Input area */}
-      <InputArea
-        input={input}
-        setInput={setInput}
-        multilineInput={multilineInput}
-        setMultilineInput={setMultilineInput}
-        commandMode={commandMode}
-        setCommandMode={setCommandMode}
-        showCommandPalette={showCommandPalette}
-        setShowCommandPalette={setShowCommandPalette}
-        commandHistory={commandHistory}
-        setCommandHistory={setCommandHistory}
-        historyIndex={historyIndex}
-        setHistoryIndex={setHistoryIndex}
-        filteredCommands={filteredCommands}
-        selectedIndex={selectedIndex}
-        showShortcuts={showShortcuts}
-        onToggleShortcuts={onToggleShortcuts}
-        onExecuteCommand={onExecuteCommand}
-        handleCommandSelect={handleCommandSelect}
-        handleSubmit={handleSubmit}
-      />
+      {editorError && !asking && (
+        <Box paddingX={1}>
+          <Text color={theme.palette.red}>{editorError}</Text>
+        </Box>
+      )}
+      {!asking && (
+        <InputArea
+          input={input}
+          setInput={setInput}
+          multilineInput={multilineInput}
+          setMultilineInput={setMultilineInput}
+          commandMode={commandMode}
+          setCommandMode={setCommandMode}
+          showCommandPalette={showCommandPalette}
+          setShowCommandPalette={setShowCommandPalette}
+          commandHistory={commandHistory}
+          setCommandHistory={setCommandHistory}
+          historyIndex={historyIndex}
+          setHistoryIndex={setHistoryIndex}
+          filteredCommands={filteredCommands}
+          selectedIndex={selectedIndex}
+          fileMatches={fileMatches}
+          fileIndex={fileIndex}
+          inputKey={inputKey}
+          showShortcuts={showShortcuts}
+          hasCollapsible={collapsibleId !== null && !accessible}
+          onToggleShortcuts={onToggleShortcuts}
+          onExecuteCommand={onExecuteCommand}
+          handleCommandSelect={handleCommandSelect}
+          handleFileSelect={handleFileSelect}
+          handleSubmit={handleSubmit}
+        />
+      )}
 
       {/* Command palette */}
       <CommandPalette
@@ -577,6 +1141,14 @@ const ChatInterface: React.FC<ChatInterfaceProps> = ({
         onSelectedIndexChange={setSelectedIndex}
       />
 
+      {/* Files for the @-mention being typed */}
+      <FilePicker
+        visible={!asking && !commandMode}
+        paths={fileMatches}
+        onSelect={handleFileSelect}
+        onSelectedIndexChange={setFileIndex}
+      />
+
       {/* Shortcuts panel */}
       <ShortcutsPanel visible={showShortcuts || false} />
     </>
@@ -1,6 +1,6 @@
 import React, { useState, useEffect, useMemo } from "react";
 import { Box, Text, useInput } from "ink";
-import theme from "../styles/gruvbox.js";
+import theme from "../styles/themes.js";
 
 // Command interface
 interface Command {
@@ -124,7 +124,7 @@ const CommandPalette: React.FC<CommandPaletteProps> = ({
             <Text
               bold
               color={
-                isSelected ? theme.colors.dark.yellow : theme.colors.dark.green
+                isSelected ? theme.palette.yellow : theme.palette.green
               }
             >
               /{command.name}
@@ -132,7 +132,7 @@ const CommandPalette: React.FC<CommandPaletteProps> = ({
           </Box>
           <Text
             color={
-              isSelected ? theme.colors.dark.yellow : theme.colors.dark.fg4
+              isSelected ? theme.palette.yellow : theme.palette.fg4
             }
           >
             {command.description}
@@ -0,0 +1,97 @@
+import React, { useState } from "react";
+import { Box, Text, useInput } from "ink";
+import theme from "../styles/themes.js";
+import { EditReviewRequest } from "../types/index.js";
+
+// Diff lines shown for a hunk before it's cut off
+const MAX_HUNK_LINES = 30;
+
+// Component props
+interface DiffReviewViewProps {
+  request: EditReviewRequest;
+  onDone: (accepted: string[]) => void;
+}
+
+// Color for a diff line
+const lineColor = (line: string): string | undefined => {
+  if (line.startsWith("+")) return theme.palette.green;
+  if (line.startsWith("-")) return theme.palette.red;
+  return undefined;
+};
+
+// Shows an Edit or Write the agent proposes hunk by hunk before it runs, and
+// applies only the hunks the user accepts
+const DiffReviewView: React.FC<DiffReviewViewProps> = ({ request, onDone }) => {
+  const [position, setPosition] = useState(0);
+  const [accepted, setAccepted] = useState<string[]>([]);
+
+  const hunks = request.hunks;
+
+  // Decide the hunks from the current one on, finishing after the last
+  const decide = (count: number, accept: boolean) => {
+    const ids = hunks.slice(position, position + count).map((hunk) => hunk.id);
+    const nextAccepted = accept ? [...accepted, ...ids] : accepted;
+    const next = position + count;
+    if (next >= hunks.length) {
+      onDone(nextAccepted);
+    } else {
+      setAccepted(nextAccepted);
+      setPosition(next);
+    }
+  };
+
+  useInput((input, key) => {
+    const rest = hunks.length - position;
+    if (input === "y") {
+      decide(1, true);
+    } else if (input === "n") {
+      decide(1, false);
+    } else if (input === "a") {
+      decide(rest, true);
+    } else if (input === "d" || input === "q" || key.escape) {
+      decide(rest, false);
+    }
+  });
+
+  const hunk = hunks[position];
+  if (!hunk) return null;
+  const lines = hunk.lines.slice(0, MAX_HUNK_LINES);
+  const hiddenLines = hunk.lines.length - lines.length;
+
+  return (
+    <Box
+      flexDirection="column"
+      borderStyle="round"
+      borderColor={theme.palette.yellow}
+      paddingX={1}
+    >
+      <Box flexDirection="row" justifyContent="space-between">
+        <Text bold color={theme.palette.yellow}>
+          {request.tool} {request.path}
+          {request.is_new_file ? " (new file)" : ""}
+        </Text>
+        <Text {...theme.styles.text.dimmed}>
+          hunk {position + 1}/{hunks.length} · +{hunk.added} -{hunk.removed}
+        </Text>
+      </Box>
+
+      <Box flexDirection="column" marginY={1}>
+        <Text color={theme.palette.aqua}>{hunk.header}</Text>
+        {lines.map((line, i) => (
+          <Text key={i} color={lineColor(line)}>
+            {line}
+          </Text>
+        ))}
+        {hiddenLines > 0 && (
+          <Text {...theme.styles.text.dimmed}>… {hiddenLines} more lines</Text>
+        )}
+      </Box>
+
+      <Text {...theme.styles.text.dimmed}>
+        y accept · n reject · a accept the rest · d or q reject the rest
+      </Text>
+    </Box>
+  );
+};
+
+export default DiffReviewView;
@@ -0,0 +1,51 @@
+import React from "react";
+import { Box, Text } from "ink";
+import theme from "../styles/themes.js";
+import { Evidence } from "../types/index.js";
+import { evidenceLabel } from "../utils/citations.js";
+
+// Lines of tool output shown for an entry
+const EXCERPT_LINES = 15;
+
+// Component props
+interface EvidencePanelProps {
+  entry: Evidence | null;
+  position: number;
+  total: number;
+}
+
+// Shows the tool result a citation like [#3] refers to
+const EvidencePanel: React.FC<EvidencePanelProps> = ({
+  entry,
+  position,
+  total,
+}) => {
+  if (!entry) return null;
+
+  const lines = entry.excerpt.split("\n");
+  const shown = lines.slice(0, EXCERPT_LINES).join("\n");
+  const more = lines.length > EXCERPT_LINES || entry.truncated;
+
+  return (
+    <Box
+      flexDirection="column"
+      borderStyle="round"
+      borderColor={theme.palette.blue}
+      paddingX={1}
+      marginY={1}
+    >
+      <Box flexDirection="row" justifyContent="space-between">
+        <Text bold color={theme.palette.blue}>
+          {evidenceLabel(entry)}
+        </Text>
+        <Text {...theme.styles.text.dimmed}>
+          {position + 1}/{total} · Ctrl+O next · Esc close
+        </Text>
+      </Box>
+      <Text wrap="wrap">{shown}</Text>
+      {more && <Text {...theme.styles.text.dimmed}>… output truncated</Text>}
+    </Box>
+  );
+};
+
+export default EvidencePanel;
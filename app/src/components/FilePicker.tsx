@@ -0,0 +1,67 @@
+import React, { useState, useEffect } from "react";
+import { Box, Text, useInput } from "ink";
+import theme from "../styles/themes.js";
+
+// Component props
+interface FilePickerProps {
+  visible: boolean;
+  paths: string[];
+  onSelect: (path: string) => void;
+  onSelectedIndexChange?: (index: number) => void;
+}
+
+// Picker for the files matching an @-mention, like the command palette
+const FilePicker: React.FC<FilePickerProps> = ({
+  visible,
+  paths,
+  onSelect,
+  onSelectedIndexChange,
+}) => {
+  const [selectedIndex, setSelectedIndex] = useState(0);
+
+  // Start from the best match whenever the matches change
+  useEffect(() => {
+    setSelectedIndex(0);
+  }, [paths]);
+
+  // Update parent component when selectedIndex changes
+  useEffect(() => {
+    onSelectedIndexChange?.(selectedIndex);
+  }, [selectedIndex, onSelectedIndexChange]);
+
+  // Arrows move through the matches and Tab picks one; Enter is handled by the
+  // input box, which would otherwise submit the prompt
+  useInput((_input, key) => {
+    if (!visible || paths.length === 0) return;
+
+    if (key.downArrow) {
+      setSelectedIndex((prev) => Math.min(prev + 1, paths.length - 1));
+    } else if (key.upArrow) {
+      setSelectedIndex((prev) => Math.max(prev - 1, 0));
+    } else if (key.tab) {
+      onSelect(paths[selectedIndex]);
+    }
+  });
+
+  if (!visible || paths.length === 0) return null;
+
+  return (
+    <Box flexDirection="column" width="100%" marginBottom={1}>
+      {paths.map((path, index) => (
+        <Box key={path} paddingX={2}>
+          <Text
+            color={
+              index === selectedIndex
+                ? theme.palette.yellow
+                : theme.palette.fg4
+            }
+          >
+            @{path}
+          </Text>
+        </Box>
+      ))}
+    </Box>
+  );
+};
+
+export default React.memo(FilePicker);
@@ -1,7 +1,7 @@
 import React, { useState, useEffect } from "react";
 import { Box, Text, useInput } from "ink";
 import Spinner from "ink-spinner";
-import theme from "../styles/gruvbox.js";
+import theme from "../styles/themes.js";
 import WelcomeBox from "./WelcomeBox.js";
 
 // Model interface
@@ -19,8 +19,14 @@ interface ModelSelectorProps {
   onSelect: (index: number) => void;
   onConfirm: () => void;
   isLoading: boolean;
+  missingOllamaModel?: string | null; // OLI_OLLAMA_MODEL when Ollama doesn't have it
+  onPull?: (model: string) => void;
 }
 
+// Models shown at once; the list scrolls with the selection, as OpenRouter
+// alone can add a hundred
+const VISIBLE_MODELS = 12;
+
 // Model selector component uses the extracted WelcomeBox component
 
 // Model selector with minimal UI
@@ -30,6 +36,8 @@ const ModelSelector: React.FC<ModelSelectorProps> = ({
   onSelect,
   onConfirm,
   isLoading,
+  missingOllamaModel,
+  onPull,
 }) => {
   // Track local selected index
   const [index, setIndex] = useState(selectedIndex);
@@ -43,6 +51,10 @@ const ModelSelector: React.FC<ModelSelectorProps> = ({
 
   // Handle keyboard input for selection and confirmation
   useInput((input, key) => {
+    if (input === "p" && missingOllamaModel && onPull) {
+      onPull(missingOllamaModel);
+      return;
+    }
     if (isLoading || models.length === 0) return;
 
     if (key.return) {
@@ -58,7 +70,7 @@ const ModelSelector: React.FC<ModelSelectorProps> = ({
   if (isLoading) {
     return (
       <WelcomeBox>
-        <Text color={theme.colors.dark.blue}>
+        <Text color={theme.palette.blue}>
           <Spinner type="dots" /> Connecting to backend...
         </Text>
       </WelcomeBox>
@@ -69,23 +81,39 @@ const ModelSelector: React.FC<ModelSelectorProps> = ({
   if (models.length === 0) {
     return (
       <WelcomeBox>
-        <Text color={theme.colors.dark.red}>
+        <Text color={theme.palette.red}>
           No models available. Please check API keys.
         </Text>
       </WelcomeBox>
     );
   }
 
+  // Keep the selection inside the visible window
+  const start = Math.max(
+    0,
+    Math.min(
+      index - Math.floor(VISIBLE_MODELS / 2),
+      models.length - VISIBLE_MODELS,
+    ),
+  );
+  const visible = models.slice(start, start + VISIBLE_MODELS);
+  const below = models.length - start - visible.length;
+
   // Model selection
   return (
     <WelcomeBox>
       <>
-        <Text color={theme.colors.dark.yellow}>Select a model:</Text>
+        <Text color={theme.palette.yellow}>Select a model:</Text>
 
         <Box marginY={1} flexDirection="column">
-          {models.map((model, i) => {
-            // Check if model is local based on the "(local)" suffix in the name
+          {start > 0 && (
+            <Text color={theme.palette.fg4}>↑ {start} more</Text>
+          )}
+          {visible.map((model, offset) => {
+            const i = start + offset;
+            // Check if model is local or served by OpenRouter from its name suffix
             const isLocal = model.name.includes("(local)");
+            const isOpenRouter = model.name.includes("(OpenRouter)");
 
             // The backend now provides the formatted model name directly
             // We just use it as-is, with appropriate styling
@@ -95,10 +123,12 @@ const ModelSelector: React.FC<ModelSelectorProps> = ({
                 key={`model-${i}`}
                 color={
                   i === index
-                    ? theme.colors.dark.green
+                    ? theme.palette.green
                     : isLocal
-                      ? theme.colors.dark.aqua
-                      : theme.colors.dark.fg
+                      ? theme.palette.aqua
+                      : isOpenRouter
+                        ? theme.palette.purple
+                        : theme.palette.fg
                 }
                 bold={i === index}
               >
@@ -107,17 +137,28 @@ const ModelSelector: React.FC<ModelSelectorProps> = ({
               </Text>
             );
           })}
+          {below > 0 && (
+            <Text color={theme.palette.fg4}>↓ {below} more</Text>
+          )}
         </Box>
 
         {models[index]?.description && (
           <Box flexDirection="column" marginY={1}>
-            <Text color={theme.colors.dark.fg4} wrap="wrap" dimColor>
+            <Text color={theme.palette.fg4} wrap="wrap" dimColor>
               {models[index].description}
             </Text>
           </Box>
         )}
 
-        <Text color={theme.colors.dark.fg4}>
+        {missingOllamaModel && (
+          <Box marginBottom={1}>
+            <Text color={theme.palette.aqua}>
+              {missingOllamaModel} isn't installed in Ollama. Press p to pull it.
+            </Text>
+          </Box>
+        )}
+
+        <Text color={theme.palette.fg4}>
           Use arrow keys ↑↓ to select, Enter to confirm
         </Text>
       </>
@@ -0,0 +1,102 @@
+import React, { useEffect, useState } from "react";
+import { Box, Text } from "ink";
+import Spinner from "ink-spinner";
+import theme from "../styles/themes.js";
+import { BackendService } from "../services/backend.js";
+import { Model, OllamaPullProgress } from "../types/index.js";
+
+// Width of the progress bar in characters
+const BAR_WIDTH = 30;
+
+// Component props
+interface OllamaPullViewProps {
+  backend: BackendService;
+  model: string;
+  onDone: (
+    result: { models: Model[]; modelIndex: number } | null,
+    summary: string,
+  ) => void;
+}
+
+// Size in bytes as e.g. "1.2 GB"
+const formatBytes = (bytes: number): string => {
+  const units = ["B", "KB", "MB", "GB"];
+  let value = bytes;
+  let unit = 0;
+  while (value >= 1000 && unit < units.length - 1) {
+    value /= 1000;
+    unit += 1;
+  }
+  return `${value.toFixed(unit === 0 ? 0 : 1)} ${units[unit]}`;
+};
+
+// Downloads a model into Ollama, showing each layer's progress as it arrives
+const OllamaPullView: React.FC<OllamaPullViewProps> = ({
+  backend,
+  model,
+  onDone,
+}) => {
+  const [progress, setProgress] = useState<OllamaPullProgress | null>(null);
+
+  useEffect(() => {
+    const handleProgress = (params: OllamaPullProgress) => {
+      if (params.model === model) setProgress(params);
+    };
+    backend.on("ollama_pull_progress", handleProgress);
+
+    backend
+      .call("pull_ollama_model", { model })
+      .then((result) => {
+        onDone(
+          {
+            models: (result.models as Model[]) || [],
+            modelIndex: result.model_index as number,
+          },
+          `Pulled ${model} with Ollama`,
+        );
+      })
+      .catch((err) => {
+        onDone(
+          null,
+          `Error pulling ${model}: ${err instanceof Error ? err.message : String(err)}`,
+        );
+      });
+
+    return () => {
+      backend.off("ollama_pull_progress", handleProgress);
+    };
+  }, [backend, model, onDone]);
+
+  const total = progress?.total ?? 0;
+  const completed = progress?.completed ?? 0;
+  const fraction = total > 0 ? Math.min(completed / total, 1) : null;
+  const filled = fraction === null ? 0 : Math.round(fraction * BAR_WIDTH);
+
+  return (
+    <Box
+      flexDirection="column"
+      borderStyle="round"
+      borderColor={theme.palette.aqua}
+      paddingX={1}
+    >
+      <Text bold color={theme.palette.aqua}>
+        <Spinner type="dots" /> Pulling {model} with Ollama
+      </Text>
+      <Text {...theme.styles.text.dimmed}>
+        {progress?.status ?? "Connecting to Ollama..."}
+      </Text>
+      {fraction !== null && (
+        <Text>
+          <Text color={theme.palette.green}>{"█".repeat(filled)}</Text>
+          <Text {...theme.styles.text.dimmed}>
+            {"░".repeat(BAR_WIDTH - filled)}
+          </Text>{" "}
+          {Math.round(fraction * 100)}% · {formatBytes(completed)} of{" "}
+          {formatBytes(total)}
+        </Text>
+      )}
+    </Box>
+  );
+};
+
+export default OllamaPullView;
@@ -0,0 +1,82 @@
+import React from "react";
+import { Box, Text, useInput } from "ink";
+import theme from "../styles/themes.js";
+import { PermissionRequest } from "../types/index.js";
+
+// Argument lines shown before they're cut off
+const MAX_ARGUMENT_LINES = 20;
+
+// What the user answered, as sent back to the backend
+export interface PermissionAnswer {
+  allow: boolean;
+  remember: "tool" | "command" | null;
+}
+
+// Component props
+interface PermissionPromptProps {
+  request: PermissionRequest;
+  onDone: (answer: PermissionAnswer) => void;
+}
+
+// The lines describing what the call would do: the command of a Bash call, or
+// the arguments of any other tool
+const argumentLines = (request: PermissionRequest): string[] => {
+  const command = request.arguments?.command;
+  if (request.tool === "Bash" && typeof command === "string") {
+    return command.split("\n").map((line, i) => (i === 0 ? `$ ${line}` : `  ${line}`));
+  }
+  return JSON.stringify(request.arguments ?? {}, null, 2).split("\n");
+};
+
+// Asks whether a tool call that needs permission may run
+const PermissionPrompt: React.FC<PermissionPromptProps> = ({ request, onDone }) => {
+  // Always allowing Bash remembers the exact command, not every command
+  const rememberScope = request.tool === "Bash" ? "command" : "tool";
+
+  useInput((input, key) => {
+    if (input === "y") {
+      onDone({ allow: true, remember: null });
+    } else if (input === "a") {
+      onDone({ allow: true, remember: rememberScope });
+    } else if (input === "n" || input === "q" || key.escape) {
+      onDone({ allow: false, remember: null });
+    }
+  });
+
+  const lines = argumentLines(request);
+  const shown = lines.slice(0, MAX_ARGUMENT_LINES);
+  const hiddenLines = lines.length - shown.length;
+
+  return (
+    <Box
+      flexDirection="column"
+      borderStyle="round"
+      borderColor={theme.palette.yellow}
+      paddingX={1}
+    >
+      <Box flexDirection="row" justifyContent="space-between">
+        <Text bold color={theme.palette.yellow}>
+          Allow {request.tool}?
+        </Text>
+        {request.working_directory && (
+          <Text {...theme.styles.text.dimmed}>{request.working_directory}</Text>
+        )}
+      </Box>
+
+      <Box flexDirection="column" marginY={1}>
+        {shown.map((line, i) => (
+          <Text key={i}>{line}</Text>
+        ))}
+        {hiddenLines > 0 && (
+          <Text {...theme.styles.text.dimmed}>… {hiddenLines} more lines</Text>
+        )}
+      </Box>
+
+      <Text {...theme.styles.text.dimmed}>
+        {`y allow · a always allow ${rememberScope === "command" ? "this command" : request.tool} · n or q deny`}
+      </Text>
+    </Box>
+  );
+};
+
+export default PermissionPrompt;
@@ -0,0 +1,137 @@
+import React, { useEffect, useState } from "react";
+import { Box, Text, useInput } from "ink";
+import theme from "../styles/themes.js";
+import { BackendService } from "../services/backend.js";
+import { RenamePlan, RenameRequest } from "../types/index.js";
+
+// Diff lines shown for a file before it's cut off
+const MAX_FILE_LINES = 30;
+
+// Component props
+interface RenameViewProps {
+  backend: BackendService;
+  request: RenameRequest;
+  onDone: (summary: string) => void;
+}
+
+// Color for a diff line
+const lineColor = (line: string): string | undefined => {
+  if (line.startsWith("+")) return theme.palette.green;
+  if (line.startsWith("-")) return theme.palette.red;
+  return undefined;
+};
+
+const errorText = (err: unknown): string =>
+  err instanceof Error ? err.message : String(err);
+
+// Shows every file a /rename changes, then applies all of them or none
+const RenameView: React.FC<RenameViewProps> = ({ backend, request, onDone }) => {
+  const [plan, setPlan] = useState<RenamePlan | null>(null);
+  const [position, setPosition] = useState(0);
+  const [busy, setBusy] = useState(false);
+
+  // Work out the edits once; nothing is written until approved
+  useEffect(() => {
+    backend
+      .call("preview_rename", {
+        old_name: request.oldName,
+        new_name: request.newName,
+      })
+      .then((result) => setPlan(result as unknown as RenamePlan))
+      .catch((err) => onDone(`Error planning the rename: ${errorText(err)}`));
+  }, [backend, request, onDone]);
+
+  const apply = async () => {
+    if (!plan) return;
+    setBusy(true);
+    try {
+      const result = await backend.call("apply_rename", {});
+      onDone(
+        `Renamed ${plan.old_name} to ${plan.new_name} in ${result.files} files. ` +
+          "Undo with /rename undo.",
+      );
+    } catch (err) {
+      onDone(`Error applying the rename, no files were changed: ${errorText(err)}`);
+    }
+  };
+
+  useInput((input, key) => {
+    if (!plan || busy) return;
+
+    if (input === "q" || key.escape) {
+      onDone(`Rename of ${plan.old_name} cancelled; no files were changed`);
+    } else if (input === "y" || key.return) {
+      apply();
+    } else if (input === "n" || key.rightArrow) {
+      setPosition((current) => Math.min(current + 1, plan.files.length - 1));
+    } else if (input === "p" || key.leftArrow) {
+      setPosition((current) => Math.max(current - 1, 0));
+    }
+  });
+
+  if (!plan) {
+    return (
+      <Box paddingX={1}>
+        <Text {...theme.styles.text.dimmed}>
+          Finding {request.oldName} across the workspace...
+        </Text>
+      </Box>
+    );
+  }
+
+  const file = plan.files[position];
+  const allLines = file.hunks.flatMap((hunk) => [hunk.header, ...hunk.lines]);
+  const lines = allLines.slice(0, MAX_FILE_LINES);
+  const hiddenLines = allLines.length - lines.length;
+
+  return (
+    <Box
+      flexDirection="column"
+      borderStyle="round"
+      borderColor={theme.palette.blue}
+      paddingX={1}
+    >
+      <Text bold color={theme.palette.blue}>
+        Rename {plan.old_name} → {plan.new_name}: {plan.occurrences} occurrences in{" "}
+        {plan.files.length} files
+      </Text>
+      {plan.source === "text" && (
+        <Text color={theme.palette.yellow}>
+          No language server rename available; whole-word matches are renamed,
+          including in comments and strings
+        </Text>
+      )}
+
+      <Box flexDirection="row" justifyContent="space-between" marginTop={1}>
+        <Text bold>
+          {file.path} ({file.occurrences})
+        </Text>
+        <Text {...theme.styles.text.dimmed}>
+          {position + 1}/{plan.files.length}
+        </Text>
+      </Box>
+
+      <Box flexDirection="column" marginY={1}>
+        {lines.map((line, i) => (
+          <Text
+            key={i}
+            color={line.startsWith("@@") ? theme.palette.aqua : lineColor(line)}
+          >
+            {line}
+          </Text>
+        ))}
+        {hiddenLines > 0 && (
+          <Text {...theme.styles.text.dimmed}>… {hiddenLines} more lines</Text>
+        )}
+      </Box>
+
+      <Text {...theme.styles.text.dimmed}>
+        {busy
+          ? "Renaming..."
+          : "y apply to all files · n/p next or previous file · q cancel"}
+      </Text>
+    </Box>
+  );
+};
+
+export default RenameView;
@@ -1,6 +1,6 @@
 import React from "react";
 import { Box, Text } from "ink";
-import theme from "../styles/gruvbox.js";
+import theme from "../styles/themes.js";
 
 // Component props
 interface ShortcutsPanelProps {
@@ -13,7 +13,19 @@ const ShortcutsPanel: React.FC<ShortcutsPanelProps> = ({ visible }) => {
 
   const shortcuts = [
     { key: "/", description: "Run a command" },
+    { key: "@", description: "Mention a file to attach it to the prompt" },
+    { key: "↑/↓", description: "Recall earlier prompts when the input is empty" },
     { key: "Ctrl+J", description: "Insert a new line" },
+    { key: "Ctrl+G", description: "Edit the prompt in $EDITOR" },
+    { key: "PgUp/PgDn", description: "Scroll through earlier messages, or use the mouse wheel" },
+    { key: "Ctrl+Y/E", description: "Scroll up or down a line" },
+    { key: "Ctrl+U/D", description: "Scroll up or down half a screen" },
+    { key: "Ctrl+Up/Down", description: "Jump to the previous or next prompt" },
+    { key: "Ctrl+O", description: "Show the tool results an answer cites" },
+    { key: "Ctrl+A", description: "Ask a side question while a task runs" },
+    { key: "Ctrl+C", description: "Stop the running task, or exit when idle" },
+    { key: "o", description: "Expand or collapse a long answer (empty input)" },
+    { key: "Click", description: "Select a message, expanding a long answer" },
   ];
 
   return (
@@ -21,7 +33,7 @@ const ShortcutsPanel: React.FC<ShortcutsPanelProps> = ({ visible }) => {
       {shortcuts.map((shortcut) => (
         <Box key={shortcut.key} marginY={0} flexDirection="row">
           <Box width={12}>
-            <Text bold color={theme.colors.dark.blue}>
+            <Text bold color={theme.palette.blue}>
               {shortcut.key}
             </Text>
           </Box>
@@ -0,0 +1,191 @@
+import React, { useEffect, useState } from "react";
+import { Box, Text, useInput } from "ink";
+import theme from "../styles/themes.js";
+import { BackendService } from "../services/backend.js";
+import { FileChange, Hunk } from "../types/index.js";
+
+// Diff lines shown for a hunk before it's cut off
+const MAX_HUNK_LINES = 30;
+
+// Something /stage asks about: one hunk, or a whole file when it has no hunks
+interface StagingItem {
+  file: FileChange;
+  hunk: Hunk | null;
+}
+
+// Component props
+interface StagingViewProps {
+  backend: BackendService;
+  onDone: (summary: string) => void;
+}
+
+// Flatten the changeset into the order it's walked: file by file, hunk by hunk
+const toItems = (files: FileChange[]): StagingItem[] =>
+  files.flatMap((file) =>
+    file.hunks.length > 0
+      ? file.hunks.map((hunk) => ({ file, hunk }))
+      : [{ file, hunk: null }],
+  );
+
+// Color for a diff line
+const lineColor = (line: string): string | undefined => {
+  if (line.startsWith("+")) return theme.palette.green;
+  if (line.startsWith("-")) return theme.palette.red;
+  return undefined;
+};
+
+// Walks unstaged changes like `git add -p`, staging the hunks the user picks
+const StagingView: React.FC<StagingViewProps> = ({ backend, onDone }) => {
+  const [items, setItems] = useState<StagingItem[] | null>(null);
+  const [position, setPosition] = useState(0);
+  const [staged, setStaged] = useState(0);
+  const [skipped, setSkipped] = useState(0);
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  // Load the changeset once
+  useEffect(() => {
+    backend
+      .call("get_unstaged_changes", {})
+      .then((result) => {
+        const loaded = toItems((result.files as FileChange[]) || []);
+        if (loaded.length === 0) {
+          onDone("Nothing to stage: the working tree has no unstaged changes");
+          return;
+        }
+        setItems(loaded);
+      })
+      .catch((err) => {
+        onDone(`Error reading changes: ${err instanceof Error ? err.message : String(err)}`);
+      });
+  }, [backend, onDone]);
+
+  const finish = (stagedCount: number, skippedCount: number) => {
+    onDone(
+      `Staged ${stagedCount} and skipped ${skippedCount} of ${items?.length ?? 0} changes. ` +
+        "Review with git diff --cached, then commit.",
+    );
+  };
+
+  // Stage the items at the given positions, then move past them
+  const stageItems = async (positions: number[]) => {
+    if (!items) return;
+    setBusy(true);
+    setError(null);
+
+    let count = 0;
+    try {
+      for (const index of positions) {
+        const { file, hunk } = items[index];
+        if (hunk) {
+          await backend.call("stage_hunk", { path: file.path, hunk_id: hunk.id });
+        } else {
+          await backend.call("stage_file", { path: file.path });
+        }
+        count += 1;
+      }
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    }
+
+    setBusy(false);
+    advance(positions.length, staged + count, skipped + positions.length - count);
+  };
+
+  const advance = (by: number, stagedCount: number, skippedCount: number) => {
+    setStaged(stagedCount);
+    setSkipped(skippedCount);
+    const next = position + by;
+    if (items && next >= items.length) {
+      finish(stagedCount, skippedCount);
+    } else {
+      setPosition(next);
+    }
+  };
+
+  // Positions from the current one to the last hunk of the same file
+  const restOfFile = (): number[] => {
+    if (!items) return [];
+    const path = items[position].file.path;
+    const positions: number[] = [];
+    for (let i = position; i < items.length && items[i].file.path === path; i++) {
+      positions.push(i);
+    }
+    return positions;
+  };
+
+  useInput((input, key) => {
+    if (!items || busy) return;
+
+    if (input === "q" || key.escape) {
+      finish(staged, skipped);
+    } else if (input === "y") {
+      stageItems([position]);
+    } else if (input === "n") {
+      advance(1, staged, skipped + 1);
+    } else if (input === "a") {
+      stageItems(restOfFile());
+    } else if (input === "d") {
+      const rest = restOfFile().length;
+      advance(rest, staged, skipped + rest);
+    }
+  });
+
+  if (!items) {
+    return (
+      <Box paddingX={1}>
+        <Text {...theme.styles.text.dimmed}>Reading changes...</Text>
+      </Box>
+    );
+  }
+
+  const { file, hunk } = items[position];
+  const lines = hunk ? hunk.lines.slice(0, MAX_HUNK_LINES) : [];
+  const hiddenLines = hunk ? hunk.lines.length - lines.length : 0;
+
+  return (
+    <Box
+      flexDirection="column"
+      borderStyle="round"
+      borderColor={theme.palette.blue}
+      paddingX={1}
+    >
+      <Box flexDirection="row" justifyContent="space-between">
+        <Text bold color={theme.palette.blue}>
+          {file.path} ({file.status})
+        </Text>
+        <Text {...theme.styles.text.dimmed}>
+          {position + 1}/{items.length}
+        </Text>
+      </Box>
+
+      {hunk ? (
+        <Box flexDirection="column" marginY={1}>
+          <Text color={theme.palette.aqua}>{hunk.header}</Text>
+          {lines.map((line, i) => (
+            <Text key={i} color={lineColor(line)}>
+              {line}
+            </Text>
+          ))}
+          {hiddenLines > 0 && (
+            <Text {...theme.styles.text.dimmed}>… {hiddenLines} more lines</Text>
+          )}
+        </Box>
+      ) : (
+        <Box marginY={1}>
+          <Text>Stage the whole file?</Text>
+        </Box>
+      )}
+
+      {error && <Text color={theme.palette.red}>{error}</Text>}
+
+      <Text {...theme.styles.text.dimmed}>
+        {busy
+          ? "Staging..."
+          : "y stage · n skip · a stage rest of file · d skip rest of file · q quit"}
+      </Text>
+    </Box>
+  );
+};
+
+export default StagingView;
@@ -1,7 +1,8 @@
 import React, { useMemo } from "react";
 import { Box, Text } from "ink";
-import theme from "../styles/gruvbox.js";
+import theme from "../styles/themes.js";
 import AnimatedSpinner from "./AnimatedSpinner.js";
+import { IndexStatus } from "../types/index.js";
 
 // Component props
 interface StatusBarProps {
@@ -9,6 +10,12 @@ interface StatusBarProps {
   isProcessing: boolean;
   backendConnected?: boolean;
   showShortcuts?: boolean;
+  accessible?: boolean;
+  readOnly?: boolean;
+  preset?: string | null;
+  indexStatus?: IndexStatus | null;
+  sessionCost?: number | null;
+  contextPercent?: number | null;
 }
 
 // Status bar component - modern minimalist design
@@ -17,6 +24,12 @@ const StatusBar: React.FC<StatusBarProps> = ({
   isProcessing,
   backendConnected = false,
   showShortcuts = false,
+  accessible = false,
+  readOnly = false,
+  preset = null,
+  indexStatus = null,
+  sessionCost = null,
+  contextPercent = null,
 }) => {
   // Get connection status icon and color - memoized to prevent rerenders
   const status = useMemo(() => {
@@ -41,6 +54,21 @@ const StatusBar: React.FC<StatusBarProps> = ({
     }
   }, [isProcessing, backendConnected]);
 
+  // Screen readers get the status as text alone, without the spinner or icon
+  const statusIcon = accessible ? `[${status.text.toLowerCase()}]` : status.icon;
+
+  // Only large repositories show the index, small ones are indexed in a moment
+  const indexText = useMemo(() => {
+    if (!indexStatus?.large_repo) {
+      return null;
+    }
+    const files = indexStatus.files_indexed.toLocaleString();
+    if (!indexStatus.complete) {
+      return accessible ? `indexing, ${files} files` : `Indexing ${files} files…`;
+    }
+    return accessible ? `large repo, ${files} files` : `large repo · ${files} files`;
+  }, [indexStatus, accessible]);
+
   return (
     <Box
       paddingX={2}
@@ -54,7 +82,7 @@ const StatusBar: React.FC<StatusBarProps> = ({
         {/* Status indicator */}
         <Box marginRight={2}>
           <Text>
-            <Text color={status.color}>{status.icon}</Text>
+            <Text color={status.color}>{statusIcon}</Text>
             <Text {...theme.styles.text.dimmed}> {status.text}</Text>
           </Text>
         </Box>
@@ -65,6 +93,55 @@ const StatusBar: React.FC<StatusBarProps> = ({
           <Text {...theme.styles.text.highlight}> {modelName}</Text>
         </Box>
 
+        {/* Sampling preset chosen with /preset */}
+        {preset && (
+          <Box marginRight={2}>
+            <Text {...theme.styles.text.dimmed}>Preset:</Text>
+            <Text> {preset}</Text>
+          </Box>
+        )}
+
+        {/* Estimated cost of the session's requests at list prices */}
+        {sessionCost !== null && sessionCost > 0 && (
+          <Box marginRight={2}>
+            <Text {...theme.styles.text.dimmed}>Cost:</Text>
+            <Text> ${sessionCost.toFixed(sessionCost < 1 ? 4 : 2)}</Text>
+          </Box>
+        )}
+
+        {/* How full the model's context window is; the history is compacted at 80% by default */}
+        {contextPercent !== null && (
+          <Box marginRight={2}>
+            <Text {...theme.styles.text.dimmed}>Context:</Text>
+            <Text
+              color={
+                contextPercent >= 90
+                  ? theme.palette.red
+                  : contextPercent >= 70
+                    ? theme.palette.yellow
+                    : undefined
+              }
+            >
+              {" "}
+              {contextPercent}% full
+            </Text>
+          </Box>
+        )}
+
+        {/* Another instance holds the workspace */}
+        {readOnly && (
+          <Box marginRight={2}>
+            <Text color={theme.palette.yellow}>read-only</Text>
+          </Box>
+        )}
+
+        {/* Workspace index progress */}
+        {indexText && (
+          <Box marginRight={2}>
+            <Text {...theme.styles.text.dimmed}>{indexText}</Text>
+          </Box>
+        )}
+
         {/* Separator */}
         <Box marginRight={2}>
           <Text {...theme.styles.text.dimmed}>|</Text>
@@ -75,7 +152,7 @@ const StatusBar: React.FC<StatusBarProps> = ({
           <Text
             {...theme.styles.text.dimmed}
             color={
-              showShortcuts ? theme.colors.dark.yellow : theme.colors.dark.gray
+              showShortcuts ? theme.palette.yellow : theme.palette.gray
             }
             bold={showShortcuts}
           >
@@ -1,31 +1,37 @@
 import React, { useEffect, useState, useMemo } from "react";
 import { Box, Text } from "ink";
-import { ToolExecution } from "../types/index.js";
+import { ToolExecution, ToolQueue } from "../types/index.js";
 import { ToolStatusIndicator } from "./ToolStatusIndicator.js";
+import ToolQueueView from "./ToolQueueView.js";
 import AnimatedSpinner from "./AnimatedSpinner.js";
-import theme from "../styles/gruvbox.js";
+import theme from "../styles/themes.js";
 
 interface StatusDisplayProps {
   toolExecutions: Map<string, ToolExecution>;
+  toolQueue?: ToolQueue | null;
   isProcessing: boolean;
   onInterrupt: () => void;
+  accessible?: boolean;
 }
 
 const StatusDisplay: React.FC<StatusDisplayProps> = ({
   toolExecutions,
+  toolQueue = null,
   isProcessing,
   /* eslint-disable-next-line @typescript-eslint/no-unused-vars */
   onInterrupt,
+  accessible = false,
 }) => {
   // State to ensure smooth transitions
   const [isVisible, setIsVisible] = useState(false);
   const [elapsedTime, setElapsedTime] = useState(0);
 
-  // Set up elapsed time counter for processing
+  // Set up elapsed time counter for processing. Screen readers would re-read the
+  // line every second, so accessibility mode shows a static status instead.
   useEffect(() => {
     let timerId: NodeJS.Timeout | null = null;
 
-    if (isProcessing) {
+    if (isProcessing && !accessible) {
       setElapsedTime(0);
       // Update elapsed time every second
       timerId = setInterval(() => {
@@ -38,7 +44,7 @@ const StatusDisplay: React.FC<StatusDisplayProps> = ({
     return () => {
       if (timerId) clearInterval(timerId);
     };
-  }, [isProcessing]);
+  }, [isProcessing, accessible]);
 
   // Memoized active tool to prevent unnecessary rerenders
   const activeTool = useMemo(() => {
@@ -76,44 +82,68 @@ const StatusDisplay: React.FC<StatusDisplayProps> = ({
   // Don't render anything when not visible
   if (!isVisible) return null;
 
+  if (accessible) {
+    return (
+      <Box flexDirection="column" marginX={1}>
+        <Text>[working] Press Escape or Ctrl+C to interrupt</Text>
+        {activeTool && (
+          <ToolStatusIndicator
+            status={activeTool.status}
+            data={{
+              name: activeTool.name,
+              file_path: activeTool.metadata.file_path as string | undefined,
+            }}
+            compact={true}
+            accessible={true}
+          />
+        )}
+      </Box>
+    );
+  }
+
   return (
-    <Box flexDirection="row" marginY={0} marginX={1}>
-      {/* Left side: Processing indicator with elapsed time and interrupt option */}
-      <Box marginLeft={1} flexDirection="row" alignItems="center">
-        <AnimatedSpinner color={theme.colors.dark.yellow} />
-        <Box marginLeft={1} marginRight={1}>
-          <Text color={theme.colors.dark.yellow}>
-            {elapsedTime > 0 && `${formattedTime}`}
-          </Text>
+    <Box flexDirection="column">
+      <Box flexDirection="row" marginY={0} marginX={1}>
+        {/* Left side: Processing indicator with elapsed time and interrupt option */}
+        <Box marginLeft={1} flexDirection="row" alignItems="center">
+          <AnimatedSpinner color={theme.palette.yellow} />
+          <Box marginLeft={1} marginRight={1}>
+            <Text color={theme.palette.yellow}>
+              {elapsedTime > 0 && `${formattedTime}`}
+            </Text>
+          </Box>
+          <Text color={theme.palette.red}>ESC or Ctrl+C to interrupt</Text>
+
+          {/* Add a separator when there's also a tool showing */}
+          {activeTool && (
+            <Box marginX={2}>
+              <Text color={theme.palette.gray}>|</Text>
+            </Box>
+          )}
         </Box>
-        <Text color={theme.colors.dark.red}>ESC to interrupt</Text>
 
-        {/* Add a separator when there's also a tool showing */}
+        {/* Tool status (if available) now positioned next to the timer */}
         {activeTool && (
-          <Box marginX={2}>
-            <Text color={theme.colors.dark.gray}>|</Text>
-          </Box>
+          <ToolStatusIndicator
+            status={activeTool.status}
+            data={{
+              name: activeTool.name,
+              file_path: activeTool.metadata.file_path as string | undefined,
+              lines: activeTool.metadata.lines as number | undefined,
+              description:
+                activeTool.message ||
+                (activeTool.metadata.description as string | undefined),
+            }}
+            compact={true}
+          />
         )}
+
+        {/* Empty space to maintain layout */}
+        <Box flexGrow={1} />
       </Box>
 
-      {/* Tool status (if available) now positioned next to the timer */}
-      {activeTool && (
-        <ToolStatusIndicator
-          status={activeTool.status}
-          data={{
-            name: activeTool.name,
-            file_path: activeTool.metadata.file_path as string | undefined,
-            lines: activeTool.metadata.lines as number | undefined,
-            description:
-              activeTool.message ||
-              (activeTool.metadata.description as string | undefined),
-          }}
-          compact={true}
-        />
-      )}
-
-      {/* Empty space to maintain layout */}
-      <Box flexGrow={1} />
+      {/* Tool calls of the current round, when the model asked for several */}
+      <ToolQueueView queue={toolQueue} />
     </Box>
   );
 };
@@ -2,7 +2,7 @@ import React, { useMemo } from "react";
 import { Box, Text } from "ink";
 import AnimatedSpinner from "./AnimatedSpinner.js";
 import { ToolExecution } from "../types/index.js";
-import theme from "../styles/gruvbox.js";
+import theme from "../styles/themes.js";
 import { ToolStatusIndicator } from "./ToolStatusIndicator.js";
 
 interface StatusPanelProps {
@@ -61,9 +61,9 @@ const StatusPanel: React.FC<StatusPanelProps> = ({
 
       {/* Right side: Interrupt instruction */}
       <Box flexDirection="row" alignItems="center">
-        <AnimatedSpinner color={theme.colors.dark.yellow} />
+        <AnimatedSpinner color={theme.palette.yellow} />
         <Box marginLeft={1} />
-        <Text color={theme.colors.dark.red}>ESC to interrupt</Text>
+        <Text color={theme.palette.red}>ESC to interrupt</Text>
       </Box>
     </Box>
   );
@@ -0,0 +1,84 @@
+import React from "react";
+import { Box, Text } from "ink";
+import theme from "../styles/themes.js";
+import AnimatedSpinner from "./AnimatedSpinner.js";
+import { QueuedCallStatus, ToolQueue } from "../types/index.js";
+
+// Calls listed before the rest are summarized
+const MAX_VISIBLE_CALLS = 8;
+
+// Component props
+interface ToolQueueViewProps {
+  queue: ToolQueue | null;
+}
+
+// Icon for a call's status
+const statusIcon = (status: QueuedCallStatus) => {
+  switch (status) {
+    case "running":
+      return <AnimatedSpinner color={theme.palette.blue} />;
+    case "completed":
+      return <Text color={theme.palette.green}>✓</Text>;
+    case "failed":
+      return <Text color={theme.palette.red}>✗</Text>;
+    case "denied":
+      return <Text color={theme.palette.yellow}>⊘</Text>;
+    default:
+      return <Text color={theme.palette.gray}>○</Text>;
+  }
+};
+
+// Format a duration compactly, e.g. "840ms" or "12.3s"
+const formatDuration = (ms: number): string =>
+  ms < 1000 ? `${ms}ms` : `${(ms / 1000).toFixed(1)}s`;
+
+// Pending, running and finished tool calls of the current iteration, shown when
+// the model asks for several at once
+const ToolQueueView: React.FC<ToolQueueViewProps> = ({ queue }) => {
+  if (!queue || queue.calls.length < 2) return null;
+
+  const finished = queue.calls.filter(
+    (call) => call.status !== "pending" && call.status !== "running",
+  ).length;
+
+  // Keep the running call in view when the queue is longer than the list
+  const running = queue.calls.findIndex((call) => call.status === "running");
+  const start = Math.max(
+    0,
+    Math.min(running - 1, queue.calls.length - MAX_VISIBLE_CALLS),
+  );
+  const visible = queue.calls.slice(start, start + MAX_VISIBLE_CALLS);
+  const after = queue.calls.length - start - visible.length;
+
+  return (
+    <Box flexDirection="column" marginX={2}>
+      <Text {...theme.styles.text.dimmed}>
+        Tool calls (round {queue.iteration}): {finished}/{queue.calls.length} done
+      </Text>
+      {start > 0 && (
+        <Text {...theme.styles.text.dimmed}>  … {start} earlier</Text>
+      )}
+      {visible.map((call) => (
+        <Box key={call.id} flexDirection="row">
+          <Box width={2}>{statusIcon(call.status)}</Box>
+          <Text
+            {...(call.status === "pending" ? theme.styles.text.dimmed : {})}
+          >
+            {call.name}
+          </Text>
+          {call.duration_ms !== null && (
+            <Text {...theme.styles.text.dimmed}>
+              {" "}
+              {formatDuration(call.duration_ms)}
+            </Text>
+          )}
+        </Box>
+      ))}
+      {after > 0 && (
+        <Text {...theme.styles.text.dimmed}>  … {after} more</Text>
+      )}
+    </Box>
+  );
+};
+
+export default ToolQueueView;
@@ -1,19 +1,22 @@
 import React, { useMemo } from "react";
 import { Box, Text } from "ink";
-import theme from "../styles/gruvbox.js";
+import theme from "../styles/themes.js";
 import { ToolStatus, ToolData } from "../types/index.js";
 import AnimatedSpinner from "./AnimatedSpinner.js";
+import { statusPrefix } from "../utils/accessibility.js";
 
 interface ToolStatusIndicatorProps {
   status: ToolStatus;
   data?: ToolData;
   compact?: boolean;
+  accessible?: boolean;
 }
 
 const ToolStatusIndicator: React.FC<ToolStatusIndicatorProps> = ({
   status,
   data,
   compact = false,
+  accessible = false,
 }) => {
   if (!data) {
     return null;
@@ -21,17 +24,22 @@ const ToolStatusIndicator: React.FC<ToolStatusIndicatorProps> = ({
 
   // Get the appropriate status indicator and color - memoized to prevent rerenders
   const statusIndicator = useMemo(() => {
+    // Plain-text prefix instead of a spinner or an icon told apart by color
+    if (accessible) {
+      return <Text>{statusPrefix(status)}</Text>;
+    }
+
     switch (status) {
       case "running":
-        return <AnimatedSpinner color={theme.colors.dark.blue} />;
+        return <AnimatedSpinner color={theme.palette.blue} />;
       case "success":
-        return <Text color={theme.colors.dark.green}>✓</Text>;
+        return <Text color={theme.palette.green}>✓</Text>;
       case "error":
-        return <Text color={theme.colors.dark.red}>✗</Text>;
+        return <Text color={theme.palette.red}>✗</Text>;
       default:
-        return <Text color={theme.colors.dark.gray}>⏺</Text>;
+        return <Text color={theme.palette.gray}>⏺</Text>;
     }
-  }, [status]);
+  }, [status, accessible]);
 
   // Format tool name and file path or pattern - memoized to prevent recalculation
   const toolTitle = useMemo(() => {
@@ -62,12 +70,12 @@ const ToolStatusIndicator: React.FC<ToolStatusIndicatorProps> = ({
     }
 
     // Add ellipsis if running
-    if (status === "running") {
+    if (status === "running" && !accessible) {
       title += "…";
     }
 
     return title;
-  }, [data.name, data.file_path, status, data.metadata?.pattern]);
+  }, [data.name, data.file_path, status, data.metadata?.pattern, accessible]);
 
   // Format details based on tool type - memoized to prevent recalculation
   const details = useMemo(() => {
@@ -107,13 +115,13 @@ const ToolStatusIndicator: React.FC<ToolStatusIndicatorProps> = ({
   const statusColor = useMemo(() => {
     switch (status) {
       case "running":
-        return theme.colors.dark.blue;
+        return theme.palette.blue;
       case "success":
-        return theme.colors.dark.green;
+        return theme.palette.green;
       case "error":
-        return theme.colors.dark.red;
+        return theme.palette.red;
       default:
-        return theme.colors.dark.gray;
+        return theme.palette.gray;
     }
   }, [status]);
 
@@ -129,7 +137,9 @@ const ToolStatusIndicator: React.FC<ToolStatusIndicatorProps> = ({
 
       {details && (
         <Box marginLeft={2} flexDirection="row" marginBottom={0}>
-          <Text color={theme.colors.dark.gray}> ⎿ {details}</Text>
+          <Text color={theme.palette.gray}>
+            {accessible ? ` ${details}` : ` ⎿ ${details}`}
+          </Text>
         </Box>
       )}
     </Box>
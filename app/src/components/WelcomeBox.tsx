@@ -1,12 +1,12 @@
 import React from "react";
 import { Box, Text } from "ink";
-import theme from "../styles/gruvbox.js";
+import theme from "../styles/themes.js";
 
 // Simple welcome box component
 const WelcomeBox = ({ children }: { children: React.ReactNode }) => (
   <Box
     borderStyle="round"
-    borderColor={theme.colors.dark.green}
+    borderColor={theme.palette.green}
     paddingX={4}
     paddingY={2}
     width={60}
@@ -14,13 +14,13 @@ const WelcomeBox = ({ children }: { children: React.ReactNode }) => (
     alignSelf="center"
     marginY={2} // Add some margin to center vertically
   >
-    <Text color={theme.colors.dark.green} bold>
+    <Text color={theme.palette.green} bold>
       ✻ Welcome to oli!
     </Text>
     <Box marginY={1} />
     {children}
     <Box marginY={1} />
-    <Text color={theme.colors.dark.fg4}>cwd: {process.cwd()}</Text>
+    <Text color={theme.palette.fg4}>cwd: {process.cwd()}</Text>
   </Box>
 );
 
@@ -51,8 +51,12 @@ export class BackendService extends EventEmitter {
       try {
         const message = JSON.parse(line);
 
+        // Handle JSON-RPC request from the backend, e.g. to review an edit
+        if ("method" in message && "id" in message && message.id !== null) {
+          this.handleRequest(message as JsonRpcRequest);
+        }
         // Handle JSON-RPC response
-        if ("id" in message && message.id !== null) {
+        else if ("id" in message && message.id !== null) {
           const response = message as JsonRpcResponse;
           const pending = this.pendingRequests.get(response.id);
 
@@ -115,6 +119,28 @@ export class BackendService extends EventEmitter {
     });
   }
 
+  // Hand a backend request to its listener with a callback that answers it, or
+  // answer with an error when nothing listens for the method
+  private handleRequest(request: JsonRpcRequest) {
+    const respond = (result: Record<string, unknown>) => {
+      this.process.stdin!.write(
+        JSON.stringify({ jsonrpc: "2.0", id: request.id, result }) + "\n",
+      );
+    };
+
+    if (this.listenerCount(request.method) === 0) {
+      this.process.stdin!.write(
+        JSON.stringify({
+          jsonrpc: "2.0",
+          id: request.id,
+          error: { code: -32601, message: `Method not found: ${request.method}` },
+        }) + "\n",
+      );
+      return;
+    }
+    this.emit(request.method, request.params, respond);
+  }
+
   // Kill the backend process
   kill() {
     this.process.kill();
@@ -171,8 +197,24 @@ export class BackendService extends EventEmitter {
   }
 }
 
-// Spawn a new backend process
-export function spawnBackend(path: string): BackendService {
+// Capabilities a client declares in the `initialize` handshake
+export interface ClientCapabilities {
+  supports_notifications?: boolean;
+  supports_permission_prompts?: boolean;
+  supports_edit_review?: boolean;
+}
+
+// What the interactive UI handles: it reviews Edit and Write hunk by hunk, and
+// asks the user before other tools that need permission run
+export const TUI_CAPABILITIES: ClientCapabilities = {
+  supports_notifications: true,
+  supports_permission_prompts: true,
+  supports_edit_review: true,
+};
+
+// Spawn a new backend process. Without capabilities the client skips the
+// handshake, so the backend doesn't send it requests it can't answer.
+export function spawnBackend(path: string, capabilities?: ClientCapabilities): BackendService {
   // Create process without showing stderr output (redirect to log file only)
   const process = spawn(path, [], {
     stdio: ["pipe", "pipe", "ignore"],
@@ -205,6 +247,15 @@ export function spawnBackend(path: string): BackendService {
         )
       ]) as Record<string, unknown>;
 
+      // Declare what this client handles, if it said
+      if (capabilities) {
+        try {
+          await backend.call("initialize", { capabilities });
+        } catch (err) {
+          // Older backends run without capability negotiation
+        }
+      }
+
       // Get the version from the backend
       let version;
       try {
@@ -40,7 +40,7 @@ export const colors = {
     gray: "#928374",
   },
 
-  // Light theme colors (not used for now)
+  // Light theme colors
   light: {
     // Background colors
     bg: "#fbf1c7",
@@ -80,114 +80,3 @@ export const colors = {
     gray: "#928374",
   },
 };
-
-// Common UI components styling
-export const styles = {
-  // Box styles
-  box: {
-    default: {
-      borderColor: colors.dark.gray,
-    },
-    header: {
-      borderStyle: "single" as const, // Type assertion to make it compatible with Ink
-      borderColor: colors.dark.green,
-      paddingX: 2,
-      paddingY: 1,
-    },
-    content: {
-      borderStyle: "single" as const,
-      borderColor: colors.dark.gray,
-    },
-    input: {
-      borderStyle: "single" as const,
-      borderColor: colors.dark.gray,
-    },
-  },
-
-  // Text styles
-  text: {
-    heading: {
-      color: colors.dark.green,
-      bold: true,
-    },
-    subheading: {
-      color: colors.dark.yellow,
-      bold: true,
-    },
-    user: {
-      color: colors.dark.green,
-      bold: true,
-    },
-    assistant: {
-      color: colors.dark.blue,
-    },
-    system: {
-      color: colors.dark.yellow,
-      italic: true,
-    },
-    tool: {
-      color: colors.dark.purple,
-      bold: true,
-    },
-    highlight: {
-      color: colors.dark.orange,
-      bold: true,
-    },
-    dimmed: {
-      color: colors.dark.fg4,
-    },
-    error: {
-      color: colors.dark.red,
-    },
-    success: {
-      color: colors.dark.green,
-    },
-    warning: {
-      color: colors.dark.yellow,
-    },
-    info: {
-      color: colors.dark.blue,
-    },
-  },
-
-  // Status indicators
-  status: {
-    active: {
-      color: colors.dark.green,
-      icon: "●",
-    },
-    processing: {
-      color: colors.dark.yellow,
-      // Spinner will be used
-    },
-    error: {
-      color: colors.dark.red,
-      icon: "○",
-    },
-    inactive: {
-      color: colors.dark.gray,
-      icon: "○",
-    },
-  },
-
-  // Button-like elements
-  button: {
-    primary: {
-      color: colors.dark.bg0,
-      backgroundColor: colors.dark.green,
-      bold: true,
-    },
-    secondary: {
-      color: colors.dark.bg0,
-      backgroundColor: colors.dark.blue,
-      bold: true,
-    },
-    danger: {
-      color: colors.dark.bg0,
-      backgroundColor: colors.dark.red,
-      bold: true,
-    },
-  },
-};
-
-export default { colors, styles };
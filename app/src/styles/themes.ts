@@ -0,0 +1,211 @@
+import { colors } from "./gruvbox.js";
+
+// Colors the UI is drawn with; each theme sets all of them
+export interface Palette {
+  bg0: string; // Text on colored backgrounds
+  bg1: string;
+  fg: string;
+  fg4: string; // Dimmed text
+  gray: string; // Borders and separators
+  red: string; // Errors and removed lines
+  green: string; // User messages, success and added lines
+  yellow: string; // System messages and warnings
+  blue: string; // Assistant messages
+  purple: string; // Tool calls
+  aqua: string; // Selections and hunk headers
+  orange: string; // Highlights
+}
+
+// Themes chosen with /theme or `theme` under [ui] in config.toml
+export const THEMES = ["dark", "light", "high-contrast", "solarized"] as const;
+export type ThemeName = (typeof THEMES)[number];
+
+const gruvbox = (source: typeof colors.dark | typeof colors.light): Palette => ({
+  bg0: source.bg0,
+  bg1: source.bg1,
+  fg: source.fg,
+  fg4: source.fg4,
+  gray: source.gray,
+  red: source.red,
+  green: source.green,
+  yellow: source.yellow,
+  blue: source.blue,
+  purple: source.purple,
+  aqua: source.aqua,
+  orange: source.orange,
+});
+
+export const palettes: Record<ThemeName, Palette> = {
+  dark: gruvbox(colors.dark),
+  light: gruvbox(colors.light),
+  // Pure black and white with bright accents, for low-vision users and
+  // washed-out projectors
+  "high-contrast": {
+    bg0: "#000000",
+    bg1: "#1c1c1c",
+    fg: "#ffffff",
+    fg4: "#d0d0d0",
+    gray: "#bcbcbc",
+    red: "#ff5f5f",
+    green: "#5fff5f",
+    yellow: "#ffff5f",
+    blue: "#5fafff",
+    purple: "#ff87ff",
+    aqua: "#5fffff",
+    orange: "#ffaf00",
+  },
+  // Solarized dark
+  solarized: {
+    bg0: "#002b36",
+    bg1: "#073642",
+    fg: "#93a1a1",
+    fg4: "#839496",
+    gray: "#657b83",
+    red: "#dc322f",
+    green: "#859900",
+    yellow: "#b58900",
+    blue: "#268bd2",
+    purple: "#d33682",
+    aqua: "#2aa198",
+    orange: "#cb4b16",
+  },
+};
+
+/**
+ * Check a theme name typed by the user
+ * @param name Theme name
+ * @returns True for one of THEMES
+ */
+export const isThemeName = (name: string): name is ThemeName =>
+  (THEMES as readonly string[]).includes(name);
+
+// Colors of the active theme. applyTheme changes them in place, so components
+// pick up the new colors when they next render.
+export const palette: Palette = { ...palettes.dark };
+
+// Common UI components styling
+const buildStyles = (p: Palette) => ({
+  // Box styles
+  box: {
+    default: {
+      borderColor: p.gray,
+    },
+    header: {
+      borderStyle: "single" as const, // Type assertion to make it compatible with Ink
+      borderColor: p.green,
+      paddingX: 2,
+      paddingY: 1,
+    },
+    content: {
+      borderStyle: "single" as const,
+      borderColor: p.gray,
+    },
+    input: {
+      borderStyle: "single" as const,
+      borderColor: p.gray,
+    },
+  },
+
+  // Text styles
+  text: {
+    heading: {
+      color: p.green,
+      bold: true,
+    },
+    subheading: {
+      color: p.yellow,
+      bold: true,
+    },
+    user: {
+      color: p.green,
+      bold: true,
+    },
+    assistant: {
+      color: p.blue,
+    },
+    system: {
+      color: p.yellow,
+      italic: true,
+    },
+    tool: {
+      color: p.purple,
+      bold: true,
+    },
+    highlight: {
+      color: p.orange,
+      bold: true,
+    },
+    dimmed: {
+      color: p.fg4,
+    },
+    error: {
+      color: p.red,
+    },
+    success: {
+      color: p.green,
+    },
+    warning: {
+      color: p.yellow,
+    },
+    info: {
+      color: p.blue,
+    },
+  },
+
+  // Status indicators
+  status: {
+    active: {
+      color: p.green,
+      icon: "●",
+    },
+    processing: {
+      color: p.yellow,
+      // Spinner will be used
+    },
+    error: {
+      color: p.red,
+      icon: "○",
+    },
+    inactive: {
+      color: p.gray,
+      icon: "○",
+    },
+  },
+
+  // Button-like elements
+  button: {
+    primary: {
+      color: p.bg0,
+      backgroundColor: p.green,
+      bold: true,
+    },
+    secondary: {
+      color: p.bg0,
+      backgroundColor: p.blue,
+      bold: true,
+    },
+    danger: {
+      color: p.bg0,
+      backgroundColor: p.red,
+      bold: true,
+    },
+  },
+});
+
+export const styles = buildStyles(palette);
+
+/**
+ * Switch the colors and styles components read to another theme
+ * @param name Theme to switch to
+ */
+export const applyTheme = (name: ThemeName): void => {
+  Object.assign(palette, palettes[name]);
+  const next = buildStyles(palette);
+  for (const group of Object.keys(next) as (keyof typeof next)[]) {
+    for (const [key, style] of Object.entries(next[group])) {
+      Object.assign((styles[group] as Record<string, object>)[key], style);
+    }
+  }
+};
+
+export default { palette, styles };
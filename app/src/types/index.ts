@@ -1,4 +1,5 @@
 // Common application types
+import type { ThemeName } from "../styles/themes.js";
 
 // Message interface
 export interface Message {
@@ -10,6 +11,19 @@ export interface Message {
   tool?: string;
   tool_status?: ToolStatus;
   tool_data?: ToolData;
+  turn_summary?: string; // e.g. "answered in 34s, 3 tool calls, 12.4k tokens"
+  evidence?: Evidence[]; // Numbered tool results the answer can cite as [#N]
+  citations?: number[]; // Indexes cited in the answer, in order
+}
+
+// A numbered tool result an answer can cite, e.g. [#3]
+export interface Evidence {
+  index: number;
+  tool: string;
+  tool_call_id: string;
+  target?: string;
+  excerpt: string;
+  truncated?: boolean;
 }
 
 // Message role type
@@ -39,6 +53,29 @@ export interface ToolExecution {
   metadata: Record<string, unknown>;
 }
 
+// Where a tool call of the current iteration stands
+export type QueuedCallStatus =
+  | "pending"
+  | "running"
+  | "completed"
+  | "failed"
+  | "denied";
+
+// One tool call in the queue
+export interface QueuedCall {
+  id: string;
+  name: string;
+  status: QueuedCallStatus;
+  duration_ms: number | null;
+}
+
+// Tool calls requested in one iteration of a turn, sent as tool_queue notifications
+export interface ToolQueue {
+  task_id: string | null;
+  iteration: number;
+  calls: QueuedCall[];
+}
+
 // Tool status update interface
 export interface ToolStatusUpdate {
   type: "started" | "updated";
@@ -71,9 +108,204 @@ export interface AppState {
   backendConnected: boolean;
   appMode: "setup" | "chat";
   useAgent: boolean;
+  showTimestamps?: boolean; // Show message timestamps in the gutter
+  accessible?: boolean; // Screen-reader friendly output: no animations, icons or color-only status
+  theme?: ThemeName; // Color theme chosen in config.toml or with /theme
+  readOnly?: boolean; // Another instance holds the workspace and modifying tools are denied
+  samplingPresets?: SamplingPresets | null; // Presets chosen with /preset, shown in the status bar
+  staging?: boolean; // Walking unstaged changes hunk by hunk with /stage
+  renaming?: RenameRequest | null; // Reviewing a /rename before it's applied
+  pullingModel?: string | null; // Ollama model being downloaded with /pull or from setup
+  pendingContext?: PastedContext | null; // Clipboard text attached to the next prompt
+  pendingAttachments?: ImageAttachment[]; // Images attached to the next prompt with /attach
+  sessionCost?: number | null; // Estimated cost of the session so far, null when no price is known
+  contextPercent?: number | null; // How full the model's context window is, null before the first run
   backendInfo?: Record<string, unknown>; // Contains backend-related info including version
 }
 
+// Clipboard text added with /paste-context, sent with the next prompt
+export interface PastedContext {
+  content: string;
+  format: string; // Detected format, e.g. "stacktrace", "log", "rust"
+  bytes: number;
+  lines: number;
+  truncated: boolean; // Larger than the backend attaches; it keeps the most useful part
+}
+
+// An image added with /attach, sent with the next prompt as image input
+export interface ImageAttachment {
+  path: string; // As typed, resolved by the backend against the working directory
+  mediaType: string; // e.g. "image/png"
+  bytes: number;
+}
+
+// A hunk of an unstaged change, as offered by /stage
+export interface Hunk {
+  id: string;
+  header: string; // The "@@ -a,b +c,d @@" line
+  lines: string[];
+  added: number;
+  removed: number;
+}
+
+// A file with unstaged changes
+export interface FileChange {
+  path: string;
+  status: "modified" | "added" | "deleted" | "untracked" | "binary";
+  hunks: Hunk[];
+}
+
+// An Edit or Write the backend asks to review before it runs, hunk by hunk
+export interface EditReviewRequest {
+  tool: string;
+  path: string;
+  is_new_file: boolean;
+  hunks: Hunk[];
+}
+
+// A tool call the backend asks permission for before it runs
+export interface PermissionRequest {
+  call_id: string | null;
+  tool: string;
+  arguments: Record<string, unknown>;
+  working_directory: string | null;
+  requested_at: number;
+}
+
+// A rename asked for with /rename <old> <new>
+export interface RenameRequest {
+  oldName: string;
+  newName: string;
+}
+
+// The edits a previewed rename makes to one file
+export interface FileRename {
+  path: string;
+  occurrences: number;
+  hunks: Hunk[];
+}
+
+// A previewed rename, as returned by preview_rename
+export interface RenamePlan {
+  old_name: string;
+  new_name: string;
+  source: "lsp" | "text"; // Language server rename, or whole-word matches
+  occurrences: number;
+  files: FileRename[];
+}
+
+// A side question asked with Ctrl+A, as returned by ask
+export interface AskAnswer {
+  id: string;
+  question: string;
+  model: string; // provider:model spec of the model that answered
+  answer: string | null;
+  error: string | null;
+  latency_ms: number;
+}
+
+// A language server or Bash command started by an oli instance, as listed by
+// get_child_processes
+export interface ChildProcessInfo {
+  orphaned: boolean; // The instance that started it has exited
+  own: boolean; // Started by this instance
+  idle_secs: number;
+  process: {
+    pid: number;
+    kind: "lsp" | "bash";
+    command: string;
+    owner_pid: number;
+    started_at: string;
+  };
+}
+
+export type PermissionLevel = "auto" | "ask" | "deny";
+
+// Tools and exact Bash commands the project always allows and its permission
+// levels, as returned by get_permission_rules, set_permission_rule and
+// set_permission_level
+export interface PermissionRules {
+  path: string; // The user's rules file, outside the workspace
+  project: string; // Canonical path of the project the rules are for
+  tools: string[];
+  commands: string[];
+  levels: Record<string, PermissionLevel>; // Can only tighten configured_levels
+  configured_levels: Record<string, PermissionLevel> | null; // From config.toml
+  edit_paths: string[] | null; // Globs Edit, Write and WriteMany are confined to
+  policy_error: string | null; // Why the configured policy is invalid
+}
+
+// One provider request, as recorded in the request ledger
+export interface RequestRecord {
+  timestamp: string;
+  provider: string;
+  model: string;
+  input_tokens: number; // Excludes cached input tokens
+  output_tokens: number;
+  cache_creation_tokens: number;
+  cache_read_tokens: number;
+  latency_ms: number;
+  cost_usd: number | null; // Null when the model's price isn't known
+  estimated: boolean; // Tokens were estimated because the provider didn't report them
+}
+
+// Sums over the requests returned by get_request_ledger
+export interface LedgerTotals {
+  requests: number;
+  input_tokens: number;
+  output_tokens: number;
+  cache_creation_tokens: number;
+  cache_read_tokens: number;
+  cost_usd: number;
+  unpriced_requests: number;
+}
+
+// Disk space used by one kind of file, as returned by get_storage_usage
+export interface StorageCategory {
+  name: string;
+  path: string;
+  bytes: number;
+  files: number;
+  prunable: boolean; // Records such as the audit log are never pruned
+}
+
+// The logs kept for one session
+export interface StoredSession {
+  id: string;
+  bytes: number;
+  files: number;
+  last_modified: string;
+  pinned: boolean;
+}
+
+// A saved conversation as listed by /resume
+export interface SavedSessionListing {
+  id: string;
+  title: string;
+  updated_at: string;
+  starred: boolean;
+  messages: number;
+  tags: string[];
+}
+
+// A message of a saved conversation found by /search
+export interface SessionSearchHit {
+  session_id: string;
+  title: string;
+  starred: boolean;
+  index: number;
+  role: string;
+  tags: string[];
+  snippet: string;
+}
+
+// UI state saved periodically so a draft survives a crash
+export interface SessionState {
+  draft: string; // Unsubmitted prompt, including earlier lines of a multi-line prompt
+  scroll_offset: number; // Messages scrolled back from the latest
+  saved_at?: string;
+}
+
 // Available commands
 export interface Command {
   name: string;
@@ -107,3 +339,39 @@ export interface JsonRpcNotification {
   method: string;
   params: Record<string, unknown>;
 }
+
+// Progress of the workspace file index, sent as index_progress notifications
+export interface QuickStart {
+  model_index: number;
+  model: string;
+  source: string; // API key variable the key comes from, or "Ollama"
+}
+
+// A progress update of an Ollama model download
+export interface OllamaPullProgress {
+  model: string;
+  status: string; // e.g. "pulling manifest", "pulling dde5aa3fc5ff", "success"
+  completed?: number | null; // Bytes of the current layer received so far
+  total?: number | null; // Size of the current layer in bytes
+}
+
+export interface SamplingPresets {
+  default: string | null; // Preset for every mode without its own
+  modes: Record<string, string>; // "chat", "agent" or "compare" to "precise", "balanced" or "creative"
+}
+
+// The system prompt in use and where it comes from, for /prompt
+export interface SystemPromptInfo {
+  source:
+    | { kind: "default" }
+    | { kind: "user" | "project"; path: string }
+    | { kind: "template"; name: string; path: string };
+  content: string;
+}
+
+export interface IndexStatus {
+  root: string;
+  large_repo: boolean; // Glob, Grep and FindFile use the index and stricter result limits
+  files_indexed: number;
+  complete: boolean; // The first walk of the workspace has finished
+}
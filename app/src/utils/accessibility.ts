@@ -0,0 +1,95 @@
+import { Message, MessageRole, ToolStatus } from "../types/index.js";
+
+/**
+ * Environment variable that turns on accessibility mode, like --accessible
+ */
+export const ACCESSIBLE_ENV = "OLI_ACCESSIBLE";
+
+/**
+ * Whether accessibility mode is enabled in the environment
+ * @returns True when OLI_ACCESSIBLE is "1" or "true"
+ */
+export const isAccessibleFromEnv = (): boolean => {
+  const value = process.env[ACCESSIBLE_ENV]?.trim().toLowerCase();
+  return value === "1" || value === "true";
+};
+
+/**
+ * Plain-text prefix for a tool status, used instead of icons and color
+ * @param status Tool status
+ * @returns Prefix such as "[running]"
+ */
+export const statusPrefix = (status: ToolStatus): string => {
+  switch (status) {
+    case "running":
+      return "[running]";
+    case "success":
+      return "[done]";
+    case "error":
+      return "[error]";
+    default:
+      return "[tool]";
+  }
+};
+
+/**
+ * Spoken label for a message author
+ * @param role Message role
+ * @returns Label such as "Assistant:"
+ */
+export const rolePrefix = (role: MessageRole): string => {
+  switch (role) {
+    case "user":
+      return "You:";
+    case "assistant":
+      return "Assistant:";
+    case "tool":
+      return "Tool:";
+    default:
+      return "System:";
+  }
+};
+
+/**
+ * Remove emoji and decorative symbols that screen readers announce as noise
+ * @param text Text that may contain emoji
+ * @returns Text with them removed
+ */
+export const stripDecorations = (text: string): string => {
+  return text
+    .replace(/[\p{Extended_Pictographic}⎿⏺✓✗️]/gu, "")
+    .replace(/[ \t]{2,}/g, " ")
+    .trim();
+};
+
+/**
+ * Describe a message as a single line of plain text, tool results included
+ * @param message Message to describe
+ * @returns Plain-text line
+ */
+export const describeMessage = (message: Message): string => {
+  if (message.role === "tool" && message.tool_status && message.tool_data) {
+    const { name, file_path, description } = message.tool_data;
+    const target = file_path ? ` ${file_path}` : "";
+    const details = description ? `: ${stripDecorations(description)}` : "";
+    return `Tool ${statusPrefix(message.tool_status)} ${name}${target}${details}`;
+  }
+
+  const line = `${rolePrefix(message.role)} ${stripDecorations(message.content)}`;
+  return message.turn_summary ? `${line} (${message.turn_summary})` : line;
+};
+
+/**
+ * Build a linearized transcript of the conversation for screen readers, one
+ * message after another with timestamps and no layout
+ * @param messages Conversation messages
+ * @returns Transcript text
+ */
+export const linearizeTranscript = (messages: Message[]): string => {
+  return messages
+    .map((message) => {
+      const time = new Date(message.timestamp).toISOString();
+      return `[${time}] ${describeMessage(message)}`;
+    })
+    .join("\n\n");
+};
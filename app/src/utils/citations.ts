@@ -0,0 +1,53 @@
+import { Evidence, Message } from "../types/index.js";
+
+/**
+ * Evidence entries a message cites, in citation order. Falls back to every
+ * entry when the answer cites none, so the tool results can still be checked.
+ * @param message Assistant message with evidence attached
+ * @returns Entries to step through
+ */
+export const citedEvidence = (message: Message): Evidence[] => {
+  const entries = message.evidence ?? [];
+  const cited = (message.citations ?? [])
+    .map((index) => entries.find((entry) => entry.index === index))
+    .filter((entry): entry is Evidence => entry !== undefined);
+  return cited.length > 0 ? cited : entries;
+};
+
+/**
+ * Citations in a message that don't match any of its evidence entries
+ * @param message Assistant message with evidence attached
+ * @returns Unmatched indexes
+ */
+export const unresolvedCitations = (message: Message): number[] => {
+  const entries = message.evidence ?? [];
+  return (message.citations ?? []).filter(
+    (index) => !entries.some((entry) => entry.index === index),
+  );
+};
+
+/**
+ * One-line label for an evidence entry
+ * @param entry Evidence entry
+ * @returns Label such as "[#3] Grep parse_args"
+ */
+export const evidenceLabel = (entry: Evidence): string => {
+  const target = entry.target ? ` ${entry.target}` : "";
+  return `[#${entry.index}] ${entry.tool}${target}`;
+};
+
+/**
+ * The latest assistant message that has evidence to show
+ * @param messages Conversation messages
+ * @returns The message, if any
+ */
+export const latestMessageWithEvidence = (
+  messages: Message[],
+): Message | undefined => {
+  return [...messages]
+    .reverse()
+    .find(
+      (message) =>
+        message.role === "assistant" && (message.evidence?.length ?? 0) > 0,
+    );
+};
@@ -0,0 +1,64 @@
+import { Message } from "../types/index.js";
+
+/**
+ * Environment variable with the number of lines above which answers are
+ * collapsed; 0 never collapses them
+ */
+export const COLLAPSE_LINES_ENV = "OLI_COLLAPSE_LINES";
+
+// Lines an answer may have before it's collapsed, unless overridden
+const DEFAULT_COLLAPSE_LINES = 60;
+
+// Lines of a collapsed answer shown as its preview
+const PREVIEW_LINES = 20;
+
+/**
+ * Line count above which answers are collapsed
+ * @returns OLI_COLLAPSE_LINES when it's a whole number, otherwise the default
+ */
+export const collapseThreshold = (): number => {
+  const value = Number.parseInt(process.env[COLLAPSE_LINES_ENV] ?? "", 10);
+  return Number.isNaN(value) || value < 0 ? DEFAULT_COLLAPSE_LINES : value;
+};
+
+/**
+ * Whether a message is an answer long enough to collapse
+ * @param message Message to check
+ * @param threshold Line count above which answers are collapsed
+ * @returns True for assistant messages over the threshold
+ */
+export const isCollapsible = (message: Message, threshold: number): boolean =>
+  threshold > 0 &&
+  message.role === "assistant" &&
+  message.content.split("\n").length > threshold;
+
+/**
+ * The start of a long answer, shown until it's expanded
+ * @param content Full answer
+ * @returns Preview text and how many lines it leaves out
+ */
+export const collapsedPreview = (
+  content: string,
+): { preview: string; hiddenLines: number } => {
+  const lines = content.split("\n");
+  return {
+    preview: lines.slice(0, PREVIEW_LINES).join("\n"),
+    hiddenLines: Math.max(0, lines.length - PREVIEW_LINES),
+  };
+};
+
+/**
+ * The answer "o" expands or collapses: the latest long one on screen
+ * @param messages Messages on screen, oldest first
+ * @param threshold Line count above which answers are collapsed
+ * @returns Its message ID, or null when no answer on screen is long
+ */
+export const latestCollapsibleId = (
+  messages: Message[],
+  threshold: number,
+): string | null => {
+  for (let i = messages.length - 1; i >= 0; i--) {
+    if (isCollapsible(messages[i], threshold)) return messages[i].id;
+  }
+  return null;
+};
@@ -1,7 +1,33 @@
-import { AppState } from "../types/index.js";
+import {
+  AppState,
+  ChildProcessInfo,
+  Evidence,
+  LedgerTotals,
+  PastedContext,
+  ImageAttachment,
+  PermissionRules,
+  RequestRecord,
+  SamplingPresets,
+  SavedSessionListing,
+  SessionSearchHit,
+  StorageCategory,
+  StoredSession,
+  SystemPromptInfo,
+} from "../types/index.js";
 import { BackendService } from "../services/backend.js";
 import { createMessages } from "./messageUtils.js";
 import { getHelpMessage } from "./commandUtils.js";
+import { linearizeTranscript } from "./accessibility.js";
+import { THEMES, applyTheme, isThemeName } from "../styles/themes.js";
+import {
+  conversationToHtml,
+  conversationToMarkdown,
+  defaultExportPath,
+  exportFormat,
+  exportedMessages,
+} from "./export.js";
+import fs from "fs";
+import path from "path";
 
 /**
  * Function type for command handlers
@@ -117,27 +143,1936 @@ export const handleClearCommand: CommandHandler = async (
 /**
  * Handle exit command
  */
-export const handleExitCommand: CommandHandler = () => {
+export const handleExitCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  // Document the session in .oli/CHANGELOG-sessions.md when OLI_SESSION_SUMMARY is set
+  try {
+    const result = await backend.call("summarize_session", {});
+    if (result.summary) {
+      console.log(`\n${result.summary}\nAppended to ${result.path}`);
+    }
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    console.error(`Could not write the session summary: ${errorMessage}`);
+  }
   process.exit(0);
 };
 
 /**
- * Handle model command
+ * Handle cancel command, stopping the running agent turn with a partial result
+ */
+export const handleCancelCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+  const reply = (content: string) => {
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  };
+
+  if (!state.isProcessing) {
+    reply("Nothing is running");
+    return;
+  }
+
+  try {
+    await backend.call("interrupt_processing", {});
+    reply("Cancelling; the partial result follows");
+  } catch (error) {
+    const errorText = error instanceof Error ? error.message : String(error);
+    reply(`Error cancelling the task: ${errorText}`);
+  }
+};
+
+/**
+ * Handle undo command, restoring the files changed by the last agent turn
+ */
+export const handleUndoCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+  const reply = (content: string) => {
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  };
+
+  if (state.isProcessing) {
+    reply("Wait for the current task to finish, or /cancel it, before undoing");
+    return;
+  }
+
+  try {
+    const result = await backend.call("undo_last_turn", {});
+    const remaining =
+      result.remaining > 0
+        ? `\n${result.remaining} earlier turns can still be undone`
+        : "";
+    reply(`${result.message}${remaining}`);
+  } catch (error) {
+    const errorText = error instanceof Error ? error.message : String(error);
+    reply(`Error undoing the last turn: ${errorText}`);
+  }
+};
+
+/**
+ * Handle init command, using the project's OLI.md or AGENTS.md from now on or
+ * writing a starter OLI.md when it has neither
+ */
+export const handleInitCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+
+  if (state.isProcessing) {
+    const systemMessage = createMessages([
+      {
+        role: "system",
+        content: "Wait for the current task to finish, or /cancel it, first",
+      },
+    ])[0];
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+    return;
+  }
+
+  setState((prev) => ({
+    ...prev,
+    messages: [...prev.messages, userMessage],
+    isProcessing: true,
+  }));
+
+  try {
+    const result = await backend.call("init_project_instructions", {
+      model_index: state.selectedModel,
+    });
+    const systemMessage = createMessages([
+      { role: "system", content: result.message as string },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, systemMessage],
+      isProcessing: false,
+    }));
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    const systemMessage = createMessages([
+      {
+        role: "system",
+        content: `Error setting up project instructions: ${errorMessage}`,
+      },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, systemMessage],
+      isProcessing: false,
+      error: errorMessage,
+    }));
+  }
+};
+
+/**
+ * Handle compact command: summarize older messages on the compaction model,
+ * optionally focusing on what follows the command, e.g. /compact the parser bug
+ */
+export const handleCompactCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const instructions = command.replace(/^\/compact\s*/, "").trim();
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+
+  if (state.isProcessing) {
+    const systemMessage = createMessages([
+      {
+        role: "system",
+        content: "Wait for the current task to finish, or /cancel it, first",
+      },
+    ])[0];
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+    return;
+  }
+
+  setState((prev) => ({
+    ...prev,
+    messages: [...prev.messages, userMessage],
+    isProcessing: true,
+  }));
+
+  let content: string;
+  try {
+    const result = await backend.call("compact_conversation", {
+      ...(instructions ? { instructions } : {}),
+    });
+    content = result.message as string;
+  } catch (error) {
+    content = `Error compacting the conversation: ${error instanceof Error ? error.message : String(error)}`;
+  }
+
+  const systemMessage = createMessages([{ role: "system", content }])[0];
+  setState((prev) => ({
+    ...prev,
+    messages: [...prev.messages, systemMessage],
+    isProcessing: false,
+  }));
+};
+
+/**
+ * Handle model command
+ */
+export const handleModelCommand: CommandHandler = (
+  command,
+  state,
+  setState,
+) => {
+  // Add user command to chat first
+  const messages = createMessages([{ role: "user", content: command }]);
+
+  // Just switch to setup mode without additional message
+  setState((prev) => ({
+    ...prev,
+    messages: [...prev.messages, messages[0]],
+    appMode: "setup", // Switch to setup mode
+  }));
+};
+
+/**
+ * Handle set command (e.g. "/set temperature 0.7" or "/set top_p default")
+ */
+export const handleSetCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const [, name, value] = command.trim().split(/\s+/);
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+
+  // Show current settings when no parameter is given
+  if (!name || !value) {
+    try {
+      const result = await backend.call("get_model_parameters", {});
+      const systemMessage = createMessages([
+        {
+          role: "system",
+          content: `Usage: /set <temperature|top_p|max_tokens> <value|default>\nCurrent parameters: ${JSON.stringify(result.parameters)}`,
+        },
+      ])[0];
+
+      setState((prev) => ({
+        ...prev,
+        messages: [...prev.messages, userMessage, systemMessage],
+      }));
+    } catch (error) {
+      const errorMessage = error instanceof Error ? error.message : String(error);
+      const systemMessage = createMessages([
+        { role: "system", content: `Error reading parameters: ${errorMessage}` },
+      ])[0];
+
+      setState((prev) => ({
+        ...prev,
+        messages: [...prev.messages, userMessage, systemMessage],
+      }));
+    }
+    return;
+  }
+
+  try {
+    await backend.call("set_model_parameter", { name, value });
+
+    const systemMessage = createMessages([
+      { role: "system", content: `Set ${name} to ${value}` },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    const systemMessage = createMessages([
+      { role: "system", content: `Error setting ${name}: ${errorMessage}` },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  }
+};
+
+/**
+ * Handle timestamps command
+ */
+export const handleTimestampsCommand: CommandHandler = (
+  command,
+  state,
+  setState,
+) => {
+  const showTimestamps = !state.showTimestamps;
+  const messages = createMessages([
+    { role: "user", content: command },
+    {
+      role: "system",
+      content: `Timestamps ${showTimestamps ? "enabled" : "disabled"}`,
+    },
+  ]);
+
+  setState((prev) => ({
+    ...prev,
+    showTimestamps,
+    messages: [...prev.messages, ...messages],
+  }));
+};
+
+/**
+ * Handle accessible command, toggling screen-reader friendly output
+ */
+export const handleAccessibleCommand: CommandHandler = (
+  command,
+  state,
+  setState,
+) => {
+  const accessible = !state.accessible;
+  const messages = createMessages([
+    { role: "user", content: command },
+    {
+      role: "system",
+      content: `Accessibility mode ${accessible ? "enabled" : "disabled"}`,
+    },
+  ]);
+
+  setState((prev) => ({
+    ...prev,
+    accessible,
+    messages: [...prev.messages, ...messages],
+  }));
+};
+
+/**
+ * Handle theme command, listing the color themes ("/theme") or switching to
+ * one for the rest of the session ("/theme light")
+ */
+export const handleThemeCommand: CommandHandler = (
+  command,
+  state,
+  setState,
+) => {
+  const name = command.split(/\s+/)[1]?.toLowerCase();
+  const current = state.theme ?? "dark";
+
+  let content: string;
+  if (!name) {
+    const list = THEMES.map((theme) =>
+      theme === current ? `${theme} (current)` : theme,
+    ).join(", ");
+    content = `Themes: ${list}\nUse /theme <name> to switch.`;
+  } else if (!isThemeName(name)) {
+    content = `Unknown theme "${name}". Choose one of: ${THEMES.join(", ")}`;
+  } else {
+    applyTheme(name);
+    content = `Switched to the ${name} theme. Set theme = "${name}" under [ui] in config.toml to keep it.`;
+  }
+
+  const messages = createMessages([
+    { role: "user", content: command },
+    { role: "system", content },
+  ]);
+
+  setState((prev) => ({
+    ...prev,
+    theme: name && isThemeName(name) ? name : prev.theme,
+    messages: [...prev.messages, ...messages],
+  }));
+};
+
+/**
+ * Handle transcript command (e.g. "/transcript" or "/transcript notes.txt"),
+ * writing the conversation as plain text for screen readers
+ */
+export const handleTranscriptCommand: CommandHandler = (
+  command,
+  state,
+  setState,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+  const target = command.split(" ").slice(1).join(" ").trim() || "oli-transcript.txt";
+  const transcriptPath = path.resolve(process.cwd(), target);
+
+  let content: string;
+  try {
+    fs.writeFileSync(transcriptPath, `${linearizeTranscript(state.messages)}\n`);
+    content = `Transcript of ${state.messages.length} messages written to ${transcriptPath}`;
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    content = `Error writing transcript: ${errorMessage}`;
+  }
+
+  const systemMessage = createMessages([{ role: "system", content }])[0];
+  setState((prev) => ({
+    ...prev,
+    messages: [...prev.messages, userMessage, systemMessage],
+  }));
+};
+
+/**
+ * Handle export command (e.g. "/export" or "/export review.html"), writing the
+ * conversation as Markdown, or HTML for .html paths, to attach to PRs and issues
+ */
+export const handleExportCommand: CommandHandler = (
+  command,
+  state,
+  setState,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+  const target = command.split(" ").slice(1).join(" ").trim() || defaultExportPath();
+  const exportPath = path.resolve(process.cwd(), target);
+  const model = state.models[state.selectedModel]?.name ?? "unknown model";
+  const format = exportFormat(exportPath);
+
+  let content: string;
+  try {
+    const document =
+      format === "html"
+        ? conversationToHtml(state.messages, model)
+        : conversationToMarkdown(state.messages, model);
+    fs.writeFileSync(exportPath, document);
+    const count = exportedMessages(state.messages).length;
+    content = `Conversation (${count} messages) exported as ${format === "html" ? "HTML" : "Markdown"} to ${exportPath}`;
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    content = `Error exporting the conversation: ${errorMessage}`;
+  }
+
+  const systemMessage = createMessages([{ role: "system", content }])[0];
+  setState((prev) => ({
+    ...prev,
+    messages: [...prev.messages, userMessage, systemMessage],
+  }));
+};
+
+/**
+ * Handle audit command (e.g. "/audit" or "/audit 50")
+ */
+export const handleAuditCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const [, limitArg] = command.trim().split(/\s+/);
+  const limit = Number.parseInt(limitArg ?? "", 10) || 20;
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+
+  try {
+    const result = await backend.call("get_audit_log", { limit });
+    const entries = (result.entries ?? []) as Array<{
+      timestamp: string;
+      tool: string;
+      arguments: Record<string, unknown>;
+      decision: string;
+      outcome?: string | null;
+    }>;
+
+    const lines = entries.map((entry) => {
+      const target =
+        entry.arguments.file_path ?? entry.arguments.command ?? "";
+      const status =
+        entry.decision === "denied" ? "denied" : (entry.outcome ?? "allowed");
+      return `  ${entry.timestamp}  ${entry.tool}  ${status}  ${target}`;
+    });
+
+    const content =
+      lines.length > 0
+        ? `Audit log (${result.path}):\n${lines.join("\n")}`
+        : `No audited actions yet (${result.path})`;
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    const systemMessage = createMessages([
+      { role: "system", content: `Error reading audit log: ${errorMessage}` },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  }
+};
+
+/**
+ * Handle env command, showing the shell, PATH and active environments Bash commands run with
+ */
+export const handleEnvCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+
+  try {
+    const result = await backend.call("get_shell_environment", {});
+    const systemMessage = createMessages([
+      { role: "system", content: result.summary as string },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    const systemMessage = createMessages([
+      {
+        role: "system",
+        content: `Error reading shell environment: ${errorMessage}`,
+      },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  }
+};
+
+/**
+ * Handle key command (e.g. "/key sk-ant-..."), checking an API key for the selected
+ * model with its provider and storing it for future sessions if it's accepted
+ */
+export const handleKeyCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const key = command.split(/\s+/).slice(1).join("").trim();
+  // Never echo the key itself into the conversation
+  const userMessage = createMessages([
+    { role: "user", content: key ? "/key ••••••••" : command },
+  ])[0];
+
+  if (!key) {
+    const systemMessage = createMessages([
+      {
+        role: "system",
+        content:
+          "Usage: /key <api key>\nThe key is checked with the selected model's provider and stored for future sessions if it's accepted.",
+      },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+    return;
+  }
+
+  try {
+    const result = await backend.call("save_api_key", {
+      model_index: state.selectedModel,
+      key,
+    });
+    const content = result.saved
+      ? `${result.report}\nKey stored in ${result.path} and used from now on. A key set in the environment or .env takes precedence in later sessions.`
+      : (result.report as string);
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+      error: result.saved ? null : prev.error,
+    }));
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    const systemMessage = createMessages([
+      { role: "system", content: `Error saving the API key: ${errorMessage}` },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  }
+};
+
+/**
+ * Handle paste-context command, attaching the clipboard to the next prompt as a
+ * labeled block instead of pasting it into the input box ("/paste-context clear"
+ * drops it again)
+ */
+export const handlePasteContextCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+
+  if (command.split(/\s+/)[1] === "clear") {
+    const systemMessage = createMessages([
+      {
+        role: "system",
+        content: state.pendingContext
+          ? "Clipboard context dropped"
+          : "No clipboard context is attached",
+      },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      pendingContext: null,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+    return;
+  }
+
+  try {
+    const result = await backend.call("read_clipboard", {});
+    const pasted: PastedContext = {
+      content: result.content as string,
+      format: result.format as string,
+      bytes: result.bytes as number,
+      lines: result.lines as number,
+      truncated: result.truncated as boolean,
+    };
+
+    const size = `${(pasted.bytes / 1024).toFixed(1)} KB`;
+    const guard = pasted.truncated
+      ? `\nIt's larger than the ${Math.round((result.max_bytes as number) / 1024)} KB limit, so only the most useful part is sent.`
+      : "";
+    const systemMessage = createMessages([
+      {
+        role: "system",
+        content: `Clipboard attached to your next prompt: ${pasted.format}, ${pasted.lines} lines, ${size}.${guard}\nUse /paste-context clear to drop it.`,
+      },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      pendingContext: pasted,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    const systemMessage = createMessages([
+      { role: "system", content: `Error reading the clipboard: ${errorMessage}` },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  }
+};
+
+/**
+ * Handle attach command, attaching image files such as screenshots to the next
+ * prompt as image input ("/attach clear" drops them again)
+ */
+export const handleAttachCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+  const paths = command.split(/\s+/).slice(1).filter(Boolean);
+  const attached = state.pendingAttachments ?? [];
+
+  if (paths.length === 0 || paths[0] === "clear") {
+    const content =
+      paths[0] === "clear"
+        ? attached.length
+          ? `Dropped ${attached.length} attached image(s)`
+          : "No images are attached"
+        : "Usage: /attach <path> [path...] attaches images to your next prompt; /attach clear drops them";
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+
+    setState((prev) => ({
+      ...prev,
+      pendingAttachments: paths[0] === "clear" ? [] : prev.pendingAttachments,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+    return;
+  }
+
+  try {
+    const added: ImageAttachment[] = [];
+    let maxImages = Infinity;
+    let modelAcceptsImages = true;
+    for (const path of paths) {
+      const result = await backend.call("check_attachment", {
+        path,
+        model_index: state.selectedModel,
+      });
+      added.push({
+        path,
+        mediaType: result.media_type as string,
+        bytes: result.bytes as number,
+      });
+      maxImages = result.max_images as number;
+      modelAcceptsImages = result.model_accepts_images as boolean;
+    }
+
+    const total = attached.length + added.length;
+    if (total > maxImages) {
+      throw new Error(`At most ${maxImages} images can be sent with a prompt`);
+    }
+
+    const list = added
+      .map((image) => `${image.path} (${(image.bytes / 1024).toFixed(1)} KB)`)
+      .join(", ");
+    const guard = modelAcceptsImages
+      ? ""
+      : "\nThe selected model doesn't accept images, so they'll be left out unless you switch models.";
+    const systemMessage = createMessages([
+      {
+        role: "system",
+        content: `Attached to your next prompt: ${list}.${guard}\nUse /attach clear to drop ${total === 1 ? "it" : "them"}.`,
+      },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      pendingAttachments: [...(prev.pendingAttachments ?? []), ...added],
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    const systemMessage = createMessages([
+      { role: "system", content: `Error attaching the image: ${errorMessage}` },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  }
+};
+
+/**
+ * Handle takeover command, enabling every tool in a second instance that was
+ * made read-only because another instance holds the workspace
+ */
+export const handleTakeoverCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+
+  try {
+    const result = await backend.call("override_workspace_lock", {});
+    const content = result.primary
+      ? "This instance already holds the workspace"
+      : "Read-only mode lifted. Edit, Write and Bash are enabled, but the other instance can still overwrite your changes.";
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+
+    setState((prev) => ({
+      ...prev,
+      readOnly: false,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    const systemMessage = createMessages([
+      {
+        role: "system",
+        content: `Error overriding the workspace lock: ${errorMessage}`,
+      },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  }
+};
+
+/**
+ * Lay out two blocks of text as columns of the given width
+ */
+const sideBySide = (left: string, right: string, width: number): string => {
+  const wrap = (text: string): string[] =>
+    text.split("\n").flatMap((line) => {
+      if (line.length === 0) return [""];
+      const chunks: string[] = [];
+      for (let i = 0; i < line.length; i += width) {
+        chunks.push(line.slice(i, i + width));
+      }
+      return chunks;
+    });
+
+  const leftLines = wrap(left);
+  const rightLines = wrap(right);
+  const rows = Math.max(leftLines.length, rightLines.length);
+
+  return Array.from({ length: rows }, (_, i) =>
+    `${(leftLines[i] ?? "").padEnd(width)} │ ${rightLines[i] ?? ""}`.trimEnd(),
+  ).join("\n");
+};
+
+/**
+ * Handle compare command (e.g. "/compare 0 1 explain src/main.rs"). Models are
+ * given by index or name; without a prompt the last message is compared.
+ */
+export const handleCompareCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const [, modelA, modelB, ...promptWords] = command.trim().split(/\s+/);
+  const lastPrompt = [...state.messages]
+    .reverse()
+    .find((msg) => msg.role === "user" && !msg.content.startsWith("/"));
+  const prompt = promptWords.join(" ") || lastPrompt?.content;
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+
+  if (!modelA || !modelB || !prompt) {
+    const systemMessage = createMessages([
+      {
+        role: "system",
+        content:
+          "Usage: /compare <model_a> <model_b> [prompt]\nModels are given by index or name. Without a prompt, your last message is used.",
+      },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+    return;
+  }
+
+  setState((prev) => ({
+    ...prev,
+    messages: [...prev.messages, userMessage],
+    isProcessing: true,
+  }));
+
+  try {
+    const result = await backend.call("compare_models", {
+      prompt,
+      model_a: /^\d+$/.test(modelA) ? Number(modelA) : modelA,
+      model_b: /^\d+$/.test(modelB) ? Number(modelB) : modelB,
+    });
+    const answers = result.answers as Array<{
+      model: string;
+      response?: string | null;
+      error?: string | null;
+      latency_ms: number;
+      input_tokens: number;
+      output_tokens: number;
+    }>;
+
+    const column = (answer: (typeof answers)[number]) =>
+      [
+        answer.model,
+        `${(answer.latency_ms / 1000).toFixed(1)}s · ~${answer.input_tokens} in · ~${answer.output_tokens} out`,
+        "",
+        answer.response ?? `Error: ${answer.error ?? "no answer"}`,
+      ].join("\n");
+
+    const width = Math.max(
+      20,
+      Math.floor(((process.stdout.columns || 100) - 7) / 2),
+    );
+    const systemMessage = createMessages([
+      {
+        role: "system",
+        content: sideBySide(column(answers[0]), column(answers[1]), width),
+      },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, systemMessage],
+      isProcessing: false,
+    }));
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    const systemMessage = createMessages([
+      { role: "system", content: `Error comparing models: ${errorMessage}` },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, systemMessage],
+      isProcessing: false,
+      error: errorMessage,
+    }));
+  }
+};
+
+/**
+ * Handle stage command, walking unstaged changes hunk by hunk
+ */
+export const handleStageCommand: CommandHandler = (
+  command,
+  state,
+  setState,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+
+  if (state.isProcessing) {
+    const systemMessage = createMessages([
+      {
+        role: "system",
+        content: "Wait for the current task to finish before staging its changes",
+      },
+    ])[0];
+
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+    return;
+  }
+
+  // App swaps the chat for the staging view until it reports back
+  setState((prev) => ({
+    ...prev,
+    messages: [...prev.messages, userMessage],
+    staging: true,
+  }));
+};
+
+/**
+ * Handle pull command, downloading an Ollama model (e.g. "/pull llama3.2")
+ */
+export const handlePullCommand: CommandHandler = (command, state, setState) => {
+  const model = command.trim().split(/\s+/)[1];
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+
+  const reply = (content: string) => {
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  };
+
+  if (!model) {
+    reply("Usage: /pull <model>\nDownloads a model into Ollama and selects it, e.g. /pull llama3.2");
+    return;
+  }
+  if (state.isProcessing) {
+    reply("Wait for the current task to finish before pulling a model");
+    return;
+  }
+
+  // App swaps the chat for the download progress until it reports back
+  setState((prev) => ({
+    ...prev,
+    messages: [...prev.messages, userMessage],
+    pullingModel: model,
+  }));
+};
+
+/**
+ * Handle rename command: preview a project-wide rename for approval, or undo the last one
+ */
+export const handleRenameCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+  const args = command.split(/\s+/).slice(1).filter(Boolean);
+
+  const reply = (content: string) => {
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  };
+
+  if (args.length === 1 && args[0] === "undo") {
+    try {
+      const result = await backend.call("undo_rename", {});
+      reply(`Rename undone: ${result.message}`);
+    } catch (error) {
+      reply(
+        `Error undoing the rename: ${error instanceof Error ? error.message : String(error)}`,
+      );
+    }
+    return;
+  }
+
+  if (args.length !== 2) {
+    reply(
+      "Usage: /rename <old> <new>\nRenames a symbol across the workspace after you review the diff. /rename undo reverts the last rename.",
+    );
+    return;
+  }
+
+  if (state.isProcessing) {
+    reply("Wait for the current task to finish before renaming");
+    return;
+  }
+
+  // App swaps the chat for the rename review until it reports back
+  setState((prev) => ({
+    ...prev,
+    messages: [...prev.messages, userMessage],
+    renaming: { oldName: args[0], newName: args[1] },
+  }));
+};
+
+// Idle time as e.g. "45s", "12m" or "3h"
+const formatIdle = (secs: number): string => {
+  if (secs < 60) return `${secs}s`;
+  if (secs < 3600) return `${Math.floor(secs / 60)}m`;
+  return `${Math.floor(secs / 3600)}h`;
+};
+
+/**
+ * Handle processes command: list the language servers and Bash commands oli
+ * started (e.g. "/processes"), or kill one (e.g. "/processes kill 4242")
+ */
+export const handleProcessesCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+  const args = command.split(/\s+/).slice(1).filter(Boolean);
+
+  const reply = (content: string) => {
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  };
+  const errorText = (error: unknown) =>
+    error instanceof Error ? error.message : String(error);
+
+  if (args[0] === "kill") {
+    const pid = Number.parseInt(args[1] ?? "", 10);
+    if (!pid) {
+      reply("Usage: /processes kill <pid>");
+      return;
+    }
+    try {
+      const result = await backend.call("kill_child_process", { pid });
+      const killed = result.process as ChildProcessInfo["process"];
+      reply(`Killed ${killed.kind} process ${killed.pid} (${killed.command})`);
+    } catch (error) {
+      reply(`Error killing process ${pid}: ${errorText(error)}`);
+    }
+    return;
+  }
+
+  try {
+    const result = await backend.call("get_child_processes", {});
+    const processes = (result.processes ?? []) as ChildProcessInfo[];
+    if (processes.length === 0) {
+      reply("No language servers or Bash commands are running");
+      return;
+    }
+
+    const lines = processes.map(({ process, orphaned, own, idle_secs }) => {
+      const owner = orphaned
+        ? "orphaned"
+        : own
+          ? "this instance"
+          : `instance ${process.owner_pid}`;
+      return `  ${process.pid}  ${process.kind}  idle ${formatIdle(idle_secs)}  ${owner}  ${process.command}`;
+    });
+    const timeout = result.idle_timeout_secs as number | null;
+    const footer = timeout
+      ? `Idle children of this instance are killed after ${formatIdle(timeout)}.`
+      : "Idle children are never killed.";
+    reply(
+      `Child processes:\n${lines.join("\n")}\n${footer} Kill one with /processes kill <pid>.`,
+    );
+  } catch (error) {
+    reply(`Error listing child processes: ${errorText(error)}`);
+  }
+};
+
+/**
+ * Handle why command, explaining the last task from its recorded tool calls
+ */
+export const handleWhyCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const question = command.replace(/^\/why\s*/, "").trim();
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+
+  setState((prev) => ({
+    ...prev,
+    messages: [...prev.messages, userMessage],
+    isProcessing: true,
+  }));
+
+  let content: string;
+  try {
+    const result = await backend.call("explain_last_task", {
+      ...(question ? { question } : {}),
+    });
+    const calls = result.tool_calls === 1 ? "1 tool call" : `${result.tool_calls} tool calls`;
+    content = `Why (from ${calls} recorded in the last task):\n\n${result.explanation}`;
+  } catch (error) {
+    content = `Error explaining the last task: ${error instanceof Error ? error.message : String(error)}`;
+  }
+
+  const systemMessage = createMessages([{ role: "system", content }])[0];
+  setState((prev) => ({
+    ...prev,
+    messages: [...prev.messages, systemMessage],
+    isProcessing: false,
+  }));
+};
+
+// Cost as e.g. "$0.0123", or "?" when the model's price isn't known
+const formatCost = (cost: number | null): string =>
+  cost === null ? "?" : `$${cost.toFixed(cost < 1 ? 4 : 2)}`;
+
+/**
+ * Handle stats command: show this session's tokens and cost per provider
+ * (e.g. "/stats session"), list recent provider requests with their tokens,
+ * latency and cost (e.g. "/stats requests 50 since 2025-06-01"), or export them
+ * as CSV (e.g. "/stats requests csv june.csv since 2025-06-01")
+ */
+export const handleStatsCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+  const args = command.split(/\s+/).slice(1).filter(Boolean);
+
+  const reply = (content: string) => {
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  };
+  const errorText = (error: unknown) =>
+    error instanceof Error ? error.message : String(error);
+
+  if (args[0] === "session") {
+    try {
+      const result = await backend.call("get_tasks", {});
+      const usage = Object.entries(
+        (result.usage ?? {}) as Record<string, LedgerTotals>,
+      );
+      if (usage.length === 0) {
+        reply("No provider requests in this session yet");
+        return;
+      }
+      const lines = usage.map(([provider, totals]) => {
+        const unpriced = totals.unpriced_requests
+          ? ` (${totals.unpriced_requests} without a known price)`
+          : "";
+        return `  ${provider}  ${totals.requests} requests  ${totals.input_tokens + totals.cache_creation_tokens + totals.cache_read_tokens} in / ${totals.output_tokens} out  ${formatCost(totals.cost_usd)}${unpriced}`;
+      });
+      reply(`This session's usage by provider:\n${lines.join("\n")}`);
+    } catch (error) {
+      reply(`Error reading session usage: ${errorText(error)}`);
+    }
+    return;
+  }
+
+  if (args[0] !== "requests") {
+    reply(
+      "Usage: /stats session | /stats requests [limit] [since <date>]\n/stats session shows this session's tokens and cost per provider. /stats requests lists recent provider requests with their tokens, latency and cost, and /stats requests csv [path] [since <date>] exports them for expense reports.",
+    );
+    return;
+  }
+
+  const rest = args.slice(1);
+  const sinceIndex = rest.indexOf("since");
+  const since = sinceIndex >= 0 ? rest[sinceIndex + 1] : undefined;
+  const options = sinceIndex >= 0 ? rest.slice(0, sinceIndex) : rest;
+
+  if (options[0] === "csv") {
+    try {
+      const result = await backend.call("export_request_ledger", {
+        ...(options[1] ? { path: options[1] } : {}),
+        ...(since ? { since } : {}),
+      });
+      reply(`Exported ${result.requests} requests to ${result.path}`);
+    } catch (error) {
+      reply(`Error exporting the request ledger: ${errorText(error)}`);
+    }
+    return;
+  }
+
+  const limit = Number.parseInt(options[0] ?? "", 10) || 20;
+  try {
+    const result = await backend.call("get_request_ledger", {
+      limit,
+      ...(since ? { since } : {}),
+    });
+    const requests = (result.requests ?? []) as RequestRecord[];
+    const totals = result.totals as LedgerTotals;
+    if (requests.length === 0) {
+      reply(`No provider requests recorded in ${result.path}`);
+      return;
+    }
+
+    const lines = requests.map((request) => {
+      const cached = request.cache_read_tokens + request.cache_creation_tokens;
+      const tokens = `${request.input_tokens} in / ${request.output_tokens} out${cached ? ` / ${cached} cached` : ""}${request.estimated ? " (est.)" : ""}`;
+      return `  ${request.timestamp.slice(0, 19).replace("T", " ")}  ${request.provider}:${request.model}  ${tokens}  ${request.latency_ms}ms  ${formatCost(request.cost_usd)}`;
+    });
+    const unpriced = totals.unpriced_requests
+      ? `, ${totals.unpriced_requests} without a known price`
+      : "";
+    reply(
+      `Last ${requests.length} provider requests:\n${lines.join("\n")}\nTotal${since ? ` since ${since}` : ""}: ${totals.requests} requests, ${totals.input_tokens + totals.cache_creation_tokens + totals.cache_read_tokens} input and ${totals.output_tokens} output tokens, ${formatCost(totals.cost_usd)}${unpriced}.`,
+    );
+  } catch (error) {
+    reply(`Error reading the request ledger: ${errorText(error)}`);
+  }
+};
+
+/**
+ * Handle config command: check config.toml for errors (e.g. "/config"), or re-read
+ * it without restarting (e.g. "/config reload")
  */
-export const handleModelCommand: CommandHandler = (
+export const handleConfigCommand: CommandHandler = async (
   command,
   state,
   setState,
+  backend,
 ) => {
-  // Add user command to chat first
-  const messages = createMessages([{ role: "user", content: command }]);
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+  const args = command.split(/\s+/).slice(1).filter(Boolean);
 
-  // Just switch to setup mode without additional message
-  setState((prev) => ({
-    ...prev,
-    messages: [...prev.messages, messages[0]],
-    appMode: "setup", // Switch to setup mode
-  }));
+  const reply = (content: string) => {
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  };
+  const errorText = (error: unknown) =>
+    error instanceof Error ? error.message : String(error);
+
+  if (args[0] === "reload") {
+    try {
+      const result = await backend.call("reload_config", {});
+      const applied = result.applied as string[];
+      const overridden = result.overridden as string[];
+      const restart = result.restart_required as string[];
+      const theme = result.theme as string;
+      if (isThemeName(theme) && theme !== (state.theme ?? "dark")) {
+        applyTheme(theme);
+        setState((prev) => ({ ...prev, theme }));
+      }
+      const lines = [
+        `Reloaded ${result.path}: ${applied.length ? applied.join(", ") : "no environment settings"}`,
+      ];
+      if (overridden.length) {
+        lines.push(`Kept from the environment: ${overridden.join(", ")}`);
+      }
+      if (restart.length) {
+        lines.push(`Restart oli for these to take effect: ${restart.join(", ")}`);
+      }
+      reply(lines.join("\n"));
+    } catch (error) {
+      reply(`Config not reloaded:\n${errorText(error)}`);
+    }
+    return;
+  }
+
+  if (args.length > 0) {
+    reply(
+      "Usage: /config [reload]\nChecks config.toml for errors, or re-reads it without restarting.",
+    );
+    return;
+  }
+
+  try {
+    const result = await backend.call("check_config", {});
+    if (!result.exists) {
+      reply(`${result.path} does not exist; defaults are used`);
+    } else if (result.valid) {
+      reply(`${result.path} is valid. Run /config reload to apply changes.`);
+    } else {
+      reply(result.message as string);
+    }
+  } catch (error) {
+    reply(`Error checking the config: ${errorText(error)}`);
+  }
+};
+
+// Size as e.g. "12.3 MB"
+const formatBytes = (bytes: number): string => {
+  const units = ["B", "KB", "MB", "GB"];
+  let size = bytes;
+  let unit = 0;
+  while (size >= 1024 && unit < units.length - 1) {
+    size /= 1024;
+    unit += 1;
+  }
+  return unit === 0 ? `${size} B` : `${size.toFixed(1)} ${units[unit]}`;
+};
+
+/**
+ * Handle storage command: show disk usage by category and the sessions with logs,
+ * prune old session logs now ("/storage prune"), or pin a session so its logs
+ * are never pruned ("/storage pin [id]", "/storage unpin [id]")
+ */
+export const handleStorageCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+  const args = command.split(/\s+/).slice(1).filter(Boolean);
+
+  const reply = (content: string) => {
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  };
+  const errorText = (error: unknown) =>
+    error instanceof Error ? error.message : String(error);
+
+  if (args[0] === "prune" && args.length === 1) {
+    try {
+      const result = await backend.call("prune_storage", {});
+      const lines = [
+        result.removed_files
+          ? `Removed ${result.removed_files} session log(s), freeing ${formatBytes(result.freed_bytes as number)}`
+          : "Nothing to prune",
+      ];
+      if (result.pinned_kept) {
+        lines.push(`Kept ${result.pinned_kept} pinned session(s)`);
+      }
+      if (result.over_quota) {
+        lines.push(
+          "Still over the storage quota; the rest is pinned, in use or not prunable",
+        );
+      }
+      reply(lines.join("\n"));
+    } catch (error) {
+      reply(`Error pruning storage: ${errorText(error)}`);
+    }
+    return;
+  }
+
+  if ((args[0] === "pin" || args[0] === "unpin") && args.length <= 2) {
+    try {
+      const result = await backend.call(`${args[0]}_session`, {
+        session_id: args[1],
+      });
+      const id = result.session_id as string;
+      if (args[0] === "pin") {
+        reply(
+          result.changed
+            ? `Pinned session ${id}; its logs won't be pruned`
+            : `Session ${id} is already pinned`,
+        );
+      } else {
+        reply(
+          result.changed
+            ? `Unpinned session ${id}`
+            : `Session ${id} wasn't pinned`,
+        );
+      }
+    } catch (error) {
+      reply(`Error: ${errorText(error)}`);
+    }
+    return;
+  }
+
+  if (args.length > 0) {
+    reply(
+      "Usage: /storage [prune | pin [session] | unpin [session]]\nShows disk usage, prunes old session logs, or pins a session (the current one by default) so its logs are kept.",
+    );
+    return;
+  }
+
+  try {
+    const result = await backend.call("get_storage_usage", {});
+    const categories = result.categories as StorageCategory[];
+    const sessions = result.sessions as StoredSession[];
+    const maxBytes = result.max_bytes as number | null;
+    const lines = [
+      `Storage: ${formatBytes(result.total_bytes as number)}${maxBytes ? ` of ${formatBytes(maxBytes)}` : ""}, session logs kept ${result.max_age_days} days`,
+      ...categories.map(
+        (category) =>
+          `  ${category.name}: ${formatBytes(category.bytes)} in ${category.files} file(s)${category.prunable ? "" : " (kept)"} · ${category.path}`,
+      ),
+    ];
+    if (sessions.length) {
+      lines.push("", "Sessions:");
+      for (const session of sessions.slice(0, 10)) {
+        const marks = [
+          session.pinned ? "pinned" : "",
+          session.id === result.current_session ? "current" : "",
+        ].filter(Boolean);
+        lines.push(
+          `  ${session.id.slice(0, 8)} ${formatBytes(session.bytes)} · ${session.last_modified.slice(0, 10)}${marks.length ? ` (${marks.join(", ")})` : ""}`,
+        );
+      }
+      if (sessions.length > 10) {
+        lines.push(`  …and ${sessions.length - 10} more`);
+      }
+    }
+    reply(lines.join("\n"));
+  } catch (error) {
+    reply(`Error reading storage usage: ${errorText(error)}`);
+  }
+};
+
+/**
+ * Handle star command: star the conversation so /resume and /search can filter on
+ * it (e.g. "/star"), or unstar it (e.g. "/star off")
+ */
+export const handleStarCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+  const args = command.split(/\s+/).slice(1).filter(Boolean);
+
+  const reply = (content: string) => {
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  };
+  const errorText = (error: unknown) =>
+    error instanceof Error ? error.message : String(error);
+
+  if (args.length > 1 || (args[0] && args[0] !== "off")) {
+    reply("Usage: /star [off]");
+    return;
+  }
+
+  try {
+    const result = await backend.call("star_session", {
+      starred: args[0] !== "off",
+    });
+    const id = (result.session_id as string).slice(0, 8);
+    reply(
+      result.starred
+        ? `Starred conversation ${id}; find it with /resume starred`
+        : `Unstarred conversation ${id}`,
+    );
+  } catch (error) {
+    reply(`Error: ${errorText(error)}`);
+  }
+};
+
+/**
+ * Handle tag and untag commands: tag the latest message, or the one n messages
+ * back (e.g. "/tag decision", "/tag todo 3"), or remove a tag (e.g. "/untag bug 2")
+ */
+export const handleTagCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+  const [name, ...args] = command.split(/\s+/).filter(Boolean);
+  const remove = name === "/untag";
+
+  const reply = (content: string) => {
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  };
+  const errorText = (error: unknown) =>
+    error instanceof Error ? error.message : String(error);
+
+  const fromEnd = args[1] === undefined ? 1 : Number(args[1]);
+  if (
+    args.length < 1 ||
+    args.length > 2 ||
+    !Number.isInteger(fromEnd) ||
+    fromEnd < 1
+  ) {
+    reply(
+      `Usage: ${name} <tag> [n]\nTags the latest message, or the one n messages back, e.g. ${name} decision 2`,
+    );
+    return;
+  }
+
+  try {
+    const result = await backend.call("tag_message", {
+      tag: args[0],
+      from_end: fromEnd,
+      remove,
+    });
+    const preview = (result.content as string).split("\n")[0].slice(0, 60);
+    const message = `${result.role} message "${preview}"`;
+    if (remove) {
+      reply(
+        result.changed
+          ? `Removed tag ${args[0]} from the ${message}`
+          : `The ${message} isn't tagged ${args[0]}`,
+      );
+    } else {
+      reply(
+        result.changed
+          ? `Tagged the ${message} as ${args[0]}`
+          : `The ${message} is already tagged ${args[0]}`,
+      );
+    }
+  } catch (error) {
+    reply(`Error: ${errorText(error)}`);
+  }
+};
+
+// Split "starred" and "tag:<tag>" filters from the other words of a command
+const parseSessionFilter = (args: string[]) => {
+  const filter: { starred: boolean; tag?: string } = { starred: false };
+  const rest: string[] = [];
+  for (const arg of args) {
+    if (arg === "starred") {
+      filter.starred = true;
+    } else if (arg.startsWith("tag:")) {
+      filter.tag = arg.slice("tag:".length);
+    } else {
+      rest.push(arg);
+    }
+  }
+  return { filter, rest };
+};
+
+/**
+ * Handle resume command: list saved conversations, optionally only starred ones or
+ * those with a tag (e.g. "/resume starred tag:todo"), or resume one by its id or
+ * the start of it (e.g. "/resume 3f2a")
+ */
+export const handleResumeCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+  const { filter, rest } = parseSessionFilter(
+    command.split(/\s+/).slice(1).filter(Boolean),
+  );
+
+  const reply = (content: string) => {
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  };
+  const errorText = (error: unknown) =>
+    error instanceof Error ? error.message : String(error);
+
+  if (rest.length > 1) {
+    reply("Usage: /resume [starred] [tag:<tag>] [session]");
+    return;
+  }
+
+  if (rest.length === 1) {
+    try {
+      const result = await backend.call("resume_session", {
+        session_id: rest[0],
+      });
+      const saved = result.messages as Array<{ role: string; content: string }>;
+      const resumed = createMessages(
+        saved
+          .filter((message) => message.role !== "system")
+          .map((message) => ({
+            role: message.role as "user" | "assistant",
+            content: message.content,
+          })),
+      );
+      const systemMessage = createMessages([
+        {
+          role: "system",
+          content: `Resumed ${result.starred ? "starred " : ""}conversation ${(result.session_id as string).slice(0, 8)}: ${result.title}`,
+        },
+      ])[0];
+      setState((prev) => ({
+        ...prev,
+        messages: [...resumed, systemMessage],
+      }));
+    } catch (error) {
+      reply(`Error: ${errorText(error)}`);
+    }
+    return;
+  }
+
+  try {
+    const result = await backend.call("list_sessions", filter);
+    const sessions = result.sessions as SavedSessionListing[];
+    if (!sessions.length) {
+      reply("No saved conversations match");
+      return;
+    }
+    const lines = sessions.slice(0, 20).map((session) => {
+      const marks = [
+        session.starred ? "★" : "",
+        session.id === result.current_session ? "(current)" : "",
+        session.tags.map((tag) => `#${tag}`).join(" "),
+      ].filter(Boolean);
+      return `  ${session.id.slice(0, 8)} ${session.updated_at.slice(0, 10)} ${session.title}${marks.length ? ` ${marks.join(" ")}` : ""}`;
+    });
+    if (sessions.length > 20) {
+      lines.push(`  …and ${sessions.length - 20} more`);
+    }
+    reply(
+      `Saved conversations:\n${lines.join("\n")}\nResume one with /resume <id>.`,
+    );
+  } catch (error) {
+    reply(`Error listing saved conversations: ${errorText(error)}`);
+  }
+};
+
+/**
+ * Handle search command: find messages in saved conversations, optionally only
+ * starred ones or messages with a tag (e.g. "/search retry logic tag:bug")
+ */
+export const handleSearchCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+  const { filter, rest } = parseSessionFilter(
+    command.split(/\s+/).slice(1).filter(Boolean),
+  );
+
+  const reply = (content: string) => {
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  };
+  const errorText = (error: unknown) =>
+    error instanceof Error ? error.message : String(error);
+
+  if (!rest.length && !filter.tag) {
+    reply("Usage: /search <query> [starred] [tag:<tag>]");
+    return;
+  }
+
+  try {
+    const result = await backend.call("search_sessions", {
+      ...filter,
+      query: rest.join(" "),
+    });
+    const hits = result.hits as SessionSearchHit[];
+    if (!hits.length) {
+      reply("No messages match");
+      return;
+    }
+    const lines = hits.map(
+      (hit) =>
+        `  ${hit.session_id.slice(0, 8)}${hit.starred ? " ★" : ""} #${hit.index} ${hit.role}${hit.tags.length ? ` [${hit.tags.join(", ")}]` : ""}: ${hit.snippet}`,
+    );
+    reply(
+      `Found ${hits.length} message(s):\n${lines.join("\n")}\nResume a conversation with /resume <id>.`,
+    );
+  } catch (error) {
+    reply(`Error searching saved conversations: ${errorText(error)}`);
+  }
+};
+
+/**
+ * Handle preset command: choose a sampling preset for every mode (e.g.
+ * "/preset precise") or one mode (e.g. "/preset compare creative"), clear it with
+ * "default", or list the presets with "/preset"
+ */
+export const handlePresetCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+  const args = command.split(/\s+/).slice(1).filter(Boolean);
+
+  const reply = (content: string, presets?: SamplingPresets) => {
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+    setState((prev) => ({
+      ...prev,
+      ...(presets ? { samplingPresets: presets } : {}),
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  };
+  const errorText = (error: unknown) =>
+    error instanceof Error ? error.message : String(error);
+  const describe = (presets: SamplingPresets) => {
+    const modes = Object.entries(presets.modes).map(
+      ([mode, preset]) => `${mode}: ${preset}`,
+    );
+    return [`all modes: ${presets.default ?? "none"}`, ...modes].join(", ");
+  };
+
+  if (args.length === 0) {
+    try {
+      const result = await backend.call("get_sampling_presets", {});
+      const available = Object.entries(
+        result.available as Record<string, { temperature: number; top_p: number }>,
+      ).map(
+        ([name, p]) => `  ${name}: temperature ${p.temperature}, top_p ${p.top_p}`,
+      );
+      reply(
+        [
+          "Usage: /preset [chat|agent|compare] <precise|balanced|creative|default>",
+          ...available,
+          `Current: ${describe(result.presets)}`,
+        ].join("\n"),
+        result.presets,
+      );
+    } catch (error) {
+      reply(`Error reading presets: ${errorText(error)}`);
+    }
+    return;
+  }
+
+  if (args.length > 2) {
+    reply("Usage: /preset [chat|agent|compare] <precise|balanced|creative|default>");
+    return;
+  }
+
+  const [mode, preset] = args.length === 2 ? args : [undefined, args[0]];
+  try {
+    const result = await backend.call("set_sampling_preset", { mode, preset });
+    reply(`Presets: ${describe(result.presets)}`, result.presets);
+  } catch (error) {
+    reply(`Error: ${errorText(error)}`);
+  }
+};
+
+/**
+ * Handle prompt command: use a named template as the system prompt (e.g.
+ * "/prompt reviewer"), go back to the configured prompt with "/prompt default",
+ * or show the prompt in use and the templates with "/prompt"
+ */
+export const handlePromptCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+  const args = command.split(/\s+/).slice(1).filter(Boolean);
+
+  const reply = (content: string) => {
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  };
+  const errorText = (error: unknown) =>
+    error instanceof Error ? error.message : String(error);
+  const describe = (prompt: SystemPromptInfo) => {
+    switch (prompt.source.kind) {
+      case "template":
+        return `template ${prompt.source.name} (${prompt.source.path})`;
+      case "project":
+      case "user":
+        return `${prompt.source.kind} override (${prompt.source.path})`;
+      default:
+        return "the default prompt";
+    }
+  };
+
+  if (args.length === 0) {
+    try {
+      const result = await backend.call("get_system_prompt", {});
+      const templates = (result.templates as string[]) || [];
+      reply(
+        [
+          "Usage: /prompt <template|default>",
+          `Using ${describe(result.prompt as SystemPromptInfo)}`,
+          templates.length > 0
+            ? `Templates: ${templates.join(", ")}`
+            : "No templates yet. Add them as .oli/prompts/<name>.md or ~/.oli/prompts/<name>.md",
+        ].join("\n"),
+      );
+    } catch (error) {
+      reply(`Error reading the system prompt: ${errorText(error)}`);
+    }
+    return;
+  }
+
+  if (args.length > 1) {
+    reply("Usage: /prompt <template|default>");
+    return;
+  }
+
+  const name = args[0] === "default" ? null : args[0];
+  try {
+    const result = await backend.call("set_prompt_template", { name });
+    reply(`Using ${describe(result.prompt as SystemPromptInfo)}`);
+  } catch (error) {
+    reply(`Error: ${errorText(error)}`);
+  }
+};
+
+/**
+ * Handle permissions command: list the tools and Bash commands the project
+ * always allows (e.g. "/permissions"), or change them (e.g. "/permissions allow
+ * command cargo test", "/permissions remove tool Bash")
+ */
+export const handlePermissionsCommand: CommandHandler = async (
+  command,
+  state,
+  setState,
+  backend,
+) => {
+  const userMessage = createMessages([{ role: "user", content: command }])[0];
+  const args = command.split(/\s+/).slice(1).filter(Boolean);
+
+  const reply = (content: string) => {
+    const systemMessage = createMessages([{ role: "system", content }])[0];
+    setState((prev) => ({
+      ...prev,
+      messages: [...prev.messages, userMessage, systemMessage],
+    }));
+  };
+  const errorText = (error: unknown) =>
+    error instanceof Error ? error.message : String(error);
+  const describe = (rules: PermissionRules) => {
+    const lines = [
+      ...rules.tools.map((tool) => `  tool     ${tool}`),
+      ...rules.commands.map((cmd) => `  command  ${cmd}`),
+    ];
+    const sections = [
+      lines.length > 0
+        ? `Always allowed (${rules.path}):\n${lines.join("\n")}`
+        : `Nothing is always allowed yet (${rules.path})`,
+    ];
+    const levels = { ...(rules.configured_levels ?? {}), ...rules.levels };
+    if (Object.keys(levels).length > 0) {
+      const levelLines = Object.entries(levels).map(
+        ([tool, level]) =>
+          `  ${tool.padEnd(10)} ${level}${tool in rules.levels ? " (project)" : ""}`,
+      );
+      sections.push(`Permission levels:\n${levelLines.join("\n")}`);
+    }
+    if (rules.edit_paths && rules.edit_paths.length > 0) {
+      sections.push(`Edits limited to: ${rules.edit_paths.join(", ")}`);
+    }
+    if (rules.policy_error) {
+      sections.push(`Invalid permission policy: ${rules.policy_error}`);
+    }
+    return sections.join("\n\n");
+  };
+
+  if (args[0] === "level") {
+    const [, tool, level] = args;
+    if (!tool || !["auto", "ask", "deny", "default"].includes(level)) {
+      reply("Usage: /permissions level <tool> <auto|ask|deny|default>");
+      return;
+    }
+    try {
+      const result = (await backend.call("set_permission_level", {
+        tool,
+        level,
+      })) as PermissionRules & { changed: boolean };
+      reply(`${result.changed ? "" : "Unchanged. "}${describe(result)}`);
+    } catch (error) {
+      reply(`Error changing permissions: ${errorText(error)}`);
+    }
+    return;
+  }
+
+  if (args.length > 0) {
+    const [action, scope] = args;
+    const value = args.slice(2).join(" ");
+    if (
+      !["allow", "remove"].includes(action) ||
+      !["tool", "command"].includes(scope) ||
+      !value
+    ) {
+      reply(
+        "Usage: /permissions [allow|remove] [tool <name>|command <exact command>]\n       /permissions level <tool> <auto|ask|deny|default>",
+      );
+      return;
+    }
+    try {
+      const result = (await backend.call("set_permission_rule", {
+        scope,
+        value,
+        allow: action === "allow",
+      })) as PermissionRules & { changed: boolean };
+      const note = result.changed
+        ? ""
+        : action === "allow"
+          ? "Already allowed. "
+          : "No such rule. ";
+      reply(`${note}${describe(result)}`);
+    } catch (error) {
+      reply(`Error changing permissions: ${errorText(error)}`);
+    }
+    return;
+  }
+
+  try {
+    const result = (await backend.call(
+      "get_permission_rules",
+      {},
+    )) as PermissionRules;
+    reply(describe(result));
+  } catch (error) {
+    reply(`Error reading permissions: ${errorText(error)}`);
+  }
 };
 
 /**
@@ -147,7 +2082,40 @@ export const commandHandlers: Record<string, CommandHandler> = {
   "/help": handleHelpCommand,
   "/clear": handleClearCommand,
   "/exit": handleExitCommand,
+  "/cancel": handleCancelCommand,
+  "/undo": handleUndoCommand,
+  "/init": handleInitCommand,
+  "/compact": handleCompactCommand,
   "/model": handleModelCommand,
+  "/set": handleSetCommand,
+  "/preset": handlePresetCommand,
+  "/prompt": handlePromptCommand,
+  "/timestamps": handleTimestampsCommand,
+  "/accessible": handleAccessibleCommand,
+  "/theme": handleThemeCommand,
+  "/transcript": handleTranscriptCommand,
+  "/export": handleExportCommand,
+  "/audit": handleAuditCommand,
+  "/env": handleEnvCommand,
+  "/compare": handleCompareCommand,
+  "/takeover": handleTakeoverCommand,
+  "/key": handleKeyCommand,
+  "/paste-context": handlePasteContextCommand,
+  "/attach": handleAttachCommand,
+  "/stage": handleStageCommand,
+  "/rename": handleRenameCommand,
+  "/pull": handlePullCommand,
+  "/processes": handleProcessesCommand,
+  "/permissions": handlePermissionsCommand,
+  "/why": handleWhyCommand,
+  "/stats": handleStatsCommand,
+  "/config": handleConfigCommand,
+  "/storage": handleStorageCommand,
+  "/star": handleStarCommand,
+  "/tag": handleTagCommand,
+  "/untag": handleTagCommand,
+  "/resume": handleResumeCommand,
+  "/search": handleSearchCommand,
 };
 
 /**
@@ -191,10 +2159,24 @@ export const processUserMessage = async (
   // Generate stable message ID
   const userMessage = createMessages([{ role: "user", content: input }])[0];
 
+  // Clipboard text added with /paste-context goes with this prompt only
+  const pasted = state.pendingContext;
+  if (pasted) {
+    userMessage.content = `${input}\n[+ clipboard: ${pasted.format}, ${pasted.lines} lines]`;
+  }
+
+  // So do images added with /attach
+  const attachments = state.pendingAttachments ?? [];
+  for (const image of attachments) {
+    userMessage.content += `\n[+ image: ${image.path}, ${(image.bytes / 1024).toFixed(1)} KB]`;
+  }
+
   // Set processing state
   setState((prev) => ({
     ...prev,
     isProcessing: true,
+    pendingContext: null,
+    pendingAttachments: [],
   }));
 
   // Add user message
@@ -209,6 +2191,11 @@ export const processUserMessage = async (
       prompt: input,
       model_index: state.selectedModel,
       use_agent: state.useAgent,
+      stream: true,
+      ...(pasted ? { clipboard: pasted.content } : {}),
+      ...(attachments.length
+        ? { attachments: attachments.map((image) => image.path) }
+        : {}),
     });
 
     // Create assistant response
@@ -219,11 +2206,36 @@ export const processUserMessage = async (
       },
     ])[0];
 
-    // Add assistant response
+    // Attach the turn footer (duration, tool calls, tokens) from the task record
+    const turn = result.turn as { summary?: string } | null | undefined;
+    if (turn?.summary) {
+      assistantMessage.turn_summary = turn.summary;
+    }
+
+    // Attach the numbered tool results so citations like [#3] can be resolved
+    const evidence = result.evidence as
+      | { entries?: Evidence[]; cited?: number[] }
+      | null
+      | undefined;
+    if (evidence?.entries?.length) {
+      assistantMessage.evidence = evidence.entries;
+      assistantMessage.citations = evidence.cited ?? [];
+    }
+
+    // Note any compaction done to fit the prompt into the context window
+    const compacted = result.context_management as string | null | undefined;
+    const notes = compacted
+      ? createMessages([{ role: "system", content: `[info] ${compacted}` }])
+      : [];
+
+    // Add assistant response, with the session's cost and context use so far for the status bar
+    const context = result.context as { percent?: number } | null | undefined;
     setState((prev) => ({
       ...prev,
-      messages: [...prev.messages, assistantMessage],
+      messages: [...prev.messages, ...notes, assistantMessage],
       isProcessing: false,
+      sessionCost: (result.session_cost_usd as number | null | undefined) ?? prev.sessionCost,
+      contextPercent: context?.percent ?? prev.contextPercent,
     }));
   } catch (err) {
     // Handle error
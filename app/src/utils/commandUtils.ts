@@ -14,11 +14,182 @@ export const AVAILABLE_COMMANDS: Command[] = [
     description: "Clear conversation history and free up context",
     value: "/clear",
   },
+  {
+    name: "compact",
+    description:
+      "Summarize older messages on a cheaper model, e.g. /compact the parser bug",
+    value: "/compact",
+  },
+  {
+    name: "cancel",
+    description: "Stop the running task, keeping a partial result",
+    value: "/cancel",
+  },
+  {
+    name: "undo",
+    description: "Restore the files changed by the last agent turn",
+    value: "/undo",
+  },
+  {
+    name: "init",
+    description:
+      "Use the project's OLI.md or AGENTS.md, or write a starter OLI.md",
+    value: "/init",
+  },
   {
     name: "model",
     description: "Switch to model selection mode",
     value: "/model",
   },
+  {
+    name: "set",
+    description: "Set a model parameter, e.g. /set temperature 0.7",
+    value: "/set",
+  },
+  {
+    name: "timestamps",
+    description: "Toggle message timestamps",
+    value: "/timestamps",
+  },
+  {
+    name: "accessible",
+    description: "Toggle screen-reader friendly output without animations or icons",
+    value: "/accessible",
+  },
+  {
+    name: "theme",
+    description: "List the color themes or switch to one",
+    value: "/theme",
+  },
+  {
+    name: "transcript",
+    description: "Write the conversation as plain text, e.g. /transcript notes.txt",
+    value: "/transcript",
+  },
+  {
+    name: "export",
+    description: "Export the conversation as Markdown, or HTML for .html paths",
+    value: "/export",
+  },
+  {
+    name: "audit",
+    description: "Show recent Edit, Write and Bash executions, e.g. /audit 50",
+    value: "/audit",
+  },
+  {
+    name: "env",
+    description: "Show the shell, PATH and environments Bash commands run with",
+    value: "/env",
+  },
+  {
+    name: "compare",
+    description:
+      "Answer a prompt with two models side by side, e.g. /compare 0 1 <prompt>",
+    value: "/compare",
+  },
+  {
+    name: "takeover",
+    description: "Enable all tools while another oli instance holds the workspace",
+    value: "/takeover",
+  },
+  {
+    name: "paste-context",
+    description: "Attach the clipboard to the next prompt as a context block",
+    value: "/paste-context",
+  },
+  {
+    name: "attach",
+    description: "Attach images such as screenshots to the next prompt",
+    value: "/attach",
+  },
+  {
+    name: "key",
+    description: "Check an API key for the selected model and store it",
+    value: "/key",
+  },
+  {
+    name: "stage",
+    description: "Stage the working tree's changes hunk by hunk",
+    value: "/stage",
+  },
+  {
+    name: "pull",
+    description: "Download a model into Ollama and select it, e.g. /pull llama3.2",
+    value: "/pull",
+  },
+  {
+    name: "rename",
+    description: "Rename a symbol across the workspace after reviewing the diff",
+    value: "/rename",
+  },
+  {
+    name: "processes",
+    description:
+      "List the language servers and Bash commands oli started, e.g. /processes kill <pid>",
+    value: "/processes",
+  },
+  {
+    name: "permissions",
+    description:
+      "List or change what the project always allows and tool permission levels, e.g. /permissions level Bash deny",
+    value: "/permissions",
+  },
+  {
+    name: "why",
+    description:
+      "Explain the last task's tool calls from its recorded trail, e.g. /why did you edit main.rs",
+    value: "/why",
+  },
+  {
+    name: "stats",
+    description:
+      "Show session usage per provider or recent requests with cost, e.g. /stats session, /stats requests csv june.csv",
+    value: "/stats",
+  },
+  {
+    name: "config",
+    description: "Check config.toml for errors, or re-read it with /config reload",
+    value: "/config",
+  },
+  {
+    name: "storage",
+    description: "Show disk usage, prune old session logs, or pin a session",
+    value: "/storage",
+  },
+  {
+    name: "star",
+    description: "Star the conversation, or unstar it with /star off",
+    value: "/star",
+  },
+  {
+    name: "tag",
+    description:
+      "Tag the latest message, or one n back, e.g. /tag decision 2; /untag removes a tag",
+    value: "/tag",
+  },
+  {
+    name: "resume",
+    description:
+      "List saved conversations, e.g. /resume starred tag:todo, or resume one by id",
+    value: "/resume",
+  },
+  {
+    name: "search",
+    description: "Search saved conversations, e.g. /search retry logic tag:bug",
+    value: "/search",
+  },
+  {
+    name: "preset",
+    description:
+      "Choose a sampling preset (precise, balanced, creative), e.g. /preset agent precise",
+    value: "/preset",
+  },
+  {
+    name: "prompt",
+    description:
+      "Use a system prompt template from .oli/prompts or ~/.oli/prompts, e.g. /prompt reviewer",
+    value: "/prompt",
+  },
   { name: "exit", description: "Exit the application", value: "/exit" },
 ];
 
@@ -0,0 +1,240 @@
+import { Evidence, Message } from "../types/index.js";
+import { stripDecorations } from "./accessibility.js";
+
+// Formats /export writes, chosen by the file extension
+export type ExportFormat = "markdown" | "html";
+
+/**
+ * Format to export to, from the extension of the target file
+ * @param filePath Target file
+ * @returns "html" for .html and .htm files, otherwise "markdown"
+ */
+export const exportFormat = (filePath: string): ExportFormat =>
+  /\.html?$/i.test(filePath) ? "html" : "markdown";
+
+/**
+ * File /export writes when no path is given
+ * @param now Time of the export
+ * @returns e.g. "oli-conversation-2024-05-01.md"
+ */
+export const defaultExportPath = (now: Date = new Date()): string =>
+  `oli-conversation-${now.toISOString().slice(0, 10)}.md`;
+
+/**
+ * The messages worth exporting: prompts, answers and finished tool calls,
+ * without slash commands and the system notes they print
+ * @param messages Conversation messages
+ * @returns Messages to export, in order
+ */
+export const exportedMessages = (messages: Message[]): Message[] =>
+  messages.filter((message) => {
+    switch (message.role) {
+      case "user":
+        return !message.content.trim().startsWith("/");
+      case "assistant":
+        return true;
+      case "tool":
+        return message.tool_status !== "running";
+      default:
+        return false;
+    }
+  });
+
+const escapeHtml = (text: string): string =>
+  text
+    .replace(/&/g, "&amp;")
+    .replace(/</g, "&lt;")
+    .replace(/>/g, "&gt;")
+    .replace(/"/g, "&quot;");
+
+// Whether a tool result reads as a diff, so it's shown as one
+const looksLikeDiff = (text: string): boolean => {
+  const lines = text.split("\n");
+  return (
+    lines.some((line) => line.startsWith("@@")) ||
+    (lines.some((line) => line.startsWith("+")) &&
+      lines.some((line) => line.startsWith("-")))
+  );
+};
+
+// One line describing a tool call, e.g. "Read src/main.rs: Read 120 lines"
+const toolCallLine = (message: Message): string => {
+  const name = message.tool_data?.name ?? message.tool ?? "Tool";
+  const target = message.tool_data?.file_path
+    ? ` ${message.tool_data.file_path}`
+    : "";
+  const description = message.tool_data?.description
+    ? `: ${stripDecorations(message.tool_data.description)}`
+    : "";
+  const failed = message.tool_status === "error" ? " (failed)" : "";
+  return `${name}${target}${description}${failed}`;
+};
+
+const evidenceTitle = (entry: Evidence): string =>
+  `[#${entry.index}] ${entry.tool}${entry.target ? ` ${entry.target}` : ""}`;
+
+// A fence longer than any backtick run in the text, so it can't be closed early
+const fenceFor = (text: string): string => {
+  const longest = Math.max(
+    0,
+    ...(text.match(/`+/g) ?? []).map((run) => run.length),
+  );
+  return "`".repeat(Math.max(3, longest + 1));
+};
+
+/**
+ * Write a conversation as Markdown, with tool outputs folded into
+ * <details> blocks so the transcript stays readable in PRs and issues
+ * @param messages Conversation messages
+ * @param model Name of the model the conversation was with
+ * @param now Time of the export
+ * @returns Markdown document
+ */
+export const conversationToMarkdown = (
+  messages: Message[],
+  model: string,
+  now: Date = new Date(),
+): string => {
+  const exported = exportedMessages(messages);
+  const prompts = exported.filter((message) => message.role === "user").length;
+  const sections = [
+    `# oli conversation\n\n_Exported ${now.toISOString()} · ${model} · ${prompts} prompt${prompts === 1 ? "" : "s"}_`,
+  ];
+
+  for (const message of exported) {
+    if (message.role === "tool") {
+      sections.push(`- Tool: ${toolCallLine(message)}`);
+      continue;
+    }
+
+    const heading = message.role === "user" ? "## User" : "## Assistant";
+    const parts = [heading, message.content.trim()];
+    if (message.turn_summary) {
+      parts.push(`_${message.turn_summary}_`);
+    }
+    for (const entry of message.evidence ?? []) {
+      const fence = fenceFor(entry.excerpt);
+      const language = looksLikeDiff(entry.excerpt) ? "diff" : "";
+      const truncated = entry.truncated ? "\n\n_Output truncated._" : "";
+      parts.push(
+        `<details>\n<summary>${escapeHtml(evidenceTitle(entry))}</summary>\n\n${fence}${language}\n${entry.excerpt}\n${fence}${truncated}\n\n</details>`,
+      );
+    }
+    sections.push(parts.join("\n\n"));
+  }
+
+  return `${sections.join("\n\n")}\n`;
+};
+
+// Message text as HTML: fenced code blocks as <pre>, the rest as paragraphs
+const textToHtml = (text: string): string =>
+  text
+    .trim()
+    .split(/^(```[^\n]*\n[\s\S]*?^```)\s*$/m)
+    .filter((block) => block.trim())
+    .map((block) => {
+      const fenced = block.match(/^```([^\n]*)\n([\s\S]*?)```$/);
+      if (fenced) {
+        return codeToHtml(fenced[2].replace(/\n$/, ""), fenced[1].trim() === "diff");
+      }
+      return block
+        .trim()
+        .split(/\n{2,}/)
+        .map((paragraph) => `<p>${escapeHtml(paragraph).replace(/\n/g, "<br>")}</p>`)
+        .join("\n");
+    })
+    .join("\n");
+
+// Code as a <pre> block, coloring added and removed lines of diffs
+const codeToHtml = (code: string, diff: boolean): string => {
+  if (!diff) {
+    return `<pre><code>${escapeHtml(code)}</code></pre>`;
+  }
+  const lines = code.split("\n").map((line) => {
+    const kind = line.startsWith("+")
+      ? "added"
+      : line.startsWith("-")
+        ? "removed"
+        : line.startsWith("@@")
+          ? "hunk"
+          : "";
+    return kind
+      ? `<span class="${kind}">${escapeHtml(line)}</span>`
+      : escapeHtml(line);
+  });
+  return `<pre class="diff"><code>${lines.join("\n")}</code></pre>`;
+};
+
+const HTML_STYLE = `body { font-family: -apple-system, "Segoe UI", sans-serif; max-width: 860px; margin: 2em auto; padding: 0 1em; color: #24292f; line-height: 1.5; }
+header p { color: #57606a; }
+section { border-left: 4px solid #d0d7de; padding: 0.25em 1em; margin: 1.5em 0; }
+section.user { border-color: #1a7f37; }
+section.assistant { border-color: #0969da; }
+h2 { font-size: 1em; margin: 0.5em 0; }
+.tool { color: #57606a; font-family: ui-monospace, monospace; font-size: 0.9em; margin: 0.25em 0; }
+.summary { color: #57606a; font-style: italic; }
+pre { background: #f6f8fa; padding: 0.75em; overflow-x: auto; border-radius: 6px; }
+.added { color: #1a7f37; }
+.removed { color: #cf222e; }
+.hunk { color: #8250df; }
+details { margin: 0.5em 0; }
+summary { cursor: pointer; font-family: ui-monospace, monospace; font-size: 0.9em; }`;
+
+/**
+ * Write a conversation as a self-contained HTML page, with tool outputs in
+ * collapsed <details> blocks and diffs colored
+ * @param messages Conversation messages
+ * @param model Name of the model the conversation was with
+ * @param now Time of the export
+ * @returns HTML document
+ */
+export const conversationToHtml = (
+  messages: Message[],
+  model: string,
+  now: Date = new Date(),
+): string => {
+  const exported = exportedMessages(messages);
+  const prompts = exported.filter((message) => message.role === "user").length;
+  const body: string[] = [];
+
+  for (const message of exported) {
+    if (message.role === "tool") {
+      body.push(`<p class="tool">Tool: ${escapeHtml(toolCallLine(message))}</p>`);
+      continue;
+    }
+
+    const parts = [
+      `<h2>${message.role === "user" ? "User" : "Assistant"}</h2>`,
+      textToHtml(message.content),
+    ];
+    if (message.turn_summary) {
+      parts.push(`<p class="summary">${escapeHtml(message.turn_summary)}</p>`);
+    }
+    for (const entry of message.evidence ?? []) {
+      const truncated = entry.truncated ? `<p class="summary">Output truncated.</p>` : "";
+      parts.push(
+        `<details><summary>${escapeHtml(evidenceTitle(entry))}</summary>\n${codeToHtml(entry.excerpt, looksLikeDiff(entry.excerpt))}${truncated}</details>`,
+      );
+    }
+    body.push(`<section class="${message.role}">\n${parts.join("\n")}\n</section>`);
+  }
+
+  return `<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>oli conversation</title>
+<style>
+${HTML_STYLE}
+</style>
+</head>
+<body>
+<header>
+<h1>oli conversation</h1>
+<p>Exported ${now.toISOString()} · ${escapeHtml(model)} · ${prompts} prompt${prompts === 1 ? "" : "s"}</p>
+</header>
+${body.join("\n")}
+</body>
+</html>
+`;
+};
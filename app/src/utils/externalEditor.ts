@@ -0,0 +1,44 @@
+import { spawnSync } from "child_process";
+import fs from "fs";
+import os from "os";
+import path from "path";
+
+/**
+ * The editor prompts are composed in: $VISUAL, then $EDITOR, then the platform default
+ */
+export const editorCommand = (): string =>
+  process.env.VISUAL?.trim() ||
+  process.env.EDITOR?.trim() ||
+  (process.platform === "win32" ? "notepad" : "vi");
+
+/**
+ * Open text in the user's editor and wait for it to exit
+ * @param text Current contents of the input box
+ * @returns The saved text without its trailing newline, or an error message if
+ * the editor couldn't be run
+ */
+export const editInExternalEditor = (
+  text: string,
+): { text: string } | { error: string } => {
+  const dir = fs.mkdtempSync(path.join(os.tmpdir(), "oli-prompt-"));
+  const file = path.join(dir, "PROMPT.md");
+  const editor = editorCommand();
+
+  try {
+    fs.writeFileSync(file, text);
+    // The command may carry arguments, e.g. "code --wait", so it runs in a shell
+    const result = spawnSync(`${editor} "${file}"`, {
+      stdio: "inherit",
+      shell: true,
+    });
+    if (result.error) {
+      return { error: `Could not run ${editor}: ${result.error.message}` };
+    }
+    if (result.status !== 0) {
+      return { error: `${editor} exited with status ${result.status}; the prompt was not changed` };
+    }
+    return { text: fs.readFileSync(file, "utf8").replace(/\r?\n$/, "") };
+  } finally {
+    fs.rmSync(dir, { recursive: true, force: true });
+  }
+};
@@ -0,0 +1,19 @@
+/**
+ * The file mention being typed at the end of the input, e.g. "src/ap" for
+ * "explain @src/ap"
+ * @param input Text in the input box
+ * @returns The text after the @, or null when the input doesn't end in a mention
+ */
+export const activeMention = (input: string): string | null => {
+  const match = /(?:^|\s)@(\S*)$/.exec(input);
+  return match ? match[1] : null;
+};
+
+/**
+ * Replace the mention being typed with a chosen path
+ * @param input Text in the input box, ending in a mention
+ * @param path Path to mention, relative to the working directory
+ * @returns The input with the mention completed and a space after it
+ */
+export const insertMention = (input: string, path: string): string =>
+  input.replace(/@\S*$/, `@${path} `);
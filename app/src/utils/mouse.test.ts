@@ -0,0 +1,35 @@
+import { test } from "node:test";
+import assert from "node:assert/strict";
+import { parseMouseInput } from "./mouse.js";
+
+test("the wheel is reported as wheelUp and wheelDown", () => {
+  const { events, rest } = parseMouseInput("\x1b[<64;10;5M\x1b[<65;10;6M");
+  assert.deepEqual(events, [
+    { kind: "wheelUp", button: 0, x: 10, y: 5, shift: false },
+    { kind: "wheelDown", button: 1, x: 10, y: 6, shift: false },
+  ]);
+  assert.equal(rest, "");
+});
+
+test("a click is a press and a release, and keys around it are kept", () => {
+  const { events, rest } = parseMouseInput("a\x1b[<0;3;7M\x1b[<0;3;7mb");
+  assert.deepEqual(
+    events.map((event) => [event.kind, event.button, event.x, event.y]),
+    [
+      ["press", 0, 3, 7],
+      ["release", 0, 3, 7],
+    ],
+  );
+  assert.equal(rest, "ab");
+
+  // Shift and dragging are flagged
+  const [drag] = parseMouseInput("\x1b[<36;4;8M").events;
+  assert.equal(drag.kind, "drag");
+  assert.equal(drag.shift, true);
+});
+
+test("a malformed sequence is left as input", () => {
+  const { events, rest } = parseMouseInput("\x1b[<0;3M\x1b[<x;1;1M");
+  assert.deepEqual(events, []);
+  assert.equal(rest, "\x1b[<0;3M\x1b[<x;1;1M");
+});
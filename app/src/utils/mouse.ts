@@ -0,0 +1,115 @@
+import { EventEmitter } from "events";
+import { PassThrough } from "stream";
+
+/**
+ * Environment variable that turns off mouse support when "0", "false" or "off",
+ * leaving the mouse to the terminal
+ */
+export const MOUSE_ENV = "OLI_MOUSE";
+
+// Report presses, releases and the wheel, encoded as SGR sequences (ESC [ < b ; x ; y M).
+// Most terminals still select text with Shift+drag while the mouse is reported.
+const ENABLE_MOUSE = "\x1b[?1000h\x1b[?1006h";
+const DISABLE_MOUSE = "\x1b[?1000l\x1b[?1006l";
+
+// One SGR mouse report
+// eslint-disable-next-line no-control-regex
+const MOUSE_SEQUENCE = /\x1b\[<(\d+);(\d+);(\d+)([Mm])/g;
+
+// A mouse report from the terminal; x and y count from 1 at the top left
+export interface MouseEvent {
+  kind: "wheelUp" | "wheelDown" | "press" | "release" | "drag";
+  button: number; // 0 left, 1 middle, 2 right
+  x: number;
+  y: number;
+  shift: boolean;
+}
+
+/**
+ * Whether mouse support is enabled in the environment
+ * @returns False when OLI_MOUSE is "0", "false" or "off"
+ */
+export const isMouseEnabledFromEnv = (): boolean => {
+  const value = process.env[MOUSE_ENV]?.trim().toLowerCase();
+  return !(value === "0" || value === "false" || value === "off");
+};
+
+/**
+ * Split terminal input into mouse reports and the keys typed around them
+ * @param chunk Input read from the terminal
+ * @returns The mouse events, and the input without their sequences
+ */
+export const parseMouseInput = (
+  chunk: string,
+): { events: MouseEvent[]; rest: string } => {
+  const events: MouseEvent[] = [];
+  const rest = chunk.replace(MOUSE_SEQUENCE, (_match, code, x, y, final) => {
+    const flags = Number(code);
+    const button = flags & 3;
+    let kind: MouseEvent["kind"];
+    if (flags & 64) {
+      kind = button === 0 ? "wheelUp" : "wheelDown";
+    } else if (flags & 32) {
+      kind = "drag";
+    } else {
+      kind = final === "m" ? "release" : "press";
+    }
+    events.push({
+      kind,
+      button,
+      x: Number(x),
+      y: Number(y),
+      shift: Boolean(flags & 4),
+    });
+    return "";
+  });
+  return { events, rest };
+};
+
+// Mouse events read from the terminal, as "mouse" events
+export const mouseEvents = new EventEmitter();
+
+/**
+ * Turn on mouse reporting and give Ink a stdin without the mouse reports, which
+ * would otherwise be typed into the input box. Reports are emitted on
+ * `mouseEvents` instead. Mouse reporting is turned off again when the process
+ * exits.
+ * @param stdin The terminal's input
+ * @param stdout The terminal's output
+ * @returns A stream to render Ink with in place of stdin
+ */
+export const captureMouse = (
+  stdin: NodeJS.ReadStream,
+  stdout: NodeJS.WriteStream,
+): NodeJS.ReadStream => {
+  const filtered = new PassThrough({ encoding: "utf8" }) as unknown as NodeJS.ReadStream &
+    PassThrough;
+
+  // Ink switches raw mode and holds the event loop open through these
+  filtered.isTTY = stdin.isTTY;
+  filtered.setRawMode = (mode: boolean) => {
+    stdin.setRawMode(mode);
+    return filtered;
+  };
+  filtered.ref = () => {
+    stdin.ref();
+    return filtered;
+  };
+  filtered.unref = () => {
+    stdin.unref();
+    return filtered;
+  };
+
+  stdin.setEncoding("utf8");
+  stdin.on("data", (chunk: string) => {
+    const { events, rest } = parseMouseInput(chunk);
+    events.forEach((event) => mouseEvents.emit("mouse", event));
+    if (rest) {
+      filtered.write(rest);
+    }
+  });
+
+  stdout.write(ENABLE_MOUSE);
+  process.on("exit", () => stdout.write(DISABLE_MOUSE));
+  return filtered;
+};
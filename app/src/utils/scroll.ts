@@ -0,0 +1,247 @@
+import type { Key } from "ink";
+import { Message } from "../types/index.js";
+
+// Scroll actions the backend's ScrollBindings can bind keys to
+export type ScrollAction =
+  | "line_up"
+  | "line_down"
+  | "half_page_up"
+  | "half_page_down"
+  | "page_up"
+  | "page_down"
+  | "top"
+  | "bottom"
+  | "previous_prompt"
+  | "next_prompt";
+
+// Key names such as "ctrl+e" or "pageup", mapped to the action they trigger
+export type ScrollBindings = Record<string, ScrollAction>;
+
+/**
+ * Bindings used until the backend reports the configured ones, matching
+ * the defaults of ScrollBindings in src/app/utils.rs
+ */
+export const DEFAULT_SCROLL_BINDINGS: ScrollBindings = {
+  "ctrl+y": "line_up",
+  "ctrl+e": "line_down",
+  "ctrl+u": "half_page_up",
+  "ctrl+d": "half_page_down",
+  pageup: "page_up",
+  pagedown: "page_down",
+  home: "top",
+  end: "bottom",
+  "ctrl+up": "previous_prompt",
+  "ctrl+down": "next_prompt",
+};
+
+// Ink's key flags; versions that report Home and End set `home` and `end`
+export type ScrollKey = Pick<
+  Key,
+  "ctrl" | "meta" | "shift" | "upArrow" | "downArrow" | "pageUp" | "pageDown"
+> & { home?: boolean; end?: boolean };
+
+/**
+ * Name a keypress the way bindings do, e.g. "ctrl+e", "ctrl+up" or "pagedown"
+ * @param input The input useInput reports, the letter for Ctrl combinations
+ * @param key The key flags useInput reports
+ * @returns The key's name, or null for keys bindings can't name
+ */
+export const keyName = (input: string, key: ScrollKey): string | null => {
+  let name: string;
+  if (key.upArrow) name = "up";
+  else if (key.downArrow) name = "down";
+  else if (key.pageUp) name = "pageup";
+  else if (key.pageDown) name = "pagedown";
+  else if (key.home) name = "home";
+  else if (key.end) name = "end";
+  else if (input.length === 1) name = input.toLowerCase();
+  else return null;
+
+  // Ink flags some escape sequences as meta, so Alt only counts for characters,
+  // and Shift only for named keys since it's already in a typed character
+  const modifiers = [
+    key.ctrl ? "ctrl+" : "",
+    key.meta && name.length === 1 ? "alt+" : "",
+    key.shift && name.length > 1 ? "shift+" : "",
+  ];
+  return modifiers.join("") + name;
+};
+
+/**
+ * The scroll action bound to a keypress
+ * @param bindings Key names mapped to actions
+ * @param input The input useInput reports
+ * @param key The key flags useInput reports
+ * @returns The bound action, or null when the key isn't bound
+ */
+export const scrollActionFor = (
+  bindings: ScrollBindings,
+  input: string,
+  key: ScrollKey,
+): ScrollAction | null => {
+  const name = keyName(input, key);
+  return name === null ? null : (bindings[name] ?? null);
+};
+
+// Where the messages sit in the transcript, in terminal rows
+export interface ScrollView {
+  contentLines: number; // Rows all messages take up
+  viewportRows: number; // Rows the messages area shows at once
+  promptLines: number[]; // Row at which each user prompt starts, in ascending order
+}
+
+/**
+ * Apply a scroll action like ScrollState::apply in src/app/utils.rs. Line
+ * scrolling moves one row, half pages and pages follow the viewport height.
+ * @param action The action to apply
+ * @param offset Rows scrolled back from the latest message
+ * @param view The transcript and viewport sizes
+ * @returns The new offset, between 0 and the rows above the viewport
+ */
+export const applyScrollAction = (
+  action: ScrollAction,
+  offset: number,
+  view: ScrollView,
+): number => {
+  const maxOffset = Math.max(0, view.contentLines - view.viewportRows);
+  const clamp = (value: number) => Math.min(maxOffset, Math.max(0, value));
+  const halfPage = Math.max(1, Math.floor(view.viewportRows / 2));
+  const page = Math.max(1, view.viewportRows - 1);
+  // Row at the top of the viewport
+  const top = maxOffset - clamp(offset);
+
+  switch (action) {
+    case "line_up":
+      return clamp(offset + 1);
+    case "line_down":
+      return clamp(offset - 1);
+    case "half_page_up":
+      return clamp(offset + halfPage);
+    case "half_page_down":
+      return clamp(offset - halfPage);
+    case "page_up":
+      return clamp(offset + page);
+    case "page_down":
+      return clamp(offset - page);
+    case "top":
+      return maxOffset;
+    case "bottom":
+      return 0;
+    case "previous_prompt": {
+      const line = [...view.promptLines].reverse().find((row) => row < top);
+      return line === undefined ? maxOffset : clamp(maxOffset - line);
+    }
+    case "next_prompt": {
+      const line = view.promptLines.find((row) => row > top);
+      return line === undefined ? 0 : clamp(maxOffset - line);
+    }
+  }
+};
+
+/**
+ * Rows a block of text takes up when wrapped at a width
+ * @param text Text to measure
+ * @param width Columns available
+ * @returns At least one row per line
+ */
+export const wrappedLines = (text: string, width: number): number =>
+  text
+    .split("\n")
+    .reduce(
+      (rows, line) => rows + Math.max(1, Math.ceil(line.length / Math.max(1, width))),
+      0,
+    );
+
+// Rows each message takes up and where the user's prompts start
+export interface TranscriptLayout {
+  messageLines: number[];
+  promptLines: number[];
+  contentLines: number;
+}
+
+/**
+ * Lay out messages one after another
+ * @param messages Messages, oldest first
+ * @param rowsOf Rows a message takes up when rendered
+ * @returns Each message's rows, the row each user prompt starts at and the total
+ */
+export const transcriptLayout = (
+  messages: Message[],
+  rowsOf: (message: Message) => number,
+): TranscriptLayout => {
+  const messageLines: number[] = [];
+  const promptLines: number[] = [];
+  let contentLines = 0;
+  for (const message of messages) {
+    if (message.role === "user") promptLines.push(contentLines);
+    const rows = rowsOf(message);
+    messageLines.push(rows);
+    contentLines += rows;
+  }
+  return { messageLines, promptLines, contentLines };
+};
+
+// The messages to render for a scroll offset
+export interface ScrollWindow {
+  start: number; // Index of the first message shown
+  end: number; // Index after the last message shown
+  hiddenBelow: number; // Rows of the last message shown that fall below the viewport
+}
+
+/**
+ * Pick the messages that overlap the viewport
+ * @param messageLines Rows each message takes up, oldest first
+ * @param offset Rows scrolled back from the latest message
+ * @param viewportRows Rows the messages area shows at once
+ * @returns The messages to render and how far the last one runs past the viewport
+ */
+export const scrollWindow = (
+  messageLines: number[],
+  offset: number,
+  viewportRows: number,
+): ScrollWindow => {
+  let end = messageLines.length;
+  let below = 0;
+  // Skip messages entirely below the viewport
+  while (end > 0 && below + messageLines[end - 1] <= offset) {
+    below += messageLines[end - 1];
+    end--;
+  }
+  const hiddenBelow = end > 0 ? offset - below : 0;
+
+  let start = end;
+  let shown = -hiddenBelow;
+  while (start > 0 && shown < viewportRows) {
+    shown += messageLines[start - 1];
+    start--;
+  }
+  return { start, end, hiddenBelow };
+};
+
+/**
+ * The message at a row of the messages area, for clicks
+ * @param messageLines Rows each message takes up, oldest first
+ * @param offset Rows scrolled back from the latest message
+ * @param viewportRows Rows the messages area shows at once
+ * @param row Row within the messages area, counting from 0 at its top
+ * @returns The message's index, or null when no message is shown at the row
+ */
+export const messageAtRow = (
+  messageLines: number[],
+  offset: number,
+  viewportRows: number,
+  row: number,
+): number | null => {
+  const contentLines = messageLines.reduce((sum, rows) => sum + rows, 0);
+  const maxOffset = Math.max(0, contentLines - viewportRows);
+  if (row < 0 || row >= Math.min(viewportRows, contentLines)) return null;
+
+  // Row of the transcript shown at the top of the viewport
+  const top = maxOffset - Math.min(maxOffset, Math.max(0, offset));
+  let rest = top + row;
+  for (let i = 0; i < messageLines.length; i++) {
+    if (rest < messageLines[i]) return i;
+    rest -= messageLines[i];
+  }
+  return null;
+};
@@ -1,6 +1,13 @@
-use crate::apis::api_client::{ApiClient, CompletionOptions, Message, ToolCall, ToolResult};
-use crate::app::logger::{format_log_with_color, LogLevel};
+use crate::api_client::{
+    split_images, ApiClient, CompletionOptions, FinishReason, ImageData, Message, ToolCall,
+    ToolChoice, ToolResult,
+};
+use crate::batch::{BatchApi, BatchRequest, BatchResult, BatchStatus};
 use crate::errors::AppError;
+use crate::ledger::{record_request, RequestUsage};
+use crate::log::{format_log_with_color, LogLevel};
+use crate::retry::{send_with_retry, RetryPolicy};
+use crate::streaming::{read_sse_stream, DeltaHandler, TokenUsage};
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use rand;
@@ -10,7 +17,18 @@ use reqwest::Response;
 use serde::{Deserialize, Serialize};
 use serde_json::{self, json, Value};
 use std::env;
-use std::time::Duration;
+use std::time::Instant;
+
+/// Environment variable turning off prompt caching when "0", "false" or "off"
+pub const PROMPT_CACHING_ENV: &str = "OLI_PROMPT_CACHING";
+
+/// Whether requests mark the system prompt, tools and latest prompts for caching,
+/// which is the default unless `OLI_PROMPT_CACHING` turns it off
+pub fn prompt_caching_enabled() -> bool {
+    env::var(PROMPT_CACHING_ENV)
+        .map(|value| !matches!(value.trim().to_lowercase().as_str(), "0" | "false" | "off"))
+        .unwrap_or(true)
+}
 
 // Helper function to log usage information from Anthropic API
 fn log_anthropic_usage(usage: &Value) {
@@ -82,10 +100,57 @@ enum AnthropicContent {
     ToolResult {
         #[serde(rename = "tool_use_id")]
         tool_call_id: String,
-        content: String,
+        content: AnthropicToolResultContent,
         #[serde(skip_serializing_if = "Option::is_none")]
         cache_control: Option<CacheControl>,
     },
+
+    #[serde(rename = "image")]
+    Image { source: AnthropicImageSource },
+}
+
+/// A tool result's content: plain text, or text and images
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+enum AnthropicToolResultContent {
+    Text(String),
+    Blocks(Vec<AnthropicContent>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct AnthropicImageSource {
+    #[serde(rename = "type")]
+    source_type: String,
+    media_type: String,
+    data: String,
+}
+
+impl From<ImageData> for AnthropicContent {
+    fn from(image: ImageData) -> Self {
+        AnthropicContent::Image {
+            source: AnthropicImageSource {
+                source_type: "base64".to_string(),
+                media_type: image.media_type,
+                data: image.data,
+            },
+        }
+    }
+}
+
+/// Text and the images carried in it as content blocks, with `cache_control` on
+/// the text
+fn text_and_images(text: &str, cache_control: Option<CacheControl>) -> Vec<AnthropicContent> {
+    let (text, images) = split_images(text);
+    let mut content = Vec::with_capacity(images.len() + 1);
+    // Empty text blocks are rejected, but a message needs at least one block
+    if !text.trim().is_empty() || images.is_empty() {
+        content.push(AnthropicContent::Text {
+            text,
+            cache_control,
+        });
+    }
+    content.extend(images.into_iter().map(AnthropicContent::from));
+    content
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -118,6 +183,30 @@ struct AnthropicResponseFormat {
 struct AnthropicToolChoice {
     #[serde(rename = "type")]
     choice_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    disable_parallel_tool_use: Option<bool>,
+}
+
+impl AnthropicToolChoice {
+    fn from_options(options: &CompletionOptions) -> Self {
+        let (choice_type, name) = match &options.tool_choice {
+            ToolChoice::Auto => ("auto", None),
+            ToolChoice::Any => ("any", None),
+            ToolChoice::None => ("none", None),
+            ToolChoice::Tool(name) => ("tool", Some(name.clone())),
+        };
+        // Parallel use can't be configured when no tool may be called
+        let disable_parallel_tool_use = (!options.parallel_tool_calls
+            && options.tool_choice != ToolChoice::None)
+            .then_some(true);
+        Self {
+            choice_type: choice_type.to_string(),
+            name,
+            disable_parallel_tool_use,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -171,10 +260,20 @@ struct AnthropicResponse {
     stop_sequence: Option<String>,
 }
 
+impl AnthropicResponse {
+    fn finish_reason(&self) -> FinishReason {
+        self.stop_reason
+            .as_deref()
+            .map(FinishReason::from_provider)
+            .unwrap_or_default()
+    }
+}
+
 pub struct AnthropicClient {
     client: ReqwestClient,
     model: String,
     api_base: String,
+    prompt_caching: bool,
 }
 
 // Helper methods
@@ -195,87 +294,28 @@ impl AnthropicClient {
             cache_type: "ephemeral".to_string(),
         }
     }
+
+    /// Cache control for a block marking a cache breakpoint, or None when
+    /// prompt caching is off
+    fn cache_breakpoint(&self) -> Option<CacheControl> {
+        self.prompt_caching.then(Self::create_ephemeral_cache)
+    }
 }
 
 impl AnthropicClient {
-    // Helper function to send a request with retry logic for overload errors
-    async fn send_request_with_retry<T: serde::Serialize + Clone>(
+    // Send a request, retrying rate limits, overloaded servers and network errors
+    async fn send_request_with_retry<T: serde::Serialize>(
         &self,
         request: &T,
+        retry: &RetryPolicy,
     ) -> Result<Response> {
-        // Implement retry logic with exponential backoff for 529 overload errors
-        let mut retries = 0;
-        let max_retries = 3; // Maximum number of retries
-        let mut delay_ms = 1000; // Start with 1 second delay
-
-        loop {
-            let result = self.client.post(&self.api_base).json(request).send().await;
-
-            match result {
-                Ok(resp) => {
-                    // If response is 429 (rate limit) or 529 (overloaded), retry
-                    if resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
-                        || resp.status().as_u16() == 529
-                    {
-                        if retries >= max_retries {
-                            // Return the last error response if max retries reached
-                            return Ok(resp);
-                        }
-
-                        // Extract retry-after header if available before cloning for the error body
-                        let retry_after = resp
-                            .headers()
-                            .get("retry-after")
-                            .and_then(|val| val.to_str().ok())
-                            .and_then(|val| val.parse::<u64>().ok())
-                            .unwrap_or(delay_ms);
-
-                        // Clone the response for logging
-                        let error_body = resp.text().await.unwrap_or_default();
-                        eprintln!(
-                            "{}",
-                            format_log_with_color(
-                                LogLevel::Warning,
-                                &format!("Anthropic API rate limited or overloaded: {error_body}")
-                            )
-                        );
-
-                        // Exponential backoff with jitter
-                        let jitter = rand::random::<u64>() % 500;
-                        let sleep_duration = Duration::from_millis(retry_after + jitter);
-
-                        // Sleep and retry
-                        tokio::time::sleep(sleep_duration).await;
-
-                        // Increase delay for next retry
-                        delay_ms = (delay_ms * 2).min(10000); // Cap at 10 seconds
-                        retries += 1;
-                        continue;
-                    }
-
-                    // For other status codes, return the response
-                    return Ok(resp);
-                }
-                Err(e) => {
-                    // For network errors, also use retry logic
-                    if retries >= max_retries {
-                        return Err(AppError::NetworkError(format!(
-                            "Failed to send request to Anthropic after {retries} retries: {e}"
-                        ))
-                        .into());
-                    }
-
-                    // Exponential backoff with jitter
-                    let jitter = rand::random::<u64>() % 500;
-                    let sleep_duration = Duration::from_millis(delay_ms + jitter);
-                    tokio::time::sleep(sleep_duration).await;
-
-                    // Increase delay for next retry
-                    delay_ms = (delay_ms * 2).min(10000); // Cap at 10 seconds
-                    retries += 1;
-                }
-            }
-        }
+        send_with_retry("Anthropic", retry, || {
+            self.client.post(&self.api_base).json(request).send()
+        })
+        .await
+        .map_err(|e| {
+            AppError::NetworkError(format!("Failed to send request to Anthropic: {e}")).into()
+        })
     }
 
     pub fn new(model: Option<String>) -> Result<Self> {
@@ -306,14 +346,28 @@ impl AnthropicClient {
             client,
             model,
             api_base: "https://api.anthropic.com/v1/messages".to_string(),
+            prompt_caching: prompt_caching_enabled(),
         })
     }
 
+    /// Send requests to another messages endpoint, e.g. a proxy or a test server
+    pub fn with_api_base(mut self, api_base: String) -> Self {
+        self.api_base = api_base;
+        self
+    }
+
+    /// Turn marking requests for prompt caching on or off, overriding `OLI_PROMPT_CACHING`
+    pub fn with_prompt_caching(mut self, enabled: bool) -> Self {
+        self.prompt_caching = enabled;
+        self
+    }
+
     /// Extracts system message from the provided messages and formats it with cache control
     /// for prompt caching.
     ///
     /// This method finds the first message with the "system" role and formats it as a `SystemContent`
-    /// with an ephemeral cache_control, allowing Claude to cache the system prompt.
+    /// with an ephemeral cache_control, allowing Claude to cache the system prompt, unless
+    /// prompt caching is off.
     fn extract_system_message(&self, messages: &[Message]) -> Option<SystemContent> {
         messages
             .iter()
@@ -322,7 +376,7 @@ impl AnthropicClient {
                 let system_block = SystemBlock {
                     block_type: "text".to_string(),
                     text: system_msg.content.clone(),
-                    cache_control: Some(Self::create_ephemeral_cache()),
+                    cache_control: self.cache_breakpoint(),
                 };
                 SystemContent::Array(vec![system_block])
             })
@@ -333,7 +387,8 @@ impl AnthropicClient {
     /// This method:
     /// 1. Filters out system messages (handled separately)
     /// 2. Formats each message as an AnthropicMessage
-    /// 3. Adds cache_control to the last and second-to-last user messages for prompt caching
+    /// 3. Adds cache_control to the last and second-to-last user messages for prompt caching,
+    ///    unless it's off
     fn convert_messages(&self, messages: Vec<Message>) -> Vec<AnthropicMessage> {
         let filtered_messages: Vec<Message> = messages
             .into_iter()
@@ -358,29 +413,14 @@ impl AnthropicClient {
 
         // Use enumerated iterator to track position efficiently
         for (idx, msg) in filtered_messages.iter().enumerate() {
-            let mut content = vec![AnthropicContent::Text {
-                text: msg.content.clone(),
-                cache_control: None,
-            }];
-
             // Apply cache control to last and second-to-last user messages
-            if let Some(last_idx) = last_user_index {
-                // Always apply cache to the last user message
-                if idx == last_idx {
-                    content = vec![AnthropicContent::Text {
-                        text: msg.content.clone(),
-                        cache_control: Some(Self::create_ephemeral_cache()),
-                    }];
-                } else if let Some(second_last_idx) = second_last_user_index {
-                    // Apply to second-to-last if it exists
-                    if idx == second_last_idx {
-                        content = vec![AnthropicContent::Text {
-                            text: msg.content.clone(),
-                            cache_control: Some(Self::create_ephemeral_cache()),
-                        }];
-                    }
-                }
-            }
+            let cache_control =
+                if Some(idx) == last_user_index || Some(idx) == second_last_user_index {
+                    self.cache_breakpoint()
+                } else {
+                    None
+                };
+            let content = text_and_images(&msg.content, cache_control);
 
             anthropic_messages.push(AnthropicMessage {
                 role: msg.role.clone(),
@@ -391,6 +431,41 @@ impl AnthropicClient {
         anthropic_messages
     }
 
+    /// Builds a request without tools, used for plain completions and batches
+    fn build_request(
+        &self,
+        messages: Vec<Message>,
+        options: &CompletionOptions,
+    ) -> AnthropicRequest {
+        // Extract system message if present
+        let system_message = self.extract_system_message(&messages);
+        let converted_messages = self.convert_messages(messages);
+
+        let max_tokens = options.max_tokens.unwrap_or(2048) as usize;
+
+        let mut request = AnthropicRequest {
+            model: self.model.clone(),
+            messages: converted_messages,
+            max_tokens,
+            system: system_message,
+            temperature: options.temperature,
+            top_p: options.top_p,
+            tools: None,
+            tool_choice: None,
+            response_format: None,
+        };
+
+        // Add structured output format if specified in options
+        if let Some(json_schema) = &options.json_schema {
+            request.response_format = Some(AnthropicResponseFormat {
+                format_type: "json".to_string(),
+                schema: serde_json::from_str(json_schema).ok(),
+            });
+        }
+
+        request
+    }
+
     /// Converts internal tool definitions to Anthropic's format with cache control
     ///
     /// This method:
@@ -399,7 +474,7 @@ impl AnthropicClient {
     /// 3. Creates a proper JSON Schema compliant schema for each tool
     fn convert_tool_definitions(
         &self,
-        tools: Vec<crate::apis::api_client::ToolDefinition>,
+        tools: Vec<crate::api_client::ToolDefinition>,
     ) -> Vec<AnthropicTool> {
         let mut tool_specs = Vec::new();
 
@@ -425,7 +500,7 @@ impl AnthropicClient {
 
             // Add cache_control to the last tool spec
             let cache_control = if i == tools.len() - 1 {
-                Some(Self::create_ephemeral_cache())
+                self.cache_breakpoint()
             } else {
                 None
             };
@@ -440,14 +515,64 @@ impl AnthropicClient {
 
         tool_specs
     }
-}
 
-#[async_trait]
-impl ApiClient for AnthropicClient {
-    async fn complete(&self, messages: Vec<Message>, options: CompletionOptions) -> Result<String> {
+    /// Build a request with tools, replaying tool results as tool use and tool
+    /// result message pairs
+    fn build_tool_request(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+    ) -> AnthropicRequest {
         // Extract system message if present
         let system_message = self.extract_system_message(&messages);
-        let converted_messages = self.convert_messages(messages);
+        let mut converted_messages = self.convert_messages(messages);
+
+        // Add tool results if they exist
+        if let Some(results) = tool_results {
+            // For each tool result, we need to add corresponding messages
+            for result in results {
+                // Ensure we have a valid tool_call_id
+                let tool_call_id = if result.tool_call_id.is_empty() {
+                    // Generate a simple UUID-like string if no ID was provided
+                    format!("tool-{}", rand::random::<u64>())
+                } else {
+                    result.tool_call_id.clone()
+                };
+
+                // Create a tool use message (from assistant)
+                let tool_use_msg = AnthropicMessage {
+                    role: "assistant".to_string(),
+                    content: vec![AnthropicContent::ToolUse {
+                        id: tool_call_id.clone(),
+                        name: "tool".to_string(), // We don't have the original name
+                        input: json!({}),         // We don't need the input for this
+                        cache_control: None,
+                    }],
+                };
+
+                // Create a tool result message (from user) with proper tool_result content
+                let tool_result_msg = AnthropicMessage {
+                    role: "user".to_string(),
+                    content: vec![AnthropicContent::ToolResult {
+                        tool_call_id: tool_call_id.clone(),
+                        content: if split_images(&result.output).1.is_empty() {
+                            AnthropicToolResultContent::Text(result.output.clone())
+                        } else {
+                            AnthropicToolResultContent::Blocks(text_and_images(
+                                &result.output,
+                                None,
+                            ))
+                        },
+                        cache_control: None,
+                    }],
+                };
+
+                // Add both messages to the conversation
+                converted_messages.push(tool_use_msg);
+                converted_messages.push(tool_result_msg);
+            }
+        }
 
         let max_tokens = options.max_tokens.unwrap_or(2048) as usize;
 
@@ -463,16 +588,72 @@ impl ApiClient for AnthropicClient {
             response_format: None,
         };
 
-        // Add structured output format if specified in options
+        // IMPORTANT: Add response_format only if json_schema exists AND tools don't exist
+        // This fixes the "extra inputs are not permitted" error when using tools
         if let Some(json_schema) = &options.json_schema {
-            request.response_format = Some(AnthropicResponseFormat {
-                format_type: "json".to_string(),
-                schema: serde_json::from_str(json_schema).ok(),
-            });
+            // Only add response_format if we're not using tools
+            if options.tools.is_none() {
+                request.response_format = Some(AnthropicResponseFormat {
+                    format_type: "json".to_string(),
+                    schema: serde_json::from_str(json_schema).ok(),
+                });
+            }
         }
 
+        // Add tools if they exist, with the requested tool choice
+        let tool_choice = AnthropicToolChoice::from_options(&options);
+        if let Some(tools) = options.tools {
+            let converted_tools = self.convert_tool_definitions(tools);
+            request.tools = Some(converted_tools);
+            request.tool_choice = Some(tool_choice);
+        }
+
+        request
+    }
+}
+
+#[async_trait]
+impl ApiClient for AnthropicClient {
+    async fn complete(&self, messages: Vec<Message>, options: CompletionOptions) -> Result<String> {
+        Ok(self.complete_with_finish(messages, options).await?.0)
+    }
+
+    async fn complete_with_tools(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+    ) -> Result<(String, Option<Vec<ToolCall>>)> {
+        let (content, tool_calls, _) = self
+            .complete_with_tools_and_finish(messages, options, tool_results)
+            .await?;
+        Ok((content, tool_calls))
+    }
+
+    async fn complete_streaming(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, TokenUsage)> {
+        let (content, usage, _) = self
+            .complete_streaming_with_finish(messages, options, on_delta)
+            .await?;
+        Ok((content, usage))
+    }
+
+    async fn complete_with_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+    ) -> Result<(String, FinishReason)> {
+        let started = Instant::now();
+        let request = self.build_request(messages, &options);
+
         // Use our retry function instead of direct API call
-        let response = self.send_request_with_retry(&request).await?;
+        let response = self
+            .send_request_with_retry(&request, &options.retry)
+            .await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -535,104 +716,34 @@ impl ApiClient for AnthropicClient {
         if let Some(usage) = &anthropic_response.usage {
             log_anthropic_usage(usage);
         }
+        record_request(
+            "anthropic",
+            &self.model,
+            anthropic_response
+                .usage
+                .as_ref()
+                .map(RequestUsage::from_anthropic)
+                .unwrap_or_default(),
+            started,
+        );
 
         let content = text_content;
 
-        Ok(content)
+        Ok((content, anthropic_response.finish_reason()))
     }
 
-    async fn complete_with_tools(
+    async fn complete_with_tools_and_finish(
         &self,
         messages: Vec<Message>,
         options: CompletionOptions,
         tool_results: Option<Vec<ToolResult>>,
-    ) -> Result<(String, Option<Vec<ToolCall>>)> {
-        // Extract system message if present
-        let system_message = self.extract_system_message(&messages);
-        let mut converted_messages = self.convert_messages(messages);
-
-        // Add tool results if they exist
-        if let Some(results) = tool_results {
-            // For each tool result, we need to add corresponding messages
-            for result in results {
-                // Ensure we have a valid tool_call_id
-                let tool_call_id = if result.tool_call_id.is_empty() {
-                    // Generate a simple UUID-like string if no ID was provided
-                    format!("tool-{}", rand::random::<u64>())
-                } else {
-                    result.tool_call_id.clone()
-                };
-
-                // Create a tool use message (from assistant)
-                let tool_use_msg = AnthropicMessage {
-                    role: "assistant".to_string(),
-                    content: vec![AnthropicContent::ToolUse {
-                        id: tool_call_id.clone(),
-                        name: "tool".to_string(), // We don't have the original name
-                        input: json!({}),         // We don't need the input for this
-                        cache_control: None,
-                    }],
-                };
-
-                // Create a tool result message (from user) with proper tool_result content
-                let tool_result_msg = AnthropicMessage {
-                    role: "user".to_string(),
-                    content: vec![AnthropicContent::ToolResult {
-                        tool_call_id: tool_call_id.clone(),
-                        content: result.output.clone(),
-                        cache_control: None,
-                    }],
-                };
-
-                // Add both messages to the conversation
-                converted_messages.push(tool_use_msg);
-                converted_messages.push(tool_result_msg);
-            }
-        }
-
-        let max_tokens = options.max_tokens.unwrap_or(2048) as usize;
-
-        let mut request = AnthropicRequest {
-            model: self.model.clone(),
-            messages: converted_messages,
-            max_tokens,
-            system: system_message,
-            temperature: options.temperature,
-            top_p: options.top_p,
-            tools: None,
-            tool_choice: None,
-            response_format: None,
-        };
-
-        // IMPORTANT: Add response_format only if json_schema exists AND tools don't exist
-        // This fixes the "extra inputs are not permitted" error when using tools
-        if let Some(json_schema) = &options.json_schema {
-            // Only add response_format if we're not using tools
-            if options.tools.is_none() {
-                request.response_format = Some(AnthropicResponseFormat {
-                    format_type: "json".to_string(),
-                    schema: serde_json::from_str(json_schema).ok(),
-                });
-            }
-        }
-
-        // Add tools if they exist
-        if let Some(tools) = options.tools {
-            let converted_tools = self.convert_tool_definitions(tools);
-            request.tools = Some(converted_tools);
-
-            // Set tool choice based on option
-            request.tool_choice = Some(AnthropicToolChoice {
-                choice_type: if options.require_tool_use {
-                    "required".to_string()
-                } else {
-                    "auto".to_string()
-                },
-            });
-        }
+    ) -> Result<(String, Option<Vec<ToolCall>>, FinishReason)> {
+        let started = Instant::now();
+        let retry = options.retry;
+        let request = self.build_tool_request(messages, options, tool_results);
 
         // Use our retry function instead of direct API call
-        let response = self.send_request_with_retry(&request).await?;
+        let response = self.send_request_with_retry(&request, &retry).await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -688,13 +799,13 @@ impl ApiClient for AnthropicClient {
                 }
                 AnthropicContent::ToolUse { name, input, .. } => {
                     // Add a tool call
-                    tool_calls_vec.push(crate::apis::api_client::ToolCall {
+                    tool_calls_vec.push(crate::api_client::ToolCall {
                         id: None, // Anthropic doesn't provide IDs like OpenAI
                         name: name.clone(),
                         arguments: input.clone(),
                     });
                 }
-                AnthropicContent::ToolResult { .. } => {
+                AnthropicContent::ToolResult { .. } | AnthropicContent::Image { .. } => {
                     // Tool results are not processed here, they're for the API to recognize tool result responses
                 }
             }
@@ -704,6 +815,16 @@ impl ApiClient for AnthropicClient {
         if let Some(usage) = &anthropic_response.usage {
             log_anthropic_usage(usage);
         }
+        record_request(
+            "anthropic",
+            &self.model,
+            anthropic_response
+                .usage
+                .as_ref()
+                .map(RequestUsage::from_anthropic)
+                .unwrap_or_default(),
+            started,
+        );
 
         // If we didn't find any text content, use an empty string
         let content = if text_content.is_empty() {
@@ -722,14 +843,322 @@ impl ApiClient for AnthropicClient {
             Some(tool_calls_vec)
         };
 
-        Ok((content, tool_calls))
+        Ok((content, tool_calls, anthropic_response.finish_reason()))
+    }
+
+    async fn complete_with_tools_streaming_and_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, Option<Vec<ToolCall>>, FinishReason)> {
+        let started = Instant::now();
+        let retry = options.retry;
+        let mut request =
+            serde_json::to_value(self.build_tool_request(messages, options, tool_results))?;
+        request["stream"] = json!(true);
+
+        let response = self.send_request_with_retry(&request, &retry).await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AppError::NetworkError(format!(
+                "Anthropic API error: {status} - {error_text}"
+            ))
+            .into());
+        }
+
+        // A tool use block starts with its name, and its input arrives as JSON
+        // fragments until the block stops
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+        let mut tool_input: Option<(String, String)> = None;
+        let mut billed = RequestUsage::default();
+        let mut finish = FinishReason::Stop;
+        read_sse_stream(response, |_, event| {
+            match event["type"].as_str() {
+                Some("content_block_start") => {
+                    let block = &event["content_block"];
+                    if block["type"] == "tool_use" {
+                        let name = block["name"].as_str().unwrap_or_default().to_string();
+                        tool_input = Some((name, String::new()));
+                    }
+                }
+                Some("content_block_delta") => {
+                    let delta = &event["delta"];
+                    if let Some(text) = delta["text"].as_str() {
+                        content.push_str(text);
+                        on_delta(text);
+                    } else if let (Some(json), Some((_, input))) =
+                        (delta["partial_json"].as_str(), tool_input.as_mut())
+                    {
+                        input.push_str(json);
+                    }
+                }
+                Some("content_block_stop") => {
+                    if let Some((name, input)) = tool_input.take() {
+                        let arguments = match input.as_str() {
+                            "" => json!({}),
+                            input => serde_json::from_str(input).map_err(|e| {
+                                AppError::Other(format!("Invalid {name} tool input: {e}"))
+                            })?,
+                        };
+                        tool_calls.push(ToolCall {
+                            id: None,
+                            name,
+                            arguments,
+                        });
+                    }
+                }
+                Some("message_start") => {
+                    billed = RequestUsage::from_anthropic(&event["message"]["usage"]);
+                }
+                Some("message_delta") => {
+                    billed.output_tokens =
+                        event["usage"]["output_tokens"].as_u64().unwrap_or(0) as u32;
+                    if let Some(reason) = event["delta"]["stop_reason"].as_str() {
+                        finish = FinishReason::from_provider(reason);
+                    }
+                }
+                Some("error") => {
+                    return Err(AppError::LLMError(format!(
+                        "Anthropic stream error: {}",
+                        event["error"]["message"]
+                            .as_str()
+                            .unwrap_or("unknown error")
+                    ))
+                    .into());
+                }
+                _ => {}
+            }
+            Ok(())
+        })
+        .await?;
+
+        record_request("anthropic", &self.model, billed, started);
+        Ok((
+            content,
+            (!tool_calls.is_empty()).then_some(tool_calls),
+            finish,
+        ))
+    }
+
+    async fn complete_streaming_with_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, TokenUsage, FinishReason)> {
+        let started = Instant::now();
+        let mut request = serde_json::to_value(self.build_request(messages, &options))?;
+        request["stream"] = json!(true);
+
+        let response = self
+            .send_request_with_retry(&request, &options.retry)
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AppError::NetworkError(format!(
+                "Anthropic API error: {status} - {error_text}"
+            ))
+            .into());
+        }
+
+        // Text arrives in content_block_delta events; input tokens are reported
+        // when the message starts, and output tokens and the stop reason when it ends
+        let mut content = String::new();
+        let mut usage = TokenUsage::default();
+        let mut billed = RequestUsage::default();
+        let mut finish = FinishReason::Stop;
+        read_sse_stream(response, |_, event| {
+            match event["type"].as_str() {
+                Some("content_block_delta") => {
+                    if let Some(text) = event["delta"]["text"].as_str() {
+                        content.push_str(text);
+                        on_delta(text);
+                    }
+                }
+                Some("message_start") => {
+                    usage.input_tokens = event["message"]["usage"]["input_tokens"]
+                        .as_u64()
+                        .unwrap_or(0) as u32;
+                    billed = RequestUsage::from_anthropic(&event["message"]["usage"]);
+                }
+                Some("message_delta") => {
+                    usage.output_tokens =
+                        event["usage"]["output_tokens"].as_u64().unwrap_or(0) as u32;
+                    billed.output_tokens = usage.output_tokens;
+                    if let Some(reason) = event["delta"]["stop_reason"].as_str() {
+                        finish = FinishReason::from_provider(reason);
+                    }
+                }
+                Some("error") => {
+                    return Err(AppError::LLMError(format!(
+                        "Anthropic stream error: {}",
+                        event["error"]["message"]
+                            .as_str()
+                            .unwrap_or("unknown error")
+                    ))
+                    .into());
+                }
+                _ => {}
+            }
+            Ok(())
+        })
+        .await?;
+
+        record_request("anthropic", &self.model, billed, started);
+        Ok((content, usage, finish))
+    }
+}
+
+/// Anthropic Message Batches API, at `{api_base}/batches`
+#[async_trait]
+impl BatchApi for AnthropicClient {
+    async fn submit_batch(&self, requests: Vec<BatchRequest>) -> Result<String> {
+        if let Some(request) = requests.iter().find(|r| !r.is_eligible()) {
+            return Err(anyhow::anyhow!(
+                "Request {} uses tools and can't be batched",
+                request.custom_id
+            ));
+        }
+
+        let requests: Vec<Value> = requests
+            .into_iter()
+            .map(|request| {
+                json!({
+                    "custom_id": request.custom_id,
+                    "params": self.build_request(request.messages, &request.options),
+                })
+            })
+            .collect();
+
+        let response = self
+            .client
+            .post(format!("{}/batches", self.api_base))
+            .json(&json!({ "requests": requests }))
+            .send()
+            .await
+            .map_err(|e| AppError::NetworkError(format!("Failed to submit batch: {e}")))?;
+        let batch = batch_response_json(response).await?;
+
+        batch["id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("Anthropic batch response has no id: {batch}"))
+    }
+
+    async fn batch_status(&self, batch_id: &str) -> Result<BatchStatus> {
+        let batch = self.get_batch(batch_id).await?;
+
+        // Batches end once every request succeeded, errored, expired or was cancelled
+        match batch["processing_status"].as_str() {
+            Some("ended") => Ok(BatchStatus::Completed),
+            Some("in_progress") | Some("canceling") => Ok(BatchStatus::InProgress),
+            other => Err(anyhow::anyhow!(
+                "Unknown Anthropic batch status: {}",
+                other.unwrap_or("none")
+            )),
+        }
+    }
+
+    async fn batch_results(&self, batch_id: &str) -> Result<Vec<BatchResult>> {
+        let batch = self.get_batch(batch_id).await?;
+        let results_url = batch["results_url"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Batch {batch_id} has no results yet"))?;
+
+        let response =
+            self.client.get(results_url).send().await.map_err(|e| {
+                AppError::NetworkError(format!("Failed to fetch batch results: {e}"))
+            })?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AppError::NetworkError(format!(
+                "Anthropic API error: {status} - {error_text}"
+            ))
+            .into());
+        }
+
+        let body = response.text().await?;
+        Ok(body
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+            .map(|line| parse_anthropic_batch_result(&line))
+            .collect())
+    }
+}
+
+impl AnthropicClient {
+    async fn get_batch(&self, batch_id: &str) -> Result<Value> {
+        let response = self
+            .client
+            .get(format!("{}/batches/{batch_id}", self.api_base))
+            .send()
+            .await
+            .map_err(|e| AppError::NetworkError(format!("Failed to check batch: {e}")))?;
+        batch_response_json(response).await
+    }
+}
+
+async fn batch_response_json(response: Response) -> Result<Value> {
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(AppError::NetworkError(format!(
+            "Anthropic API error: {status} - {error_text}"
+        ))
+        .into());
+    }
+    Ok(response.json().await?)
+}
+
+fn parse_anthropic_batch_result(line: &Value) -> BatchResult {
+    let custom_id = line["custom_id"].as_str().unwrap_or_default();
+    let result = &line["result"];
+
+    match result["type"].as_str() {
+        Some("succeeded") => {
+            let text = result["message"]["content"]
+                .as_array()
+                .and_then(|content| {
+                    content
+                        .iter()
+                        .find(|block| block["type"] == "text")
+                        .and_then(|block| block["text"].as_str())
+                })
+                .unwrap_or_default();
+            BatchResult::success(custom_id, text.to_string())
+        }
+        Some("errored") => {
+            let message = result["error"]["error"]["message"]
+                .as_str()
+                .or_else(|| result["error"]["message"].as_str())
+                .unwrap_or("request errored");
+            BatchResult::failure(custom_id, message.to_string())
+        }
+        Some(other) => BatchResult::failure(custom_id, format!("request {other}")),
+        None => BatchResult::failure(custom_id, "missing result".to_string()),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::apis::api_client::{Message, ToolDefinition};
+    use crate::api_client::{Message, ToolDefinition};
     use serde_json::json;
 
     #[test]
@@ -1231,4 +1660,28 @@ mod tests {
             "Tool should have cache control"
         );
     }
+
+    #[test]
+    fn test_tool_choice_from_options() {
+        let choice = |tool_choice, parallel_tool_calls| {
+            let options = CompletionOptions {
+                tool_choice,
+                parallel_tool_calls,
+                ..CompletionOptions::default()
+            };
+            serde_json::to_value(AnthropicToolChoice::from_options(&options)).unwrap()
+        };
+
+        assert_eq!(choice(ToolChoice::Auto, true), json!({"type": "auto"}));
+        assert_eq!(
+            choice(ToolChoice::Any, false),
+            json!({"type": "any", "disable_parallel_tool_use": true})
+        );
+        assert_eq!(
+            choice(ToolChoice::Tool("Read".to_string()), true),
+            json!({"type": "tool", "name": "Read"})
+        );
+        // Parallel use isn't sent when no tool may be called
+        assert_eq!(choice(ToolChoice::None, false), json!({"type": "none"}));
+    }
 }
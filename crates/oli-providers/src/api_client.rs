@@ -0,0 +1,897 @@
+use crate::retry::RetryPolicy;
+use crate::streaming::{DeltaHandler, TokenUsage};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Message {
+    pub role: String,
+    pub content: String,
+}
+
+impl Message {
+    pub fn system(content: String) -> Self {
+        Self {
+            role: "system".to_string(),
+            content,
+        }
+    }
+
+    pub fn user(content: String) -> Self {
+        Self {
+            role: "user".to_string(),
+            content,
+        }
+    }
+
+    pub fn assistant(content: String) -> Self {
+        Self {
+            role: "assistant".to_string(),
+            content,
+        }
+    }
+}
+
+/// How an image is written into message text: the opening tag with its media
+/// type, the base64 data, then the closing tag
+const IMAGE_BLOCK_START: &str = "<oli-image media_type=\"";
+const IMAGE_BLOCK_END: &str = "</oli-image>";
+
+/// An image carried in the text of a message or tool result, e.g. a screenshot
+/// the Read tool opened. Clients whose models take images send it as an image
+/// part; the others leave it out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageData {
+    /// e.g. `image/png`
+    pub media_type: String,
+    /// The image, base64-encoded
+    pub data: String,
+}
+
+impl ImageData {
+    /// The image as a block to put in message text
+    pub fn to_block(&self) -> String {
+        format!(
+            "{IMAGE_BLOCK_START}{}\">{}{IMAGE_BLOCK_END}",
+            self.media_type, self.data
+        )
+    }
+
+    /// The image as a `data:` URL
+    pub fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.media_type, self.data)
+    }
+}
+
+/// Separate the image blocks in `text` from the rest of it
+pub fn split_images(text: &str) -> (String, Vec<ImageData>) {
+    let mut rest = text;
+    let mut remaining = String::new();
+    let mut images = Vec::new();
+    while let Some(start) = rest.find(IMAGE_BLOCK_START) {
+        let block = &rest[start + IMAGE_BLOCK_START.len()..];
+        let Some((media_type, after)) = block.split_once("\">") else {
+            break;
+        };
+        let Some((data, after)) = after.split_once(IMAGE_BLOCK_END) else {
+            break;
+        };
+        remaining.push_str(&rest[..start]);
+        images.push(ImageData {
+            media_type: media_type.to_string(),
+            data: data.trim().to_string(),
+        });
+        rest = after;
+    }
+    remaining.push_str(rest);
+    (remaining, images)
+}
+
+/// `text` with each image block replaced by a note, for models that don't take
+/// images
+pub fn without_images(text: &str) -> String {
+    let (mut remaining, images) = split_images(text);
+    if !images.is_empty() {
+        remaining.push_str(&format!(
+            "\n[{} image(s) left out: this model doesn't accept images]",
+            images.len()
+        ));
+    }
+    remaining
+}
+
+/// Manages the conversation session with history of messages
+#[derive(Debug, Clone)]
+pub struct SessionManager {
+    /// History of messages for the current session
+    pub messages: Vec<Message>,
+    /// Maximum number of messages to keep in the session
+    pub max_messages: usize,
+    /// System message to prepend to all conversations
+    pub system_message: Option<Message>,
+    /// Tags of messages, e.g. "decision" or "todo", by index into `messages`
+    pub message_tags: BTreeMap<usize, BTreeSet<String>>,
+}
+
+impl Default for SessionManager {
+    fn default() -> Self {
+        Self {
+            messages: Vec::new(),
+            max_messages: 100,
+            system_message: None,
+            message_tags: BTreeMap::new(),
+        }
+    }
+}
+
+impl SessionManager {
+    /// Create a new session manager with a specific message capacity
+    pub fn new(max_messages: usize) -> Self {
+        Self {
+            messages: Vec::new(),
+            max_messages,
+            system_message: None,
+            message_tags: BTreeMap::new(),
+        }
+    }
+
+    /// Add a system message that will be prepended to the conversation
+    pub fn with_system_message(mut self, content: String) -> Self {
+        self.system_message = Some(Message::system(content));
+        self
+    }
+
+    /// Add a user message to the conversation
+    pub fn add_user_message(&mut self, content: String) {
+        self.add_message(Message::user(content));
+    }
+
+    /// Add an assistant message to the conversation
+    pub fn add_assistant_message(&mut self, content: String) {
+        self.add_message(Message::assistant(content));
+    }
+
+    /// Add a message to the conversation
+    pub fn add_message(&mut self, message: Message) {
+        self.messages.push(message);
+        self.trim_if_needed();
+    }
+
+    /// Replace all messages with a single summary message
+    pub fn replace_with_summary(&mut self, summary: String) {
+        self.messages.clear();
+        self.message_tags.clear();
+        self.add_message(Message::system(format!(
+            "Previous conversation summary: {summary}"
+        )));
+    }
+
+    /// Get all messages for the API call, including the system message if present
+    pub fn get_messages_for_api(&self) -> Vec<Message> {
+        let mut api_messages = Vec::new();
+
+        // Add system message if present
+        if let Some(sys_message) = &self.system_message {
+            api_messages.push(sys_message.clone());
+        }
+
+        // Add conversation messages
+        api_messages.extend(self.messages.clone());
+
+        api_messages
+    }
+
+    /// Replace the content of the message at `index`, keeping its role
+    pub fn edit_message(&mut self, index: usize, content: String) -> Result<()> {
+        let count = self.messages.len();
+        let message = self.messages.get_mut(index).ok_or_else(|| {
+            anyhow::anyhow!("No message at index {index}; the session has {count}")
+        })?;
+        message.content = content;
+        Ok(())
+    }
+
+    /// Remove the message at `index`, returning it
+    pub fn delete_message(&mut self, index: usize) -> Result<Message> {
+        if index >= self.messages.len() {
+            return Err(anyhow::anyhow!(
+                "No message at index {index}; the session has {}",
+                self.messages.len()
+            ));
+        }
+        self.remove_tags(index..index + 1);
+        Ok(self.messages.remove(index))
+    }
+
+    /// Tag the message at `index`. Returns whether it didn't have the tag yet.
+    pub fn tag_message(&mut self, index: usize, tag: &str) -> Result<bool> {
+        if index >= self.messages.len() {
+            return Err(anyhow::anyhow!(
+                "No message at index {index}; the session has {}",
+                self.messages.len()
+            ));
+        }
+        Ok(self
+            .message_tags
+            .entry(index)
+            .or_default()
+            .insert(tag.to_string()))
+    }
+
+    /// Remove a tag from the message at `index`. Returns whether it had the tag.
+    pub fn untag_message(&mut self, index: usize, tag: &str) -> bool {
+        let Some(tags) = self.message_tags.get_mut(&index) else {
+            return false;
+        };
+        let removed = tags.remove(tag);
+        if tags.is_empty() {
+            self.message_tags.remove(&index);
+        }
+        removed
+    }
+
+    // Drop the tags of removed messages and move later tags down with their messages
+    fn remove_tags(&mut self, removed: std::ops::Range<usize>) {
+        let count = removed.len();
+        self.message_tags = std::mem::take(&mut self.message_tags)
+            .into_iter()
+            .filter(|(index, _)| !removed.contains(index))
+            .map(|(index, tags)| {
+                let index = if index >= removed.end {
+                    index - count
+                } else {
+                    index
+                };
+                (index, tags)
+            })
+            .collect();
+    }
+
+    /// Clear all messages in the session
+    pub fn clear(&mut self) {
+        self.messages.clear();
+        self.message_tags.clear();
+    }
+
+    /// Get the current number of messages
+    pub fn message_count(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Trim messages if the count exceeds max_messages
+    fn trim_if_needed(&mut self) {
+        if self.messages.len() > self.max_messages {
+            let to_remove = self.messages.len() - self.max_messages;
+            self.messages.drain(0..to_remove);
+            self.remove_tags(0..to_remove);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolDefinition {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: Option<String>, // Required for OpenAI to map tool results back to calls
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolResult {
+    pub tool_call_id: String,
+    pub output: String,
+}
+
+/// Whether the model may, must or must not call a tool in its response
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolChoice {
+    /// The model decides whether to call a tool
+    #[default]
+    Auto,
+    /// The model must call at least one of the offered tools
+    Any,
+    /// The model must answer without calling a tool
+    None,
+    /// The model must call the named tool
+    Tool(String),
+}
+
+impl ToolChoice {
+    /// Whether the response must contain a tool call
+    pub fn requires_tool(&self) -> bool {
+        matches!(self, Self::Any | Self::Tool(_))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionOptions {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub tools: Option<Vec<ToolDefinition>>,
+    pub json_schema: Option<String>,
+    pub tool_choice: ToolChoice,
+    /// Whether the model may request several tool calls in one response. Turning
+    /// it off makes the model wait for each result before asking for the next tool.
+    pub parallel_tool_calls: bool,
+    /// How requests that hit rate limits, overloaded servers or network errors are retried
+    #[serde(default)]
+    pub retry: RetryPolicy,
+}
+
+impl Default for CompletionOptions {
+    fn default() -> Self {
+        Self {
+            temperature: Some(0.7),
+            top_p: Some(0.9),
+            max_tokens: Some(2048),
+            tools: None,
+            json_schema: None,
+            tool_choice: ToolChoice::Auto,
+            parallel_tool_calls: true,
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+/// Sampling parameters that override the defaults for a session or a single request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelParameters {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub max_tokens: Option<u32>,
+}
+
+impl ModelParameters {
+    /// Parse any parameters present in a JSON object, ignoring unrelated fields
+    pub fn from_json(params: &serde_json::Value) -> Result<Self> {
+        let temperature = match params.get("temperature") {
+            Some(value) if !value.is_null() => Some(
+                value
+                    .as_f64()
+                    .ok_or_else(|| anyhow::anyhow!("'temperature' must be a number"))?
+                    as f32,
+            ),
+            _ => None,
+        };
+        let top_p = match params.get("top_p") {
+            Some(value) if !value.is_null() => Some(
+                value
+                    .as_f64()
+                    .ok_or_else(|| anyhow::anyhow!("'top_p' must be a number"))?
+                    as f32,
+            ),
+            _ => None,
+        };
+        let max_tokens = match params.get("max_tokens") {
+            Some(value) if !value.is_null() => Some(
+                value
+                    .as_u64()
+                    .and_then(|v| u32::try_from(v).ok())
+                    .ok_or_else(|| anyhow::anyhow!("'max_tokens' must be a positive integer"))?,
+            ),
+            _ => None,
+        };
+
+        Ok(Self {
+            temperature,
+            top_p,
+            max_tokens,
+        })
+    }
+
+    /// Set a single parameter from its textual form, e.g. from `/set temperature 0.7`.
+    /// The value `default` clears the override.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        let value = value.trim();
+        let reset = value.eq_ignore_ascii_case("default");
+        let mut updated = *self;
+
+        match name.trim() {
+            "temperature" => {
+                updated.temperature = if reset {
+                    None
+                } else {
+                    Some(value.parse().map_err(|_| {
+                        anyhow::anyhow!("Invalid temperature '{}': expected a number", value)
+                    })?)
+                };
+            }
+            "top_p" => {
+                updated.top_p = if reset {
+                    None
+                } else {
+                    Some(value.parse().map_err(|_| {
+                        anyhow::anyhow!("Invalid top_p '{}': expected a number", value)
+                    })?)
+                };
+            }
+            "max_tokens" => {
+                updated.max_tokens = if reset {
+                    None
+                } else {
+                    Some(value.parse().map_err(|_| {
+                        anyhow::anyhow!(
+                            "Invalid max_tokens '{}': expected a positive integer",
+                            value
+                        )
+                    })?)
+                };
+            }
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Unknown parameter '{}'. Expected temperature, top_p or max_tokens",
+                    other
+                ))
+            }
+        }
+
+        // Only commit the change once the new value is known to be valid
+        updated.validate(None)?;
+        *self = updated;
+        Ok(())
+    }
+
+    /// Combine with overrides, where values set in `overrides` take precedence
+    pub fn merge(&self, overrides: &ModelParameters) -> ModelParameters {
+        ModelParameters {
+            temperature: overrides.temperature.or(self.temperature),
+            top_p: overrides.top_p.or(self.top_p),
+            max_tokens: overrides.max_tokens.or(self.max_tokens),
+        }
+    }
+
+    /// Apply the set parameters on top of completion options
+    pub fn apply_to(&self, options: &mut CompletionOptions) {
+        if let Some(temperature) = self.temperature {
+            options.temperature = Some(temperature);
+        }
+        if let Some(top_p) = self.top_p {
+            options.top_p = Some(top_p);
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            options.max_tokens = Some(max_tokens);
+        }
+    }
+
+    /// Validate parameters against the limits of a provider, or generic limits if none given
+    pub fn validate(&self, provider: Option<&crate::provider::LLMProvider>) -> Result<()> {
+        let max_temperature = provider.map(|p| p.max_temperature()).unwrap_or(2.0);
+        if let Some(temperature) = self.temperature {
+            if !(0.0..=max_temperature).contains(&temperature) {
+                return Err(anyhow::anyhow!(
+                    "temperature must be between 0 and {}, got {}",
+                    max_temperature,
+                    temperature
+                ));
+            }
+        }
+
+        if let Some(top_p) = self.top_p {
+            if !(0.0..=1.0).contains(&top_p) {
+                return Err(anyhow::anyhow!(
+                    "top_p must be between 0 and 1, got {}",
+                    top_p
+                ));
+            }
+        }
+
+        if let Some(max_tokens) = self.max_tokens {
+            let limit = provider.map(|p| p.max_output_tokens()).unwrap_or(u32::MAX);
+            if max_tokens == 0 || max_tokens > limit {
+                return Err(anyhow::anyhow!(
+                    "max_tokens must be between 1 and {}, got {}",
+                    limit,
+                    max_tokens
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Named temperature and top_p bundles, e.g. `precise` for reviews and `creative`
+/// for brainstorming
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplingPreset {
+    Precise,
+    Balanced,
+    Creative,
+}
+
+impl SamplingPreset {
+    pub const ALL: [SamplingPreset; 3] = [
+        SamplingPreset::Precise,
+        SamplingPreset::Balanced,
+        SamplingPreset::Creative,
+    ];
+
+    /// Parse a preset from its name, ignoring case
+    pub fn parse(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name().eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown preset '{}'. Expected precise, balanced or creative",
+                    name.trim()
+                )
+            })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SamplingPreset::Precise => "precise",
+            SamplingPreset::Balanced => "balanced",
+            SamplingPreset::Creative => "creative",
+        }
+    }
+
+    /// The sampling parameters the preset stands for, before clamping to a model
+    pub fn parameters(&self) -> ModelParameters {
+        let (temperature, top_p) = match self {
+            SamplingPreset::Precise => (0.2, 0.8),
+            SamplingPreset::Balanced => (0.7, 0.9),
+            SamplingPreset::Creative => (1.2, 0.95),
+        };
+        ModelParameters {
+            temperature: Some(temperature),
+            top_p: Some(top_p),
+            max_tokens: None,
+        }
+    }
+
+    /// The preset's parameters clamped to what a model accepts: temperatures above
+    /// the provider's limit are lowered, and models with fixed sampling get none
+    pub fn parameters_for(
+        &self,
+        provider: &crate::provider::LLMProvider,
+        model: &str,
+    ) -> ModelParameters {
+        if !provider.accepts_sampling(model) {
+            return ModelParameters::default();
+        }
+        let mut parameters = self.parameters();
+        parameters.temperature = parameters
+            .temperature
+            .map(|temperature| temperature.min(provider.max_temperature()));
+        parameters
+    }
+}
+
+impl std::fmt::Display for SamplingPreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Continuation requests made for one answer cut off at the output token limit
+pub const MAX_CONTINUATIONS: usize = 3;
+/// Asks the model to pick up a cut-off answer
+pub const CONTINUE_PROMPT: &str = "Your last answer was cut off at the output token limit. \
+    Continue exactly where it stopped, without repeating anything or adding a preamble.";
+/// Shortest repeated text treated as an overlap when stitching a continuation on
+const MIN_OVERLAP_CHARS: usize = 16;
+/// Longest repeated text looked for when stitching a continuation on
+const MAX_OVERLAP_CHARS: usize = 500;
+
+/// Why the model stopped generating
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FinishReason {
+    /// The answer is complete
+    #[default]
+    Stop,
+    /// The model is waiting for tool results
+    ToolUse,
+    /// The answer was cut off at the output token limit
+    Length,
+}
+
+impl FinishReason {
+    /// Map a provider's stop reason: Anthropic's `stop_reason`, OpenAI's and Ollama's
+    /// `finish_reason`/`done_reason`, or Gemini's `finishReason`
+    pub fn from_provider(reason: &str) -> Self {
+        match reason {
+            "max_tokens" | "length" | "MAX_TOKENS" => Self::Length,
+            "tool_use" | "tool_calls" | "function_call" => Self::ToolUse,
+            _ => Self::Stop,
+        }
+    }
+
+    pub fn is_truncated(self) -> bool {
+        self == Self::Length
+    }
+}
+
+/// The conversation for a continuation request: the original messages, the answer so
+/// far, and a request to carry on from where it stopped
+pub fn continuation_messages(messages: &[Message], answer_so_far: &str) -> Vec<Message> {
+    let mut continued = messages.to_vec();
+    continued.push(Message::assistant(answer_so_far.to_string()));
+    continued.push(Message::user(CONTINUE_PROMPT.to_string()));
+    continued
+}
+
+/// Join a continuation onto the answer it continues, dropping text the model repeated
+/// from the end of the answer
+pub fn stitch_continuation(answer_so_far: &str, continuation: &str) -> String {
+    let longest = answer_so_far
+        .len()
+        .min(continuation.len())
+        .min(MAX_OVERLAP_CHARS);
+
+    for overlap in (MIN_OVERLAP_CHARS..=longest).rev() {
+        if !continuation.is_char_boundary(overlap) {
+            continue;
+        }
+        if answer_so_far.ends_with(&continuation[..overlap]) {
+            return format!("{answer_so_far}{}", &continuation[overlap..]);
+        }
+    }
+    format!("{answer_so_far}{continuation}")
+}
+
+// This trait cannot be made into a dyn trait because it has async methods
+#[async_trait::async_trait]
+pub trait ApiClient: Send + Sync {
+    // Basic completion without tool usage
+    #[allow(dead_code)]
+    async fn complete(&self, messages: Vec<Message>, options: CompletionOptions) -> Result<String>;
+
+    async fn complete_with_tools(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+    ) -> Result<(String, Option<Vec<ToolCall>>)>;
+
+    /// Completion without tools that passes text to `on_delta` as it is generated.
+    /// Providers without streaming support send the whole answer as one delta.
+    async fn complete_streaming(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, TokenUsage)> {
+        let usage_messages = messages.clone();
+        let content = self.complete(messages, options).await?;
+        on_delta(&content);
+        let usage = TokenUsage::estimate(&usage_messages, &content);
+        Ok((content, usage))
+    }
+
+    /// `complete`, also reporting why the model stopped. Clients that can't tell
+    /// report every answer as finished.
+    async fn complete_with_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+    ) -> Result<(String, FinishReason)> {
+        let content = self.complete(messages, options).await?;
+        Ok((content, FinishReason::Stop))
+    }
+
+    /// `complete_with_tools`, also reporting why the model stopped
+    async fn complete_with_tools_and_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+    ) -> Result<(String, Option<Vec<ToolCall>>, FinishReason)> {
+        let (content, tool_calls) = self
+            .complete_with_tools(messages, options, tool_results)
+            .await?;
+        let finish = if tool_calls.is_some() {
+            FinishReason::ToolUse
+        } else {
+            FinishReason::Stop
+        };
+        Ok((content, tool_calls, finish))
+    }
+
+    /// `complete_with_tools_and_finish`, passing text to `on_delta` as it is
+    /// generated. Providers without streaming support send the whole text as one delta.
+    async fn complete_with_tools_streaming_and_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, Option<Vec<ToolCall>>, FinishReason)> {
+        let (content, tool_calls, finish) = self
+            .complete_with_tools_and_finish(messages, options, tool_results)
+            .await?;
+        if !content.is_empty() {
+            on_delta(&content);
+        }
+        Ok((content, tool_calls, finish))
+    }
+
+    /// `complete_streaming`, also reporting why the model stopped
+    async fn complete_streaming_with_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, TokenUsage, FinishReason)> {
+        let (content, usage) = self.complete_streaming(messages, options, on_delta).await?;
+        Ok((content, usage, FinishReason::Stop))
+    }
+}
+
+// Instead of using a trait object, we'll use an enum to handle different providers
+#[derive(Clone)]
+pub enum ApiClientEnum {
+    #[cfg(feature = "anthropic")]
+    Anthropic(Arc<crate::anthropic::AnthropicClient>),
+    #[cfg(feature = "openai")]
+    OpenAI(Arc<crate::openai::OpenAIClient>),
+    #[cfg(feature = "ollama")]
+    Ollama(Arc<crate::ollama::OllamaClient>),
+    #[cfg(feature = "gemini")]
+    Gemini(Arc<crate::gemini::GeminiClient>),
+    #[cfg(feature = "openrouter")]
+    OpenRouter(Arc<crate::openrouter::OpenRouterClient>),
+    CustomMock(Arc<dyn ApiClient>),
+}
+
+impl ApiClientEnum {
+    fn client(&self) -> &dyn ApiClient {
+        match self {
+            #[cfg(feature = "anthropic")]
+            Self::Anthropic(client) => client.as_ref(),
+            #[cfg(feature = "openai")]
+            Self::OpenAI(client) => client.as_ref(),
+            #[cfg(feature = "ollama")]
+            Self::Ollama(client) => client.as_ref(),
+            #[cfg(feature = "gemini")]
+            Self::Gemini(client) => client.as_ref(),
+            #[cfg(feature = "openrouter")]
+            Self::OpenRouter(client) => client.as_ref(),
+            Self::CustomMock(client) => client.as_ref(),
+        }
+    }
+
+    /// Complete without tools. Answers cut off at the output token limit are
+    /// continued and stitched together, up to `MAX_CONTINUATIONS` times.
+    #[allow(dead_code)]
+    pub async fn complete(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+    ) -> Result<String> {
+        let client = self.client();
+        let (mut content, mut finish) = client
+            .complete_with_finish(messages.clone(), options.clone())
+            .await?;
+
+        let mut continuations = 0;
+        while finish.is_truncated() && continuations < MAX_CONTINUATIONS {
+            let (more, more_finish) = client
+                .complete_with_finish(continuation_messages(&messages, &content), options.clone())
+                .await?;
+            content = stitch_continuation(&content, &more);
+            finish = more_finish;
+            continuations += 1;
+        }
+        Ok(content)
+    }
+
+    /// Complete with tools. Text answers cut off at the output token limit are
+    /// continued like `complete`; the continuation requests carry no tool results
+    /// because the conversation already holds them.
+    pub async fn complete_with_tools(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+    ) -> Result<(String, Option<Vec<ToolCall>>)> {
+        let client = self.client();
+        let (mut content, mut tool_calls, mut finish) = client
+            .complete_with_tools_and_finish(messages.clone(), options.clone(), tool_results)
+            .await?;
+
+        let mut continuations = 0;
+        while finish.is_truncated() && tool_calls.is_none() && continuations < MAX_CONTINUATIONS {
+            let (more, more_tool_calls, more_finish) = client
+                .complete_with_tools_and_finish(
+                    continuation_messages(&messages, &content),
+                    options.clone(),
+                    None,
+                )
+                .await?;
+            content = stitch_continuation(&content, &more);
+            tool_calls = more_tool_calls;
+            finish = more_finish;
+            continuations += 1;
+        }
+        Ok((content, tool_calls))
+    }
+
+    /// Complete without tools, streaming text to `on_delta`. Continuations stream
+    /// straight after the cut-off text; since that text has already been shown,
+    /// repeated overlaps are kept rather than stitched away.
+    pub async fn complete_streaming(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, TokenUsage)> {
+        let client = self.client();
+        let (mut content, mut usage, mut finish) = client
+            .complete_streaming_with_finish(messages.clone(), options.clone(), &mut *on_delta)
+            .await?;
+
+        let mut continuations = 0;
+        while finish.is_truncated() && continuations < MAX_CONTINUATIONS {
+            let (more, more_usage, more_finish) = client
+                .complete_streaming_with_finish(
+                    continuation_messages(&messages, &content),
+                    options.clone(),
+                    &mut *on_delta,
+                )
+                .await?;
+            content.push_str(&more);
+            usage.input_tokens += more_usage.input_tokens;
+            usage.output_tokens += more_usage.output_tokens;
+            finish = more_finish;
+            continuations += 1;
+        }
+        Ok((content, usage))
+    }
+
+    /// Complete with tools, streaming text to `on_delta`. Continuations stream
+    /// straight after the cut-off text, like `complete_streaming`.
+    pub async fn complete_with_tools_streaming(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, Option<Vec<ToolCall>>)> {
+        let client = self.client();
+        let (mut content, mut tool_calls, mut finish) = client
+            .complete_with_tools_streaming_and_finish(
+                messages.clone(),
+                options.clone(),
+                tool_results,
+                &mut *on_delta,
+            )
+            .await?;
+
+        let mut continuations = 0;
+        while finish.is_truncated() && tool_calls.is_none() && continuations < MAX_CONTINUATIONS {
+            let (more, more_tool_calls, more_finish) = client
+                .complete_with_tools_streaming_and_finish(
+                    continuation_messages(&messages, &content),
+                    options.clone(),
+                    None,
+                    &mut *on_delta,
+                )
+                .await?;
+            content.push_str(&more);
+            tool_calls = more_tool_calls;
+            finish = more_finish;
+            continuations += 1;
+        }
+        Ok((content, tool_calls))
+    }
+
+    pub fn custom_for_testing(client: Arc<dyn ApiClient>) -> Self {
+        Self::CustomMock(client)
+    }
+}
+
+pub type DynApiClient = ApiClientEnum;
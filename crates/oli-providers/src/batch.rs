@@ -0,0 +1,147 @@
+use crate::api_client::{CompletionOptions, Message};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// How often the poller checks on a batch by default
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// Both providers process batches within 24 hours
+const DEFAULT_POLL_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// One completion request submitted as part of a batch
+#[derive(Debug, Clone)]
+pub struct BatchRequest {
+    /// Caller-chosen id used to match results to requests
+    pub custom_id: String,
+    pub messages: Vec<Message>,
+    pub options: CompletionOptions,
+}
+
+impl BatchRequest {
+    pub fn new(custom_id: impl Into<String>, messages: Vec<Message>) -> Self {
+        Self {
+            custom_id: custom_id.into(),
+            messages,
+            options: CompletionOptions::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: CompletionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Whether the request can be batched. Requests offering tools need a round
+    /// trip per tool call, so only plain completions are eligible.
+    pub fn is_eligible(&self) -> bool {
+        self.options.tools.is_none() && !self.options.tool_choice.requires_tool()
+    }
+}
+
+/// Processing state of a submitted batch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    InProgress,
+    /// Finished processing; results can be retrieved
+    Completed,
+    /// The batch as a whole failed, expired or was cancelled
+    Failed,
+}
+
+/// Outcome of one request in a finished batch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchResult {
+    pub custom_id: String,
+    pub content: Option<String>,
+    pub error: Option<String>,
+}
+
+impl BatchResult {
+    pub fn success(custom_id: impl Into<String>, content: String) -> Self {
+        Self {
+            custom_id: custom_id.into(),
+            content: Some(content),
+            error: None,
+        }
+    }
+
+    pub fn failure(custom_id: impl Into<String>, error: String) -> Self {
+        Self {
+            custom_id: custom_id.into(),
+            content: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// Provider batch endpoints, which process requests asynchronously at half the
+/// usual price. Suited to pipeline and CI usage where latency doesn't matter.
+#[async_trait]
+pub trait BatchApi: Send + Sync {
+    /// Submit eligible requests and return the provider's batch id
+    async fn submit_batch(&self, requests: Vec<BatchRequest>) -> Result<String>;
+
+    async fn batch_status(&self, batch_id: &str) -> Result<BatchStatus>;
+
+    /// Results of a completed batch, one per request
+    async fn batch_results(&self, batch_id: &str) -> Result<Vec<BatchResult>>;
+}
+
+/// Waits for a submitted batch to finish and retrieves its results
+#[derive(Debug, Clone, Copy)]
+pub struct BatchPoller {
+    interval: Duration,
+    timeout: Duration,
+}
+
+impl Default for BatchPoller {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BatchPoller {
+    pub fn new() -> Self {
+        Self {
+            interval: DEFAULT_POLL_INTERVAL,
+            timeout: DEFAULT_POLL_TIMEOUT,
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Poll until the batch completes, then return its results
+    pub async fn wait(&self, api: &dyn BatchApi, batch_id: &str) -> Result<Vec<BatchResult>> {
+        let started = Instant::now();
+
+        loop {
+            match api.batch_status(batch_id).await? {
+                BatchStatus::Completed => return api.batch_results(batch_id).await,
+                BatchStatus::Failed => {
+                    return Err(anyhow::anyhow!(
+                        "Batch {batch_id} failed, expired or was cancelled"
+                    ))
+                }
+                BatchStatus::InProgress => {}
+            }
+
+            if started.elapsed() >= self.timeout {
+                return Err(anyhow::anyhow!(
+                    "Batch {batch_id} did not complete within {}s",
+                    self.timeout.as_secs()
+                ));
+            }
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+}
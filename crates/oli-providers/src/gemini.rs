@@ -1,7 +1,12 @@
-use crate::apis::api_client::{ApiClient, CompletionOptions, Message, ToolCall, ToolResult};
-use crate::app::logger::{format_log_with_color, LogLevel};
+use crate::api_client::{
+    without_images, ApiClient, CompletionOptions, FinishReason, Message, ToolCall, ToolChoice,
+    ToolResult,
+};
 use crate::errors::AppError;
-use crate::models::GEMINI_MODEL_NAME;
+use crate::ledger::{record_request, RequestUsage};
+use crate::log::{format_log_with_color, LogLevel};
+use crate::retry::{send_with_retry, RetryPolicy};
+use crate::streaming::{DeltaHandler, TokenUsage};
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use rand;
@@ -11,7 +16,10 @@ use reqwest::Response;
 use serde::{Deserialize, Serialize};
 use serde_json::{self, json, Value};
 use std::env;
-use std::time::Duration;
+use std::time::Instant;
+
+/// Model used when none is given
+pub const GEMINI_MODEL_NAME: &str = "gemini-2.5-pro-exp-03-25";
 
 // Gemini API models
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,6 +83,40 @@ struct GeminiRequest {
     tools: Option<Vec<GeminiTool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     generation_config: Option<GeminiGenerationConfig>,
+    #[serde(rename = "toolConfig", skip_serializing_if = "Option::is_none")]
+    tool_config: Option<GeminiToolConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GeminiToolConfig {
+    #[serde(rename = "functionCallingConfig")]
+    function_calling_config: GeminiFunctionCallingConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiFunctionCallingConfig {
+    mode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    allowed_function_names: Option<Vec<String>>,
+}
+
+impl GeminiToolConfig {
+    /// Gemini has no setting for parallel function calls, so only the choice is mapped
+    fn from_choice(choice: &ToolChoice) -> Self {
+        let (mode, allowed_function_names) = match choice {
+            ToolChoice::Auto => ("AUTO", None),
+            ToolChoice::Any => ("ANY", None),
+            ToolChoice::None => ("NONE", None),
+            ToolChoice::Tool(name) => ("ANY", Some(vec![name.clone()])),
+        };
+        Self {
+            function_calling_config: GeminiFunctionCallingConfig {
+                mode: mode.to_string(),
+                allowed_function_names,
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -87,11 +129,39 @@ struct GeminiTool {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct GeminiResponse {
     candidates: Vec<GeminiCandidate>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "usageMetadata", skip_serializing_if = "Option::is_none")]
     usage_metadata: Option<GeminiUsageMetadata>,
 }
 
+impl GeminiResponse {
+    fn finish_reason(&self) -> FinishReason {
+        self.candidates
+            .first()
+            .and_then(|candidate| candidate.finish_reason.as_deref())
+            .map(FinishReason::from_provider)
+            .unwrap_or_default()
+    }
+
+    /// Usage for the request ledger, estimated when the response doesn't report it.
+    /// Prompt tokens include cached ones.
+    fn request_usage(&self, messages: &[Message], content: &str) -> RequestUsage {
+        match &self.usage_metadata {
+            Some(usage) => RequestUsage {
+                input_tokens: usage
+                    .prompt_token_count
+                    .saturating_sub(usage.cached_content_token_count),
+                output_tokens: usage.candidates_token_count,
+                cache_creation_tokens: 0,
+                cache_read_tokens: usage.cached_content_token_count,
+                estimated: false,
+            },
+            None => RequestUsage::estimated(TokenUsage::estimate(messages, content)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct GeminiCandidate {
     content: GeminiMessage,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -103,98 +173,38 @@ struct GeminiCandidate {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct GeminiUsageMetadata {
+    #[serde(default)]
     prompt_token_count: u32,
+    #[serde(default)]
     candidates_token_count: u32,
+    #[serde(default)]
+    cached_content_token_count: u32,
+    #[serde(default)]
     total_token_count: u32,
 }
 
 pub struct GeminiClient {
     client: ReqwestClient,
-    #[allow(dead_code)] // Keep the model field for consistency with other API clients
     model: String,
     api_base: String,
 }
 
 impl GeminiClient {
-    // Helper function to send a request with retry logic for overload errors
-    async fn send_request_with_retry<T: serde::Serialize + Clone>(
+    // Send a request, retrying rate limits, overloaded servers and network errors
+    async fn send_request_with_retry<T: serde::Serialize>(
         &self,
         request: &T,
+        retry: &RetryPolicy,
     ) -> Result<Response> {
-        // Implement retry logic with exponential backoff
-        let mut retries = 0;
-        let max_retries = 3; // Maximum number of retries
-        let mut delay_ms = 1000; // Start with 1 second delay
-
-        loop {
-            let result = self.client.post(&self.api_base).json(request).send().await;
-
-            match result {
-                Ok(resp) => {
-                    // If response is 429 (rate limit) or 503 (overloaded), retry
-                    if resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
-                        || resp.status() == reqwest::StatusCode::SERVICE_UNAVAILABLE
-                    {
-                        if retries >= max_retries {
-                            // Return the last error response if max retries reached
-                            return Ok(resp);
-                        }
-
-                        // Extract retry-after header if available before cloning for the error body
-                        let retry_after = resp
-                            .headers()
-                            .get("retry-after")
-                            .and_then(|val| val.to_str().ok())
-                            .and_then(|val| val.parse::<u64>().ok())
-                            .unwrap_or(delay_ms);
-
-                        // Clone the response for logging
-                        let error_body = resp.text().await.unwrap_or_default();
-                        eprintln!(
-                            "{}",
-                            format_log_with_color(
-                                LogLevel::Warning,
-                                &format!("Gemini API rate limited or overloaded: {error_body}")
-                            )
-                        );
-
-                        // Exponential backoff with jitter
-                        let jitter = rand::random::<u64>() % 500;
-                        let sleep_duration = Duration::from_millis(retry_after + jitter);
-
-                        // Sleep and retry
-                        tokio::time::sleep(sleep_duration).await;
-
-                        // Increase delay for next retry
-                        delay_ms = (delay_ms * 2).min(10000); // Cap at 10 seconds
-                        retries += 1;
-                        continue;
-                    }
-
-                    // For other status codes, return the response
-                    return Ok(resp);
-                }
-                Err(e) => {
-                    // For network errors, also use retry logic
-                    if retries >= max_retries {
-                        return Err(AppError::NetworkError(format!(
-                            "Failed to send request to Gemini after {retries} retries: {e}"
-                        ))
-                        .into());
-                    }
-
-                    // Exponential backoff with jitter
-                    let jitter = rand::random::<u64>() % 500;
-                    let sleep_duration = Duration::from_millis(delay_ms + jitter);
-                    tokio::time::sleep(sleep_duration).await;
-
-                    // Increase delay for next retry
-                    delay_ms = (delay_ms * 2).min(10000); // Cap at 10 seconds
-                    retries += 1;
-                }
-            }
-        }
+        send_with_retry("Gemini", retry, || {
+            self.client.post(&self.api_base).json(request).send()
+        })
+        .await
+        .map_err(|e| {
+            AppError::NetworkError(format!("Failed to send request to Gemini: {e}")).into()
+        })
     }
 
     pub fn new(model: Option<String>) -> Result<Self> {
@@ -241,6 +251,12 @@ impl GeminiClient {
         })
     }
 
+    /// Send requests to another `generateContent` URL, e.g. a proxy or a test server
+    pub fn with_api_base(mut self, api_base: String) -> Self {
+        self.api_base = api_base;
+        self
+    }
+
     fn convert_messages(&self, messages: Vec<Message>) -> Vec<GeminiMessage> {
         let mut gemini_messages = Vec::new();
         let mut current_role = String::new();
@@ -268,7 +284,7 @@ impl GeminiClient {
 
             // Add content
             current_parts.push(GeminiContent::Text {
-                text: msg.content.clone(),
+                text: without_images(&msg.content),
             });
         }
 
@@ -285,7 +301,7 @@ impl GeminiClient {
 
     fn convert_tool_definitions(
         &self,
-        tools: Vec<crate::apis::api_client::ToolDefinition>,
+        tools: Vec<crate::api_client::ToolDefinition>,
     ) -> Vec<GeminiTool> {
         let function_declarations = tools
             .into_iter()
@@ -401,7 +417,7 @@ impl GeminiClient {
                     function_response: GeminiFunctionResponse {
                         name: "function".to_string(), // Generic name
                         response: json!({
-                            "content": result.output,
+                            "content": without_images(&result.output),
                             "tool_call_id": result.tool_call_id
                         }),
                     },
@@ -500,6 +516,42 @@ impl GeminiClient {
 #[async_trait]
 impl ApiClient for GeminiClient {
     async fn complete(&self, messages: Vec<Message>, options: CompletionOptions) -> Result<String> {
+        Ok(self.complete_with_finish(messages, options).await?.0)
+    }
+
+    async fn complete_with_tools(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+    ) -> Result<(String, Option<Vec<ToolCall>>)> {
+        let (content, tool_calls, _) = self
+            .complete_with_tools_and_finish(messages, options, tool_results)
+            .await?;
+        Ok((content, tool_calls))
+    }
+
+    // Gemini answers arrive whole, as one delta
+    async fn complete_streaming_with_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, TokenUsage, FinishReason)> {
+        let usage_messages = messages.clone();
+        let (content, finish) = self.complete_with_finish(messages, options).await?;
+        on_delta(&content);
+        let usage = TokenUsage::estimate(&usage_messages, &content);
+        Ok((content, usage, finish))
+    }
+
+    async fn complete_with_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+    ) -> Result<(String, FinishReason)> {
+        let started = Instant::now();
+        let usage_messages = messages.clone();
         // Convert messages to Gemini format
         let contents = self.convert_messages(messages);
 
@@ -519,10 +571,13 @@ impl ApiClient for GeminiClient {
             contents,
             tools: None,
             generation_config: Some(generation_config),
+            tool_config: None,
         };
 
         // Send request with retry logic
-        let response = self.send_request_with_retry(&request).await?;
+        let response = self
+            .send_request_with_retry(&request, &options.retry)
+            .await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -566,15 +621,19 @@ impl ApiClient for GeminiClient {
         // Extract text content
         let content = self.extract_text_content(&gemini_response)?;
 
-        Ok(content)
+        let usage = gemini_response.request_usage(&usage_messages, &content);
+        record_request("gemini", &self.model, usage, started);
+        Ok((content, gemini_response.finish_reason()))
     }
 
-    async fn complete_with_tools(
+    async fn complete_with_tools_and_finish(
         &self,
         messages: Vec<Message>,
         options: CompletionOptions,
         tool_results: Option<Vec<ToolResult>>,
-    ) -> Result<(String, Option<Vec<ToolCall>>)> {
+    ) -> Result<(String, Option<Vec<ToolCall>>, FinishReason)> {
+        let started = Instant::now();
+        let usage_messages = messages.clone();
         // Convert messages to Gemini format
         let mut contents = self.convert_messages(messages);
 
@@ -600,15 +659,19 @@ impl ApiClient for GeminiClient {
             contents,
             generation_config: Some(generation_config),
             tools: None,
+            tool_config: None,
         };
 
         // Add tools if specified
         if let Some(tools) = options.tools {
             request.tools = Some(self.convert_tool_definitions(tools));
+            request.tool_config = Some(GeminiToolConfig::from_choice(&options.tool_choice));
         }
 
         // Send request with retry logic
-        let response = self.send_request_with_retry(&request).await?;
+        let response = self
+            .send_request_with_retry(&request, &options.retry)
+            .await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -657,7 +720,9 @@ impl ApiClient for GeminiClient {
         // Extract tool calls
         let tool_calls = self.extract_tool_calls(&gemini_response);
 
-        Ok((content, tool_calls))
+        let usage = gemini_response.request_usage(&usage_messages, &content);
+        record_request("gemini", &self.model, usage, started);
+        Ok((content, tool_calls, gemini_response.finish_reason()))
     }
 }
 
@@ -665,7 +730,7 @@ impl ApiClient for GeminiClient {
 // Unit tests within the same module to test private implementation details
 mod tests {
     use super::*;
-    use crate::apis::api_client::{Message, ToolDefinition, ToolResult};
+    use crate::api_client::{Message, ToolDefinition, ToolResult};
     use serde_json::json;
 
     #[test]
@@ -1063,4 +1128,19 @@ mod tests {
         let result = client.extract_text_content(&response);
         assert!(result.is_err(), "Should return error for empty candidates");
     }
+
+    #[test]
+    fn test_tool_config_from_choice() {
+        let config = GeminiToolConfig::from_choice(&ToolChoice::Tool("Glob".to_string()));
+        assert_eq!(
+            serde_json::to_value(config).unwrap(),
+            json!({"functionCallingConfig": {"mode": "ANY", "allowedFunctionNames": ["Glob"]}})
+        );
+
+        let config = GeminiToolConfig::from_choice(&ToolChoice::None);
+        assert_eq!(
+            serde_json::to_value(config).unwrap(),
+            json!({"functionCallingConfig": {"mode": "NONE"}})
+        );
+    }
 }
@@ -0,0 +1,276 @@
+use crate::provider::LLMProvider;
+use serde::Serialize;
+use std::time::Duration;
+
+/// How long the validation request may take
+const VALIDATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What is wrong with a provider's API key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyProblem {
+    Missing,
+    /// Doesn't look like a key for the provider, e.g. pasted with quotes or for another provider
+    Malformed,
+    /// Rejected by the provider (401/403)
+    Unauthorized,
+    /// Accepted, but the account is out of credit or rate limited (402/429)
+    OutOfQuota,
+    /// The provider couldn't be reached, so the key wasn't checked
+    Unreachable,
+}
+
+/// Result of checking the API key for a cloud provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyDiagnostics {
+    pub provider: String,
+    /// Environment variable the key is read from
+    pub env_var: String,
+    pub problem: Option<KeyProblem>,
+    /// Status code or error message behind the problem
+    pub detail: Option<String>,
+    /// What to do about the problem, in order
+    pub next_steps: Vec<String>,
+}
+
+impl KeyDiagnostics {
+    pub fn new(
+        provider: &LLMProvider,
+        problem: Option<KeyProblem>,
+        detail: Option<String>,
+    ) -> Self {
+        let env_var = key_env_var(provider).unwrap_or_default();
+        let next_steps = problem
+            .map(|problem| next_steps(provider, problem))
+            .unwrap_or_default();
+        Self {
+            provider: provider_name(provider).to_string(),
+            env_var: env_var.to_string(),
+            problem,
+            detail,
+            next_steps,
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.problem.is_none()
+    }
+
+    /// Human-readable summary for the setup screen or an error message
+    pub fn report(&self) -> String {
+        let Some(problem) = self.problem else {
+            return format!("{} API key diagnostics:\n  ✓ Key accepted", self.provider);
+        };
+
+        let summary = match problem {
+            KeyProblem::Missing => format!("No API key found in {}", self.env_var),
+            KeyProblem::Malformed => {
+                format!("{} doesn't look like a {} key", self.env_var, self.provider)
+            }
+            KeyProblem::Unauthorized => {
+                format!("{} rejected the key in {}", self.provider, self.env_var)
+            }
+            KeyProblem::OutOfQuota => format!(
+                "{} accepted the key but the account is out of quota",
+                self.provider
+            ),
+            KeyProblem::Unreachable => {
+                format!("Could not reach {} to check the key", self.provider)
+            }
+        };
+        let detail = self
+            .detail
+            .as_ref()
+            .map(|detail| format!(" ({detail})"))
+            .unwrap_or_default();
+
+        let mut lines = vec![
+            format!("{} API key diagnostics:", self.provider),
+            format!("  ✗ {summary}{detail}"),
+        ];
+        lines.extend(self.next_steps.iter().map(|step| format!("  → {step}")));
+        lines.join("\n")
+    }
+}
+
+/// Environment variable holding the provider's API key; local models need none
+pub fn key_env_var(provider: &LLMProvider) -> Option<&'static str> {
+    match provider {
+        LLMProvider::Anthropic => Some("ANTHROPIC_API_KEY"),
+        LLMProvider::OpenAI => Some("OPENAI_API_KEY"),
+        LLMProvider::Gemini => Some("GEMINI_API_KEY"),
+        LLMProvider::OpenRouter => Some("OPENROUTER_API_KEY"),
+        LLMProvider::AzureOpenAI => Some("AZURE_OPENAI_API_KEY"),
+        LLMProvider::OpenAICompatible => Some("OPENAI_COMPATIBLE_API_KEY"),
+        LLMProvider::Ollama => None,
+    }
+}
+
+fn provider_name(provider: &LLMProvider) -> &'static str {
+    match provider {
+        LLMProvider::Anthropic => "Anthropic",
+        LLMProvider::OpenAI => "OpenAI",
+        LLMProvider::Gemini => "Google",
+        LLMProvider::OpenRouter => "OpenRouter",
+        LLMProvider::AzureOpenAI => "Azure OpenAI",
+        LLMProvider::OpenAICompatible => "OpenAI-compatible API",
+        LLMProvider::Ollama => "Ollama",
+    }
+}
+
+/// Page where the provider's keys are created and billing is managed
+fn console_url(provider: &LLMProvider) -> &'static str {
+    match provider {
+        LLMProvider::Anthropic => "https://console.anthropic.com/settings/keys",
+        LLMProvider::OpenAI => "https://platform.openai.com/api-keys",
+        LLMProvider::Gemini => "https://aistudio.google.com/app/apikey",
+        LLMProvider::OpenRouter => "https://openrouter.ai/settings/keys",
+        // Keys are under "Keys and Endpoint" of the resource
+        LLMProvider::AzureOpenAI => "https://portal.azure.com",
+        // Keys come from whichever service the base URL points at
+        LLMProvider::OpenAICompatible => "your provider's console",
+        LLMProvider::Ollama => "https://ollama.com",
+    }
+}
+
+/// Specific next steps for a key problem
+pub fn next_steps(provider: &LLMProvider, problem: KeyProblem) -> Vec<String> {
+    let url = console_url(provider);
+    let store = "Run /key <your key> to check a key and store it for future sessions".to_string();
+    match problem {
+        KeyProblem::Missing => vec![format!("Create a key at {url}"), store],
+        KeyProblem::Malformed => vec![
+            "Copy the key again without quotes, spaces or line breaks".to_string(),
+            format!(
+                "Make sure it's a {} key from {url}",
+                provider_name(provider)
+            ),
+            store,
+        ],
+        KeyProblem::Unauthorized => vec![
+            format!("The key may have been revoked or mistyped; create a new one at {url}"),
+            store,
+        ],
+        KeyProblem::OutOfQuota => vec![
+            format!("Check the account's credit and rate limits at {url}"),
+            "Wait a minute if you were rate limited, or switch to another model with /model"
+                .to_string(),
+        ],
+        KeyProblem::Unreachable => vec![
+            "Check your network connection and any proxy settings".to_string(),
+            "Local models via Ollama work offline".to_string(),
+        ],
+    }
+}
+
+/// Catch keys that can't be valid without asking the provider
+pub fn check_key_format(provider: &LLMProvider, key: &str) -> Option<KeyProblem> {
+    // Local servers such as vLLM and LM Studio take no key
+    if key.is_empty() && *provider != LLMProvider::OpenAICompatible {
+        return Some(KeyProblem::Missing);
+    }
+    if key
+        .chars()
+        .any(|c| c.is_whitespace() || c == '"' || c == '\'')
+    {
+        return Some(KeyProblem::Malformed);
+    }
+
+    let well_formed = match provider {
+        LLMProvider::Anthropic => key.starts_with("sk-ant-"),
+        // Anthropic and OpenRouter keys also start with "sk-", so rule them out explicitly
+        LLMProvider::OpenAI => {
+            key.starts_with("sk-") && !key.starts_with("sk-ant-") && !key.starts_with("sk-or-")
+        }
+        LLMProvider::Gemini => key.starts_with("AIza") && key.len() == 39,
+        LLMProvider::OpenRouter => key.starts_with("sk-or-"),
+        // Azure keys have no prefix, just at least 32 letters and digits
+        LLMProvider::AzureOpenAI => {
+            key.len() >= 32 && key.chars().all(|c| c.is_ascii_alphanumeric())
+        }
+        LLMProvider::OpenAICompatible | LLMProvider::Ollama => true,
+    };
+    (!well_formed).then_some(KeyProblem::Malformed)
+}
+
+/// Map the status of the validation request to a key problem
+pub fn classify_status(provider: &LLMProvider, status: u16) -> Option<KeyProblem> {
+    match status {
+        200..=299 => None,
+        401 | 403 => Some(KeyProblem::Unauthorized),
+        // Google answers invalid keys with 400 rather than 401
+        400 if *provider == LLMProvider::Gemini => Some(KeyProblem::Unauthorized),
+        402 | 429 => Some(KeyProblem::OutOfQuota),
+        _ => Some(KeyProblem::Unreachable),
+    }
+}
+
+/// Check a key's format, then validate it with a request that costs no tokens:
+/// listing the provider's models
+pub async fn diagnose_api_key(provider: &LLMProvider, key: &str) -> KeyDiagnostics {
+    if let Some(problem) = check_key_format(provider, key) {
+        return KeyDiagnostics::new(provider, Some(problem), None);
+    }
+
+    let client = reqwest::Client::new();
+    let request = match provider {
+        LLMProvider::Anthropic => client
+            .get("https://api.anthropic.com/v1/models")
+            .header("x-api-key", key)
+            .header("anthropic-version", "2023-06-01"),
+        LLMProvider::OpenAI => client
+            .get("https://api.openai.com/v1/models")
+            .bearer_auth(key),
+        LLMProvider::Gemini => client
+            .get("https://generativelanguage.googleapis.com/v1beta/models")
+            .query(&[("key", key)]),
+        // The models list is public, so ask about the key itself
+        LLMProvider::OpenRouter => client
+            .get("https://openrouter.ai/api/v1/key")
+            .bearer_auth(key),
+        // Keys belong to a resource, so the request goes to its endpoint
+        LLMProvider::AzureOpenAI => {
+            let Ok(endpoint) = std::env::var("AZURE_OPENAI_ENDPOINT") else {
+                return KeyDiagnostics::new(
+                    provider,
+                    Some(KeyProblem::Unreachable),
+                    Some("AZURE_OPENAI_ENDPOINT is not set".to_string()),
+                );
+            };
+            client
+                .get(format!(
+                    "{}/openai/models",
+                    endpoint.trim().trim_end_matches('/')
+                ))
+                .query(&[("api-version", "2024-10-21")])
+                .header("api-key", key)
+        }
+        // Keys belong to whichever server the base URL points at
+        LLMProvider::OpenAICompatible => {
+            let Ok(base_url) = std::env::var("OPENAI_COMPATIBLE_BASE_URL") else {
+                return KeyDiagnostics::new(
+                    provider,
+                    Some(KeyProblem::Unreachable),
+                    Some("OPENAI_COMPATIBLE_BASE_URL is not set".to_string()),
+                );
+            };
+            let request = client.get(format!("{}/models", base_url.trim().trim_end_matches('/')));
+            if key.is_empty() {
+                request
+            } else {
+                request.bearer_auth(key)
+            }
+        }
+        LLMProvider::Ollama => return KeyDiagnostics::new(provider, None, None),
+    };
+
+    match request.timeout(VALIDATION_TIMEOUT).send().await {
+        Ok(response) => {
+            let status = response.status();
+            let problem = classify_status(provider, status.as_u16());
+            let detail = problem.map(|_| format!("HTTP {status}"));
+            KeyDiagnostics::new(provider, problem, detail)
+        }
+        Err(e) => KeyDiagnostics::new(provider, Some(KeyProblem::Unreachable), Some(e.to_string())),
+    }
+}
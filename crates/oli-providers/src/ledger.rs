@@ -0,0 +1,458 @@
+use crate::log::{format_log_with_color, LogLevel};
+use crate::streaming::TokenUsage;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::future::Future;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+/// Environment variable overriding the request ledger location
+pub const REQUEST_LEDGER_ENV: &str = "OLI_REQUEST_LEDGER";
+
+/// Columns of the CSV export, in order
+pub const CSV_HEADER: &str = "timestamp,provider,model,input_tokens,output_tokens,\
+cache_creation_tokens,cache_read_tokens,latency_ms,cost_usd,estimated";
+
+/// Tokens billed for one request. Input tokens exclude the cached ones, which are
+/// priced separately.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub cache_creation_tokens: u32,
+    pub cache_read_tokens: u32,
+    /// The provider didn't report usage, so the tokens were estimated
+    pub estimated: bool,
+}
+
+impl RequestUsage {
+    /// Usage from an Anthropic `usage` object
+    pub fn from_anthropic(usage: &Value) -> Self {
+        Self {
+            input_tokens: count(usage, "input_tokens"),
+            output_tokens: count(usage, "output_tokens"),
+            cache_creation_tokens: count(usage, "cache_creation_input_tokens"),
+            cache_read_tokens: count(usage, "cache_read_input_tokens"),
+            estimated: false,
+        }
+    }
+
+    /// Usage from an OpenAI `usage` object, whose prompt tokens include cached ones
+    pub fn from_openai(usage: &Value) -> Self {
+        let cached = count(&usage["prompt_tokens_details"], "cached_tokens");
+        Self {
+            input_tokens: count(usage, "prompt_tokens").saturating_sub(cached),
+            output_tokens: count(usage, "completion_tokens"),
+            cache_creation_tokens: 0,
+            cache_read_tokens: cached,
+            estimated: false,
+        }
+    }
+
+    /// Usage estimated from the request and answer text
+    pub fn estimated(usage: TokenUsage) -> Self {
+        Self {
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            estimated: true,
+            ..Self::default()
+        }
+    }
+}
+
+fn count(usage: &Value, field: &str) -> u32 {
+    usage.get(field).and_then(Value::as_u64).unwrap_or(0) as u32
+}
+
+/// List price of a model in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+    pub cache_write: f64,
+    pub cache_read: f64,
+}
+
+impl ModelPrice {
+    const fn new(input: f64, output: f64, cache_write: f64, cache_read: f64) -> Self {
+        Self {
+            input,
+            output,
+            cache_write,
+            cache_read,
+        }
+    }
+
+    /// Cost in USD of a request with this usage
+    pub fn cost(&self, usage: &RequestUsage) -> f64 {
+        (usage.input_tokens as f64 * self.input
+            + usage.output_tokens as f64 * self.output
+            + usage.cache_creation_tokens as f64 * self.cache_write
+            + usage.cache_read_tokens as f64 * self.cache_read)
+            / 1_000_000.0
+    }
+}
+
+/// Prices by provider and a fragment of the model name, most specific first
+const PRICES: &[(&str, &str, ModelPrice)] = &[
+    ("anthropic", "opus", ModelPrice::new(15.0, 75.0, 18.75, 1.5)),
+    ("anthropic", "sonnet", ModelPrice::new(3.0, 15.0, 3.75, 0.3)),
+    (
+        "anthropic",
+        "3-5-haiku",
+        ModelPrice::new(0.8, 4.0, 1.0, 0.08),
+    ),
+    ("anthropic", "haiku", ModelPrice::new(0.25, 1.25, 0.3, 0.03)),
+    (
+        "openai",
+        "gpt-4o-mini",
+        ModelPrice::new(0.15, 0.6, 0.0, 0.075),
+    ),
+    ("openai", "gpt-4o", ModelPrice::new(2.5, 10.0, 0.0, 1.25)),
+    (
+        "openai",
+        "gpt-4.1-nano",
+        ModelPrice::new(0.1, 0.4, 0.0, 0.025),
+    ),
+    (
+        "openai",
+        "gpt-4.1-mini",
+        ModelPrice::new(0.4, 1.6, 0.0, 0.1),
+    ),
+    ("openai", "gpt-4.1", ModelPrice::new(2.0, 8.0, 0.0, 0.5)),
+    ("openai", "o4-mini", ModelPrice::new(1.1, 4.4, 0.0, 0.275)),
+    ("openai", "o3", ModelPrice::new(2.0, 8.0, 0.0, 0.5)),
+    ("gemini", "2.5-flash", ModelPrice::new(0.3, 2.5, 0.0, 0.075)),
+    ("gemini", "2.5-pro", ModelPrice::new(1.25, 10.0, 0.0, 0.31)),
+    ("gemini", "2.0-flash", ModelPrice::new(0.1, 0.4, 0.0, 0.025)),
+];
+
+/// The price of a model, if known. Local Ollama models are free.
+pub fn model_price(provider: &str, model: &str) -> Option<ModelPrice> {
+    if provider == "ollama" {
+        return Some(ModelPrice::new(0.0, 0.0, 0.0, 0.0));
+    }
+    let model = model.to_lowercase();
+    PRICES
+        .iter()
+        .find(|(priced, fragment, _)| *priced == provider && model.contains(fragment))
+        .map(|(_, _, price)| *price)
+}
+
+/// One request sent to a provider, as written to the ledger
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RequestRecord {
+    /// RFC 3339 timestamp, in UTC, of when the response arrived
+    pub timestamp: String,
+    pub provider: String,
+    pub model: String,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    #[serde(default)]
+    pub cache_creation_tokens: u32,
+    #[serde(default)]
+    pub cache_read_tokens: u32,
+    pub latency_ms: u64,
+    /// None when the model's price isn't known
+    pub cost_usd: Option<f64>,
+    #[serde(default)]
+    pub estimated: bool,
+}
+
+impl RequestRecord {
+    pub fn new(provider: &str, model: &str, usage: RequestUsage, latency_ms: u64) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            provider: provider.to_string(),
+            model: model.to_string(),
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_creation_tokens: usage.cache_creation_tokens,
+            cache_read_tokens: usage.cache_read_tokens,
+            latency_ms,
+            cost_usd: model_price(provider, model).map(|price| price.cost(&usage)),
+            estimated: usage.estimated,
+        }
+    }
+
+    /// The record as a CSV row, in the order of `CSV_HEADER`
+    pub fn csv_row(&self) -> String {
+        [
+            csv_field(&self.timestamp),
+            csv_field(&self.provider),
+            csv_field(&self.model),
+            self.input_tokens.to_string(),
+            self.output_tokens.to_string(),
+            self.cache_creation_tokens.to_string(),
+            self.cache_read_tokens.to_string(),
+            self.latency_ms.to_string(),
+            self.cost_usd
+                .map(|cost| format!("{cost:.6}"))
+                .unwrap_or_default(),
+            self.estimated.to_string(),
+        ]
+        .join(",")
+    }
+}
+
+/// Quote a CSV field when it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The records as CSV, with a header row
+pub fn to_csv(records: &[RequestRecord]) -> String {
+    let mut csv = format!("{CSV_HEADER}\n");
+    for record in records {
+        csv.push_str(&record.csv_row());
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Sums over a set of requests
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LedgerTotals {
+    pub requests: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cost_usd: f64,
+    /// Requests to models without a known price, left out of the cost
+    pub unpriced_requests: usize,
+}
+
+impl LedgerTotals {
+    pub fn of(records: &[RequestRecord]) -> Self {
+        records.iter().fold(Self::default(), |mut totals, record| {
+            totals.requests += 1;
+            totals.input_tokens += record.input_tokens as u64;
+            totals.output_tokens += record.output_tokens as u64;
+            totals.cache_creation_tokens += record.cache_creation_tokens as u64;
+            totals.cache_read_tokens += record.cache_read_tokens as u64;
+            match record.cost_usd {
+                Some(cost) => totals.cost_usd += cost,
+                None => totals.unpriced_requests += 1,
+            }
+            totals
+        })
+    }
+
+    /// Input tokens including the cached ones
+    pub fn total_input_tokens(&self) -> u64 {
+        self.input_tokens + self.cache_creation_tokens + self.cache_read_tokens
+    }
+}
+
+tokio::task_local! {
+    static CURRENT_METER: UsageMeter;
+}
+
+/// Called with each request a meter records and the meter's totals after it
+pub type UsageListener = Arc<dyn Fn(&RequestRecord, &LedgerTotals) + Send + Sync>;
+
+/// Collects the requests made while a future runs, e.g. to report the real token
+/// usage and cost of one agent turn. Requests are collected whether or not a
+/// ledger is enabled.
+#[derive(Clone, Default)]
+pub struct UsageMeter {
+    records: Arc<Mutex<Vec<RequestRecord>>>,
+    listener: Option<UsageListener>,
+}
+
+impl std::fmt::Debug for UsageMeter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UsageMeter")
+            .field("records", &self.records)
+            .field("listener", &self.listener.is_some())
+            .finish()
+    }
+}
+
+impl UsageMeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `listener` as each request is recorded, e.g. to report usage live
+    pub fn with_listener(
+        mut self,
+        listener: impl Fn(&RequestRecord, &LedgerTotals) + Send + Sync + 'static,
+    ) -> Self {
+        self.listener = Some(Arc::new(listener));
+        self
+    }
+
+    /// The meter the running task records to, if any. Pass it to `scope` on
+    /// spawned tasks so their requests are counted too.
+    pub fn current() -> Option<Self> {
+        CURRENT_METER.try_with(Self::clone).ok()
+    }
+
+    /// Run `future`, recording the requests it makes to this meter
+    pub fn scope<F: Future>(&self, future: F) -> impl Future<Output = F::Output> {
+        CURRENT_METER.scope(self.clone(), future)
+    }
+
+    pub fn record(&self, record: RequestRecord) {
+        let totals = {
+            let mut records = self.records.lock().unwrap();
+            records.push(record.clone());
+            self.listener.as_ref().map(|_| LedgerTotals::of(&records))
+        };
+        // Call the listener without holding the lock so it can read the meter
+        if let (Some(listener), Some(totals)) = (&self.listener, totals) {
+            listener(&record, &totals);
+        }
+    }
+
+    /// Add the requests recorded by another meter
+    pub fn extend(&self, other: &UsageMeter) {
+        let other = other.records();
+        self.records.lock().unwrap().extend(other);
+    }
+
+    pub fn records(&self) -> Vec<RequestRecord> {
+        self.records.lock().unwrap().clone()
+    }
+
+    pub fn totals(&self) -> LedgerTotals {
+        LedgerTotals::of(&self.records.lock().unwrap())
+    }
+
+    /// Totals per provider, e.g. "anthropic" and "ollama"
+    pub fn totals_by_provider(&self) -> BTreeMap<String, LedgerTotals> {
+        let mut by_provider: BTreeMap<String, Vec<RequestRecord>> = BTreeMap::new();
+        for record in self.records.lock().unwrap().iter() {
+            by_provider
+                .entry(record.provider.clone())
+                .or_default()
+                .push(record.clone());
+        }
+        by_provider
+            .into_iter()
+            .map(|(provider, records)| (provider, LedgerTotals::of(&records)))
+            .collect()
+    }
+}
+
+/// Append-only JSON lines log of every request sent to a provider, with its token
+/// usage, latency and cost, for expense reporting
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestLedger {
+    path: PathBuf,
+}
+
+static ACTIVE_LEDGER: OnceLock<RequestLedger> = OnceLock::new();
+
+impl RequestLedger {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// The configured ledger, `~/.oli/requests.jsonl` unless overridden by
+    /// `OLI_REQUEST_LEDGER`
+    pub fn from_env() -> Option<Self> {
+        match std::env::var(REQUEST_LEDGER_ENV) {
+            Ok(path) if !path.trim().is_empty() => Some(Self::new(PathBuf::from(path))),
+            _ => dirs::home_dir().map(|home| Self::new(home.join(".oli").join("requests.jsonl"))),
+        }
+    }
+
+    /// Make this the ledger provider clients record their requests to. Until a
+    /// ledger is enabled, requests aren't recorded.
+    pub fn enable(self) {
+        let _ = ACTIVE_LEDGER.set(self);
+    }
+
+    /// The enabled ledger, if any
+    pub fn active() -> Option<&'static Self> {
+        ACTIVE_LEDGER.get()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a record, creating the file and its directory if needed
+    pub fn record(&self, record: &RequestRecord) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open request ledger {}", self.path.display()))?;
+
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        Ok(())
+    }
+
+    /// Records made at or after `since`, an RFC 3339 timestamp or a date such as
+    /// `2025-06-01`, oldest first. Malformed lines are skipped.
+    pub fn read_since(&self, since: Option<&str>) -> Result<Vec<RequestRecord>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let file = std::fs::File::open(&self.path)
+            .with_context(|| format!("Failed to open request ledger {}", self.path.display()))?;
+        // Timestamps are all UTC, so they compare as strings
+        Ok(BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<RequestRecord>(&line).ok())
+            .filter(|record| since.is_none_or(|since| record.timestamp.as_str() >= since))
+            .collect())
+    }
+
+    /// The most recent records, oldest first
+    pub fn recent(&self, limit: usize) -> Result<Vec<RequestRecord>> {
+        let records = self.read_since(None)?;
+        let skip = records.len().saturating_sub(limit);
+        Ok(records.into_iter().skip(skip).collect())
+    }
+
+    /// Write the records made at or after `since` to `dest` as CSV, returning how
+    /// many were written
+    pub fn export_csv(&self, dest: &Path, since: Option<&str>) -> Result<usize> {
+        let records = self.read_since(since)?;
+        std::fs::write(dest, to_csv(&records))
+            .with_context(|| format!("Failed to write {}", dest.display()))?;
+        Ok(records.len())
+    }
+}
+
+/// Record a request that started at `started` to the current usage meter and the
+/// enabled ledger. Failing to record it is logged and never fails the request.
+pub fn record_request(provider: &str, model: &str, usage: RequestUsage, started: Instant) {
+    let record = RequestRecord::new(provider, model, usage, started.elapsed().as_millis() as u64);
+    if let Some(meter) = UsageMeter::current() {
+        meter.record(record.clone());
+    }
+    let Some(ledger) = RequestLedger::active() else {
+        return;
+    };
+    if let Err(e) = ledger.record(&record) {
+        eprintln!(
+            "{}",
+            format_log_with_color(
+                LogLevel::Warning,
+                &format!("Failed to record request in the ledger: {e}")
+            )
+        );
+    }
+}
@@ -0,0 +1,40 @@
+//! Model provider clients for oli.
+//!
+//! Every client implements [`api_client::ApiClient`], and streams through the
+//! [`streaming::DeltaHandler`] callback, so callers can switch providers without
+//! changing how they complete or stream. Each provider is behind a feature of the
+//! same name, all enabled by default:
+//!
+//! ```toml
+//! oli-providers = { version = "0.1", default-features = false, features = ["anthropic"] }
+//! ```
+
+#[cfg(feature = "anthropic")]
+pub mod anthropic;
+pub mod api_client;
+pub mod batch;
+#[cfg(any(
+    feature = "anthropic",
+    feature = "openai",
+    feature = "ollama",
+    feature = "gemini"
+))]
+mod errors;
+#[cfg(feature = "gemini")]
+pub mod gemini;
+pub mod key_check;
+pub mod ledger;
+pub mod log;
+#[cfg(feature = "ollama")]
+pub mod ollama;
+#[cfg(feature = "openai")]
+pub mod openai;
+#[cfg(feature = "openrouter")]
+pub mod openrouter;
+pub mod provider;
+pub mod retry;
+pub mod streaming;
+pub mod tokenizer;
+
+pub use api_client::{ApiClient, ApiClientEnum, CompletionOptions, Message};
+pub use provider::LLMProvider;
@@ -1,8 +1,12 @@
-use crate::apis::api_client::{
-    ApiClient, CompletionOptions, Message, ToolCall, ToolDefinition, ToolResult,
+use crate::api_client::{
+    without_images, ApiClient, CompletionOptions, FinishReason, Message, ToolCall, ToolChoice,
+    ToolDefinition, ToolResult,
 };
-use crate::app::logger::{format_log_with_color, LogLevel};
 use crate::errors::AppError;
+use crate::ledger::{record_request, RequestUsage};
+use crate::log::{format_log_with_color, LogLevel};
+use crate::retry::{send_with_retry, RetryPolicy};
+use crate::streaming::{DeltaHandler, TokenUsage};
 use anyhow::Result;
 use async_trait::async_trait;
 use rand;
@@ -10,7 +14,7 @@ use rand;
 use reqwest::Client as ReqwestClient;
 use serde::{Deserialize, Serialize};
 use serde_json::{self, json, Value};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 // Ollama API Types
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -137,12 +141,55 @@ struct OllamaResponse {
     total_duration: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     load_duration: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prompt_eval_count: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     prompt_eval_duration: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     eval_count: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     eval_duration: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    done_reason: Option<String>,
+}
+
+impl OllamaResponse {
+    fn finish_reason(&self) -> FinishReason {
+        self.done_reason
+            .as_deref()
+            .map(FinishReason::from_provider)
+            .unwrap_or_default()
+    }
+
+    /// Usage for the request ledger, estimated when Ollama doesn't report the counts
+    fn request_usage(&self, messages: &[Message]) -> RequestUsage {
+        match (self.prompt_eval_count, self.eval_count) {
+            (Some(input), Some(output)) => RequestUsage {
+                input_tokens: input as u32,
+                output_tokens: output as u32,
+                ..RequestUsage::default()
+            },
+            _ => RequestUsage::estimated(TokenUsage::estimate(messages, &self.message.content)),
+        }
+    }
+}
+
+/// One line of a streamed chat response. The last one has `done` set, with the
+/// finish reason and token counts.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct OllamaStreamChunk {
+    #[serde(default)]
+    message: Option<OllamaMessage>,
+    #[serde(default)]
+    done: bool,
+    #[serde(default)]
+    done_reason: Option<String>,
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+    #[serde(default)]
+    eval_count: Option<u64>,
+    #[serde(default)]
+    error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -243,7 +290,7 @@ impl OllamaClient {
                 // Convert standard messages to Ollama format
                 OllamaMessage {
                     role: msg.role,
-                    content: msg.content,
+                    content: without_images(&msg.content),
                     tool_calls: None,
                     tool_call_id: None,
                 }
@@ -265,6 +312,162 @@ impl OllamaClient {
             .collect()
     }
 
+    /// A chat request for `messages` without tools
+    fn chat_request(
+        &self,
+        messages: Vec<Message>,
+        options: &CompletionOptions,
+        stream: bool,
+    ) -> OllamaRequest {
+        OllamaRequest {
+            model: self.model.clone(),
+            messages: self.convert_messages(messages),
+            stream,
+            temperature: options.temperature,
+            top_p: options.top_p,
+            options: None,
+            format: if options.json_schema.is_some() {
+                Some("json".to_string())
+            } else {
+                None
+            },
+            tools: None,
+        }
+    }
+
+    /// A chat request offering the tools in `options`, with any tool results
+    /// appended to the conversation
+    fn tool_request(
+        &self,
+        messages: Vec<Message>,
+        options: &CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+        stream: bool,
+    ) -> OllamaRequest {
+        let mut request = self.chat_request(messages, options, stream);
+
+        // Add tool results if provided
+        if let Some(results) = tool_results {
+            for result in results {
+                request.messages.push(OllamaMessage {
+                    role: "tool".to_string(),
+                    content: without_images(&result.output),
+                    tool_calls: None,
+                    tool_call_id: Some(result.tool_call_id),
+                });
+            }
+        }
+
+        // Add tools if provided. Ollama can't be told to call a tool, but tools are
+        // left out when none may be called.
+        if let Some(tools) = options
+            .tools
+            .clone()
+            .filter(|_| options.tool_choice != ToolChoice::None)
+        {
+            let converted_tools = self.convert_tool_definitions(tools);
+            request.tools = Some(converted_tools);
+        }
+        request
+    }
+
+    /// Send a chat request with `stream` set and read the answer as it arrives, one
+    /// JSON object per line, passing each piece of text to `on_delta`. Returns the
+    /// whole answer, with the finish reason and token counts from the final line.
+    async fn stream_chat(
+        &self,
+        request: &OllamaRequest,
+        retry: &RetryPolicy,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<OllamaResponse> {
+        let url = format!("{}/api/chat", self.api_base);
+        let send = || self.client.post(&url).json(request).send();
+        let mut response = send_with_retry("Ollama", retry, send)
+            .await
+            .map_err(|e| AppError::NetworkError(self.describe_request_error(&e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AppError::NetworkError(format!(
+                "Ollama API error: {status} - {error_text}"
+            ))
+            .into());
+        }
+
+        let mut answer = OllamaResponse {
+            model: self.model.clone(),
+            created_at: String::new(),
+            message: OllamaMessage {
+                role: "assistant".to_string(),
+                content: String::new(),
+                tool_calls: None,
+                tool_call_id: None,
+            },
+            done: false,
+            total_duration: None,
+            load_duration: None,
+            prompt_eval_count: None,
+            prompt_eval_duration: None,
+            eval_count: None,
+            eval_duration: None,
+            done_reason: None,
+        };
+        let mut read_line = |line: &[u8]| -> Result<()> {
+            // Lines are only decoded once complete, so characters split across
+            // chunks come through intact
+            let Ok(chunk) = serde_json::from_slice::<OllamaStreamChunk>(line) else {
+                return Ok(());
+            };
+            if let Some(error) = chunk.error {
+                return Err(AppError::LLMError(format!("Ollama API error: {error}")).into());
+            }
+            if let Some(message) = chunk.message {
+                if !message.content.is_empty() {
+                    on_delta(&message.content);
+                    answer.message.content.push_str(&message.content);
+                }
+                if let Some(calls) = message.tool_calls {
+                    answer
+                        .message
+                        .tool_calls
+                        .get_or_insert_with(Vec::new)
+                        .extend(calls);
+                }
+            }
+            if chunk.done {
+                answer.done = true;
+                answer.done_reason = chunk.done_reason;
+                answer.prompt_eval_count = chunk.prompt_eval_count;
+                answer.eval_count = chunk.eval_count;
+            }
+            Ok(())
+        };
+
+        let mut buffer = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| AppError::NetworkError(self.describe_request_error(&e)))?
+        {
+            buffer.extend_from_slice(&chunk);
+            while let Some(end) = buffer.iter().position(|&byte| byte == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                read_line(&line)?;
+            }
+        }
+        // The final line may not end with a newline
+        read_line(&buffer)?;
+
+        if !answer.done {
+            return Err(AppError::NetworkError(
+                "Ollama stopped streaming before the answer was done".to_string(),
+            )
+            .into());
+        }
+        Ok(answer)
+    }
+
     pub async fn list_models(&self) -> Result<Vec<OllamaModelInfo>> {
         let url = format!("{}/api/tags", self.api_base);
 
@@ -298,17 +501,7 @@ impl OllamaClient {
         let response = match self.client.get(&url).send().await {
             Ok(resp) => resp,
             Err(e) => {
-                let error_msg = if e.is_connect() {
-                    // Connection failed - likely Ollama is not running
-                    format!("Failed to connect to Ollama server at {}. Make sure 'ollama serve' is running. Error: {}",
-                            self.api_base, e)
-                } else if e.is_timeout() {
-                    format!("Request to Ollama timed out: {e}")
-                } else if e.is_request() {
-                    format!("Failed to build request to Ollama: {e}")
-                } else {
-                    format!("Failed to send request to Ollama: {e}")
-                };
+                let error_msg = self.describe_request_error(&e);
 
                 eprintln!("{}", format_log_with_color(LogLevel::Error, &error_msg));
                 return Err(AppError::NetworkError(error_msg).into());
@@ -360,12 +553,362 @@ impl OllamaClient {
             }
         }
     }
+
+    /// Download `model` into Ollama, passing each progress update to `on_progress`
+    /// as it arrives. Fails with Ollama's message when the model doesn't exist.
+    pub async fn pull_model(
+        &self,
+        model: &str,
+        mut on_progress: impl FnMut(&OllamaPullProgress),
+    ) -> Result<()> {
+        let url = format!("{}/api/pull", self.api_base);
+        let mut response = self
+            .client
+            .post(&url)
+            .json(&json!({ "model": model, "name": model, "stream": true }))
+            .send()
+            .await
+            .map_err(|e| AppError::NetworkError(self.describe_request_error(&e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AppError::NetworkError(format!(
+                "Ollama could not pull '{model}': {status} - {error_text}"
+            ))
+            .into());
+        }
+
+        // Progress arrives as one JSON object per line
+        let mut buffer = Vec::new();
+        let mut succeeded = false;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| AppError::NetworkError(self.describe_request_error(&e)))?
+        {
+            buffer.extend_from_slice(&chunk);
+            while let Some(end) = buffer.iter().position(|&byte| byte == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                let Ok(progress) = serde_json::from_slice::<OllamaPullProgress>(&line) else {
+                    continue;
+                };
+                if let Some(error) = &progress.error {
+                    return Err(AppError::LLMError(format!(
+                        "Ollama could not pull '{model}': {error}"
+                    ))
+                    .into());
+                }
+                succeeded |= progress.status == "success";
+                on_progress(&progress);
+            }
+        }
+
+        if !succeeded {
+            return Err(AppError::NetworkError(format!(
+                "Ollama stopped pulling '{model}' before it finished"
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Explain a failed request to any Ollama endpoint
+    fn describe_request_error(&self, e: &reqwest::Error) -> String {
+        if e.is_connect() {
+            format!(
+                "Failed to connect to Ollama server at {}. Make sure 'ollama serve' is running or set OLLAMA_API_BASE. Error: {e}",
+                self.api_base
+            )
+        } else if e.is_timeout() {
+            format!("Request to Ollama timed out: {e}")
+        } else if e.is_builder() {
+            format!("Failed to build HTTP request: {e} - This may be due to a configuration issue with reqwest")
+        } else if e.is_request() {
+            format!("Failed to build request to Ollama: {e}")
+        } else {
+            format!("Failed to send request to Ollama: {e}")
+        }
+    }
+
+    /// Check that the Ollama server is reachable, recent enough for tool calling
+    /// and has the selected model, reporting each problem with a suggested fix
+    pub async fn diagnose(&self) -> OllamaDiagnostics {
+        let mut diagnostics = OllamaDiagnostics {
+            api_base: self.api_base.clone(),
+            model: (!self.model.is_empty()).then(|| self.model.clone()),
+            ..Default::default()
+        };
+
+        // Reachability and version
+        let version_url = format!("{}/api/version", self.api_base);
+        match self
+            .client
+            .get(&version_url)
+            .timeout(DIAGNOSTIC_TIMEOUT)
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => {
+                diagnostics.reachable = true;
+                diagnostics.version = response
+                    .json::<Value>()
+                    .await
+                    .ok()
+                    .and_then(|body| body["version"].as_str().map(str::to_string));
+            }
+            Ok(response) => {
+                diagnostics.problems.push(format!(
+                    "{} answered {} — is this an Ollama server? Check OLLAMA_API_BASE.",
+                    self.api_base,
+                    response.status()
+                ));
+                return diagnostics;
+            }
+            Err(e) => {
+                let problem = if e.is_timeout() {
+                    format!("Ollama at {} did not respond in time.", self.api_base)
+                } else {
+                    format!(
+                        "Cannot reach Ollama at {}. Start it with 'ollama serve' or set OLLAMA_API_BASE.",
+                        self.api_base
+                    )
+                };
+                diagnostics.problems.push(problem);
+                return diagnostics;
+            }
+        }
+
+        if let Some(version) = &diagnostics.version {
+            if !ollama_version_at_least(version, MIN_OLLAMA_VERSION) {
+                diagnostics.problems.push(format!(
+                    "Ollama {version} is too old for tool calling; upgrade to {MIN_OLLAMA_VERSION} or newer."
+                ));
+            }
+        }
+
+        // Selected model
+        let Some(model) = diagnostics.model.clone() else {
+            return diagnostics;
+        };
+        let available = match self.list_models().await {
+            Ok(models) => models
+                .iter()
+                .any(|m| m.name == model || m.name == format!("{model}:latest")),
+            Err(e) => {
+                diagnostics
+                    .problems
+                    .push(format!("Could not list Ollama models: {e}"));
+                return diagnostics;
+            }
+        };
+        diagnostics.model_available = Some(available);
+
+        if !available {
+            diagnostics.problems.push(format!(
+                "Model '{model}' is not installed. Pull it with 'ollama pull {model}'."
+            ));
+            diagnostics.pull_command = Some(format!("ollama pull {model}"));
+            return diagnostics;
+        }
+
+        // Context length of the selected model
+        let show_url = format!("{}/api/show", self.api_base);
+        if let Ok(response) = self
+            .client
+            .post(&show_url)
+            .timeout(DIAGNOSTIC_TIMEOUT)
+            .json(&json!({ "model": model, "name": model }))
+            .send()
+            .await
+        {
+            if let Ok(body) = response.json::<Value>().await {
+                diagnostics.context_length = body["model_info"].as_object().and_then(|info| {
+                    info.iter()
+                        .find(|(key, _)| key.ends_with(".context_length"))
+                        .and_then(|(_, value)| value.as_u64())
+                });
+            }
+        }
+
+        diagnostics
+    }
+}
+
+/// One progress update of `ollama pull`, e.g. "pulling manifest", or a layer's
+/// download with its size and the bytes received so far
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OllamaPullProgress {
+    #[serde(default)]
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl OllamaPullProgress {
+    /// Share of the current layer downloaded, between 0 and 1, when its size is known
+    pub fn fraction(&self) -> Option<f64> {
+        match (self.completed, self.total) {
+            (Some(completed), Some(total)) if total > 0 => {
+                Some((completed as f64 / total as f64).min(1.0))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Oldest Ollama release with tool calling, which agent mode relies on
+pub const MIN_OLLAMA_VERSION: &str = "0.3.0";
+
+/// How long each diagnostic request may take
+const DIAGNOSTIC_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Result of checking the Ollama setup for the selected model
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OllamaDiagnostics {
+    pub api_base: String,
+    pub reachable: bool,
+    pub version: Option<String>,
+    pub model: Option<String>,
+    /// Whether the selected model is installed, when the server could be asked
+    pub model_available: Option<bool>,
+    /// Context window of the selected model in tokens
+    pub context_length: Option<u64>,
+    /// Problems found, each with a suggested fix
+    pub problems: Vec<String>,
+    /// Command that installs the selected model when it is missing
+    pub pull_command: Option<String>,
+}
+
+impl OllamaDiagnostics {
+    pub fn is_healthy(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Human-readable summary for the setup screen or an error message
+    pub fn report(&self) -> String {
+        let mut lines = vec![format!("Ollama diagnostics for {}:", self.api_base)];
+
+        if self.reachable {
+            lines.push(format!(
+                "  ✓ Server reachable (version {})",
+                self.version.as_deref().unwrap_or("unknown")
+            ));
+        }
+        if let (Some(model), Some(true)) = (&self.model, self.model_available) {
+            let context = self
+                .context_length
+                .map(|tokens| format!(", context length {tokens} tokens"))
+                .unwrap_or_default();
+            lines.push(format!("  ✓ Model '{model}' installed{context}"));
+        }
+        for problem in &self.problems {
+            lines.push(format!("  ✗ {problem}"));
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// Compare dotted Ollama versions such as "0.5.7", ignoring any pre-release suffix
+pub fn ollama_version_at_least(version: &str, minimum: &str) -> bool {
+    let parse = |v: &str| -> Vec<u64> {
+        let mut parts: Vec<u64> = v
+            .trim_start_matches('v')
+            .split(['.', '-', '+'])
+            .take(3)
+            .map(|part| part.parse().unwrap_or(0))
+            .collect();
+        parts.resize(3, 0);
+        parts
+    };
+    parse(version) >= parse(minimum)
 }
 
 #[async_trait]
 impl ApiClient for OllamaClient {
     async fn complete(&self, messages: Vec<Message>, options: CompletionOptions) -> Result<String> {
-        let ollama_messages = self.convert_messages(messages);
+        Ok(self.complete_with_finish(messages, options).await?.0)
+    }
+
+    async fn complete_with_tools(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+    ) -> Result<(String, Option<Vec<ToolCall>>)> {
+        let (content, tool_calls, _) = self
+            .complete_with_tools_and_finish(messages, options, tool_results)
+            .await?;
+        Ok((content, tool_calls))
+    }
+
+    async fn complete_streaming_with_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, TokenUsage, FinishReason)> {
+        let started = Instant::now();
+        let usage_messages = messages.clone();
+        if self.model.is_empty() {
+            return Err(anyhow::anyhow!("No model specified for Ollama request"));
+        }
+
+        let request = self.chat_request(messages, &options, true);
+        let answer = self.stream_chat(&request, &options.retry, on_delta).await?;
+
+        let finish = answer.finish_reason();
+        let billed = answer.request_usage(&usage_messages);
+        record_request("ollama", &self.model, billed, started);
+        let usage = TokenUsage {
+            input_tokens: billed.input_tokens,
+            output_tokens: billed.output_tokens,
+        };
+        Ok((answer.message.content, usage, finish))
+    }
+
+    async fn complete_with_tools_streaming_and_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, Option<Vec<ToolCall>>, FinishReason)> {
+        let started = Instant::now();
+        let usage_messages = messages.clone();
+        if self.model.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Model name is empty. Please select a valid Ollama model."
+            ));
+        }
+
+        let request = self.tool_request(messages, &options, tool_results, true);
+        let answer = self.stream_chat(&request, &options.retry, on_delta).await?;
+
+        let finish = answer.finish_reason();
+        let usage = answer.request_usage(&usage_messages);
+        record_request("ollama", &self.model, usage, started);
+        Ok(tool_outcome(
+            answer.message.content,
+            answer.message.tool_calls,
+            finish,
+        ))
+    }
+
+    async fn complete_with_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+    ) -> Result<(String, FinishReason)> {
+        let started = Instant::now();
+        let usage_messages = messages.clone();
 
         // Make sure we have a valid model name
         if self.model.is_empty() {
@@ -373,20 +916,7 @@ impl ApiClient for OllamaClient {
         }
         let model_name = self.model.clone();
 
-        let request = OllamaRequest {
-            model: model_name.clone(),
-            messages: ollama_messages,
-            stream: false,
-            temperature: options.temperature,
-            top_p: options.top_p,
-            options: None,
-            format: if options.json_schema.is_some() {
-                Some("json".to_string())
-            } else {
-                None
-            },
-            tools: None,
-        };
+        let request = self.chat_request(messages, &options, false);
 
         let url = format!("{}/api/chat", self.api_base);
 
@@ -413,22 +943,11 @@ impl ApiClient for OllamaClient {
         );
 
         // Use match to provide more detailed error handling
-        let response = match self.client.post(&url).json(&request).send().await {
+        let send = || self.client.post(&url).json(&request).send();
+        let response = match send_with_retry("Ollama", &options.retry, send).await {
             Ok(resp) => resp,
             Err(e) => {
-                let error_msg = if e.is_connect() {
-                    // Connection failed - likely Ollama is not running
-                    format!("Failed to connect to Ollama server at {}. Make sure 'ollama serve' is running. Error: {}",
-                        self.api_base, e)
-                } else if e.is_timeout() {
-                    format!("Request to Ollama timed out: {e}")
-                } else if e.is_request() {
-                    format!("Failed to build request to Ollama: {e}")
-                } else if e.is_builder() {
-                    format!("Failed to build HTTP request: {e} - This may be due to a configuration issue with reqwest")
-                } else {
-                    format!("Failed to send request to Ollama: {e}")
-                };
+                let error_msg = self.describe_request_error(&e);
 
                 eprintln!("{}", format_log_with_color(LogLevel::Error, &error_msg));
                 return Err(AppError::NetworkError(error_msg).into());
@@ -497,8 +1016,9 @@ impl ApiClient for OllamaClient {
                 );
 
                 // Log the response text for debugging (truncated to avoid excessive logging)
-                let preview = if response_text.len() > 100 {
-                    format!("{}... [truncated]", &response_text[..100])
+                let preview = if response_text.chars().count() > 100 {
+                    let start: String = response_text.chars().take(100).collect();
+                    format!("{start}... [truncated]")
                 } else {
                     response_text.clone()
                 };
@@ -549,9 +1069,14 @@ impl ApiClient for OllamaClient {
                                 done: value.get("done").and_then(|d| d.as_bool()).unwrap_or(true),
                                 total_duration: None,
                                 load_duration: None,
+                                prompt_eval_count: None,
                                 prompt_eval_duration: None,
                                 eval_count: None,
                                 eval_duration: None,
+                                done_reason: value
+                                    .get("done_reason")
+                                    .and_then(|r| r.as_str())
+                                    .map(str::to_string),
                             }
                         } else {
                             // If we didn't find a message, create a synthetic error response
@@ -579,15 +1104,20 @@ impl ApiClient for OllamaClient {
             }
         };
 
-        Ok(ollama_response.message.content)
+        let finish = ollama_response.finish_reason();
+        let usage = ollama_response.request_usage(&usage_messages);
+        record_request("ollama", &self.model, usage, started);
+        Ok((ollama_response.message.content, finish))
     }
 
-    async fn complete_with_tools(
+    async fn complete_with_tools_and_finish(
         &self,
         messages: Vec<Message>,
         options: CompletionOptions,
         tool_results: Option<Vec<ToolResult>>,
-    ) -> Result<(String, Option<Vec<ToolCall>>)> {
+    ) -> Result<(String, Option<Vec<ToolCall>>, FinishReason)> {
+        let started = Instant::now();
+        let usage_messages = messages.clone();
         // Ensure we have a valid model
         if self.model.is_empty() {
             return Err(anyhow::anyhow!(
@@ -596,42 +1126,7 @@ impl ApiClient for OllamaClient {
         }
         let model_name = self.model.clone();
 
-        // Convert messages to Ollama format
-        let mut ollama_messages = self.convert_messages(messages);
-
-        // Add tool results if provided
-        if let Some(results) = tool_results {
-            for result in results {
-                ollama_messages.push(OllamaMessage {
-                    role: "tool".to_string(),
-                    content: result.output,
-                    tool_calls: None,
-                    tool_call_id: Some(result.tool_call_id),
-                });
-            }
-        }
-
-        // Create the request payload
-        let mut request = OllamaRequest {
-            model: model_name.clone(),
-            messages: ollama_messages,
-            stream: false,
-            temperature: options.temperature,
-            top_p: options.top_p,
-            options: None,
-            format: if options.json_schema.is_some() {
-                Some("json".to_string())
-            } else {
-                None
-            },
-            tools: None,
-        };
-
-        // Add tools if provided
-        if let Some(tools) = options.tools {
-            let converted_tools = self.convert_tool_definitions(tools);
-            request.tools = Some(converted_tools);
-        }
+        let request = self.tool_request(messages, &options, tool_results, false);
 
         let url = format!("{}/api/chat", self.api_base);
 
@@ -659,22 +1154,11 @@ impl ApiClient for OllamaClient {
         );
 
         // Use match to provide more detailed error handling
-        let response = match self.client.post(&url).json(&request).send().await {
+        let send = || self.client.post(&url).json(&request).send();
+        let response = match send_with_retry("Ollama", &options.retry, send).await {
             Ok(resp) => resp,
             Err(e) => {
-                let error_msg = if e.is_connect() {
-                    // Connection failed - likely Ollama is not running
-                    format!("Failed to connect to Ollama server at {}. Make sure 'ollama serve' is running. Error: {}",
-                        self.api_base, e)
-                } else if e.is_timeout() {
-                    format!("Request to Ollama timed out: {e}")
-                } else if e.is_request() {
-                    format!("Failed to build request to Ollama: {e}")
-                } else if e.is_builder() {
-                    format!("Failed to build HTTP request: {e} - This may be due to a configuration issue with reqwest")
-                } else {
-                    format!("Failed to send request to Ollama: {e}")
-                };
+                let error_msg = self.describe_request_error(&e);
 
                 eprintln!("{}", format_log_with_color(LogLevel::Error, &error_msg));
                 return Err(AppError::NetworkError(error_msg).into());
@@ -743,8 +1227,9 @@ impl ApiClient for OllamaClient {
                 );
 
                 // Log the response text for debugging (truncated to avoid excessive logging)
-                let preview = if response_text.len() > 100 {
-                    format!("{}... [truncated]", &response_text[..100])
+                let preview = if response_text.chars().count() > 100 {
+                    let start: String = response_text.chars().take(100).collect();
+                    format!("{start}... [truncated]")
                 } else {
                     response_text.clone()
                 };
@@ -795,9 +1280,14 @@ impl ApiClient for OllamaClient {
                                 done: value.get("done").and_then(|d| d.as_bool()).unwrap_or(true),
                                 total_duration: None,
                                 load_duration: None,
+                                prompt_eval_count: None,
                                 prompt_eval_duration: None,
                                 eval_count: None,
                                 eval_duration: None,
+                                done_reason: value
+                                    .get("done_reason")
+                                    .and_then(|r| r.as_str())
+                                    .map(str::to_string),
                             }
                         } else {
                             // If we didn't find a message, create a synthetic error response
@@ -828,156 +1318,175 @@ impl ApiClient for OllamaClient {
         // Extract the content and tool calls from the response
         let content = ollama_response.message.content.clone();
 
-        // Check for tool calls in the response
-        if let Some(ollama_tool_calls) = ollama_response.message.tool_calls {
-            if !ollama_tool_calls.is_empty() {
-                eprintln!(
-                    "{}",
-                    format_log_with_color(
-                        LogLevel::Debug,
-                        &format!(
-                            "Found {} tool calls in Ollama response",
-                            ollama_tool_calls.len()
-                        )
-                    )
-                );
-
-                let tool_calls = ollama_tool_calls
-                    .iter()
-                    .map(|call| {
-                        // Parse arguments as JSON
-                        let arguments_result =
-                            serde_json::from_str::<Value>(&call.function.arguments);
-                        let arguments = match arguments_result {
-                            Ok(args) => args,
-                            Err(e) => {
-                                eprintln!(
-                                    "{}",
-                                    format_log_with_color(
-                                        LogLevel::Warning,
-                                        &format!("Failed to parse tool arguments as JSON: {e}. Using empty object instead.")
-                                    )
-                                );
-                                json!({})
-                            },
-                        };
-
-                        // Generate a random ID if one wasn't provided
-                        let id = if call.id.is_empty() {
-                            format!("ollama-tool-{}", rand::random::<u64>())
-                        } else {
-                            call.id.clone()
-                        };
-
-                        // Create a tool call
-                        ToolCall {
-                            id: Some(id),
-                            name: call.function.name.clone(),
-                            arguments,
-                        }
-                    })
-                    .collect::<Vec<_>>();
+        let finish = ollama_response.finish_reason();
+        let usage = ollama_response.request_usage(&usage_messages);
+        record_request("ollama", &self.model, usage, started);
 
-                return Ok((String::new(), Some(tool_calls)));
-            }
-        }
+        Ok(tool_outcome(
+            content,
+            ollama_response.message.tool_calls,
+            finish,
+        ))
+    }
+}
 
-        // Also try to check if the content itself contains a tool call in JSON format
-        // This handles cases where Ollama doesn't properly format its tool_calls field
-        // but still returns JSON in the content field that looks like a tool call
-        let content_str = content.trim();
-        if content_str.starts_with('{') && content_str.ends_with('}') {
+/// Tool calls in an Ollama answer, from its `tool_calls` or, for models that
+/// write them as JSON, from its text. Answers without any are returned as they are.
+fn tool_outcome(
+    content: String,
+    received: Option<Vec<OllamaToolCall>>,
+    finish: FinishReason,
+) -> (String, Option<Vec<ToolCall>>, FinishReason) {
+    // Check for tool calls in the response
+    if let Some(ollama_tool_calls) = received {
+        if !ollama_tool_calls.is_empty() {
             eprintln!(
                 "{}",
                 format_log_with_color(
                     LogLevel::Debug,
-                    "Content appears to be JSON, checking for tool calls"
+                    &format!(
+                        "Found {} tool calls in Ollama response",
+                        ollama_tool_calls.len()
+                    )
                 )
             );
 
-            if let Ok(json_value) = serde_json::from_str::<Value>(content_str) {
-                // Check for OpenAI style tool calls
-                if let Some(tool_calls) = json_value.get("tool_calls").and_then(|tc| tc.as_array())
-                {
-                    if !tool_calls.is_empty() {
-                        eprintln!(
-                            "{}",
-                            format_log_with_color(
-                                LogLevel::Debug,
-                                &format!(
-                                    "Found {} OpenAI-style tool calls in JSON content",
-                                    tool_calls.len()
+            let tool_calls = ollama_tool_calls
+                .iter()
+                .map(|call| {
+                    // Parse arguments as JSON
+                    let arguments_result =
+                        serde_json::from_str::<Value>(&call.function.arguments);
+                    let arguments = match arguments_result {
+                        Ok(args) => args,
+                        Err(e) => {
+                            eprintln!(
+                                "{}",
+                                format_log_with_color(
+                                    LogLevel::Warning,
+                                    &format!("Failed to parse tool arguments as JSON: {e}. Passing them on for repair.")
                                 )
-                            )
-                        );
-
-                        let calls = tool_calls
-                            .iter()
-                            .filter_map(|call| {
-                                let id = call.get("id").and_then(|id| id.as_str()).unwrap_or("");
-                                let function = call.get("function")?;
-                                let name = function.get("name")?.as_str()?;
-                                let arguments = function.get("arguments")?;
-
-                                let args_str = arguments.as_str().unwrap_or("{}");
-                                let args: Value =
-                                    serde_json::from_str(args_str).unwrap_or(json!({}));
-
-                                Some(ToolCall {
-                                    id: Some(id.to_string()),
-                                    name: name.to_string(),
-                                    arguments: args,
-                                })
-                            })
-                            .collect::<Vec<_>>();
+                            );
+                            Value::String(call.function.arguments.clone())
+                        },
+                    };
 
-                        if !calls.is_empty() {
-                            return Ok((String::new(), Some(calls)));
-                        }
+                    // Generate a random ID if one wasn't provided
+                    let id = if call.id.is_empty() {
+                        format!("ollama-tool-{}", rand::random::<u64>())
+                    } else {
+                        call.id.clone()
+                    };
+
+                    // Create a tool call
+                    ToolCall {
+                        id: Some(id),
+                        name: call.function.name.clone(),
+                        arguments,
                     }
-                }
+                })
+                .collect::<Vec<_>>();
 
-                // Check for the simpler/custom format that our old implementation expected
-                if let (Some(tool_name), Some(tool_args)) = (
-                    json_value.get("tool").and_then(|t| t.as_str()),
-                    json_value.get("args"),
-                ) {
+            return (String::new(), Some(tool_calls), FinishReason::ToolUse);
+        }
+    }
+
+    // Also try to check if the content itself contains a tool call in JSON format
+    // This handles cases where Ollama doesn't properly format its tool_calls field
+    // but still returns JSON in the content field that looks like a tool call
+    let content_str = content.trim();
+    if content_str.starts_with('{') && content_str.ends_with('}') {
+        eprintln!(
+            "{}",
+            format_log_with_color(
+                LogLevel::Debug,
+                "Content appears to be JSON, checking for tool calls"
+            )
+        );
+
+        if let Ok(json_value) = serde_json::from_str::<Value>(content_str) {
+            // Check for OpenAI style tool calls
+            if let Some(tool_calls) = json_value.get("tool_calls").and_then(|tc| tc.as_array()) {
+                if !tool_calls.is_empty() {
                     eprintln!(
                         "{}",
                         format_log_with_color(
                             LogLevel::Debug,
-                            &format!("Found simple tool call format with tool: {tool_name}")
+                            &format!(
+                                "Found {} OpenAI-style tool calls in JSON content",
+                                tool_calls.len()
+                            )
                         )
                     );
 
-                    let tool_call = ToolCall {
-                        id: Some(format!("ollama-tool-{}", rand::random::<u64>())),
-                        name: tool_name.to_string(),
-                        arguments: tool_args.clone(),
-                    };
+                    let calls = tool_calls
+                        .iter()
+                        .filter_map(|call| {
+                            let id = call.get("id").and_then(|id| id.as_str()).unwrap_or("");
+                            let function = call.get("function")?;
+                            let name = function.get("name")?.as_str()?;
+                            let arguments = function.get("arguments")?;
+
+                            let args = match arguments.as_str() {
+                                Some(args_str) => serde_json::from_str(args_str)
+                                    .unwrap_or_else(|_| arguments.clone()),
+                                None => arguments.clone(),
+                            };
 
-                    return Ok((String::new(), Some(vec![tool_call])));
+                            Some(ToolCall {
+                                id: Some(id.to_string()),
+                                name: name.to_string(),
+                                arguments: args,
+                            })
+                        })
+                        .collect::<Vec<_>>();
+
+                    if !calls.is_empty() {
+                        return (String::new(), Some(calls), FinishReason::ToolUse);
+                    }
                 }
             }
-        }
 
-        // If no tool calls were found, just return the content
-        eprintln!(
-            "{}",
-            format_log_with_color(
-                LogLevel::Debug,
-                "No tool calls found in response, returning content"
-            )
-        );
+            // Check for the simpler/custom format that our old implementation expected
+            if let (Some(tool_name), Some(tool_args)) = (
+                json_value.get("tool").and_then(|t| t.as_str()),
+                json_value.get("args"),
+            ) {
+                eprintln!(
+                    "{}",
+                    format_log_with_color(
+                        LogLevel::Debug,
+                        &format!("Found simple tool call format with tool: {tool_name}")
+                    )
+                );
+
+                let tool_call = ToolCall {
+                    id: Some(format!("ollama-tool-{}", rand::random::<u64>())),
+                    name: tool_name.to_string(),
+                    arguments: tool_args.clone(),
+                };
 
-        Ok((content, None))
+                return (String::new(), Some(vec![tool_call]), FinishReason::ToolUse);
+            }
+        }
     }
+
+    // If no tool calls were found, just return the content
+    eprintln!(
+        "{}",
+        format_log_with_color(
+            LogLevel::Debug,
+            "No tool calls found in response, returning content"
+        )
+    );
+
+    (content, None, finish)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::apis::api_client::{Message, ToolDefinition};
+    use crate::api_client::{Message, ToolDefinition};
     use serde_json::json;
 
     #[test]
@@ -0,0 +1,1444 @@
+use crate::api_client::{
+    split_images, ApiClient, CompletionOptions, FinishReason, Message, ToolCall, ToolChoice,
+    ToolDefinition, ToolResult,
+};
+use crate::batch::{BatchApi, BatchRequest, BatchResult, BatchStatus};
+use crate::errors::AppError;
+use crate::ledger::{record_request, RequestUsage};
+use crate::log::{format_log_with_color, LogLevel};
+use crate::retry::{send_with_retry, RetryPolicy};
+use crate::streaming::{read_sse_stream, DeltaHandler, TokenUsage};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::Client as ReqwestClient;
+use serde::{Deserialize, Serialize};
+use serde_json::{self, json, Value};
+use std::env;
+use std::time::Instant;
+
+/// Environment variable holding the Azure OpenAI resource's endpoint, e.g.
+/// `https://my-resource.openai.azure.com`
+pub const AZURE_OPENAI_ENDPOINT_ENV: &str = "AZURE_OPENAI_ENDPOINT";
+/// Environment variable holding the Azure OpenAI resource's key
+pub const AZURE_OPENAI_API_KEY_ENV: &str = "AZURE_OPENAI_API_KEY";
+/// Environment variable naming the deployment requests go to
+pub const AZURE_OPENAI_DEPLOYMENT_ENV: &str = "AZURE_OPENAI_DEPLOYMENT";
+/// Environment variable overriding the `api-version` of Azure OpenAI requests
+pub const AZURE_OPENAI_API_VERSION_ENV: &str = "AZURE_OPENAI_API_VERSION";
+/// Deployment used when none is named, the name Azure suggests for a GPT-4o deployment
+pub const AZURE_OPENAI_DEPLOYMENT: &str = "gpt-4o";
+/// Latest generally available Azure OpenAI API version
+pub const AZURE_OPENAI_API_VERSION: &str = "2024-10-21";
+
+/// Environment variable holding the base URL of an OpenAI-compatible API, e.g.
+/// `https://api.deepseek.com/v1` or `http://localhost:1234/v1` for LM Studio
+pub const OPENAI_COMPATIBLE_BASE_URL_ENV: &str = "OPENAI_COMPATIBLE_BASE_URL";
+/// Environment variable holding the OpenAI-compatible API's key, if it takes one
+pub const OPENAI_COMPATIBLE_API_KEY_ENV: &str = "OPENAI_COMPATIBLE_API_KEY";
+/// Environment variable naming the model of the OpenAI-compatible API
+pub const OPENAI_COMPATIBLE_MODEL_ENV: &str = "OPENAI_COMPATIBLE_MODEL";
+
+/// A model deployment in an Azure OpenAI resource. Azure serves OpenAI's chat
+/// completions API per deployment rather than per model, versioned by a query
+/// parameter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AzureDeployment {
+    /// The resource's endpoint, e.g. `https://my-resource.openai.azure.com`
+    pub endpoint: String,
+    pub deployment: String,
+    pub api_version: String,
+}
+
+impl AzureDeployment {
+    pub fn new(endpoint: &str, deployment: &str) -> Self {
+        Self {
+            endpoint: endpoint.trim().trim_end_matches('/').to_string(),
+            deployment: deployment.trim().to_string(),
+            api_version: AZURE_OPENAI_API_VERSION.to_string(),
+        }
+    }
+
+    pub fn with_api_version(mut self, api_version: &str) -> Self {
+        self.api_version = api_version.trim().to_string();
+        self
+    }
+
+    /// The deployment from `AZURE_OPENAI_ENDPOINT`, `deployment` or else
+    /// `AZURE_OPENAI_DEPLOYMENT`, and `AZURE_OPENAI_API_VERSION` if set
+    pub fn from_env(deployment: Option<String>) -> Result<Self> {
+        let endpoint = env::var(AZURE_OPENAI_ENDPOINT_ENV)
+            .ok()
+            .filter(|endpoint| !endpoint.trim().is_empty())
+            .with_context(|| format!("{AZURE_OPENAI_ENDPOINT_ENV} environment variable not set"))?;
+        let deployment = deployment
+            .or_else(|| env::var(AZURE_OPENAI_DEPLOYMENT_ENV).ok())
+            .filter(|deployment| !deployment.trim().is_empty())
+            .unwrap_or_else(|| AZURE_OPENAI_DEPLOYMENT.to_string());
+        let azure = Self::new(&endpoint, &deployment);
+        Ok(match env::var(AZURE_OPENAI_API_VERSION_ENV) {
+            Ok(version) if !version.trim().is_empty() => azure.with_api_version(&version),
+            _ => azure,
+        })
+    }
+
+    /// The deployment's chat completions endpoint, with the API version
+    pub fn chat_completions_url(&self) -> String {
+        format!(
+            "{}/openai/deployments/{}/chat/completions?api-version={}",
+            self.endpoint, self.deployment, self.api_version
+        )
+    }
+}
+
+// OpenAI API Types
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIFunction {
+    name: String,
+    description: String,
+    parameters: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAITool {
+    #[serde(rename = "type")]
+    tool_type: String,
+    function: OpenAIFunction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIFunctionCall {
+    name: String,
+    arguments: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIToolCall {
+    id: String,
+    #[serde(rename = "type")]
+    tool_type: String,
+    function: OpenAIFunctionCall,
+}
+
+/// A message's content: plain text, or text and images as parts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum OpenAIContent {
+    Text(String),
+    Parts(Vec<Value>),
+}
+
+impl OpenAIContent {
+    /// Text content, with images carried in it sent as image parts
+    fn from_text(text: String) -> Self {
+        let (remaining, images) = split_images(&text);
+        if images.is_empty() {
+            return Self::Text(text);
+        }
+        let mut parts = vec![json!({ "type": "text", "text": remaining })];
+        parts.extend(
+            images.iter().map(
+                |image| json!({ "type": "image_url", "image_url": { "url": image.data_url() } }),
+            ),
+        );
+        Self::Parts(parts)
+    }
+
+    /// The text of the content, without any images
+    fn text(&self) -> String {
+        match self {
+            Self::Text(text) => text.clone(),
+            Self::Parts(parts) => parts
+                .iter()
+                .filter_map(|part| part["text"].as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+impl PartialEq<str> for OpenAIContent {
+    fn eq(&self, other: &str) -> bool {
+        matches!(self, Self::Text(text) if text == other)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIMessage {
+    role: String,
+    content: Option<OpenAIContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_calls: Option<Vec<OpenAIToolCall>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIRequest {
+    model: String,
+    messages: Vec<OpenAIMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<OpenAITool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parallel_tool_calls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIResponseChoice {
+    index: usize,
+    message: OpenAIMessage,
+    finish_reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIResponse {
+    id: String,
+    object: String,
+    created: u64,
+    model: String,
+    choices: Vec<OpenAIResponseChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<Value>,
+}
+
+/// Parse tool call arguments as JSON, keeping unparseable arguments as text so
+/// the agent can repair them
+fn parse_tool_arguments(arguments: &str) -> Value {
+    serde_json::from_str(arguments).unwrap_or_else(|_| Value::String(arguments.to_string()))
+}
+
+/// OpenAI's `tool_choice` value for a tool choice
+fn openai_tool_choice(choice: &ToolChoice) -> Value {
+    match choice {
+        ToolChoice::Auto => json!("auto"),
+        ToolChoice::Any => json!("required"),
+        ToolChoice::None => json!("none"),
+        ToolChoice::Tool(name) => json!({ "type": "function", "function": { "name": name } }),
+    }
+}
+
+pub struct OpenAIClient {
+    client: ReqwestClient,
+    model: String,
+    api_base: String,
+    /// Provider requests are recorded under in the ledger
+    provider: &'static str,
+}
+
+// Helper methods
+impl OpenAIClient {
+    /// Returns the model name being used by this client
+    ///
+    /// Primarily used for testing purposes.
+    #[cfg(test)]
+    pub(crate) fn get_model_name(&self) -> &str {
+        &self.model
+    }
+}
+
+impl OpenAIClient {
+    pub fn new(model: Option<String>) -> Result<Self> {
+        // Try to get API key from environment
+        let api_key =
+            env::var("OPENAI_API_KEY").context("OPENAI_API_KEY environment variable not set")?;
+
+        Self::with_api_key(api_key, model)
+    }
+
+    pub fn with_api_key(api_key: String, model: Option<String>) -> Result<Self> {
+        // Create new client with appropriate headers
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {api_key}"))?,
+        );
+
+        let client = ReqwestClient::builder().default_headers(headers).build()?;
+
+        // Default to GPT-4o as the latest model with tooling capabilities
+        let model = model.unwrap_or_else(|| "gpt-4o".to_string());
+
+        Ok(Self {
+            client,
+            model,
+            api_base: "https://api.openai.com/v1/chat/completions".to_string(),
+            provider: "openai",
+        })
+    }
+
+    /// A client for a deployment in Azure OpenAI, which authenticates with an
+    /// `api-key` header rather than a bearer token. Requests are recorded under `azure`.
+    pub fn with_azure(api_key: String, azure: AzureDeployment) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert("api-key", HeaderValue::from_str(&api_key)?);
+        let client = ReqwestClient::builder().default_headers(headers).build()?;
+
+        Ok(Self {
+            client,
+            // Azure picks the model by deployment and ignores this, but the ledger records it
+            model: azure.deployment.clone(),
+            api_base: azure.chat_completions_url(),
+            provider: "azure",
+        })
+    }
+
+    /// A client for the Azure OpenAI deployment the environment configures, see
+    /// [`AzureDeployment::from_env`], with the key in `AZURE_OPENAI_API_KEY`
+    pub fn azure_from_env(deployment: Option<String>) -> Result<Self> {
+        let api_key = env::var(AZURE_OPENAI_API_KEY_ENV)
+            .with_context(|| format!("{AZURE_OPENAI_API_KEY_ENV} environment variable not set"))?;
+        Self::with_azure(api_key, AzureDeployment::from_env(deployment)?)
+    }
+
+    /// A client for another service with OpenAI's chat completions API, such as
+    /// DeepSeek, xAI, Together, vLLM or LM Studio. `base_url` is the API's root, e.g.
+    /// `https://api.x.ai/v1`; local servers usually take no key. Requests are
+    /// recorded under `openai_compatible`.
+    pub fn with_compatible(base_url: &str, api_key: Option<String>, model: String) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        if let Some(api_key) = api_key.filter(|key| !key.trim().is_empty()) {
+            headers.insert(
+                AUTHORIZATION,
+                HeaderValue::from_str(&format!("Bearer {}", api_key.trim()))?,
+            );
+        }
+        let client = ReqwestClient::builder().default_headers(headers).build()?;
+
+        let base_url = base_url.trim().trim_end_matches('/');
+        let api_base = if base_url.ends_with("/chat/completions") {
+            base_url.to_string()
+        } else {
+            format!("{base_url}/chat/completions")
+        };
+        Ok(Self {
+            client,
+            model,
+            api_base,
+            provider: "openai_compatible",
+        })
+    }
+
+    /// A client for the OpenAI-compatible API in `OPENAI_COMPATIBLE_BASE_URL`, with
+    /// `model` or else `OPENAI_COMPATIBLE_MODEL`, and `api_key` or else the key in
+    /// `OPENAI_COMPATIBLE_API_KEY` if set
+    pub fn compatible_from_env(model: Option<String>, api_key: Option<String>) -> Result<Self> {
+        let base_url = env::var(OPENAI_COMPATIBLE_BASE_URL_ENV)
+            .ok()
+            .filter(|url| !url.trim().is_empty())
+            .with_context(|| {
+                format!("{OPENAI_COMPATIBLE_BASE_URL_ENV} environment variable not set")
+            })?;
+        let model = model
+            .or_else(|| env::var(OPENAI_COMPATIBLE_MODEL_ENV).ok())
+            .filter(|model| !model.trim().is_empty())
+            .with_context(|| {
+                format!("{OPENAI_COMPATIBLE_MODEL_ENV} environment variable not set")
+            })?;
+        let api_key = api_key
+            .filter(|key| !key.trim().is_empty())
+            .or_else(|| env::var(OPENAI_COMPATIBLE_API_KEY_ENV).ok());
+        Self::with_compatible(&base_url, api_key, model.trim().to_string())
+    }
+
+    /// Send requests to another chat completions endpoint, e.g. a proxy or a test server
+    pub fn with_api_base(mut self, api_base: String) -> Self {
+        self.api_base = api_base;
+        self
+    }
+
+    /// Record requests in the ledger under another provider, for compatible APIs
+    /// such as OpenRouter
+    #[cfg(feature = "openrouter")]
+    pub(crate) fn with_provider(mut self, provider: &'static str) -> Self {
+        self.provider = provider;
+        self
+    }
+
+    // Send a request, retrying rate limits, overloaded servers and network errors
+    async fn send_request<T: Serialize>(
+        &self,
+        request: &T,
+        retry: &RetryPolicy,
+    ) -> reqwest::Result<reqwest::Response> {
+        let provider = match self.provider {
+            "openrouter" => "OpenRouter",
+            "azure" => "Azure OpenAI",
+            "openai_compatible" => "OpenAI-compatible API",
+            _ => "OpenAI",
+        };
+        send_with_retry(provider, retry, || {
+            self.client.post(&self.api_base).json(request).send()
+        })
+        .await
+    }
+
+    /// Base URL of the API, e.g. `https://api.openai.com/v1`
+    fn api_root(&self) -> &str {
+        self.api_base.trim_end_matches("/chat/completions")
+    }
+
+    /// Converts internal message format to OpenAI's message format
+    ///
+    /// This method converts each message to OpenAI's format with appropriate
+    /// role and content fields.
+    fn convert_messages(&self, messages: Vec<Message>) -> Vec<OpenAIMessage> {
+        messages
+            .into_iter()
+            .map(|msg| {
+                // Convert standard messages
+                OpenAIMessage {
+                    role: msg.role,
+                    content: Some(OpenAIContent::from_text(msg.content)),
+                    tool_calls: None,
+                    tool_call_id: None,
+                }
+            })
+            .collect()
+    }
+
+    /// Builds a request without tools, used for plain completions and batches
+    fn build_request(&self, messages: Vec<Message>, options: &CompletionOptions) -> OpenAIRequest {
+        let openai_messages = self.convert_messages(messages);
+
+        let mut request = OpenAIRequest {
+            model: self.model.clone(),
+            messages: openai_messages,
+            max_tokens: options.max_tokens,
+            temperature: options.temperature,
+            top_p: options.top_p,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+        };
+
+        // Add structured output format if specified in options
+        if let Some(_json_schema) = &options.json_schema {
+            request.response_format = Some(json!({
+                "type": "json_object"
+            }));
+        }
+
+        request
+    }
+
+    /// Converts internal tool definitions to OpenAI's format
+    ///
+    /// This method converts tool definitions to OpenAI's function format with
+    /// appropriate name, description, and parameters.
+    fn convert_tool_definitions(&self, tools: Vec<ToolDefinition>) -> Vec<OpenAITool> {
+        tools
+            .into_iter()
+            .map(|tool| OpenAITool {
+                tool_type: "function".to_string(),
+                function: OpenAIFunction {
+                    name: tool.name,
+                    description: tool.description,
+                    parameters: tool.parameters,
+                },
+            })
+            .collect()
+    }
+
+    /// Build a request with tools, answering any tool calls in the conversation
+    /// that don't have a result yet
+    fn build_tool_request(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+    ) -> OpenAIRequest {
+        // Convert messages to OpenAI format
+        let mut openai_messages = self.convert_messages(messages);
+
+        // Track tool calls that need responses
+        let mut pending_tool_calls = Vec::new();
+
+        // First pass: identify all tool calls that need responses
+        for msg in &openai_messages {
+            if msg.role == "assistant" && msg.tool_calls.is_some() {
+                if let Some(tool_calls) = &msg.tool_calls {
+                    for call in tool_calls {
+                        pending_tool_calls.push(call.id.clone());
+                    }
+                }
+            }
+        }
+
+        // Second pass: remove tool call IDs that already have responses
+        for msg in &openai_messages {
+            if msg.role == "tool" && msg.tool_call_id.is_some() {
+                if let Some(tool_call_id) = &msg.tool_call_id {
+                    pending_tool_calls.retain(|id| id != tool_call_id);
+                }
+            }
+        }
+
+        // Add tool results for any pending tool calls
+        if let Some(results) = &tool_results {
+            let result_map: std::collections::HashMap<String, String> = results
+                .iter()
+                .map(|r| (r.tool_call_id.clone(), r.output.clone()))
+                .collect();
+
+            // Add responses for any pending tool calls
+            for tool_id in &pending_tool_calls {
+                if let Some(output) = result_map.get(tool_id) {
+                    openai_messages.push(OpenAIMessage {
+                        role: "tool".to_string(),
+                        content: Some(OpenAIContent::Text(output.clone())),
+                        tool_calls: None,
+                        tool_call_id: Some(tool_id.clone()),
+                    });
+                } else {
+                    // For any tool call without a provided result, add a default response
+                    // This is crucial for OpenAI - every tool call must have a response
+                    openai_messages.push(OpenAIMessage {
+                        role: "tool".to_string(),
+                        content: Some(OpenAIContent::Text(
+                            "Tool execution completed without detailed results.".to_string(),
+                        )),
+                        tool_calls: None,
+                        tool_call_id: Some(tool_id.clone()),
+                    });
+                }
+            }
+        } else if !pending_tool_calls.is_empty() {
+            // If we have pending tool calls but no results were provided,
+            // we need to add default responses for all pending tool calls
+            for tool_id in &pending_tool_calls {
+                openai_messages.push(OpenAIMessage {
+                    role: "tool".to_string(),
+                    content: Some(OpenAIContent::Text(
+                        "Tool execution completed without detailed results.".to_string(),
+                    )),
+                    tool_calls: None,
+                    tool_call_id: Some(tool_id.clone()),
+                });
+            }
+        }
+
+        let mut request = OpenAIRequest {
+            model: self.model.clone(),
+            messages: openai_messages,
+            max_tokens: options.max_tokens,
+            temperature: options.temperature,
+            top_p: options.top_p,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+        };
+
+        // Add structured output format if specified in options
+        if let Some(_json_schema) = &options.json_schema {
+            request.response_format = Some(json!({
+                "type": "json_object"
+            }));
+
+            // Ensure at least one message contains the word "json" when using json_object response format
+            let has_json_keyword = request.messages.iter().any(|msg| {
+                msg.content
+                    .as_ref()
+                    .is_some_and(|content| content.text().to_lowercase().contains("json"))
+            });
+
+            if !has_json_keyword && !request.messages.is_empty() {
+                // Add "json" to the user's last message if it doesn't already contain it
+                if let Some(last_user_msg) = request
+                    .messages
+                    .iter_mut()
+                    .rev()
+                    .find(|msg| msg.role == "user")
+                {
+                    match &mut last_user_msg.content {
+                        Some(OpenAIContent::Text(content)) => {
+                            *content = format!("{content} (Please provide the response as JSON)");
+                        }
+                        Some(OpenAIContent::Parts(parts)) => parts.push(json!({
+                            "type": "text",
+                            "text": "(Please provide the response as JSON)",
+                        })),
+                        None => {}
+                    }
+                }
+            }
+        }
+
+        // Add tools if they exist
+        if let Some(tools) = options.tools {
+            let converted_tools = self.convert_tool_definitions(tools);
+            request.tools = Some(converted_tools);
+
+            request.tool_choice = Some(openai_tool_choice(&options.tool_choice));
+            // Only sent when turned off, as the default is on and some models reject it
+            if !options.parallel_tool_calls {
+                request.parallel_tool_calls = Some(false);
+            }
+        }
+
+        request
+    }
+}
+
+#[async_trait]
+impl ApiClient for OpenAIClient {
+    async fn complete(&self, messages: Vec<Message>, options: CompletionOptions) -> Result<String> {
+        Ok(self.complete_with_finish(messages, options).await?.0)
+    }
+
+    async fn complete_with_tools(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+    ) -> Result<(String, Option<Vec<ToolCall>>)> {
+        let (content, tool_calls, _) = self
+            .complete_with_tools_and_finish(messages, options, tool_results)
+            .await?;
+        Ok((content, tool_calls))
+    }
+
+    async fn complete_streaming(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, TokenUsage)> {
+        let (content, usage, _) = self
+            .complete_streaming_with_finish(messages, options, on_delta)
+            .await?;
+        Ok((content, usage))
+    }
+
+    async fn complete_with_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+    ) -> Result<(String, FinishReason)> {
+        let started = Instant::now();
+        let retry = options.retry;
+        let request = self.build_request(messages, &options);
+
+        eprintln!(
+            "{}",
+            format_log_with_color(
+                LogLevel::Debug,
+                &format!("Sending request to OpenAI API with model: {}", self.model)
+            )
+        );
+
+        let response = self.send_request(&request, &retry).await.map_err(|e| {
+            let error_msg = format!("Failed to send request to OpenAI: {e}");
+            eprintln!("{}", format_log_with_color(LogLevel::Error, &error_msg));
+            AppError::NetworkError(error_msg)
+        })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AppError::NetworkError(format!(
+                "OpenAI API error: {status} - {error_text}"
+            ))
+            .into());
+        }
+
+        // Parse response
+        let response_text = response.text().await.map_err(|e| {
+            let error_msg = format!("Failed to get response text: {e}");
+            eprintln!("{}", format_log_with_color(LogLevel::Error, &error_msg));
+            AppError::NetworkError(error_msg)
+        })?;
+
+        eprintln!(
+            "{}",
+            format_log_with_color(
+                LogLevel::Debug,
+                &format!(
+                    "OpenAI API response received: {} bytes",
+                    response_text.len()
+                )
+            )
+        );
+
+        let openai_response: OpenAIResponse =
+            serde_json::from_str(&response_text).map_err(|e| {
+                let error_msg = format!("Failed to parse OpenAI response: {e}");
+                eprintln!("{}", format_log_with_color(LogLevel::Error, &error_msg));
+                AppError::Other(error_msg)
+            })?;
+        record_request(
+            self.provider,
+            &self.model,
+            openai_response
+                .usage
+                .as_ref()
+                .map(RequestUsage::from_openai)
+                .unwrap_or_default(),
+            started,
+        );
+
+        // Extract content from the first choice
+        if let Some(first_choice) = openai_response.choices.first() {
+            if let Some(content) = &first_choice.message.content {
+                let finish = FinishReason::from_provider(&first_choice.finish_reason);
+                return Ok((content.text(), finish));
+            }
+        }
+
+        let error_msg = "No content in OpenAI response".to_string();
+        eprintln!("{}", format_log_with_color(LogLevel::Error, &error_msg));
+        Err(AppError::LLMError(error_msg).into())
+    }
+
+    async fn complete_with_tools_and_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+    ) -> Result<(String, Option<Vec<ToolCall>>, FinishReason)> {
+        let started = Instant::now();
+        let retry = options.retry;
+        let request = self.build_tool_request(messages, options, tool_results);
+
+        eprintln!(
+            "{}",
+            format_log_with_color(
+                LogLevel::Debug,
+                &format!("Sending request to OpenAI API with model: {}", self.model)
+            )
+        );
+
+        let response = self.send_request(&request, &retry).await.map_err(|e| {
+            let error_msg = format!("Failed to send request to OpenAI: {e}");
+            eprintln!("{}", format_log_with_color(LogLevel::Error, &error_msg));
+            AppError::NetworkError(error_msg)
+        })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AppError::NetworkError(format!(
+                "OpenAI API error: {status} - {error_text}"
+            ))
+            .into());
+        }
+
+        // Parse response
+        let response_text = response.text().await.map_err(|e| {
+            let error_msg = format!("Failed to get response text: {e}");
+            eprintln!("{}", format_log_with_color(LogLevel::Error, &error_msg));
+            AppError::NetworkError(error_msg)
+        })?;
+
+        eprintln!(
+            "{}",
+            format_log_with_color(
+                LogLevel::Debug,
+                &format!(
+                    "OpenAI API response received: {} bytes",
+                    response_text.len()
+                )
+            )
+        );
+
+        let openai_response: OpenAIResponse =
+            serde_json::from_str(&response_text).map_err(|e| {
+                let error_msg = format!("Failed to parse OpenAI response: {e}");
+                eprintln!("{}", format_log_with_color(LogLevel::Error, &error_msg));
+                AppError::Other(error_msg)
+            })?;
+        record_request(
+            self.provider,
+            &self.model,
+            openai_response
+                .usage
+                .as_ref()
+                .map(RequestUsage::from_openai)
+                .unwrap_or_default(),
+            started,
+        );
+
+        // Extract content and tool calls from the first choice
+        if let Some(first_choice) = openai_response.choices.first() {
+            let content = first_choice
+                .message
+                .content
+                .as_ref()
+                .map(OpenAIContent::text)
+                .unwrap_or_default();
+
+            // Extract tool calls if present
+            let tool_calls = if let Some(openai_tool_calls) = &first_choice.message.tool_calls {
+                if openai_tool_calls.is_empty() {
+                    None
+                } else {
+                    let calls = openai_tool_calls
+                        .iter()
+                        .map(|call| {
+                            // Create a tool call with OpenAI's required format
+                            ToolCall {
+                                id: Some(call.id.clone()), // Important for tool results later
+                                name: call.function.name.clone(),
+                                arguments: parse_tool_arguments(&call.function.arguments),
+                            }
+                        })
+                        .collect::<Vec<_>>();
+
+                    if calls.is_empty() {
+                        None
+                    } else {
+                        Some(calls)
+                    }
+                }
+            } else {
+                None
+            };
+
+            let finish = FinishReason::from_provider(&first_choice.finish_reason);
+            return Ok((content, tool_calls, finish));
+        }
+
+        Ok((String::new(), None, FinishReason::Stop))
+    }
+
+    async fn complete_with_tools_streaming_and_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, Option<Vec<ToolCall>>, FinishReason)> {
+        let started = Instant::now();
+        let retry = options.retry;
+        let mut request =
+            serde_json::to_value(self.build_tool_request(messages, options, tool_results))?;
+        request["stream"] = json!(true);
+        request["stream_options"] = json!({ "include_usage": true });
+
+        let response = self.send_request(&request, &retry).await.map_err(|e| {
+            AppError::NetworkError(format!("Failed to send request to OpenAI: {e}"))
+        })?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AppError::NetworkError(format!(
+                "OpenAI API error: {status} - {error_text}"
+            ))
+            .into());
+        }
+
+        // Tool calls arrive in pieces keyed by their index: the id and name first,
+        // then the arguments a fragment at a time
+        let mut content = String::new();
+        let mut calls: Vec<(String, String, String)> = Vec::new();
+        let mut billed = RequestUsage::default();
+        let mut finish = FinishReason::Stop;
+        read_sse_stream(response, |_, chunk| {
+            let delta = &chunk["choices"][0]["delta"];
+            if let Some(text) = delta["content"].as_str().filter(|text| !text.is_empty()) {
+                content.push_str(text);
+                on_delta(text);
+            }
+            for call in delta["tool_calls"].as_array().into_iter().flatten() {
+                let index = call["index"].as_u64().unwrap_or(0) as usize;
+                if calls.len() <= index {
+                    calls.resize(index + 1, Default::default());
+                }
+                let (id, name, arguments) = &mut calls[index];
+                if let Some(value) = call["id"].as_str() {
+                    id.push_str(value);
+                }
+                if let Some(value) = call["function"]["name"].as_str() {
+                    name.push_str(value);
+                }
+                if let Some(value) = call["function"]["arguments"].as_str() {
+                    arguments.push_str(value);
+                }
+            }
+            if let Some(reason) = chunk["choices"][0]["finish_reason"].as_str() {
+                finish = FinishReason::from_provider(reason);
+            }
+            if chunk["usage"].is_object() {
+                billed = RequestUsage::from_openai(&chunk["usage"]);
+            }
+            Ok(())
+        })
+        .await?;
+
+        record_request(self.provider, &self.model, billed, started);
+        let tool_calls: Vec<ToolCall> = calls
+            .into_iter()
+            .filter(|(_, name, _)| !name.is_empty())
+            .map(|(id, name, arguments)| ToolCall {
+                id: Some(id),
+                name,
+                // Tools without parameters may stream no arguments at all
+                arguments: match arguments.as_str() {
+                    "" => json!({}),
+                    arguments => parse_tool_arguments(arguments),
+                },
+            })
+            .collect();
+        Ok((
+            content,
+            (!tool_calls.is_empty()).then_some(tool_calls),
+            finish,
+        ))
+    }
+
+    async fn complete_streaming_with_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, TokenUsage, FinishReason)> {
+        let started = Instant::now();
+        let retry = options.retry;
+        let mut request = serde_json::to_value(self.build_request(messages, &options))?;
+        request["stream"] = json!(true);
+        request["stream_options"] = json!({ "include_usage": true });
+
+        let response = self.send_request(&request, &retry).await.map_err(|e| {
+            AppError::NetworkError(format!("Failed to send request to OpenAI: {e}"))
+        })?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AppError::NetworkError(format!(
+                "OpenAI API error: {status} - {error_text}"
+            ))
+            .into());
+        }
+
+        // Each chunk carries a content delta and the last one a finish reason; usage
+        // comes in a final chunk without choices
+        let mut content = String::new();
+        let mut usage = TokenUsage::default();
+        let mut billed = RequestUsage::default();
+        let mut finish = FinishReason::Stop;
+        read_sse_stream(response, |_, chunk| {
+            // The first chunk only announces the role, with empty content
+            if let Some(text) = chunk["choices"][0]["delta"]["content"]
+                .as_str()
+                .filter(|text| !text.is_empty())
+            {
+                content.push_str(text);
+                on_delta(text);
+            }
+            if let Some(reason) = chunk["choices"][0]["finish_reason"].as_str() {
+                finish = FinishReason::from_provider(reason);
+            }
+            if let Some(reported) = chunk["usage"].as_object() {
+                usage.input_tokens = reported
+                    .get("prompt_tokens")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(0) as u32;
+                usage.output_tokens = reported
+                    .get("completion_tokens")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(0) as u32;
+                billed = RequestUsage::from_openai(&chunk["usage"]);
+            }
+            Ok(())
+        })
+        .await?;
+
+        record_request(self.provider, &self.model, billed, started);
+        Ok((content, usage, finish))
+    }
+}
+
+/// OpenAI Batch API: requests are uploaded as a JSONL file, processed within
+/// 24 hours, and the results downloaded from an output file
+#[async_trait]
+impl BatchApi for OpenAIClient {
+    async fn submit_batch(&self, requests: Vec<BatchRequest>) -> Result<String> {
+        if let Some(request) = requests.iter().find(|r| !r.is_eligible()) {
+            return Err(anyhow::anyhow!(
+                "Request {} uses tools and can't be batched",
+                request.custom_id
+            ));
+        }
+
+        let mut lines = Vec::new();
+        for request in requests {
+            lines.push(serde_json::to_string(&json!({
+                "custom_id": request.custom_id,
+                "method": "POST",
+                "url": "/v1/chat/completions",
+                "body": self.build_request(request.messages, &request.options),
+            }))?);
+        }
+
+        // Upload the requests as a multipart file with purpose "batch"
+        let boundary = format!("oli-batch-{}", uuid::Uuid::new_v4().simple());
+        let body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"purpose\"\r\n\r\nbatch\r\n\
+             --{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"batch.jsonl\"\r\n\
+             Content-Type: application/jsonl\r\n\r\n{}\r\n--{boundary}--\r\n",
+            lines.join("\n")
+        );
+        let response = self
+            .client
+            .post(format!("{}/files", self.api_root()))
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| AppError::NetworkError(format!("Failed to upload batch file: {e}")))?;
+        let file = openai_response_json(response).await?;
+        let file_id = file["id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("OpenAI file upload response has no id: {file}"))?;
+
+        let response = self
+            .client
+            .post(format!("{}/batches", self.api_root()))
+            .json(&json!({
+                "input_file_id": file_id,
+                "endpoint": "/v1/chat/completions",
+                "completion_window": "24h",
+            }))
+            .send()
+            .await
+            .map_err(|e| AppError::NetworkError(format!("Failed to submit batch: {e}")))?;
+        let batch = openai_response_json(response).await?;
+
+        batch["id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("OpenAI batch response has no id: {batch}"))
+    }
+
+    async fn batch_status(&self, batch_id: &str) -> Result<BatchStatus> {
+        let batch = self.get_batch(batch_id).await?;
+
+        match batch["status"].as_str() {
+            Some("completed") => Ok(BatchStatus::Completed),
+            Some("validating") | Some("in_progress") | Some("finalizing") | Some("cancelling") => {
+                Ok(BatchStatus::InProgress)
+            }
+            Some("failed") | Some("expired") | Some("cancelled") => Ok(BatchStatus::Failed),
+            other => Err(anyhow::anyhow!(
+                "Unknown OpenAI batch status: {}",
+                other.unwrap_or("none")
+            )),
+        }
+    }
+
+    async fn batch_results(&self, batch_id: &str) -> Result<Vec<BatchResult>> {
+        let batch = self.get_batch(batch_id).await?;
+
+        // Successful requests are in the output file and failed ones in the error file
+        let mut results = Vec::new();
+        for file_id in [&batch["output_file_id"], &batch["error_file_id"]]
+            .into_iter()
+            .filter_map(Value::as_str)
+        {
+            let response = self
+                .client
+                .get(format!("{}/files/{file_id}/content", self.api_root()))
+                .send()
+                .await
+                .map_err(|e| {
+                    AppError::NetworkError(format!("Failed to fetch batch results: {e}"))
+                })?;
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response.text().await.unwrap_or_default();
+                return Err(AppError::NetworkError(format!(
+                    "OpenAI API error: {status} - {error_text}"
+                ))
+                .into());
+            }
+
+            let body = response.text().await?;
+            results.extend(
+                body.lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+                    .map(|line| parse_openai_batch_result(&line)),
+            );
+        }
+
+        Ok(results)
+    }
+}
+
+impl OpenAIClient {
+    async fn get_batch(&self, batch_id: &str) -> Result<Value> {
+        let response = self
+            .client
+            .get(format!("{}/batches/{batch_id}", self.api_root()))
+            .send()
+            .await
+            .map_err(|e| AppError::NetworkError(format!("Failed to check batch: {e}")))?;
+        openai_response_json(response).await
+    }
+}
+
+async fn openai_response_json(response: reqwest::Response) -> Result<Value> {
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(
+            AppError::NetworkError(format!("OpenAI API error: {status} - {error_text}")).into(),
+        );
+    }
+    Ok(response.json().await?)
+}
+
+fn parse_openai_batch_result(line: &Value) -> BatchResult {
+    let custom_id = line["custom_id"].as_str().unwrap_or_default();
+
+    if let Some(message) = line["error"]["message"].as_str() {
+        return BatchResult::failure(custom_id, message.to_string());
+    }
+
+    let response = &line["response"];
+    if response["status_code"].as_u64() != Some(200) {
+        let message = response["body"]["error"]["message"]
+            .as_str()
+            .unwrap_or("request failed");
+        return BatchResult::failure(custom_id, message.to_string());
+    }
+
+    match response["body"]["choices"][0]["message"]["content"].as_str() {
+        Some(content) => BatchResult::success(custom_id, content.to_string()),
+        None => BatchResult::failure(custom_id, "No content in OpenAI response".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_client::{Message, ToolDefinition};
+    use serde_json::json;
+
+    #[test]
+    fn test_openai_model_name() {
+        // Test that the default model name is correct when providing None
+        // This doesn't make API calls, just tests the client setup logic
+        let api_key = "test_api_key".to_string();
+        let client = OpenAIClient::with_api_key(api_key, None).unwrap();
+
+        // Verify the model name is the expected default
+        assert_eq!(
+            client.get_model_name(),
+            "gpt-4o",
+            "Default model name should be gpt-4o"
+        );
+    }
+
+    #[test]
+    fn test_openai_with_custom_model() {
+        // Test that the custom model name is used correctly
+        let api_key = "test_api_key".to_string();
+        let model_name = "gpt-4-turbo".to_string();
+        let client = OpenAIClient::with_api_key(api_key, Some(model_name.clone())).unwrap();
+
+        // Verify the custom model name is used
+        assert_eq!(
+            client.get_model_name(),
+            model_name,
+            "Custom model name should be used"
+        );
+    }
+
+    #[test]
+    fn test_message_conversion() {
+        // Set up a client for testing conversion methods
+        let api_key = "test_api_key".to_string();
+        let client = OpenAIClient::with_api_key(api_key, None).unwrap();
+
+        // Create test messages
+        let messages = vec![
+            Message {
+                role: "system".to_string(),
+                content: "You are a helpful assistant.".to_string(),
+            },
+            Message {
+                role: "user".to_string(),
+                content: "Hello".to_string(),
+            },
+            Message {
+                role: "assistant".to_string(),
+                content: "Hi there! How can I help you today?".to_string(),
+            },
+        ];
+
+        // Convert the messages
+        let openai_messages = client.convert_messages(messages);
+
+        // Verify messages are converted correctly
+        assert_eq!(openai_messages.len(), 3, "Should have 3 messages");
+
+        // Check system message
+        let system_msg = &openai_messages[0];
+        assert_eq!(
+            system_msg.role, "system",
+            "First message should be a system message"
+        );
+        assert_eq!(
+            system_msg.content.as_ref().unwrap(),
+            "You are a helpful assistant.",
+            "Content should match"
+        );
+        assert!(
+            system_msg.tool_calls.is_none(),
+            "System message should not have tool calls"
+        );
+
+        // Check user message
+        let user_msg = &openai_messages[1];
+        assert_eq!(
+            user_msg.role, "user",
+            "Second message should be a user message"
+        );
+        assert_eq!(
+            user_msg.content.as_ref().unwrap(),
+            "Hello",
+            "Content should match"
+        );
+        assert!(
+            user_msg.tool_calls.is_none(),
+            "User message should not have tool calls"
+        );
+
+        // Check assistant message
+        let assistant_msg = &openai_messages[2];
+        assert_eq!(
+            assistant_msg.role, "assistant",
+            "Third message should be an assistant message"
+        );
+        assert_eq!(
+            assistant_msg.content.as_ref().unwrap(),
+            "Hi there! How can I help you today?",
+            "Content should match"
+        );
+        assert!(
+            assistant_msg.tool_calls.is_none(),
+            "Assistant message should not have tool calls"
+        );
+    }
+
+    #[test]
+    fn test_message_conversion_edge_cases() {
+        // Set up a client for testing conversion methods
+        let api_key = "test_api_key".to_string();
+        let client = OpenAIClient::with_api_key(api_key, None).unwrap();
+
+        // Test with empty messages
+        let empty_messages: Vec<Message> = vec![];
+        let openai_messages = client.convert_messages(empty_messages);
+        assert!(openai_messages.is_empty(), "Should produce no messages");
+
+        // Test with a single message
+        let single_message = vec![Message {
+            role: "user".to_string(),
+            content: "Hello".to_string(),
+        }];
+
+        let openai_messages = client.convert_messages(single_message);
+        assert_eq!(openai_messages.len(), 1, "Should produce 1 message");
+        assert_eq!(openai_messages[0].role, "user", "Should be a user message");
+        assert_eq!(
+            openai_messages[0].content.as_ref().unwrap(),
+            "Hello",
+            "Content should match"
+        );
+    }
+
+    #[test]
+    fn test_tool_definitions_conversion() {
+        // Set up a client for testing conversion methods
+        let api_key = "test_api_key".to_string();
+        let client = OpenAIClient::with_api_key(api_key, None).unwrap();
+
+        // Create test tools
+        let tools = vec![
+            ToolDefinition {
+                name: "calculator".to_string(),
+                description: "Calculate mathematical expressions".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "expression": {
+                            "type": "string",
+                            "description": "The mathematical expression to evaluate"
+                        }
+                    }
+                }),
+            },
+            ToolDefinition {
+                name: "weather".to_string(),
+                description: "Get weather information".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "location": {
+                            "type": "string",
+                            "description": "The location to get weather for"
+                        }
+                    }
+                }),
+            },
+        ];
+
+        // Convert the tools
+        let openai_tools = client.convert_tool_definitions(tools);
+
+        // Verify tools are converted correctly
+        assert_eq!(openai_tools.len(), 2, "Should have 2 tools");
+
+        // Check first tool
+        let calculator_tool = &openai_tools[0];
+        assert_eq!(
+            calculator_tool.tool_type, "function",
+            "Tool type should be function"
+        );
+        assert_eq!(
+            calculator_tool.function.name, "calculator",
+            "Name should match"
+        );
+        assert_eq!(
+            calculator_tool.function.description, "Calculate mathematical expressions",
+            "Description should match"
+        );
+
+        // Check function parameters
+        let calculator_params = &calculator_tool.function.parameters;
+        assert!(
+            calculator_params.is_object(),
+            "Parameters should be an object"
+        );
+        assert!(
+            calculator_params.get("properties").is_some(),
+            "Parameters should have properties"
+        );
+        assert!(
+            calculator_params
+                .get("properties")
+                .unwrap()
+                .get("expression")
+                .is_some(),
+            "Expression property should exist"
+        );
+
+        // Check second tool
+        let weather_tool = &openai_tools[1];
+        assert_eq!(
+            weather_tool.tool_type, "function",
+            "Tool type should be function"
+        );
+        assert_eq!(weather_tool.function.name, "weather", "Name should match");
+        assert_eq!(
+            weather_tool.function.description, "Get weather information",
+            "Description should match"
+        );
+
+        // Check function parameters
+        let weather_params = &weather_tool.function.parameters;
+        assert!(weather_params.is_object(), "Parameters should be an object");
+        assert!(
+            weather_params.get("properties").is_some(),
+            "Parameters should have properties"
+        );
+        assert!(
+            weather_params
+                .get("properties")
+                .unwrap()
+                .get("location")
+                .is_some(),
+            "Location property should exist"
+        );
+    }
+
+    #[test]
+    fn test_tool_definitions_edge_cases() {
+        // Set up a client for testing conversion methods
+        let api_key = "test_api_key".to_string();
+        let client = OpenAIClient::with_api_key(api_key, None).unwrap();
+
+        // Test with empty tools
+        let empty_tools: Vec<ToolDefinition> = vec![];
+        let openai_tools = client.convert_tool_definitions(empty_tools);
+        assert!(openai_tools.is_empty(), "Should produce no tools");
+
+        // Test with a single tool with minimal properties
+        let single_tool = vec![ToolDefinition {
+            name: "simple".to_string(),
+            description: "Simple tool".to_string(),
+            parameters: json!({
+                "type": "object"
+            }),
+        }];
+
+        let openai_tools = client.convert_tool_definitions(single_tool);
+        assert_eq!(openai_tools.len(), 1, "Should produce 1 tool");
+
+        // Check the simple tool
+        let simple_tool = &openai_tools[0];
+        assert_eq!(
+            simple_tool.tool_type, "function",
+            "Tool type should be function"
+        );
+        assert_eq!(simple_tool.function.name, "simple", "Name should match");
+        assert_eq!(
+            simple_tool.function.description, "Simple tool",
+            "Description should match"
+        );
+
+        // Check function parameters
+        let simple_params = &simple_tool.function.parameters;
+        assert!(simple_params.is_object(), "Parameters should be an object");
+        assert_eq!(
+            simple_params.get("type").unwrap(),
+            "object",
+            "Type should be object"
+        );
+    }
+
+    #[test]
+    fn test_openai_tool_choice() {
+        assert_eq!(openai_tool_choice(&ToolChoice::Auto), json!("auto"));
+        assert_eq!(openai_tool_choice(&ToolChoice::Any), json!("required"));
+        assert_eq!(openai_tool_choice(&ToolChoice::None), json!("none"));
+        assert_eq!(
+            openai_tool_choice(&ToolChoice::Tool("Bash".to_string())),
+            json!({"type": "function", "function": {"name": "Bash"}})
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use crate::api_client::{
+    ApiClient, CompletionOptions, FinishReason, Message, ToolCall, ToolResult,
+};
+use crate::openai::OpenAIClient;
+use crate::streaming::{DeltaHandler, TokenUsage};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client as ReqwestClient;
+use serde::{Deserialize, Serialize};
+use std::env;
+
+/// Model used when none is given. OpenRouter names models `vendor/model`.
+pub const OPENROUTER_MODEL_NAME: &str = "deepseek/deepseek-chat";
+/// Root of OpenRouter's OpenAI-compatible API
+pub const OPENROUTER_API_BASE: &str = "https://openrouter.ai/api/v1";
+
+/// A model OpenRouter serves, as listed by its models endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenRouterModelInfo {
+    /// The ID requests select it by, e.g. `meta-llama/llama-3.3-70b-instruct`
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub context_length: Option<u64>,
+    /// Request parameters the model accepts, such as `tools` and `temperature`
+    #[serde(default)]
+    pub supported_parameters: Vec<String>,
+}
+
+impl OpenRouterModelInfo {
+    /// Whether the model accepts tool definitions, which the agent needs
+    pub fn supports_tools(&self) -> bool {
+        self.supported_parameters.iter().any(|p| p == "tools")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterModelList {
+    data: Vec<OpenRouterModelInfo>,
+}
+
+/// OpenRouter serves models from many vendors, such as DeepSeek, Meta and Mistral,
+/// behind one API key. Its chat completions API is OpenAI's, so requests go through
+/// an `OpenAIClient` pointed at it; usage is recorded under `openrouter`.
+pub struct OpenRouterClient {
+    inner: OpenAIClient,
+    client: ReqwestClient,
+    api_base: String,
+}
+
+impl OpenRouterClient {
+    pub fn new(model: Option<String>) -> Result<Self> {
+        let api_key = env::var("OPENROUTER_API_KEY")
+            .context("OPENROUTER_API_KEY environment variable not set")?;
+        Self::with_api_key(api_key, model)
+    }
+
+    pub fn with_api_key(api_key: String, model: Option<String>) -> Result<Self> {
+        let model = model.unwrap_or_else(|| OPENROUTER_MODEL_NAME.to_string());
+        let inner = OpenAIClient::with_api_key(api_key, Some(model))?
+            .with_api_base(format!("{OPENROUTER_API_BASE}/chat/completions"))
+            .with_provider("openrouter");
+        Ok(Self {
+            inner,
+            client: ReqwestClient::new(),
+            api_base: OPENROUTER_API_BASE.to_string(),
+        })
+    }
+
+    /// Send requests to another root than `https://openrouter.ai/api/v1`, e.g. a
+    /// proxy or a test server
+    pub fn with_api_base(mut self, api_base: String) -> Self {
+        let api_base = api_base.trim_end_matches('/').to_string();
+        self.inner = self
+            .inner
+            .with_api_base(format!("{api_base}/chat/completions"));
+        self.api_base = api_base;
+        self
+    }
+
+    /// The models OpenRouter serves. Listing them needs no API key.
+    pub async fn list_models(&self) -> Result<Vec<OpenRouterModelInfo>> {
+        let response = self
+            .client
+            .get(format!("{}/models", self.api_base))
+            .send()
+            .await
+            .context("Failed to list OpenRouter models")?;
+        if !response.status().is_success() {
+            anyhow::bail!("OpenRouter models request failed: {}", response.status());
+        }
+        let list: OpenRouterModelList = response
+            .json()
+            .await
+            .context("Failed to parse OpenRouter models")?;
+        Ok(list.data)
+    }
+}
+
+#[async_trait]
+impl ApiClient for OpenRouterClient {
+    async fn complete(&self, messages: Vec<Message>, options: CompletionOptions) -> Result<String> {
+        self.inner.complete(messages, options).await
+    }
+
+    async fn complete_with_tools(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+    ) -> Result<(String, Option<Vec<ToolCall>>)> {
+        self.inner
+            .complete_with_tools(messages, options, tool_results)
+            .await
+    }
+
+    async fn complete_streaming(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, TokenUsage)> {
+        self.inner
+            .complete_streaming(messages, options, on_delta)
+            .await
+    }
+
+    async fn complete_with_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+    ) -> Result<(String, FinishReason)> {
+        self.inner.complete_with_finish(messages, options).await
+    }
+
+    async fn complete_with_tools_and_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+    ) -> Result<(String, Option<Vec<ToolCall>>, FinishReason)> {
+        self.inner
+            .complete_with_tools_and_finish(messages, options, tool_results)
+            .await
+    }
+
+    async fn complete_with_tools_streaming_and_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, Option<Vec<ToolCall>>, FinishReason)> {
+        self.inner
+            .complete_with_tools_streaming_and_finish(messages, options, tool_results, on_delta)
+            .await
+    }
+
+    async fn complete_streaming_with_finish(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, TokenUsage, FinishReason)> {
+        self.inner
+            .complete_streaming_with_finish(messages, options, on_delta)
+            .await
+    }
+}
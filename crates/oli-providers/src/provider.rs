@@ -0,0 +1,91 @@
+/// A model provider oli can talk to
+#[derive(Debug, Clone, PartialEq)]
+pub enum LLMProvider {
+    Anthropic,
+    OpenAI,
+    Ollama,
+    Gemini,
+    OpenRouter,
+    /// OpenAI models deployed in an Azure OpenAI resource
+    AzureOpenAI,
+    /// Any other server with OpenAI's chat completions API, such as DeepSeek, xAI,
+    /// Together, vLLM or LM Studio
+    OpenAICompatible,
+}
+
+impl LLMProvider {
+    /// Highest temperature accepted by the provider's API
+    pub fn max_temperature(&self) -> f32 {
+        match self {
+            LLMProvider::Anthropic => 1.0,
+            LLMProvider::OpenAI
+            | LLMProvider::AzureOpenAI
+            | LLMProvider::OpenAICompatible
+            | LLMProvider::Ollama
+            | LLMProvider::Gemini
+            | LLMProvider::OpenRouter => 2.0,
+        }
+    }
+
+    /// Whether the provider's client sends images in messages as image input;
+    /// the others leave them out with a note to the model
+    pub fn accepts_images(&self) -> bool {
+        match self {
+            LLMProvider::Anthropic
+            | LLMProvider::OpenAI
+            | LLMProvider::AzureOpenAI
+            | LLMProvider::OpenRouter
+            | LLMProvider::OpenAICompatible => true,
+            LLMProvider::Ollama | LLMProvider::Gemini => false,
+        }
+    }
+
+    /// Whether a model accepts temperature and top_p; OpenAI's reasoning models
+    /// only run with their defaults. Azure deployments are usually named after
+    /// their model, so are judged the same way.
+    pub fn accepts_sampling(&self, model: &str) -> bool {
+        match self {
+            LLMProvider::OpenAI | LLMProvider::AzureOpenAI => {
+                let model = model.to_lowercase();
+                !["o1", "o3", "o4"]
+                    .iter()
+                    .any(|prefix| model.starts_with(prefix))
+            }
+            LLMProvider::Anthropic
+            | LLMProvider::OpenAICompatible
+            | LLMProvider::Ollama
+            | LLMProvider::Gemini
+            | LLMProvider::OpenRouter => true,
+        }
+    }
+
+    /// Largest `max_tokens` value accepted by the provider's API
+    pub fn max_output_tokens(&self) -> u32 {
+        match self {
+            LLMProvider::Anthropic => 64_000,
+            LLMProvider::OpenAI | LLMProvider::AzureOpenAI => 16_384,
+            LLMProvider::Gemini => 65_536,
+            // Varies by model; OpenRouter rejects more than the model allows
+            LLMProvider::OpenRouter => 16_384,
+            // Varies by server; most accept at least this much
+            LLMProvider::OpenAICompatible => 8_192,
+            // Local models have no fixed output limit
+            LLMProvider::Ollama => u32::MAX,
+        }
+    }
+
+    /// Tokens of context the provider's models accept, used to size tool results
+    pub fn context_window(&self) -> u32 {
+        match self {
+            LLMProvider::Anthropic => 200_000,
+            LLMProvider::OpenAI | LLMProvider::AzureOpenAI => 128_000,
+            LLMProvider::Gemini => 1_048_576,
+            // Varies by model; most tool-capable ones take at least this much
+            LLMProvider::OpenRouter => 128_000,
+            // Varies by model; local servers often run models with smaller windows
+            LLMProvider::OpenAICompatible => 32_768,
+            // Ollama's default context length; models can be configured larger
+            LLMProvider::Ollama => 8_192,
+        }
+    }
+}
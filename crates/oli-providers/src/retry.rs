@@ -0,0 +1,174 @@
+//! Retrying requests that failed for reasons that pass, such as rate limits,
+//! overloaded servers and dropped connections, shared by all clients
+
+use crate::log::{format_log_with_color, LogLevel};
+use reqwest::header::HeaderMap;
+use reqwest::Response;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How failed requests are retried, set per request in `CompletionOptions`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 turns retrying off
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each one after
+    pub initial_delay_ms: u64,
+    /// Longest delay between attempts. A server asking to wait longer with
+    /// `Retry-After` gets its error returned instead.
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_delay_ms: 1000,
+            max_delay_ms: 60_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Send each request once
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// How long to wait before retry number `retry`, counting from 0: the server's
+    /// `Retry-After` when it sent one, else the exponential backoff with jitter.
+    /// None when the server asks for a longer wait than `max_delay_ms`.
+    pub fn delay(&self, retry: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        let max_delay = Duration::from_millis(self.max_delay_ms);
+        if let Some(retry_after) = retry_after {
+            return (retry_after <= max_delay).then_some(retry_after);
+        }
+
+        let backoff = Duration::from_millis(self.initial_delay_ms)
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(max_delay);
+        // Somewhere between half the backoff and all of it, so clients rate limited
+        // together don't all retry at once
+        let half = backoff / 2;
+        let jitter = rand::random::<f64>() * half.as_secs_f64();
+        Some(half + Duration::from_secs_f64(jitter))
+    }
+}
+
+/// Whether a response status is worth retrying: timeouts, rate limits and server
+/// errors, including Anthropic's 529 when overloaded
+pub fn is_retryable_status(status: u16) -> bool {
+    matches!(status, 408 | 429 | 500 | 502 | 503 | 504 | 529)
+}
+
+/// The wait a response asks for, from `retry-after-ms` or `retry-after` in seconds
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    if let Some(ms) = header("retry-after-ms").and_then(|v| v.trim().parse::<f64>().ok()) {
+        return Some(Duration::from_secs_f64(ms.max(0.0) / 1000.0));
+    }
+    header("retry-after")
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .map(|secs| Duration::from_secs_f64(secs.max(0.0)))
+}
+
+/// A retry about to be made
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryEvent {
+    /// Name of the provider, e.g. "Anthropic"
+    pub provider: String,
+    /// What failed, e.g. "429 Too Many Requests"
+    pub reason: String,
+    /// Number of this retry, from 1
+    pub retry: u32,
+    pub max_retries: u32,
+    pub delay: Duration,
+}
+
+impl RetryEvent {
+    /// One line for progress output
+    pub fn message(&self) -> String {
+        format!(
+            "{} API request failed ({}); retrying in {:.1}s (retry {} of {})",
+            self.provider,
+            self.reason,
+            self.delay.as_secs_f64(),
+            self.retry,
+            self.max_retries
+        )
+    }
+}
+
+/// Called before each retry made while it's in scope
+pub type RetryListener = Arc<dyn Fn(&RetryEvent) + Send + Sync>;
+
+tokio::task_local! {
+    static CURRENT_LISTENER: RetryListener;
+}
+
+/// Run `future`, passing each retry its requests make to `listener`, e.g. to show
+/// them as progress
+pub fn with_retry_listener<F: Future>(
+    listener: RetryListener,
+    future: F,
+) -> impl Future<Output = F::Output> {
+    CURRENT_LISTENER.scope(listener, future)
+}
+
+/// Send a request with `send`, retrying under `policy` while it fails with a
+/// retryable status or a network error other than a refused connection. The last
+/// response is returned whatever its status, for the caller to report.
+pub async fn send_with_retry<F, Fut>(
+    provider: &str,
+    policy: &RetryPolicy,
+    mut send: F,
+) -> reqwest::Result<Response>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = reqwest::Result<Response>>,
+{
+    let mut retry = 0;
+    loop {
+        let result = send().await;
+        let (reason, retry_after) = match &result {
+            Ok(response) if is_retryable_status(response.status().as_u16()) => {
+                let status = response.status();
+                let reason = status.canonical_reason().unwrap_or("Overloaded");
+                (
+                    format!("{} {reason}", status.as_u16()),
+                    retry_after(response.headers()),
+                )
+            }
+            // A refused connection means nothing is listening, e.g. Ollama isn't
+            // running, which waiting won't fix
+            Err(e) if !e.is_connect() && !e.is_builder() => (format!("network error: {e}"), None),
+            _ => return result,
+        };
+        let delay = match policy.delay(retry, retry_after) {
+            Some(delay) if retry < policy.max_retries => delay,
+            _ => return result,
+        };
+        drop(result);
+        retry += 1;
+
+        let event = RetryEvent {
+            provider: provider.to_string(),
+            reason,
+            retry,
+            max_retries: policy.max_retries,
+            delay,
+        };
+        eprintln!(
+            "{}",
+            format_log_with_color(LogLevel::Warning, &event.message())
+        );
+        let _ = CURRENT_LISTENER.try_with(|listener| listener(&event));
+        tokio::time::sleep(delay).await;
+    }
+}
@@ -0,0 +1,114 @@
+use crate::api_client::{split_images, Message};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Tokens an attached image is counted as, about what a large screenshot costs
+pub(crate) const IMAGE_TOKENS: usize = 1_600;
+
+/// Rough token count, at about four bytes per token, with each attached image
+/// counted as a fixed amount rather than by the size of its data
+pub fn estimate_tokens(text: &str) -> usize {
+    let (text, images) = split_images(text);
+    text.len().div_ceil(4) + images.len() * IMAGE_TOKENS
+}
+
+/// Receives text as the model generates it
+pub type DeltaHandler<'a> = &'a mut (dyn FnMut(&str) + Send);
+
+/// Tokens used by one completion, as reported by the provider where possible
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+}
+
+impl TokenUsage {
+    /// Estimate usage for providers that don't report it
+    pub fn estimate(messages: &[Message], response: &str) -> Self {
+        Self {
+            input_tokens: messages
+                .iter()
+                .map(|msg| estimate_tokens(&msg.content) as u32)
+                .sum(),
+            output_tokens: estimate_tokens(response) as u32,
+        }
+    }
+}
+
+/// One server-sent event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// Splits a server-sent events body into events as chunks arrive. Bytes are
+/// buffered until a line is complete, so characters and `\r\n` split across
+/// chunks come through intact.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a chunk of the body and return the events it completes
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let mut line: Vec<u8> = self.buffer.drain(..=end).collect();
+            line.pop();
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            let line = String::from_utf8_lossy(&line);
+
+            // A blank line ends the event
+            if line.is_empty() {
+                let event = self.event.take();
+                if !self.data.is_empty() {
+                    events.push(SseEvent {
+                        event,
+                        data: std::mem::take(&mut self.data).join("\n"),
+                    });
+                }
+            } else if let Some(value) = line.strip_prefix("event:") {
+                self.event = Some(value.trim().to_string());
+            } else if let Some(value) = line.strip_prefix("data:") {
+                self.data
+                    .push(value.strip_prefix(' ').unwrap_or(value).to_string());
+            }
+        }
+        events
+    }
+}
+
+/// Read a streamed response body, passing each event's JSON data to `on_event`
+/// until the stream ends or sends `[DONE]`
+pub async fn read_sse_stream(
+    mut response: reqwest::Response,
+    mut on_event: impl FnMut(Option<&str>, Value) -> Result<()>,
+) -> Result<()> {
+    let mut parser = SseParser::new();
+
+    while let Some(chunk) = response.chunk().await? {
+        for event in parser.push(&chunk) {
+            if event.data == "[DONE]" {
+                return Ok(());
+            }
+            // Skip keep-alive comments and anything else that isn't JSON
+            if let Ok(data) = serde_json::from_str(&event.data) {
+                on_event(event.event.as_deref(), data)?;
+            }
+        }
+    }
+    Ok(())
+}
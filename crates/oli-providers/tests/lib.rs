@@ -0,0 +1,3 @@
+// This file is just an entry point for the tests.
+// The actual tests are in the tests directory structure.
+pub mod providers;
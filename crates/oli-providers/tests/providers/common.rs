@@ -0,0 +1,52 @@
+//! Helpers for serving recorded provider responses
+
+use oli_providers::api_client::{Message, ToolDefinition};
+use serde_json::json;
+use std::path::PathBuf;
+use wiremock::ResponseTemplate;
+
+/// The contents of a recorded response under tests/fixtures
+pub fn fixture(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name);
+    std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {e}", path.display()))
+}
+
+/// A response with a recorded JSON body
+pub fn json_response(status: u16, name: &str) -> ResponseTemplate {
+    ResponseTemplate::new(status).set_body_raw(fixture(name), "application/json")
+}
+
+/// A response streaming a recorded server-sent events body
+pub fn sse_response(name: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(fixture(name), "text/event-stream")
+}
+
+/// A rate-limit response asking to retry straight away
+pub fn rate_limited(name: &str) -> ResponseTemplate {
+    json_response(429, name).insert_header("retry-after", "0")
+}
+
+/// The conversation each test sends
+pub fn messages() -> Vec<Message> {
+    vec![
+        Message::system("You are a coding assistant.".to_string()),
+        Message::user("Which version is this crate?".to_string()),
+    ]
+}
+
+/// The tool each tool-call test offers
+pub fn read_tool() -> Vec<ToolDefinition> {
+    vec![ToolDefinition {
+        name: "Read".to_string(),
+        description: "Read a file".to_string(),
+        parameters: json!({
+            "type": "object",
+            "properties": { "file_path": { "type": "string" } },
+            "required": ["file_path"]
+        }),
+    }]
+}
@@ -0,0 +1,10 @@
+//! Provider clients tested against recorded HTTP responses in tests/fixtures,
+//! served by a local mock server so no API keys or network are needed
+
+mod common;
+mod test_anthropic;
+mod test_gemini;
+mod test_ollama;
+mod test_openai;
+mod test_openrouter;
+mod test_retry;
@@ -0,0 +1,302 @@
+//! Anthropic Messages API client against recorded responses
+
+use super::common::{json_response, messages, rate_limited, read_tool, sse_response};
+use oli_providers::anthropic::AnthropicClient;
+use oli_providers::api_client::{
+    ApiClient, CompletionOptions, FinishReason, ImageData, Message, ToolResult,
+};
+use serde_json::{json, Value};
+use wiremock::matchers::{body_string_contains, header, method, path};
+use wiremock::{Mock, MockServer};
+
+fn client(server: &MockServer) -> AnthropicClient {
+    AnthropicClient::with_api_key("test-key".to_string(), None)
+        .unwrap()
+        .with_api_base(format!("{}/v1/messages", server.uri()))
+}
+
+fn with_tools() -> CompletionOptions {
+    CompletionOptions {
+        tools: Some(read_tool()),
+        ..CompletionOptions::default()
+    }
+}
+
+#[tokio::test]
+async fn test_tool_call_round_trip() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(header("x-api-key", "test-key"))
+        .and(header("anthropic-version", "2023-06-01"))
+        .respond_with(json_response(200, "anthropic/tool_use.json"))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("tool_result"))
+        .respond_with(json_response(200, "anthropic/answer.json"))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    let client = client(&server);
+
+    let (content, tool_calls, finish) = client
+        .complete_with_tools_and_finish(messages(), with_tools(), None)
+        .await
+        .unwrap();
+    assert_eq!(content, "I'll read the manifest to check the version.");
+    assert_eq!(finish, FinishReason::ToolUse);
+    let tool_calls = tool_calls.unwrap();
+    assert_eq!(tool_calls.len(), 1);
+    assert_eq!(tool_calls[0].name, "Read");
+    assert_eq!(
+        tool_calls[0].arguments,
+        json!({ "file_path": "Cargo.toml" })
+    );
+
+    let results = vec![ToolResult {
+        tool_call_id: "toolu_01A09q90qw90lq917835lq9".to_string(),
+        output: "version = \"0.1.4-post1\"".to_string(),
+    }];
+    let (content, tool_calls, finish) = client
+        .complete_with_tools_and_finish(messages(), with_tools(), Some(results))
+        .await
+        .unwrap();
+    assert_eq!(content, "The crate is at version 0.1.4-post1.");
+    assert!(tool_calls.is_none());
+    assert_eq!(finish, FinishReason::Stop);
+
+    // The first request offers the tool; the second sends its result back
+    let requests = server.received_requests().await.unwrap();
+    let first: Value = requests[0].body_json().unwrap();
+    assert_eq!(first["system"][0]["text"], "You are a coding assistant.");
+    assert_eq!(first["tools"][0]["name"], "Read");
+    assert_eq!(first["tool_choice"]["type"], "auto");
+    let second: Value = requests[1].body_json().unwrap();
+    let result = second["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|message| message["content"].as_array().unwrap())
+        .find(|block| block["type"] == "tool_result")
+        .unwrap();
+    assert_eq!(result["tool_use_id"], "toolu_01A09q90qw90lq917835lq9");
+    assert_eq!(result["content"], "version = \"0.1.4-post1\"");
+}
+
+#[tokio::test]
+async fn test_streaming_chunks() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("\"stream\":true"))
+        .respond_with(sse_response("anthropic/stream.sse"))
+        .mount(&server)
+        .await;
+
+    let mut deltas = Vec::new();
+    let (content, usage, finish) = client(&server)
+        .complete_streaming_with_finish(messages(), CompletionOptions::default(), &mut |text| {
+            deltas.push(text.to_string())
+        })
+        .await
+        .unwrap();
+    assert_eq!(deltas, vec!["Hello", ", wor", "ld!"]);
+    assert_eq!(content, "Hello, world!");
+    assert_eq!((usage.input_tokens, usage.output_tokens), (25, 6));
+    assert_eq!(finish, FinishReason::Length);
+}
+
+#[tokio::test]
+async fn test_streaming_tool_use() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("\"stream\":true"))
+        .respond_with(sse_response("anthropic/stream_tool_use.sse"))
+        .mount(&server)
+        .await;
+
+    let mut deltas = Vec::new();
+    let (content, tool_calls, finish) = client(&server)
+        .complete_with_tools_streaming_and_finish(messages(), with_tools(), None, &mut |text| {
+            deltas.push(text.to_string())
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        deltas,
+        vec!["I'll read the manifest", " to check the version."]
+    );
+    assert_eq!(content, "I'll read the manifest to check the version.");
+    assert_eq!(finish, FinishReason::ToolUse);
+
+    // The input arrives as JSON fragments
+    let tool_calls = tool_calls.unwrap();
+    assert_eq!(tool_calls.len(), 1);
+    assert_eq!(tool_calls[0].name, "Read");
+    assert_eq!(
+        tool_calls[0].arguments,
+        json!({ "file_path": "Cargo.toml" })
+    );
+
+    let request: Value = server.received_requests().await.unwrap()[0]
+        .body_json()
+        .unwrap();
+    assert_eq!(request["tools"][0]["name"], "Read");
+}
+
+#[tokio::test]
+async fn test_stream_error_event() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(sse_response("anthropic/stream_error.sse"))
+        .mount(&server)
+        .await;
+
+    let error = client(&server)
+        .complete_streaming(messages(), CompletionOptions::default(), &mut |_| {})
+        .await
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("Anthropic stream error: Overloaded"));
+}
+
+#[tokio::test]
+async fn test_rate_limit_is_retried() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(rate_limited("anthropic/rate_limited.json"))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(json_response(200, "anthropic/answer.json"))
+        .mount(&server)
+        .await;
+
+    let answer = client(&server)
+        .complete(messages(), CompletionOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(answer, "The crate is at version 0.1.4-post1.");
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_persistent_rate_limit_is_an_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(rate_limited("anthropic/rate_limited.json"))
+        .expect(4)
+        .mount(&server)
+        .await;
+
+    let error = client(&server)
+        .complete(messages(), CompletionOptions::default())
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("429"), "{error}");
+    assert!(error.contains("rate_limit_error"), "{error}");
+}
+
+#[tokio::test]
+async fn test_malformed_response_is_an_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(json_response(200, "anthropic/malformed.json"))
+        .mount(&server)
+        .await;
+
+    let error = client(&server)
+        .complete_with_tools(messages(), with_tools(), None)
+        .await
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("Failed to parse Anthropic response"));
+}
+
+#[tokio::test]
+async fn test_prompt_caching_can_be_turned_off() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(json_response(200, "anthropic/answer.json"))
+        .mount(&server)
+        .await;
+
+    let cached = client(&server).with_prompt_caching(true);
+    cached
+        .complete_with_tools_and_finish(messages(), with_tools(), None)
+        .await
+        .unwrap();
+    let uncached = client(&server).with_prompt_caching(false);
+    uncached
+        .complete_with_tools_and_finish(messages(), with_tools(), None)
+        .await
+        .unwrap();
+
+    // The system prompt, last tool and latest prompt are cache breakpoints unless turned off
+    let requests = server.received_requests().await.unwrap();
+    let first: Value = requests[0].body_json().unwrap();
+    assert_eq!(first["system"][0]["cache_control"]["type"], "ephemeral");
+    assert_eq!(first["tools"][0]["cache_control"]["type"], "ephemeral");
+    assert_eq!(
+        first["messages"][0]["content"][0]["cache_control"]["type"],
+        "ephemeral"
+    );
+    let second: Value = requests[1].body_json().unwrap();
+    assert!(!second.to_string().contains("cache_control"));
+}
+
+#[tokio::test]
+async fn test_images_are_sent_as_image_blocks() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(json_response(200, "anthropic/answer.json"))
+        .mount(&server)
+        .await;
+
+    let image = ImageData {
+        media_type: "image/png".to_string(),
+        data: "iVBORw0KGgo=".to_string(),
+    };
+    let output = format!(
+        "Image shot.png (image/png, 8 bytes) is attached.\n{}",
+        image.to_block()
+    );
+    let mut conversation = messages();
+    conversation.push(Message::user(format!(
+        "Tool result for call toolu_1: {output}"
+    )));
+    let results = vec![ToolResult {
+        tool_call_id: "toolu_1".to_string(),
+        output,
+    }];
+    client(&server)
+        .complete_with_tools_and_finish(conversation, with_tools(), Some(results))
+        .await
+        .unwrap();
+
+    let request: Value = server.received_requests().await.unwrap()[0]
+        .body_json()
+        .unwrap();
+    let image_block = json!({
+        "type": "image",
+        "source": { "type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo=" }
+    });
+    // In the conversation...
+    let message = &request["messages"][1]["content"];
+    assert_eq!(
+        message[0]["text"],
+        "Tool result for call toolu_1: Image shot.png (image/png, 8 bytes) is attached.\n"
+    );
+    assert_eq!(message[1], image_block);
+    // ...and in the tool result
+    let tool_result = &request["messages"][3]["content"][0];
+    assert_eq!(tool_result["type"], "tool_result");
+    assert_eq!(tool_result["content"][1], image_block);
+    assert!(!request.to_string().contains("oli-image"));
+}
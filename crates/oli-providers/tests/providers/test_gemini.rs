@@ -0,0 +1,156 @@
+//! Gemini generateContent client against recorded responses
+
+use super::common::{json_response, messages, rate_limited, read_tool};
+use oli_providers::api_client::{ApiClient, CompletionOptions, FinishReason, ToolResult};
+use oli_providers::gemini::GeminiClient;
+use serde_json::{json, Value};
+use wiremock::matchers::{body_string_contains, method, path, query_param};
+use wiremock::{Mock, MockServer};
+
+fn client(server: &MockServer) -> GeminiClient {
+    GeminiClient::with_api_key("test-key".to_string(), None)
+        .unwrap()
+        .with_api_base(format!(
+            "{}/v1beta/models/gemini-2.5-pro-exp-03-25:generateContent?key=test-key",
+            server.uri()
+        ))
+}
+
+fn with_tools() -> CompletionOptions {
+    CompletionOptions {
+        tools: Some(read_tool()),
+        ..CompletionOptions::default()
+    }
+}
+
+#[tokio::test]
+async fn test_tool_call_round_trip() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(
+            "/v1beta/models/gemini-2.5-pro-exp-03-25:generateContent",
+        ))
+        .and(query_param("key", "test-key"))
+        .respond_with(json_response(200, "gemini/function_call.json"))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("function_response"))
+        .respond_with(json_response(200, "gemini/answer.json"))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    let client = client(&server);
+
+    let (content, tool_calls, finish) = client
+        .complete_with_tools_and_finish(messages(), with_tools(), None)
+        .await
+        .unwrap();
+    assert_eq!(content, "");
+    assert_eq!(finish, FinishReason::Stop);
+    let tool_calls = tool_calls.unwrap();
+    assert_eq!(tool_calls[0].name, "Read");
+    assert_eq!(
+        tool_calls[0].arguments,
+        json!({ "file_path": "Cargo.toml" })
+    );
+
+    let results = vec![ToolResult {
+        tool_call_id: tool_calls[0].id.clone().unwrap(),
+        output: "version = \"0.1.4-post1\"".to_string(),
+    }];
+    let (content, tool_calls, finish) = client
+        .complete_with_tools_and_finish(messages(), with_tools(), Some(results))
+        .await
+        .unwrap();
+    assert_eq!(content, "The crate is at version 0.1.4-post1.");
+    assert!(tool_calls.is_none());
+    assert_eq!(finish, FinishReason::Length);
+
+    let requests = server.received_requests().await.unwrap();
+    let first: Value = requests[0].body_json().unwrap();
+    assert_eq!(first["tools"][0]["functionDeclarations"][0]["name"], "Read");
+    let second: Value = requests[1].body_json().unwrap();
+    let response = second["contents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|content| content["parts"].as_array().unwrap())
+        .find_map(|part| part.get("function_response"))
+        .unwrap();
+    assert_eq!(response["response"]["content"], "version = \"0.1.4-post1\"");
+}
+
+#[tokio::test]
+async fn test_streaming_arrives_as_one_chunk() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(json_response(200, "gemini/answer.json"))
+        .mount(&server)
+        .await;
+
+    let mut deltas = Vec::new();
+    let (content, _, finish) = client(&server)
+        .complete_streaming_with_finish(messages(), CompletionOptions::default(), &mut |text| {
+            deltas.push(text.to_string())
+        })
+        .await
+        .unwrap();
+    assert_eq!(deltas, vec!["The crate is at version 0.1.4-post1."]);
+    assert_eq!(content, deltas[0]);
+    assert_eq!(finish, FinishReason::Length);
+}
+
+#[tokio::test]
+async fn test_rate_limit_is_retried() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(rate_limited("gemini/rate_limited.json"))
+        .up_to_n_times(2)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(json_response(200, "gemini/answer.json"))
+        .mount(&server)
+        .await;
+
+    let answer = client(&server)
+        .complete(messages(), CompletionOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(answer, "The crate is at version 0.1.4-post1.");
+    assert_eq!(server.received_requests().await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_malformed_responses() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("functionDeclarations"))
+        .respond_with(json_response(200, "anthropic/malformed.json"))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(json_response(200, "gemini/no_candidates.json"))
+        .mount(&server)
+        .await;
+    let client = client(&server);
+
+    // A blocked prompt comes back without candidates
+    let error = client
+        .complete(messages(), CompletionOptions::default())
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("No response candidates"));
+
+    let error = client
+        .complete_with_tools(messages(), with_tools(), None)
+        .await
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("Failed to parse Gemini response"));
+}
@@ -0,0 +1,255 @@
+//! Ollama chat client against recorded responses
+
+use super::common::{fixture, json_response, messages, read_tool};
+use oli_providers::api_client::{ApiClient, CompletionOptions, FinishReason};
+use oli_providers::ollama::OllamaClient;
+use serde_json::{json, Value};
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn client(server: &MockServer) -> OllamaClient {
+    OllamaClient::with_base_url("llama3.2".to_string(), server.uri()).unwrap()
+}
+
+/// A response streaming a recorded body, one JSON object per line
+fn ndjson_response(name: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(fixture(name), "application/x-ndjson")
+}
+
+fn with_tools() -> CompletionOptions {
+    CompletionOptions {
+        tools: Some(read_tool()),
+        ..CompletionOptions::default()
+    }
+}
+
+#[tokio::test]
+async fn test_tool_call_round_trip() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/chat"))
+        .and(body_string_contains("\"tools\""))
+        .respond_with(json_response(200, "ollama/tool_calls.json"))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/chat"))
+        .respond_with(json_response(200, "ollama/answer.json"))
+        .mount(&server)
+        .await;
+    let client = client(&server);
+
+    // Ollama sends arguments as an object and calls without ids, so ids are made up
+    let (content, tool_calls, finish) = client
+        .complete_with_tools_and_finish(messages(), with_tools(), None)
+        .await
+        .unwrap();
+    assert_eq!(content, "");
+    assert_eq!(finish, FinishReason::ToolUse);
+    let tool_calls = tool_calls.unwrap();
+    assert_eq!(tool_calls[0].name, "Read");
+    assert_eq!(
+        tool_calls[0].arguments,
+        json!({ "file_path": "Cargo.toml" })
+    );
+    assert!(tool_calls[0]
+        .id
+        .as_deref()
+        .unwrap()
+        .starts_with("ollama-tool-"));
+
+    let answer = client
+        .complete(messages(), CompletionOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(answer, "The crate is at version 0.1.4-post1.");
+
+    let requests = server.received_requests().await.unwrap();
+    let first: Value = requests[0].body_json().unwrap();
+    assert_eq!(first["model"], "llama3.2");
+    assert_eq!(first["stream"], false);
+    assert_eq!(first["tools"][0]["function"]["name"], "Read");
+}
+
+#[tokio::test]
+async fn test_streaming_forwards_each_delta() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/chat"))
+        .respond_with(ndjson_response("ollama/stream.ndjson"))
+        .mount(&server)
+        .await;
+
+    let mut deltas = Vec::new();
+    let (content, usage, finish) = client(&server)
+        .complete_streaming_with_finish(messages(), CompletionOptions::default(), &mut |text| {
+            deltas.push(text.to_string())
+        })
+        .await
+        .unwrap();
+    assert_eq!(deltas, vec!["The crate is ", "at version", " 0.1.4-post1."]);
+    assert_eq!(content, "The crate is at version 0.1.4-post1.");
+    // The finish reason and token counts come from the final, done line
+    assert_eq!(finish, FinishReason::Length);
+    assert_eq!((usage.input_tokens, usage.output_tokens), (260, 12));
+
+    let requests = server.received_requests().await.unwrap();
+    let request: Value = requests[0].body_json().unwrap();
+    assert_eq!(request["stream"], true);
+    assert!(request.get("tools").is_none());
+}
+
+#[tokio::test]
+async fn test_streaming_with_tools() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/chat"))
+        .respond_with(ndjson_response("ollama/stream_tool_calls.ndjson"))
+        .mount(&server)
+        .await;
+
+    let mut deltas = Vec::new();
+    let (_, tool_calls, finish) = client(&server)
+        .complete_with_tools_streaming_and_finish(messages(), with_tools(), None, &mut |text| {
+            deltas.push(text.to_string())
+        })
+        .await
+        .unwrap();
+    assert_eq!(deltas, vec!["Checking the manifest."]);
+    assert_eq!(finish, FinishReason::ToolUse);
+    let tool_calls = tool_calls.unwrap();
+    assert_eq!(tool_calls[0].name, "Read");
+    assert_eq!(
+        tool_calls[0].arguments,
+        json!({ "file_path": "Cargo.toml" })
+    );
+
+    let requests = server.received_requests().await.unwrap();
+    let request: Value = requests[0].body_json().unwrap();
+    assert_eq!(request["stream"], true);
+    assert_eq!(request["tools"][0]["function"]["name"], "Read");
+}
+
+#[tokio::test]
+async fn test_stream_error_is_reported() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "{\"model\":\"llama3.2\",\"message\":{\"role\":\"assistant\",\"content\":\"Hel\"},\"done\":false}\n{\"error\":\"model runner has unexpectedly stopped\"}\n",
+            "application/x-ndjson",
+        ))
+        .mount(&server)
+        .await;
+
+    let error = client(&server)
+        .complete_streaming_with_finish(messages(), CompletionOptions::default(), &mut |_| {})
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("unexpectedly stopped"), "{error}");
+}
+
+#[tokio::test]
+async fn test_error_status_is_reported() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(json_response(404, "ollama/model_not_found.json"))
+        .mount(&server)
+        .await;
+
+    let error = client(&server)
+        .complete(messages(), CompletionOptions::default())
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("404"), "{error}");
+    assert!(error.contains("try pulling it first"), "{error}");
+}
+
+#[tokio::test]
+async fn test_malformed_responses() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("\"tools\""))
+        .respond_with(
+            // Cut off mid-way through a multi-byte character's worth of text
+            ResponseTemplate::new(200).set_body_raw(
+                "{\"message\": ".to_string() + &"é".repeat(120),
+                "application/json",
+            ),
+        )
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(json_response(200, "ollama/partial.json"))
+        .mount(&server)
+        .await;
+    let client = client(&server);
+
+    // A response missing fields is read as far as it goes, keeping non-text content
+    let (content, finish) = client
+        .complete_with_finish(messages(), CompletionOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        content,
+        "{\"answer\":\"Structured output instead of text\"}"
+    );
+    assert_eq!(finish, FinishReason::Length);
+
+    // A body that isn't JSON is an error quoting the start of it
+    let error = client
+        .complete_with_tools(messages(), with_tools(), None)
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("Failed to parse Ollama tool response as JSON"),
+        "{error}"
+    );
+    assert!(error.contains("... [truncated]"), "{error}");
+}
+
+#[tokio::test]
+async fn test_pull_reports_progress() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/pull"))
+        .and(body_string_contains("\"llama3.2\""))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(fixture("ollama/pull.ndjson"), "application/x-ndjson"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/pull"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "{\"status\":\"pulling manifest\"}\n{\"error\":\"pull model manifest: file does not exist\"}\n",
+            "application/x-ndjson",
+        ))
+        .mount(&server)
+        .await;
+    let client = client(&server);
+
+    let mut updates = Vec::new();
+    client
+        .pull_model("llama3.2", |progress| updates.push(progress.clone()))
+        .await
+        .unwrap();
+    assert_eq!(updates.len(), 7);
+    assert_eq!(updates[0].status, "pulling manifest");
+    assert_eq!(updates[0].fraction(), None);
+    assert_eq!(updates[2].fraction(), Some(0.5));
+    assert_eq!(updates[6].status, "success");
+
+    // Ollama's own message explains a model that doesn't exist
+    let error = client
+        .pull_model("no-such-model", |_| {})
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("file does not exist"), "{error}");
+}
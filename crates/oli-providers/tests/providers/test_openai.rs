@@ -0,0 +1,319 @@
+//! OpenAI Chat Completions client against recorded responses
+
+use super::common::{json_response, messages, rate_limited, read_tool, sse_response};
+use oli_providers::api_client::{
+    ApiClient, CompletionOptions, FinishReason, ImageData, Message, ToolChoice, ToolResult,
+};
+use oli_providers::openai::{AzureDeployment, OpenAIClient, AZURE_OPENAI_API_VERSION};
+use serde_json::{json, Value};
+use wiremock::matchers::{body_string_contains, header, method, path, query_param};
+use wiremock::{Mock, MockServer};
+
+fn client(server: &MockServer) -> OpenAIClient {
+    OpenAIClient::with_api_key("test-key".to_string(), None)
+        .unwrap()
+        .with_api_base(format!("{}/v1/chat/completions", server.uri()))
+}
+
+fn with_tools() -> CompletionOptions {
+    CompletionOptions {
+        tools: Some(read_tool()),
+        tool_choice: ToolChoice::Any,
+        parallel_tool_calls: false,
+        ..CompletionOptions::default()
+    }
+}
+
+#[tokio::test]
+async fn test_tool_call_round_trip() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(header("authorization", "Bearer test-key"))
+        .respond_with(json_response(200, "openai/tool_calls.json"))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("call_abc123"))
+        .respond_with(json_response(200, "openai/answer.json"))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    let client = client(&server);
+
+    let (content, tool_calls, finish) = client
+        .complete_with_tools_and_finish(messages(), with_tools(), None)
+        .await
+        .unwrap();
+    assert_eq!(content, "");
+    assert_eq!(finish, FinishReason::ToolUse);
+    let tool_calls = tool_calls.unwrap();
+    assert_eq!(tool_calls[0].id.as_deref(), Some("call_abc123"));
+    assert_eq!(
+        tool_calls[0].arguments,
+        json!({ "file_path": "Cargo.toml" })
+    );
+    // Arguments that aren't valid JSON are kept as text so the agent can repair them
+    assert_eq!(tool_calls[1].name, "Glob");
+    assert_eq!(
+        tool_calls[1].arguments,
+        Value::String("{\"pattern\":\"**/*.rs\"".to_string())
+    );
+
+    // Results travel in the conversation as text, as the agent sends them; the
+    // results passed alongside must not become `tool` messages without a call
+    let mut conversation = messages();
+    conversation.push(Message::user(
+        "Tool result for call call_abc123: version = \"0.1.4-post1\"".to_string(),
+    ));
+    let results = vec![ToolResult {
+        tool_call_id: "call_abc123".to_string(),
+        output: "version = \"0.1.4-post1\"".to_string(),
+    }];
+    let (content, tool_calls, finish) = client
+        .complete_with_tools_and_finish(conversation, with_tools(), Some(results))
+        .await
+        .unwrap();
+    assert_eq!(content, "The crate is at version 0.1.4-post1.");
+    assert!(tool_calls.is_none());
+    assert_eq!(finish, FinishReason::Stop);
+
+    let requests = server.received_requests().await.unwrap();
+    let first: Value = requests[0].body_json().unwrap();
+    assert_eq!(first["tools"][0]["function"]["name"], "Read");
+    assert_eq!(first["tool_choice"], "required");
+    assert_eq!(first["parallel_tool_calls"], false);
+    let second: Value = requests[1].body_json().unwrap();
+    let roles: Vec<&str> = second["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|message| message["role"].as_str().unwrap())
+        .collect();
+    assert_eq!(roles, vec!["system", "user", "user"]);
+}
+
+#[tokio::test]
+async fn test_streaming_chunks() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("\"include_usage\":true"))
+        .respond_with(sse_response("openai/stream.sse"))
+        .mount(&server)
+        .await;
+
+    let mut deltas = Vec::new();
+    let (content, usage, finish) = client(&server)
+        .complete_streaming_with_finish(messages(), CompletionOptions::default(), &mut |text| {
+            deltas.push(text.to_string())
+        })
+        .await
+        .unwrap();
+    assert_eq!(deltas, vec!["Hello", ", world!"]);
+    assert_eq!(content, "Hello, world!");
+    assert_eq!((usage.input_tokens, usage.output_tokens), (18, 4));
+    assert_eq!(finish, FinishReason::Stop);
+}
+
+#[tokio::test]
+async fn test_streaming_tool_calls() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("\"stream\":true"))
+        .respond_with(sse_response("openai/stream_tool_calls.sse"))
+        .mount(&server)
+        .await;
+
+    let mut deltas = Vec::new();
+    let (content, tool_calls, finish) = client(&server)
+        .complete_with_tools_streaming_and_finish(messages(), with_tools(), None, &mut |text| {
+            deltas.push(text.to_string())
+        })
+        .await
+        .unwrap();
+    assert!(deltas.is_empty());
+    assert_eq!(content, "");
+    assert_eq!(finish, FinishReason::ToolUse);
+
+    // Argument fragments are joined per call
+    let tool_calls = tool_calls.unwrap();
+    assert_eq!(tool_calls.len(), 2);
+    assert_eq!(tool_calls[0].id.as_deref(), Some("call_abc123"));
+    assert_eq!(
+        tool_calls[0].arguments,
+        json!({ "file_path": "Cargo.toml" })
+    );
+    assert_eq!(tool_calls[1].id.as_deref(), Some("call_def456"));
+    assert_eq!(tool_calls[1].arguments, json!({ "file_path": "README.md" }));
+
+    let request: Value = server.received_requests().await.unwrap()[0]
+        .body_json()
+        .unwrap();
+    assert_eq!(request["tools"][0]["function"]["name"], "Read");
+}
+
+#[tokio::test]
+async fn test_persistent_rate_limit_is_an_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(rate_limited("openai/rate_limited.json"))
+        .expect(4)
+        .mount(&server)
+        .await;
+
+    let error = client(&server)
+        .complete(messages(), CompletionOptions::default())
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("429"), "{error}");
+    assert!(error.contains("rate_limit_exceeded"), "{error}");
+}
+
+#[tokio::test]
+async fn test_malformed_responses() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("tools"))
+        .respond_with(json_response(200, "openai/no_choices.json"))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(json_response(200, "anthropic/malformed.json"))
+        .mount(&server)
+        .await;
+    let client = client(&server);
+
+    // A response with no choices is an empty answer when tools were offered...
+    let (content, tool_calls, _) = client
+        .complete_with_tools_and_finish(messages(), with_tools(), None)
+        .await
+        .unwrap();
+    assert!(content.is_empty() && tool_calls.is_none());
+
+    // ...and a body that isn't JSON is an error
+    let error = client
+        .complete(messages(), CompletionOptions::default())
+        .await
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("Failed to parse OpenAI response"));
+}
+
+#[tokio::test]
+async fn test_azure_deployment_requests() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/openai/deployments/gpt-4o-mini/chat/completions"))
+        .and(query_param("api-version", AZURE_OPENAI_API_VERSION))
+        .and(header("api-key", "azure-key"))
+        .respond_with(json_response(200, "openai/answer.json"))
+        .mount(&server)
+        .await;
+
+    // A trailing slash on the endpoint is fine
+    let azure = AzureDeployment::new(&format!("{}/", server.uri()), "gpt-4o-mini");
+    assert_eq!(
+        azure.chat_completions_url(),
+        format!(
+            "{}/openai/deployments/gpt-4o-mini/chat/completions?api-version={AZURE_OPENAI_API_VERSION}",
+            server.uri()
+        )
+    );
+    let answer = OpenAIClient::with_azure("azure-key".to_string(), azure)
+        .unwrap()
+        .complete(messages(), CompletionOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(answer, "The crate is at version 0.1.4-post1.");
+
+    // The key goes in the api-key header only, never as a bearer token
+    let request = &server.received_requests().await.unwrap()[0];
+    assert!(request.headers.get("authorization").is_none());
+
+    let preview = AzureDeployment::new("https://example.openai.azure.com", "gpt-4o")
+        .with_api_version("2025-01-01-preview");
+    assert!(preview
+        .chat_completions_url()
+        .ends_with("/gpt-4o/chat/completions?api-version=2025-01-01-preview"));
+}
+
+#[tokio::test]
+async fn test_openai_compatible_requests() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_string_contains("\"deepseek-chat\""))
+        .respond_with(json_response(200, "openai/answer.json"))
+        .mount(&server)
+        .await;
+
+    // The base URL is the API's root, with or without a trailing slash
+    let answer = OpenAIClient::with_compatible(
+        &format!("{}/v1/", server.uri()),
+        Some("deepseek-key".to_string()),
+        "deepseek-chat".to_string(),
+    )
+    .unwrap()
+    .complete(messages(), CompletionOptions::default())
+    .await
+    .unwrap();
+    assert_eq!(answer, "The crate is at version 0.1.4-post1.");
+
+    // Local servers take no key, so none is sent
+    OpenAIClient::with_compatible(
+        &format!("{}/v1/chat/completions", server.uri()),
+        None,
+        "deepseek-chat".to_string(),
+    )
+    .unwrap()
+    .complete(messages(), CompletionOptions::default())
+    .await
+    .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(
+        requests[0].headers.get("authorization").unwrap(),
+        "Bearer deepseek-key"
+    );
+    assert!(requests[1].headers.get("authorization").is_none());
+}
+
+#[tokio::test]
+async fn test_images_are_sent_as_image_parts() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(json_response(200, "openai/answer.json"))
+        .mount(&server)
+        .await;
+
+    let image = ImageData {
+        media_type: "image/jpeg".to_string(),
+        data: "/9j/4AAQ".to_string(),
+    };
+    let mut conversation = messages();
+    conversation.push(Message::user(format!(
+        "Tool result for call call_1: Image photo.jpg is attached.\n{}",
+        image.to_block()
+    )));
+    client(&server)
+        .complete_with_tools_and_finish(conversation, with_tools(), None)
+        .await
+        .unwrap();
+
+    let request: Value = server.received_requests().await.unwrap()[0]
+        .body_json()
+        .unwrap();
+    // Plain messages stay plain text
+    assert!(request["messages"][1]["content"].is_string());
+    assert_eq!(
+        request["messages"][2]["content"],
+        json!([
+            { "type": "text", "text": "Tool result for call call_1: Image photo.jpg is attached.\n" },
+            { "type": "image_url", "image_url": { "url": "data:image/jpeg;base64,/9j/4AAQ" } }
+        ])
+    );
+}
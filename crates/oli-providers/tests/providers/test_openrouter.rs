@@ -0,0 +1,98 @@
+//! OpenRouter client against recorded responses
+
+use super::common::{json_response, messages, read_tool};
+use oli_providers::api_client::{ApiClient, CompletionOptions, FinishReason};
+use oli_providers::openrouter::{OpenRouterClient, OPENROUTER_MODEL_NAME};
+use serde_json::{json, Value};
+use wiremock::matchers::{body_string_contains, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn client(server: &MockServer, model: Option<&str>) -> OpenRouterClient {
+    OpenRouterClient::with_api_key("sk-or-test".to_string(), model.map(str::to_string))
+        .unwrap()
+        .with_api_base(format!("{}/api/v1", server.uri()))
+}
+
+#[tokio::test]
+async fn test_requests_select_the_model_by_id() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/chat/completions"))
+        .and(header("authorization", "Bearer sk-or-test"))
+        .and(body_string_contains("meta-llama/llama-3.3-70b-instruct"))
+        .respond_with(json_response(200, "openai/tool_calls.json"))
+        .mount(&server)
+        .await;
+
+    let options = CompletionOptions {
+        tools: Some(read_tool()),
+        ..CompletionOptions::default()
+    };
+    let (_, tool_calls, finish) = client(&server, Some("meta-llama/llama-3.3-70b-instruct"))
+        .complete_with_tools_and_finish(messages(), options, None)
+        .await
+        .unwrap();
+    assert_eq!(finish, FinishReason::ToolUse);
+    let tool_calls = tool_calls.unwrap();
+    assert_eq!(tool_calls[0].name, "Read");
+    assert_eq!(
+        tool_calls[0].arguments,
+        json!({ "file_path": "Cargo.toml" })
+    );
+
+    let request: Value = server.received_requests().await.unwrap()[0]
+        .body_json()
+        .unwrap();
+    assert_eq!(request["tools"][0]["function"]["name"], "Read");
+}
+
+#[tokio::test]
+async fn test_default_model() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/chat/completions"))
+        .respond_with(json_response(200, "openai/answer.json"))
+        .mount(&server)
+        .await;
+
+    let answer = client(&server, None)
+        .complete(messages(), CompletionOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(answer, "The crate is at version 0.1.4-post1.");
+    let request: Value = server.received_requests().await.unwrap()[0]
+        .body_json()
+        .unwrap();
+    assert_eq!(request["model"], OPENROUTER_MODEL_NAME);
+}
+
+#[tokio::test]
+async fn test_list_models() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [
+                {
+                    "id": "deepseek/deepseek-chat",
+                    "name": "DeepSeek: DeepSeek V3",
+                    "context_length": 163840,
+                    "supported_parameters": ["tools", "tool_choice", "temperature"]
+                },
+                {
+                    "id": "example/no-tools",
+                    "name": "Example: No Tools",
+                    "supported_parameters": ["temperature"]
+                }
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    let models = client(&server, None).list_models().await.unwrap();
+    assert_eq!(models.len(), 2);
+    assert_eq!(models[0].id, "deepseek/deepseek-chat");
+    assert_eq!(models[0].context_length, Some(163840));
+    assert!(models[0].supports_tools());
+    assert!(!models[1].supports_tools());
+}
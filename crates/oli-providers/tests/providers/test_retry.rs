@@ -0,0 +1,128 @@
+//! Retrying rate limits, overloaded servers and network errors, shared by all clients
+
+use super::common::{json_response, messages, rate_limited};
+use oli_providers::anthropic::AnthropicClient;
+use oli_providers::api_client::{ApiClient, CompletionOptions};
+use oli_providers::openai::OpenAIClient;
+use oli_providers::retry::{retry_after, with_retry_listener, RetryEvent, RetryPolicy};
+use reqwest::header::{HeaderMap, HeaderValue};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use wiremock::{Mock, MockServer};
+
+fn openai(server: &MockServer) -> OpenAIClient {
+    OpenAIClient::with_api_key("test-key".to_string(), None)
+        .unwrap()
+        .with_api_base(format!("{}/v1/chat/completions", server.uri()))
+}
+
+#[test]
+fn test_backoff_doubles_with_jitter_up_to_the_limit() {
+    let policy = RetryPolicy {
+        max_retries: 5,
+        initial_delay_ms: 1000,
+        max_delay_ms: 3000,
+    };
+    for (retry, full) in [(0, 1000), (1, 2000), (2, 3000), (10, 3000)] {
+        let delay = policy.delay(retry, None).unwrap();
+        assert!(delay >= Duration::from_millis(full / 2), "{delay:?}");
+        assert!(delay <= Duration::from_millis(full), "{delay:?}");
+    }
+
+    // The server's wait is used as is, unless it's longer than the limit
+    assert_eq!(
+        policy.delay(0, Some(Duration::from_secs(2))),
+        Some(Duration::from_secs(2))
+    );
+    assert_eq!(policy.delay(0, Some(Duration::from_secs(4))), None);
+}
+
+#[test]
+fn test_retry_after_headers() {
+    let mut headers = HeaderMap::new();
+    assert_eq!(retry_after(&headers), None);
+    headers.insert("retry-after", HeaderValue::from_static("7"));
+    assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+    // The millisecond header OpenAI sends is more precise, so takes precedence
+    headers.insert("retry-after-ms", HeaderValue::from_static("250"));
+    assert_eq!(retry_after(&headers), Some(Duration::from_millis(250)));
+}
+
+#[tokio::test]
+async fn test_overloaded_request_is_retried_and_reported() {
+    let server = MockServer::start().await;
+    Mock::given(wiremock::matchers::method("POST"))
+        .respond_with(
+            json_response(529, "anthropic/rate_limited.json").insert_header("retry-after", "0"),
+        )
+        .up_to_n_times(2)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(wiremock::matchers::method("POST"))
+        .respond_with(json_response(200, "anthropic/answer.json"))
+        .mount(&server)
+        .await;
+    let client = AnthropicClient::with_api_key("test-key".to_string(), None)
+        .unwrap()
+        .with_api_base(format!("{}/v1/messages", server.uri()));
+
+    let events = Arc::new(Mutex::new(Vec::<RetryEvent>::new()));
+    let recorded = events.clone();
+    let answer = with_retry_listener(
+        Arc::new(move |event: &RetryEvent| recorded.lock().unwrap().push(event.clone())),
+        client.complete(messages(), CompletionOptions::default()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(answer, "The crate is at version 0.1.4-post1.");
+
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].provider, "Anthropic");
+    assert_eq!(events[1].retry, 2);
+    assert_eq!(
+        events[0].message(),
+        "Anthropic API request failed (529 Overloaded); retrying in 0.0s (retry 1 of 3)"
+    );
+}
+
+#[tokio::test]
+async fn test_retrying_can_be_turned_off() {
+    let server = MockServer::start().await;
+    Mock::given(wiremock::matchers::method("POST"))
+        .respond_with(rate_limited("openai/rate_limited.json"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let options = CompletionOptions {
+        retry: RetryPolicy::none(),
+        ..CompletionOptions::default()
+    };
+    let error = openai(&server)
+        .complete(messages(), options)
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("429"), "{error}");
+}
+
+#[tokio::test]
+async fn test_long_retry_after_returns_the_error() {
+    let server = MockServer::start().await;
+    Mock::given(wiremock::matchers::method("POST"))
+        .respond_with(
+            json_response(429, "openai/rate_limited.json").insert_header("retry-after", "3600"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let error = openai(&server)
+        .complete(messages(), CompletionOptions::default())
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("rate_limit_exceeded"), "{error}");
+}
@@ -0,0 +1,31 @@
+//! Embed the oli agent in a Rust program without the TUI or JSON-RPC server.
+//!
+//! Usage: `ANTHROPIC_API_KEY=... cargo run --example embed -- "What does src/lib.rs export?"`
+
+use anyhow::Result;
+use oli_server::{Client, LLMProvider, ToolPolicy};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let prompt = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "List the files in the current directory".to_string());
+
+    let working_dir = std::env::current_dir()?.to_string_lossy().to_string();
+
+    // Only allow tools that read, so the example never changes files
+    let mut client = Client::new(LLMProvider::Anthropic)
+        .with_working_directory(working_dir)
+        .with_tool_policy(ToolPolicy::ReadOnly);
+
+    for model in client.list_models() {
+        println!("available model: {} ({})", model.name, model.file_name);
+    }
+
+    let answer = client
+        .send_prompt(&prompt, |progress| eprintln!("  {progress}"))
+        .await?;
+    println!("{answer}");
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+//! Build the per-tool accuracy report from tool calls recorded during a benchmark run.
+//!
+//! Usage: `cargo run --example tool_report -- benchmark_results/tool_tests/tool_calls.jsonl benchmark_results/tool_tests`
+//!
+//! Writes `tool_report.md` and `tool_report.json` to the output directory.
+
+use anyhow::{Context, Result};
+use oli_server::agent::tool_stats::{ToolStatsLog, ToolUsageReport};
+use std::path::PathBuf;
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let input = args
+        .next()
+        .context("Usage: tool_report <tool_calls.jsonl> [output_dir]")?;
+    let output_dir = PathBuf::from(args.next().unwrap_or_else(|| ".".to_string()));
+
+    let records = ToolStatsLog::new(PathBuf::from(input)).read_all()?;
+    let report = ToolUsageReport::from_records(&records);
+
+    std::fs::create_dir_all(&output_dir)?;
+    std::fs::write(output_dir.join("tool_report.md"), report.to_markdown())?;
+    std::fs::write(output_dir.join("tool_report.json"), report.to_json()?)?;
+
+    print!("{}", report.to_markdown());
+    Ok(())
+}
@@ -0,0 +1,227 @@
+use crate::agent::permissions::{PermissionLevel, RuleScope};
+use crate::communication::rpc::RpcServer;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+/// Environment variable with a URL that pending tool requests are POSTed to
+pub const APPROVAL_WEBHOOK_ENV: &str = "OLI_APPROVAL_WEBHOOK";
+/// Environment variable with a shell command that receives pending tool requests on stdin
+pub const APPROVAL_COMMAND_ENV: &str = "OLI_APPROVAL_COMMAND";
+/// Environment variable overriding how long to wait for a decision, in seconds
+pub const APPROVAL_TIMEOUT_ENV: &str = "OLI_APPROVAL_TIMEOUT_SECS";
+
+/// Request a client that declared `supports_permission_prompts` answers with
+/// whether a tool call may run, when no approval backend decides for it
+pub const REQUEST_PERMISSION_METHOD: &str = "oli/requestPermission";
+
+const DEFAULT_APPROVAL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long to wait for the user to answer a permission prompt
+const PERMISSION_PROMPT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// A pending tool execution sent to the approval backend
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    pub tool: String,
+    pub arguments: Value,
+    pub working_directory: Option<String>,
+    /// Unix timestamp in seconds
+    pub requested_at: u64,
+}
+
+/// Decision returned by the approval backend
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ApprovalDecision {
+    pub allow: bool,
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Allow the tool, or the exact Bash command, from now on without asking
+    #[serde(default)]
+    pub remember: Option<RuleScope>,
+}
+
+impl ApprovalDecision {
+    pub fn allow() -> Self {
+        Self {
+            allow: true,
+            reason: None,
+            remember: None,
+        }
+    }
+
+    pub fn deny(reason: impl Into<String>) -> Self {
+        Self {
+            allow: false,
+            reason: Some(reason.into()),
+            remember: None,
+        }
+    }
+
+    /// Parse a decision from a backend response. Accepts `{"allow": true}` or
+    /// `{"decision": "allow" | "deny", "reason": "..."}`, and on an allow
+    /// `"remember": "tool" | "command"` to always allow the tool or command.
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let reason = value["reason"].as_str().map(str::to_string);
+
+        let allow = match value["allow"].as_bool() {
+            Some(allow) => allow,
+            None => match value["decision"].as_str().map(str::to_lowercase).as_deref() {
+                Some("allow") | Some("approve") | Some("approved") => true,
+                Some("deny") | Some("reject") | Some("rejected") => false,
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "Approval response must contain 'allow' or 'decision': {}",
+                        value
+                    ))
+                }
+            },
+        };
+        let remember = match value["remember"].as_str() {
+            Some(scope) if allow => Some(RuleScope::parse(scope)?),
+            _ => None,
+        };
+
+        Ok(Self {
+            allow,
+            reason,
+            remember,
+        })
+    }
+}
+
+/// Ask the user of the connected client whether a call may run. Errors, timeouts
+/// and malformed answers deny it.
+pub fn ask_client(
+    server: &RpcServer,
+    request: &ApprovalRequest,
+    call_id: Option<&str>,
+) -> ApprovalDecision {
+    let mut params = serde_json::to_value(request).unwrap_or_default();
+    params["call_id"] = serde_json::json!(call_id);
+    match server.request_client(REQUEST_PERMISSION_METHOD, params, PERMISSION_PROMPT_TIMEOUT) {
+        Ok(answer) => ApprovalDecision::from_json(&answer)
+            .unwrap_or_else(|e| ApprovalDecision::deny(format!("invalid answer: {e}"))),
+        Err(e) => ApprovalDecision::deny(format!("{e}")),
+    }
+}
+
+/// External system that approves or denies risky tool executions, for
+/// centralized review of agent actions in team environments
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApprovalBackend {
+    /// POST the request as JSON and read a JSON decision from the response
+    Webhook { url: String, timeout: Duration },
+    /// Run a shell command with the request as JSON on stdin. A JSON decision on
+    /// stdout takes precedence; otherwise exit status 0 allows and anything else denies.
+    Command { command: String, timeout: Duration },
+}
+
+impl ApprovalBackend {
+    /// Read the approval backend from the environment, preferring the webhook
+    pub fn from_env() -> Option<Self> {
+        let timeout = std::env::var(APPROVAL_TIMEOUT_ENV)
+            .ok()
+            .and_then(|secs| secs.trim().parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_APPROVAL_TIMEOUT);
+
+        let non_empty = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+
+        if let Some(url) = non_empty(APPROVAL_WEBHOOK_ENV) {
+            Some(Self::Webhook { url, timeout })
+        } else {
+            non_empty(APPROVAL_COMMAND_ENV).map(|command| Self::Command { command, timeout })
+        }
+    }
+
+    /// Whether a tool must be approved before it runs when nothing configures its level
+    pub fn requires_approval(tool_name: &str) -> bool {
+        PermissionLevel::default_for(tool_name) == PermissionLevel::Ask
+    }
+
+    /// Ask the backend for a decision. Errors and timeouts deny the request.
+    pub async fn decide(&self, request: &ApprovalRequest) -> ApprovalDecision {
+        let timeout = match self {
+            Self::Webhook { timeout, .. } | Self::Command { timeout, .. } => *timeout,
+        };
+
+        match tokio::time::timeout(timeout, self.request_decision(request)).await {
+            Ok(Ok(decision)) => decision,
+            Ok(Err(e)) => ApprovalDecision::deny(format!("approval backend error: {e}")),
+            Err(_) => ApprovalDecision::deny(format!(
+                "no approval decision within {}s",
+                timeout.as_secs()
+            )),
+        }
+    }
+
+    async fn request_decision(&self, request: &ApprovalRequest) -> Result<ApprovalDecision> {
+        match self {
+            Self::Webhook { url, .. } => {
+                let response = reqwest::Client::new()
+                    .post(url)
+                    .json(request)
+                    .send()
+                    .await
+                    .context("Failed to reach approval webhook")?;
+
+                if !response.status().is_success() {
+                    return Ok(ApprovalDecision::deny(format!(
+                        "approval webhook returned {}",
+                        response.status()
+                    )));
+                }
+
+                let body: Value = response
+                    .json()
+                    .await
+                    .context("Approval webhook returned invalid JSON")?;
+                ApprovalDecision::from_json(&body)
+            }
+            Self::Command { command, .. } => {
+                let mut child = tokio::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::null())
+                    .kill_on_drop(true)
+                    .spawn()
+                    .context("Failed to start approval command")?;
+
+                // Write the request while the output is read, so a large request
+                // can't fill the pipe. A command that decides by exit status alone
+                // may exit without reading it, so a broken pipe is no error.
+                if let Some(mut stdin) = child.stdin.take() {
+                    let input = serde_json::to_vec(request)?;
+                    tokio::spawn(async move {
+                        let _ = stdin.write_all(&input).await;
+                    });
+                }
+
+                let output = child.wait_with_output().await?;
+                let stdout = String::from_utf8_lossy(&output.stdout);
+
+                // A JSON decision on stdout wins over the exit status
+                if let Ok(value) = serde_json::from_str::<Value>(stdout.trim()) {
+                    if let Ok(decision) = ApprovalDecision::from_json(&value) {
+                        return Ok(decision);
+                    }
+                }
+
+                if output.status.success() {
+                    Ok(ApprovalDecision::allow())
+                } else {
+                    Ok(ApprovalDecision::deny(format!(
+                        "approval command exited with {}",
+                        output.status
+                    )))
+                }
+            }
+        }
+    }
+}
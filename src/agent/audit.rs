@@ -0,0 +1,96 @@
+use crate::communication::capabilities::RISKY_TOOLS;
+use crate::tools::shell::EnvSnapshot;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the audit log location
+pub const AUDIT_LOG_ENV: &str = "OLI_AUDIT_LOG";
+
+/// One mutating tool execution, as written to the audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// RFC 3339 timestamp of the execution
+    pub timestamp: String,
+    pub task_id: Option<String>,
+    pub tool: String,
+    pub arguments: Value,
+    /// Hash of the diff previewed for Edit and Write
+    pub diff_hash: Option<String>,
+    /// "allowed" or "denied"
+    pub decision: String,
+    pub reason: Option<String>,
+    /// "success" or "error" for executed tools, none when denied
+    pub outcome: Option<String>,
+    /// Shell and PATH/virtualenv/nvm context a Bash command ran with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<EnvSnapshot>,
+}
+
+/// Append-only JSON lines log of every Edit, Write and Bash execution,
+/// kept separately from debug logs for compliance review
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// The configured audit log, `~/.oli/audit.jsonl` unless overridden by `OLI_AUDIT_LOG`
+    pub fn from_env() -> Option<Self> {
+        match std::env::var(AUDIT_LOG_ENV) {
+            Ok(path) if !path.trim().is_empty() => Some(Self::new(PathBuf::from(path))),
+            _ => dirs::home_dir().map(|home| Self::new(home.join(".oli").join("audit.jsonl"))),
+        }
+    }
+
+    /// Whether executions of a tool are recorded
+    pub fn is_audited(tool_name: &str) -> bool {
+        RISKY_TOOLS.contains(&tool_name)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an entry to the log, creating the file and its directory if needed
+    pub fn record(&self, entry: &AuditEntry) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open audit log {}", self.path.display()))?;
+
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+
+    /// Read the most recent entries, oldest first. Malformed lines are skipped.
+    pub fn recent(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let file = std::fs::File::open(&self.path)
+            .with_context(|| format!("Failed to open audit log {}", self.path.display()))?;
+        let entries: Vec<AuditEntry> = BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect();
+
+        let skip = entries.len().saturating_sub(limit);
+        Ok(entries.into_iter().skip(skip).collect())
+    }
+}
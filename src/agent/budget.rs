@@ -0,0 +1,80 @@
+use std::time::{Duration, Instant};
+
+/// Heading of the budget note appended to the system message
+pub const BUDGET_HEADING: &str = "## BUDGET";
+/// Tool rounds left at or below which the model is told to wrap up
+pub const LOW_ITERATIONS: usize = 10;
+/// Share of the time limit left at or below which the model is told to wrap up
+const LOW_TIME_FRACTION: f64 = 0.2;
+
+/// Tool rounds and time a turn has used, told to the model on every request so it can
+/// prioritize and summarize instead of being cut off mid-exploration
+#[derive(Debug, Clone)]
+pub struct TurnBudget {
+    started: Instant,
+    iterations_used: usize,
+    max_iterations: usize,
+    time_limit: Option<Duration>,
+}
+
+impl TurnBudget {
+    pub fn new(max_iterations: usize, time_limit: Option<Duration>) -> Self {
+        Self {
+            started: Instant::now(),
+            iterations_used: 0,
+            max_iterations,
+            time_limit,
+        }
+    }
+
+    /// Record how many tool rounds the turn has run
+    pub fn set_iterations_used(&mut self, iterations: usize) {
+        self.iterations_used = iterations;
+    }
+
+    pub fn remaining_iterations(&self) -> usize {
+        self.max_iterations.saturating_sub(self.iterations_used)
+    }
+
+    /// The note for the time elapsed so far
+    pub fn note(&self) -> String {
+        self.note_at(self.started.elapsed())
+    }
+
+    /// The note as it reads once `elapsed` has passed
+    pub fn note_at(&self, elapsed: Duration) -> String {
+        let remaining = self.remaining_iterations();
+        let mut note = format!(
+            "{BUDGET_HEADING}\nTool rounds: {} of {} used, about {remaining} left.",
+            self.iterations_used, self.max_iterations
+        );
+        let mut low = remaining <= LOW_ITERATIONS;
+
+        match self.time_limit {
+            Some(limit) => {
+                let left = limit.saturating_sub(elapsed);
+                note.push_str(&format!(
+                    " Time: {}s elapsed, about {}s left of {}s.",
+                    elapsed.as_secs(),
+                    left.as_secs(),
+                    limit.as_secs()
+                ));
+                low |= left.as_secs_f64() <= limit.as_secs_f64() * LOW_TIME_FRACTION;
+            }
+            None => note.push_str(&format!(" Time: {}s elapsed.", elapsed.as_secs())),
+        }
+
+        if low {
+            note.push_str(
+                "\nThe budget is nearly spent. Stop exploring, finish the most important part \
+                 and summarize what you found and what is left to do.",
+            );
+        } else {
+            note.push_str(
+                "\nPrioritize the tool calls that matter most; the turn is cut off when the \
+                 budget runs out.",
+            );
+        }
+        note
+    }
+}
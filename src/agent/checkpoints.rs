@@ -0,0 +1,411 @@
+use crate::agent::permissions::edited_paths;
+use crate::tools::fs::file_ops::resolve_path;
+use crate::tools::remote::ExecutionBackend;
+use crate::tools::shell::ShellConfig;
+use crate::tools::shell_session::session_dir;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+/// Turns kept for /undo
+pub const MAX_CHECKPOINTS: usize = 20;
+
+/// The files an agent turn changed, as they were before it changed them
+#[derive(Debug, Clone, Default)]
+struct TurnCheckpoint {
+    /// What the turn was asked, to say what's being undone
+    label: String,
+    /// Contents before the turn's first Edit or Write to each file; None when the
+    /// file didn't exist
+    files: BTreeMap<PathBuf, Option<Vec<u8>>>,
+    /// What each of the turn's Bash commands changed, in order
+    bash: Vec<BashChange>,
+    /// The repository before the Bash command that's running
+    running_bash: Option<TreeSnapshot>,
+    /// Whether Bash ran outside a git repository, where its changes can't be undone
+    untracked_bash: bool,
+}
+
+impl TurnCheckpoint {
+    fn is_empty(&self) -> bool {
+        self.files.is_empty()
+            && self.bash.is_empty()
+            && self.running_bash.is_none()
+            && !self.untracked_bash
+    }
+
+    // Record what the running Bash command changed, now that it's done
+    fn finish_bash(&mut self) {
+        let Some(before) = self.running_bash.take() else {
+            return;
+        };
+        match BashChange::since(before) {
+            Ok(change) if change.paths.is_empty() => {}
+            Ok(change) => self.bash.push(change),
+            Err(_) => self.untracked_bash = true,
+        }
+    }
+}
+
+/// The working tree of a repository, untracked files included and ignored ones
+/// not, written as a git tree object without touching the index or the files
+#[derive(Debug, Clone)]
+struct TreeSnapshot {
+    repo_root: PathBuf,
+    tree: String,
+}
+
+/// The files one Bash command changed in a repository
+#[derive(Debug, Clone)]
+struct BashChange {
+    repo_root: PathBuf,
+    /// Tree before the command ran
+    before: String,
+    /// Tree after it ran
+    after: String,
+    /// Paths it added, changed or deleted, relative to the repository
+    paths: Vec<String>,
+}
+
+/// What undoing a turn did
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct UndoReport {
+    /// The prompt of the undone turn
+    pub label: String,
+    /// Files put back as they were
+    pub restored: Vec<String>,
+    /// Files the turn created, now deleted
+    pub removed: Vec<String>,
+    /// Files Bash changed that changed again after the turn, left as they are
+    pub kept: Vec<String>,
+    pub warning: Option<String>,
+}
+
+impl UndoReport {
+    pub fn summary(&self) -> String {
+        let mut lines = vec![format!("Undid \"{}\"", self.label)];
+        lines.extend(
+            self.restored
+                .iter()
+                .map(|path| format!("  restored {path}")),
+        );
+        lines.extend(self.removed.iter().map(|path| format!("  removed  {path}")));
+        lines.extend(
+            self.kept
+                .iter()
+                .map(|path| format!("  kept     {path} (changed after the turn)")),
+        );
+        if self.restored.is_empty() && self.removed.is_empty() && self.kept.is_empty() {
+            lines.push("  No files needed restoring".to_string());
+        }
+        if let Some(warning) = &self.warning {
+            lines.push(format!("Note: {warning}"));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Checkpoints of the latest agent turns, taken before every Edit, Write, WriteMany
+/// and Bash call and after every Bash call, so `/undo` can put back the files a
+/// turn changed. Clones share
+/// the checkpoints: the agent records them and the App undoes them.
+#[derive(Debug, Clone, Default)]
+pub struct Checkpoints {
+    turns: Arc<Mutex<Vec<TurnCheckpoint>>>,
+}
+
+impl Checkpoints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start the checkpoint of a new turn, labelled with its prompt
+    pub fn begin_turn(&self, label: &str) {
+        let mut turns = self.turns.lock().unwrap();
+        if turns.last().is_some_and(TurnCheckpoint::is_empty) {
+            turns.pop();
+        }
+        turns.push(TurnCheckpoint {
+            label: label.trim().to_string(),
+            ..Default::default()
+        });
+        let excess = turns.len().saturating_sub(MAX_CHECKPOINTS);
+        turns.drain(..excess);
+    }
+
+    /// Save what a tool call is about to change, before it runs, resolving its paths
+    /// and running Bash in `working_dir`. Only the first save of a file in a turn
+    /// counts, so undo goes back to the start of the turn.
+    pub fn record_call(&self, tool: &str, arguments: &Value, working_dir: &Path) {
+        if tool == "Bash" {
+            self.record_bash(working_dir);
+            return;
+        }
+        let paths: Vec<PathBuf> = edited_paths(tool, arguments)
+            .into_iter()
+            .map(|path| resolve_path(&working_dir.join(path)))
+            .filter(|path| !ExecutionBackend::for_path(path).transfers_files())
+            .collect();
+        if paths.is_empty() {
+            return;
+        }
+
+        let mut turns = self.turns.lock().unwrap();
+        let turn = current_turn(&mut turns);
+        for path in paths {
+            turn.files
+                .entry(path)
+                .or_insert_with_key(|path| std::fs::read(path).ok());
+        }
+    }
+
+    /// Save what a tool call changed, after it ran. For Bash, the repository is
+    /// compared with its snapshot from before the command, so undo restores only
+    /// the files the command changed.
+    pub fn complete_call(&self, tool: &str) {
+        if tool != "Bash" {
+            return;
+        }
+        let mut turns = self.turns.lock().unwrap();
+        current_turn(&mut turns).finish_bash();
+    }
+
+    fn record_bash(&self, dir: &Path) {
+        if !ExecutionBackend::for_path(dir).is_local() {
+            return;
+        }
+        // A persistent shell runs the command wherever an earlier one left it. A
+        // command that changes directory itself is only snapshotted where it starts.
+        let shell = ShellConfig::from_env();
+        let dir = match shell.persistent.then(|| session_dir(&shell)).flatten() {
+            Some(session_dir) => session_dir,
+            None => dir.to_path_buf(),
+        };
+        let dir = dir.as_path();
+        let mut turns = self.turns.lock().unwrap();
+        let turn = current_turn(&mut turns);
+        // A command that never reported back ends here
+        turn.finish_bash();
+        match TreeSnapshot::take(dir) {
+            Ok(snapshot) => turn.running_bash = Some(snapshot),
+            Err(_) => turn.untracked_bash = true,
+        }
+    }
+
+    /// Number of turns that can be undone
+    pub fn len(&self) -> usize {
+        let turns = self.turns.lock().unwrap();
+        turns.iter().filter(|turn| !turn.is_empty()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Put back the files changed by the latest turn that changed any, and forget it
+    pub fn undo_last(&self) -> Result<UndoReport> {
+        let mut turn = {
+            let mut turns = self.turns.lock().unwrap();
+            while turns.last().is_some_and(TurnCheckpoint::is_empty) {
+                turns.pop();
+            }
+            turns
+                .pop()
+                .ok_or_else(|| anyhow::anyhow!("No agent changes to undo"))?
+        };
+        turn.finish_bash();
+
+        let mut report = UndoReport {
+            label: turn.label.clone(),
+            ..Default::default()
+        };
+        if turn.untracked_bash {
+            report.warning = Some(
+                "Bash ran outside a git repository, so changes it made weren't restored"
+                    .to_string(),
+            );
+        }
+
+        // Files Bash changed, except those Edit or Write also changed, which are
+        // restored from their own snapshot below
+        restore_bash_changes(&turn.bash, &turn.files, &mut report)?;
+        if !report.kept.is_empty() {
+            let note = format!(
+                "{} files Bash changed were changed again after the turn and were left as they are",
+                report.kept.len()
+            );
+            report.warning = Some(match report.warning.take() {
+                Some(warning) => format!("{warning}. {note}"),
+                None => note,
+            });
+        }
+
+        for (path, before) in &turn.files {
+            match before {
+                Some(contents) => {
+                    if std::fs::read(path).ok().as_ref() == Some(contents) {
+                        continue;
+                    }
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent)
+                            .with_context(|| format!("Failed to create {}", parent.display()))?;
+                    }
+                    std::fs::write(path, contents)
+                        .with_context(|| format!("Failed to restore {}", path.display()))?;
+                    report.restored.push(path.display().to_string());
+                }
+                None if path.exists() => {
+                    std::fs::remove_file(path)
+                        .with_context(|| format!("Failed to remove {}", path.display()))?;
+                    report.removed.push(path.display().to_string());
+                }
+                None => {}
+            }
+        }
+        Ok(report)
+    }
+}
+
+// The latest turn, started unlabelled if none was
+fn current_turn(turns: &mut Vec<TurnCheckpoint>) -> &mut TurnCheckpoint {
+    if turns.is_empty() {
+        turns.push(TurnCheckpoint::default());
+    }
+    turns.last_mut().unwrap()
+}
+
+impl TreeSnapshot {
+    fn take(dir: &Path) -> Result<Self> {
+        let repo_root = PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"])?.trim());
+
+        // Stage everything in an index of our own, starting from a copy of the real
+        // one so unchanged files aren't hashed again
+        let index_dir = tempfile::tempdir().context("Failed to create a temporary index")?;
+        let index = index_dir.path().join("index");
+        let real_index =
+            repo_root.join(git(&repo_root, &["rev-parse", "--git-path", "index"])?.trim());
+        if real_index.exists() {
+            std::fs::copy(&real_index, &index).context("Failed to copy the git index")?;
+        }
+        git_with_index(&repo_root, &index, &["add", "--all"])?;
+        let tree = git_with_index(&repo_root, &index, &["write-tree"])?
+            .trim()
+            .to_string();
+        Ok(Self { repo_root, tree })
+    }
+}
+
+impl BashChange {
+    // What changed in the repository of `before` since it was taken
+    fn since(before: TreeSnapshot) -> Result<Self> {
+        let after = TreeSnapshot::take(&before.repo_root)?;
+        let paths = changed_paths(&before.repo_root, &before.tree, &after.tree, &[])?;
+        Ok(Self {
+            repo_root: before.repo_root,
+            before: before.tree,
+            after: after.tree,
+            paths,
+        })
+    }
+}
+
+/// Put back the files Bash commands changed as they were before the first command
+/// that changed each, skipping `skip` and keeping files that changed again since
+/// the last command that changed them
+fn restore_bash_changes(
+    changes: &[BashChange],
+    skip: &BTreeMap<PathBuf, Option<Vec<u8>>>,
+    report: &mut UndoReport,
+) -> Result<()> {
+    // Per repository and path: the tree before the first change and after the last
+    let mut trees: BTreeMap<&Path, BTreeMap<&str, (&str, &str)>> = BTreeMap::new();
+    for change in changes {
+        let paths = trees.entry(change.repo_root.as_path()).or_default();
+        for path in &change.paths {
+            paths
+                .entry(path.as_str())
+                .and_modify(|(_, after)| *after = change.after.as_str())
+                .or_insert((change.before.as_str(), change.after.as_str()));
+        }
+    }
+
+    for (repo_root, paths) in trees {
+        let paths: BTreeMap<&str, (&str, &str)> = paths
+            .into_iter()
+            .filter(|(path, _)| !skip.contains_key(&resolve_path(&repo_root.join(path))))
+            .collect();
+        if paths.is_empty() {
+            continue;
+        }
+
+        let now = TreeSnapshot::take(repo_root)?.tree;
+        for (path, (before, after)) in paths {
+            let full_path = repo_root.join(path);
+            if !changed_paths(repo_root, after, &now, &[path])?.is_empty() {
+                report.kept.push(full_path.display().to_string());
+                continue;
+            }
+            if git(repo_root, &["cat-file", "-e", &format!("{before}:{path}")]).is_ok() {
+                git(
+                    repo_root,
+                    &["restore", "--source", before, "--worktree", "--", path],
+                )?;
+                report.restored.push(full_path.display().to_string());
+            } else if full_path.exists() {
+                // The command created it
+                std::fs::remove_file(&full_path)
+                    .with_context(|| format!("Failed to remove {}", full_path.display()))?;
+                report.removed.push(full_path.display().to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Paths that differ between two trees, limited to `paths` unless it's empty
+fn changed_paths(repo_root: &Path, from: &str, to: &str, paths: &[&str]) -> Result<Vec<String>> {
+    let mut args = vec![
+        "diff-tree",
+        "-r",
+        "--name-only",
+        "--no-renames",
+        from,
+        to,
+        "--",
+    ];
+    args.extend(paths);
+    Ok(git(repo_root, &args)?.lines().map(str::to_string).collect())
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    run_git(Command::new("git"), dir, args)
+}
+
+// Run git with `index` in place of the repository's index
+fn git_with_index(dir: &Path, index: &Path, args: &[&str]) -> Result<String> {
+    let mut command = Command::new("git");
+    command.env("GIT_INDEX_FILE", index);
+    run_git(command, dir, args)
+}
+
+fn run_git(mut command: Command, dir: &Path, args: &[&str]) -> Result<String> {
+    let output = command
+        .arg("-C")
+        .arg(dir)
+        .args(["-c", "core.quotePath=false"])
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
@@ -0,0 +1,112 @@
+use crate::agent::core::Agent;
+use crate::agent::policy::ToolPolicy;
+use crate::context::provider::estimate_tokens;
+use serde::Serialize;
+use std::time::Instant;
+
+/// One model's answer in a side-by-side comparison
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelAnswer {
+    pub model: String,
+    pub response: Option<String>,
+    pub error: Option<String>,
+    pub latency_ms: u64,
+    /// Estimated tokens sent to the model, including tool results
+    pub input_tokens: usize,
+    /// Estimated tokens of the model's answers
+    pub output_tokens: usize,
+}
+
+/// Answers of two models to the same prompt
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelComparison {
+    pub prompt: String,
+    pub answers: Vec<ModelAnswer>,
+}
+
+impl ModelComparison {
+    /// Send the prompt to both agents at the same time. Each agent runs with
+    /// read-only tools and a fresh conversation, so neither can change the
+    /// workspace and both see the same context.
+    pub async fn run(prompt: &str, a: (String, Agent), b: (String, Agent)) -> Self {
+        let (answer_a, answer_b) = tokio::join!(answer(prompt, a.0, a.1), answer(prompt, b.0, b.1));
+
+        Self {
+            prompt: prompt.to_string(),
+            answers: vec![answer_a, answer_b],
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("| | ");
+        out.push_str(
+            &self
+                .answers
+                .iter()
+                .map(|answer| answer.model.clone())
+                .collect::<Vec<_>>()
+                .join(" | "),
+        );
+        out.push_str(" |\n|---|");
+        out.push_str(&"---|".repeat(self.answers.len()));
+        out.push('\n');
+
+        let row = |label: &str, value: &dyn Fn(&ModelAnswer) -> String| {
+            let values: Vec<String> = self.answers.iter().map(value).collect();
+            format!("| {label} | {} |\n", values.join(" | "))
+        };
+        out.push_str(&row("Latency", &|a| {
+            format!("{:.1}s", a.latency_ms as f64 / 1000.0)
+        }));
+        out.push_str(&row("Input tokens", &|a| format!("~{}", a.input_tokens)));
+        out.push_str(&row("Output tokens", &|a| format!("~{}", a.output_tokens)));
+
+        for answer in &self.answers {
+            out.push_str(&format!("\n### {}\n\n", answer.model));
+            match (&answer.response, &answer.error) {
+                (Some(response), _) => out.push_str(response),
+                (None, Some(error)) => out.push_str(&format!("Error: {error}")),
+                (None, None) => out.push_str("No answer"),
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+async fn answer(prompt: &str, model: String, agent: Agent) -> ModelAnswer {
+    let mut agent = agent.with_tool_policy(ToolPolicy::ReadOnly);
+    agent.clear_history();
+
+    let started = Instant::now();
+    let result = agent.execute(prompt).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let (input_tokens, output_tokens) =
+        agent
+            .conversation_history()
+            .iter()
+            .fold((0, 0), |(input, output), message| {
+                let tokens = estimate_tokens(&message.content);
+                if message.role == "assistant" {
+                    (input, output + tokens)
+                } else {
+                    (input + tokens, output)
+                }
+            });
+
+    let (response, error) = match result {
+        Ok(response) => (Some(response), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    ModelAnswer {
+        model,
+        // Fall back to the prompt and answer when the agent keeps no history
+        input_tokens: input_tokens.max(estimate_tokens(prompt)),
+        output_tokens: output_tokens.max(response.as_deref().map_or(0, estimate_tokens)),
+        response,
+        error,
+        latency_ms,
+    }
+}
@@ -0,0 +1,249 @@
+use crate::agent::tools::ToolCall;
+use crate::communication::rpc::get_global_rpc_server;
+use crate::tools::fs::diff::{DiffLine, DiffTools};
+use crate::tools::notebook::Notebook;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Request a client that declared `supports_conflict_prompts` answers with
+/// `{"resolution": "apply" | "skip"}`
+pub const RESOLVE_CONFLICT_METHOD: &str = "oli/resolveConflict";
+
+/// How long to wait for the client to resolve a conflict before skipping the edit
+const RESOLVE_CONFLICT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Changed lines shown per side of a conflict preview
+const PREVIEW_LINES: usize = 40;
+
+/// Files as the agent last saw them in the current task, captured when it reads
+/// or writes them, so edits to files changed on disk since can be held back
+#[derive(Debug, Clone, Default)]
+pub struct ReadSnapshots {
+    files: HashMap<PathBuf, Snapshot>,
+}
+
+#[derive(Debug, Clone)]
+struct Snapshot {
+    hash: u64,
+    content: String,
+}
+
+impl ReadSnapshots {
+    pub fn clear(&mut self) {
+        self.files.clear();
+    }
+
+    /// Capture a file's content as it is on disk now. Files that can't be read
+    /// are forgotten.
+    pub fn record(&mut self, path: &Path) {
+        let key = snapshot_key(path);
+        match std::fs::read_to_string(&key) {
+            Ok(content) => {
+                self.files.insert(
+                    key,
+                    Snapshot {
+                        hash: content_hash(&content),
+                        content,
+                    },
+                );
+            }
+            Err(_) => {
+                self.files.remove(&key);
+            }
+        }
+    }
+
+    /// Capture every known file again, e.g. after the agent's own Bash command
+    /// may have changed them
+    pub fn refresh(&mut self) {
+        let paths: Vec<PathBuf> = self.files.keys().cloned().collect();
+        for path in paths {
+            self.record(&path);
+        }
+    }
+
+    /// Record the files a successful tool call read or wrote. Bash may change any
+    /// file, so everything is captured again.
+    pub fn record_call(&mut self, call: &ToolCall) {
+        match call {
+            ToolCall::Read(params) => self.record(Path::new(&params.file_path)),
+            ToolCall::NotebookRead(params) => self.record(Path::new(&params.file_path)),
+            ToolCall::Edit(params) => self.record(Path::new(&params.file_path)),
+            ToolCall::Write(params) => self.record(Path::new(&params.file_path)),
+            ToolCall::WriteMany(params) => {
+                for file in &params.files {
+                    self.record(Path::new(&file.path));
+                }
+            }
+            ToolCall::NotebookEditCell(params) => self.record(Path::new(&params.file_path)),
+            ToolCall::Bash(_) => self.refresh(),
+            _ => {}
+        }
+    }
+
+    /// The first file a modifying call would change that differs on disk from
+    /// when the agent saw it. Files the agent never read aren't checked.
+    pub fn conflict_for(&self, call: &ToolCall) -> Option<FileConflict> {
+        match call {
+            ToolCall::Edit(params) => self.conflict(Path::new(&params.file_path), |original| {
+                original.replace(&params.old_string, &params.new_string)
+            }),
+            ToolCall::Write(params) => self.conflict(Path::new(&params.file_path), |original| {
+                params.content_after(original)
+            }),
+            ToolCall::WriteMany(params) => params
+                .files
+                .iter()
+                .find_map(|file| self.conflict(Path::new(&file.path), |_| file.content.clone())),
+            ToolCall::NotebookEditCell(params) => {
+                let path = Path::new(&params.file_path);
+                self.conflict(path, |original| {
+                    Notebook::parse(path, original)
+                        .and_then(|mut notebook| {
+                            notebook.apply(params)?;
+                            notebook.to_json()
+                        })
+                        .unwrap_or_else(|_| original.to_string())
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Compare a file with its snapshot, building the agent's version from the
+    /// content it saw
+    fn conflict(&self, path: &Path, proposed: impl FnOnce(&str) -> String) -> Option<FileConflict> {
+        let snapshot = self.files.get(&snapshot_key(path))?;
+        // A file deleted since is a change too
+        let current = std::fs::read_to_string(path).unwrap_or_default();
+        if content_hash(&current) == snapshot.hash {
+            return None;
+        }
+
+        Some(FileConflict {
+            path: path.to_path_buf(),
+            proposed: proposed(&snapshot.content),
+            original: snapshot.content.clone(),
+            current,
+        })
+    }
+}
+
+/// A file that changed on disk after the agent read it, with the three versions
+/// needed to decide which change to keep
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileConflict {
+    pub path: PathBuf,
+    /// The file when the agent read it
+    pub original: String,
+    /// The file on disk now, with the user's change
+    pub current: String,
+    /// The file as the agent's edit would leave it, applied to `original`
+    pub proposed: String,
+}
+
+/// How a conflict was resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Apply the agent's edit on top of the file on disk
+    Apply,
+    /// Leave the file as it is and tell the agent
+    Skip,
+}
+
+impl FileConflict {
+    /// The user's change and the agent's change, each relative to the file the
+    /// agent read
+    pub fn preview(&self) -> String {
+        format!(
+            "{} changed on disk after the agent read it.\n\nYour change:\n{}\nAgent change:\n{}",
+            self.path.display(),
+            changed_lines(&self.original, &self.current),
+            changed_lines(&self.original, &self.proposed),
+        )
+    }
+
+    /// The tool result telling the model its edit was held back
+    pub fn held_message(&self, tool: &str) -> String {
+        format!(
+            "ERROR EXECUTING TOOL: {tool} was not applied because {} changed on disk after \
+             you read it. The user's change:\n{}\nRead the file again and redo your change on \
+             top of theirs if it's still needed.",
+            self.path.display(),
+            changed_lines(&self.original, &self.current),
+        )
+    }
+
+    /// Show the conflict to the client and, if it can answer, ask whether to apply
+    /// the edit anyway. Clients that can't answer, errors and timeouts skip it.
+    pub fn resolve(&self) -> Resolution {
+        let Some(server) = get_global_rpc_server() else {
+            return Resolution::Skip;
+        };
+        let params = json!({
+            "path": self.path,
+            "original": self.original,
+            "current": self.current,
+            "proposed": self.proposed,
+            "preview": self.preview(),
+        });
+        server
+            .send_notification("file_conflict", params.clone())
+            .ok();
+
+        let answers = server
+            .client_capabilities()
+            .is_some_and(|caps| caps.supports_conflict_prompts);
+        if !answers {
+            return Resolution::Skip;
+        }
+        match server.request_client(RESOLVE_CONFLICT_METHOD, params, RESOLVE_CONFLICT_TIMEOUT) {
+            Ok(answer) if answer["resolution"].as_str() == Some("apply") => Resolution::Apply,
+            _ => Resolution::Skip,
+        }
+    }
+}
+
+/// Changed lines between two versions, prefixed with `+` and `-`
+fn changed_lines(old: &str, new: &str) -> String {
+    let changes: Vec<String> = DiffTools::generate_diff(old, new)
+        .into_iter()
+        .filter_map(|line| match line {
+            DiffLine::Added(text) => Some(format!("+ {text}")),
+            DiffLine::Removed(text) => Some(format!("- {text}")),
+            DiffLine::Context(_) => None,
+        })
+        .collect();
+
+    if changes.is_empty() {
+        return "  (no line changes)\n".to_string();
+    }
+    let mut text: String = changes
+        .iter()
+        .take(PREVIEW_LINES)
+        .map(|line| format!("{line}\n"))
+        .collect();
+    if changes.len() > PREVIEW_LINES {
+        text.push_str(&format!(
+            "  ... {} more changed lines\n",
+            changes.len() - PREVIEW_LINES
+        ));
+    }
+    text
+}
+
+/// Snapshots are keyed by canonical path, so relative and absolute paths match
+fn snapshot_key(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
@@ -1,21 +1,30 @@
+use crate::agent::approval::ApprovalBackend;
+use crate::agent::audit::AuditLog;
+use crate::agent::checkpoints::Checkpoints;
+use crate::agent::evidence::Evidence;
 use crate::agent::executor::AgentExecutor;
+use crate::agent::policy::ToolPolicy;
+use crate::agent::result_limits::ResultLimits;
+use crate::agent::tool_queue::ToolQueue;
+use crate::agent::tool_stats::ToolStatsLog;
+use crate::agent::trail::TrailRound;
 use crate::apis::anthropic::AnthropicClient;
-use crate::apis::api_client::{ApiClientEnum, DynApiClient, Message};
+use crate::apis::api_client::{ApiClientEnum, DynApiClient, Message, ModelParameters, ToolChoice};
 use crate::apis::gemini::GeminiClient;
 use crate::apis::ollama::OllamaClient;
-use crate::apis::openai::OpenAIClient;
-use crate::prompts::add_working_directory_to_prompt;
+use crate::apis::openai::{AzureDeployment, OpenAIClient};
+use crate::apis::openrouter::OpenRouterClient;
+use crate::context::git_history::GitHistory;
+use crate::context::project_instructions::ProjectInstructions;
+use crate::context::provider::{ContextAssembler, ContextProvider};
+use crate::prompts::{add_project_instructions_to_prompt, add_working_directory_to_prompt};
 use anyhow::{Context, Result};
-use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::sync::mpsc;
 
-#[derive(Clone)]
-pub enum LLMProvider {
-    Anthropic,
-    OpenAI,
-    Ollama,
-    Gemini,
-}
+pub use crate::apis::provider::LLMProvider;
 
 #[derive(Clone)]
 pub struct Agent {
@@ -24,20 +33,88 @@ pub struct Agent {
     api_client: Option<DynApiClient>,
     system_prompt: Option<String>,
     working_directory: Option<String>,
+    project_instructions: Option<ProjectInstructions>,
     progress_sender: Option<mpsc::Sender<String>>,
+    delta_sender: Option<mpsc::UnboundedSender<String>>,
+    tool_queue_sender: Option<mpsc::Sender<ToolQueue>>,
+    model_parameters: ModelParameters,
+    first_tool_choice: ToolChoice,
+    tool_policy: ToolPolicy,
+    approval_backend: Option<ApprovalBackend>,
+    audit_log: Option<AuditLog>,
+    task_id: Option<String>,
+    git_history: bool,
+    context_assembler: ContextAssembler,
+    time_limit: Option<Duration>,
+    cancel_flag: Option<Arc<AtomicBool>>,
+    checkpoints: Option<Checkpoints>,
+    tool_stats: Option<ToolStatsLog>,
+    result_limits: ResultLimits,
+    // Evidence and trail of the last turn, recorded by `execute` through `&self`
+    last_turn: LastTurn,
     // Store the conversation history
     conversation_history: Vec<crate::apis::api_client::Message>,
 }
 
+/// Numbered tool results of a turn, which its answer cites as `[#N]`, and its
+/// tool calls round by round
+#[derive(Debug, Clone, Default)]
+struct TurnRecord {
+    evidence: Vec<Evidence>,
+    trail: Vec<TrailRound>,
+}
+
+/// The record of the last turn behind a lock. Clones get their own copy, so a
+/// sub-agent's turns don't replace its parent's.
+#[derive(Debug, Default)]
+struct LastTurn(Mutex<TurnRecord>);
+
+impl LastTurn {
+    fn lock(&self) -> std::sync::MutexGuard<'_, TurnRecord> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn record(&self, evidence: Vec<Evidence>, trail: Vec<TrailRound>) {
+        *self.lock() = TurnRecord { evidence, trail };
+    }
+}
+
+impl Clone for LastTurn {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(self.lock().clone()))
+    }
+}
+
 impl Agent {
     pub fn new(provider: LLMProvider) -> Self {
+        let result_limits =
+            ResultLimits::for_context_window(provider.context_window()).with_env_overrides();
         Self {
             provider,
             model: None,
             api_client: None,
             system_prompt: None,
             working_directory: None,
+            project_instructions: None,
             progress_sender: None,
+            delta_sender: None,
+            tool_queue_sender: None,
+            model_parameters: ModelParameters::default(),
+            first_tool_choice: ToolChoice::Auto,
+            tool_policy: ToolPolicy::default(),
+            approval_backend: None,
+            audit_log: None,
+            task_id: None,
+            git_history: true,
+            context_assembler: ContextAssembler::new(),
+            time_limit: None,
+            cancel_flag: None,
+            checkpoints: None,
+            tool_stats: None,
+            result_limits,
+            last_turn: LastTurn::default(),
             conversation_history: Vec::new(),
         }
     }
@@ -67,13 +144,144 @@ impl Agent {
         self
     }
 
+    /// Stream the text of each completion as the model generates it
+    pub fn with_delta_sender(mut self, sender: Option<mpsc::UnboundedSender<String>>) -> Self {
+        self.delta_sender = sender;
+        self
+    }
+
+    /// Send the tool calls of each iteration, with their status and durations, as they run
+    pub fn with_tool_queue_sender(mut self, sender: mpsc::Sender<ToolQueue>) -> Self {
+        self.tool_queue_sender = Some(sender);
+        self
+    }
+
     pub fn with_working_directory(mut self, working_dir: String) -> Self {
         self.working_directory = Some(working_dir);
         self
     }
 
+    /// Add the project's `OLI.md` or `AGENTS.md` to the system prompt
+    pub fn with_project_instructions(mut self, instructions: Option<ProjectInstructions>) -> Self {
+        self.project_instructions = instructions;
+        self
+    }
+
+    pub fn with_model_parameters(mut self, parameters: ModelParameters) -> Self {
+        self.model_parameters = parameters;
+        self
+    }
+
+    /// Constrain the first model request of each turn, e.g. to `ToolChoice::Any` so
+    /// the agent looks at the workspace before it answers
+    pub fn with_first_tool_choice(mut self, choice: ToolChoice) -> Self {
+        self.first_tool_choice = choice;
+        self
+    }
+
+    pub fn with_tool_policy(mut self, policy: ToolPolicy) -> Self {
+        self.tool_policy = policy;
+        self
+    }
+
+    /// Send risky tool executions to an external system for approval
+    pub fn with_approval_backend(mut self, backend: Option<ApprovalBackend>) -> Self {
+        self.approval_backend = backend;
+        self
+    }
+
+    /// Record Edit, Write and Bash executions in an append-only audit log
+    pub fn with_audit_log(mut self, audit_log: Option<AuditLog>) -> Self {
+        self.audit_log = audit_log;
+        self
+    }
+
+    /// Task that tool executions are attributed to in the audit log
+    pub fn with_task_id(mut self, task_id: String) -> Self {
+        self.task_id = Some(task_id);
+        self
+    }
+
+    /// Include the recent git history of files mentioned in each query (on by default)
+    pub fn with_git_history(mut self, enabled: bool) -> Self {
+        self.git_history = enabled;
+        self
+    }
+
+    /// Add a provider's context to every model request
+    pub fn with_context_provider(mut self, provider: Arc<dyn ContextProvider>) -> Self {
+        self.context_assembler = self.context_assembler.with_provider(provider);
+        self
+    }
+
+    /// Limit the tokens all context providers together may add to a request
+    pub fn with_context_budget(mut self, tokens: usize) -> Self {
+        self.context_assembler = self.context_assembler.with_total_budget(tokens);
+        self
+    }
+
+    /// Stop turns that run longer than `limit`, returning what was produced so far
+    pub fn with_time_limit(mut self, limit: Option<Duration>) -> Self {
+        self.time_limit = limit;
+        self
+    }
+
+    /// Stop the running turn once `flag` is set, returning what was produced so far
+    pub fn with_cancel_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.cancel_flag = Some(flag);
+        self
+    }
+
+    /// Record the files each turn changes, so they can be restored with /undo
+    pub fn with_checkpoints(mut self, checkpoints: Checkpoints) -> Self {
+        self.checkpoints = Some(checkpoints);
+        self
+    }
+
+    /// Record the outcome of every tool call, for benchmark reports
+    pub fn with_tool_stats(mut self, tool_stats: Option<ToolStatsLog>) -> Self {
+        self.tool_stats = tool_stats;
+        self
+    }
+
+    /// Limit how much of each tool result is passed to the model. By default results
+    /// are limited to a share of the provider's context window; pass
+    /// `ModelConfig::result_limits` to size them to the model's own.
+    pub fn with_result_limits(mut self, limits: ResultLimits) -> Self {
+        self.result_limits = limits;
+        self
+    }
+
+    /// Use an already constructed API client instead of creating one in `initialize`
+    pub fn with_api_client(mut self, client: DynApiClient) -> Self {
+        self.api_client = Some(client);
+        self
+    }
+
+    /// The API client created by `initialize` or set with `with_api_client`
+    pub fn api_client(&self) -> Option<&DynApiClient> {
+        self.api_client.as_ref()
+    }
+
+    pub fn progress_sender(&self) -> Option<&mpsc::Sender<String>> {
+        self.progress_sender.as_ref()
+    }
+
+    /// Whether the cancel flag, if any, has been set
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel_flag
+            .as_ref()
+            .is_some_and(|flag| flag.load(std::sync::atomic::Ordering::SeqCst))
+    }
+
+    /// Whether an API client has been set up for this agent
+    pub fn is_initialized(&self) -> bool {
+        self.api_client.is_some()
+    }
+
     pub fn clear_history(&mut self) {
         self.conversation_history.clear();
+        self.last_turn.record(Vec::new(), Vec::new());
     }
 
     /// Add a message to the conversation history
@@ -81,6 +289,21 @@ impl Agent {
         self.conversation_history.push(message);
     }
 
+    /// Get the conversation history
+    pub fn conversation_history(&self) -> &[Message] {
+        &self.conversation_history
+    }
+
+    /// Numbered tool results of the last `execute`, which its answer cites as `[#N]`
+    pub fn last_evidence(&self) -> Vec<Evidence> {
+        self.last_turn.lock().evidence.clone()
+    }
+
+    /// Tool calls of the last `execute` and how each ended, round by round
+    pub fn last_trail(&self) -> Vec<TrailRound> {
+        self.last_turn.lock().trail.clone()
+    }
+
     /// Get a clone of the conversation history (for testing)
     pub fn get_conversation_history_for_test(&self) -> Vec<Message> {
         self.conversation_history.clone()
@@ -105,6 +328,19 @@ impl Agent {
                 let client = GeminiClient::new(self.model.clone())?;
                 ApiClientEnum::Gemini(Arc::new(client))
             }
+            LLMProvider::OpenRouter => {
+                let client = OpenRouterClient::new(self.model.clone())?;
+                ApiClientEnum::OpenRouter(Arc::new(client))
+            }
+            LLMProvider::AzureOpenAI => {
+                // The model is the name of the deployment
+                let client = OpenAIClient::azure_from_env(self.model.clone())?;
+                ApiClientEnum::OpenAI(Arc::new(client))
+            }
+            LLMProvider::OpenAICompatible => {
+                let client = OpenAIClient::compatible_from_env(self.model.clone(), None)?;
+                ApiClientEnum::OpenAI(Arc::new(client))
+            }
         });
 
         Ok(())
@@ -131,6 +367,19 @@ impl Agent {
                 let client = GeminiClient::with_api_key(api_key, self.model.clone())?;
                 ApiClientEnum::Gemini(Arc::new(client))
             }
+            LLMProvider::OpenRouter => {
+                let client = OpenRouterClient::with_api_key(api_key, self.model.clone())?;
+                ApiClientEnum::OpenRouter(Arc::new(client))
+            }
+            LLMProvider::AzureOpenAI => {
+                let azure = AzureDeployment::from_env(self.model.clone())?;
+                let client = OpenAIClient::with_azure(api_key, azure)?;
+                ApiClientEnum::OpenAI(Arc::new(client))
+            }
+            LLMProvider::OpenAICompatible => {
+                let client = OpenAIClient::compatible_from_env(self.model.clone(), Some(api_key))?;
+                ApiClientEnum::OpenAI(Arc::new(client))
+            }
         });
 
         Ok(())
@@ -143,7 +392,19 @@ impl Agent {
             .context("Agent not initialized. Call initialize() first.")?;
 
         // Create and configure executor with persisted conversation history
-        let mut executor = AgentExecutor::new(api_client.clone());
+        let mut executor = AgentExecutor::new(api_client.clone())
+            .with_model_parameters(self.model_parameters)
+            .with_first_tool_choice(self.first_tool_choice.clone())
+            .with_tool_policy(self.tool_policy.clone())
+            .with_approval_backend(self.approval_backend.clone())
+            .with_audit_log(self.audit_log.clone(), self.task_id.clone())
+            .with_time_limit(self.time_limit)
+            .with_tool_stats(self.tool_stats.clone())
+            .with_checkpoints(self.checkpoints.clone())
+            .with_result_limits(self.result_limits.clone());
+        if let Some(flag) = &self.cancel_flag {
+            executor = executor.with_cancel_flag(flag.clone());
+        }
 
         // Add existing conversation history if any
         if !self.conversation_history.is_empty() {
@@ -185,6 +446,12 @@ impl Agent {
         if let Some(sender) = &self.progress_sender {
             executor = executor.with_progress_sender(sender.clone());
         }
+        if let Some(sender) = &self.delta_sender {
+            executor = executor.with_delta_sender(sender.clone());
+        }
+        if let Some(sender) = &self.tool_queue_sender {
+            executor = executor.with_tool_queue_sender(sender.clone());
+        }
 
         // Check if a system message exists in the history
         let has_system_message = self
@@ -204,36 +471,50 @@ impl Agent {
                 crate::prompts::get_agent_prompt_with_cwd(working_dir_ref)
             };
 
-            // Add system message
+            // Add system message, with the project's instructions if it has any
+            let prompt = match &self.project_instructions {
+                Some(instructions) => add_project_instructions_to_prompt(&prompt, instructions),
+                None => prompt,
+            };
             executor.add_system_message(prompt);
-        } else {
-            // System message exists - ensure it has working directory information
+        } else if let Some(existing_system_msg) = self
+            .conversation_history
+            .iter()
+            .find(|msg| msg.role == "system")
+        {
+            // System message exists - ensure it has working directory information and
+            // the project's instructions
+            let mut updated_content = existing_system_msg.content.clone();
             if let Some(working_dir) = &self.working_directory {
-                // Find and update existing system message with CWD info
-                if let Some(existing_system_msg) = self
-                    .conversation_history
-                    .iter()
-                    .find(|msg| msg.role == "system")
-                {
-                    // Check if the existing system message already contains CWD info
-                    if !existing_system_msg.content.contains("## WORKING DIRECTORY") {
-                        // Create updated system message with CWD
-                        let updated_content = add_working_directory_to_prompt(
-                            &existing_system_msg.content,
-                            working_dir,
-                        );
-                        // Replace the existing system message
-                        executor.add_system_message(updated_content);
-                    }
-                }
+                updated_content = add_working_directory_to_prompt(&updated_content, working_dir);
+            }
+            if let Some(instructions) = &self.project_instructions {
+                updated_content =
+                    add_project_instructions_to_prompt(&updated_content, instructions);
+            }
+            // Replace the existing system message if anything was added
+            if updated_content != existing_system_msg.content {
+                executor.add_system_message(updated_content);
             }
         }
 
+        // Context providers, including the recent history of files mentioned in the query
+        let mut context_assembler = self.context_assembler.clone();
+        if let (Some(working_dir), true) = (&self.working_directory, self.git_history) {
+            context_assembler =
+                context_assembler.with_provider(Arc::new(GitHistory::new(working_dir)));
+        }
+        if !context_assembler.is_empty() {
+            executor = executor.with_context_assembler(context_assembler);
+        }
+
         // Add the original user query
         executor.add_user_message(query.to_string());
 
         // Execute and get result
         let result = executor.execute().await?;
+        self.last_turn
+            .record(executor.evidence().to_vec(), executor.trail().to_vec());
 
         // Save updated conversation history for future calls
         // We need to make sure we preserve the system message in the history
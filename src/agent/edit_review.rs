@@ -0,0 +1,299 @@
+use crate::agent::tools::{ToolCall, WriteParams};
+use crate::communication::rpc::get_global_rpc_server;
+use crate::tools::fs::diff::{DiffLine, DiffTools};
+use crate::tools::fs::file_ops::FileOps;
+use crate::tools::git_stage::Hunk;
+use serde_json::json;
+use std::path::Path;
+use std::time::Duration;
+
+/// Request a client that declared `supports_edit_review` answers with the hunks of
+/// a proposed Edit or Write the user accepts
+pub const REVIEW_EDIT_METHOD: &str = "oli/reviewEdit";
+
+/// Tools whose changes are reviewed hunk by hunk
+pub const REVIEWED_TOOLS: &[&str] = &["Edit", "Write"];
+
+/// How long to wait for the user to review an edit
+const REVIEW_EDIT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Unchanged lines shown before and after each hunk
+const CONTEXT_LINES: usize = 3;
+
+/// What the user decided about a proposed edit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReviewDecision {
+    /// Every hunk, so the call runs as proposed
+    AcceptAll,
+    /// No hunk, so the call is denied
+    RejectAll,
+    /// Some hunks: the file is written with only those applied. `rejected` lists
+    /// the ids of the others.
+    Partial {
+        content: String,
+        rejected: Vec<String>,
+    },
+}
+
+/// A proposed Edit or Write split into hunks, each of which the user accepts or
+/// rejects before the tool runs
+#[derive(Debug, Clone)]
+pub struct EditReview {
+    pub path: String,
+    pub hunks: Vec<Hunk>,
+    original: String,
+    proposed: String,
+}
+
+impl EditReview {
+    /// The review of a call, or None when it isn't an Edit or Write or can't be
+    /// previewed, in which case the tool reports the problem when it runs
+    pub fn for_call(tool_call: &ToolCall) -> Option<Self> {
+        let (path, original, proposed) = match tool_call {
+            ToolCall::Edit(params) => {
+                let path = Path::new(&params.file_path);
+                let (proposed, _) = FileOps::generate_edit_diff(
+                    path,
+                    &params.old_string,
+                    &params.new_string,
+                    params.expected_replacements,
+                )
+                .ok()?;
+                (&params.file_path, FileOps::read_file(path).ok()?, proposed)
+            }
+            ToolCall::Write(params) => {
+                let path = Path::new(&params.file_path);
+                let original = if path.exists() {
+                    FileOps::read_file(path).ok()?
+                } else {
+                    String::new()
+                };
+                let proposed = params.content_after(&original);
+                (&params.file_path, original, proposed)
+            }
+            _ => return None,
+        };
+        Some(Self::new(path, &original, &proposed))
+    }
+
+    /// Split the change from `original` to `proposed` into hunks
+    pub fn new(path: &str, original: &str, proposed: &str) -> Self {
+        let diff = DiffTools::generate_diff(original, proposed);
+        let hunks = change_runs(&diff)
+            .into_iter()
+            .enumerate()
+            .map(|(index, run)| hunk(index, &diff, run))
+            .collect();
+        Self {
+            path: path.to_string(),
+            hunks,
+            original: original.to_string(),
+            proposed: proposed.to_string(),
+        }
+    }
+
+    /// The decision for the hunks with the given ids accepted
+    pub fn decide(&self, accepted: &[String]) -> ReviewDecision {
+        let rejected: Vec<String> = self
+            .hunks
+            .iter()
+            .filter(|hunk| !accepted.contains(&hunk.id))
+            .map(|hunk| hunk.id.clone())
+            .collect();
+        if rejected.is_empty() {
+            ReviewDecision::AcceptAll
+        } else if rejected.len() == self.hunks.len() {
+            ReviewDecision::RejectAll
+        } else {
+            ReviewDecision::Partial {
+                content: self.apply(accepted),
+                rejected,
+            }
+        }
+    }
+
+    /// The original content with only the hunks with the given ids applied
+    pub fn apply(&self, accepted: &[String]) -> String {
+        let diff = DiffTools::generate_diff(&self.original, &self.proposed);
+        let runs = change_runs(&diff);
+
+        let mut lines: Vec<&str> = Vec::new();
+        let mut position = 0;
+        for (index, (start, end)) in runs.into_iter().enumerate() {
+            lines.extend(diff[position..start].iter().map(line_text));
+            let keep_added = accepted.contains(&index.to_string());
+            lines.extend(diff[start..end].iter().filter_map(|line| match line {
+                DiffLine::Added(text) if keep_added => Some(text.as_str()),
+                DiffLine::Removed(text) if !keep_added => Some(text.as_str()),
+                _ => None,
+            }));
+            position = end;
+        }
+        lines.extend(diff[position..].iter().map(line_text));
+
+        if lines.is_empty() {
+            return String::new();
+        }
+        let newline = if self.original.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let mut content = lines.join(newline);
+        let ends_with_newline = if self.proposed.is_empty() {
+            self.original.ends_with('\n')
+        } else {
+            self.proposed.ends_with('\n')
+        };
+        if ends_with_newline {
+            content.push_str(newline);
+        }
+        content
+    }
+
+    /// Ask the client which hunks to apply. Errors and timeouts reject the edit.
+    pub fn request(&self, tool: &str) -> ReviewDecision {
+        let Some(server) = get_global_rpc_server() else {
+            return ReviewDecision::RejectAll;
+        };
+        let params = json!({
+            "tool": tool,
+            "path": self.path,
+            "is_new_file": self.original.is_empty() && !Path::new(&self.path).exists(),
+            "hunks": self.hunks,
+        });
+        match server.request_client(REVIEW_EDIT_METHOD, params, REVIEW_EDIT_TIMEOUT) {
+            Ok(answer) => {
+                let accepted: Vec<String> = answer["accepted"]
+                    .as_array()
+                    .map(|ids| {
+                        ids.iter()
+                            .filter_map(|id| id.as_str().map(str::to_string))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                self.decide(&accepted)
+            }
+            Err(_) => ReviewDecision::RejectAll,
+        }
+    }
+
+    /// The call that writes the accepted hunks in place of the proposed change
+    pub fn partial_call(&self, content: String) -> ToolCall {
+        ToolCall::Write(WriteParams {
+            file_path: self.path.clone(),
+            content,
+            // The reviewed call already chose where the file goes
+            create_dirs: Some(true),
+            mode: None,
+        })
+    }
+
+    /// Message for the model when the user rejected every hunk
+    pub fn rejected_message(&self, tool: &str) -> String {
+        format!(
+            "ERROR EXECUTING TOOL: {tool} was rejected by the user, who declined every change to {}",
+            self.path
+        )
+    }
+
+    /// Note added to the result when the user rejected some hunks, so the model
+    /// knows the file differs from what it proposed
+    pub fn partial_note(&self, rejected: &[String]) -> String {
+        let numbers: Vec<String> = rejected
+            .iter()
+            .filter_map(|id| id.parse::<usize>().ok())
+            .map(|index| (index + 1).to_string())
+            .collect();
+        format!(
+            "The user rejected hunk{} {} of {}, so {} only has the other changes. Read it before editing it again.",
+            if numbers.len() == 1 { "" } else { "s" },
+            numbers.join(", "),
+            self.hunks.len(),
+            self.path
+        )
+    }
+}
+
+/// Whether the connected client reviews Edit and Write calls hunk by hunk
+pub fn client_reviews_edits() -> bool {
+    get_global_rpc_server()
+        .and_then(|server| server.client_capabilities())
+        .is_some_and(|caps| caps.supports_edit_review)
+}
+
+/// The `[start, end)` ranges of consecutive added and removed lines
+fn change_runs(diff: &[DiffLine]) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (i, line) in diff.iter().enumerate() {
+        match (line, start) {
+            (DiffLine::Context(_), Some(run_start)) => {
+                runs.push((run_start, i));
+                start = None;
+            }
+            (DiffLine::Added(_) | DiffLine::Removed(_), None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(run_start) = start {
+        runs.push((run_start, diff.len()));
+    }
+    runs
+}
+
+/// The hunk of a run of changes, with the unchanged lines around it
+fn hunk(index: usize, diff: &[DiffLine], (start, end): (usize, usize)) -> Hunk {
+    let from = start.saturating_sub(CONTEXT_LINES);
+    let to = (end + CONTEXT_LINES).min(diff.len());
+
+    // Line numbers of the first shown line in the old and new versions
+    let mut old_line = 1;
+    let mut new_line = 1;
+    for line in &diff[..from] {
+        match line {
+            DiffLine::Context(_) => {
+                old_line += 1;
+                new_line += 1;
+            }
+            DiffLine::Removed(_) => old_line += 1,
+            DiffLine::Added(_) => new_line += 1,
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut old_count, mut new_count, mut added, mut removed) = (0, 0, 0, 0);
+    for line in &diff[from..to] {
+        match line {
+            DiffLine::Context(text) => {
+                lines.push(format!(" {text}"));
+                old_count += 1;
+                new_count += 1;
+            }
+            DiffLine::Removed(text) => {
+                lines.push(format!("-{text}"));
+                old_count += 1;
+                removed += 1;
+            }
+            DiffLine::Added(text) => {
+                lines.push(format!("+{text}"));
+                new_count += 1;
+                added += 1;
+            }
+        }
+    }
+
+    Hunk {
+        id: index.to_string(),
+        header: format!("@@ -{old_line},{old_count} +{new_line},{new_count} @@"),
+        lines,
+        added,
+        removed,
+    }
+}
+
+fn line_text(line: &DiffLine) -> &str {
+    match line {
+        DiffLine::Added(text) | DiffLine::Removed(text) | DiffLine::Context(text) => text,
+    }
+}
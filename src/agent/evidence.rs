@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Longest tool output kept on an evidence entry; the full result stays in the conversation
+pub const EVIDENCE_EXCERPT_CHARS: usize = 2000;
+
+/// A numbered tool result the final answer can cite as `[#index]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Evidence {
+    pub index: usize,
+    pub tool: String,
+    pub tool_call_id: String,
+    /// File, pattern, command or objective the tool acted on, when there is one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Start of the tool output, cut to `EVIDENCE_EXCERPT_CHARS`
+    pub excerpt: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+impl Evidence {
+    pub fn new(
+        index: usize,
+        tool: &str,
+        tool_call_id: &str,
+        arguments: &Value,
+        output: &str,
+    ) -> Self {
+        let truncated = output.chars().count() > EVIDENCE_EXCERPT_CHARS;
+        let excerpt = if truncated {
+            output.chars().take(EVIDENCE_EXCERPT_CHARS).collect()
+        } else {
+            output.to_string()
+        };
+
+        Self {
+            index,
+            tool: tool.to_string(),
+            tool_call_id: tool_call_id.to_string(),
+            target: evidence_target(arguments),
+            excerpt,
+            truncated,
+        }
+    }
+
+    /// The tag the model cites this entry with, e.g. `[#3]`
+    pub fn tag(&self) -> String {
+        citation_tag(self.index)
+    }
+}
+
+pub fn citation_tag(index: usize) -> String {
+    format!("[#{index}]")
+}
+
+/// `text` without the citation tag a tool result is numbered with, e.g. `[#3] `
+pub fn strip_citation_tag(text: &str) -> &str {
+    text.strip_prefix("[#")
+        .and_then(|rest| rest.split_once("] "))
+        .filter(|(index, _)| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+        .map_or(text, |(_, rest)| rest)
+}
+
+/// Evidence indexes cited in `text` as `[#N]`, in order of first appearance
+pub fn parse_citations(text: &str) -> Vec<usize> {
+    let mut cited = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("[#") {
+        rest = &rest[start + 2..];
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if digits.is_empty() || !rest[digits.len()..].starts_with(']') {
+            continue;
+        }
+        if let Ok(index) = digits.parse::<usize>() {
+            if !cited.contains(&index) {
+                cited.push(index);
+            }
+        }
+        rest = &rest[digits.len() + 1..];
+    }
+
+    cited
+}
+
+/// Citations in `text` that don't match any entry in `evidence`
+pub fn unresolved_citations(text: &str, evidence: &[Evidence]) -> Vec<usize> {
+    parse_citations(text)
+        .into_iter()
+        .filter(|index| !evidence.iter().any(|entry| entry.index == *index))
+        .collect()
+}
+
+// The argument identifying what a tool looked at; the paths, comma-separated, for
+// tools given a list of files
+fn evidence_target(arguments: &Value) -> Option<String> {
+    if let Some(files) = arguments.get("files").and_then(Value::as_array) {
+        let paths: Vec<&str> = files
+            .iter()
+            .filter_map(|file| file.get("path").and_then(Value::as_str))
+            .collect();
+        return (!paths.is_empty()).then(|| paths.join(", "));
+    }
+
+    ["file_path", "path", "pattern", "command", "objective"]
+        .iter()
+        .find_map(|key| arguments.get(key).and_then(Value::as_str))
+        .map(str::to_string)
+}
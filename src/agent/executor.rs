@@ -1,18 +1,116 @@
-use crate::agent::tools::{get_tool_definitions, ToolCall as AgentToolCall};
+use crate::agent::approval::{ask_client, ApprovalBackend, ApprovalDecision, ApprovalRequest};
+use crate::agent::audit::{AuditEntry, AuditLog};
+use crate::agent::budget::TurnBudget;
+use crate::agent::checkpoints::Checkpoints;
+use crate::agent::conflicts::{ReadSnapshots, Resolution};
+use crate::agent::edit_review::{client_reviews_edits, EditReview, ReviewDecision, REVIEWED_TOOLS};
+use crate::agent::evidence::{citation_tag, strip_citation_tag, Evidence};
+use crate::agent::permissions::{
+    touches_permission_rules, PermissionFile, PermissionLevel, PermissionPolicy, PermissionRules,
+};
+use crate::agent::policy::ToolPolicy;
+use crate::agent::result_limits::ResultLimits;
+use crate::agent::tool_queue::{QueuedCallStatus, ToolQueue};
+use crate::agent::tool_repair::{repair_arguments, ArgumentRepair};
+use crate::agent::tool_stats::{ToolCallRecord, ToolFailure, ToolStatsLog};
+use crate::agent::tools::{get_tool_definitions, AgentParams, ToolCall as AgentToolCall};
+use crate::agent::trail::{TrailCall, TrailRound};
 use crate::apis::api_client::{
-    CompletionOptions, DynApiClient, Message, ToolCall as ApiToolCall, ToolDefinition, ToolResult,
+    CompletionOptions, DynApiClient, Message, ModelParameters, ToolCall as ApiToolCall, ToolChoice,
+    ToolDefinition, ToolResult,
 };
-use crate::prompts::add_working_directory_to_prompt;
+use crate::apis::retry::{with_retry_listener, RetryEvent, RetryListener, RetryPolicy};
+use crate::communication::capabilities::RISKY_TOOLS;
+use crate::communication::rpc::ClientScope;
+use crate::context::provider::{ContextAssembler, ContextRequest};
+use crate::prompts::{add_working_directory_to_prompt, SUB_AGENT_PROMPT};
+use crate::tools::remote::ExecutionBackend;
+use crate::tools::shell::EnvSnapshot;
 use anyhow::{Context, Result};
 use serde_json::{self, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+/// Prefix of the user message that carries a tool result in the conversation
+const TOOL_RESULT_PREFIX: &str = "Tool result for call ";
+/// Tool results shorter than this are always stored in full
+const DEDUP_MIN_RESULT_LEN: usize = 100;
+/// Start of the reference a repeated tool result is replaced with, followed by the
+/// id of the call with the identical result
+const DEDUP_REFERENCE_PREFIX: &str = "Same as result for call ";
+const DEDUP_REFERENCE_SUFFIX: &str = " (identical output omitted to save context)";
+/// Prefix of the message returned when a turn is interrupted before it finishes
+pub const PARTIAL_RESULT_PREFIX: &str = "[Partial result]";
+/// Most tool call rounds one turn may run
+pub const MAX_LOOPS: usize = 100;
+/// Tool that delegates an objective to a sub-agent with its own conversation
+pub const SUB_AGENT_TOOL: &str = "Agent";
+/// Marker sent for every executed tool, forwarded unprefixed from sub-agents so
+/// tool counts still add up
+const TOOL_EXECUTED_MARKER: &str = "[TOOL_EXECUTED]";
+/// Environment variable limiting how long a turn may run, in seconds
+pub const TURN_TIMEOUT_ENV: &str = "OLI_TURN_TIMEOUT_SECS";
+
+/// Turn time limit configured with `OLI_TURN_TIMEOUT_SECS`, if any
+pub fn turn_time_limit_from_env() -> Option<Duration> {
+    std::env::var(TURN_TIMEOUT_ENV)
+        .ok()
+        .and_then(|secs| secs.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+}
+
+/// Why a turn stopped before the model finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Interruption {
+    TimedOut,
+    Cancelled,
+}
+
 pub struct AgentExecutor {
     api_client: DynApiClient,
     conversation: Vec<Message>,
     tool_definitions: Vec<ToolDefinition>,
     progress_sender: Option<mpsc::Sender<String>>,
+    // Receives the text of each completion as the model generates it
+    delta_sender: Option<mpsc::UnboundedSender<String>>,
+    // Receives the tool queue of each iteration as its calls start and finish
+    tool_queue_sender: Option<mpsc::Sender<ToolQueue>>,
     working_directory: Option<String>,
+    model_parameters: ModelParameters,
+    // Tool choice for the first request of each turn; later requests let the model decide
+    first_tool_choice: ToolChoice,
+    tool_policy: ToolPolicy,
+    approval_backend: Option<ApprovalBackend>,
+    audit_log: Option<AuditLog>,
+    task_id: Option<String>,
+    tool_stats: Option<ToolStatsLog>,
+    context_assembler: Option<ContextAssembler>,
+    time_limit: Option<Duration>,
+    cancel_flag: Option<Arc<AtomicBool>>,
+    // Records files before each edit and Bash call so /undo can restore them
+    checkpoints: Option<Checkpoints>,
+    result_limits: ResultLimits,
+    // Latest assistant text and the tools executed in the current turn, salvaged if it's interrupted
+    turn_content: Option<String>,
+    turn_tools: Vec<String>,
+    // Content hash of each tool result in the conversation -> id of the call that produced it
+    seen_tool_results: HashMap<u64, String>,
+    // Numbered tool results of the current turn, which the final answer cites as [#N]
+    evidence: Vec<Evidence>,
+    // Tool calls of the current turn and how they ended, for explaining it afterwards
+    trail: Vec<TrailRound>,
+    // Rounds and time used by the current turn, told to the model with each request
+    budget: TurnBudget,
+    // Files as the agent last read or wrote them, to hold back edits to files changed since
+    read_snapshots: ReadSnapshots,
 }
 
 impl AgentExecutor {
@@ -31,7 +129,28 @@ impl AgentExecutor {
             conversation: Vec::new(),
             tool_definitions: tool_defs,
             progress_sender: None,
+            delta_sender: None,
+            tool_queue_sender: None,
             working_directory: None,
+            model_parameters: ModelParameters::default(),
+            first_tool_choice: ToolChoice::Auto,
+            tool_policy: ToolPolicy::default(),
+            approval_backend: None,
+            audit_log: None,
+            task_id: None,
+            tool_stats: None,
+            context_assembler: None,
+            time_limit: None,
+            cancel_flag: None,
+            checkpoints: None,
+            result_limits: ResultLimits::unlimited(),
+            turn_content: None,
+            turn_tools: Vec::new(),
+            seen_tool_results: HashMap::new(),
+            evidence: Vec::new(),
+            trail: Vec::new(),
+            budget: TurnBudget::new(MAX_LOOPS, None),
+            read_snapshots: ReadSnapshots::default(),
         }
     }
 
@@ -66,9 +185,30 @@ impl AgentExecutor {
             }
         }
 
+        // Track tool results already in the history so repeats can be deduplicated.
+        // Results were hashed before they were numbered, so the tag is left out.
+        self.seen_tool_results.clear();
+        for msg in &history {
+            if let Some((call_id, result)) = split_tool_result(&msg.content) {
+                self.seen_tool_results
+                    .entry(hash_tool_result(strip_citation_tag(result)))
+                    .or_insert_with(|| call_id.to_string());
+            }
+        }
+
         self.conversation = history;
     }
 
+    /// Numbered tool results of the last turn, in the order they ran
+    pub fn evidence(&self) -> &[Evidence] {
+        &self.evidence
+    }
+
+    /// Tool calls of the last turn, round by round
+    pub fn trail(&self) -> &[TrailRound] {
+        &self.trail
+    }
+
     pub fn get_conversation_history(&self) -> Vec<Message> {
         self.conversation.clone()
     }
@@ -78,6 +218,85 @@ impl AgentExecutor {
         self
     }
 
+    /// Stream the text of each completion as it is generated. Text of later rounds
+    /// is separated from earlier text by a blank line.
+    pub fn with_delta_sender(mut self, sender: mpsc::UnboundedSender<String>) -> Self {
+        self.delta_sender = Some(sender);
+        self
+    }
+
+    /// Send the tool queue of each iteration whenever one of its calls starts or finishes
+    pub fn with_tool_queue_sender(mut self, sender: mpsc::Sender<ToolQueue>) -> Self {
+        self.tool_queue_sender = Some(sender);
+        self
+    }
+
+    /// Override the default sampling parameters used for every completion
+    pub fn with_model_parameters(mut self, parameters: ModelParameters) -> Self {
+        self.model_parameters = parameters;
+        self
+    }
+
+    /// Constrain the first request of each turn, e.g. to force a tool call before
+    /// the model answers
+    pub fn with_first_tool_choice(mut self, choice: ToolChoice) -> Self {
+        self.first_tool_choice = choice;
+        self
+    }
+
+    pub fn with_tool_policy(mut self, policy: ToolPolicy) -> Self {
+        self.tool_policy = policy;
+        self
+    }
+
+    pub fn with_approval_backend(mut self, backend: Option<ApprovalBackend>) -> Self {
+        self.approval_backend = backend;
+        self
+    }
+
+    /// Record Edit, Write and Bash executions, attributed to `task_id`, in an audit log
+    pub fn with_audit_log(mut self, audit_log: Option<AuditLog>, task_id: Option<String>) -> Self {
+        self.audit_log = audit_log;
+        self.task_id = task_id;
+        self
+    }
+
+    /// Record the outcome of every tool call, for benchmark reports
+    pub fn with_tool_stats(mut self, tool_stats: Option<ToolStatsLog>) -> Self {
+        self.tool_stats = tool_stats;
+        self
+    }
+
+    /// Add the context assembled from these providers to every model request
+    pub fn with_context_assembler(mut self, assembler: ContextAssembler) -> Self {
+        self.context_assembler = Some(assembler);
+        self
+    }
+
+    /// Stop a turn that runs longer than `limit`, returning a partial result
+    pub fn with_time_limit(mut self, limit: Option<Duration>) -> Self {
+        self.time_limit = limit;
+        self
+    }
+
+    /// Stop the turn, returning a partial result, once `flag` is set
+    pub fn with_cancel_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.cancel_flag = Some(flag);
+        self
+    }
+
+    /// Record checkpoints before each Edit, Write, WriteMany and Bash call
+    pub fn with_checkpoints(mut self, checkpoints: Option<Checkpoints>) -> Self {
+        self.checkpoints = checkpoints;
+        self
+    }
+
+    /// Limit how much of each tool result is passed to the model (unlimited by default)
+    pub fn with_result_limits(mut self, limits: ResultLimits) -> Self {
+        self.result_limits = limits;
+        self
+    }
+
     pub fn add_system_message(&mut self, content: String) {
         // If we have a working directory, ensure it's included in the system message
         let system_content = if let Some(cwd) = &self.working_directory {
@@ -108,18 +327,70 @@ impl AgentExecutor {
         self.conversation.push(Message::user(content));
     }
 
+    /// Run the turn to completion. If the time limit is hit or the turn is cancelled,
+    /// whatever was produced so far is returned as a partial result instead.
     pub async fn execute(&mut self) -> Result<String> {
+        self.turn_content = None;
+        self.turn_tools.clear();
+        self.evidence.clear();
+        self.trail.clear();
+        self.read_snapshots.clear();
+        self.budget = TurnBudget::new(MAX_LOOPS, self.time_limit);
+
+        let started = Instant::now();
+        let time_limit = self.time_limit;
+        let cancel_flag = self.cancel_flag.clone();
+
+        let retry_listener = self.retry_listener();
+        let interruption = {
+            let turn = with_retry_listener(retry_listener, self.run_turn());
+            tokio::pin!(turn);
+            tokio::select! {
+                result = &mut turn => return result,
+                interruption = wait_for_interruption(time_limit, cancel_flag) => interruption,
+            }
+        };
+
+        Ok(self.salvage_partial_result(interruption, started.elapsed()))
+    }
+
+    // Reports requests retried after rate limits or network errors as progress
+    fn retry_listener(&self) -> RetryListener {
+        let sender = self.progress_sender.clone();
+        Arc::new(move |event: &RetryEvent| {
+            if let Some(sender) = &sender {
+                let _ = sender.try_send(event.message());
+            }
+        })
+    }
+
+    async fn run_turn(&mut self) -> Result<String> {
         // Log working directory if available
         self.log_working_directory().await;
-        if let Some(cwd) = &self.working_directory {
-            self.add_system_message(format!("## WORKING DIRECTORY\n{cwd}"));
+        // Keep the system prompt, including any project instructions, and make sure
+        // it names the working directory
+        if let Some(cwd) = self.working_directory.clone() {
+            let system = self
+                .conversation
+                .iter()
+                .find(|msg| msg.role == "system")
+                .map(|msg| msg.content.clone());
+            match system {
+                Some(content) => self.add_system_message(content),
+                None => self.add_system_message(format!("## WORKING DIRECTORY\n{cwd}")),
+            }
         }
 
         // Create standard completion options
         let options = self.create_completion_options();
 
-        // Get initial completion
-        let (content, tool_calls) = self.get_initial_completion(&options).await?;
+        // Get initial completion, with the turn's first tool choice
+        let first_options = CompletionOptions {
+            tool_choice: self.first_tool_choice.clone(),
+            ..options.clone()
+        };
+        let (content, tool_calls) = self.get_initial_completion(&first_options).await?;
+        self.record_turn_content(&content);
 
         // If no tool calls, just return the response
         if tool_calls.is_none() {
@@ -146,14 +417,20 @@ impl AgentExecutor {
 
     // Helper method to create standard completion options
     fn create_completion_options(&self) -> CompletionOptions {
-        CompletionOptions {
+        let mut options = CompletionOptions {
             temperature: Some(0.25),
             top_p: Some(0.95),
             max_tokens: Some(4096),
             tools: Some(self.tool_definitions.clone()),
-            require_tool_use: false,
             json_schema: None,
-        }
+            tool_choice: ToolChoice::Auto,
+            // Ask for one tool at a time when each call waits on an external approval,
+            // so the model sees a denial before it plans the next call
+            parallel_tool_calls: self.approval_backend.is_none(),
+            retry: RetryPolicy::default(),
+        };
+        self.model_parameters.apply_to(&mut options);
+        options
     }
 
     // Helper method to get initial completion
@@ -161,11 +438,69 @@ impl AgentExecutor {
         &self,
         options: &CompletionOptions,
     ) -> Result<(String, Option<Vec<ApiToolCall>>)> {
+        self.complete(options.clone(), None).await
+    }
+
+    // Request a completion, streaming its text to the delta sender if there is one
+    async fn complete(
+        &self,
+        options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+    ) -> Result<(String, Option<Vec<ApiToolCall>>)> {
+        let messages = self.request_messages();
+        let Some(sender) = self.delta_sender.clone() else {
+            return self
+                .api_client
+                .complete_with_tools(messages, options, tool_results)
+                .await;
+        };
+
+        let mut separator = self.turn_content.is_some().then_some("\n\n");
+        let mut on_delta = move |text: &str| {
+            let text = match separator.take() {
+                Some(separator) => format!("{separator}{text}"),
+                None => text.to_string(),
+            };
+            let _ = sender.send(text);
+        };
         self.api_client
-            .complete_with_tools(self.conversation.clone(), options.clone(), None)
+            .complete_with_tools_streaming(messages, options, tool_results, &mut on_delta)
             .await
     }
 
+    // The conversation with the turn's budget and freshly assembled context appended to
+    // the system message. Both are rebuilt for every request and never stored in the
+    // conversation.
+    fn request_messages(&self) -> Vec<Message> {
+        let mut messages = self.conversation.clone();
+        let context = match self.assemble_context() {
+            Some(context) => format!("{}\n\n{context}", self.budget.note()),
+            None => self.budget.note(),
+        };
+
+        match messages.iter_mut().find(|msg| msg.role == "system") {
+            Some(system) => system.content = format!("{}\n\n{context}", system.content),
+            None => messages.insert(0, Message::system(context)),
+        }
+        messages
+    }
+
+    fn assemble_context(&self) -> Option<String> {
+        let assembler = self.context_assembler.as_ref()?;
+        let query = self
+            .conversation
+            .iter()
+            .rev()
+            .find(|msg| msg.role == "user" && !msg.content.starts_with(TOOL_RESULT_PREFIX))
+            .map(|msg| msg.content.as_str())
+            .unwrap_or_default();
+        let request = ContextRequest {
+            query,
+            working_directory: self.working_directory.as_deref(),
+        };
+        assembler.assemble(&request)
+    }
+
     // Helper method to add an assistant's response to the conversation
     fn add_assistant_response(&mut self, content: &str, tool_calls: &Option<Vec<ApiToolCall>>) {
         add_assistant_message_to_conversation(&mut self.conversation, content, tool_calls);
@@ -185,7 +520,6 @@ impl AgentExecutor {
         let mut current_content = initial_content;
         let mut current_tool_calls = initial_tool_calls;
         let mut loop_count = 0;
-        const MAX_LOOPS: usize = 100; // Limit for tool call loops
         let mut task_completed = false;
 
         while let Some(ref calls) = current_tool_calls {
@@ -196,9 +530,12 @@ impl AgentExecutor {
             {
                 break;
             }
+            self.budget.set_iterations_used(loop_count);
 
             // Execute all tool calls
-            let tool_results = self.execute_tool_calls(calls, loop_count).await;
+            let tool_results = self
+                .execute_tool_calls(&current_content, calls, loop_count)
+                .await;
 
             // Get next completion with appropriate options
             let (next_content, next_tool_calls, is_complete) = self
@@ -206,6 +543,7 @@ impl AgentExecutor {
                 .await?;
 
             // Update state for next iteration
+            self.record_turn_content(&next_content);
             current_content = next_content;
             current_tool_calls = next_tool_calls;
 
@@ -289,10 +627,8 @@ impl AgentExecutor {
         };
 
         // Request completion with tool results
-        let (next_content, next_tool_calls) = self
-            .api_client
-            .complete_with_tools(self.conversation.clone(), next_options, Some(tool_results))
-            .await?;
+        let (next_content, next_tool_calls) =
+            self.complete(next_options, Some(tool_results)).await?;
 
         // Process response to check for completion status
         let (processed_content, is_complete) = process_response(&next_content);
@@ -306,7 +642,7 @@ impl AgentExecutor {
         base_options: &CompletionOptions,
     ) -> CompletionOptions {
         CompletionOptions {
-            require_tool_use: false,
+            tool_choice: ToolChoice::Auto,
             json_schema: Some(
                 r#"{
                     "type": "object",
@@ -334,7 +670,7 @@ impl AgentExecutor {
 
     // Log warning if approaching max loops
     async fn log_approaching_max_loops(&self, loop_count: usize, max_loops: usize) {
-        if loop_count >= max_loops - 10 && loop_count % 5 == 0 {
+        if loop_count >= max_loops - 10 && loop_count.is_multiple_of(5) {
             if let Some(sender) = &self.progress_sender {
                 let _ = sender
                     .send(
@@ -355,7 +691,7 @@ impl AgentExecutor {
         }
 
         let final_options = CompletionOptions {
-            require_tool_use: false,
+            tool_choice: ToolChoice::Auto,
             json_schema: Some(
                 r#"{
                     "type": "object",
@@ -375,7 +711,7 @@ impl AgentExecutor {
         // Request final summary
         let (final_content, _) = self
             .api_client
-            .complete_with_tools(self.conversation.clone(), final_options, None)
+            .complete_with_tools(self.request_messages(), final_options, None)
             .await?;
 
         let (processed_content, _) = process_response(&final_content);
@@ -384,75 +720,762 @@ impl AgentExecutor {
 
     async fn execute_tool_calls(
         &mut self,
+        content: &str,
         calls: &[ApiToolCall],
-        _loop_count: usize,
+        loop_count: usize,
     ) -> Vec<ToolResult> {
         let mut results = Vec::with_capacity(calls.len());
+        let mut queue = ToolQueue::new(self.task_id.clone(), loop_count, calls);
+        let mut round = TrailRound::new(loop_count, content);
+        self.send_tool_queue(&queue).await;
+
+        // Runs of consecutive read-only calls execute concurrently, while calls that
+        // change files or run commands execute alone, so each sees the changes of the
+        // calls before it. Results keep the order of the calls.
+        let mut start = 0;
+        while start < calls.len() {
+            let end = if runs_concurrently(&calls[start].name) {
+                start
+                    + calls[start..]
+                        .iter()
+                        .take_while(|call| runs_concurrently(&call.name))
+                        .count()
+            } else {
+                start + 1
+            };
 
-        for (i, call) in calls.iter().enumerate() {
-            // Send tool execution progress message
-            if let Some(sender) = &self.progress_sender {
-                let _ = sender
-                    .send(format!("⏺ [{}] Executing {}...", call.name, call.name))
-                    .await;
+            let mut batch = Vec::with_capacity(end - start);
+            for (i, call) in calls.iter().enumerate().take(end).skip(start) {
+                queue.start(i);
+                self.send_tool_queue(&queue).await;
+                batch.push(self.prepare_call(i, call).await);
             }
 
-            // Parse the tool call into our enum
-            let tool_call: AgentToolCall = match parse_tool_call(&call.name, &call.arguments) {
-                Ok(tc) => tc,
-                Err(e) => {
-                    send_error_message(
-                        &self.progress_sender,
-                        &format!("Failed to parse tool call: {e}"),
-                    )
+            let outputs = if batch.len() > 1 {
+                execute_concurrently(&batch).await
+            } else {
+                let mut outputs = Vec::with_capacity(batch.len());
+                for prepared in &batch {
+                    outputs.push(self.execute_prepared(prepared).await);
+                }
+                outputs
+            };
+
+            for (prepared, output) in batch.into_iter().zip(outputs) {
+                let result = self
+                    .finish_call(prepared, output, &mut queue, &mut round)
                     .await;
+                results.push(result);
+            }
+            start = end;
+        }
 
-                    // Add error result and continue to next tool call
-                    let tool_call_id = call.id.clone().unwrap_or_else(|| format!("tool_{i}"));
-                    let error_message = format!("ERROR PARSING TOOL CALL: {e}. Please check the format of your arguments and try again.");
+        self.trail.push(round);
+        results
+    }
 
-                    self.add_tool_result_to_conversation(&tool_call_id, &error_message);
-                    results.push(ToolResult {
-                        tool_call_id,
-                        output: error_message,
-                    });
+    /// Repair and parse a call's arguments, then check whether it may run
+    async fn prepare_call(&self, index: usize, call: &ApiToolCall) -> PreparedCall {
+        // Send tool execution progress message
+        if let Some(sender) = &self.progress_sender {
+            let _ = sender
+                .send(format!("⏺ [{}] Executing {}...", call.name, call.name))
+                .await;
+        }
 
-                    continue;
-                }
-            };
+        // Fix malformed arguments, e.g. JSON sent as a string or with trailing commas,
+        // before they're validated
+        let schema = self
+            .tool_definitions
+            .iter()
+            .find(|def| def.name == call.name)
+            .map(|def| &def.parameters);
+        let (arguments, repairs) = repair_arguments(&call.arguments, schema);
+        let call = if repairs.is_empty() {
+            call.clone()
+        } else {
+            ApiToolCall {
+                arguments,
+                ..call.clone()
+            }
+        };
 
-            // Execute the tool with preview for file modification tools
-            let result = execute_tool_with_preview(&tool_call, call, &self.progress_sender).await;
+        // Parse the tool call into our enum
+        let mut parsed = parse_tool_call(&call.name, &call.arguments);
+
+        // The tool policy or the approval backend may deny it, or it would overwrite
+        // a change made to the file since the agent read it. The user may reject the
+        // hunks of an edit the client reviews, or some of them.
+        let mut partial = None;
+        let denial = match &parsed {
+            Ok(tool_call) => match self.check_tool_permission(&call).await {
+                Permission::Deny(denial) => Some(denial),
+                Permission::Allow => self.check_conflicts(tool_call, &call).await,
+                Permission::Review => match self.check_conflicts(tool_call, &call).await {
+                    Some(denial) => Some(denial),
+                    None => match self.review_edit(tool_call, &call).await {
+                        Ok(accepted) => {
+                            partial = accepted;
+                            None
+                        }
+                        Err(denial) => Some(denial),
+                    },
+                },
+            },
+            Err(_) => None,
+        };
+        let note = partial.map(|(tool_call, note)| {
+            parsed = Ok(tool_call);
+            note
+        });
 
-            // Create a valid tool result ID
-            let tool_call_id = call.id.clone().unwrap_or_else(|| format!("tool_{i}"));
+        PreparedCall {
+            index,
+            call,
+            repairs,
+            parsed,
+            denial,
+            note,
+        }
+    }
 
-            // Send tool execution completed message
-            if let Some(sender) = &self.progress_sender {
-                let _ = sender.send("[TOOL_EXECUTED]".to_string()).await;
+    /// Execute a call on its own, with a preview for file modification tools, after
+    /// recording a checkpoint of what it changes. None if it can't run.
+    async fn execute_prepared(&self, prepared: &PreparedCall) -> Option<(String, Option<String>)> {
+        let tool_call = prepared.runnable()?;
+        if let AgentToolCall::Agent(params) = tool_call {
+            return Some((self.run_sub_agent(params).await, None));
+        }
+        if let Some(checkpoints) = &self.checkpoints {
+            // Bash runs, and relative paths resolve, in the process's directory
+            let working_dir = std::env::current_dir().unwrap_or_default();
+            checkpoints.record_call(&prepared.call.name, &prepared.call.arguments, &working_dir);
+        }
+        let (output, diff) =
+            execute_tool_with_preview(tool_call, &prepared.call, &self.progress_sender).await;
+        if let Some(checkpoints) = &self.checkpoints {
+            checkpoints.complete_call(&prepared.call.name);
+        }
+        match &prepared.note {
+            Some(note) if !output.starts_with("ERROR EXECUTING TOOL") => {
+                Some((format!("{output}\n\n{note}"), diff))
+            }
+            _ => Some((output, diff)),
+        }
+    }
+
+    /// Record how a call ended and add its result to the conversation
+    async fn finish_call(
+        &mut self,
+        prepared: PreparedCall,
+        output: Option<(String, Option<String>)>,
+        queue: &mut ToolQueue,
+        round: &mut TrailRound,
+    ) -> ToolResult {
+        let PreparedCall {
+            index: i,
+            call,
+            repairs,
+            parsed,
+            denial,
+            ..
+        } = prepared;
+        // Create a valid tool result ID
+        let tool_call_id = call.id.clone().unwrap_or_else(|| format!("tool_{i}"));
+
+        let tool_call = match parsed {
+            Ok(tool_call) => tool_call,
+            Err(e) => {
+                send_error_message(
+                    &self.progress_sender,
+                    &format!("Failed to parse tool call: {e}"),
+                )
+                .await;
+                let error = format!("{e:#}");
+                self.record_tool_stats(
+                    &call,
+                    &repairs,
+                    Some(ToolFailure::from_parse_error(&error)),
+                    Some(error),
+                );
+
+                let error_message = format!("ERROR PARSING TOOL CALL: {e}. Please check the format of your arguments and try again.");
+                self.add_tool_result_to_conversation(&tool_call_id, &error_message);
+                round.calls.push(TrailCall::new(
+                    &call.name,
+                    &call.arguments,
+                    QueuedCallStatus::Failed,
+                    &error_message,
+                ));
+                queue.finish(i, QueuedCallStatus::Failed);
+                self.send_tool_queue(queue).await;
+                return ToolResult {
+                    tool_call_id,
+                    output: error_message,
+                };
+            }
+        };
+
+        let (result, diff) = match (&denial, output) {
+            (Some(denial), _) => (denial.clone(), None),
+            (None, Some(output)) => output,
+            (None, None) => (
+                format!("ERROR EXECUTING TOOL: {} did not run", call.name),
+                None,
+            ),
+        };
+        if denial.is_none() && !result.starts_with("ERROR EXECUTING TOOL") {
+            self.read_snapshots.record_call(&tool_call);
+        }
+        self.record_audit_entry(&call, denial.as_deref(), diff.as_deref(), &result);
+        if denial.is_some() {
+            self.record_tool_stats(&call, &repairs, Some(ToolFailure::Denied), denial.clone());
+            queue.finish(i, QueuedCallStatus::Denied);
+        } else if result.starts_with("ERROR EXECUTING TOOL") {
+            self.record_tool_stats(
+                &call,
+                &repairs,
+                Some(ToolFailure::ExecutionError),
+                Some(result.clone()),
+            );
+            queue.finish(i, QueuedCallStatus::Failed);
+        } else {
+            self.record_tool_stats(&call, &repairs, None, None);
+            queue.finish(i, QueuedCallStatus::Completed);
+        }
+        self.send_tool_queue(queue).await;
+        round.calls.push(TrailCall::new(
+            &call.name,
+            &call.arguments,
+            queue.calls[i].status,
+            &result,
+        ));
+
+        self.turn_tools.push(call.name.clone());
+
+        // Send tool execution completed message
+        if let Some(sender) = &self.progress_sender {
+            let _ = sender.send(TOOL_EXECUTED_MARKER.to_string()).await;
+        }
+
+        // Cut oversized results down to the tool's limit, then replace repeats of an
+        // earlier identical result with a reference to it
+        let result = self.result_limits.apply(&call.name, result);
+        let result = self.dedup_tool_result(&tool_call_id, result);
+
+        // Number the result so the final answer can cite it, unless the tool was denied
+        let result = if denial.is_none() {
+            let index = self.evidence.len() + 1;
+            self.evidence.push(Evidence::new(
+                index,
+                &call.name,
+                &tool_call_id,
+                &call.arguments,
+                &result,
+            ));
+            format!("{} {result}", citation_tag(index))
+        } else {
+            result
+        };
+
+        // Add tool result to conversation and results collection
+        self.add_tool_result_to_conversation(&tool_call_id, &result);
+        ToolResult {
+            tool_call_id,
+            output: result,
+        }
+    }
+
+    /// Run an Agent call's objective in a fresh executor with its own conversation
+    /// and only the read-only tools this executor may use, returning just its
+    /// report, so the exploration doesn't fill up this conversation. Boxed because
+    /// the sub-agent's own tool calls go through this code again.
+    fn run_sub_agent(&self, params: &AgentParams) -> Pin<Box<dyn Future<Output = String> + Send>> {
+        let mut sub_agent = self.sub_agent();
+        sub_agent.add_system_message(SUB_AGENT_PROMPT.trim().to_string());
+        sub_agent.add_user_message(params.objective.clone());
+
+        Box::pin(async move {
+            match sub_agent.execute().await {
+                Ok(report) => report,
+                Err(e) => format!("ERROR EXECUTING TOOL: The sub-agent failed: {e:#}"),
+            }
+        })
+    }
+
+    // An executor sharing this one's model, limits and working directory, offered
+    // the read-only tools this one may use except Agent, so sub-agents don't nest.
+    // Its progress messages are prefixed and merged into this executor's; it doesn't
+    // stream text or report tool queues, which would mix with this turn's.
+    fn sub_agent(&self) -> AgentExecutor {
+        let offered = |name: &str| {
+            name != SUB_AGENT_TOOL
+                && ToolPolicy::ReadOnly.allows(name)
+                && self.tool_policy.allows(name)
+        };
+        let (tool_definitions, excluded): (Vec<_>, Vec<_>) = self
+            .tool_definitions
+            .iter()
+            .cloned()
+            .partition(|def| offered(&def.name));
+
+        let mut sub_agent = AgentExecutor::new(self.api_client.clone())
+            .with_model_parameters(self.model_parameters)
+            .with_tool_policy(ToolPolicy::Deny(
+                excluded.into_iter().map(|def| def.name).collect(),
+            ))
+            .with_tool_stats(self.tool_stats.clone())
+            .with_time_limit(self.time_limit)
+            .with_result_limits(self.result_limits.clone());
+        sub_agent.tool_definitions = tool_definitions;
+        if let Some(flag) = &self.cancel_flag {
+            sub_agent = sub_agent.with_cancel_flag(flag.clone());
+        }
+        if let Some(working_dir) = &self.working_directory {
+            sub_agent.set_working_directory(working_dir.clone());
+        }
+
+        let Some(parent) = self.progress_sender.clone() else {
+            return sub_agent;
+        };
+        let (sender, mut receiver) = mpsc::channel::<String>(100);
+        tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                let message = if message == TOOL_EXECUTED_MARKER {
+                    message
+                } else {
+                    format!("[agent] {message}")
+                };
+                let _ = parent.send(message).await;
+            }
+        });
+        sub_agent.with_progress_sender(sender)
+    }
+
+    async fn send_tool_queue(&self, queue: &ToolQueue) {
+        if let Some(sender) = &self.tool_queue_sender {
+            let _ = sender.send(queue.clone()).await;
+        }
+    }
+
+    /// Check the tool policy, the permission levels and edit paths, the project's
+    /// always-allow rules and the approval backend, if configured, whether a tool
+    /// may run, or whether the client reviews it instead.
+    async fn check_tool_permission(&self, call: &ApiToolCall) -> Permission {
+        // Deny tools the embedding application has disallowed
+        if !self.tool_policy.allows(&call.name) {
+            return Permission::Deny(format!(
+                "ERROR EXECUTING TOOL: {} was denied by the tool policy",
+                call.name
+            ));
+        }
+
+        let policy = match PermissionPolicy::from_env() {
+            Ok(policy) => policy,
+            Err(e) => {
+                return Permission::Deny(format!(
+                    "ERROR EXECUTING TOOL: {} was denied because the permission policy is invalid: {e:#}",
+                    call.name
+                ))
+            }
+        };
+        let permissions = self
+            .working_directory
+            .as_deref()
+            .and_then(|dir| PermissionFile::for_working_dir(std::path::Path::new(dir)));
+        let rules = match permissions.as_ref().map(PermissionFile::load) {
+            Some(Ok(rules)) => rules,
+            Some(Err(e)) => {
+                send_error_message(&self.progress_sender, &format!("{e:#}")).await;
+                PermissionRules::default()
+            }
+            None => PermissionRules::default(),
+        };
+
+        let level = policy.level_for(&call.name, &rules);
+        if level == PermissionLevel::Deny {
+            return Permission::Deny(format!(
+                "ERROR EXECUTING TOOL: {} is denied by the permission policy",
+                call.name
+            ));
+        }
+
+        // Edits are confined to the edit paths whatever else allows them
+        let working_dir = match &self.working_directory {
+            Some(dir) => std::path::PathBuf::from(dir),
+            None => std::env::current_dir().unwrap_or_default(),
+        };
+        match policy.denied_edit_path(&call.name, &call.arguments, &working_dir) {
+            Ok(None) => {}
+            Ok(Some(path)) => {
+                return Permission::Deny(format!(
+                    "ERROR EXECUTING TOOL: {} may not change {path}: it is outside the allowed edit paths",
+                    call.name
+                ))
+            }
+            Err(e) => {
+                return Permission::Deny(format!(
+                    "ERROR EXECUTING TOOL: {} was denied because the edit paths are invalid: {e:#}",
+                    call.name
+                ))
+            }
+        }
+
+        // Tools and commands the project always allows, and auto tools, run without
+        // asking, unless they'd change the permission rules themselves
+        let changes_rules = touches_permission_rules(
+            &call.name,
+            &call.arguments,
+            &working_dir,
+            permissions.as_ref().map(PermissionFile::path),
+        );
+        if !changes_rules
+            && (level == PermissionLevel::Auto
+                || rules.allows(&call.name, &call.arguments).is_some())
+        {
+            return Permission::Allow;
+        }
+
+        // A client that reviews edits hunk by hunk answers for them itself
+        if REVIEWED_TOOLS.contains(&call.name.as_str()) && client_reviews_edits() {
+            return Permission::Review;
+        }
+
+        // Deny outright when the connected client can't answer permission prompts
+        let server = crate::communication::rpc::get_global_rpc_server();
+        let client_capabilities = server
+            .as_ref()
+            .and_then(|server| server.client_capabilities());
+        if client_capabilities
+            .as_ref()
+            .is_some_and(|caps| !caps.supports_permission_prompts)
+        {
+            return Permission::Deny(format!(
+                "ERROR EXECUTING TOOL: {} was denied because the client cannot answer permission prompts",
+                call.name
+            ));
+        }
+
+        let request = ApprovalRequest {
+            tool: call.name.clone(),
+            arguments: call.arguments.clone(),
+            working_directory: self.working_directory.clone(),
+            requested_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+
+        // The approval backend decides when there is one; otherwise a client that
+        // answers permission prompts asks its user
+        let (decision, decider) = match (self.approval_backend.as_ref(), server) {
+            (Some(backend), server) => {
+                if let Some(sender) = &self.progress_sender {
+                    let _ = sender
+                        .send(format!("[{}] Waiting for external approval...", call.name))
+                        .await;
+                }
+
+                // Let the client show the pending call while the backend decides
+                if let Some(server) = server {
+                    let mut params = serde_json::to_value(&request).unwrap_or_default();
+                    params["call_id"] = serde_json::json!(call.id);
+                    let _ = server.send_notification("tool_permission_request", params);
+                }
+
+                (backend.decide(&request).await, "the approval backend")
             }
+            (None, Some(server)) if client_capabilities.is_some() => {
+                if let Some(sender) = &self.progress_sender {
+                    let _ = sender
+                        .send(format!("[{}] Waiting for permission...", call.name))
+                        .await;
+                }
+                // Wait for the answer off the runtime's threads
+                let prompt = request.clone();
+                let call_id = call.id.clone();
+                let decision = tokio::task::spawn_blocking(move || {
+                    ask_client(&server, &prompt, call_id.as_deref())
+                })
+                .await
+                .unwrap_or_else(|e| ApprovalDecision::deny(format!("{e}")));
+                (decision, "the user")
+            }
+            (None, _) => return Permission::Allow,
+        };
 
-            // Add tool result to conversation and results collection
-            self.add_tool_result_to_conversation(&tool_call_id, &result);
-            results.push(ToolResult {
-                tool_call_id,
-                output: result,
+        if decision.allow {
+            // Remember an "always allow" answer so the call isn't asked about again
+            let rule = decision.remember.and_then(|scope| {
+                Some((
+                    scope,
+                    PermissionRules::rule_for(scope, &call.name, &call.arguments)?,
+                ))
             });
+            if let (Some(file), Some((scope, value))) = (&permissions, rule) {
+                if let Err(e) = file.update(|rules| rules.add(scope, &value)) {
+                    send_error_message(&self.progress_sender, &format!("{e:#}")).await;
+                }
+            }
+            Permission::Allow
+        } else {
+            Permission::Deny(format!(
+                "ERROR EXECUTING TOOL: {} was denied by {decider}: {}",
+                call.name,
+                decision.reason.as_deref().unwrap_or("no reason given")
+            ))
         }
+    }
 
-        results
+    /// Hold back an edit to a file that changed on disk since the agent read it,
+    /// unless the client chooses to apply it anyway. Returns the message for the
+    /// model when it's held back.
+    async fn check_conflicts(
+        &self,
+        tool_call: &AgentToolCall,
+        call: &ApiToolCall,
+    ) -> Option<String> {
+        let conflict = self.read_snapshots.conflict_for(tool_call)?;
+
+        if let Some(sender) = &self.progress_sender {
+            let _ = sender
+                .send(format!("[{}] Conflict: {}", call.name, conflict.preview()))
+                .await;
+        }
+        match conflict.resolve() {
+            Resolution::Apply => None,
+            Resolution::Skip => Some(conflict.held_message(&call.name)),
+        }
+    }
+
+    /// Show an Edit or Write to the client hunk by hunk. Returns the call writing
+    /// only the accepted hunks, with a note for the model, when the user rejected
+    /// some of them, and the message for the model when they rejected all.
+    async fn review_edit(
+        &self,
+        tool_call: &AgentToolCall,
+        call: &ApiToolCall,
+    ) -> Result<Option<(AgentToolCall, String)>, String> {
+        let Some(review) = EditReview::for_call(tool_call).filter(|r| !r.hunks.is_empty()) else {
+            return Ok(None);
+        };
+
+        if let Some(sender) = &self.progress_sender {
+            let _ = sender
+                .send(format!(
+                    "[{}] Waiting for review of {} hunk{} in {}...",
+                    call.name,
+                    review.hunks.len(),
+                    if review.hunks.len() == 1 { "" } else { "s" },
+                    review.path
+                ))
+                .await;
+        }
+        match review.request(&call.name) {
+            ReviewDecision::AcceptAll => Ok(None),
+            ReviewDecision::RejectAll => Err(review.rejected_message(&call.name)),
+            ReviewDecision::Partial { content, rejected } => Ok(Some((
+                review.partial_call(content),
+                review.partial_note(&rejected),
+            ))),
+        }
+    }
+
+    fn record_turn_content(&mut self, content: &str) {
+        if !content.trim().is_empty() {
+            self.turn_content = Some(content.to_string());
+        }
+    }
+
+    /// Build the partial result for an interrupted turn and keep the conversation
+    /// consistent, so the model can pick the task up again on the next prompt
+    fn salvage_partial_result(&mut self, interruption: Interruption, elapsed: Duration) -> String {
+        self.close_pending_tool_calls();
+
+        let reason = match interruption {
+            Interruption::TimedOut => format!("timed out after {}s", elapsed.as_secs()),
+            Interruption::Cancelled => format!("cancelled after {}s", elapsed.as_secs()),
+        };
+
+        let mut message = format!("{PARTIAL_RESULT_PREFIX} The task {reason} before it finished.");
+        if let Some(content) = &self.turn_content {
+            message.push_str(&format!("\n\nProgress so far:\n{}", content.trim()));
+        }
+        if !self.turn_tools.is_empty() {
+            message.push_str(&format!(
+                "\n\nTools completed ({}): {}",
+                self.turn_tools.len(),
+                self.turn_tools.join(", ")
+            ));
+        }
+        message.push_str("\n\nReply \"continue\" to resume where it stopped.");
+
+        self.add_assistant_response(&message, &None);
+        message
+    }
+
+    /// Add a result for each call of the last tool-calling assistant message that
+    /// was interrupted before it ran
+    fn close_pending_tool_calls(&mut self) {
+        let Some(position) = self.conversation.iter().rposition(|msg| {
+            msg.role == "assistant"
+                && msg.content.starts_with('{')
+                && msg.content.contains("tool_calls")
+        }) else {
+            return;
+        };
+
+        let call_ids: Vec<String> =
+            serde_json::from_str::<Value>(&self.conversation[position].content)
+                .ok()
+                .and_then(|value| value["tool_calls"].as_array().cloned())
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|call| call["id"].as_str().map(str::to_string))
+                .filter(|id| !id.is_empty())
+                .collect();
+
+        for call_id in call_ids {
+            let prefix = format!("{TOOL_RESULT_PREFIX}{call_id}: ");
+            let answered = self.conversation[position..]
+                .iter()
+                .any(|msg| msg.content.starts_with(&prefix));
+            if !answered {
+                self.add_tool_result_to_conversation(
+                    &call_id,
+                    "NOT EXECUTED: the turn was interrupted before this tool ran",
+                );
+            }
+        }
+    }
+
+    /// Record a tool call's outcome for benchmark reports, if enabled
+    fn record_tool_stats(
+        &self,
+        call: &ApiToolCall,
+        repairs: &[ArgumentRepair],
+        failure: Option<ToolFailure>,
+        error: Option<String>,
+    ) {
+        let Some(tool_stats) = &self.tool_stats else {
+            return;
+        };
+
+        // Argument names the tool's schema doesn't define, e.g. "path" instead of "file_path"
+        let known_params = self
+            .tool_definitions
+            .iter()
+            .find(|def| def.name == call.name)
+            .and_then(|def| def.parameters["properties"].as_object());
+        let unexpected_params = match (known_params, call.arguments.as_object()) {
+            (Some(known), Some(arguments)) => arguments
+                .keys()
+                .filter(|name| !known.contains_key(*name))
+                .cloned()
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let record = ToolCallRecord {
+            tool: call.name.clone(),
+            success: failure.is_none(),
+            failure,
+            error,
+            missing_id: call.id.as_deref().is_none_or(str::is_empty),
+            unexpected_params,
+            repairs: repairs.to_vec(),
+        };
+        if let Err(e) = tool_stats.record(&record) {
+            eprintln!("Failed to record tool stats: {e}");
+        }
+    }
+
+    /// Append an audited tool execution, or its denial, to the audit log
+    fn record_audit_entry(
+        &self,
+        call: &ApiToolCall,
+        denial: Option<&str>,
+        diff: Option<&str>,
+        result: &str,
+    ) {
+        let Some(audit_log) = &self.audit_log else {
+            return;
+        };
+        if !AuditLog::is_audited(&call.name) {
+            return;
+        }
+
+        let entry = AuditEntry {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            task_id: self.task_id.clone(),
+            tool: call.name.clone(),
+            arguments: call.arguments.clone(),
+            diff_hash: diff.map(|diff| format!("{:016x}", hash_tool_result(diff))),
+            decision: if denial.is_some() {
+                "denied"
+            } else {
+                "allowed"
+            }
+            .to_string(),
+            reason: denial.map(str::to_string),
+            outcome: denial.is_none().then(|| {
+                if result.starts_with("ERROR EXECUTING TOOL") {
+                    "error".to_string()
+                } else {
+                    "success".to_string()
+                }
+            }),
+            // The local environment says nothing about commands run on a remote target
+            environment: (call.name == "Bash"
+                && denial.is_none()
+                && ExecutionBackend::current().is_local())
+            .then(EnvSnapshot::current)
+            .flatten()
+            .cloned(),
+        };
+
+        // Auditing must never interrupt the agent
+        if let Err(e) = audit_log.record(&entry) {
+            eprintln!("Failed to write audit log: {e}");
+        }
+    }
+
+    /// Return a short reference instead of the full text if an identical result
+    /// is already in the conversation, otherwise record the result and return it unchanged
+    fn dedup_tool_result(&mut self, tool_call_id: &str, result: String) -> String {
+        if result.len() < DEDUP_MIN_RESULT_LEN {
+            return result;
+        }
+
+        let hash = hash_tool_result(&result);
+        match self.seen_tool_results.get(&hash) {
+            Some(original_id) => {
+                format!("{DEDUP_REFERENCE_PREFIX}{original_id}{DEDUP_REFERENCE_SUFFIX}")
+            }
+            None => {
+                self.seen_tool_results
+                    .insert(hash, tool_call_id.to_string());
+                result
+            }
+        }
     }
 
     fn add_tool_result_to_conversation(&mut self, tool_call_id: &str, result: &str) {
         self.conversation.push(Message {
             role: "user".to_string(),
-            content: format!("Tool result for call {tool_call_id}: {result}"),
+            content: format!("{TOOL_RESULT_PREFIX}{tool_call_id}: {result}"),
         });
     }
 }
 
 // Helper functions to improve readability
 
+fn hash_tool_result(result: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    result.hash(&mut hasher);
+    hasher.finish()
+}
+
 fn add_assistant_message_to_conversation(
     conversation: &mut Vec<Message>,
     content: &str,
@@ -503,7 +1526,7 @@ pub fn should_request_completion(loop_count: usize, max_loops: usize, threshold:
     }
 
     // Periodically check based on threshold
-    if threshold == 1 || loop_count % threshold == 0 {
+    if threshold == 1 || loop_count.is_multiple_of(threshold) {
         return true;
     }
 
@@ -533,6 +1556,132 @@ pub fn process_response(content: &str) -> (String, bool) {
     (content.to_string(), false)
 }
 
+/// Resolve once the time limit passes or the cancel flag is set; never resolves if neither is configured
+async fn wait_for_interruption(
+    time_limit: Option<Duration>,
+    cancel_flag: Option<Arc<AtomicBool>>,
+) -> Interruption {
+    if time_limit.is_none() && cancel_flag.is_none() {
+        return std::future::pending().await;
+    }
+
+    let deadline = time_limit.map(|limit| tokio::time::Instant::now() + limit);
+    loop {
+        if cancel_flag
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::SeqCst))
+        {
+            return Interruption::Cancelled;
+        }
+        if deadline.is_some_and(|deadline| tokio::time::Instant::now() >= deadline) {
+            return Interruption::TimedOut;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+/// Put the full result back into tool results in `kept` that only refer to an
+/// identical result in `removed`, so that once the history is compacted or its
+/// oldest messages are dropped, no result points at one the model can't see.
+/// Returns how many were restored.
+pub fn restore_dedup_references(kept: &mut [Message], removed: &[Message]) -> usize {
+    let removed_results: HashMap<&str, &str> = removed
+        .iter()
+        .filter_map(|msg| split_tool_result(&msg.content))
+        .map(|(call_id, result)| (call_id, strip_citation_tag(result)))
+        .collect();
+    if removed_results.is_empty() {
+        return 0;
+    }
+
+    let mut restored = 0;
+    for msg in kept {
+        let Some((call_id, result)) = split_tool_result(&msg.content) else {
+            continue;
+        };
+        let body = strip_citation_tag(result);
+        let Some(original) = body
+            .strip_prefix(DEDUP_REFERENCE_PREFIX)
+            .and_then(|rest| rest.strip_suffix(DEDUP_REFERENCE_SUFFIX))
+            .and_then(|original_id| removed_results.get(original_id))
+        else {
+            continue;
+        };
+        // Keep the citation tag the reference was numbered with
+        let tag = &result[..result.len() - body.len()];
+        msg.content = format!("{TOOL_RESULT_PREFIX}{call_id}: {tag}{original}");
+        restored += 1;
+    }
+    restored
+}
+
+// The call id and result of a tool result message
+fn split_tool_result(content: &str) -> Option<(&str, &str)> {
+    content
+        .strip_prefix(TOOL_RESULT_PREFIX)
+        .and_then(|rest| rest.split_once(": "))
+}
+
+/// Whether a tool only reads, so it can run at the same time as other such calls.
+/// Edit, Write, WriteMany, NotebookEditCell, Bash and RunTests run alone, as does
+/// Agent, whose sub-agent the executor runs itself.
+pub fn runs_concurrently(tool: &str) -> bool {
+    !RISKY_TOOLS.contains(&tool) && tool != SUB_AGENT_TOOL
+}
+
+// A tool call with its arguments repaired and parsed and its permission checked
+struct PreparedCall {
+    index: usize,
+    call: ApiToolCall,
+    repairs: Vec<ArgumentRepair>,
+    // The parsed call, or why it didn't parse
+    parsed: Result<AgentToolCall>,
+    denial: Option<String>,
+    // Added to the result, e.g. which hunks of an edit the user rejected
+    note: Option<String>,
+}
+
+// Whether a call may run, as the permission checks decide
+enum Permission {
+    Allow,
+    Deny(String),
+    // The client shows the edit hunk by hunk and the user picks what to apply
+    Review,
+}
+
+impl PreparedCall {
+    // The call to execute, unless it didn't parse or was denied
+    fn runnable(&self) -> Option<&AgentToolCall> {
+        match (&self.parsed, &self.denial) {
+            (Ok(tool_call), None) => Some(tool_call),
+            _ => None,
+        }
+    }
+}
+
+// Execute read-only calls at once, each on the blocking thread pool, returning their
+// outputs in the order of the calls
+async fn execute_concurrently(batch: &[PreparedCall]) -> Vec<Option<(String, Option<String>)>> {
+    let runs = batch.iter().map(|prepared| {
+        let tool_call = prepared.runnable().cloned();
+        async move {
+            let tool_call = tool_call?;
+            // Tools asking the client, e.g. for an edit review, ask the one the run is for
+            let client = ClientScope::current();
+            let output =
+                match tokio::task::spawn_blocking(move || client.enter(|| tool_call.execute()))
+                    .await
+                {
+                    Ok(Ok(output)) => output,
+                    Ok(Err(e)) => format!("ERROR EXECUTING TOOL: {e}"),
+                    Err(e) => format!("ERROR EXECUTING TOOL: {e}"),
+                };
+            Some((output, None))
+        }
+    });
+    futures::future::join_all(runs).await
+}
+
 async fn send_error_message(sender: &Option<mpsc::Sender<String>>, message: &str) {
     if let Some(sender) = sender {
         let _ = sender.send(format!("[error] {message}")).await;
@@ -543,9 +1692,15 @@ async fn execute_tool_with_preview(
     tool_call: &AgentToolCall,
     call: &ApiToolCall,
     progress_sender: &Option<mpsc::Sender<String>>,
-) -> String {
+) -> (String, Option<String>) {
+    // Diff previewed for file modifications, kept for the audit log
+    let mut previewed_diff = None;
+
     // Check if tool needs diff preview
-    let needs_diff_preview = matches!(call.name.as_str(), "Edit" | "Write");
+    let needs_diff_preview = matches!(
+        call.name.as_str(),
+        "Edit" | "Write" | "WriteMany" | "NotebookEditCell"
+    );
 
     let result = if needs_diff_preview {
         // Handle file modification tools with diff preview
@@ -567,6 +1722,7 @@ async fn execute_tool_with_preview(
                         if let Some(sender) = progress_sender {
                             let _ = sender.send(diff.clone()).await;
                         }
+                        previewed_diff = Some(diff);
                         // Execute the tool
                         tool_call.execute()
                     }
@@ -579,12 +1735,60 @@ async fn execute_tool_with_preview(
 
                 // Generate diff without making changes
                 let path = PathBuf::from(&params.file_path);
-                match FileOps::generate_write_diff(&path, &params.content) {
+                match FileOps::generate_write_diff_with_options(
+                    &path,
+                    &params.content,
+                    params.mode(),
+                    params.create_dirs(),
+                ) {
                     Ok((diff, _)) => {
                         // Send diff as progress message
                         if let Some(sender) = progress_sender {
                             let _ = sender.send(diff.clone()).await;
                         }
+                        previewed_diff = Some(diff);
+                        // Execute the tool
+                        tool_call.execute()
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            AgentToolCall::WriteMany(params) => {
+                use crate::app::workspace_lock::workspace_root;
+                use crate::tools::fs::file_ops::FileOps;
+
+                // One preview of every file, checked against the workspace before any is written
+                let preview = std::env::current_dir()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|cwd| {
+                        FileOps::generate_write_many_diff(&params.files(), &workspace_root(&cwd))
+                    });
+                match preview {
+                    Ok(diff) => {
+                        // Send diff as progress message
+                        if let Some(sender) = progress_sender {
+                            let _ = sender.send(diff.clone()).await;
+                        }
+                        previewed_diff = Some(diff);
+                        // Execute the tool
+                        tool_call.execute()
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            AgentToolCall::NotebookEditCell(params) => {
+                use crate::tools::notebook;
+                use std::path::PathBuf;
+
+                // Generate the cell's diff without making changes
+                let path = PathBuf::from(&params.file_path);
+                match notebook::generate_cell_edit(&path, params) {
+                    Ok((_, diff)) => {
+                        // Send diff as progress message
+                        if let Some(sender) = progress_sender {
+                            let _ = sender.send(diff.clone()).await;
+                        }
+                        previewed_diff = Some(diff);
                         // Execute the tool
                         tool_call.execute()
                     }
@@ -598,10 +1802,11 @@ async fn execute_tool_with_preview(
         tool_call.execute()
     };
 
-    match result {
+    let output = match result {
         Ok(output) => output,
         Err(e) => format!("ERROR EXECUTING TOOL: {e}"),
-    }
+    };
+    (output, previewed_diff)
 }
 
 fn parse_tool_call(name: &str, args: &Value) -> Result<AgentToolCall> {
@@ -621,6 +1826,31 @@ fn parse_tool_call(name: &str, args: &Value) -> Result<AgentToolCall> {
                 serde_json::from_value(args.clone()).context("Failed to parse Grep parameters")?;
             Ok(AgentToolCall::Grep(params))
         }
+        "FindFile" => {
+            let params = serde_json::from_value(args.clone())
+                .context("Failed to parse FindFile parameters")?;
+            Ok(AgentToolCall::FindFile(params))
+        }
+        "DependencyGraph" => {
+            let params = serde_json::from_value(args.clone())
+                .context("Failed to parse DependencyGraph parameters")?;
+            Ok(AgentToolCall::DependencyGraph(params))
+        }
+        "GitStatus" => {
+            let params = serde_json::from_value(args.clone())
+                .context("Failed to parse GitStatus parameters")?;
+            Ok(AgentToolCall::GitStatus(params))
+        }
+        "GitDiff" => {
+            let params = serde_json::from_value(args.clone())
+                .context("Failed to parse GitDiff parameters")?;
+            Ok(AgentToolCall::GitDiff(params))
+        }
+        "GitLog" => {
+            let params = serde_json::from_value(args.clone())
+                .context("Failed to parse GitLog parameters")?;
+            Ok(AgentToolCall::GitLog(params))
+        }
         "LS" => {
             let params =
                 serde_json::from_value(args.clone()).context("Failed to parse LS parameters")?;
@@ -636,11 +1866,36 @@ fn parse_tool_call(name: &str, args: &Value) -> Result<AgentToolCall> {
                 serde_json::from_value(args.clone()).context("Failed to parse Write parameters")?;
             Ok(AgentToolCall::Write(params))
         }
+        "WriteMany" => {
+            let params = serde_json::from_value(args.clone())
+                .context("Failed to parse WriteMany parameters")?;
+            Ok(AgentToolCall::WriteMany(params))
+        }
         "Bash" => {
             let params =
                 serde_json::from_value(args.clone()).context("Failed to parse Bash parameters")?;
             Ok(AgentToolCall::Bash(params))
         }
+        "RunTests" => {
+            let params = serde_json::from_value(args.clone())
+                .context("Failed to parse RunTests parameters")?;
+            Ok(AgentToolCall::RunTests(params))
+        }
+        "NotebookRead" => {
+            let params = serde_json::from_value(args.clone())
+                .context("Failed to parse NotebookRead parameters")?;
+            Ok(AgentToolCall::NotebookRead(params))
+        }
+        "NotebookEditCell" => {
+            let params = serde_json::from_value(args.clone())
+                .context("Failed to parse NotebookEditCell parameters")?;
+            Ok(AgentToolCall::NotebookEditCell(params))
+        }
+        "Agent" => {
+            let params =
+                serde_json::from_value(args.clone()).context("Failed to parse Agent parameters")?;
+            Ok(AgentToolCall::Agent(params))
+        }
         _ => Err(anyhow::anyhow!("Unknown tool: {}", name)),
     }
 }
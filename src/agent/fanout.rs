@@ -0,0 +1,349 @@
+use crate::agent::core::Agent;
+use crate::agent::executor::PARTIAL_RESULT_PREFIX;
+use crate::apis::api_client::{CompletionOptions, Message};
+use crate::apis::ledger::UsageMeter;
+use crate::communication::rpc::ClientScope;
+use crate::context::provider::estimate_tokens;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Semaphore};
+
+/// Marker the executor sends for every executed tool, forwarded unprefixed so
+/// tool counts still add up across sub-agents
+const TOOL_EXECUTED_MARKER: &str = "[TOOL_EXECUTED]";
+
+const PLANNING_PROMPT: &str = "You split coding tasks into independent subtasks that separate agents can work on in parallel. \
+Each subtask must be self-contained: it must not depend on the results of another subtask, and no two subtasks may edit the same file. \
+If the task can't be split this way, return it as a single subtask.";
+
+const SYNTHESIS_PROMPT: &str = "Several agents worked in parallel on parts of a task. \
+Combine their results into one answer to the original task. Point out any conflicts between their results and mention parts that failed or were skipped.";
+
+/// Limits for running sub-agents in parallel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FanOutConfig {
+    /// Most subtasks a task is split into
+    pub max_subtasks: usize,
+    /// Most sub-agents running at the same time
+    pub max_concurrency: usize,
+    /// Estimated tokens all sub-agents together may use. Subtasks that haven't
+    /// started when the budget is spent are skipped.
+    pub token_budget: usize,
+}
+
+impl Default for FanOutConfig {
+    fn default() -> Self {
+        Self {
+            max_subtasks: 5,
+            max_concurrency: 3,
+            token_budget: 200_000,
+        }
+    }
+}
+
+impl FanOutConfig {
+    pub fn with_max_subtasks(mut self, max_subtasks: usize) -> Self {
+        self.max_subtasks = max_subtasks.max(1);
+        self
+    }
+
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    pub fn with_token_budget(mut self, tokens: usize) -> Self {
+        self.token_budget = tokens;
+        self
+    }
+
+    /// Read a fan-out setting from request parameters: `true` for the defaults,
+    /// or an object with any of `max_subtasks`, `max_concurrency` and `token_budget`
+    pub fn from_json(value: &Value) -> Option<Self> {
+        match value {
+            Value::Bool(true) => Some(Self::default()),
+            Value::Object(_) => {
+                let mut config = Self::default();
+                if let Some(max) = value["max_subtasks"].as_u64() {
+                    config = config.with_max_subtasks(max as usize);
+                }
+                if let Some(max) = value["max_concurrency"].as_u64() {
+                    config = config.with_max_concurrency(max as usize);
+                }
+                if let Some(tokens) = value["token_budget"].as_u64() {
+                    config = config.with_token_budget(tokens as usize);
+                }
+                Some(config)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Outcome of one sub-agent
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SubtaskResult {
+    pub index: usize,
+    pub description: String,
+    pub response: Option<String>,
+    pub error: Option<String>,
+    /// Estimated tokens of the sub-agent's conversation
+    pub tokens: usize,
+}
+
+/// Result of a fanned-out task
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FanOutResult {
+    /// The synthesized answer to the original task
+    pub response: String,
+    pub subtasks: Vec<SubtaskResult>,
+    pub tokens_used: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubtaskPlan {
+    subtasks: Vec<String>,
+}
+
+/// Parse the planner's answer into subtask descriptions, accepting a JSON
+/// object with a `subtasks` array, optionally wrapped in prose or a code fence
+pub fn parse_subtasks(content: &str, max_subtasks: usize) -> Vec<String> {
+    let json = match (content.find('{'), content.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &content[start..=end],
+        _ => content,
+    };
+
+    serde_json::from_str::<SubtaskPlan>(json)
+        .map(|plan| plan.subtasks)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|subtask| subtask.trim().to_string())
+        .filter(|subtask| !subtask.is_empty())
+        .take(max_subtasks)
+        .collect()
+}
+
+/// One section per subtask with its description and outcome
+fn subtask_sections(results: &[SubtaskResult]) -> Vec<String> {
+    results
+        .iter()
+        .map(|result| {
+            let outcome = match (&result.response, &result.error) {
+                (Some(response), _) => response.clone(),
+                (None, Some(error)) => format!("FAILED: {error}"),
+                (None, None) => "No result".to_string(),
+            };
+            format!(
+                "### Subtask {}: {}\n{outcome}",
+                result.index + 1,
+                result.description
+            )
+        })
+        .collect()
+}
+
+impl Agent {
+    /// Split a task into independent subtasks, run a sub-agent with its own
+    /// conversation on each (up to `max_concurrency` at a time), then combine
+    /// their results into one answer. Tasks that can't be split run normally.
+    pub async fn execute_fan_out(&self, query: &str, config: FanOutConfig) -> Result<FanOutResult> {
+        let subtasks = self.plan_subtasks(query, config.max_subtasks).await?;
+        if subtasks.len() < 2 {
+            let response = self.execute(query).await?;
+            return Ok(FanOutResult {
+                tokens_used: estimate_tokens(query) + estimate_tokens(&response),
+                response,
+                subtasks: Vec::new(),
+            });
+        }
+
+        self.send_progress(format!(
+            "Split task into {} subtasks, running up to {} in parallel",
+            subtasks.len(),
+            config.max_concurrency
+        ))
+        .await;
+
+        let semaphore = Arc::new(Semaphore::new(config.max_concurrency.max(1)));
+        let tokens_used = Arc::new(AtomicUsize::new(0));
+        let mut handles = Vec::new();
+
+        for (index, description) in subtasks.into_iter().enumerate() {
+            let sub_agent = self.sub_agent(index);
+            let semaphore = semaphore.clone();
+            let tokens_used = tokens_used.clone();
+            let prompt = format!(
+                "{description}\n\nThis is one part of a larger task that other agents are working on in parallel. \
+                 Only do this part. The full task is:\n{query}"
+            );
+
+            // Count the sub-agent's requests towards the turn's usage
+            let meter = UsageMeter::current().unwrap_or_default();
+            let client = ClientScope::current();
+            handles.push(tokio::spawn(client.scope(meter.scope(async move {
+                let _permit = semaphore.acquire_owned().await;
+
+                if sub_agent.is_cancelled() {
+                    return SubtaskResult {
+                        index,
+                        description,
+                        response: None,
+                        error: Some("skipped: the task was cancelled".to_string()),
+                        tokens: 0,
+                    };
+                }
+
+                if tokens_used.load(Ordering::SeqCst) >= config.token_budget {
+                    return SubtaskResult {
+                        index,
+                        description,
+                        response: None,
+                        error: Some(format!(
+                            "skipped: the token budget of {} tokens was used up",
+                            config.token_budget
+                        )),
+                        tokens: 0,
+                    };
+                }
+
+                let result = sub_agent.execute(&prompt).await;
+                let tokens = sub_agent
+                    .conversation_history()
+                    .iter()
+                    .map(|msg| estimate_tokens(&msg.content))
+                    .sum::<usize>()
+                    .max(estimate_tokens(&prompt));
+                tokens_used.fetch_add(tokens, Ordering::SeqCst);
+
+                // The sub-agent's progress forwarder finishes once it's dropped
+                drop(sub_agent);
+
+                let (response, error) = match result {
+                    Ok(response) => (Some(response), None),
+                    Err(e) => (None, Some(e.to_string())),
+                };
+                SubtaskResult {
+                    index,
+                    description,
+                    response,
+                    error,
+                    tokens,
+                }
+            }))));
+        }
+
+        let mut results = Vec::new();
+        for handle in handles {
+            results.push(handle.await.context("Sub-agent panicked")?);
+        }
+
+        // Don't ask the model to combine the results of a cancelled task
+        let response = if self.is_cancelled() {
+            format!(
+                "{PARTIAL_RESULT_PREFIX} The task was cancelled before it finished.\n\n{}",
+                subtask_sections(&results).join("\n\n")
+            )
+        } else {
+            self.synthesize(query, &results).await?
+        };
+        let tokens_used = tokens_used.load(Ordering::SeqCst) + estimate_tokens(&response);
+
+        Ok(FanOutResult {
+            response,
+            subtasks: results,
+            tokens_used,
+        })
+    }
+
+    async fn plan_subtasks(&self, query: &str, max_subtasks: usize) -> Result<Vec<String>> {
+        let api_client = self
+            .api_client()
+            .context("Agent not initialized. Call initialize() first.")?;
+
+        let options = CompletionOptions {
+            temperature: Some(0.2),
+            max_tokens: Some(1024),
+            json_schema: Some(
+                r#"{
+                    "type": "object",
+                    "properties": {
+                        "subtasks": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Independent subtasks, each a complete instruction for one agent"
+                        }
+                    },
+                    "required": ["subtasks"]
+                }"#
+                .to_string(),
+            ),
+            ..CompletionOptions::default()
+        };
+        let messages = vec![
+            Message::system(PLANNING_PROMPT.to_string()),
+            Message::user(format!(
+                "Split this task into at most {max_subtasks} independent subtasks. \
+                 Answer with JSON like {{\"subtasks\": [\"...\"]}}.\n\nTask: {query}"
+            )),
+        ];
+
+        let content = api_client.complete(messages, options).await?;
+        Ok(parse_subtasks(&content, max_subtasks))
+    }
+
+    async fn synthesize(&self, query: &str, results: &[SubtaskResult]) -> Result<String> {
+        let api_client = self
+            .api_client()
+            .context("Agent not initialized. Call initialize() first.")?;
+
+        let sections = subtask_sections(results);
+
+        let messages = vec![
+            Message::system(SYNTHESIS_PROMPT.to_string()),
+            Message::user(format!(
+                "Original task: {query}\n\n## SUBTASK RESULTS\n{}",
+                sections.join("\n\n")
+            )),
+        ];
+        let options = CompletionOptions {
+            max_tokens: Some(4096),
+            ..CompletionOptions::default()
+        };
+
+        api_client.complete(messages, options).await
+    }
+
+    /// A copy of this agent with an empty conversation, whose progress messages
+    /// are prefixed with the subtask number and merged into this agent's stream.
+    /// Sub-agents don't stream their text, which would interleave.
+    fn sub_agent(&self, index: usize) -> Agent {
+        let mut sub_agent = self.clone().with_delta_sender(None);
+        sub_agent.clear_history();
+
+        let Some(parent) = self.progress_sender().cloned() else {
+            return sub_agent;
+        };
+        let (sender, mut receiver) = mpsc::channel::<String>(100);
+        tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                let message = if message == TOOL_EXECUTED_MARKER {
+                    message
+                } else {
+                    format!("[subtask {}] {message}", index + 1)
+                };
+                let _ = parent.send(message).await;
+            }
+        });
+
+        sub_agent.with_progress_sender(sender)
+    }
+
+    async fn send_progress(&self, message: String) {
+        if let Some(sender) = self.progress_sender() {
+            let _ = sender.send(message).await;
+        }
+    }
+}
@@ -1,4 +1,20 @@
 // Export agent implementation
+pub mod approval;
+pub mod audit;
+pub mod budget;
+pub mod checkpoints;
+pub mod compare;
+pub mod conflicts;
 pub mod core;
+pub mod edit_review;
+pub mod evidence;
 pub mod executor;
+pub mod fanout;
+pub mod permissions;
+pub mod policy;
+pub mod result_limits;
+pub mod tool_queue;
+pub mod tool_repair;
+pub mod tool_stats;
 pub mod tools;
+pub mod trail;
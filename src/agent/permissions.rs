@@ -0,0 +1,400 @@
+use crate::communication::capabilities::RISKY_TOOLS;
+use crate::tools::fs::file_ops::resolve_path;
+use anyhow::{Context, Result};
+use ignore::overrides::{Override, OverrideBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Rules file relative to the working directory that earlier versions read. Rules
+/// now live outside the workspace, and tools must ask before touching this file.
+pub const PERMISSIONS_FILE: &str = ".oli/permissions.toml";
+/// Environment variable overriding the file the user's permission rules are kept in
+pub const PERMISSIONS_FILE_ENV: &str = "OLI_PERMISSIONS_FILE";
+/// Environment variable setting the permission level of tools, e.g. `Bash=ask,Write=deny`
+pub const TOOL_PERMISSIONS_ENV: &str = "OLI_TOOL_PERMISSIONS";
+/// Environment variable restricting the files Edit, Write, WriteMany and NotebookEditCell may change to
+/// comma-separated globs relative to the working directory, `!` excluding, e.g.
+/// `src/**,!**/*.lock`
+pub const EDIT_PATHS_ENV: &str = "OLI_EDIT_PATHS";
+
+/// Tools whose paths the edit path globs restrict
+const EDITING_TOOLS: &[&str] = &["Edit", "Write", "WriteMany", "NotebookEditCell"];
+
+/// Whether a tool runs without asking, asks first or never runs, from least to
+/// most strict
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionLevel {
+    Auto,
+    Ask,
+    Deny,
+}
+
+impl PermissionLevel {
+    pub fn parse(level: &str) -> Result<Self> {
+        match level.trim().to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "ask" => Ok(Self::Ask),
+            "deny" => Ok(Self::Deny),
+            other => Err(anyhow::anyhow!(
+                "Unknown permission level '{other}'; use auto, ask or deny"
+            )),
+        }
+    }
+
+    /// The level of a tool nothing configures: ask before tools that change files
+    /// or run commands, other than RunTests
+    pub fn default_for(tool: &str) -> Self {
+        if RISKY_TOOLS.contains(&tool) && tool != "RunTests" {
+            Self::Ask
+        } else {
+            Self::Auto
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Ask => "ask",
+            Self::Deny => "deny",
+        }
+    }
+}
+
+/// Parse a `Tool=level` list such as `Bash=ask,Write=deny`
+pub fn parse_levels(spec: &str) -> Result<BTreeMap<String, PermissionLevel>> {
+    let mut levels = BTreeMap::new();
+    for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (tool, level) = entry
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Expected Tool=level, got '{entry}'"))?;
+        levels.insert(tool.trim().to_string(), PermissionLevel::parse(level)?);
+    }
+    Ok(levels)
+}
+
+/// Permission levels and edit path globs from `OLI_TOOL_PERMISSIONS` and
+/// `OLI_EDIT_PATHS`. A project's own levels in its rules file can only make them
+/// stricter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PermissionPolicy {
+    pub levels: BTreeMap<String, PermissionLevel>,
+    /// Globs relative to the working directory; none leaves edits unrestricted
+    pub edit_paths: Vec<String>,
+}
+
+impl PermissionPolicy {
+    /// The policy the environment sets. Invalid values are an error rather than no
+    /// policy, so a typo doesn't silently allow what it meant to deny.
+    pub fn from_env() -> Result<Self> {
+        let levels = match std::env::var(TOOL_PERMISSIONS_ENV) {
+            Ok(spec) => {
+                parse_levels(&spec).with_context(|| format!("Invalid {TOOL_PERMISSIONS_ENV}"))?
+            }
+            Err(_) => BTreeMap::new(),
+        };
+        let edit_paths: Vec<String> = std::env::var(EDIT_PATHS_ENV)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|glob| !glob.is_empty())
+            .map(str::to_string)
+            .collect();
+        check_edit_paths(&edit_paths).with_context(|| format!("Invalid {EDIT_PATHS_ENV}"))?;
+        Ok(Self { levels, edit_paths })
+    }
+
+    /// The level of `tool`: the configured one, else the default, unless the
+    /// project's is stricter. A project can tighten a level but never loosen it,
+    /// so a checked-out repository can't turn off the user's prompts.
+    pub fn level_for(&self, tool: &str, rules: &PermissionRules) -> PermissionLevel {
+        let configured = self
+            .levels
+            .get(tool)
+            .copied()
+            .unwrap_or_else(|| PermissionLevel::default_for(tool));
+        rules
+            .levels
+            .get(tool)
+            .map_or(configured, |&project| project.max(configured))
+    }
+
+    /// The first file an editing call would change outside the edit paths, if any.
+    /// With edit paths set, nothing outside `working_dir` may be edited.
+    pub fn denied_edit_path(
+        &self,
+        tool: &str,
+        arguments: &Value,
+        working_dir: &Path,
+    ) -> Result<Option<String>> {
+        if self.edit_paths.is_empty() || !EDITING_TOOLS.contains(&tool) {
+            return Ok(None);
+        }
+        let root = resolve_path(working_dir);
+        let matcher = edit_matcher(&root, &self.edit_paths)?;
+
+        Ok(edited_paths(tool, arguments)
+            .into_iter()
+            .find(|path| {
+                let resolved = resolve_path(&root.join(path));
+                match resolved.strip_prefix(&root) {
+                    Ok(relative) => matcher.matched(relative, false).is_ignore(),
+                    Err(_) => true,
+                }
+            })
+            .map(str::to_string))
+    }
+}
+
+/// The paths an Edit, Write, WriteMany or NotebookEditCell call would change, as given; none for
+/// other tools
+pub fn edited_paths<'a>(tool: &str, arguments: &'a Value) -> Vec<&'a str> {
+    match tool {
+        "WriteMany" => arguments["files"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|file| file["path"].as_str())
+            .collect(),
+        tool if EDITING_TOOLS.contains(&tool) => {
+            arguments["file_path"].as_str().into_iter().collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Whether a call would change the rules file of `working_dir` or the user's
+/// `rules_file`, which tools must always ask before doing. Bash commands are
+/// matched by the file names they mention.
+pub fn touches_permission_rules(
+    tool: &str,
+    arguments: &Value,
+    working_dir: &Path,
+    rules_file: Option<&Path>,
+) -> bool {
+    let root = resolve_path(working_dir);
+    let protected: Vec<PathBuf> = std::iter::once(root.join(PERMISSIONS_FILE))
+        .chain(rules_file.map(resolve_path))
+        .collect();
+
+    if let Some(command) = bash_command(tool, arguments) {
+        return protected
+            .iter()
+            .filter_map(|path| path.file_name()?.to_str())
+            .any(|name| command.contains(name));
+    }
+    edited_paths(tool, arguments)
+        .into_iter()
+        .any(|path| protected.contains(&resolve_path(&root.join(path))))
+}
+
+/// Check edit path globs parse
+pub fn check_edit_paths(globs: &[String]) -> Result<()> {
+    edit_matcher(Path::new("."), globs).map(|_| ())
+}
+
+// Globs matched relative to `root`. Paths matching a `!` glob, or none of the
+// others when there are any, are ignored.
+fn edit_matcher(root: &Path, globs: &[String]) -> Result<Override> {
+    let mut builder = OverrideBuilder::new(root);
+    for glob in globs {
+        builder
+            .add(glob)
+            .with_context(|| format!("Invalid glob '{glob}'"))?;
+    }
+    Ok(builder.build()?)
+}
+
+/// What an "always allow" answer covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleScope {
+    /// Every call of the tool
+    Tool,
+    /// Bash running exactly this command
+    Command,
+}
+
+impl RuleScope {
+    pub fn parse(scope: &str) -> Result<Self> {
+        match scope.trim().to_lowercase().as_str() {
+            "tool" => Ok(Self::Tool),
+            "command" => Ok(Self::Command),
+            other => Err(anyhow::anyhow!(
+                "Unknown rule scope '{other}'; use tool or command"
+            )),
+        }
+    }
+}
+
+/// Tools and Bash commands the user always allows in a project without asking, and
+/// the project's own permission levels
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PermissionRules {
+    /// Tools allowed whatever their arguments
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub tools: BTreeSet<String>,
+    /// Exact Bash commands, compared without surrounding whitespace
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub commands: BTreeSet<String>,
+    /// Levels tightening the configured ones for this project
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub levels: BTreeMap<String, PermissionLevel>,
+}
+
+impl PermissionRules {
+    /// Whether a call is always allowed, and by which rule
+    pub fn allows(&self, tool: &str, arguments: &Value) -> Option<RuleScope> {
+        if self.tools.contains(tool) {
+            return Some(RuleScope::Tool);
+        }
+        let command = bash_command(tool, arguments)?;
+        self.commands
+            .contains(command)
+            .then_some(RuleScope::Command)
+    }
+
+    /// The value an "always allow" answer for this call adds under `scope`, if the
+    /// scope applies to it
+    pub fn rule_for(scope: RuleScope, tool: &str, arguments: &Value) -> Option<String> {
+        match scope {
+            RuleScope::Tool => Some(tool.to_string()),
+            RuleScope::Command => bash_command(tool, arguments).map(str::to_string),
+        }
+    }
+
+    /// Add a rule. Returns whether it's new.
+    pub fn add(&mut self, scope: RuleScope, value: &str) -> bool {
+        let value = value.trim().to_string();
+        match scope {
+            RuleScope::Tool => self.tools.insert(value),
+            RuleScope::Command => self.commands.insert(value),
+        }
+    }
+
+    /// Remove a rule. Returns whether it existed.
+    pub fn remove(&mut self, scope: RuleScope, value: &str) -> bool {
+        let value = value.trim();
+        match scope {
+            RuleScope::Tool => self.tools.remove(value),
+            RuleScope::Command => self.commands.remove(value),
+        }
+    }
+
+    /// Set a tool's level for the project, or with None go back to the configured
+    /// one. Returns whether it changed.
+    pub fn set_level(&mut self, tool: &str, level: Option<PermissionLevel>) -> bool {
+        let tool = tool.trim().to_string();
+        match level {
+            Some(level) => self.levels.insert(tool, level) != Some(level),
+            None => self.levels.remove(&tool).is_some(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty() && self.commands.is_empty() && self.levels.is_empty()
+    }
+}
+
+// The trimmed command of a Bash call, or None for other tools
+fn bash_command<'a>(tool: &str, arguments: &'a Value) -> Option<&'a str> {
+    if tool != "Bash" {
+        return None;
+    }
+    arguments["command"]
+        .as_str()
+        .map(str::trim)
+        .filter(|command| !command.is_empty())
+}
+
+/// The user's rules for one project. They're kept outside the workspace, in a
+/// file of the user's with a table per project keyed by its canonical path, so
+/// nothing that can write to the workspace can allow itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PermissionFile {
+    path: PathBuf,
+    project: String,
+}
+
+impl PermissionFile {
+    /// The rules of the project in `working_dir`, kept in `path`
+    pub fn new(path: PathBuf, working_dir: &Path) -> Self {
+        Self {
+            path,
+            project: resolve_path(working_dir).to_string_lossy().to_string(),
+        }
+    }
+
+    /// The rules of the project in `working_dir`, kept in
+    /// `~/.oli/project_permissions.toml` unless overridden by `OLI_PERMISSIONS_FILE`
+    pub fn for_working_dir(working_dir: &Path) -> Option<Self> {
+        let path = match std::env::var(PERMISSIONS_FILE_ENV) {
+            Ok(path) if !path.trim().is_empty() => PathBuf::from(path),
+            _ => dirs::home_dir()?
+                .join(".oli")
+                .join("project_permissions.toml"),
+        };
+        Some(Self::new(path, working_dir))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The canonical path of the project the rules are for
+    pub fn project(&self) -> &str {
+        &self.project
+    }
+
+    /// The saved rules; none when there are none for the project. An invalid file
+    /// is an error rather than no rules, so a typo doesn't silently drop them.
+    pub fn load(&self) -> Result<PermissionRules> {
+        Ok(self.load_all()?.remove(&self.project).unwrap_or_default())
+    }
+
+    // Every project's rules
+    fn load_all(&self) -> Result<BTreeMap<String, PermissionRules>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(contents) => toml::from_str(&contents)
+                .with_context(|| format!("Failed to parse {}", self.path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", self.path.display())),
+        }
+    }
+
+    /// Save the project's rules, leaving other projects' as they are
+    pub fn save(&self, rules: &PermissionRules) -> Result<()> {
+        let mut all = self.load_all()?;
+        if rules.is_empty() {
+            all.remove(&self.project);
+        } else {
+            all.insert(self.project.clone(), rules.clone());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        // Write to a temporary file first so a crash mid-save can't corrupt the rules
+        let temp_path = self.path.with_extension("toml.tmp");
+        std::fs::write(&temp_path, toml::to_string(&all)?)
+            .with_context(|| format!("Failed to write {}", temp_path.display()))?;
+        std::fs::rename(&temp_path, &self.path)
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        Ok(())
+    }
+
+    /// Load the rules, change them and save them if `change` reports a change
+    pub fn update(&self, change: impl FnOnce(&mut PermissionRules) -> bool) -> Result<bool> {
+        let mut rules = self.load()?;
+        let changed = change(&mut rules);
+        if changed {
+            self.save(&rules)?;
+        }
+        Ok(changed)
+    }
+}
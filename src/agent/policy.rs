@@ -0,0 +1,24 @@
+use crate::communication::capabilities::RISKY_TOOLS;
+
+/// Controls which tools the agent is allowed to execute
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ToolPolicy {
+    /// Every tool may run
+    #[default]
+    AllowAll,
+    /// Only tools that don't modify files or run commands may run
+    ReadOnly,
+    /// Every tool except the named ones may run
+    Deny(Vec<String>),
+}
+
+impl ToolPolicy {
+    /// Check whether a tool may be executed under this policy
+    pub fn allows(&self, tool_name: &str) -> bool {
+        match self {
+            ToolPolicy::AllowAll => true,
+            ToolPolicy::ReadOnly => !RISKY_TOOLS.contains(&tool_name),
+            ToolPolicy::Deny(tools) => !tools.iter().any(|tool| tool == tool_name),
+        }
+    }
+}
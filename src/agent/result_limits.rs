@@ -0,0 +1,113 @@
+use crate::apis::api_client::{split_images, ImageData};
+use std::collections::HashMap;
+
+/// Environment variable overriding the result limit of individual tools, e.g.
+/// `Bash=200000,Read=none`. `none` keeps a tool's results whole.
+pub const RESULT_LIMITS_ENV: &str = "OLI_TOOL_RESULT_LIMITS";
+/// Share of the model's context window one tool result may fill
+pub const RESULT_CONTEXT_SHARE: f64 = 0.02;
+/// Smallest default limit, so models with small context windows still see useful results
+pub const MIN_RESULT_CHARS: usize = 4_000;
+/// Rough characters per token, matching the estimate used for context budgets
+const CHARS_PER_TOKEN: usize = 4;
+
+/// How many characters of each tool result are passed to the model. The default
+/// scales with the model's context window; individual tools can be overridden.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResultLimits {
+    // None keeps results whole
+    default_chars: Option<usize>,
+    overrides: HashMap<String, Option<usize>>,
+}
+
+impl ResultLimits {
+    /// Results whole, whatever their size
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Limit each result to a share of a context window of `tokens`
+    pub fn for_context_window(tokens: u32) -> Self {
+        let chars = (tokens as f64 * RESULT_CONTEXT_SHARE) as usize * CHARS_PER_TOKEN;
+        Self {
+            default_chars: Some(chars.max(MIN_RESULT_CHARS)),
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Limit a tool's results to `chars`, or keep them whole with `None`
+    pub fn with_override(mut self, tool: &str, chars: Option<usize>) -> Self {
+        self.overrides.insert(tool.to_string(), chars);
+        self
+    }
+
+    /// Apply the overrides set in `OLI_TOOL_RESULT_LIMITS`
+    pub fn with_env_overrides(self) -> Self {
+        match std::env::var(RESULT_LIMITS_ENV) {
+            Ok(spec) => self.with_overrides_from(&spec),
+            Err(_) => self,
+        }
+    }
+
+    /// Apply overrides written as `Tool=chars` pairs separated by commas. Malformed
+    /// entries are skipped.
+    pub fn with_overrides_from(mut self, spec: &str) -> Self {
+        for entry in spec.split(',') {
+            let Some((tool, limit)) = entry.split_once('=') else {
+                continue;
+            };
+            let (tool, limit) = (tool.trim(), limit.trim());
+            if tool.is_empty() {
+                continue;
+            }
+
+            let chars = if limit.eq_ignore_ascii_case("none") {
+                None
+            } else {
+                match limit.parse::<usize>() {
+                    Ok(chars) if chars > 0 => Some(chars),
+                    _ => continue,
+                }
+            };
+            self.overrides.insert(tool.to_string(), chars);
+        }
+        self
+    }
+
+    /// Most characters of a result from `tool` the model sees, if limited
+    pub fn limit_for(&self, tool: &str) -> Option<usize> {
+        match self.overrides.get(tool) {
+            Some(chars) => *chars,
+            None => self.default_chars,
+        }
+    }
+
+    /// Cut a result from `tool` down to its limit, keeping the start and the end,
+    /// where commands usually report errors
+    pub fn apply(&self, tool: &str, output: String) -> String {
+        let Some(limit) = self.limit_for(tool) else {
+            return output;
+        };
+
+        // Attached images are kept whole; only the text around them is cut
+        let (text, images) = split_images(&output);
+        if !images.is_empty() {
+            let blocks: String = images.iter().map(ImageData::to_block).collect();
+            return format!("{}\n{blocks}", self.apply(tool, text));
+        }
+        let total = output.chars().count();
+        if total <= limit {
+            return output;
+        }
+
+        let head_chars = limit * 2 / 3;
+        let tail_chars = limit - head_chars;
+        let head: String = output.chars().take(head_chars).collect();
+        let tail: String = output.chars().skip(total - tail_chars).collect();
+        format!(
+            "{head}\n\n[... {} characters omitted: {tool} results are limited to {limit} \
+             characters. Narrow the call to see the rest ...]\n\n{tail}",
+            total - limit
+        )
+    }
+}
@@ -0,0 +1,85 @@
+use crate::apis::api_client::ToolCall;
+use serde::Serialize;
+use std::time::Instant;
+
+/// Where a tool call of the current iteration stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueuedCallStatus {
+    Pending,
+    Running,
+    Completed,
+    /// The arguments couldn't be parsed or the tool returned an error
+    Failed,
+    /// The tool policy or approval backend denied the call
+    Denied,
+}
+
+/// One tool call in the queue
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueuedCall {
+    pub id: String,
+    pub name: String,
+    pub status: QueuedCallStatus,
+    /// How long the call ran, once it has finished
+    pub duration_ms: Option<u64>,
+    #[serde(skip)]
+    started: Option<Instant>,
+}
+
+/// The tool calls the model requested in one iteration of a turn, in the order
+/// they run. A snapshot is sent each time a call starts or finishes.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolQueue {
+    pub task_id: Option<String>,
+    /// Iteration of the turn's tool loop, counting from 1
+    pub iteration: usize,
+    pub calls: Vec<QueuedCall>,
+}
+
+impl ToolQueue {
+    pub fn new(task_id: Option<String>, iteration: usize, calls: &[ToolCall]) -> Self {
+        let calls = calls
+            .iter()
+            .enumerate()
+            .map(|(i, call)| QueuedCall {
+                id: call.id.clone().unwrap_or_else(|| format!("tool_{i}")),
+                name: call.name.clone(),
+                status: QueuedCallStatus::Pending,
+                duration_ms: None,
+                started: None,
+            })
+            .collect();
+        Self {
+            task_id,
+            iteration,
+            calls,
+        }
+    }
+
+    pub fn start(&mut self, index: usize) {
+        if let Some(call) = self.calls.get_mut(index) {
+            call.status = QueuedCallStatus::Running;
+            call.started = Some(Instant::now());
+        }
+    }
+
+    pub fn finish(&mut self, index: usize, status: QueuedCallStatus) {
+        if let Some(call) = self.calls.get_mut(index) {
+            call.status = status;
+            call.duration_ms = call
+                .started
+                .map(|started| started.elapsed().as_millis() as u64);
+        }
+    }
+
+    /// Whether every call has finished
+    pub fn is_done(&self) -> bool {
+        self.calls.iter().all(|call| {
+            !matches!(
+                call.status,
+                QueuedCallStatus::Pending | QueuedCallStatus::Running
+            )
+        })
+    }
+}
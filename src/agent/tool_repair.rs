@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Most times a string is decoded, for arguments encoded more than once
+const MAX_DECODE_DEPTH: usize = 3;
+
+/// A fix applied to tool call arguments the model sent in a malformed shape
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgumentRepair {
+    /// The arguments were wrapped in a Markdown code fence
+    CodeFence,
+    /// The arguments object was sent as a JSON string
+    StringifiedJson,
+    /// A comma before a closing brace or bracket was removed
+    TrailingComma,
+    /// Raw newlines or tabs inside a string were escaped
+    ControlCharacters,
+    /// An object or array parameter was sent as a JSON string
+    StringifiedField,
+    /// A number or boolean parameter was sent as a string, or the reverse
+    CoercedValue,
+}
+
+impl ArgumentRepair {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArgumentRepair::CodeFence => "code_fence",
+            ArgumentRepair::StringifiedJson => "stringified_json",
+            ArgumentRepair::TrailingComma => "trailing_comma",
+            ArgumentRepair::ControlCharacters => "control_characters",
+            ArgumentRepair::StringifiedField => "stringified_field",
+            ArgumentRepair::CoercedValue => "coerced_value",
+        }
+    }
+}
+
+/// Repair common problems in tool call arguments before they are validated:
+/// code fences, JSON sent as a string (possibly more than once), trailing commas,
+/// raw control characters, and parameters whose type doesn't match `schema`.
+/// Arguments that can't be repaired are returned unchanged.
+pub fn repair_arguments(arguments: &Value, schema: Option<&Value>) -> (Value, Vec<ArgumentRepair>) {
+    let mut repairs = Vec::new();
+    let mut value = arguments.clone();
+
+    for _ in 0..MAX_DECODE_DEPTH {
+        let Value::String(text) = &value else {
+            break;
+        };
+        match decode_json(text) {
+            Some((decoded, fixes)) => {
+                if fixes.is_empty() {
+                    repairs.push(ArgumentRepair::StringifiedJson);
+                }
+                repairs.extend(fixes);
+                value = decoded;
+            }
+            None => break,
+        }
+    }
+
+    if let (Value::Object(fields), Some(properties)) = (
+        &mut value,
+        schema.and_then(|schema| schema["properties"].as_object()),
+    ) {
+        coerce_fields(fields, properties, &mut repairs);
+    }
+
+    repairs.dedup();
+    (value, repairs)
+}
+
+// Parse text as JSON, applying fixes until it parses
+fn decode_json(text: &str) -> Option<(Value, Vec<ArgumentRepair>)> {
+    let mut fixes = Vec::new();
+    let mut text = text.trim().to_string();
+
+    if let Some(unfenced) = strip_code_fence(&text) {
+        text = unfenced;
+        fixes.push(ArgumentRepair::CodeFence);
+    }
+    if let Ok(value) = serde_json::from_str(&text) {
+        return Some((value, fixes));
+    }
+
+    let fixed = remove_trailing_commas(&text);
+    if fixed != text {
+        text = fixed;
+        fixes.push(ArgumentRepair::TrailingComma);
+        if let Ok(value) = serde_json::from_str(&text) {
+            return Some((value, fixes));
+        }
+    }
+
+    let fixed = escape_control_characters(&text);
+    if fixed != text {
+        fixes.push(ArgumentRepair::ControlCharacters);
+        if let Ok(value) = serde_json::from_str(&fixed) {
+            return Some((value, fixes));
+        }
+    }
+
+    None
+}
+
+fn strip_code_fence(text: &str) -> Option<String> {
+    let rest = text.strip_prefix("```")?;
+    let rest = rest.strip_suffix("```")?;
+    // Drop the language tag on the opening line, e.g. ```json
+    let body = match rest.find('\n') {
+        Some(newline) if !rest[..newline].contains(['{', '[']) => &rest[newline + 1..],
+        _ => rest,
+    };
+    Some(body.trim().to_string())
+}
+
+// Remove commas directly before a closing brace or bracket, outside strings
+fn remove_trailing_commas(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, &c) in chars.iter().enumerate() {
+        if in_string {
+            out.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        if c == ',' {
+            let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}') | Some(']')) {
+                continue;
+            }
+        }
+        if c == '"' {
+            in_string = true;
+        }
+        out.push(c);
+    }
+    out
+}
+
+// Escape raw newlines, carriage returns and tabs inside strings
+fn escape_control_characters(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in text.chars() {
+        if in_string && !escaped {
+            match c {
+                '\n' => {
+                    out.push_str("\\n");
+                    continue;
+                }
+                '\r' => {
+                    out.push_str("\\r");
+                    continue;
+                }
+                '\t' => {
+                    out.push_str("\\t");
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        out.push(c);
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && in_string {
+            escaped = true;
+        } else if c == '"' {
+            in_string = !in_string;
+        }
+    }
+    out
+}
+
+// Convert parameters to the type their schema declares where that's unambiguous
+fn coerce_fields(
+    fields: &mut Map<String, Value>,
+    properties: &Map<String, Value>,
+    repairs: &mut Vec<ArgumentRepair>,
+) {
+    for (name, value) in fields.iter_mut() {
+        let Some(expected) = properties.get(name).and_then(|p| p["type"].as_str()) else {
+            continue;
+        };
+
+        let (coerced, repair) = match (expected, &*value) {
+            ("object" | "array", Value::String(text)) => match decode_json(text) {
+                Some((decoded, _))
+                    if (expected == "object" && decoded.is_object())
+                        || (expected == "array" && decoded.is_array()) =>
+                {
+                    (decoded, ArgumentRepair::StringifiedField)
+                }
+                _ => continue,
+            },
+            ("integer", Value::String(text)) => match text.trim().parse::<i64>() {
+                Ok(number) => (Value::from(number), ArgumentRepair::CoercedValue),
+                Err(_) => continue,
+            },
+            ("integer", Value::Number(number)) if number.as_i64().is_none() => {
+                match number.as_f64() {
+                    Some(float) if float.fract() == 0.0 => {
+                        (Value::from(float as i64), ArgumentRepair::CoercedValue)
+                    }
+                    _ => continue,
+                }
+            }
+            ("number", Value::String(text)) => match text.trim().parse::<f64>() {
+                Ok(number) => (Value::from(number), ArgumentRepair::CoercedValue),
+                Err(_) => continue,
+            },
+            ("boolean", Value::String(text)) => match text.trim() {
+                "true" => (Value::Bool(true), ArgumentRepair::CoercedValue),
+                "false" => (Value::Bool(false), ArgumentRepair::CoercedValue),
+                _ => continue,
+            },
+            ("string", Value::Number(number)) => (
+                Value::String(number.to_string()),
+                ArgumentRepair::CoercedValue,
+            ),
+            ("string", Value::Bool(flag)) => (
+                Value::String(flag.to_string()),
+                ArgumentRepair::CoercedValue,
+            ),
+            _ => continue,
+        };
+
+        *value = coerced;
+        repairs.push(repair);
+    }
+}
@@ -0,0 +1,287 @@
+use crate::agent::tool_repair::ArgumentRepair;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Environment variable with the file benchmark runs record tool calls to
+pub const TOOL_STATS_ENV: &str = "OLI_TOOL_STATS_FILE";
+
+/// Why a tool call made by the model failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolFailure {
+    /// The model called a tool that doesn't exist
+    UnknownTool,
+    /// A required parameter was missing, usually because of a wrong parameter name
+    MissingParameter,
+    /// The arguments didn't match the tool's schema in some other way
+    InvalidArguments,
+    /// The arguments were valid but the tool returned an error
+    ExecutionError,
+    /// The tool policy, approval backend or client denied the call
+    Denied,
+}
+
+impl ToolFailure {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ToolFailure::UnknownTool => "unknown_tool",
+            ToolFailure::MissingParameter => "missing_parameter",
+            ToolFailure::InvalidArguments => "invalid_arguments",
+            ToolFailure::ExecutionError => "execution_error",
+            ToolFailure::Denied => "denied",
+        }
+    }
+
+    /// Classify an error from parsing a tool call's arguments
+    pub fn from_parse_error(error: &str) -> Self {
+        if error.starts_with("Unknown tool") {
+            ToolFailure::UnknownTool
+        } else if error.contains("missing field") {
+            ToolFailure::MissingParameter
+        } else {
+            ToolFailure::InvalidArguments
+        }
+    }
+}
+
+/// Outcome of one tool call made by the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCallRecord {
+    pub tool: String,
+    pub success: bool,
+    pub failure: Option<ToolFailure>,
+    pub error: Option<String>,
+    /// The model didn't give the call an id
+    #[serde(default)]
+    pub missing_id: bool,
+    /// Argument names that aren't in the tool's schema
+    #[serde(default)]
+    pub unexpected_params: Vec<String>,
+    /// Fixes applied to malformed arguments before the call was parsed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repairs: Vec<ArgumentRepair>,
+}
+
+/// JSON lines file of tool call outcomes, written during benchmark runs
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolStatsLog {
+    path: PathBuf,
+}
+
+impl ToolStatsLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// The log named by `OLI_TOOL_STATS_FILE`, if set
+    pub fn from_env() -> Option<Self> {
+        std::env::var(TOOL_STATS_ENV)
+            .ok()
+            .filter(|path| !path.trim().is_empty())
+            .map(|path| Self::new(PathBuf::from(path)))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(&self, record: &ToolCallRecord) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        Ok(())
+    }
+
+    /// Read every record, skipping malformed lines
+    pub fn read_all(&self) -> Result<Vec<ToolCallRecord>> {
+        let file = std::fs::File::open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        Ok(BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect())
+    }
+}
+
+/// Accuracy of one tool across a benchmark run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolUsage {
+    pub tool: String,
+    pub calls: usize,
+    pub successes: usize,
+    pub accuracy: f64,
+    pub failures: BTreeMap<String, usize>,
+}
+
+/// Per-tool breakdown of tool call accuracy and failure modes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolUsageReport {
+    pub total_calls: usize,
+    pub successful_calls: usize,
+    pub accuracy: f64,
+    pub tools: Vec<ToolUsage>,
+    /// Failure counts across all tools, most common first
+    pub failure_modes: Vec<(String, usize)>,
+    pub missing_ids: usize,
+    /// `Tool.param` names the model used that aren't in the schema, most common first
+    pub unexpected_params: Vec<(String, usize)>,
+    /// Calls whose arguments had to be repaired before they could be parsed
+    pub repaired_calls: usize,
+    /// Repair counts across all tools, most common first
+    pub repairs: Vec<(String, usize)>,
+}
+
+impl ToolUsageReport {
+    pub fn from_records(records: &[ToolCallRecord]) -> Self {
+        let mut tools: BTreeMap<String, ToolUsage> = BTreeMap::new();
+        let mut failure_modes: BTreeMap<String, usize> = BTreeMap::new();
+        let mut unexpected_params: BTreeMap<String, usize> = BTreeMap::new();
+        let mut repairs: BTreeMap<String, usize> = BTreeMap::new();
+
+        for record in records {
+            let usage = tools
+                .entry(record.tool.clone())
+                .or_insert_with(|| ToolUsage {
+                    tool: record.tool.clone(),
+                    calls: 0,
+                    successes: 0,
+                    accuracy: 0.0,
+                    failures: BTreeMap::new(),
+                });
+            usage.calls += 1;
+            if record.success {
+                usage.successes += 1;
+            }
+            if let Some(failure) = record.failure {
+                *usage
+                    .failures
+                    .entry(failure.name().to_string())
+                    .or_default() += 1;
+                *failure_modes.entry(failure.name().to_string()).or_default() += 1;
+            }
+            for param in &record.unexpected_params {
+                *unexpected_params
+                    .entry(format!("{}.{param}", record.tool))
+                    .or_default() += 1;
+            }
+            for repair in &record.repairs {
+                *repairs.entry(repair.name().to_string()).or_default() += 1;
+            }
+        }
+
+        let tools: Vec<ToolUsage> = tools
+            .into_values()
+            .map(|mut usage| {
+                usage.accuracy = ratio(usage.successes, usage.calls);
+                usage
+            })
+            .collect();
+
+        let successful_calls = records.iter().filter(|r| r.success).count();
+
+        Self {
+            total_calls: records.len(),
+            successful_calls,
+            accuracy: ratio(successful_calls, records.len()),
+            tools,
+            failure_modes: most_common_first(failure_modes),
+            missing_ids: records.iter().filter(|r| r.missing_id).count(),
+            unexpected_params: most_common_first(unexpected_params),
+            repaired_calls: records.iter().filter(|r| !r.repairs.is_empty()).count(),
+            repairs: most_common_first(repairs),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("### Tool Call Accuracy\n\n");
+        out.push_str(&format!(
+            "{}/{} tool calls succeeded ({:.1}%)\n\n",
+            self.successful_calls,
+            self.total_calls,
+            self.accuracy * 100.0
+        ));
+
+        out.push_str("| Tool | Calls | Successful | Accuracy | Failures |\n");
+        out.push_str("|------|-------|------------|----------|----------|\n");
+        for usage in &self.tools {
+            let failures = if usage.failures.is_empty() {
+                "-".to_string()
+            } else {
+                usage
+                    .failures
+                    .iter()
+                    .map(|(mode, count)| format!("{mode}: {count}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            out.push_str(&format!(
+                "| {} | {} | {} | {:.1}% | {} |\n",
+                usage.tool,
+                usage.calls,
+                usage.successes,
+                usage.accuracy * 100.0,
+                failures
+            ));
+        }
+
+        if !self.failure_modes.is_empty()
+            || self.missing_ids > 0
+            || !self.unexpected_params.is_empty()
+        {
+            out.push_str("\n#### Common Failure Modes\n\n");
+            for (mode, count) in &self.failure_modes {
+                out.push_str(&format!("- {mode}: {count}\n"));
+            }
+            if self.missing_ids > 0 {
+                out.push_str(&format!(
+                    "- tool calls without an id: {}\n",
+                    self.missing_ids
+                ));
+            }
+            for (param, count) in &self.unexpected_params {
+                out.push_str(&format!("- unexpected parameter `{param}`: {count}\n"));
+            }
+        }
+
+        if self.repaired_calls > 0 {
+            out.push_str(&format!(
+                "\n#### Repaired Arguments\n\n{} tool calls needed their arguments repaired\n\n",
+                self.repaired_calls
+            ));
+            for (repair, count) in &self.repairs {
+                out.push_str(&format!("- {repair}: {count}\n"));
+            }
+        }
+
+        out
+    }
+}
+
+fn ratio(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+fn most_common_first(counts: BTreeMap<String, usize>) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
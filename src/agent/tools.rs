@@ -1,31 +1,55 @@
+use crate::app::workspace_lock::workspace_root;
 use crate::tools::{
-    fs::file_ops::FileOps,
-    fs::search::SearchTools,
+    dependencies::DependencyGraph,
+    editor,
+    fs::file_ops::{FileOps, WriteMode},
+    fs::fuzzy::{find_files, DEFAULT_FIND_LIMIT},
+    fs::oliignore::{check_path, OliIgnore, OLIIGNORE_FILE},
+    fs::read_limits::ReadLimits,
+    fs::search::{GrepOutputOptions, SearchTools},
+    fs::workspace_index::{truncation_note, WorkspaceIndex, LARGE_REPO_RESULT_LIMIT},
+    git::GitInspector,
     lsp::{
         DefinitionParams, LspServerManager, ModelsCodeLensParams as CodeLensParams,
         ModelsDocumentSymbolParams as DocumentSymbolParams,
         ModelsSemanticTokensParams as SemanticTokensParams,
     },
+    notebook::{self, NotebookEditCellParams, NotebookReadParams},
+    processes::{track_child, ChildKind},
+    remote::ExecutionBackend,
+    shell::ShellConfig,
+    shell_session::{run_in_session, CommandOutput},
+    test_runner::{run_tests, TestFramework, DEFAULT_TEST_TIMEOUT_SECS, MAX_TEST_TIMEOUT_SECS},
 };
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ToolType {
     Read,
     Glob,
     Grep,
+    FindFile,
+    DependencyGraph,
+    GitStatus,
+    GitDiff,
+    GitLog,
     LS,
     Edit,
     Write,
+    WriteMany,
     Bash,
+    RunTests,
+    NotebookRead,
+    NotebookEditCell,
     DocumentSymbol,
     SemanticTokens,
     CodeLens,
     Definition,
+    Agent,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,6 +70,50 @@ pub struct GrepParams {
     pub pattern: String,
     pub include: Option<String>,
     pub path: Option<String>,
+    pub context_lines: Option<usize>,
+    pub max_results: Option<usize>,
+    pub show_line_numbers: Option<bool>,
+}
+
+impl GrepParams {
+    pub fn output_options(&self) -> GrepOutputOptions {
+        GrepOutputOptions {
+            context_lines: self.context_lines.unwrap_or(0),
+            show_line_numbers: self.show_line_numbers.unwrap_or(true),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindFileParams {
+    pub query: String,
+    pub path: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyGraphParams {
+    pub target: Option<String>,
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitStatusParams {
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitDiffParams {
+    pub path: Option<String>,
+    pub staged: Option<bool>,
+    pub revision: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitLogParams {
+    pub path: Option<String>,
+    pub limit: Option<usize>,
+    pub revision: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,6 +134,44 @@ pub struct EditParams {
 pub struct WriteParams {
     pub file_path: String,
     pub content: String,
+    pub create_dirs: Option<bool>,
+    pub mode: Option<WriteMode>,
+}
+
+impl WriteParams {
+    pub fn mode(&self) -> WriteMode {
+        self.mode.unwrap_or_default()
+    }
+
+    pub fn create_dirs(&self) -> bool {
+        self.create_dirs.unwrap_or(false)
+    }
+
+    /// The file's content once this write is done, given what it holds now
+    pub fn content_after(&self, existing: &str) -> String {
+        self.mode().apply(existing, &self.content)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileContent {
+    pub path: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WriteManyParams {
+    pub files: Vec<FileContent>,
+}
+
+impl WriteManyParams {
+    /// The files to write, as paths and contents
+    pub fn files(&self) -> Vec<(PathBuf, String)> {
+        self.files
+            .iter()
+            .map(|file| (PathBuf::from(&file.path), file.content.clone()))
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,20 +181,57 @@ pub struct BashParams {
     pub description: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunTestsParams {
+    /// `cargo`, `pytest` or `npm`; detected from the project when not given
+    pub framework: Option<String>,
+    /// Run only the tests matching this name, path or pattern
+    pub filter: Option<String>,
+    /// Project directory, the working directory when not given
+    pub path: Option<String>,
+    pub timeout_secs: Option<u64>,
+}
+
+impl RunTestsParams {
+    pub fn timeout(&self) -> Duration {
+        let secs = self
+            .timeout_secs
+            .unwrap_or(DEFAULT_TEST_TIMEOUT_SECS)
+            .clamp(1, MAX_TEST_TIMEOUT_SECS);
+        Duration::from_secs(secs)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentParams {
+    /// What the sub-agent should find out, as a complete instruction
+    pub objective: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "tool", content = "params")]
 pub enum ToolCall {
     Read(ReadParams),
     Glob(GlobParams),
     Grep(GrepParams),
+    FindFile(FindFileParams),
+    DependencyGraph(DependencyGraphParams),
+    GitStatus(GitStatusParams),
+    GitDiff(GitDiffParams),
+    GitLog(GitLogParams),
     LS(LSParams),
     Edit(EditParams),
     Write(WriteParams),
+    WriteMany(WriteManyParams),
     Bash(BashParams),
+    RunTests(RunTestsParams),
+    NotebookRead(NotebookReadParams),
+    NotebookEditCell(NotebookEditCellParams),
     DocumentSymbol(DocumentSymbolParams),
     SemanticTokens(SemanticTokensParams),
     CodeLens(CodeLensParams),
     Definition(DefinitionParams),
+    Agent(AgentParams),
 }
 
 // Uses App.start_tool_execution/update_tool_progress/complete_tool_execution from app/core.rs
@@ -159,8 +302,157 @@ fn send_tool_notification(
     }
 }
 
+/// Tell the model that search results leave out paths excluded by `.oliignore`
+fn oliignore_note(search_dir: &Path) -> String {
+    match OliIgnore::find(search_dir) {
+        Some(oliignore) => format!(
+            "\nNote: paths excluded by {} are not searched.\n",
+            oliignore.root().join(OLIIGNORE_FILE).display()
+        ),
+        None => String::new(),
+    }
+}
+
+/// Run a git tool on the repository containing `path`, or the current directory,
+/// sending its status notifications
+fn run_git_tool(
+    tool_name: &str,
+    path: Option<&str>,
+    description: String,
+    run: impl FnOnce(&GitInspector, Option<&Path>) -> Result<String>,
+) -> Result<String> {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let tool_id = format!("{}-direct-{start_time}", tool_name.to_lowercase());
+
+    let metadata = serde_json::json!({ "path": path, "description": description });
+    send_tool_notification(
+        tool_name,
+        "running",
+        &description,
+        metadata,
+        &tool_id,
+        start_time,
+    )
+    .ok();
+
+    let path = path.map(PathBuf::from);
+    let result = path
+        .as_deref()
+        .map(check_path)
+        .transpose()
+        .and_then(|_| {
+            let dir = path
+                .clone()
+                .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+            GitInspector::for_path(&dir)
+        })
+        .and_then(|git| run(&git, path.as_deref()));
+
+    let (status, message) = match &result {
+        Ok(_) => ("success", description.clone()),
+        Err(e) => ("error", format!("Error running {tool_name}: {e}")),
+    };
+    let metadata = serde_json::json!({ "path": path, "description": message });
+    send_tool_notification(tool_name, status, &message, metadata, &tool_id, start_time).ok();
+
+    result
+}
+
+// Run the project's tests with RunTests, reporting counts and failing tests
+fn run_tests_tool(params: &RunTestsParams) -> Result<String> {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let tool_id = format!("runtests-direct-{start_time}");
+    let description = match &params.filter {
+        Some(filter) => format!("Running tests matching {filter}"),
+        None => "Running tests".to_string(),
+    };
+    let metadata = serde_json::json!({ "path": params.path, "description": description });
+    send_tool_notification(
+        "RunTests",
+        "running",
+        &description,
+        metadata,
+        &tool_id,
+        start_time,
+    )
+    .ok();
+
+    let dir = params
+        .path
+        .as_deref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+    let result = match params.framework.as_deref() {
+        Some(name) => TestFramework::parse(name),
+        None if ExecutionBackend::for_path(&dir).transfers_files() => Err(anyhow::anyhow!(
+            "The project lives on the remote target, so its test framework can't be \
+             detected. Pass framework as cargo, pytest or npm."
+        )),
+        None => TestFramework::detect(&dir).ok_or_else(|| {
+            anyhow::anyhow!(
+                "No Cargo.toml, pytest config or package.json test script found in {}. \
+                 Pass framework, or the path of the project, or use Bash for other test runners.",
+                dir.display()
+            )
+        }),
+    }
+    .and_then(|framework| run_tests(framework, params.filter.as_deref(), &dir, params.timeout()));
+
+    let (status, message) = match &result {
+        Ok(summary) if summary.success() => ("success", format!("{} tests passed", summary.passed)),
+        Ok(summary) => (
+            "error",
+            format!("{} failed, {} passed", summary.failed, summary.passed),
+        ),
+        Err(e) => ("error", format!("Error running tests: {e}")),
+    };
+    let metadata = serde_json::json!({ "path": params.path, "description": message });
+    send_tool_notification("RunTests", status, &message, metadata, &tool_id, start_time).ok();
+
+    result.map(|summary| summary.report())
+}
+
 impl ToolCall {
     pub fn execute(&self) -> Result<String> {
+        // Glob, Grep, FindFile, DependencyGraph and the git tools read the local disk,
+        // which doesn't hold the workspace when files are transferred to and from a
+        // remote target
+        if let ToolCall::Glob(GlobParams { path, .. })
+        | ToolCall::Grep(GrepParams { path, .. })
+        | ToolCall::FindFile(FindFileParams { path, .. })
+        | ToolCall::DependencyGraph(DependencyGraphParams { path, .. })
+        | ToolCall::GitStatus(GitStatusParams { path })
+        | ToolCall::GitDiff(GitDiffParams { path, .. })
+        | ToolCall::GitLog(GitLogParams { path, .. }) = self
+        {
+            let dir = path
+                .as_deref()
+                .map(PathBuf::from)
+                .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+            if ExecutionBackend::for_path(&dir).transfers_files() {
+                anyhow::bail!(
+                    "Glob, Grep, FindFile, DependencyGraph, GitStatus, GitDiff and GitLog are not \
+                     available when files live on the remote target. Use Bash with find, grep or \
+                     git instead."
+                );
+            }
+        }
+
+        // The LSP tools read the file they're given, so they refuse the same paths Read does
+        if let ToolCall::DocumentSymbol(DocumentSymbolParams { file_path, .. })
+        | ToolCall::SemanticTokens(SemanticTokensParams { file_path, .. })
+        | ToolCall::CodeLens(CodeLensParams { file_path, .. })
+        | ToolCall::Definition(DefinitionParams { file_path, .. }) = self
+        {
+            check_path(Path::new(file_path))?;
+        }
+
         match self {
             ToolCall::Read(params) => {
                 // Generate a unique ID for this execution
@@ -197,8 +489,8 @@ impl ToolCall {
 
                 // Read the file
                 let path = PathBuf::from(&params.file_path);
-                // Always use read_file_lines with provided offset and limit
-                let result = FileOps::read_file_lines(&path, params.offset, Some(params.limit));
+                // Read within the size limits, refusing binaries and attaching images
+                let result = ReadLimits::from_env().read(&path, params.offset, params.limit);
 
                 // Send appropriate completion notification
                 if let Ok(ref content) = result {
@@ -287,8 +579,13 @@ impl ToolCall {
                 // Add a brief delay to ensure the running state is visible
                 std::thread::sleep(std::time::Duration::from_millis(500));
 
-                // Perform the glob search with optional path parameter
-                let result = if let Some(path) = &params.path {
+                // Perform the glob search with optional path parameter, on the file
+                // index in large repositories
+                let search_dir = Path::new(params.path.as_deref().unwrap_or("."));
+                let large_repo = WorkspaceIndex::for_dir(search_dir);
+                let result = if let Some(index) = &large_repo {
+                    index.glob(&params.pattern, search_dir)
+                } else if let Some(path) = &params.path {
                     let path_buf = PathBuf::from(path);
                     SearchTools::glob_search_in_dir(&path_buf, &params.pattern)
                 } else {
@@ -303,9 +600,18 @@ impl ToolCall {
                             results.len(),
                             params.pattern
                         );
-                        for (i, path) in results.iter().enumerate() {
+                        let shown = match large_repo {
+                            Some(_) => LARGE_REPO_RESULT_LIMIT,
+                            None => results.len(),
+                        };
+                        for (i, path) in results.iter().take(shown).enumerate() {
                             output.push_str(&format!("{}. {}\n", i + 1, path.display()));
                         }
+                        if let Some(index) = &large_repo {
+                            output.push_str(&truncation_note(shown, Some(results.len())));
+                            output.push_str(&index.progress_note());
+                        }
+                        output.push_str(&oliignore_note(search_dir));
 
                         // Send success notification with count, pattern, and optional path
                         let metadata = if let Some(path) = &params.path {
@@ -456,23 +762,57 @@ impl ToolCall {
 
                 // Execute the grep search
                 let search_dir = params.path.as_ref().map(Path::new);
-                let result = SearchTools::grep_search(
-                    &params.pattern,
-                    params.include.as_deref(),
-                    search_dir,
-                );
+                // Large repositories are searched in parallel, up to a stricter limit
+                let large_repo = WorkspaceIndex::for_dir(search_dir.unwrap_or(Path::new(".")));
+                let result = if large_repo.is_some() {
+                    SearchTools::grep_search_parallel(
+                        &params.pattern,
+                        params.include.as_deref(),
+                        search_dir,
+                        LARGE_REPO_RESULT_LIMIT,
+                    )
+                } else {
+                    SearchTools::grep_search(&params.pattern, params.include.as_deref(), search_dir)
+                        .map(|results| (results, false))
+                };
 
                 match result {
-                    Ok(results) => {
+                    Ok((results, stopped_early)) => {
+                        // Count the matches in each file, in the order they're listed
+                        let mut per_file: Vec<(&Path, usize)> = Vec::new();
+                        for (path, _, _) in &results {
+                            match per_file.last_mut() {
+                                Some((last, count)) if *last == path.as_path() => *count += 1,
+                                _ => per_file.push((path, 1)),
+                            }
+                        }
+
                         // Format the output
                         let mut output = format!(
-                            "Found {} matches for pattern '{}':\n\n",
+                            "Found {} matches in {} files for pattern '{}':\n\n",
                             results.len(),
+                            per_file.len(),
                             params.pattern
                         );
-                        for (path, line_num, line) in &results {
-                            output.push_str(&format!("{}:{}:{}\n", path.display(), line_num, line));
+                        let shown = params
+                            .max_results
+                            .map_or(results.len(), |max| max.min(results.len()));
+                        output.push_str(&SearchTools::format_grep_matches(
+                            &results[..shown],
+                            &params.output_options(),
+                        ));
+                        if shown < results.len() {
+                            output.push_str(&format!(
+                                "\nShowing the first {shown} matches. Matches per file:\n"
+                            ));
+                            for (path, count) in &per_file {
+                                output.push_str(&format!("{}: {count}\n", path.display()));
+                            }
                         }
+                        if stopped_early {
+                            output.push_str(&truncation_note(results.len(), None));
+                        }
+                        output.push_str(&oliignore_note(search_dir.unwrap_or(Path::new("."))));
 
                         // Send success notification
                         let metadata = serde_json::json!({
@@ -480,7 +820,8 @@ impl ToolCall {
                             "include": params.include,
                             "path": params.path,
                             "count": results.len(),
-                            "description": format!("Found {} files", results.len()),
+                            "files": per_file.len(),
+                            "description": format!("Found {} matches in {} files", results.len(), per_file.len()),
                         });
                         // Create a tool name with parameters based on available options
                         let tool_name = match (&params.path, &params.include) {
@@ -552,6 +893,247 @@ impl ToolCall {
                     }
                 }
             }
+            ToolCall::FindFile(params) => {
+                // Generate a unique ID for this execution
+                let tool_id = format!(
+                    "findfile-direct-{}",
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_millis()
+                );
+
+                let start_time = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis();
+
+                // Send start notification
+                let metadata = serde_json::json!({
+                    "query": params.query,
+                    "path": params.path,
+                    "description": format!("FindFile(query: \"{}\")", params.query),
+                });
+                send_tool_notification(
+                    "FindFile",
+                    "running",
+                    &format!("Finding files matching: {}", params.query),
+                    metadata,
+                    &tool_id,
+                    start_time,
+                )
+                .ok();
+
+                // Rank the workspace's files against the partial name
+                let search_dir = params
+                    .path
+                    .as_deref()
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+                let limit = params.limit.unwrap_or(DEFAULT_FIND_LIMIT);
+                let result = find_files(&params.query, &search_dir, limit);
+
+                match result {
+                    Ok(matches) => {
+                        // Best match first, as absolute paths ready for Read
+                        let mut output = format!(
+                            "Found {} files matching '{}', best match first:\n\n",
+                            matches.len(),
+                            params.query
+                        );
+                        for (i, found) in matches.iter().enumerate() {
+                            output.push_str(&format!(
+                                "{}. {}\n",
+                                i + 1,
+                                search_dir.join(&found.path).display()
+                            ));
+                        }
+                        if let Some(index) = WorkspaceIndex::for_dir(&search_dir) {
+                            output.push_str(&index.progress_note());
+                        }
+                        output.push_str(&oliignore_note(&search_dir));
+
+                        // Send success notification
+                        let metadata = serde_json::json!({
+                            "query": params.query,
+                            "path": params.path,
+                            "count": matches.len(),
+                            "description": format!("Found {} files", matches.len()),
+                        });
+                        send_tool_notification(
+                            "FindFile",
+                            "success",
+                            &format!("Found {} files", matches.len()),
+                            metadata,
+                            &tool_id,
+                            start_time,
+                        )
+                        .ok();
+
+                        Ok(output)
+                    }
+                    Err(e) => {
+                        // Send error notification
+                        let metadata = serde_json::json!({
+                            "query": params.query,
+                            "path": params.path,
+                            "description": format!("Error finding files: {}", e),
+                        });
+                        send_tool_notification(
+                            "FindFile",
+                            "error",
+                            &format!("Error finding files: {e}"),
+                            metadata,
+                            &tool_id,
+                            start_time,
+                        )
+                        .ok();
+
+                        Err(e)
+                    }
+                }
+            }
+            ToolCall::DependencyGraph(params) => {
+                // Generate a unique ID for this execution
+                let tool_id = format!(
+                    "dependencygraph-direct-{}",
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_millis()
+                );
+
+                let start_time = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis();
+
+                // Send start notification
+                let description = match &params.target {
+                    Some(target) => format!("DependencyGraph(target: \"{target}\")"),
+                    None => "DependencyGraph()".to_string(),
+                };
+                let metadata = serde_json::json!({
+                    "target": params.target,
+                    "path": params.path,
+                    "description": description,
+                });
+                send_tool_notification(
+                    "DependencyGraph",
+                    "running",
+                    "Parsing imports and manifests",
+                    metadata,
+                    &tool_id,
+                    start_time,
+                )
+                .ok();
+
+                let search_dir = params
+                    .path
+                    .as_deref()
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+
+                match DependencyGraph::build(&search_dir) {
+                    Ok(graph) => {
+                        // Only the files depending on the target when one is given
+                        let (summary, adjacency) = match &params.target {
+                            Some(target) => {
+                                let dependents = graph.dependents_of(target);
+                                (
+                                    format!(
+                                        "{} of {} files scanned depend on '{}'",
+                                        dependents.len(),
+                                        graph.files_scanned,
+                                        target
+                                    ),
+                                    dependents,
+                                )
+                            }
+                            None => (
+                                format!(
+                                    "{} of {} files scanned have dependencies",
+                                    graph.adjacency.len(),
+                                    graph.files_scanned
+                                ),
+                                graph.adjacency.clone(),
+                            ),
+                        };
+                        let output = format!(
+                            "{summary}. Paths are relative to {}; each maps to the modules or \
+                             packages it imports or declares.\n\n{}\n{}",
+                            search_dir.display(),
+                            serde_json::to_string_pretty(&adjacency)?,
+                            oliignore_note(&search_dir)
+                        );
+
+                        // Send success notification
+                        let metadata = serde_json::json!({
+                            "target": params.target,
+                            "path": params.path,
+                            "count": adjacency.len(),
+                            "description": summary,
+                        });
+                        send_tool_notification(
+                            "DependencyGraph",
+                            "success",
+                            &summary,
+                            metadata,
+                            &tool_id,
+                            start_time,
+                        )
+                        .ok();
+
+                        Ok(output)
+                    }
+                    Err(e) => {
+                        // Send error notification
+                        let metadata = serde_json::json!({
+                            "target": params.target,
+                            "path": params.path,
+                            "description": format!("Error building dependency graph: {}", e),
+                        });
+                        send_tool_notification(
+                            "DependencyGraph",
+                            "error",
+                            &format!("Error building dependency graph: {e}"),
+                            metadata,
+                            &tool_id,
+                            start_time,
+                        )
+                        .ok();
+
+                        Err(e)
+                    }
+                }
+            }
+            ToolCall::GitStatus(params) => run_git_tool(
+                "GitStatus",
+                params.path.as_deref(),
+                "Checking repository status".to_string(),
+                |git, _| git.status(),
+            ),
+            ToolCall::GitDiff(params) => {
+                let staged = params.staged.unwrap_or(false);
+                let description = match (&params.revision, staged) {
+                    (Some(revision), _) => format!("Diffing against {revision}"),
+                    (None, true) => "Reading staged changes".to_string(),
+                    (None, false) => "Reading unstaged changes".to_string(),
+                };
+                run_git_tool(
+                    "GitDiff",
+                    params.path.as_deref(),
+                    description,
+                    |git, path| git.diff(staged, params.revision.as_deref(), path),
+                )
+            }
+            ToolCall::GitLog(params) => run_git_tool(
+                "GitLog",
+                params.path.as_deref(),
+                "Reading commit history".to_string(),
+                |git, path| git.log(params.limit, params.revision.as_deref(), path),
+            ),
+            ToolCall::RunTests(params) => run_tests_tool(params),
             ToolCall::LS(params) => {
                 // Generate a unique ID for this execution
                 let tool_id = format!(
@@ -586,21 +1168,35 @@ impl ToolCall {
                 // Add a brief delay to ensure the running state is visible
                 std::thread::sleep(std::time::Duration::from_millis(500));
 
-                // List the directory
+                // List the directory, on the remote target if files live there
                 let path = PathBuf::from(&params.path);
-                let result = FileOps::list_directory(&path);
+                let backend = ExecutionBackend::for_path(&path);
+                let result = if backend.transfers_files() {
+                    backend.list_directory(&path).map(|entries| (entries, 0))
+                } else {
+                    FileOps::list_directory_with_excluded(&path).map(|(entries, excluded)| {
+                        let entries = entries
+                            .iter()
+                            .map(|entry| {
+                                let name = entry.file_name().unwrap_or_default();
+                                (name.to_string_lossy().to_string(), entry.is_dir())
+                            })
+                            .collect::<Vec<_>>();
+                        (entries, excluded)
+                    })
+                };
 
                 match result {
-                    Ok(entries) => {
+                    Ok((entries, excluded)) => {
                         // Build the output format
                         let mut output = format!("Directory listing for '{}':\n", params.path);
-                        for (i, entry) in entries.iter().enumerate() {
-                            let file_type = if entry.is_dir() { "DIR" } else { "FILE" };
+                        for (i, (name, is_dir)) in entries.iter().enumerate() {
+                            let file_type = if *is_dir { "DIR" } else { "FILE" };
+                            output.push_str(&format!("{:3}. [{}] {}\n", i + 1, file_type, name));
+                        }
+                        if excluded > 0 {
                             output.push_str(&format!(
-                                "{:3}. [{}] {}\n",
-                                i + 1,
-                                file_type,
-                                entry.file_name().unwrap_or_default().to_string_lossy()
+                                "({excluded} entries hidden: path excluded by .oliignore)\n"
                             ));
                         }
 
@@ -679,7 +1275,7 @@ impl ToolCall {
 
                 // Edit the file
                 let path = PathBuf::from(&params.file_path);
-                match FileOps::edit_file(
+                match editor::edit_file(
                     &path,
                     &params.old_string,
                     &params.new_string,
@@ -723,10 +1319,89 @@ impl ToolCall {
                     }
                 }
             }
-            ToolCall::Write(params) => {
+            ToolCall::Write(params) => {
+                // Generate a unique ID for this execution
+                let tool_id = format!(
+                    "write-direct-{}",
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_millis()
+                );
+
+                let start_time = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis();
+
+                // Send start notification
+                let metadata = serde_json::json!({
+                    "file_path": params.file_path,
+                    "description": format!("Writing file: {}", params.file_path),
+                });
+                send_tool_notification(
+                    "Write",
+                    "running",
+                    &format!("Writing file: {}", params.file_path),
+                    metadata,
+                    &tool_id,
+                    start_time,
+                )
+                .ok();
+
+                // Add a brief delay to ensure the running state is visible
+                std::thread::sleep(std::time::Duration::from_millis(500));
+
+                // Write the file
+                let path = PathBuf::from(&params.file_path);
+                match editor::write_file_with_diff(
+                    &path,
+                    &params.content,
+                    params.mode(),
+                    params.create_dirs(),
+                ) {
+                    Ok(diff) => {
+                        // Send success notification
+                        let metadata = serde_json::json!({
+                            "file_path": params.file_path,
+                            "description": format!("Successfully wrote file: {}", params.file_path),
+                        });
+                        send_tool_notification(
+                            "Write",
+                            "success",
+                            &format!("Successfully wrote file: {}", params.file_path),
+                            metadata,
+                            &tool_id,
+                            start_time,
+                        )
+                        .ok();
+
+                        Ok(diff)
+                    }
+                    Err(e) => {
+                        // Send error notification
+                        let metadata = serde_json::json!({
+                            "file_path": params.file_path,
+                            "description": format!("Error writing file: {}", e),
+                        });
+                        send_tool_notification(
+                            "Write",
+                            "error",
+                            &format!("Error writing file: {e}"),
+                            metadata,
+                            &tool_id,
+                            start_time,
+                        )
+                        .ok();
+
+                        Err(e)
+                    }
+                }
+            }
+            ToolCall::WriteMany(params) => {
                 // Generate a unique ID for this execution
                 let tool_id = format!(
-                    "write-direct-{}",
+                    "write-many-direct-{}",
                     SystemTime::now()
                         .duration_since(UNIX_EPOCH)
                         .unwrap_or_default()
@@ -738,37 +1413,37 @@ impl ToolCall {
                     .unwrap_or_default()
                     .as_millis();
 
+                let paths: Vec<&str> = params.files.iter().map(|file| file.path.as_str()).collect();
+
                 // Send start notification
                 let metadata = serde_json::json!({
-                    "file_path": params.file_path,
-                    "description": format!("Writing file: {}", params.file_path),
+                    "file_paths": paths,
+                    "description": format!("Writing {} files", paths.len()),
                 });
                 send_tool_notification(
-                    "Write",
+                    "WriteMany",
                     "running",
-                    &format!("Writing file: {}", params.file_path),
+                    &format!("Writing {} files", paths.len()),
                     metadata,
                     &tool_id,
                     start_time,
                 )
                 .ok();
 
-                // Add a brief delay to ensure the running state is visible
-                std::thread::sleep(std::time::Duration::from_millis(500));
-
-                // Write the file
-                let path = PathBuf::from(&params.file_path);
-                match FileOps::write_file_with_diff(&path, &params.content) {
+                // Every path must be inside the workspace; all files are written or none.
+                // They go straight to disk, as an attached editor can't roll them back.
+                let root = workspace_root(&std::env::current_dir()?);
+                match FileOps::write_many(&params.files(), &root) {
                     Ok(diff) => {
                         // Send success notification
                         let metadata = serde_json::json!({
-                            "file_path": params.file_path,
-                            "description": format!("Successfully wrote file: {}", params.file_path),
+                            "file_paths": paths,
+                            "description": format!("Successfully wrote {} files", paths.len()),
                         });
                         send_tool_notification(
-                            "Write",
+                            "WriteMany",
                             "success",
-                            &format!("Successfully wrote file: {}", params.file_path),
+                            &format!("Successfully wrote {} files", paths.len()),
                             metadata,
                             &tool_id,
                             start_time,
@@ -780,13 +1455,13 @@ impl ToolCall {
                     Err(e) => {
                         // Send error notification
                         let metadata = serde_json::json!({
-                            "file_path": params.file_path,
-                            "description": format!("Error writing file: {}", e),
+                            "file_paths": paths,
+                            "description": format!("Error writing files: {e:#}"),
                         });
                         send_tool_notification(
-                            "Write",
+                            "WriteMany",
                             "error",
-                            &format!("Error writing file: {e}"),
+                            &format!("Error writing files: {e:#}"),
                             metadata,
                             &tool_id,
                             start_time,
@@ -797,6 +1472,122 @@ impl ToolCall {
                     }
                 }
             }
+            ToolCall::NotebookRead(params) => {
+                // Generate a unique ID for this execution
+                let tool_id = format!(
+                    "notebook-read-direct-{}",
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_millis()
+                );
+
+                let start_time = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis();
+
+                // Send start notification
+                let metadata = serde_json::json!({
+                    "file_path": params.file_path,
+                    "description": format!("Reading notebook: {}", params.file_path),
+                });
+                send_tool_notification(
+                    "NotebookRead",
+                    "running",
+                    &format!("Reading notebook: {}", params.file_path),
+                    metadata,
+                    &tool_id,
+                    start_time,
+                )
+                .ok();
+
+                let path = PathBuf::from(&params.file_path);
+                let result = notebook::read_notebook(&path);
+
+                let (status, message) = match &result {
+                    Ok(_) => ("success", format!("Read notebook: {}", params.file_path)),
+                    Err(e) => ("error", format!("Error reading notebook: {e}")),
+                };
+                let metadata = serde_json::json!({
+                    "file_path": params.file_path,
+                    "description": message,
+                });
+                send_tool_notification(
+                    "NotebookRead",
+                    status,
+                    &message,
+                    metadata,
+                    &tool_id,
+                    start_time,
+                )
+                .ok();
+
+                result
+            }
+            ToolCall::NotebookEditCell(params) => {
+                // Generate a unique ID for this execution
+                let tool_id = format!(
+                    "notebook-edit-direct-{}",
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_millis()
+                );
+
+                let start_time = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis();
+
+                // Send start notification
+                let metadata = serde_json::json!({
+                    "file_path": params.file_path,
+                    "cell_id": params.cell_id,
+                    "cell_index": params.cell_index,
+                    "description": format!("Editing notebook: {}", params.file_path),
+                });
+                send_tool_notification(
+                    "NotebookEditCell",
+                    "running",
+                    &format!("Editing notebook: {}", params.file_path),
+                    metadata,
+                    &tool_id,
+                    start_time,
+                )
+                .ok();
+
+                // Add a brief delay to ensure the running state is visible
+                std::thread::sleep(std::time::Duration::from_millis(500));
+
+                let path = PathBuf::from(&params.file_path);
+                let result = notebook::edit_cell(&path, params);
+
+                let (status, message) = match &result {
+                    Ok(_) => (
+                        "success",
+                        format!("Successfully edited notebook: {}", params.file_path),
+                    ),
+                    Err(e) => ("error", format!("Error editing notebook: {e}")),
+                };
+                let metadata = serde_json::json!({
+                    "file_path": params.file_path,
+                    "cell_id": params.cell_id,
+                    "cell_index": params.cell_index,
+                    "description": message,
+                });
+                send_tool_notification(
+                    "NotebookEditCell",
+                    status,
+                    &message,
+                    metadata,
+                    &tool_id,
+                    start_time,
+                )
+                .ok();
+
+                result
+            }
             ToolCall::Bash(params) => {
                 // Generate a unique ID for this execution
                 let tool_id = format!(
@@ -832,30 +1623,58 @@ impl ToolCall {
                 )
                 .ok();
 
-                use std::process::{Command, Stdio};
+                use std::process::Stdio;
 
+                // Run in the configured shell (login mode, rc file) rather than whatever
+                // environment the TUI was launched with, or on the workspace's remote target.
                 // Use a simpler execution model to avoid issues with wait_timeout and async
-                let output = Command::new("sh")
-                    .arg("-c")
-                    .arg(&params.command)
-                    .stdout(Stdio::piped())
-                    .stderr(Stdio::piped())
-                    .output();
+                let working_dir = std::env::current_dir().unwrap_or_default();
+                let shell = ShellConfig::from_env();
+                let backend = ExecutionBackend::for_path(&working_dir);
+                let output = if shell.persistent && backend == ExecutionBackend::Local {
+                    // One long-lived shell, so cd and exported variables carry over
+                    run_in_session(
+                        &shell,
+                        &working_dir,
+                        &params.command,
+                        params.timeout.map(Duration::from_millis),
+                    )
+                } else {
+                    let mut command = backend.command(&shell, &working_dir, &params.command);
+                    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+                    // Its own process group, so killing an orphaned command also kills
+                    // whatever it started
+                    #[cfg(unix)]
+                    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+                    command
+                        .spawn()
+                        .and_then(|child| {
+                            let _tracked = track_child(
+                                ChildKind::Bash,
+                                child.id(),
+                                &params.command,
+                                cfg!(unix),
+                            );
+                            child.wait_with_output()
+                        })
+                        .map(CommandOutput::from)
+                        .map_err(anyhow::Error::from)
+                };
 
                 match output {
                     Ok(output) => {
-                        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-                        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+                        let stdout = output.stdout.clone();
+                        let stderr = output.stderr.clone();
 
                         let result =
-                            if output.status.success() {
+                            if output.success() {
                                 // Send success notification with command as the name and output in the message
                                 let description = params.description.clone().unwrap_or_else(|| {
                                     format!("Command executed: {}", params.command)
                                 });
                                 let metadata = serde_json::json!({
                                     "command": params.command,
-                                    "exit_code": output.status.code().unwrap_or(0),
+                                    "exit_code": output.exit_code,
                                     "description": description,
                                 });
                                 send_tool_notification(
@@ -873,16 +1692,14 @@ impl ToolCall {
                                 // Send error notification with command as the name and error details in the message
                                 let error_output = format!(
                                     "Failed with exit code: {}\nStdout: {}\nStderr: {}",
-                                    output.status.code().unwrap_or(-1),
-                                    stdout,
-                                    stderr
+                                    output.exit_code, stdout, stderr
                                 );
                                 let description = params.description.clone().unwrap_or_else(|| {
                                     format!("Command failed: {}", params.command)
                                 });
                                 let metadata = serde_json::json!({
                                     "command": params.command,
-                                    "exit_code": output.status.code().unwrap_or(-1),
+                                    "exit_code": output.exit_code,
                                     "description": description,
                                 });
                                 send_tool_notification(
@@ -897,9 +1714,7 @@ impl ToolCall {
 
                                 format!(
                                     "Command failed with exit code: {}\nStdout: {}\nStderr: {}",
-                                    output.status.code().unwrap_or(-1),
-                                    stdout,
-                                    stderr
+                                    output.exit_code, stdout, stderr
                                 )
                             };
 
@@ -926,7 +1741,7 @@ impl ToolCall {
                         )
                         .ok();
 
-                        Err(e.into())
+                        Err(e)
                     }
                 }
             }
@@ -1329,6 +2144,11 @@ impl ToolCall {
                             params.position.line, params.position.character, params.file_path
                         );
 
+                        // Leave out definitions in files excluded by .oliignore
+                        let (locations, excluded): (Vec<_>, Vec<_>) =
+                            locations.into_iter().partition(|location| {
+                                check_path(Path::new(&location.uri.replace("file://", ""))).is_ok()
+                            });
                         for (i, location) in locations.iter().enumerate() {
                             let uri = location.uri.replace("file://", "");
 
@@ -1342,6 +2162,13 @@ impl ToolCall {
                             ));
                         }
 
+                        if !excluded.is_empty() {
+                            output.push_str(&format!(
+                                "({} definitions hidden: path excluded by .oliignore)\n",
+                                excluded.len()
+                            ));
+                        }
+
                         // Send success notification
                         let location_count = locations.len();
                         let metadata = serde_json::json!({
@@ -1391,6 +2218,10 @@ impl ToolCall {
                     }
                 }
             }
+            // Sub-agents need the model, so the executor runs them itself
+            ToolCall::Agent(_) => Err(anyhow::anyhow!(
+                "The Agent tool can only run inside an agent executor"
+            )),
         }
     }
 }
@@ -1399,7 +2230,7 @@ pub fn get_tool_definitions() -> Vec<Value> {
     vec![
         serde_json::json!({
             "name": "Read",
-            "description": "Reads a file from the local filesystem. The file_path must be an absolute path.",
+            "description": "Reads a file from the local filesystem. The file_path must be an absolute path. Large files are returned a part at a time, with a note saying which offset to read from next. Images (PNG, JPEG, GIF and WebP) are attached for you to view; other binary files can't be read.",
             "parameters": {
                 "type": "object",
                 "properties": {
@@ -1432,6 +2263,18 @@ pub fn get_tool_definitions() -> Vec<Value> {
                     "path": {
                         "type": "string",
                         "description": "The directory to search in (defaults to current directory)"
+                    },
+                    "context_lines": {
+                        "type": "integer",
+                        "description": "Optional. Lines to show before and after each match, marked path-line-text. Defaults to 0."
+                    },
+                    "max_results": {
+                        "type": "integer",
+                        "description": "Optional. Show at most this many matches, followed by the number of matches in each file. Defaults to all of them."
+                    },
+                    "show_line_numbers": {
+                        "type": "boolean",
+                        "description": "Optional. Prefix each line with its line number, as path:line:text. Defaults to true."
                     }
                 },
                 "required": ["pattern"]
@@ -1439,7 +2282,7 @@ pub fn get_tool_definitions() -> Vec<Value> {
         }),
         serde_json::json!({
             "name": "Grep",
-            "description": "Fast content search tool using regular expressions to find patterns in file contents",
+            "description": "Fast content search tool using regular expressions to find patterns in file contents. Returns the matching lines as path:line:text, ready to Edit without reading the file first.",
             "parameters": {
                 "type": "object",
                 "properties": {
@@ -1459,6 +2302,104 @@ pub fn get_tool_definitions() -> Vec<Value> {
                 "required": ["pattern"]
             }
         }),
+        serde_json::json!({
+            "name": "FindFile",
+            "description": "Fuzzy file finder, like fzf. Ranks the workspace's files against a partial name and returns the best matches, e.g. query \"scroll state\" finds useScrollState.ts. Use it when you know roughly what a file is called but not its path or a glob pattern. Respects .gitignore and .oliignore.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Partial file name or path; space-separated terms must all match"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "The directory to search in (defaults to current directory)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of paths to return (defaults to 20)"
+                    }
+                },
+                "required": ["query"]
+            }
+        }),
+        serde_json::json!({
+            "name": "DependencyGraph",
+            "description": "Answers \"where is X imported or depended on\" in one call by parsing import statements (Rust, Python, JavaScript/TypeScript, Go) and manifests (Cargo.toml, package.json, pyproject.toml, requirements.txt). Returns a JSON adjacency list from each file to what it imports. Use it instead of repeated Grep calls to judge the blast radius of a change. Respects .gitignore and .oliignore.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "target": {
+                        "type": "string",
+                        "description": "Package, module or file to find dependents of, e.g. \"serde\", \"api_client\" or \"src/utils/format.ts\". Omit for the whole graph"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "The directory to analyze (defaults to current directory)"
+                    }
+                },
+                "required": []
+            }
+        }),
+        serde_json::json!({
+            "name": "GitStatus",
+            "description": "Shows the current branch, how far it is ahead of or behind its upstream, and the staged, unstaged and untracked files, like `git status --short`. Read-only and runs without asking for permission, so prefer it over Bash for inspecting the repository.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "A directory inside the repository (defaults to current directory)"
+                    }
+                },
+                "required": []
+            }
+        }),
+        serde_json::json!({
+            "name": "GitDiff",
+            "description": "Shows changes as a unified diff: by default unstaged changes, with staged=true the changes staged for commit, or with a revision the changes since it. Read-only and runs without asking for permission. Long diffs are truncated; pass a path to narrow them down.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "File or directory to limit the diff to (defaults to the whole repository of the current directory)"
+                    },
+                    "staged": {
+                        "type": "boolean",
+                        "description": "Show the staged changes instead of the unstaged ones (defaults to false)"
+                    },
+                    "revision": {
+                        "type": "string",
+                        "description": "Commit, branch or range to diff against, e.g. \"HEAD~3\" or \"main..feature\""
+                    }
+                },
+                "required": []
+            }
+        }),
+        serde_json::json!({
+            "name": "GitLog",
+            "description": "Lists recent commits, newest first, with their short hash, date, author and subject. Read-only and runs without asking for permission. Pass a path to see only the commits that touched it.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "File or directory to show the history of (defaults to the whole repository of the current directory)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of commits to return (defaults to 20, at most 100)"
+                    },
+                    "revision": {
+                        "type": "string",
+                        "description": "Branch, commit or range to list, e.g. \"main\" or \"v1.0..HEAD\" (defaults to HEAD)"
+                    }
+                },
+                "required": []
+            }
+        }),
         serde_json::json!({
             "name": "LS",
             "description": "Lists files and directories in a given path",
@@ -1508,7 +2449,7 @@ pub fn get_tool_definitions() -> Vec<Value> {
         }),
         serde_json::json!({
             "name": "Write",
-            "description": "Write a file to the local filesystem. Overwrites the existing file if there is one.",
+            "description": "Write a file to the local filesystem. Overwrites the existing file if there is one, unless mode says otherwise.",
             "parameters": {
                 "type": "object",
                 "properties": {
@@ -1519,11 +2460,48 @@ pub fn get_tool_definitions() -> Vec<Value> {
                     "content": {
                         "type": "string",
                         "description": "The content to write to the file"
+                    },
+                    "create_dirs": {
+                        "type": "boolean",
+                        "description": "Optional. Create the file's directory and any missing parents. Defaults to false, in which case writing into a directory that doesn't exist fails."
+                    },
+                    "mode": {
+                        "type": "string",
+                        "enum": ["overwrite", "append", "create_new"],
+                        "description": "Optional. \"overwrite\" (the default) replaces the file, \"append\" adds the content to its end, creating it if needed, and \"create_new\" fails if the file already exists."
                     }
                 },
                 "required": ["file_path", "content"]
             }
         }),
+        serde_json::json!({
+            "name": "WriteMany",
+            "description": "Write several files at once, e.g. to scaffold a new module with its tests. All paths must be inside the workspace. The files are written together: if any of them fails, none are changed.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "files": {
+                        "type": "array",
+                        "description": "The files to write",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "path": {
+                                    "type": "string",
+                                    "description": "The absolute path to the file to write"
+                                },
+                                "content": {
+                                    "type": "string",
+                                    "description": "The content to write to the file"
+                                }
+                            },
+                            "required": ["path", "content"]
+                        }
+                    }
+                },
+                "required": ["files"]
+            }
+        }),
         serde_json::json!({
             "name": "Bash",
             "description": "Executes a bash command",
@@ -1536,7 +2514,7 @@ pub fn get_tool_definitions() -> Vec<Value> {
                     },
                     "timeout": {
                         "type": "integer",
-                        "description": "Optional timeout in milliseconds (max 600000)"
+                        "description": "Optional timeout in milliseconds (max 600000), applied when commands run in a persistent shell"
                     },
                     "description": {
                         "type": "string",
@@ -1546,6 +2524,82 @@ pub fn get_tool_definitions() -> Vec<Value> {
                 "required": ["command"]
             }
         }),
+        serde_json::json!({
+            "name": "RunTests",
+            "description": "Runs the project's tests and returns how many passed and failed, the names of the failing tests and the output explaining the failures. The framework (cargo test, pytest or npm test) is detected from Cargo.toml, pytest or Python project config, or package.json. Use it rather than Bash to run tests: it doesn't need permission. Iterate on failures with filter to rerun only the failing tests.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "framework": {
+                        "type": "string",
+                        "enum": ["cargo", "pytest", "npm"],
+                        "description": "The test framework, when detection picks the wrong one"
+                    },
+                    "filter": {
+                        "type": "string",
+                        "description": "Run only matching tests: a test name filter for cargo, a path, node id or -k expression for pytest, or arguments passed to npm test"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "The absolute path of the project directory; defaults to the working directory"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Seconds to wait before stopping the tests (default 600, max 1800)"
+                    }
+                }
+            }
+        }),
+        serde_json::json!({
+            "name": "NotebookRead",
+            "description": "Reads a Jupyter notebook (.ipynb) as a list of cells, each with its index, id, type, source and outputs. Use it instead of Read for notebooks, whose raw JSON is hard to follow.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "file_path": {
+                        "type": "string",
+                        "description": "The absolute path to the notebook to read"
+                    }
+                },
+                "required": ["file_path"]
+            }
+        }),
+        serde_json::json!({
+            "name": "NotebookEditCell",
+            "description": "Edits one cell of a Jupyter notebook (.ipynb): replaces its source, inserts a new cell or deletes it. Address the cell by the id or index NotebookRead shows. Replacing a code cell clears its outputs. Use it instead of Edit or Write for notebooks.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "file_path": {
+                        "type": "string",
+                        "description": "The absolute path to the notebook to edit"
+                    },
+                    "cell_id": {
+                        "type": "string",
+                        "description": "The id of the cell to edit; when inserting, the new cell goes after it"
+                    },
+                    "cell_index": {
+                        "type": "integer",
+                        "description": "The index of the cell to edit, from 0; when inserting, the position of the new cell"
+                    },
+                    "new_source": {
+                        "type": "string",
+                        "description": "The new source of the cell (required unless deleting)"
+                    },
+                    "cell_type": {
+                        "type": "string",
+                        "enum": ["code", "markdown"],
+                        "description": "The type of the cell (defaults to its current type, or code for a new cell)"
+                    },
+                    "edit_mode": {
+                        "type": "string",
+                        "enum": ["replace", "insert", "delete"],
+                        "description": "Replace the cell's source, insert a new cell or delete the cell (defaults to replace). Inserting without a cell_id or cell_index appends the cell"
+                    }
+                },
+                "required": ["file_path"]
+            }
+        }),
         serde_json::json!({
             "name": "DocumentSymbol",
             "description": "Extracts document symbols from a file using LSP",
@@ -1637,5 +2691,19 @@ pub fn get_tool_definitions() -> Vec<Value> {
                 "required": ["file_path", "position", "server_type"]
             }
         }),
+        serde_json::json!({
+            "name": "Agent",
+            "description": "Delegates an objective, such as a broad search or an exploration of unfamiliar code, to a sub-agent with its own conversation and only the read-only tools. Only the sub-agent's final report comes back, so use it for open-ended searches whose intermediate results you don't need, e.g. \"find where config is loaded and which environment variables override it\". The sub-agent can't see this conversation: state everything it needs in the objective.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "objective": {
+                        "type": "string",
+                        "description": "What the sub-agent should find out, as a complete, self-contained instruction"
+                    }
+                },
+                "required": ["objective"]
+            }
+        }),
     ]
 }
@@ -0,0 +1,123 @@
+use crate::agent::tool_queue::QueuedCallStatus;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Longest part of a tool's output kept in the trail
+pub const TRAIL_OUTCOME_CHARS: usize = 300;
+
+/// A tool call as it was recorded when it ran
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrailCall {
+    pub tool: String,
+    pub arguments: Value,
+    pub status: QueuedCallStatus,
+    /// Start of the tool's output, or the reason it was denied
+    pub outcome: String,
+}
+
+impl TrailCall {
+    pub fn new(tool: &str, arguments: &Value, status: QueuedCallStatus, output: &str) -> Self {
+        let output = output.trim();
+        let outcome = if output.chars().count() > TRAIL_OUTCOME_CHARS {
+            let start: String = output.chars().take(TRAIL_OUTCOME_CHARS).collect();
+            format!("{start}…")
+        } else {
+            output.to_string()
+        };
+        Self {
+            tool: tool.to_string(),
+            arguments: arguments.clone(),
+            status,
+            outcome,
+        }
+    }
+}
+
+/// The tool calls of one iteration of a turn, with what the model wrote when it
+/// requested them
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrailRound {
+    /// Iteration of the turn's tool loop, counting from 1
+    pub iteration: usize,
+    pub note: Option<String>,
+    pub calls: Vec<TrailCall>,
+}
+
+impl TrailRound {
+    pub fn new(iteration: usize, note: &str) -> Self {
+        let note = note.trim();
+        Self {
+            iteration,
+            note: (!note.is_empty()).then(|| note.to_string()),
+            calls: Vec::new(),
+        }
+    }
+}
+
+/// The recorded tool calls and decisions of a finished task, so its reasoning can
+/// be explained from what actually happened rather than from the model's memory
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TaskTrail {
+    pub task_id: String,
+    pub prompt: String,
+    pub rounds: Vec<TrailRound>,
+    pub answer: String,
+}
+
+impl TaskTrail {
+    pub fn new(task_id: &str, prompt: &str, rounds: Vec<TrailRound>, answer: &str) -> Self {
+        Self {
+            task_id: task_id.to_string(),
+            prompt: prompt.to_string(),
+            rounds,
+            answer: answer.to_string(),
+        }
+    }
+
+    /// Number of tool calls in the trail
+    pub fn call_count(&self) -> usize {
+        self.rounds.iter().map(|round| round.calls.len()).sum()
+    }
+
+    /// Prompt asking for an explanation of the task from its trail, answering
+    /// `question` when one is given
+    pub fn explanation_prompt(&self, question: Option<&str>) -> String {
+        let mut prompt = String::from(
+            "Below is the recorded trail of a task an AI coding agent just carried out: \
+             the request, each round of tool calls with the note the agent wrote when it \
+             made them, how each call ended, and the final answer. Using only this record, \
+             explain why the agent took the actions it did. Point out any step the record \
+             doesn't justify instead of inventing a reason for it.\n",
+        );
+        match question.map(str::trim).filter(|q| !q.is_empty()) {
+            Some(question) => prompt.push_str(&format!("\nQuestion: {question}\n")),
+            None => prompt.push_str(
+                "\nGive a short account of the reasoning, step by step, and call out edits \
+                 or commands that might surprise the user.\n",
+            ),
+        }
+
+        prompt.push_str(&format!("\n## Request\n{}\n", self.prompt.trim()));
+        if self.rounds.is_empty() {
+            prompt.push_str("\n## Tool calls\nNone; the agent answered directly.\n");
+        }
+        for round in &self.rounds {
+            prompt.push_str(&format!("\n## Round {}\n", round.iteration));
+            if let Some(note) = &round.note {
+                prompt.push_str(&format!("Agent's note: {note}\n"));
+            }
+            for call in &round.calls {
+                let status = serde_json::to_value(call.status)
+                    .ok()
+                    .and_then(|status| status.as_str().map(str::to_string))
+                    .unwrap_or_default();
+                prompt.push_str(&format!(
+                    "- {} {} [{status}]: {}\n",
+                    call.tool, call.arguments, call.outcome
+                ));
+            }
+        }
+        prompt.push_str(&format!("\n## Final answer\n{}\n", self.answer.trim()));
+        prompt
+    }
+}
@@ -1,5 +1,7 @@
-pub mod anthropic;
-pub mod api_client;
-pub mod gemini;
-pub mod ollama;
-pub mod openai;
+//! Provider API clients. They live in the `oli-providers` crate so they can be used
+//! without the rest of oli; the modules are re-exported here under their old paths.
+
+pub use oli_providers::{
+    anthropic, api_client, batch, gemini, key_check, ledger, log, ollama, openai, openrouter,
+    provider, retry, streaming, tokenizer,
+};
@@ -0,0 +1,166 @@
+use super::core::App;
+use crate::agent::core::LLMProvider;
+use crate::apis::key_check::{diagnose_api_key, key_env_var, KeyDiagnostics};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the key store location
+pub const KEY_STORE_ENV: &str = "OLI_KEY_STORE";
+
+/// File of `NAME=value` lines holding API keys saved with `/key`, readable only by
+/// the user. Keys already set in the environment or `.env` take precedence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyStore {
+    path: PathBuf,
+}
+
+impl KeyStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// The configured store, `~/.oli/keys.env` unless overridden by `OLI_KEY_STORE`
+    pub fn from_env() -> Option<Self> {
+        match std::env::var(KEY_STORE_ENV) {
+            Ok(path) if !path.trim().is_empty() => Some(Self::new(PathBuf::from(path))),
+            _ => dirs::home_dir().map(|home| Self::new(home.join(".oli").join("keys.env"))),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Set the stored keys that aren't already in the environment
+    pub fn load(&self) {
+        if self.path.exists() {
+            let _ = dotenv::from_path(&self.path);
+        }
+    }
+
+    /// The stored value of `env_var`, if any
+    pub fn get(&self, env_var: &str) -> Option<String> {
+        let contents = std::fs::read_to_string(&self.path).ok()?;
+        contents.lines().find_map(|line| {
+            line.strip_prefix(env_var)
+                .and_then(|rest| rest.strip_prefix('='))
+                .map(|value| value.to_string())
+        })
+    }
+
+    /// Store `key` as `env_var`, replacing any earlier value, and use it from now on
+    pub fn save(&self, env_var: &str, key: &str) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        let existing = std::fs::read_to_string(&self.path).unwrap_or_default();
+        let prefix = format!("{env_var}=");
+        let mut lines: Vec<String> = existing
+            .lines()
+            .filter(|line| !line.starts_with(&prefix))
+            .map(|line| line.to_string())
+            .collect();
+        lines.push(format!("{prefix}{key}"));
+
+        std::fs::write(&self.path, lines.join("\n") + "\n")
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&self.path, std::fs::Permissions::from_mode(0o600))
+                .with_context(|| format!("Failed to restrict {}", self.path.display()))?;
+        }
+
+        std::env::set_var(env_var, key);
+        Ok(())
+    }
+}
+
+/// Cloud provider serving a model, by the same name matching used to pick API keys
+pub fn cloud_provider(model_name: &str) -> Option<LLMProvider> {
+    let name = model_name.to_lowercase();
+    // OpenRouter serves other vendors' models, so check it before their names
+    if name.contains("openrouter") {
+        Some(LLMProvider::OpenRouter)
+    } else if name.contains("azure") {
+        Some(LLMProvider::AzureOpenAI)
+    } else if name.contains("compatible") {
+        Some(LLMProvider::OpenAICompatible)
+    } else if name.contains("claude") {
+        Some(LLMProvider::Anthropic)
+    } else if name.contains("gpt") {
+        Some(LLMProvider::OpenAI)
+    } else if name.contains("gemini") {
+        Some(LLMProvider::Gemini)
+    } else {
+        None
+    }
+}
+
+impl App {
+    fn key_provider(&self, model_index: usize) -> Result<(String, LLMProvider)> {
+        let model = self.current_model(model_index)?;
+        let provider = cloud_provider(&model.name)
+            .with_context(|| format!("{} doesn't use an API key", model.name))?;
+        Ok((model.name.clone(), provider))
+    }
+
+    /// Check the API key for a cloud model: whether it's missing or malformed, and
+    /// whether the provider accepts it
+    pub fn diagnose_api_key(&self, model_index: usize) -> Result<KeyDiagnostics> {
+        let runtime = self
+            .tokio_runtime
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Async runtime not available"))?;
+        let (model_name, provider) = self.key_provider(model_index)?;
+        let key = self.get_api_key_for_model(&model_name);
+        Ok(runtime.block_on(diagnose_api_key(&provider, &key)))
+    }
+
+    /// Whether the model's provider sends images attached with /attach as image
+    /// input; local models and Gemini get a note that they were left out
+    pub fn model_accepts_images(&self, model_index: usize) -> bool {
+        self.available_models
+            .get(model_index)
+            .and_then(|model| cloud_provider(&model.name))
+            .is_some_and(|provider| provider.accepts_images())
+    }
+
+    /// Diagnose the API key after a query to a cloud model failed. Only the first
+    /// failure in a session is diagnosed, to avoid repeating the same report.
+    pub fn explain_api_key_failure(&mut self, model_index: usize) -> Option<String> {
+        if self.api_key_failure_diagnosed || self.key_provider(model_index).is_err() {
+            return None;
+        }
+        self.api_key_failure_diagnosed = true;
+
+        let diagnostics = self.diagnose_api_key(model_index).ok()?;
+        (!diagnostics.is_healthy()).then(|| diagnostics.report())
+    }
+
+    /// Check `key` for a cloud model and, if the provider accepts it, store it in the
+    /// key store and use it from now on. Returns the diagnostics either way.
+    pub fn save_api_key(&mut self, model_index: usize, key: &str) -> Result<KeyDiagnostics> {
+        let runtime = self
+            .tokio_runtime
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Async runtime not available"))?;
+        let (_, provider) = self.key_provider(model_index)?;
+        let key = key.trim();
+
+        let diagnostics = runtime.block_on(diagnose_api_key(&provider, key));
+        if !diagnostics.is_healthy() {
+            return Ok(diagnostics);
+        }
+
+        let env_var = key_env_var(&provider).context("Provider doesn't use an API key")?;
+        let store = KeyStore::from_env().context("No home directory for the key store")?;
+        store.save(env_var, key)?;
+        // A key passed in explicitly would shadow the stored one
+        self.api_key = None;
+        self.api_key_failure_diagnosed = false;
+        Ok(diagnostics)
+    }
+}
@@ -0,0 +1,164 @@
+use crate::agent::core::{Agent, LLMProvider};
+use crate::agent::policy::ToolPolicy;
+use crate::apis::key_check::key_env_var;
+use crate::app::core::App;
+use crate::app::summarizer::{SummarizerConfig, SUMMARIZER_MODEL_ENV};
+use anyhow::Result;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use uuid::Uuid;
+
+/// Environment variable selecting the model for side questions, as a
+/// `provider:model` spec like `OLI_SUMMARIZER_MODEL`
+pub const ASK_MODEL_ENV: &str = "OLI_ASK_MODEL";
+
+/// Cheap models used for side questions when no model is configured, tried in
+/// order until one has an API key
+const DEFAULT_ASK_MODELS: &[&str] = &[
+    "anthropic:claude-3-5-haiku-latest",
+    "openai:gpt-4o-mini",
+    "gemini:gemini-2.0-flash",
+];
+
+/// Answers kept for promotion into the main session
+const MAX_KEPT_ANSWERS: usize = 20;
+
+/// A side question answered outside the main conversation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AskAnswer {
+    pub id: String,
+    pub question: String,
+    /// `provider:model` spec of the model that answered
+    pub model: String,
+    pub answer: Option<String>,
+    pub error: Option<String>,
+    pub latency_ms: u64,
+}
+
+/// Quick questions about the codebase answered by a cheaper model with read-only
+/// tools while the main task runs. Each question starts a fresh conversation in
+/// the same workspace; the main conversation is neither read nor changed until
+/// an answer is promoted into it.
+#[derive(Debug, Clone, Default)]
+pub struct AskSession {
+    answers: Arc<Mutex<Vec<AskAnswer>>>,
+}
+
+impl AskSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The model for side questions: `OLI_ASK_MODEL`, then the summarizer model,
+    /// then the first cheap default whose provider has an API key
+    pub fn model() -> Result<SummarizerConfig> {
+        for env_var in [ASK_MODEL_ENV, SUMMARIZER_MODEL_ENV] {
+            if let Some(spec) = std::env::var(env_var).ok().filter(|s| !s.trim().is_empty()) {
+                return SummarizerConfig::parse(&spec)
+                    .map_err(|e| anyhow::anyhow!("Invalid {env_var}: {e}"));
+            }
+        }
+
+        DEFAULT_ASK_MODELS
+            .iter()
+            .filter_map(|spec| SummarizerConfig::parse(spec).ok())
+            .find(|config| !api_key(&config.provider).is_empty())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No model for side questions. Set {ASK_MODEL_ENV}, e.g. ollama:llama3.2, \
+                     or an Anthropic, OpenAI or Gemini API key"
+                )
+            })
+    }
+
+    /// Answer a question with read-only tools, blocking until done. Errors are
+    /// reported in the answer so it can still be shown.
+    pub fn ask(&self, question: &str, working_dir: Option<String>) -> Result<AskAnswer> {
+        let question = question.trim();
+        if question.is_empty() {
+            anyhow::bail!("The question is empty");
+        }
+        let model = Self::model()?;
+
+        let started = Instant::now();
+        let result = tokio::runtime::Runtime::new()?.block_on(async {
+            Self::agent(&model, working_dir)
+                .await?
+                .execute(question)
+                .await
+        });
+
+        let (answer, error) = match result {
+            Ok(answer) => (Some(answer), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let answer = AskAnswer {
+            id: Uuid::new_v4().to_string(),
+            question: question.to_string(),
+            model: model.spec(),
+            answer,
+            error,
+            latency_ms: started.elapsed().as_millis() as u64,
+        };
+
+        let mut answers = self.answers.lock().unwrap();
+        answers.push(answer.clone());
+        let excess = answers.len().saturating_sub(MAX_KEPT_ANSWERS);
+        answers.drain(..excess);
+        Ok(answer)
+    }
+
+    /// A kept answer by id
+    pub fn get(&self, id: &str) -> Option<AskAnswer> {
+        self.answers
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|answer| answer.id == id)
+            .cloned()
+    }
+
+    async fn agent(model: &SummarizerConfig, working_dir: Option<String>) -> Result<Agent> {
+        let mut agent = Agent::new(model.provider.clone())
+            .with_model(model.model.clone())
+            .with_tool_policy(ToolPolicy::ReadOnly);
+        if let Some(cwd) = working_dir {
+            agent = agent.with_working_directory(cwd);
+        }
+        agent
+            .initialize_with_api_key(api_key(&model.provider))
+            .await?;
+        Ok(agent)
+    }
+}
+
+/// API key for a provider from the environment; local models need none
+fn api_key(provider: &LLMProvider) -> String {
+    match key_env_var(provider) {
+        Some(env_var) => std::env::var(env_var).unwrap_or_default(),
+        None => String::new(),
+    }
+}
+
+impl App {
+    /// Add a side question and its answer to the main conversation, so the main
+    /// task can build on it
+    pub fn promote_answer(&mut self, answer: &AskAnswer) -> Result<()> {
+        let text = answer
+            .answer
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("The side question has no answer to promote"))?;
+
+        let question = format!("(Side question) {}", answer.question);
+        self.messages.push(format!("[user] {question}"));
+        self.messages.push(format!("[assistant] {text}"));
+        let session = self
+            .session_manager
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Session manager not available"))?;
+        session.add_user_message(question);
+        session.add_assistant_message(text.to_string());
+        Ok(())
+    }
+}
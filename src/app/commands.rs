@@ -22,6 +22,76 @@ pub fn get_available_commands() -> Vec<SpecialCommand> {
         SpecialCommand::new("/help", "Show help and available commands"),
         SpecialCommand::new("/clear", "Clear conversation history"),
         SpecialCommand::new("/exit", "Exit the application"),
+        SpecialCommand::new("/cancel", "Stop the running task, keeping a partial result"),
+        SpecialCommand::new(
+            "/compact",
+            "Summarize older messages on the compaction model to free up context",
+        ),
+        SpecialCommand::new("/undo", "Restore the files changed by the last agent turn"),
+        SpecialCommand::new(
+            "/init",
+            "Use the project's OLI.md or AGENTS.md, or write a starter OLI.md",
+        ),
         SpecialCommand::new("/memory", "Display and manage codebase memory"),
+        SpecialCommand::new(
+            "/set",
+            "Set a model parameter (temperature, top_p, max_tokens)",
+        ),
+        SpecialCommand::new(
+            "/preset",
+            "Choose a sampling preset (precise, balanced, creative) for a mode",
+        ),
+        SpecialCommand::new(
+            "/accessible",
+            "Toggle screen-reader friendly output without animations or icons",
+        ),
+        SpecialCommand::new("/theme", "List the color themes or switch to one"),
+        SpecialCommand::new(
+            "/transcript",
+            "Write the conversation as a plain-text transcript",
+        ),
+        SpecialCommand::new(
+            "/export",
+            "Export the conversation as Markdown, or HTML for .html paths",
+        ),
+        SpecialCommand::new("/audit", "Show recent Edit, Write and Bash executions"),
+        SpecialCommand::new("/env", "Show the shell environment Bash commands run with"),
+        SpecialCommand::new(
+            "/compare",
+            "Answer a prompt with two models side by side (read-only tools)",
+        ),
+        SpecialCommand::new(
+            "/takeover",
+            "Enable all tools while another oli instance holds the workspace",
+        ),
+        SpecialCommand::new(
+            "/paste-context",
+            "Attach the clipboard to the next prompt as a context block",
+        ),
+        SpecialCommand::new(
+            "/attach",
+            "Attach images such as screenshots to the next prompt",
+        ),
+        SpecialCommand::new(
+            "/key",
+            "Check an API key for the selected model and store it",
+        ),
+        SpecialCommand::new("/stage", "Stage the working tree's changes hunk by hunk"),
+        SpecialCommand::new(
+            "/rename",
+            "Rename a symbol across the workspace after reviewing the diff",
+        ),
+        SpecialCommand::new(
+            "/processes",
+            "List or kill the language servers and Bash commands oli started",
+        ),
+        SpecialCommand::new(
+            "/permissions",
+            "List or change what the project always allows and its tool permission levels",
+        ),
+        SpecialCommand::new(
+            "/why",
+            "Explain the last task's tool calls and decisions from its recorded trail",
+        ),
     ]
 }
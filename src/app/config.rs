@@ -0,0 +1,661 @@
+use crate::agent::approval::{APPROVAL_COMMAND_ENV, APPROVAL_TIMEOUT_ENV, APPROVAL_WEBHOOK_ENV};
+use crate::agent::audit::AUDIT_LOG_ENV;
+use crate::agent::executor::TURN_TIMEOUT_ENV;
+use crate::agent::permissions::{
+    check_edit_paths, PermissionLevel, EDIT_PATHS_ENV, TOOL_PERMISSIONS_ENV,
+};
+use crate::agent::result_limits::RESULT_LIMITS_ENV;
+use crate::apis::anthropic::PROMPT_CACHING_ENV;
+use crate::apis::api_client::ModelParameters;
+use crate::apis::ledger::REQUEST_LEDGER_ENV;
+use crate::apis::openai::{
+    AZURE_OPENAI_API_VERSION_ENV, AZURE_OPENAI_DEPLOYMENT_ENV, AZURE_OPENAI_ENDPOINT_ENV,
+    OPENAI_COMPATIBLE_BASE_URL_ENV, OPENAI_COMPATIBLE_MODEL_ENV,
+};
+use crate::app::ask::ASK_MODEL_ENV;
+use crate::app::history::{AUTO_COMPACT_ENV, COMPACT_MODEL_ENV};
+use crate::app::logger::{
+    LOG_DIR_ENV, LOG_MAX_BYTES_ENV, LOG_MAX_FILES_ENV, LOG_MEMORY_LINES_ENV, LOG_RETENTION_DAYS_ENV,
+};
+use crate::app::presets::{PresetSelection, SAMPLING_PRESETS_ENV};
+use crate::app::quick_start::OLLAMA_MODEL_ENV;
+use crate::app::session_store::SESSION_DIR_ENV;
+use crate::app::session_summary::SESSION_SUMMARY_ENV;
+use crate::app::storage::STORAGE_MAX_BYTES_ENV;
+use crate::app::summarizer::SUMMARIZER_MODEL_ENV;
+use crate::app::theme::{THEMES, THEME_ENV};
+use crate::app::utils::{ScrollBindings, SCROLL_BINDINGS_ENV};
+use crate::app::workspace_lock::SECOND_INSTANCE_ENV;
+use crate::tools::fs::read_limits::{READ_MAX_BYTES_ENV, READ_MAX_LINES_ENV};
+use crate::tools::fs::workspace_index::LARGE_REPO_ENV;
+use crate::tools::processes::{IDLE_TIMEOUT_ENV, PROCESS_DIR_ENV};
+use crate::tools::shell::{SHELL_ENV, SHELL_LOGIN_ENV, SHELL_PERSISTENT_ENV, SHELL_RC_ENV};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use toml::Spanned;
+
+/// Environment variable overriding the config file location
+pub const CONFIG_FILE_ENV: &str = "OLI_CONFIG";
+
+/// Settings only read when oli starts, so changing them needs a restart
+pub const STARTUP_SETTINGS: &[&str] = &[
+    LOG_DIR_ENV,
+    LOG_MAX_BYTES_ENV,
+    LOG_MAX_FILES_ENV,
+    LOG_RETENTION_DAYS_ENV,
+    LOG_MEMORY_LINES_ENV,
+    PROCESS_DIR_ENV,
+    IDLE_TIMEOUT_ENV,
+    LARGE_REPO_ENV,
+    REQUEST_LEDGER_ENV,
+    SESSION_DIR_ENV,
+    SECOND_INSTANCE_ENV,
+    SCROLL_BINDINGS_ENV,
+];
+
+/// Settings this process set from the config file, with the values it set
+static FROM_CONFIG: Mutex<BTreeMap<&'static str, String>> = Mutex::new(BTreeMap::new());
+
+/// The contents of `config.toml`. Every setting can also be given as the `OLI_*`
+/// environment variable it maps to, which takes precedence.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OliConfig {
+    pub model: ModelSection,
+    pub azure: AzureSection,
+    pub openai_compatible: OpenAICompatibleSection,
+    pub agent: AgentSection,
+    pub approval: ApprovalSection,
+    pub permissions: PermissionsSection,
+    pub shell: ShellSection,
+    pub logging: LoggingSection,
+    pub processes: ProcessesSection,
+    pub workspace: WorkspaceSection,
+    pub files: FilesSection,
+    pub storage: StorageSection,
+    pub ui: UiSection,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelSection {
+    pub temperature: Option<Spanned<f64>>,
+    pub top_p: Option<Spanned<f64>>,
+    pub max_tokens: Option<Spanned<u32>>,
+    /// `OLI_SUMMARIZER_MODEL`
+    pub summarizer: Option<Spanned<String>>,
+    /// `OLI_COMPACT_MODEL`
+    pub compact: Option<Spanned<String>>,
+    /// `OLI_ASK_MODEL`
+    pub ask: Option<Spanned<String>>,
+    /// `OLI_SAMPLING_PRESETS`, e.g. `balanced` or `agent=precise,chat=creative`
+    pub presets: Option<Spanned<String>>,
+    /// `OLI_OLLAMA_MODEL`
+    pub ollama: Option<Spanned<String>>,
+    /// `OLI_PROMPT_CACHING`
+    pub prompt_caching: Option<Spanned<bool>>,
+}
+
+/// The Azure OpenAI resource; its key is read from `AZURE_OPENAI_API_KEY` or stored
+/// with `/key`, not kept in the config
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AzureSection {
+    /// `AZURE_OPENAI_ENDPOINT`, e.g. `https://my-resource.openai.azure.com`
+    pub endpoint: Option<Spanned<String>>,
+    /// `AZURE_OPENAI_DEPLOYMENT`
+    pub deployment: Option<Spanned<String>>,
+    /// `AZURE_OPENAI_API_VERSION`
+    pub api_version: Option<Spanned<String>>,
+}
+
+/// Another service with OpenAI's chat completions API, e.g. DeepSeek, xAI, vLLM or
+/// LM Studio; its key, if it takes one, is read from `OPENAI_COMPATIBLE_API_KEY` or
+/// stored with `/key`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OpenAICompatibleSection {
+    /// `OPENAI_COMPATIBLE_BASE_URL`, e.g. `https://api.deepseek.com/v1`
+    pub base_url: Option<Spanned<String>>,
+    /// `OPENAI_COMPATIBLE_MODEL`, e.g. `deepseek-chat`
+    pub model: Option<Spanned<String>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AgentSection {
+    /// `OLI_TURN_TIMEOUT_SECS`
+    pub turn_timeout_secs: Option<Spanned<u64>>,
+    /// `OLI_SECOND_INSTANCE`: `warn` or `read_only`
+    pub second_instance: Option<Spanned<String>>,
+    /// `OLI_SESSION_SUMMARY`
+    pub session_summary: Option<Spanned<bool>>,
+    /// `OLI_TOOL_RESULT_LIMITS`, e.g. `Bash=200000,Read=none`
+    pub tool_result_limits: Option<Spanned<String>>,
+    /// `OLI_READ_MAX_BYTES`
+    pub read_max_bytes: Option<Spanned<u64>>,
+    /// `OLI_READ_MAX_LINES`
+    pub read_max_lines: Option<Spanned<u64>>,
+    /// `OLI_AUTO_COMPACT_PERCENT`; 0 turns automatic compaction off
+    pub auto_compact_percent: Option<Spanned<u64>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApprovalSection {
+    /// `OLI_APPROVAL_WEBHOOK`
+    pub webhook: Option<Spanned<String>>,
+    /// `OLI_APPROVAL_COMMAND`
+    pub command: Option<Spanned<String>>,
+    /// `OLI_APPROVAL_TIMEOUT_SECS`
+    pub timeout_secs: Option<Spanned<u64>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PermissionsSection {
+    /// `OLI_TOOL_PERMISSIONS`: `auto`, `ask` or `deny` per tool, e.g. `{ Bash = "ask" }`
+    pub tools: BTreeMap<String, Spanned<String>>,
+    /// `OLI_EDIT_PATHS`: globs relative to the working directory, `!` excluding
+    pub edit_paths: Option<Spanned<Vec<String>>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShellSection {
+    /// `OLI_SHELL`
+    pub program: Option<Spanned<String>>,
+    /// `OLI_SHELL_LOGIN`
+    pub login: Option<Spanned<bool>>,
+    /// `OLI_SHELL_RC`
+    pub rc: Option<Spanned<String>>,
+    /// `OLI_SHELL_PERSISTENT`
+    pub persistent: Option<Spanned<bool>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingSection {
+    /// `OLI_LOG_DIR`
+    pub dir: Option<Spanned<String>>,
+    /// `OLI_LOG_MAX_BYTES`
+    pub max_bytes: Option<Spanned<u64>>,
+    /// `OLI_LOG_MAX_FILES`
+    pub max_files: Option<Spanned<u64>>,
+    /// `OLI_LOG_RETENTION_DAYS`
+    pub retention_days: Option<Spanned<u64>>,
+    /// `OLI_LOG_MEMORY_LINES`
+    pub memory_lines: Option<Spanned<u64>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProcessesSection {
+    /// `OLI_PROCESS_DIR`
+    pub dir: Option<Spanned<String>>,
+    /// `OLI_CHILD_IDLE_TIMEOUT`
+    pub idle_timeout_secs: Option<Spanned<u64>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspaceSection {
+    /// `OLI_LARGE_REPO`: `on`, `off`, `auto` or a file count
+    pub large_repo: Option<Spanned<toml::Value>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilesSection {
+    /// `OLI_AUDIT_LOG`
+    pub audit_log: Option<Spanned<String>>,
+    /// `OLI_REQUEST_LEDGER`
+    pub request_ledger: Option<Spanned<String>>,
+    /// `OLI_SESSION_DIR`
+    pub session_dir: Option<Spanned<String>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageSection {
+    /// `OLI_STORAGE_MAX_BYTES`, 0 for no limit
+    pub max_bytes: Option<Spanned<u64>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiSection {
+    /// `OLI_THEME`: `dark`, `light`, `high-contrast` or `solarized`
+    pub theme: Option<Spanned<String>>,
+    /// `OLI_SCROLL_BINDINGS`: overrides such as `ctrl+k=line_up, ctrl+d=none`
+    pub scroll_bindings: Option<Spanned<String>>,
+}
+
+/// One problem found in a config file, with its 1-based position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigIssue {
+    pub line: usize,
+    pub column: usize,
+    /// Characters of the line the problem covers, at least 1
+    pub width: usize,
+    pub message: String,
+}
+
+/// Why a config file couldn't be used, pointing at each problem in the file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    pub issues: Vec<ConfigIssue>,
+    source: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, issue) in self.issues.iter().enumerate() {
+            if i > 0 {
+                write!(f, "\n\n")?;
+            }
+            let text = self.source.lines().nth(issue.line - 1).unwrap_or_default();
+            let gutter = " ".repeat(issue.line.to_string().len());
+            writeln!(
+                f,
+                "{}:{}:{}: {}",
+                self.path.display(),
+                issue.line,
+                issue.column,
+                issue.message
+            )?;
+            writeln!(f, "{gutter} |")?;
+            writeln!(f, "{} | {text}", issue.line)?;
+            write!(
+                f,
+                "{gutter} | {}{}",
+                " ".repeat(issue.column - 1),
+                "^".repeat(issue.width)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl OliConfig {
+    /// Parse and validate the text of a config file, reporting every out-of-range
+    /// value, or the first unknown key or wrong type
+    pub fn parse(source: &str, path: &Path) -> std::result::Result<Self, ConfigError> {
+        let mut checker = Checker {
+            source,
+            issues: Vec::new(),
+        };
+        let config = match toml::from_str::<Self>(source) {
+            Ok(config) => {
+                config.validate(&mut checker);
+                Some(config)
+            }
+            Err(e) => {
+                let span = e.span().unwrap_or(0..0);
+                checker.report(span, e.message().trim());
+                None
+            }
+        };
+
+        match config {
+            Some(config) if checker.issues.is_empty() => Ok(config),
+            _ => Err(ConfigError {
+                path: path.to_path_buf(),
+                issues: checker.issues,
+                source: source.to_string(),
+            }),
+        }
+    }
+
+    fn validate(&self, checker: &mut Checker) {
+        checker.range("temperature", &self.model.temperature, 0.0, 2.0);
+        checker.range("top_p", &self.model.top_p, 0.0, 1.0);
+        checker.at_least("max_tokens", &self.model.max_tokens, 1);
+        if let Some(presets) = &self.model.presets {
+            if let Err(e) = PresetSelection::parse(presets.get_ref()) {
+                checker.report(presets.span(), &format!("`presets`: {e}"));
+            }
+        }
+        if let Some(endpoint) = &self.azure.endpoint {
+            if !endpoint.get_ref().starts_with("https://") {
+                checker.report(endpoint.span(), "`endpoint` must be an https:// URL");
+            }
+        }
+        if let Some(base_url) = &self.openai_compatible.base_url {
+            let url = base_url.get_ref();
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                checker.report(
+                    base_url.span(),
+                    "`base_url` must be an http:// or https:// URL",
+                );
+            }
+        }
+        checker.one_of(
+            "second_instance",
+            &self.agent.second_instance,
+            &["warn", "read_only"],
+        );
+        checker.one_of("theme", &self.ui.theme, THEMES);
+        if let Some(bindings) = &self.ui.scroll_bindings {
+            if let Err(e) = ScrollBindings::with_overrides(bindings.get_ref()) {
+                checker.report(bindings.span(), &format!("`scroll_bindings`: {e}"));
+            }
+        }
+        checker.at_least("read_max_bytes", &self.agent.read_max_bytes, 1024);
+        checker.at_least("read_max_lines", &self.agent.read_max_lines, 1);
+        checker.range(
+            "auto_compact_percent",
+            &self.agent.auto_compact_percent,
+            0,
+            100,
+        );
+        checker.range("timeout_secs", &self.approval.timeout_secs, 1, 3600);
+        for (tool, level) in &self.permissions.tools {
+            if let Err(e) = PermissionLevel::parse(level.get_ref()) {
+                checker.report(level.span(), &format!("`tools.{tool}`: {e}"));
+            }
+        }
+        if let Some(edit_paths) = &self.permissions.edit_paths {
+            let globs = edit_paths.get_ref();
+            if globs.iter().any(|glob| glob.contains(',')) {
+                checker.report(edit_paths.span(), "`edit_paths` globs can't contain commas");
+            } else if let Err(e) = check_edit_paths(globs) {
+                checker.report(edit_paths.span(), &format!("`edit_paths`: {e:#}"));
+            }
+        }
+        checker.at_least("max_bytes", &self.logging.max_bytes, 1024);
+        checker.range("max_files", &self.logging.max_files, 0, 100);
+        checker.at_least("memory_lines", &self.logging.memory_lines, 1);
+
+        if let Some(webhook) = &self.approval.webhook {
+            if !webhook.get_ref().starts_with("http://")
+                && !webhook.get_ref().starts_with("https://")
+            {
+                checker.report(
+                    webhook.span(),
+                    "`webhook` must be an http:// or https:// URL",
+                );
+            }
+            if let Some(command) = &self.approval.command {
+                checker.report(
+                    command.span(),
+                    "set either `webhook` or `command`, not both; the webhook would be used",
+                );
+            }
+        }
+
+        if let Some(large_repo) = &self.workspace.large_repo {
+            let valid = match large_repo.get_ref() {
+                toml::Value::String(mode) => ["on", "off", "auto"].contains(&mode.as_str()),
+                toml::Value::Integer(files) => *files >= 0,
+                _ => false,
+            };
+            if !valid {
+                checker.report(
+                    large_repo.span(),
+                    "`large_repo` must be \"on\", \"off\", \"auto\" or a file count",
+                );
+            }
+        }
+    }
+
+    /// The environment variables the config sets, with their values
+    pub fn settings(&self) -> Vec<(&'static str, String)> {
+        let mut settings = Vec::new();
+        let mut set = |name: &'static str, value: Option<String>| {
+            if let Some(value) = value {
+                settings.push((name, value));
+            }
+        };
+        fn text<T: ToString>(value: &Option<Spanned<T>>) -> Option<String> {
+            value.as_ref().map(|value| value.get_ref().to_string())
+        }
+
+        set(SUMMARIZER_MODEL_ENV, text(&self.model.summarizer));
+        set(COMPACT_MODEL_ENV, text(&self.model.compact));
+        set(ASK_MODEL_ENV, text(&self.model.ask));
+        set(SAMPLING_PRESETS_ENV, text(&self.model.presets));
+        set(OLLAMA_MODEL_ENV, text(&self.model.ollama));
+        set(PROMPT_CACHING_ENV, text(&self.model.prompt_caching));
+        set(AZURE_OPENAI_ENDPOINT_ENV, text(&self.azure.endpoint));
+        set(AZURE_OPENAI_DEPLOYMENT_ENV, text(&self.azure.deployment));
+        set(AZURE_OPENAI_API_VERSION_ENV, text(&self.azure.api_version));
+        set(
+            OPENAI_COMPATIBLE_BASE_URL_ENV,
+            text(&self.openai_compatible.base_url),
+        );
+        set(
+            OPENAI_COMPATIBLE_MODEL_ENV,
+            text(&self.openai_compatible.model),
+        );
+        set(TURN_TIMEOUT_ENV, text(&self.agent.turn_timeout_secs));
+        set(SECOND_INSTANCE_ENV, text(&self.agent.second_instance));
+        set(SESSION_SUMMARY_ENV, text(&self.agent.session_summary));
+        set(RESULT_LIMITS_ENV, text(&self.agent.tool_result_limits));
+        set(READ_MAX_BYTES_ENV, text(&self.agent.read_max_bytes));
+        set(READ_MAX_LINES_ENV, text(&self.agent.read_max_lines));
+        set(AUTO_COMPACT_ENV, text(&self.agent.auto_compact_percent));
+        set(APPROVAL_WEBHOOK_ENV, text(&self.approval.webhook));
+        set(APPROVAL_COMMAND_ENV, text(&self.approval.command));
+        set(APPROVAL_TIMEOUT_ENV, text(&self.approval.timeout_secs));
+        if !self.permissions.tools.is_empty() {
+            let levels: Vec<String> = self
+                .permissions
+                .tools
+                .iter()
+                .map(|(tool, level)| format!("{tool}={}", level.get_ref()))
+                .collect();
+            set(TOOL_PERMISSIONS_ENV, Some(levels.join(",")));
+        }
+        set(
+            EDIT_PATHS_ENV,
+            self.permissions
+                .edit_paths
+                .as_ref()
+                .map(|globs| globs.get_ref().join(",")),
+        );
+        set(SHELL_ENV, text(&self.shell.program));
+        set(SHELL_LOGIN_ENV, text(&self.shell.login));
+        set(SHELL_RC_ENV, text(&self.shell.rc));
+        set(SHELL_PERSISTENT_ENV, text(&self.shell.persistent));
+        set(LOG_DIR_ENV, text(&self.logging.dir));
+        set(LOG_MAX_BYTES_ENV, text(&self.logging.max_bytes));
+        set(LOG_MAX_FILES_ENV, text(&self.logging.max_files));
+        set(LOG_RETENTION_DAYS_ENV, text(&self.logging.retention_days));
+        set(LOG_MEMORY_LINES_ENV, text(&self.logging.memory_lines));
+        set(PROCESS_DIR_ENV, text(&self.processes.dir));
+        set(IDLE_TIMEOUT_ENV, text(&self.processes.idle_timeout_secs));
+        set(
+            LARGE_REPO_ENV,
+            self.workspace
+                .large_repo
+                .as_ref()
+                .map(|value| match value.get_ref() {
+                    toml::Value::String(mode) => mode.clone(),
+                    other => other.to_string(),
+                }),
+        );
+        set(AUDIT_LOG_ENV, text(&self.files.audit_log));
+        set(REQUEST_LEDGER_ENV, text(&self.files.request_ledger));
+        set(SESSION_DIR_ENV, text(&self.files.session_dir));
+        set(STORAGE_MAX_BYTES_ENV, text(&self.storage.max_bytes));
+        set(THEME_ENV, text(&self.ui.theme));
+        set(SCROLL_BINDINGS_ENV, text(&self.ui.scroll_bindings));
+        settings
+    }
+
+    /// The model parameters the config sets as defaults
+    pub fn model_parameters(&self) -> ModelParameters {
+        ModelParameters {
+            temperature: self.model.temperature.as_ref().map(|t| *t.get_ref() as f32),
+            top_p: self.model.top_p.as_ref().map(|p| *p.get_ref() as f32),
+            max_tokens: self.model.max_tokens.as_ref().map(|m| *m.get_ref()),
+        }
+    }
+
+    /// Set the environment variables of `settings`, except those already set
+    /// outside the config, and unset the ones an earlier load set that this config
+    /// no longer does
+    pub fn apply(&self) -> AppliedConfig {
+        let settings: BTreeMap<&'static str, String> = self.settings().into_iter().collect();
+        let mut from_config = FROM_CONFIG.lock().unwrap();
+        let mut applied = AppliedConfig::default();
+        let ours = |name: &str, from_config: &BTreeMap<&'static str, String>| {
+            std::env::var(name).ok().as_ref() == from_config.get(name)
+        };
+
+        for name in from_config.keys().copied().collect::<Vec<_>>() {
+            if !settings.contains_key(name) {
+                if ours(name, &from_config) {
+                    std::env::remove_var(name);
+                    applied.note_change(name);
+                }
+                from_config.remove(name);
+            }
+        }
+
+        for (name, value) in settings {
+            let current = std::env::var(name).ok();
+            if current.is_some() && !ours(name, &from_config) {
+                applied.overridden.push(name.to_string());
+                continue;
+            }
+            if current.as_ref() != Some(&value) {
+                std::env::set_var(name, &value);
+                applied.note_change(name);
+            }
+            applied.applied.push(name.to_string());
+            from_config.insert(name, value);
+        }
+        applied
+    }
+}
+
+/// What applying a config did to the environment
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AppliedConfig {
+    /// Settings taken from the config
+    pub applied: Vec<String>,
+    /// Settings ignored because the environment already sets them
+    pub overridden: Vec<String>,
+    /// Changed settings that only take effect after a restart
+    pub restart_required: Vec<String>,
+}
+
+impl AppliedConfig {
+    fn note_change(&mut self, name: &str) {
+        if STARTUP_SETTINGS.contains(&name) {
+            self.restart_required.push(name.to_string());
+        }
+    }
+}
+
+/// Collects the problems found while validating a config
+struct Checker<'a> {
+    source: &'a str,
+    issues: Vec<ConfigIssue>,
+}
+
+impl Checker<'_> {
+    fn report(&mut self, span: Range<usize>, message: &str) {
+        let start = span.start.min(self.source.len());
+        let before = &self.source[..start];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.source[start..]
+            .find('\n')
+            .map_or(self.source.len(), |i| start + i);
+        let end = span.end.clamp(start, line_end);
+        self.issues.push(ConfigIssue {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            width: self.source[start..end].chars().count().max(1),
+            message: message.to_string(),
+        });
+    }
+
+    fn range<T: PartialOrd + fmt::Display + Copy>(
+        &mut self,
+        key: &str,
+        value: &Option<Spanned<T>>,
+        min: T,
+        max: T,
+    ) {
+        if let Some(value) = value {
+            let number = *value.get_ref();
+            if number < min || number > max {
+                let message = format!("`{key}` must be between {min} and {max}, got {number}");
+                self.report(value.span(), &message);
+            }
+        }
+    }
+
+    fn at_least<T: PartialOrd + fmt::Display + Copy>(
+        &mut self,
+        key: &str,
+        value: &Option<Spanned<T>>,
+        min: T,
+    ) {
+        if let Some(value) = value {
+            let number = *value.get_ref();
+            if number < min {
+                let message = format!("`{key}` must be at least {min}, got {number}");
+                self.report(value.span(), &message);
+            }
+        }
+    }
+
+    fn one_of(&mut self, key: &str, value: &Option<Spanned<String>>, allowed: &[&str]) {
+        let Some(value) = value else {
+            return;
+        };
+        if !allowed.contains(&value.get_ref().as_str()) {
+            let allowed: Vec<String> = allowed.iter().map(|a| format!("\"{a}\"")).collect();
+            self.report(
+                value.span(),
+                &format!("`{key}` must be one of {}", allowed.join(", ")),
+            );
+        }
+    }
+}
+
+/// The config file, `~/.oli/config.toml` unless overridden by `OLI_CONFIG`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigFile {
+    path: PathBuf,
+}
+
+impl ConfigFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn from_env() -> Option<Self> {
+        match std::env::var(CONFIG_FILE_ENV) {
+            Ok(path) if !path.trim().is_empty() => Some(Self::new(PathBuf::from(path))),
+            _ => dirs::home_dir().map(|home| Self::new(home.join(".oli").join("config.toml"))),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read and validate the file. None when it doesn't exist; a `ConfigError`
+    /// when it's invalid.
+    pub fn load(&self) -> Result<Option<OliConfig>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let source = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        Ok(Some(OliConfig::parse(&source, &self.path)?))
+    }
+}
@@ -1,13 +1,48 @@
+use crate::agent::approval::ApprovalBackend;
+use crate::agent::audit::AuditLog;
+use crate::agent::checkpoints::Checkpoints;
+use crate::agent::compare::ModelComparison;
 use crate::agent::core::Agent;
-use crate::apis::api_client::{ApiClient, SessionManager};
+use crate::agent::evidence::Evidence;
+use crate::agent::executor::turn_time_limit_from_env;
+use crate::agent::fanout::FanOutConfig;
+use crate::agent::trail::TaskTrail;
+use crate::apis::api_client::{
+    ApiClient, CompletionOptions, ImageData, Message, ModelParameters, SamplingPreset,
+    SessionManager,
+};
+use crate::apis::batch::{BatchApi, BatchPoller, BatchRequest, BatchResult, BatchStatus};
+use crate::apis::ledger::{LedgerTotals, UsageMeter};
+use crate::apis::ollama::{OllamaClient, OllamaDiagnostics};
+use crate::apis::retry::{with_retry_listener, RetryEvent, RetryListener};
+use crate::apis::streaming::{DeltaHandler, TokenUsage};
+use crate::app::api_keys::KeyStore;
 use crate::app::history::ConversationSummary;
-use crate::app::logger::{format_log_with_color, LogLevel};
+use crate::app::logger::{format_log_with_color, LogConfig, LogLevel, Logger, SessionLogger};
 use crate::app::memory::MemoryManager;
+use crate::app::presets::{PresetSelection, SamplingMode};
+use crate::app::queries::QueryRegistry;
+use crate::app::session_store::SessionStore;
+use crate::app::session_summary::SessionActivity;
+use crate::app::storage::StorageManager;
+use crate::app::summarizer::SummarizerConfig;
+use crate::app::turn_record::TurnRecord;
+use crate::app::workspace_lock::WorkspaceLock;
+use crate::communication::rpc::ClientScope;
+use crate::context::editor::EditorContext;
+use crate::context::file_mentions::expand_file_mentions;
+use crate::context::images::attach_images;
+use crate::context::project_instructions::ProjectInstructions;
 use crate::models;
 use crate::models::{ModelConfig, ANTHROPIC_MODEL_NAME, GEMINI_MODEL_NAME, OPENAI_MODEL_NAME};
+use crate::prompts::templates::{PromptLibrary, SystemPrompt};
+use crate::tools::rename::{AppliedRename, RenamePlan};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use tokio::runtime::Runtime;
 use uuid::Uuid;
@@ -23,7 +58,7 @@ pub enum AppState {
 }
 
 /// Status of a task
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TaskStatus {
     /// Task is in progress/ongoing
     InProgress,
@@ -39,7 +74,7 @@ pub enum TaskStatus {
 }
 
 /// Represents a task the assistant is working on
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Task {
     pub id: String,
     pub description: String,
@@ -49,6 +84,10 @@ pub struct Task {
     pub tool_count: u32,
     pub input_tokens: u32,
     pub output_tokens: u32,
+    /// Cost in USD at list prices, when the providers reported usage for models
+    /// with a known price
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
 }
 
 impl Task {
@@ -68,6 +107,7 @@ impl Task {
             tool_count: 0,
             input_tokens: 0,
             output_tokens: 0,
+            cost_usd: None,
         }
     }
 
@@ -120,10 +160,55 @@ impl Task {
             .as_secs();
     }
 
+    /// Replace the estimated tokens with the usage the providers reported
+    pub fn record_usage(&mut self, totals: &LedgerTotals) {
+        self.input_tokens = totals.total_input_tokens() as u32;
+        self.output_tokens = totals.output_tokens as u32;
+        self.cost_usd = (totals.requests > totals.unpriced_requests).then_some(totals.cost_usd);
+    }
+
     /// Check if this task is still in progress
     pub fn is_in_progress(&self) -> bool {
         matches!(self.status, TaskStatus::InProgress)
     }
+
+    /// Footer describing a completed turn, e.g. "answered in 34s, 3 tool calls, 12.4k tokens"
+    pub fn turn_summary(&self) -> Option<String> {
+        let TaskStatus::Completed {
+            duration_secs,
+            tool_uses,
+            input_tokens,
+            output_tokens,
+        } = self.status
+        else {
+            return None;
+        };
+
+        let duration = if duration_secs >= 60 {
+            format!("{}m {:02}s", duration_secs / 60, duration_secs % 60)
+        } else {
+            format!("{duration_secs}s")
+        };
+
+        let tool_calls = match tool_uses {
+            1 => "1 tool call".to_string(),
+            n => format!("{n} tool calls"),
+        };
+
+        let total_tokens = input_tokens as u64 + output_tokens as u64;
+        let tokens = if total_tokens >= 1000 {
+            format!("{:.1}k tokens", total_tokens as f64 / 1000.0)
+        } else {
+            format!("{total_tokens} tokens")
+        };
+
+        match self.cost_usd {
+            Some(cost) if cost > 0.0 => Some(format!(
+                "answered in {duration}, {tool_calls}, {tokens}, ${cost:.2}"
+            )),
+            _ => Some(format!("answered in {duration}, {tool_calls}, {tokens}")),
+        }
+    }
 }
 
 /// Tool execution status enum
@@ -209,11 +294,18 @@ impl ToolExecution {
 pub struct App {
     pub state: AppState,
     pub messages: Vec<String>,
-    pub logs: Vec<String>,
+    /// Session log file plus the capped in-memory log view
+    pub logger: SessionLogger,
     pub available_models: Vec<ModelConfig>,
     pub error_message: Option<String>,
     pub last_run_time: Instant,
     pub use_agent: bool,
+    /// Split agent tasks across parallel sub-agents
+    pub fan_out: Option<FanOutConfig>,
+    /// Send the answer of each run as `content_delta` notifications while it is generated
+    pub stream_responses: bool,
+    /// Images attached to the next prompt with /attach, sent as image input
+    pub attached_images: Vec<ImageData>,
     pub agent: Option<Agent>,
     pub tokio_runtime: Option<Runtime>,
     pub api_key: Option<String>,
@@ -227,6 +319,57 @@ pub struct App {
     pub memory_manager: MemoryManager,
     // Add tracking for tool executions
     pub tool_executions: HashMap<String, ToolExecution>,
+    // Session-level overrides for temperature, top_p and max_tokens
+    pub model_parameters: ModelParameters,
+    // Sampling presets chosen per mode, beneath the session-level overrides
+    pub sampling_presets: PresetSelection,
+    // Dedicated model for summaries; falls back to the main model when unset
+    pub summarizer: Option<SummarizerConfig>,
+    // Whether Ollama diagnostics were already attached to a failed local query
+    pub ollama_failure_diagnosed: bool,
+    // Whether a failed query to a cloud model has had its API key diagnosed this session
+    pub api_key_failure_diagnosed: bool,
+    // Numbered tool results of the last agent run, which its answer cites as [#N]
+    pub last_evidence: Vec<Evidence>,
+    // Tool calls and decisions of the last agent run, which /why explains
+    pub last_trail: Option<TaskTrail>,
+    // How the last run went, for `oli -p --output-format json`
+    pub last_turn: Option<TurnRecord>,
+    // Advisory lock on the workspace, shared with other oli instances in it and
+    // with the other sessions of a daemon
+    pub workspace_lock: Option<Arc<Mutex<WorkspaceLock>>>,
+    // Prompts, changed files and commands recorded for the summary written at exit
+    pub session_activity: SessionActivity,
+    // Rename previewed with /rename, waiting for approval
+    pub pending_rename: Option<RenamePlan>,
+    // Last applied rename, kept for /rename undo
+    pub last_rename: Option<AppliedRename>,
+    // Where the conversation is saved after each turn for /resume and /search
+    pub session_store: Option<SessionStore>,
+    // Id the conversation is saved under; changes on /resume and when history is cleared
+    pub saved_session_id: String,
+    // Whether the saved conversation is starred
+    pub session_starred: bool,
+    // Requests made by this session's runs, for token and cost totals per provider
+    pub session_usage: UsageMeter,
+    // Set from another thread to stop the running agent turn with a partial result
+    pub cancel_requested: Arc<AtomicBool>,
+    // Files as they were before each agent turn changed them, for /undo
+    pub checkpoints: Checkpoints,
+    // The project's OLI.md or AGENTS.md, added to the agent's system prompt
+    pub project_instructions: Option<ProjectInstructions>,
+    // Template chosen with /prompt in place of the system prompt overrides
+    pub prompt_template: Option<String>,
+    // What was done to make room in the context window before the latest run
+    pub context_management: Option<String>,
+    // Model of the latest run, whose context window the history is measured against
+    pub last_model_index: Option<usize>,
+    // The file, cursor and selection in the user's editor, added to each request.
+    // Shared so editors can update it while a run holds the app.
+    pub editor_context: Arc<Mutex<EditorContext>>,
+    // Query started with start_query that the current run belongs to, whose
+    // progress messages are recorded for get_query_progress
+    pub active_query: Option<(QueryRegistry, String)>,
 }
 
 impl App {
@@ -234,6 +377,9 @@ impl App {
     pub fn new() -> Self {
         // Load environment variables
         let _ = dotenv::dotenv();
+        if let Some(store) = KeyStore::from_env() {
+            store.load();
+        }
 
         // Create tokio runtime for async operations
         let tokio_runtime = Runtime::new().ok();
@@ -252,6 +398,14 @@ impl App {
         // Generate a unique session ID
         let session_id = Uuid::new_v4().to_string();
 
+        // Remove session logs past their retention period, or the oldest ones while
+        // storage is over quota, before starting a new one
+        let log_config = LogConfig::from_env();
+        if let Err(e) = StorageManager::from_env(&log_config).prune(Some(&session_id)) {
+            eprintln!("Failed to prune old session logs: {e}");
+        }
+        let logger = SessionLogger::new(&session_id, log_config);
+
         // Initialize memory manager with oli.md in the current directory
         let memory_manager = MemoryManager::new();
 
@@ -262,14 +416,22 @@ impl App {
             }
         }
 
+        // Read the project's instructions for agents, if it has any
+        let project_instructions = current_working_dir
+            .as_deref()
+            .and_then(|cwd| ProjectInstructions::load(Path::new(cwd)));
+
         Self {
             state: AppState::Setup,
             messages: vec![],
-            logs: vec![],
+            logger,
             available_models: models::get_available_models(),
             error_message: None,
             last_run_time: std::time::Instant::now(),
             use_agent: false,
+            fan_out: None,
+            stream_responses: false,
+            attached_images: Vec::new(),
             agent: None,
             tokio_runtime,
             api_key: None,
@@ -278,12 +440,57 @@ impl App {
             current_task_id: None,
             conversation_summaries: Vec::new(),
             session_manager,
-            session_id,
+            session_id: session_id.clone(),
             memory_manager,
             tool_executions: HashMap::new(),
+            model_parameters: ModelParameters::default(),
+            sampling_presets: PresetSelection::from_env(),
+            summarizer: SummarizerConfig::from_env(),
+            ollama_failure_diagnosed: false,
+            api_key_failure_diagnosed: false,
+            last_evidence: Vec::new(),
+            last_trail: None,
+            last_turn: None,
+            workspace_lock: None,
+            session_activity: SessionActivity::default(),
+            pending_rename: None,
+            last_rename: None,
+            session_store: None,
+            saved_session_id: session_id,
+            session_starred: false,
+            session_usage: UsageMeter::new(),
+            cancel_requested: Arc::new(AtomicBool::new(false)),
+            checkpoints: Checkpoints::new(),
+            project_instructions,
+            prompt_template: None,
+            context_management: None,
+            last_model_index: None,
+            editor_context: Arc::new(Mutex::new(EditorContext::default())),
+            active_query: None,
         }
     }
 
+    /// A fresh conversation set up like this one, e.g. for a client of a daemon:
+    /// the same models, model parameters, presets, summarizer, session store and
+    /// working directory, and the same workspace lock, so the tool policy of a
+    /// second instance applies to it too
+    pub fn new_session(&self) -> Self {
+        let mut session = Self::new();
+        session.available_models = self.available_models.clone();
+        session.model_parameters = self.model_parameters;
+        session.sampling_presets = self.sampling_presets.clone();
+        session.summarizer = self.summarizer.clone();
+        session.session_store = self.session_store.clone();
+        session.workspace_lock = self.workspace_lock.clone();
+        session.current_working_dir = self.current_working_dir.clone();
+        session.project_instructions = self.project_instructions.clone();
+        session.use_agent = self.use_agent;
+        session.fan_out = self.fan_out;
+        session.stream_responses = self.stream_responses;
+        session.prompt_template = self.prompt_template.clone();
+        session
+    }
+
     /// Get the current model configuration
     pub fn current_model(&self, index: usize) -> Result<&ModelConfig> {
         self.available_models
@@ -300,11 +507,18 @@ impl App {
     }
 
     /// Helper function to get an API key for a given model
-    fn get_api_key_for_model(&self, model_name: &str) -> String {
+    pub(crate) fn get_api_key_for_model(&self, model_name: &str) -> String {
         let model_name_lower = model_name.to_lowercase();
 
         self.api_key.clone().unwrap_or_else(|| {
-            if model_name_lower.contains("claude") {
+            if model_name_lower.contains("openrouter") {
+                std::env::var("OPENROUTER_API_KEY").unwrap_or_default()
+            } else if model_name_lower.contains("azure") {
+                std::env::var("AZURE_OPENAI_API_KEY").unwrap_or_default()
+            } else if model_name_lower.contains("compatible") {
+                // Local servers such as vLLM and LM Studio take no key
+                std::env::var("OPENAI_COMPATIBLE_API_KEY").unwrap_or_default()
+            } else if model_name_lower.contains("claude") {
                 std::env::var("ANTHROPIC_API_KEY").unwrap_or_default()
             } else if model_name_lower.contains("gpt") {
                 std::env::var("OPENAI_API_KEY").unwrap_or_default()
@@ -325,7 +539,13 @@ impl App {
 
     /// Helper function to determine API source based on model name
     pub fn get_api_source(model_name_lower: &str) -> &'static str {
-        if model_name_lower.contains("claude") {
+        if model_name_lower.contains("openrouter") {
+            "OpenRouter"
+        } else if model_name_lower.contains("azure") {
+            "Azure OpenAI"
+        } else if model_name_lower.contains("compatible") {
+            "OpenAI-compatible API"
+        } else if model_name_lower.contains("claude") {
             "Anthropic"
         } else if model_name_lower.contains("gpt") {
             "OpenAI"
@@ -341,8 +561,15 @@ impl App {
     /// Helper function to validate API key for a given model
     pub fn validate_api_key(model_name: &str, api_key: &str) -> Result<()> {
         let model_name_lower = model_name.to_lowercase();
-        if api_key.is_empty() && !model_name_lower.contains("local") {
-            let api_env_var = if model_name_lower.contains("claude") {
+        if api_key.is_empty()
+            && !model_name_lower.contains("local")
+            && !model_name_lower.contains("compatible")
+        {
+            let api_env_var = if model_name_lower.contains("openrouter") {
+                "OPENROUTER_API_KEY"
+            } else if model_name_lower.contains("azure") {
+                "AZURE_OPENAI_API_KEY"
+            } else if model_name_lower.contains("claude") {
                 "ANTHROPIC_API_KEY"
             } else if model_name_lower.contains("gpt") {
                 "OPENAI_API_KEY"
@@ -353,7 +580,7 @@ impl App {
             };
 
             return Err(anyhow::anyhow!(
-                "No API key available for {}. Please set {} environment variable.",
+                "No API key available for {}. Set the {} environment variable, or run /key <your key> to check and store one.",
                 model_name,
                 api_env_var
             ));
@@ -362,7 +589,7 @@ impl App {
     }
 
     /// Helper function to determine LLM provider and validate availability
-    fn determine_provider(
+    pub(crate) fn determine_provider(
         model_name: &str,
         api_key: &str,
         model_file_name: &str,
@@ -372,8 +599,25 @@ impl App {
         let model_name_lower = model_name.to_lowercase();
         let has_key = !api_key.is_empty();
 
-        // Determine the provider based on model name
+        // Determine the provider based on model name. OpenRouter model names also
+        // name their vendor, and Azure deployments their model, so they're matched first.
+        // OpenAI-compatible servers may take no key.
         let provider = match model_name_lower.as_str() {
+            name if name.contains("compatible") => Some(LLMProvider::OpenAICompatible),
+            name if name.contains("openrouter") => {
+                if has_key {
+                    Some(LLMProvider::OpenRouter)
+                } else {
+                    None
+                }
+            }
+            name if name.contains("azure") => {
+                if has_key {
+                    Some(LLMProvider::AzureOpenAI)
+                } else {
+                    None
+                }
+            }
             name if name.contains("claude") => {
                 if has_key {
                     Some(LLMProvider::Anthropic)
@@ -416,6 +660,14 @@ impl App {
 
         // Determine the agent model
         let agent_model = match model_name_lower.as_str() {
+            name if name.contains("compatible") => Some(model_file_name.to_string()),
+            name if name.contains("openrouter") || name.contains("azure") => {
+                if has_key {
+                    Some(model_file_name.to_string())
+                } else {
+                    None
+                }
+            }
             name if name.contains("claude") => {
                 if has_key {
                     Some(ANTHROPIC_MODEL_NAME.to_string())
@@ -453,7 +705,26 @@ impl App {
     ) -> Result<Box<dyn ApiClient>> {
         let model_name_lower = model_type.to_lowercase();
 
-        if model_name_lower.contains("claude") {
+        if model_name_lower.contains("openrouter") {
+            // Use OpenRouter for any model it serves, selected by its ID
+            let client = crate::apis::openrouter::OpenRouterClient::with_api_key(
+                api_key,
+                Some(model_file_name),
+            )?;
+            Ok(Box::new(client))
+        } else if model_name_lower.contains("azure") {
+            // Use the Azure OpenAI deployment named by the model's file name
+            let azure = crate::apis::openai::AzureDeployment::from_env(Some(model_file_name))?;
+            let client = crate::apis::openai::OpenAIClient::with_azure(api_key, azure)?;
+            Ok(Box::new(client))
+        } else if model_name_lower.contains("compatible") {
+            // Use the OpenAI-compatible API in OPENAI_COMPATIBLE_BASE_URL
+            let client = crate::apis::openai::OpenAIClient::compatible_from_env(
+                Some(model_file_name),
+                Some(api_key),
+            )?;
+            Ok(Box::new(client))
+        } else if model_name_lower.contains("claude") {
             // Use Anthropic API for Claude models
             let client = crate::apis::anthropic::AnthropicClient::with_api_key(
                 api_key,
@@ -479,9 +750,235 @@ impl App {
         }
     }
 
+    /// Check the Ollama server, and the selected model when `model_index` is a local model
+    pub fn diagnose_ollama(&self, model_index: Option<usize>) -> Result<OllamaDiagnostics> {
+        let runtime = self
+            .tokio_runtime
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Async runtime not available"))?;
+
+        let model = model_index
+            .and_then(|index| self.available_models.get(index))
+            .filter(|model| model.name.to_lowercase().contains("local"))
+            .map(|model| model.file_name.clone());
+
+        let client = OllamaClient::new(model)?;
+        Ok(runtime.block_on(client.diagnose()))
+    }
+
+    /// Diagnose the Ollama setup after a query to a local model failed. Only the
+    /// first failure in a session is diagnosed, to avoid repeating the same report.
+    pub fn explain_local_model_failure(&mut self, model_index: usize) -> Option<String> {
+        let is_local = self
+            .available_models
+            .get(model_index)
+            .is_some_and(|model| model.name.to_lowercase().contains("local"));
+        if !is_local || self.ollama_failure_diagnosed {
+            return None;
+        }
+        self.ollama_failure_diagnosed = true;
+
+        let diagnostics = self.diagnose_ollama(Some(model_index)).ok()?;
+        (!diagnostics.is_healthy()).then(|| diagnostics.report())
+    }
+
+    /// Helper function to create a batch API client based on model type
+    fn create_batch_client(
+        model_type: &str,
+        api_key: String,
+        model_file_name: String,
+    ) -> Result<Box<dyn BatchApi>> {
+        let model_name_lower = model_type.to_lowercase();
+
+        if model_name_lower.contains("openrouter")
+            || model_name_lower.contains("azure")
+            || model_name_lower.contains("compatible")
+        {
+            Err(anyhow::anyhow!(
+                "Batch processing is only supported for Anthropic and OpenAI models, not {}",
+                model_type
+            ))
+        } else if model_name_lower.contains("claude") {
+            let client = crate::apis::anthropic::AnthropicClient::with_api_key(
+                api_key,
+                Some(model_file_name),
+            )?;
+            Ok(Box::new(client))
+        } else if model_name_lower.contains("gpt") {
+            let client =
+                crate::apis::openai::OpenAIClient::with_api_key(api_key, Some(model_file_name))?;
+            Ok(Box::new(client))
+        } else {
+            Err(anyhow::anyhow!(
+                "Batch processing is only supported for Anthropic and OpenAI models, not {}",
+                model_type
+            ))
+        }
+    }
+
+    fn batch_client(&self, model_index: usize) -> Result<Box<dyn BatchApi>> {
+        let model = self.current_model(model_index)?;
+        let api_key = self.get_api_key_for_model(&model.name);
+        Self::validate_api_key(&model.name, &api_key)?;
+        Self::create_batch_client(&model.name, api_key, model.file_name.clone())
+    }
+
+    /// Submit prompts as a batch at half the usual price, for pipelines where
+    /// latency doesn't matter. Each prompt is a `(custom_id, prompt)` pair.
+    pub fn submit_batch(
+        &self,
+        model_index: usize,
+        prompts: Vec<(String, String)>,
+        system_prompt: Option<String>,
+    ) -> Result<String> {
+        let runtime = self
+            .tokio_runtime
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Async runtime not available"))?;
+        if prompts.is_empty() {
+            return Err(anyhow::anyhow!("A batch needs at least one prompt"));
+        }
+
+        let requests = prompts
+            .into_iter()
+            .map(|(custom_id, prompt)| {
+                let mut messages = Vec::new();
+                if let Some(system) = &system_prompt {
+                    messages.push(Message::system(system.clone()));
+                }
+                messages.push(Message::user(prompt));
+                BatchRequest::new(custom_id, messages).with_options(CompletionOptions {
+                    max_tokens: Some(4096),
+                    ..CompletionOptions::default()
+                })
+            })
+            .collect();
+
+        let client = self.batch_client(model_index)?;
+        runtime.block_on(client.submit_batch(requests))
+    }
+
+    /// Check on a submitted batch. With `wait`, poll until it completes. Results
+    /// are returned once the batch has completed.
+    pub fn batch_results(
+        &self,
+        model_index: usize,
+        batch_id: &str,
+        wait: bool,
+    ) -> Result<(BatchStatus, Option<Vec<BatchResult>>)> {
+        let runtime = self
+            .tokio_runtime
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Async runtime not available"))?;
+        let client = self.batch_client(model_index)?;
+
+        runtime.block_on(async {
+            if wait {
+                let results = BatchPoller::new().wait(client.as_ref(), batch_id).await?;
+                return Ok((BatchStatus::Completed, Some(results)));
+            }
+
+            match client.batch_status(batch_id).await? {
+                BatchStatus::Completed => Ok((
+                    BatchStatus::Completed,
+                    Some(client.batch_results(batch_id).await?),
+                )),
+                status => Ok((status, None)),
+            }
+        })
+    }
+
+    /// Find a model by index or by (part of) its name or id
+    pub fn find_model(&self, model: &str) -> Result<usize> {
+        if let Ok(index) = model.trim().parse::<usize>() {
+            return self.current_model(index).map(|_| index);
+        }
+
+        let model_lower = model.trim().to_lowercase();
+        self.available_models
+            .iter()
+            .position(|m| {
+                m.name.to_lowercase().contains(&model_lower)
+                    || m.file_name.to_lowercase() == model_lower
+            })
+            .ok_or_else(|| anyhow::anyhow!("Unknown model: {}", model))
+    }
+
+    /// Build an initialized agent for a model in the comparison, without the
+    /// session history
+    async fn comparison_agent(
+        model: &ModelConfig,
+        api_key: String,
+        parameters: ModelParameters,
+        working_dir: Option<String>,
+    ) -> Result<Agent> {
+        let (provider, agent_model) =
+            Self::determine_provider(&model.name, &api_key, &model.file_name)?;
+
+        let mut agent = Agent::new(provider)
+            .with_model(agent_model)
+            .with_model_parameters(parameters)
+            .with_result_limits(model.result_limits())
+            .with_time_limit(turn_time_limit_from_env());
+        if let Some(cwd) = working_dir {
+            agent = agent.with_working_directory(cwd);
+        }
+
+        agent.initialize_with_api_key(api_key).await?;
+        Ok(agent)
+    }
+
+    /// Send the same prompt to two models at once with read-only tools, to help
+    /// pick a default model. The session history is left untouched.
+    pub fn compare_models(
+        &self,
+        prompt: &str,
+        model_a: usize,
+        model_b: usize,
+    ) -> Result<ModelComparison> {
+        let runtime = self
+            .tokio_runtime
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Async runtime not available"))?;
+
+        let mut agents = Vec::new();
+        for index in [model_a, model_b] {
+            let model = self.current_model(index)?;
+            if !model.has_agent_support() {
+                return Err(anyhow::anyhow!(
+                    "{} doesn't support tool use and can't be compared",
+                    model.name
+                ));
+            }
+            let api_key = self.get_api_key_for_model(&model.name);
+            Self::validate_api_key(&model.name, &api_key)?;
+            // Each model gets the compare preset clamped to what it accepts
+            let (parameters, _) = self.session_parameters(SamplingMode::Compare, model, &api_key);
+            agents.push((model.clone(), api_key, parameters));
+        }
+
+        runtime.block_on(async {
+            let mut built = Vec::new();
+            for (model, api_key, parameters) in agents {
+                let agent = Self::comparison_agent(
+                    &model,
+                    api_key,
+                    parameters,
+                    self.current_working_dir.clone(),
+                )
+                .await?;
+                built.push((model.name, agent));
+            }
+
+            let b = built.pop().expect("two agents");
+            let a = built.pop().expect("two agents");
+            Ok(ModelComparison::run(prompt, a, b).await)
+        })
+    }
+
     /// Helper function to estimate token count from text
     pub fn estimate_tokens(text: &str) -> u32 {
-        (text.len() as f64 / 4.0).ceil() as u32
+        crate::apis::streaming::estimate_tokens(text) as u32
     }
 
     /// Handle progress messages from agent threads
@@ -716,39 +1213,107 @@ impl App {
             }
             "Glob" => "Finding files by pattern".to_string(),
             "Grep" => "Searching code for pattern".to_string(),
+            "FindFile" => "Finding files by name".to_string(),
+            "DependencyGraph" => "Mapping imports and dependencies".to_string(),
+            "GitStatus" => "Checking repository status".to_string(),
+            "GitDiff" => "Reading changes".to_string(),
+            "GitLog" => "Reading commit history".to_string(),
             "LS" => "Listing directory contents".to_string(),
             "Edit" => "Modifying file".to_string(),
+            "NotebookRead" => "Reading notebook".to_string(),
+            "NotebookEditCell" => "Editing notebook cell".to_string(),
             "Replace" => "Replacing file contents".to_string(),
             "Bash" => "Executing command".to_string(),
+            "RunTests" => "Running tests".to_string(),
+            "Agent" => "Delegating to a sub-agent".to_string(),
             _ => "Executing tool".to_string(),
         }
     }
 
+    /// A sender whose text is sent to the UI as `content_delta` notifications for
+    /// the task, in order
+    fn forward_deltas(
+        runtime: &Runtime,
+        task_id: &str,
+    ) -> tokio::sync::mpsc::UnboundedSender<String> {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<String>();
+        let task_id = task_id.to_string();
+        runtime.spawn(ClientScope::current().scope(async move {
+            while let Some(delta) = receiver.recv().await {
+                if let Some(rpc_server) = crate::communication::rpc::get_global_rpc_server() {
+                    let _ = rpc_server.send_notification(
+                        "content_delta",
+                        serde_json::json!({ "task_id": task_id, "delta": delta }),
+                    );
+                }
+            }
+        }));
+        sender
+    }
+
+    /// A meter for the requests of a task that reports each one to the UI as a
+    /// `token_usage` notification while the task runs
+    fn usage_meter(task_id: &str) -> UsageMeter {
+        let task_id = task_id.to_string();
+        UsageMeter::new().with_listener(move |record, totals| {
+            if let Some(rpc_server) = crate::communication::rpc::get_global_rpc_server() {
+                let _ = rpc_server.send_notification(
+                    "token_usage",
+                    serde_json::json!({
+                        "task_id": task_id,
+                        "request": record,
+                        "task_totals": totals,
+                    }),
+                );
+            }
+        })
+    }
+
     /// Set up a progress tracking thread for UI notifications
-    fn setup_progress_tracking(task_id: String) -> std::sync::mpsc::Sender<String> {
-        let (progress_tx, progress_rx) = std::sync::mpsc::channel();
+    fn setup_progress_tracking(
+        task_id: String,
+        active_query: Option<(QueryRegistry, String)>,
+    ) -> std::sync::mpsc::Sender<String> {
+        let (progress_tx, progress_rx) = std::sync::mpsc::channel::<String>();
         let task_id_clone = task_id.clone();
 
+        let client = ClientScope::current();
         std::thread::spawn(move || {
-            while let Ok(message) = progress_rx.recv() {
-                // Emit progress events for the UI to pick up
-                if let Some(rpc_server) = crate::communication::rpc::get_global_rpc_server() {
-                    let _ = rpc_server.event_sender().send((
-                        "processing_progress".to_string(),
-                        serde_json::json!({
-                            "task_id": task_id_clone,
-                            "message": message
-                        }),
-                    ));
+            client.enter(|| {
+                while let Ok(message) = progress_rx.recv() {
+                    // Keep the message for clients polling a query started with start_query
+                    if let Some((queries, query_id)) = &active_query {
+                        queries.record_progress(query_id, &message);
+                    }
+
+                    // Emit progress events for the UI to pick up
+                    if let Some(rpc_server) = crate::communication::rpc::get_global_rpc_server() {
+                        // Written to stdout right away, while the request is still running
+                        let _ = rpc_server.send_notification(
+                            "agent_progress",
+                            serde_json::json!({
+                                "task_id": task_id_clone,
+                                "message": message
+                            }),
+                        );
+                        let _ = rpc_server.event_sender().send((
+                            "processing_progress".to_string(),
+                            serde_json::json!({
+                                "task_id": task_id_clone,
+                                "message": message
+                            }),
+                        ));
+                    }
                 }
-            }
+            })
         });
 
         progress_tx
     }
 
-    /// Process model response and update app state
-    fn process_model_response(&mut self, response: String) -> String {
+    /// Process model response and update app state, completing the task with the
+    /// usage recorded by `meter`, or estimated when providers reported none
+    fn process_model_response(&mut self, response: String, meter: &UsageMeter) -> String {
         // Add the assistant response to the session
         if let Some(session) = &mut self.session_manager {
             session.add_assistant_message(response.clone());
@@ -757,25 +1322,133 @@ impl App {
         // Add the response to the message history
         self.messages.push(format!("[assistant] {response}"));
 
-        // Complete the task with estimated tokens
-        let estimated_tokens = Self::estimate_tokens(&response);
-        self.complete_current_task(estimated_tokens);
+        // Complete the task with the reported tokens, or estimated ones
+        let totals = meter.totals();
+        let output_tokens = if totals.requests > 0 {
+            if let Some(task) = self.current_task_mut() {
+                task.record_usage(&totals);
+            }
+            totals.output_tokens as u32
+        } else {
+            Self::estimate_tokens(&response)
+        };
+        self.complete_current_task(output_tokens);
+
+        // Save after completing the task so it isn't saved as still running
+        if let Err(e) = self.save_session() {
+            self.log(&format!("Failed to save the session: {e}"));
+        }
 
         eprintln!(
             "{}",
             format_log_with_color(
                 LogLevel::Info,
-                &format!("Run completed, received approximately {estimated_tokens} tokens")
+                &format!("Run completed, received {output_tokens} tokens")
             )
         );
 
         response
     }
 
+    /// Set a session-level model parameter, e.g. from `/set temperature 0.7`
+    pub fn set_model_parameter(&mut self, name: &str, value: &str) -> Result<ModelParameters> {
+        self.model_parameters.set(name, value)?;
+        Ok(self.model_parameters)
+    }
+
+    /// Choose the sampling preset for a mode, or for every mode without `mode`,
+    /// e.g. from `/preset agent precise`. The preset `default` clears it.
+    pub fn set_sampling_preset(
+        &mut self,
+        mode: Option<&str>,
+        preset: &str,
+    ) -> Result<&PresetSelection> {
+        let mode = mode.map(SamplingMode::parse).transpose()?;
+        let preset = if preset.trim().eq_ignore_ascii_case("default") {
+            None
+        } else {
+            Some(SamplingPreset::parse(preset)?)
+        };
+        self.sampling_presets.set(mode, preset);
+        Ok(&self.sampling_presets)
+    }
+
+    /// System prompt overrides and templates for the working directory
+    pub fn prompt_library(&self) -> PromptLibrary {
+        PromptLibrary::for_working_dir(&self.working_dir())
+    }
+
+    /// The system prompt in use: the chosen template, the project's or the user's
+    /// override, or the default
+    pub fn system_prompt(&self) -> Result<SystemPrompt> {
+        self.prompt_library()
+            .resolve(self.prompt_template.as_deref())
+    }
+
+    /// Use the template `name` as the system prompt, or go back to the overrides
+    /// and the default with None. Returns the prompt now in use.
+    pub fn set_prompt_template(&mut self, name: Option<&str>) -> Result<SystemPrompt> {
+        let name = name.map(str::trim).filter(|name| !name.is_empty());
+        let prompt = self.prompt_library().resolve(name)?;
+        self.prompt_template = name.map(str::to_string);
+        self.apply_system_prompt(&prompt);
+        Ok(prompt)
+    }
+
+    /// Read the system prompt again, so edits to its files apply to the next run
+    fn refresh_system_prompt(&mut self) -> Result<()> {
+        let prompt = self.system_prompt()?;
+        self.apply_system_prompt(&prompt);
+        Ok(())
+    }
+
+    // Make the prompt, with the working directory section, the session's system message
+    fn apply_system_prompt(&mut self, prompt: &SystemPrompt) {
+        let content = prompt.with_working_directory(&self.working_dir().to_string_lossy());
+        if let Some(session) = &mut self.session_manager {
+            session.system_message = Some(Message::system(content));
+        }
+    }
+
+    /// Sampling parameters for a mode on a model: the mode's preset clamped to the
+    /// model, under the session-level overrides. Also returns the model's provider,
+    /// if known, to validate further overrides against.
+    pub(crate) fn session_parameters(
+        &self,
+        mode: SamplingMode,
+        model: &ModelConfig,
+        api_key: &str,
+    ) -> (ModelParameters, Option<crate::agent::core::LLMProvider>) {
+        let (provider, provider_model) =
+            match Self::determine_provider(&model.name, api_key, &model.file_name) {
+                Ok((provider, provider_model)) => (Some(provider), provider_model),
+                Err(_) => (None, model.file_name.clone()),
+            };
+        let preset = self
+            .sampling_presets
+            .parameters_for(mode, provider.as_ref(), &provider_model);
+        (preset.merge(&self.model_parameters), provider)
+    }
+
     /// Run the model with the given prompt
     pub fn run(&mut self, prompt: &str, model_index: Option<usize>) -> Result<String> {
+        self.run_with_parameters(prompt, model_index, ModelParameters::default())
+    }
+
+    /// Run the model with the given prompt, applying per-request parameter overrides
+    /// on top of the session-level parameters
+    pub fn run_with_parameters(
+        &mut self,
+        prompt: &str,
+        model_index: Option<usize>,
+        overrides: ModelParameters,
+    ) -> Result<String> {
+        self.refresh_system_prompt()?;
+
         // Create a task for this run
         let task_id = self.create_task(prompt);
+        self.cancel_requested.store(false, Ordering::SeqCst);
+        self.checkpoints.begin_turn(prompt);
 
         // Log processing message
         eprintln!(
@@ -785,6 +1458,9 @@ impl App {
 
         // Update run time and add to message history
         self.last_run_time = Instant::now();
+        self.last_evidence.clear();
+        self.last_trail = None;
+        self.session_activity.record_prompt(prompt);
         self.messages.push(format!("[user] {prompt}"));
 
         // Check for runtime
@@ -812,6 +1488,8 @@ impl App {
         let model_file_name = model.file_name.clone();
         let supports_agent = model.has_agent_support();
         let model_name_lower = model_name.to_lowercase();
+        // Tool results are limited to a share of this model's own context window
+        let result_limits = model.result_limits();
 
         // Log model info
         eprintln!(
@@ -823,6 +1501,17 @@ impl App {
         let api_key = self.get_api_key_for_model(&model_name);
         Self::validate_api_key(&model_name, &api_key)?;
 
+        // Resolve the mode's preset and parameter overrides, and validate them
+        // against the provider's limits
+        let mode = if supports_agent && self.use_agent {
+            SamplingMode::Agent
+        } else {
+            SamplingMode::Chat
+        };
+        let (parameters, provider) = self.session_parameters(mode, model, &api_key);
+        let parameters = parameters.merge(&overrides);
+        parameters.validate(provider.as_ref())?;
+
         // Log API key source (without exposing the key)
         let api_source = Self::get_api_source(&model_name_lower);
         eprintln!(
@@ -838,6 +1527,14 @@ impl App {
             return Err(anyhow::anyhow!("Session manager not available"));
         }
 
+        // Attach the files mentioned with @path, so the model sees their contents
+        let prompt = &expand_file_mentions(prompt, &self.working_dir());
+        let prompt = &attach_images(prompt, &std::mem::take(&mut self.attached_images));
+
+        // Compact the history first if the prompt would fill too much of the context window
+        self.last_model_index = Some(model_index);
+        self.context_management = self.manage_context(model_index, prompt);
+
         // Add user message to session
         if let Some(session) = &mut self.session_manager {
             session.add_user_message(prompt.to_string());
@@ -849,11 +1546,21 @@ impl App {
             None => return Err(anyhow::anyhow!("Session manager not available")),
         };
 
+        // Track the estimated prompt size on the task
+        let input_tokens = messages
+            .iter()
+            .map(|m| Self::estimate_tokens(&m.content))
+            .sum();
+        self.add_input_tokens(input_tokens);
+
         // Check model type and log warning if needed
         let unrecognized = !model_name_lower.contains("claude")
             && !model_name_lower.contains("gpt")
             && !model_name_lower.contains("local")
-            && !model_name_lower.contains("gemini");
+            && !model_name_lower.contains("gemini")
+            && !model_name_lower.contains("openrouter")
+            && !model_name_lower.contains("azure")
+            && !model_name_lower.contains("compatible");
 
         if unrecognized {
             eprintln!(
@@ -866,15 +1573,16 @@ impl App {
         }
 
         // Set up standard completion options
-        let options = crate::apis::api_client::CompletionOptions {
+        let mut options = crate::apis::api_client::CompletionOptions {
             temperature: Some(0.7),
             top_p: Some(0.9),
             max_tokens: Some(2048),
             ..Default::default()
         };
+        parameters.apply_to(&mut options);
 
         // Set up progress tracking
-        let progress_tx = Self::setup_progress_tracking(task_id.clone());
+        let progress_tx = Self::setup_progress_tracking(task_id.clone(), self.active_query.clone());
         let runtime = self.tokio_runtime.as_ref().unwrap();
 
         // Run with agent if supported and enabled
@@ -885,7 +1593,28 @@ impl App {
 
             // Create and configure the agent
             let mut agent = crate::agent::core::Agent::new(provider);
-            agent = agent.with_model(agent_model);
+            agent = agent
+                .with_model(agent_model)
+                .with_model_parameters(parameters)
+                .with_result_limits(result_limits)
+                .with_approval_backend(ApprovalBackend::from_env())
+                .with_audit_log(AuditLog::from_env())
+                .with_task_id(task_id.clone())
+                .with_time_limit(turn_time_limit_from_env())
+                .with_cancel_flag(self.cancel_requested.clone())
+                .with_checkpoints(self.checkpoints.clone())
+                .with_project_instructions(self.project_instructions.clone());
+
+            // Tell the model what the user is looking at in their editor
+            let editor_context = self.editor_context.lock().unwrap().clone();
+            if !editor_context.is_empty() {
+                agent = agent.with_context_provider(Arc::new(editor_context));
+            }
+
+            // Deny modifying tools while another instance holds the workspace, if enforced
+            if let Some(lock) = &self.workspace_lock {
+                agent = agent.with_tool_policy(lock.lock().unwrap().tool_policy());
+            }
 
             // Pass current working directory to the agent
             if let Some(cwd) = &self.current_working_dir {
@@ -897,15 +1626,37 @@ impl App {
                 tokio::sync::mpsc::channel::<String>(100);
             agent = agent.with_progress_sender(progress_tx_sender);
 
+            // Forward generated text to the UI as content_delta notifications
+            if self.stream_responses {
+                agent = agent.with_delta_sender(Some(Self::forward_deltas(runtime, &task_id)));
+            }
+
+            // Forward the tool queue of each iteration to the UI as tool_queue notifications
+            let (queue_sender, mut queue_receiver) =
+                tokio::sync::mpsc::channel::<crate::agent::tool_queue::ToolQueue>(100);
+            agent = agent.with_tool_queue_sender(queue_sender);
+            runtime.spawn(ClientScope::current().scope(async move {
+                while let Some(queue) = queue_receiver.recv().await {
+                    if let Some(rpc_server) = crate::communication::rpc::get_global_rpc_server() {
+                        let _ =
+                            rpc_server.send_notification("tool_queue", serde_json::json!(queue));
+                    }
+                }
+            }));
+
             // Clone values needed for the progress tracking thread
             let progress_tx_clone = progress_tx.clone();
             let task_id_clone = task_id.clone();
 
-            // Spawn a thread to handle agent progress messages
-            std::thread::spawn(move || {
+            // Spawn a thread to handle agent progress messages, counting executed tools
+            let progress_thread = std::thread::spawn(move || {
                 let rt = tokio::runtime::Runtime::new().unwrap();
                 rt.block_on(async {
+                    let mut tool_count = 0;
                     while let Some(message) = progress_rx_receiver.recv().await {
+                        if message == "[TOOL_EXECUTED]" {
+                            tool_count += 1;
+                        }
                         Self::handle_agent_progress(
                             message,
                             task_id_clone.clone(),
@@ -913,7 +1664,8 @@ impl App {
                         )
                         .await;
                     }
-                });
+                    tool_count
+                })
             });
 
             // Initialize the agent
@@ -937,16 +1689,33 @@ impl App {
                 );
             }
 
-            // Execute the agent with the prompt
-            let response = runtime.block_on(async { agent.execute(prompt).await })?;
-
-            // Set a default tool count
+            // Execute the agent with the prompt, fanning out to sub-agents if enabled,
+            // recording the usage its requests report
+            let meter = Self::usage_meter(&task_id);
+            let result = match self.fan_out {
+                Some(config) => runtime
+                    .block_on(meter.scope(agent.execute_fan_out(prompt, config)))
+                    .map(|fan_out| fan_out.response),
+                None => runtime.block_on(meter.scope(agent.execute(prompt))),
+            };
+            self.session_usage.extend(&meter);
+            let response = result?;
+
+            // Dropping the agent closes the progress channel, so the thread can report
+            // how many tools were executed during this turn
+            self.last_evidence = agent.last_evidence();
+            self.session_activity.record_tools(&self.last_evidence);
+            let rounds = agent.last_trail();
+            drop(agent);
+            let tool_count = progress_thread.join().unwrap_or(0);
             if let Some(task) = self.current_task_mut() {
-                task.tool_count = 1;
+                task.tool_count = tool_count;
             }
 
-            // Process response and return
-            Ok(self.process_model_response(response))
+            // Process response, keeping the run's trail for /why, and return
+            let answer = self.process_model_response(response, &meter);
+            self.last_trail = Some(TaskTrail::new(&task_id, prompt, rounds, &answer));
+            Ok(answer)
         } else {
             // Create API client based on model type
             let client_future =
@@ -960,15 +1729,105 @@ impl App {
             };
             let _ = progress_tx.send(format!("Sending request to {model_display}"));
 
-            // Execute the API call and get response
+            // Execute the API call and get response, recording the usage it reports and
+            // showing its retries as progress
             let client = runtime.block_on(client_future)?;
-            let response = runtime.block_on(async { client.complete(messages, options).await })?;
+            let meter = Self::usage_meter(&task_id);
+            let retry_tx = progress_tx.clone();
+            let retry_progress: RetryListener = Arc::new(move |event: &RetryEvent| {
+                let _ = retry_tx.send(event.message());
+            });
+            let result = if self.stream_responses {
+                let sender = Self::forward_deltas(runtime, &task_id);
+                let mut on_delta = move |text: &str| {
+                    let _ = sender.send(text.to_string());
+                };
+                runtime
+                    .block_on(with_retry_listener(
+                        retry_progress,
+                        meter.scope(client.complete_streaming(messages, options, &mut on_delta)),
+                    ))
+                    .map(|(response, _)| response)
+            } else {
+                runtime.block_on(with_retry_listener(
+                    retry_progress,
+                    meter.scope(client.complete(messages, options)),
+                ))
+            };
+            self.session_usage.extend(&meter);
+            let response = result?;
 
             // Process response and return
-            Ok(self.process_model_response(response))
+            Ok(self.process_model_response(response, &meter))
         }
     }
 
+    /// Send the prompt and session history to the model without tools, passing
+    /// text to `on_delta` as it is generated
+    pub fn query_model(
+        &mut self,
+        prompt: &str,
+        model_index: Option<usize>,
+        overrides: ModelParameters,
+        on_delta: DeltaHandler<'_>,
+    ) -> Result<(String, TokenUsage)> {
+        self.refresh_system_prompt()?;
+        let runtime = self
+            .tokio_runtime
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Async runtime not available"))?;
+
+        let model = self.current_model(model_index.unwrap_or(0))?.clone();
+        let api_key = self.get_api_key_for_model(&model.name);
+        Self::validate_api_key(&model.name, &api_key)?;
+
+        let (parameters, provider) = self.session_parameters(SamplingMode::Chat, &model, &api_key);
+        let parameters = parameters.merge(&overrides);
+        parameters.validate(provider.as_ref())?;
+
+        let mut options = CompletionOptions {
+            temperature: Some(0.7),
+            top_p: Some(0.9),
+            max_tokens: Some(2048),
+            ..Default::default()
+        };
+        parameters.apply_to(&mut options);
+
+        let client = runtime.block_on(Self::create_api_client(
+            &model.name,
+            api_key,
+            model.file_name.clone(),
+        ))?;
+
+        let task_id = self.create_task(prompt);
+        self.last_run_time = Instant::now();
+        self.messages.push(format!("[user] {prompt}"));
+        let prompt = expand_file_mentions(prompt, &self.working_dir());
+        let session = self
+            .session_manager
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Session manager not available"))?;
+        session.add_user_message(prompt);
+        let messages = session.get_messages_for_api();
+
+        let runtime = self.tokio_runtime.as_ref().unwrap();
+        let meter = Self::usage_meter(&task_id);
+        let result =
+            runtime.block_on(meter.scope(client.complete_streaming(messages, options, on_delta)));
+        self.session_usage.extend(&meter);
+        let (response, usage) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                self.fail_current_task(&e.to_string());
+                return Err(e);
+            }
+        };
+
+        self.add_input_tokens(usage.input_tokens);
+        let response = self.process_model_response(response, &meter);
+        Ok((response, usage))
+    }
+
     /// Check if there are any active tasks
     pub fn has_active_tasks(&self) -> bool {
         self.tasks.iter().any(|task| task.is_in_progress())
@@ -992,12 +1851,20 @@ impl App {
                     "tool_count": task.tool_count,
                     "input_tokens": task.input_tokens,
                     "output_tokens": task.output_tokens,
+                    "cost_usd": task.cost_usd,
                     "created_at": task.created_at,
                 })
             })
             .collect()
     }
 
+    /// Cost in USD of this session's requests at list prices, or None until a
+    /// request to a model with a known price was made
+    pub fn session_cost_usd(&self) -> Option<f64> {
+        let totals = self.session_usage.totals();
+        (totals.requests > totals.unpriced_requests).then_some(totals.cost_usd)
+    }
+
     /// Create a new task and set it as current
     pub fn create_task(&mut self, description: &str) -> String {
         let task = Task::new(description);
@@ -1216,13 +2083,9 @@ impl App {
         }
     }
 
-    /// Add a log message (now deprecated in favor of direct eprintln calls)
-    #[deprecated(
-        since = "0.2.0",
-        note = "Use eprintln with format_log_with_color instead"
-    )]
-    pub fn log(&mut self, _message: &str) {
-        // This function is kept for backward compatibility but should not be used
+    /// Add a message to the session log and the in-memory log view
+    pub fn log(&mut self, message: &str) {
+        self.logger.log(message, &[]);
     }
 }
 
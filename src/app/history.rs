@@ -1,16 +1,27 @@
-use crate::agent::core::Agent;
+use crate::agent::executor::restore_dedup_references;
 use crate::apis::api_client::Message;
+use crate::apis::tokenizer::{count_message_tokens, count_tokens};
 use crate::app::core::{App, AppState};
+use crate::app::summarizer::SummarizerConfig;
 use crate::prompts::CONVERSATION_SUMMARY_PROMPT;
 use anyhow::Result;
+use serde::Serialize;
 use std::time::Instant;
 
-/// Message content threshold before considering summarization (in chars)
-const DEFAULT_SUMMARIZATION_CHAR_THRESHOLD: usize = 1000000;
-/// Message count threshold before considering summarization
-const DEFAULT_SUMMARIZATION_COUNT_THRESHOLD: usize = 1000;
+/// Environment variable selecting the model `/compact` summarizes with, e.g.
+/// `anthropic:claude-3-5-haiku-latest` or `ollama:llama3.2`. The summarizer model,
+/// then the main model, is used when it's unset.
+pub const COMPACT_MODEL_ENV: &str = "OLI_COMPACT_MODEL";
+
+/// Environment variable with how full the model's context window may get, in
+/// percent, before the history is compacted ahead of a run. 0 turns it off.
+pub const AUTO_COMPACT_ENV: &str = "OLI_AUTO_COMPACT_PERCENT";
+pub const DEFAULT_AUTO_COMPACT_PERCENT: u32 = 80;
+
 /// Maximum number of messages to keep unsummarized (recent history)
 const DEFAULT_KEEP_RECENT_COUNT: usize = 20;
+/// Latest session messages `/compact` keeps as they are
+const COMPACT_KEEP_RECENT_COUNT: usize = 4;
 
 #[derive(Clone)]
 /// Represents a conversation summary
@@ -36,12 +47,77 @@ impl ConversationSummary {
     }
 }
 
+/// What `/compact` did, with the estimated size of the history sent with each
+/// request before and after
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CompactReport {
+    /// Number of session messages replaced by the summary
+    pub messages_summarized: usize,
+    pub tokens_before: u32,
+    pub tokens_after: u32,
+    /// `provider:model` spec of the model that wrote the summary, or None for the
+    /// main model
+    pub model: Option<String>,
+}
+
+/// How much of the model's context window the history sent with each request
+/// takes, e.g. for "context 74% full" in the status bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ContextUsage {
+    pub used_tokens: u32,
+    pub window_tokens: u32,
+    pub percent: u32,
+}
+
+impl ContextUsage {
+    pub fn new(used_tokens: u32, window_tokens: u32) -> Self {
+        let percent = (u64::from(used_tokens) * 100 / u64::from(window_tokens.max(1))) as u32;
+        Self {
+            used_tokens,
+            window_tokens,
+            percent,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        format!("context {}% full", self.percent)
+    }
+}
+
+/// How full the context window may get before the history is compacted:
+/// `OLI_AUTO_COMPACT_PERCENT`, or 80%. None when automatic compaction is off.
+pub fn auto_compact_percent() -> Option<u32> {
+    let percent = std::env::var(AUTO_COMPACT_ENV)
+        .ok()
+        .and_then(|value| value.trim().parse::<u32>().ok())
+        .unwrap_or(DEFAULT_AUTO_COMPACT_PERCENT);
+    (percent > 0).then_some(percent.min(100))
+}
+
+impl CompactReport {
+    /// One line for the chat
+    pub fn message(&self) -> String {
+        format!(
+            "Compacted {} messages with {}: ~{} → ~{} tokens",
+            self.messages_summarized,
+            self.model.as_deref().unwrap_or("the main model"),
+            self.tokens_before,
+            self.tokens_after
+        )
+    }
+}
+
 /// Context compression management trait for the application
 pub trait ContextCompressor {
     /// Generate a summary of the conversation history
     fn compress_context(&mut self) -> Result<()>;
 
-    /// Check if conversation should be summarized based on thresholds
+    /// Replace all but the latest session messages with a summary written by the
+    /// compaction model, focusing on `instructions` when given
+    fn compact(&mut self, instructions: Option<&str>) -> Result<CompactReport>;
+
+    /// Check if the history fills more of the last run's model's context window
+    /// than `OLI_AUTO_COMPACT_PERCENT` allows
     fn should_compress(&self) -> bool;
 
     /// Get the total character count of conversation history
@@ -60,6 +136,144 @@ pub trait ContextCompressor {
     fn session_to_display_messages(&self, session_messages: &[Message]) -> Vec<String>;
 }
 
+impl App {
+    /// Messages of the session the next request is built from, without the system message
+    pub fn history(&self) -> &[Message] {
+        self.session_manager
+            .as_ref()
+            .map(|session| session.messages.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Replace the content of a session message, e.g. to redact a pasted secret
+    pub fn edit_history_message(&mut self, index: usize, content: String) -> Result<()> {
+        self.session_manager
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Session manager not available"))?
+            .edit_message(index, content)?;
+        self.sync_agent_history();
+        Ok(())
+    }
+
+    /// Remove a session message so later requests no longer include it
+    pub fn delete_history_message(&mut self, index: usize) -> Result<Message> {
+        let removed = self
+            .session_manager
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Session manager not available"))?
+            .delete_message(index)?;
+        self.sync_agent_history();
+        Ok(removed)
+    }
+
+    /// The model `/compact` summarizes with: `OLI_COMPACT_MODEL`, then the
+    /// summarizer model; `None` for the main model
+    pub fn compact_model(&self) -> Result<Option<SummarizerConfig>> {
+        match std::env::var(COMPACT_MODEL_ENV) {
+            Ok(spec) if !spec.trim().is_empty() => SummarizerConfig::parse(&spec)
+                .map(Some)
+                .map_err(|e| anyhow::anyhow!("Invalid {COMPACT_MODEL_ENV}: {e}")),
+            _ => Ok(self.summarizer.clone()),
+        }
+    }
+
+    /// Tokens of the history sent with each request, system message included
+    pub fn history_tokens(&self) -> u32 {
+        self.session_manager
+            .as_ref()
+            .map(|session| count_message_tokens(&session.get_messages_for_api()) as u32)
+            .unwrap_or(0)
+    }
+
+    /// How full the context window of the last run's model is, or None before
+    /// the first run
+    pub fn context_usage(&self) -> Option<ContextUsage> {
+        let model = self.available_models.get(self.last_model_index?)?;
+        Some(ContextUsage::new(
+            self.history_tokens(),
+            model.context_limit(),
+        ))
+    }
+
+    /// Make room for `prompt` in the context window of the model at
+    /// `model_index` when the history and the prompt together would fill more of
+    /// it than `OLI_AUTO_COMPACT_PERCENT` allows. The history is compacted; when
+    /// that fails or isn't enough, its oldest messages are dropped. Returns what
+    /// was done, if anything.
+    pub fn manage_context(&mut self, model_index: usize, prompt: &str) -> Option<String> {
+        let percent = auto_compact_percent()?;
+        let window = self.available_models.get(model_index)?.context_limit();
+        let budget = (u64::from(window) * u64::from(percent) / 100) as u32;
+        let prompt_tokens = count_tokens(prompt) as u32;
+        let before = ContextUsage::new(self.history_tokens() + prompt_tokens, window);
+        if before.used_tokens <= budget {
+            return None;
+        }
+
+        let mut done = vec![format!("The context window was {}% full", before.percent)];
+        match self.compact(None) {
+            Ok(report) => done.push(report.message()),
+            Err(e) => self.log(&format!("Failed to compact the history: {e}")),
+        }
+
+        // Compaction keeps the latest messages whole, which can still be too much
+        let history_budget = budget.saturating_sub(prompt_tokens);
+        if self.history_tokens() > history_budget {
+            let dropped = self.drop_oldest_messages(history_budget);
+            if dropped > 0 {
+                done.push(format!("Dropped the {dropped} oldest messages"));
+            }
+        }
+
+        let message = done.join("; ");
+        self.messages.push(format!("[info] {message}"));
+        Some(message)
+    }
+
+    /// Drop the oldest session messages until the history takes at most
+    /// `max_tokens`, keeping the latest few and starting the history on a
+    /// prompt. Returns how many were dropped.
+    pub fn drop_oldest_messages(&mut self, max_tokens: u32) -> usize {
+        let Some(session) = &mut self.session_manager else {
+            return 0;
+        };
+
+        let mut dropped = 0;
+        while session.messages.len() > COMPACT_KEEP_RECENT_COUNT
+            && count_message_tokens(&session.get_messages_for_api()) as u32 > max_tokens
+        {
+            let mut removed: Vec<Message> = session.delete_message(0).into_iter().collect();
+            // Answers to a dropped prompt go with it
+            while session.messages.len() > COMPACT_KEEP_RECENT_COUNT
+                && session.messages[0].role != "user"
+            {
+                removed.extend(session.delete_message(0));
+            }
+            dropped += removed.len();
+            restore_dedup_references(&mut session.messages, &removed);
+        }
+
+        if dropped > 0 {
+            self.sync_agent_history();
+        }
+        dropped
+    }
+
+    // Rebuild the agent's conversation from the session after it was edited
+    pub(crate) fn sync_agent_history(&mut self) {
+        let messages = match &self.session_manager {
+            Some(session) => session.get_messages_for_api(),
+            None => return,
+        };
+        if let Some(agent) = &mut self.agent {
+            agent.clear_history();
+            for message in messages {
+                agent.add_message(message);
+            }
+        }
+    }
+}
+
 impl ContextCompressor for App {
     fn compress_context(&mut self) -> Result<()> {
         // Don't summarize if no messages
@@ -67,11 +281,10 @@ impl ContextCompressor for App {
             return Ok(());
         }
 
-        // Check if we have an agent for summarization
-        let agent = match &self.agent {
-            Some(agent) => agent.clone(),
-            None => return Err(anyhow::anyhow!("No agent available for summarization")),
-        };
+        // Check if we have a summarizer model or an agent to fall back to
+        if self.summarizer.is_none() && self.agent.is_none() {
+            return Err(anyhow::anyhow!("No agent available for summarization"));
+        }
 
         // Keep the most recent messages unsummarized
         let keep_recent = DEFAULT_KEEP_RECENT_COUNT.min(self.messages.len());
@@ -90,8 +303,9 @@ impl ContextCompressor for App {
         self.messages
             .push("[wait] ⚪ Summarizing conversation history...".into());
 
-        // Generate the summary using the agent
-        let summary = self.generate_summary_with_agent(&agent, &messages_to_summarize)?;
+        // Generate the summary, preferring the dedicated summarizer model
+        let prompt = format!("{CONVERSATION_SUMMARY_PROMPT}{messages_to_summarize}");
+        let summary = self.complete_with_summarizer(&prompt)?;
 
         // Create a new summary record
         let summary_record =
@@ -137,25 +351,85 @@ impl ContextCompressor for App {
         Ok(())
     }
 
+    fn compact(&mut self, instructions: Option<&str>) -> Result<CompactReport> {
+        let messages = self.history().to_vec();
+        let to_summarize = messages.len().saturating_sub(COMPACT_KEEP_RECENT_COUNT);
+        // A previous summary on its own isn't worth summarizing again
+        let only_summary = to_summarize == 1 && messages[0].role == "system";
+        if to_summarize == 0 || only_summary {
+            return Err(anyhow::anyhow!(
+                "Nothing to compact; the last {COMPACT_KEEP_RECENT_COUNT} messages are always kept"
+            ));
+        }
+
+        let transcript = messages[..to_summarize]
+            .iter()
+            .map(|message| format!("{}: {}", message.role, message.content))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let focus = match instructions.map(str::trim) {
+            Some(instructions) if !instructions.is_empty() => {
+                format!("Pay particular attention to: {instructions}\n\n")
+            }
+            _ => String::new(),
+        };
+        let prompt = format!("{CONVERSATION_SUMMARY_PROMPT}{focus}{transcript}");
+
+        let tokens_before = self.history_tokens();
+        let model = self.compact_model()?;
+        let (summary, model) = self.complete_with_model(model.as_ref(), &prompt)?;
+
+        let session = self
+            .session_manager
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Session manager not available"))?;
+        // Tags of the kept messages move down with them, behind the summary
+        let kept_tags: Vec<_> = session
+            .message_tags
+            .iter()
+            .filter(|(index, _)| **index >= to_summarize)
+            .map(|(index, tags)| (index - to_summarize + 1, tags.clone()))
+            .collect();
+        // Results kept as references to summarized ones get their content back
+        let mut kept = messages[to_summarize..].to_vec();
+        restore_dedup_references(&mut kept, &messages[..to_summarize]);
+        session.replace_with_summary(summary.clone());
+        for message in kept {
+            session.add_message(message);
+        }
+        session.message_tags.extend(kept_tags);
+        self.sync_agent_history();
+
+        self.conversation_summaries.push(ConversationSummary::new(
+            summary,
+            to_summarize,
+            transcript.len(),
+        ));
+        let report = CompactReport {
+            messages_summarized: to_summarize,
+            tokens_before,
+            tokens_after: self.history_tokens(),
+            model,
+        };
+        self.messages
+            .push(format!("[success] ⏺ {}", report.message()));
+        if let Err(e) = self.save_session() {
+            self.log(&format!("Failed to save the session: {e}"));
+        }
+
+        Ok(report)
+    }
+
     fn should_compress(&self) -> bool {
         // Don't summarize in non-chat state
         if self.state != AppState::Chat {
             return false;
         }
 
-        // Check both message count and character count thresholds
-        let message_count = self.messages.len();
-        let char_count = self.conversation_char_count();
-
-        // Also check the session manager if available
-        let session_count = self
-            .session_manager
-            .as_ref()
-            .map_or(0, |s| s.message_count());
-
-        message_count > DEFAULT_SUMMARIZATION_COUNT_THRESHOLD
-            || char_count > DEFAULT_SUMMARIZATION_CHAR_THRESHOLD
-            || session_count > DEFAULT_SUMMARIZATION_COUNT_THRESHOLD
+        match (self.context_usage(), auto_compact_percent()) {
+            (Some(usage), Some(percent)) => usage.percent >= percent,
+            _ => false,
+        }
     }
 
     fn conversation_char_count(&self) -> usize {
@@ -182,6 +456,13 @@ impl ContextCompressor for App {
             session.clear();
         }
 
+        // A new conversation starts with a fresh persistent shell
+        crate::tools::shell_session::reset_session();
+
+        // Later turns are saved as a new conversation, leaving the cleared one to resume
+        self.saved_session_id = uuid::Uuid::new_v4().to_string();
+        self.session_starred = false;
+
         // Notify clients that history was cleared
         self.messages.push("[info] Chat history cleared".into());
     }
@@ -245,28 +526,3 @@ impl ContextCompressor for App {
             .collect()
     }
 }
-
-impl App {
-    /// Internal method to generate a summary with the agent
-    fn generate_summary_with_agent(&mut self, agent: &Agent, content: &str) -> Result<String> {
-        // Create a tokio runtime if needed
-        let runtime = match &self.tokio_runtime {
-            Some(rt) => rt,
-            None => return Err(anyhow::anyhow!("Async runtime not available")),
-        };
-
-        // Create a cloned agent to avoid borrowing issues
-        let agent_clone = agent.clone();
-
-        // Copy the content for the async block
-        let content_to_summarize = content.to_string();
-
-        // Define the summarization prompt
-        let prompt = format!("{CONVERSATION_SUMMARY_PROMPT}{content_to_summarize}");
-
-        // Execute the summarization
-        let result = runtime.block_on(async { agent_clone.execute(&prompt).await })?;
-
-        Ok(result)
-    }
-}
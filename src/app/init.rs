@@ -0,0 +1,110 @@
+use super::core::App;
+use crate::agent::core::Agent;
+use crate::agent::policy::ToolPolicy;
+use crate::app::presets::SamplingMode;
+use crate::context::project_instructions::{ProjectInstructions, PROJECT_INSTRUCTION_FILES};
+use crate::prompts::INIT_PROJECT_PROMPT;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// What `/init` did
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InitReport {
+    pub path: String,
+    /// Whether a starter file was written; false when the project already had one,
+    /// which was read again
+    pub created: bool,
+    pub message: String,
+}
+
+impl App {
+    /// Read the project's `OLI.md` or `AGENTS.md` again, e.g. after it was edited
+    pub fn reload_project_instructions(&mut self) -> Option<&ProjectInstructions> {
+        self.project_instructions = ProjectInstructions::load(&self.working_dir());
+        self.project_instructions.as_ref()
+    }
+
+    /// Use the project's `OLI.md` or `AGENTS.md` from now on, or when it has
+    /// neither, write a starter `OLI.md` from what the model finds exploring the
+    /// repository with read-only tools
+    pub fn init_project_instructions(&mut self, model_index: usize) -> Result<InitReport> {
+        if let Some(instructions) = self.reload_project_instructions() {
+            let path = instructions.path.display().to_string();
+            return Ok(InitReport {
+                message: format!(
+                    "Using the project instructions in {path}. Edit it and run /init again to \
+                     pick up changes."
+                ),
+                path,
+                created: false,
+            });
+        }
+
+        let model = self.current_model(model_index)?.clone();
+        if !model.has_agent_support() {
+            anyhow::bail!(
+                "{} doesn't support tool use, so can't explore the repository. Choose another \
+                 model with /model.",
+                model.name
+            );
+        }
+        let api_key = self.get_api_key_for_model(&model.name);
+        Self::validate_api_key(&model.name, &api_key)?;
+        let (parameters, _) = self.session_parameters(SamplingMode::Agent, &model, &api_key);
+        let (provider, agent_model) =
+            Self::determine_provider(&model.name, &api_key, &model.file_name)?;
+
+        let runtime = self
+            .tokio_runtime
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Async runtime not available"))?;
+        let working_dir = self.working_dir();
+        let answer = runtime.block_on(async {
+            let mut agent = Agent::new(provider)
+                .with_model(agent_model)
+                .with_model_parameters(parameters)
+                .with_result_limits(model.result_limits())
+                .with_tool_policy(ToolPolicy::ReadOnly)
+                .with_working_directory(working_dir.display().to_string());
+            agent.initialize_with_api_key(api_key).await?;
+            agent.execute(INIT_PROJECT_PROMPT).await
+        })?;
+
+        let contents = starter_file_contents(&answer)
+            .ok_or_else(|| anyhow::anyhow!("The model didn't write any instructions"))?;
+        let path = working_dir.join(PROJECT_INSTRUCTION_FILES[0]);
+        std::fs::write(&path, &contents)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        self.project_instructions = ProjectInstructions::from_contents(path.clone(), &contents);
+
+        let path = path.display().to_string();
+        Ok(InitReport {
+            message: format!(
+                "Wrote {path}. oli adds it to the system prompt from now on; review and edit it \
+                 to suit the project."
+            ),
+            path,
+            created: true,
+        })
+    }
+
+    pub(crate) fn working_dir(&self) -> PathBuf {
+        self.current_working_dir
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+}
+
+/// The file from the model's answer, without a code fence it may have wrapped it
+/// in; None when it's blank
+pub fn starter_file_contents(answer: &str) -> Option<String> {
+    let mut contents = answer.trim();
+    if let Some(fenced) = contents.strip_prefix("```") {
+        // Drop the fence's language tag along with it
+        let body = fenced.split_once('\n').map_or("", |(_, body)| body);
+        contents = body.trim_end().strip_suffix("```").unwrap_or(body).trim();
+    }
+    (!contents.is_empty()).then(|| format!("{contents}\n"))
+}
@@ -1,5 +1,10 @@
 use anyhow::Result;
-use std::path::PathBuf;
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub use crate::apis::log::{format_log, format_log_with_color, LogLevel};
 
 /// Logger trait for writing logs to a file and displaying them in the TUI
 pub trait Logger {
@@ -19,61 +24,226 @@ pub trait Logger {
     fn write_log_to_file(&self, message: &str) -> Result<()>;
 }
 
-/// Log level for messages
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum LogLevel {
-    /// Debug level for detailed information
-    Debug,
-    /// Info level for general information
-    Info,
-    /// Warning level for potential issues
-    Warning,
-    /// Error level for error conditions
-    Error,
+/// Environment variable with the directory session logs are written to
+pub const LOG_DIR_ENV: &str = "OLI_LOG_DIR";
+/// Environment variable with the size in bytes at which a session log is rotated
+pub const LOG_MAX_BYTES_ENV: &str = "OLI_LOG_MAX_BYTES";
+/// Environment variable with the number of rotated files kept per session log
+pub const LOG_MAX_FILES_ENV: &str = "OLI_LOG_MAX_FILES";
+/// Environment variable with the number of days session logs are kept
+pub const LOG_RETENTION_DAYS_ENV: &str = "OLI_LOG_RETENTION_DAYS";
+/// Environment variable with the number of lines kept for the in-memory log view
+pub const LOG_MEMORY_LINES_ENV: &str = "OLI_LOG_MEMORY_LINES";
+
+/// Prefix of session log file names, used to find old logs to prune
+pub(crate) const SESSION_LOG_PREFIX: &str = "session-";
+
+/// Size, rotation and retention limits for session logs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogConfig {
+    pub directory: PathBuf,
+    /// Rotate the log file once it reaches this size
+    pub max_file_bytes: u64,
+    /// Rotated files kept per log, as `.1` (newest) to `.N`
+    pub max_rotated_files: usize,
+    /// Delete session logs not modified for this many days at startup
+    pub retention_days: u64,
+    /// Lines kept in memory for the log view; older lines are dropped
+    pub max_memory_lines: usize,
 }
 
-impl LogLevel {
-    /// Get a string representation of the log level
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            LogLevel::Debug => "DEBUG",
-            LogLevel::Info => "INFO",
-            LogLevel::Warning => "WARN",
-            LogLevel::Error => "ERROR",
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            directory: dirs::home_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join(".oli")
+                .join("logs"),
+            max_file_bytes: 5 * 1024 * 1024,
+            max_rotated_files: 3,
+            retention_days: 7,
+            max_memory_lines: 1000,
         }
     }
+}
 
-    /// Get a color code for the log level
-    pub fn color_code(&self) -> &'static str {
-        match self {
-            LogLevel::Debug => "\x1b[36m",   // Cyan
-            LogLevel::Info => "\x1b[32m",    // Green
-            LogLevel::Warning => "\x1b[33m", // Yellow
-            LogLevel::Error => "\x1b[31m",   // Red
+impl LogConfig {
+    /// Defaults overridden by `OLI_LOG_DIR`, `OLI_LOG_MAX_BYTES`, `OLI_LOG_MAX_FILES`,
+    /// `OLI_LOG_RETENTION_DAYS` and `OLI_LOG_MEMORY_LINES`
+    pub fn from_env() -> Self {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let defaults = Self::default();
+
+        Self {
+            directory: var(LOG_DIR_ENV)
+                .map(PathBuf::from)
+                .unwrap_or(defaults.directory),
+            max_file_bytes: var(LOG_MAX_BYTES_ENV)
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(defaults.max_file_bytes),
+            max_rotated_files: var(LOG_MAX_FILES_ENV)
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(defaults.max_rotated_files),
+            retention_days: var(LOG_RETENTION_DAYS_ENV)
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(defaults.retention_days),
+            max_memory_lines: var(LOG_MEMORY_LINES_ENV)
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(defaults.max_memory_lines),
         }
     }
 }
 
-/// Format a log message with level, timestamp, and message
-pub fn format_log(level: LogLevel, message: &str) -> String {
-    let now = chrono::Local::now();
-    let timestamp = now.format("%Y-%m-%d %H:%M:%S%.3f");
+/// Fixed-size buffer of the most recent log lines
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogBuffer {
+    lines: VecDeque<String>,
+    capacity: usize,
+    dropped: usize,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::with_capacity(capacity.min(1024)),
+            capacity,
+            dropped: 0,
+        }
+    }
+
+    /// Add a line, dropping the oldest once the buffer is full
+    pub fn push(&mut self, line: String) {
+        if self.capacity == 0 {
+            self.dropped += 1;
+            return;
+        }
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+            self.dropped += 1;
+        }
+        self.lines.push_back(line);
+    }
 
-    format!("[{}] [{}] {}", timestamp, level.as_str(), message)
+    /// The most recent `limit` lines, oldest first
+    pub fn recent(&self, limit: usize) -> Vec<&str> {
+        let skip = self.lines.len().saturating_sub(limit);
+        self.lines.iter().skip(skip).map(String::as_str).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Lines dropped to stay within capacity
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+}
+
+/// Logger writing one file per session and day, rotated by size, with a capped
+/// in-memory view
+#[derive(Debug, Clone)]
+pub struct SessionLogger {
+    config: LogConfig,
+    session_id: String,
+    pub buffer: LogBuffer,
+    pub show_logs: bool,
 }
 
-/// Format a log message with color for terminal display
-pub fn format_log_with_color(level: LogLevel, message: &str) -> String {
-    let now = chrono::Local::now();
-    let timestamp = now.format("%Y-%m-%d %H:%M:%S%.3f");
-    let reset = "\x1b[0m";
-
-    format!(
-        "[{}] [{}{}{}] {}",
-        timestamp,
-        level.color_code(),
-        level.as_str(),
-        reset,
-        message
-    )
+impl SessionLogger {
+    pub fn new(session_id: &str, config: LogConfig) -> Self {
+        Self {
+            buffer: LogBuffer::new(config.max_memory_lines),
+            config,
+            session_id: session_id.to_string(),
+            show_logs: false,
+        }
+    }
+
+    pub fn config(&self) -> &LogConfig {
+        &self.config
+    }
+
+    // Path of the `index`th rotated file, the live file for 0
+    fn rotated_path(path: &Path, index: usize) -> PathBuf {
+        if index == 0 {
+            return path.to_path_buf();
+        }
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{index}"));
+        path.with_file_name(name)
+    }
+
+    // Shift `.1` to `.2` and so on, dropping the oldest, then move the live file to `.1`
+    fn rotate(&self, path: &Path) -> Result<()> {
+        if self.config.max_rotated_files == 0 {
+            std::fs::remove_file(path)?;
+            return Ok(());
+        }
+        for index in (0..self.config.max_rotated_files).rev() {
+            let from = Self::rotated_path(path, index);
+            if from.exists() {
+                std::fs::rename(&from, Self::rotated_path(path, index + 1))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Logger for SessionLogger {
+    fn log(&mut self, message: &str, args: &[&str]) {
+        // Fill `{}` placeholders in order
+        let mut args = args.iter();
+        let mut text = String::with_capacity(message.len());
+        let mut parts = message.split("{}").peekable();
+        while let Some(part) = parts.next() {
+            text.push_str(part);
+            if parts.peek().is_some() {
+                text.push_str(args.next().copied().unwrap_or("{}"));
+            }
+        }
+
+        let line = format_log(LogLevel::Info, &text);
+        let _ = self.write_log_to_file(&line);
+        self.buffer.push(line);
+    }
+
+    fn toggle_log_view(&mut self) {
+        self.show_logs = !self.show_logs;
+    }
+
+    fn get_log_directory(&self) -> PathBuf {
+        self.config.directory.clone()
+    }
+
+    /// A new file starts each day, so logs also rotate daily
+    fn get_log_file_path(&self) -> PathBuf {
+        let day = chrono::Local::now().format("%Y-%m-%d");
+        self.config
+            .directory
+            .join(format!("{SESSION_LOG_PREFIX}{day}-{}.log", self.session_id))
+    }
+
+    fn write_log_to_file(&self, message: &str) -> Result<()> {
+        std::fs::create_dir_all(&self.config.directory)?;
+        let path = self.get_log_file_path();
+
+        let size = std::fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+        if size > 0 && size + message.len() as u64 + 1 > self.config.max_file_bytes {
+            self.rotate(&path)?;
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(file, "{message}")?;
+        Ok(())
+    }
 }
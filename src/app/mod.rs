@@ -1,11 +1,29 @@
+pub mod api_keys;
+pub mod ask;
 pub mod commands;
+pub mod config;
 pub mod core;
 pub mod history;
+pub mod init;
 pub mod logger;
 pub mod memory;
 pub mod memory_methods;
 pub mod models;
+pub mod presets;
+pub mod prompt_history;
+pub mod queries;
+pub mod quick_start;
+pub mod rename;
+pub mod session;
+pub mod session_store;
+pub mod session_summary;
+pub mod storage;
+pub mod summarizer;
+pub mod theme;
+pub mod turn_record;
 pub mod utils;
+pub mod why;
+pub mod workspace_lock;
 
 // Re-export logger items
 pub use logger::{format_log, format_log_with_color, LogLevel, Logger};
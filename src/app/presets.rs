@@ -0,0 +1,120 @@
+use crate::apis::api_client::{ModelParameters, SamplingPreset};
+use crate::apis::provider::LLMProvider;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Environment variable choosing sampling presets, e.g. `balanced` for every mode or
+/// `agent=precise,chat=creative` for individual ones
+pub const SAMPLING_PRESETS_ENV: &str = "OLI_SAMPLING_PRESETS";
+
+/// What a model call is for; each mode can have its own preset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplingMode {
+    /// Plain prompts answered without tools
+    Chat,
+    /// Prompts answered by the agent with tools
+    Agent,
+    /// `/compare` runs
+    Compare,
+}
+
+impl SamplingMode {
+    pub const ALL: [SamplingMode; 3] = [
+        SamplingMode::Chat,
+        SamplingMode::Agent,
+        SamplingMode::Compare,
+    ];
+
+    /// Parse a mode from its name, ignoring case
+    pub fn parse(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.name().eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown mode '{}'. Expected chat, agent or compare",
+                    name.trim()
+                )
+            })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SamplingMode::Chat => "chat",
+            SamplingMode::Agent => "agent",
+            SamplingMode::Compare => "compare",
+        }
+    }
+}
+
+/// The presets chosen for a session: one for every mode, and overrides for
+/// individual modes. Explicit `/set` values and per-request parameters still win.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresetSelection {
+    pub default: Option<SamplingPreset>,
+    pub modes: BTreeMap<SamplingMode, SamplingPreset>,
+}
+
+impl PresetSelection {
+    /// Presets set in `OLI_SAMPLING_PRESETS`; a malformed value chooses none
+    pub fn from_env() -> Self {
+        std::env::var(SAMPLING_PRESETS_ENV)
+            .ok()
+            .and_then(|spec| Self::parse(&spec).ok())
+            .unwrap_or_default()
+    }
+
+    /// Parse presets written as a preset name for every mode and `mode=preset`
+    /// pairs, separated by commas
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut selection = Self::default();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.split_once('=') {
+                Some((mode, preset)) => {
+                    selection.set(
+                        Some(SamplingMode::parse(mode)?),
+                        Some(SamplingPreset::parse(preset)?),
+                    );
+                }
+                None => selection.set(None, Some(SamplingPreset::parse(entry)?)),
+            }
+        }
+        Ok(selection)
+    }
+
+    /// Choose the preset for a mode, or for every mode without `mode`. `None`
+    /// clears it.
+    pub fn set(&mut self, mode: Option<SamplingMode>, preset: Option<SamplingPreset>) {
+        match (mode, preset) {
+            (Some(mode), Some(preset)) => {
+                self.modes.insert(mode, preset);
+            }
+            (Some(mode), None) => {
+                self.modes.remove(&mode);
+            }
+            (None, preset) => self.default = preset,
+        }
+    }
+
+    /// The preset a mode runs with, if any
+    pub fn for_mode(&self, mode: SamplingMode) -> Option<SamplingPreset> {
+        self.modes.get(&mode).copied().or(self.default)
+    }
+
+    /// The mode's preset parameters clamped to a model, or none without a preset.
+    /// Without a known provider the preset is used as is.
+    pub fn parameters_for(
+        &self,
+        mode: SamplingMode,
+        provider: Option<&LLMProvider>,
+        model: &str,
+    ) -> ModelParameters {
+        match (self.for_mode(mode), provider) {
+            (Some(preset), Some(provider)) => preset.parameters_for(provider, model),
+            (Some(preset), None) => preset.parameters(),
+            (None, _) => ModelParameters::default(),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the prompt history file location
+pub const PROMPT_HISTORY_ENV: &str = "OLI_HISTORY_FILE";
+
+/// Most prompts kept in the history file; older ones are dropped
+pub const MAX_HISTORY_ENTRIES: usize = 1000;
+
+/// Prompts submitted in the input box, oldest first, so Up can recall them in
+/// later sessions like a shell. Each line holds one prompt as a JSON string so
+/// multi-line prompts survive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptHistory {
+    path: PathBuf,
+    max_entries: usize,
+}
+
+impl PromptHistory {
+    pub fn new(path: PathBuf, max_entries: usize) -> Self {
+        Self { path, max_entries }
+    }
+
+    /// The configured file, `~/.oli/history` unless overridden by `OLI_HISTORY_FILE`
+    pub fn from_env() -> Option<Self> {
+        let path = match std::env::var(PROMPT_HISTORY_ENV) {
+            Ok(path) if !path.trim().is_empty() => PathBuf::from(path),
+            _ => dirs::home_dir()?.join(".oli").join("history"),
+        };
+        Some(Self::new(path, MAX_HISTORY_ENTRIES))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The saved prompts, oldest first. A missing file is an empty history and
+    /// lines that can't be read are skipped.
+    pub fn load(&self) -> Vec<String> {
+        let Ok(contents) = std::fs::read_to_string(&self.path) else {
+            return Vec::new();
+        };
+        let entries: Vec<String> = contents
+            .lines()
+            .filter_map(|line| serde_json::from_str::<String>(line).ok())
+            .collect();
+        let skip = entries.len().saturating_sub(self.max_entries);
+        entries.into_iter().skip(skip).collect()
+    }
+
+    /// Add a prompt to the end of the history. Blank prompts and repeats of the
+    /// latest prompt aren't recorded. Returns whether the prompt was added.
+    pub fn add(&self, prompt: &str) -> Result<bool> {
+        if prompt.trim().is_empty() {
+            return Ok(false);
+        }
+        let mut entries = self.load();
+        if entries.last().is_some_and(|last| last == prompt) {
+            return Ok(false);
+        }
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        let line = serde_json::to_string(prompt)?;
+        if entries.len() < self.max_entries {
+            // Append so prompts from other instances in the meantime are kept
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .with_context(|| format!("Failed to open {}", self.path.display()))?;
+            writeln!(file, "{line}")
+                .with_context(|| format!("Failed to write {}", self.path.display()))?;
+            return Ok(true);
+        }
+
+        // Full: drop the oldest prompts and rewrite the file through a temporary
+        // file so a crash mid-write can't lose the history
+        entries.push(prompt.to_string());
+        let skip = entries.len() - self.max_entries;
+        let mut contents = String::new();
+        for entry in &entries[skip..] {
+            contents.push_str(&serde_json::to_string(entry)?);
+            contents.push('\n');
+        }
+        let temp_path = self.path.with_extension("tmp");
+        std::fs::write(&temp_path, contents)
+            .with_context(|| format!("Failed to write {}", temp_path.display()))?;
+        std::fs::rename(&temp_path, &self.path)
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,169 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Finished queries kept for polling; the oldest are dropped first
+const MAX_KEPT_QUERIES: usize = 20;
+
+/// Progress messages kept per query
+const MAX_PROGRESS_MESSAGES: usize = 50;
+
+/// Where a query started with `start_query` is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryStatus {
+    /// Waiting for the query or run ahead of it to finish
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl QueryStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// A query running in the background, as reported to pollers
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueryProgress {
+    pub id: String,
+    pub status: QueryStatus,
+    pub prompt: String,
+    pub started_at: u64, // Unix timestamp
+    pub finished_at: Option<u64>,
+    /// The latest progress messages, oldest first, e.g. tool calls as they run
+    pub messages: Vec<String>,
+    /// Progress messages received in total, including any no longer kept
+    pub message_count: usize,
+    /// What `run` would have answered, once the query completed
+    pub result: Option<Value>,
+    pub error: Option<String>,
+}
+
+/// Agent queries started over RPC without waiting for them. Queries run one at a
+/// time on their own threads, so the registry is read without the `App` lock
+/// that a running query holds, and GUI clients poll it instead of blocking.
+#[derive(Debug, Clone, Default)]
+pub struct QueryRegistry {
+    queries: Arc<Mutex<VecDeque<QueryProgress>>>,
+}
+
+impl QueryRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a queued query for `prompt`, returning its id
+    pub fn start(&self, prompt: &str) -> String {
+        let id = Uuid::new_v4().to_string();
+        let mut queries = self.queries.lock().unwrap();
+        queries.push_back(QueryProgress {
+            id: id.clone(),
+            status: QueryStatus::Queued,
+            prompt: prompt.to_string(),
+            started_at: now(),
+            finished_at: None,
+            messages: Vec::new(),
+            message_count: 0,
+            result: None,
+            error: None,
+        });
+
+        // Drop the oldest finished queries beyond the limit
+        while queries.iter().filter(|q| q.status.is_finished()).count() > MAX_KEPT_QUERIES {
+            let Some(oldest) = queries.iter().position(|q| q.status.is_finished()) else {
+                break;
+            };
+            queries.remove(oldest);
+        }
+        id
+    }
+
+    pub fn get(&self, id: &str) -> Option<QueryProgress> {
+        self.queries
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|query| query.id == id)
+            .cloned()
+    }
+
+    /// Mark a queued query running. False when it was cancelled while it waited.
+    pub fn begin(&self, id: &str) -> bool {
+        self.update(id, |query| {
+            if query.status != QueryStatus::Queued {
+                return false;
+            }
+            query.status = QueryStatus::Running;
+            true
+        })
+        .unwrap_or(false)
+    }
+
+    /// Add a progress message to a running query
+    pub fn record_progress(&self, id: &str, message: &str) {
+        self.update(id, |query| {
+            query.message_count += 1;
+            query.messages.push(message.to_string());
+            if query.messages.len() > MAX_PROGRESS_MESSAGES {
+                query.messages.remove(0);
+            }
+        });
+    }
+
+    /// Record how a query ended. A query cancelled while running stays cancelled,
+    /// keeping whatever it answered before it stopped.
+    pub fn finish(&self, id: &str, outcome: Result<Value, String>) {
+        self.update(id, |query| {
+            if query.status != QueryStatus::Cancelled {
+                query.status = match outcome {
+                    Ok(_) => QueryStatus::Completed,
+                    Err(_) => QueryStatus::Failed,
+                };
+            }
+            match outcome {
+                Ok(result) => query.result = Some(result),
+                Err(error) => query.error = Some(error),
+            }
+            query.finished_at = Some(now());
+        });
+    }
+
+    /// Cancel a query: a queued one never starts, and a running one is stopped
+    /// through `cancel_flag` at its next tool call. Returns the query's status
+    /// afterwards, or None for an unknown id.
+    pub fn cancel(&self, id: &str, cancel_flag: &AtomicBool) -> Option<QueryStatus> {
+        self.update(id, |query| {
+            match query.status {
+                QueryStatus::Queued => {
+                    query.status = QueryStatus::Cancelled;
+                    query.finished_at = Some(now());
+                }
+                QueryStatus::Running => {
+                    query.status = QueryStatus::Cancelled;
+                    cancel_flag.store(true, Ordering::SeqCst);
+                }
+                _ => {}
+            }
+            query.status
+        })
+    }
+
+    fn update<T>(&self, id: &str, change: impl FnOnce(&mut QueryProgress) -> T) -> Option<T> {
+        let mut queries = self.queries.lock().unwrap();
+        queries.iter_mut().find(|query| query.id == id).map(change)
+    }
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -0,0 +1,89 @@
+use super::core::App;
+use crate::apis::key_check::{check_key_format, key_env_var};
+use crate::app::api_keys::cloud_provider;
+use crate::models::{find_local_model, ModelConfig};
+use serde::Serialize;
+
+/// Environment variable naming the Ollama model to start with, e.g. `llama3.2`
+pub const OLLAMA_MODEL_ENV: &str = "OLI_OLLAMA_MODEL";
+
+/// The model to start chatting with straight away, when exactly one provider can
+/// be used without setup
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QuickStart {
+    pub model_index: usize,
+    pub model: String,
+    /// Where the credentials come from: an API key variable, or `Ollama`
+    pub source: String,
+}
+
+impl QuickStart {
+    /// One line telling the user what was picked and how to change it
+    pub fn notice(&self) -> String {
+        format!(
+            "Using {} via {}. Run /model to choose another model.",
+            self.model, self.source
+        )
+    }
+}
+
+/// Whether an Ollama model is the configured one, with or without its tag
+fn is_configured_ollama_model(file_name: &str, configured: &str) -> bool {
+    let configured = configured.trim();
+    file_name == configured || file_name == format!("{configured}:latest")
+}
+
+/// The only usable model among `models`, if exactly one provider is: a cloud model
+/// whose API key, looked up by its variable with `key_for`, is well-formed, or the
+/// configured Ollama model. Local models are only listed when Ollama is running.
+pub fn pick_quick_start(
+    models: &[ModelConfig],
+    key_for: impl Fn(&str) -> Option<String>,
+    ollama_model: Option<&str>,
+) -> Option<QuickStart> {
+    let mut viable = models.iter().enumerate().filter_map(|(index, model)| {
+        let source = match cloud_provider(&model.name) {
+            Some(provider) => {
+                let env_var = key_env_var(&provider)?;
+                let key = key_for(env_var).unwrap_or_default();
+                check_key_format(&provider, key.trim())
+                    .is_none()
+                    .then(|| env_var.to_string())?
+            }
+            None => is_configured_ollama_model(&model.file_name, ollama_model?)
+                .then(|| "Ollama".to_string())?,
+        };
+        Some(QuickStart {
+            model_index: index,
+            model: model.name.clone(),
+            source,
+        })
+    });
+
+    let first = viable.next()?;
+    viable.next().is_none().then_some(first)
+}
+
+impl App {
+    /// The model to skip setup with, from the API keys in the environment and
+    /// `OLI_OLLAMA_MODEL`. Keys aren't checked with the provider, to keep startup fast.
+    pub fn quick_start(&self) -> Option<QuickStart> {
+        let ollama_model = std::env::var(OLLAMA_MODEL_ENV)
+            .ok()
+            .filter(|model| !model.trim().is_empty());
+        pick_quick_start(
+            &self.available_models,
+            |env_var| std::env::var(env_var).ok(),
+            ollama_model.as_deref(),
+        )
+    }
+
+    /// The model in `OLI_OLLAMA_MODEL` when Ollama doesn't have it, so setup can
+    /// offer to pull it
+    pub fn missing_ollama_model(&self) -> Option<String> {
+        let configured = std::env::var(OLLAMA_MODEL_ENV).ok()?;
+        let configured = configured.trim();
+        (!configured.is_empty() && find_local_model(&self.available_models, configured).is_none())
+            .then(|| configured.to_string())
+    }
+}
@@ -0,0 +1,71 @@
+use crate::app::core::App;
+use crate::tools::rename::{RenamePlan, SymbolRenamer};
+use anyhow::Result;
+use std::path::PathBuf;
+
+impl App {
+    fn workspace_root(&self) -> Result<PathBuf> {
+        match &self.current_working_dir {
+            Some(dir) => Ok(PathBuf::from(dir)),
+            None => Ok(std::env::current_dir()?),
+        }
+    }
+
+    fn ensure_writable(&self) -> Result<()> {
+        if self
+            .workspace_lock
+            .as_ref()
+            .is_some_and(|lock| lock.lock().unwrap().is_read_only())
+        {
+            anyhow::bail!("The workspace is read-only while another oli instance holds it");
+        }
+        Ok(())
+    }
+
+    /// Work out a project-wide rename and keep it for approval
+    pub fn preview_rename(&mut self, old_name: &str, new_name: &str) -> Result<&RenamePlan> {
+        let plan = SymbolRenamer::new(&self.workspace_root()?).plan(old_name, new_name)?;
+        Ok(self.pending_rename.insert(plan))
+    }
+
+    /// Apply the previewed rename to every file at once, keeping it for undo.
+    /// Returns the number of files changed.
+    pub fn apply_rename(&mut self) -> Result<usize> {
+        self.ensure_writable()?;
+        let plan = self
+            .pending_rename
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("No rename to apply; preview one with /rename"))?;
+
+        let applied = plan.apply()?;
+        let files = applied.file_count();
+        self.log(&format!(
+            "Renamed {} to {} in {files} files",
+            applied.old_name, applied.new_name
+        ));
+        self.last_rename = Some(applied);
+        Ok(files)
+    }
+
+    /// Revert the last applied rename. Returns a description of what was undone.
+    pub fn undo_rename(&mut self) -> Result<String> {
+        self.ensure_writable()?;
+        let applied = self
+            .last_rename
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("No rename to undo"))?;
+
+        if let Err(e) = applied.undo() {
+            self.last_rename = Some(applied);
+            return Err(e);
+        }
+        let description = format!(
+            "{} is {} again in {} files",
+            applied.new_name,
+            applied.old_name,
+            applied.file_count()
+        );
+        self.log(&format!("Undid rename: {description}"));
+        Ok(description)
+    }
+}
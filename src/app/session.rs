@@ -0,0 +1,94 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the session state file location
+pub const SESSION_FILE_ENV: &str = "OLI_SESSION_FILE";
+
+/// UI state saved periodically so it survives a crash or restart
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionState {
+    /// Prompt being written but not yet submitted, including earlier lines of a
+    /// multi-line prompt
+    #[serde(default)]
+    pub draft: String,
+    /// Messages scrolled back from the latest in the chat view
+    #[serde(default)]
+    pub scroll_offset: usize,
+    /// RFC 3339 timestamp of the save
+    #[serde(default)]
+    pub saved_at: String,
+}
+
+impl SessionState {
+    /// Whether there is anything worth restoring
+    pub fn is_empty(&self) -> bool {
+        self.draft.trim().is_empty() && self.scroll_offset == 0
+    }
+}
+
+/// JSON file holding the latest `SessionState`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionFile {
+    path: PathBuf,
+}
+
+impl SessionFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// The configured file, `~/.oli/session.json` unless overridden by `OLI_SESSION_FILE`
+    pub fn from_env() -> Option<Self> {
+        match std::env::var(SESSION_FILE_ENV) {
+            Ok(path) if !path.trim().is_empty() => Some(Self::new(PathBuf::from(path))),
+            _ => dirs::home_dir().map(|home| Self::new(home.join(".oli").join("session.json"))),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Save the state, stamping it with the current time. An empty state removes
+    /// the file so there's nothing to restore.
+    pub fn save(&self, state: &SessionState) -> Result<()> {
+        if state.is_empty() {
+            return self.clear();
+        }
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        let state = SessionState {
+            saved_at: chrono::Utc::now().to_rfc3339(),
+            ..state.clone()
+        };
+
+        // Write to a temporary file first so a crash mid-save can't corrupt the last state
+        let temp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(&state)?)
+            .with_context(|| format!("Failed to write {}", temp_path.display()))?;
+        std::fs::rename(&temp_path, &self.path)
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        Ok(())
+    }
+
+    /// The saved state, if any. An unreadable file is treated as no state.
+    pub fn load(&self) -> Option<SessionState> {
+        let contents = std::fs::read_to_string(&self.path).ok()?;
+        serde_json::from_str::<SessionState>(&contents)
+            .ok()
+            .filter(|state| !state.is_empty())
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e).with_context(|| format!("Failed to remove {}", self.path.display())),
+        }
+    }
+}
@@ -0,0 +1,466 @@
+use super::core::{App, Task, TaskStatus};
+use super::history::{ContextCompressor, ConversationSummary};
+use crate::apis::api_client::Message;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the directory conversations are saved in
+pub const SESSION_DIR_ENV: &str = "OLI_SESSION_DIR";
+
+/// Characters of a message shown around a search match
+const SNIPPET_CHARS: usize = 120;
+/// Characters of the first prompt used as a session's title
+const TITLE_CHARS: usize = 80;
+/// Why a task that was still running when its conversation was saved is failed on resume
+const INTERRUPTED_TASK: &str = "Interrupted before it finished";
+
+/// A tag as it's stored: lowercase, without surrounding whitespace or a leading `#`
+pub fn normalize_tag(tag: &str) -> Result<String> {
+    let tag = tag.trim().trim_start_matches('#').to_lowercase();
+    if tag.is_empty() || tag.chars().any(char::is_whitespace) {
+        return Err(anyhow::anyhow!(
+            "Tags are single words, e.g. decision, bug or todo"
+        ));
+    }
+    Ok(tag)
+}
+
+/// A message of a saved conversation with its tags
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedMessage {
+    pub role: String,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+}
+
+/// A summary of earlier messages of a saved conversation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedSummary {
+    pub content: String,
+    /// Number of messages summarized
+    pub messages_count: usize,
+    /// Characters of the messages summarized
+    pub original_chars: usize,
+}
+
+impl From<&ConversationSummary> for SavedSummary {
+    fn from(summary: &ConversationSummary) -> Self {
+        Self {
+            content: summary.content.clone(),
+            messages_count: summary.messages_count,
+            original_chars: summary.original_chars,
+        }
+    }
+}
+
+/// A conversation saved so it can be resumed and searched later
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSession {
+    pub id: String,
+    /// RFC 3339 time it was first saved
+    pub started_at: String,
+    /// RFC 3339 time it was last saved
+    pub updated_at: String,
+    #[serde(default)]
+    pub working_directory: Option<String>,
+    #[serde(default)]
+    pub starred: bool,
+    pub messages: Vec<SavedMessage>,
+    /// Tasks run in the conversation, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tasks: Vec<Task>,
+    /// Summaries of messages compressed out of the conversation, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub summaries: Vec<SavedSummary>,
+}
+
+impl SavedSession {
+    /// The first line of the first prompt
+    pub fn title(&self) -> String {
+        let prompt = self
+            .messages
+            .iter()
+            .find(|msg| msg.role == "user")
+            .and_then(|msg| msg.content.lines().find(|line| !line.trim().is_empty()))
+            .unwrap_or("(no prompt)")
+            .trim();
+        truncate(prompt, TITLE_CHARS)
+    }
+
+    /// Every tag used in the conversation
+    pub fn tags(&self) -> BTreeSet<String> {
+        self.messages
+            .iter()
+            .flat_map(|msg| msg.tags.iter().cloned())
+            .collect()
+    }
+
+    fn matches(&self, filter: &SessionFilter) -> bool {
+        (!filter.starred || self.starred)
+            && filter
+                .tag
+                .as_ref()
+                .is_none_or(|tag| self.messages.iter().any(|msg| msg.tags.contains(tag)))
+    }
+}
+
+/// Which saved conversations to list or search
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionFilter {
+    /// Only starred conversations
+    pub starred: bool,
+    /// Only conversations, or when searching only messages, with this tag
+    pub tag: Option<String>,
+}
+
+/// A saved conversation as listed by /resume
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionListing {
+    pub id: String,
+    pub title: String,
+    pub updated_at: String,
+    pub starred: bool,
+    pub messages: usize,
+    pub tags: Vec<String>,
+}
+
+/// A message found by /search
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchHit {
+    pub session_id: String,
+    pub title: String,
+    pub starred: bool,
+    /// Index of the message in the conversation
+    pub index: usize,
+    pub role: String,
+    pub tags: Vec<String>,
+    /// The part of the message around the match, on one line
+    pub snippet: String,
+}
+
+/// Directory of saved conversations, one JSON file each
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionStore {
+    dir: PathBuf,
+}
+
+impl SessionStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// The configured directory, `~/.oli/sessions` unless overridden by `OLI_SESSION_DIR`
+    pub fn from_env() -> Option<Self> {
+        match std::env::var(SESSION_DIR_ENV) {
+            Ok(dir) if !dir.trim().is_empty() => Some(Self::new(PathBuf::from(dir))),
+            _ => dirs::home_dir().map(|home| Self::new(home.join(".oli").join("sessions"))),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// File a conversation is saved in
+    pub fn path_of(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.json"))
+    }
+
+    /// Save a conversation, keeping the start time of an earlier save
+    pub fn save(&self, session: &SavedSession) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+
+        let mut session = session.clone();
+        if let Some(previous) = self.load(&session.id) {
+            session.started_at = previous.started_at;
+        }
+
+        // Write to a temporary file first so a crash mid-save can't corrupt the last save
+        let path = self.path_of(&session.id);
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(&session)?)
+            .with_context(|| format!("Failed to write {}", temp_path.display()))?;
+        std::fs::rename(&temp_path, &path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+
+    /// The saved conversation with this id, if any
+    pub fn load(&self, id: &str) -> Option<SavedSession> {
+        let contents = std::fs::read_to_string(self.path_of(id)).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    /// Saved conversations, newest first. Unreadable files are skipped.
+    pub fn all(&self) -> Result<Vec<SavedSession>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.dir.display()))
+            }
+        };
+
+        let mut sessions: Vec<SavedSession> = entries
+            .flatten()
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+            .filter_map(|contents| serde_json::from_str(&contents).ok())
+            .collect();
+        sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        Ok(sessions)
+    }
+
+    /// The one saved conversation whose id starts with `prefix`
+    pub fn resolve(&self, prefix: &str) -> Result<SavedSession> {
+        let prefix = prefix.trim();
+        let mut matches: Vec<SavedSession> = self
+            .all()?
+            .into_iter()
+            .filter(|session| !prefix.is_empty() && session.id.starts_with(prefix))
+            .collect();
+        match matches.len() {
+            1 => Ok(matches.remove(0)),
+            0 => Err(anyhow::anyhow!("No saved session matches '{prefix}'")),
+            n => Err(anyhow::anyhow!(
+                "'{prefix}' matches {n} saved sessions; give more of the id"
+            )),
+        }
+    }
+
+    /// Saved conversations that pass the filter, newest first
+    pub fn list(&self, filter: &SessionFilter) -> Result<Vec<SessionListing>> {
+        Ok(self
+            .all()?
+            .into_iter()
+            .filter(|session| session.matches(filter))
+            .map(|session| SessionListing {
+                title: session.title(),
+                tags: session.tags().into_iter().collect(),
+                id: session.id,
+                updated_at: session.updated_at,
+                starred: session.starred,
+                messages: session.messages.len(),
+            })
+            .collect())
+    }
+
+    /// Messages containing `query`, ignoring case, from the newest conversations
+    /// first. An empty query matches every message, e.g. to list those with a tag.
+    pub fn search(
+        &self,
+        query: &str,
+        filter: &SessionFilter,
+        limit: usize,
+    ) -> Result<Vec<SearchHit>> {
+        let query = query.trim().to_lowercase();
+        let mut hits = Vec::new();
+        for session in self.all()? {
+            if !session.matches(filter) {
+                continue;
+            }
+            let title = session.title();
+            for (index, message) in session.messages.iter().enumerate() {
+                if filter
+                    .tag
+                    .as_ref()
+                    .is_some_and(|tag| !message.tags.contains(tag))
+                {
+                    continue;
+                }
+                let Some(snippet) = snippet(&message.content, &query) else {
+                    continue;
+                };
+                hits.push(SearchHit {
+                    session_id: session.id.clone(),
+                    title: title.clone(),
+                    starred: session.starred,
+                    index,
+                    role: message.role.clone(),
+                    tags: message.tags.iter().cloned().collect(),
+                    snippet,
+                });
+                if hits.len() >= limit {
+                    return Ok(hits);
+                }
+            }
+        }
+        Ok(hits)
+    }
+}
+
+// The part of `content` around the first match of the lowercase `query`, on one
+// line, or None when it doesn't match
+fn snippet(content: &str, query: &str) -> Option<String> {
+    let flat: String = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    let lower = flat.to_lowercase();
+    // Lowercasing can change lengths, so positions are found in characters
+    let position = if query.is_empty() {
+        0
+    } else {
+        let byte = lower.find(query)?;
+        lower[..byte].chars().count()
+    };
+    let start = position.saturating_sub(SNIPPET_CHARS / 3);
+    let text: String = flat.chars().skip(start).take(SNIPPET_CHARS).collect();
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if flat.chars().count() > start + SNIPPET_CHARS {
+        "…"
+    } else {
+        ""
+    };
+    Some(format!("{prefix}{text}{suffix}"))
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() > max_chars {
+        let start: String = text.chars().take(max_chars).collect();
+        format!("{start}…")
+    } else {
+        text.to_string()
+    }
+}
+
+impl App {
+    /// The conversation as it's saved
+    pub fn saved_session(&self) -> SavedSession {
+        let now = chrono::Utc::now().to_rfc3339();
+        let messages = match &self.session_manager {
+            Some(session) => session
+                .messages
+                .iter()
+                .enumerate()
+                .map(|(index, message)| SavedMessage {
+                    role: message.role.clone(),
+                    content: message.content.clone(),
+                    tags: session
+                        .message_tags
+                        .get(&index)
+                        .cloned()
+                        .unwrap_or_default(),
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        SavedSession {
+            id: self.saved_session_id.clone(),
+            started_at: now.clone(),
+            updated_at: now,
+            working_directory: self.current_working_dir.clone(),
+            starred: self.session_starred,
+            messages,
+            tasks: self.tasks.clone(),
+            summaries: self
+                .conversation_summaries
+                .iter()
+                .map(SavedSummary::from)
+                .collect(),
+        }
+    }
+
+    /// Save the conversation, if conversations are saved and it isn't empty
+    pub fn save_session(&self) -> Result<()> {
+        match &self.session_store {
+            Some(store) if !self.history().is_empty() => store.save(&self.saved_session()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Star or unstar the conversation, so /resume and /search can filter on it
+    pub fn star_session(&mut self, starred: bool) -> Result<()> {
+        self.session_starred = starred;
+        self.save_session()
+    }
+
+    /// Add or remove a tag on the message `from_end` messages back, 1 being the
+    /// latest. Returns the index of the message and whether its tags changed.
+    pub fn tag_history_message(
+        &mut self,
+        from_end: usize,
+        tag: &str,
+        remove: bool,
+    ) -> Result<(usize, bool)> {
+        let tag = normalize_tag(tag)?;
+        let session = self
+            .session_manager
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Session manager not available"))?;
+        let count = session.messages.len();
+        if from_end == 0 || from_end > count {
+            return Err(anyhow::anyhow!(
+                "No message {from_end} back; the session has {count}"
+            ));
+        }
+
+        let index = count - from_end;
+        let changed = if remove {
+            session.untag_message(index, &tag)
+        } else {
+            session.tag_message(index, &tag)?
+        };
+        self.save_session()?;
+        Ok((index, changed))
+    }
+
+    /// Replace the conversation with a saved one. Later turns are saved to it.
+    pub fn resume_session(&mut self, prefix: &str) -> Result<SavedSession> {
+        let store = self
+            .session_store
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Conversations aren't being saved"))?;
+        let saved = store.resolve(prefix)?;
+
+        // Save the current conversation first so it can be resumed in turn
+        self.save_session()?;
+
+        let session = self
+            .session_manager
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Session manager not available"))?;
+        session.clear();
+        for message in &saved.messages {
+            session.messages.push(Message {
+                role: message.role.clone(),
+                content: message.content.clone(),
+            });
+        }
+        for (index, message) in saved.messages.iter().enumerate() {
+            if !message.tags.is_empty() {
+                session.message_tags.insert(index, message.tags.clone());
+            }
+        }
+
+        let session_messages = session.messages.clone();
+        self.messages = self.session_to_display_messages(&session_messages);
+        self.conversation_summaries = saved
+            .summaries
+            .iter()
+            .map(|summary| {
+                ConversationSummary::new(
+                    summary.content.clone(),
+                    summary.messages_count,
+                    summary.original_chars,
+                )
+            })
+            .collect();
+
+        // Nothing is running a task that was in progress when the conversation was saved
+        self.tasks = saved.tasks.clone();
+        self.current_task_id = None;
+        for task in &mut self.tasks {
+            if task.status == TaskStatus::InProgress {
+                task.fail(INTERRUPTED_TASK);
+            }
+        }
+
+        self.saved_session_id = saved.id.clone();
+        self.session_starred = saved.starred;
+        self.sync_agent_history();
+        Ok(saved)
+    }
+}
@@ -0,0 +1,258 @@
+use super::core::App;
+use crate::agent::evidence::Evidence;
+use crate::apis::api_client::Message;
+use crate::app::workspace_lock::workspace_root;
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Project-level log that session summaries are appended to, relative to the workspace root
+pub const SESSION_CHANGELOG_FILE: &str = ".oli/CHANGELOG-sessions.md";
+/// Environment variable that turns on session summaries at exit
+pub const SESSION_SUMMARY_ENV: &str = "OLI_SESSION_SUMMARY";
+
+/// Longest part of a message included in the prompt for the summary
+const MAX_MESSAGE_CHARS: usize = 600;
+/// Messages from the end of the conversation included in the prompt for the summary
+const MAX_SUMMARY_MESSAGES: usize = 30;
+
+/// Whether a summary should be written at exit, i.e. `OLI_SESSION_SUMMARY` is "1" or "true"
+pub fn session_summary_enabled() -> bool {
+    std::env::var(SESSION_SUMMARY_ENV)
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true"))
+        .unwrap_or(false)
+}
+
+/// What happened in a session: prompts, files the agent changed and commands it ran
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionActivity {
+    /// RFC 3339 timestamp of when the session started
+    pub started_at: String,
+    pub prompts: Vec<String>,
+    pub files_changed: Vec<String>,
+    pub commands: Vec<String>,
+}
+
+impl Default for SessionActivity {
+    fn default() -> Self {
+        Self {
+            started_at: chrono::Utc::now().to_rfc3339(),
+            prompts: Vec::new(),
+            files_changed: Vec::new(),
+            commands: Vec::new(),
+        }
+    }
+}
+
+impl SessionActivity {
+    pub fn record_prompt(&mut self, prompt: &str) {
+        let prompt = prompt.trim();
+        if !prompt.is_empty() {
+            self.prompts.push(prompt.to_string());
+        }
+    }
+
+    /// Record the files changed by Edit, Write, WriteMany and NotebookEditCell and the commands run by Bash in a
+    /// turn. Failed tool calls are left out.
+    pub fn record_tools(&mut self, evidence: &[Evidence]) {
+        for entry in evidence {
+            let Some(target) = &entry.target else {
+                continue;
+            };
+            if entry.excerpt.starts_with("ERROR EXECUTING TOOL") {
+                continue;
+            }
+
+            match entry.tool.as_str() {
+                "Edit" | "Write" | "NotebookEditCell" if !self.files_changed.contains(target) => {
+                    self.files_changed.push(target.clone());
+                }
+                "WriteMany" => {
+                    for path in target.split(", ") {
+                        if !self.files_changed.iter().any(|changed| changed == path) {
+                            self.files_changed.push(path.to_string());
+                        }
+                    }
+                }
+                "Bash" => self.commands.push(target.clone()),
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether there's nothing worth summarizing
+    pub fn is_empty(&self) -> bool {
+        self.prompts.is_empty() && self.files_changed.is_empty() && self.commands.is_empty()
+    }
+}
+
+/// A finished session's summary, as appended to the session changelog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionSummary {
+    pub activity: SessionActivity,
+    /// RFC 3339 timestamp of when the session ended
+    pub ended_at: String,
+    /// Model-written account of what was accomplished and decided, when available
+    pub notes: Option<String>,
+}
+
+impl SessionSummary {
+    pub fn new(activity: SessionActivity, notes: Option<String>) -> Self {
+        Self {
+            activity,
+            ended_at: chrono::Utc::now().to_rfc3339(),
+            notes: notes
+                .map(|notes| notes.trim().to_string())
+                .filter(|notes| !notes.is_empty()),
+        }
+    }
+
+    /// Markdown section for the changelog, headed by the session's time span
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!(
+            "## Session {} – {}\n",
+            format_time(&self.activity.started_at),
+            format_time(&self.ended_at)
+        );
+
+        if let Some(notes) = &self.notes {
+            markdown.push_str(&format!("\n{notes}\n"));
+        }
+
+        let sections = [
+            ("Prompts", &self.activity.prompts, false),
+            ("Files changed", &self.activity.files_changed, true),
+            ("Commands run", &self.activity.commands, true),
+        ];
+        for (title, items, code) in sections {
+            if items.is_empty() {
+                continue;
+            }
+            markdown.push_str(&format!("\n**{title}:**\n"));
+            for item in items {
+                let item = first_line(item);
+                if code {
+                    markdown.push_str(&format!("- `{item}`\n"));
+                } else {
+                    markdown.push_str(&format!("- {item}\n"));
+                }
+            }
+        }
+
+        markdown
+    }
+}
+
+/// Prompt asking a model to summarize the session from its activity and conversation
+pub fn summary_prompt(activity: &SessionActivity, conversation: &[Message]) -> String {
+    let mut prompt = String::from(
+        "Summarize this coding session for a project changelog in 2-5 short bullet points. \
+         Cover what was accomplished and any decisions made, with their reasons when given. \
+         Don't list every file or command; those are recorded separately. \
+         Reply with the bullet points only.\n\n",
+    );
+
+    if !activity.files_changed.is_empty() {
+        prompt.push_str(&format!(
+            "Files changed: {}\n",
+            activity.files_changed.join(", ")
+        ));
+    }
+    if !activity.commands.is_empty() {
+        prompt.push_str(&format!(
+            "Commands run: {}\n",
+            activity
+                .commands
+                .iter()
+                .map(|command| first_line(command))
+                .collect::<Vec<_>>()
+                .join("; ")
+        ));
+    }
+
+    prompt.push_str("\nConversation:\n");
+    let start = conversation.len().saturating_sub(MAX_SUMMARY_MESSAGES);
+    for message in conversation[start..]
+        .iter()
+        .filter(|message| message.role == "user" || message.role == "assistant")
+    {
+        let content: String = message.content.chars().take(MAX_MESSAGE_CHARS).collect();
+        prompt.push_str(&format!("[{}] {}\n", message.role, content.trim()));
+    }
+
+    prompt
+}
+
+/// Append a summary to `.oli/CHANGELOG-sessions.md` under `root`, creating it with a
+/// heading if needed. Returns the changelog's path.
+pub fn append_to_changelog(root: &Path, summary: &SessionSummary) -> Result<PathBuf> {
+    let path = root.join(SESSION_CHANGELOG_FILE);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    let is_new = !path.exists();
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+
+    if is_new {
+        writeln!(file, "# Session Changelog\n")?;
+    }
+    writeln!(file, "{}", summary.to_markdown())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+// "2026-10-16 14:03 UTC" from an RFC 3339 timestamp, or the timestamp itself if it doesn't parse
+fn format_time(timestamp: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|time| {
+            time.with_timezone(&chrono::Utc)
+                .format("%Y-%m-%d %H:%M UTC")
+                .to_string()
+        })
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or("").trim()
+}
+
+impl App {
+    /// Summarize the session so far and append it to the workspace's session
+    /// changelog, then start recording afresh. Returns None when nothing happened.
+    /// The model-written notes are skipped if no model is available.
+    pub fn summarize_session(&mut self) -> Result<Option<(SessionSummary, PathBuf)>> {
+        if self.session_activity.is_empty() {
+            return Ok(None);
+        }
+
+        let conversation = self
+            .session_manager
+            .as_ref()
+            .map(|session| session.messages.clone())
+            .unwrap_or_default();
+        let prompt = summary_prompt(&self.session_activity, &conversation);
+        let notes = match self.complete_with_summarizer(&prompt) {
+            Ok(notes) => Some(notes),
+            Err(e) => {
+                self.log(&format!("Session summary written without notes: {e}"));
+                None
+            }
+        };
+
+        let activity = std::mem::take(&mut self.session_activity);
+        let summary = SessionSummary::new(activity, notes);
+        let working_dir = self
+            .current_working_dir
+            .as_deref()
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        let path = append_to_changelog(&workspace_root(&working_dir), &summary)?;
+        Ok(Some((summary, path)))
+    }
+}
@@ -0,0 +1,378 @@
+use crate::agent::audit::AuditLog;
+use crate::agent::tool_stats::ToolStatsLog;
+use crate::apis::ledger::RequestLedger;
+use crate::app::logger::{LogConfig, SESSION_LOG_PREFIX};
+use crate::app::session::SessionFile;
+use crate::app::session_store::SessionStore;
+use crate::tools::processes::ProcessSupervisor;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Environment variable with the most bytes oli's files may use together, 0 for no limit
+pub const STORAGE_MAX_BYTES_ENV: &str = "OLI_STORAGE_MAX_BYTES";
+/// Limit on the bytes oli's files may use together unless `OLI_STORAGE_MAX_BYTES` is set
+pub const DEFAULT_MAX_STORAGE_BYTES: u64 = 1024 * 1024 * 1024;
+/// File in the log directory listing the sessions whose logs are never pruned
+const PINNED_SESSIONS_FILE: &str = "pinned-sessions.json";
+
+/// Disk space used by one kind of file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CategoryUsage {
+    pub name: String,
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub files: usize,
+    /// Whether pruning removes these files. Records such as the audit log are kept.
+    pub prunable: bool,
+}
+
+/// The logs kept for one session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StoredSession {
+    pub id: String,
+    pub bytes: u64,
+    pub files: usize,
+    /// RFC 3339 time its newest log was written
+    pub last_modified: String,
+    pub pinned: bool,
+}
+
+/// Disk space used by oli, by category, against the quota
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StorageUsage {
+    pub categories: Vec<CategoryUsage>,
+    /// Sessions with logs, newest first
+    pub sessions: Vec<StoredSession>,
+    pub total_bytes: u64,
+    pub max_bytes: Option<u64>,
+    pub max_age_days: u64,
+}
+
+/// What a prune removed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PruneReport {
+    pub removed_files: usize,
+    pub freed_bytes: u64,
+    /// Pinned sessions whose logs were kept regardless of age or quota
+    pub pinned_kept: usize,
+    /// Whether usage is still above the quota, because everything left is pinned,
+    /// in use or not prunable
+    pub over_quota: bool,
+}
+
+// A session log file, live or rotated
+struct SessionLogFile {
+    path: PathBuf,
+    session_id: String,
+    bytes: u64,
+    modified: SystemTime,
+}
+
+/// Measures the files oli keeps and prunes the oldest session logs once they're
+/// past the retention period or over the size quota. Pinned sessions are never
+/// pruned, nor is the current one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageManager {
+    log_dir: PathBuf,
+    max_age: Duration,
+    max_bytes: Option<u64>,
+    // Files counted towards the quota but never pruned
+    records: Vec<(String, PathBuf)>,
+}
+
+impl StorageManager {
+    /// Manage the session logs of `log_config`, pruned after its retention period,
+    /// with no size quota
+    pub fn new(log_config: &LogConfig) -> Self {
+        Self {
+            log_dir: log_config.directory.clone(),
+            max_age: Duration::from_secs(log_config.retention_days * 24 * 60 * 60),
+            max_bytes: None,
+            records: Vec::new(),
+        }
+    }
+
+    /// The session logs of `log_config` together with the audit log, request ledger,
+    /// tool stats, session state and process records, limited by `OLI_STORAGE_MAX_BYTES`
+    pub fn from_env(log_config: &LogConfig) -> Self {
+        let mut manager = Self::new(log_config).with_max_bytes(Self::max_bytes_from_env());
+        if let Some(log) = AuditLog::from_env() {
+            manager = manager.with_record("audit log", log.path().to_path_buf());
+        }
+        if let Some(ledger) = RequestLedger::from_env() {
+            manager = manager.with_record("request ledger", ledger.path().to_path_buf());
+        }
+        if let Some(stats) = ToolStatsLog::from_env() {
+            manager = manager.with_record("tool stats", stats.path().to_path_buf());
+        }
+        if let Some(session) = SessionFile::from_env() {
+            manager = manager.with_record("session state", session.path().to_path_buf());
+        }
+        if let Some(store) = SessionStore::from_env() {
+            manager = manager.with_record("saved conversations", store.dir().to_path_buf());
+        }
+        if let Some(supervisor) = ProcessSupervisor::from_env() {
+            manager = manager.with_record("processes", supervisor.dir().to_path_buf());
+        }
+        manager
+    }
+
+    /// The quota set by `OLI_STORAGE_MAX_BYTES`, 1 GiB by default. None when it's 0.
+    pub fn max_bytes_from_env() -> Option<u64> {
+        match std::env::var(STORAGE_MAX_BYTES_ENV)
+            .ok()
+            .and_then(|bytes| bytes.trim().parse::<u64>().ok())
+        {
+            Some(0) => None,
+            Some(bytes) => Some(bytes),
+            None => Some(DEFAULT_MAX_STORAGE_BYTES),
+        }
+    }
+
+    /// Limit the bytes all managed files may use together, or lift the limit
+    pub fn with_max_bytes(mut self, max_bytes: Option<u64>) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Count a file or directory towards the quota without ever pruning it
+    pub fn with_record(mut self, name: &str, path: PathBuf) -> Self {
+        self.records.push((name.to_string(), path));
+        self
+    }
+
+    fn pins_path(&self) -> PathBuf {
+        self.log_dir.join(PINNED_SESSIONS_FILE)
+    }
+
+    /// Ids of the pinned sessions
+    pub fn pinned(&self) -> BTreeSet<String> {
+        std::fs::read_to_string(self.pins_path())
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_pins(&self, pins: &BTreeSet<String>) -> Result<()> {
+        std::fs::create_dir_all(&self.log_dir)
+            .with_context(|| format!("Failed to create {}", self.log_dir.display()))?;
+        let path = self.pins_path();
+        std::fs::write(&path, serde_json::to_string_pretty(pins)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Keep a session's logs however old they get. Returns whether it wasn't pinned yet.
+    pub fn pin(&self, session_id: &str) -> Result<bool> {
+        let mut pins = self.pinned();
+        let added = pins.insert(session_id.to_string());
+        if added {
+            self.save_pins(&pins)?;
+        }
+        Ok(added)
+    }
+
+    /// Let a session's logs be pruned again. Returns whether it was pinned.
+    pub fn unpin(&self, session_id: &str) -> Result<bool> {
+        let mut pins = self.pinned();
+        let removed = pins.remove(session_id);
+        if removed {
+            self.save_pins(&pins)?;
+        }
+        Ok(removed)
+    }
+
+    /// The one session with logs, or pinned, whose id starts with `prefix`
+    pub fn resolve_session(&self, prefix: &str) -> Result<String> {
+        let prefix = prefix.trim();
+        let mut ids: BTreeSet<String> = self
+            .session_logs()?
+            .into_iter()
+            .map(|file| file.session_id)
+            .collect();
+        ids.extend(self.pinned());
+
+        let matches: Vec<String> = ids
+            .into_iter()
+            .filter(|id| !prefix.is_empty() && id.starts_with(prefix))
+            .collect();
+        match matches.as_slice() {
+            [id] => Ok(id.clone()),
+            [] => Err(anyhow::anyhow!("No session matches '{prefix}'")),
+            _ => Err(anyhow::anyhow!(
+                "'{prefix}' matches {} sessions; give more of the id",
+                matches.len()
+            )),
+        }
+    }
+
+    // Session logs in the log directory, oldest first
+    fn session_logs(&self) -> Result<Vec<SessionLogFile>> {
+        let entries = match std::fs::read_dir(&self.log_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.log_dir.display()))
+            }
+        };
+
+        let mut files: Vec<SessionLogFile> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let session_id = session_id_of_log(&entry.file_name().to_string_lossy())?;
+                let meta = entry.metadata().ok().filter(|meta| meta.is_file())?;
+                Some(SessionLogFile {
+                    path: entry.path(),
+                    session_id,
+                    bytes: meta.len(),
+                    modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                })
+            })
+            .collect();
+        files.sort_by_key(|file| file.modified);
+        Ok(files)
+    }
+
+    /// Sessions with logs, newest first
+    pub fn sessions(&self) -> Result<Vec<StoredSession>> {
+        let pins = self.pinned();
+        let mut sessions: BTreeMap<String, (u64, usize, SystemTime)> = BTreeMap::new();
+        for file in self.session_logs()? {
+            let session = sessions
+                .entry(file.session_id)
+                .or_insert((0, 0, SystemTime::UNIX_EPOCH));
+            session.0 += file.bytes;
+            session.1 += 1;
+            session.2 = session.2.max(file.modified);
+        }
+
+        let mut sessions: Vec<(SystemTime, StoredSession)> = sessions
+            .into_iter()
+            .map(|(id, (bytes, files, modified))| {
+                let session = StoredSession {
+                    pinned: pins.contains(&id),
+                    id,
+                    bytes,
+                    files,
+                    last_modified: chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339(),
+                };
+                (modified, session)
+            })
+            .collect();
+        sessions.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+        Ok(sessions.into_iter().map(|(_, session)| session).collect())
+    }
+
+    /// Disk space used by session logs and each record
+    pub fn usage(&self) -> Result<StorageUsage> {
+        let logs = self.session_logs()?;
+        let mut categories = vec![CategoryUsage {
+            name: "session logs".to_string(),
+            path: self.log_dir.clone(),
+            bytes: logs.iter().map(|file| file.bytes).sum(),
+            files: logs.len(),
+            prunable: true,
+        }];
+        for (name, path) in &self.records {
+            let (bytes, files) = disk_usage(path);
+            categories.push(CategoryUsage {
+                name: name.clone(),
+                path: path.clone(),
+                bytes,
+                files,
+                prunable: false,
+            });
+        }
+
+        Ok(StorageUsage {
+            total_bytes: categories.iter().map(|category| category.bytes).sum(),
+            categories,
+            sessions: self.sessions()?,
+            max_bytes: self.max_bytes,
+            max_age_days: self.max_age.as_secs() / (24 * 60 * 60),
+        })
+    }
+
+    /// Delete session logs past the retention period, then the oldest remaining
+    /// ones until usage is within the quota. Logs of pinned sessions and of
+    /// `current_session` are kept.
+    pub fn prune(&self, current_session: Option<&str>) -> Result<PruneReport> {
+        let pins = self.pinned();
+        let keep = |file: &SessionLogFile| {
+            pins.contains(&file.session_id) || Some(file.session_id.as_str()) == current_session
+        };
+        let logs = self.session_logs()?;
+        let mut report = PruneReport {
+            pinned_kept: logs
+                .iter()
+                .filter(|file| pins.contains(&file.session_id))
+                .map(|file| file.session_id.as_str())
+                .collect::<BTreeSet<_>>()
+                .len(),
+            ..PruneReport::default()
+        };
+        let mut remove = |file: &SessionLogFile| {
+            let removed = std::fs::remove_file(&file.path).is_ok();
+            if removed {
+                report.removed_files += 1;
+                report.freed_bytes += file.bytes;
+            }
+            removed
+        };
+
+        let now = SystemTime::now();
+        let mut remaining = Vec::new();
+        for file in logs {
+            let expired = now
+                .duration_since(file.modified)
+                .is_ok_and(|age| age > self.max_age);
+            if !(expired && !keep(&file) && remove(&file)) {
+                remaining.push(file);
+            }
+        }
+
+        if let Some(max_bytes) = self.max_bytes {
+            let mut total = self.usage()?.total_bytes;
+            for file in remaining.iter().filter(|file| !keep(file)) {
+                if total <= max_bytes {
+                    break;
+                }
+                if remove(file) {
+                    total = total.saturating_sub(file.bytes);
+                }
+            }
+            report.over_quota = total > max_bytes;
+        }
+        Ok(report)
+    }
+}
+
+/// Id of the session a log file belongs to, from names like
+/// `session-2025-06-01-<id>.log` or its rotated `.log.1`
+fn session_id_of_log(name: &str) -> Option<String> {
+    let rest = name.strip_prefix(SESSION_LOG_PREFIX)?;
+    // Skip the `YYYY-MM-DD-` date
+    let rest = rest.get(11..)?;
+    let (id, _) = rest.split_once(".log")?;
+    (!id.is_empty()).then(|| id.to_string())
+}
+
+// Bytes and files under a path, 0 if it doesn't exist
+fn disk_usage(path: &Path) -> (u64, usize) {
+    let Ok(meta) = std::fs::metadata(path) else {
+        return (0, 0);
+    };
+    if meta.is_file() {
+        return (meta.len(), 1);
+    }
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries.flatten().fold((0, 0), |(bytes, files), entry| {
+                let (b, f) = disk_usage(&entry.path());
+                (bytes + b, files + f)
+            })
+        })
+        .unwrap_or((0, 0))
+}
@@ -0,0 +1,215 @@
+use super::core::App;
+use crate::agent::core::LLMProvider;
+use crate::apis::api_client::{ApiClientEnum, CompletionOptions, Message};
+use crate::app::logger::{format_log_with_color, LogLevel};
+use anyhow::Result;
+use std::sync::Arc;
+
+/// Environment variable selecting a dedicated summarizer model, e.g.
+/// `anthropic:claude-3-5-haiku-latest` or `ollama:llama3.2`
+pub const SUMMARIZER_MODEL_ENV: &str = "OLI_SUMMARIZER_MODEL";
+
+/// A dedicated, usually cheaper, model used for background work such as
+/// context compression, session titles and follow-up suggestions
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummarizerConfig {
+    pub provider: LLMProvider,
+    pub model: String,
+}
+
+impl SummarizerConfig {
+    /// Parse a `provider:model` spec. The provider prefix may be omitted for
+    /// Claude, GPT and Gemini models, where it is inferred from the model name.
+    /// OpenRouter models always need it, e.g. `openrouter:deepseek/deepseek-chat`,
+    /// as do Azure OpenAI deployments, e.g. `azure:gpt-4o-mini`, and models of the
+    /// OpenAI-compatible API, e.g. `compatible:deepseek-chat`.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+
+        // Ollama model names may contain a colon themselves (e.g. llama3:8b),
+        // so only treat the prefix as a provider when it is a known one
+        let (provider, model) = match spec.split_once(':') {
+            Some((prefix, model)) => match Self::provider_from_name(prefix) {
+                Some(provider) => (provider, model.trim()),
+                None => (Self::infer_provider(spec)?, spec),
+            },
+            None => (Self::infer_provider(spec)?, spec),
+        };
+
+        if model.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Summarizer model spec '{}' is missing a model name",
+                spec
+            ));
+        }
+
+        Ok(Self {
+            provider,
+            model: model.to_string(),
+        })
+    }
+
+    /// Read the summarizer configuration from the environment, if set and valid
+    pub fn from_env() -> Option<Self> {
+        let spec = std::env::var(SUMMARIZER_MODEL_ENV).ok()?;
+        if spec.trim().is_empty() {
+            return None;
+        }
+
+        match Self::parse(&spec) {
+            Ok(config) => Some(config),
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    format_log_with_color(
+                        LogLevel::Warning,
+                        &format!("Ignoring {SUMMARIZER_MODEL_ENV}: {e}")
+                    )
+                );
+                None
+            }
+        }
+    }
+
+    /// Canonical `provider:model` form of this configuration
+    pub fn spec(&self) -> String {
+        let provider = match self.provider {
+            LLMProvider::Anthropic => "anthropic",
+            LLMProvider::OpenAI => "openai",
+            LLMProvider::Ollama => "ollama",
+            LLMProvider::Gemini => "gemini",
+            LLMProvider::OpenRouter => "openrouter",
+            LLMProvider::AzureOpenAI => "azure",
+            LLMProvider::OpenAICompatible => "compatible",
+        };
+        format!("{provider}:{}", self.model)
+    }
+
+    /// Create an API client for the summarizer, reading API keys from the environment
+    pub fn create_client(&self) -> Result<ApiClientEnum> {
+        let model = Some(self.model.clone());
+        let client = match self.provider {
+            LLMProvider::Anthropic => ApiClientEnum::Anthropic(Arc::new(
+                crate::apis::anthropic::AnthropicClient::new(model)?,
+            )),
+            LLMProvider::OpenAI => {
+                ApiClientEnum::OpenAI(Arc::new(crate::apis::openai::OpenAIClient::new(model)?))
+            }
+            LLMProvider::Ollama => {
+                ApiClientEnum::Ollama(Arc::new(crate::apis::ollama::OllamaClient::new(model)?))
+            }
+            LLMProvider::Gemini => {
+                ApiClientEnum::Gemini(Arc::new(crate::apis::gemini::GeminiClient::new(model)?))
+            }
+            LLMProvider::OpenRouter => ApiClientEnum::OpenRouter(Arc::new(
+                crate::apis::openrouter::OpenRouterClient::new(model)?,
+            )),
+            LLMProvider::AzureOpenAI => ApiClientEnum::OpenAI(Arc::new(
+                crate::apis::openai::OpenAIClient::azure_from_env(model)?,
+            )),
+            LLMProvider::OpenAICompatible => ApiClientEnum::OpenAI(Arc::new(
+                crate::apis::openai::OpenAIClient::compatible_from_env(model, None)?,
+            )),
+        };
+        Ok(client)
+    }
+
+    fn provider_from_name(name: &str) -> Option<LLMProvider> {
+        match name.trim().to_lowercase().as_str() {
+            "anthropic" | "claude" => Some(LLMProvider::Anthropic),
+            "openai" | "gpt" => Some(LLMProvider::OpenAI),
+            "gemini" | "google" => Some(LLMProvider::Gemini),
+            "ollama" | "local" => Some(LLMProvider::Ollama),
+            "openrouter" => Some(LLMProvider::OpenRouter),
+            "azure" => Some(LLMProvider::AzureOpenAI),
+            "compatible" => Some(LLMProvider::OpenAICompatible),
+            _ => None,
+        }
+    }
+
+    fn infer_provider(model: &str) -> Result<LLMProvider> {
+        let model_lower = model.to_lowercase();
+        if model_lower.contains("claude") {
+            Ok(LLMProvider::Anthropic)
+        } else if model_lower.contains("gpt") {
+            Ok(LLMProvider::OpenAI)
+        } else if model_lower.contains("gemini") {
+            Ok(LLMProvider::Gemini)
+        } else {
+            Err(anyhow::anyhow!(
+                "Cannot infer provider for summarizer model '{}'. Use provider:model, e.g. ollama:{}",
+                model,
+                model
+            ))
+        }
+    }
+}
+
+impl App {
+    /// Set the dedicated summarizer model, or clear it with `None` or an empty spec
+    pub fn set_summarizer_model(&mut self, spec: Option<&str>) -> Result<Option<String>> {
+        self.summarizer = match spec.map(str::trim) {
+            Some(spec) if !spec.is_empty() => Some(SummarizerConfig::parse(spec)?),
+            _ => None,
+        };
+        Ok(self.summarizer.as_ref().map(SummarizerConfig::spec))
+    }
+
+    /// Run a one-off prompt on the summarizer model, falling back to the main
+    /// agent when no summarizer is configured or it fails
+    pub fn complete_with_summarizer(&self, prompt: &str) -> Result<String> {
+        self.complete_with_model(self.summarizer.as_ref(), prompt)
+            .map(|(response, _)| response)
+    }
+
+    /// Run a one-off prompt on `model`, falling back to the main agent when it's
+    /// `None` or fails. Returns the response and the spec of the model that wrote
+    /// it, `None` for the main model.
+    pub(crate) fn complete_with_model(
+        &self,
+        model: Option<&SummarizerConfig>,
+        prompt: &str,
+    ) -> Result<(String, Option<String>)> {
+        let runtime = match &self.tokio_runtime {
+            Some(rt) => rt,
+            None => return Err(anyhow::anyhow!("Async runtime not available")),
+        };
+
+        if let Some(model) = model {
+            let options = CompletionOptions {
+                temperature: Some(0.3),
+                max_tokens: Some(2048),
+                ..Default::default()
+            };
+            let result = model.create_client().and_then(|client| {
+                runtime.block_on(async {
+                    client
+                        .complete(vec![Message::user(prompt.to_string())], options)
+                        .await
+                })
+            });
+
+            match result {
+                Ok(response) => return Ok((response, Some(model.spec()))),
+                Err(e) => eprintln!(
+                    "{}",
+                    format_log_with_color(
+                        LogLevel::Warning,
+                        &format!(
+                            "Summarizer model {} failed, falling back to main model: {e}",
+                            model.spec()
+                        )
+                    )
+                ),
+            }
+        }
+
+        let agent = match &self.agent {
+            Some(agent) => agent.clone(),
+            None => return Err(anyhow::anyhow!("No agent available for summarization")),
+        };
+
+        let response = runtime.block_on(async { agent.execute(prompt).await })?;
+        Ok((response, None))
+    }
+}
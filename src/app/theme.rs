@@ -0,0 +1,17 @@
+/// Environment variable choosing the color theme of the terminal UI
+pub const THEME_ENV: &str = "OLI_THEME";
+
+/// Themes the terminal UI can switch between, the first being the default
+pub const THEMES: &[&str] = &["dark", "light", "high-contrast", "solarized"];
+
+/// The theme set in `OLI_THEME` or `[ui] theme`, or the default when it's unset
+/// or not a known theme
+pub fn theme_from_env() -> &'static str {
+    let chosen = std::env::var(THEME_ENV).unwrap_or_default();
+    let chosen = chosen.trim().to_lowercase();
+    THEMES
+        .iter()
+        .find(|theme| **theme == chosen)
+        .copied()
+        .unwrap_or(THEMES[0])
+}
@@ -0,0 +1,103 @@
+use super::core::{App, TaskStatus};
+use crate::agent::tool_queue::QueuedCallStatus;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Whether a turn produced an answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnOutcome {
+    Success,
+    Error,
+}
+
+/// A tool call the agent made during a turn
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TurnToolCall {
+    pub tool: String,
+    pub arguments: Value,
+    pub status: QueuedCallStatus,
+}
+
+/// Tokens and cost of a turn, as reported by the providers where they report them
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TurnUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub cost_usd: Option<f64>,
+}
+
+/// One turn of a headless run, printed by `oli -p --output-format json` so CI
+/// pipelines can parse the result
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TurnRecord {
+    pub task_id: Option<String>,
+    pub prompt: String,
+    pub outcome: TurnOutcome,
+    pub response: Option<String>,
+    pub error: Option<String>,
+    /// Tool calls of an agent run that finished; a failed run only has its count
+    pub tool_calls: Vec<TurnToolCall>,
+    pub tool_count: u32,
+    pub usage: TurnUsage,
+    pub duration_ms: u64,
+    /// Status the CLI exits with: 0 on success, 1 on failure
+    pub exit_code: i32,
+}
+
+impl App {
+    /// Record how the last run went, from its task and the tool calls it made, and
+    /// keep it as `last_turn`. `result` is the run's answer or its error.
+    pub fn record_turn(&mut self, prompt: &str, result: Result<&str, &str>) -> TurnRecord {
+        // Runs create their task before anything else
+        let task = self.tasks.last();
+
+        let tool_calls = match (task, &self.last_trail) {
+            (Some(task), Some(trail)) if trail.task_id == task.id => trail
+                .rounds
+                .iter()
+                .flat_map(|round| &round.calls)
+                .map(|call| TurnToolCall {
+                    tool: call.tool.clone(),
+                    arguments: call.arguments.clone(),
+                    status: call.status,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let error = match (&result, task.map(|task| &task.status)) {
+            (Err(error), _) => Some(error.to_string()),
+            (Ok(_), Some(TaskStatus::Failed(error))) => Some(error.clone()),
+            _ => None,
+        };
+        let outcome = if error.is_some() {
+            TurnOutcome::Error
+        } else {
+            TurnOutcome::Success
+        };
+
+        let record = TurnRecord {
+            task_id: task.map(|task| task.id.clone()),
+            prompt: prompt.to_string(),
+            outcome,
+            response: result.ok().map(str::to_string),
+            error,
+            tool_count: task.map_or(tool_calls.len() as u32, |task| task.tool_count),
+            tool_calls,
+            usage: task.map_or_else(TurnUsage::default, |task| TurnUsage {
+                input_tokens: task.input_tokens,
+                output_tokens: task.output_tokens,
+                cost_usd: task.cost_usd,
+            }),
+            duration_ms: self.last_run_time.elapsed().as_millis() as u64,
+            exit_code: if outcome == TurnOutcome::Success {
+                0
+            } else {
+                1
+            },
+        };
+        self.last_turn = Some(record.clone());
+        record
+    }
+}
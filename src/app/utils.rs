@@ -1,5 +1,12 @@
 // Utility functions for the App
 
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// Environment variable with scroll key overrides for the terminal UI, e.g.
+/// `ctrl+k=line_up, ctrl+d=none`
+pub const SCROLL_BINDINGS_ENV: &str = "OLI_SCROLL_BINDINGS";
+
 /// A scrollable state for managing UI scrolling and positioning
 #[derive(Debug, Clone, Default)]
 pub struct ScrollState {
@@ -100,6 +107,67 @@ impl ScrollState {
         self.scroll_down(self.viewport_size.saturating_sub(1).max(1));
     }
 
+    /// Scroll up by a single line
+    pub fn line_up(&mut self) {
+        self.scroll_up(1);
+    }
+
+    /// Scroll down by a single line
+    pub fn line_down(&mut self) {
+        self.scroll_down(1);
+    }
+
+    /// Half page up (scroll up by half the viewport height)
+    pub fn half_page_up(&mut self) {
+        self.scroll_up((self.viewport_size / 2).max(1));
+    }
+
+    /// Half page down (scroll down by half the viewport height)
+    pub fn half_page_down(&mut self) {
+        self.scroll_down((self.viewport_size / 2).max(1));
+    }
+
+    /// Scroll so that the given line is at the top of the viewport, if possible
+    pub fn scroll_to(&mut self, line: usize) {
+        let max = self.max_scroll();
+        self.position = line.min(max);
+        self.follow_bottom = self.position >= max;
+    }
+
+    /// Jump to the closest boundary (e.g. the start of a user prompt) above the
+    /// current position. `boundaries` are line offsets in ascending order.
+    pub fn previous_boundary(&mut self, boundaries: &[usize]) {
+        match boundaries.iter().rev().find(|&&line| line < self.position) {
+            Some(&line) => self.scroll_to(line),
+            None => self.scroll_to_top(),
+        }
+    }
+
+    /// Jump to the closest boundary below the current position. Boundaries that
+    /// can't reach the top of the viewport end up scrolling to the bottom.
+    pub fn next_boundary(&mut self, boundaries: &[usize]) {
+        match boundaries.iter().find(|&&line| line > self.position) {
+            Some(&line) => self.scroll_to(line),
+            None => self.scroll_to_bottom(),
+        }
+    }
+
+    /// Apply a scroll action, using `boundaries` for prompt jumps
+    pub fn apply(&mut self, action: ScrollAction, boundaries: &[usize]) {
+        match action {
+            ScrollAction::LineUp => self.line_up(),
+            ScrollAction::LineDown => self.line_down(),
+            ScrollAction::HalfPageUp => self.half_page_up(),
+            ScrollAction::HalfPageDown => self.half_page_down(),
+            ScrollAction::PageUp => self.page_up(),
+            ScrollAction::PageDown => self.page_down(),
+            ScrollAction::Top => self.scroll_to_top(),
+            ScrollAction::Bottom => self.scroll_to_bottom(),
+            ScrollAction::PreviousPrompt => self.previous_boundary(boundaries),
+            ScrollAction::NextPrompt => self.next_boundary(boundaries),
+        }
+    }
+
     /// Determine if we need to show the "more above" indicator
     pub fn has_more_above(&self) -> bool {
         self.position > 0
@@ -111,6 +179,146 @@ impl ScrollState {
     }
 }
 
+/// Scroll actions that can be bound to keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollAction {
+    LineUp,
+    LineDown,
+    HalfPageUp,
+    HalfPageDown,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+    PreviousPrompt,
+    NextPrompt,
+}
+
+impl ScrollAction {
+    /// Parse an action from its snake_case name, e.g. `half_page_down`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "line_up" => Some(Self::LineUp),
+            "line_down" => Some(Self::LineDown),
+            "half_page_up" => Some(Self::HalfPageUp),
+            "half_page_down" => Some(Self::HalfPageDown),
+            "page_up" => Some(Self::PageUp),
+            "page_down" => Some(Self::PageDown),
+            "top" => Some(Self::Top),
+            "bottom" => Some(Self::Bottom),
+            "previous_prompt" => Some(Self::PreviousPrompt),
+            "next_prompt" => Some(Self::NextPrompt),
+            _ => None,
+        }
+    }
+
+    /// The snake_case name `from_name` parses
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::LineUp => "line_up",
+            Self::LineDown => "line_down",
+            Self::HalfPageUp => "half_page_up",
+            Self::HalfPageDown => "half_page_down",
+            Self::PageUp => "page_up",
+            Self::PageDown => "page_down",
+            Self::Top => "top",
+            Self::Bottom => "bottom",
+            Self::PreviousPrompt => "previous_prompt",
+            Self::NextPrompt => "next_prompt",
+        }
+    }
+}
+
+/// Key bindings for scroll actions, keyed by names such as `ctrl+e` or `pageup`
+#[derive(Debug, Clone)]
+pub struct ScrollBindings {
+    bindings: HashMap<String, ScrollAction>,
+}
+
+impl Default for ScrollBindings {
+    fn default() -> Self {
+        let mut bindings = Self {
+            bindings: HashMap::new(),
+        };
+        bindings.bind("ctrl+y", ScrollAction::LineUp);
+        bindings.bind("ctrl+e", ScrollAction::LineDown);
+        bindings.bind("ctrl+u", ScrollAction::HalfPageUp);
+        bindings.bind("ctrl+d", ScrollAction::HalfPageDown);
+        bindings.bind("pageup", ScrollAction::PageUp);
+        bindings.bind("pagedown", ScrollAction::PageDown);
+        bindings.bind("home", ScrollAction::Top);
+        bindings.bind("end", ScrollAction::Bottom);
+        bindings.bind("ctrl+up", ScrollAction::PreviousPrompt);
+        bindings.bind("ctrl+down", ScrollAction::NextPrompt);
+        bindings
+    }
+}
+
+impl ScrollBindings {
+    /// Default bindings with overrides from a spec like `ctrl+k=line_up, ctrl+j=line_down`.
+    /// Binding a key to `none` removes it.
+    pub fn with_overrides(spec: &str) -> Result<Self> {
+        let mut bindings = Self::default();
+
+        for entry in spec.split(',').filter(|e| !e.trim().is_empty()) {
+            let (key, action) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Invalid scroll binding '{}'", entry.trim()))?;
+
+            if action.trim().eq_ignore_ascii_case("none") {
+                bindings.unbind(key);
+                continue;
+            }
+
+            let action = ScrollAction::from_name(action)
+                .ok_or_else(|| anyhow::anyhow!("Unknown scroll action '{}'", action.trim()))?;
+            bindings.bind(key, action);
+        }
+
+        Ok(bindings)
+    }
+
+    /// Default bindings with the overrides in `OLI_SCROLL_BINDINGS` or `[ui] scroll_bindings`
+    pub fn from_env() -> Result<Self> {
+        Self::with_overrides(&std::env::var(SCROLL_BINDINGS_ENV).unwrap_or_default())
+    }
+
+    /// Every bound key with its action, sorted by key
+    pub fn bindings(&self) -> Vec<(&str, ScrollAction)> {
+        let mut bindings: Vec<_> = self
+            .bindings
+            .iter()
+            .map(|(key, action)| (key.as_str(), *action))
+            .collect();
+        bindings.sort_by_key(|(key, _)| *key);
+        bindings
+    }
+
+    /// Bind a key to a scroll action, replacing any existing binding
+    pub fn bind(&mut self, key: &str, action: ScrollAction) {
+        self.bindings.insert(Self::normalize_key(key), action);
+    }
+
+    /// Remove the binding for a key
+    pub fn unbind(&mut self, key: &str) {
+        self.bindings.remove(&Self::normalize_key(key));
+    }
+
+    /// Look up the scroll action bound to a key
+    pub fn action_for(&self, key: &str) -> Option<ScrollAction> {
+        self.bindings.get(&Self::normalize_key(key)).copied()
+    }
+
+    fn normalize_key(key: &str) -> String {
+        key.trim()
+            .to_lowercase()
+            .replace(' ', "")
+            .replace("control+", "ctrl+")
+            .replace("page_up", "pageup")
+            .replace("page_down", "pagedown")
+    }
+}
+
 /// Interface for scrollable components
 pub trait Scrollable {
     /// Get a mutable reference to the message scroll state
@@ -129,6 +337,12 @@ pub trait Scrollable {
         self.message_scroll_state().scroll_down(amount);
     }
 
+    /// Apply a scroll action to the message view, where `prompt_lines` are the
+    /// line offsets at which user prompts start
+    fn apply_scroll_action(&mut self, action: ScrollAction, prompt_lines: &[usize]) {
+        self.message_scroll_state().apply(action, prompt_lines);
+    }
+
     /// Auto scroll messages to bottom
     fn auto_scroll_to_bottom(&mut self) {
         self.message_scroll_state().scroll_to_bottom();
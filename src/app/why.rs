@@ -0,0 +1,14 @@
+use super::core::App;
+use anyhow::Result;
+
+impl App {
+    /// Explain the last agent run from its recorded tool calls and decisions,
+    /// answering `question` when one is given. The model is handed the trail rather
+    /// than asked to recall what it did.
+    pub fn explain_last_task(&self, question: Option<&str>) -> Result<String> {
+        let trail = self.last_trail.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("No agent run to explain yet. /why explains the last finished task.")
+        })?;
+        self.complete_with_summarizer(&trail.explanation_prompt(question))
+    }
+}
@@ -0,0 +1,217 @@
+use crate::agent::policy::ToolPolicy;
+use crate::tools::processes::process_alive;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Advisory lock file held by the first oli instance in a workspace, relative to its root
+pub const WORKSPACE_LOCK_FILE: &str = ".oli/instance.lock";
+/// Environment variable choosing what later instances in a locked workspace may do
+pub const SECOND_INSTANCE_ENV: &str = "OLI_SECOND_INSTANCE";
+
+/// What an instance may do when another one already holds the workspace
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SecondInstancePolicy {
+    /// Show a warning but allow every tool
+    #[default]
+    Warn,
+    /// Deny tools that modify files or run commands until the user overrides it
+    ReadOnly,
+}
+
+impl SecondInstancePolicy {
+    /// The policy set by `OLI_SECOND_INSTANCE` (`warn` or `read_only`), defaulting to warn
+    pub fn from_env() -> Self {
+        match std::env::var(SECOND_INSTANCE_ENV)
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .replace('-', "_")
+            .as_str()
+        {
+            "read_only" | "readonly" => SecondInstancePolicy::ReadOnly,
+            _ => SecondInstancePolicy::Warn,
+        }
+    }
+}
+
+/// Who holds a workspace, as written to the lock file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstanceInfo {
+    pub pid: u32,
+    pub session_id: String,
+    /// RFC 3339 timestamp of when the instance started
+    pub started_at: String,
+    pub working_dir: String,
+}
+
+impl InstanceInfo {
+    pub fn current(session_id: &str, working_dir: &Path) -> Self {
+        Self {
+            pid: std::process::id(),
+            session_id: session_id.to_string(),
+            started_at: chrono::Utc::now().to_rfc3339(),
+            working_dir: working_dir.display().to_string(),
+        }
+    }
+}
+
+/// Per-workspace advisory lock. The first instance holds it; later instances see
+/// who holds it and are warned, or made read-only depending on the policy.
+#[derive(Debug)]
+pub struct WorkspaceLock {
+    path: PathBuf,
+    instance: InstanceInfo,
+    holder: Option<InstanceInfo>,
+    read_only: bool,
+    policy: SecondInstancePolicy,
+}
+
+impl WorkspaceLock {
+    /// Take the lock for the workspace containing `working_dir`, or record who holds
+    /// it when another live instance already does. Stale locks left by instances
+    /// that exited are taken over.
+    pub fn acquire(
+        working_dir: &Path,
+        session_id: &str,
+        policy: SecondInstancePolicy,
+    ) -> Result<Self> {
+        let path = workspace_root(working_dir).join(WORKSPACE_LOCK_FILE);
+        let mut lock = Self {
+            path,
+            instance: InstanceInfo::current(session_id, working_dir),
+            holder: None,
+            read_only: false,
+            policy,
+        };
+        lock.refresh()?;
+        Ok(lock)
+    }
+
+    /// Check the lock file again, taking the lock if its holder has exited since
+    pub fn refresh(&mut self) -> Result<()> {
+        if self.is_primary() && self.holds_file() {
+            return Ok(());
+        }
+
+        if let Some(holder) = self.live_holder() {
+            if self.holder.is_none() {
+                self.read_only = self.policy == SecondInstancePolicy::ReadOnly;
+            }
+            self.holder = Some(holder);
+            return Ok(());
+        }
+
+        self.write_lock_file()?;
+        self.holder = None;
+        self.read_only = false;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether this instance holds the workspace
+    pub fn is_primary(&self) -> bool {
+        self.holder.is_none()
+    }
+
+    /// The other instance holding the workspace, when this one doesn't
+    pub fn holder(&self) -> Option<&InstanceInfo> {
+        self.holder.as_ref()
+    }
+
+    /// Whether tools that modify the workspace are denied
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Allow every tool again even though another instance holds the workspace
+    pub fn override_read_only(&mut self) {
+        self.read_only = false;
+    }
+
+    /// Tool policy for the agent: read-only while enforcement is on
+    pub fn tool_policy(&self) -> ToolPolicy {
+        if self.read_only {
+            ToolPolicy::ReadOnly
+        } else {
+            ToolPolicy::AllowAll
+        }
+    }
+
+    /// Banner text for a second instance, None for the holder
+    pub fn warning(&self) -> Option<String> {
+        let holder = self.holder.as_ref()?;
+        let mut warning = format!(
+            "Another oli instance (pid {}, started {}) is already working in this workspace. \
+             Edits from both sessions can overwrite each other.",
+            holder.pid, holder.started_at
+        );
+        if self.read_only {
+            warning.push_str(
+                " This instance is read-only: Edit, Write and Bash are disabled until you run /takeover.",
+            );
+        }
+        Some(warning)
+    }
+
+    /// Remove the lock file if this instance holds it
+    pub fn release(&self) -> Result<()> {
+        if !self.is_primary() || !self.holds_file() {
+            return Ok(());
+        }
+        std::fs::remove_file(&self.path)
+            .with_context(|| format!("Failed to remove {}", self.path.display()))
+    }
+
+    // Whether the lock file names this instance
+    fn holds_file(&self) -> bool {
+        read_lock_file(&self.path).is_some_and(|info| info.pid == self.instance.pid)
+    }
+
+    // The instance named in the lock file, if it's another process that's still running
+    fn live_holder(&self) -> Option<InstanceInfo> {
+        read_lock_file(&self.path)
+            .filter(|info| info.pid != self.instance.pid && process_alive(info.pid))
+    }
+
+    fn write_lock_file(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        file.write_all(serde_json::to_string_pretty(&self.instance)?.as_bytes())
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+impl Drop for WorkspaceLock {
+    fn drop(&mut self) {
+        let _ = self.release();
+    }
+}
+
+/// The repository containing `dir` (nearest ancestor with `.git`), or `dir` itself
+pub fn workspace_root(dir: &Path) -> PathBuf {
+    dir.ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .unwrap_or(dir)
+        .to_path_buf()
+}
+
+fn read_lock_file(path: &Path) -> Option<InstanceInfo> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
@@ -0,0 +1,188 @@
+use crate::agent::approval::ApprovalBackend;
+use crate::agent::audit::AuditLog;
+use crate::agent::core::{Agent, LLMProvider};
+use crate::agent::policy::ToolPolicy;
+use crate::apis::api_client::{DynApiClient, Message, ModelParameters};
+use crate::context::provider::ContextProvider;
+use crate::models::{self, ModelConfig};
+use anyhow::Result;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// High-level client for embedding the oli agent in other Rust programs,
+/// without the TUI or the JSON-RPC server.
+///
+/// ```no_run
+/// use oli_server::{Client, LLMProvider, ToolPolicy};
+///
+/// # async fn run() -> anyhow::Result<()> {
+/// let mut client = Client::new(LLMProvider::Anthropic)
+///     .with_working_directory(".".to_string())
+///     .with_tool_policy(ToolPolicy::ReadOnly);
+///
+/// let answer = client
+///     .send_prompt("Summarize src/lib.rs", |progress| println!("{progress}"))
+///     .await?;
+/// println!("{answer}");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Client {
+    agent: Agent,
+    api_key: Option<String>,
+    tool_policy: ToolPolicy,
+}
+
+impl Client {
+    /// Create a client for a provider. API keys are read from the environment
+    /// (e.g. `ANTHROPIC_API_KEY`) unless set with [`Client::with_api_key`].
+    pub fn new(provider: LLMProvider) -> Self {
+        Self {
+            agent: Agent::new(provider),
+            api_key: None,
+            tool_policy: ToolPolicy::default(),
+        }
+    }
+
+    pub fn with_model(mut self, model: String) -> Self {
+        self.agent = self.agent.with_model(model);
+        self
+    }
+
+    pub fn with_api_key(mut self, api_key: String) -> Self {
+        self.api_key = Some(api_key);
+        self
+    }
+
+    /// Use a custom API client, e.g. a mock in tests or a preconfigured provider client
+    pub fn with_api_client(mut self, client: DynApiClient) -> Self {
+        self.agent = self.agent.with_api_client(client);
+        self
+    }
+
+    pub fn with_system_prompt(mut self, prompt: String) -> Self {
+        self.agent = self.agent.with_system_prompt(prompt);
+        self
+    }
+
+    pub fn with_working_directory(mut self, working_dir: String) -> Self {
+        self.agent = self.agent.with_working_directory(working_dir);
+        self
+    }
+
+    pub fn with_model_parameters(mut self, parameters: ModelParameters) -> Self {
+        self.agent = self.agent.with_model_parameters(parameters);
+        self
+    }
+
+    pub fn with_tool_policy(mut self, policy: ToolPolicy) -> Self {
+        self.set_policy(policy);
+        self
+    }
+
+    /// Send Edit, Write and Bash executions to an external system for approval
+    pub fn with_approval_backend(mut self, backend: ApprovalBackend) -> Self {
+        self.agent = self.agent.with_approval_backend(Some(backend));
+        self
+    }
+
+    /// Record Edit, Write and Bash executions in an append-only audit log
+    pub fn with_audit_log(mut self, audit_log: AuditLog) -> Self {
+        self.agent = self.agent.with_audit_log(Some(audit_log));
+        self
+    }
+
+    /// Include the recent git history of files mentioned in prompts (on by default)
+    pub fn with_git_history(mut self, enabled: bool) -> Self {
+        self.agent = self.agent.with_git_history(enabled);
+        self
+    }
+
+    /// Add a provider's context, e.g. pinned files or team conventions, to every model request
+    pub fn with_context_provider(mut self, provider: Arc<dyn ContextProvider>) -> Self {
+        self.agent = self.agent.with_context_provider(provider);
+        self
+    }
+
+    /// Stop prompts that run longer than `limit`. The answer is then a partial
+    /// result starting with `[Partial result]`, and sending "continue" resumes the task.
+    pub fn with_time_limit(mut self, limit: Duration) -> Self {
+        self.agent = self.agent.with_time_limit(Some(limit));
+        self
+    }
+
+    /// Stop the running prompt, with a partial result, once `flag` is set from another task
+    pub fn with_cancel_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.agent = self.agent.with_cancel_flag(flag);
+        self
+    }
+
+    /// Change which tools the agent may execute for the following prompts
+    pub fn set_policy(&mut self, policy: ToolPolicy) {
+        self.tool_policy = policy.clone();
+        self.agent = self.agent.clone().with_tool_policy(policy);
+    }
+
+    /// Get the current tool policy
+    pub fn policy(&self) -> &ToolPolicy {
+        &self.tool_policy
+    }
+
+    /// List the models oli knows about, including any local Ollama models
+    pub fn list_models(&self) -> Vec<ModelConfig> {
+        models::get_available_models()
+    }
+
+    /// Create the provider's API client. Called automatically by `send_prompt`.
+    pub async fn initialize(&mut self) -> Result<()> {
+        if self.agent.is_initialized() {
+            return Ok(());
+        }
+
+        match self.api_key.clone() {
+            Some(api_key) => self.agent.initialize_with_api_key(api_key).await,
+            None => self.agent.initialize().await,
+        }
+    }
+
+    /// Send a prompt to the agent and return its final answer. Progress messages
+    /// (tool executions, diffs, iteration updates) are passed to `stream_handler`
+    /// as they arrive. The exchange is kept as context for later prompts.
+    pub async fn send_prompt<F>(&mut self, prompt: &str, mut stream_handler: F) -> Result<String>
+    where
+        F: FnMut(&str),
+    {
+        self.initialize().await?;
+
+        let (progress_tx, mut progress_rx) = mpsc::channel::<String>(100);
+        let agent = self.agent.clone().with_progress_sender(progress_tx);
+
+        // The agent owns the only sender, so the progress loop ends once it is dropped
+        let execution = async move { agent.execute(prompt).await };
+        let progress = async {
+            while let Some(message) = progress_rx.recv().await {
+                stream_handler(&message);
+            }
+        };
+        let (response, _) = tokio::join!(execution, progress);
+        let response = response?;
+
+        self.agent.add_message(Message::user(prompt.to_string()));
+        self.agent.add_message(Message::assistant(response.clone()));
+
+        Ok(response)
+    }
+
+    /// Get the conversation history kept between prompts
+    pub fn history(&self) -> &[Message] {
+        self.agent.conversation_history()
+    }
+
+    /// Forget the conversation history
+    pub fn clear_history(&mut self) {
+        self.agent.clear_history();
+    }
+}
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+/// Version of the JSON-RPC protocol spoken by the server
+pub const PROTOCOL_VERSION: &str = "1.0";
+
+/// Tools that modify the filesystem or run commands and therefore need user approval.
+/// RunTests runs the project's own code, so it's one too, though it runs without
+/// asking by default.
+pub const RISKY_TOOLS: &[&str] = &[
+    "Edit",
+    "Write",
+    "WriteMany",
+    "NotebookEditCell",
+    "Bash",
+    "RunTests",
+];
+
+/// Capabilities declared by a client during the `initialize` handshake
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientCapabilities {
+    /// Whether the client reads notifications from the server
+    pub supports_notifications: bool,
+    /// Whether the client can answer tool permission prompts
+    pub supports_permission_prompts: bool,
+    /// Whether the client is an editor that applies `workspace/applyEdit` requests
+    /// to its buffers, so Edit and Write go through it instead of the disk
+    pub supports_apply_edit: bool,
+    /// Whether the client answers `oli/resolveConflict` requests when a file the
+    /// agent is about to edit changed on disk since it was read
+    pub supports_conflict_prompts: bool,
+    /// Whether the client answers `oli/reviewEdit` requests with the hunks of an
+    /// Edit or Write the user accepts, in place of a permission prompt
+    pub supports_edit_review: bool,
+}
+
+impl Default for ClientCapabilities {
+    fn default() -> Self {
+        Self {
+            supports_notifications: true,
+            supports_permission_prompts: false,
+            supports_apply_edit: false,
+            supports_conflict_prompts: false,
+            supports_edit_review: false,
+        }
+    }
+}
+
+impl ClientCapabilities {
+    /// Parse client capabilities from `initialize` params, falling back to defaults
+    pub fn from_params(params: &serde_json::Value) -> Self {
+        params
+            .get("capabilities")
+            .and_then(|caps| serde_json::from_value(caps.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Whether a tool should be denied without asking because the client can't answer prompts
+    pub fn should_auto_deny(&self, tool_name: &str) -> bool {
+        !self.supports_permission_prompts && RISKY_TOOLS.contains(&tool_name)
+    }
+}
+
+/// Capabilities advertised by the server in response to `initialize`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerCapabilities {
+    pub notifications: bool,
+    pub subscriptions: bool,
+    pub permission_prompts: bool,
+    pub apply_edit: bool,
+    pub tools: Vec<String>,
+}
+
+impl ServerCapabilities {
+    /// Capabilities of this server build, adapted to what the client declared
+    pub fn negotiate(client: &ClientCapabilities) -> Self {
+        Self {
+            notifications: client.supports_notifications,
+            subscriptions: client.supports_notifications,
+            permission_prompts: client.supports_permission_prompts,
+            apply_edit: client.supports_apply_edit,
+            tools: crate::agent::tools::get_tool_definitions()
+                .iter()
+                .filter_map(|def| def["name"].as_str())
+                .filter(|name| !client.should_auto_deny(name))
+                .map(|name| name.to_string())
+                .collect(),
+        }
+    }
+}
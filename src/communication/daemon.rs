@@ -0,0 +1,518 @@
+use crate::communication::rpc::{ClientScope, RpcServer};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Environment variable overriding the Unix socket `oli-server daemon` listens on
+pub const DAEMON_SOCKET_ENV: &str = "OLI_DAEMON_SOCKET";
+
+/// Environment variable overriding the file `oli-server daemon` writes its token to
+pub const DAEMON_TOKEN_FILE_ENV: &str = "OLI_DAEMON_TOKEN_FILE";
+
+/// Method each connection must call first, with the daemon's token
+pub const AUTHENTICATE_METHOD: &str = "authenticate";
+
+/// How often the daemon checks for new connections and for a shutdown request
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Longest first line read from a connection before it authenticates
+const MAX_AUTHENTICATE_LINE: u64 = 4096;
+
+/// Where `oli-server daemon` listens
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DaemonConfig {
+    /// Unix domain socket to create
+    pub socket: Option<PathBuf>,
+    /// Port on 127.0.0.1 to listen on; 0 picks a free one
+    pub tcp_port: Option<u16>,
+    /// File the daemon writes the token clients authenticate with to; the default
+    /// location when None
+    pub token_file: Option<PathBuf>,
+}
+
+impl DaemonConfig {
+    /// Parse `[--socket PATH] [--tcp PORT] [--token-file PATH]`. Without a socket
+    /// or port, the daemon listens on the default socket.
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let mut config = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| anyhow::anyhow!("{arg} needs a value"))
+            };
+            match arg.as_str() {
+                "--socket" => config.socket = Some(PathBuf::from(value()?)),
+                "--token-file" => config.token_file = Some(PathBuf::from(value()?)),
+                "--tcp" => {
+                    let port = value()?;
+                    config.tcp_port = Some(
+                        port.parse()
+                            .with_context(|| format!("Invalid TCP port: {port}"))?,
+                    );
+                }
+                _ => anyhow::bail!("Unknown argument: {arg}"),
+            }
+        }
+
+        if config.socket.is_none() && config.tcp_port.is_none() {
+            config.socket = Some(Self::default_socket().ok_or_else(|| {
+                anyhow::anyhow!("Could not determine the socket location; pass --socket")
+            })?);
+        }
+        Ok(config)
+    }
+
+    /// `~/.oli/oli.sock` unless overridden by `OLI_DAEMON_SOCKET`
+    pub fn default_socket() -> Option<PathBuf> {
+        match std::env::var(DAEMON_SOCKET_ENV) {
+            Ok(path) if !path.trim().is_empty() => Some(PathBuf::from(path)),
+            _ => dirs::home_dir().map(|home| home.join(".oli").join("oli.sock")),
+        }
+    }
+
+    /// `~/.oli/daemon.token` unless overridden by `OLI_DAEMON_TOKEN_FILE`
+    pub fn default_token_file() -> Option<PathBuf> {
+        match std::env::var(DAEMON_TOKEN_FILE_ENV) {
+            Ok(path) if !path.trim().is_empty() => Some(PathBuf::from(path)),
+            _ => dirs::home_dir().map(|home| home.join(".oli").join("daemon.token")),
+        }
+    }
+}
+
+/// A socket clients connect to
+enum Listener {
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+    Tcp(TcpListener),
+}
+
+impl Listener {
+    fn accept(&self) -> std::io::Result<Connection> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(listener, _) => listener
+                .accept()
+                .map(|(stream, _)| Connection::Unix(stream)),
+            Self::Tcp(listener) => listener.accept().map(|(stream, _)| Connection::Tcp(stream)),
+        }
+    }
+
+    fn set_nonblocking(&self) -> std::io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(listener, _) => listener.set_nonblocking(true),
+            Self::Tcp(listener) => listener.set_nonblocking(true),
+        }
+    }
+
+    fn address(&self) -> String {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(_, path) => format!("unix:{}", path.display()),
+            Self::Tcp(listener) => listener
+                .local_addr()
+                .map(|address| format!("tcp:{address}"))
+                .unwrap_or_else(|_| "tcp".to_string()),
+        }
+    }
+}
+
+/// One client's connection
+enum Connection {
+    #[cfg(unix)]
+    Unix(UnixStream),
+    Tcp(TcpStream),
+}
+
+impl Connection {
+    fn try_clone(&self) -> std::io::Result<Self> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.try_clone().map(Self::Unix),
+            Self::Tcp(stream) => stream.try_clone().map(Self::Tcp),
+        }
+    }
+
+    fn set_blocking(&self) -> std::io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.set_nonblocking(false),
+            Self::Tcp(stream) => stream.set_nonblocking(false),
+        }
+    }
+
+    /// Stop reading requests, so the connection's loop ends once it has answered
+    /// the request it's handling
+    fn stop_reading(&self) {
+        let _ = match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.shutdown(Shutdown::Read),
+            Self::Tcp(stream) => stream.shutdown(Shutdown::Read),
+        };
+    }
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.read(buf),
+            Self::Tcp(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.write(buf),
+            Self::Tcp(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.flush(),
+            Self::Tcp(stream) => stream.flush(),
+        }
+    }
+}
+
+/// A long-lived backend that editors and other clients connect to over a Unix
+/// socket or a local TCP port. Each connection is served on its own thread with a
+/// session of its own. What a request's handling sends to the client goes to that
+/// connection alone, while notifications sent outside any request reach every client.
+///
+/// Clients first call `authenticate` with the token the daemon wrote to its token
+/// file, readable by this user only. A connection that sends anything else first,
+/// or a line that isn't JSON-RPC at any point, is closed.
+pub struct Daemon {
+    listeners: Vec<Listener>,
+    shutdown: Arc<AtomicBool>,
+    token: Arc<str>,
+    token_file: PathBuf,
+}
+
+impl Daemon {
+    /// Listen where `config` says. A socket file left behind by a daemon that
+    /// exited is replaced; one a daemon still listens on is an error.
+    pub fn bind(config: &DaemonConfig) -> Result<Self> {
+        let mut listeners = Vec::new();
+        if let Some(path) = &config.socket {
+            listeners.push(bind_socket(path)?);
+        }
+        if let Some(port) = config.tcp_port {
+            let listener = TcpListener::bind(("127.0.0.1", port))
+                .with_context(|| format!("Failed to listen on 127.0.0.1:{port}"))?;
+            listeners.push(Listener::Tcp(listener));
+        }
+        if listeners.is_empty() {
+            anyhow::bail!("The daemon needs a socket or a TCP port to listen on");
+        }
+
+        let token_file = match &config.token_file {
+            Some(path) => path.clone(),
+            None => DaemonConfig::default_token_file().ok_or_else(|| {
+                anyhow::anyhow!("Could not determine the token file location; pass --token-file")
+            })?,
+        };
+        let token = new_token();
+        write_token(&token_file, &token)?;
+
+        Ok(Self {
+            listeners,
+            shutdown: Arc::new(AtomicBool::new(false)),
+            token: token.into(),
+            token_file,
+        })
+    }
+
+    /// Token clients authenticate with
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// File the token is written to
+    pub fn token_file(&self) -> &std::path::Path {
+        &self.token_file
+    }
+
+    /// Where clients connect, e.g. `unix:/home/me/.oli/oli.sock` or `tcp:127.0.0.1:7777`
+    pub fn addresses(&self) -> Vec<String> {
+        self.listeners.iter().map(Listener::address).collect()
+    }
+
+    /// Flag that shuts the daemon down when set
+    pub fn shutdown_flag(&self) -> Arc<AtomicBool> {
+        self.shutdown.clone()
+    }
+
+    /// Shut down gracefully on SIGINT or SIGTERM
+    pub fn shutdown_on_signal(&self) {
+        let shutdown = self.shutdown.clone();
+        std::thread::spawn(move || {
+            let Ok(runtime) = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            else {
+                return;
+            };
+            runtime.block_on(async {
+                #[cfg(unix)]
+                {
+                    use tokio::signal::unix::{signal, SignalKind};
+                    if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+                        tokio::select! {
+                            _ = tokio::signal::ctrl_c() => {}
+                            _ = terminate.recv() => {}
+                        }
+                        return;
+                    }
+                }
+                let _ = tokio::signal::ctrl_c().await;
+            });
+            shutdown.store(true, Ordering::SeqCst);
+        });
+    }
+
+    /// Serve clients until shut down by the shutdown flag, a signal or a client's
+    /// `shutdown` request. `session` registers the methods of each new connection,
+    /// typically against state of its own. On shutdown, requests being handled are
+    /// answered before their connections close, and the socket and token files are
+    /// removed.
+    pub fn run<F>(self, hub: &RpcServer, session: F) -> Result<()>
+    where
+        F: Fn(&mut RpcServer) + Send + Sync + 'static,
+    {
+        for listener in &self.listeners {
+            listener
+                .set_nonblocking()
+                .context("Failed to configure the daemon's listener")?;
+        }
+        hub.start_daemon();
+
+        let session = Arc::new(session);
+        let connections: Arc<Mutex<HashMap<u64, Connection>>> = Arc::default();
+        let mut workers = Vec::new();
+        let mut next_id = 1;
+
+        while !self.shutdown.load(Ordering::SeqCst) {
+            let mut accepted = false;
+            for listener in &self.listeners {
+                let connection = match listener.accept() {
+                    Ok(connection) => connection,
+                    // Nothing to accept, or a client that gave up while connecting
+                    Err(_) => continue,
+                };
+                accepted = true;
+
+                let id = next_id;
+                next_id += 1;
+                let (Ok(()), Ok(handle)) = (connection.set_blocking(), connection.try_clone())
+                else {
+                    continue;
+                };
+                // Tracked before its thread starts, so a shutdown always reaches it
+                connections.lock().unwrap().insert(id, handle);
+
+                let hub = hub.clone();
+                let session = session.clone();
+                let connections = connections.clone();
+                let shutdown = self.shutdown.clone();
+                let token = self.token.clone();
+                workers.push(std::thread::spawn(move || {
+                    let _ = serve_connection(id, connection, &token, &hub, &*session, &shutdown);
+                    hub.remove_daemon_client(id);
+                    connections.lock().unwrap().remove(&id);
+                }));
+            }
+
+            workers.retain(|worker| !worker.is_finished());
+            if !accepted {
+                std::thread::sleep(ACCEPT_INTERVAL);
+            }
+        }
+
+        for connection in connections.lock().unwrap().values() {
+            connection.stop_reading();
+        }
+        for worker in workers {
+            let _ = worker.join();
+        }
+        hub.stop_daemon();
+
+        for listener in &self.listeners {
+            #[cfg(unix)]
+            if let Listener::Unix(_, path) = listener {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+        let _ = std::fs::remove_file(&self.token_file);
+        Ok(())
+    }
+}
+
+/// A random token of 256 bits, hex encoded
+fn new_token() -> String {
+    rand::random::<[u8; 32]>()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Write `token` to `path`, readable by this user only
+fn write_token(path: &std::path::Path, token: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // A file left by an earlier daemon keeps its mode, so restrict it first
+        if path.exists() {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+                .with_context(|| format!("Failed to restrict access to {}", path.display()))?;
+        }
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to write the daemon token to {}", path.display()))?;
+    file.write_all(token.as_bytes())
+        .with_context(|| format!("Failed to write the daemon token to {}", path.display()))
+}
+
+/// Compare tokens in time that doesn't depend on where they differ
+fn tokens_match(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Read the connection's first line and check it's an `authenticate` request with
+/// the daemon's token, answering it either way
+fn authenticate(reader: &mut impl BufRead, writer: &mut impl Write, token: &str) -> Result<bool> {
+    // Unauthenticated clients can't make the daemon buffer an endless line; a
+    // longer one is cut short, fails to parse and is refused
+    let mut line = String::new();
+    reader.take(MAX_AUTHENTICATE_LINE).read_line(&mut line)?;
+    let request = serde_json::from_str::<serde_json::Value>(&line).unwrap_or_default();
+    let authenticated = request["method"] == AUTHENTICATE_METHOD
+        && request["params"]["token"]
+            .as_str()
+            .is_some_and(|given| tokens_match(given, token));
+
+    let response = if authenticated {
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "result": { "success": true },
+        })
+    } else {
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": request.get("id"),
+            "error": { "code": -32001, "message": "Not authenticated" },
+        })
+    };
+    writeln!(writer, "{response}")?;
+    writer.flush()?;
+    Ok(authenticated)
+}
+
+/// Create the socket at `path`, readable by this user only
+#[cfg(unix)]
+fn bind_socket(path: &std::path::Path) -> Result<Listener> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            anyhow::bail!("A daemon is already listening on {}", path.display());
+        }
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to remove stale socket {}", path.display()))?;
+    }
+    let parent = path.parent().unwrap_or(std::path::Path::new(""));
+    std::fs::create_dir_all(parent)
+        .with_context(|| format!("Failed to create {}", parent.display()))?;
+
+    // Bind in a directory only this user can enter and restrict the socket there,
+    // so it's never open to other users, then move it into place
+    let private_dir = parent.join(format!(
+        ".oli-{}",
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    ));
+    std::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&private_dir)
+        .with_context(|| format!("Failed to create {}", private_dir.display()))?;
+    let staged = private_dir.join("sock");
+    let listener = UnixListener::bind(&staged)
+        .with_context(|| format!("Failed to listen on {}", path.display()))
+        .and_then(|listener| {
+            std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))
+                .with_context(|| format!("Failed to restrict access to {}", path.display()))?;
+            std::fs::rename(&staged, path)
+                .with_context(|| format!("Failed to listen on {}", path.display()))?;
+            Ok(listener)
+        });
+    let _ = std::fs::remove_file(&staged);
+    let _ = std::fs::remove_dir(&private_dir);
+    Ok(Listener::Unix(listener?, path.to_path_buf()))
+}
+
+#[cfg(not(unix))]
+fn bind_socket(_path: &std::path::Path) -> Result<Listener> {
+    anyhow::bail!("Unix sockets are not supported on this platform; use --tcp")
+}
+
+/// Serve one client with its own server until it disconnects or the daemon shuts down
+fn serve_connection(
+    id: u64,
+    mut connection: Connection,
+    token: &str,
+    hub: &RpcServer,
+    session: &(dyn Fn(&mut RpcServer) + Send + Sync),
+    shutdown: &Arc<AtomicBool>,
+) -> Result<()> {
+    let mut reader = BufReader::new(connection.try_clone()?);
+    if !authenticate(&mut reader, &mut connection, token)? {
+        return Ok(());
+    }
+
+    let reading = connection.try_clone()?;
+    let mut server = RpcServer::with_output(connection);
+    session(&mut server);
+
+    // Any client can stop the daemon, e.g. an editor that started it and is exiting
+    let shutdown = shutdown.clone();
+    server.register_method("shutdown", move |_| {
+        shutdown.store(true, Ordering::SeqCst);
+        Ok(serde_json::json!({ "success": true }))
+    });
+
+    hub.add_daemon_client(id, server.clone());
+    // Requests are handled within the connection's scope, so what they send to
+    // the client goes to this connection alone. A line that isn't JSON-RPC ends
+    // the connection, as the client is out of step with the protocol.
+    let served = ClientScope::of(&server).enter(|| server.serve_strict(reader));
+    // The server reads lines on a thread of its own, which stops once reading ends
+    reading.stop_reading();
+    served
+}
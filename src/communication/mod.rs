@@ -1 +1,3 @@
+pub mod capabilities;
+pub mod daemon;
 pub mod rpc;
@@ -1,10 +1,16 @@
+use crate::communication::capabilities::{
+    ClientCapabilities, ServerCapabilities, PROTOCOL_VERSION,
+};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::future::Future;
 use std::io::{BufRead, BufReader, Write};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::{Arc, Mutex, Once};
+use std::time::Duration;
 
 /// JSON-RPC 2.0 request structure
 #[derive(Debug, Deserialize)]
@@ -42,10 +48,82 @@ struct Notification {
     params: serde_json::Value,
 }
 
+/// JSON-RPC 2.0 request sent by the server to the client
+#[derive(Debug, Serialize)]
+struct ClientRequest {
+    jsonrpc: String,
+    id: u64,
+    method: String,
+    params: serde_json::Value,
+}
+
+/// Waiters for the client's answers to server requests, by request id
+type PendingClientRequests = Arc<Mutex<HashMap<u64, Sender<Result<serde_json::Value>>>>>;
+
 /// Method handler type
 type MethodHandler =
     Box<dyn Fn(serde_json::Value) -> Result<serde_json::Value, anyhow::Error> + Send + Sync>;
 
+/// Handler for methods that report incremental results while they run
+type StreamingHandler = Box<
+    dyn Fn(serde_json::Value, &ResponseStream) -> Result<serde_json::Value, anyhow::Error>
+        + Send
+        + Sync,
+>;
+
+/// Handler for methods that run on their own thread
+type BackgroundHandler =
+    Arc<dyn Fn(serde_json::Value) -> Result<serde_json::Value, anyhow::Error> + Send + Sync>;
+
+/// Where a client's responses and notifications are written, locked per message
+type ClientOutput = Arc<Mutex<Box<dyn Write + Send>>>;
+
+/// Servers of the clients connected to a daemon, by connection id. None unless
+/// the server runs as a daemon.
+type DaemonClients = Arc<Mutex<Option<HashMap<u64, RpcServer>>>>;
+
+/// Writes a notification with the given method and params
+type NotificationSink = Box<dyn Fn(&str, serde_json::Value) + Send + Sync>;
+
+/// Sends incremental results of one request as notifications carrying its id,
+/// so clients can match them to the request they made
+pub struct ResponseStream {
+    request_id: Option<u64>,
+    sink: NotificationSink,
+}
+
+impl ResponseStream {
+    pub fn new(
+        request_id: Option<u64>,
+        sink: impl Fn(&str, serde_json::Value) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            request_id,
+            sink: Box::new(sink),
+        }
+    }
+
+    pub fn request_id(&self) -> Option<u64> {
+        self.request_id
+    }
+
+    /// Send a `content_delta` notification with newly generated text
+    pub fn delta(&self, text: &str) {
+        (self.sink)(
+            "content_delta",
+            serde_json::json!({ "request_id": self.request_id, "delta": text }),
+        );
+    }
+
+    /// Send the final `done` notification with usage statistics
+    pub fn done(&self, usage: serde_json::Value) {
+        (self.sink)(
+            "done",
+            serde_json::json!({ "request_id": self.request_id, "usage": usage }),
+        );
+    }
+}
+
 /// Subscription manager for event-based communication
 pub struct SubscriptionManager {
     subscribers: HashMap<String, Vec<u64>>, // event_type -> list of subscription IDs
@@ -100,15 +178,25 @@ impl SubscriptionManager {
     }
 }
 
-/// JSON-RPC server over stdio
+/// JSON-RPC server over stdio, or over one connection of a daemon
 pub struct RpcServer {
     methods: Arc<Mutex<HashMap<String, MethodHandler>>>,
+    streaming_methods: Arc<Mutex<HashMap<String, StreamingHandler>>>,
+    background_methods: Arc<Mutex<HashMap<String, BackgroundHandler>>>,
     event_sender: Sender<(String, serde_json::Value)>,
     // Replace the standard mpsc::Receiver with an Arc<Mutex<>> wrapper to make it thread-safe
     event_receiver: Arc<Mutex<Receiver<(String, serde_json::Value)>>>,
     is_running: Arc<AtomicBool>,
     // Add subscription manager for real-time event streaming
     subscription_manager: Arc<Mutex<SubscriptionManager>>,
+    // Capabilities declared by the client, None until the initialize handshake
+    client_capabilities: Arc<Mutex<Option<ClientCapabilities>>>,
+    // Requests sent to the client that are waiting for its answer
+    pending_client_requests: PendingClientRequests,
+    client_request_counter: Arc<AtomicU64>,
+    output: ClientOutput,
+    // Clients connected while this server runs as a daemon
+    daemon_clients: DaemonClients,
 }
 
 // Global RPC server instance
@@ -123,17 +211,70 @@ impl Clone for RpcServer {
 
         Self {
             methods: self.methods.clone(),
+            streaming_methods: self.streaming_methods.clone(),
+            background_methods: self.background_methods.clone(),
             event_sender,
             event_receiver: Arc::new(Mutex::new(event_receiver)),
             is_running: self.is_running.clone(),
             subscription_manager: self.subscription_manager.clone(),
+            client_capabilities: self.client_capabilities.clone(),
+            pending_client_requests: self.pending_client_requests.clone(),
+            client_request_counter: self.client_request_counter.clone(),
+            output: self.output.clone(),
+            daemon_clients: self.daemon_clients.clone(),
         }
     }
 }
 
-/// Get global RPC server instance
+thread_local! {
+    static CLIENT_THREAD: RefCell<Option<Arc<RpcServer>>> = const { RefCell::new(None) };
+}
+
+tokio::task_local! {
+    static CLIENT_TASK: Option<Arc<RpcServer>>;
+}
+
+/// The daemon connection a request came in on, carried to the threads and tasks
+/// that handle it, so notifications and requests to the client reach that
+/// connection only. Empty outside a daemon connection.
+#[derive(Clone, Default)]
+pub struct ClientScope(Option<Arc<RpcServer>>);
+
+impl ClientScope {
+    /// The connection of the request being handled on this thread or task
+    pub fn current() -> Self {
+        let task = CLIENT_TASK.try_with(Clone::clone).ok().flatten();
+        Self(task.or_else(|| CLIENT_THREAD.with(|client| client.borrow().clone())))
+    }
+
+    /// Scope of one connection's server
+    pub(crate) fn of(server: &RpcServer) -> Self {
+        #[allow(clippy::arc_with_non_send_sync)]
+        Self(Some(Arc::new(server.clone())))
+    }
+
+    /// Run `f` on this thread within the scope
+    pub fn enter<R>(&self, f: impl FnOnce() -> R) -> R {
+        let previous = CLIENT_THREAD.with(|client| client.replace(self.0.clone()));
+        let result = f();
+        CLIENT_THREAD.with(|client| *client.borrow_mut() = previous);
+        result
+    }
+
+    /// Run `future` within the scope, wherever the runtime polls it, e.g. in a
+    /// task passed to `tokio::spawn`
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CLIENT_TASK.scope(self.0, future).await
+    }
+}
+
+/// Get global RPC server instance. While a daemon connection's request is
+/// handled, that connection's server.
 #[allow(static_mut_refs)]
 pub fn get_global_rpc_server() -> Option<Arc<RpcServer>> {
+    if let Some(client) = ClientScope::current().0 {
+        return Some(client);
+    }
     unsafe { GLOBAL_RPC_SERVER.clone() }
 }
 
@@ -147,14 +288,7 @@ fn set_global_rpc_server(server: Arc<RpcServer>) {
 impl RpcServer {
     /// Create a new RPC server
     pub fn new() -> Self {
-        let (event_sender, event_receiver) = channel();
-        let server = Self {
-            methods: Arc::new(Mutex::new(HashMap::new())),
-            event_sender,
-            event_receiver: Arc::new(Mutex::new(event_receiver)),
-            is_running: Arc::new(AtomicBool::new(false)),
-            subscription_manager: Arc::new(Mutex::new(SubscriptionManager::new())),
-        };
+        let server = Self::with_output(std::io::stdout());
 
         // Create a clone for global registration
         let server_clone = server.clone();
@@ -168,6 +302,26 @@ impl RpcServer {
         server
     }
 
+    /// Create an RPC server writing to `output` instead of stdout, e.g. for one
+    /// client of a daemon. Unlike `new`, it doesn't become the global server.
+    pub fn with_output(output: impl Write + Send + 'static) -> Self {
+        let (event_sender, event_receiver) = channel();
+        Self {
+            methods: Arc::new(Mutex::new(HashMap::new())),
+            streaming_methods: Arc::new(Mutex::new(HashMap::new())),
+            background_methods: Arc::new(Mutex::new(HashMap::new())),
+            event_sender,
+            event_receiver: Arc::new(Mutex::new(event_receiver)),
+            is_running: Arc::new(AtomicBool::new(false)),
+            subscription_manager: Arc::new(Mutex::new(SubscriptionManager::new())),
+            client_capabilities: Arc::new(Mutex::new(None)),
+            pending_client_requests: Arc::new(Mutex::new(HashMap::new())),
+            client_request_counter: Arc::new(AtomicU64::new(1)),
+            output: Arc::new(Mutex::new(Box::new(output))),
+            daemon_clients: Arc::new(Mutex::new(None)),
+        }
+    }
+
     /// Register a method handler
     pub fn register_method<F>(&mut self, name: &str, handler: F)
     where
@@ -182,6 +336,97 @@ impl RpcServer {
             .insert(name.to_string(), Box::new(handler));
     }
 
+    /// Register a handler that can send notifications tied to the request while it runs
+    pub fn register_streaming_method<F>(&mut self, name: &str, handler: F)
+    where
+        F: Fn(serde_json::Value, &ResponseStream) -> Result<serde_json::Value, anyhow::Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.streaming_methods
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), Box::new(handler));
+    }
+
+    /// Register a handler that runs on its own thread, so requests arriving while
+    /// it works are answered without waiting for it. Its response is written when
+    /// it returns, possibly after responses to later requests.
+    pub fn register_background_method<F>(&mut self, name: &str, handler: F)
+    where
+        F: Fn(serde_json::Value) -> Result<serde_json::Value, anyhow::Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.background_methods
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), Arc::new(handler));
+    }
+
+    /// Call a registered method directly, bypassing stdio. Notifications from
+    /// streaming methods go through the event channel.
+    pub fn call_method(
+        &self,
+        name: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, anyhow::Error> {
+        if self.streaming_methods.lock().unwrap().contains_key(name) {
+            let event_sender = Mutex::new(self.event_sender.clone());
+            let stream = ResponseStream::new(None, move |method, params| {
+                let _ = event_sender
+                    .lock()
+                    .unwrap()
+                    .send((method.to_string(), params));
+            });
+            return self.call_streaming_method(name, params, &stream);
+        }
+
+        let background = self.background_methods.lock().unwrap().get(name).cloned();
+        if let Some(handler) = background {
+            return handler(params);
+        }
+
+        let methods = self.methods.lock().unwrap();
+        let handler = methods
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("Method not found: {}", name))?;
+        handler(params)
+    }
+
+    /// Call a registered streaming method directly, sending its notifications to `stream`
+    pub fn call_streaming_method(
+        &self,
+        name: &str,
+        params: serde_json::Value,
+        stream: &ResponseStream,
+    ) -> Result<serde_json::Value, anyhow::Error> {
+        let methods = self.streaming_methods.lock().unwrap();
+        let handler = methods
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("Method not found: {}", name))?;
+        handler(params, stream)
+    }
+
+    /// Get the capabilities declared by the client, if it has sent `initialize`. A
+    /// daemon has no client of its own; its connections answer for theirs.
+    pub fn client_capabilities(&self) -> Option<ClientCapabilities> {
+        self.client_capabilities.lock().unwrap().clone()
+    }
+
+    /// Store the capabilities declared by the client
+    pub fn set_client_capabilities(&self, capabilities: ClientCapabilities) {
+        *self.client_capabilities.lock().unwrap() = Some(capabilities);
+    }
+
+    /// Whether notifications should be written to stdout for the connected client
+    fn client_wants_notifications(&self) -> bool {
+        self.client_capabilities()
+            .is_none_or(|caps| caps.supports_notifications)
+    }
+
     /// Get event sender for emitting events
     pub fn event_sender(&self) -> Sender<(String, serde_json::Value)> {
         self.event_sender.clone()
@@ -209,22 +454,122 @@ impl RpcServer {
             return Ok(());
         }
 
-        // Forward all notifications via stdout
-        // For events with subscribers, we'll immediately send a notification through stdout
         let notification = Notification {
             jsonrpc: "2.0".to_string(),
             method: method.to_string(),
             params: params.clone(),
         };
 
-        // Send directly to stdout to ensure immediate delivery
-        let stdout = std::io::stdout();
-        let mut stdout = stdout.lock();
-        serde_json::to_writer(&mut stdout, &notification)?;
-        stdout.write_all(b"\n")?;
-        stdout.flush()?;
+        // Notifications not sent on behalf of a connection, e.g. indexing progress,
+        // go to every client of a daemon that reads them
+        if self.is_daemon() {
+            let outputs: Vec<ClientOutput> = self
+                .daemon_clients
+                .lock()
+                .unwrap()
+                .iter()
+                .flat_map(|clients| clients.values())
+                .filter(|client| client.client_wants_notifications())
+                .map(|client| client.output.clone())
+                .collect();
+            for output in outputs {
+                let _ = write_line(&output, &notification);
+            }
+            return Ok(());
+        }
 
-        Ok(())
+        // Clients that declared they don't read notifications never receive them
+        if !self.client_wants_notifications() {
+            return Ok(());
+        }
+
+        // Write directly to the output to ensure immediate delivery
+        write_line(&self.output, &notification)
+    }
+
+    /// Send a request to the client and wait for its answer. Only a running server
+    /// reads answers, and only while no method blocks its loop, so call this from
+    /// background methods. A daemon asks through the server of the connection
+    /// whose request is being handled, which `get_global_rpc_server` returns.
+    pub fn request_client(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+        timeout: Duration,
+    ) -> Result<serde_json::Value> {
+        if self.is_daemon() {
+            anyhow::bail!("{method} was not sent on behalf of a connected client");
+        }
+        if !self.is_running() {
+            anyhow::bail!("No client is connected");
+        }
+
+        let id = self.client_request_counter.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = channel();
+        self.pending_client_requests
+            .lock()
+            .unwrap()
+            .insert(id, sender);
+
+        let request = ClientRequest {
+            jsonrpc: "2.0".to_string(),
+            id,
+            method: method.to_string(),
+            params,
+        };
+        let answer = write_line(&self.output, &request).and_then(|_| {
+            receiver.recv_timeout(timeout).map_err(|_| {
+                anyhow::anyhow!(
+                    "The client did not answer {method} within {}s",
+                    timeout.as_secs()
+                )
+            })?
+        });
+        self.pending_client_requests.lock().unwrap().remove(&id);
+        answer
+    }
+
+    /// Hand the client's answer to a server request to whoever is waiting for it.
+    /// Returns false when no request with its id is waiting.
+    pub fn resolve_client_request(&self, message: &serde_json::Value) -> bool {
+        let Some(id) = message["id"].as_u64() else {
+            return false;
+        };
+        let Some(sender) = self.pending_client_requests.lock().unwrap().remove(&id) else {
+            return false;
+        };
+
+        let answer = match message.get("error") {
+            Some(error) if !error.is_null() => Err(anyhow::anyhow!(
+                "{}",
+                error["message"]
+                    .as_str()
+                    .unwrap_or("The client returned an error")
+            )),
+            _ => Ok(message["result"].clone()),
+        };
+        sender.send(answer).is_ok()
+    }
+
+    /// Register the `initialize` handshake handler for capability negotiation
+    pub fn register_initialize_handler(&mut self, version: &str) {
+        let client_capabilities = self.client_capabilities.clone();
+        let version = version.to_string();
+        self.register_method("initialize", move |params| {
+            let capabilities = ClientCapabilities::from_params(&params);
+            let server_capabilities = ServerCapabilities::negotiate(&capabilities);
+
+            *client_capabilities.lock().unwrap() = Some(capabilities);
+
+            Ok(serde_json::json!({
+                "server_info": {
+                    "name": "oli-server",
+                    "version": version
+                },
+                "protocol_version": PROTOCOL_VERSION,
+                "capabilities": server_capabilities
+            }))
+        });
     }
 
     /// Register subscription method handlers
@@ -270,18 +615,59 @@ impl RpcServer {
 
     /// Run the RPC server, processing stdin and writing to stdout
     pub fn run(&self) -> Result<()> {
+        self.serve(BufReader::new(std::io::stdin()))
+    }
+
+    /// Process each line read from `reader` as a JSON-RPC request, writing to this
+    /// server's output, until the reader ends
+    pub fn serve(&self, reader: impl BufRead + Send + 'static) -> Result<()> {
+        self.serve_lines(reader, false)
+    }
+
+    /// Like `serve`, but stop at the first line that isn't a JSON-RPC request after
+    /// answering it with a parse error, for clients that aren't trusted to stay in sync
+    pub fn serve_strict(&self, reader: impl BufRead + Send + 'static) -> Result<()> {
+        self.serve_lines(reader, true)
+    }
+
+    fn serve_lines(
+        &self,
+        reader: impl BufRead + Send + 'static,
+        stop_on_parse_error: bool,
+    ) -> Result<()> {
         // Set running state
         self.is_running.store(true, Ordering::SeqCst);
 
-        let stdin = std::io::stdin();
-        let stdout = std::io::stdout();
-        let mut stdout = stdout.lock();
-
-        let reader = BufReader::new(stdin.lock());
         let methods = self.methods.clone();
 
+        // Lines are read on a thread of their own, which hands answers to requests
+        // the server sent straight to whoever waits for them. A method blocking this
+        // loop, e.g. on the app a background method holds while it waits for the
+        // user's answer, can then no longer keep that answer from being read.
+        let (line_sender, lines) = channel();
+        let answers = self.clone();
+        std::thread::spawn(move || {
+            for line in reader.lines() {
+                let failed = line.is_err();
+                if let Ok(line) = &line {
+                    // Answers to requests the server sent carry no method
+                    if let Ok(message) = serde_json::from_str::<serde_json::Value>(line) {
+                        if message.get("method").is_none()
+                            && (message.get("result").is_some() || message.get("error").is_some())
+                        {
+                            answers.resolve_client_request(&message);
+                            continue;
+                        }
+                    }
+                }
+                if line_sender.send(line).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
         // Process each line of input as a JSON-RPC request
-        for line in reader.lines() {
+        for line in lines {
             let line = line?;
             if line.trim().is_empty() {
                 continue;
@@ -302,81 +688,75 @@ impl RpcServer {
                             data: Some(serde_json::Value::String(e.to_string())),
                         }),
                     };
-                    serde_json::to_writer(&mut stdout, &response)?;
-                    stdout.write_all(b"\n")?;
-                    stdout.flush()?;
+                    write_line(&self.output, &response)?;
+                    if stop_on_parse_error {
+                        break;
+                    }
                     continue;
                 }
             };
+            // Background methods answer from their own thread, followed by the events
+            // they queued, while this loop goes on reading requests
+            let background = self
+                .background_methods
+                .lock()
+                .unwrap()
+                .get(&request.method)
+                .cloned();
+            if let Some(handler) = background {
+                let server = self.clone_with_events();
+                let client = ClientScope::current();
+                std::thread::spawn(move || {
+                    let result = client.enter(|| handler(request.params));
+                    let _ = write_line(&server.output, &method_response(request.id, result));
+                    let _ = server.flush_events();
+                });
+                continue;
+            }
 
             // Check for method
+            let streaming_methods = self.streaming_methods.lock().unwrap();
             let methods = methods.lock().unwrap();
-            let handler = match methods.get(&request.method) {
-                Some(handler) => handler,
-                None => {
-                    // Send method not found error
-                    let response = Response {
+            let result = if let Some(handler) = streaming_methods.get(&request.method) {
+                // Streaming methods write their notifications straight to stdout while
+                // they run, before the response
+                let send_notifications = self.client_wants_notifications();
+                let output = self.output.clone();
+                let stream = ResponseStream::new(request.id, move |method, params| {
+                    if !send_notifications {
+                        return;
+                    }
+                    let notification = Notification {
                         jsonrpc: "2.0".to_string(),
-                        id: request.id,
-                        result: None,
-                        error: Some(RpcError {
-                            code: -32601,
-                            message: "Method not found".to_string(),
-                            data: None,
-                        }),
+                        method: method.to_string(),
+                        params,
                     };
-                    serde_json::to_writer(&mut stdout, &response)?;
-                    stdout.write_all(b"\n")?;
-                    stdout.flush()?;
-                    continue;
-                }
+                    let _ = write_line(&output, &notification);
+                });
+                handler(request.params.clone(), &stream)
+            } else if let Some(handler) = methods.get(&request.method) {
+                handler(request.params.clone())
+            } else {
+                // Send method not found error
+                let response = Response {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    result: None,
+                    error: Some(RpcError {
+                        code: -32601,
+                        message: "Method not found".to_string(),
+                        data: None,
+                    }),
+                };
+                write_line(&self.output, &response)?;
+                continue;
             };
 
-            // Execute the method
-            match handler(request.params.clone()) {
-                Ok(result) => {
-                    // Send success response
-                    let response = Response {
-                        jsonrpc: "2.0".to_string(),
-                        id: request.id,
-                        result: Some(result),
-                        error: None,
-                    };
-                    serde_json::to_writer(&mut stdout, &response)?;
-                    stdout.write_all(b"\n")?;
-                    stdout.flush()?;
-                }
-                Err(e) => {
-                    // Send error response
-                    let response = Response {
-                        jsonrpc: "2.0".to_string(),
-                        id: request.id,
-                        result: None,
-                        error: Some(RpcError {
-                            code: -32603,
-                            message: "Internal error".to_string(),
-                            data: Some(serde_json::Value::String(e.to_string())),
-                        }),
-                    };
-                    serde_json::to_writer(&mut stdout, &response)?;
-                    stdout.write_all(b"\n")?;
-                    stdout.flush()?;
-                }
-            };
+            // Send the method's result
+            write_line(&self.output, &method_response(request.id, result))?;
 
             // Check for any events to send
-            if let Ok(receiver) = self.event_receiver.try_lock() {
-                while let Ok((method, params)) = receiver.try_recv() {
-                    let notification = Notification {
-                        jsonrpc: "2.0".to_string(),
-                        method,
-                        params,
-                    };
-                    serde_json::to_writer(&mut stdout, &notification)?;
-                    stdout.write_all(b"\n")?;
-                    stdout.flush()?;
-                }
-            }
+            self.flush_events()?;
         }
 
         // Set running state to false
@@ -384,6 +764,97 @@ impl RpcServer {
 
         Ok(())
     }
+
+    /// Whether this server runs as a daemon, serving clients on their own connections
+    fn is_daemon(&self) -> bool {
+        self.daemon_clients.lock().unwrap().is_some()
+    }
+
+    /// Start serving clients as a daemon; until `stop_daemon`, notifications go to
+    /// the clients added with `add_daemon_client`
+    pub(crate) fn start_daemon(&self) {
+        *self.daemon_clients.lock().unwrap() = Some(HashMap::new());
+        self.is_running.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn stop_daemon(&self) {
+        *self.daemon_clients.lock().unwrap() = None;
+        self.is_running.store(false, Ordering::SeqCst);
+    }
+
+    /// Track the server of a client that connected to the daemon
+    pub(crate) fn add_daemon_client(&self, id: u64, client: RpcServer) {
+        if let Some(clients) = self.daemon_clients.lock().unwrap().as_mut() {
+            clients.insert(id, client);
+        }
+    }
+
+    pub(crate) fn remove_daemon_client(&self, id: u64) {
+        if let Some(clients) = self.daemon_clients.lock().unwrap().as_mut() {
+            clients.remove(&id);
+        }
+    }
+
+    /// A handle sharing this server's methods, capabilities and event channel, for
+    /// flushing events from another thread
+    fn clone_with_events(&self) -> Self {
+        Self {
+            event_sender: self.event_sender.clone(),
+            event_receiver: self.event_receiver.clone(),
+            ..self.clone()
+        }
+    }
+
+    /// Write the events queued on the channel as notifications
+    fn flush_events(&self) -> Result<()> {
+        let send_events = self.client_wants_notifications();
+        if let Ok(receiver) = self.event_receiver.try_lock() {
+            while let Ok((method, params)) = receiver.try_recv() {
+                if !send_events {
+                    continue;
+                }
+                let notification = Notification {
+                    jsonrpc: "2.0".to_string(),
+                    method,
+                    params,
+                };
+                write_line(&self.output, &notification)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Response carrying a method's result, or an internal error
+fn method_response(id: Option<u64>, result: Result<serde_json::Value>) -> Response {
+    match result {
+        Ok(result) => Response {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: Some(result),
+            error: None,
+        },
+        Err(e) => Response {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: None,
+            error: Some(RpcError {
+                code: -32603,
+                message: "Internal error".to_string(),
+                data: Some(serde_json::Value::String(e.to_string())),
+            }),
+        },
+    }
+}
+
+/// Write one JSON message per line. The output is locked for each message only,
+/// so background methods can write between them.
+fn write_line(output: &ClientOutput, message: &impl Serialize) -> Result<()> {
+    let mut output = output.lock().unwrap();
+    serde_json::to_writer(&mut *output, message)?;
+    output.write_all(b"\n")?;
+    output.flush()?;
+    Ok(())
 }
 
 impl Default for RpcServer {
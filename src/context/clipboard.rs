@@ -0,0 +1,30 @@
+use anyhow::Result;
+use std::process::Command;
+
+/// Commands that print the system clipboard, tried in order until one succeeds
+const CLIPBOARD_COMMANDS: &[(&str, &[&str])] = &[
+    ("pbpaste", &[]),
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-selection", "clipboard", "-o"]),
+    ("xsel", &["--clipboard", "--output"]),
+    (
+        "powershell.exe",
+        &["-NoProfile", "-Command", "Get-Clipboard"],
+    ),
+];
+
+/// Read text from the system clipboard with the platform's paste command
+pub fn read_clipboard() -> Result<String> {
+    for (program, args) in CLIPBOARD_COMMANDS {
+        let Ok(output) = Command::new(program).args(*args).output() else {
+            continue;
+        };
+        if output.status.success() {
+            return Ok(String::from_utf8_lossy(&output.stdout).replace("\r\n", "\n"));
+        }
+    }
+
+    Err(anyhow::anyhow!(
+        "Could not read the clipboard. Install pbpaste, wl-paste, xclip or xsel."
+    ))
+}
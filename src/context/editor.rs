@@ -0,0 +1,148 @@
+use crate::context::provider::{ContextProvider, ContextRequest};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Name of the editor context provider
+pub const EDITOR_CONTEXT_PROVIDER: &str = "editor";
+/// Longest selection added to the prompt, in characters
+pub const MAX_SELECTION_CHARS: usize = 4_000;
+/// Most other open files listed
+const MAX_OPEN_FILES: usize = 20;
+
+/// A position in a document, zero-based like LSP positions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditorPosition {
+    pub line: u32,
+    pub character: u32,
+}
+
+/// The text the user selected
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditorSelection {
+    pub start: EditorPosition,
+    pub end: EditorPosition,
+    pub text: Option<String>,
+}
+
+/// What the user has open in their editor, sent by an editor extension with
+/// `set_editor_context`, so prompts like "fix this" or "explain the selection"
+/// refer to the right code
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditorContext {
+    /// The file in the focused editor
+    pub file: Option<String>,
+    pub cursor: Option<EditorPosition>,
+    pub selection: Option<EditorSelection>,
+    /// Other files open in the editor
+    #[serde(default)]
+    pub open_files: Vec<String>,
+}
+
+impl EditorContext {
+    /// Parse the params of `set_editor_context`. An empty selection counts as none.
+    pub fn from_params(params: &serde_json::Value) -> Result<Self> {
+        let mut context: Self = serde_json::from_value(params.clone())
+            .map_err(|e| anyhow::anyhow!("Invalid editor context: {e}"))?;
+        context.file = context.file.filter(|file| !file.trim().is_empty());
+        context.selection = context
+            .selection
+            .filter(|selection| selection.start != selection.end);
+        Ok(context)
+    }
+
+    /// Whether there's nothing to tell the model
+    pub fn is_empty(&self) -> bool {
+        self.file.is_none() && self.open_files.is_empty()
+    }
+
+    /// The editor section of the system prompt, with paths relative to
+    /// `working_dir` where they're inside it; None when the context is empty
+    pub fn section(&self, working_dir: Option<&str>) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        let display = |path: &str| match working_dir {
+            Some(dir) => Path::new(path)
+                .strip_prefix(dir)
+                .map(|relative| relative.display().to_string())
+                .unwrap_or_else(|_| path.to_string()),
+            None => path.to_string(),
+        };
+
+        let mut lines = vec!["## EDITOR".to_string()];
+        if let Some(file) = &self.file {
+            let cursor = self
+                .cursor
+                .map(|cursor| {
+                    format!(
+                        ", with the cursor at line {}, column {}",
+                        cursor.line + 1,
+                        cursor.character + 1
+                    )
+                })
+                .unwrap_or_default();
+            lines.push(format!(
+                "The user is looking at `{}` in their editor{cursor}. \"This file\" or \"here\" in the request refers to it.",
+                display(file)
+            ));
+        }
+
+        if let Some(selection) = &self.selection {
+            lines.push(format!(
+                "Selected: lines {}-{}. \"This\" or \"the selection\" in the request refers to it.",
+                selection.start.line + 1,
+                selection.end.line + 1
+            ));
+            if let Some(text) = selection.text.as_deref().filter(|text| !text.is_empty()) {
+                let total = text.chars().count();
+                let shown: String = text.chars().take(MAX_SELECTION_CHARS).collect();
+                let fence = "`".repeat(3.max(longest_backtick_run(&shown) + 1));
+                lines.push(format!(
+                    "{fence}\n{}\n{fence}",
+                    shown.trim_end_matches('\n')
+                ));
+                if total > MAX_SELECTION_CHARS {
+                    lines.push(format!(
+                        "[Selection cut to {MAX_SELECTION_CHARS} of {total} characters]"
+                    ));
+                }
+            }
+        }
+
+        let others: Vec<String> = self
+            .open_files
+            .iter()
+            .filter(|path| Some(*path) != self.file.as_ref())
+            .take(MAX_OPEN_FILES)
+            .map(|path| format!("`{}`", display(path)))
+            .collect();
+        if !others.is_empty() {
+            lines.push(format!("Also open: {}", others.join(", ")));
+        }
+        Some(lines.join("\n"))
+    }
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+impl ContextProvider for EditorContext {
+    fn name(&self) -> &str {
+        EDITOR_CONTEXT_PROVIDER
+    }
+
+    // What the user is looking at matters more than the history of other files
+    fn priority(&self) -> i32 {
+        20
+    }
+
+    fn token_budget(&self) -> usize {
+        1_500
+    }
+
+    fn provide(&self, request: &ContextRequest) -> Option<String> {
+        self.section(request.working_directory)
+    }
+}
@@ -0,0 +1,108 @@
+use crate::tools::fs::fuzzy::{find_files, indexed_files};
+use crate::tools::fs::oliignore;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Largest file attached for an `@` mention, in bytes; larger files get a hint to
+/// read them with the Read tool instead
+pub const MAX_MENTIONED_FILE_BYTES: u64 = 64 * 1024;
+
+/// Most files attached to one prompt
+pub const MAX_MENTIONED_FILES: usize = 10;
+
+/// Paths mentioned in a prompt as `@path`, e.g. `explain @src/app/core.rs`, in the
+/// order they appear. Mentions start the prompt or follow whitespace, so email
+/// addresses aren't taken for them, and trailing punctuation isn't part of them.
+pub fn file_mentions(prompt: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+    for word in prompt.split_whitespace() {
+        let Some(path) = word.strip_prefix('@') else {
+            continue;
+        };
+        let path = path.trim_end_matches(|c: char| ".,;:!?)]}'\"`".contains(c));
+        if !path.is_empty() && !mentions.iter().any(|m| m == path) {
+            mentions.push(path.to_string());
+        }
+    }
+    mentions
+}
+
+/// Attach the files a prompt mentions with `@path` under a `## MENTIONED FILES`
+/// heading, resolving relative paths against `working_dir`. Mentions that aren't
+/// files, like `@someone`, are left alone; directories, large and binary files and
+/// paths excluded by `.oliignore` get a note instead of their contents.
+pub fn expand_file_mentions(prompt: &str, working_dir: &Path) -> String {
+    let mut attached = String::new();
+    let mut count = 0;
+    for mention in file_mentions(prompt) {
+        let path = working_dir.join(&mention);
+        let Ok(metadata) = path.metadata() else {
+            continue;
+        };
+        if count == MAX_MENTIONED_FILES {
+            let _ = writeln!(
+                attached,
+                "\nOnly the first {MAX_MENTIONED_FILES} mentioned paths are attached; use the Read tool for the rest."
+            );
+            break;
+        }
+        count += 1;
+
+        let note = if metadata.is_dir() {
+            Some("is a directory; use the LS or Glob tool to see what it contains".to_string())
+        } else if oliignore::check_path(&path).is_err() {
+            Some("is excluded by .oliignore and was not attached".to_string())
+        } else if metadata.len() > MAX_MENTIONED_FILE_BYTES {
+            Some(format!(
+                "is too large to attach ({} bytes); use the Read tool with an offset and limit",
+                metadata.len()
+            ))
+        } else {
+            match read_text(&path) {
+                Some(content) => {
+                    let _ = writeln!(
+                        attached,
+                        "\n<file path=\"{mention}\">\n{}\n</file>",
+                        content.trim_end()
+                    );
+                    None
+                }
+                None => Some("is a binary file and was not attached".to_string()),
+            }
+        };
+        if let Some(note) = note {
+            let _ = writeln!(attached, "\n{mention} {note}.");
+        }
+    }
+
+    if attached.is_empty() {
+        prompt.to_string()
+    } else {
+        format!("{prompt}\n\n## MENTIONED FILES\n{}", attached.trim_end())
+    }
+}
+
+/// Files under `dir` to offer for a mention being typed, best fuzzy matches for
+/// `query` first, as paths relative to `dir`. An empty query lists files in order.
+pub fn complete_file_path(query: &str, dir: &Path, limit: usize) -> Vec<String> {
+    let paths: Vec<_> = if query.trim().is_empty() {
+        indexed_files(dir).into_iter().take(limit).collect()
+    } else {
+        find_files(query, dir, limit)
+            .map(|matches| matches.into_iter().map(|m| m.path).collect())
+            .unwrap_or_default()
+    };
+    paths
+        .iter()
+        .map(|path| path.to_string_lossy().replace('\\', "/"))
+        .collect()
+}
+
+// The file's text, or None when it isn't UTF-8 text
+fn read_text(path: &Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    if bytes.contains(&0) {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
@@ -0,0 +1,160 @@
+use crate::context::provider::{ContextProvider, ContextRequest};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Number of recent commits listed for each mentioned file
+const COMMITS_PER_FILE: usize = 3;
+/// Maximum number of mentioned files to look up per prompt
+const MAX_FILES: usize = 3;
+
+/// Name of the git history context provider
+pub const GIT_HISTORY_PROVIDER: &str = "git_history";
+
+/// Context provider that adds a short digest of the recent git history of
+/// files mentioned in a prompt, so the model can respect in-flight work and
+/// recent design decisions
+#[derive(Debug, Clone)]
+pub struct GitHistory {
+    repo_dir: PathBuf,
+}
+
+/// A commit touching a mentioned file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitSummary {
+    pub hash: String,
+    pub author: String,
+    pub relative_date: String,
+    pub subject: String,
+}
+
+impl GitHistory {
+    pub fn new(repo_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            repo_dir: repo_dir.into(),
+        }
+    }
+
+    /// Files in the repository that are mentioned in the prompt, in order of appearance
+    pub fn mentioned_files(&self, prompt: &str) -> Vec<String> {
+        let mut files: Vec<String> = Vec::new();
+
+        for word in prompt.split_whitespace() {
+            let candidate = word
+                .trim_matches(|c: char| "`'\"()[]{}<>,;:!?".contains(c))
+                .trim_end_matches('.')
+                .trim_start_matches("./")
+                .trim_start_matches('@');
+
+            // Only consider words that look like file names
+            if candidate.is_empty() || !(candidate.contains('.') || candidate.contains('/')) {
+                continue;
+            }
+            if files.iter().any(|f| f == candidate) {
+                continue;
+            }
+
+            let path = Path::new(candidate);
+            let full_path = if path.is_absolute() {
+                path.to_path_buf()
+            } else {
+                self.repo_dir.join(path)
+            };
+            if full_path.is_file() {
+                files.push(candidate.to_string());
+            }
+            if files.len() == MAX_FILES {
+                break;
+            }
+        }
+
+        files
+    }
+
+    /// The most recent commits touching a file, newest first
+    pub fn recent_commits(&self, file: &str) -> Vec<CommitSummary> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.repo_dir)
+            .args([
+                "log",
+                &format!("-n{COMMITS_PER_FILE}"),
+                "--format=%h%x1f%an%x1f%ar%x1f%s",
+                "--",
+                file,
+            ])
+            .output();
+
+        let Ok(output) = output else {
+            return Vec::new();
+        };
+        if !output.status.success() {
+            return Vec::new();
+        }
+
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\x1f');
+                Some(CommitSummary {
+                    hash: fields.next()?.to_string(),
+                    author: fields.next()?.to_string(),
+                    relative_date: fields.next()?.to_string(),
+                    subject: fields.next()?.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Build the git history section for a prompt, or `None` when it mentions
+    /// no tracked files or the directory isn't a git repository
+    pub fn context_for(&self, prompt: &str) -> Option<String> {
+        let sections: Vec<String> = self
+            .mentioned_files(prompt)
+            .into_iter()
+            .filter_map(|file| {
+                let commits = self.recent_commits(&file);
+                if commits.is_empty() {
+                    return None;
+                }
+
+                let lines: Vec<String> = commits
+                    .iter()
+                    .map(|c| {
+                        format!(
+                            "- {} {} ({}): {}",
+                            c.hash, c.author, c.relative_date, c.subject
+                        )
+                    })
+                    .collect();
+                Some(format!("{file}:\n{}", lines.join("\n")))
+            })
+            .collect();
+
+        if sections.is_empty() {
+            None
+        } else {
+            Some(format!(
+                "## RECENT GIT HISTORY\nRecent commits touching the files mentioned in the request:\n{}",
+                sections.join("\n")
+            ))
+        }
+    }
+}
+
+impl ContextProvider for GitHistory {
+    fn name(&self) -> &str {
+        GIT_HISTORY_PROVIDER
+    }
+
+    fn priority(&self) -> i32 {
+        10
+    }
+
+    fn token_budget(&self) -> usize {
+        500
+    }
+
+    fn provide(&self, request: &ContextRequest) -> Option<String> {
+        self.context_for(request.query)
+    }
+}
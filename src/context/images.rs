@@ -0,0 +1,72 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use std::path::{Path, PathBuf};
+
+use crate::apis::api_client::ImageData;
+use crate::tools::fs::read_limits::{image_media_type, MAX_IMAGE_BYTES};
+
+/// Most images attached to one prompt with /attach
+pub const MAX_ATTACHED_IMAGES: usize = 10;
+
+/// An image attached to a prompt with /attach, e.g. a screenshot of a stack trace
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageAttachment {
+    pub path: PathBuf,
+    pub image: ImageData,
+    pub bytes: u64,
+}
+
+impl ImageAttachment {
+    /// Load a PNG, JPEG, GIF or WebP image, resolving a relative path against
+    /// `working_dir`
+    pub fn load(path: &str, working_dir: &Path) -> Result<Self> {
+        let path = working_dir.join(path);
+        let size = std::fs::metadata(&path)
+            .with_context(|| format!("Failed to read image: {}", path.display()))?
+            .len();
+        if size > MAX_IMAGE_BYTES {
+            anyhow::bail!(
+                "{} is an image of {size} bytes, larger than the {MAX_IMAGE_BYTES} bytes that can be attached",
+                path.display()
+            );
+        }
+        let bytes = std::fs::read(&path)
+            .with_context(|| format!("Failed to read image: {}", path.display()))?;
+        let media_type = image_media_type(&bytes).ok_or_else(|| {
+            anyhow::anyhow!("{} is not a PNG, JPEG, GIF or WebP image", path.display())
+        })?;
+
+        Ok(Self {
+            path,
+            image: ImageData {
+                media_type: media_type.to_string(),
+                data: base64::engine::general_purpose::STANDARD.encode(bytes),
+            },
+            bytes: size,
+        })
+    }
+
+    /// Load every image of `paths`, failing on the first that can't be attached
+    pub fn load_all(paths: &[String], working_dir: &Path) -> Result<Vec<Self>> {
+        if paths.len() > MAX_ATTACHED_IMAGES {
+            anyhow::bail!(
+                "{} images were attached; at most {MAX_ATTACHED_IMAGES} can be sent with a prompt",
+                paths.len()
+            );
+        }
+        paths
+            .iter()
+            .map(|path| Self::load(path, working_dir))
+            .collect()
+    }
+}
+
+/// Append images to a prompt as image blocks, which the provider clients send
+/// as image input
+pub fn attach_images(prompt: &str, images: &[ImageData]) -> String {
+    if images.is_empty() {
+        return prompt.to_string();
+    }
+    let blocks: Vec<String> = images.iter().map(ImageData::to_block).collect();
+    format!("{prompt}\n\n{}", blocks.join("\n"))
+}
@@ -0,0 +1,9 @@
+// Export context sources injected into prompts
+pub mod clipboard;
+pub mod editor;
+pub mod file_mentions;
+pub mod git_history;
+pub mod images;
+pub mod piped_input;
+pub mod project_instructions;
+pub mod provider;
@@ -0,0 +1,234 @@
+/// Largest amount of piped input or pasted context attached to a prompt, in bytes
+pub const MAX_PIPED_INPUT_BYTES: usize = 100 * 1024;
+
+/// Format of text attached to a prompt, piped into a headless prompt (e.g.
+/// `cat error.log | oli -p "explain"`) or pasted from the clipboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipedFormat {
+    Diff,
+    Json,
+    StackTrace,
+    Log,
+    /// Source code in the named language
+    Code(&'static str),
+    Text,
+}
+
+impl PipedFormat {
+    /// Guess the format of piped input from its content
+    pub fn detect(input: &str) -> Self {
+        let trimmed = input.trim_start();
+
+        if trimmed.starts_with("diff --git")
+            || trimmed.starts_with("--- ")
+            || trimmed.lines().any(|line| line.starts_with("@@ "))
+        {
+            return PipedFormat::Diff;
+        }
+
+        if (trimmed.starts_with('{') || trimmed.starts_with('['))
+            && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+        {
+            return PipedFormat::Json;
+        }
+
+        if looks_like_stack_trace(trimmed) {
+            return PipedFormat::StackTrace;
+        }
+
+        // Logs have a level or timestamp on a good share of their lines
+        let lines: Vec<&str> = trimmed.lines().take(50).collect();
+        let log_lines = lines
+            .iter()
+            .filter(|line| looks_like_log_line(line))
+            .count();
+        if !lines.is_empty() && log_lines * 3 >= lines.len() {
+            return PipedFormat::Log;
+        }
+
+        match detect_language(&lines) {
+            Some(language) => PipedFormat::Code(language),
+            None => PipedFormat::Text,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PipedFormat::Diff => "diff",
+            PipedFormat::Json => "json",
+            PipedFormat::StackTrace => "stacktrace",
+            PipedFormat::Log => "log",
+            PipedFormat::Code(language) => language,
+            PipedFormat::Text => "text",
+        }
+    }
+}
+
+fn looks_like_stack_trace(input: &str) -> bool {
+    if input.contains("Traceback (most recent call last):")
+        || (input.contains("panicked at") && input.contains("stack backtrace:"))
+        || (input.contains("goroutine ") && input.contains("panic:"))
+    {
+        return true;
+    }
+
+    // JavaScript and Java frames, e.g. "    at main (index.js:3:9)"
+    input
+        .lines()
+        .filter(|line| {
+            line.trim_start().starts_with("at ") && line.starts_with(char::is_whitespace)
+        })
+        .take(2)
+        .count()
+        == 2
+}
+
+/// Line starts that mark source code in each language
+const LANGUAGE_MARKERS: &[(&str, &[&str])] = &[
+    (
+        "rust",
+        &[
+            "fn ", "pub fn ", "use ", "impl ", "let mut ", "#[derive", "mod ",
+        ],
+    ),
+    (
+        "python",
+        &["def ", "import ", "from ", "class ", "elif ", "async def "],
+    ),
+    ("go", &["package ", "func ", "import (", "type "]),
+    (
+        "typescript",
+        &["interface ", "export interface ", "export type ", "type "],
+    ),
+    (
+        "javascript",
+        &[
+            "const ",
+            "function ",
+            "export ",
+            "let ",
+            "module.exports",
+            "require(",
+        ],
+    ),
+    (
+        "bash",
+        &[
+            "#!/bin/",
+            "#!/usr/bin/env bash",
+            "export ",
+            "echo ",
+            "if [ ",
+        ],
+    ),
+];
+
+// The language whose markers start the most lines, needing at least two
+fn detect_language(lines: &[&str]) -> Option<&'static str> {
+    LANGUAGE_MARKERS
+        .iter()
+        .map(|(language, markers)| {
+            let count = lines
+                .iter()
+                .filter(|line| {
+                    markers
+                        .iter()
+                        .any(|marker| line.trim_start().starts_with(marker))
+                })
+                .count();
+            (*language, count)
+        })
+        .filter(|(_, count)| *count >= 2)
+        // The first language listed wins ties
+        .fold(
+            None,
+            |best: Option<(&str, usize)>, (language, count)| match best {
+                Some((_, best_count)) if best_count >= count => best,
+                _ => Some((language, count)),
+            },
+        )
+        .map(|(language, _)| language)
+}
+
+fn looks_like_log_line(line: &str) -> bool {
+    const LEVELS: &[&str] = &["ERROR", "WARN", "INFO", "DEBUG", "TRACE", "FATAL"];
+    let head: String = line.chars().take(40).collect();
+
+    let has_level = LEVELS.iter().any(|level| head.contains(level));
+    // e.g. "2024-05-01" or "12:30:45"
+    let has_timestamp = head.as_bytes().windows(8).any(|w| {
+        let date = w[..4].iter().all(u8::is_ascii_digit) && w[4] == b'-';
+        let time = w[0].is_ascii_digit()
+            && w[1].is_ascii_digit()
+            && w[2] == b':'
+            && w[3].is_ascii_digit()
+            && w[4].is_ascii_digit()
+            && w[5] == b':';
+        date || time
+    });
+
+    has_level || has_timestamp
+}
+
+/// Attach piped input to a prompt as a fenced block, truncating it to
+/// `MAX_PIPED_INPUT_BYTES`. Logs keep their end, where failures usually are;
+/// everything else keeps its beginning.
+pub fn attach_piped_input(prompt: &str, input: &str) -> String {
+    attach_context_block(prompt, "PIPED INPUT", input)
+}
+
+/// Attach text to a prompt as a fenced block under a `## LABEL (format)` heading,
+/// truncated as piped input is
+pub fn attach_context_block(prompt: &str, label: &str, input: &str) -> String {
+    if input.trim().is_empty() {
+        return prompt.to_string();
+    }
+
+    let format = PipedFormat::detect(input);
+    let (content, truncated) = if input.len() > MAX_PIPED_INPUT_BYTES {
+        let content = if format == PipedFormat::Log {
+            let mut start = input.len() - MAX_PIPED_INPUT_BYTES;
+            while !input.is_char_boundary(start) {
+                start += 1;
+            }
+            input[start..].to_string()
+        } else if format == PipedFormat::StackTrace {
+            // Traces name the error at one end or the other depending on the language
+            let mut head_end = MAX_PIPED_INPUT_BYTES / 2;
+            while !input.is_char_boundary(head_end) {
+                head_end -= 1;
+            }
+            let mut tail_start = input.len() - MAX_PIPED_INPUT_BYTES / 2;
+            while !input.is_char_boundary(tail_start) {
+                tail_start += 1;
+            }
+            format!("{}\n...\n{}", &input[..head_end], &input[tail_start..])
+        } else {
+            let mut end = MAX_PIPED_INPUT_BYTES;
+            while !input.is_char_boundary(end) {
+                end -= 1;
+            }
+            input[..end].to_string()
+        };
+        (content, true)
+    } else {
+        (input.to_string(), false)
+    };
+
+    let note = if truncated {
+        format!(
+            " (truncated from {} to {} bytes)",
+            input.len(),
+            content.len()
+        )
+    } else {
+        String::new()
+    };
+
+    format!(
+        "{prompt}\n\n## {label} ({}){note}\n```{}\n{}\n```",
+        format.name(),
+        format.name(),
+        content.trim_end()
+    )
+}
@@ -0,0 +1,65 @@
+use crate::app::memory::MemoryManager;
+use std::path::{Path, PathBuf};
+
+/// Files with a project's instructions for agents, looked up in the working
+/// directory in this order. `OLI.md` is the one `/init` writes.
+pub const PROJECT_INSTRUCTION_FILES: &[&str] = &["OLI.md", "AGENTS.md"];
+
+/// Largest amount of a project instructions file added to the system prompt, in bytes
+pub const MAX_PROJECT_INSTRUCTIONS_BYTES: usize = 32 * 1024;
+
+/// A project's instructions for agents, from `OLI.md` or `AGENTS.md` in its
+/// working directory, added to the system prompt of every agent turn
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectInstructions {
+    pub path: PathBuf,
+    pub contents: String,
+    /// Whether the file was cut to `MAX_PROJECT_INSTRUCTIONS_BYTES`
+    pub truncated: bool,
+}
+
+impl ProjectInstructions {
+    /// The first instructions file in `working_dir` with any content; None when
+    /// there's none or it can't be read
+    pub fn load(working_dir: &Path) -> Option<Self> {
+        PROJECT_INSTRUCTION_FILES.iter().find_map(|name| {
+            let path = working_dir.join(name);
+            let contents = std::fs::read_to_string(&path).ok()?;
+            Self::from_contents(path, &contents)
+        })
+    }
+
+    /// Instructions with the given contents, None when they're blank or still the
+    /// untouched memory file template, which on case-insensitive file systems is
+    /// the same file as `OLI.md`
+    pub fn from_contents(path: PathBuf, contents: &str) -> Option<Self> {
+        let contents = contents.trim();
+        if contents.is_empty() || contents == MemoryManager::default_memory_template().trim() {
+            return None;
+        }
+
+        let truncated = contents.len() > MAX_PROJECT_INSTRUCTIONS_BYTES;
+        let contents = if truncated {
+            let mut end = MAX_PROJECT_INSTRUCTIONS_BYTES;
+            while !contents.is_char_boundary(end) {
+                end -= 1;
+            }
+            &contents[..end]
+        } else {
+            contents
+        };
+        Some(Self {
+            path,
+            contents: contents.to_string(),
+            truncated,
+        })
+    }
+
+    /// Name of the file, e.g. `AGENTS.md`
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+pub use crate::apis::streaming::estimate_tokens;
+
+/// Tokens a provider may add per request unless it sets its own budget
+pub const DEFAULT_PROVIDER_BUDGET: usize = 1_000;
+/// Tokens all providers together may add per request
+pub const DEFAULT_TOTAL_BUDGET: usize = 4_000;
+
+const TRUNCATION_MARKER: &str = "\n[... context truncated]";
+
+/// The request that context is being assembled for
+#[derive(Debug, Clone, Copy)]
+pub struct ContextRequest<'a> {
+    /// The latest user prompt
+    pub query: &'a str,
+    pub working_directory: Option<&'a str>,
+}
+
+/// Source of context injected into every model request, such as recent git
+/// history or pinned files. Providers return a self-contained markdown section.
+pub trait ContextProvider: Send + Sync {
+    /// Name used to identify the provider, e.g. to replace or remove it
+    fn name(&self) -> &str;
+
+    /// Providers with higher priorities are included first when the total budget is tight
+    fn priority(&self) -> i32 {
+        0
+    }
+
+    /// Most tokens this provider may add to a request
+    fn token_budget(&self) -> usize {
+        DEFAULT_PROVIDER_BUDGET
+    }
+
+    /// Build the context section for a request, or `None` when there's nothing to add
+    fn provide(&self, request: &ContextRequest) -> Option<String>;
+}
+
+/// Collects the sections of all registered providers before each model request,
+/// in priority order and within each provider's and the overall token budget
+#[derive(Clone)]
+pub struct ContextAssembler {
+    providers: Vec<Arc<dyn ContextProvider>>,
+    total_budget: usize,
+}
+
+impl Default for ContextAssembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContextAssembler {
+    pub fn new() -> Self {
+        Self {
+            providers: Vec::new(),
+            total_budget: DEFAULT_TOTAL_BUDGET,
+        }
+    }
+
+    /// Register a provider, replacing any provider with the same name
+    pub fn with_provider(mut self, provider: Arc<dyn ContextProvider>) -> Self {
+        self.providers.retain(|p| p.name() != provider.name());
+        self.providers.push(provider);
+        self
+    }
+
+    pub fn with_total_budget(mut self, tokens: usize) -> Self {
+        self.total_budget = tokens;
+        self
+    }
+
+    pub fn without_provider(mut self, name: &str) -> Self {
+        self.providers.retain(|p| p.name() != name);
+        self
+    }
+
+    /// Names of the registered providers, highest priority first
+    pub fn provider_names(&self) -> Vec<String> {
+        self.by_priority()
+            .iter()
+            .map(|p| p.name().to_string())
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    /// Build the combined context for a request, or `None` when no provider adds anything
+    pub fn assemble(&self, request: &ContextRequest) -> Option<String> {
+        let mut remaining = self.total_budget;
+        let mut sections = Vec::new();
+
+        for provider in self.by_priority() {
+            if remaining == 0 {
+                break;
+            }
+            let Some(section) = provider.provide(request) else {
+                continue;
+            };
+            let section = section.trim();
+            if section.is_empty() {
+                continue;
+            }
+
+            let section = truncate_to_tokens(section, provider.token_budget().min(remaining));
+            remaining = remaining.saturating_sub(estimate_tokens(&section));
+            sections.push(section);
+        }
+
+        if sections.is_empty() {
+            None
+        } else {
+            Some(sections.join("\n\n"))
+        }
+    }
+
+    fn by_priority(&self) -> Vec<&Arc<dyn ContextProvider>> {
+        let mut providers: Vec<_> = self.providers.iter().collect();
+        // Stable, so providers with equal priorities keep their registration order
+        providers.sort_by_key(|p| std::cmp::Reverse(p.priority()));
+        providers
+    }
+}
+
+fn truncate_to_tokens(text: &str, tokens: usize) -> String {
+    let max_bytes = tokens * 4;
+    if text.len() <= max_bytes {
+        return text.to_string();
+    }
+
+    let mut end = max_bytes.saturating_sub(TRUNCATION_MARKER.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{TRUNCATION_MARKER}", &text[..end])
+}
@@ -1,8 +1,9 @@
 pub mod agent;
 pub mod apis;
 pub mod app;
+pub mod client;
 pub mod communication;
-mod errors;
+pub mod context;
 pub mod models;
 pub mod prompts;
 pub mod tools;
@@ -10,6 +11,8 @@ pub mod tools;
 // Re-export key backend components
 pub use agent::core::Agent;
 pub use agent::core::LLMProvider;
+pub use agent::policy::ToolPolicy;
 pub use app::core::App;
 pub use app::core::AppState;
+pub use client::Client;
 pub use communication::rpc::RpcServer;
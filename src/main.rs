@@ -1,41 +1,416 @@
 use anyhow::Result;
+use oli_server::agent::audit::AuditLog;
+use oli_server::agent::evidence::parse_citations;
+use oli_server::agent::fanout::FanOutConfig;
+use oli_server::agent::permissions::{
+    PermissionFile, PermissionLevel, PermissionPolicy, PermissionRules, RuleScope,
+};
+use oli_server::apis::api_client::{ModelParameters, SamplingPreset};
+use oli_server::apis::ledger::{LedgerTotals, RequestLedger};
+use oli_server::app::api_keys::KeyStore;
+use oli_server::app::ask::AskSession;
+use oli_server::app::config::{ConfigError, ConfigFile, OliConfig};
 use oli_server::app::history::ContextCompressor;
-use oli_server::communication::rpc::RpcServer;
+use oli_server::app::logger::Logger;
+use oli_server::app::presets::{PresetSelection, SamplingMode};
+use oli_server::app::prompt_history::PromptHistory;
+use oli_server::app::queries::QueryRegistry;
+use oli_server::app::session::{SessionFile, SessionState};
+use oli_server::app::session_store::{normalize_tag, SessionFilter, SessionStore};
+use oli_server::app::session_summary::session_summary_enabled;
+use oli_server::app::storage::StorageManager;
+use oli_server::app::theme::{theme_from_env, THEMES};
+use oli_server::app::utils::ScrollBindings;
+use oli_server::app::workspace_lock::{SecondInstancePolicy, WorkspaceLock};
+use oli_server::communication::daemon::{Daemon, DaemonConfig};
+use oli_server::communication::rpc::{get_global_rpc_server, ClientScope, RpcServer};
+use oli_server::context::clipboard::read_clipboard;
+use oli_server::context::editor::EditorContext;
+use oli_server::context::file_mentions::complete_file_path;
+use oli_server::context::images::{ImageAttachment, MAX_ATTACHED_IMAGES};
+use oli_server::context::piped_input::{
+    attach_context_block, attach_piped_input, PipedFormat, MAX_PIPED_INPUT_BYTES,
+};
+use oli_server::models::{find_local_model, get_available_models, pull_ollama_model, ModelConfig};
+use oli_server::tools::fs::workspace_index::{LargeRepoSetting, WorkspaceIndex};
+use oli_server::tools::git_stage::GitStager;
+use oli_server::tools::processes::ProcessSupervisor;
+use oli_server::tools::shell::{EnvSnapshot, ShellConfig};
 use oli_server::App;
 use serde_json::json;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// Package version from Cargo.toml
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// How often child processes are checked for idleness and orphans
+const PROCESS_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
 /// Main function to initialize and run the oli server
 fn main() -> Result<()> {
+    // `oli-server config check [path]` validates the config file and exits
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("config") {
+        std::process::exit(run_config_command(&args[1..]));
+    }
+
+    // `oli-server daemon [--socket PATH] [--tcp PORT] [--token-file PATH]` serves
+    // clients on a socket instead of stdio
+    let daemon_config = match args.first().map(String::as_str) {
+        Some("daemon") => match DaemonConfig::from_args(&args[1..]) {
+            Ok(config) => Some(config),
+            Err(e) => {
+                eprintln!(
+                    "{e}\nUsage: oli-server daemon [--socket PATH] [--tcp PORT] [--token-file PATH]"
+                );
+                std::process::exit(2);
+            }
+        },
+        _ => None,
+    };
+
+    // Settings from config.toml apply below the environment and .env
+    let _ = dotenv::dotenv();
+    let config = ConfigFile::from_env().map(|file| file.load());
+
     // Initialize app state
     let app = Arc::new(Mutex::new(App::new()));
+    apply_config(&app, config);
+    acquire_workspace_lock(&app);
+    supervise_child_processes(&app);
+
+    // Record every provider request with its tokens, latency and cost
+    if let Some(ledger) = RequestLedger::from_env() {
+        ledger.enable();
+    }
+
+    // Save the conversation after each turn so it can be resumed and searched later
+    app.lock().unwrap().session_store = SessionStore::from_env();
 
     // Set up RPC server
     let mut rpc_server = RpcServer::new();
 
+    // Index the workspace in the background, switching to large-repo mode if it's big
+    start_workspace_index();
+
+    if let Some(config) = daemon_config {
+        return run_daemon(&rpc_server, &app, &config);
+    }
+
+    // Register all API methods, then run the RPC server - silently to avoid UI interference
+    register_apis(&mut rpc_server, &app);
+    rpc_server.run()?;
+
+    Ok(())
+}
+
+/// Register every API method against `app`
+fn register_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>) {
     // Get a clone of the event sender for use in closures
     let global_event_sender = rpc_server.event_sender();
 
-    // Register all API methods
-    register_model_interaction_apis(&mut rpc_server, &app, &global_event_sender);
-    register_agent_control_apis(&mut rpc_server, &app);
-    register_model_discovery_apis(&mut rpc_server, &app);
-    register_task_management_apis(&mut rpc_server, &app);
-    register_conversation_apis(&mut rpc_server, &app);
-    register_system_apis(&mut rpc_server);
+    register_model_interaction_apis(rpc_server, app, &global_event_sender);
+    register_query_apis(rpc_server, app, &global_event_sender);
+    register_agent_control_apis(rpc_server, app);
+    register_model_discovery_apis(rpc_server, app);
+    register_task_management_apis(rpc_server, app);
+    register_conversation_apis(rpc_server, app);
+    register_saved_session_apis(rpc_server, app);
+    register_api_key_apis(rpc_server, app);
+    register_batch_apis(rpc_server, app);
+    register_system_apis(rpc_server);
+    register_config_apis(rpc_server, app);
+    register_storage_apis(rpc_server, app);
+    register_workspace_apis(rpc_server, app);
+    register_staging_apis(rpc_server);
+    register_rename_apis(rpc_server, app);
+    register_ask_apis(rpc_server, app);
+    register_process_apis(rpc_server);
+    register_permission_apis(rpc_server, app);
+    register_editor_apis(rpc_server, app);
+
+    // Register the initialize handshake for client capability negotiation
+    rpc_server.register_initialize_handler(VERSION);
 
     // Register subscription handlers for real-time event streaming
     rpc_server.register_subscription_handlers();
+}
 
-    // We've registered subscription handlers but no need to log in UI mode
+/// Serve clients on a Unix socket or local TCP port until shut down. Each
+/// connection gets a conversation of its own, set up like `app`.
+fn run_daemon(hub: &RpcServer, app: &Arc<Mutex<App>>, config: &DaemonConfig) -> Result<()> {
+    let daemon = Daemon::bind(config)?;
+    daemon.shutdown_on_signal();
+    for address in daemon.addresses() {
+        eprintln!("oli daemon listening on {address}");
+    }
+    eprintln!("oli daemon token in {}", daemon.token_file().display());
 
-    // Run the RPC server - silently to avoid UI interference
-    rpc_server.run()?;
+    let app = app.clone();
+    daemon.run(hub, move |connection| {
+        let session = Arc::new(Mutex::new(app.lock().unwrap().new_session()));
+        register_apis(connection, &session);
+    })
+}
 
-    Ok(())
+/// Run `config check [path]`, printing any problems with the config file. Returns
+/// the exit code.
+fn run_config_command(args: &[String]) -> i32 {
+    let file = match args {
+        [command] if command == "check" => ConfigFile::from_env(),
+        [command, path] if command == "check" => Some(ConfigFile::new(path.into())),
+        _ => {
+            eprintln!("Usage: oli config check [path]");
+            return 2;
+        }
+    };
+    let Some(file) = file else {
+        eprintln!("Could not determine the config file location");
+        return 1;
+    };
+
+    match file.load() {
+        Ok(Some(_)) => {
+            println!("{} is valid", file.path().display());
+            0
+        }
+        Ok(None) => {
+            println!(
+                "{} does not exist; defaults are used",
+                file.path().display()
+            );
+            0
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            1
+        }
+    }
+}
+
+/// Apply the config file loaded at startup, logging why it was skipped if invalid
+fn apply_config(app: &Arc<Mutex<App>>, config: Option<Result<Option<OliConfig>>>) {
+    let mut app = app.lock().unwrap();
+    match config {
+        Some(Ok(Some(config))) => {
+            config.apply();
+            app.model_parameters = app.model_parameters.merge(&config.model_parameters());
+            app.sampling_presets = PresetSelection::from_env();
+        }
+        Some(Err(e)) => app.log(&format!("Ignoring invalid config file:\n{e}")),
+        _ => {}
+    }
+}
+
+/// Take the advisory lock on the workspace, or note which instance already holds it
+fn acquire_workspace_lock(app: &Arc<Mutex<App>>) {
+    let mut app = app.lock().unwrap();
+    let Ok(working_dir) = std::env::current_dir() else {
+        return;
+    };
+
+    match WorkspaceLock::acquire(
+        &working_dir,
+        &app.session_id.clone(),
+        SecondInstancePolicy::from_env(),
+    ) {
+        Ok(lock) => {
+            if let Some(warning) = lock.warning() {
+                app.log(&warning);
+            }
+            app.workspace_lock = Some(Arc::new(Mutex::new(lock)));
+        }
+        Err(e) => app.log(&format!("Failed to lock the workspace: {e}")),
+    }
+}
+
+/// Kill language servers and Bash commands left running by instances that crashed,
+/// then keep killing this instance's children once they've been idle too long
+fn supervise_child_processes(app: &Arc<Mutex<App>>) {
+    let Some(supervisor) = ProcessSupervisor::from_env() else {
+        return;
+    };
+
+    let reaped = supervisor.reap_orphans();
+    if !reaped.is_empty() {
+        app.lock().unwrap().log(&format!(
+            "Killed {} child processes left running by oli instances that exited",
+            reaped.len()
+        ));
+    }
+
+    let idle_timeout = ProcessSupervisor::idle_timeout_from_env();
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(PROCESS_CHECK_INTERVAL);
+        let mut killed = supervisor.reap_orphans();
+        if let Some(timeout) = idle_timeout {
+            killed.extend(supervisor.kill_idle(timeout));
+        }
+        if killed.is_empty() {
+            continue;
+        }
+        // The App is locked while a task runs; the log line isn't worth waiting for
+        if let Ok(mut app) = app.try_lock() {
+            for child in &killed {
+                app.log(&format!(
+                    "Killed idle or orphaned child process {} ({})",
+                    child.pid, child.command
+                ));
+            }
+        }
+    });
+}
+
+/// Index the files of the working directory on a background thread, reporting
+/// progress to the status bar with `index_progress` notifications
+fn start_workspace_index() {
+    let Ok(working_dir) = std::env::current_dir() else {
+        return;
+    };
+    WorkspaceIndex::start(&working_dir, LargeRepoSetting::from_env(), |status| {
+        if let Some(server) = get_global_rpc_server() {
+            let _ = server.send_notification("index_progress", json!(status));
+        }
+    });
+}
+
+/// Directory relative /attach paths are resolved against
+fn attachment_dir(app: &App) -> PathBuf {
+    app.current_working_dir
+        .clone()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Run a prompt for `run` and `start_query`, answering with the response, the
+/// turn's record and the evidence its answer cites
+fn run_prompt(
+    app: &mut App,
+    params: &serde_json::Value,
+    event_sender: &std::sync::mpsc::Sender<(String, serde_json::Value)>,
+) -> Result<serde_json::Value> {
+    // Extract query from params
+    let prompt = params["prompt"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Missing prompt parameter"))?;
+
+    // Attach text piped into headless mode, e.g. `cat error.log | oli -p "explain"`
+    let typed_prompt = prompt;
+    let prompt = match params["stdin"].as_str() {
+        Some(input) => attach_piped_input(prompt, input),
+        None => prompt.to_string(),
+    };
+
+    // Attach clipboard text added with /paste-context as its own labeled block
+    let prompt = match params["clipboard"].as_str() {
+        Some(text) => attach_context_block(&prompt, "CLIPBOARD", text),
+        None => prompt,
+    };
+
+    // Get model index if provided
+    let model_index = params["model_index"].as_u64().unwrap_or(0) as usize;
+
+    // Check if agent mode is explicitly specified
+    let use_agent = params["use_agent"].as_bool().unwrap_or(app.use_agent);
+
+    // Per-request overrides for temperature, top_p and max_tokens
+    let overrides = ModelParameters::from_json(params)?;
+
+    // Update agent usage flag
+    app.use_agent = use_agent;
+
+    // Split the task across parallel sub-agents for this request if asked to
+    app.fan_out = FanOutConfig::from_json(&params["fan_out"]);
+
+    // Stream the answer as content_delta notifications if asked to
+    app.stream_responses = params["stream"].as_bool().unwrap_or(false);
+
+    // Attach images added with /attach, sent to the model as image input
+    let attachments: Vec<String> = params["attachments"]
+        .as_array()
+        .map(|paths| {
+            paths
+                .iter()
+                .filter_map(|path| path.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    app.attached_images = ImageAttachment::load_all(&attachments, &attachment_dir(app))?
+        .into_iter()
+        .map(|attachment| attachment.image)
+        .collect();
+
+    // We'll skip logging model selection to avoid UI clutter
+
+    // Send processing started event
+    let _ = event_sender.send((
+        "processing_started".to_string(),
+        json!({
+            "model_index": model_index,
+            "use_agent": use_agent
+        }),
+    ));
+
+    // Run the model with the selected model index
+    match app.run_with_parameters(&prompt, Some(model_index), overrides) {
+        Ok(response) => {
+            // Send processing complete event
+            let _ = event_sender.send(("processing_complete".to_string(), json!({})));
+            app.record_turn(typed_prompt, Ok(&response));
+
+            // Report the turn's duration, tool calls and token usage from its task record
+            let turn = app.tasks.last().map(|task| {
+                json!({
+                    "task_id": task.id,
+                    "started_at": task.created_at,
+                    "completed_at": task.updated_at,
+                    "tool_count": task.tool_count,
+                    "input_tokens": task.input_tokens,
+                    "output_tokens": task.output_tokens,
+                    "cost_usd": task.cost_usd,
+                    "summary": task.turn_summary()
+                })
+            });
+
+            // Numbered tool results the answer can cite as [#N], so the UI can show them
+            let evidence = json!({
+                "entries": app.last_evidence,
+                "cited": parse_citations(&response)
+            });
+
+            Ok(json!({
+                "response": response,
+                "turn": turn,
+                "evidence": evidence,
+                "session_cost_usd": app.session_cost_usd(),
+                // How full the model's context window is, and any compaction done to make room
+                "context": app.context_usage(),
+                "context_management": app.context_management
+            }))
+        }
+        Err(err) => {
+            // Explain the first failure of a model with Ollama or API key diagnostics
+            let explanation = app
+                .explain_local_model_failure(model_index)
+                .or_else(|| app.explain_api_key_failure(model_index));
+            let err = match explanation {
+                Some(report) => anyhow::anyhow!("{err}\n\n{report}"),
+                None => err,
+            };
+            app.record_turn(typed_prompt, Err(&err.to_string()));
+
+            // Send processing error event
+            let _ = event_sender.send((
+                "processing_error".to_string(),
+                json!({ "error": err.to_string() }),
+            ));
+
+            Err(anyhow::anyhow!("Error running model: {}", err))
+        }
+    }
 }
 
 /// Register APIs for model interaction
@@ -48,53 +423,143 @@ fn register_model_interaction_apis(
     let app_clone = app.clone();
     let event_sender = event_sender.clone();
 
-    // Register run method
-    rpc_server.register_method("run", move |params| {
+    // Register run method on its own thread, so side questions can be asked while it works
+    rpc_server.register_background_method("run", move |params| {
         let mut app = app_clone.lock().unwrap();
+        run_prompt(&mut app, &params, &event_sender)
+    });
+
+    // Clone app state for get_turn_record handler
+    let app_clone = app.clone();
+
+    // Register get_turn_record method, describing the last run for `--output-format json`
+    rpc_server.register_method("get_turn_record", move |_| {
+        let app = app_clone.lock().unwrap();
+        Ok(json!({ "record": app.last_turn }))
+    });
 
-        // Extract query from params
+    // Clone app state for query_model handler
+    let app_clone = app.clone();
+
+    // Register query_model method for plain completions, streamed to GUI clients as
+    // content_delta notifications followed by a done notification with usage stats
+    rpc_server.register_streaming_method("query_model", move |params, stream| {
+        let mut app = app_clone.lock().unwrap();
         let prompt = params["prompt"]
             .as_str()
             .ok_or_else(|| anyhow::anyhow!("Missing prompt parameter"))?;
-
-        // Get model index if provided
         let model_index = params["model_index"].as_u64().unwrap_or(0) as usize;
+        let overrides = ModelParameters::from_json(&params)?;
+        let streaming = params["stream"].as_bool().unwrap_or(false);
 
-        // Check if agent mode is explicitly specified
-        let use_agent = params["use_agent"].as_bool().unwrap_or(app.use_agent);
+        let (response, usage) =
+            app.query_model(prompt, Some(model_index), overrides, &mut |delta| {
+                if streaming {
+                    stream.delta(delta);
+                }
+            })?;
+        if streaming {
+            stream.done(json!(usage));
+        }
 
-        // Update agent usage flag
-        app.use_agent = use_agent;
+        Ok(json!({ "response": response, "usage": usage }))
+    });
 
-        // We'll skip logging model selection to avoid UI clutter
+    // Clone app state for compare_models handler
+    let app_clone = app.clone();
 
-        // Send processing started event
-        let _ = event_sender.send((
-            "processing_started".to_string(),
-            json!({
-                "model_index": model_index,
-                "use_agent": use_agent
-            }),
-        ));
+    // Register compare_models method to answer one prompt with two models side by side
+    rpc_server.register_method("compare_models", move |params| {
+        let app = app_clone.lock().unwrap();
+        let prompt = params["prompt"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing prompt parameter"))?;
 
-        // Run the model with the selected model index
-        match app.run(prompt, Some(model_index)) {
-            Ok(response) => {
-                // Send processing complete event
-                let _ = event_sender.send(("processing_complete".to_string(), json!({})));
+        // Models are given by index or by name
+        let model = |key: &str| match &params[key] {
+            serde_json::Value::Number(n) => Ok(n.to_string()),
+            serde_json::Value::String(s) => Ok(s.clone()),
+            _ => Err(anyhow::anyhow!("Missing {key} parameter")),
+        };
+        let model_a = app.find_model(&model("model_a")?)?;
+        let model_b = app.find_model(&model("model_b")?)?;
 
-                Ok(json!({ "response": response }))
-            }
-            Err(err) => {
-                // Send processing error event
-                let _ = event_sender.send((
-                    "processing_error".to_string(),
-                    json!({ "error": err.to_string() }),
-                ));
+        let comparison = app.compare_models(prompt, model_a, model_b)?;
+        Ok(json!({
+            "answers": comparison.answers,
+            "summary": comparison.to_markdown(),
+        }))
+    });
+}
 
-                Err(anyhow::anyhow!("Error running model: {}", err))
-            }
-        }
+/// Register APIs for running agent queries in the background and polling them,
+/// so GUI clients stay responsive while the App is locked by a long tool loop
+fn register_query_apis(
+    rpc_server: &mut RpcServer,
+    app: &Arc<Mutex<App>>,
+    event_sender: &std::sync::mpsc::Sender<(String, serde_json::Value)>,
+) {
+    let queries = QueryRegistry::new();
+
+    // Clone app state, event sender and registry for start_query handler
+    let app_clone = app.clone();
+    let event_sender = event_sender.clone();
+    let queries_clone = queries.clone();
+
+    // Register start_query method, which takes the parameters of run and answers
+    // with a query id straight away, running the prompt on its own thread
+    rpc_server.register_method("start_query", move |params| {
+        let prompt = params["prompt"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing prompt parameter"))?;
+        let query_id = queries_clone.start(prompt);
+
+        let app = app_clone.clone();
+        let event_sender = event_sender.clone();
+        let queries = queries_clone.clone();
+        let id = query_id.clone();
+        // The query runs for the client that started it
+        let client = ClientScope::current();
+        std::thread::spawn(move || {
+            client.enter(|| {
+                let mut app = app.lock().unwrap();
+                if !queries.begin(&id) {
+                    return;
+                }
+                app.active_query = Some((queries.clone(), id.clone()));
+                let outcome = run_prompt(&mut app, &params, &event_sender);
+                app.active_query = None;
+                queries.finish(&id, outcome.map_err(|e| e.to_string()));
+            })
+        });
+
+        Ok(json!({ "query_id": query_id }))
+    });
+
+    // Register get_query_progress method, answered from the registry without
+    // waiting for the App
+    let queries_clone = queries.clone();
+    rpc_server.register_method("get_query_progress", move |params| {
+        let query_id = params["query_id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing query_id parameter"))?;
+        let query = queries_clone
+            .get(query_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown query: {query_id}"))?;
+        Ok(json!(query))
+    });
+
+    // Register cancel_query method, with the cancel flag cloned as for
+    // interrupt_processing since a running query holds the App lock
+    let cancel_requested = app.lock().unwrap().cancel_requested.clone();
+    rpc_server.register_method("cancel_query", move |params| {
+        let query_id = params["query_id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing query_id parameter"))?;
+        let status = queries
+            .cancel(query_id, &cancel_requested)
+            .ok_or_else(|| anyhow::anyhow!("Unknown query: {query_id}"))?;
+        Ok(json!({ "query_id": query_id, "status": status }))
     });
 }
 
@@ -119,6 +584,152 @@ fn register_agent_control_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>
             "agent_mode": use_agent
         }))
     });
+
+    // Clone app state for set_model_parameter handler
+    let app_clone = app.clone();
+
+    // Register set_model_parameter method for session-level sampling overrides
+    rpc_server.register_method("set_model_parameter", move |params| {
+        let mut app = app_clone.lock().unwrap();
+
+        let name = params["name"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'name' parameter"))?;
+
+        // Accept both numbers and strings so "/set temperature 0.7" can be forwarded as-is
+        let value = match &params["value"] {
+            serde_json::Value::String(value) => value.clone(),
+            serde_json::Value::Number(value) => value.to_string(),
+            _ => return Err(anyhow::anyhow!("Missing 'value' parameter")),
+        };
+
+        let parameters = app.set_model_parameter(name, &value)?;
+
+        Ok(json!({
+            "success": true,
+            "parameters": parameters
+        }))
+    });
+
+    // Clone app state for get_model_parameters handler
+    let app_clone = app.clone();
+
+    // Register get_model_parameters method
+    rpc_server.register_method("get_model_parameters", move |_| {
+        let app = app_clone.lock().unwrap();
+        Ok(json!({ "parameters": app.model_parameters }))
+    });
+
+    // Clone app state for set_sampling_preset handler
+    let app_clone = app.clone();
+
+    // Register set_sampling_preset method to choose a preset for one mode or all of them
+    rpc_server.register_method("set_sampling_preset", move |params| {
+        let mut app = app_clone.lock().unwrap();
+
+        let preset = params["preset"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'preset' parameter"))?;
+        let presets = app
+            .set_sampling_preset(params["mode"].as_str(), preset)?
+            .clone();
+
+        Ok(json!({
+            "success": true,
+            "presets": presets,
+            "active": active_preset(&app),
+        }))
+    });
+
+    // Clone app state for get_sampling_presets handler
+    let app_clone = app.clone();
+
+    // Register get_sampling_presets method, listing each preset's parameters
+    rpc_server.register_method("get_sampling_presets", move |_| {
+        let app = app_clone.lock().unwrap();
+        let available: serde_json::Map<String, serde_json::Value> = SamplingPreset::ALL
+            .iter()
+            .map(|preset| (preset.name().to_string(), json!(preset.parameters())))
+            .collect();
+
+        Ok(json!({
+            "presets": app.sampling_presets,
+            "active": active_preset(&app),
+            "available": available,
+        }))
+    });
+
+    // Clone app state for get_system_prompt handler
+    let app_clone = app.clone();
+
+    // Register get_system_prompt method, with the templates /prompt can choose
+    rpc_server.register_method("get_system_prompt", move |_| {
+        let app = app_clone.lock().unwrap();
+        Ok(json!({
+            "prompt": app.system_prompt()?,
+            "template": app.prompt_template,
+            "templates": app.prompt_library().templates(),
+        }))
+    });
+
+    // Clone app state for set_prompt_template handler
+    let app_clone = app.clone();
+
+    // Register set_prompt_template method; a null or empty name clears the template
+    rpc_server.register_method("set_prompt_template", move |params| {
+        let mut app = app_clone.lock().unwrap();
+        let prompt = app.set_prompt_template(params["name"].as_str())?;
+        Ok(json!({
+            "success": true,
+            "prompt": prompt,
+        }))
+    });
+
+    // Clone app state for set_summarizer_model handler
+    let app_clone = app.clone();
+
+    // Register set_summarizer_model method; a null or empty model falls back to the main model
+    rpc_server.register_method("set_summarizer_model", move |params| {
+        let mut app = app_clone.lock().unwrap();
+
+        let summarizer = app.set_summarizer_model(params["model"].as_str())?;
+
+        Ok(json!({
+            "success": true,
+            "summarizer": summarizer
+        }))
+    });
+
+    // Clone app state for get_summarizer_model handler
+    let app_clone = app.clone();
+
+    // Register get_summarizer_model method
+    rpc_server.register_method("get_summarizer_model", move |_| {
+        let app = app_clone.lock().unwrap();
+        let summarizer = app.summarizer.as_ref().map(|s| s.spec());
+        Ok(json!({ "summarizer": summarizer }))
+    });
+}
+
+/// The preset the next prompt runs with, for the status bar
+fn active_preset(app: &App) -> Option<SamplingPreset> {
+    let mode = if app.use_agent {
+        SamplingMode::Agent
+    } else {
+        SamplingMode::Chat
+    };
+    app.sampling_presets.for_mode(mode)
+}
+
+/// A model as listed to the client
+fn model_json(model: &ModelConfig) -> serde_json::Value {
+    json!({
+        "name": model.name,
+        "id": model.file_name,
+        "description": model.description,
+        "supports_agent": model.has_agent_support(),
+        "price": model.price()
+    })
 }
 
 /// Register APIs for model discovery
@@ -134,19 +745,28 @@ fn register_model_discovery_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App
         let models = app
             .available_models
             .iter()
-            .map(|m| {
-                json!({
-                    "name": m.name,
-                    "id": m.file_name,
-                    "description": m.description,
-                    "supports_agent": m.has_agent_support()
-                })
-            })
+            .map(model_json)
             .collect::<Vec<_>>();
 
         Ok(json!({ "models": models }))
     });
 
+    // Clone app state for get_quick_start handler
+    let app_clone = app.clone();
+
+    // Register get_quick_start method, naming the model to skip setup with if exactly
+    // one provider is usable
+    rpc_server.register_method("get_quick_start", move |_| {
+        let app = app_clone.lock().unwrap();
+        let quick_start = app.quick_start();
+        let notice = quick_start.as_ref().map(|quick_start| quick_start.notice());
+        Ok(json!({
+            "quick_start": quick_start,
+            "notice": notice,
+            "missing_ollama_model": app.missing_ollama_model(),
+        }))
+    });
+
     // Clone app state for set_selected_model handler
     let app_clone = app.clone();
 
@@ -173,70 +793,390 @@ fn register_model_discovery_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App
         }
 
         // Get model name but don't log selection (to avoid UI clutter)
-        let _model_name = app.available_models[model_index].name.clone();
+        let model_name = app.available_models[model_index].name.clone();
+
+        // Check the Ollama setup or the API key up front when a model is selected
+        let diagnostics = if model_name.to_lowercase().contains("local") {
+            app.diagnose_ollama(Some(model_index))
+                .ok()
+                .map(|d| (d.is_healthy(), d.report(), json!(d)))
+        } else {
+            app.diagnose_api_key(model_index)
+                .ok()
+                .map(|d| (d.is_healthy(), d.report(), json!(d)))
+        };
 
         Ok(json!({
             "success": true,
             "model": {
-                "name": app.available_models[model_index].name,
+                "name": model_name,
                 "id": app.available_models[model_index].file_name,
                 "index": model_index
-            }
+            },
+            "diagnostics": diagnostics.map(|(healthy, report, details)| json!({
+                "healthy": healthy,
+                "report": report,
+                "details": details,
+            }))
         }))
     });
-}
 
-/// Register APIs for task management
-fn register_task_management_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>) {
-    // Clone app state for get_tasks handler
+    // Clone app state for pull_ollama_model handler
     let app_clone = app.clone();
 
-    // Register get_tasks method
-    rpc_server.register_method("get_tasks", move |_| {
-        let app = app_clone.lock().unwrap();
-        Ok(json!({ "tasks": app.get_task_statuses() }))
+    // Register pull_ollama_model method, which downloads a model into Ollama, sending
+    // ollama_pull_progress notifications as it goes, then lists the models again
+    rpc_server.register_background_method("pull_ollama_model", move |params| {
+        let model = params["model"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing model parameter"))?
+            .trim()
+            .to_string();
+        pull_ollama_model(&model, |progress| {
+            if let Some(server) = get_global_rpc_server() {
+                let _ = server.send_notification(
+                    "ollama_pull_progress",
+                    json!({
+                        "model": model,
+                        "status": progress.status,
+                        "completed": progress.completed,
+                        "total": progress.total,
+                    }),
+                );
+            }
+        })?;
+
+        let mut app = app_clone.lock().unwrap();
+        app.available_models = get_available_models();
+        let model_index = find_local_model(&app.available_models, &model)
+            .ok_or_else(|| anyhow::anyhow!("Pulled '{model}', but Ollama doesn't list it"))?;
+        let models = app
+            .available_models
+            .iter()
+            .map(model_json)
+            .collect::<Vec<_>>();
+        Ok(json!({ "model_index": model_index, "models": models }))
     });
 
-    // Clone app state for cancel_task handler
+    // Clone app state for diagnose_ollama handler
     let app_clone = app.clone();
 
-    // Register cancel_task method
-    rpc_server.register_method("cancel_task", move |params| {
-        let mut app = app_clone.lock().unwrap();
-
-        // Extract task ID from params if provided
-        let task_id = params["task_id"].as_str();
+    // Register diagnose_ollama method
+    rpc_server.register_method("diagnose_ollama", move |params| {
+        let app = app_clone.lock().unwrap();
+        let model_index = params["model_index"].as_u64().map(|index| index as usize);
+        let diagnostics = app.diagnose_ollama(model_index)?;
 
-        if let Some(task_id) = task_id {
-            // Cancel specific task
-            app.fail_current_task(&format!("Task canceled by user: {task_id}"));
-            Ok(json!({ "success": true, "message": "Task canceled" }))
-        } else {
-            // Cancel current task if any
-            if app.current_task_id.is_some() {
-                app.fail_current_task("Task canceled by user");
-                Ok(json!({ "success": true, "message": "Current task canceled" }))
-            } else {
-                Ok(json!({ "success": false, "message": "No active task to cancel" }))
-            }
-        }
+        Ok(json!({
+            "healthy": diagnostics.is_healthy(),
+            "report": diagnostics.report(),
+            "details": diagnostics,
+        }))
     });
 }
 
-/// Register APIs for conversation management
-fn register_conversation_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>) {
-    // Clone app state for clear_conversation handler
+/// Register APIs for checking and storing provider API keys
+fn register_api_key_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>) {
+    // Clone app state for diagnose_api_key handler
     let app_clone = app.clone();
 
-    // Register clear_conversation method
-    rpc_server.register_method("clear_conversation", move |_| {
-        let mut app = app_clone.lock().unwrap();
+    // Register diagnose_api_key method
+    rpc_server.register_method("diagnose_api_key", move |params| {
+        let app = app_clone.lock().unwrap();
+        let model_index = params["model_index"]
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("Missing model_index parameter"))?
+            as usize;
+        let diagnostics = app.diagnose_api_key(model_index)?;
+
+        Ok(json!({
+            "healthy": diagnostics.is_healthy(),
+            "report": diagnostics.report(),
+            "details": diagnostics,
+        }))
+    });
+
+    // Clone app state for save_api_key handler
+    let app_clone = app.clone();
+
+    // Register save_api_key method, which stores a key only once the provider accepts it
+    rpc_server.register_method("save_api_key", move |params| {
+        let mut app = app_clone.lock().unwrap();
+        let model_index = params["model_index"]
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("Missing model_index parameter"))?
+            as usize;
+        let key = params["key"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing key parameter"))?;
+        let diagnostics = app.save_api_key(model_index, key)?;
+
+        Ok(json!({
+            "saved": diagnostics.is_healthy(),
+            "path": KeyStore::from_env().map(|store| store.path().to_path_buf()),
+            "report": diagnostics.report(),
+            "details": diagnostics,
+        }))
+    });
+}
+
+/// Register APIs for offline batch processing
+fn register_batch_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>) {
+    // Clone app state for submit_batch handler
+    let app_clone = app.clone();
+
+    // Register submit_batch method for bulk prompts in pipelines
+    rpc_server.register_method("submit_batch", move |params| {
+        let app = app_clone.lock().unwrap();
+        let model_index = params["model_index"].as_u64().unwrap_or(0) as usize;
+        let requests = params["requests"]
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("Missing requests parameter"))?;
+
+        let prompts = requests
+            .iter()
+            .enumerate()
+            .map(|(i, request)| {
+                let prompt = request["prompt"]
+                    .as_str()
+                    .ok_or_else(|| anyhow::anyhow!("Request {i} is missing a prompt"))?;
+                let id = request["id"]
+                    .as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("request-{i}"));
+                Ok((id, prompt.to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let system_prompt = params["system_prompt"].as_str().map(str::to_string);
+
+        let batch_id = app.submit_batch(model_index, prompts, system_prompt)?;
+        Ok(json!({ "batch_id": batch_id }))
+    });
+
+    // Clone app state for get_batch handler
+    let app_clone = app.clone();
+
+    // Register get_batch method to check on or wait for a batch
+    rpc_server.register_method("get_batch", move |params| {
+        let app = app_clone.lock().unwrap();
+        let model_index = params["model_index"].as_u64().unwrap_or(0) as usize;
+        let batch_id = params["batch_id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing batch_id parameter"))?;
+        let wait = params["wait"].as_bool().unwrap_or(false);
+
+        let (status, results) = app.batch_results(model_index, batch_id, wait)?;
+        Ok(json!({ "batch_id": batch_id, "status": status, "results": results }))
+    });
+}
+
+/// Register APIs for task management
+fn register_task_management_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>) {
+    // Clone app state for get_tasks handler
+    let app_clone = app.clone();
+
+    // Register get_tasks method
+    rpc_server.register_method("get_tasks", move |_| {
+        let app = app_clone.lock().unwrap();
+        Ok(json!({
+            "tasks": app.get_task_statuses(),
+            "usage": app.session_usage.totals_by_provider(),
+            "session_cost_usd": app.session_cost_usd(),
+            "context": app.context_usage(),
+        }))
+    });
+
+    // Clone app state for cancel_task handler
+    let app_clone = app.clone();
+
+    // Register cancel_task method
+    rpc_server.register_method("cancel_task", move |params| {
+        let mut app = app_clone.lock().unwrap();
+
+        // Extract task ID from params if provided
+        let task_id = params["task_id"].as_str();
+
+        if let Some(task_id) = task_id {
+            // Cancel specific task
+            app.fail_current_task(&format!("Task canceled by user: {task_id}"));
+            Ok(json!({ "success": true, "message": "Task canceled" }))
+        } else {
+            // Cancel current task if any
+            if app.current_task_id.is_some() {
+                app.fail_current_task("Task canceled by user");
+                Ok(json!({ "success": true, "message": "Current task canceled" }))
+            } else {
+                Ok(json!({ "success": false, "message": "No active task to cancel" }))
+            }
+        }
+    });
+
+    // Clone the cancel flag for interrupt_processing handler; run holds the app lock
+    // for the whole turn, so the flag is set without taking it
+    let cancel_requested = app.lock().unwrap().cancel_requested.clone();
+
+    // Register interrupt_processing method for Ctrl+C, Escape and /cancel
+    rpc_server.register_method("interrupt_processing", move |_| {
+        cancel_requested.store(true, std::sync::atomic::Ordering::SeqCst);
+        Ok(json!({ "success": true }))
+    });
+
+    // Register undo_last_turn method for /undo, with the checkpoints cloned like the
+    // cancel flag
+    let checkpoints = app.lock().unwrap().checkpoints.clone();
+    rpc_server.register_method("undo_last_turn", move |_| {
+        let report = checkpoints.undo_last()?;
+        Ok(json!({
+            "success": true,
+            "message": report.summary(),
+            "label": report.label,
+            "restored": report.restored,
+            "removed": report.removed,
+            "kept": report.kept,
+            "warning": report.warning,
+            "remaining": checkpoints.len(),
+        }))
+    });
+
+    // Clone app state for init_project_instructions handler
+    let app_clone = app.clone();
+
+    // Register init_project_instructions method for /init, writing a starter OLI.md
+    // when the project has no instructions file
+    rpc_server.register_method("init_project_instructions", move |params| {
+        let mut app = app_clone.lock().unwrap();
+        let model_index = params["model_index"].as_u64().unwrap_or(0) as usize;
+        let report = app.init_project_instructions(model_index)?;
+        Ok(json!({
+            "success": true,
+            "message": report.message,
+            "path": report.path,
+            "created": report.created,
+        }))
+    });
+
+    // Clone app state for complete_file_path handler
+    let app_clone = app.clone();
+
+    // Register complete_file_path method for @-mentions in the input box, fuzzy
+    // matching what's typed after the @ against the project's files
+    rpc_server.register_method("complete_file_path", move |params| {
+        let dir = app_clone
+            .lock()
+            .unwrap()
+            .current_working_dir
+            .clone()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."));
+        let query = params["query"].as_str().unwrap_or_default();
+        let limit = params["limit"].as_u64().unwrap_or(10) as usize;
+        Ok(json!({ "paths": complete_file_path(query, &dir, limit) }))
+    });
+
+    // Clone app state for check_attachment handler
+    let app_clone = app.clone();
+
+    // Register check_attachment method so /attach can check an image before it's
+    // sent with the next prompt
+    rpc_server.register_method("check_attachment", move |params| {
+        let path = params["path"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing path parameter"))?;
+        let model_index = params["model_index"].as_u64().unwrap_or(0) as usize;
+        let app = app_clone.lock().unwrap();
+        let attachment = ImageAttachment::load(path, &attachment_dir(&app))?;
+
+        Ok(json!({
+            "path": attachment.path.display().to_string(),
+            "media_type": attachment.image.media_type,
+            "bytes": attachment.bytes,
+            "max_images": MAX_ATTACHED_IMAGES,
+            "model_accepts_images": app.model_accepts_images(model_index),
+        }))
+    });
+
+    // Register get_request_ledger method for /stats requests
+    rpc_server.register_method("get_request_ledger", move |params| {
+        let limit = params["limit"].as_u64().unwrap_or(20) as usize;
+        let since = params["since"].as_str();
+        let ledger = RequestLedger::from_env()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine the request ledger location"))?;
+        let records = ledger.read_since(since)?;
+        let totals = LedgerTotals::of(&records);
+        let skip = records.len().saturating_sub(limit);
+
+        Ok(json!({
+            "path": ledger.path().display().to_string(),
+            "requests": records[skip..],
+            "totals": totals,
+        }))
+    });
+
+    // Register export_request_ledger method to write the ledger as CSV for expense reports
+    rpc_server.register_method("export_request_ledger", move |params| {
+        let path = params["path"].as_str().unwrap_or("oli-requests.csv");
+        let since = params["since"].as_str();
+        let ledger = RequestLedger::from_env()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine the request ledger location"))?;
+        let requests = ledger.export_csv(std::path::Path::new(path), since)?;
+
+        Ok(json!({ "path": path, "requests": requests }))
+    });
+}
+
+/// Register APIs for conversation management
+fn register_conversation_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>) {
+    // Clone app state for summarize_session handler
+    let app_clone = app.clone();
+
+    // Register summarize_session so the UI can document the session when it exits
+    rpc_server.register_method("summarize_session", move |params| {
+        let mut app = app_clone.lock().unwrap();
+
+        // Summaries are opt-in unless the caller asks for one explicitly
+        let force = params["force"].as_bool().unwrap_or(false);
+        if !force && !session_summary_enabled() {
+            return Ok(json!({ "enabled": false, "summary": null, "path": null }));
+        }
+
+        let (summary, path) = match app.summarize_session()? {
+            Some((summary, path)) => (
+                Some(summary.to_markdown()),
+                Some(path.display().to_string()),
+            ),
+            None => (None, None),
+        };
+        Ok(json!({ "enabled": true, "summary": summary, "path": path }))
+    });
+
+    // Clone app state for explain_last_task handler
+    let app_clone = app.clone();
+
+    // Register explain_last_task so /why can explain the last run from its trail
+    rpc_server.register_method("explain_last_task", move |params| {
+        let app = app_clone.lock().unwrap();
+        let question = params["question"].as_str();
+        let explanation = app.explain_last_task(question)?;
+        let trail = app.last_trail.as_ref();
+
+        Ok(json!({
+            "task_id": trail.map(|trail| trail.task_id.clone()),
+            "tool_calls": trail.map_or(0, |trail| trail.call_count()),
+            "explanation": explanation,
+        }))
+    });
+
+    // Clone app state for clear_conversation handler
+    let app_clone = app.clone();
+
+    // Register clear_conversation method
+    rpc_server.register_method("clear_conversation", move |_| {
+        let mut app = app_clone.lock().unwrap();
 
         // Use the history.rs implementation to clear everything
         // This clears messages, summaries, session manager, and agent history
         app.clear_history();
-
-        // We'll skip logging to avoid UI clutter
+        app.log("Conversation history cleared");
 
         // Return success
         Ok(json!({
@@ -245,6 +1185,112 @@ fn register_conversation_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>)
         }))
     });
 
+    // Clone app state for compact_conversation handler
+    let app_clone = app.clone();
+
+    // Register compact_conversation method to summarize older messages on the compaction model
+    rpc_server.register_method("compact_conversation", move |params| {
+        let mut app = app_clone.lock().unwrap();
+        let report = app.compact(params["instructions"].as_str())?;
+        app.log(&report.message());
+
+        Ok(json!({
+            "messages_summarized": report.messages_summarized,
+            "tokens_before": report.tokens_before,
+            "tokens_after": report.tokens_after,
+            "model": report.model,
+            "message": report.message(),
+            "count": app.history().len()
+        }))
+    });
+
+    // Clone app state for get_history handler
+    let app_clone = app.clone();
+
+    // Register get_history method, listing the messages later requests are built from
+    rpc_server.register_method("get_history", move |_| {
+        let app = app_clone.lock().unwrap();
+        let messages = app
+            .history()
+            .iter()
+            .enumerate()
+            .map(|(index, message)| {
+                json!({
+                    "index": index,
+                    "role": message.role,
+                    "content": message.content
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Ok(json!({ "messages": messages }))
+    });
+
+    // Clone app state for edit_message handler
+    let app_clone = app.clone();
+
+    // Register edit_message method, e.g. to redact a secret pasted into a prompt
+    rpc_server.register_method("edit_message", move |params| {
+        let mut app = app_clone.lock().unwrap();
+        let index = params["index"]
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("Missing index parameter"))?
+            as usize;
+        let content = params["content"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing content parameter"))?;
+        app.edit_history_message(index, content.to_string())?;
+
+        Ok(json!({ "success": true, "count": app.history().len() }))
+    });
+
+    // Clone app state for delete_message handler
+    let app_clone = app.clone();
+
+    // Register delete_message method
+    rpc_server.register_method("delete_message", move |params| {
+        let mut app = app_clone.lock().unwrap();
+        let index = params["index"]
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("Missing index parameter"))?
+            as usize;
+        let removed = app.delete_history_message(index)?;
+
+        Ok(json!({
+            "success": true,
+            "removed": { "role": removed.role, "content": removed.content },
+            "count": app.history().len()
+        }))
+    });
+
+    // Clone app state for clear_history handler
+    let app_clone = app.clone();
+
+    // Register clear_history method; the same as clear_conversation, named to match the
+    // other history methods
+    rpc_server.register_method("clear_history", move |_| {
+        let mut app = app_clone.lock().unwrap();
+        app.clear_history();
+        app.log("Conversation history cleared");
+
+        Ok(json!({ "success": true, "count": 0 }))
+    });
+
+    // Clone app state for get_logs handler
+    let app_clone = app.clone();
+
+    // Register get_logs method for the log view
+    rpc_server.register_method("get_logs", move |params| {
+        let app = app_clone.lock().unwrap();
+        let limit = params["limit"].as_u64().unwrap_or(100) as usize;
+
+        Ok(json!({
+            "path": app.logger.get_log_file_path().display().to_string(),
+            "lines": app.logger.buffer.recent(limit),
+            "dropped": app.logger.buffer.dropped(),
+        }))
+    });
+
     // Clone app state for get_memory_info handler
     let app_clone = app.clone();
 
@@ -350,8 +1396,655 @@ fn register_conversation_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>)
     });
 }
 
+/// Register APIs for checking and reloading config.toml
+fn register_config_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>) {
+    // Register check_config method to validate the config file without applying it
+    rpc_server.register_method("check_config", move |_| {
+        let file = ConfigFile::from_env()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine the config file location"))?;
+        let (exists, issues, message) = match file.load() {
+            Ok(config) => (config.is_some(), Vec::new(), None),
+            Err(e) => {
+                let issues = e
+                    .downcast_ref::<ConfigError>()
+                    .map(|error| error.issues.clone())
+                    .unwrap_or_default();
+                (true, issues, Some(e.to_string()))
+            }
+        };
+
+        Ok(json!({
+            "path": file.path().display().to_string(),
+            "exists": exists,
+            "valid": message.is_none(),
+            "issues": issues,
+            "message": message,
+        }))
+    });
+
+    // Clone app state for reload_config handler
+    let app_clone = app.clone();
+
+    // Register reload_config method to re-read the config file without restarting
+    rpc_server.register_method("reload_config", move |_| {
+        let file = ConfigFile::from_env()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine the config file location"))?;
+        let config = file.load()?.unwrap_or_default();
+        let applied = config.apply();
+
+        let mut app = app_clone.lock().unwrap();
+        app.model_parameters = app.model_parameters.merge(&config.model_parameters());
+        app.sampling_presets = PresetSelection::from_env();
+
+        Ok(json!({
+            "path": file.path().display().to_string(),
+            "applied": applied.applied,
+            "overridden": applied.overridden,
+            "restart_required": applied.restart_required,
+            "model_parameters": app.model_parameters,
+            "theme": theme_from_env(),
+        }))
+    });
+
+    // Register get_theme method so the UI starts with the theme chosen in config.toml
+    rpc_server.register_method("get_theme", move |_| {
+        Ok(json!({ "theme": theme_from_env(), "themes": THEMES }))
+    });
+
+    // Register get_scroll_bindings method so the UI scrolls with the keys chosen in config.toml
+    rpc_server.register_method("get_scroll_bindings", move |_| {
+        let bindings: serde_json::Map<String, serde_json::Value> = ScrollBindings::from_env()?
+            .bindings()
+            .into_iter()
+            .map(|(key, action)| (key.to_string(), json!(action.name())))
+            .collect();
+
+        Ok(json!({ "bindings": bindings }))
+    });
+}
+
+/// Register APIs for starring, tagging, resuming and searching saved conversations
+fn register_saved_session_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>) {
+    // Clone app state for star_session handler
+    let app_clone = app.clone();
+
+    // Register star_session method to star or unstar the current conversation
+    rpc_server.register_method("star_session", move |params| {
+        let mut app = app_clone.lock().unwrap();
+        let starred = params["starred"].as_bool().unwrap_or(true);
+        app.star_session(starred)?;
+
+        Ok(json!({ "session_id": app.saved_session_id, "starred": starred }))
+    });
+
+    // Clone app state for tag_message handler
+    let app_clone = app.clone();
+
+    // Register tag_message method to tag a message, e.g. as a decision or a todo
+    rpc_server.register_method("tag_message", move |params| {
+        let mut app = app_clone.lock().unwrap();
+        let tag = params["tag"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing tag parameter"))?;
+        let from_end = params["from_end"].as_u64().unwrap_or(1) as usize;
+        let remove = params["remove"].as_bool().unwrap_or(false);
+        let (index, changed) = app.tag_history_message(from_end, tag, remove)?;
+        let message = &app.history()[index];
+
+        Ok(json!({
+            "index": index,
+            "role": message.role,
+            "content": message.content,
+            "changed": changed,
+        }))
+    });
+
+    // Clone app state for list_sessions handler
+    let app_clone = app.clone();
+
+    // Register list_sessions method, listing saved conversations newest first
+    rpc_server.register_method("list_sessions", move |params| {
+        let app = app_clone.lock().unwrap();
+        let store = app
+            .session_store
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Conversations aren't being saved"))?;
+        let filter = session_filter(&params)?;
+
+        Ok(json!({
+            "sessions": store.list(&filter)?,
+            "current_session": app.saved_session_id,
+        }))
+    });
+
+    // Clone app state for search_sessions handler
+    let app_clone = app.clone();
+
+    // Register search_sessions method to find messages across saved conversations
+    rpc_server.register_method("search_sessions", move |params| {
+        let app = app_clone.lock().unwrap();
+        let store = app
+            .session_store
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Conversations aren't being saved"))?;
+        let query = params["query"].as_str().unwrap_or_default();
+        let filter = session_filter(&params)?;
+        if query.trim().is_empty() && filter.tag.is_none() {
+            return Err(anyhow::anyhow!("Give a query or a tag to search for"));
+        }
+        let limit = params["limit"].as_u64().unwrap_or(20) as usize;
+
+        Ok(json!({ "hits": store.search(query, &filter, limit)? }))
+    });
+
+    // Clone app state for resume_session handler
+    let app_clone = app.clone();
+
+    // Register resume_session method to continue a saved conversation
+    rpc_server.register_method("resume_session", move |params| {
+        let mut app = app_clone.lock().unwrap();
+        let session_id = params["session_id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing session_id parameter"))?;
+        let saved = app.resume_session(session_id)?;
+        app.log(&format!("Resumed session {}", saved.id));
+
+        Ok(json!({
+            "session_id": saved.id,
+            "title": saved.title(),
+            "starred": saved.starred,
+            "messages": saved.messages,
+            "summaries": saved.summaries,
+            "tasks": app.get_task_statuses(),
+        }))
+    });
+
+    // Clone app state for load_session handler
+    let app_clone = app.clone();
+
+    // Register load_session method to read a saved conversation without resuming it
+    rpc_server.register_method("load_session", move |params| {
+        let app = app_clone.lock().unwrap();
+        let store = app
+            .session_store
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Conversations aren't being saved"))?;
+        let session_id = params["session_id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing session_id parameter"))?;
+        let saved = store.resolve(session_id)?;
+
+        Ok(json!({
+            "title": saved.title(),
+            "session": saved,
+        }))
+    });
+}
+
+/// The saved-session filter given by `starred` and `tag` parameters
+fn session_filter(params: &serde_json::Value) -> Result<SessionFilter> {
+    Ok(SessionFilter {
+        starred: params["starred"].as_bool().unwrap_or(false),
+        tag: params["tag"].as_str().map(normalize_tag).transpose()?,
+    })
+}
+
+/// Register storage APIs
+fn register_storage_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>) {
+    // Clone app state for get_storage_usage handler
+    let app_clone = app.clone();
+
+    // Register get_storage_usage method to show disk usage by category
+    rpc_server.register_method("get_storage_usage", move |_| {
+        let app = app_clone.lock().unwrap();
+        let storage = StorageManager::from_env(app.logger.config());
+        let mut usage = serde_json::to_value(storage.usage()?)?;
+        usage["current_session"] = json!(app.session_id);
+        Ok(usage)
+    });
+
+    // Clone app state for prune_storage handler
+    let app_clone = app.clone();
+
+    // Register prune_storage method to remove old session logs now
+    rpc_server.register_method("prune_storage", move |_| {
+        let app = app_clone.lock().unwrap();
+        let storage = StorageManager::from_env(app.logger.config());
+        let report = storage.prune(Some(&app.session_id))?;
+        Ok(serde_json::to_value(report)?)
+    });
+
+    // Clone app state for pin_session handler
+    let app_clone = app.clone();
+
+    // Register pin_session method to exempt a session's logs from pruning
+    rpc_server.register_method("pin_session", move |params| {
+        let app = app_clone.lock().unwrap();
+        let storage = StorageManager::from_env(app.logger.config());
+        let session_id = match params["session_id"].as_str() {
+            Some(prefix) if !prefix.trim().is_empty() => storage.resolve_session(prefix)?,
+            _ => app.session_id.clone(),
+        };
+        let changed = storage.pin(&session_id)?;
+        Ok(json!({ "session_id": session_id, "changed": changed }))
+    });
+
+    // Clone app state for unpin_session handler
+    let app_clone = app.clone();
+
+    // Register unpin_session method to let a session's logs be pruned again
+    rpc_server.register_method("unpin_session", move |params| {
+        let app = app_clone.lock().unwrap();
+        let storage = StorageManager::from_env(app.logger.config());
+        let session_id = match params["session_id"].as_str() {
+            Some(prefix) if !prefix.trim().is_empty() => storage.resolve_session(prefix)?,
+            _ => app.session_id.clone(),
+        };
+        let changed = storage.unpin(&session_id)?;
+        Ok(json!({ "session_id": session_id, "changed": changed }))
+    });
+}
+
 /// Register system APIs
 fn register_system_apis(rpc_server: &mut RpcServer) {
     // Register get_version method to expose the Rust backend version
     rpc_server.register_method("get_version", move |_| Ok(json!({ "version": VERSION })));
+
+    // Register get_audit_log method for compliance tooling
+    rpc_server.register_method("get_audit_log", move |params| {
+        let limit = params["limit"].as_u64().unwrap_or(20) as usize;
+        let audit_log = AuditLog::from_env()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine the audit log location"))?;
+        let entries = audit_log.recent(limit)?;
+
+        Ok(json!({
+            "path": audit_log.path().display().to_string(),
+            "entries": entries,
+        }))
+    });
+
+    // Register get_index_status method so the status bar can show indexing progress
+    rpc_server.register_method("get_index_status", move |_| {
+        let status = std::env::current_dir()
+            .ok()
+            .and_then(|dir| WorkspaceIndex::for_dir(&dir))
+            .map(|index| index.status());
+        Ok(json!({ "status": status }))
+    });
+
+    // Register get_shell_environment method to show what Bash commands run with
+    rpc_server.register_method("get_shell_environment", move |_| {
+        let config = ShellConfig::from_env();
+        let snapshot = EnvSnapshot::capture(&config)?;
+
+        Ok(json!({
+            "shell": snapshot.shell,
+            "variables": snapshot.variables,
+            "summary": snapshot.summary(),
+        }))
+    });
+
+    // Register read_clipboard method so /paste-context can attach the clipboard to the
+    // next prompt instead of pasting it into the input box
+    rpc_server.register_method("read_clipboard", move |_| {
+        let content = read_clipboard()?;
+        if content.trim().is_empty() {
+            return Err(anyhow::anyhow!("The clipboard is empty"));
+        }
+
+        Ok(json!({
+            "format": PipedFormat::detect(&content).name(),
+            "bytes": content.len(),
+            "lines": content.lines().count(),
+            "truncated": content.len() > MAX_PIPED_INPUT_BYTES,
+            "max_bytes": MAX_PIPED_INPUT_BYTES,
+            "content": content,
+        }))
+    });
+
+    // Register save_session_state method so the UI can persist its draft input
+    rpc_server.register_method("save_session_state", move |params| {
+        let session_file = SessionFile::from_env()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine the session file location"))?;
+        let state: SessionState = serde_json::from_value(params)?;
+        session_file.save(&state)?;
+
+        Ok(json!({ "success": true }))
+    });
+
+    // Register load_session_state method to restore a draft after a restart or crash
+    rpc_server.register_method("load_session_state", move |_| {
+        let state = SessionFile::from_env().and_then(|session_file| session_file.load());
+
+        Ok(json!({ "state": state }))
+    });
+
+    // Register get_prompt_history method so Up in the input box can recall earlier prompts
+    rpc_server.register_method("get_prompt_history", move |_| {
+        let entries = PromptHistory::from_env()
+            .map(|history| history.load())
+            .unwrap_or_default();
+
+        Ok(json!({ "entries": entries }))
+    });
+
+    // Register add_prompt_history method to record a submitted prompt
+    rpc_server.register_method("add_prompt_history", move |params| {
+        let prompt = params["prompt"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing prompt parameter"))?;
+        let history = PromptHistory::from_env()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine the history file location"))?;
+        let added = history.add(prompt)?;
+
+        Ok(json!({ "success": true, "added": added }))
+    });
+}
+
+/// Register APIs for the advisory lock shared with other oli instances in the workspace
+fn register_workspace_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>) {
+    // Register get_workspace_status so the UI can warn when another instance holds the workspace
+    let app_clone = app.clone();
+    rpc_server.register_method("get_workspace_status", move |_| {
+        let lock = workspace_lock(&app_clone)?;
+        let mut lock = lock.lock().unwrap();
+
+        // Take the lock over if the instance holding it has exited since
+        lock.refresh()?;
+        Ok(workspace_status(&lock))
+    });
+
+    // Register override_workspace_lock to lift read-only mode in a second instance
+    let app_clone = app.clone();
+    rpc_server.register_method("override_workspace_lock", move |_| {
+        let lock = workspace_lock(&app_clone)?;
+        let status = {
+            let mut lock = lock.lock().unwrap();
+            lock.override_read_only();
+            workspace_status(&lock)
+        };
+        app_clone
+            .lock()
+            .unwrap()
+            .log("Read-only mode overridden; all tools are enabled");
+        Ok(status)
+    });
+}
+
+/// The workspace lock `app` shares with the other sessions of a daemon
+fn workspace_lock(app: &Arc<Mutex<App>>) -> Result<Arc<Mutex<WorkspaceLock>>> {
+    app.lock()
+        .unwrap()
+        .workspace_lock
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("The workspace lock is not available"))
+}
+
+fn workspace_status(lock: &WorkspaceLock) -> serde_json::Value {
+    json!({
+        "lock_file": lock.path().display().to_string(),
+        "primary": lock.is_primary(),
+        "holder": lock.holder(),
+        "read_only": lock.is_read_only(),
+        "warning": lock.warning(),
+    })
+}
+
+/// Register APIs for staging the working tree's changes hunk by hunk
+fn register_staging_apis(rpc_server: &mut RpcServer) {
+    // Register get_unstaged_changes so the UI can walk the changeset
+    rpc_server.register_method("get_unstaged_changes", move |_| {
+        let stager = GitStager::for_dir(&std::env::current_dir()?)?;
+        let changes = stager.changes()?;
+
+        Ok(json!({
+            "repo_root": stager.repo_root().display().to_string(),
+            "files": changes,
+        }))
+    });
+
+    // Register stage_hunk to stage one hunk, like answering "y" in git add -p
+    rpc_server.register_method("stage_hunk", move |params| {
+        let path = params["path"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'path' parameter"))?;
+        let hunk_id = params["hunk_id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'hunk_id' parameter"))?;
+
+        GitStager::for_dir(&std::env::current_dir()?)?.stage_hunk(path, hunk_id)?;
+        Ok(json!({ "success": true }))
+    });
+
+    // Register stage_file to stage a whole file, including untracked and binary files
+    rpc_server.register_method("stage_file", move |params| {
+        let path = params["path"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'path' parameter"))?;
+
+        GitStager::for_dir(&std::env::current_dir()?)?.stage_file(path)?;
+        Ok(json!({ "success": true }))
+    });
+}
+
+/// Register APIs for previewing, applying and undoing project-wide renames
+fn register_rename_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>) {
+    // Register preview_rename to show a rename's edits before anything is written
+    let app_clone = app.clone();
+    rpc_server.register_method("preview_rename", move |params| {
+        let old_name = params["old_name"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'old_name' parameter"))?;
+        let new_name = params["new_name"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'new_name' parameter"))?;
+
+        let mut app = app_clone.lock().unwrap();
+        let plan = app.preview_rename(old_name, new_name)?;
+        Ok(json!({
+            "old_name": plan.old_name,
+            "new_name": plan.new_name,
+            "source": plan.source,
+            "occurrences": plan.occurrences(),
+            "files": plan.files,
+        }))
+    });
+
+    // Register apply_rename to write the previewed rename to every file at once
+    let app_clone = app.clone();
+    rpc_server.register_method("apply_rename", move |_| {
+        let mut app = app_clone.lock().unwrap();
+        let files = app.apply_rename()?;
+        Ok(json!({ "success": true, "files": files }))
+    });
+
+    // Register undo_rename to put the files of the last rename back
+    let app_clone = app.clone();
+    rpc_server.register_method("undo_rename", move |_| {
+        let mut app = app_clone.lock().unwrap();
+        let description = app.undo_rename()?;
+        Ok(json!({ "success": true, "message": description }))
+    });
+}
+
+/// Register APIs through which an editor extension shares what the user has open
+fn register_editor_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>) {
+    // Shared with the app rather than read through it, so updates don't wait for a run
+    let editor_context = app.lock().unwrap().editor_context.clone();
+
+    // Register set_editor_context to replace the file, cursor and selection the
+    // model is told about; an empty context clears it
+    let context_clone = editor_context.clone();
+    rpc_server.register_method("set_editor_context", move |params| {
+        let context = EditorContext::from_params(&params)?;
+        let working_dir = std::env::current_dir().ok();
+        let section = context.section(working_dir.as_deref().and_then(|dir| dir.to_str()));
+        *context_clone.lock().unwrap() = context;
+        Ok(json!({ "success": true, "prompt_section": section }))
+    });
+
+    // Register get_editor_context to return what the model is told about
+    rpc_server.register_method("get_editor_context", move |_| {
+        Ok(json!(*editor_context.lock().unwrap()))
+    });
+}
+
+/// Register APIs for side questions answered while the main task runs
+/// Register APIs listing and killing the language servers and Bash commands oli started
+fn register_process_apis(rpc_server: &mut RpcServer) {
+    // Register get_child_processes method to list the children of every oli instance
+    rpc_server.register_method("get_child_processes", move |_| {
+        let supervisor = ProcessSupervisor::from_env()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine the process directory"))?;
+        let processes: Vec<serde_json::Value> = supervisor
+            .list()
+            .into_iter()
+            .map(|child| {
+                json!({
+                    "orphaned": child.is_orphaned(),
+                    "own": child.owner_pid == std::process::id(),
+                    "idle_secs": child.idle_for().as_secs(),
+                    "process": child,
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "directory": supervisor.dir().display().to_string(),
+            "idle_timeout_secs": ProcessSupervisor::idle_timeout_from_env().map(|t| t.as_secs()),
+            "processes": processes,
+        }))
+    });
+
+    // Register kill_child_process method to stop a language server or Bash command
+    rpc_server.register_method("kill_child_process", move |params| {
+        let pid = params["pid"]
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'pid' parameter"))?;
+        let supervisor = ProcessSupervisor::from_env()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine the process directory"))?;
+        let child = supervisor.kill(pid as u32)?;
+
+        Ok(json!({ "success": true, "process": child }))
+    });
+}
+
+/// Register APIs managing the tools and Bash commands the project always allows
+fn register_permission_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>) {
+    // Read now: the App is locked for as long as the main task runs
+    let permissions = app
+        .lock()
+        .unwrap()
+        .current_working_dir
+        .as_ref()
+        .and_then(|dir| PermissionFile::for_working_dir(std::path::Path::new(dir)));
+
+    // Register get_permission_rules method to list the always-allow rules
+    let permissions_clone = permissions.clone();
+    rpc_server.register_method("get_permission_rules", move |_| {
+        let file = permissions_clone
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No working directory or home to keep rules for"))?;
+        Ok(permission_rules_json(file, &file.load()?))
+    });
+
+    // Register set_permission_rule method to always allow a tool or Bash command, or stop
+    let permissions_clone = permissions.clone();
+    rpc_server.register_method("set_permission_rule", move |params| {
+        let file = permissions_clone
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No working directory or home to keep rules for"))?;
+        let scope = RuleScope::parse(
+            params["scope"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing 'scope' parameter"))?,
+        )?;
+        let value = params["value"]
+            .as_str()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'value' parameter"))?;
+        let allow = params["allow"].as_bool().unwrap_or(true);
+
+        let changed = file.update(|rules| {
+            if allow {
+                rules.add(scope, value)
+            } else {
+                rules.remove(scope, value)
+            }
+        })?;
+        let mut result = permission_rules_json(file, &file.load()?);
+        result["changed"] = json!(changed);
+        Ok(result)
+    });
+
+    // Register set_permission_level method to set a tool's level for the project
+    rpc_server.register_method("set_permission_level", move |params| {
+        let file = permissions
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No working directory or home to keep rules for"))?;
+        let tool = params["tool"]
+            .as_str()
+            .map(str::trim)
+            .filter(|tool| !tool.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'tool' parameter"))?;
+        let level = match params["level"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'level' parameter"))?
+        {
+            "default" => None,
+            level => Some(PermissionLevel::parse(level)?),
+        };
+
+        let changed = file.update(|rules| rules.set_level(tool, level))?;
+        let mut result = permission_rules_json(file, &file.load()?);
+        result["changed"] = json!(changed);
+        Ok(result)
+    });
+}
+
+/// The rules of a permissions file as returned by the permission APIs, with the
+/// configured policy they apply on top of
+fn permission_rules_json(file: &PermissionFile, rules: &PermissionRules) -> serde_json::Value {
+    let policy = PermissionPolicy::from_env();
+    json!({
+        "path": file.path().display().to_string(),
+        "project": file.project(),
+        "tools": rules.tools,
+        "commands": rules.commands,
+        "levels": rules.levels,
+        "configured_levels": policy.as_ref().map(|policy| &policy.levels).ok(),
+        "edit_paths": policy.as_ref().map(|policy| &policy.edit_paths).ok(),
+        "policy_error": policy.as_ref().err().map(|e| format!("{e:#}")),
+    })
+}
+
+fn register_ask_apis(rpc_server: &mut RpcServer, app: &Arc<Mutex<App>>) {
+    let asks = AskSession::new();
+    // Read now: the App is locked for as long as the main task runs
+    let working_dir = app.lock().unwrap().current_working_dir.clone();
+
+    // Register ask to answer a question with read-only tools and a cheaper model
+    let asks_clone = asks.clone();
+    rpc_server.register_background_method("ask", move |params| {
+        let question = params["question"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'question' parameter"))?;
+        let answer = asks_clone.ask(question, working_dir.clone())?;
+        Ok(json!(answer))
+    });
+
+    // Register promote_ask to add an answered side question to the main conversation
+    let app_clone = app.clone();
+    rpc_server.register_method("promote_ask", move |params| {
+        let id = params["id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'id' parameter"))?;
+        let answer = asks
+            .get(id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown side question: {id}"))?;
+
+        app_clone.lock().unwrap().promote_answer(&answer)?;
+        Ok(json!({ "success": true }))
+    });
 }
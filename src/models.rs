@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 // Model name constants to avoid duplication
 pub const ANTHROPIC_MODEL_NAME: &str = "claude-sonnet-4-20250514";
 pub const OPENAI_MODEL_NAME: &str = "gpt-4o";
-pub const GEMINI_MODEL_NAME: &str = "gemini-2.5-pro-exp-03-25";
+pub use crate::apis::gemini::GEMINI_MODEL_NAME;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelConfig {
@@ -12,15 +12,74 @@ pub struct ModelConfig {
     pub description: String,
     pub recommended_for: String,
     pub supports_agent: bool,
+    /// Tokens of context the model accepts, where known for the model itself
+    #[serde(default)]
+    pub context_window: Option<u32>,
 }
 
 impl ModelConfig {
     pub fn has_agent_support(&self) -> bool {
         self.supports_agent
     }
+
+    /// The provider the model's requests are priced under in the request ledger,
+    /// or None for OpenRouter, whose prices vary by upstream provider
+    pub fn pricing_provider(&self) -> Option<&'static str> {
+        let name = self.name.to_lowercase();
+        if name.ends_with("(local)") {
+            Some("ollama")
+        } else if name.ends_with("(openrouter)") {
+            None
+        } else if name.contains("claude") {
+            Some("anthropic")
+        } else if name.contains("gpt") || name.contains("azure") {
+            Some("openai")
+        } else if name.contains("gemini") {
+            Some("gemini")
+        } else {
+            None
+        }
+    }
+
+    /// Tokens of context the model accepts: its own window where known, else the
+    /// usual window of its provider
+    pub fn context_limit(&self) -> u32 {
+        if let Some(tokens) = self.context_window {
+            return tokens;
+        }
+        let provider = if self.name.to_lowercase().ends_with("(local)") {
+            Some(LLMProvider::Ollama)
+        } else {
+            cloud_provider(&self.name)
+        };
+        provider
+            .map(|provider| provider.context_window())
+            .unwrap_or(LLMProvider::OpenAICompatible.context_window())
+    }
+
+    /// Tool result limits sized to the model's context window, with the overrides
+    /// set in `OLI_TOOL_RESULT_LIMITS`
+    pub fn result_limits(&self) -> ResultLimits {
+        ResultLimits::for_context_window(self.context_limit()).with_env_overrides()
+    }
+
+    /// List price of the model in USD per million tokens, if known. Task and
+    /// session costs are estimated from it and the usage providers report.
+    pub fn price(&self) -> Option<ModelPrice> {
+        model_price(self.pricing_provider()?, &self.file_name)
+    }
 }
 
-use crate::apis::ollama::OllamaClient;
+use crate::agent::result_limits::ResultLimits;
+use crate::apis::ledger::{model_price, ModelPrice};
+use crate::apis::ollama::{OllamaClient, OllamaPullProgress};
+use crate::apis::openai::{
+    AZURE_OPENAI_DEPLOYMENT, AZURE_OPENAI_DEPLOYMENT_ENV, OPENAI_COMPATIBLE_BASE_URL_ENV,
+    OPENAI_COMPATIBLE_MODEL_ENV,
+};
+use crate::apis::openrouter::{OpenRouterClient, OpenRouterModelInfo};
+use crate::apis::provider::LLMProvider;
+use crate::app::api_keys::cloud_provider;
 use anyhow::Result;
 
 pub fn get_available_models() -> Vec<ModelConfig> {
@@ -33,6 +92,7 @@ pub fn get_available_models() -> Vec<ModelConfig> {
             description: "Latest Anthropic Claude with advanced code capabilities".into(),
             recommended_for: "Professional code tasks, requires ANTHROPIC_API_KEY".into(),
             supports_agent: true,
+            context_window: Some(200_000),
         },
         // GPT-4o - OpenAI model supporting tool use
         ModelConfig {
@@ -41,6 +101,7 @@ pub fn get_available_models() -> Vec<ModelConfig> {
             description: "Latest OpenAI model with advanced tool use capabilities".into(),
             recommended_for: "Professional code tasks, requires OPENAI_API_KEY".into(),
             supports_agent: true,
+            context_window: Some(128_000),
         },
         // Gemini 2.5 Pro - Google model supporting tool use
         ModelConfig {
@@ -49,9 +110,15 @@ pub fn get_available_models() -> Vec<ModelConfig> {
             description: "Google's latest Gemini model with advanced code capabilities".into(),
             recommended_for: "Professional code tasks, requires GEMINI_API_KEY".into(),
             supports_agent: true,
+            context_window: Some(1_048_576),
         },
+        // OpenAI models deployed in the company's own Azure OpenAI resource
+        azure_model_config(),
     ];
 
+    // The model of the OpenAI-compatible API, once its base URL and model are set
+    models.extend(openai_compatible_model_config());
+
     // Try to fetch available models from Ollama
     if let Ok(ollama_models) = get_available_ollama_models() {
         // Add each available Ollama model to the list
@@ -74,13 +141,133 @@ pub fn get_available_models() -> Vec<ModelConfig> {
                 description,
                 recommended_for: "Local code tasks, requires Ollama to be running".into(),
                 supports_agent: true,
+                // Ollama runs models with its default context length unless configured
+                context_window: None,
             });
         }
     }
 
+    // With an OpenRouter key, add the models it serves that can call tools
+    models.extend(
+        get_available_openrouter_models()
+            .iter()
+            .map(openrouter_model_config),
+    );
+
     models
 }
 
+/// The index of the local model `name`, with or without its `:latest` tag
+pub fn find_local_model(models: &[ModelConfig], name: &str) -> Option<usize> {
+    let name = name.trim();
+    models.iter().position(|model| {
+        model.name.ends_with("(local)")
+            && (model.file_name == name || model.file_name == format!("{name}:latest"))
+    })
+}
+
+/// Download `model` into Ollama, passing each progress update to `on_progress`
+pub fn pull_ollama_model(model: &str, on_progress: impl FnMut(&OllamaPullProgress)) -> Result<()> {
+    let model = model.trim();
+    if model.is_empty() {
+        anyhow::bail!("Name the Ollama model to pull, e.g. llama3.2");
+    }
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let client = OllamaClient::new(Some(model.to_string()))?;
+    runtime.block_on(client.pull_model(model, on_progress))
+}
+
+/// The model list entry of the Azure OpenAI deployment in `AZURE_OPENAI_DEPLOYMENT`,
+/// `gpt-4o` unless set. "Azure" in the name is what routes it to Azure, and the
+/// deployment is what requests select it by.
+pub fn azure_model_config() -> ModelConfig {
+    let deployment = std::env::var(AZURE_OPENAI_DEPLOYMENT_ENV)
+        .ok()
+        .filter(|deployment| !deployment.trim().is_empty())
+        .unwrap_or_else(|| AZURE_OPENAI_DEPLOYMENT.to_string());
+    ModelConfig {
+        name: "Azure OpenAI".into(),
+        description: format!("The {deployment} deployment of your Azure OpenAI resource"),
+        file_name: deployment,
+        recommended_for: "Requires AZURE_OPENAI_ENDPOINT and AZURE_OPENAI_API_KEY".into(),
+        supports_agent: true,
+        context_window: None,
+    }
+}
+
+/// The model list entry of the OpenAI-compatible API in `OPENAI_COMPATIBLE_BASE_URL`,
+/// serving `OPENAI_COMPATIBLE_MODEL`, or None until both are set. "Compatible" in the
+/// name is what routes it there, and the model is what requests select it by.
+pub fn openai_compatible_model_config() -> Option<ModelConfig> {
+    let setting = |env_var| {
+        std::env::var(env_var)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let base_url = setting(OPENAI_COMPATIBLE_BASE_URL_ENV)?;
+    let model = setting(OPENAI_COMPATIBLE_MODEL_ENV)?;
+    Some(ModelConfig {
+        name: "OpenAI-compatible".into(),
+        description: format!("{model} at {base_url}"),
+        file_name: model,
+        recommended_for: "Set OPENAI_COMPATIBLE_API_KEY if the server takes a key".into(),
+        supports_agent: true,
+        context_window: None,
+    })
+}
+
+/// The model list entry of an OpenRouter model. The "(OpenRouter)" suffix is
+/// what routes it to OpenRouter, and its ID is what requests select it by.
+pub fn openrouter_model_config(info: &OpenRouterModelInfo) -> ModelConfig {
+    let context = info
+        .context_length
+        .map(|tokens| format!(", {}k tokens of context", tokens / 1000))
+        .unwrap_or_default();
+    ModelConfig {
+        name: format!("{} (OpenRouter)", info.name),
+        file_name: info.id.clone(),
+        description: format!("{} via OpenRouter{context}", info.id),
+        recommended_for: "Requires OPENROUTER_API_KEY".into(),
+        supports_agent: true,
+        context_window: info.context_length.map(|tokens| tokens as u32),
+    }
+}
+
+fn get_available_openrouter_models() -> Vec<OpenRouterModelInfo> {
+    let has_key = std::env::var("OPENROUTER_API_KEY").is_ok_and(|key| !key.trim().is_empty());
+    if !has_key {
+        return Vec::new();
+    }
+
+    let Ok(runtime) = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    else {
+        return Vec::new();
+    };
+    let result = runtime.block_on(async {
+        let client = OpenRouterClient::new(None)?;
+        // Short timeout so an unreachable OpenRouter doesn't hold up startup
+        tokio::time::timeout(std::time::Duration::from_secs(5), client.list_models())
+            .await
+            .map_err(|_| anyhow::anyhow!("Timeout waiting for OpenRouter"))?
+    });
+
+    match result {
+        Ok(models) => models
+            .into_iter()
+            .filter(OpenRouterModelInfo::supports_tools)
+            .collect(),
+        Err(e) => {
+            eprintln!("Returning no OpenRouter models due to error: {e}");
+            Vec::new()
+        }
+    }
+}
+
 fn get_available_ollama_models() -> Result<Vec<crate::apis::ollama::OllamaModelInfo>> {
     // Try to get the list of models from Ollama in a non-async context
     // We'll use a short timeout to avoid blocking the UI if Ollama is not running
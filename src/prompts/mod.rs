@@ -1,6 +1,10 @@
 //! This module contains all the prompts used in the application.
 //! Centralizing prompts helps maintain consistency and makes them easier to update.
 
+pub mod templates;
+
+use crate::context::project_instructions::ProjectInstructions;
+
 /// Format the working directory prompt with the provided directory
 pub fn format_working_directory_prompt(working_dir: &str) -> String {
     // We need to use a string literal for the format! macro
@@ -18,6 +22,33 @@ pub fn add_working_directory_to_prompt(prompt: &str, working_dir: &str) -> Strin
     }
 }
 
+/// Format the project instructions section from the project's `OLI.md` or `AGENTS.md`
+pub fn format_project_instructions_prompt(instructions: &ProjectInstructions) -> String {
+    let truncated = if instructions.truncated {
+        "\n\n(The file is longer; read it for the rest.)"
+    } else {
+        ""
+    };
+    format!(
+        "## PROJECT INSTRUCTIONS\nThe project's {} gives these instructions for working in it. Follow them unless the user asks otherwise.\n\n{}{truncated}",
+        instructions.file_name(),
+        instructions.contents
+    )
+}
+
+/// Add the project instructions section to a system prompt if it doesn't already have it
+pub fn add_project_instructions_to_prompt(
+    prompt: &str,
+    instructions: &ProjectInstructions,
+) -> String {
+    if prompt.contains("## PROJECT INSTRUCTIONS") {
+        prompt.to_string()
+    } else {
+        let instructions_section = format_project_instructions_prompt(instructions);
+        format!("{prompt}\n\n{instructions_section}")
+    }
+}
+
 /// Default system prompt for the agent including working directory information
 pub fn get_agent_prompt_with_cwd(working_dir: Option<&str>) -> String {
     let base_prompt = DEFAULT_AGENT_PROMPT.to_string();
@@ -79,12 +110,21 @@ When helping users:
 ## TOOL USAGE
 You have access to various tools for working with code:
 - Use search tools to explore codebases and find relevant files
+- Use FindFile when you know roughly what a file is called but not its path
+- Use DependencyGraph to find what imports or depends on a module or package before changing it
+- Use GitStatus, GitDiff and GitLog rather than Bash to inspect the repository; they don't need permission
 - Use file reading tools to understand code contents
 - Use file editing and writing tools to make changes
-- Use command execution to run tests and perform operations
+- Use NotebookRead and NotebookEditCell for Jupyter notebooks (.ipynb) instead of Read, Edit and Write
+- Use RunTests to run the project's tests (cargo, pytest or npm); it reports the failing tests
+- Use command execution for other operations, and for test runners RunTests doesn't know
+- Use Agent to delegate a broad search or exploration, e.g. "find where config is loaded", to a sub-agent that reports back only its findings
 - Use code parsing when you need to analyze structure and relationships
 - Always choose the most appropriate tool for each task
 
+## CITING EVIDENCE
+Each tool result is numbered with a tag such as [#3]. When your final answer makes a claim based on a tool result, e.g. that a function is unused or a test fails, cite the result right after the claim using its tag, like "`parse_args` is never called [#3]". Only cite tags that appear in the tool results of this conversation.
+
 ## COMMUNICATION APPROACH
 - Be direct and to the point
 - Use precise technical language
@@ -106,6 +146,35 @@ Always ensure your code and suggestions are:
 Always prioritize being helpful, accurate, and providing working solutions that follow modern software development practices.
 "#;
 
+/// Prompt for `/init`, asking the agent to write a starter `OLI.md` for the project
+pub const INIT_PROJECT_PROMPT: &str = r##"
+Write a starter OLI.md for the project in the working directory: the instructions oli reads at the start of every session there.
+
+Explore the repository with your read-only tools first. Look at the README, the build and package manifests (Cargo.toml, package.json, pyproject.toml, Makefile and the like), CI configuration and the top-level layout of the source and tests.
+
+Then reply with ONLY the contents of the file, in Markdown, starting with "# OLI.md" and with these sections:
+- ## Project Structure: what the main directories and modules hold
+- ## Build Commands: how to build, lint and format, as exact commands
+- ## Test Commands: how to run all the tests and a single test
+- ## Conventions: code style, error handling and naming the code follows
+
+Only include what you found in the repository. Keep it under 60 lines and don't wrap the file in a code fence.
+"##;
+
+/// System prompt for a sub-agent started with the Agent tool, which explores on the
+/// main agent's behalf and reports back in one message
+pub const SUB_AGENT_PROMPT: &str = r#"
+You are a sub-agent of oli, a coding assistant. The main agent delegated an objective to you so that your exploration doesn't fill up its conversation.
+
+You only have read-only tools: search and read the code, but don't try to change files or run commands. Work through the objective as thoroughly as it needs, then reply with a concise report that answers it.
+
+The main agent only sees your final reply, not your tool results, so:
+- Name the files, line numbers and symbols your findings are based on
+- Quote the few lines of code that matter rather than describing them
+- Don't cite tool results with tags such as [#3]
+- Say what you couldn't find or weren't sure about
+"#;
+
 /// Prompt for generating conversation summaries
 pub const CONVERSATION_SUMMARY_PROMPT: &str = r#"
 You're assisting with summarizing the conversation history. Please create a CONCISE summary of the following conversation, focusing on:
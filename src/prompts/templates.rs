@@ -0,0 +1,170 @@
+use super::{add_working_directory_to_prompt, DEFAULT_SESSION_PROMPT};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the user-level prompt directory, `~/.oli`
+pub const PROMPT_DIR_ENV: &str = "OLI_PROMPT_DIR";
+
+/// File replacing the default system prompt, in the user-level directory or the
+/// project's `.oli` directory
+pub const SYSTEM_PROMPT_FILE: &str = "system_prompt.md";
+
+/// Directory of named templates, e.g. `prompts/reviewer.md` for `/prompt reviewer`
+pub const TEMPLATES_DIR: &str = "prompts";
+
+/// The project's directory of prompt overrides and templates
+pub const PROJECT_PROMPT_DIR: &str = ".oli";
+
+/// Where the system prompt in use comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PromptSource {
+    /// The built-in prompt
+    Default,
+    /// `system_prompt.md` in the user-level directory
+    User { path: PathBuf },
+    /// `.oli/system_prompt.md` in the project
+    Project { path: PathBuf },
+    /// A template chosen with `/prompt <name>`
+    Template { name: String, path: PathBuf },
+}
+
+/// A resolved system prompt and where it came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SystemPrompt {
+    pub source: PromptSource,
+    pub content: String,
+}
+
+impl SystemPrompt {
+    /// The prompt with the working directory section added, as sent to the model
+    pub fn with_working_directory(&self, working_dir: &str) -> String {
+        add_working_directory_to_prompt(self.content.trim(), working_dir)
+    }
+}
+
+/// System prompt overrides and named templates, looked up in the project's `.oli`
+/// directory before the user-level one, so a project can replace what a user set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptLibrary {
+    user_dir: Option<PathBuf>,
+    project_dir: Option<PathBuf>,
+}
+
+impl PromptLibrary {
+    pub fn new(user_dir: Option<PathBuf>, project_dir: Option<PathBuf>) -> Self {
+        Self {
+            user_dir,
+            project_dir,
+        }
+    }
+
+    /// The library for a project: `~/.oli`, unless overridden by `OLI_PROMPT_DIR`,
+    /// and the project's `.oli`
+    pub fn for_working_dir(working_dir: &Path) -> Self {
+        let user_dir = match std::env::var(PROMPT_DIR_ENV) {
+            Ok(dir) if !dir.trim().is_empty() => Some(PathBuf::from(dir)),
+            _ => dirs::home_dir().map(|home| home.join(".oli")),
+        };
+        Self::new(user_dir, Some(working_dir.join(PROJECT_PROMPT_DIR)))
+    }
+
+    /// The system prompt to use: the template `name` if one is chosen, else the
+    /// project's override, the user's override, or the built-in prompt. Empty
+    /// override files are ignored.
+    pub fn resolve(&self, template: Option<&str>) -> Result<SystemPrompt> {
+        if let Some(name) = template {
+            let (path, content) = self.template(name)?;
+            return Ok(SystemPrompt {
+                source: PromptSource::Template {
+                    name: name.to_string(),
+                    path,
+                },
+                content,
+            });
+        }
+
+        if let Some(dir) = &self.project_dir {
+            let path = dir.join(SYSTEM_PROMPT_FILE);
+            if let Some(content) = read_prompt(&path)? {
+                return Ok(SystemPrompt {
+                    source: PromptSource::Project { path },
+                    content,
+                });
+            }
+        }
+        if let Some(dir) = &self.user_dir {
+            let path = dir.join(SYSTEM_PROMPT_FILE);
+            if let Some(content) = read_prompt(&path)? {
+                return Ok(SystemPrompt {
+                    source: PromptSource::User { path },
+                    content,
+                });
+            }
+        }
+        Ok(SystemPrompt {
+            source: PromptSource::Default,
+            content: DEFAULT_SESSION_PROMPT.to_string(),
+        })
+    }
+
+    /// Names of the templates in either directory, sorted
+    pub fn templates(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .dirs()
+            .filter_map(|dir| std::fs::read_dir(dir.join(TEMPLATES_DIR)).ok())
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "md" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_string_lossy().to_string())
+            })
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// The path and contents of the template `name`, the project's over the user's
+    pub fn template(&self, name: &str) -> Result<(PathBuf, String)> {
+        let name = name.trim();
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            anyhow::bail!("Invalid prompt template name '{name}'");
+        }
+        for dir in self.dirs() {
+            let path = dir.join(TEMPLATES_DIR).join(format!("{name}.md"));
+            if let Some(content) = read_prompt(&path)? {
+                return Ok((path, content));
+            }
+        }
+
+        let available = self.templates();
+        if available.is_empty() {
+            anyhow::bail!(
+                "No prompt template named '{name}'. Add one as {PROJECT_PROMPT_DIR}/{TEMPLATES_DIR}/{name}.md or ~/.oli/{TEMPLATES_DIR}/{name}.md"
+            )
+        }
+        anyhow::bail!(
+            "No prompt template named '{name}'. Available: {}",
+            available.join(", ")
+        )
+    }
+
+    // The project's directory first
+    fn dirs(&self) -> impl Iterator<Item = &PathBuf> {
+        self.project_dir.iter().chain(self.user_dir.iter())
+    }
+}
+
+// The prompt in a file, or None when it's missing or blank
+fn read_prompt(path: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) if content.trim().is_empty() => Ok(None),
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
@@ -0,0 +1,320 @@
+use crate::tools::fs::oliignore::OLIIGNORE_FILE;
+use anyhow::Result;
+use ignore::WalkBuilder;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Files larger than this are not parsed
+const MAX_FILE_BYTES: u64 = 512 * 1024;
+
+lazy_static! {
+    static ref RUST_USE: Regex =
+        Regex::new(r"^\s*(?:pub(?:\([^)]*\))?\s+)?use\s+(?:::)?([A-Za-z_][\w:]*)").unwrap();
+    static ref RUST_EXTERN_CRATE: Regex = Regex::new(r"^\s*extern\s+crate\s+(\w+)").unwrap();
+    static ref PYTHON_IMPORT: Regex = Regex::new(r"^\s*import\s+([\w., ]+)").unwrap();
+    static ref PYTHON_FROM: Regex = Regex::new(r"^\s*from\s+(\.*[\w.]*)\s+import\b").unwrap();
+    static ref JS_FROM: Regex = Regex::new(r#"\bfrom\s*['"]([^'"]+)['"]"#).unwrap();
+    static ref JS_BARE_IMPORT: Regex = Regex::new(r#"^\s*import\s*['"]([^'"]+)['"]"#).unwrap();
+    static ref JS_CALL: Regex =
+        Regex::new(r#"\b(?:require|import)\s*\(\s*['"]([^'"]+)['"]\s*\)"#).unwrap();
+    static ref GO_IMPORT: Regex = Regex::new(r#"^\s*import\s+(?:[\w.]+\s+)?"([^"]+)""#).unwrap();
+    static ref GO_BLOCK_LINE: Regex = Regex::new(r#"^\s*(?:[\w.]+\s+)?"([^"]+)""#).unwrap();
+    static ref TOML_KEY: Regex = Regex::new(r#"^\s*"?([A-Za-z0-9_.-]+)"?\s*="#).unwrap();
+    static ref QUOTED: Regex = Regex::new(r#"["']([^"']+)["']"#).unwrap();
+    static ref REQUIREMENT_NAME: Regex = Regex::new(r"^\s*([A-Za-z0-9][A-Za-z0-9._-]*)").unwrap();
+}
+
+/// What a file or manifest depends on, as written in it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DependencyGraph {
+    /// Source files and manifests, relative to the root, to the modules or packages
+    /// they import or declare
+    pub adjacency: BTreeMap<String, Vec<String>>,
+    pub files_scanned: usize,
+}
+
+impl DependencyGraph {
+    /// Parse the imports and manifests of every file under `root`, skipping those
+    /// ignored by git or `.oliignore`
+    pub fn build(root: &Path) -> Result<Self> {
+        if !root.is_dir() {
+            anyhow::bail!("{} is not a directory", root.display());
+        }
+
+        let mut graph = Self::default();
+        for path in workspace_files(root) {
+            let Some(dependencies) = parse_file(&path) else {
+                continue;
+            };
+            graph.files_scanned += 1;
+            if dependencies.is_empty() {
+                continue;
+            }
+
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            graph.adjacency.insert(
+                relative.to_string_lossy().replace('\\', "/"),
+                dependencies.into_iter().collect(),
+            );
+        }
+        Ok(graph)
+    }
+
+    /// The part of the graph that depends on `target`: a package, a module or a
+    /// file. A dependency matches when one of its path segments is the target, so
+    /// `api_client` matches `crate::apis::api_client::Message` and `./api_client`.
+    pub fn dependents_of(&self, target: &str) -> BTreeMap<String, Vec<String>> {
+        let target = normalize_target(target);
+        self.adjacency
+            .iter()
+            .filter_map(|(file, dependencies)| {
+                let matching: Vec<String> = dependencies
+                    .iter()
+                    .filter(|dependency| matches_target(dependency, &target))
+                    .cloned()
+                    .collect();
+                (!matching.is_empty()).then(|| (file.clone(), matching))
+            })
+            .collect()
+    }
+}
+
+fn workspace_files(root: &Path) -> Vec<PathBuf> {
+    WalkBuilder::new(root)
+        .standard_filters(true)
+        .add_custom_ignore_filename(OLIIGNORE_FILE)
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .filter(|entry| {
+            entry
+                .metadata()
+                .is_ok_and(|metadata| metadata.len() <= MAX_FILE_BYTES)
+        })
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// Dependencies of a file, or None when it's neither source code nor a manifest
+/// this parser understands
+pub fn parse_file(path: &Path) -> Option<BTreeSet<String>> {
+    let name = path.file_name()?.to_str()?;
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+
+    let parse: fn(&str) -> BTreeSet<String> = match (name, extension) {
+        ("Cargo.toml", _) => parse_cargo_manifest,
+        ("package.json", _) => parse_package_json,
+        ("pyproject.toml", _) => parse_pyproject,
+        (name, "txt") if name.starts_with("requirements") => parse_requirements,
+        (_, "rs") => parse_rust_imports,
+        (_, "py") => parse_python_imports,
+        (_, "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs") => parse_js_imports,
+        (_, "go") => parse_go_imports,
+        _ => return None,
+    };
+
+    let content = fs::read_to_string(path).ok()?;
+    Some(parse(&content))
+}
+
+/// Modules named by `use` and `extern crate`, without the braces or glob
+pub fn parse_rust_imports(content: &str) -> BTreeSet<String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            RUST_USE
+                .captures(line)
+                .or_else(|| RUST_EXTERN_CRATE.captures(line))
+        })
+        .map(|captures| captures[1].trim_end_matches(':').to_string())
+        .filter(|module| !module.is_empty())
+        .collect()
+}
+
+/// Modules named by `import a, b` and `from a import b`
+pub fn parse_python_imports(content: &str) -> BTreeSet<String> {
+    let mut modules = BTreeSet::new();
+    for line in content.lines() {
+        if let Some(captures) = PYTHON_FROM.captures(line) {
+            modules.insert(captures[1].to_string());
+        } else if let Some(captures) = PYTHON_IMPORT.captures(line) {
+            for module in captures[1].split(',') {
+                // `import numpy as np` names numpy
+                if let Some(module) = module.split_whitespace().next() {
+                    modules.insert(module.to_string());
+                }
+            }
+        }
+    }
+    modules
+}
+
+/// Specifiers of `import`, `export ... from`, `require()` and dynamic `import()`,
+/// including imports split across lines, whose `from` clause ends a later line
+pub fn parse_js_imports(content: &str) -> BTreeSet<String> {
+    content
+        .lines()
+        .flat_map(|line| {
+            JS_FROM
+                .captures_iter(line)
+                .chain(JS_BARE_IMPORT.captures_iter(line))
+                .chain(JS_CALL.captures_iter(line))
+                .map(|captures| captures[1].to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Packages of single-line imports and `import ( ... )` blocks
+pub fn parse_go_imports(content: &str) -> BTreeSet<String> {
+    let mut packages = BTreeSet::new();
+    let mut in_block = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if in_block {
+            if trimmed.starts_with(')') {
+                in_block = false;
+            } else if let Some(captures) = GO_BLOCK_LINE.captures(line) {
+                packages.insert(captures[1].to_string());
+            }
+        } else if trimmed.starts_with("import (") {
+            in_block = true;
+        } else if let Some(captures) = GO_IMPORT.captures(line) {
+            packages.insert(captures[1].to_string());
+        }
+    }
+    packages
+}
+
+/// Crates in the dependency tables, including `[target.'...'.dependencies]` and
+/// `[dependencies.name]` tables
+pub fn parse_cargo_manifest(content: &str) -> BTreeSet<String> {
+    let mut crates = BTreeSet::new();
+    let mut in_dependencies = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if let Some(table) = trimmed
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            in_dependencies = false;
+            match table.rsplit_once('.') {
+                Some((parent, name)) if parent.ends_with("dependencies") => {
+                    crates.insert(name.trim_matches('"').to_string());
+                }
+                _ => in_dependencies = table.ends_with("dependencies"),
+            }
+        } else if in_dependencies {
+            if let Some(captures) = TOML_KEY.captures(line) {
+                crates.insert(captures[1].to_string());
+            }
+        }
+    }
+    crates
+}
+
+/// Packages in `dependencies`, `devDependencies`, `peerDependencies` and
+/// `optionalDependencies`
+pub fn parse_package_json(content: &str) -> BTreeSet<String> {
+    let Ok(manifest) = serde_json::from_str::<serde_json::Value>(content) else {
+        return BTreeSet::new();
+    };
+    [
+        "dependencies",
+        "devDependencies",
+        "peerDependencies",
+        "optionalDependencies",
+    ]
+    .iter()
+    .filter_map(|section| manifest[section].as_object())
+    .flat_map(|packages| packages.keys().cloned())
+    .collect()
+}
+
+/// Package names, without versions, extras or markers
+pub fn parse_requirements(content: &str) -> BTreeSet<String> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('-'))
+        .filter_map(|line| REQUIREMENT_NAME.captures(line))
+        .map(|captures| captures[1].to_string())
+        .collect()
+}
+
+/// Packages in PEP 621 `dependencies` and `optional-dependencies` lists and in
+/// Poetry's dependency tables
+pub fn parse_pyproject(content: &str) -> BTreeSet<String> {
+    let mut packages = BTreeSet::new();
+    let mut table = String::new();
+    let mut in_list = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            table = name.to_string();
+            in_list = false;
+            continue;
+        }
+
+        let poetry_table = table.starts_with("tool.poetry") && table.ends_with("dependencies");
+        if poetry_table {
+            if let Some(captures) = TOML_KEY.captures(line) {
+                if &captures[1] != "python" {
+                    packages.insert(captures[1].to_string());
+                }
+            }
+            continue;
+        }
+
+        // `dependencies = [` in [project], or any list in [project.optional-dependencies]
+        let starts_list = trimmed.contains("= [")
+            && (trimmed.starts_with("dependencies") || table == "project.optional-dependencies");
+        if starts_list || in_list {
+            let items = if starts_list {
+                trimmed.split_once('[').map(|(_, rest)| rest).unwrap_or("")
+            } else {
+                trimmed
+            };
+            for captures in QUOTED.captures_iter(items) {
+                if let Some(name) = REQUIREMENT_NAME.captures(&captures[1]) {
+                    packages.insert(name[1].to_string());
+                }
+            }
+            in_list = !items.contains(']');
+        }
+    }
+    packages
+}
+
+/// The name to look for: a file path is reduced to its stem, so `src/app/core.rs`
+/// looks for `core`
+fn normalize_target(target: &str) -> String {
+    let target = target.trim();
+    if target.contains('/') || target.contains('\\') {
+        let path = Path::new(target);
+        if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+            return stem.to_string();
+        }
+    }
+    target.to_string()
+}
+
+fn matches_target(dependency: &str, target: &str) -> bool {
+    // Qualified targets such as `crate::apis`, `os.path` or `@scope/pkg` match as text
+    if target.contains([':', '.', '/', '@']) {
+        return dependency.contains(target);
+    }
+    // Cargo writes `serde-json` where code says `serde_json`
+    let target = target.replace('-', "_");
+    dependency
+        .split([':', '.', '/'])
+        .any(|segment| segment.replace('-', "_") == target)
+}
@@ -0,0 +1,174 @@
+use crate::communication::rpc::get_global_rpc_server;
+use crate::tools::fs::file_ops::{FileOps, WriteMode};
+use crate::tools::remote::ExecutionBackend;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Request an editor answers by applying a `WorkspaceEdit` to its buffers
+pub const APPLY_EDIT_METHOD: &str = "workspace/applyEdit";
+
+/// How long to wait for the editor to apply an edit
+const APPLY_EDIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Replace `old_string` with `new_string` like `FileOps::edit_file`, through the
+/// attached editor when there is one so the change lands in its buffer and undo
+/// history. Returns the diff of the change.
+pub fn edit_file(
+    path: &Path,
+    old_string: &str,
+    new_string: &str,
+    expected_replacements: Option<usize>,
+) -> Result<String> {
+    if !editor_attached(path) {
+        return FileOps::edit_file(path, old_string, new_string, expected_replacements);
+    }
+
+    let (_, diff) =
+        FileOps::generate_edit_diff(path, old_string, new_string, expected_replacements)?;
+    let content = FileOps::read_file(path)?;
+    apply_in_editor(&replace_edit(path, &content, old_string, new_string))?;
+    Ok(diff)
+}
+
+/// Write `content` like `FileOps::write_file_with_options`, through the attached
+/// editor when there is one. Returns the diff of the change.
+pub fn write_file_with_diff(
+    path: &Path,
+    content: &str,
+    mode: WriteMode,
+    create_dirs: bool,
+) -> Result<String> {
+    if !editor_attached(path) {
+        return FileOps::write_file_with_options(path, content, mode, create_dirs);
+    }
+
+    let (diff, is_new_file) =
+        FileOps::generate_write_diff_with_options(path, content, mode, create_dirs)?;
+    let old_content = if is_new_file {
+        None
+    } else {
+        Some(FileOps::read_file(path)?)
+    };
+    let new_content = mode.apply(old_content.as_deref().unwrap_or_default(), content);
+    apply_in_editor(&write_edit(path, old_content.as_deref(), &new_content))?;
+    Ok(diff)
+}
+
+/// Whether an editor that applies workspace edits is connected. Files on a remote
+/// target are always written directly.
+fn editor_attached(path: &Path) -> bool {
+    let Some(server) = get_global_rpc_server() else {
+        return false;
+    };
+    server.is_running()
+        && server
+            .client_capabilities()
+            .is_some_and(|caps| caps.supports_apply_edit)
+        && !ExecutionBackend::for_path(path).transfers_files()
+}
+
+/// Ask the editor to apply `edit`, failing when it refuses or doesn't answer
+fn apply_in_editor(edit: &Value) -> Result<()> {
+    let server = get_global_rpc_server().ok_or_else(|| anyhow::anyhow!("No editor attached"))?;
+    let result = server
+        .request_client(
+            APPLY_EDIT_METHOD,
+            json!({ "label": "oli", "edit": edit }),
+            APPLY_EDIT_TIMEOUT,
+        )
+        .map_err(|e| anyhow::anyhow!("The edit may not have been applied: {e}"))?;
+
+    if result["applied"].as_bool() != Some(true) {
+        anyhow::bail!(
+            "The editor did not apply the edit: {}",
+            result["failureReason"]
+                .as_str()
+                .unwrap_or("no reason given")
+        );
+    }
+    Ok(())
+}
+
+/// A `WorkspaceEdit` replacing every occurrence of `old_string` in `content`, the
+/// current text of the file at `path`
+pub fn replace_edit(path: &Path, content: &str, old_string: &str, new_string: &str) -> Value {
+    let edits: Vec<Value> = if old_string.is_empty() {
+        Vec::new()
+    } else {
+        content
+            .match_indices(old_string)
+            .map(|(start, matched)| {
+                text_edit(
+                    position_at(content, start),
+                    position_at(content, start + matched.len()),
+                    new_string,
+                )
+            })
+            .collect()
+    };
+    json!({ "changes": { path_to_uri(path): edits } })
+}
+
+/// A `WorkspaceEdit` giving the file at `path` the content `content`: the whole
+/// document is replaced when `old_content` is its current text, and the file is
+/// created when it's None
+pub fn write_edit(path: &Path, old_content: Option<&str>, content: &str) -> Value {
+    let uri = path_to_uri(path);
+    match old_content {
+        Some(old_content) => {
+            let edit = text_edit(
+                json!({ "line": 0, "character": 0 }),
+                position_at(old_content, old_content.len()),
+                content,
+            );
+            json!({ "changes": { uri: [edit] } })
+        }
+        None => {
+            let insert = text_edit(
+                json!({ "line": 0, "character": 0 }),
+                json!({ "line": 0, "character": 0 }),
+                content,
+            );
+            json!({
+                "documentChanges": [
+                    { "kind": "create", "uri": uri, "options": { "ignoreIfExists": true } },
+                    { "textDocument": { "uri": uri, "version": null }, "edits": [insert] }
+                ]
+            })
+        }
+    }
+}
+
+fn text_edit(start: Value, end: Value, new_text: &str) -> Value {
+    json!({ "range": { "start": start, "end": end }, "newText": new_text })
+}
+
+/// The LSP position of a byte offset, with the character counted in UTF-16 code
+/// units
+pub fn position_at(content: &str, offset: usize) -> Value {
+    let before = &content[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let character = before[line_start..].encode_utf16().count();
+    json!({ "line": line, "character": character })
+}
+
+/// A `file://` URI for a path, made absolute against the working directory
+pub fn path_to_uri(path: &Path) -> String {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|_| PathBuf::from(path))
+    };
+    let path = absolute.to_string_lossy().replace('\\', "/");
+    let path = if path.starts_with('/') {
+        path
+    } else {
+        format!("/{path}")
+    };
+    format!("file://{}", path.replace(' ', "%20"))
+}
@@ -1,3 +1,4 @@
+use crate::tools::fs::highlight::{Language, SyntaxHighlighter};
 use anyhow::Result;
 use std::fmt::Write;
 
@@ -84,8 +85,15 @@ impl DiffTools {
         diff
     }
 
-    /// Format diff as a string with line numbers and colors
+    /// Format diff as a string with line numbers and colors, highlighting syntax
+    /// based on the file's extension when the language is recognized
     pub fn format_diff(diff: &[DiffLine], file_path: &str) -> Result<String> {
+        let language = Language::from_path(file_path);
+        let highlight = |text: &str, base_color: &str| match language {
+            Some(language) => SyntaxHighlighter::highlight_line(text, language, base_color),
+            None => text.to_string(),
+        };
+
         let mut output = String::new();
         let mut line_number = 0;
         let mut adds = 0;
@@ -118,16 +126,19 @@ impl DiffTools {
                 match line {
                     DiffLine::Context(text) => {
                         line_number += 1;
+                        let text = highlight(text, "");
                         writeln!(output, "     {line_number:3}  {text}")?;
                     }
                     DiffLine::Added(text) => {
                         line_number += 1;
+                        let text = highlight(text, "\x1b[92m");
                         // Use ANSI colors to show additions in light green
                         writeln!(output, "     \x1b[92m{line_number:3}+ {text}\x1b[0m")?;
                     }
                     DiffLine::Removed(text) => {
                         // For removed lines, use a darker red color
                         // Don't increment line number for removed lines
+                        let text = highlight(text, "\x1b[91m");
                         writeln!(output, "     \x1b[91m{line_number:3}- {text}\x1b[0m")?;
                     }
                 }
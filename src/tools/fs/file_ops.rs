@@ -1,14 +1,45 @@
 use anyhow::{Context, Result};
-use std::fs::{self, File};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
 use std::io::{Read, Write};
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 use super::diff::DiffTools;
+use super::oliignore::{self, OliIgnore};
+use crate::tools::remote::ExecutionBackend;
+
+/// How a write treats a file that already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteMode {
+    /// Replace the file's content
+    #[default]
+    Overwrite,
+    /// Add to the end of the file, creating it if it doesn't exist
+    Append,
+    /// Create the file, failing if it already exists
+    CreateNew,
+}
+
+impl WriteMode {
+    /// The file's content after writing `content` to a file holding `existing`
+    pub fn apply(self, existing: &str, content: &str) -> String {
+        match self {
+            Self::Append => format!("{existing}{content}"),
+            Self::Overwrite | Self::CreateNew => content.to_string(),
+        }
+    }
+}
 
 pub struct FileOps;
 
 impl FileOps {
     pub fn read_file(path: &Path) -> Result<String> {
+        let backend = ExecutionBackend::for_path(path);
+        if backend.transfers_files() {
+            return backend.read_file(path);
+        }
+
         let mut file =
             File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
         let mut content = String::new();
@@ -18,6 +49,7 @@ impl FileOps {
     }
 
     pub fn read_file_with_line_numbers(path: &Path) -> Result<String> {
+        oliignore::check_path(path)?;
         let content = Self::read_file(path)?;
         let numbered_content = content
             .lines()
@@ -29,6 +61,7 @@ impl FileOps {
     }
 
     pub fn read_file_lines(path: &Path, offset: usize, limit: Option<usize>) -> Result<String> {
+        oliignore::check_path(path)?;
         let content = Self::read_file(path)?;
         let lines: Vec<&str> = content.lines().collect();
         let start = offset.min(lines.len());
@@ -47,8 +80,30 @@ impl FileOps {
     }
 
     pub fn generate_write_diff(path: &Path, content: &str) -> Result<(String, bool)> {
+        Self::generate_write_diff_with_options(path, content, WriteMode::Overwrite, true)
+    }
+
+    /// Diff of writing `content` to `path` in `mode`, and whether the file is new.
+    /// Without `create_dirs` the file's directory must already exist.
+    pub fn generate_write_diff_with_options(
+        path: &Path,
+        content: &str,
+        mode: WriteMode,
+        create_dirs: bool,
+    ) -> Result<(String, bool)> {
+        oliignore::check_path(path)?;
+        if !create_dirs {
+            Self::check_parent_dir(path)?;
+        }
+
         // Check if file exists to determine if this is an update or new file
-        let is_new_file = !path.exists();
+        let is_new_file = !Self::exists(path);
+        if !is_new_file && mode == WriteMode::CreateNew {
+            anyhow::bail!(
+                "{} already exists; use mode \"overwrite\" to replace it or \"append\" to add to it",
+                path.display()
+            );
+        }
 
         let old_content = if is_new_file {
             String::new()
@@ -57,13 +112,35 @@ impl FileOps {
         };
 
         // Generate a diff
-        let diff_lines = DiffTools::generate_diff(&old_content, content);
+        let diff_lines = DiffTools::generate_diff(&old_content, &mode.apply(&old_content, content));
         let formatted_diff = DiffTools::format_diff(&diff_lines, &path.display().to_string())?;
 
         Ok((formatted_diff, is_new_file))
     }
 
+    /// Fail unless the directory `path` would be written in exists
+    pub fn check_parent_dir(path: &Path) -> Result<()> {
+        let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        else {
+            return Ok(());
+        };
+        if !Self::exists(parent) {
+            anyhow::bail!(
+                "Directory {} does not exist; set create_dirs to create it",
+                parent.display()
+            );
+        }
+        Ok(())
+    }
+
     pub fn write_file(path: &Path, content: &str) -> Result<()> {
+        let backend = ExecutionBackend::for_path(path);
+        if backend.transfers_files() {
+            return backend.write_file(path, content);
+        }
+
         // Ensure parent directory exists
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)
@@ -78,8 +155,163 @@ impl FileOps {
     }
 
     pub fn write_file_with_diff(path: &Path, content: &str) -> Result<String> {
-        let (diff, _) = Self::generate_write_diff(path, content)?;
-        Self::write_file(path, content)?;
+        Self::write_file_with_options(path, content, WriteMode::Overwrite, true)
+    }
+
+    /// Write `content` to `path` in `mode`, creating missing parent directories
+    /// only when `create_dirs` is set. Returns the diff of the change.
+    pub fn write_file_with_options(
+        path: &Path,
+        content: &str,
+        mode: WriteMode,
+        create_dirs: bool,
+    ) -> Result<String> {
+        let (diff, is_new_file) =
+            Self::generate_write_diff_with_options(path, content, mode, create_dirs)?;
+
+        let backend = ExecutionBackend::for_path(path);
+        match mode {
+            WriteMode::Overwrite => Self::write_file(path, content)?,
+            // Files on a remote target are written whole
+            _ if backend.transfers_files() => {
+                let existing = if is_new_file {
+                    String::new()
+                } else {
+                    Self::read_file(path)?
+                };
+                backend.write_file(path, &mode.apply(&existing, content))?;
+            }
+            WriteMode::Append | WriteMode::CreateNew => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create directory: {}", parent.display())
+                    })?;
+                }
+                let mut options = OpenOptions::new();
+                if mode == WriteMode::Append {
+                    options.append(true).create(true);
+                } else {
+                    options.write(true).create_new(true);
+                }
+                let mut file = options
+                    .open(path)
+                    .with_context(|| format!("Failed to open file: {}", path.display()))?;
+                file.write_all(content.as_bytes())
+                    .with_context(|| format!("Failed to write to file: {}", path.display()))?;
+            }
+        }
+        Ok(diff)
+    }
+
+    /// Whether `path` exists, on the target it lives on
+    fn exists(path: &Path) -> bool {
+        let backend = ExecutionBackend::for_path(path);
+        if backend.transfers_files() {
+            backend.exists(path)
+        } else {
+            path.exists()
+        }
+    }
+
+    /// Check that every file of a multi-file write is inside `root` and may be
+    /// written, before any of them is
+    pub fn check_write_many(files: &[(PathBuf, String)], root: &Path) -> Result<()> {
+        if files.is_empty() {
+            anyhow::bail!("No files to write");
+        }
+
+        let root = resolve_path(root);
+        let mut seen = Vec::with_capacity(files.len());
+        for (path, _) in files {
+            // A link to a missing file can't be resolved, and writing through it
+            // would create its target wherever it points
+            if let Some(link) = dangling_symlink(path) {
+                anyhow::bail!("{} is a symbolic link to a missing file", link.display());
+            }
+            let resolved = resolve_path(path);
+            if !resolved.starts_with(&root) {
+                anyhow::bail!(
+                    "{} is outside the workspace {}",
+                    path.display(),
+                    root.display()
+                );
+            }
+            if seen.contains(&resolved) {
+                anyhow::bail!("{} is listed more than once", path.display());
+            }
+            if resolved.is_dir() {
+                anyhow::bail!("{} is a directory", path.display());
+            }
+            if ExecutionBackend::for_path(path).transfers_files() {
+                anyhow::bail!(
+                    "{} is on a remote target; write it with Write instead",
+                    path.display()
+                );
+            }
+            oliignore::check_path(path)?;
+            seen.push(resolved);
+        }
+        Ok(())
+    }
+
+    /// One preview of every file a multi-file write creates or changes
+    pub fn generate_write_many_diff(files: &[(PathBuf, String)], root: &Path) -> Result<String> {
+        Self::check_write_many(files, root)?;
+
+        let mut created = 0;
+        let mut diffs = Vec::with_capacity(files.len());
+        for (path, content) in files {
+            let (diff, is_new_file) = Self::generate_write_diff(path, content)?;
+            created += usize::from(is_new_file);
+            diffs.push(diff);
+        }
+
+        Ok(format!(
+            "Writing {} files ({created} new, {} updated)\n{}",
+            files.len(),
+            files.len() - created,
+            diffs.join("\n")
+        ))
+    }
+
+    /// Write several files inside `root` as one change: either all are written or,
+    /// if any fails, those already written are restored and new files and the
+    /// directories made for them are removed. Returns the combined diff.
+    pub fn write_many(files: &[(PathBuf, String)], root: &Path) -> Result<String> {
+        let diff = Self::generate_write_many_diff(files, root)?;
+
+        let mut written: Vec<(&Path, Option<Vec<u8>>)> = Vec::with_capacity(files.len());
+        let mut created_dirs: Vec<PathBuf> = Vec::new();
+        for (path, content) in files {
+            let result = (|| -> Result<()> {
+                let previous = if path.exists() {
+                    Some(
+                        fs::read(path)
+                            .with_context(|| format!("Failed to read file: {}", path.display()))?,
+                    )
+                } else {
+                    None
+                };
+                if let Some(parent) = path.parent() {
+                    created_dirs.extend(missing_ancestors(parent));
+                    fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create directory: {}", parent.display())
+                    })?;
+                }
+                written.push((path, previous));
+                fs::write(path, content)
+                    .with_context(|| format!("Failed to write to file: {}", path.display()))
+            })();
+
+            if let Err(e) = result {
+                let rolled_back = rollback(&written, &created_dirs);
+                return Err(e.context(if rolled_back {
+                    "No files were written"
+                } else {
+                    "Some files could not be restored"
+                }));
+            }
+        }
         Ok(diff)
     }
 
@@ -89,6 +321,7 @@ impl FileOps {
         new_string: &str,
         expected_replacements: Option<usize>,
     ) -> Result<(String, String)> {
+        oliignore::check_path(path)?;
         let content = Self::read_file(path)?;
 
         // Count occurrences to ensure we're replacing a unique string
@@ -134,7 +367,14 @@ impl FileOps {
         Ok(diff)
     }
 
+    /// List a directory, leaving out entries excluded by `.oliignore`
     pub fn list_directory(path: &Path) -> Result<Vec<PathBuf>> {
+        Ok(Self::list_directory_with_excluded(path)?.0)
+    }
+
+    /// List a directory, returning the visible entries and how many were excluded by `.oliignore`
+    pub fn list_directory_with_excluded(path: &Path) -> Result<(Vec<PathBuf>, usize)> {
+        oliignore::check_path(path)?;
         let entries = fs::read_dir(path)
             .with_context(|| format!("Failed to read directory: {}", path.display()))?;
 
@@ -147,7 +387,10 @@ impl FileOps {
         // Sort by name
         paths.sort();
 
-        Ok(paths)
+        Ok(match OliIgnore::find(path) {
+            Some(oliignore) => oliignore.filter(paths),
+            None => (paths, 0),
+        })
     }
 
     #[allow(dead_code)]
@@ -186,3 +429,83 @@ impl FileOps {
         Ok(info)
     }
 }
+
+// Absolute path with `..` and symlinks resolved as far as it exists, so a path
+// can't leave the workspace through either
+pub(crate) fn resolve_path(path: &Path) -> PathBuf {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            component => normalized.push(component),
+        }
+    }
+
+    let mut existing = normalized.as_path();
+    let mut missing = Vec::new();
+    while !existing.exists() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => break,
+        }
+    }
+    let mut resolved = existing
+        .canonicalize()
+        .unwrap_or_else(|_| existing.to_path_buf());
+    resolved.extend(missing.iter().rev());
+    resolved
+}
+
+// The first of `path` and its ancestors that is a symbolic link whose target
+// doesn't exist
+fn dangling_symlink(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|ancestor| {
+        !ancestor.as_os_str().is_empty()
+            && !ancestor.exists()
+            && ancestor
+                .symlink_metadata()
+                .is_ok_and(|metadata| metadata.file_type().is_symlink())
+    })
+}
+
+// Directories that creating `dir` would make, outermost first
+fn missing_ancestors(dir: &Path) -> Vec<PathBuf> {
+    let mut missing: Vec<PathBuf> = dir
+        .ancestors()
+        .take_while(|ancestor| !ancestor.as_os_str().is_empty() && !ancestor.exists())
+        .map(Path::to_path_buf)
+        .collect();
+    missing.reverse();
+    missing
+}
+
+// Undo a partial multi-file write, newest first. Returns whether everything was restored.
+fn rollback(written: &[(&Path, Option<Vec<u8>>)], created_dirs: &[PathBuf]) -> bool {
+    let mut restored = true;
+    for (path, previous) in written.iter().rev() {
+        let result = match previous {
+            Some(content) => fs::write(path, content),
+            None if path.exists() => fs::remove_file(path),
+            None => Ok(()),
+        };
+        restored &= result.is_ok();
+    }
+    for dir in created_dirs.iter().rev() {
+        restored &= fs::remove_dir(dir).is_ok() || !dir.exists();
+    }
+    restored
+}
@@ -0,0 +1,175 @@
+use anyhow::Result;
+use ignore::WalkBuilder;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use super::oliignore::OLIIGNORE_FILE;
+use super::workspace_index::WorkspaceIndex;
+
+/// Number of results FindFile returns unless asked for more
+pub const DEFAULT_FIND_LIMIT: usize = 20;
+/// How long a workspace's file list is reused before it's walked again
+const INDEX_TTL: Duration = Duration::from_secs(30);
+/// Workspaces larger than this are only indexed up to the limit
+const MAX_INDEXED_FILES: usize = 100_000;
+
+// Points for each matched character, and bonuses that make matches read like fzf's
+const SCORE_MATCH: i64 = 16;
+const BONUS_BOUNDARY: i64 = 10;
+const BONUS_CONSECUTIVE: i64 = 6;
+const BONUS_FILE_NAME: i64 = 12;
+const PENALTY_GAP: i64 = 1;
+const MAX_GAP_PENALTY: i64 = 12;
+
+// File lists by directory, with when they were walked
+type FileIndex = HashMap<PathBuf, (Instant, Vec<PathBuf>)>;
+
+/// A path that matched a fuzzy query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// Path relative to the searched directory
+    pub path: PathBuf,
+    pub score: i64,
+}
+
+/// Files under a directory, respecting `.gitignore` and `.oliignore`, as paths
+/// relative to it. Lists are cached for a short time so repeated lookups are fast;
+/// large repositories use the background index instead.
+pub fn indexed_files(dir: &Path) -> Vec<PathBuf> {
+    if let Some(index) = WorkspaceIndex::for_dir(dir) {
+        return index.files_under(dir);
+    }
+
+    static INDEX: OnceLock<Mutex<FileIndex>> = OnceLock::new();
+    let index = INDEX.get_or_init(|| Mutex::new(HashMap::new()));
+
+    if let Some((indexed_at, files)) = index.lock().unwrap().get(dir) {
+        if indexed_at.elapsed() < INDEX_TTL {
+            return files.clone();
+        }
+    }
+
+    let files: Vec<PathBuf> = WalkBuilder::new(dir)
+        .hidden(false)
+        .standard_filters(true)
+        .add_custom_ignore_filename(OLIIGNORE_FILE)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+        .filter_map(|entry| entry.path().strip_prefix(dir).ok().map(Path::to_path_buf))
+        .take(MAX_INDEXED_FILES)
+        .collect();
+
+    index
+        .lock()
+        .unwrap()
+        .insert(dir.to_path_buf(), (Instant::now(), files.clone()));
+    files
+}
+
+/// The files under `dir` best matching `query`, highest score first
+pub fn find_files(query: &str, dir: &Path, limit: usize) -> Result<Vec<FuzzyMatch>> {
+    if query.trim().is_empty() {
+        anyhow::bail!("The query must not be empty");
+    }
+    if !dir.is_dir() {
+        anyhow::bail!("Not a directory: {}", dir.display());
+    }
+
+    let mut matches: Vec<FuzzyMatch> = indexed_files(dir)
+        .into_iter()
+        .filter_map(|path| {
+            let score = fuzzy_score(query, &path.to_string_lossy())?;
+            Some(FuzzyMatch { path, score })
+        })
+        .collect();
+
+    // Prefer higher scores, then shorter and alphabetically earlier paths
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.path.as_os_str().len().cmp(&b.path.as_os_str().len()))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    matches.truncate(limit.max(1));
+    Ok(matches)
+}
+
+/// Score how well `candidate` matches `query`, or None when it doesn't. Each
+/// whitespace-separated term must appear in order (not necessarily adjacent);
+/// matches at word boundaries, in runs and in the file name score higher.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.chars().collect();
+    let file_name_start = candidate
+        .iter()
+        .rposition(|&c| c == '/' || c == '\\')
+        .map_or(0, |i| i + 1);
+
+    query
+        .split_whitespace()
+        .map(|term| score_term(term, &candidate, file_name_start))
+        .sum()
+}
+
+// Best score for one term over every starting position of its first character
+fn score_term(term: &str, candidate: &[char], file_name_start: usize) -> Option<i64> {
+    let term: Vec<char> = term.chars().flat_map(char::to_lowercase).collect();
+    let first = *term.first()?;
+
+    (0..candidate.len())
+        .filter(|&start| lower(candidate[start]) == first)
+        .filter_map(|start| score_from(&term, candidate, start, file_name_start))
+        .max()
+}
+
+// Match the term greedily from `start`, taking the first occurrence of each character
+fn score_from(
+    term: &[char],
+    candidate: &[char],
+    start: usize,
+    file_name_start: usize,
+) -> Option<i64> {
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+    let mut position = start;
+
+    for &wanted in term {
+        let next = (position..candidate.len()).find(|&i| lower(candidate[i]) == wanted)?;
+
+        score += SCORE_MATCH;
+        if is_boundary(candidate, next) {
+            score += BONUS_BOUNDARY;
+        }
+        match previous {
+            Some(prev) if next == prev + 1 => score += BONUS_CONSECUTIVE,
+            Some(prev) => score -= ((next - prev - 1) as i64 * PENALTY_GAP).min(MAX_GAP_PENALTY),
+            None => {}
+        }
+
+        previous = Some(next);
+        position = next + 1;
+    }
+
+    if start >= file_name_start {
+        score += BONUS_FILE_NAME;
+    }
+    Some(score)
+}
+
+// Start of the path, after a separator, or a lowercase-to-uppercase step as in "scrollState"
+fn is_boundary(candidate: &[char], index: usize) -> bool {
+    if index == 0 {
+        return true;
+    }
+    let before = candidate[index - 1];
+    let current = candidate[index];
+    matches!(before, '/' | '\\' | '_' | '-' | '.' | ' ')
+        || (before.is_lowercase() && current.is_uppercase())
+}
+
+fn lower(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
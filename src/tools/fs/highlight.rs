@@ -0,0 +1,224 @@
+use std::path::Path;
+
+const KEYWORD_COLOR: &str = "\x1b[94m";
+const STRING_COLOR: &str = "\x1b[93m";
+const COMMENT_COLOR: &str = "\x1b[90m";
+const NUMBER_COLOR: &str = "\x1b[96m";
+const RESET: &str = "\x1b[0m";
+
+/// Languages recognized for syntax highlighting in diff previews
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Go,
+    Java,
+    C,
+    Cpp,
+    Shell,
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl Language {
+    /// Detect the language of a file from its extension
+    pub fn from_path(path: &str) -> Option<Self> {
+        let path = Path::new(path);
+        let extension = path.extension()?.to_str()?.to_lowercase();
+
+        match extension.as_str() {
+            "rs" => Some(Self::Rust),
+            "py" | "pyi" => Some(Self::Python),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Self::JavaScript),
+            "ts" | "tsx" | "mts" | "cts" => Some(Self::TypeScript),
+            "go" => Some(Self::Go),
+            "java" => Some(Self::Java),
+            "c" | "h" => Some(Self::C),
+            "cc" | "cpp" | "cxx" | "hpp" | "hh" => Some(Self::Cpp),
+            "sh" | "bash" | "zsh" => Some(Self::Shell),
+            "toml" => Some(Self::Toml),
+            "yml" | "yaml" => Some(Self::Yaml),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// Prefix that starts a comment running to the end of the line
+    pub fn line_comment(&self) -> Option<&'static str> {
+        match self {
+            Self::Rust
+            | Self::JavaScript
+            | Self::TypeScript
+            | Self::Go
+            | Self::Java
+            | Self::C
+            | Self::Cpp => Some("//"),
+            Self::Python | Self::Shell | Self::Toml | Self::Yaml => Some("#"),
+            Self::Json => None,
+        }
+    }
+
+    /// Reserved words highlighted as keywords, separated by whitespace
+    pub fn keywords(&self) -> &'static str {
+        match self {
+            Self::Rust => concat!(
+                "as async await break const continue crate dyn else enum extern false fn for if ",
+                "impl in let loop match mod move mut pub ref return self Self static struct ",
+                "super trait true type unsafe use where while",
+            ),
+            Self::Python => concat!(
+                "and as assert async await break class continue def del elif else except False ",
+                "finally for from global if import in is lambda None nonlocal not or pass raise ",
+                "return True try while with yield",
+            ),
+            Self::JavaScript | Self::TypeScript => concat!(
+                "async await break case catch class const continue default delete do else enum ",
+                "export extends false finally for from function if implements import in ",
+                "instanceof interface let new null return static super switch this throw true ",
+                "try type typeof undefined var void while yield",
+            ),
+            Self::Go => concat!(
+                "break case chan const continue default defer else fallthrough false for func ",
+                "go goto if import interface map nil package range return select struct switch ",
+                "true type var",
+            ),
+            Self::Java => concat!(
+                "abstract boolean break case catch class continue default do else enum extends ",
+                "false final finally for if implements import instanceof int interface new null ",
+                "package private protected public return static super switch this throw throws ",
+                "true try void while",
+            ),
+            Self::C | Self::Cpp => concat!(
+                "auto break case char class const continue default delete do double else enum ",
+                "extern false float for if include int long namespace new nullptr public ",
+                "private return short sizeof static struct switch template true typedef union ",
+                "unsigned using void while",
+            ),
+            Self::Shell => concat!(
+                "case do done elif else esac export fi for function if in local return then ",
+                "while",
+            ),
+            Self::Toml | Self::Yaml | Self::Json => "true false null",
+        }
+    }
+}
+
+/// Lightweight, line-based syntax highlighter for diff previews
+pub struct SyntaxHighlighter;
+
+impl SyntaxHighlighter {
+    /// Highlight a single line, restoring `base_color` after each token so that
+    /// the surrounding added/removed color is preserved
+    pub fn highlight_line(line: &str, language: Language, base_color: &str) -> String {
+        let chars: Vec<char> = line.chars().collect();
+        let comment = language.line_comment();
+        let keywords = language.keywords();
+        let mut output = String::with_capacity(line.len() + 16);
+        let mut i = 0;
+
+        while i < chars.len() {
+            let c = chars[i];
+
+            // Comments run to the end of the line
+            if let Some(prefix) = comment {
+                if starts_with_at(&chars, i, prefix) {
+                    let rest: String = chars[i..].iter().collect();
+                    push_token(&mut output, COMMENT_COLOR, &rest, base_color);
+                    break;
+                }
+            }
+
+            // String literals, ending at the matching unescaped quote or end of line
+            if c == '"' || c == '\'' || (c == '`' && language != Language::Rust) {
+                // Rust lifetimes and chars share the quote; only treat ' as a string if it closes nearby
+                if c == '\'' && language == Language::Rust && !is_char_literal(&chars, i) {
+                    output.push(c);
+                    i += 1;
+                    continue;
+                }
+
+                let start = i;
+                i += 1;
+                while i < chars.len() && chars[i] != c {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                let end = (i + 1).min(chars.len());
+                let literal: String = chars[start..end].iter().collect();
+                push_token(&mut output, STRING_COLOR, &literal, base_color);
+                i = end;
+                continue;
+            }
+
+            // Numbers that aren't part of an identifier
+            if c.is_ascii_digit() && (i == 0 || !is_ident_char(chars[i - 1])) {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_ascii_alphanumeric() || chars[i] == '.' || chars[i] == '_')
+                {
+                    i += 1;
+                }
+                let number: String = chars[start..i].iter().collect();
+                push_token(&mut output, NUMBER_COLOR, &number, base_color);
+                continue;
+            }
+
+            // Identifiers, highlighted when they are keywords
+            if is_ident_char(c) {
+                let start = i;
+                while i < chars.len() && is_ident_char(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                if keywords.split_whitespace().any(|keyword| keyword == word) {
+                    push_token(&mut output, KEYWORD_COLOR, &word, base_color);
+                } else {
+                    output.push_str(&word);
+                }
+                continue;
+            }
+
+            output.push(c);
+            i += 1;
+        }
+
+        output
+    }
+}
+
+fn push_token(output: &mut String, color: &str, token: &str, base_color: &str) {
+    output.push_str(color);
+    output.push_str(token);
+    output.push_str(RESET);
+    output.push_str(base_color);
+}
+
+fn starts_with_at(chars: &[char], index: usize, prefix: &str) -> bool {
+    let mut prefix_chars = prefix.chars();
+    let mut i = index;
+    loop {
+        match prefix_chars.next() {
+            None => return true,
+            Some(p) if i < chars.len() && chars[i] == p => i += 1,
+            Some(_) => return false,
+        }
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_char_literal(chars: &[char], index: usize) -> bool {
+    // 'a' or '\n' style literals
+    matches!(chars.get(index + 2), Some('\''))
+        || matches!(
+            (chars.get(index + 1), chars.get(index + 3)),
+            (Some('\\'), Some('\''))
+        )
+}
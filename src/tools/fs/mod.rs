@@ -1,3 +1,8 @@
 pub mod diff;
 pub mod file_ops;
+pub mod fuzzy;
+pub mod highlight;
+pub mod oliignore;
+pub mod read_limits;
 pub mod search;
+pub mod workspace_index;
@@ -0,0 +1,103 @@
+use anyhow::Result;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::{Path, PathBuf};
+
+/// File listing paths hidden from the agent, in gitignore syntax
+pub const OLIIGNORE_FILE: &str = ".oliignore";
+
+/// Paths excluded from all agent visibility by the nearest `.oliignore` file.
+/// Applied on top of `.gitignore`, so files tracked by git can be hidden too.
+#[derive(Debug, Clone)]
+pub struct OliIgnore {
+    root: PathBuf,
+    matcher: Gitignore,
+}
+
+impl OliIgnore {
+    /// Load the `.oliignore` file in `start` (or its directory, for files) or the
+    /// nearest ancestor that has one
+    pub fn find(start: &Path) -> Option<Self> {
+        let start = normalize(start);
+        let dir = if start.is_dir() {
+            start.as_path()
+        } else {
+            start.parent()?
+        };
+
+        dir.ancestors()
+            .find(|dir| dir.join(OLIIGNORE_FILE).is_file())
+            .and_then(Self::load)
+    }
+
+    /// Load the `.oliignore` file in `root`
+    pub fn load(root: &Path) -> Option<Self> {
+        let root = normalize(root);
+        let mut builder = GitignoreBuilder::new(&root);
+        if builder.add(root.join(OLIIGNORE_FILE)).is_some() {
+            return None;
+        }
+        let matcher = builder.build().ok()?;
+
+        Some(Self { root, matcher })
+    }
+
+    /// Directory containing the `.oliignore` file
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        let path = normalize(path);
+        if !path.starts_with(&self.root) || path == self.root {
+            return false;
+        }
+
+        self.matcher
+            .matched_path_or_any_parents(&path, path.is_dir())
+            .is_ignore()
+    }
+
+    /// Split paths into those the agent may see and the number excluded
+    pub fn filter(&self, paths: Vec<PathBuf>) -> (Vec<PathBuf>, usize) {
+        let total = paths.len();
+        let visible: Vec<PathBuf> = paths
+            .into_iter()
+            .filter(|path| !self.is_excluded(path))
+            .collect();
+        let excluded = total - visible.len();
+        (visible, excluded)
+    }
+}
+
+/// Fail with an explanation for the model when `path` is excluded by `.oliignore`
+pub fn check_path(path: &Path) -> Result<()> {
+    match OliIgnore::find(path) {
+        Some(oliignore) if oliignore.is_excluded(path) => Err(anyhow::anyhow!(
+            "{}: path excluded by .oliignore",
+            path.display()
+        )),
+        _ => Ok(()),
+    }
+}
+
+// Absolute path with symlinks and `..` resolved where the path exists, so it
+// can be compared with the root of the `.oliignore` file
+fn normalize(path: &Path) -> PathBuf {
+    if let Ok(canonical) = path.canonicalize() {
+        return canonical;
+    }
+
+    // Files that don't exist yet, e.g. the target of Write
+    if let (Some(parent), Some(name)) = (path.parent(), path.file_name()) {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        if let Ok(parent) = parent.canonicalize() {
+            return parent.join(name);
+        }
+    }
+
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,190 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Cursor, Read};
+use std::path::Path;
+
+use super::file_ops::FileOps;
+use super::oliignore;
+use crate::apis::api_client::ImageData;
+use crate::tools::remote::ExecutionBackend;
+
+/// Environment variable capping the bytes of a file one Read returns
+pub const READ_MAX_BYTES_ENV: &str = "OLI_READ_MAX_BYTES";
+/// Environment variable capping the lines of a file one Read returns
+pub const READ_MAX_LINES_ENV: &str = "OLI_READ_MAX_LINES";
+pub const DEFAULT_READ_MAX_BYTES: usize = 100_000;
+pub const DEFAULT_READ_MAX_LINES: usize = 2_000;
+/// Longest line shown whole; a minified file is otherwise one enormous line
+const MAX_LINE_CHARS: usize = 2_000;
+/// Largest image attached, the per-image limit of the providers that take images
+pub const MAX_IMAGE_BYTES: u64 = 5 * 1024 * 1024;
+/// Bytes at the start of a file checked for binary content
+const SNIFF_BYTES: u64 = 8_192;
+
+/// How much of a file one Read returns, so a large or binary file doesn't flood
+/// the context
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadLimits {
+    pub max_bytes: usize,
+    pub max_lines: usize,
+}
+
+impl Default for ReadLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_READ_MAX_BYTES,
+            max_lines: DEFAULT_READ_MAX_LINES,
+        }
+    }
+}
+
+impl ReadLimits {
+    /// The defaults, overridden by `OLI_READ_MAX_BYTES` and `OLI_READ_MAX_LINES`
+    pub fn from_env() -> Self {
+        let limit = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.trim().parse::<usize>().ok())
+                .filter(|value| *value > 0)
+        };
+        let defaults = Self::default();
+        Self {
+            max_bytes: limit(READ_MAX_BYTES_ENV).unwrap_or(defaults.max_bytes),
+            max_lines: limit(READ_MAX_LINES_ENV).unwrap_or(defaults.max_lines),
+        }
+    }
+
+    /// Read up to `limit` lines of `path` from `offset`, numbered like
+    /// `FileOps::read_file_lines`, ending with a notice when these limits cut the
+    /// output short. Images are returned as an image block the model can view;
+    /// other binary files are refused.
+    pub fn read(&self, path: &Path, offset: usize, limit: usize) -> Result<String> {
+        oliignore::check_path(path)?;
+
+        // Files on a remote target are fetched whole, as text
+        if ExecutionBackend::for_path(path).transfers_files() {
+            let content = FileOps::read_file(path)?;
+            return Ok(self.number_lines(content.lines().map(str::to_string), offset, limit));
+        }
+
+        let mut file =
+            File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+        let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        let mut head = Vec::new();
+        (&mut file)
+            .take(SNIFF_BYTES)
+            .read_to_end(&mut head)
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+
+        if let Some(media_type) = image_media_type(&head) {
+            return read_image(path, media_type, size);
+        }
+        if is_binary(&head) {
+            anyhow::bail!(
+                "{} is a binary file ({size} bytes) and can't be read as text",
+                path.display()
+            );
+        }
+
+        let lines = BufReader::new(Cursor::new(head).chain(file))
+            .split(b'\n')
+            .map_while(Result::ok)
+            .map(|line| {
+                let line = String::from_utf8_lossy(&line);
+                line.strip_suffix('\r').unwrap_or(&line).to_string()
+            });
+        Ok(self.number_lines(lines, offset, limit))
+    }
+
+    /// Number the lines from `offset`, stopping at `limit` lines or these limits
+    fn number_lines(
+        &self,
+        lines: impl Iterator<Item = String>,
+        offset: usize,
+        limit: usize,
+    ) -> String {
+        let max_lines = limit.min(self.max_lines);
+        let mut shown: Vec<String> = Vec::new();
+        let mut bytes = 0;
+        let mut cut_at = None;
+        for (i, line) in lines.enumerate().skip(offset) {
+            if shown.len() >= max_lines {
+                // Only worth a notice when the limit, not the caller, stopped the read
+                if self.max_lines < limit {
+                    cut_at = Some(format!("{} lines", self.max_lines));
+                }
+                break;
+            }
+            let numbered = format!("{:4} | {}", i + 1, shorten_line(&line));
+            if !shown.is_empty() && bytes + numbered.len() > self.max_bytes {
+                cut_at = Some(format!("{} bytes", self.max_bytes));
+                break;
+            }
+            bytes += numbered.len() + 1;
+            shown.push(numbered);
+        }
+
+        let mut output = shown.join("\n");
+        if let Some(cut_at) = cut_at {
+            let end = offset + shown.len();
+            output.push_str(&format!(
+                "\n\n[Showing lines {}-{end}: Read returns at most {cut_at}. \
+                 Read again with offset {end} to see more.]",
+                offset + 1
+            ));
+        }
+        output
+    }
+}
+
+/// `line`, cut down to `MAX_LINE_CHARS` characters
+fn shorten_line(line: &str) -> String {
+    let total = line.chars().count();
+    if total <= MAX_LINE_CHARS {
+        return line.to_string();
+    }
+    let start: String = line.chars().take(MAX_LINE_CHARS).collect();
+    format!("{start}… [{} more characters]", total - MAX_LINE_CHARS)
+}
+
+/// The media type of an image the providers take, recognised by its first bytes
+pub fn image_media_type(head: &[u8]) -> Option<&'static str> {
+    if head.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if head.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if head.len() >= 12 && head.starts_with(b"RIFF") && &head[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// Whether the start of a file looks binary: a NUL byte, or bytes that aren't
+/// UTF-8 other than a character cut off at the end
+fn is_binary(head: &[u8]) -> bool {
+    head.contains(&0) || std::str::from_utf8(head).is_err_and(|error| error.error_len().is_some())
+}
+
+fn read_image(path: &Path, media_type: &str, size: u64) -> Result<String> {
+    if size > MAX_IMAGE_BYTES {
+        anyhow::bail!(
+            "{} is an image of {size} bytes, larger than the {MAX_IMAGE_BYTES} bytes that can be attached",
+            path.display()
+        );
+    }
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let image = ImageData {
+        media_type: media_type.to_string(),
+        data: base64::engine::general_purpose::STANDARD.encode(bytes),
+    };
+    Ok(format!(
+        "Image {} ({media_type}, {size} bytes) is attached.\n{}",
+        path.display(),
+        image.to_block()
+    ))
+}
@@ -1,14 +1,37 @@
 use anyhow::{Context, Result};
 use glob::glob;
-use ignore::WalkBuilder;
+use ignore::{WalkBuilder, WalkState};
 use regex::Regex;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use walkdir::{DirEntry, WalkDir};
 
+use super::oliignore::{OliIgnore, OLIIGNORE_FILE};
+
+/// A matching line: the file, its 1-based line number and the line
+pub type GrepMatch = (PathBuf, usize, String);
+
+/// How Grep presents the lines it found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GrepOutputOptions {
+    /// Lines shown before and after each match
+    pub context_lines: usize,
+    /// Prefix each line with its line number
+    pub show_line_numbers: bool,
+}
+
+impl Default for GrepOutputOptions {
+    fn default() -> Self {
+        Self {
+            context_lines: 0,
+            show_line_numbers: true,
+        }
+    }
+}
+
 pub struct SearchTools;
 
 impl SearchTools {
@@ -45,7 +68,7 @@ impl SearchTools {
 
     /// Checks if a repository uses ignore files by looking for .gitignore, .npmignore, etc.
     fn has_ignore_files(dir: &Path) -> bool {
-        let ignore_files = [".gitignore", ".npmignore", ".dockerignore"];
+        let ignore_files = [".gitignore", ".npmignore", ".dockerignore", OLIIGNORE_FILE];
 
         // Check current directory first
         for file in &ignore_files {
@@ -169,7 +192,12 @@ impl SearchTools {
 
         // If there are no matches or only one match, no need for complex filtering
         if glob_matches.is_empty() || glob_matches.len() == 1 {
-            return Ok(glob_matches);
+            return Ok(
+                match glob_matches.first().and_then(|path| OliIgnore::find(path)) {
+                    Some(oliignore) => oliignore.filter(glob_matches).0,
+                    None => glob_matches,
+                },
+            );
         }
 
         // Try to find the project root to respect repository-specific ignore files
@@ -188,6 +216,7 @@ impl SearchTools {
                 let walker = WalkBuilder::new(base_dir)
                     .hidden(false) // Don't skip hidden files by default
                     .standard_filters(true) // Use .gitignore etc.
+                    .add_custom_ignore_filename(OLIIGNORE_FILE) // Layered on top of .gitignore
                     .build();
 
                 // Mark when we've finished processing to avoid redundant work
@@ -219,11 +248,15 @@ impl SearchTools {
         }
 
         // Fallback to the default method if repository-specific ignore patterns can't be used
+        let oliignore = common_parent.as_deref().and_then(OliIgnore::find);
         for path in glob_matches {
             // Skip paths based on common ignore patterns
             if Self::is_ignored_path(&path) {
                 continue;
             }
+            if oliignore.as_ref().is_some_and(|o| o.is_excluded(&path)) {
+                continue;
+            }
             matches.push(path);
         }
 
@@ -351,6 +384,7 @@ impl SearchTools {
             let walker = WalkBuilder::new(dir)
                 .hidden(false)
                 .standard_filters(true) // Respect .gitignore, .ignore, etc.
+                .add_custom_ignore_filename(OLIIGNORE_FILE) // Layered on top of .gitignore
                 .build();
 
             for entry in walker.flatten() {
@@ -387,10 +421,14 @@ impl SearchTools {
             }
         } else {
             // Fall back to traditional walkdir with our hardcoded ignore patterns
+            let oliignore = OliIgnore::find(dir);
             for entry in WalkDir::new(dir)
                 .follow_links(true)
                 .into_iter()
-                .filter_entry(|e| !Self::should_skip_dir(e))
+                .filter_entry(|e| {
+                    !Self::should_skip_dir(e)
+                        && !oliignore.as_ref().is_some_and(|o| o.is_excluded(e.path()))
+                })
                 .filter_map(|e| e.ok())
                 .filter(|e| e.file_type().is_file())
             {
@@ -431,6 +469,154 @@ impl SearchTools {
 
         Ok(matches)
     }
+
+    /// Search like `grep_search` with the parallel walker, stopping once `limit`
+    /// matches are found, for large repositories. Returns the matches and whether
+    /// the search stopped early.
+    pub fn grep_search_parallel(
+        pattern: &str,
+        include_pattern: Option<&str>,
+        search_dir: Option<&Path>,
+        limit: usize,
+    ) -> Result<(Vec<GrepMatch>, bool)> {
+        let regex =
+            Regex::new(pattern).with_context(|| format!("Invalid regex pattern: {pattern}"))?;
+        let dir = search_dir.unwrap_or_else(|| Path::new("."));
+        let include_regex = Self::create_file_pattern_filter(include_pattern);
+        let matches = Mutex::new(Vec::new());
+        let stopped = AtomicBool::new(false);
+
+        WalkBuilder::new(dir)
+            .hidden(false)
+            .standard_filters(true)
+            .add_custom_ignore_filename(OLIIGNORE_FILE)
+            .build_parallel()
+            .run(|| {
+                Box::new(|entry| {
+                    if stopped.load(Ordering::SeqCst) {
+                        return WalkState::Quit;
+                    }
+                    let Ok(entry) = entry else {
+                        return WalkState::Continue;
+                    };
+                    let path = entry.path();
+                    if !entry.file_type().is_some_and(|ft| ft.is_file())
+                        || Self::is_likely_binary_or_generated(path)
+                        || include_regex
+                            .as_ref()
+                            .is_some_and(|include| !include.is_match(&path.to_string_lossy()))
+                    {
+                        return WalkState::Continue;
+                    }
+
+                    let Ok(file) = File::open(path) else {
+                        return WalkState::Continue;
+                    };
+                    let found: Vec<_> = BufReader::new(file)
+                        .lines()
+                        .enumerate()
+                        .filter_map(|(line_num, line)| {
+                            let line = line.ok()?;
+                            regex
+                                .is_match(&line)
+                                .then(|| (path.to_path_buf(), line_num + 1, line))
+                        })
+                        .collect();
+                    let mut matches = matches.lock().unwrap();
+                    matches.extend(found);
+                    if matches.len() >= limit {
+                        stopped.store(true, Ordering::SeqCst);
+                        return WalkState::Quit;
+                    }
+                    WalkState::Continue
+                })
+            });
+
+        let mut matches = matches.into_inner().unwrap();
+        matches.sort_by(|a, b| {
+            let a_modified = std::fs::metadata(&a.0).and_then(|m| m.modified()).ok();
+            let b_modified = std::fs::metadata(&b.0).and_then(|m| m.modified()).ok();
+            b_modified.cmp(&a_modified)
+        });
+        matches.truncate(limit);
+        Ok((matches, stopped.into_inner()))
+    }
+
+    /// Format matches like ripgrep: `path:line:text` for matching lines and
+    /// `path-line-text` for the context around them, with `--` between groups
+    /// that aren't adjacent. Matches must be grouped by file, in line order.
+    pub fn format_grep_matches(matches: &[GrepMatch], options: &GrepOutputOptions) -> String {
+        let mut output = String::new();
+        let mut start = 0;
+        while start < matches.len() {
+            let path = &matches[start].0;
+            let end = start
+                + matches[start..]
+                    .iter()
+                    .take_while(|(other, _, _)| other == path)
+                    .count();
+            let file_matches = &matches[start..end];
+            start = end;
+
+            // Without context, or when the file can't be read again, just the matches
+            let file_lines = (options.context_lines > 0)
+                .then(|| std::fs::read_to_string(path).ok())
+                .flatten();
+            let Some(file_lines) = file_lines else {
+                for (_, line_num, line) in file_matches {
+                    output.push_str(&grep_line(path, *line_num, line, ':', options));
+                }
+                continue;
+            };
+            let lines: Vec<&str> = file_lines.lines().collect();
+
+            // Merge the context windows of nearby matches
+            let mut groups: Vec<(usize, usize)> = Vec::new();
+            for (_, line_num, _) in file_matches {
+                let first = line_num.saturating_sub(options.context_lines).max(1);
+                let last = (line_num + options.context_lines).min(lines.len());
+                match groups.last_mut() {
+                    Some((_, group_last)) if first <= *group_last + 1 => {
+                        *group_last = (*group_last).max(last)
+                    }
+                    _ => groups.push((first, last)),
+                }
+            }
+
+            for (first, last) in groups {
+                if !output.is_empty() {
+                    output.push_str("--\n");
+                }
+                for line_num in first..=last {
+                    let is_match = file_matches.iter().any(|(_, num, _)| *num == line_num);
+                    let separator = if is_match { ':' } else { '-' };
+                    output.push_str(&grep_line(
+                        path,
+                        line_num,
+                        lines[line_num - 1],
+                        separator,
+                        options,
+                    ));
+                }
+            }
+        }
+        output
+    }
+}
+
+/// One line of Grep output, with `separator` after the path and line number
+fn grep_line(
+    path: &Path,
+    line_num: usize,
+    line: &str,
+    separator: char,
+    options: &GrepOutputOptions,
+) -> String {
+    if options.show_line_numbers {
+        format!("{}{separator}{line_num}{separator}{line}\n", path.display())
+    } else {
+        format!("{}{separator}{line}\n", path.display())
+    }
 }
 
 fn glob_to_regex(glob_pattern: &str) -> String {
@@ -0,0 +1,303 @@
+use anyhow::{Context, Result};
+use glob::{MatchOptions, Pattern};
+use ignore::{WalkBuilder, WalkState};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::Duration;
+
+use super::oliignore::OLIIGNORE_FILE;
+
+/// Environment variable controlling large-repo mode: `on`, `off`, or the file
+/// count above which it turns on (100000 by default)
+pub const LARGE_REPO_ENV: &str = "OLI_LARGE_REPO";
+/// Workspaces with more files than this switch to large-repo mode
+pub const DEFAULT_LARGE_REPO_FILES: usize = 100_000;
+/// Most Glob paths or Grep matches returned in large-repo mode
+pub const LARGE_REPO_RESULT_LIMIT: usize = 500;
+/// How often a large workspace is indexed again, in the background
+const REINDEX_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Files indexed between progress reports
+const PROGRESS_EVERY: usize = 10_000;
+
+/// Whether large-repo mode is used, as set by `OLI_LARGE_REPO`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LargeRepoSetting {
+    /// On once the workspace has more than this many files
+    Auto(usize),
+    On,
+    Off,
+}
+
+impl Default for LargeRepoSetting {
+    fn default() -> Self {
+        Self::Auto(DEFAULT_LARGE_REPO_FILES)
+    }
+}
+
+impl LargeRepoSetting {
+    pub fn from_env() -> Self {
+        std::env::var(LARGE_REPO_ENV)
+            .map(|value| Self::parse(&value))
+            .unwrap_or_default()
+    }
+
+    /// Parse `on`, `off` or a file count; anything else means the default
+    pub fn parse(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "on" | "1" | "true" => Self::On,
+            "off" | "0" | "false" => Self::Off,
+            value => value.parse().map(Self::Auto).unwrap_or_default(),
+        }
+    }
+}
+
+/// How far indexing has got, as shown in the status bar
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct IndexStatus {
+    pub root: String,
+    /// Whether Glob, Grep and FindFile use the index and stricter result limits
+    pub large_repo: bool,
+    pub files_indexed: usize,
+    /// False until the first walk of the workspace has finished
+    pub complete: bool,
+}
+
+/// The files of a workspace, indexed in the background with the parallel walker so
+/// Glob and FindFile don't walk a large repository on every call. The index can be
+/// searched while it's still being built.
+#[derive(Debug)]
+pub struct WorkspaceIndex {
+    root: PathBuf,
+    setting: LargeRepoSetting,
+    // Paths relative to the root
+    files: RwLock<Vec<PathBuf>>,
+    status: Mutex<IndexStatus>,
+}
+
+static GLOBAL_INDEX: OnceLock<Arc<WorkspaceIndex>> = OnceLock::new();
+
+impl WorkspaceIndex {
+    pub fn new(root: &Path, setting: LargeRepoSetting) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            setting,
+            files: RwLock::new(Vec::new()),
+            status: Mutex::new(IndexStatus {
+                root: root.display().to_string(),
+                large_repo: setting == LargeRepoSetting::On,
+                ..IndexStatus::default()
+            }),
+        }
+    }
+
+    /// Index `root` on a background thread, and again every few minutes while it's a
+    /// large repository, making the index the one tools use. `on_progress` is called
+    /// as indexing goes. Does nothing when large-repo mode is off.
+    pub fn start(
+        root: &Path,
+        setting: LargeRepoSetting,
+        on_progress: impl Fn(&IndexStatus) + Send + 'static,
+    ) -> Option<Arc<Self>> {
+        if setting == LargeRepoSetting::Off {
+            return None;
+        }
+        let index = Arc::new(Self::new(root, setting));
+        GLOBAL_INDEX.set(index.clone()).ok()?;
+
+        let worker = index.clone();
+        std::thread::spawn(move || loop {
+            worker.build(&on_progress);
+            if !worker.status().large_repo {
+                break;
+            }
+            std::thread::sleep(REINDEX_INTERVAL);
+        });
+        Some(index)
+    }
+
+    /// The index started for a large repository containing `dir`, if any
+    pub fn for_dir(dir: &Path) -> Option<Arc<Self>> {
+        let index = GLOBAL_INDEX.get()?;
+        (index.status().large_repo && absolute(dir).starts_with(&index.root)).then(|| index.clone())
+    }
+
+    pub fn status(&self) -> IndexStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Walk the workspace, respecting `.gitignore` and `.oliignore`. The first walk
+    /// fills the index as it goes; later walks replace it when they finish.
+    pub fn build(&self, on_progress: &dyn Fn(&IndexStatus)) {
+        let first_build = !self.status().complete;
+        let mut files = Vec::new();
+        let (sender, receiver) = channel::<PathBuf>();
+
+        std::thread::scope(|scope| {
+            let root = &self.root;
+            scope.spawn(move || {
+                WalkBuilder::new(root)
+                    .hidden(false)
+                    .standard_filters(true)
+                    .add_custom_ignore_filename(OLIIGNORE_FILE)
+                    .filter_entry(|entry| entry.file_name() != ".git")
+                    .build_parallel()
+                    .run(|| {
+                        let sender = sender.clone();
+                        Box::new(move |entry| {
+                            let Ok(entry) = entry else {
+                                return WalkState::Continue;
+                            };
+                            if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+                                return WalkState::Continue;
+                            }
+                            match entry.path().strip_prefix(root) {
+                                Ok(path) if sender.send(path.to_path_buf()).is_err() => {
+                                    WalkState::Quit
+                                }
+                                _ => WalkState::Continue,
+                            }
+                        })
+                    });
+            });
+
+            // The first walk publishes each batch so tools can use the partial index
+            let mut flush = |batch: &mut Vec<PathBuf>| {
+                if batch.is_empty() {
+                    return;
+                }
+                if !first_build {
+                    files.append(batch);
+                    return;
+                }
+                let indexed = {
+                    let mut shared = self.files.write().unwrap();
+                    shared.append(batch);
+                    shared.len()
+                };
+                let status = {
+                    let mut status = self.status.lock().unwrap();
+                    status.files_indexed = indexed;
+                    status.large_repo = self.is_large(indexed);
+                    status.clone()
+                };
+                on_progress(&status);
+            };
+
+            let mut batch = Vec::new();
+            for path in receiver {
+                batch.push(path);
+                if batch.len() == PROGRESS_EVERY {
+                    flush(&mut batch);
+                }
+            }
+            flush(&mut batch);
+        });
+
+        if !first_build {
+            *self.files.write().unwrap() = files;
+        }
+        let status = {
+            let mut status = self.status.lock().unwrap();
+            status.files_indexed = self.files.read().unwrap().len();
+            status.large_repo = self.is_large(status.files_indexed);
+            status.complete = true;
+            status.clone()
+        };
+        on_progress(&status);
+    }
+
+    fn is_large(&self, files: usize) -> bool {
+        match self.setting {
+            LargeRepoSetting::On => true,
+            LargeRepoSetting::Off => false,
+            LargeRepoSetting::Auto(threshold) => files > threshold,
+        }
+    }
+
+    /// Indexed files under `dir`, as paths relative to it
+    pub fn files_under(&self, dir: &Path) -> Vec<PathBuf> {
+        let Ok(prefix) = absolute(dir)
+            .strip_prefix(&self.root)
+            .map(Path::to_path_buf)
+        else {
+            return Vec::new();
+        };
+        self.files
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|path| path.strip_prefix(&prefix).ok().map(Path::to_path_buf))
+            .collect()
+    }
+
+    /// Indexed files matching a glob pattern, relative to `dir` unless absolute,
+    /// most recently modified first
+    pub fn glob(&self, pattern: &str, dir: &Path) -> Result<Vec<PathBuf>> {
+        let dir = absolute(dir);
+        let full_pattern = if Path::new(pattern).is_absolute() {
+            pattern.to_string()
+        } else {
+            dir.join(pattern).to_string_lossy().into_owned()
+        };
+        let pattern = Pattern::new(&full_pattern)
+            .with_context(|| format!("Invalid glob pattern: {pattern}"))?;
+        // `*` stops at separators, as it does when walking with glob()
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+
+        let mut matches: Vec<(Option<std::time::SystemTime>, PathBuf)> = self
+            .files
+            .read()
+            .unwrap()
+            .iter()
+            .map(|path| self.root.join(path))
+            .filter(|path| pattern.matches_path_with(path, options))
+            .map(|path| {
+                let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+                (modified, path)
+            })
+            .collect();
+        matches.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+        Ok(matches.into_iter().map(|(_, path)| path).collect())
+    }
+
+    /// Note appended to results from an index that's still being built
+    pub fn progress_note(&self) -> String {
+        let status = self.status();
+        if status.complete {
+            return String::new();
+        }
+        format!(
+            "\n[The file index is still being built ({} files so far); results may be \
+             incomplete.]\n",
+            status.files_indexed
+        )
+    }
+}
+
+/// Note appended to results cut down to `shown` in large-repo mode. `total` is
+/// None when the search stopped early, so the total isn't known.
+pub fn truncation_note(shown: usize, total: Option<usize>) -> String {
+    let found = match total {
+        Some(total) if total <= shown => return String::new(),
+        Some(total) => format!("showing {shown} of {total} results"),
+        None => format!("stopped after the first {shown} results"),
+    };
+    format!("\n[Large repository: {found}. Narrow the pattern or path to see the rest.]\n")
+}
+
+/// `path` made absolute against the working directory, without `.` components
+fn absolute(path: &Path) -> PathBuf {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    };
+    path.components().collect()
+}
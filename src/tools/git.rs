@@ -0,0 +1,272 @@
+use crate::tools::fs::oliignore::OliIgnore;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Commits GitLog shows unless asked for another number
+pub const DEFAULT_LOG_LIMIT: usize = 20;
+/// Most commits GitLog shows
+pub const MAX_LOG_LIMIT: usize = 100;
+/// Characters of diff GitDiff returns before truncating it
+pub const MAX_DIFF_CHARS: usize = 50_000;
+
+/// Read-only view of the git repository containing a directory, for the GitStatus,
+/// GitDiff and GitLog tools. These never change the repository, so unlike Bash
+/// they run without asking. Paths excluded by `.oliignore` are left out.
+#[derive(Debug, Clone)]
+pub struct GitInspector {
+    repo_root: PathBuf,
+    oliignore: Option<OliIgnore>,
+}
+
+impl GitInspector {
+    /// The inspector for the repository containing `path`, a file or directory
+    pub fn for_path(path: &Path) -> Result<Self> {
+        let dir = if path.is_file() {
+            path.parent().unwrap_or(path)
+        } else {
+            path
+        };
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["rev-parse", "--show-toplevel"])
+            .output()
+            .context("Failed to run git")?;
+        if !output.status.success() {
+            anyhow::bail!("{} is not inside a git repository", path.display());
+        }
+
+        let repo_root = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+        Ok(Self {
+            oliignore: OliIgnore::find(&repo_root),
+            repo_root,
+        })
+    }
+
+    pub fn repo_root(&self) -> &Path {
+        &self.repo_root
+    }
+
+    /// The branch, its upstream and the changed files, like `git status --short`
+    pub fn status(&self) -> Result<String> {
+        let status = self.git(&["status", "--porcelain=v1", "--branch"])?;
+        let mut lines = status.lines();
+        let branch = lines
+            .next()
+            .and_then(|line| line.strip_prefix("## "))
+            .unwrap_or("unknown");
+
+        let mut hidden = 0;
+        let files: Vec<&str> = lines
+            .filter(|line| {
+                // Renames list both paths as "old -> new"
+                let paths = line.get(3..).unwrap_or_default();
+                let visible = paths.split(" -> ").all(|path| !self.is_excluded(path));
+                if !visible {
+                    hidden += 1;
+                }
+                visible
+            })
+            .collect();
+
+        let mut output = format!("Branch: {branch}\n\n");
+        if files.is_empty() {
+            output.push_str("Working tree clean\n");
+        } else {
+            output.push_str(
+                "Codes give the staged then the unstaged change: M modified, A added, \
+                 D deleted, R renamed, ?? untracked.\n\n",
+            );
+            for file in files {
+                output.push_str(file);
+                output.push('\n');
+            }
+        }
+        output.push_str(&hidden_note(hidden));
+        Ok(output)
+    }
+
+    /// Changes in the working tree against the index, or with `staged` the index
+    /// against HEAD. A `revision` such as `HEAD~3` or `main..feature` diffs against
+    /// it instead. `path` limits the diff to a file or directory.
+    pub fn diff(
+        &self,
+        staged: bool,
+        revision: Option<&str>,
+        path: Option<&Path>,
+    ) -> Result<String> {
+        let mut args = vec!["diff", "--no-color", "--no-ext-diff"];
+        if staged {
+            args.push("--cached");
+        }
+        if let Some(revision) = revision {
+            args.push(check_revision(revision)?);
+        }
+        let pathspec = path.map(|path| path.to_string_lossy().to_string());
+        args.push("--");
+        if let Some(pathspec) = &pathspec {
+            args.push(pathspec);
+        }
+        let diff = self.git(&args)?;
+
+        // Leave out the sections of excluded files
+        let mut hidden = 0;
+        let mut output = String::new();
+        for section in split_diff(&diff) {
+            if diff_paths(section)
+                .iter()
+                .any(|path| self.is_excluded(path))
+            {
+                hidden += 1;
+            } else {
+                output.push_str(section);
+            }
+        }
+
+        if output.is_empty() {
+            output.push_str("No changes\n");
+        } else if output.len() > MAX_DIFF_CHARS {
+            let mut end = MAX_DIFF_CHARS;
+            while !output.is_char_boundary(end) {
+                end -= 1;
+            }
+            output.truncate(end);
+            output.push_str(&format!(
+                "\n\n(Diff truncated at {MAX_DIFF_CHARS} characters. Pass a path to see the rest.)\n"
+            ));
+        }
+        output.push_str(&hidden_note(hidden));
+        Ok(output)
+    }
+
+    /// The most recent commits, newest first, optionally from `revision` and
+    /// only those touching `path`
+    pub fn log(
+        &self,
+        limit: Option<usize>,
+        revision: Option<&str>,
+        path: Option<&Path>,
+    ) -> Result<String> {
+        let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT).clamp(1, MAX_LOG_LIMIT);
+        let count = format!("-n{limit}");
+        let mut args = vec![
+            "log",
+            &count,
+            "--date=short",
+            "--format=%h%x1f%ad%x1f%an%x1f%s",
+        ];
+        if let Some(revision) = revision {
+            args.push(check_revision(revision)?);
+        }
+        let pathspec = path.map(|path| path.to_string_lossy().to_string());
+        args.push("--");
+        if let Some(pathspec) = &pathspec {
+            args.push(pathspec);
+        }
+        let log = self.git(&args)?;
+
+        let commits: Vec<String> = log
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\x1f');
+                let (hash, date, author, subject) = (
+                    fields.next()?,
+                    fields.next()?,
+                    fields.next()?,
+                    fields.next()?,
+                );
+                Some(format!("{hash} {date} {author}: {subject}"))
+            })
+            .collect();
+        if commits.is_empty() {
+            return Ok("No commits\n".to_string());
+        }
+        Ok(format!(
+            "{} most recent commits, newest first:\n\n{}\n",
+            commits.len(),
+            commits.join("\n")
+        ))
+    }
+
+    // Whether a path relative to the repository root is excluded by `.oliignore`
+    fn is_excluded(&self, path: &str) -> bool {
+        let path = path.trim().trim_matches('"');
+        self.oliignore
+            .as_ref()
+            .is_some_and(|oliignore| oliignore.is_excluded(&self.repo_root.join(path)))
+    }
+
+    fn git(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.repo_root)
+            .args(["-c", "core.quotePath=false"])
+            .args(args)
+            .output()
+            .context("Failed to run git")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "git {} failed: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+// Revisions come from the model; one starting with '-' would be read as an option
+fn check_revision(revision: &str) -> Result<&str> {
+    let revision = revision.trim();
+    if revision.is_empty() || revision.starts_with('-') {
+        anyhow::bail!("Invalid revision '{revision}'");
+    }
+    Ok(revision)
+}
+
+/// Split a diff into one section per file, each starting with its `diff --git` line
+pub fn split_diff(diff: &str) -> Vec<&str> {
+    let mut starts: Vec<usize> = diff
+        .match_indices("diff --git ")
+        .map(|(index, _)| index)
+        .filter(|&index| index == 0 || diff.as_bytes()[index - 1] == b'\n')
+        .collect();
+    if starts.first() != Some(&0) {
+        starts.insert(0, 0);
+    }
+    starts.push(diff.len());
+    starts
+        .windows(2)
+        .map(|bounds| &diff[bounds[0]..bounds[1]])
+        .filter(|section| !section.is_empty())
+        .collect()
+}
+
+// The old and new paths of a file's diff section, from its `diff --git` line, which
+// binary files only have, and its `---`, `+++` and rename lines
+fn diff_paths(section: &str) -> Vec<&str> {
+    let header = section
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("diff --git a/"))
+        .and_then(|paths| paths.split_once(" b/"))
+        .map(|(old, new)| [old, new]);
+    let lines = section
+        .lines()
+        .take_while(|line| !line.starts_with("@@"))
+        .filter_map(|line| {
+            line.strip_prefix("--- a/")
+                .or_else(|| line.strip_prefix("+++ b/"))
+                .or_else(|| line.strip_prefix("rename from "))
+                .or_else(|| line.strip_prefix("rename to "))
+        });
+    header.into_iter().flatten().chain(lines).collect()
+}
+
+fn hidden_note(hidden: usize) -> String {
+    match hidden {
+        0 => String::new(),
+        count => format!("\nNote: {count} files excluded by .oliignore are not shown.\n"),
+    }
+}
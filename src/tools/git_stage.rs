@@ -0,0 +1,230 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// How a file differs from the index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeStatus {
+    Modified,
+    Added,
+    Deleted,
+    /// Not tracked by git; it can only be staged as a whole
+    Untracked,
+    /// Binary content without hunks; it can only be staged as a whole
+    Binary,
+}
+
+/// One hunk of an unstaged change, as `git add -p` offers it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Hunk {
+    /// Stable across staging other hunks of the file, which shifts line numbers
+    pub id: String,
+    /// The `@@ -a,b +c,d @@` line
+    pub header: String,
+    /// Diff lines, each starting with ' ', '+', '-' or '\'
+    pub lines: Vec<String>,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// A file with unstaged changes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileChange {
+    /// Path relative to the repository root
+    pub path: String,
+    pub status: ChangeStatus,
+    pub hunks: Vec<Hunk>,
+    // The `diff --git`, `---` and `+++` lines a patch for one hunk needs
+    #[serde(skip)]
+    patch_header: Vec<String>,
+}
+
+/// Stages unstaged changes hunk by hunk, like `git add -p`, so agent edits can be
+/// split into separate commits
+#[derive(Debug, Clone)]
+pub struct GitStager {
+    repo_root: PathBuf,
+}
+
+impl GitStager {
+    /// The stager for the repository containing `dir`
+    pub fn for_dir(dir: &Path) -> Result<Self> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["rev-parse", "--show-toplevel"])
+            .output()
+            .context("Failed to run git")?;
+        if !output.status.success() {
+            anyhow::bail!("{} is not inside a git repository", dir.display());
+        }
+
+        Ok(Self {
+            repo_root: PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()),
+        })
+    }
+
+    pub fn repo_root(&self) -> &Path {
+        &self.repo_root
+    }
+
+    /// Unstaged changes to tracked files, split into hunks, followed by untracked files
+    pub fn changes(&self) -> Result<Vec<FileChange>> {
+        let diff = self.git(&["diff", "--no-color", "--no-ext-diff", "-U3"])?;
+        let mut changes = parse_diff(&diff);
+
+        let untracked = self.git(&["ls-files", "--others", "--exclude-standard"])?;
+        changes.extend(
+            untracked
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(|path| FileChange {
+                    path: path.to_string(),
+                    status: ChangeStatus::Untracked,
+                    hunks: Vec::new(),
+                    patch_header: Vec::new(),
+                }),
+        );
+        Ok(changes)
+    }
+
+    /// Stage one hunk of a file
+    pub fn stage_hunk(&self, path: &str, hunk_id: &str) -> Result<()> {
+        let changes = self.changes()?;
+        let file = changes
+            .iter()
+            .find(|change| change.path == path)
+            .with_context(|| format!("No unstaged changes in {path}"))?;
+        let hunk = file
+            .hunks
+            .iter()
+            .find(|hunk| hunk.id == hunk_id)
+            .with_context(|| format!("No such hunk in {path}; it may already be staged"))?;
+
+        let mut patch = file.patch_header.join("\n");
+        patch.push('\n');
+        patch.push_str(&hunk.header);
+        patch.push('\n');
+        for line in &hunk.lines {
+            patch.push_str(line);
+            patch.push('\n');
+        }
+
+        let mut child = Command::new("git")
+            .arg("-C")
+            .arg(&self.repo_root)
+            .args(["apply", "--cached", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run git apply")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(patch.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to stage hunk in {path}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+
+    /// Stage a whole file, including untracked and binary files
+    pub fn stage_file(&self, path: &str) -> Result<()> {
+        self.git(&["add", "--", path])?;
+        Ok(())
+    }
+
+    fn git(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.repo_root)
+            .args(args)
+            .output()
+            .context("Failed to run git")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "git {} failed: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+/// Split `git diff` output into files and hunks
+pub fn parse_diff(diff: &str) -> Vec<FileChange> {
+    let mut changes: Vec<FileChange> = Vec::new();
+
+    for line in diff.lines() {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            // "a/old b/new"; the new path is the one that's staged
+            let path = paths
+                .rsplit_once(" b/")
+                .map(|(_, new)| new)
+                .unwrap_or(paths)
+                .to_string();
+            changes.push(FileChange {
+                path,
+                status: ChangeStatus::Modified,
+                hunks: Vec::new(),
+                patch_header: vec![line.to_string()],
+            });
+            continue;
+        }
+
+        let Some(file) = changes.last_mut() else {
+            continue;
+        };
+
+        if line.starts_with("@@") {
+            file.hunks.push(Hunk {
+                id: String::new(),
+                header: line.to_string(),
+                lines: Vec::new(),
+                added: 0,
+                removed: 0,
+            });
+        } else if let Some(hunk) = file.hunks.last_mut() {
+            match line.chars().next() {
+                Some('+') => hunk.added += 1,
+                Some('-') => hunk.removed += 1,
+                _ => {}
+            }
+            hunk.lines.push(line.to_string());
+        } else {
+            // Extended header lines before the first hunk
+            if line.starts_with("new file mode") {
+                file.status = ChangeStatus::Added;
+            } else if line.starts_with("deleted file mode") {
+                file.status = ChangeStatus::Deleted;
+            } else if line.starts_with("Binary files ") {
+                file.status = ChangeStatus::Binary;
+            }
+            file.patch_header.push(line.to_string());
+        }
+    }
+
+    for file in &mut changes {
+        for hunk in &mut file.hunks {
+            hunk.id = hunk_id(&file.path, &hunk.lines);
+        }
+    }
+    changes
+}
+
+fn hunk_id(path: &str, lines: &[String]) -> String {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    lines.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
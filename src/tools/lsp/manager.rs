@@ -1,10 +1,11 @@
 use anyhow::{anyhow, Result};
+use serde_json::Value;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
-use super::servers::LspServer;
+use super::servers::{LspServer, RUST_ANALYZER_READY_TIMEOUT};
 use crate::tools::lsp::models::{
     CodeLens, DocumentSymbol, Location, LspServerType, Position, Range, SemanticTokens,
 };
@@ -46,6 +47,18 @@ impl LspServerManager {
 
             // Initialize the server
             server.initialize()?;
+
+            // rust-analyzer answers with partial results until it has loaded the
+            // workspace, which takes a while in large ones
+            if *server_type == LspServerType::Rust
+                && !server.wait_until_ready(RUST_ANALYZER_READY_TIMEOUT)?
+            {
+                eprintln!(
+                    "rust-analyzer is still indexing {} after {}s; results may be incomplete",
+                    workspace_path.display(),
+                    RUST_ANALYZER_READY_TIMEOUT.as_secs()
+                );
+            }
             servers.insert(server_key.clone(), server);
         }
 
@@ -416,8 +429,88 @@ impl LspServerManager {
         let result = server.definition(&uri, position.line, position.character)?;
 
         // Parse the result
-        let locations: Vec<Location> = serde_json::from_value(result)?;
-        Ok(locations)
+        Location::parse_all(result)
+    }
+
+    /// Get the references to the symbol at a specific position, including its
+    /// declaration
+    pub fn references(
+        &self,
+        file_path: &str,
+        position: &Position,
+        server_type: &LspServerType,
+    ) -> Result<Vec<Location>> {
+        // Normalize the path - convert relative to absolute
+        let path = if Path::new(file_path).is_relative() {
+            let current_dir = std::env::current_dir()?;
+            current_dir.join(file_path).canonicalize()?
+        } else {
+            PathBuf::from(file_path).canonicalize()?
+        };
+
+        // Use find_workspace_root with the Path
+        let workspace_path = self.find_workspace_root(&path)?;
+        let server_key = self.get_server(server_type, &workspace_path)?;
+
+        // Create a proper URI with file:// scheme
+        let uri = format!("file://{}", path.to_string_lossy().replace('\\', "/"));
+        let file_content = fs::read_to_string(&path)?;
+        let language_id = match server_type {
+            LspServerType::Python => "python",
+            LspServerType::Rust => "rust",
+        };
+
+        let mut servers = self
+            .servers
+            .lock()
+            .map_err(|_| anyhow!("Failed to lock servers mutex"))?;
+        let server = servers
+            .get_mut(&server_key)
+            .ok_or_else(|| anyhow!("Server not found: {}", server_key))?;
+
+        // Notify the server about the file
+        server.did_open_text_document(&uri, language_id, 1, &file_content)?;
+
+        let result = server.references(&uri, position.line, position.character, true)?;
+        Location::parse_all(result)
+    }
+
+    /// Rename the symbol at a position across the workspace. Returns the server's
+    /// `WorkspaceEdit` without applying it.
+    pub fn rename(
+        &self,
+        file_path: &str,
+        position: &Position,
+        new_name: &str,
+        server_type: &LspServerType,
+    ) -> Result<Value> {
+        let path = if Path::new(file_path).is_relative() {
+            let current_dir = std::env::current_dir()?;
+            current_dir.join(file_path).canonicalize()?
+        } else {
+            PathBuf::from(file_path).canonicalize()?
+        };
+
+        let workspace_path = self.find_workspace_root(&path)?;
+        let server_key = self.get_server(server_type, &workspace_path)?;
+
+        let uri = format!("file://{}", path.to_string_lossy().replace('\\', "/"));
+        let file_content = fs::read_to_string(&path)?;
+        let language_id = match server_type {
+            LspServerType::Python => "python",
+            LspServerType::Rust => "rust",
+        };
+
+        let mut servers = self
+            .servers
+            .lock()
+            .map_err(|_| anyhow!("Failed to lock servers mutex"))?;
+        let server = servers
+            .get_mut(&server_key)
+            .ok_or_else(|| anyhow!("Server not found: {}", server_key))?;
+
+        server.did_open_text_document(&uri, language_id, 1, &file_content)?;
+        server.rename(&uri, position.line, position.character, new_name)
     }
 
     /// Find the root directory of a workspace
@@ -10,3 +10,4 @@ pub use models::{
     SemanticTokens, SemanticTokensParams as ModelsSemanticTokensParams, SymbolKind,
 };
 pub use protocol::{CodeLensParams, DocumentSymbolParams, SemanticTokensParams};
+pub use servers::{RUST_ANALYZER_ENV, RUST_ANALYZER_READY_TIMEOUT};
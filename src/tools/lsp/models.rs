@@ -49,6 +49,33 @@ pub struct Location {
     pub range: Range,
 }
 
+impl Location {
+    /// Locations from a definition or references result, which servers send as null,
+    /// one `Location`, a list of them, or a list of `LocationLink`s
+    pub fn parse_all(result: serde_json::Value) -> anyhow::Result<Vec<Location>> {
+        let items = match result {
+            serde_json::Value::Null => Vec::new(),
+            serde_json::Value::Array(items) => items,
+            location => vec![location],
+        };
+        items
+            .into_iter()
+            .map(|item| match item.get("targetUri") {
+                // A LocationLink, located by the symbol's name within its target
+                Some(uri) => Ok(Location {
+                    uri: serde_json::from_value(uri.clone())?,
+                    range: serde_json::from_value(
+                        item.get("targetSelectionRange")
+                            .unwrap_or(&item["targetRange"])
+                            .clone(),
+                    )?,
+                }),
+                None => Ok(serde_json::from_value(item)?),
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum DocumentSymbolResponse {
@@ -160,19 +160,6 @@ pub enum MarkupKind {
     Markdown,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TextDocumentItem {
-    pub uri: String,
-    pub language_id: String,
-    pub version: u32,
-    pub text: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct DidOpenTextDocumentParams {
-    pub text_document: TextDocumentItem,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TextDocumentIdentifier {
     pub uri: String,
@@ -193,12 +180,6 @@ pub struct CodeLensParams {
     pub text_document: TextDocumentIdentifier,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TextDocumentPositionParams {
-    pub text_document: TextDocumentIdentifier,
-    pub position: crate::tools::lsp::models::Position,
-}
-
 pub fn get_initialize_params(root_path: &str) -> InitializeParams {
     InitializeParams {
         process_id: Some(std::process::id()),
@@ -309,7 +290,8 @@ pub fn get_initialize_params(root_path: &str) -> InitializeParams {
             window: Some(WindowClientCapabilities {
                 work_done_progress: Some(true),
             }),
-            experimental: None,
+            // Lets rust-analyzer report when it has finished loading the workspace
+            experimental: Some(serde_json::json!({ "serverStatusNotification": true })),
         },
         trace: Some(String::from("off")),
         workspace_folders: Some(vec![WorkspaceFolder {
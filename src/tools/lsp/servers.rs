@@ -1,20 +1,43 @@
 use anyhow::{anyhow, Result};
-use serde_json::Value;
-use std::io::{BufRead, BufReader, Read, Write};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Child, Command, Stdio};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
 
 use crate::tools::lsp::protocol::{
     get_initialize_params, NotificationMessage, RequestId, RequestMessage, ResponseMessage,
 };
+use crate::tools::processes::{track_child, ChildKind, TrackedChild};
+
+/// Environment variable naming the rust-analyzer binary to run, when it isn't the
+/// one on PATH
+pub const RUST_ANALYZER_ENV: &str = "OLI_RUST_ANALYZER";
+
+/// How long rust-analyzer may take to load the workspace before it's queried anyway
+pub const RUST_ANALYZER_READY_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How long to wait for the answer to a request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long to wait for the answer to `shutdown` before killing the server
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
 
 pub struct LspServer {
     process: Child,
+    // Recorded so the server is killed if oli crashes before shutting it down
+    tracked: TrackedChild,
     #[allow(dead_code)]
     server_type: String,
     root_path: PathBuf,
     initialized: bool,
     next_request_id: u64,
+    // Messages from the server, read on their own thread so waits can time out
+    messages: Receiver<Value>,
+    // Whether the server has loaded the workspace. Only rust-analyzer reports it;
+    // other servers are taken to be ready once initialized.
+    ready: bool,
 }
 
 impl LspServer {
@@ -54,15 +77,8 @@ impl LspServer {
             .stderr(Stdio::piped())
             .spawn()?;
 
-        let server = LspServer {
-            process,
-            server_type: "python".to_string(),
-            root_path: root_path.to_path_buf(),
-            initialized: false,
-            next_request_id: 1,
-        };
-
-        Ok(server)
+        let tracked = track_child(ChildKind::Lsp, process.id(), "pyright-langserver", false);
+        Ok(Self::new(process, tracked, "python", root_path, true))
     }
 
     pub fn start_rust_server(root_path: &Path) -> Result<Self> {
@@ -71,36 +87,50 @@ impl LspServer {
             root_path.display()
         );
 
-        // Check if rust-analyzer is installed
-        let rust_analyzer_check = Command::new("sh")
-            .arg("-c")
-            .arg("command -v rust-analyzer")
+        // rustup installs a rust-analyzer proxy even without the component, so check
+        // that it runs rather than that it's on PATH
+        let binary = std::env::var(RUST_ANALYZER_ENV).unwrap_or_else(|_| "rust-analyzer".into());
+        let installed = Command::new(&binary)
+            .arg("--version")
             .stdout(Stdio::null())
             .stderr(Stdio::null())
-            .status();
-
-        if rust_analyzer_check.is_err() || !rust_analyzer_check.unwrap().success() {
+            .status()
+            .is_ok_and(|status| status.success());
+        if !installed {
             return Err(anyhow!(
-                "Rust LSP server (rust-analyzer) not found. Please install it."
+                "Rust LSP server ({binary}) not found. Install it with 'rustup component add rust-analyzer', or set {RUST_ANALYZER_ENV} to its path."
             ));
         }
 
-        let process = Command::new("rust-analyzer")
+        let process = Command::new(&binary)
             .current_dir(root_path)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
+            .stderr(Stdio::null())
             .spawn()?;
 
-        let server = LspServer {
+        let tracked = track_child(ChildKind::Lsp, process.id(), "rust-analyzer", false);
+        Ok(Self::new(process, tracked, "rust", root_path, false))
+    }
+
+    fn new(
+        mut process: Child,
+        tracked: TrackedChild,
+        server_type: &str,
+        root_path: &Path,
+        ready: bool,
+    ) -> Self {
+        let stdout = process.stdout.take().expect("stdout is piped");
+        LspServer {
             process,
-            server_type: "rust".to_string(),
+            tracked,
+            server_type: server_type.to_string(),
             root_path: root_path.to_path_buf(),
             initialized: false,
             next_request_id: 1,
-        };
-
-        Ok(server)
+            messages: read_messages(stdout),
+            ready,
+        }
     }
 
     pub fn initialize(&mut self) -> Result<ResponseMessage> {
@@ -120,7 +150,7 @@ impl LspServer {
     pub fn shutdown(&mut self) -> Result<()> {
         if self.initialized {
             // Send shutdown request
-            self.send_request("shutdown", None)?;
+            self.request("shutdown", None, SHUTDOWN_TIMEOUT)?;
 
             // Send exit notification
             self.send_notification("exit", None)?;
@@ -136,10 +166,20 @@ impl LspServer {
         method: &str,
         params: Option<Value>,
     ) -> Result<Option<ResponseMessage>> {
+        self.request(method, params, REQUEST_TIMEOUT).map(Some)
+    }
+
+    fn request(
+        &mut self,
+        method: &str,
+        params: Option<Value>,
+        timeout: Duration,
+    ) -> Result<ResponseMessage> {
         if !self.initialized && method != "initialize" {
             return Err(anyhow!("LSP server not initialized"));
         }
 
+        self.tracked.touch();
         let id = self.next_request_id;
         self.next_request_id += 1;
 
@@ -149,75 +189,116 @@ impl LspServer {
             method: method.to_string(),
             params,
         };
+        self.write_message(&serde_json::to_value(request)?)?;
+
+        // Servers send notifications and requests of their own before answering
+        let deadline = Instant::now() + timeout;
+        loop {
+            let message = self.next_message(deadline)?.ok_or_else(|| {
+                anyhow!(
+                    "No response from the LSP server to {method} within {}s",
+                    timeout.as_secs()
+                )
+            })?;
+            if message.get("method").is_some() || message["id"] != json!(id) {
+                continue;
+            }
 
-        let request_json = serde_json::to_string(&request)?;
-        let content_length = request_json.len();
-
-        let message = format!("Content-Length: {content_length}\r\n\r\n{request_json}");
+            return match serde_json::from_value::<ResponseMessage>(message.clone()) {
+                Ok(response) => Ok(response),
+                // Keep the result of a response that's otherwise non-standard
+                Err(e) => match message.get("result") {
+                    Some(result) => Ok(ResponseMessage {
+                        jsonrpc: "2.0".to_string(),
+                        id: RequestId::Number(id),
+                        result: Some(result.clone()),
+                        error: None,
+                    }),
+                    None => Err(e.into()),
+                },
+            };
+        }
+    }
 
-        if let Some(stdin) = self.process.stdin.as_mut() {
-            stdin.write_all(message.as_bytes())?;
-            stdin.flush()?;
-        } else {
-            return Err(anyhow!("Failed to get stdin handle"));
+    /// Wait for the server to finish loading the workspace, returning false if it
+    /// hasn't by `timeout`. rust-analyzer gives partial or empty results until then.
+    pub fn wait_until_ready(&mut self, timeout: Duration) -> Result<bool> {
+        let deadline = Instant::now() + timeout;
+        while !self.ready {
+            if self.next_message(deadline)?.is_none() {
+                return Ok(false);
+            }
         }
+        Ok(true)
+    }
 
-        // Read response
-        if let Some(stdout) = self.process.stdout.as_mut() {
-            let mut reader = BufReader::new(stdout);
-            let mut header = String::new();
-            let mut content_length = 0;
-
-            // Read headers
-            loop {
-                header.clear();
-                reader.read_line(&mut header)?;
-                if header.trim().is_empty() {
-                    break;
-                }
+    // The next message from the server, answering its requests and noting its status
+    // along the way; None when nothing arrives by `deadline`
+    fn next_message(&mut self, deadline: Instant) -> Result<Option<Value>> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let message = match self.messages.recv_timeout(timeout) {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => return Ok(None),
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(anyhow!("The {} LSP server exited", self.server_type))
+            }
+        };
 
-                if let Some(stripped) = header.strip_prefix("Content-Length: ") {
-                    content_length = stripped.trim().parse::<usize>()?;
-                }
+        if let Some(method) = message["method"].as_str() {
+            if message.get("id").is_some() {
+                self.answer_server_request(method, &message)?;
+            } else {
+                self.note_notification(method, &message["params"]);
             }
+        }
+        Ok(Some(message))
+    }
 
-            // Check if we have a valid content length
-            if content_length == 0 {
-                return Err(anyhow!("Invalid content length from LSP server"));
+    // Servers ask the client for settings and to register progress tokens and
+    // capabilities; defaults and acknowledgements are enough for queries
+    fn answer_server_request(&mut self, method: &str, request: &Value) -> Result<()> {
+        let result = match method {
+            // One setting per item asked for; null leaves each at its default
+            "workspace/configuration" => {
+                let items = request["params"]["items"].as_array().map_or(0, Vec::len);
+                Value::Array(vec![Value::Null; items])
             }
+            _ => Value::Null,
+        };
+        self.write_message(&json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+    }
 
-            // Read content
-            let mut content = vec![0; content_length];
-            reader.read_exact(&mut content)?;
-
-            // Log the response for debugging
-            let content_str = String::from_utf8_lossy(&content);
-            eprintln!("LSP response: {content_str}");
-
-            // Parse the response
-            match serde_json::from_slice::<ResponseMessage>(&content) {
-                Ok(response) => return Ok(Some(response)),
-                Err(e) => {
-                    eprintln!("Error parsing LSP response: {e}");
-                    // Try to manually extract the result to handle non-standard responses
-                    if let Ok(json) = serde_json::from_slice::<serde_json::Value>(&content) {
-                        if let Some(result) = json.get("result") {
-                            // Create a synthesized response
-                            let response = ResponseMessage {
-                                jsonrpc: "2.0".to_string(),
-                                id: Default::default(),
-                                result: Some(result.clone()),
-                                error: None,
-                            };
-                            return Ok(Some(response));
-                        }
-                    }
-                    return Err(e.into());
-                }
+    fn note_notification(&mut self, method: &str, params: &Value) {
+        match method {
+            // rust-analyzer's status, quiescent once it has loaded the workspace and
+            // finished indexing; it becomes busy again when files change
+            "experimental/serverStatus" => {
+                self.ready = params["quiescent"].as_bool().unwrap_or(false);
+            }
+            // Older rust-analyzers only report the end of indexing as progress
+            "$/progress"
+                if params["token"]
+                    .as_str()
+                    .is_some_and(|token| token.ends_with("Indexing"))
+                    && params["value"]["kind"] == "end" =>
+            {
+                self.ready = true;
             }
+            _ => {}
         }
+    }
 
-        Ok(None)
+    fn write_message(&mut self, message: &Value) -> Result<()> {
+        let json = serde_json::to_string(message)?;
+        let framed = format!("Content-Length: {}\r\n\r\n{json}", json.len());
+        let stdin = self
+            .process
+            .stdin
+            .as_mut()
+            .ok_or_else(|| anyhow!("Failed to get stdin handle"))?;
+        stdin.write_all(framed.as_bytes())?;
+        stdin.flush()?;
+        Ok(())
     }
 
     fn send_notification(&mut self, method: &str, params: Option<Value>) -> Result<()> {
@@ -231,18 +312,7 @@ impl LspServer {
             params,
         };
 
-        let notification_json = serde_json::to_string(&notification)?;
-        let content_length = notification_json.len();
-
-        let message = format!("Content-Length: {content_length}\r\n\r\n{notification_json}");
-
-        if let Some(stdin) = self.process.stdin.as_mut() {
-            stdin.write_all(message.as_bytes())?;
-            stdin.flush()?;
-            Ok(())
-        } else {
-            Err(anyhow!("Failed to get stdin handle"))
-        }
+        self.write_message(&serde_json::to_value(notification)?)
     }
 
     pub fn did_open_text_document(
@@ -277,8 +347,10 @@ impl LspServer {
         // Log what we're about to do
         eprintln!("Sending documentSymbol request for URI: {uri}");
 
-        // Add a delay to ensure the server is ready (pyright needs this sometimes)
-        std::thread::sleep(std::time::Duration::from_millis(1000));
+        // Give pyright a moment to analyze the file it was just sent
+        if self.server_type == "python" {
+            std::thread::sleep(Duration::from_millis(1000));
+        }
 
         // Send the request
         let response = self
@@ -403,6 +475,52 @@ impl LspServer {
         }
     }
 
+    pub fn references(
+        &mut self,
+        uri: &str,
+        line: u32,
+        character: u32,
+        include_declaration: bool,
+    ) -> Result<Value> {
+        let params = serde_json::json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character },
+            "context": { "includeDeclaration": include_declaration }
+        });
+
+        let response = self
+            .send_request("textDocument/references", Some(params))?
+            .ok_or_else(|| anyhow!("No response from LSP server"))?;
+
+        match response.result {
+            Some(result) => Ok(result),
+            None => Err(anyhow!("No result in LSP response: {:?}", response.error)),
+        }
+    }
+
+    pub fn rename(
+        &mut self,
+        uri: &str,
+        line: u32,
+        character: u32,
+        new_name: &str,
+    ) -> Result<Value> {
+        let params = serde_json::json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character },
+            "newName": new_name
+        });
+
+        let response = self
+            .send_request("textDocument/rename", Some(params))?
+            .ok_or_else(|| anyhow!("No response from LSP server"))?;
+
+        match response.result {
+            Some(result) => Ok(result),
+            None => Err(anyhow!("No result in LSP response: {:?}", response.error)),
+        }
+    }
+
     #[allow(dead_code)]
     pub fn get_server_type(&self) -> &str {
         &self.server_type
@@ -418,3 +536,42 @@ impl Drop for LspServer {
         }
     }
 }
+
+// Read messages framed by Content-Length headers from the server on a thread of
+// their own, until it closes its output
+fn read_messages(stdout: ChildStdout) -> Receiver<Value> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(stdout);
+        while let Ok(Some(message)) = read_message(&mut reader) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim();
+        if header.is_empty() && content_length.is_some() {
+            break;
+        }
+        if let Some(length) = header.strip_prefix("Content-Length:") {
+            content_length = Some(length.trim().parse::<usize>()?);
+        }
+    }
+
+    let mut content = vec![0; content_length.unwrap_or_default()];
+    reader.read_exact(&mut content)?;
+    // A message that isn't JSON is skipped rather than ending the stream
+    Ok(Some(
+        serde_json::from_slice(&content).unwrap_or(Value::Null),
+    ))
+}
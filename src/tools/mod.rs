@@ -1,2 +1,13 @@
+pub mod dependencies;
+pub mod editor;
 pub mod fs;
+pub mod git;
+pub mod git_stage;
 pub mod lsp;
+pub mod notebook;
+pub mod processes;
+pub mod remote;
+pub mod rename;
+pub mod shell;
+pub mod shell_session;
+pub mod test_runner;
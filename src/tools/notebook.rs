@@ -0,0 +1,348 @@
+//! Reading and editing Jupyter notebooks (`.ipynb`) a cell at a time, so the agent
+//! never has to rewrite a notebook's JSON by hand
+
+use crate::tools::fs::diff::DiffTools;
+use crate::tools::fs::file_ops::FileOps;
+use crate::tools::fs::oliignore;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Longest output shown for a cell by NotebookRead, in characters
+pub const MAX_OUTPUT_CHARS: usize = 2000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotebookReadParams {
+    pub file_path: String,
+}
+
+/// What NotebookEditCell does to the cell it addresses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CellEditMode {
+    /// Replace the cell's source, and its type when one is given
+    #[default]
+    Replace,
+    /// Insert a new cell at `cell_index`, after the cell `cell_id`, or at the end
+    Insert,
+    /// Remove the cell
+    Delete,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotebookEditCellParams {
+    pub file_path: String,
+    /// The cell's `id`, as shown by NotebookRead
+    pub cell_id: Option<String>,
+    /// The cell's position, from 0
+    pub cell_index: Option<usize>,
+    /// The cell's new source; required unless deleting
+    pub new_source: Option<String>,
+    /// `code` or `markdown`; new cells default to code
+    pub cell_type: Option<String>,
+    pub edit_mode: Option<CellEditMode>,
+}
+
+/// A notebook's JSON, kept whole so fields oli doesn't know about are written back
+/// unchanged
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notebook {
+    path: PathBuf,
+    json: Value,
+}
+
+impl Notebook {
+    pub fn load(path: &Path) -> Result<Self> {
+        oliignore::check_path(path)?;
+        let content = FileOps::read_file(path)?;
+        Self::parse(path, &content)
+    }
+
+    pub fn parse(path: &Path, content: &str) -> Result<Self> {
+        let json: Value = serde_json::from_str(content)
+            .with_context(|| format!("{} is not valid notebook JSON", path.display()))?;
+        if !json["cells"].is_array() {
+            anyhow::bail!("{} has no cells; is it a Jupyter notebook?", path.display());
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            json,
+        })
+    }
+
+    pub fn cells(&self) -> &[Value] {
+        self.json["cells"].as_array().map_or(&[], Vec::as_slice)
+    }
+
+    fn cells_mut(&mut self) -> &mut Vec<Value> {
+        self.json["cells"]
+            .as_array_mut()
+            .expect("parse checked the notebook has cells")
+    }
+
+    /// The notebook as the model reads it: each cell's index, id, type and source,
+    /// followed by its outputs
+    pub fn render(&self) -> String {
+        let cells = self.cells();
+        let mut output = format!(
+            "Notebook {} with {} cell{}",
+            self.path.display(),
+            cells.len(),
+            if cells.len() == 1 { "" } else { "s" }
+        );
+        if let Some(language) = self.json["metadata"]["language_info"]["name"]
+            .as_str()
+            .or_else(|| self.json["metadata"]["kernelspec"]["language"].as_str())
+        {
+            let _ = write!(output, " ({language})");
+        }
+        output.push('\n');
+
+        for (index, cell) in cells.iter().enumerate() {
+            let _ = write!(output, "\nCell {index} [{}", cell_type(cell));
+            if let Some(id) = cell["id"].as_str() {
+                let _ = write!(output, ", id: {id}");
+            }
+            if let Some(count) = cell["execution_count"].as_u64() {
+                let _ = write!(output, ", execution count: {count}");
+            }
+            output.push_str("]\n");
+            output.push_str(&source(cell));
+            if !output.ends_with('\n') {
+                output.push('\n');
+            }
+
+            let outputs = cell["outputs"].as_array().map_or(&[][..], Vec::as_slice);
+            if !outputs.is_empty() {
+                output.push_str("Outputs:\n");
+                for cell_output in outputs {
+                    for line in render_output(cell_output).lines() {
+                        let _ = writeln!(output, "  {line}");
+                    }
+                }
+            }
+        }
+        output
+    }
+
+    /// Apply an edit, returning the diff of the cell it changed
+    pub fn apply(&mut self, edit: &NotebookEditCellParams) -> Result<String> {
+        let mode = edit.edit_mode.unwrap_or_default();
+        let cell_type = edit.cell_type.as_deref().map(parse_cell_type).transpose()?;
+        let new_source = || {
+            edit.new_source.as_deref().ok_or_else(|| {
+                anyhow::anyhow!("new_source is required to replace or insert a cell")
+            })
+        };
+
+        let (index, old, new) = match mode {
+            CellEditMode::Replace => {
+                let index = self.find_cell(edit)?;
+                let new_source = new_source()?;
+                let cell = &mut self.cells_mut()[index];
+                let old = describe_cell(cell);
+                set_source(cell, new_source);
+                if let Some(cell_type) = cell_type {
+                    set_cell_type(cell, cell_type);
+                }
+                // Outputs belong to the old source
+                if cell.get("outputs").is_some() {
+                    cell["outputs"] = json!([]);
+                    cell["execution_count"] = Value::Null;
+                }
+                (index, Some(old), Some(describe_cell(cell)))
+            }
+            CellEditMode::Insert => {
+                let index = match (&edit.cell_id, edit.cell_index) {
+                    (None, None) => self.cells().len(),
+                    (None, Some(index)) if index <= self.cells().len() => index,
+                    _ => self.find_cell(edit)? + 1,
+                };
+                let cell = self.new_cell(cell_type.unwrap_or("code"), new_source()?);
+                let new = describe_cell(&cell);
+                self.cells_mut().insert(index, cell);
+                (index, None, Some(new))
+            }
+            CellEditMode::Delete => {
+                let index = self.find_cell(edit)?;
+                let cell = self.cells_mut().remove(index);
+                (index, Some(describe_cell(&cell)), None)
+            }
+        };
+
+        let label = format!("{} cell {index}", self.path.display());
+        let diff_lines = DiffTools::generate_diff(
+            old.as_deref().unwrap_or_default(),
+            new.as_deref().unwrap_or_default(),
+        );
+        DiffTools::format_diff(&diff_lines, &label)
+    }
+
+    /// The index of the cell an edit addresses, by id or by index
+    pub fn find_cell(&self, edit: &NotebookEditCellParams) -> Result<usize> {
+        let cells = self.cells();
+        match (&edit.cell_id, edit.cell_index) {
+            (Some(id), index) => {
+                let found = cells
+                    .iter()
+                    .position(|cell| cell["id"].as_str() == Some(id.as_str()))
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "No cell with id '{id}' in {}; use NotebookRead to see the cell ids",
+                            self.path.display()
+                        )
+                    })?;
+                if index.is_some_and(|index| index != found) {
+                    anyhow::bail!("Cell '{id}' is cell {found}, not cell {}", index.unwrap());
+                }
+                Ok(found)
+            }
+            (None, Some(index)) if index < cells.len() => Ok(index),
+            (None, Some(index)) => Err(anyhow::anyhow!(
+                "Cell {index} is out of range; {} has {} cells",
+                self.path.display(),
+                cells.len()
+            )),
+            (None, None) => Err(anyhow::anyhow!("Pass the cell's cell_id or cell_index")),
+        }
+    }
+
+    fn new_cell(&self, cell_type: &str, new_source: &str) -> Value {
+        let mut cell = json!({ "cell_type": cell_type, "metadata": {}, "source": [] });
+        set_source(&mut cell, new_source);
+        if cell_type == "code" {
+            cell["execution_count"] = Value::Null;
+            cell["outputs"] = json!([]);
+        }
+        // Cell ids are required from nbformat 4.5 on
+        let has_ids = self.json["nbformat"].as_u64().unwrap_or(4) > 4
+            || self.json["nbformat_minor"].as_u64().unwrap_or(0) >= 5;
+        if has_ids {
+            let id = uuid::Uuid::new_v4().simple().to_string();
+            cell["id"] = Value::String(id[..8].to_string());
+        }
+        cell
+    }
+
+    /// The notebook's JSON as Jupyter writes it, indented by one space
+    pub fn to_json(&self) -> Result<String> {
+        let mut buffer = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+        let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+        self.json.serialize(&mut serializer)?;
+        let mut json = String::from_utf8(buffer)?;
+        json.push('\n');
+        Ok(json)
+    }
+}
+
+/// Read a notebook for the model
+pub fn read_notebook(path: &Path) -> Result<String> {
+    Ok(Notebook::load(path)?.render())
+}
+
+/// The notebook an edit would write and the diff of the cell it changes, without
+/// writing anything
+pub fn generate_cell_edit(path: &Path, edit: &NotebookEditCellParams) -> Result<(String, String)> {
+    let mut notebook = Notebook::load(path)?;
+    let diff = notebook.apply(edit)?;
+    Ok((notebook.to_json()?, diff))
+}
+
+/// Edit one cell of a notebook on disk, returning the cell's diff. Notebooks are
+/// written straight to disk, as an editor's notebook view can't take a text edit.
+pub fn edit_cell(path: &Path, edit: &NotebookEditCellParams) -> Result<String> {
+    let (json, diff) = generate_cell_edit(path, edit)?;
+    FileOps::write_file(path, &json)?;
+    Ok(diff)
+}
+
+fn parse_cell_type(cell_type: &str) -> Result<&'static str> {
+    match cell_type.trim().to_lowercase().as_str() {
+        "code" => Ok("code"),
+        "markdown" => Ok("markdown"),
+        "raw" => Ok("raw"),
+        other => Err(anyhow::anyhow!(
+            "Unknown cell type '{other}'; use code or markdown"
+        )),
+    }
+}
+
+fn cell_type(cell: &Value) -> &str {
+    cell["cell_type"].as_str().unwrap_or("code")
+}
+
+/// A cell's source, stored either as one string or as a list of lines
+pub fn source(cell: &Value) -> String {
+    match &cell["source"] {
+        Value::String(source) => source.clone(),
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+// Stored as lines, each keeping its newline, as Jupyter writes them
+fn set_source(cell: &mut Value, source: &str) {
+    let lines: Vec<Value> = source
+        .split_inclusive('\n')
+        .map(|line| Value::String(line.to_string()))
+        .collect();
+    cell["source"] = Value::Array(lines);
+}
+
+fn set_cell_type(cell: &mut Value, cell_type: &str) {
+    cell["cell_type"] = Value::String(cell_type.to_string());
+    let Some(fields) = cell.as_object_mut() else {
+        return;
+    };
+    if cell_type == "code" {
+        fields.entry("execution_count").or_insert(Value::Null);
+        fields.entry("outputs").or_insert_with(|| json!([]));
+    } else {
+        fields.remove("execution_count");
+        fields.remove("outputs");
+    }
+}
+
+// The cell as diffed: its type on the first line, then its source
+fn describe_cell(cell: &Value) -> String {
+    format!("[{}]\n{}", cell_type(cell), source(cell))
+}
+
+// One output as text: streams and plain-text results as they are, errors by name,
+// and rich outputs such as images by their type
+fn render_output(output: &Value) -> String {
+    let join = |value: &Value| match value {
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect::<String>(),
+        Value::String(text) => text.clone(),
+        _ => String::new(),
+    };
+    let text = match output["output_type"].as_str() {
+        Some("stream") => join(&output["text"]),
+        Some("error") => format!(
+            "{}: {}",
+            output["ename"].as_str().unwrap_or("Error"),
+            output["evalue"].as_str().unwrap_or_default()
+        ),
+        _ => {
+            let data = output["data"].as_object().cloned().unwrap_or_else(Map::new);
+            match data.get("text/plain") {
+                Some(text) => join(text),
+                None => data
+                    .keys()
+                    .map(|mime| format!("[{mime} output]"))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            }
+        }
+    };
+
+    if text.chars().count() > MAX_OUTPUT_CHARS {
+        let shown: String = text.chars().take(MAX_OUTPUT_CHARS).collect();
+        format!("{shown}\n... (output truncated)")
+    } else {
+        text
+    }
+}
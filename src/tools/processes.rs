@@ -0,0 +1,332 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Environment variable overriding the directory child processes are recorded in
+pub const PROCESS_DIR_ENV: &str = "OLI_PROCESS_DIR";
+/// Environment variable with the seconds a child may stay idle before it's killed,
+/// 0 to never kill idle children
+pub const IDLE_TIMEOUT_ENV: &str = "OLI_CHILD_IDLE_TIMEOUT";
+
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// How long a terminated child gets to exit before it's killed outright
+const TERMINATE_GRACE: Duration = Duration::from_secs(2);
+
+/// What a child process was started for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChildKind {
+    Lsp,
+    Bash,
+}
+
+/// A child process started by an oli instance, as recorded in the process directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChildProcess {
+    pub pid: u32,
+    pub kind: ChildKind,
+    pub command: String,
+    /// The oli instance that started it
+    pub owner_pid: u32,
+    /// Whether the child leads its own process group, which is killed with it
+    pub process_group: bool,
+    /// RFC 3339 timestamps
+    pub started_at: String,
+    pub last_active_at: String,
+    /// When the operating system started the process, to tell it apart from a
+    /// later process reusing its pid
+    #[serde(default)]
+    pub os_start_time: Option<u64>,
+}
+
+impl ChildProcess {
+    /// Whether the recorded process is still running
+    pub fn is_running(&self) -> bool {
+        process_alive(self.pid)
+            && (self.os_start_time.is_none() || self.os_start_time == process_start_time(self.pid))
+    }
+
+    /// Whether the instance that started it has exited
+    pub fn is_orphaned(&self) -> bool {
+        !process_alive(self.owner_pid)
+    }
+
+    /// Time since the child was started or last used
+    pub fn idle_for(&self) -> Duration {
+        chrono::DateTime::parse_from_rfc3339(&self.last_active_at)
+            .ok()
+            .and_then(|last| {
+                (chrono::Utc::now() - last.with_timezone(&chrono::Utc))
+                    .to_std()
+                    .ok()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Keeps a file per language server and Bash command in the process directory, so
+/// children left running by a crashed instance can be found and killed later
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessSupervisor {
+    dir: PathBuf,
+}
+
+impl ProcessSupervisor {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// The configured directory, `~/.oli/processes` unless overridden by `OLI_PROCESS_DIR`
+    pub fn from_env() -> Option<Self> {
+        match std::env::var(PROCESS_DIR_ENV) {
+            Ok(dir) if !dir.trim().is_empty() => Some(Self::new(PathBuf::from(dir))),
+            _ => dirs::home_dir().map(|home| Self::new(home.join(".oli").join("processes"))),
+        }
+    }
+
+    /// The idle timeout set by `OLI_CHILD_IDLE_TIMEOUT`, 30 minutes by default. None
+    /// when idle children are never killed.
+    pub fn idle_timeout_from_env() -> Option<Duration> {
+        match std::env::var(IDLE_TIMEOUT_ENV)
+            .ok()
+            .and_then(|secs| secs.trim().parse::<u64>().ok())
+        {
+            Some(0) => None,
+            Some(secs) => Some(Duration::from_secs(secs)),
+            None => Some(DEFAULT_IDLE_TIMEOUT),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Record a child started by this instance
+    pub fn track(
+        &self,
+        kind: ChildKind,
+        pid: u32,
+        command: &str,
+        process_group: bool,
+    ) -> Result<ChildProcess> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let child = ChildProcess {
+            pid,
+            kind,
+            command: command.to_string(),
+            owner_pid: std::process::id(),
+            process_group,
+            started_at: now.clone(),
+            last_active_at: now,
+            os_start_time: process_start_time(pid),
+        };
+        self.write(&child)?;
+        Ok(child)
+    }
+
+    /// Note that a child was just used, resetting its idle time
+    pub fn touch(&self, pid: u32) -> Result<()> {
+        let Some(mut child) = self.read(pid) else {
+            return Ok(());
+        };
+        child.last_active_at = chrono::Utc::now().to_rfc3339();
+        self.write(&child)
+    }
+
+    /// Forget a child, once it has exited
+    pub fn untrack(&self, pid: u32) {
+        let _ = std::fs::remove_file(self.record_path(pid));
+    }
+
+    /// Children that are still running, oldest first. Records of children that
+    /// exited are removed.
+    pub fn list(&self) -> Vec<ChildProcess> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+
+        let mut children: Vec<ChildProcess> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let contents = std::fs::read_to_string(entry.path()).ok()?;
+                serde_json::from_str::<ChildProcess>(&contents).ok()
+            })
+            .filter(|child| {
+                let running = child.is_running();
+                if !running {
+                    self.untrack(child.pid);
+                }
+                running
+            })
+            .collect();
+        children.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+        children
+    }
+
+    /// Kill the children of instances that have exited, returning them
+    pub fn reap_orphans(&self) -> Vec<ChildProcess> {
+        self.list()
+            .into_iter()
+            .filter(|child| child.is_orphaned())
+            .filter(|child| self.terminate(child).is_ok())
+            .collect()
+    }
+
+    /// Kill this instance's children that have been idle longer than `timeout`,
+    /// returning them
+    pub fn kill_idle(&self, timeout: Duration) -> Vec<ChildProcess> {
+        let own_pid = std::process::id();
+        self.list()
+            .into_iter()
+            .filter(|child| child.owner_pid == own_pid && child.idle_for() > timeout)
+            .filter(|child| self.terminate(child).is_ok())
+            .collect()
+    }
+
+    /// Kill a recorded child. Processes oli didn't start are never touched.
+    pub fn kill(&self, pid: u32) -> Result<ChildProcess> {
+        let child = self
+            .list()
+            .into_iter()
+            .find(|child| child.pid == pid)
+            .ok_or_else(|| anyhow::anyhow!("No running child process with pid {pid}"))?;
+        self.terminate(&child)?;
+        Ok(child)
+    }
+
+    /// Ask the child to exit, kill it if it hasn't after a grace period, and forget it
+    fn terminate(&self, child: &ChildProcess) -> Result<()> {
+        signal(child, "TERM")?;
+        let deadline = std::time::Instant::now() + TERMINATE_GRACE;
+        while child.is_running() && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        if child.is_running() {
+            signal(child, "KILL")?;
+        }
+        self.untrack(child.pid);
+        Ok(())
+    }
+
+    fn record_path(&self, pid: u32) -> PathBuf {
+        self.dir.join(format!("{pid}.json"))
+    }
+
+    fn read(&self, pid: u32) -> Option<ChildProcess> {
+        let contents = std::fs::read_to_string(self.record_path(pid)).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    fn write(&self, child: &ChildProcess) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let path = self.record_path(child.pid);
+        std::fs::write(&path, serde_json::to_string_pretty(child)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Removes a child's record when dropped, after the child has been waited for
+#[derive(Debug)]
+pub struct TrackedChild {
+    supervisor: Option<ProcessSupervisor>,
+    pid: u32,
+}
+
+impl TrackedChild {
+    /// Note that the child was just used
+    pub fn touch(&self) {
+        if let Some(supervisor) = &self.supervisor {
+            let _ = supervisor.touch(self.pid);
+        }
+    }
+}
+
+impl Drop for TrackedChild {
+    fn drop(&mut self) {
+        if let Some(supervisor) = &self.supervisor {
+            supervisor.untrack(self.pid);
+        }
+    }
+}
+
+/// Record a child in the configured process directory until the returned guard is
+/// dropped. Failing to record it never stops the child from running.
+pub fn track_child(kind: ChildKind, pid: u32, command: &str, process_group: bool) -> TrackedChild {
+    let supervisor = ProcessSupervisor::from_env()
+        .filter(|supervisor| supervisor.track(kind, pid, command, process_group).is_ok());
+    TrackedChild { supervisor, pid }
+}
+
+#[cfg(unix)]
+fn signal(child: &ChildProcess, signal: &str) -> Result<()> {
+    // A negative pid signals the whole process group
+    let target = if child.process_group {
+        format!("-{}", child.pid)
+    } else {
+        child.pid.to_string()
+    };
+    let status = std::process::Command::new("kill")
+        .args([&format!("-{signal}"), "--", &target])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .context("Failed to run kill")?;
+    if !status.success() && child.is_running() {
+        anyhow::bail!("Failed to signal process {}", child.pid);
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn signal(child: &ChildProcess, _signal: &str) -> Result<()> {
+    let status = std::process::Command::new("taskkill")
+        .args(["/PID", &child.pid.to_string(), "/T", "/F"])
+        .status()
+        .context("Failed to run taskkill")?;
+    if !status.success() {
+        anyhow::bail!("Failed to kill process {}", child.pid);
+    }
+    Ok(())
+}
+
+/// Start time of a process in clock ticks since boot, from `/proc/<pid>/stat`
+#[cfg(target_os = "linux")]
+fn process_start_time(pid: u32) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // Fields after the command name, which may contain spaces, start at the state,
+    // the third field; the start time is the 22nd
+    let (_, rest) = stat.rsplit_once(") ")?;
+    rest.split_whitespace().nth(19)?.parse().ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_start_time(_pid: u32) -> Option<u64> {
+    None
+}
+
+/// Whether a process is still running
+#[cfg(target_os = "linux")]
+pub fn process_alive(pid: u32) -> bool {
+    // Zombies have exited and are only waiting to be reaped
+    std::fs::read_to_string(format!("/proc/{pid}/stat")).is_ok_and(|stat| {
+        stat.rsplit_once(") ")
+            .is_none_or(|(_, rest)| !rest.starts_with('Z'))
+    })
+}
+
+/// Whether a process is still running
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn process_alive(pid: u32) -> bool {
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Whether a process is still running. Without a cheap liveness check, assume it is.
+#[cfg(not(unix))]
+pub fn process_alive(_pid: u32) -> bool {
+    true
+}
@@ -0,0 +1,313 @@
+use crate::tools::fs::file_ops::resolve_path;
+use crate::tools::shell::ShellConfig;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Environment variable overriding the file remote targets are kept in
+pub const REMOTE_TARGETS_FILE_ENV: &str = "OLI_REMOTE_TARGETS_FILE";
+
+/// Runtimes a container target may run `exec` with
+pub const CONTAINER_RUNTIMES: &[&str] = &["docker", "podman"];
+
+/// Machine tools run against instead of the local one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RemoteTarget {
+    /// A host reached with `ssh`
+    Ssh {
+        host: String,
+        #[serde(default)]
+        user: Option<String>,
+        #[serde(default)]
+        port: Option<u16>,
+        #[serde(default)]
+        identity_file: Option<PathBuf>,
+    },
+    /// A running container, reached with `docker exec` or a compatible runtime
+    Container {
+        name: String,
+        #[serde(default = "default_runtime")]
+        runtime: String,
+    },
+}
+
+fn default_runtime() -> String {
+    "docker".to_string()
+}
+
+/// A workspace's entry in the remote targets file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteConfig {
+    pub target: RemoteTarget,
+    /// Where the workspace lives on the target
+    pub remote_root: PathBuf,
+    /// Read and write files on the target instead of locally, for targets that
+    /// don't share the workspace (a bind-mounted dev container does)
+    #[serde(default)]
+    pub transfer_files: bool,
+    /// The workspace the entry is for, which maps to `remote_root`
+    #[serde(skip)]
+    pub local_root: PathBuf,
+}
+
+/// Where Edit, Write, Read, LS and Bash act: this machine, or the remote target
+/// configured for the workspace
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ExecutionBackend {
+    #[default]
+    Local,
+    Remote(RemoteConfig),
+}
+
+impl ExecutionBackend {
+    /// The backend configured for the workspace containing `path`, from the entry
+    /// of the nearest workspace in the remote targets file. Malformed entries are
+    /// ignored.
+    pub fn for_path(path: &Path) -> Self {
+        let Some(file) = targets_file() else {
+            return ExecutionBackend::Local;
+        };
+        let targets = Self::load_targets(&file).unwrap_or_default();
+        if targets.is_empty() {
+            return ExecutionBackend::Local;
+        }
+
+        resolve_path(path)
+            .ancestors()
+            .find_map(|dir| targets.get(dir))
+            .cloned()
+            .map(ExecutionBackend::Remote)
+            .unwrap_or_default()
+    }
+
+    /// The backend for the current working directory
+    pub fn current() -> Self {
+        Self::for_path(&std::env::current_dir().unwrap_or_default())
+    }
+
+    /// Load the remote targets in `file` by workspace. Entries that don't parse,
+    /// or name a target oli won't run, are left out.
+    pub fn load_targets(file: &Path) -> Result<BTreeMap<PathBuf, RemoteConfig>> {
+        let contents = match std::fs::read_to_string(file) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", file.display())),
+        };
+        let entries: BTreeMap<String, serde_json::Value> = serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid remote targets in {}", file.display()))?;
+
+        Ok(entries
+            .into_iter()
+            .filter_map(|(workspace, entry)| {
+                let mut config: RemoteConfig = serde_json::from_value(entry).ok()?;
+                config.target.validate().ok()?;
+                config.local_root = resolve_path(Path::new(&workspace));
+                Some((config.local_root.clone(), config))
+            })
+            .collect())
+    }
+
+    pub fn is_local(&self) -> bool {
+        matches!(self, ExecutionBackend::Local)
+    }
+
+    /// Whether file contents are read and written on the remote target
+    pub fn transfers_files(&self) -> bool {
+        matches!(self, ExecutionBackend::Remote(config) if config.transfer_files)
+    }
+
+    /// The path on the target for a local workspace path. Paths outside the
+    /// workspace are unchanged.
+    pub fn translate_path(&self, path: &Path) -> PathBuf {
+        match self {
+            ExecutionBackend::Local => path.to_path_buf(),
+            ExecutionBackend::Remote(config) => match path.strip_prefix(&config.local_root) {
+                Ok(relative) if relative.as_os_str().is_empty() => config.remote_root.clone(),
+                Ok(relative) => config.remote_root.join(relative),
+                Err(_) => path.to_path_buf(),
+            },
+        }
+    }
+
+    /// A command running `script` in `working_dir`: in the configured shell locally,
+    /// or on the target in the matching remote directory
+    pub fn command(&self, shell: &ShellConfig, working_dir: &Path, script: &str) -> Command {
+        let config = match self {
+            ExecutionBackend::Local => {
+                let mut command = shell.command(script);
+                if !working_dir.as_os_str().is_empty() {
+                    command.current_dir(working_dir);
+                }
+                return command;
+            }
+            ExecutionBackend::Remote(config) => config,
+        };
+
+        let remote_dir = self.translate_path(working_dir);
+        match &config.target {
+            RemoteTarget::Ssh {
+                host,
+                user,
+                port,
+                identity_file,
+            } => {
+                let mut command = Command::new("ssh");
+                command.args(["-o", "BatchMode=yes"]);
+                if let Some(port) = port {
+                    command.arg("-p").arg(port.to_string());
+                }
+                if let Some(identity_file) = identity_file {
+                    command.arg("-i").arg(identity_file);
+                }
+                let destination = match user {
+                    Some(user) => format!("{user}@{host}"),
+                    None => host.clone(),
+                };
+                command.arg("--").arg(destination).arg(format!(
+                    "cd {} && {script}",
+                    shell_quote(&remote_dir.to_string_lossy())
+                ));
+                command
+            }
+            RemoteTarget::Container { name, runtime } => {
+                let mut command = Command::new(runtime);
+                command
+                    .args(["exec", "-i", "-w"])
+                    .arg(&remote_dir)
+                    .arg(name)
+                    .args(["sh", "-c", script]);
+                command
+            }
+        }
+    }
+
+    /// Read a file from the target
+    pub fn read_file(&self, path: &Path) -> Result<String> {
+        let remote_path = shell_quote(&self.translate_path(path).to_string_lossy());
+        let output = self
+            .run_remote(&format!("cat -- {remote_path}"), None)
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+        String::from_utf8(output)
+            .with_context(|| format!("Failed to read file: {} is not UTF-8", path.display()))
+    }
+
+    /// Write a file on the target, creating its directory
+    pub fn write_file(&self, path: &Path, content: &str) -> Result<()> {
+        let remote_path = self.translate_path(path);
+        let parent = remote_path.parent().unwrap_or(Path::new("/"));
+        let script = format!(
+            "mkdir -p {} && cat > {}",
+            shell_quote(&parent.to_string_lossy()),
+            shell_quote(&remote_path.to_string_lossy())
+        );
+        self.run_remote(&script, Some(content.as_bytes()))
+            .with_context(|| format!("Failed to write to file: {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Whether a path exists on the target
+    pub fn exists(&self, path: &Path) -> bool {
+        let remote_path = shell_quote(&self.translate_path(path).to_string_lossy());
+        self.run_remote(&format!("test -e {remote_path}"), None)
+            .is_ok()
+    }
+
+    /// Entries of a directory on the target as `(name, is_dir)`, sorted by name
+    pub fn list_directory(&self, path: &Path) -> Result<Vec<(String, bool)>> {
+        let remote_path = shell_quote(&self.translate_path(path).to_string_lossy());
+        let output = self.run_remote(&format!("ls -1Ap -- {remote_path}"), None)?;
+
+        let mut entries: Vec<(String, bool)> = String::from_utf8_lossy(&output)
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| match line.strip_suffix('/') {
+                Some(dir) => (dir.to_string(), true),
+                None => (line.to_string(), false),
+            })
+            .collect();
+        entries.sort();
+        Ok(entries)
+    }
+
+    // Run a script on the target from the remote workspace root, returning stdout
+    fn run_remote(&self, script: &str, stdin: Option<&[u8]>) -> Result<Vec<u8>> {
+        let ExecutionBackend::Remote(config) = self else {
+            anyhow::bail!("No remote target is configured");
+        };
+
+        let mut child = self
+            .command(&ShellConfig::default(), &config.local_root, script)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to start remote command")?;
+
+        // Write stdin, then drop it so the remote side sees end of input
+        if let Some(mut child_stdin) = child.stdin.take() {
+            if let Some(input) = stdin {
+                child_stdin.write_all(input)?;
+            }
+        }
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Remote command failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(output.stdout)
+    }
+}
+
+impl RemoteTarget {
+    /// Check the target can't smuggle options into `ssh` or the container runtime,
+    /// and that a container target runs a known runtime
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            RemoteTarget::Ssh { host, user, .. } => {
+                if host.is_empty() || host.starts_with('-') {
+                    anyhow::bail!("Invalid SSH host: {host:?}");
+                }
+                if let Some(user) = user.as_ref().filter(|user| user.starts_with('-')) {
+                    anyhow::bail!("Invalid SSH user: {user:?}");
+                }
+            }
+            RemoteTarget::Container { name, runtime } => {
+                if name.is_empty() || name.starts_with('-') {
+                    anyhow::bail!("Invalid container name: {name:?}");
+                }
+                let program = Path::new(runtime)
+                    .file_name()
+                    .map(|name| name.to_string_lossy());
+                if !program.is_some_and(|program| CONTAINER_RUNTIMES.contains(&program.as_ref())) {
+                    anyhow::bail!(
+                        "Unsupported container runtime {runtime:?}; use {}",
+                        CONTAINER_RUNTIMES.join(" or ")
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The file remote targets are kept in: `~/.oli/remote_targets.json` unless
+/// overridden by `OLI_REMOTE_TARGETS_FILE`. It lives outside the workspace so a
+/// cloned repository can't choose where, or with what, its commands run.
+pub fn targets_file() -> Option<PathBuf> {
+    match std::env::var(REMOTE_TARGETS_FILE_ENV) {
+        Ok(path) if !path.trim().is_empty() => Some(PathBuf::from(path)),
+        _ => Some(dirs::home_dir()?.join(".oli").join("remote_targets.json")),
+    }
+}
+
+/// Quote a string as a single shell word
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
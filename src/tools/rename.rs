@@ -0,0 +1,497 @@
+use crate::tools::fs::oliignore::OLIIGNORE_FILE;
+use crate::tools::git_stage::Hunk;
+use crate::tools::lsp::{LspServerManager, LspServerType, Position};
+use anyhow::{Context, Result};
+use ignore::WalkBuilder;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Unchanged lines shown around each renamed line
+const CONTEXT_LINES: usize = 2;
+/// Files larger than this are skipped by the text fallback
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Where the edits of a rename came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenameSource {
+    /// The language server's rename, which understands scopes
+    Lsp,
+    /// Whole-word matches in every file, including comments and strings
+    Text,
+}
+
+/// The edits a rename makes to one file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileRename {
+    /// Path relative to the workspace root
+    pub path: String,
+    pub occurrences: usize,
+    /// The changed lines, in the shape the changeset view shows
+    pub hunks: Vec<Hunk>,
+    #[serde(skip)]
+    original: String,
+    #[serde(skip)]
+    updated: String,
+}
+
+/// A previewed rename, applied only once approved
+#[derive(Debug, Clone, Serialize)]
+pub struct RenamePlan {
+    pub old_name: String,
+    pub new_name: String,
+    pub source: RenameSource,
+    pub files: Vec<FileRename>,
+    #[serde(skip)]
+    root: PathBuf,
+}
+
+impl RenamePlan {
+    pub fn occurrences(&self) -> usize {
+        self.files.iter().map(|file| file.occurrences).sum()
+    }
+
+    /// Write every file or none of them. Fails without changes if any file was
+    /// edited since the preview.
+    pub fn apply(&self) -> Result<AppliedRename> {
+        let files: Vec<FileSnapshot> = self
+            .files
+            .iter()
+            .map(|file| FileSnapshot {
+                path: self.root.join(&file.path),
+                before: file.original.clone(),
+                after: file.updated.clone(),
+            })
+            .collect();
+        replace_all(&files, |file| (&file.before, &file.after))?;
+
+        Ok(AppliedRename {
+            old_name: self.old_name.clone(),
+            new_name: self.new_name.clone(),
+            files,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FileSnapshot {
+    path: PathBuf,
+    before: String,
+    after: String,
+}
+
+/// A rename that was applied, kept so it can be undone
+#[derive(Debug, Clone)]
+pub struct AppliedRename {
+    pub old_name: String,
+    pub new_name: String,
+    files: Vec<FileSnapshot>,
+}
+
+impl AppliedRename {
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Put every file back as it was before the rename. Fails without changes if
+    /// any file was edited since.
+    pub fn undo(&self) -> Result<()> {
+        replace_all(&self.files, |file| (&file.after, &file.before))
+    }
+}
+
+/// Renames a symbol across a workspace, with the language server where one runs
+/// for the symbol's language and whole-word matching otherwise
+#[derive(Debug, Clone)]
+pub struct SymbolRenamer {
+    root: PathBuf,
+    use_lsp: bool,
+}
+
+impl SymbolRenamer {
+    pub fn new(root: &Path) -> Self {
+        // Language servers report canonical paths
+        Self {
+            root: root.canonicalize().unwrap_or_else(|_| root.to_path_buf()),
+            use_lsp: true,
+        }
+    }
+
+    /// Rename with whole-word matching only
+    pub fn without_lsp(mut self) -> Self {
+        self.use_lsp = false;
+        self
+    }
+
+    /// Work out the edits for renaming `old_name` to `new_name` without writing them
+    pub fn plan(&self, old_name: &str, new_name: &str) -> Result<RenamePlan> {
+        validate_identifier(old_name)?;
+        validate_identifier(new_name)?;
+        if old_name == new_name {
+            anyhow::bail!("The new name is the same as the old one");
+        }
+
+        let lsp_files = if self.use_lsp {
+            self.lsp_edits(old_name, new_name).ok().flatten()
+        } else {
+            None
+        };
+        let (source, files) = match lsp_files {
+            Some(files) => (RenameSource::Lsp, files),
+            None => (RenameSource::Text, self.text_edits(old_name, new_name)?),
+        };
+
+        if files.is_empty() {
+            anyhow::bail!("No occurrences of {old_name} found");
+        }
+        Ok(RenamePlan {
+            old_name: old_name.to_string(),
+            new_name: new_name.to_string(),
+            source,
+            files,
+            root: self.root.clone(),
+        })
+    }
+
+    /// Ask the language server to rename the symbol at its definition. None when no
+    /// definition is found or the server made no edits.
+    fn lsp_edits(&self, old_name: &str, new_name: &str) -> Result<Option<Vec<FileRename>>> {
+        let Some((path, position, server_type)) = self.find_definition(old_name)? else {
+            return Ok(None);
+        };
+
+        let edit = LspServerManager::new().rename(
+            &path.to_string_lossy(),
+            &position,
+            new_name,
+            &server_type,
+        )?;
+
+        let mut files = Vec::new();
+        for (path, edits) in workspace_edit_files(&edit) {
+            let original = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let updated = apply_text_edits(&original, &edits);
+            if updated != original {
+                files.push(self.file_rename(&path, original, updated, edits.len()));
+            }
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok((!files.is_empty()).then_some(files))
+    }
+
+    /// First definition of `name` in a Rust or Python file, with the server for it
+    fn find_definition(&self, name: &str) -> Result<Option<(PathBuf, Position, LspServerType)>> {
+        let definition = Regex::new(&format!(
+            r"\b(?:fn|struct|enum|trait|type|const|static|mod|let|def|class)\s+({})\b",
+            regex::escape(name)
+        ))?;
+
+        for path in self.workspace_files() {
+            let server_type = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("rs") => LspServerType::Rust,
+                Some("py") => LspServerType::Python,
+                _ => continue,
+            };
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+
+            for (line, text) in content.lines().enumerate() {
+                if let Some(found) = definition.captures(text).and_then(|c| c.get(1)) {
+                    // LSP counts characters in UTF-16 code units
+                    let character = text[..found.start()].encode_utf16().count();
+                    let position = Position {
+                        line: line as u32,
+                        character: character as u32,
+                    };
+                    return Ok(Some((path, position, server_type)));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Replace whole-word matches of `old_name` in every file of the workspace
+    fn text_edits(&self, old_name: &str, new_name: &str) -> Result<Vec<FileRename>> {
+        let word = Regex::new(&format!(r"\b{}\b", regex::escape(old_name)))?;
+
+        let mut files = Vec::new();
+        for path in self.workspace_files() {
+            let Ok(original) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let occurrences = word.find_iter(&original).count();
+            if occurrences == 0 {
+                continue;
+            }
+            let updated = word.replace_all(&original, new_name).into_owned();
+            files.push(self.file_rename(&path, original, updated, occurrences));
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+
+    /// Files in the workspace, skipping those ignored by git or `.oliignore`
+    fn workspace_files(&self) -> Vec<PathBuf> {
+        WalkBuilder::new(&self.root)
+            .standard_filters(true)
+            .add_custom_ignore_filename(OLIIGNORE_FILE)
+            .build()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+            .filter(|entry| {
+                entry
+                    .metadata()
+                    .is_ok_and(|metadata| metadata.len() <= MAX_FILE_BYTES)
+            })
+            .map(|entry| entry.into_path())
+            .collect()
+    }
+
+    fn file_rename(
+        &self,
+        path: &Path,
+        original: String,
+        updated: String,
+        occurrences: usize,
+    ) -> FileRename {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        FileRename {
+            path: relative.to_string_lossy().replace('\\', "/"),
+            occurrences,
+            hunks: rename_hunks(&original, &updated),
+            original,
+            updated,
+        }
+    }
+}
+
+fn validate_identifier(name: &str) -> Result<()> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|first| first.is_alphabetic() || first == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_');
+    if !valid {
+        anyhow::bail!("{name:?} is not a valid identifier");
+    }
+    Ok(())
+}
+
+/// One edit from an LSP `WorkspaceEdit`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub start: (u32, u32),
+    pub end: (u32, u32),
+    pub new_text: String,
+}
+
+/// The edits in an LSP `WorkspaceEdit` grouped by file, from either its `changes`
+/// map or its `documentChanges` list. File creations and renames are skipped.
+pub fn workspace_edit_files(edit: &Value) -> BTreeMap<PathBuf, Vec<TextEdit>> {
+    let mut files: BTreeMap<PathBuf, Vec<TextEdit>> = BTreeMap::new();
+
+    if let Some(changes) = edit["changes"].as_object() {
+        for (uri, edits) in changes {
+            files
+                .entry(uri_to_path(uri))
+                .or_default()
+                .extend(parse_text_edits(edits));
+        }
+    }
+    if let Some(document_changes) = edit["documentChanges"].as_array() {
+        for change in document_changes {
+            if let Some(uri) = change["textDocument"]["uri"].as_str() {
+                files
+                    .entry(uri_to_path(uri))
+                    .or_default()
+                    .extend(parse_text_edits(&change["edits"]));
+            }
+        }
+    }
+    files
+}
+
+fn parse_text_edits(edits: &Value) -> Vec<TextEdit> {
+    let position = |value: &Value| {
+        (
+            value["line"].as_u64().unwrap_or(0) as u32,
+            value["character"].as_u64().unwrap_or(0) as u32,
+        )
+    };
+
+    edits
+        .as_array()
+        .map(|edits| {
+            edits
+                .iter()
+                .filter_map(|edit| {
+                    Some(TextEdit {
+                        start: position(&edit["range"]["start"]),
+                        end: position(&edit["range"]["end"]),
+                        new_text: edit["newText"].as_str()?.to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn uri_to_path(uri: &str) -> PathBuf {
+    PathBuf::from(
+        uri.strip_prefix("file://")
+            .unwrap_or(uri)
+            .replace("%20", " "),
+    )
+}
+
+/// Apply LSP text edits, whose positions count UTF-16 code units, to `content`
+pub fn apply_text_edits(content: &str, edits: &[TextEdit]) -> String {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let offset = |(line, character): (u32, u32)| {
+        let Some(&start) = line_starts.get(line as usize) else {
+            return content.len();
+        };
+        let mut units = 0;
+        for (i, c) in content[start..].char_indices() {
+            if units >= character as usize || c == '\n' {
+                return start + i;
+            }
+            units += c.len_utf16();
+        }
+        content.len()
+    };
+
+    // Apply from the end so earlier offsets stay valid
+    let mut edits: Vec<&TextEdit> = edits.iter().collect();
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.start));
+
+    let mut updated = content.to_string();
+    for edit in edits {
+        let (start, end) = (offset(edit.start), offset(edit.end));
+        if start <= end {
+            updated.replace_range(start..end, &edit.new_text);
+        }
+    }
+    updated
+}
+
+/// Hunks for the lines a rename changed, with a little context around each
+pub fn rename_hunks(original: &str, updated: &str) -> Vec<Hunk> {
+    let old_lines: Vec<&str> = original.lines().collect();
+    let new_lines: Vec<&str> = updated.lines().collect();
+    // Renames within lines keep the line count; anything else is shown whole
+    if old_lines.len() != new_lines.len() {
+        return vec![whole_file_hunk(&old_lines, &new_lines)];
+    }
+
+    let changed: Vec<usize> = (0..old_lines.len())
+        .filter(|&i| old_lines[i] != new_lines[i])
+        .collect();
+
+    let mut hunks = Vec::new();
+    let mut i = 0;
+    while i < changed.len() {
+        let start = changed[i].saturating_sub(CONTEXT_LINES);
+        let mut end = changed[i];
+        while i + 1 < changed.len() && changed[i + 1] <= end + 2 * CONTEXT_LINES + 1 {
+            i += 1;
+            end = changed[i];
+        }
+        let end = (end + CONTEXT_LINES + 1).min(old_lines.len());
+
+        let mut lines = Vec::new();
+        for line in start..end {
+            if old_lines[line] == new_lines[line] {
+                lines.push(format!(" {}", old_lines[line]));
+            } else {
+                lines.push(format!("-{}", old_lines[line]));
+                lines.push(format!("+{}", new_lines[line]));
+            }
+        }
+        let changed_lines = lines.iter().filter(|line| line.starts_with('-')).count();
+        hunks.push(Hunk {
+            id: hunks.len().to_string(),
+            header: format!(
+                "@@ -{},{} +{},{} @@",
+                start + 1,
+                end - start,
+                start + 1,
+                end - start
+            ),
+            lines,
+            added: changed_lines,
+            removed: changed_lines,
+        });
+        i += 1;
+    }
+    hunks
+}
+
+fn whole_file_hunk(old_lines: &[&str], new_lines: &[&str]) -> Hunk {
+    let mut lines: Vec<String> = old_lines.iter().map(|line| format!("-{line}")).collect();
+    lines.extend(new_lines.iter().map(|line| format!("+{line}")));
+    Hunk {
+        id: "0".to_string(),
+        header: format!("@@ -1,{} +1,{} @@", old_lines.len(), new_lines.len()),
+        lines,
+        added: new_lines.len(),
+        removed: old_lines.len(),
+    }
+}
+
+/// Replace the content of each file, all or nothing. `contents` gives the content
+/// a file must still have and the content to write.
+fn replace_all<'a>(
+    files: &'a [FileSnapshot],
+    contents: impl Fn(&'a FileSnapshot) -> (&'a String, &'a String),
+) -> Result<()> {
+    let changed: Vec<String> = files
+        .iter()
+        .filter(|file| fs::read_to_string(&file.path).ok().as_ref() != Some(contents(file).0))
+        .map(|file| file.path.display().to_string())
+        .collect();
+    if !changed.is_empty() {
+        anyhow::bail!(
+            "Changed since the rename was planned, so nothing was written: {}",
+            changed.join(", ")
+        );
+    }
+
+    for (written, file) in files.iter().enumerate() {
+        if let Err(e) = write_atomically(&file.path, contents(file).1) {
+            // Put back the files already written
+            for file in &files[..written] {
+                let _ = write_atomically(&file.path, contents(file).0);
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Write through a temporary file in the same directory, so a file is never left
+/// half written
+fn write_atomically(path: &Path, content: &str) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("{} is not a file", path.display()))?;
+    let temp = path.with_file_name(format!(".{}.oli-rename", file_name.to_string_lossy()));
+
+    fs::write(&temp, content).with_context(|| format!("Failed to write {}", temp.display()))?;
+    if let Ok(metadata) = fs::metadata(path) {
+        let _ = fs::set_permissions(&temp, metadata.permissions());
+    }
+    if let Err(e) = fs::rename(&temp, path) {
+        let _ = fs::remove_file(&temp);
+        return Err(e).with_context(|| format!("Failed to replace {}", path.display()));
+    }
+    Ok(())
+}
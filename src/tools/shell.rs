@@ -0,0 +1,215 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+/// Environment variable with the shell Bash commands run in, e.g. `bash` or `zsh`
+pub const SHELL_ENV: &str = "OLI_SHELL";
+/// Environment variable enabling login-shell mode (`-l`) when set to `1` or `true`
+pub const SHELL_LOGIN_ENV: &str = "OLI_SHELL_LOGIN";
+/// Environment variable with an rc file sourced before every command
+pub const SHELL_RC_ENV: &str = "OLI_SHELL_RC";
+/// Environment variable running Bash commands in one long-lived shell when set to
+/// `1` or `true`, so `cd`, exported variables and activated environments persist
+pub const SHELL_PERSISTENT_ENV: &str = "OLI_SHELL_PERSISTENT";
+
+/// Variables that decide which tools a command picks up
+const CAPTURED_VARIABLES: &[&str] = &[
+    "PATH",
+    "SHELL",
+    "VIRTUAL_ENV",
+    "CONDA_DEFAULT_ENV",
+    "CONDA_PREFIX",
+    "PYENV_VERSION",
+    "NVM_DIR",
+    "NVM_BIN",
+    "NODE_VERSION",
+    "GOPATH",
+    "CARGO_HOME",
+    "RUSTUP_TOOLCHAIN",
+    "JAVA_HOME",
+];
+
+/// How Bash tool commands are started
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShellConfig {
+    pub shell: String,
+    /// Run as a login shell, so profile files set up PATH, nvm and the like
+    pub login: bool,
+    /// File sourced before every command, or once when the shell is persistent
+    pub rc_file: Option<PathBuf>,
+    /// Run every command in the same long-lived shell process
+    #[serde(default)]
+    pub persistent: bool,
+}
+
+impl Default for ShellConfig {
+    fn default() -> Self {
+        Self {
+            shell: "sh".to_string(),
+            login: false,
+            rc_file: None,
+            persistent: false,
+        }
+    }
+}
+
+impl ShellConfig {
+    /// The shell configured by `OLI_SHELL`, `OLI_SHELL_LOGIN`, `OLI_SHELL_RC` and
+    /// `OLI_SHELL_PERSISTENT`
+    pub fn from_env() -> Self {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        let enabled = |name: &str| var(name).is_some_and(|value| value == "1" || value == "true");
+
+        Self {
+            shell: var(SHELL_ENV).unwrap_or_else(|| "sh".to_string()),
+            login: enabled(SHELL_LOGIN_ENV),
+            rc_file: var(SHELL_RC_ENV).map(|path| match path.strip_prefix("~/") {
+                Some(rest) => dirs::home_dir()
+                    .map(|home| home.join(rest))
+                    .unwrap_or_else(|| PathBuf::from(&path)),
+                None => PathBuf::from(path),
+            }),
+            persistent: enabled(SHELL_PERSISTENT_ENV),
+        }
+    }
+
+    pub fn with_shell(mut self, shell: impl Into<String>) -> Self {
+        self.shell = shell.into();
+        self
+    }
+
+    pub fn with_login(mut self, login: bool) -> Self {
+        self.login = login;
+        self
+    }
+
+    pub fn with_rc_file(mut self, rc_file: Option<PathBuf>) -> Self {
+        self.rc_file = rc_file;
+        self
+    }
+
+    pub fn with_persistent(mut self, persistent: bool) -> Self {
+        self.persistent = persistent;
+        self
+    }
+
+    /// A command running `script` in the configured shell
+    pub fn command(&self, script: &str) -> Command {
+        let script = match &self.rc_file {
+            Some(rc_file) => format!(". '{}'\n{script}", rc_file.display()),
+            None => script.to_string(),
+        };
+
+        let mut command = Command::new(&self.shell);
+        if self.login {
+            command.arg("-l");
+        }
+        command.arg("-c").arg(script);
+        command
+    }
+
+    /// A command starting the configured shell to read commands from its stdin, as
+    /// the persistent shell. The rc file is sourced by the session when it starts.
+    pub fn session_command(&self) -> Command {
+        let mut command = Command::new(&self.shell);
+        if self.login {
+            command.arg("-l");
+        }
+        command.arg("-s");
+        command
+    }
+}
+
+/// The environment Bash commands effectively run with, recorded with every
+/// audited Bash execution so it can be reproduced
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnvSnapshot {
+    pub shell: ShellConfig,
+    /// Values of the variables that decide which tools a command picks up
+    pub variables: BTreeMap<String, String>,
+}
+
+impl EnvSnapshot {
+    /// Run the configured shell once and record the environment it ends up with
+    pub fn capture(config: &ShellConfig) -> Result<Self> {
+        let output = config
+            .command("env")
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .with_context(|| format!("Failed to start {}", config.shell))?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "{} exited with {} while capturing the environment",
+                config.shell,
+                output.status
+            ));
+        }
+
+        let variables = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .filter(|(name, _)| CAPTURED_VARIABLES.contains(name))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+
+        Ok(Self {
+            shell: config.clone(),
+            variables,
+        })
+    }
+
+    /// Snapshot for the shell configured in the environment, captured once per process
+    pub fn current() -> Option<&'static EnvSnapshot> {
+        static SNAPSHOT: OnceLock<Option<EnvSnapshot>> = OnceLock::new();
+        SNAPSHOT
+            .get_or_init(|| Self::capture(&ShellConfig::from_env()).ok())
+            .as_ref()
+    }
+
+    pub fn path_entries(&self) -> Vec<&str> {
+        self.variables
+            .get("PATH")
+            .map(|path| path.split(':').filter(|entry| !entry.is_empty()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Human-readable description of the shell, active environments and PATH
+    pub fn summary(&self) -> String {
+        let mut shell = self.shell.shell.clone();
+        if self.shell.login {
+            shell.push_str(" (login)");
+        }
+        if let Some(rc_file) = &self.shell.rc_file {
+            shell.push_str(&format!(", sourcing {}", rc_file.display()));
+        }
+        if self.shell.persistent {
+            shell.push_str(", persistent");
+        }
+
+        let mut lines = vec![format!("Shell: {shell}")];
+        for (name, label) in [
+            ("VIRTUAL_ENV", "Python virtualenv"),
+            ("CONDA_DEFAULT_ENV", "Conda environment"),
+            ("NVM_BIN", "nvm Node"),
+            ("RUSTUP_TOOLCHAIN", "Rust toolchain"),
+        ] {
+            if let Some(value) = self.variables.get(name) {
+                lines.push(format!("{label}: {value}"));
+            }
+        }
+
+        lines.push("PATH:".to_string());
+        lines.extend(self.path_entries().iter().map(|entry| format!("  {entry}")));
+        lines.join("\n")
+    }
+}
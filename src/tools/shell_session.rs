@@ -0,0 +1,356 @@
+use crate::tools::processes::{track_child, ChildKind, TrackedChild};
+use crate::tools::shell::ShellConfig;
+use anyhow::{Context, Result};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How long a command in the persistent shell may run unless the call sets a timeout
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(120);
+/// Longest timeout a call may set
+pub const MAX_COMMAND_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// What a command printed and the status it exited with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+}
+
+impl From<std::process::Output> for CommandOutput {
+    fn from(output: std::process::Output) -> Self {
+        Self {
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            exit_code: output.status.code().unwrap_or(-1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// A long-lived shell that Bash commands run in one after another, so `cd`,
+/// exported variables and activated virtualenvs carry over between them. Each
+/// command is followed by a marker line on stdout and stderr, which tells where
+/// its output ends and, on stdout, its exit status and the shell's directory.
+pub struct ShellSession {
+    config: ShellConfig,
+    child: Child,
+    stdin: ChildStdin,
+    output: Receiver<(Stream, Vec<u8>)>,
+    // Unique to the session, so output can't pass for a marker by accident
+    nonce: String,
+    commands_run: u64,
+    tracked: TrackedChild,
+    // Where the shell was after the last command
+    current_dir: PathBuf,
+}
+
+impl ShellSession {
+    /// Start the configured shell in `working_dir`, sourcing its rc file once
+    pub fn start(config: &ShellConfig, working_dir: &Path) -> Result<Self> {
+        let mut command = config.session_command();
+        if !working_dir.as_os_str().is_empty() {
+            command.current_dir(working_dir);
+        }
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        // Its own process group, so a timed-out command dies with the shell
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+        let mut child = command
+            .spawn()
+            .with_context(|| format!("Failed to start {}", config.shell))?;
+        let tracked = track_child(
+            ChildKind::Bash,
+            child.id(),
+            &format!("{} (persistent shell)", config.shell),
+            cfg!(unix),
+        );
+
+        let (sender, output) = mpsc::channel();
+        let stdout = child.stdout.take().context("Shell stdout not captured")?;
+        let stderr = child.stderr.take().context("Shell stderr not captured")?;
+        forward_output(stdout, Stream::Stdout, sender.clone());
+        forward_output(stderr, Stream::Stderr, sender);
+        let stdin = child.stdin.take().context("Shell stdin not captured")?;
+
+        let mut session = Self {
+            config: config.clone(),
+            child,
+            stdin,
+            output,
+            nonce: uuid::Uuid::new_v4().simple().to_string(),
+            commands_run: 0,
+            tracked,
+            current_dir: if working_dir.as_os_str().is_empty() {
+                std::env::current_dir().unwrap_or_default()
+            } else {
+                working_dir.to_path_buf()
+            },
+        };
+        if let Some(rc_file) = &config.rc_file {
+            let sourced = session.run(
+                &format!(". {}", shell_quote(&rc_file.to_string_lossy())),
+                DEFAULT_COMMAND_TIMEOUT,
+            )?;
+            if !sourced.success() {
+                anyhow::bail!(
+                    "Failed to source {}: {}",
+                    rc_file.display(),
+                    sourced.stderr.trim()
+                );
+            }
+        }
+        Ok(session)
+    }
+
+    pub fn config(&self) -> &ShellConfig {
+        &self.config
+    }
+
+    /// The directory the shell was in when the last command finished
+    pub fn current_dir(&self) -> &Path {
+        &self.current_dir
+    }
+
+    /// Whether the shell is still running
+    pub fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    /// Run `script` in the shell and wait for it to finish. Its stdin is empty, so
+    /// commands that prompt don't read the next command instead. Fails when the
+    /// command runs past `timeout` or the shell exits, after which the session
+    /// can't be used again.
+    pub fn run(&mut self, script: &str, timeout: Duration) -> Result<CommandOutput> {
+        self.commands_run += 1;
+        self.tracked.touch();
+        let marker = format!("__OLI_DONE_{}_{}__", self.nonce, self.commands_run);
+
+        // `command eval` keeps a syntax error in the command from ending the shell
+        let framed = format!(
+            "command eval {} </dev/null\nprintf '\\n%s %s %s\\n' '{marker}' \"$?\" \"$PWD\"\nprintf '\\n%s\\n' '{marker}' >&2\n",
+            shell_quote(script)
+        );
+        self.stdin
+            .write_all(framed.as_bytes())
+            .and_then(|_| self.stdin.flush())
+            .context("The persistent shell has exited")?;
+
+        let deadline = Instant::now() + timeout;
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let mut exit_code = None;
+        let mut stderr_done = false;
+        // Where the markers may still start, so each chunk is searched once
+        let mut stdout_searched = 0;
+        let mut stderr_searched = 0;
+        while exit_code.is_none() || !stderr_done {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.output.recv_timeout(remaining) {
+                Ok((Stream::Stdout, bytes)) => stdout.extend_from_slice(&bytes),
+                Ok((Stream::Stderr, bytes)) => stderr.extend_from_slice(&bytes),
+                Err(RecvTimeoutError::Timeout) => {
+                    anyhow::bail!("Command timed out after {}s", timeout.as_secs_f64().round())
+                }
+                Err(RecvTimeoutError::Disconnected) => anyhow::bail!(
+                    "The persistent shell exited while running the command{}",
+                    partial_output(&stdout, &stderr)
+                ),
+            }
+
+            if exit_code.is_none() {
+                if let Some((code, dir)) =
+                    take_exit_code(&mut stdout, &marker, &mut stdout_searched)
+                {
+                    exit_code = Some(code);
+                    if !dir.is_empty() {
+                        self.current_dir = PathBuf::from(dir);
+                    }
+                }
+            }
+            if !stderr_done {
+                stderr_done =
+                    take_marker(&mut stderr, &format!("\n{marker}\n"), &mut stderr_searched);
+            }
+        }
+
+        Ok(CommandOutput {
+            stdout: String::from_utf8_lossy(&stdout).to_string(),
+            stderr: String::from_utf8_lossy(&stderr).to_string(),
+            exit_code: exit_code.unwrap_or(-1),
+        })
+    }
+}
+
+impl Drop for ShellSession {
+    fn drop(&mut self) {
+        // Kill commands the shell started along with it
+        #[cfg(unix)]
+        let _ = std::process::Command::new("kill")
+            .args(["-KILL", "--", &format!("-{}", self.child.id())])
+            .stderr(Stdio::null())
+            .status();
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The persistent shell of this oli process, started on first use
+fn session() -> &'static Mutex<Option<ShellSession>> {
+    static SESSION: OnceLock<Mutex<Option<ShellSession>>> = OnceLock::new();
+    SESSION.get_or_init(|| Mutex::new(None))
+}
+
+/// Run a Bash command in the persistent shell, starting it in `working_dir` if it
+/// isn't running or the shell configuration has changed. After a timeout or a
+/// crash the shell is stopped, and the next command starts a fresh one.
+pub fn run_in_session(
+    config: &ShellConfig,
+    working_dir: &Path,
+    script: &str,
+    timeout: Option<Duration>,
+) -> Result<CommandOutput> {
+    let timeout = timeout
+        .unwrap_or(DEFAULT_COMMAND_TIMEOUT)
+        .min(MAX_COMMAND_TIMEOUT);
+    let mut session = session().lock().unwrap_or_else(|e| e.into_inner());
+
+    let reusable = session
+        .as_mut()
+        .is_some_and(|running| running.is_alive() && running.config() == config);
+    if !reusable {
+        *session = Some(ShellSession::start(config, working_dir)?);
+    }
+
+    let result = session
+        .as_mut()
+        .expect("the session was just started")
+        .run(script, timeout);
+    if let Err(e) = result {
+        *session = None;
+        return Err(e.context(
+            "The persistent shell was restarted, so its working directory and variables were reset",
+        ));
+    }
+    result
+}
+
+/// The directory the persistent shell is in, when one with `config` is running
+pub fn session_dir(config: &ShellConfig) -> Option<PathBuf> {
+    let mut session = session().lock().unwrap_or_else(|e| e.into_inner());
+    let running = session.as_mut()?;
+    (running.config() == config && running.is_alive()).then(|| running.current_dir().to_path_buf())
+}
+
+/// Stop the persistent shell, if one is running, so the next command starts afresh
+pub fn reset_session() {
+    let mut session = session().lock().unwrap_or_else(|e| e.into_inner());
+    *session = None;
+}
+
+// Send everything read from a stream of the shell until it closes
+fn forward_output(
+    mut reader: impl Read + Send + 'static,
+    stream: Stream,
+    sender: mpsc::Sender<(Stream, Vec<u8>)>,
+) {
+    std::thread::spawn(move || {
+        let mut buffer = [0u8; 8192];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => {
+                    if sender.send((stream, buffer[..read].to_vec())).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+// Remove the marker and what follows it from the output, if it has arrived.
+// `searched` is where it may still start, so output is searched only once.
+fn take_marker(output: &mut Vec<u8>, marker: &str, searched: &mut usize) -> bool {
+    match find_from(output, marker.as_bytes(), searched) {
+        Some(position) => {
+            output.truncate(position);
+            true
+        }
+        None => false,
+    }
+}
+
+// Remove the stdout marker line, returning the exit status and the directory it
+// carries once the whole line has arrived
+fn take_exit_code(
+    stdout: &mut Vec<u8>,
+    marker: &str,
+    searched: &mut usize,
+) -> Option<(i32, String)> {
+    let start = find_from(stdout, format!("\n{marker} ").as_bytes(), searched)?;
+    let line = &stdout[start + 1..];
+    let end = line.iter().position(|&byte| byte == b'\n')?;
+    let fields = String::from_utf8_lossy(&line[marker.len() + 1..end]).to_string();
+    let (code, dir) = fields.split_once(' ').unwrap_or((&fields, ""));
+    let code = code.trim().parse().unwrap_or(-1);
+    let dir = dir.to_string();
+    stdout.truncate(start);
+    Some((code, dir))
+}
+
+// Find `needle` at or after `from`, then move `from` to where it may still start:
+// where it was found, or just before the end, where only part of it has arrived
+fn find_from(haystack: &[u8], needle: &[u8], from: &mut usize) -> Option<usize> {
+    let position = find(&haystack[*from..], needle).map(|position| *from + position);
+    *from = position.unwrap_or_else(|| {
+        haystack
+            .len()
+            .saturating_sub(needle.len().saturating_sub(1))
+            .max(*from)
+    });
+    position
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn partial_output(stdout: &[u8], stderr: &[u8]) -> String {
+    let output = format!(
+        "{}{}",
+        String::from_utf8_lossy(stdout),
+        String::from_utf8_lossy(stderr)
+    );
+    let output = output.trim();
+    if output.is_empty() {
+        String::new()
+    } else {
+        format!(". Output so far:\n{output}")
+    }
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
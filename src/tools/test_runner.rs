@@ -0,0 +1,420 @@
+use crate::tools::processes::{track_child, ChildKind};
+use crate::tools::remote::ExecutionBackend;
+use crate::tools::shell::ShellConfig;
+use crate::tools::shell_session::CommandOutput;
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Serialize;
+use std::io::Read;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+/// Seconds RunTests waits for the test suite unless asked for another limit
+pub const DEFAULT_TEST_TIMEOUT_SECS: u64 = 600;
+/// Longest RunTests waits for the test suite
+pub const MAX_TEST_TIMEOUT_SECS: u64 = 1_800;
+/// Failing tests listed by name; the rest are counted
+const MAX_FAILING_NAMES: usize = 50;
+/// Lines of failure output returned, so the agent sees why tests failed
+const MAX_FAILURE_LINES: usize = 200;
+/// Lines of one failing test's output returned
+const MAX_LINES_PER_FAILURE: usize = 40;
+
+/// A test framework RunTests knows how to run and read the results of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestFramework {
+    Cargo,
+    Pytest,
+    Npm,
+}
+
+impl TestFramework {
+    /// The framework of the project in `dir`, from the files at its root:
+    /// `Cargo.toml`, pytest or Python project config, or a `package.json` with a
+    /// test script
+    pub fn detect(dir: &Path) -> Option<Self> {
+        if dir.join("Cargo.toml").is_file() {
+            return Some(Self::Cargo);
+        }
+        let python = [
+            "pytest.ini",
+            "conftest.py",
+            "pyproject.toml",
+            "setup.cfg",
+            "tox.ini",
+        ];
+        if python.iter().any(|file| dir.join(file).is_file()) {
+            return Some(Self::Pytest);
+        }
+        let has_test_script = std::fs::read_to_string(dir.join("package.json"))
+            .ok()
+            .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+            .and_then(|package| package["scripts"]["test"].as_str().map(str::to_string))
+            // npm init's placeholder script only fails
+            .is_some_and(|script| !script.contains("no test specified"));
+        has_test_script.then_some(Self::Npm)
+    }
+
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "cargo" => Ok(Self::Cargo),
+            "pytest" => Ok(Self::Pytest),
+            "npm" => Ok(Self::Npm),
+            other => anyhow::bail!("Unknown test framework '{other}': use cargo, pytest or npm"),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo",
+            Self::Pytest => "pytest",
+            Self::Npm => "npm",
+        }
+    }
+
+    /// The command running the tests, only those matching `filter` if given
+    pub fn command(&self, filter: Option<&str>) -> String {
+        let filter = filter.map(str::trim).filter(|filter| !filter.is_empty());
+        match (self, filter) {
+            (Self::Cargo, None) => "cargo test".to_string(),
+            (Self::Cargo, Some(filter)) => format!("cargo test {}", shell_quote(filter)),
+            // -rfE lists every failure and error by name at the end
+            (Self::Pytest, None) => "python -m pytest -q -rfE".to_string(),
+            (Self::Pytest, Some(filter)) => {
+                format!("python -m pytest -q -rfE {}", shell_quote(filter))
+            }
+            (Self::Npm, None) => "npm test".to_string(),
+            (Self::Npm, Some(filter)) => format!("npm test -- {}", shell_quote(filter)),
+        }
+    }
+}
+
+/// What a test run found, parsed from the framework's output
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TestSummary {
+    pub framework: TestFramework,
+    pub command: String,
+    pub exit_code: i32,
+    pub passed: usize,
+    pub failed: usize,
+    pub ignored: usize,
+    /// Names of the failing tests, as the framework prints them
+    pub failing: Vec<String>,
+    /// Output explaining the failures, cut down to `MAX_FAILURE_LINES`
+    pub failure_output: String,
+    /// Whether test counts were found; otherwise the output's end is kept instead
+    pub counted: bool,
+}
+
+impl TestSummary {
+    /// Read the results of a run of `command` from its combined output
+    pub fn parse(framework: TestFramework, command: &str, output: &str, exit_code: i32) -> Self {
+        let mut summary = Self {
+            framework,
+            command: command.to_string(),
+            exit_code,
+            passed: 0,
+            failed: 0,
+            ignored: 0,
+            failing: Vec::new(),
+            failure_output: String::new(),
+            counted: false,
+        };
+        match framework {
+            TestFramework::Cargo => summary.parse_cargo(output),
+            TestFramework::Pytest => summary.parse_pytest(output),
+            TestFramework::Npm => summary.parse_npm(output),
+        }
+        summary.failing.dedup();
+
+        // Without counts, or with failures the parser couldn't explain, the end of
+        // the output is the best explanation, e.g. a compile error
+        if !summary.counted || (!summary.success() && summary.failure_output.is_empty()) {
+            summary.failure_output = last_lines(output, MAX_FAILURE_LINES);
+        }
+        summary
+    }
+
+    pub fn success(&self) -> bool {
+        self.exit_code == 0 && self.failed == 0
+    }
+
+    /// The summary returned to the agent
+    pub fn report(&self) -> String {
+        let status = if self.success() { "PASSED" } else { "FAILED" };
+        let mut report = format!(
+            "RunTests ({}): {}\nResult: {status}",
+            self.framework.name(),
+            self.command
+        );
+        if self.counted {
+            report.push_str(&format!(
+                ": {} passed, {} failed, {} ignored",
+                self.passed, self.failed, self.ignored
+            ));
+        } else {
+            report.push_str(": no test counts found in the output");
+        }
+        report.push_str(&format!(" (exit code {})\n", self.exit_code));
+
+        if !self.failing.is_empty() {
+            report.push_str("\nFailing tests:\n");
+            for name in self.failing.iter().take(MAX_FAILING_NAMES) {
+                report.push_str(&format!("- {name}\n"));
+            }
+            if self.failing.len() > MAX_FAILING_NAMES {
+                report.push_str(&format!(
+                    "- … and {} more\n",
+                    self.failing.len() - MAX_FAILING_NAMES
+                ));
+            }
+        }
+        if !self.failure_output.is_empty() {
+            report.push_str(&format!("\nOutput:\n{}\n", self.failure_output));
+        }
+        report
+    }
+
+    /// `test result:` lines, one per test binary, and `test x ... FAILED` lines,
+    /// with the `---- x stdout ----` sections holding each failure's panic
+    fn parse_cargo(&mut self, output: &str) {
+        let result = Regex::new(r"test result: \w+\. (\d+) passed; (\d+) failed; (\d+) ignored")
+            .expect("valid regex");
+        for captures in result.captures_iter(output) {
+            self.counted = true;
+            self.passed += captures[1].parse::<usize>().unwrap_or(0);
+            self.failed += captures[2].parse::<usize>().unwrap_or(0);
+            self.ignored += captures[3].parse::<usize>().unwrap_or(0);
+        }
+
+        let failed_line = Regex::new(r"^test (\S+) \.\.\. FAILED").expect("valid regex");
+        let section = Regex::new(r"^---- (\S+) stdout ----$").expect("valid regex");
+        let mut sections: Vec<String> = Vec::new();
+        let mut current: Option<Vec<&str>> = None;
+        for line in output.lines() {
+            if let Some(captures) = failed_line.captures(line) {
+                self.failing.push(captures[1].to_string());
+            }
+            if section.is_match(line) {
+                if let Some(lines) = current.take() {
+                    sections.push(cap_lines(&lines));
+                }
+                current = Some(vec![line]);
+            } else if line == "failures:" || line.starts_with("test result:") {
+                if let Some(lines) = current.take() {
+                    sections.push(cap_lines(&lines));
+                }
+            } else if let Some(lines) = current.as_mut() {
+                lines.push(line);
+            }
+        }
+        if let Some(lines) = current {
+            sections.push(cap_lines(&lines));
+        }
+        self.failure_output = first_lines(&sections.join("\n"), MAX_FAILURE_LINES);
+    }
+
+    /// The `== 2 failed, 10 passed in 0.5s ==` line and the `FAILED path::test`
+    /// lines of the short summary
+    fn parse_pytest(&mut self, output: &str) {
+        let counts = Regex::new(r"(\d+) (passed|failed|errors?|skipped|xfailed|xpassed)")
+            .expect("valid regex");
+        let totals = output
+            .lines()
+            .rev()
+            .find(|line| line.starts_with('=') && counts.is_match(line));
+        if let Some(line) = totals {
+            self.counted = true;
+            for captures in counts.captures_iter(line) {
+                let count = captures[1].parse::<usize>().unwrap_or(0);
+                match &captures[2] {
+                    "passed" | "xpassed" => self.passed += count,
+                    "failed" | "error" | "errors" => self.failed += count,
+                    _ => self.ignored += count,
+                }
+            }
+        }
+
+        let failed_line = Regex::new(r"^(?:FAILED|ERROR) (\S+)").expect("valid regex");
+        self.failing = output
+            .lines()
+            .filter_map(|line| failed_line.captures(line).map(|c| c[1].to_string()))
+            .collect();
+
+        // The tracebacks sit between the FAILURES banner and the short summary
+        let start = output
+            .find("= FAILURES =")
+            .or_else(|| output.find("= ERRORS ="));
+        if let Some(start) = start {
+            let details = &output[start..];
+            let end = details
+                .find("short test summary info")
+                .unwrap_or(details.len());
+            let details = details[..end].trim_end_matches(['=', ' ', '\n']);
+            self.failure_output = first_lines(details, MAX_FAILURE_LINES);
+        }
+    }
+
+    /// Jest's `Tests: 1 failed, 5 passed, 6 total`, Vitest's `Tests 1 failed | 5
+    /// passed (6)` or Mocha's `5 passing` and `1 failing`, with the `●` headings
+    /// Jest gives each failure
+    fn parse_npm(&mut self, output: &str) {
+        let counts = Regex::new(r"(\d+) (passed|passing|failed|failing|skipped|pending|todo)")
+            .expect("valid regex");
+        let mocha_counts = Regex::new(r"^\d+ (passing|failing|pending)").expect("valid regex");
+        let summary_lines: Vec<&str> = output
+            .lines()
+            .map(str::trim_start)
+            .filter(|line| {
+                line.starts_with("Tests:")
+                    || line.starts_with("Tests ")
+                    || mocha_counts.is_match(line)
+            })
+            .collect();
+        for line in summary_lines {
+            for captures in counts.captures_iter(line) {
+                self.counted = true;
+                let count = captures[1].parse::<usize>().unwrap_or(0);
+                match &captures[2] {
+                    "passed" | "passing" => self.passed += count,
+                    "failed" | "failing" => self.failed += count,
+                    _ => self.ignored += count,
+                }
+            }
+        }
+
+        let jest_failure = Regex::new(r"^\s*● (.+)$").expect("valid regex");
+        let mocha_failure = Regex::new(r"^\s*\d+\) (.+)$").expect("valid regex");
+        for line in output.lines() {
+            if let Some(captures) = jest_failure.captures(line) {
+                // "● Console" blocks are logs, not failures
+                if !captures[1].starts_with("Console") {
+                    self.failing.push(captures[1].trim().to_string());
+                }
+            } else if let Some(captures) = mocha_failure.captures(line) {
+                self.failing.push(captures[1].trim().to_string());
+            }
+        }
+    }
+}
+
+/// Run the tests of the project in `working_dir` with `framework`, in the
+/// configured shell or on the remote target, stopping them after `timeout`
+pub fn run_tests(
+    framework: TestFramework,
+    filter: Option<&str>,
+    working_dir: &Path,
+    timeout: Duration,
+) -> Result<TestSummary> {
+    let command_line = framework.command(filter);
+    let shell = ShellConfig::from_env();
+    let backend = ExecutionBackend::for_path(working_dir);
+    let mut command = backend.command(&shell, working_dir, &command_line);
+    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    // Its own process group, so stopping the run also stops the test binaries
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to run {command_line}"))?;
+    let _tracked = track_child(ChildKind::Bash, child.id(), &command_line, cfg!(unix));
+
+    // Read both streams while waiting, so a chatty suite can't fill a pipe and stall
+    let mut stdout = child.stdout.take().context("Test output not captured")?;
+    let mut stderr = child.stderr.take().context("Test output not captured")?;
+    let stdout_reader = std::thread::spawn(move || {
+        let mut text = String::new();
+        let _ = stdout.read_to_string(&mut text);
+        text
+    });
+    let stderr_reader = std::thread::spawn(move || {
+        let mut text = String::new();
+        let _ = stderr.read_to_string(&mut text);
+        text
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if Instant::now() >= deadline {
+            stop(&mut child);
+            break None;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    };
+
+    let output = CommandOutput {
+        stdout: stdout_reader.join().unwrap_or_default(),
+        stderr: stderr_reader.join().unwrap_or_default(),
+        exit_code: status.and_then(|status| status.code()).unwrap_or(-1),
+    };
+    if status.is_none() {
+        anyhow::bail!(
+            "{command_line} didn't finish within {} seconds and was stopped. Run fewer tests \
+             with a filter or raise the timeout.\n\nLast output:\n{}",
+            timeout.as_secs(),
+            last_lines(&format!("{}{}", output.stdout, output.stderr), 40)
+        );
+    }
+
+    // Cargo and pytest print results to stdout and progress or build errors to stderr
+    let combined = format!("{}\n{}", output.stdout, output.stderr);
+    Ok(TestSummary::parse(
+        framework,
+        &command_line,
+        &combined,
+        output.exit_code,
+    ))
+}
+
+/// Stop a test run and every process it started
+fn stop(child: &mut std::process::Child) {
+    #[cfg(unix)]
+    {
+        let _ = std::process::Command::new("kill")
+            .args(["-KILL", &format!("-{}", child.id())])
+            .status();
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+fn cap_lines(lines: &[&str]) -> String {
+    let kept = lines.len().min(MAX_LINES_PER_FAILURE);
+    cap(&lines[..kept], lines.len() - kept)
+}
+
+fn first_lines(text: &str, limit: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let kept = lines.len().min(limit);
+    cap(&lines[..kept], lines.len() - kept)
+}
+
+fn last_lines(text: &str, limit: usize) -> String {
+    let lines: Vec<&str> = text.trim_end().lines().collect();
+    let hidden = lines.len().saturating_sub(limit);
+    let kept = lines[hidden..].join("\n");
+    if hidden > 0 {
+        format!("… {hidden} earlier lines\n{kept}")
+    } else {
+        kept
+    }
+}
+
+fn cap(lines: &[&str], hidden: usize) -> String {
+    let kept = lines.join("\n").trim_end().to_string();
+    if hidden > 0 {
+        format!("{kept}\n… {hidden} more lines")
+    } else {
+        kept
+    }
+}
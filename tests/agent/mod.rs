@@ -1,4 +1,19 @@
 // Agent module tests
+pub mod test_approval;
+pub mod test_audit;
+pub mod test_budget;
+pub mod test_checkpoints;
+pub mod test_compare;
+pub mod test_conflicts;
 pub mod test_core;
+pub mod test_edit_review;
+pub mod test_evidence;
 pub mod test_executor;
+pub mod test_fanout;
+pub mod test_permissions;
+pub mod test_result_limits;
+pub mod test_tool_queue;
+pub mod test_tool_repair;
+pub mod test_tool_stats;
 pub mod test_tools;
+pub mod test_trail;
@@ -0,0 +1,114 @@
+//! Tests for the external approval backend
+
+use oli_server::agent::approval::{ask_client, ApprovalBackend, ApprovalDecision, ApprovalRequest};
+use oli_server::agent::permissions::RuleScope;
+use oli_server::communication::rpc::RpcServer;
+use serde_json::json;
+use std::time::Duration;
+
+fn request(tool: &str) -> ApprovalRequest {
+    ApprovalRequest {
+        tool: tool.to_string(),
+        arguments: json!({ "command": "cargo test" }),
+        working_directory: Some("/tmp".to_string()),
+        requested_at: 0,
+    }
+}
+
+fn command_backend(command: &str, timeout: Duration) -> ApprovalBackend {
+    ApprovalBackend::Command {
+        command: command.to_string(),
+        timeout,
+    }
+}
+
+#[test]
+fn test_decision_from_json() {
+    let decision = ApprovalDecision::from_json(&json!({ "allow": true })).unwrap();
+    assert!(decision.allow);
+
+    let decision =
+        ApprovalDecision::from_json(&json!({ "decision": "deny", "reason": "not on a Friday" }))
+            .unwrap();
+    assert!(!decision.allow);
+    assert_eq!(decision.reason.as_deref(), Some("not on a Friday"));
+
+    assert!(ApprovalDecision::from_json(&json!({ "status": "ok" })).is_err());
+
+    // An allow can ask for the tool or command to be allowed from now on
+    let decision =
+        ApprovalDecision::from_json(&json!({ "allow": true, "remember": "command" })).unwrap();
+    assert_eq!(decision.remember, Some(RuleScope::Command));
+    let decision =
+        ApprovalDecision::from_json(&json!({ "allow": false, "remember": "tool" })).unwrap();
+    assert_eq!(decision.remember, None);
+    assert!(ApprovalDecision::from_json(&json!({ "allow": true, "remember": "all" })).is_err());
+}
+
+#[test]
+fn test_requires_approval() {
+    assert!(ApprovalBackend::requires_approval("Bash"));
+    assert!(ApprovalBackend::requires_approval("Edit"));
+    assert!(!ApprovalBackend::requires_approval("Read"));
+}
+
+#[tokio::test]
+async fn test_command_backend_exit_status() {
+    // The request is passed on stdin
+    let backend = command_backend("grep -q '\"tool\":\"Bash\"'", Duration::from_secs(5));
+    assert!(backend.decide(&request("Bash")).await.allow);
+    assert!(!backend.decide(&request("Edit")).await.allow);
+}
+
+#[tokio::test]
+async fn test_command_backend_json_decision() {
+    let backend = command_backend(
+        "cat > /dev/null; echo '{\"decision\": \"deny\", \"reason\": \"needs review\"}'",
+        Duration::from_secs(5),
+    );
+    let decision = backend.decide(&request("Bash")).await;
+    assert!(!decision.allow);
+    assert_eq!(decision.reason.as_deref(), Some("needs review"));
+}
+
+#[tokio::test]
+async fn test_command_backend_that_ignores_stdin() {
+    // A large request, such as a Write of a big file, to a command that decides
+    // by its exit status without reading it
+    let mut large = request("Write");
+    large.arguments = json!({ "file_path": "big.txt", "content": "x".repeat(1 << 20) });
+
+    let allow = command_backend("exit 0", Duration::from_secs(5));
+    assert!(allow.decide(&large).await.allow);
+    assert!(allow.decide(&request("Bash")).await.allow);
+
+    let deny = command_backend("exit 1", Duration::from_secs(5));
+    let decision = deny.decide(&large).await;
+    assert!(!decision.allow);
+    assert!(decision.reason.unwrap().contains("exited with"));
+}
+
+#[tokio::test]
+async fn test_command_backend_timeout_denies() {
+    let backend = command_backend("sleep 5", Duration::from_millis(200));
+    let decision = backend.decide(&request("Bash")).await;
+    assert!(!decision.allow);
+    assert!(decision.reason.unwrap().contains("no approval decision"));
+}
+
+#[tokio::test]
+async fn test_unreachable_webhook_denies() {
+    let backend = ApprovalBackend::Webhook {
+        url: "http://127.0.0.1:9/approve".to_string(),
+        timeout: Duration::from_secs(5),
+    };
+    assert!(!backend.decide(&request("Write")).await.allow);
+}
+
+#[test]
+fn test_permission_prompt_without_a_client_denies() {
+    // A prompt nobody can answer denies the call rather than running it
+    let decision = ask_client(&RpcServer::new(), &request("Bash"), Some("toolu_01"));
+    assert!(!decision.allow);
+    assert!(decision.reason.unwrap().contains("No client is connected"));
+}
@@ -0,0 +1,72 @@
+//! Tests for the append-only audit log
+
+use oli_server::agent::audit::{AuditEntry, AuditLog};
+use serde_json::json;
+
+fn entry(tool: &str, decision: &str) -> AuditEntry {
+    AuditEntry {
+        timestamp: "2025-01-01T00:00:00+00:00".to_string(),
+        task_id: Some("task-1".to_string()),
+        tool: tool.to_string(),
+        arguments: json!({ "command": "ls" }),
+        diff_hash: None,
+        decision: decision.to_string(),
+        reason: None,
+        outcome: Some("success".to_string()),
+        environment: None,
+    }
+}
+
+#[test]
+fn test_record_appends_and_creates_directory() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let audit_log = AuditLog::new(temp_dir.path().join("nested").join("audit.jsonl"));
+
+    // Reading a log that doesn't exist yet returns nothing
+    assert!(audit_log.recent(10).unwrap().is_empty());
+
+    audit_log.record(&entry("Bash", "allowed")).unwrap();
+    audit_log.record(&entry("Edit", "denied")).unwrap();
+
+    let contents = std::fs::read_to_string(audit_log.path()).unwrap();
+    assert_eq!(contents.lines().count(), 2);
+
+    let entries = audit_log.recent(10).unwrap();
+    assert_eq!(
+        entries,
+        vec![entry("Bash", "allowed"), entry("Edit", "denied")]
+    );
+}
+
+#[test]
+fn test_recent_returns_latest_entries() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let audit_log = AuditLog::new(temp_dir.path().join("audit.jsonl"));
+
+    for tool in ["Write", "Edit", "Bash"] {
+        audit_log.record(&entry(tool, "allowed")).unwrap();
+    }
+    // Malformed lines are skipped rather than failing the read
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(audit_log.path())
+        .and_then(|mut file| std::io::Write::write_all(&mut file, b"not json\n"))
+        .unwrap();
+
+    let tools: Vec<String> = audit_log
+        .recent(2)
+        .unwrap()
+        .into_iter()
+        .map(|e| e.tool)
+        .collect();
+    assert_eq!(tools, vec!["Edit", "Bash"]);
+}
+
+#[test]
+fn test_is_audited() {
+    assert!(AuditLog::is_audited("Edit"));
+    assert!(AuditLog::is_audited("Write"));
+    assert!(AuditLog::is_audited("Bash"));
+    assert!(AuditLog::is_audited("RunTests"));
+    assert!(!AuditLog::is_audited("Read"));
+}
@@ -0,0 +1,48 @@
+//! Tests for the turn budget told to the model
+
+use oli_server::agent::budget::{TurnBudget, BUDGET_HEADING};
+use std::time::Duration;
+
+#[test]
+fn test_note_counts_rounds() {
+    let mut budget = TurnBudget::new(100, None);
+    budget.set_iterations_used(12);
+
+    let note = budget.note_at(Duration::from_secs(65));
+    assert!(note.starts_with(BUDGET_HEADING));
+    assert!(note.contains("Tool rounds: 12 of 100 used, about 88 left."));
+    assert!(note.contains("Time: 65s elapsed."));
+    assert!(note.contains("Prioritize"));
+    assert!(!note.contains("nearly spent"));
+}
+
+#[test]
+fn test_note_warns_when_rounds_run_low() {
+    let mut budget = TurnBudget::new(100, None);
+    budget.set_iterations_used(92);
+    assert_eq!(budget.remaining_iterations(), 8);
+    assert!(budget
+        .note_at(Duration::from_secs(1))
+        .contains("nearly spent"));
+
+    // More rounds than the limit never underflow
+    budget.set_iterations_used(120);
+    assert_eq!(budget.remaining_iterations(), 0);
+}
+
+#[test]
+fn test_note_includes_time_limit() {
+    let budget = TurnBudget::new(100, Some(Duration::from_secs(300)));
+
+    let note = budget.note_at(Duration::from_secs(100));
+    assert!(note.contains("Time: 100s elapsed, about 200s left of 300s."));
+    assert!(!note.contains("nearly spent"));
+
+    // The last fifth of the time limit counts as nearly spent
+    assert!(budget
+        .note_at(Duration::from_secs(250))
+        .contains("nearly spent"));
+    assert!(budget
+        .note_at(Duration::from_secs(400))
+        .contains("about 0s left"));
+}
@@ -0,0 +1,199 @@
+//! Tests for the checkpoints taken before edits and Bash, and undoing a turn with them
+
+use oli_server::agent::checkpoints::{Checkpoints, MAX_CHECKPOINTS};
+use serde_json::json;
+use std::path::Path;
+use std::process::Command;
+
+fn git(dir: &Path, args: &[&str]) {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args([
+            "-c",
+            "user.name=Test Author",
+            "-c",
+            "user.email=test@example.com",
+        ])
+        .args(args)
+        .output()
+        .expect("git should be installed");
+    assert!(output.status.success(), "git {args:?} failed");
+}
+
+#[test]
+fn test_undo_restores_edited_files_and_removes_written_ones() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    std::fs::write(dir.join("lib.rs"), "fn one() {}\n").unwrap();
+
+    let checkpoints = Checkpoints::new();
+    checkpoints.begin_turn("Add two");
+    checkpoints.record_call("Edit", &json!({ "file_path": "lib.rs" }), dir);
+    std::fs::write(dir.join("lib.rs"), "fn one() {}\nfn two() {}\n").unwrap();
+    // Later saves of the same file in the turn don't replace the first
+    checkpoints.record_call("Edit", &json!({ "file_path": "lib.rs" }), dir);
+    std::fs::write(dir.join("lib.rs"), "fn two() {}\n").unwrap();
+    checkpoints.record_call(
+        "WriteMany",
+        &json!({ "files": [{ "path": "new/two.rs", "content": "" }] }),
+        dir,
+    );
+    std::fs::create_dir(dir.join("new")).unwrap();
+    std::fs::write(dir.join("new/two.rs"), "fn two() {}\n").unwrap();
+    // Reading changes nothing, so isn't recorded
+    checkpoints.record_call("View", &json!({ "file_path": "other.rs" }), dir);
+    assert_eq!(checkpoints.len(), 1);
+
+    let report = checkpoints.undo_last().unwrap();
+    assert_eq!(report.label, "Add two");
+    assert_eq!(report.restored.len(), 1);
+    assert_eq!(report.removed.len(), 1);
+    assert_eq!(
+        std::fs::read_to_string(dir.join("lib.rs")).unwrap(),
+        "fn one() {}\n"
+    );
+    assert!(!dir.join("new/two.rs").exists());
+    assert!(report.summary().starts_with("Undid \"Add two\""));
+
+    assert!(checkpoints.is_empty());
+    assert!(checkpoints.undo_last().is_err());
+}
+
+#[test]
+fn test_undo_goes_back_one_turn_at_a_time() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    let file = dir.join("notes.txt");
+    std::fs::write(&file, "first\n").unwrap();
+    let write = json!({ "file_path": file.to_string_lossy() });
+
+    let checkpoints = Checkpoints::new();
+    checkpoints.begin_turn("Second");
+    checkpoints.record_call("Write", &write, dir);
+    std::fs::write(&file, "second\n").unwrap();
+    checkpoints.begin_turn("Third");
+    checkpoints.record_call("Write", &write, dir);
+    std::fs::write(&file, "third\n").unwrap();
+    // A turn that changed nothing is skipped
+    checkpoints.begin_turn("Just a question");
+    assert_eq!(checkpoints.len(), 2);
+
+    assert_eq!(checkpoints.undo_last().unwrap().label, "Third");
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "second\n");
+    assert_eq!(checkpoints.undo_last().unwrap().label, "Second");
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "first\n");
+
+    for turn in 0..MAX_CHECKPOINTS + 5 {
+        checkpoints.begin_turn(&format!("Turn {turn}"));
+        checkpoints.record_call("Write", &write, dir);
+    }
+    assert_eq!(checkpoints.len(), MAX_CHECKPOINTS);
+}
+
+#[test]
+fn test_undo_restores_files_bash_changed_in_a_git_repository() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    git(dir, &["init", "-q", "-b", "main"]);
+    std::fs::write(dir.join("lib.rs"), "fn one() {}\n").unwrap();
+    std::fs::write(dir.join("main.rs"), "fn main() {}\n").unwrap();
+    git(dir, &["add", "."]);
+    git(dir, &["commit", "-q", "-m", "Add lib and main"]);
+    // Uncommitted work and an untracked file from before the turn are kept
+    std::fs::write(dir.join("lib.rs"), "fn one() { work() }\n").unwrap();
+    std::fs::write(dir.join("scratch.txt"), "mine\n").unwrap();
+
+    let checkpoints = Checkpoints::new();
+    checkpoints.begin_turn("Clean up");
+    checkpoints.record_call("Bash", &json!({ "command": "..." }), dir);
+    std::fs::write(dir.join("lib.rs"), "").unwrap();
+    std::fs::remove_file(dir.join("main.rs")).unwrap();
+    std::fs::write(dir.join("generated.rs"), "fn gen() {}\n").unwrap();
+    checkpoints.complete_call("Bash");
+    // Taking the checkpoint didn't touch the working tree or the stash list
+    let stash = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["stash", "list"])
+        .output()
+        .unwrap();
+    assert!(stash.stdout.is_empty());
+
+    let report = checkpoints.undo_last().unwrap();
+    assert_eq!(report.warning, None);
+    assert_eq!(
+        std::fs::read_to_string(dir.join("lib.rs")).unwrap(),
+        "fn one() { work() }\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("main.rs")).unwrap(),
+        "fn main() {}\n"
+    );
+    assert!(!dir.join("generated.rs").exists());
+    assert!(dir.join("scratch.txt").exists());
+    assert_eq!(report.restored.len(), 2);
+    assert_eq!(report.removed.len(), 1);
+}
+
+#[test]
+fn test_undo_leaves_files_changed_after_the_turn() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    git(dir, &["init", "-q", "-b", "main"]);
+    std::fs::write(dir.join("lib.rs"), "fn one() {}\n").unwrap();
+    std::fs::write(dir.join("main.rs"), "fn main() {}\n").unwrap();
+    git(dir, &["add", "."]);
+    git(dir, &["commit", "-q", "-m", "Add lib and main"]);
+
+    let checkpoints = Checkpoints::new();
+    checkpoints.begin_turn("Format");
+    checkpoints.record_call("Bash", &json!({ "command": "cargo fmt" }), dir);
+    std::fs::write(dir.join("lib.rs"), "fn one() {  }\n").unwrap();
+    std::fs::write(dir.join("main.rs"), "fn main() {  }\n").unwrap();
+    checkpoints.complete_call("Bash");
+    // A second command changes nothing
+    checkpoints.record_call("Bash", &json!({ "command": "cargo check" }), dir);
+    checkpoints.complete_call("Bash");
+
+    // After the turn, the user edits a file the command changed, another one it
+    // didn't, and creates a file
+    std::fs::write(dir.join("main.rs"), "fn main() { run() }\n").unwrap();
+    std::fs::write(dir.join("notes.txt"), "todo\n").unwrap();
+    git(dir, &["add", "notes.txt"]);
+    std::fs::write(dir.join("untracked.txt"), "mine\n").unwrap();
+
+    let report = checkpoints.undo_last().unwrap();
+    assert_eq!(
+        std::fs::read_to_string(dir.join("lib.rs")).unwrap(),
+        "fn one() {}\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("main.rs")).unwrap(),
+        "fn main() { run() }\n"
+    );
+    assert!(dir.join("notes.txt").exists());
+    assert!(dir.join("untracked.txt").exists());
+    assert_eq!(report.restored.len(), 1);
+    assert!(report.removed.is_empty());
+    assert_eq!(report.kept.len(), 1);
+    assert!(report.kept[0].ends_with("main.rs"));
+    assert!(report
+        .warning
+        .as_deref()
+        .unwrap()
+        .contains("changed again after the turn"));
+    assert!(report.summary().contains("(changed after the turn)"));
+}
+
+#[test]
+fn test_bash_outside_git_is_reported_as_not_undone() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let checkpoints = Checkpoints::new();
+    checkpoints.begin_turn("Run it");
+    checkpoints.record_call("Bash", &json!({ "command": "make" }), temp_dir.path());
+
+    let report = checkpoints.undo_last().unwrap();
+    assert!(report.warning.is_some());
+    assert!(report.restored.is_empty());
+}
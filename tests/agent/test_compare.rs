@@ -0,0 +1,116 @@
+//! Tests for answering the same prompt with two models side by side
+
+use oli_server::agent::compare::ModelComparison;
+use oli_server::agent::core::{Agent, LLMProvider};
+use oli_server::apis::api_client::{
+    ApiClient, ApiClientEnum, CompletionOptions, Message, ToolCall, ToolResult,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Tries to write a file on its first call, then answers with its name. Tracks
+// how many models are answering at once.
+struct CompareApiClient {
+    name: &'static str,
+    write_path: String,
+    calls: AtomicUsize,
+    running: Arc<AtomicUsize>,
+    max_running: Arc<AtomicUsize>,
+}
+
+#[async_trait::async_trait]
+impl ApiClient for CompareApiClient {
+    async fn complete(
+        &self,
+        _messages: Vec<Message>,
+        _options: CompletionOptions,
+    ) -> anyhow::Result<String> {
+        Ok(self.name.to_string())
+    }
+
+    async fn complete_with_tools(
+        &self,
+        _messages: Vec<Message>,
+        _options: CompletionOptions,
+        _tool_results: Option<Vec<ToolResult>>,
+    ) -> anyhow::Result<(String, Option<Vec<ToolCall>>)> {
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_running.fetch_max(running, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        self.running.fetch_sub(1, Ordering::SeqCst);
+
+        if self.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+            let call = ToolCall {
+                id: Some(format!("call-{}", self.name)),
+                name: "Write".to_string(),
+                arguments: serde_json::json!({
+                    "file_path": self.write_path,
+                    "content": "changed",
+                }),
+            };
+            return Ok((String::new(), Some(vec![call])));
+        }
+        Ok((format!("Answer from {}", self.name), None))
+    }
+}
+
+fn agents(write_path: &str) -> ((String, Agent), (String, Agent), Arc<AtomicUsize>) {
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let agent = |name: &'static str| {
+        let client = Arc::new(CompareApiClient {
+            name,
+            write_path: write_path.to_string(),
+            calls: AtomicUsize::new(0),
+            running: running.clone(),
+            max_running: max_running.clone(),
+        });
+        let mut agent = Agent::new(LLMProvider::Anthropic)
+            .with_api_client(ApiClientEnum::custom_for_testing(client));
+        agent.add_message(Message::user("Earlier question".to_string()));
+        (name.to_string(), agent)
+    };
+    (agent("model-a"), agent("model-b"), max_running)
+}
+
+#[tokio::test]
+async fn test_compare_runs_both_models_read_only() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("main.rs");
+    let (a, b, max_running) = agents(&path.to_string_lossy());
+
+    let comparison = ModelComparison::run("Explain main.rs", a, b).await;
+
+    assert_eq!(comparison.answers.len(), 2);
+    assert_eq!(comparison.answers[0].model, "model-a");
+    assert_eq!(
+        comparison.answers[1].response.as_deref(),
+        Some("Answer from model-b")
+    );
+    for answer in &comparison.answers {
+        assert!(answer.error.is_none());
+        assert!(answer.latency_ms >= 60);
+        assert!(answer.input_tokens > 0);
+        assert!(answer.output_tokens > 0);
+    }
+
+    // Both models answer at the same time and neither can write files
+    assert_eq!(max_running.load(Ordering::SeqCst), 2);
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+async fn test_comparison_markdown() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let (a, b, _) = agents(&temp_dir.path().join("main.rs").to_string_lossy());
+
+    let markdown = ModelComparison::run("Explain main.rs", a, b)
+        .await
+        .to_markdown();
+
+    assert!(markdown.starts_with("| | model-a | model-b |\n|---|---|---|\n"));
+    assert!(markdown.contains("| Latency | "));
+    assert!(markdown.contains("| Output tokens | ~"));
+    assert!(markdown.contains("### model-a\n\nAnswer from model-a\n"));
+}
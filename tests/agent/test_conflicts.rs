@@ -0,0 +1,149 @@
+//! Tests for holding back edits to files changed since the agent read them
+
+use oli_server::agent::conflicts::ReadSnapshots;
+use oli_server::agent::tools::{
+    BashParams, EditParams, FileContent, ReadParams, ToolCall, WriteManyParams, WriteParams,
+};
+use std::path::Path;
+
+fn read(path: &Path) -> ToolCall {
+    ToolCall::Read(ReadParams {
+        file_path: path.to_string_lossy().to_string(),
+        offset: 0,
+        limit: 100,
+    })
+}
+
+fn edit(path: &Path, old: &str, new: &str) -> ToolCall {
+    ToolCall::Edit(EditParams {
+        file_path: path.to_string_lossy().to_string(),
+        old_string: old.to_string(),
+        new_string: new.to_string(),
+        expected_replacements: None,
+    })
+}
+
+#[test]
+fn test_edit_after_a_change_on_disk_conflicts() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("lib.rs");
+    std::fs::write(&path, "fn parse_args() {}\nfn main() {}\n").unwrap();
+
+    let mut snapshots = ReadSnapshots::default();
+    snapshots.record_call(&read(&path));
+    let call = edit(&path, "parse_args", "parse_flags");
+    assert!(snapshots.conflict_for(&call).is_none());
+
+    // The user changes the file after the agent read it
+    std::fs::write(&path, "fn parse_args() {}\nfn main() { run() }\n").unwrap();
+    let conflict = snapshots.conflict_for(&call).unwrap();
+    assert_eq!(conflict.original, "fn parse_args() {}\nfn main() {}\n");
+    assert_eq!(
+        conflict.current,
+        "fn parse_args() {}\nfn main() { run() }\n"
+    );
+    // The agent's change is shown against the file it read
+    assert_eq!(conflict.proposed, "fn parse_flags() {}\nfn main() {}\n");
+
+    let preview = conflict.preview();
+    assert!(preview.contains("changed on disk after the agent read it"));
+    let (mine, agents) = preview.split_once("Agent change:").unwrap();
+    assert!(mine.contains("+ fn main() { run() }"));
+    assert!(agents.contains("- fn parse_args() {}\n+ fn parse_flags() {}"));
+
+    let message = conflict.held_message("Edit");
+    assert!(message.starts_with("ERROR EXECUTING TOOL: Edit was not applied"));
+    assert!(message.contains("Read the file again"));
+
+    // Reading it again takes the change into account
+    snapshots.record_call(&read(&path));
+    assert!(snapshots.conflict_for(&call).is_none());
+}
+
+#[test]
+fn test_the_agents_own_changes_dont_conflict() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("notes.txt");
+    std::fs::write(&path, "one\n").unwrap();
+
+    let mut snapshots = ReadSnapshots::default();
+    snapshots.record_call(&read(&path));
+
+    // A successful write is recorded as what the agent last saw
+    let write = ToolCall::Write(WriteParams {
+        file_path: path.to_string_lossy().to_string(),
+        content: "two\n".to_string(),
+        create_dirs: None,
+        mode: None,
+    });
+    std::fs::write(&path, "two\n").unwrap();
+    snapshots.record_call(&write);
+    assert!(snapshots
+        .conflict_for(&edit(&path, "two", "three"))
+        .is_none());
+
+    // So is whatever its own Bash commands did
+    std::fs::write(&path, "formatted\n").unwrap();
+    snapshots.record_call(&ToolCall::Bash(BashParams {
+        command: "fmt notes.txt".to_string(),
+        timeout: None,
+        description: None,
+    }));
+    assert!(snapshots.conflict_for(&write).is_none());
+}
+
+#[test]
+fn test_unread_and_deleted_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let read_path = dir.path().join("a.txt");
+    let unread_path = dir.path().join("b.txt");
+    std::fs::write(&read_path, "a\n").unwrap();
+    std::fs::write(&unread_path, "b\n").unwrap();
+
+    let mut snapshots = ReadSnapshots::default();
+    snapshots.record_call(&read(&read_path));
+
+    // Files the agent never read aren't checked
+    std::fs::write(&unread_path, "changed\n").unwrap();
+    let many = |files: &[&Path]| {
+        ToolCall::WriteMany(WriteManyParams {
+            files: files
+                .iter()
+                .map(|path| FileContent {
+                    path: path.to_string_lossy().to_string(),
+                    content: "new\n".to_string(),
+                })
+                .collect(),
+        })
+    };
+    assert!(snapshots.conflict_for(&many(&[&unread_path])).is_none());
+
+    // Deleting a file the agent read is a change too
+    std::fs::remove_file(&read_path).unwrap();
+    let conflict = snapshots
+        .conflict_for(&many(&[&unread_path, &read_path]))
+        .unwrap();
+    assert_eq!(conflict.path, read_path);
+    assert_eq!(conflict.current, "");
+}
+
+#[test]
+fn test_an_append_proposes_the_file_it_would_leave() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("notes.log");
+    std::fs::write(&path, "one\n").unwrap();
+
+    let mut snapshots = ReadSnapshots::default();
+    snapshots.record_call(&read(&path));
+    std::fs::write(&path, "one\nedited\n").unwrap();
+
+    let append: ToolCall = serde_json::from_value(serde_json::json!({
+        "tool": "Write",
+        "params": { "file_path": path.to_string_lossy(), "content": "two\n", "mode": "append" }
+    }))
+    .unwrap();
+    let conflict = snapshots
+        .conflict_for(&append)
+        .expect("the file changed on disk");
+    assert_eq!(conflict.proposed, "one\ntwo\n");
+}
@@ -0,0 +1,66 @@
+//! Tests for reviewing proposed edits hunk by hunk
+
+use oli_server::agent::edit_review::{EditReview, ReviewDecision};
+use oli_server::agent::tools::{EditParams, ToolCall};
+
+const ORIGINAL: &str =
+    "use std::io;\n\nfn parse() {}\n\nfn a() {}\nfn b() {}\nfn c() {}\nfn d() {}\n\nfn main() {}\n";
+const PROPOSED: &str = "use std::fs;\n\nfn parse() {}\n\nfn a() {}\nfn b() {}\nfn c() {}\nfn d() {}\n\nfn main() { run() }\n";
+
+#[test]
+fn test_edit_is_split_into_hunks_with_context() {
+    let review = EditReview::new("src/main.rs", ORIGINAL, PROPOSED);
+
+    assert_eq!(review.hunks.len(), 2);
+    let first = &review.hunks[0];
+    assert_eq!(first.id, "0");
+    assert_eq!(first.header, "@@ -1,4 +1,4 @@");
+    assert_eq!(
+        first.lines,
+        vec!["-use std::io;", "+use std::fs;", " ", " fn parse() {}", " "]
+    );
+    assert_eq!((first.added, first.removed), (1, 1));
+    assert_eq!(review.hunks[1].header, "@@ -7,4 +7,4 @@");
+}
+
+#[test]
+fn test_decisions_apply_only_accepted_hunks() {
+    let review = EditReview::new("src/main.rs", ORIGINAL, PROPOSED);
+
+    assert_eq!(
+        review.decide(&["0".to_string(), "1".to_string()]),
+        ReviewDecision::AcceptAll
+    );
+    assert_eq!(review.decide(&[]), ReviewDecision::RejectAll);
+    assert_eq!(
+        review.decide(&["1".to_string()]),
+        ReviewDecision::Partial {
+            content: ORIGINAL.replace("fn main() {}", "fn main() { run() }"),
+            rejected: vec!["0".to_string()],
+        }
+    );
+    assert!(review
+        .partial_note(&["0".to_string()])
+        .starts_with("The user rejected hunk 1 of 2, so src/main.rs"));
+}
+
+#[test]
+fn test_review_of_an_edit_call_reads_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("main.rs");
+    std::fs::write(&path, ORIGINAL).unwrap();
+
+    let call = ToolCall::Edit(EditParams {
+        file_path: path.to_string_lossy().to_string(),
+        old_string: "fn main() {}".to_string(),
+        new_string: "fn main() { run() }".to_string(),
+        expected_replacements: None,
+    });
+    let review = EditReview::for_call(&call).unwrap();
+    assert_eq!(review.hunks.len(), 1);
+    assert_eq!(review.apply(&[]), ORIGINAL);
+    assert_eq!(
+        review.apply(&["0".to_string()]),
+        ORIGINAL.replace("fn main() {}", "fn main() { run() }")
+    );
+}
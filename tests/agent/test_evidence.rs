@@ -0,0 +1,76 @@
+//! Tests for numbering tool results and resolving citations to them
+
+use oli_server::agent::evidence::{
+    citation_tag, parse_citations, unresolved_citations, Evidence, EVIDENCE_EXCERPT_CHARS,
+};
+use oli_server::prompts::DEFAULT_AGENT_PROMPT;
+use serde_json::json;
+
+#[test]
+fn test_parse_citations_in_order_without_duplicates() {
+    let text = "`parse_args` is unused [#3], see also [#1] and [#3].";
+    assert_eq!(parse_citations(text), vec![3, 1]);
+}
+
+#[test]
+fn test_parse_citations_ignores_malformed_tags() {
+    let text = "Not citations: [#], [#x], [#12 and #4]. Real one: [#7]";
+    assert_eq!(parse_citations(text), vec![7]);
+    assert!(parse_citations("no tags here").is_empty());
+}
+
+#[test]
+fn test_evidence_target_from_arguments() {
+    let read = Evidence::new(
+        1,
+        "Read",
+        "call_1",
+        &json!({"file_path": "/src/lib.rs"}),
+        "ok",
+    );
+    assert_eq!(read.target.as_deref(), Some("/src/lib.rs"));
+
+    let grep = Evidence::new(2, "Grep", "call_2", &json!({"pattern": "parse_args"}), "ok");
+    assert_eq!(grep.target.as_deref(), Some("parse_args"));
+
+    let files = json!({"files": [{"path": "/src/a.rs", "content": ""}, {"path": "/src/b.rs", "content": ""}]});
+    let write_many = Evidence::new(4, "WriteMany", "call_4", &files, "ok");
+    assert_eq!(write_many.target.as_deref(), Some("/src/a.rs, /src/b.rs"));
+
+    let none = Evidence::new(3, "Custom", "call_3", &json!({}), "ok");
+    assert!(none.target.is_none());
+    assert_eq!(none.tag(), "[#3]");
+    assert_eq!(citation_tag(12), "[#12]");
+}
+
+#[test]
+fn test_evidence_excerpt_is_truncated() {
+    let output = "x".repeat(EVIDENCE_EXCERPT_CHARS + 10);
+    let entry = Evidence::new(1, "Bash", "call_1", &json!({"command": "ls"}), &output);
+    assert!(entry.truncated);
+    assert_eq!(entry.excerpt.chars().count(), EVIDENCE_EXCERPT_CHARS);
+
+    let short = Evidence::new(2, "Bash", "call_2", &json!({"command": "ls"}), "a\nb");
+    assert!(!short.truncated);
+    assert_eq!(short.excerpt, "a\nb");
+
+    // The truncated flag is only serialized when set
+    let serialized = serde_json::to_value(&short).unwrap();
+    assert!(serialized.get("truncated").is_none());
+    assert_eq!(serialized["target"], "ls");
+}
+
+#[test]
+fn test_unresolved_citations() {
+    let evidence = vec![Evidence::new(1, "Read", "call_1", &json!({}), "ok")];
+    assert_eq!(
+        unresolved_citations("Used in main [#1], unused in lib [#4]", &evidence),
+        vec![4]
+    );
+}
+
+#[test]
+fn test_agent_prompt_asks_for_citations() {
+    assert!(DEFAULT_AGENT_PROMPT.contains("## CITING EVIDENCE"));
+    assert!(DEFAULT_AGENT_PROMPT.contains("[#3]"));
+}
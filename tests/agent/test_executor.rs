@@ -1,12 +1,15 @@
 //! Unit tests for the Agent executor module
 
+use oli_server::agent::approval::ApprovalBackend;
 use oli_server::agent::executor::{
     determine_completion_threshold, process_response, should_request_completion, AgentExecutor,
 };
+use oli_server::agent::result_limits::ResultLimits;
 // Necessary for tests
 use anyhow::Result;
 use oli_server::apis::api_client::{
-    ApiClient, CompletionOptions, DynApiClient, Message, ToolCall as ApiToolCall, ToolResult,
+    ApiClient, CompletionOptions, DynApiClient, Message, ToolCall as ApiToolCall, ToolChoice,
+    ToolResult,
 };
 use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
@@ -331,6 +334,31 @@ mod execution_tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_delta_sender_streams_each_round() {
+        let (api_client, mock) = create_mock_api_client();
+        let tool_call = ApiToolCall {
+            id: Some("tool_1".to_string()),
+            name: "LS".to_string(),
+            arguments: serde_json::json!({ "path": "/some/path" }),
+        };
+        mock.add_response("Let me look.", Some(vec![tool_call]));
+        mock.add_response("The directory is empty.", None);
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let mut executor = AgentExecutor::new(api_client).with_delta_sender(sender);
+        executor.add_user_message("List files in /some/path".to_string());
+        executor.execute().await.expect("Execution failed");
+        drop(executor);
+
+        // Later rounds start on a new paragraph; the final summary isn't streamed
+        let mut deltas = Vec::new();
+        while let Some(delta) = receiver.recv().await {
+            deltas.push(delta);
+        }
+        assert_eq!(deltas, vec!["Let me look.", "\n\nThe directory is empty."]);
+    }
+
     #[tokio::test]
     async fn test_execute_multiple_tool_calls() {
         // Create a mock API client and get both the client and the underlying mock
@@ -790,6 +818,604 @@ mod execution_tests {
             "Expected either a diff preview message or at least 2 API calls"
         );
     }
+
+    #[tokio::test]
+    async fn test_repeated_tool_results_are_deduplicated() {
+        let (api_client, mock) = create_mock_api_client();
+
+        // A file large enough for its contents to be worth deduplicating
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("notes.txt");
+        std::fs::write(&file_path, "a line of notes that repeats\n".repeat(10)).unwrap();
+
+        let read_call = |id: &str| ApiToolCall {
+            id: Some(id.to_string()),
+            name: "Read".to_string(),
+            arguments: serde_json::json!({
+                "file_path": file_path.to_string_lossy(),
+                "offset": 0,
+                "limit": 100
+            }),
+        };
+
+        // The model reads the same file twice
+        mock.add_response("Reading the file", Some(vec![read_call("read_1")]));
+        mock.add_response("Reading it again", Some(vec![read_call("read_2")]));
+        mock.add_response("Done", None);
+
+        let mut executor = AgentExecutor::new(api_client);
+        executor.add_user_message("Read notes.txt twice".to_string());
+        executor.execute().await.expect("Execution failed");
+
+        let history = executor.get_conversation_history();
+        let first = history
+            .iter()
+            .find(|m| m.content.starts_with("Tool result for call read_1:"))
+            .expect("first result in history");
+        let second = history
+            .iter()
+            .find(|m| m.content.starts_with("Tool result for call read_2:"))
+            .expect("second result in history");
+
+        assert!(first.content.contains("a line of notes that repeats"));
+        assert!(
+            second.content.contains("Same as result for call read_1"),
+            "Repeated result should reference the first one"
+        );
+        assert!(!second.content.contains("a line of notes that repeats"));
+    }
+
+    #[tokio::test]
+    async fn test_restored_tool_results_are_deduplicated() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("notes.txt");
+        std::fs::write(&file_path, "a line of notes that repeats\n".repeat(10)).unwrap();
+
+        let read_call = |id: &str| ApiToolCall {
+            id: Some(id.to_string()),
+            name: "Read".to_string(),
+            arguments: serde_json::json!({
+                "file_path": file_path.to_string_lossy(),
+                "offset": 0,
+                "limit": 100
+            }),
+        };
+
+        // The first session reads the file, and its numbered result is saved
+        let (api_client, mock) = create_mock_api_client();
+        mock.add_response("Reading the file", Some(vec![read_call("read_1")]));
+        mock.add_response("Done", None);
+        let mut executor = AgentExecutor::new(api_client);
+        executor.add_user_message("Read notes.txt".to_string());
+        executor.execute().await.expect("Execution failed");
+        let saved = executor.get_conversation_history();
+        assert!(saved
+            .iter()
+            .any(|m| m.content.starts_with("Tool result for call read_1: [#1] ")));
+
+        // A restored session reading it again gets a reference to the saved result
+        let (api_client, mock) = create_mock_api_client();
+        mock.add_response("Reading it again", Some(vec![read_call("read_2")]));
+        mock.add_response("Done", None);
+        let mut executor = AgentExecutor::new(api_client);
+        executor.set_conversation_history(saved);
+        executor.add_user_message("Read notes.txt again".to_string());
+        executor.execute().await.expect("Execution failed");
+
+        let history = executor.get_conversation_history();
+        let second = history
+            .iter()
+            .find(|m| m.content.starts_with("Tool result for call read_2:"))
+            .expect("second result in history");
+        assert!(
+            second.content.contains("Same as result for call read_1"),
+            "Repeated result should reference the restored one"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_results_are_numbered_as_evidence() {
+        let (api_client, mock) = create_mock_api_client();
+
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("lib.rs");
+        std::fs::write(&file_path, "fn parse_args() {}\n").unwrap();
+
+        let read_call = ApiToolCall {
+            id: Some("read_1".to_string()),
+            name: "Read".to_string(),
+            arguments: serde_json::json!({
+                "file_path": file_path.to_string_lossy(),
+                "offset": 0,
+                "limit": 100
+            }),
+        };
+        let ls_call = ApiToolCall {
+            id: Some("ls_1".to_string()),
+            name: "LS".to_string(),
+            arguments: serde_json::json!({ "path": dir.path().to_string_lossy() }),
+        };
+
+        mock.add_response("Checking", Some(vec![read_call, ls_call]));
+        mock.add_response("`parse_args` is defined but never called [#1]", None);
+
+        let mut executor = AgentExecutor::new(api_client);
+        executor.add_user_message("Is parse_args used?".to_string());
+        executor.execute().await.expect("Execution failed");
+
+        let evidence = executor.evidence();
+        assert_eq!(evidence.len(), 2);
+        assert_eq!(evidence[0].index, 1);
+        assert_eq!(evidence[0].tool, "Read");
+        assert_eq!(evidence[0].tool_call_id, "read_1");
+        assert_eq!(
+            evidence[0].target.as_deref(),
+            Some(file_path.to_string_lossy().as_ref())
+        );
+        assert!(evidence[0].excerpt.contains("parse_args"));
+        assert_eq!(evidence[1].index, 2);
+        assert_eq!(evidence[1].tool, "LS");
+
+        // The model sees the tag in front of each result
+        let calls = mock.get_calls();
+        let tool_results = calls[1].2.as_ref().expect("tool results in second call");
+        assert!(tool_results[0].output.starts_with("[#1] "));
+        assert!(tool_results[1].output.starts_with("[#2] "));
+
+        let history = executor.get_conversation_history();
+        assert!(history
+            .iter()
+            .any(|m| m.content.starts_with("Tool result for call read_1: [#1] ")));
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_are_recorded_in_the_trail() {
+        use oli_server::agent::policy::ToolPolicy;
+        use oli_server::agent::tool_queue::QueuedCallStatus;
+
+        let (api_client, mock) = create_mock_api_client();
+
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("lib.rs");
+        std::fs::write(&file_path, "fn parse_args() {}\n").unwrap();
+
+        let read_call = ApiToolCall {
+            id: Some("read_1".to_string()),
+            name: "Read".to_string(),
+            arguments: serde_json::json!({
+                "file_path": file_path.to_string_lossy(),
+                "offset": 0,
+                "limit": 100
+            }),
+        };
+        let edit_call = ApiToolCall {
+            id: Some("edit_1".to_string()),
+            name: "Edit".to_string(),
+            arguments: serde_json::json!({
+                "file_path": file_path.to_string_lossy(),
+                "old_string": "parse_args",
+                "new_string": "parse_flags"
+            }),
+        };
+
+        mock.add_response("Reading lib.rs first", Some(vec![read_call]));
+        mock.add_response("Renaming the function", Some(vec![edit_call]));
+        mock.add_response("I couldn't rename it", None);
+
+        let mut executor = AgentExecutor::new(api_client).with_tool_policy(ToolPolicy::ReadOnly);
+        executor.add_user_message("Rename parse_args".to_string());
+        executor.execute().await.expect("Execution failed");
+
+        let trail = executor.trail();
+        assert_eq!(trail.len(), 2);
+        assert_eq!(trail[0].iteration, 1);
+        assert_eq!(trail[0].note.as_deref(), Some("Reading lib.rs first"));
+        assert_eq!(trail[0].calls[0].tool, "Read");
+        assert_eq!(trail[0].calls[0].status, QueuedCallStatus::Completed);
+        assert!(trail[0].calls[0].outcome.contains("parse_args"));
+
+        // The denied edit is recorded with the reason, although it isn't evidence
+        assert_eq!(trail[1].note.as_deref(), Some("Renaming the function"));
+        assert_eq!(trail[1].calls[0].tool, "Edit");
+        assert_eq!(trail[1].calls[0].status, QueuedCallStatus::Denied);
+        assert!(trail[1].calls[0]
+            .outcome
+            .contains("denied by the tool policy"));
+        assert_eq!(executor.evidence().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_agent_tool_runs_a_read_only_sub_agent() {
+        let (api_client, mock) = create_mock_api_client();
+        mock.add_response(
+            "Delegating the search",
+            Some(vec![ApiToolCall {
+                id: Some("agent_1".to_string()),
+                name: "Agent".to_string(),
+                arguments: serde_json::json!({ "objective": "Find where config is loaded" }),
+            }]),
+        );
+        // The sub-agent answers straight away
+        mock.add_response("Config is loaded in src/app/config.rs:42", None);
+
+        let (sender, mut receiver) = mpsc::channel(100);
+        let mut executor = AgentExecutor::new(api_client).with_progress_sender(sender);
+        executor.set_working_directory("/project".to_string());
+        executor.add_user_message("Where is config loaded?".to_string());
+        executor.execute().await.expect("Execution failed");
+
+        let calls = mock.get_calls();
+        let tool_names = |options: &CompletionOptions| -> Vec<String> {
+            options
+                .tools
+                .as_ref()
+                .unwrap()
+                .iter()
+                .map(|def| def.name.clone())
+                .collect()
+        };
+        assert!(tool_names(&calls[0].1).contains(&"Agent".to_string()));
+
+        // The sub-agent starts from its own conversation with only read-only tools
+        let (sub_messages, sub_options, _) = &calls[1];
+        assert_eq!(sub_messages.len(), 2);
+        assert!(sub_messages[0].content.contains("sub-agent"));
+        assert!(sub_messages[0].content.contains("/project"));
+        assert_eq!(sub_messages[1].content, "Find where config is loaded");
+        let sub_tools = tool_names(sub_options);
+        assert!(sub_tools.contains(&"Grep".to_string()));
+        for tool in ["Agent", "Edit", "Write", "Bash"] {
+            assert!(!sub_tools.contains(&tool.to_string()), "{tool}");
+        }
+
+        // Only its report comes back to the parent
+        let results = calls[2].2.as_ref().expect("tool results should be sent");
+        assert_eq!(results[0].tool_call_id, "agent_1");
+        assert!(results[0]
+            .output
+            .ends_with("Config is loaded in src/app/config.rs:42"));
+        assert_eq!(
+            executor.evidence()[0].target.as_deref(),
+            Some("Find where config is loaded")
+        );
+
+        drop(executor);
+        let mut progress = Vec::new();
+        while let Some(message) = receiver.recv().await {
+            progress.push(message);
+        }
+        assert!(progress
+            .iter()
+            .any(|message| message.starts_with("[agent] ")));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_sub_agent_cannot_run_tests() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let marker = temp_dir.path().join("ran");
+        std::fs::write(
+            temp_dir.path().join("package.json"),
+            format!(
+                r#"{{"scripts": {{"test": "touch {}"}}}}"#,
+                marker.to_string_lossy()
+            ),
+        )
+        .unwrap();
+
+        let (api_client, mock) = create_mock_api_client();
+        mock.add_response(
+            "Delegating",
+            Some(vec![ApiToolCall {
+                id: Some("agent_1".to_string()),
+                name: "Agent".to_string(),
+                arguments: serde_json::json!({ "objective": "Check the tests pass" }),
+            }]),
+        );
+        mock.add_response(
+            "Running the tests",
+            Some(vec![ApiToolCall {
+                id: Some("tests_1".to_string()),
+                name: "RunTests".to_string(),
+                arguments: serde_json::json!({}),
+            }]),
+        );
+        mock.add_response("I couldn't run them", None);
+        mock.add_response("Done", None);
+
+        let mut executor = AgentExecutor::new(api_client);
+        executor.set_working_directory(temp_dir.path().to_string_lossy().to_string());
+        executor.add_user_message("Do the tests pass?".to_string());
+        executor.execute().await.expect("Execution failed");
+
+        let calls = mock.get_calls();
+        let sub_tools: Vec<&str> = calls[1]
+            .1
+            .tools
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|def| def.name.as_str())
+            .collect();
+        assert!(!sub_tools.contains(&"RunTests"));
+        let results = calls[2].2.as_ref().expect("tool results should be sent");
+        assert_eq!(results[0].tool_call_id, "tests_1");
+        assert!(results[0].output.contains("denied by the tool policy"));
+        assert!(!marker.exists());
+    }
+
+    #[tokio::test]
+    async fn test_read_only_calls_run_together_and_edits_alone() {
+        use oli_server::agent::executor::runs_concurrently;
+
+        for tool in ["Read", "Grep", "Glob", "LS", "GitDiff"] {
+            assert!(runs_concurrently(tool), "{tool}");
+        }
+        for tool in ["Edit", "Write", "WriteMany", "Bash", "Agent", "RunTests"] {
+            assert!(!runs_concurrently(tool), "{tool}");
+        }
+
+        let (api_client, mock) = create_mock_api_client();
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.txt");
+        let second = dir.path().join("second.txt");
+        std::fs::write(&first, "alpha\n").unwrap();
+
+        let call = |id: &str, name: &str, arguments: serde_json::Value| ApiToolCall {
+            id: Some(id.to_string()),
+            name: name.to_string(),
+            arguments,
+        };
+        let read = |path: &std::path::Path| serde_json::json!({ "file_path": path.to_string_lossy(), "offset": 0, "limit": 100 });
+        mock.add_response(
+            "Reading, writing and reading again",
+            Some(vec![
+                call("read_1", "Read", read(&first)),
+                call("glob_1", "Glob", serde_json::json!({ "pattern": "*.txt", "path": dir.path() })),
+                call(
+                    "write_1",
+                    "Write",
+                    serde_json::json!({ "file_path": second.to_string_lossy(), "content": "beta\n" }),
+                ),
+                call("read_2", "Read", read(&second)),
+                call("read_3", "Read", read(&first)),
+            ]),
+        );
+        mock.add_response("Done", None);
+
+        let mut executor = AgentExecutor::new(api_client);
+        executor.add_user_message("Copy the file".to_string());
+        executor.execute().await.expect("Execution failed");
+
+        // Results come back in the order of the calls
+        let calls = mock.get_calls();
+        let ids: Vec<String> = calls[1]
+            .2
+            .as_ref()
+            .expect("tool results should be sent")
+            .iter()
+            .map(|result| result.tool_call_id.clone())
+            .collect();
+        assert_eq!(ids, vec!["read_1", "glob_1", "write_1", "read_2", "read_3"]);
+
+        // The read after the write ran after it, so it sees the new file
+        let trail = executor.trail();
+        let outcomes: Vec<&str> = trail[0]
+            .calls
+            .iter()
+            .map(|call| call.outcome.as_str())
+            .collect();
+        assert!(outcomes[0].contains("alpha"), "{}", outcomes[0]);
+        assert!(outcomes[3].contains("beta"), "{}", outcomes[3]);
+        assert!(outcomes[4].contains("alpha"), "{}", outcomes[4]);
+        assert_eq!(executor.evidence().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_tool_results_are_cut_to_the_limit() {
+        let (api_client, mock) = create_mock_api_client();
+
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("big.txt");
+        std::fs::write(&file_path, "line\n".repeat(2_000)).unwrap();
+
+        let read_call = ApiToolCall {
+            id: Some("read_1".to_string()),
+            name: "Read".to_string(),
+            arguments: serde_json::json!({
+                "file_path": file_path.to_string_lossy(),
+                "offset": 0,
+                "limit": 2000
+            }),
+        };
+        mock.add_response("Reading", Some(vec![read_call]));
+        mock.add_response("Done", None);
+
+        let mut executor = AgentExecutor::new(api_client)
+            .with_result_limits(ResultLimits::unlimited().with_override("Read", Some(500)));
+        executor.add_user_message("Read big.txt".to_string());
+        executor.execute().await.expect("Execution failed");
+
+        let calls = mock.get_calls();
+        let output = &calls[1].2.as_ref().expect("tool results in second call")[0].output;
+        assert!(output.contains("Read results are limited to 500 characters"));
+        assert!(output.len() < 700);
+    }
+
+    #[tokio::test]
+    async fn test_requests_carry_the_turn_budget() {
+        let (api_client, mock) = create_mock_api_client();
+
+        let ls_call = ApiToolCall {
+            id: Some("ls_1".to_string()),
+            name: "LS".to_string(),
+            arguments: serde_json::json!({ "path": "/some/path" }),
+        };
+        mock.add_response("Looking", Some(vec![ls_call]));
+        mock.add_response("Done", None);
+
+        let mut executor = AgentExecutor::new(api_client);
+        executor.add_system_message("You are helpful".to_string());
+        executor.add_user_message("List files".to_string());
+        executor.execute().await.expect("Execution failed");
+
+        let calls = mock.get_calls();
+        let first = &calls[0].0[0];
+        assert!(first.content.starts_with("You are helpful"));
+        assert!(first
+            .content
+            .contains("## BUDGET\nTool rounds: 0 of 100 used"));
+        let second = &calls[1].0[0];
+        assert!(second
+            .content
+            .contains("Tool rounds: 1 of 100 used, about 99 left"));
+
+        // The note is rebuilt per request rather than stored in the conversation
+        let history = executor.get_conversation_history();
+        assert!(history.iter().all(|msg| !msg.content.contains("## BUDGET")));
+    }
+
+    #[tokio::test]
+    async fn test_first_tool_choice_and_serialized_tools() {
+        let (api_client, mock) = create_mock_api_client();
+
+        let ls_call = ApiToolCall {
+            id: Some("ls_1".to_string()),
+            name: "LS".to_string(),
+            arguments: serde_json::json!({ "path": "/some/path" }),
+        };
+        mock.add_response("Looking", Some(vec![ls_call]));
+        mock.add_response("Done", None);
+
+        let mut executor = AgentExecutor::new(api_client)
+            .with_first_tool_choice(ToolChoice::Any)
+            .with_approval_backend(Some(ApprovalBackend::Command {
+                command: "true".to_string(),
+                timeout: std::time::Duration::from_secs(5),
+            }));
+        executor.add_user_message("List files".to_string());
+        executor.execute().await.expect("Execution failed");
+
+        // Only the first request forces a tool call
+        let calls = mock.get_calls();
+        assert_eq!(calls[0].1.tool_choice, ToolChoice::Any);
+        assert_eq!(calls[1].1.tool_choice, ToolChoice::Auto);
+
+        // Tools are requested one at a time while calls wait on approval
+        assert!(calls.iter().all(|call| !call.1.parallel_tool_calls));
+    }
+
+    #[tokio::test]
+    async fn test_always_allowed_commands_skip_approval() {
+        use oli_server::agent::permissions::PERMISSIONS_FILE_ENV;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let working_dir = temp_dir.path().to_string_lossy().to_string();
+        let rules_dir = tempfile::tempdir().unwrap();
+        let rules_path = rules_dir.path().join("rules.toml");
+        std::env::set_var(PERMISSIONS_FILE_ENV, &rules_path);
+        let bash_call = || ApiToolCall {
+            id: Some("bash_1".to_string()),
+            name: "Bash".to_string(),
+            arguments: serde_json::json!({ "command": "echo approved", "description": "Echo" }),
+        };
+
+        // The backend allows the command and asks for it to be remembered
+        let (api_client, mock) = create_mock_api_client();
+        mock.add_response("Running", Some(vec![bash_call()]));
+        mock.add_response("Done", None);
+        let mut executor =
+            AgentExecutor::new(api_client).with_approval_backend(Some(ApprovalBackend::Command {
+                command: r#"cat > /dev/null; echo '{"allow": true, "remember": "command"}'"#
+                    .to_string(),
+                timeout: std::time::Duration::from_secs(5),
+            }));
+        executor.set_working_directory(working_dir.clone());
+        executor.add_user_message("Echo".to_string());
+        executor.execute().await.expect("Execution failed");
+        let rules = std::fs::read_to_string(&rules_path).unwrap();
+        assert!(rules.contains("echo approved"));
+        assert!(!temp_dir.path().join(".oli").exists());
+
+        // Later runs of the command don't ask a backend that would deny it
+        let (api_client, mock) = create_mock_api_client();
+        mock.add_response("Running", Some(vec![bash_call()]));
+        mock.add_response("Done", None);
+        let mut executor =
+            AgentExecutor::new(api_client).with_approval_backend(Some(ApprovalBackend::Command {
+                command: "false".to_string(),
+                timeout: std::time::Duration::from_secs(5),
+            }));
+        executor.set_working_directory(working_dir);
+        executor.add_user_message("Echo again".to_string());
+        executor.execute().await.expect("Execution failed");
+        let history = executor.get_conversation_history();
+        assert!(history.iter().any(|msg| msg.content.contains("approved")));
+        assert!(history
+            .iter()
+            .all(|msg| !msg.content.contains("was denied")));
+    }
+
+    #[tokio::test]
+    async fn test_tool_queue_reports_each_call() {
+        use oli_server::agent::tool_queue::QueuedCallStatus;
+
+        let (api_client, mock) = create_mock_api_client();
+        let temp_dir = tempfile::tempdir().unwrap();
+        mock.add_response(
+            "Reading and listing",
+            Some(vec![
+                ApiToolCall {
+                    id: Some("tool_1".to_string()),
+                    name: "Read".to_string(),
+                    arguments: serde_json::json!({ "invalid_param": "value" }),
+                },
+                ApiToolCall {
+                    id: Some("tool_2".to_string()),
+                    name: "LS".to_string(),
+                    arguments: serde_json::json!({ "path": temp_dir.path() }),
+                },
+            ]),
+        );
+        mock.add_response("Done", None);
+
+        let (sender, mut receiver) = mpsc::channel(100);
+        let mut executor = AgentExecutor::new(api_client).with_tool_queue_sender(sender);
+        executor.add_user_message("List files".to_string());
+        executor.execute().await.expect("Execution failed");
+        drop(executor);
+
+        let mut snapshots = Vec::new();
+        while let Some(queue) = receiver.recv().await {
+            snapshots.push(queue);
+        }
+
+        // Queued, then both read-only calls started together and finished in order
+        assert_eq!(snapshots.len(), 5);
+        let statuses = |index: usize| -> Vec<QueuedCallStatus> {
+            snapshots[index]
+                .calls
+                .iter()
+                .map(|call| call.status)
+                .collect()
+        };
+        assert_eq!(
+            statuses(0),
+            vec![QueuedCallStatus::Pending, QueuedCallStatus::Pending]
+        );
+        assert_eq!(
+            statuses(2),
+            vec![QueuedCallStatus::Running, QueuedCallStatus::Running]
+        );
+        assert_eq!(
+            statuses(3),
+            vec![QueuedCallStatus::Failed, QueuedCallStatus::Running]
+        );
+        let last = snapshots.last().unwrap();
+        assert_eq!(last.iteration, 1);
+        assert!(last.is_done());
+        assert_eq!(last.calls[1].status, QueuedCallStatus::Completed);
+        assert!(last.calls[1].duration_ms.is_some());
+    }
 }
 
 #[cfg(test)]
@@ -1049,3 +1675,110 @@ mod util_function_tests {
         assert_eq!(parsed["tool_calls"][0]["name"], "TestTool");
     }
 }
+
+#[cfg(test)]
+mod partial_result_tests {
+    use super::*;
+    use oli_server::agent::executor::PARTIAL_RESULT_PREFIX;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    // Answers the first request with a tool call, then takes too long to answer again
+    struct SlowApiClient {
+        tool_call: ApiToolCall,
+        requests: Mutex<usize>,
+    }
+
+    #[async_trait::async_trait]
+    impl ApiClient for SlowApiClient {
+        async fn complete(
+            &self,
+            _messages: Vec<Message>,
+            _options: CompletionOptions,
+        ) -> Result<String> {
+            Ok("unused".to_string())
+        }
+
+        async fn complete_with_tools(
+            &self,
+            _messages: Vec<Message>,
+            _options: CompletionOptions,
+            _tool_results: Option<Vec<ToolResult>>,
+        ) -> Result<(String, Option<Vec<ApiToolCall>>)> {
+            let request = {
+                let mut requests = self.requests.lock().unwrap();
+                *requests += 1;
+                *requests
+            };
+            if request == 1 {
+                return Ok((
+                    "Listing the directory first".to_string(),
+                    Some(vec![self.tool_call.clone()]),
+                ));
+            }
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(("Too late".to_string(), None))
+        }
+    }
+
+    fn slow_executor(dir: &std::path::Path) -> AgentExecutor {
+        let client = SlowApiClient {
+            tool_call: ApiToolCall {
+                id: Some("ls_1".to_string()),
+                name: "LS".to_string(),
+                arguments: serde_json::json!({ "path": dir.to_string_lossy() }),
+            },
+            requests: Mutex::new(0),
+        };
+        let api_client =
+            oli_server::apis::api_client::ApiClientEnum::custom_for_testing(Arc::new(client));
+        let mut executor = AgentExecutor::new(api_client);
+        executor.add_user_message("Look around".to_string());
+        executor
+    }
+
+    #[tokio::test]
+    async fn test_time_limit_returns_partial_result() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut executor =
+            slow_executor(dir.path()).with_time_limit(Some(Duration::from_millis(500)));
+
+        let result = executor
+            .execute()
+            .await
+            .expect("partial result, not an error");
+        assert!(result.starts_with(PARTIAL_RESULT_PREFIX));
+        assert!(result.contains("timed out"));
+        assert!(result.contains("Listing the directory first"));
+        assert!(result.contains("Tools completed (1): LS"));
+
+        // The tool result and the partial result stay in the history for a follow-up
+        let history = executor.get_conversation_history();
+        assert!(history
+            .iter()
+            .any(|m| m.content.starts_with("Tool result for call ls_1:")));
+        assert_eq!(history.last().unwrap().role, "assistant");
+        assert_eq!(history.last().unwrap().content, result);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_flag_returns_partial_result() {
+        let dir = tempfile::tempdir().unwrap();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut executor = slow_executor(dir.path()).with_cancel_flag(cancel.clone());
+
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            cancel.store(true, Ordering::SeqCst);
+        });
+
+        let result = executor
+            .execute()
+            .await
+            .expect("partial result, not an error");
+        canceller.await.unwrap();
+        assert!(result.starts_with(PARTIAL_RESULT_PREFIX));
+        assert!(result.contains("cancelled"));
+        assert!(result.contains("continue"));
+    }
+}
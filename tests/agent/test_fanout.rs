@@ -0,0 +1,186 @@
+//! Tests for running sub-agents in parallel on independent subtasks
+
+use oli_server::agent::core::{Agent, LLMProvider};
+use oli_server::agent::executor::PARTIAL_RESULT_PREFIX;
+use oli_server::agent::fanout::{parse_subtasks, FanOutConfig};
+use oli_server::apis::api_client::{
+    ApiClient, ApiClientEnum, CompletionOptions, Message, ToolCall, ToolResult,
+};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Plans the given subtasks, answers each sub-agent after a short delay and
+// tracks how many sub-agents run at once
+struct FanOutApiClient {
+    plan: String,
+    running: AtomicUsize,
+    max_running: AtomicUsize,
+    synthesis_input: Mutex<Option<String>>,
+}
+
+impl FanOutApiClient {
+    fn new(subtasks: &[&str]) -> Arc<Self> {
+        Arc::new(Self {
+            plan: serde_json::json!({ "subtasks": subtasks }).to_string(),
+            running: AtomicUsize::new(0),
+            max_running: AtomicUsize::new(0),
+            synthesis_input: Mutex::new(None),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl ApiClient for FanOutApiClient {
+    async fn complete(
+        &self,
+        messages: Vec<Message>,
+        _options: CompletionOptions,
+    ) -> anyhow::Result<String> {
+        let user = messages.last().unwrap().content.clone();
+        if user.starts_with("Split this task") {
+            Ok(format!("Here is the plan:\n```json\n{}\n```", self.plan))
+        } else {
+            *self.synthesis_input.lock().unwrap() = Some(user);
+            Ok("Combined answer".to_string())
+        }
+    }
+
+    async fn complete_with_tools(
+        &self,
+        messages: Vec<Message>,
+        _options: CompletionOptions,
+        _tool_results: Option<Vec<ToolResult>>,
+    ) -> anyhow::Result<(String, Option<Vec<ToolCall>>)> {
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_running.fetch_max(running, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        self.running.fetch_sub(1, Ordering::SeqCst);
+
+        // Sub-agents see only their own subtask
+        let users: Vec<&Message> = messages.iter().filter(|m| m.role == "user").collect();
+        assert_eq!(users.len(), 1, "sub-agent conversations are isolated");
+        let subtask = users[0].content.lines().next().unwrap_or_default();
+        Ok((format!("Finished {subtask}"), None))
+    }
+}
+
+fn agent(client: Arc<FanOutApiClient>) -> Agent {
+    Agent::new(LLMProvider::Anthropic).with_api_client(ApiClientEnum::custom_for_testing(client))
+}
+
+#[test]
+fn test_parse_subtasks() {
+    let content =
+        "Sure:\n```json\n{\"subtasks\": [\"Fix a.rs\", \" \", \"Fix b.rs\", \"Fix c.rs\"]}\n```";
+    assert_eq!(parse_subtasks(content, 2), vec!["Fix a.rs", "Fix b.rs"]);
+    assert!(parse_subtasks("I can't split this", 5).is_empty());
+}
+
+#[test]
+fn test_config_from_json() {
+    assert_eq!(FanOutConfig::from_json(&serde_json::Value::Null), None);
+    assert_eq!(FanOutConfig::from_json(&serde_json::json!(false)), None);
+    assert_eq!(
+        FanOutConfig::from_json(&serde_json::json!(true)),
+        Some(FanOutConfig::default())
+    );
+
+    let config =
+        FanOutConfig::from_json(&serde_json::json!({ "max_concurrency": 0, "token_budget": 50 }))
+            .unwrap();
+    assert_eq!(config.max_concurrency, 1);
+    assert_eq!(config.token_budget, 50);
+}
+
+#[tokio::test]
+async fn test_subtasks_run_concurrently_within_limit() {
+    let client = FanOutApiClient::new(&["Fix a.rs", "Fix b.rs", "Fix c.rs", "Fix d.rs"]);
+    let (sender, mut receiver) = tokio::sync::mpsc::channel(100);
+    let temp_dir = tempfile::tempdir().unwrap();
+    let mut agent = agent(client.clone())
+        .with_working_directory(temp_dir.path().to_string_lossy().to_string())
+        .with_progress_sender(sender);
+    agent.add_message(Message::user("Earlier question".to_string()));
+
+    let config = FanOutConfig::default().with_max_concurrency(2);
+    let result = agent
+        .execute_fan_out("Fix all four files", config)
+        .await
+        .unwrap();
+    drop(agent);
+
+    assert_eq!(result.response, "Combined answer");
+    assert_eq!(result.subtasks.len(), 4);
+    assert_eq!(
+        result.subtasks[2].response.as_deref(),
+        Some("Finished Fix c.rs")
+    );
+    assert!(result.tokens_used > 0);
+    assert_eq!(client.max_running.load(Ordering::SeqCst), 2);
+
+    let synthesis_input = client.synthesis_input.lock().unwrap().clone().unwrap();
+    assert!(synthesis_input.contains("Original task: Fix all four files"));
+    assert!(synthesis_input.contains("### Subtask 4: Fix d.rs\nFinished Fix d.rs"));
+
+    // Progress from sub-agents is merged into the parent's stream
+    let mut progress = Vec::new();
+    while let Some(message) = receiver.recv().await {
+        progress.push(message);
+    }
+    assert!(progress[0].starts_with("Split task into 4 subtasks"));
+    assert!(progress.iter().any(|m| m.starts_with("[subtask 3] ")));
+}
+
+#[tokio::test]
+async fn test_token_budget_skips_remaining_subtasks() {
+    let client = FanOutApiClient::new(&["Fix a.rs", "Fix b.rs", "Fix c.rs"]);
+    let config = FanOutConfig::default()
+        .with_max_concurrency(1)
+        .with_token_budget(1);
+
+    let result = agent(client.clone())
+        .execute_fan_out("Fix three files", config)
+        .await
+        .unwrap();
+
+    assert!(result.subtasks[0].response.is_some());
+    for skipped in &result.subtasks[1..] {
+        assert!(skipped.error.as_deref().unwrap().contains("token budget"));
+    }
+    let synthesis_input = client.synthesis_input.lock().unwrap().clone().unwrap();
+    assert!(synthesis_input.contains("FAILED: skipped"));
+}
+
+#[tokio::test]
+async fn test_cancelled_task_skips_subtasks_and_synthesis() {
+    let client = FanOutApiClient::new(&["Fix a.rs", "Fix b.rs"]);
+    let cancel = Arc::new(AtomicBool::new(true));
+
+    let result = agent(client.clone())
+        .with_cancel_flag(cancel)
+        .execute_fan_out("Fix two files", FanOutConfig::default())
+        .await
+        .unwrap();
+
+    for skipped in &result.subtasks {
+        assert!(skipped.error.as_deref().unwrap().contains("cancelled"));
+    }
+    assert!(result.response.starts_with(PARTIAL_RESULT_PREFIX));
+    assert!(result.response.contains("### Subtask 2: Fix b.rs"));
+    assert!(client.synthesis_input.lock().unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_single_subtask_runs_normally() {
+    let client = FanOutApiClient::new(&["Rename the crate"]);
+
+    let result = agent(client.clone())
+        .execute_fan_out("Rename the crate", FanOutConfig::default())
+        .await
+        .unwrap();
+
+    assert!(result.subtasks.is_empty());
+    assert_eq!(result.response, "Finished Rename the crate");
+    assert!(client.synthesis_input.lock().unwrap().is_none());
+}
@@ -0,0 +1,232 @@
+//! Tests for the per-project always-allow rules, permission levels and edit paths
+
+use oli_server::agent::permissions::{
+    parse_levels, touches_permission_rules, PermissionFile, PermissionLevel, PermissionPolicy,
+    PermissionRules, RuleScope,
+};
+use serde_json::json;
+
+#[test]
+fn test_rules_match_tools_and_exact_commands() {
+    let mut rules = PermissionRules::default();
+    assert!(rules.add(RuleScope::Command, " cargo test "));
+    assert!(!rules.add(RuleScope::Command, "cargo test"));
+    assert!(rules.add(RuleScope::Tool, "Edit"));
+
+    let bash = |command: &str| json!({ "command": command });
+    assert_eq!(
+        rules.allows("Bash", &bash("cargo test  ")),
+        Some(RuleScope::Command)
+    );
+    assert_eq!(rules.allows("Bash", &bash("cargo test --release")), None);
+    assert_eq!(
+        rules.allows("Edit", &json!({ "file_path": "a.rs" })),
+        Some(RuleScope::Tool)
+    );
+    // A command rule only applies to Bash
+    assert_eq!(rules.allows("Write", &bash("cargo test")), None);
+
+    assert_eq!(
+        PermissionRules::rule_for(RuleScope::Command, "Bash", &bash(" ls ")),
+        Some("ls".to_string())
+    );
+    assert_eq!(
+        PermissionRules::rule_for(RuleScope::Command, "Edit", &json!({})),
+        None
+    );
+    assert!(rules.remove(RuleScope::Tool, "Edit"));
+    assert!(!rules.remove(RuleScope::Tool, "Edit"));
+}
+
+#[test]
+fn test_file_round_trip() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let project = temp_dir.path().join("project");
+    std::fs::create_dir(&project).unwrap();
+    let rules_path = temp_dir.path().join("home/project_permissions.toml");
+    let file = PermissionFile::new(rules_path.clone(), &project);
+    assert!(file.load().unwrap().is_empty());
+
+    assert!(file
+        .update(|rules| rules.add(RuleScope::Command, "cargo test"))
+        .unwrap());
+    assert!(!file
+        .update(|rules| rules.add(RuleScope::Command, "cargo test"))
+        .unwrap());
+    let saved = std::fs::read_to_string(&rules_path).unwrap();
+    let table = format!("[\"{}\"]", file.project());
+    assert!(saved.contains(&table));
+    assert!(saved.contains("commands = [\"cargo test\"]"));
+    // Nothing is kept in the workspace
+    assert!(!project.join(".oli").exists());
+
+    // Other projects' rules are kept apart and left alone
+    let other_dir = temp_dir.path().join("other");
+    std::fs::create_dir(&other_dir).unwrap();
+    let other = PermissionFile::new(rules_path.clone(), &other_dir);
+    assert!(other.load().unwrap().is_empty());
+    assert!(other
+        .update(|rules| rules.add(RuleScope::Tool, "Edit"))
+        .unwrap());
+    assert!(file.load().unwrap().commands.contains("cargo test"));
+    assert!(other
+        .update(|rules| rules.remove(RuleScope::Tool, "Edit"))
+        .unwrap());
+    assert!(!std::fs::read_to_string(&rules_path)
+        .unwrap()
+        .contains("other"));
+
+    // A file that doesn't parse is an error rather than no rules
+    std::fs::write(file.path(), format!("{table}\ncomands = []\n")).unwrap();
+    assert!(file.load().is_err());
+}
+
+#[test]
+fn test_levels_default_then_configured_then_project() {
+    let mut policy = PermissionPolicy {
+        levels: parse_levels("Bash=auto, Read=deny").unwrap(),
+        ..Default::default()
+    };
+    let mut rules = PermissionRules::default();
+    assert_eq!(policy.level_for("Edit", &rules), PermissionLevel::Ask);
+    assert_eq!(policy.level_for("Grep", &rules), PermissionLevel::Auto);
+    assert_eq!(policy.level_for("RunTests", &rules), PermissionLevel::Auto);
+    assert_eq!(policy.level_for("Bash", &rules), PermissionLevel::Auto);
+    assert_eq!(policy.level_for("Read", &rules), PermissionLevel::Deny);
+
+    assert!(rules.set_level("Bash", Some(PermissionLevel::Deny)));
+    assert!(!rules.set_level("Bash", Some(PermissionLevel::Deny)));
+    assert_eq!(policy.level_for("Bash", &rules), PermissionLevel::Deny);
+    assert!(rules.set_level("Bash", None));
+    assert_eq!(policy.level_for("Bash", &rules), PermissionLevel::Auto);
+
+    // A project can't loosen a level
+    assert!(rules.set_level("Read", Some(PermissionLevel::Auto)));
+    assert_eq!(policy.level_for("Read", &rules), PermissionLevel::Deny);
+    assert!(rules.set_level("Edit", Some(PermissionLevel::Auto)));
+    assert_eq!(policy.level_for("Edit", &rules), PermissionLevel::Ask);
+
+    policy.levels.clear();
+    assert_eq!(policy.level_for("Bash", &rules), PermissionLevel::Ask);
+    assert!(parse_levels("Bash=sometimes").is_err());
+    assert!(parse_levels("Bash").is_err());
+}
+
+#[test]
+fn test_edit_paths_confine_editing_tools() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    let policy = PermissionPolicy {
+        edit_paths: vec!["src/**".to_string(), "!**/*.lock".to_string()],
+        ..Default::default()
+    };
+    let denied = |tool: &str, arguments: serde_json::Value| {
+        policy.denied_edit_path(tool, &arguments, dir).unwrap()
+    };
+
+    assert_eq!(denied("Edit", json!({ "file_path": "src/lib.rs" })), None);
+    let inside = dir.join("src/app/mod.rs").to_string_lossy().to_string();
+    assert_eq!(denied("Write", json!({ "file_path": inside })), None);
+    assert_eq!(
+        denied("Edit", json!({ "file_path": "README.md" })),
+        Some("README.md".to_string())
+    );
+    assert_eq!(
+        denied("Edit", json!({ "file_path": "src/Cargo.lock" })),
+        Some("src/Cargo.lock".to_string())
+    );
+    // Nothing outside the working directory, however it's spelled
+    assert_eq!(
+        denied(
+            "Write",
+            json!({ "file_path": "src/../../elsewhere/src/a.rs" })
+        ),
+        Some("src/../../elsewhere/src/a.rs".to_string())
+    );
+    assert_eq!(
+        denied(
+            "WriteMany",
+            json!({ "files": [{ "path": "src/a.rs" }, { "path": "docs/b.md" }] })
+        ),
+        Some("docs/b.md".to_string())
+    );
+    // Other tools aren't restricted
+    assert_eq!(denied("Read", json!({ "file_path": "/etc/hosts" })), None);
+
+    // Only exclusions allow everything else in the working directory
+    let policy = PermissionPolicy {
+        edit_paths: vec!["!.env".to_string()],
+        ..Default::default()
+    };
+    let check = |path: &str| {
+        policy
+            .denied_edit_path("Edit", &json!({ "file_path": path }), dir)
+            .unwrap()
+    };
+    assert_eq!(check("docs/guide.md"), None);
+    assert_eq!(check(".env"), Some(".env".to_string()));
+    assert_eq!(
+        check("/tmp/outside.txt"),
+        Some("/tmp/outside.txt".to_string())
+    );
+
+    // No edit paths leaves edits unrestricted
+    assert_eq!(
+        PermissionPolicy::default()
+            .denied_edit_path("Edit", &json!({ "file_path": "/tmp/x" }), dir)
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_levels_round_trip_through_the_file() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file = PermissionFile::new(temp_dir.path().join("rules.toml"), temp_dir.path());
+    assert!(file
+        .update(|rules| rules.set_level("Write", Some(PermissionLevel::Deny)))
+        .unwrap());
+    let saved = std::fs::read_to_string(file.path()).unwrap();
+    assert!(saved.contains(".levels]\nWrite = \"deny\""));
+    assert_eq!(
+        file.load().unwrap().levels.get("Write"),
+        Some(&PermissionLevel::Deny)
+    );
+}
+
+#[test]
+fn test_calls_changing_the_rules_are_recognised() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    let rules_file = dir.join("home/project_permissions.toml");
+    let touches = |tool: &str, arguments: serde_json::Value| {
+        touches_permission_rules(tool, &arguments, dir, Some(&rules_file))
+    };
+
+    assert!(touches(
+        "Write",
+        json!({ "file_path": ".oli/permissions.toml" })
+    ));
+    assert!(touches(
+        "Edit",
+        json!({ "file_path": "src/../.oli/permissions.toml" })
+    ));
+    assert!(touches(
+        "Write",
+        json!({ "file_path": rules_file.to_string_lossy() })
+    ));
+    assert!(touches(
+        "WriteMany",
+        json!({ "files": [{ "path": "a.rs" }, { "path": ".oli/permissions.toml" }] })
+    ));
+    assert!(touches(
+        "Bash",
+        json!({ "command": "echo 'tools = [\\\"Bash\\\"]' >> ~/.oli/project_permissions.toml" })
+    ));
+    assert!(!touches("Write", json!({ "file_path": "src/lib.rs" })));
+    assert!(!touches("Bash", json!({ "command": "cargo test" })));
+    assert!(!touches(
+        "Read",
+        json!({ "file_path": ".oli/permissions.toml" })
+    ));
+}
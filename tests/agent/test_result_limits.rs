@@ -0,0 +1,105 @@
+//! Tests for the tool result limits derived from the model's context window
+
+use oli_server::agent::core::LLMProvider;
+use oli_server::agent::result_limits::{ResultLimits, MIN_RESULT_CHARS};
+use oli_server::apis::api_client::{split_images, ImageData};
+
+#[test]
+fn test_limit_scales_with_context_window() {
+    // 2% of 200k tokens at ~4 characters per token
+    let limits = ResultLimits::for_context_window(200_000);
+    assert_eq!(limits.limit_for("Bash"), Some(16_000));
+
+    let larger = ResultLimits::for_context_window(LLMProvider::Gemini.context_window());
+    assert!(larger.limit_for("Bash") > limits.limit_for("Bash"));
+
+    // Small context windows still get a useful minimum
+    let small = ResultLimits::for_context_window(LLMProvider::Ollama.context_window());
+    assert_eq!(small.limit_for("Bash"), Some(MIN_RESULT_CHARS));
+
+    assert_eq!(ResultLimits::unlimited().limit_for("Bash"), None);
+}
+
+#[test]
+fn test_overrides() {
+    let limits = ResultLimits::for_context_window(200_000)
+        .with_overrides_from("Bash=50000, Read=none,Grep=abc,=5,LS=0,Glob");
+
+    assert_eq!(limits.limit_for("Bash"), Some(50_000));
+    assert_eq!(limits.limit_for("Read"), None);
+    // Malformed entries leave the default in place
+    assert_eq!(limits.limit_for("Grep"), Some(16_000));
+    assert_eq!(limits.limit_for("LS"), Some(16_000));
+    assert_eq!(limits.limit_for("Glob"), Some(16_000));
+
+    let limits = limits.with_override("Grep", Some(100));
+    assert_eq!(limits.limit_for("Grep"), Some(100));
+}
+
+#[test]
+fn test_apply_keeps_start_and_end() {
+    let limits = ResultLimits::unlimited().with_override("Bash", Some(30));
+    let output = format!("START{}END", "x".repeat(100));
+
+    let cut = limits.apply("Bash", output.clone());
+    assert!(cut.starts_with("STARTxxxxxxxxxxxxxxx"));
+    assert!(cut.ends_with("xxxxxxxEND"));
+    assert!(cut.contains("[... 78 characters omitted: Bash results are limited to 30 characters"));
+
+    // Short results and tools without a limit are untouched
+    assert_eq!(limits.apply("Bash", "short".to_string()), "short");
+    assert_eq!(limits.apply("Read", output.clone()), output);
+}
+
+#[test]
+fn test_apply_respects_character_boundaries() {
+    let limits = ResultLimits::unlimited().with_override("Read", Some(10));
+    let cut = limits.apply("Read", "é".repeat(50));
+    assert!(cut.starts_with("éééééé\n"));
+    assert!(cut.ends_with("\néééé"));
+}
+
+#[test]
+fn test_images_are_kept_whole() {
+    let image = ImageData {
+        media_type: "image/png".to_string(),
+        data: "A".repeat(20_000),
+    };
+    let output = format!("Image shot.png is attached.\n{}", image.to_block());
+    let limited = ResultLimits::for_context_window(200_000).apply("Read", output);
+
+    let (text, images) = split_images(&limited);
+    assert_eq!(images, vec![image]);
+    assert!(text.starts_with("Image shot.png is attached."));
+    assert!(!text.contains("omitted"));
+}
+
+#[test]
+fn test_limits_follow_the_models_own_window() {
+    use oli_server::models::ModelConfig;
+
+    let model = |name: &str, context_window: Option<u32>| ModelConfig {
+        name: name.into(),
+        file_name: "model".into(),
+        description: String::new(),
+        recommended_for: String::new(),
+        supports_agent: true,
+        context_window,
+    };
+
+    // An 8k OpenRouter model gets smaller limits than one with 128k
+    let small = model("Small (OpenRouter)", Some(8_000)).result_limits();
+    let large = model("Large (OpenRouter)", Some(128_000)).result_limits();
+    assert_eq!(small.limit_for("Read"), Some(MIN_RESULT_CHARS));
+    assert_eq!(
+        large.limit_for("Read"),
+        ResultLimits::for_context_window(128_000).limit_for("Read")
+    );
+    assert!(small.limit_for("Read") < large.limit_for("Read"));
+
+    // Without a window of its own, a model gets its provider's
+    assert_eq!(
+        model("GPT-4o", None).result_limits(),
+        ResultLimits::for_context_window(LLMProvider::OpenAI.context_window())
+    );
+}
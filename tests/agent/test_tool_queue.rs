@@ -0,0 +1,63 @@
+use oli_server::agent::tool_queue::{QueuedCallStatus, ToolQueue};
+use oli_server::apis::api_client::ToolCall;
+
+fn calls() -> Vec<ToolCall> {
+    vec![
+        ToolCall {
+            id: Some("call_a".to_string()),
+            name: "Grep".to_string(),
+            arguments: serde_json::json!({ "pattern": "fn main" }),
+        },
+        ToolCall {
+            id: None,
+            name: "Read".to_string(),
+            arguments: serde_json::json!({ "file_path": "src/main.rs" }),
+        },
+    ]
+}
+
+#[test]
+fn test_new_queue_is_pending() {
+    let queue = ToolQueue::new(Some("task-1".to_string()), 2, &calls());
+
+    assert_eq!(queue.iteration, 2);
+    assert_eq!(queue.calls[0].id, "call_a");
+    // Calls without an id get the same fallback as their tool result
+    assert_eq!(queue.calls[1].id, "tool_1");
+    assert!(queue
+        .calls
+        .iter()
+        .all(|call| call.status == QueuedCallStatus::Pending && call.duration_ms.is_none()));
+    assert!(!queue.is_done());
+}
+
+#[test]
+fn test_finished_calls_have_durations() {
+    let mut queue = ToolQueue::new(None, 1, &calls());
+
+    queue.start(0);
+    assert_eq!(queue.calls[0].status, QueuedCallStatus::Running);
+    queue.finish(0, QueuedCallStatus::Completed);
+    queue.start(1);
+    queue.finish(1, QueuedCallStatus::Denied);
+
+    assert!(queue.calls[0].duration_ms.is_some());
+    assert_eq!(queue.calls[1].status, QueuedCallStatus::Denied);
+    assert!(queue.is_done());
+
+    // Out-of-range indexes are ignored
+    queue.finish(5, QueuedCallStatus::Failed);
+    assert_eq!(queue.calls.len(), 2);
+}
+
+#[test]
+fn test_queue_serializes_for_the_ui() {
+    let mut queue = ToolQueue::new(Some("task-1".to_string()), 1, &calls());
+    queue.start(0);
+
+    let json = serde_json::to_value(&queue).unwrap();
+    assert_eq!(json["task_id"], "task-1");
+    assert_eq!(json["calls"][0]["status"], "running");
+    assert_eq!(json["calls"][1]["status"], "pending");
+    assert!(json["calls"][0].get("started").is_none());
+}
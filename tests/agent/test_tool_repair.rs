@@ -0,0 +1,103 @@
+//! Tests for repairing malformed tool call arguments
+
+use oli_server::agent::tool_repair::{repair_arguments, ArgumentRepair};
+use serde_json::{json, Value};
+
+fn schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "file_path": { "type": "string" },
+            "offset": { "type": "integer" },
+            "recursive": { "type": "boolean" },
+            "options": { "type": "object" },
+            "edits": { "type": "array" }
+        }
+    })
+}
+
+#[test]
+fn test_valid_arguments_are_unchanged() {
+    let arguments = json!({ "file_path": "/tmp/a.txt", "offset": 10 });
+    let (repaired, repairs) = repair_arguments(&arguments, Some(&schema()));
+
+    assert_eq!(repaired, arguments);
+    assert!(repairs.is_empty());
+}
+
+#[test]
+fn test_stringified_json_is_decoded() {
+    let arguments = Value::String(r#"{"file_path": "/tmp/a.txt"}"#.to_string());
+    let (repaired, repairs) = repair_arguments(&arguments, None);
+    assert_eq!(repaired, json!({ "file_path": "/tmp/a.txt" }));
+    assert_eq!(repairs, vec![ArgumentRepair::StringifiedJson]);
+
+    // Encoded twice
+    let arguments = Value::String(serde_json::to_string(&arguments).unwrap());
+    let (repaired, repairs) = repair_arguments(&arguments, None);
+    assert_eq!(repaired, json!({ "file_path": "/tmp/a.txt" }));
+    assert_eq!(repairs, vec![ArgumentRepair::StringifiedJson]);
+}
+
+#[test]
+fn test_code_fence_and_trailing_commas_are_removed() {
+    let arguments =
+        Value::String("```json\n{\"file_path\": \"a,}\", \"edits\": [1, 2,],}\n```".to_string());
+    let (repaired, repairs) = repair_arguments(&arguments, None);
+
+    assert_eq!(repaired, json!({ "file_path": "a,}", "edits": [1, 2] }));
+    assert_eq!(
+        repairs,
+        vec![ArgumentRepair::CodeFence, ArgumentRepair::TrailingComma]
+    );
+}
+
+#[test]
+fn test_raw_newlines_in_strings_are_escaped() {
+    let arguments = Value::String("{\"content\": \"line one\nline two\"}".to_string());
+    let (repaired, repairs) = repair_arguments(&arguments, None);
+
+    assert_eq!(repaired, json!({ "content": "line one\nline two" }));
+    assert_eq!(repairs, vec![ArgumentRepair::ControlCharacters]);
+}
+
+#[test]
+fn test_fields_are_coerced_to_schema_types() {
+    let arguments = json!({
+        "file_path": 42,
+        "offset": "10",
+        "recursive": "true",
+        "options": "{\"depth\": 2}",
+        "edits": "[\"a\"]",
+        "unknown": "5"
+    });
+    let (repaired, repairs) = repair_arguments(&arguments, Some(&schema()));
+
+    assert_eq!(
+        repaired,
+        json!({
+            "file_path": "42",
+            "offset": 10,
+            "recursive": true,
+            "options": { "depth": 2 },
+            "edits": ["a"],
+            "unknown": "5"
+        })
+    );
+    assert!(repairs.contains(&ArgumentRepair::CoercedValue));
+    assert!(repairs.contains(&ArgumentRepair::StringifiedField));
+}
+
+#[test]
+fn test_unrepairable_arguments_are_returned_unchanged() {
+    let arguments = Value::String("not json at all".to_string());
+    let (repaired, repairs) = repair_arguments(&arguments, Some(&schema()));
+    assert_eq!(repaired, arguments);
+    assert!(repairs.is_empty());
+
+    // Ambiguous values are left for validation to reject
+    let arguments = json!({ "offset": "ten", "recursive": "yes" });
+    let (repaired, repairs) = repair_arguments(&arguments, Some(&schema()));
+    assert_eq!(repaired, arguments);
+    assert!(repairs.is_empty());
+}
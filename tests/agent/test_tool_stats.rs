@@ -0,0 +1,199 @@
+//! Tests for per-tool usage analytics used by benchmark reports
+
+use oli_server::agent::executor::AgentExecutor;
+use oli_server::agent::tool_repair::ArgumentRepair;
+use oli_server::agent::tool_stats::{ToolCallRecord, ToolFailure, ToolStatsLog, ToolUsageReport};
+use oli_server::apis::api_client::{
+    ApiClient, ApiClientEnum, CompletionOptions, Message, ToolCall as ApiToolCall, ToolResult,
+};
+use std::sync::{Arc, Mutex};
+
+fn record(tool: &str, failure: Option<ToolFailure>) -> ToolCallRecord {
+    ToolCallRecord {
+        tool: tool.to_string(),
+        success: failure.is_none(),
+        failure,
+        error: None,
+        missing_id: false,
+        unexpected_params: Vec::new(),
+        repairs: Vec::new(),
+    }
+}
+
+#[test]
+fn test_failure_from_parse_error() {
+    assert_eq!(
+        ToolFailure::from_parse_error("Unknown tool: Search"),
+        ToolFailure::UnknownTool
+    );
+    assert_eq!(
+        ToolFailure::from_parse_error("Failed to parse Read parameters: missing field `file_path`"),
+        ToolFailure::MissingParameter
+    );
+    assert_eq!(
+        ToolFailure::from_parse_error("Failed to parse Read parameters: invalid type: string"),
+        ToolFailure::InvalidArguments
+    );
+}
+
+#[test]
+fn test_report_breaks_down_accuracy_per_tool() {
+    let mut misnamed = record("Read", Some(ToolFailure::MissingParameter));
+    misnamed.unexpected_params = vec!["path".to_string()];
+    misnamed.missing_id = true;
+
+    let records = vec![
+        record("Read", None),
+        record("Read", None),
+        misnamed,
+        record("Grep", Some(ToolFailure::ExecutionError)),
+        record("Edit", None),
+    ];
+    let report = ToolUsageReport::from_records(&records);
+
+    assert_eq!(report.total_calls, 5);
+    assert_eq!(report.successful_calls, 3);
+    assert_eq!(report.missing_ids, 1);
+
+    let read = report.tools.iter().find(|t| t.tool == "Read").unwrap();
+    assert_eq!((read.calls, read.successes), (3, 2));
+    assert!((read.accuracy - 2.0 / 3.0).abs() < 1e-9);
+    assert_eq!(read.failures.get("missing_parameter"), Some(&1));
+
+    assert_eq!(report.unexpected_params, vec![("Read.path".to_string(), 1)]);
+
+    let markdown = report.to_markdown();
+    assert!(markdown.contains("3/5 tool calls succeeded (60.0%)"));
+    assert!(markdown.contains("| Read | 3 | 2 | 66.7% | missing_parameter: 1 |"));
+    assert!(markdown.contains("| Edit | 1 | 1 | 100.0% | - |"));
+    assert!(markdown.contains("unexpected parameter `Read.path`: 1"));
+
+    assert!(!markdown.contains("Repaired Arguments"));
+
+    let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+    assert_eq!(json["total_calls"], 5);
+}
+
+#[test]
+fn test_report_counts_repaired_arguments() {
+    let mut fenced = record("Read", None);
+    fenced.repairs = vec![ArgumentRepair::CodeFence, ArgumentRepair::TrailingComma];
+    let mut stringified = record("Grep", None);
+    stringified.repairs = vec![
+        ArgumentRepair::StringifiedJson,
+        ArgumentRepair::TrailingComma,
+    ];
+
+    let report = ToolUsageReport::from_records(&[fenced, stringified, record("Read", None)]);
+
+    assert_eq!(report.repaired_calls, 2);
+    assert_eq!(
+        report.repairs,
+        vec![
+            ("trailing_comma".to_string(), 2),
+            ("code_fence".to_string(), 1),
+            ("stringified_json".to_string(), 1),
+        ]
+    );
+
+    let markdown = report.to_markdown();
+    assert!(markdown.contains("2 tool calls needed their arguments repaired"));
+    assert!(markdown.contains("- trailing_comma: 2"));
+}
+
+// Returns queued responses
+struct QueuedApiClient {
+    responses: Mutex<Vec<(String, Option<Vec<ApiToolCall>>)>>,
+}
+
+#[async_trait::async_trait]
+impl ApiClient for QueuedApiClient {
+    async fn complete(
+        &self,
+        _messages: Vec<Message>,
+        _options: CompletionOptions,
+    ) -> anyhow::Result<String> {
+        Ok("Done".to_string())
+    }
+
+    async fn complete_with_tools(
+        &self,
+        _messages: Vec<Message>,
+        _options: CompletionOptions,
+        _tool_results: Option<Vec<ToolResult>>,
+    ) -> anyhow::Result<(String, Option<Vec<ApiToolCall>>)> {
+        let mut responses = self.responses.lock().unwrap();
+        if responses.is_empty() {
+            Ok(("Done".to_string(), None))
+        } else {
+            Ok(responses.remove(0))
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_executor_records_tool_calls() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let stats = ToolStatsLog::new(temp_dir.path().join("tool_calls.jsonl"));
+
+    let calls = vec![
+        // Valid call
+        ApiToolCall {
+            id: Some("ls_1".to_string()),
+            name: "LS".to_string(),
+            arguments: serde_json::json!({ "path": temp_dir.path().to_string_lossy() }),
+        },
+        // Wrong parameter name and no id
+        ApiToolCall {
+            id: None,
+            name: "Read".to_string(),
+            arguments: serde_json::json!({ "path": "/tmp/file.txt" }),
+        },
+    ];
+    let client = QueuedApiClient {
+        responses: Mutex::new(vec![("Working".to_string(), Some(calls))]),
+    };
+
+    let mut executor = AgentExecutor::new(ApiClientEnum::custom_for_testing(Arc::new(client)))
+        .with_tool_stats(Some(stats.clone()));
+    executor.add_user_message("Look around".to_string());
+    executor.execute().await.unwrap();
+
+    let records = stats.read_all().unwrap();
+    assert_eq!(records.len(), 2);
+
+    assert_eq!(records[0].tool, "LS");
+    assert!(records[0].success);
+
+    assert_eq!(records[1].tool, "Read");
+    assert_eq!(records[1].failure, Some(ToolFailure::MissingParameter));
+    assert!(records[1].missing_id);
+    assert_eq!(records[1].unexpected_params, vec!["path"]);
+}
+
+#[tokio::test]
+async fn test_executor_repairs_malformed_arguments() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let stats = ToolStatsLog::new(temp_dir.path().join("tool_calls.jsonl"));
+    let path = temp_dir.path().to_string_lossy().to_string();
+
+    let calls = vec![ApiToolCall {
+        id: Some("ls_1".to_string()),
+        name: "LS".to_string(),
+        // Arguments sent as a string with a trailing comma
+        arguments: serde_json::Value::String(format!("{{\"path\": {path:?},}}")),
+    }];
+    let client = QueuedApiClient {
+        responses: Mutex::new(vec![("Working".to_string(), Some(calls))]),
+    };
+
+    let mut executor = AgentExecutor::new(ApiClientEnum::custom_for_testing(Arc::new(client)))
+        .with_tool_stats(Some(stats.clone()));
+    executor.add_user_message("Look around".to_string());
+    executor.execute().await.unwrap();
+
+    let records = stats.read_all().unwrap();
+    assert_eq!(records.len(), 1);
+    assert!(records[0].success);
+    assert_eq!(records[0].repairs, vec![ArgumentRepair::TrailingComma]);
+}
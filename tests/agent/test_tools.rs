@@ -1,4 +1,5 @@
 use oli_server::agent::core::{Agent, LLMProvider};
+use oli_server::agent::tool_stats::ToolStatsLog;
 use oli_server::agent::tools::{
     BashParams, EditParams, GlobParams, GrepParams, LSParams, ReadParams, ToolCall, WriteParams,
 };
@@ -35,8 +36,10 @@ async fn setup_ollama_agent() -> Option<(Agent, u64)> {
         }
     };
 
-    // Initialize agent with Ollama
-    let mut agent = Agent::new(LLMProvider::Ollama).with_model(model);
+    // Initialize agent with Ollama, recording tool calls for the benchmark report
+    let mut agent = Agent::new(LLMProvider::Ollama)
+        .with_model(model)
+        .with_tool_stats(ToolStatsLog::from_env());
     if let Err(e) = agent.initialize().await {
         println!("Failed to initialize agent: {e}");
         return None;
@@ -299,6 +302,9 @@ async fn test_grep_tool_direct() {
         pattern: "IMPORTANT".to_string(),
         path: Some(temp_dir.path().to_string_lossy().to_string()),
         include: None,
+        context_lines: None,
+        max_results: None,
+        show_line_numbers: None,
     })
     .execute();
 
@@ -317,6 +323,9 @@ async fn test_grep_tool_direct() {
         pattern: "(?i)important".to_string(), // Case-insensitive regex
         path: Some(temp_dir.path().to_string_lossy().to_string()),
         include: None,
+        context_lines: None,
+        max_results: None,
+        show_line_numbers: None,
     })
     .execute();
 
@@ -339,6 +348,9 @@ async fn test_grep_tool_direct() {
         pattern: "important".to_string(),
         path: Some(temp_dir.path().to_string_lossy().to_string()),
         include: Some("*.txt".to_string()),
+        context_lines: None,
+        max_results: None,
+        show_line_numbers: None,
     })
     .execute();
 
@@ -355,6 +367,24 @@ async fn test_grep_tool_direct() {
             && !grep_txt_output.contains("code.rs"),
         "Pattern-filtered grep should only search txt files: {grep_txt_output}"
     );
+
+    // Capped results still count every match, per file
+    let capped_output = ToolCall::Grep(GrepParams {
+        pattern: "(?i)important".to_string(),
+        path: Some(temp_dir.path().to_string_lossy().to_string()),
+        include: None,
+        context_lines: None,
+        max_results: Some(1),
+        show_line_numbers: None,
+    })
+    .execute()
+    .expect("Failed to grep with max_results");
+    assert!(
+        capped_output.contains("in 4 files")
+            && capped_output.contains("Showing the first 1 matches")
+            && capped_output.contains("code.rs: 3"),
+        "Capped grep should list the matches in each file: {capped_output}"
+    );
 }
 
 #[tokio::test]
@@ -1008,6 +1038,8 @@ async fn test_write_tool_direct() {
     let write_result = ToolCall::Write(WriteParams {
         file_path: test_file_path.to_string_lossy().to_string(),
         content: new_content.to_string(),
+        create_dirs: None,
+        mode: None,
     })
     .execute();
 
@@ -1039,6 +1071,8 @@ async fn test_write_tool_direct() {
     let create_result = ToolCall::Write(WriteParams {
         file_path: new_file_path.to_string_lossy().to_string(),
         content: create_content.to_string(),
+        create_dirs: None,
+        mode: None,
     })
     .execute();
 
@@ -0,0 +1,71 @@
+//! Tests for the recorded trail of a task and the prompt explaining it
+
+use oli_server::agent::tool_queue::QueuedCallStatus;
+use oli_server::agent::trail::{TaskTrail, TrailCall, TrailRound, TRAIL_OUTCOME_CHARS};
+use serde_json::json;
+
+fn sample_trail() -> TaskTrail {
+    let mut round = TrailRound::new(1, "  The config is parsed in main.rs  ");
+    round.calls.push(TrailCall::new(
+        "Read",
+        &json!({ "file_path": "src/main.rs" }),
+        QueuedCallStatus::Completed,
+        "fn main() {}",
+    ));
+    round.calls.push(TrailCall::new(
+        "Bash",
+        &json!({ "command": "rm -rf target" }),
+        QueuedCallStatus::Denied,
+        "ERROR EXECUTING TOOL: Bash was denied by the tool policy",
+    ));
+    TaskTrail::new(
+        "task-1",
+        "Why is startup slow?",
+        vec![round],
+        "Parsing is slow.",
+    )
+}
+
+#[test]
+fn test_trail_call_outcome_is_cut_short() {
+    let call = TrailCall::new(
+        "Read",
+        &json!({}),
+        QueuedCallStatus::Completed,
+        &"x".repeat(TRAIL_OUTCOME_CHARS + 50),
+    );
+    assert_eq!(call.outcome.chars().count(), TRAIL_OUTCOME_CHARS + 1);
+    assert!(call.outcome.ends_with('…'));
+}
+
+#[test]
+fn test_empty_note_is_dropped() {
+    assert_eq!(TrailRound::new(2, "   ").note, None);
+    assert_eq!(
+        sample_trail().rounds[0].note.as_deref(),
+        Some("The config is parsed in main.rs")
+    );
+}
+
+#[test]
+fn test_explanation_prompt_lists_the_recorded_steps() {
+    let trail = sample_trail();
+    assert_eq!(trail.call_count(), 2);
+
+    let prompt = trail.explanation_prompt(Some("why did you try to delete target?"));
+    assert!(prompt.contains("Question: why did you try to delete target?"));
+    assert!(prompt.contains("## Request\nWhy is startup slow?"));
+    assert!(prompt.contains("## Round 1\nAgent's note: The config is parsed in main.rs"));
+    assert!(prompt.contains(r#"- Read {"file_path":"src/main.rs"} [completed]: fn main() {}"#));
+    assert!(prompt.contains("[denied]: ERROR EXECUTING TOOL: Bash was denied"));
+    assert!(prompt.contains("## Final answer\nParsing is slow."));
+}
+
+#[test]
+fn test_explanation_prompt_without_tool_calls_or_question() {
+    let trail = TaskTrail::new("task-2", "Say hi", Vec::new(), "Hi");
+    let prompt = trail.explanation_prompt(Some("  "));
+    assert!(!prompt.contains("Question:"));
+    assert!(prompt.contains("call out edits or commands that might surprise the user"));
+    assert!(prompt.contains("None; the agent answered directly."));
+}
@@ -31,6 +31,12 @@ mod test_anthropic {
 }
 mod test_api_client;
 mod test_api_client_enum;
+mod test_batch;
+mod test_continuation;
 mod test_gemini;
+mod test_key_check;
+mod test_ledger;
 mod test_ollama;
 mod test_openai;
+mod test_streaming;
+mod test_tokenizer;
@@ -1,7 +1,9 @@
 //! Unit tests for the API client module
 
+use oli_server::agent::core::LLMProvider;
 use oli_server::apis::api_client::{
-    CompletionOptions, Message, SessionManager, ToolCall, ToolDefinition, ToolResult,
+    CompletionOptions, Message, ModelParameters, SamplingPreset, SessionManager, ToolCall,
+    ToolChoice, ToolDefinition, ToolResult,
 };
 use serde_json::json;
 
@@ -89,6 +91,8 @@ fn test_completion_options() {
     // Default values should be set
     assert!(options.max_tokens.unwrap() > 0);
     assert!(options.temperature.unwrap() >= 0.0 && options.temperature.unwrap() <= 1.0);
+    assert_eq!(options.tool_choice, ToolChoice::Auto);
+    assert!(options.parallel_tool_calls);
 
     // Create custom options
     let custom_options = CompletionOptions {
@@ -102,14 +106,16 @@ fn test_completion_options() {
                 "properties": {}
             }),
         }]),
-        require_tool_use: false,
+        tool_choice: ToolChoice::Tool("TestTool".to_string()),
+        parallel_tool_calls: false,
         ..Default::default()
     };
 
     // Verify custom values
     assert_eq!(custom_options.max_tokens, Some(1000));
     assert_eq!(custom_options.temperature, Some(0.8));
-    assert!(!custom_options.require_tool_use);
+    assert!(custom_options.tool_choice.requires_tool());
+    assert!(!custom_options.parallel_tool_calls);
     assert_eq!(
         custom_options.tools.as_ref().unwrap()[0].name,
         "TestTool".to_string()
@@ -196,6 +202,55 @@ pub mod session_manager_tests {
         assert!(session_manager.system_message.is_some());
     }
 
+    #[test]
+    fn test_edit_and_delete_message() {
+        let mut session_manager = SessionManager::new(100);
+        session_manager.add_user_message("My key is sk-secret".to_string());
+        session_manager.add_assistant_message("Noted".to_string());
+
+        session_manager
+            .edit_message(0, "My key is [redacted]".to_string())
+            .unwrap();
+        assert_eq!(session_manager.messages[0].role, "user");
+        assert_eq!(session_manager.messages[0].content, "My key is [redacted]");
+
+        let removed = session_manager.delete_message(1).unwrap();
+        assert_eq!(removed.content, "Noted");
+        assert_eq!(session_manager.message_count(), 1);
+
+        // Out-of-range indexes are errors
+        assert!(session_manager.edit_message(1, "x".to_string()).is_err());
+        assert!(session_manager.delete_message(1).is_err());
+    }
+
+    #[test]
+    fn test_tags_follow_their_messages() {
+        let mut session_manager = SessionManager::new(3);
+        for i in 0..3 {
+            session_manager.add_user_message(format!("Message {i}"));
+        }
+        assert!(session_manager.tag_message(1, "bug").unwrap());
+        assert!(!session_manager.tag_message(1, "bug").unwrap());
+        session_manager.tag_message(2, "todo").unwrap();
+        assert!(session_manager.tag_message(3, "todo").is_err());
+
+        // Deleting an earlier message moves later tags down with their messages
+        session_manager.delete_message(0).unwrap();
+        assert!(session_manager.message_tags[&0].contains("bug"));
+        assert!(session_manager.message_tags[&1].contains("todo"));
+
+        // Messages trimmed past the limit take their tags with them
+        session_manager.add_user_message("Message 3".to_string());
+        session_manager.add_user_message("Message 4".to_string());
+        assert_eq!(session_manager.messages[0].content, "Message 2");
+        assert_eq!(session_manager.message_tags.len(), 1);
+        assert!(session_manager.message_tags[&0].contains("todo"));
+
+        assert!(session_manager.untag_message(0, "todo"));
+        assert!(!session_manager.untag_message(0, "todo"));
+        assert!(session_manager.message_tags.is_empty());
+    }
+
     #[test]
     fn test_message_count() {
         // Create a session manager
@@ -232,3 +287,90 @@ pub mod session_manager_tests {
         assert_eq!(session_manager.messages[0].role, "system");
     }
 }
+
+/// Tests for session and per-request model parameter overrides
+#[test]
+fn test_model_parameters_set_and_merge() {
+    let mut session = ModelParameters::default();
+    session.set("temperature", "0.7").unwrap();
+    session.set("max_tokens", "1024").unwrap();
+    assert_eq!(session.temperature, Some(0.7));
+    assert_eq!(session.max_tokens, Some(1024));
+
+    // Unknown names and malformed values are rejected
+    assert!(session.set("frequency_penalty", "1").is_err());
+    assert!(session.set("top_p", "high").is_err());
+    assert!(session.set("top_p", "1.5").is_err());
+
+    // "default" clears an override
+    session.set("max_tokens", "default").unwrap();
+    assert_eq!(session.max_tokens, None);
+
+    // Per-request overrides take precedence over session settings
+    let request =
+        ModelParameters::from_json(&json!({ "temperature": 0.1, "prompt": "hi" })).unwrap();
+    let merged = session.merge(&request);
+    assert_eq!(merged.temperature, Some(0.1));
+    assert_eq!(merged.top_p, None);
+
+    let mut options = CompletionOptions::default();
+    merged.apply_to(&mut options);
+    assert_eq!(options.temperature, Some(0.1));
+    assert_eq!(options.top_p, Some(0.9));
+    assert_eq!(options.max_tokens, Some(2048));
+
+    assert!(ModelParameters::from_json(&json!({ "max_tokens": "lots" })).is_err());
+}
+
+#[test]
+fn test_model_parameters_provider_limits() {
+    let params = ModelParameters {
+        temperature: Some(1.5),
+        top_p: None,
+        max_tokens: Some(32_000),
+    };
+
+    // Anthropic caps temperature at 1.0, OpenAI allows up to 2.0 but fewer output tokens
+    assert!(params.validate(Some(&LLMProvider::Anthropic)).is_err());
+    assert!(params.validate(Some(&LLMProvider::OpenAI)).is_err());
+    assert!(params.validate(Some(&LLMProvider::Gemini)).is_ok());
+    assert!(params.validate(None).is_ok());
+
+    let zero_tokens = ModelParameters {
+        max_tokens: Some(0),
+        ..Default::default()
+    };
+    assert!(zero_tokens.validate(Some(&LLMProvider::Ollama)).is_err());
+}
+
+#[test]
+fn test_sampling_presets_clamp_to_the_model() {
+    assert_eq!(
+        SamplingPreset::parse(" Creative ").unwrap(),
+        SamplingPreset::Creative
+    );
+    assert!(SamplingPreset::parse("wild").is_err());
+
+    // Creative runs hotter than Anthropic allows, so it's lowered to the limit
+    let creative = SamplingPreset::Creative;
+    let anthropic = creative.parameters_for(&LLMProvider::Anthropic, "claude-sonnet-4-20250514");
+    assert_eq!(anthropic.temperature, Some(1.0));
+    assert_eq!(anthropic.top_p, creative.parameters().top_p);
+    assert!(anthropic.validate(Some(&LLMProvider::Anthropic)).is_ok());
+    assert_eq!(
+        creative.parameters_for(&LLMProvider::Gemini, "gemini-2.5-pro"),
+        creative.parameters()
+    );
+
+    // Reasoning models only run with their default sampling
+    assert_eq!(
+        creative.parameters_for(&LLMProvider::OpenAI, "o3-mini"),
+        ModelParameters::default()
+    );
+    assert_eq!(
+        SamplingPreset::Precise
+            .parameters_for(&LLMProvider::OpenAI, "gpt-4o")
+            .temperature,
+        Some(0.2)
+    );
+}
@@ -0,0 +1,228 @@
+//! Tests for the Anthropic and OpenAI batch APIs and the batch poller
+
+use async_trait::async_trait;
+use oli_server::apis::anthropic::AnthropicClient;
+use oli_server::apis::api_client::{CompletionOptions, Message, ToolDefinition};
+use oli_server::apis::batch::{BatchApi, BatchPoller, BatchRequest, BatchResult, BatchStatus};
+use oli_server::apis::openai::OpenAIClient;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Serve canned responses on a local port, keyed by "METHOD /path"
+async fn serve_stub(routes: impl Fn(&str) -> Vec<(String, String)>) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let routes = routes(&base);
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut buffer = vec![0; 65536];
+            let read = socket.read(&mut buffer).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+            let mut parts = request.split_whitespace();
+            let route = format!(
+                "{} {}",
+                parts.next().unwrap_or(""),
+                parts.next().unwrap_or("")
+            );
+
+            let response = match routes.iter().find(|(r, _)| *r == route) {
+                Some((_, body)) => format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                ),
+                None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string(),
+            };
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+
+    base
+}
+
+fn requests() -> Vec<BatchRequest> {
+    vec![
+        BatchRequest::new("first", vec![Message::user("Summarize a.rs".to_string())]),
+        BatchRequest::new("second", vec![Message::user("Summarize b.rs".to_string())]),
+    ]
+}
+
+#[test]
+fn test_requests_with_tools_are_not_eligible() {
+    let plain = BatchRequest::new("plain", vec![]);
+    assert!(plain.is_eligible());
+
+    let with_tools = BatchRequest::new("tools", vec![]).with_options(CompletionOptions {
+        tools: Some(vec![ToolDefinition {
+            name: "Read".to_string(),
+            description: "Read a file".to_string(),
+            parameters: serde_json::json!({}),
+        }]),
+        ..CompletionOptions::default()
+    });
+    assert!(!with_tools.is_eligible());
+}
+
+#[tokio::test]
+async fn test_anthropic_batch_round_trip() {
+    let base = serve_stub(|base| {
+        vec![
+            (
+                "POST /v1/messages/batches".to_string(),
+                r#"{"id":"msgbatch_1","processing_status":"in_progress"}"#.to_string(),
+            ),
+            (
+                "GET /v1/messages/batches/msgbatch_1".to_string(),
+                format!(
+                    r#"{{"id":"msgbatch_1","processing_status":"ended","results_url":"{base}/results/msgbatch_1"}}"#
+                ),
+            ),
+            (
+                "GET /results/msgbatch_1".to_string(),
+                [
+                    r#"{"custom_id":"first","result":{"type":"succeeded","message":{"content":[{"type":"text","text":"a.rs parses input"}]}}}"#,
+                    r#"{"custom_id":"second","result":{"type":"errored","error":{"type":"error","error":{"type":"invalid_request_error","message":"prompt is too long"}}}}"#,
+                ]
+                .join("\n"),
+            ),
+        ]
+    })
+    .await;
+
+    let client = AnthropicClient::with_api_key("test".to_string(), None)
+        .unwrap()
+        .with_api_base(format!("{base}/v1/messages"));
+
+    let batch_id = client.submit_batch(requests()).await.unwrap();
+    assert_eq!(batch_id, "msgbatch_1");
+    assert_eq!(
+        client.batch_status(&batch_id).await.unwrap(),
+        BatchStatus::Completed
+    );
+
+    let results = client.batch_results(&batch_id).await.unwrap();
+    assert_eq!(
+        results,
+        vec![
+            BatchResult::success("first", "a.rs parses input".to_string()),
+            BatchResult::failure("second", "prompt is too long".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_openai_batch_round_trip() {
+    let base = serve_stub(|_| {
+        vec![
+            (
+                "POST /v1/files".to_string(),
+                r#"{"id":"file-in","purpose":"batch"}"#.to_string(),
+            ),
+            (
+                "POST /v1/batches".to_string(),
+                r#"{"id":"batch_1","status":"validating"}"#.to_string(),
+            ),
+            (
+                "GET /v1/batches/batch_1".to_string(),
+                r#"{"id":"batch_1","status":"completed","output_file_id":"file-out","error_file_id":"file-err"}"#
+                    .to_string(),
+            ),
+            (
+                "GET /v1/files/file-out/content".to_string(),
+                r#"{"custom_id":"first","response":{"status_code":200,"body":{"choices":[{"message":{"role":"assistant","content":"a.rs parses input"}}]}},"error":null}"#
+                    .to_string(),
+            ),
+            (
+                "GET /v1/files/file-err/content".to_string(),
+                r#"{"custom_id":"second","response":{"status_code":400,"body":{"error":{"message":"context length exceeded"}}},"error":null}"#
+                    .to_string(),
+            ),
+        ]
+    })
+    .await;
+
+    let client = OpenAIClient::with_api_key("test".to_string(), None)
+        .unwrap()
+        .with_api_base(format!("{base}/v1/chat/completions"));
+
+    let batch_id = client.submit_batch(requests()).await.unwrap();
+    assert_eq!(batch_id, "batch_1");
+
+    let results = BatchPoller::new()
+        .with_interval(Duration::from_millis(10))
+        .wait(&client, &batch_id)
+        .await
+        .unwrap();
+    assert_eq!(
+        results,
+        vec![
+            BatchResult::success("first", "a.rs parses input".to_string()),
+            BatchResult::failure("second", "context length exceeded".to_string()),
+        ]
+    );
+}
+
+// Reports a queue of statuses, one per check
+struct ScriptedBatch {
+    statuses: Mutex<Vec<BatchStatus>>,
+}
+
+#[async_trait]
+impl BatchApi for ScriptedBatch {
+    async fn submit_batch(&self, _requests: Vec<BatchRequest>) -> anyhow::Result<String> {
+        Ok("batch".to_string())
+    }
+
+    async fn batch_status(&self, _batch_id: &str) -> anyhow::Result<BatchStatus> {
+        let mut statuses = self.statuses.lock().unwrap();
+        Ok(if statuses.len() > 1 {
+            statuses.remove(0)
+        } else {
+            statuses[0].clone()
+        })
+    }
+
+    async fn batch_results(&self, _batch_id: &str) -> anyhow::Result<Vec<BatchResult>> {
+        Ok(vec![BatchResult::success("only", "done".to_string())])
+    }
+}
+
+#[tokio::test]
+async fn test_poller_waits_for_completion() {
+    let api = ScriptedBatch {
+        statuses: Mutex::new(vec![
+            BatchStatus::InProgress,
+            BatchStatus::InProgress,
+            BatchStatus::Completed,
+        ]),
+    };
+    let poller = BatchPoller::new().with_interval(Duration::from_millis(1));
+
+    let results = poller.wait(&api, "batch").await.unwrap();
+    assert_eq!(results[0].content.as_deref(), Some("done"));
+    assert!(api.statuses.lock().unwrap().len() == 1);
+}
+
+#[tokio::test]
+async fn test_poller_reports_failures_and_timeouts() {
+    let failed = ScriptedBatch {
+        statuses: Mutex::new(vec![BatchStatus::Failed]),
+    };
+    let error = BatchPoller::new().wait(&failed, "batch").await.unwrap_err();
+    assert!(error.to_string().contains("failed"));
+
+    let stuck = ScriptedBatch {
+        statuses: Mutex::new(vec![BatchStatus::InProgress]),
+    };
+    let error = BatchPoller::new()
+        .with_interval(Duration::from_millis(1))
+        .with_timeout(Duration::from_millis(20))
+        .wait(&stuck, "batch")
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("did not complete"));
+}
@@ -0,0 +1,323 @@
+//! Tests for continuing answers cut off at the output token limit
+
+use async_trait::async_trait;
+use oli_server::apis::api_client::{
+    continuation_messages, stitch_continuation, ApiClient, ApiClientEnum, CompletionOptions,
+    FinishReason, Message, ToolCall, ToolResult, CONTINUE_PROMPT, MAX_CONTINUATIONS,
+};
+use oli_server::apis::streaming::{DeltaHandler, TokenUsage};
+use serde_json::json;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// One answer: its text, tool calls and why it stopped
+type Part = (String, Option<Vec<ToolCall>>, FinishReason);
+
+/// Answers with queued parts, recording each request it receives
+#[derive(Default)]
+struct PartsApiClient {
+    parts: Mutex<VecDeque<Part>>,
+    requests: Mutex<Vec<(Vec<Message>, bool)>>,
+}
+
+impl PartsApiClient {
+    fn new(parts: Vec<(&str, FinishReason)>) -> Arc<Self> {
+        Arc::new(Self {
+            parts: Mutex::new(
+                parts
+                    .into_iter()
+                    .map(|(text, finish)| (text.to_string(), None, finish))
+                    .collect(),
+            ),
+            requests: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn next_part(&self, messages: Vec<Message>, has_tool_results: bool) -> Part {
+        self.requests
+            .lock()
+            .unwrap()
+            .push((messages, has_tool_results));
+        self.parts
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| ("!".to_string(), None, FinishReason::Length))
+    }
+
+    fn request_count(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+}
+
+#[async_trait]
+impl ApiClient for PartsApiClient {
+    async fn complete(
+        &self,
+        messages: Vec<Message>,
+        options: CompletionOptions,
+    ) -> anyhow::Result<String> {
+        Ok(self.complete_with_finish(messages, options).await?.0)
+    }
+
+    async fn complete_with_tools(
+        &self,
+        messages: Vec<Message>,
+        _options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+    ) -> anyhow::Result<(String, Option<Vec<ToolCall>>)> {
+        let (content, tool_calls, _) = self.next_part(messages, tool_results.is_some());
+        Ok((content, tool_calls))
+    }
+
+    async fn complete_with_finish(
+        &self,
+        messages: Vec<Message>,
+        _options: CompletionOptions,
+    ) -> anyhow::Result<(String, FinishReason)> {
+        let (content, _, finish) = self.next_part(messages, false);
+        Ok((content, finish))
+    }
+
+    async fn complete_with_tools_and_finish(
+        &self,
+        messages: Vec<Message>,
+        _options: CompletionOptions,
+        tool_results: Option<Vec<ToolResult>>,
+    ) -> anyhow::Result<(String, Option<Vec<ToolCall>>, FinishReason)> {
+        Ok(self.next_part(messages, tool_results.is_some()))
+    }
+
+    async fn complete_streaming_with_finish(
+        &self,
+        messages: Vec<Message>,
+        _options: CompletionOptions,
+        on_delta: DeltaHandler<'_>,
+    ) -> anyhow::Result<(String, TokenUsage, FinishReason)> {
+        let (content, _, finish) = self.next_part(messages, false);
+        on_delta(&content);
+        let usage = TokenUsage {
+            input_tokens: 10,
+            output_tokens: 5,
+        };
+        Ok((content, usage, finish))
+    }
+}
+
+fn messages() -> Vec<Message> {
+    vec![Message::user("Write a long story".to_string())]
+}
+
+#[test]
+fn test_finish_reasons_from_each_provider() {
+    // Anthropic, OpenAI and Ollama, Gemini
+    assert_eq!(
+        FinishReason::from_provider("max_tokens"),
+        FinishReason::Length
+    );
+    assert_eq!(FinishReason::from_provider("length"), FinishReason::Length);
+    assert_eq!(
+        FinishReason::from_provider("MAX_TOKENS"),
+        FinishReason::Length
+    );
+
+    assert_eq!(
+        FinishReason::from_provider("tool_use"),
+        FinishReason::ToolUse
+    );
+    assert_eq!(
+        FinishReason::from_provider("tool_calls"),
+        FinishReason::ToolUse
+    );
+    assert_eq!(FinishReason::from_provider("end_turn"), FinishReason::Stop);
+    assert_eq!(FinishReason::from_provider("STOP"), FinishReason::Stop);
+    assert!(FinishReason::Length.is_truncated());
+    assert!(!FinishReason::Stop.is_truncated());
+}
+
+#[test]
+fn test_stitch_drops_repeated_overlap() {
+    assert_eq!(
+        stitch_continuation("The quick brown fox jumps over", " the lazy dog."),
+        "The quick brown fox jumps over the lazy dog."
+    );
+    assert_eq!(
+        stitch_continuation(
+            "The quick brown fox jumps over",
+            "brown fox jumps over the lazy dog."
+        ),
+        "The quick brown fox jumps over the lazy dog."
+    );
+    // Short coincidental repeats are kept
+    assert_eq!(
+        stitch_continuation("one, two", "two, three"),
+        "one, twotwo, three"
+    );
+    // Multi-byte characters at the overlap boundary don't panic
+    assert_eq!(
+        stitch_continuation("Ünïcödé — tëxt ", "— tëxt and more"),
+        "Ünïcödé — tëxt — tëxt and more"
+    );
+}
+
+#[test]
+fn test_continuation_messages_carry_the_answer_so_far() {
+    let continued = continuation_messages(&messages(), "Once upon a time");
+
+    assert_eq!(continued.len(), 3);
+    assert_eq!(
+        continued[1],
+        Message::assistant("Once upon a time".to_string())
+    );
+    assert_eq!(continued[2], Message::user(CONTINUE_PROMPT.to_string()));
+}
+
+#[tokio::test]
+async fn test_truncated_answers_are_continued_and_stitched() {
+    let client = PartsApiClient::new(vec![
+        ("Once upon a time, ", FinishReason::Length),
+        ("there was a crate.", FinishReason::Stop),
+    ]);
+    let api = ApiClientEnum::custom_for_testing(client.clone());
+
+    let content = api
+        .complete(messages(), CompletionOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(content, "Once upon a time, there was a crate.");
+    let requests = client.requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(
+        requests[1].0,
+        continuation_messages(&messages(), "Once upon a time, ")
+    );
+}
+
+#[tokio::test]
+async fn test_continuations_are_bounded() {
+    let client = PartsApiClient::new(Vec::new());
+    let api = ApiClientEnum::custom_for_testing(client.clone());
+
+    let content = api
+        .complete(messages(), CompletionOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(client.request_count(), 1 + MAX_CONTINUATIONS);
+    assert_eq!(content, "!".repeat(1 + MAX_CONTINUATIONS));
+}
+
+#[tokio::test]
+async fn test_streamed_continuations_follow_the_cut_off_text() {
+    let client = PartsApiClient::new(vec![
+        ("Once upon ", FinishReason::Length),
+        ("a time.", FinishReason::Stop),
+    ]);
+    let api = ApiClientEnum::custom_for_testing(client.clone());
+
+    let mut deltas = Vec::new();
+    let (content, usage) = api
+        .complete_streaming(messages(), CompletionOptions::default(), &mut |delta| {
+            deltas.push(delta.to_string())
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(deltas, vec!["Once upon ", "a time."]);
+    assert_eq!(content, "Once upon a time.");
+    assert_eq!(
+        usage,
+        TokenUsage {
+            input_tokens: 20,
+            output_tokens: 10
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_tool_completions_continue_text_without_tool_results() {
+    let client = PartsApiClient::new(vec![
+        ("Summary part one, ", FinishReason::Length),
+        ("part two.", FinishReason::Stop),
+    ]);
+    let api = ApiClientEnum::custom_for_testing(client.clone());
+    let results = vec![ToolResult {
+        tool_call_id: "call-1".to_string(),
+        output: "file contents".to_string(),
+    }];
+
+    let (content, tool_calls) = api
+        .complete_with_tools(messages(), CompletionOptions::default(), Some(results))
+        .await
+        .unwrap();
+
+    assert_eq!(content, "Summary part one, part two.");
+    assert!(tool_calls.is_none());
+    let requests = client.requests.lock().unwrap();
+    assert!(requests[0].1, "the first request carries the tool results");
+    assert!(!requests[1].1, "the continuation doesn't repeat them");
+}
+
+#[tokio::test]
+async fn test_tool_calls_are_not_continued() {
+    let call = ToolCall {
+        id: Some("call-1".to_string()),
+        name: "Read".to_string(),
+        arguments: json!({ "file_path": "src/lib.rs" }),
+    };
+    let client = Arc::new(PartsApiClient {
+        parts: Mutex::new(VecDeque::from([(
+            "Reading".to_string(),
+            Some(vec![call]),
+            FinishReason::Length,
+        )])),
+        requests: Mutex::new(Vec::new()),
+    });
+    let api = ApiClientEnum::custom_for_testing(client.clone());
+
+    let (_, tool_calls) = api
+        .complete_with_tools(messages(), CompletionOptions::default(), None)
+        .await
+        .unwrap();
+
+    assert_eq!(tool_calls.unwrap().len(), 1);
+    assert_eq!(client.request_count(), 1);
+}
+
+#[tokio::test]
+async fn test_clients_without_finish_reasons_are_not_continued() {
+    struct PlainApiClient;
+
+    #[async_trait]
+    impl ApiClient for PlainApiClient {
+        async fn complete(
+            &self,
+            _messages: Vec<Message>,
+            _options: CompletionOptions,
+        ) -> anyhow::Result<String> {
+            Ok("Whole answer".to_string())
+        }
+
+        async fn complete_with_tools(
+            &self,
+            _messages: Vec<Message>,
+            _options: CompletionOptions,
+            _tool_results: Option<Vec<ToolResult>>,
+        ) -> anyhow::Result<(String, Option<Vec<ToolCall>>)> {
+            Ok(("Whole answer".to_string(), None))
+        }
+    }
+
+    let api = ApiClientEnum::custom_for_testing(Arc::new(PlainApiClient));
+    let mut deltas = Vec::new();
+    let (content, _) = api
+        .complete_streaming(messages(), CompletionOptions::default(), &mut |delta| {
+            deltas.push(delta.to_string())
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(content, "Whole answer");
+    assert_eq!(deltas, vec!["Whole answer"]);
+}
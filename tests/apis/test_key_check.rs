@@ -0,0 +1,163 @@
+//! Tests for diagnosing provider API keys
+
+use oli_server::agent::core::LLMProvider;
+use oli_server::apis::key_check::{
+    check_key_format, classify_status, diagnose_api_key, key_env_var, KeyDiagnostics, KeyProblem,
+};
+
+#[test]
+fn test_check_key_format() {
+    let anthropic = format!("sk-ant-api03-{}", "a".repeat(40));
+    assert_eq!(check_key_format(&LLMProvider::Anthropic, &anthropic), None);
+    assert_eq!(
+        check_key_format(&LLMProvider::Anthropic, ""),
+        Some(KeyProblem::Missing)
+    );
+    // Pasted with quotes, or with a trailing newline
+    assert_eq!(
+        check_key_format(&LLMProvider::Anthropic, &format!("\"{anthropic}\"")),
+        Some(KeyProblem::Malformed)
+    );
+    assert_eq!(
+        check_key_format(&LLMProvider::Anthropic, &format!("{anthropic}\n")),
+        Some(KeyProblem::Malformed)
+    );
+
+    // A key for another provider
+    assert_eq!(
+        check_key_format(&LLMProvider::OpenAI, &anthropic),
+        Some(KeyProblem::Malformed)
+    );
+    assert_eq!(
+        check_key_format(&LLMProvider::OpenAI, "sk-proj-abc123"),
+        None
+    );
+
+    let gemini = format!("AIza{}", "b".repeat(35));
+    assert_eq!(check_key_format(&LLMProvider::Gemini, &gemini), None);
+    assert_eq!(
+        check_key_format(&LLMProvider::Gemini, "AIzashort"),
+        Some(KeyProblem::Malformed)
+    );
+
+    // OpenRouter keys also start with "sk-"
+    let openrouter = format!("sk-or-v1-{}", "c".repeat(64));
+    assert_eq!(
+        check_key_format(&LLMProvider::OpenRouter, &openrouter),
+        None
+    );
+    assert_eq!(
+        check_key_format(&LLMProvider::OpenAI, &openrouter),
+        Some(KeyProblem::Malformed)
+    );
+    assert_eq!(
+        check_key_format(&LLMProvider::OpenRouter, "sk-proj-abc123"),
+        Some(KeyProblem::Malformed)
+    );
+
+    // Azure keys have no prefix
+    let azure = "0123456789abcdef".repeat(2);
+    assert_eq!(check_key_format(&LLMProvider::AzureOpenAI, &azure), None);
+    assert_eq!(
+        check_key_format(&LLMProvider::AzureOpenAI, "sk-proj-abc123"),
+        Some(KeyProblem::Malformed)
+    );
+
+    // OpenAI-compatible servers take any key, or none
+    assert_eq!(
+        check_key_format(&LLMProvider::OpenAICompatible, "xai-abc123"),
+        None
+    );
+    assert_eq!(check_key_format(&LLMProvider::OpenAICompatible, ""), None);
+    assert_eq!(
+        check_key_format(&LLMProvider::OpenAICompatible, "\"sk-abc\""),
+        Some(KeyProblem::Malformed)
+    );
+}
+
+#[test]
+fn test_classify_status() {
+    let provider = LLMProvider::OpenAI;
+    assert_eq!(classify_status(&provider, 200), None);
+    assert_eq!(
+        classify_status(&provider, 401),
+        Some(KeyProblem::Unauthorized)
+    );
+    assert_eq!(
+        classify_status(&provider, 403),
+        Some(KeyProblem::Unauthorized)
+    );
+    assert_eq!(
+        classify_status(&provider, 429),
+        Some(KeyProblem::OutOfQuota)
+    );
+    assert_eq!(
+        classify_status(&provider, 402),
+        Some(KeyProblem::OutOfQuota)
+    );
+    assert_eq!(
+        classify_status(&provider, 500),
+        Some(KeyProblem::Unreachable)
+    );
+
+    // Google rejects invalid keys with 400
+    assert_eq!(
+        classify_status(&LLMProvider::Gemini, 400),
+        Some(KeyProblem::Unauthorized)
+    );
+    assert_eq!(
+        classify_status(&provider, 400),
+        Some(KeyProblem::Unreachable)
+    );
+}
+
+#[test]
+fn test_report_lists_next_steps() {
+    let diagnostics = KeyDiagnostics::new(
+        &LLMProvider::Anthropic,
+        Some(KeyProblem::Unauthorized),
+        Some("HTTP 401 Unauthorized".to_string()),
+    );
+    assert!(!diagnostics.is_healthy());
+    assert_eq!(diagnostics.env_var, "ANTHROPIC_API_KEY");
+
+    let report = diagnostics.report();
+    assert!(report.contains("✗ Anthropic rejected the key in ANTHROPIC_API_KEY (HTTP 401"));
+    assert!(report.contains("console.anthropic.com"));
+    assert!(report.contains("/key <your key>"));
+
+    let quota = KeyDiagnostics::new(&LLMProvider::OpenAI, Some(KeyProblem::OutOfQuota), None);
+    assert!(quota.report().contains("out of quota"));
+    assert!(quota.report().contains("/model"));
+
+    let healthy = KeyDiagnostics::new(&LLMProvider::Gemini, None, None);
+    assert!(healthy.is_healthy());
+    assert!(healthy.next_steps.is_empty());
+    assert!(healthy.report().contains("✓ Key accepted"));
+}
+
+#[test]
+fn test_key_env_var() {
+    assert_eq!(key_env_var(&LLMProvider::OpenAI), Some("OPENAI_API_KEY"));
+    assert_eq!(key_env_var(&LLMProvider::Gemini), Some("GEMINI_API_KEY"));
+    assert_eq!(
+        key_env_var(&LLMProvider::OpenRouter),
+        Some("OPENROUTER_API_KEY")
+    );
+    assert_eq!(
+        key_env_var(&LLMProvider::AzureOpenAI),
+        Some("AZURE_OPENAI_API_KEY")
+    );
+    assert_eq!(
+        key_env_var(&LLMProvider::OpenAICompatible),
+        Some("OPENAI_COMPATIBLE_API_KEY")
+    );
+    assert_eq!(key_env_var(&LLMProvider::Ollama), None);
+}
+
+#[tokio::test]
+async fn test_diagnose_malformed_key_without_a_request() {
+    let diagnostics = diagnose_api_key(&LLMProvider::Anthropic, "not-a-key").await;
+    assert_eq!(diagnostics.problem, Some(KeyProblem::Malformed));
+    assert_eq!(diagnostics.detail, None);
+}
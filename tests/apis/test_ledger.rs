@@ -0,0 +1,229 @@
+//! Tests for the per-request ledger and its cost calculation
+
+use oli_server::apis::ledger::{
+    model_price, record_request, to_csv, LedgerTotals, RequestLedger, RequestRecord, RequestUsage,
+    UsageMeter, CSV_HEADER,
+};
+use oli_server::apis::streaming::TokenUsage;
+use serde_json::json;
+
+fn record(timestamp: &str, model: &str, cost_usd: Option<f64>) -> RequestRecord {
+    RequestRecord {
+        timestamp: timestamp.to_string(),
+        provider: "anthropic".to_string(),
+        model: model.to_string(),
+        input_tokens: 1000,
+        output_tokens: 200,
+        cache_creation_tokens: 0,
+        cache_read_tokens: 500,
+        latency_ms: 1200,
+        cost_usd,
+        estimated: false,
+    }
+}
+
+#[test]
+fn test_usage_from_provider_reports() {
+    let anthropic = RequestUsage::from_anthropic(&json!({
+        "input_tokens": 120,
+        "output_tokens": 40,
+        "cache_creation_input_tokens": 900,
+        "cache_read_input_tokens": 3000
+    }));
+    assert_eq!(
+        anthropic,
+        RequestUsage {
+            input_tokens: 120,
+            output_tokens: 40,
+            cache_creation_tokens: 900,
+            cache_read_tokens: 3000,
+            estimated: false,
+        }
+    );
+
+    // OpenAI counts cached tokens as part of the prompt
+    let openai = RequestUsage::from_openai(&json!({
+        "prompt_tokens": 2000,
+        "completion_tokens": 50,
+        "prompt_tokens_details": { "cached_tokens": 1500 }
+    }));
+    assert_eq!(openai.input_tokens, 500);
+    assert_eq!(openai.cache_read_tokens, 1500);
+    assert_eq!(openai.output_tokens, 50);
+
+    let estimated = RequestUsage::estimated(TokenUsage {
+        input_tokens: 10,
+        output_tokens: 5,
+    });
+    assert!(estimated.estimated);
+    assert_eq!(estimated.input_tokens, 10);
+}
+
+#[test]
+fn test_cost_uses_the_model_price() {
+    let usage = RequestUsage {
+        input_tokens: 1_000_000,
+        output_tokens: 100_000,
+        cache_creation_tokens: 0,
+        cache_read_tokens: 1_000_000,
+        estimated: false,
+    };
+    let price = model_price("anthropic", "claude-sonnet-4-20250514").unwrap();
+    assert!((price.cost(&usage) - 4.8).abs() < 1e-9);
+
+    // The more specific name wins over its prefix
+    let mini = model_price("openai", "gpt-4o-mini").unwrap();
+    assert_eq!(mini.input, 0.15);
+
+    // Local models are free; unknown models have no price
+    let record = RequestRecord::new("ollama", "llama3", usage, 10);
+    assert_eq!(record.cost_usd, Some(0.0));
+    assert_eq!(model_price("openai", "some-future-model"), None);
+    assert_eq!(
+        RequestRecord::new("openai", "some-future-model", usage, 10).cost_usd,
+        None
+    );
+}
+
+#[test]
+fn test_ledger_records_and_filters_by_date() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let ledger = RequestLedger::new(temp_dir.path().join("nested").join("requests.jsonl"));
+
+    // Reading a ledger that doesn't exist yet returns nothing
+    assert!(ledger.recent(10).unwrap().is_empty());
+
+    let may = record("2025-05-31T23:59:00+00:00", "claude-sonnet-4", Some(0.01));
+    let june = record("2025-06-02T08:00:00+00:00", "claude-opus-4", Some(0.05));
+    ledger.record(&may).unwrap();
+    ledger.record(&june).unwrap();
+
+    assert_eq!(ledger.recent(1).unwrap(), vec![june.clone()]);
+    assert_eq!(ledger.read_since(Some("2025-06-01")).unwrap(), vec![june]);
+    assert_eq!(ledger.read_since(None).unwrap().len(), 2);
+}
+
+#[test]
+fn test_csv_export_and_totals() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let ledger = RequestLedger::new(temp_dir.path().join("requests.jsonl"));
+    ledger
+        .record(&record(
+            "2025-06-01T00:00:00+00:00",
+            "claude, custom",
+            Some(0.25),
+        ))
+        .unwrap();
+    ledger
+        .record(&record("2025-06-02T00:00:00+00:00", "unknown", None))
+        .unwrap();
+
+    let dest = temp_dir.path().join("june.csv");
+    assert_eq!(ledger.export_csv(&dest, None).unwrap(), 2);
+    let csv = std::fs::read_to_string(&dest).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], CSV_HEADER);
+    // Fields with commas are quoted, and unknown costs are left empty
+    assert_eq!(
+        lines[1],
+        "2025-06-01T00:00:00+00:00,anthropic,\"claude, custom\",1000,200,0,500,1200,0.250000,false"
+    );
+    assert!(lines[2].ends_with(",1200,,false"));
+
+    let records = ledger.read_since(None).unwrap();
+    assert_eq!(to_csv(&records), csv);
+    let totals = LedgerTotals::of(&records);
+    assert_eq!(totals.requests, 2);
+    assert_eq!(totals.input_tokens, 2000);
+    assert_eq!(totals.cache_read_tokens, 1000);
+    assert_eq!(totals.unpriced_requests, 1);
+    assert!((totals.cost_usd - 0.25).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn test_usage_meter_collects_requests_in_scope() {
+    let usage = RequestUsage {
+        input_tokens: 1000,
+        output_tokens: 200,
+        ..RequestUsage::default()
+    };
+    let started = std::time::Instant::now();
+
+    // Requests outside a scope, including in other tasks, aren't counted
+    record_request("anthropic", "claude-sonnet-4", usage, started);
+    let meter = UsageMeter::new();
+    meter
+        .scope(async {
+            record_request("anthropic", "claude-sonnet-4", usage, started);
+            // Spawned tasks are counted when given the current meter
+            let current = UsageMeter::current().unwrap();
+            tokio::spawn(current.scope(async move {
+                record_request("ollama", "qwen2.5-coder", usage, started);
+            }))
+            .await
+            .unwrap();
+            tokio::spawn(async move {
+                record_request("openai", "gpt-4o", usage, started);
+            })
+            .await
+            .unwrap();
+        })
+        .await;
+    assert!(UsageMeter::current().is_none());
+
+    let totals = meter.totals();
+    assert_eq!(totals.requests, 2);
+    assert_eq!(totals.total_input_tokens(), 2000);
+    let by_provider = meter.totals_by_provider();
+    assert_eq!(
+        by_provider.keys().collect::<Vec<_>>(),
+        vec!["anthropic", "ollama"]
+    );
+    assert!((by_provider["anthropic"].cost_usd - 0.006).abs() < 1e-9);
+    assert_eq!(by_provider["ollama"].cost_usd, 0.0);
+
+    // Session totals add up the meters of each run
+    let session = UsageMeter::new();
+    session.extend(&meter);
+    session.extend(&meter);
+    assert_eq!(session.totals().requests, 4);
+}
+
+#[tokio::test]
+async fn test_usage_meter_listener_sees_each_request() {
+    let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let listener_seen = seen.clone();
+    let meter = UsageMeter::new().with_listener(move |record, totals| {
+        listener_seen.lock().unwrap().push((
+            record.model.clone(),
+            totals.requests,
+            totals.output_tokens,
+        ));
+    });
+    let usage = RequestUsage {
+        input_tokens: 100,
+        output_tokens: 20,
+        ..RequestUsage::default()
+    };
+    let started = std::time::Instant::now();
+
+    meter
+        .scope(async {
+            record_request("anthropic", "claude-sonnet-4", usage, started);
+            record_request("openai", "gpt-4o", usage, started);
+        })
+        .await;
+
+    assert_eq!(
+        *seen.lock().unwrap(),
+        vec![
+            ("claude-sonnet-4".to_string(), 1, 20),
+            ("gpt-4o".to_string(), 2, 40)
+        ]
+    );
+
+    // Adding another meter's requests doesn't report them again
+    let session = UsageMeter::new();
+    session.extend(&meter);
+    assert_eq!(seen.lock().unwrap().len(), 2);
+}
@@ -26,3 +26,107 @@ fn test_ollama_with_custom_base_url() {
         "Failed to create Ollama client with custom base URL"
     );
 }
+
+#[test]
+fn test_ollama_version_at_least() {
+    use oli_server::apis::ollama::ollama_version_at_least;
+
+    assert!(ollama_version_at_least("0.5.7", "0.3.0"));
+    assert!(ollama_version_at_least("0.10.1", "0.3.0"));
+    assert!(ollama_version_at_least("0.3", "0.3.0"));
+    assert!(ollama_version_at_least("0.3.0-rc1", "0.3.0"));
+    assert!(!ollama_version_at_least("0.2.8", "0.3.0"));
+}
+
+// Serve canned Ollama responses on a local port, keyed by request path
+async fn serve_ollama_stub(routes: Vec<(&'static str, &'static str)>) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut buffer = vec![0; 8192];
+            let read = socket.read(&mut buffer).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+            let path = request.split_whitespace().nth(1).unwrap_or("").to_string();
+
+            let response = match routes.iter().find(|(route, _)| *route == path) {
+                Some((_, body)) => format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                ),
+                None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string(),
+            };
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+
+    format!("http://{address}")
+}
+
+const TAGS: &str = r#"{"models":[{"name":"qwen2.5-coder:7b","modified_at":"2025-01-01T00:00:00Z","size":1,"digest":"abc"}]}"#;
+
+#[tokio::test]
+async fn test_diagnose_healthy_setup() {
+    let api_base = serve_ollama_stub(vec![
+        ("/api/version", r#"{"version":"0.5.7"}"#),
+        ("/api/tags", TAGS),
+        (
+            "/api/show",
+            r#"{"model_info":{"qwen2.context_length":32768}}"#,
+        ),
+    ])
+    .await;
+
+    let client = OllamaClient::with_base_url("qwen2.5-coder:7b".to_string(), api_base).unwrap();
+    let diagnostics = client.diagnose().await;
+
+    assert!(diagnostics.is_healthy(), "{}", diagnostics.report());
+    assert!(diagnostics.reachable);
+    assert_eq!(diagnostics.version.as_deref(), Some("0.5.7"));
+    assert_eq!(diagnostics.model_available, Some(true));
+    assert_eq!(diagnostics.context_length, Some(32768));
+    assert!(diagnostics.report().contains("context length 32768"));
+}
+
+#[tokio::test]
+async fn test_diagnose_missing_model_and_old_version() {
+    let api_base = serve_ollama_stub(vec![
+        ("/api/version", r#"{"version":"0.2.1"}"#),
+        ("/api/tags", TAGS),
+    ])
+    .await;
+
+    let client = OllamaClient::with_base_url("llama3.2".to_string(), api_base).unwrap();
+    let diagnostics = client.diagnose().await;
+
+    assert!(!diagnostics.is_healthy());
+    assert_eq!(diagnostics.model_available, Some(false));
+    assert_eq!(
+        diagnostics.pull_command.as_deref(),
+        Some("ollama pull llama3.2")
+    );
+    let report = diagnostics.report();
+    assert!(report.contains("too old for tool calling"));
+    assert!(report.contains("is not installed"));
+}
+
+#[tokio::test]
+async fn test_diagnose_unreachable_server() {
+    // Bind and drop a listener to get a port nothing is listening on
+    let address = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let client =
+        OllamaClient::with_base_url("llama3.2".to_string(), format!("http://{address}")).unwrap();
+    let diagnostics = client.diagnose().await;
+
+    assert!(!diagnostics.reachable);
+    assert!(diagnostics.model_available.is_none());
+    assert!(diagnostics.report().contains("ollama serve"));
+}
@@ -0,0 +1,255 @@
+//! Tests for streamed completions
+
+use async_trait::async_trait;
+use oli_server::apis::anthropic::AnthropicClient;
+use oli_server::apis::api_client::{
+    ApiClient, CompletionOptions, FinishReason, Message, ToolCall, ToolResult,
+};
+use oli_server::apis::openai::OpenAIClient;
+use oli_server::apis::streaming::{SseEvent, SseParser, TokenUsage};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Serve a server-sent events body split into several writes, recording the request
+async fn serve_events(chunks: Vec<String>) -> (String, Arc<Mutex<String>>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let received = Arc::new(Mutex::new(String::new()));
+    let received_clone = received.clone();
+
+    tokio::spawn(async move {
+        if let Ok((mut socket, _)) = listener.accept().await {
+            let mut buffer = vec![0; 65536];
+            let read = socket.read(&mut buffer).await.unwrap_or(0);
+            *received_clone.lock().unwrap() = String::from_utf8_lossy(&buffer[..read]).to_string();
+
+            let _ = socket
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n",
+                )
+                .await;
+            for chunk in chunks {
+                let _ = socket.write_all(chunk.as_bytes()).await;
+                let _ = socket.flush().await;
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+    });
+
+    (base, received)
+}
+
+fn messages() -> Vec<Message> {
+    vec![Message::user("Say hello".to_string())]
+}
+
+#[test]
+fn test_sse_parser_handles_split_events() {
+    let mut parser = SseParser::new();
+
+    assert!(parser
+        .push(b"event: message_start\r\ndata: {\"a\"")
+        .is_empty());
+    let events = parser.push(b":1}\r\n\r\n: keep-alive\n\ndata: [DONE]\n\n");
+    assert_eq!(
+        events,
+        vec![
+            SseEvent {
+                event: Some("message_start".to_string()),
+                data: "{\"a\":1}".to_string(),
+            },
+            SseEvent {
+                event: None,
+                data: "[DONE]".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_sse_parser_keeps_characters_split_across_chunks() {
+    let mut parser = SseParser::new();
+    let body = "data: {\"text\":\"café — 😀\"}\n\n".as_bytes();
+
+    // Cut inside each multi-byte character
+    let mut events = Vec::new();
+    let e_acute = body.iter().position(|&b| b == 0xC3).unwrap();
+    let dash = body.iter().position(|&b| b == 0xE2).unwrap();
+    let emoji = body.iter().position(|&b| b == 0xF0).unwrap();
+    for chunk in [
+        &body[..e_acute + 1],
+        &body[e_acute + 1..dash + 2],
+        &body[dash + 2..emoji + 3],
+        &body[emoji + 3..],
+    ] {
+        events.extend(parser.push(chunk));
+    }
+
+    assert_eq!(
+        events,
+        vec![SseEvent {
+            event: None,
+            data: "{\"text\":\"café — 😀\"}".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn test_sse_parser_handles_crlf_split_across_chunks() {
+    let mut parser = SseParser::new();
+
+    assert!(parser.push(b"event: ping\r").is_empty());
+    assert!(parser.push(b"\ndata: one\r").is_empty());
+    assert!(parser.push(b"\ndata: two\r\n\r").is_empty());
+    let events = parser.push(b"\n");
+
+    assert_eq!(
+        events,
+        vec![SseEvent {
+            event: Some("ping".to_string()),
+            data: "one\ntwo".to_string(),
+        }]
+    );
+}
+
+#[tokio::test]
+async fn test_anthropic_streams_text_deltas() {
+    let (base, received) = serve_events(vec![
+        "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":12}}}\n\n".to_string(),
+        "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"Hel\"}}\n\nevent: content_block_delta\ndata: {\"type\":\"content_block_delta\",".to_string(),
+        "\"delta\":{\"type\":\"text_delta\",\"text\":\"lo\"}}\n\n".to_string(),
+        "event: message_delta\ndata: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":2}}\n\nevent: message_stop\ndata: {\"type\":\"message_stop\"}\n\n".to_string(),
+    ])
+    .await;
+    let client = AnthropicClient::with_api_key("test".to_string(), None)
+        .unwrap()
+        .with_api_base(format!("{base}/v1/messages"));
+
+    let mut deltas = Vec::new();
+    let (content, usage) = client
+        .complete_streaming(messages(), CompletionOptions::default(), &mut |delta| {
+            deltas.push(delta.to_string())
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(deltas, vec!["Hel", "lo"]);
+    assert_eq!(content, "Hello");
+    assert_eq!(
+        usage,
+        TokenUsage {
+            input_tokens: 12,
+            output_tokens: 2
+        }
+    );
+    assert!(received.lock().unwrap().contains("\"stream\":true"));
+}
+
+#[tokio::test]
+async fn test_anthropic_stream_reports_the_stop_reason() {
+    let (base, _) = serve_events(vec![
+        "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"Cut\"}}\n\n".to_string(),
+        "event: message_delta\ndata: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"max_tokens\"},\"usage\":{\"output_tokens\":1}}\n\n".to_string(),
+    ])
+    .await;
+    let client = AnthropicClient::with_api_key("test".to_string(), None)
+        .unwrap()
+        .with_api_base(format!("{base}/v1/messages"));
+
+    let (content, _, finish) = client
+        .complete_streaming_with_finish(messages(), CompletionOptions::default(), &mut |_| {})
+        .await
+        .unwrap();
+
+    assert_eq!(content, "Cut");
+    assert_eq!(finish, FinishReason::Length);
+}
+
+#[tokio::test]
+async fn test_anthropic_stream_error_event() {
+    let (base, _) = serve_events(vec![
+        "event: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n".to_string(),
+    ])
+    .await;
+    let client = AnthropicClient::with_api_key("test".to_string(), None)
+        .unwrap()
+        .with_api_base(format!("{base}/v1/messages"));
+
+    let error = client
+        .complete_streaming(messages(), CompletionOptions::default(), &mut |_| {})
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("Overloaded"));
+}
+
+#[tokio::test]
+async fn test_openai_streams_content_deltas() {
+    let (base, received) = serve_events(vec![
+        "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n".to_string(),
+        "data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\ndata: {\"choices\":[{\"delta\":{\"content\":\" there\"}}]}\n\n".to_string(),
+        "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":8,\"completion_tokens\":3}}\n\ndata: [DONE]\n\n".to_string(),
+    ])
+    .await;
+    let client = OpenAIClient::with_api_key("test".to_string(), None)
+        .unwrap()
+        .with_api_base(format!("{base}/v1/chat/completions"));
+
+    let mut deltas = Vec::new();
+    let (content, usage) = client
+        .complete_streaming(messages(), CompletionOptions::default(), &mut |delta| {
+            deltas.push(delta.to_string())
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(deltas, vec!["Hi", " there"]);
+    assert_eq!(content, "Hi there");
+    assert_eq!(
+        usage,
+        TokenUsage {
+            input_tokens: 8,
+            output_tokens: 3
+        }
+    );
+    assert!(received.lock().unwrap().contains("\"include_usage\":true"));
+}
+
+struct PlainApiClient;
+
+#[async_trait]
+impl ApiClient for PlainApiClient {
+    async fn complete(
+        &self,
+        _messages: Vec<Message>,
+        _options: CompletionOptions,
+    ) -> anyhow::Result<String> {
+        Ok("Whole answer".to_string())
+    }
+
+    async fn complete_with_tools(
+        &self,
+        _messages: Vec<Message>,
+        _options: CompletionOptions,
+        _tool_results: Option<Vec<ToolResult>>,
+    ) -> anyhow::Result<(String, Option<Vec<ToolCall>>)> {
+        Ok((String::new(), None))
+    }
+}
+
+#[tokio::test]
+async fn test_providers_without_streaming_send_one_delta() {
+    let mut deltas = Vec::new();
+    let (content, usage) = PlainApiClient
+        .complete_streaming(messages(), CompletionOptions::default(), &mut |delta| {
+            deltas.push(delta.to_string())
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(deltas, vec!["Whole answer"]);
+    assert_eq!(content, "Whole answer");
+    assert_eq!(usage, TokenUsage::estimate(&messages(), "Whole answer"));
+    assert!(usage.output_tokens > 0);
+}
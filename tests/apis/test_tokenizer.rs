@@ -0,0 +1,46 @@
+use oli_server::apis::api_client::{ImageData, Message};
+use oli_server::apis::tokenizer::{count_message_tokens, count_tokens};
+
+#[test]
+fn test_common_words_are_one_token_each() {
+    assert_eq!(count_tokens(""), 0);
+    // A space joins the word after it, as in tiktoken
+    assert_eq!(count_tokens("the quick brown fox"), 4);
+    assert_eq!(count_tokens("why ".repeat(200).trim_end()), 200);
+}
+
+#[test]
+fn test_long_words_numbers_and_punctuation_split() {
+    // Split into pieces of six letters
+    assert_eq!(count_tokens("internationalization"), 4);
+    // Numbers in groups of three digits
+    assert_eq!(count_tokens("1234567"), 3);
+    // `fn`, ` main`, `()`, ` {`, ` }`
+    assert_eq!(count_tokens("fn main() { }"), 5);
+}
+
+#[test]
+fn test_prose_lands_near_the_real_count() {
+    // 57 tokens with cl100k
+    let text = "Automatic context window management keeps long sessions within the \
+                model's limits. When the history fills most of the window, older \
+                messages are summarized, and if that isn't enough, the oldest ones \
+                are dropped so the next request still fits.";
+    let tokens = count_tokens(text);
+    assert!((50..=65).contains(&tokens), "{tokens}");
+}
+
+#[test]
+fn test_messages_count_their_overhead_and_images() {
+    let messages = vec![Message::user("hello".to_string())];
+    assert_eq!(count_message_tokens(&messages), 5);
+
+    let image = ImageData {
+        media_type: "image/png".to_string(),
+        data: "iVBORw0KGgo".repeat(1000),
+    };
+    let with_image = format!("hello\n\n{}", image.to_block());
+    // The image's base64 data isn't counted as text
+    assert!(count_tokens(&with_image) < 2_000);
+    assert!(count_tokens(&with_image) > count_tokens("hello"));
+}
@@ -1,4 +1,18 @@
+mod test_api_keys;
+mod test_ask;
+mod test_config;
 mod test_core;
 mod test_history;
 mod test_logger;
+mod test_presets;
+mod test_prompt_history;
+mod test_queries;
+mod test_quick_start;
 mod test_scroll;
+mod test_session;
+mod test_session_store;
+mod test_session_summary;
+mod test_storage;
+mod test_summarizer;
+mod test_turn_record;
+mod test_workspace_lock;
@@ -0,0 +1,76 @@
+//! Tests for storing API keys and matching models to providers
+
+use oli_server::agent::core::LLMProvider;
+use oli_server::app::api_keys::{cloud_provider, KeyStore};
+
+#[test]
+fn test_key_store_save_replaces_earlier_value() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let store = KeyStore::new(temp_dir.path().join("nested").join("keys.env"));
+
+    store.save("OLI_TEST_STORED_KEY", "first").unwrap();
+    store.save("OLI_TEST_OTHER_KEY", "other").unwrap();
+    store.save("OLI_TEST_STORED_KEY", "second").unwrap();
+
+    assert_eq!(store.get("OLI_TEST_STORED_KEY").as_deref(), Some("second"));
+    assert_eq!(store.get("OLI_TEST_OTHER_KEY").as_deref(), Some("other"));
+    assert_eq!(store.get("OLI_TEST_MISSING_KEY"), None);
+    let contents = std::fs::read_to_string(store.path()).unwrap();
+    assert_eq!(contents.matches("OLI_TEST_STORED_KEY=").count(), 1);
+
+    // The saved key is used right away
+    assert_eq!(std::env::var("OLI_TEST_STORED_KEY").unwrap(), "second");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(store.path())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
+
+#[test]
+fn test_key_store_load_keeps_existing_environment() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("keys.env");
+    std::fs::write(
+        &path,
+        "OLI_TEST_LOADED_KEY=stored\nOLI_TEST_PRESET_KEY=stored\n",
+    )
+    .unwrap();
+    std::env::set_var("OLI_TEST_PRESET_KEY", "from environment");
+
+    KeyStore::new(path).load();
+    assert_eq!(std::env::var("OLI_TEST_LOADED_KEY").unwrap(), "stored");
+    assert_eq!(
+        std::env::var("OLI_TEST_PRESET_KEY").unwrap(),
+        "from environment"
+    );
+}
+
+#[test]
+fn test_cloud_provider() {
+    assert_eq!(
+        cloud_provider("Claude 4 Sonnet"),
+        Some(LLMProvider::Anthropic)
+    );
+    assert_eq!(cloud_provider("GPT-4o"), Some(LLMProvider::OpenAI));
+    assert_eq!(cloud_provider("Gemini 2.5 Pro"), Some(LLMProvider::Gemini));
+    assert_eq!(cloud_provider("qwen2.5-coder (local)"), None);
+    // OpenRouter serves other vendors' models under their names
+    assert_eq!(
+        cloud_provider("Anthropic: Claude Sonnet 4 (OpenRouter)"),
+        Some(LLMProvider::OpenRouter)
+    );
+    assert_eq!(
+        cloud_provider("Azure OpenAI"),
+        Some(LLMProvider::AzureOpenAI)
+    );
+    assert_eq!(
+        cloud_provider("OpenAI-compatible"),
+        Some(LLMProvider::OpenAICompatible)
+    );
+}
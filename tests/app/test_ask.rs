@@ -0,0 +1,68 @@
+use oli_server::app::ask::{AskAnswer, AskSession, ASK_MODEL_ENV};
+use oli_server::{App, LLMProvider};
+
+fn answer(answer: Option<&str>) -> AskAnswer {
+    AskAnswer {
+        id: "side-1".to_string(),
+        question: "Where are API keys validated?".to_string(),
+        model: "ollama:llama3.2".to_string(),
+        answer: answer.map(str::to_string),
+        error: answer.is_none().then(|| "timed out".to_string()),
+        latency_ms: 1200,
+    }
+}
+
+#[test]
+fn test_ask_model_from_env() {
+    std::env::set_var(ASK_MODEL_ENV, "ollama:llama3.2");
+    let model = AskSession::model().unwrap();
+    assert_eq!(model.provider, LLMProvider::Ollama);
+    assert_eq!(model.model, "llama3.2");
+
+    // A model whose provider can't be inferred is reported rather than skipped
+    std::env::set_var(ASK_MODEL_ENV, "mystery-model");
+    let error = AskSession::model().unwrap_err().to_string();
+    assert!(error.contains(ASK_MODEL_ENV));
+
+    std::env::remove_var(ASK_MODEL_ENV);
+}
+
+#[test]
+fn test_ask_rejects_empty_question() {
+    let asks = AskSession::new();
+    assert!(asks.ask("   ", None).is_err());
+    assert!(asks.get("side-1").is_none());
+}
+
+#[test]
+fn test_promote_answer_adds_to_conversation() {
+    let mut app = App::new();
+    let before = app
+        .session_manager
+        .as_ref()
+        .map(|session| session.messages.len())
+        .unwrap();
+
+    app.promote_answer(&answer(Some("In src/apis/key_check.rs")))
+        .unwrap();
+
+    let session = app.session_manager.as_ref().unwrap();
+    assert_eq!(session.messages.len(), before + 2);
+    assert_eq!(
+        session.messages[before].content,
+        "(Side question) Where are API keys validated?"
+    );
+    assert_eq!(
+        app.messages.last().unwrap(),
+        "[assistant] In src/apis/key_check.rs"
+    );
+}
+
+#[test]
+fn test_promote_answer_without_answer_fails() {
+    let mut app = App::new();
+    let before = app.messages.len();
+
+    assert!(app.promote_answer(&answer(None)).is_err());
+    assert_eq!(app.messages.len(), before);
+}
@@ -0,0 +1,271 @@
+//! Tests for config.toml validation and applying it to the environment
+
+use oli_server::app::config::{ConfigError, ConfigFile, OliConfig};
+use std::path::Path;
+
+fn parse(source: &str) -> Result<OliConfig, ConfigError> {
+    OliConfig::parse(source, Path::new("config.toml"))
+}
+
+#[test]
+fn test_valid_config_maps_to_settings() {
+    let config = parse(
+        r#"
+[model]
+temperature = 0.2
+max_tokens = 4096
+ollama = "llama3.2"
+prompt_caching = false
+
+[shell]
+program = "zsh"
+login = true
+
+[workspace]
+large_repo = 50000
+"#,
+    )
+    .unwrap();
+
+    let settings = config.settings();
+    assert!(settings.contains(&("OLI_SHELL", "zsh".to_string())));
+    assert!(settings.contains(&("OLI_SHELL_LOGIN", "true".to_string())));
+    assert!(settings.contains(&("OLI_LARGE_REPO", "50000".to_string())));
+    assert!(settings.contains(&("OLI_OLLAMA_MODEL", "llama3.2".to_string())));
+    assert!(settings.contains(&("OLI_PROMPT_CACHING", "false".to_string())));
+
+    let parameters = config.model_parameters();
+    assert_eq!(parameters.temperature, Some(0.2));
+    assert_eq!(parameters.max_tokens, Some(4096));
+    assert_eq!(parameters.top_p, None);
+}
+
+#[test]
+fn test_unknown_key_points_at_its_position() {
+    let error = parse("[model]\ntemperature = 0.5\ntempreature = 0.7\n").unwrap_err();
+    assert_eq!(error.issues.len(), 1);
+    let issue = &error.issues[0];
+    assert_eq!((issue.line, issue.column, issue.width), (3, 1, 11));
+    assert!(issue.message.contains("unknown field `tempreature`"));
+    assert!(issue.message.contains("`temperature`"));
+
+    // The rendered error quotes the line and underlines the key
+    let rendered = error.to_string();
+    assert!(rendered.starts_with("config.toml:3:1: unknown field"));
+    assert!(rendered.contains("3 | tempreature = 0.7\n  | ^^^^^^^^^^^"));
+}
+
+#[test]
+fn test_wrong_type_is_reported() {
+    let error = parse("[shell]\nlogin = \"yes\"\n").unwrap_err();
+    assert_eq!(error.issues[0].line, 2);
+    assert!(error.issues[0].message.contains("invalid type"));
+}
+
+#[test]
+fn test_every_out_of_range_value_is_reported() {
+    let error = parse(
+        r#"[model]
+temperature = 3.5
+top_p = 0.9
+
+[agent]
+second_instance = "readonly"
+
+[approval]
+webhook = "example.com/approve"
+timeout_secs = 0
+"#,
+    )
+    .unwrap_err();
+
+    let messages: Vec<(usize, &str)> = error
+        .issues
+        .iter()
+        .map(|issue| (issue.line, issue.message.as_str()))
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            (2, "`temperature` must be between 0 and 2, got 3.5"),
+            (
+                6,
+                "`second_instance` must be one of \"warn\", \"read_only\""
+            ),
+            (10, "`timeout_secs` must be between 1 and 3600, got 0"),
+            (9, "`webhook` must be an http:// or https:// URL"),
+        ]
+    );
+    // Values are underlined, quotes included
+    assert_eq!(error.issues[1].column, 19);
+    assert_eq!(error.issues[1].width, 10);
+}
+
+#[test]
+fn test_presets_are_checked() {
+    let config = parse("[model]\npresets = \"agent=precise\"\n").unwrap();
+    assert!(config
+        .settings()
+        .contains(&("OLI_SAMPLING_PRESETS", "agent=precise".to_string())));
+
+    let error = parse("[model]\npresets = \"agent=wild\"\n").unwrap_err();
+    assert_eq!(error.issues[0].line, 2);
+    assert!(error.issues[0].message.contains("Unknown preset 'wild'"));
+}
+
+#[test]
+fn test_permissions_are_checked() {
+    let config = parse(
+        "[permissions]\ntools = { Bash = \"deny\", Edit = \"auto\" }\nedit_paths = [\"src/**\", \"!**/*.lock\"]\n",
+    )
+    .unwrap();
+    let settings = config.settings();
+    assert!(settings.contains(&("OLI_TOOL_PERMISSIONS", "Bash=deny,Edit=auto".to_string())));
+    assert!(settings.contains(&("OLI_EDIT_PATHS", "src/**,!**/*.lock".to_string())));
+
+    let error =
+        parse("[permissions]\ntools = { Bash = \"never\" }\nedit_paths = [\"a,b\"]\n").unwrap_err();
+    let messages: Vec<(usize, &str)> = error
+        .issues
+        .iter()
+        .map(|issue| (issue.line, issue.message.as_str()))
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            (
+                2,
+                "`tools.Bash`: Unknown permission level 'never'; use auto, ask or deny"
+            ),
+            (3, "`edit_paths` globs can't contain commas"),
+        ]
+    );
+}
+
+#[test]
+fn test_azure_section_maps_to_its_variables() {
+    let config = parse(
+        "[azure]\nendpoint = \"https://my-resource.openai.azure.com\"\ndeployment = \"gpt-4o-mini\"\n",
+    )
+    .unwrap();
+    let settings = config.settings();
+    assert!(settings.contains(&(
+        "AZURE_OPENAI_ENDPOINT",
+        "https://my-resource.openai.azure.com".to_string()
+    )));
+    assert!(settings.contains(&("AZURE_OPENAI_DEPLOYMENT", "gpt-4o-mini".to_string())));
+
+    let error = parse("[azure]\nendpoint = \"my-resource\"\n").unwrap_err();
+    assert_eq!(error.issues[0].line, 2);
+    assert_eq!(
+        error.issues[0].message,
+        "`endpoint` must be an https:// URL"
+    );
+}
+
+#[test]
+fn test_openai_compatible_section_maps_to_its_variables() {
+    let config = parse(
+        "[openai_compatible]\nbase_url = \"http://localhost:1234/v1\"\nmodel = \"qwen2.5-coder-7b\"\n",
+    )
+    .unwrap();
+    let settings = config.settings();
+    assert!(settings.contains(&(
+        "OPENAI_COMPATIBLE_BASE_URL",
+        "http://localhost:1234/v1".to_string()
+    )));
+    assert!(settings.contains(&("OPENAI_COMPATIBLE_MODEL", "qwen2.5-coder-7b".to_string())));
+
+    let error = parse("[openai_compatible]\nbase_url = \"api.deepseek.com\"\n").unwrap_err();
+    assert_eq!(error.issues[0].line, 2);
+    assert_eq!(
+        error.issues[0].message,
+        "`base_url` must be an http:// or https:// URL"
+    );
+}
+
+#[test]
+fn test_ui_theme_is_checked() {
+    let config = parse("[ui]\ntheme = \"solarized\"\n").unwrap();
+    assert!(config
+        .settings()
+        .contains(&("OLI_THEME", "solarized".to_string())));
+
+    let error = parse("[ui]\ntheme = \"neon\"\n").unwrap_err();
+    assert_eq!(error.issues[0].line, 2);
+    assert_eq!(
+        error.issues[0].message,
+        "`theme` must be one of \"dark\", \"light\", \"high-contrast\", \"solarized\""
+    );
+}
+
+#[test]
+fn test_ui_scroll_bindings_are_checked() {
+    let config = parse("[ui]\nscroll_bindings = \"ctrl+k=line_up\"\n").unwrap();
+    assert!(config
+        .settings()
+        .contains(&("OLI_SCROLL_BINDINGS", "ctrl+k=line_up".to_string())));
+
+    let error = parse("[ui]\nscroll_bindings = \"ctrl+k=sideways\"\n").unwrap_err();
+    assert_eq!(error.issues[0].line, 2);
+    assert_eq!(
+        error.issues[0].message,
+        "`scroll_bindings`: Unknown scroll action 'sideways'"
+    );
+}
+
+#[test]
+fn test_auto_compact_percent_is_checked() {
+    let config = parse("[agent]\nauto_compact_percent = 60\n").unwrap();
+    assert!(config
+        .settings()
+        .contains(&("OLI_AUTO_COMPACT_PERCENT", "60".to_string())));
+
+    let error = parse("[agent]\nauto_compact_percent = 150\n").unwrap_err();
+    assert_eq!(
+        error.issues[0].message,
+        "`auto_compact_percent` must be between 0 and 100, got 150"
+    );
+}
+
+#[test]
+fn test_missing_file_loads_as_none() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file = ConfigFile::new(temp_dir.path().join("config.toml"));
+    assert!(file.load().unwrap().is_none());
+
+    std::fs::write(file.path(), "[files]\naudit_log = 5\n").unwrap();
+    let error = file.load().unwrap_err();
+    let error = error.downcast_ref::<ConfigError>().unwrap();
+    assert_eq!(error.path, file.path());
+}
+
+#[test]
+fn test_apply_respects_the_environment_and_reloads() {
+    // The request ledger location is only read at startup, so no other test uses it
+    std::env::remove_var("OLI_REQUEST_LEDGER");
+    let first = parse("[files]\nrequest_ledger = \"/tmp/a.jsonl\"\n").unwrap();
+    let applied = first.apply();
+    assert_eq!(applied.applied, vec!["OLI_REQUEST_LEDGER"]);
+    assert_eq!(std::env::var("OLI_REQUEST_LEDGER").unwrap(), "/tmp/a.jsonl");
+
+    // A reload replaces the value it set, and notes it needs a restart
+    let second = parse("[files]\nrequest_ledger = \"/tmp/b.jsonl\"\n").unwrap();
+    let applied = second.apply();
+    assert_eq!(applied.restart_required, vec!["OLI_REQUEST_LEDGER"]);
+    assert_eq!(std::env::var("OLI_REQUEST_LEDGER").unwrap(), "/tmp/b.jsonl");
+
+    // Removing the setting unsets it
+    parse("").unwrap().apply();
+    assert!(std::env::var("OLI_REQUEST_LEDGER").is_err());
+
+    // A value set outside the config wins
+    std::env::set_var("OLI_REQUEST_LEDGER", "/tmp/env.jsonl");
+    let applied = second.apply();
+    assert_eq!(applied.overridden, vec!["OLI_REQUEST_LEDGER"]);
+    assert_eq!(
+        std::env::var("OLI_REQUEST_LEDGER").unwrap(),
+        "/tmp/env.jsonl"
+    );
+    std::env::remove_var("OLI_REQUEST_LEDGER");
+}
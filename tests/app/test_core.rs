@@ -1,6 +1,7 @@
 use anyhow::Result;
 use oli_server::apis::api_client::SessionManager;
-use oli_server::app::core::{App, TaskStatus, ToolExecutionStatus};
+use oli_server::apis::ledger::{LedgerTotals, RequestRecord, RequestUsage};
+use oli_server::app::core::{App, Task, TaskStatus, ToolExecutionStatus};
 use oli_server::models::ModelConfig;
 use std::{collections::HashMap, env};
 
@@ -36,6 +37,7 @@ fn test_local_model_no_api_key_required() -> Result<()> {
         description: "Test local model via Ollama".into(),
         recommended_for: "Testing".into(),
         supports_agent: true,
+        context_window: None,
     }];
 
     // Ensure no API keys are set in the environment
@@ -75,6 +77,7 @@ fn test_cloud_model_requires_api_key() -> Result<()> {
         description: "Test Claude model".into(),
         recommended_for: "Testing".into(),
         supports_agent: true,
+        context_window: None,
     }];
 
     // Ensure no API keys are set in the environment
@@ -294,6 +297,60 @@ fn test_task_management() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_task_turn_summary() -> Result<()> {
+    let mut task = Task::new("Summarize me");
+
+    // In-progress tasks have no footer yet
+    assert!(task.turn_summary().is_none());
+
+    task.status = TaskStatus::Completed {
+        duration_secs: 34,
+        tool_uses: 3,
+        input_tokens: 12_000,
+        output_tokens: 400,
+    };
+    assert_eq!(
+        task.turn_summary().as_deref(),
+        Some("answered in 34s, 3 tool calls, 12.4k tokens")
+    );
+
+    task.status = TaskStatus::Completed {
+        duration_secs: 125,
+        tool_uses: 1,
+        input_tokens: 500,
+        output_tokens: 20,
+    };
+    assert_eq!(
+        task.turn_summary().as_deref(),
+        Some("answered in 2m 05s, 1 tool call, 520 tokens")
+    );
+
+    // Usage reported by the providers replaces the estimates and adds the cost
+    let mut task = Task::new("Reported");
+    task.record_usage(&LedgerTotals {
+        requests: 2,
+        input_tokens: 1_000,
+        output_tokens: 300,
+        cache_read_tokens: 4_000,
+        cost_usd: 0.0342,
+        ..LedgerTotals::default()
+    });
+    assert_eq!((task.input_tokens, task.output_tokens), (5_000, 300));
+    task.status = TaskStatus::Completed {
+        duration_secs: 34,
+        tool_uses: 3,
+        input_tokens: task.input_tokens,
+        output_tokens: task.output_tokens,
+    };
+    assert_eq!(
+        task.turn_summary().as_deref(),
+        Some("answered in 34s, 3 tool calls, 5.3k tokens, $0.03")
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_task_failure() -> Result<()> {
     // Test task failure handling
@@ -373,3 +430,65 @@ fn test_tool_execution_tracking() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_set_model_parameter() -> Result<()> {
+    let mut app = setup_app()?;
+
+    let params = app.set_model_parameter("temperature", "0.3")?;
+    assert_eq!(params.temperature, Some(0.3));
+    assert_eq!(app.model_parameters.temperature, Some(0.3));
+
+    // Invalid values leave the session settings untouched
+    assert!(app.set_model_parameter("temperature", "5").is_err());
+    assert_eq!(app.model_parameters.temperature, Some(0.3));
+
+    Ok(())
+}
+
+#[test]
+fn test_model_prices_and_session_cost() -> Result<()> {
+    let model = |name: &str, file_name: &str| ModelConfig {
+        name: name.into(),
+        file_name: file_name.into(),
+        description: String::new(),
+        recommended_for: String::new(),
+        supports_agent: true,
+        context_window: None,
+    };
+    let sonnet = model("Claude 4 Sonnet", "claude-sonnet-4-20250514")
+        .price()
+        .unwrap();
+    assert_eq!((sonnet.input, sonnet.output), (3.0, 15.0));
+    assert_eq!(
+        model("llama3 (local)", "llama3").price().unwrap().input,
+        0.0
+    );
+    assert!(model("DeepSeek (OpenRouter)", "deepseek/deepseek-chat")
+        .price()
+        .is_none());
+
+    let app = setup_app()?;
+    assert_eq!(app.session_cost_usd(), None);
+
+    let usage = RequestUsage {
+        input_tokens: 1_000_000,
+        output_tokens: 100_000,
+        ..RequestUsage::default()
+    };
+    app.session_usage.record(RequestRecord::new(
+        "anthropic",
+        "claude-sonnet-4-20250514",
+        usage,
+        10,
+    ));
+    app.session_usage.record(RequestRecord::new(
+        "openrouter",
+        "deepseek/deepseek-chat",
+        usage,
+        10,
+    ));
+    assert_eq!(app.session_cost_usd(), Some(4.5));
+
+    Ok(())
+}
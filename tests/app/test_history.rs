@@ -1,6 +1,41 @@
-use oli_server::apis::api_client::SessionManager;
-use oli_server::app::history::{ContextCompressor, ConversationSummary};
+use oli_server::apis::api_client::{
+    ApiClient, ApiClientEnum, CompletionOptions, Message, SessionManager, ToolCall, ToolResult,
+};
+use oli_server::app::history::{
+    ContextCompressor, ContextUsage, ConversationSummary, COMPACT_MODEL_ENV,
+};
+use oli_server::models::ModelConfig;
 use oli_server::{Agent, App, AppState, LLMProvider};
+use std::sync::{Arc, Mutex};
+
+// Writes a short summary, keeping the prompt it was asked with
+#[derive(Default)]
+struct SummaryClient {
+    prompt: Mutex<String>,
+}
+
+#[async_trait::async_trait]
+impl ApiClient for SummaryClient {
+    async fn complete(
+        &self,
+        _messages: Vec<Message>,
+        _options: CompletionOptions,
+    ) -> anyhow::Result<String> {
+        Ok(String::new())
+    }
+
+    async fn complete_with_tools(
+        &self,
+        messages: Vec<Message>,
+        _options: CompletionOptions,
+        _tool_results: Option<Vec<ToolResult>>,
+    ) -> anyhow::Result<(String, Option<Vec<ToolCall>>)> {
+        if let Some(prompt) = messages.iter().rev().find(|msg| msg.role == "user") {
+            *self.prompt.lock().unwrap() = prompt.content.clone();
+        }
+        Ok(("The user is fixing the config parser.".to_string(), None))
+    }
+}
 
 #[test]
 fn test_conversation_char_count() {
@@ -18,30 +53,109 @@ fn test_conversation_char_count() {
     assert_eq!(app.conversation_char_count(), 10);
 }
 
+// A model with a small context window, so a short history fills it
+fn small_model(context_window: u32) -> ModelConfig {
+    ModelConfig {
+        name: "Small Model (local)".into(),
+        file_name: "small".into(),
+        description: String::new(),
+        recommended_for: String::new(),
+        supports_agent: true,
+        context_window: Some(context_window),
+    }
+}
+
+// Five questions and answers of about 200 tokens each
+fn long_session() -> SessionManager {
+    let mut session = SessionManager::new(100).with_system_message("You are oli.".to_string());
+    for turn in 0..5 {
+        session.add_user_message(format!("Question {turn}: {}", "why ".repeat(200)));
+        session.add_assistant_message(format!("Answer {turn}: {}", "because ".repeat(200)));
+    }
+    session
+}
+
 #[test]
 fn test_should_compress() {
     let mut app = App::new();
     app.state = AppState::Chat;
+    app.available_models = vec![small_model(2_000)];
 
-    // Empty conversation should not need summarization
+    // Nothing to measure against before the first run
+    app.session_manager = Some(long_session());
+    assert_eq!(app.context_usage(), None);
     assert!(!app.should_compress());
 
-    // Add enough messages to trigger summarization by count (1000 is the threshold in history.rs)
-    for i in 0..1001 {
-        app.messages.push(format!("Message {i}"));
-    }
-
-    // Should now need summarization
+    // About 2,000 tokens of history fill the window past 80%
+    app.last_model_index = Some(0);
+    let usage = app.context_usage().unwrap();
+    assert_eq!(usage.window_tokens, 2_000);
+    assert!(usage.percent >= 80, "{usage:?}");
+    assert_eq!(usage.message(), format!("context {}% full", usage.percent));
     assert!(app.should_compress());
 
-    // Reset and try with character count
-    app.messages.clear();
+    // The same history in a large window
+    app.available_models = vec![small_model(200_000)];
+    assert_eq!(app.context_usage().unwrap().percent, 1);
+    assert!(!app.should_compress());
+}
 
-    // Add a single large message (1,000,000 chars is the threshold in history.rs)
-    app.messages.push("A".repeat(1000001));
+#[test]
+fn test_context_usage_percent() {
+    assert_eq!(ContextUsage::new(74_000, 100_000).percent, 74);
+    assert_eq!(ContextUsage::new(0, 200_000).message(), "context 0% full");
+    // A window of 0 doesn't divide by zero
+    assert_eq!(ContextUsage::new(10, 0).percent, 1_000);
+}
 
-    // Should need summarization due to character count
-    assert!(app.should_compress());
+#[test]
+fn test_manage_context_compacts_when_the_window_fills() {
+    let client = Arc::new(SummaryClient::default());
+    let mut app = App::new();
+    app.summarizer = None;
+    app.agent = Some(
+        Agent::new(LLMProvider::Anthropic)
+            .with_api_client(ApiClientEnum::custom_for_testing(client)),
+    );
+    app.available_models = vec![small_model(200_000)];
+    app.session_manager = Some(long_session());
+
+    // Plenty of room
+    assert_eq!(app.manage_context(0, "And now?"), None);
+    assert_eq!(app.history().len(), 10);
+
+    app.available_models = vec![small_model(2_000)];
+    let done = app.manage_context(0, "And now?").unwrap();
+    assert!(done.starts_with("The context window was "), "{done}");
+    assert!(done.contains("Compacted 6 messages"), "{done}");
+    assert!(app.messages.last().unwrap().contains(&done));
+
+    // The summary and the last four messages fit
+    assert_eq!(app.history().len(), 5);
+    assert!(app.history_tokens() < 1_600);
+}
+
+#[test]
+fn test_drop_oldest_messages() {
+    let mut app = App::new();
+    app.session_manager = Some(long_session());
+    let before = app.history_tokens();
+
+    // Room enough already
+    assert_eq!(app.drop_oldest_messages(before), 0);
+
+    // Whole question-and-answer pairs go, oldest first
+    let dropped = app.drop_oldest_messages(before / 2);
+    assert_eq!(dropped % 2, 0);
+    assert!(app.history_tokens() <= before / 2);
+    let history = app.history();
+    assert_eq!(history[0].role, "user");
+    assert!(history.last().unwrap().content.starts_with("Answer 4"));
+
+    // The last four messages are always kept
+    app.drop_oldest_messages(0);
+    assert_eq!(app.history().len(), 4);
+    assert!(app.history()[0].content.starts_with("Question 3"));
 }
 
 #[test]
@@ -199,3 +313,170 @@ fn test_agent_conversation_history_integration() {
     assert_eq!(agent_messages[4].role, "assistant");
     assert_eq!(agent_messages[4].content, "Second assistant response");
 }
+
+#[test]
+fn test_edit_and_delete_history_messages() {
+    let mut app = App::new();
+    let mut session = SessionManager::new(100).with_system_message("System".to_string());
+    session.add_user_message("Deploy with token ghp_secret".to_string());
+    session.add_assistant_message("Deployed".to_string());
+    session.add_user_message("Thanks".to_string());
+    app.session_manager = Some(session);
+    app.agent = Some(Agent::new(LLMProvider::Anthropic));
+
+    // The system message isn't part of the editable history
+    assert_eq!(app.history().len(), 3);
+
+    app.edit_history_message(0, "Deploy with token [redacted]".to_string())
+        .unwrap();
+    let removed = app.delete_history_message(2).unwrap();
+    assert_eq!(removed.content, "Thanks");
+
+    let history = app.history();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].content, "Deploy with token [redacted]");
+
+    // Later requests are built from the edited session, and the agent follows it
+    let api_messages = app.session_manager.as_ref().unwrap().get_messages_for_api();
+    assert!(api_messages
+        .iter()
+        .all(|m| !m.content.contains("ghp_secret")));
+    let agent_messages = app
+        .agent
+        .as_ref()
+        .unwrap()
+        .get_conversation_history_for_test();
+    assert_eq!(agent_messages.len(), 3);
+    assert_eq!(agent_messages[1].content, "Deploy with token [redacted]");
+
+    assert!(app.delete_history_message(5).is_err());
+}
+
+// A session whose last four messages hold a tool result that only refers to an
+// identical, earlier one
+fn session_with_repeated_result(content: &str) -> SessionManager {
+    let mut session = SessionManager::new(100);
+    session.add_user_message("Read the config".to_string());
+    session.add_assistant_message("Reading".to_string());
+    session.add_user_message(format!("Tool result for call read_1: [#1] {content}"));
+    session.add_assistant_message("It sets the port".to_string());
+    session.add_user_message("Read it again".to_string());
+    session.add_assistant_message("Reading".to_string());
+    session.add_user_message(
+        "Tool result for call read_2: [#1] Same as result for call read_1 \
+         (identical output omitted to save context)"
+            .to_string(),
+    );
+    session.add_assistant_message("It still sets the port".to_string());
+    session
+}
+
+#[test]
+fn test_removing_a_result_restores_references_to_it() {
+    std::env::remove_var(COMPACT_MODEL_ENV);
+    let content = format!("port = 8080\n{}", "# comment\n".repeat(20));
+    let mut app = App::new();
+    app.summarizer = None;
+    app.agent = Some(Agent::new(LLMProvider::Anthropic).with_api_client(
+        ApiClientEnum::custom_for_testing(Arc::new(SummaryClient::default())),
+    ));
+    app.session_manager = Some(session_with_repeated_result(&content));
+
+    // Compaction summarizes the original and keeps the reference
+    app.compact(None).unwrap();
+    let history = app.history();
+    assert_eq!(history.len(), 5);
+    assert_eq!(
+        history[3].content,
+        format!("Tool result for call read_2: [#1] {content}")
+    );
+    // The agent sees the restored result too
+    let agent_history = app.agent.as_ref().unwrap().conversation_history();
+    assert!(agent_history
+        .iter()
+        .any(|msg| msg.content.contains("read_2: [#1] port = 8080")));
+
+    // Dropping the oldest messages restores them the same way
+    app.session_manager = Some(session_with_repeated_result(&content));
+    assert_eq!(app.drop_oldest_messages(0), 4);
+    assert_eq!(
+        app.history()[2].content,
+        format!("Tool result for call read_2: [#1] {content}")
+    );
+
+    // A reference whose original is still there is left alone
+    app.session_manager = Some(session_with_repeated_result(&content));
+    assert_eq!(app.drop_oldest_messages(u32::MAX), 0);
+    assert!(app.history()[6]
+        .content
+        .contains("Same as result for call read_1"));
+}
+
+#[test]
+fn test_compact_summarizes_all_but_the_latest_messages() {
+    std::env::remove_var(COMPACT_MODEL_ENV);
+    let client = Arc::new(SummaryClient::default());
+    let mut app = App::new();
+    app.summarizer = None;
+    app.agent = Some(
+        Agent::new(LLMProvider::Anthropic)
+            .with_api_client(ApiClientEnum::custom_for_testing(client.clone())),
+    );
+
+    let mut session = SessionManager::new(100).with_system_message("You are oli.".to_string());
+    for turn in 0..5 {
+        session.add_user_message(format!("Question {turn}: {}", "why ".repeat(200)));
+        session.add_assistant_message(format!("Answer {turn}: {}", "because ".repeat(200)));
+    }
+    session.tag_message(9, "decision").unwrap();
+    app.session_manager = Some(session);
+
+    let report = app.compact(Some("the parser")).unwrap();
+    assert_eq!(report.messages_summarized, 6);
+    assert_eq!(report.model, None);
+    assert!(report.tokens_after < report.tokens_before, "{report:?}");
+    assert!(report
+        .message()
+        .starts_with("Compacted 6 messages with the main model: ~"));
+    assert!(app.messages.last().unwrap().contains(&report.message()));
+
+    let prompt = client.prompt.lock().unwrap().clone();
+    assert!(prompt.contains("Pay particular attention to: the parser"));
+    assert!(prompt.contains("Question 2") && !prompt.contains("Question 3"));
+
+    // The summary, then the last four messages with their tags
+    let history = app.history();
+    assert_eq!(history.len(), 5);
+    assert!(history[0]
+        .content
+        .contains("The user is fixing the config parser."));
+    assert!(history[1].content.starts_with("Question 3"));
+    let tags = &app.session_manager.as_ref().unwrap().message_tags;
+    assert!(tags[&4].contains("decision"));
+
+    // Too little left to compact
+    assert!(app
+        .compact(None)
+        .unwrap_err()
+        .to_string()
+        .contains("Nothing to compact"));
+
+    // The compaction model comes before the summarizer model; set here rather than
+    // in a test of its own, as tests run in parallel
+    app.set_summarizer_model(Some("gpt-4o-mini")).unwrap();
+
+    std::env::remove_var(COMPACT_MODEL_ENV);
+    assert_eq!(
+        app.compact_model().unwrap().map(|model| model.spec()),
+        Some("openai:gpt-4o-mini".to_string())
+    );
+
+    std::env::set_var(COMPACT_MODEL_ENV, "ollama:llama3.2");
+    assert_eq!(
+        app.compact_model().unwrap().map(|model| model.spec()),
+        Some("ollama:llama3.2".to_string())
+    );
+    std::env::set_var(COMPACT_MODEL_ENV, "mystery-model");
+    assert!(app.compact_model().is_err());
+    std::env::remove_var(COMPACT_MODEL_ENV);
+}
@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use tempfile::tempdir;
 
 use chrono::Local;
+use oli_server::app::logger::{LogBuffer, LogConfig, SessionLogger};
 use oli_server::app::{format_log, format_log_with_color, LogLevel, Logger};
 
 // Mock implementation of Logger for testing
@@ -139,3 +140,68 @@ fn test_write_log_to_file() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+fn log_config(directory: PathBuf) -> LogConfig {
+    LogConfig {
+        directory,
+        max_file_bytes: 200,
+        max_rotated_files: 2,
+        retention_days: 7,
+        max_memory_lines: 3,
+    }
+}
+
+#[test]
+fn test_log_buffer_keeps_most_recent_lines() {
+    let mut buffer = LogBuffer::new(3);
+    for i in 0..5 {
+        buffer.push(format!("line {i}"));
+    }
+
+    assert_eq!(buffer.len(), 3);
+    assert_eq!(buffer.dropped(), 2);
+    assert_eq!(buffer.recent(10), vec!["line 2", "line 3", "line 4"]);
+    assert_eq!(buffer.recent(1), vec!["line 4"]);
+}
+
+#[test]
+fn test_session_logger_fills_placeholders() {
+    let temp_dir = tempdir().unwrap();
+    let mut logger = SessionLogger::new("abc", log_config(temp_dir.path().to_path_buf()));
+
+    logger.log("Loaded {} from {}", &["model", "cache"]);
+
+    let lines = logger.buffer.recent(1);
+    assert!(lines[0].ends_with("[INFO] Loaded model from cache"));
+
+    let file_name = logger.get_log_file_path();
+    let file_name = file_name.file_name().unwrap().to_string_lossy();
+    let day = Local::now().format("%Y-%m-%d").to_string();
+    assert_eq!(file_name, format!("session-{day}-abc.log"));
+}
+
+#[test]
+fn test_session_logger_rotates_by_size() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let mut logger = SessionLogger::new("abc", log_config(temp_dir.path().to_path_buf()));
+
+    // Each line is about 80 bytes, so a 200 byte file holds two
+    for i in 0..10 {
+        logger.log(&format!("message number {i} {}", "x".repeat(40)), &[]);
+    }
+
+    let path = logger.get_log_file_path();
+    let rotated = |index: usize| PathBuf::from(format!("{}.{index}", path.display()));
+    assert!(fs::metadata(&path)?.len() <= 200);
+    assert!(rotated(1).exists());
+    assert!(rotated(2).exists());
+    assert!(!rotated(3).exists());
+
+    // The newest lines are in the live file, the next newest in `.1`
+    assert!(fs::read_to_string(&path)?.contains("message number 9"));
+    assert!(fs::read_to_string(rotated(1))?.contains("message number 7"));
+
+    // Only the last three lines stay in memory
+    assert_eq!(logger.buffer.len(), 3);
+    Ok(())
+}
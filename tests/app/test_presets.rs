@@ -0,0 +1,74 @@
+//! Tests for choosing sampling presets per mode
+
+use oli_server::agent::core::LLMProvider;
+use oli_server::apis::api_client::SamplingPreset;
+use oli_server::app::presets::{PresetSelection, SamplingMode};
+use oli_server::App;
+
+#[test]
+fn test_parse_presets() {
+    let selection = PresetSelection::parse("balanced, agent=precise,COMPARE=creative").unwrap();
+    assert_eq!(selection.default, Some(SamplingPreset::Balanced));
+    assert_eq!(
+        selection.for_mode(SamplingMode::Agent),
+        Some(SamplingPreset::Precise)
+    );
+    assert_eq!(
+        selection.for_mode(SamplingMode::Compare),
+        Some(SamplingPreset::Creative)
+    );
+    // Modes without their own preset use the one for every mode
+    assert_eq!(
+        selection.for_mode(SamplingMode::Chat),
+        Some(SamplingPreset::Balanced)
+    );
+
+    assert_eq!(
+        PresetSelection::parse("").unwrap(),
+        PresetSelection::default()
+    );
+    assert!(PresetSelection::parse("review=precise").is_err());
+    assert!(PresetSelection::parse("agent=cold").is_err());
+}
+
+#[test]
+fn test_preset_parameters_for_a_mode() {
+    let selection = PresetSelection::parse("agent=creative").unwrap();
+    let parameters =
+        selection.parameters_for(SamplingMode::Agent, Some(&LLMProvider::Anthropic), "claude");
+    assert_eq!(parameters.temperature, Some(1.0));
+
+    // Without a preset the model's defaults apply
+    let parameters = selection.parameters_for(SamplingMode::Chat, None, "claude");
+    assert_eq!(parameters.temperature, None);
+    assert_eq!(parameters.top_p, None);
+}
+
+#[test]
+fn test_set_and_clear_presets() {
+    let mut app = App::new();
+    app.sampling_presets = PresetSelection::default();
+
+    app.set_sampling_preset(None, "precise").unwrap();
+    app.set_sampling_preset(Some("chat"), "Creative").unwrap();
+    assert_eq!(
+        app.sampling_presets.for_mode(SamplingMode::Agent),
+        Some(SamplingPreset::Precise)
+    );
+    assert_eq!(
+        app.sampling_presets.for_mode(SamplingMode::Chat),
+        Some(SamplingPreset::Creative)
+    );
+
+    // "default" clears a mode's preset, falling back to the one for every mode
+    app.set_sampling_preset(Some("chat"), "default").unwrap();
+    assert_eq!(
+        app.sampling_presets.for_mode(SamplingMode::Chat),
+        Some(SamplingPreset::Precise)
+    );
+    app.set_sampling_preset(None, "default").unwrap();
+    assert_eq!(app.sampling_presets, PresetSelection::default());
+
+    assert!(app.set_sampling_preset(Some("review"), "precise").is_err());
+    assert!(app.set_sampling_preset(None, "hot").is_err());
+}
@@ -0,0 +1,44 @@
+//! Tests for the prompt history recalled with Up in the input box
+
+use oli_server::app::prompt_history::PromptHistory;
+use tempfile::TempDir;
+
+#[test]
+fn test_add_and_load_keep_order_and_multiline_prompts() {
+    let dir = TempDir::new().unwrap();
+    let history = PromptHistory::new(dir.path().join("oli").join("history"), 10);
+    assert!(history.load().is_empty());
+
+    assert!(history.add("explain src/main.rs").unwrap());
+    assert!(history.add("write a test\nfor the parser").unwrap());
+    // Blank prompts and repeats of the latest prompt aren't recorded
+    assert!(!history.add("   ").unwrap());
+    assert!(!history.add("write a test\nfor the parser").unwrap());
+    assert!(history.add("explain src/main.rs").unwrap());
+
+    assert_eq!(
+        history.load(),
+        vec![
+            "explain src/main.rs",
+            "write a test\nfor the parser",
+            "explain src/main.rs",
+        ]
+    );
+}
+
+#[test]
+fn test_history_drops_the_oldest_prompts_past_the_limit() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("history");
+    let history = PromptHistory::new(path.clone(), 3);
+
+    for prompt in ["one", "two", "three", "four", "five"] {
+        history.add(prompt).unwrap();
+    }
+    assert_eq!(history.load(), vec!["three", "four", "five"]);
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 3);
+
+    // Lines that aren't prompts are skipped rather than failing the load
+    std::fs::write(&path, "\"one\"\nnot json\n\"two\"\n").unwrap();
+    assert_eq!(history.load(), vec!["one", "two"]);
+}
@@ -0,0 +1,68 @@
+//! Tests for the registry of queries started with start_query
+
+use oli_server::app::queries::{QueryRegistry, QueryStatus};
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[test]
+fn test_query_lifecycle() {
+    let queries = QueryRegistry::new();
+    let id = queries.start("Fix the failing test");
+    assert_eq!(queries.get(&id).unwrap().status, QueryStatus::Queued);
+
+    assert!(queries.begin(&id));
+    queries.record_progress(&id, "Running Bash: cargo test");
+    queries.record_progress(&id, "Running Edit: src/lib.rs");
+    let running = queries.get(&id).unwrap();
+    assert_eq!(running.status, QueryStatus::Running);
+    assert_eq!(running.messages.len(), 2);
+    assert_eq!(running.message_count, 2);
+    assert!(running.finished_at.is_none());
+
+    queries.finish(&id, Ok(json!({ "response": "Fixed" })));
+    let finished = queries.get(&id).unwrap();
+    assert_eq!(finished.status, QueryStatus::Completed);
+    assert_eq!(finished.result.unwrap()["response"], "Fixed");
+    assert!(finished.finished_at.is_some());
+
+    let failed = queries.start("Explain");
+    queries.begin(&failed);
+    queries.finish(&failed, Err("No API key".to_string()));
+    let failed = queries.get(&failed).unwrap();
+    assert_eq!(failed.status, QueryStatus::Failed);
+    assert_eq!(failed.error.as_deref(), Some("No API key"));
+
+    assert!(queries.get("no-such-query").is_none());
+}
+
+#[test]
+fn test_cancel_query() {
+    let queries = QueryRegistry::new();
+    let flag = AtomicBool::new(false);
+
+    // A queued query never starts, and the running one isn't disturbed
+    let queued = queries.start("Refactor");
+    assert_eq!(queries.cancel(&queued, &flag), Some(QueryStatus::Cancelled));
+    assert!(!flag.load(Ordering::SeqCst));
+    assert!(!queries.begin(&queued));
+
+    // A running query is stopped through the flag, keeping its partial answer
+    let running = queries.start("Refactor again");
+    queries.begin(&running);
+    assert_eq!(
+        queries.cancel(&running, &flag),
+        Some(QueryStatus::Cancelled)
+    );
+    assert!(flag.load(Ordering::SeqCst));
+    queries.finish(&running, Ok(json!({ "response": "Partial" })));
+    let cancelled = queries.get(&running).unwrap();
+    assert_eq!(cancelled.status, QueryStatus::Cancelled);
+    assert!(cancelled.result.is_some());
+
+    // Finished queries keep their status
+    assert_eq!(
+        queries.cancel(&running, &flag),
+        Some(QueryStatus::Cancelled)
+    );
+    assert_eq!(queries.cancel("no-such-query", &flag), None);
+}
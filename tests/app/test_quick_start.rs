@@ -0,0 +1,101 @@
+//! Tests for picking a model to skip setup with
+
+use oli_server::app::quick_start::pick_quick_start;
+use oli_server::models::{find_local_model, ModelConfig};
+
+const ANTHROPIC_KEY: &str = "sk-ant-api03-abcdef";
+const OPENAI_KEY: &str = "sk-proj-abcdef";
+
+fn model(name: &str, file_name: &str) -> ModelConfig {
+    ModelConfig {
+        name: name.to_string(),
+        file_name: file_name.to_string(),
+        description: String::new(),
+        recommended_for: String::new(),
+        supports_agent: true,
+        context_window: None,
+    }
+}
+
+fn models() -> Vec<ModelConfig> {
+    vec![
+        model("Claude 3.7 Sonnet", "claude-3-7-sonnet-20250219"),
+        model("GPT-4o", "gpt-4o"),
+        model("llama3.2 (local)", "llama3.2:latest"),
+    ]
+}
+
+fn keys<'a>(set: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+    move |env_var| {
+        set.iter()
+            .find(|(name, _)| *name == env_var)
+            .map(|(_, key)| key.to_string())
+    }
+}
+
+#[test]
+fn test_single_key_picks_its_model() {
+    let picked = pick_quick_start(
+        &models(),
+        keys(&[("ANTHROPIC_API_KEY", ANTHROPIC_KEY)]),
+        None,
+    )
+    .unwrap();
+    assert_eq!(picked.model_index, 0);
+    assert_eq!(picked.source, "ANTHROPIC_API_KEY");
+    assert_eq!(
+        picked.notice(),
+        "Using Claude 3.7 Sonnet via ANTHROPIC_API_KEY. Run /model to choose another model."
+    );
+}
+
+#[test]
+fn test_several_or_no_providers_need_setup() {
+    let both = [
+        ("ANTHROPIC_API_KEY", ANTHROPIC_KEY),
+        ("OPENAI_API_KEY", OPENAI_KEY),
+    ];
+    assert_eq!(pick_quick_start(&models(), keys(&both), None), None);
+    assert_eq!(pick_quick_start(&models(), keys(&[]), None), None);
+
+    // A key plus a configured Ollama model is still a choice to make
+    let one = [("OPENAI_API_KEY", OPENAI_KEY)];
+    assert_eq!(
+        pick_quick_start(&models(), keys(&one), Some("llama3.2")),
+        None
+    );
+}
+
+#[test]
+fn test_malformed_keys_are_ignored() {
+    // An Anthropic key in the OpenAI variable doesn't count
+    let set = [
+        ("OPENAI_API_KEY", ANTHROPIC_KEY),
+        ("ANTHROPIC_API_KEY", ANTHROPIC_KEY),
+    ];
+    let picked = pick_quick_start(&models(), keys(&set), None).unwrap();
+    assert_eq!(picked.model_index, 0);
+}
+
+#[test]
+fn test_configured_ollama_model() {
+    let picked = pick_quick_start(&models(), keys(&[]), Some("llama3.2")).unwrap();
+    assert_eq!(picked.model_index, 2);
+    assert_eq!(picked.source, "Ollama");
+
+    // Only the configured model counts
+    assert_eq!(
+        pick_quick_start(&models(), keys(&[]), Some("mistral")),
+        None
+    );
+}
+
+#[test]
+fn test_find_local_model() {
+    let models = models();
+    assert_eq!(find_local_model(&models, "llama3.2"), Some(2));
+    assert_eq!(find_local_model(&models, " llama3.2:latest "), Some(2));
+    // A model Ollama doesn't have, and a cloud model of the same name
+    assert_eq!(find_local_model(&models, "qwen2.5-coder"), None);
+    assert_eq!(find_local_model(&models, "gpt-4o"), None);
+}
@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod scroll_tests {
-    use oli_server::app::utils::ScrollState;
+    use oli_server::app::utils::{ScrollAction, ScrollBindings, ScrollState};
 
     #[test]
     fn test_scroll_state_creation() {
@@ -122,4 +122,101 @@ mod scroll_tests {
         scroll.update_dimensions(50, 10);
         assert_eq!(scroll.position, 40);
     }
+
+    #[test]
+    fn test_line_and_half_page_scroll() {
+        let mut scroll = ScrollState::new();
+        scroll.update_dimensions(50, 10);
+
+        scroll.line_up();
+        assert_eq!(scroll.position, 39);
+        assert!(!scroll.follow_bottom);
+
+        // Half page moves by half the viewport height
+        scroll.half_page_up();
+        assert_eq!(scroll.position, 34);
+
+        scroll.half_page_down();
+        assert_eq!(scroll.position, 39);
+
+        // Scrolling back to the bottom re-enables follow
+        scroll.line_down();
+        assert_eq!(scroll.position, 40);
+        assert!(scroll.follow_bottom);
+
+        // Half page is at least one line in a tiny viewport
+        scroll.update_dimensions(50, 1);
+        scroll.half_page_up();
+        assert_eq!(scroll.position, 48);
+    }
+
+    #[test]
+    fn test_prompt_boundary_jumps() {
+        let mut scroll = ScrollState::new();
+        scroll.update_dimensions(50, 10);
+        let prompts = [0, 12, 30, 45];
+
+        // From the bottom (40), the previous prompt starts at line 30
+        scroll.apply(ScrollAction::PreviousPrompt, &prompts);
+        assert_eq!(scroll.position, 30);
+
+        scroll.apply(ScrollAction::PreviousPrompt, &prompts);
+        assert_eq!(scroll.position, 12);
+
+        scroll.apply(ScrollAction::NextPrompt, &prompts);
+        assert_eq!(scroll.position, 30);
+
+        // A prompt that can't reach the top of the viewport scrolls to the bottom
+        scroll.apply(ScrollAction::NextPrompt, &prompts);
+        assert_eq!(scroll.position, 40);
+        assert!(scroll.follow_bottom);
+
+        // Without earlier prompts, jumping back goes to the top
+        scroll.scroll_to_top();
+        scroll.apply(ScrollAction::PreviousPrompt, &prompts);
+        assert_eq!(scroll.position, 0);
+    }
+
+    #[test]
+    fn test_scroll_bindings() {
+        let bindings = ScrollBindings::default();
+        assert_eq!(bindings.action_for("ctrl+e"), Some(ScrollAction::LineDown));
+        assert_eq!(bindings.action_for("Ctrl+Y"), Some(ScrollAction::LineUp));
+        assert_eq!(
+            bindings.action_for("ctrl+d"),
+            Some(ScrollAction::HalfPageDown)
+        );
+        assert_eq!(
+            bindings.action_for("control+u"),
+            Some(ScrollAction::HalfPageUp)
+        );
+        assert_eq!(bindings.action_for("PageUp"), Some(ScrollAction::PageUp));
+
+        let bindings = ScrollBindings::with_overrides("ctrl+k=line_up, ctrl+e=none").unwrap();
+        assert_eq!(bindings.action_for("ctrl+k"), Some(ScrollAction::LineUp));
+        assert_eq!(bindings.action_for("ctrl+e"), None);
+        assert_eq!(
+            bindings.action_for("ctrl+d"),
+            Some(ScrollAction::HalfPageDown)
+        );
+
+        assert!(ScrollBindings::with_overrides("ctrl+k=sideways").is_err());
+        assert!(ScrollBindings::with_overrides("ctrl+k").is_err());
+    }
+
+    #[test]
+    fn test_scroll_bindings_list_every_key_by_name() {
+        let bindings = ScrollBindings::with_overrides("ctrl+k=previous_prompt, home=none").unwrap();
+        let listed = bindings.bindings();
+
+        assert!(listed.contains(&("ctrl+k", ScrollAction::PreviousPrompt)));
+        assert!(listed.contains(&("ctrl+e", ScrollAction::LineDown)));
+        assert!(!listed.iter().any(|(key, _)| *key == "home"));
+        assert!(listed.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        // Names round-trip, which is how the UI receives them
+        for (_, action) in listed {
+            assert_eq!(ScrollAction::from_name(action.name()), Some(action));
+        }
+    }
 }
@@ -0,0 +1,59 @@
+//! Tests for saving and restoring the UI's draft input
+
+use oli_server::app::session::{SessionFile, SessionState};
+
+fn draft(text: &str) -> SessionState {
+    SessionState {
+        draft: text.to_string(),
+        scroll_offset: 4,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_state_round_trips() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let session_file = SessionFile::new(temp_dir.path().join("nested").join("session.json"));
+    assert_eq!(session_file.load(), None);
+
+    session_file
+        .save(&draft("Refactor the parser\nso that"))
+        .unwrap();
+    let restored = session_file.load().unwrap();
+
+    assert_eq!(restored.draft, "Refactor the parser\nso that");
+    assert_eq!(restored.scroll_offset, 4);
+    assert!(chrono::DateTime::parse_from_rfc3339(&restored.saved_at).is_ok());
+    assert!(!temp_dir
+        .path()
+        .join("nested")
+        .join("session.json.tmp")
+        .exists());
+}
+
+#[test]
+fn test_empty_state_clears_file() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let session_file = SessionFile::new(temp_dir.path().join("session.json"));
+
+    session_file.save(&draft("half a prompt")).unwrap();
+    session_file.save(&SessionState::default()).unwrap();
+
+    assert!(!session_file.path().exists());
+    assert_eq!(session_file.load(), None);
+    // Clearing again is fine
+    session_file.clear().unwrap();
+}
+
+#[test]
+fn test_corrupt_file_is_ignored() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let session_file = SessionFile::new(temp_dir.path().join("session.json"));
+
+    std::fs::write(session_file.path(), "{\"draft\": \"unfinished").unwrap();
+    assert_eq!(session_file.load(), None);
+
+    // Fields missing from older files default
+    std::fs::write(session_file.path(), "{\"draft\": \"hello\"}").unwrap();
+    assert_eq!(session_file.load().unwrap().scroll_offset, 0);
+}
@@ -0,0 +1,209 @@
+//! Tests for saving, starring, tagging and resuming conversations
+
+use oli_server::app::core::TaskStatus;
+use oli_server::app::history::{ContextCompressor, ConversationSummary};
+use oli_server::app::session_store::{
+    normalize_tag, SavedMessage, SavedSession, SessionFilter, SessionStore,
+};
+use oli_server::App;
+use std::collections::BTreeSet;
+
+fn saved(
+    id: &str,
+    updated_at: &str,
+    starred: bool,
+    messages: &[(&str, &str, &[&str])],
+) -> SavedSession {
+    SavedSession {
+        id: id.to_string(),
+        started_at: updated_at.to_string(),
+        updated_at: updated_at.to_string(),
+        working_directory: None,
+        starred,
+        messages: messages
+            .iter()
+            .map(|(role, content, tags)| SavedMessage {
+                role: role.to_string(),
+                content: content.to_string(),
+                tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            })
+            .collect(),
+        tasks: Vec::new(),
+        summaries: Vec::new(),
+    }
+}
+
+#[test]
+fn test_normalize_tag() {
+    assert_eq!(normalize_tag(" #Decision ").unwrap(), "decision");
+    assert!(normalize_tag("#").is_err());
+    assert!(normalize_tag("two words").is_err());
+}
+
+#[test]
+fn test_list_and_search_with_filters() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let store = SessionStore::new(temp_dir.path().join("sessions"));
+
+    // A store that hasn't saved anything yet is empty
+    assert!(store.list(&SessionFilter::default()).unwrap().is_empty());
+
+    store
+        .save(&saved(
+            "aaa111",
+            "2025-06-01T10:00:00+00:00",
+            false,
+            &[
+                ("user", "Fix the retry logic\nin the client", &[]),
+                ("assistant", "The retry loop never backs off", &["bug"]),
+            ],
+        ))
+        .unwrap();
+    store
+        .save(&saved(
+            "bbb222",
+            "2025-06-02T10:00:00+00:00",
+            true,
+            &[
+                ("user", "Should we keep retries?", &[]),
+                ("assistant", "Keep three retries", &["decision"]),
+            ],
+        ))
+        .unwrap();
+
+    // Newest first, titled by the first line of the first prompt
+    let listed = store.list(&SessionFilter::default()).unwrap();
+    let ids: Vec<&str> = listed.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, vec!["bbb222", "aaa111"]);
+    assert_eq!(listed[1].title, "Fix the retry logic");
+    assert_eq!(listed[1].tags, vec!["bug"]);
+
+    let starred = SessionFilter {
+        starred: true,
+        tag: None,
+    };
+    assert_eq!(store.list(&starred).unwrap()[0].id, "bbb222");
+    assert_eq!(store.list(&starred).unwrap().len(), 1);
+
+    // Searching ignores case and only returns tagged messages when filtered by tag
+    let hits = store
+        .search("RETRIES", &SessionFilter::default(), 10)
+        .unwrap();
+    assert_eq!(hits.len(), 2);
+    assert_eq!((hits[0].index, hits[0].role.as_str()), (0, "user"));
+    let bugs = SessionFilter {
+        starred: false,
+        tag: Some("bug".to_string()),
+    };
+    let hits = store.search("", &bugs, 10).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].session_id, "aaa111");
+    assert_eq!(hits[0].snippet, "The retry loop never backs off");
+    assert_eq!(
+        store
+            .search("retr", &SessionFilter::default(), 1)
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // Ids resolve from a unique prefix
+    assert_eq!(store.resolve("bbb").unwrap().id, "bbb222");
+    assert!(store.resolve("ccc").is_err());
+    assert!(store.resolve("").is_err());
+}
+
+#[test]
+fn test_saving_keeps_the_start_time() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let store = SessionStore::new(temp_dir.path().to_path_buf());
+    let first = saved(
+        "abc",
+        "2025-06-01T10:00:00+00:00",
+        false,
+        &[("user", "Hi", &[])],
+    );
+    store.save(&first).unwrap();
+
+    let mut later = saved(
+        "abc",
+        "2025-06-03T10:00:00+00:00",
+        true,
+        &[("user", "Hi", &[])],
+    );
+    later.started_at = later.updated_at.clone();
+    store.save(&later).unwrap();
+
+    let loaded = store.load("abc").unwrap();
+    assert_eq!(loaded.started_at, "2025-06-01T10:00:00+00:00");
+    assert_eq!(loaded.updated_at, "2025-06-03T10:00:00+00:00");
+    assert!(loaded.starred);
+}
+
+#[test]
+fn test_star_tag_and_resume() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let mut app = App::new();
+    app.session_store = Some(SessionStore::new(temp_dir.path().to_path_buf()));
+    let session = app.session_manager.as_mut().unwrap();
+    session.add_user_message("Which database?".to_string());
+    session.add_assistant_message("Postgres".to_string());
+
+    let (index, changed) = app.tag_history_message(1, "#Decision", false).unwrap();
+    assert_eq!((index, changed), (1, true));
+    assert!(app.tag_history_message(3, "todo", false).is_err());
+    app.star_session(true).unwrap();
+    let first_id = app.saved_session_id.clone();
+
+    // Clearing history starts a new saved conversation
+    app.clear_history();
+    assert_ne!(app.saved_session_id, first_id);
+    assert!(!app.session_starred);
+
+    let resumed = app.resume_session(&first_id[..8]).unwrap();
+    assert!(resumed.starred);
+    assert_eq!(app.saved_session_id, first_id);
+    assert!(app.session_starred);
+    assert_eq!(app.history().len(), 2);
+    assert_eq!(
+        app.session_manager.as_ref().unwrap().message_tags[&1],
+        BTreeSet::from(["decision".to_string()])
+    );
+    assert_eq!(app.messages.len(), 2);
+}
+
+#[test]
+fn test_resume_restores_tasks_and_summaries() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let mut app = App::new();
+    app.session_store = Some(SessionStore::new(temp_dir.path().to_path_buf()));
+    let session = app.session_manager.as_mut().unwrap();
+    session.add_user_message("Add a retry".to_string());
+    session.add_assistant_message("Added".to_string());
+    app.create_task("Add a retry");
+    app.complete_current_task(10);
+    // A task still running when oli crashed
+    app.create_task("Write the tests");
+    app.conversation_summaries
+        .push(ConversationSummary::new("Earlier work".to_string(), 4, 200));
+    app.save_session().unwrap();
+    let first_id = app.saved_session_id.clone();
+
+    let saved = app.session_store.as_ref().unwrap().load(&first_id).unwrap();
+    assert_eq!(saved.tasks.len(), 2);
+    assert_eq!(saved.summaries[0].content, "Earlier work");
+
+    // A restarted oli resumes the conversation with its tasks and summaries
+    let mut restarted = App::new();
+    restarted.session_store = app.session_store.clone();
+    restarted.resume_session(&first_id).unwrap();
+    assert_eq!(restarted.tasks.len(), 2);
+    assert!(matches!(
+        restarted.tasks[0].status,
+        TaskStatus::Completed { .. }
+    ));
+    assert!(matches!(restarted.tasks[1].status, TaskStatus::Failed(_)));
+    assert!(!restarted.has_active_tasks());
+    assert_eq!(restarted.summary_count(), 1);
+    assert_eq!(restarted.conversation_summaries[0].messages_count, 4);
+}
@@ -0,0 +1,118 @@
+//! Tests for the session summary appended to the changelog at exit
+
+use oli_server::agent::evidence::Evidence;
+use oli_server::apis::api_client::Message;
+use oli_server::app::session_summary::{
+    append_to_changelog, summary_prompt, SessionActivity, SessionSummary, SESSION_CHANGELOG_FILE,
+};
+use oli_server::App;
+use serde_json::json;
+
+fn evidence(index: usize, tool: &str, arguments: serde_json::Value, output: &str) -> Evidence {
+    Evidence::new(index, tool, &format!("call_{index}"), &arguments, output)
+}
+
+fn activity() -> SessionActivity {
+    let mut activity = SessionActivity::default();
+    activity.record_prompt("Rename parse_args to parse_cli");
+    activity.record_tools(&[
+        evidence(
+            1,
+            "Read",
+            json!({"file_path": "/repo/src/cli.rs"}),
+            "fn parse_args",
+        ),
+        evidence(
+            2,
+            "Edit",
+            json!({"file_path": "/repo/src/cli.rs"}),
+            "Edited",
+        ),
+        evidence(
+            3,
+            "Edit",
+            json!({"file_path": "/repo/src/cli.rs"}),
+            "Edited",
+        ),
+        evidence(
+            4,
+            "Write",
+            json!({"file_path": "/repo/src/new.rs"}),
+            "ERROR EXECUTING TOOL: denied",
+        ),
+        evidence(5, "Bash", json!({"command": "cargo test\n--quiet"}), "ok"),
+    ]);
+    activity
+}
+
+#[test]
+fn test_activity_records_changed_files_and_commands() {
+    let activity = activity();
+
+    assert_eq!(activity.prompts, vec!["Rename parse_args to parse_cli"]);
+    // Reads and failed writes aren't changes, and repeated edits are listed once
+    assert_eq!(activity.files_changed, vec!["/repo/src/cli.rs"]);
+    assert_eq!(activity.commands, vec!["cargo test\n--quiet"]);
+    assert!(!activity.is_empty());
+    assert!(SessionActivity::default().is_empty());
+}
+
+#[test]
+fn test_summary_markdown() {
+    let summary = SessionSummary::new(
+        activity(),
+        Some("- Renamed the argument parser\n".to_string()),
+    );
+    let markdown = summary.to_markdown();
+
+    assert!(markdown.starts_with("## Session "));
+    assert!(markdown.contains(" UTC – "));
+    assert!(markdown.contains("- Renamed the argument parser"));
+    assert!(markdown.contains("**Prompts:**\n- Rename parse_args to parse_cli"));
+    assert!(markdown.contains("**Files changed:**\n- `/repo/src/cli.rs`"));
+    // Multi-line commands are shown by their first line
+    assert!(markdown.contains("**Commands run:**\n- `cargo test`"));
+
+    // Blank notes are left out
+    let summary = SessionSummary::new(activity(), Some("  ".to_string()));
+    assert!(summary.notes.is_none());
+}
+
+#[test]
+fn test_summary_prompt_includes_activity_and_conversation() {
+    let conversation = vec![
+        Message::system("You are oli".to_string()),
+        Message::user("Rename parse_args".to_string()),
+        Message::assistant("Renamed it, kept a deprecated alias for callers".to_string()),
+    ];
+    let prompt = summary_prompt(&activity(), &conversation);
+
+    assert!(prompt.contains("decisions"));
+    assert!(prompt.contains("Files changed: /repo/src/cli.rs"));
+    assert!(prompt.contains("Commands run: cargo test"));
+    assert!(prompt.contains("[assistant] Renamed it, kept a deprecated alias"));
+    assert!(!prompt.contains("You are oli"));
+}
+
+#[test]
+fn test_append_to_changelog() {
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    let first = SessionSummary::new(activity(), None);
+    let path = append_to_changelog(temp_dir.path(), &first).unwrap();
+    assert_eq!(path, temp_dir.path().join(SESSION_CHANGELOG_FILE));
+
+    let second = SessionSummary::new(activity(), Some("- Second session".to_string()));
+    append_to_changelog(temp_dir.path(), &second).unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents.matches("# Session Changelog").count(), 1);
+    assert_eq!(contents.matches("## Session ").count(), 2);
+    assert!(contents.contains("- Second session"));
+}
+
+#[test]
+fn test_summarize_session_without_activity_does_nothing() {
+    let mut app = App::new();
+    assert!(app.summarize_session().unwrap().is_none());
+}
@@ -0,0 +1,132 @@
+//! Tests for storage usage, pinning and pruning of session logs
+
+use oli_server::app::logger::LogConfig;
+use oli_server::app::storage::StorageManager;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tempfile::tempdir;
+
+fn log_config(directory: PathBuf) -> LogConfig {
+    LogConfig {
+        directory,
+        max_file_bytes: 1024,
+        max_rotated_files: 2,
+        retention_days: 7,
+        max_memory_lines: 10,
+    }
+}
+
+// Write a file of `bytes` bytes last modified `days_ago` days ago
+fn write_aged(path: &Path, bytes: usize, days_ago: u64) {
+    fs::write(path, "x".repeat(bytes)).unwrap();
+    let modified = SystemTime::now() - Duration::from_secs(days_ago * 24 * 60 * 60);
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+}
+
+#[test]
+fn test_prune_removes_expired_session_logs() {
+    let temp_dir = tempdir().unwrap();
+    let storage = StorageManager::new(&log_config(temp_dir.path().to_path_buf()));
+    let old = temp_dir.path().join("session-2020-01-01-old.log");
+    let old_rotated = temp_dir.path().join("session-2020-01-01-old.log.1");
+    let recent = temp_dir.path().join("session-2020-01-01-recent.log");
+    let other = temp_dir.path().join("notes.txt");
+    write_aged(&old, 10, 30);
+    write_aged(&old_rotated, 10, 30);
+    write_aged(&recent, 10, 0);
+    write_aged(&other, 10, 30);
+
+    let report = storage.prune(None).unwrap();
+    assert_eq!(report.removed_files, 2);
+    assert_eq!(report.freed_bytes, 20);
+    assert!(!old.exists());
+    assert!(!old_rotated.exists());
+    assert!(recent.exists());
+    assert!(other.exists());
+
+    // A missing directory is nothing to prune
+    let missing = StorageManager::new(&log_config(temp_dir.path().join("missing")));
+    assert_eq!(missing.prune(None).unwrap().removed_files, 0);
+}
+
+#[test]
+fn test_pinned_and_current_sessions_are_kept() {
+    let temp_dir = tempdir().unwrap();
+    let storage = StorageManager::new(&log_config(temp_dir.path().to_path_buf()));
+    let starred = temp_dir.path().join("session-2020-01-01-aaaa-1111.log");
+    let current = temp_dir.path().join("session-2020-01-01-bbbb-2222.log");
+    let stale = temp_dir.path().join("session-2020-01-01-cccc-3333.log");
+    for path in [&starred, &current, &stale] {
+        write_aged(path, 10, 30);
+    }
+
+    // Sessions are found by a prefix of their id
+    let id = storage.resolve_session("aaaa").unwrap();
+    assert_eq!(id, "aaaa-1111");
+    assert!(storage.pin(&id).unwrap());
+    assert!(!storage.pin(&id).unwrap());
+    assert!(storage.resolve_session("zzzz").is_err());
+
+    let report = storage.prune(Some("bbbb-2222")).unwrap();
+    assert_eq!(report.removed_files, 1);
+    assert_eq!(report.pinned_kept, 1);
+    assert!(starred.exists());
+    assert!(current.exists());
+    assert!(!stale.exists());
+
+    // Unpinned sessions are pruned again
+    assert!(storage.unpin("aaaa-1111").unwrap());
+    storage.prune(Some("bbbb-2222")).unwrap();
+    assert!(!starred.exists());
+}
+
+#[test]
+fn test_quota_removes_the_oldest_logs_first() {
+    let temp_dir = tempdir().unwrap();
+    let logs = temp_dir.path().join("logs");
+    fs::create_dir_all(&logs).unwrap();
+    let audit = temp_dir.path().join("audit.jsonl");
+    fs::write(&audit, "x".repeat(100)).unwrap();
+    let storage = StorageManager::new(&log_config(logs.clone()))
+        .with_max_bytes(Some(350))
+        .with_record("audit log", audit.clone());
+
+    let oldest = logs.join("session-2020-01-01-one.log");
+    let middle = logs.join("session-2020-01-02-two.log");
+    let newest = logs.join("session-2020-01-03-three.log");
+    write_aged(&oldest, 100, 3);
+    write_aged(&middle, 100, 2);
+    write_aged(&newest, 100, 1);
+
+    let usage = storage.usage().unwrap();
+    assert_eq!(usage.total_bytes, 400);
+    assert_eq!(usage.categories[0].files, 3);
+    assert_eq!(usage.categories[1].name, "audit log");
+    assert!(!usage.categories[1].prunable);
+    let ids: Vec<&str> = usage.sessions.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, vec!["three", "two", "one"]);
+
+    // Records count towards the quota but only session logs are removed
+    let report = storage.prune(None).unwrap();
+    assert_eq!(report.removed_files, 1);
+    assert!(!report.over_quota);
+    assert!(!oldest.exists());
+    assert!(middle.exists());
+    assert!(audit.exists());
+
+    // When nothing prunable is left, usage stays over the quota
+    storage.pin("two").unwrap();
+    let report = storage
+        .clone()
+        .with_max_bytes(Some(50))
+        .prune(Some("three"))
+        .unwrap();
+    assert_eq!(report.removed_files, 0);
+    assert!(report.over_quota);
+}
@@ -0,0 +1,71 @@
+use oli_server::app::summarizer::SummarizerConfig;
+use oli_server::{App, LLMProvider};
+
+#[test]
+fn test_summarizer_config_parse() {
+    // Explicit provider prefix
+    let config = SummarizerConfig::parse("anthropic:claude-3-5-haiku-latest").unwrap();
+    assert_eq!(config.provider, LLMProvider::Anthropic);
+    assert_eq!(config.model, "claude-3-5-haiku-latest");
+
+    // Ollama model names keep their own tag after the provider prefix
+    let config = SummarizerConfig::parse("ollama:llama3:8b").unwrap();
+    assert_eq!(config.provider, LLMProvider::Ollama);
+    assert_eq!(config.model, "llama3:8b");
+    assert_eq!(config.spec(), "ollama:llama3:8b");
+
+    // Provider inferred from well-known model names
+    let config = SummarizerConfig::parse("gpt-4o-mini").unwrap();
+    assert_eq!(config.provider, LLMProvider::OpenAI);
+    assert_eq!(config.spec(), "openai:gpt-4o-mini");
+
+    // OpenRouter model IDs keep their vendor and variant
+    let config = SummarizerConfig::parse("openrouter:deepseek/deepseek-chat:free").unwrap();
+    assert_eq!(config.provider, LLMProvider::OpenRouter);
+    assert_eq!(config.model, "deepseek/deepseek-chat:free");
+
+    // Azure deployments are named by the resource, so need the prefix too
+    let config = SummarizerConfig::parse("azure:gpt-4o-mini").unwrap();
+    assert_eq!(config.provider, LLMProvider::AzureOpenAI);
+    assert_eq!(config.spec(), "azure:gpt-4o-mini");
+
+    // As are models of the OpenAI-compatible API
+    let config = SummarizerConfig::parse("compatible:deepseek-chat").unwrap();
+    assert_eq!(config.provider, LLMProvider::OpenAICompatible);
+    assert_eq!(config.model, "deepseek-chat");
+
+    // Unknown models need an explicit provider
+    assert!(SummarizerConfig::parse("llama3:8b").is_err());
+    assert!(SummarizerConfig::parse("ollama:").is_err());
+}
+
+#[test]
+fn test_set_summarizer_model() {
+    let mut app = App::new();
+
+    let spec = app.set_summarizer_model(Some("gemini-2.0-flash")).unwrap();
+    assert_eq!(spec.as_deref(), Some("gemini:gemini-2.0-flash"));
+    assert!(app.summarizer.is_some());
+
+    // An invalid spec leaves the current summarizer in place
+    assert!(app.set_summarizer_model(Some("mystery-model")).is_err());
+    assert!(app.summarizer.is_some());
+
+    // Clearing falls back to the main model
+    assert_eq!(app.set_summarizer_model(None).unwrap(), None);
+    assert!(app.summarizer.is_none());
+}
+
+#[test]
+fn test_summarizer_without_fallback_agent() {
+    let mut app = App::new();
+    app.summarizer = None;
+
+    // Without a summarizer or an agent to fall back to there is nothing to summarize with
+    let result = app.complete_with_summarizer("Summarize this");
+    assert!(result.is_err());
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("No agent available for summarization"));
+}
@@ -0,0 +1,63 @@
+//! Tests for the per-turn records printed by `oli -p --output-format json`
+
+use oli_server::agent::tool_queue::QueuedCallStatus;
+use oli_server::agent::trail::{TaskTrail, TrailCall, TrailRound};
+use oli_server::app::core::App;
+use oli_server::app::turn_record::TurnOutcome;
+use serde_json::json;
+
+#[test]
+fn test_record_of_a_finished_agent_run() {
+    let mut app = App::new();
+    let task_id = app.create_task("run the tests");
+    app.add_tool_use();
+    app.complete_current_task(120);
+
+    let mut round = TrailRound::new(1, "Running the test suite");
+    round.calls.push(TrailCall::new(
+        "Bash",
+        &json!({ "command": "cargo test" }),
+        QueuedCallStatus::Completed,
+        "test result: ok. 12 passed",
+    ));
+    app.last_trail = Some(TaskTrail::new(
+        &task_id,
+        "run the tests",
+        vec![round],
+        "All 12 tests pass.",
+    ));
+
+    let record = app.record_turn("run the tests", Ok("All 12 tests pass."));
+    assert_eq!(record.task_id.as_deref(), Some(task_id.as_str()));
+    assert_eq!(record.outcome, TurnOutcome::Success);
+    assert_eq!(record.exit_code, 0);
+    assert_eq!(record.tool_count, 1);
+    assert_eq!(record.usage.output_tokens, 120);
+    assert_eq!(app.last_turn.as_ref(), Some(&record));
+
+    let json = serde_json::to_value(&record).unwrap();
+    assert_eq!(json["outcome"], "success");
+    assert_eq!(json["response"], "All 12 tests pass.");
+    assert_eq!(
+        json["tool_calls"],
+        json!([{ "tool": "Bash", "arguments": { "command": "cargo test" }, "status": "completed" }])
+    );
+    assert!(json["duration_ms"].is_u64());
+}
+
+#[test]
+fn test_record_of_a_failed_run() {
+    let mut app = App::new();
+    app.create_task("fix the build");
+    app.fail_current_task("Invalid API key");
+
+    let record = app.record_turn("fix the build", Err("Error running model: Invalid API key"));
+    assert_eq!(record.outcome, TurnOutcome::Error);
+    assert_eq!(record.exit_code, 1);
+    assert_eq!(record.response, None);
+    assert_eq!(
+        record.error.as_deref(),
+        Some("Error running model: Invalid API key")
+    );
+    assert!(record.tool_calls.is_empty());
+}
@@ -0,0 +1,167 @@
+//! Tests for the advisory lock shared by oli instances in one workspace
+
+use oli_server::agent::policy::ToolPolicy;
+use oli_server::app::workspace_lock::{
+    workspace_root, InstanceInfo, SecondInstancePolicy, WorkspaceLock, WORKSPACE_LOCK_FILE,
+};
+use oli_server::App;
+use std::path::Path;
+use std::process::{Child, Command};
+use std::sync::{Arc, Mutex};
+
+// A lock file naming another running process as the holder
+fn held_by_other_process(root: &Path) -> Child {
+    let child = Command::new("sleep").arg("30").spawn().unwrap();
+    let holder = InstanceInfo {
+        pid: child.id(),
+        session_id: "other-session".to_string(),
+        started_at: "2026-01-01T00:00:00+00:00".to_string(),
+        working_dir: root.display().to_string(),
+    };
+    let path = root.join(WORKSPACE_LOCK_FILE);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, serde_json::to_string(&holder).unwrap()).unwrap();
+    child
+}
+
+#[test]
+fn test_first_instance_holds_the_lock() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let lock =
+        WorkspaceLock::acquire(temp_dir.path(), "session-1", SecondInstancePolicy::Warn).unwrap();
+
+    assert!(lock.is_primary());
+    assert!(lock.holder().is_none());
+    assert!(lock.warning().is_none());
+    assert_eq!(lock.tool_policy(), ToolPolicy::AllowAll);
+
+    let contents = std::fs::read_to_string(temp_dir.path().join(WORKSPACE_LOCK_FILE)).unwrap();
+    let info: InstanceInfo = serde_json::from_str(&contents).unwrap();
+    assert_eq!(info.pid, std::process::id());
+    assert_eq!(info.session_id, "session-1");
+
+    // Dropping the lock releases it
+    drop(lock);
+    assert!(!temp_dir.path().join(WORKSPACE_LOCK_FILE).exists());
+}
+
+#[test]
+fn test_second_instance_is_warned() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let mut other = held_by_other_process(temp_dir.path());
+
+    let lock =
+        WorkspaceLock::acquire(temp_dir.path(), "session-2", SecondInstancePolicy::Warn).unwrap();
+    assert!(!lock.is_primary());
+    assert_eq!(lock.holder().unwrap().session_id, "other-session");
+    assert!(!lock.is_read_only());
+    let warning = lock.warning().unwrap();
+    assert!(warning.contains(&format!("pid {}", other.id())));
+    assert!(!warning.contains("read-only"));
+
+    // A second instance leaves the holder's lock file in place
+    drop(lock);
+    assert!(temp_dir.path().join(WORKSPACE_LOCK_FILE).exists());
+
+    other.kill().unwrap();
+    other.wait().unwrap();
+}
+
+#[test]
+fn test_read_only_enforcement_and_override() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let mut other = held_by_other_process(temp_dir.path());
+
+    let mut lock =
+        WorkspaceLock::acquire(temp_dir.path(), "session-2", SecondInstancePolicy::ReadOnly)
+            .unwrap();
+    assert!(lock.is_read_only());
+    assert_eq!(lock.tool_policy(), ToolPolicy::ReadOnly);
+    assert!(lock.warning().unwrap().contains("/takeover"));
+
+    lock.override_read_only();
+    assert!(!lock.is_read_only());
+    assert_eq!(lock.tool_policy(), ToolPolicy::AllowAll);
+
+    // Refreshing while the holder is still running keeps the override
+    lock.refresh().unwrap();
+    assert!(!lock.is_primary());
+    assert!(!lock.is_read_only());
+
+    other.kill().unwrap();
+    other.wait().unwrap();
+}
+
+#[test]
+fn test_stale_lock_is_taken_over() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let mut other = held_by_other_process(temp_dir.path());
+
+    let mut lock =
+        WorkspaceLock::acquire(temp_dir.path(), "session-2", SecondInstancePolicy::ReadOnly)
+            .unwrap();
+    assert!(!lock.is_primary());
+
+    // Once the holder exits, the next check takes the lock
+    other.kill().unwrap();
+    other.wait().unwrap();
+    lock.refresh().unwrap();
+
+    assert!(lock.is_primary());
+    assert!(!lock.is_read_only());
+    let contents = std::fs::read_to_string(temp_dir.path().join(WORKSPACE_LOCK_FILE)).unwrap();
+    assert!(contents.contains("session-2"));
+}
+
+#[test]
+fn test_workspace_root_is_the_repository() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let nested = temp_dir.path().join("src").join("app");
+    std::fs::create_dir_all(&nested).unwrap();
+    assert_eq!(workspace_root(&nested), nested);
+
+    std::fs::create_dir(temp_dir.path().join(".git")).unwrap();
+    assert_eq!(workspace_root(&nested), temp_dir.path());
+}
+
+#[test]
+fn test_second_instance_policy_from_env() {
+    std::env::set_var("OLI_SECOND_INSTANCE", "read-only");
+    assert_eq!(
+        SecondInstancePolicy::from_env(),
+        SecondInstancePolicy::ReadOnly
+    );
+    std::env::set_var("OLI_SECOND_INSTANCE", "warn");
+    assert_eq!(SecondInstancePolicy::from_env(), SecondInstancePolicy::Warn);
+    std::env::remove_var("OLI_SECOND_INSTANCE");
+    assert_eq!(SecondInstancePolicy::from_env(), SecondInstancePolicy::Warn);
+}
+
+#[test]
+fn test_daemon_sessions_share_the_lock() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let mut other = held_by_other_process(temp_dir.path());
+
+    let lock =
+        WorkspaceLock::acquire(temp_dir.path(), "daemon", SecondInstancePolicy::ReadOnly).unwrap();
+    let mut app = App::new();
+    app.model_parameters.temperature = Some(0.3);
+    app.workspace_lock = Some(Arc::new(Mutex::new(lock)));
+
+    // A client's session is read-only like the daemon it connected to
+    let session = app.new_session();
+    assert_ne!(session.session_id, app.session_id);
+    assert_eq!(session.model_parameters.temperature, Some(0.3));
+    let shared = session.workspace_lock.clone().unwrap();
+    assert!(Arc::ptr_eq(&shared, app.workspace_lock.as_ref().unwrap()));
+    assert_eq!(shared.lock().unwrap().tool_policy(), ToolPolicy::ReadOnly);
+
+    // Taking over from one session lifts read-only mode for all of them
+    shared.lock().unwrap().override_read_only();
+    let lock = app.workspace_lock.as_ref().unwrap().lock().unwrap();
+    assert_eq!(lock.tool_policy(), ToolPolicy::AllowAll);
+    drop(lock);
+
+    other.kill().unwrap();
+    other.wait().unwrap();
+}
@@ -0,0 +1,211 @@
+//! Tests for the high-level embedding Client
+
+use anyhow::Result;
+use oli_server::apis::api_client::{
+    ApiClient, ApiClientEnum, CompletionOptions, Message, ToolCall as ApiToolCall, ToolResult,
+};
+use oli_server::{Client, LLMProvider, ToolPolicy};
+use std::sync::{Arc, Mutex};
+
+// Mock client returning queued responses and recording the messages it receives
+struct MockApiClient {
+    responses: Mutex<Vec<(String, Option<Vec<ApiToolCall>>)>>,
+    received: Mutex<Vec<Vec<Message>>>,
+}
+
+impl MockApiClient {
+    fn new(responses: Vec<(String, Option<Vec<ApiToolCall>>)>) -> Self {
+        Self {
+            responses: Mutex::new(responses),
+            received: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn next_response(&self) -> (String, Option<Vec<ApiToolCall>>) {
+        let mut responses = self.responses.lock().unwrap();
+        if responses.is_empty() {
+            ("Done".to_string(), None)
+        } else {
+            responses.remove(0)
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ApiClient for MockApiClient {
+    async fn complete(
+        &self,
+        messages: Vec<Message>,
+        _options: CompletionOptions,
+    ) -> Result<String> {
+        self.received.lock().unwrap().push(messages);
+        Ok(self.next_response().0)
+    }
+
+    async fn complete_with_tools(
+        &self,
+        messages: Vec<Message>,
+        _options: CompletionOptions,
+        _tool_results: Option<Vec<ToolResult>>,
+    ) -> Result<(String, Option<Vec<ApiToolCall>>)> {
+        self.received.lock().unwrap().push(messages);
+        Ok(self.next_response())
+    }
+}
+
+#[tokio::test]
+async fn test_send_prompt_keeps_history() -> Result<()> {
+    let mock = Arc::new(MockApiClient::new(vec![(
+        "Hello from the agent".to_string(),
+        None,
+    )]));
+    let mut client = Client::new(LLMProvider::Anthropic)
+        .with_api_client(ApiClientEnum::custom_for_testing(mock.clone()));
+
+    let response = client.send_prompt("Hi", |_| {}).await?;
+    assert_eq!(response, "Hello from the agent");
+
+    // The exchange is kept for the next prompt
+    assert_eq!(client.history().len(), 2);
+    assert_eq!(client.history()[0].content, "Hi");
+    assert_eq!(client.history()[1].content, "Hello from the agent");
+
+    client.clear_history();
+    assert!(client.history().is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_read_only_policy_denies_bash() -> Result<()> {
+    let bash_call = ApiToolCall {
+        id: Some("tool_1".to_string()),
+        name: "Bash".to_string(),
+        arguments: serde_json::json!({ "command": "touch should_not_exist.txt" }),
+    };
+    let mock = Arc::new(MockApiClient::new(vec![
+        ("Running a command".to_string(), Some(vec![bash_call])),
+        ("Finished".to_string(), None),
+    ]));
+    let mut client = Client::new(LLMProvider::Anthropic)
+        .with_api_client(ApiClientEnum::custom_for_testing(mock.clone()))
+        .with_tool_policy(ToolPolicy::ReadOnly);
+    assert_eq!(client.policy(), &ToolPolicy::ReadOnly);
+
+    let mut progress = Vec::new();
+    client
+        .send_prompt("Create a file", |message| {
+            progress.push(message.to_string())
+        })
+        .await?;
+
+    // The progress handler saw the tool execution
+    assert!(progress.iter().any(|m| m.contains("[Bash]")));
+
+    // The model was told the tool was denied
+    let received = mock.received.lock().unwrap();
+    let denied = received
+        .iter()
+        .flatten()
+        .any(|m| m.content.contains("Bash was denied by the tool policy"));
+    assert!(denied, "Bash should be denied under the read-only policy");
+    assert!(!std::path::Path::new("should_not_exist.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_tool_policy_allows() {
+    assert!(ToolPolicy::AllowAll.allows("Bash"));
+    assert!(ToolPolicy::ReadOnly.allows("Read"));
+    assert!(!ToolPolicy::ReadOnly.allows("Edit"));
+
+    let policy = ToolPolicy::Deny(vec!["WebFetch".to_string()]);
+    assert!(!policy.allows("WebFetch"));
+    assert!(policy.allows("Bash"));
+}
+
+#[tokio::test]
+async fn test_approval_backend_denies_bash() -> Result<()> {
+    use oli_server::agent::approval::ApprovalBackend;
+    use std::time::Duration;
+
+    let bash_call = ApiToolCall {
+        id: Some("tool_1".to_string()),
+        name: "Bash".to_string(),
+        arguments: serde_json::json!({ "command": "touch should_not_exist_either.txt" }),
+    };
+    let mock = Arc::new(MockApiClient::new(vec![
+        ("Running a command".to_string(), Some(vec![bash_call])),
+        ("Finished".to_string(), None),
+    ]));
+    let mut client = Client::new(LLMProvider::Anthropic)
+        .with_api_client(ApiClientEnum::custom_for_testing(mock.clone()))
+        .with_approval_backend(ApprovalBackend::Command {
+            command: "cat > /dev/null; exit 1".to_string(),
+            timeout: Duration::from_secs(5),
+        });
+
+    client.send_prompt("Create a file", |_| {}).await?;
+
+    let received = mock.received.lock().unwrap();
+    let denied = received.iter().flatten().any(|m| {
+        m.content
+            .contains("Bash was denied by the approval backend")
+    });
+    assert!(denied, "Bash should be denied by the approval command");
+    assert!(!std::path::Path::new("should_not_exist_either.txt").exists());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_audit_log_records_mutating_tools() -> Result<()> {
+    use oli_server::agent::audit::AuditLog;
+
+    let temp_dir = tempfile::tempdir()?;
+    let file_path = temp_dir.path().join("notes.txt");
+    let audit_log = AuditLog::new(temp_dir.path().join("audit.jsonl"));
+
+    let write_call = ApiToolCall {
+        id: Some("tool_1".to_string()),
+        name: "Write".to_string(),
+        arguments: serde_json::json!({
+            "file_path": file_path.to_string_lossy(),
+            "content": "hello\n",
+        }),
+    };
+    let bash_call = ApiToolCall {
+        id: Some("tool_2".to_string()),
+        name: "Bash".to_string(),
+        arguments: serde_json::json!({ "command": "echo hi" }),
+    };
+    let mock = Arc::new(MockApiClient::new(vec![
+        ("Writing".to_string(), Some(vec![write_call, bash_call])),
+        ("Finished".to_string(), None),
+    ]));
+    let mut client = Client::new(LLMProvider::Anthropic)
+        .with_api_client(ApiClientEnum::custom_for_testing(mock))
+        .with_tool_policy(ToolPolicy::Deny(vec!["Bash".to_string()]))
+        .with_audit_log(audit_log.clone());
+
+    client.send_prompt("Write some notes", |_| {}).await?;
+
+    let entries = audit_log.recent(10)?;
+    assert_eq!(entries.len(), 2);
+
+    assert_eq!(entries[0].tool, "Write");
+    assert_eq!(entries[0].decision, "allowed");
+    assert_eq!(entries[0].outcome.as_deref(), Some("success"));
+    assert!(entries[0].diff_hash.is_some());
+
+    assert_eq!(entries[1].tool, "Bash");
+    assert_eq!(entries[1].decision, "denied");
+    assert!(entries[1].outcome.is_none());
+    assert!(entries[1]
+        .reason
+        .as_deref()
+        .is_some_and(|r| r.contains("tool policy")));
+
+    Ok(())
+}
@@ -1 +1,3 @@
+#[cfg(unix)]
+mod test_daemon;
 mod test_rpc;
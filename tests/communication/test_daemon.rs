@@ -0,0 +1,424 @@
+use oli_server::communication::daemon::{Daemon, DaemonConfig};
+use oli_server::communication::rpc::{get_global_rpc_server, RpcServer};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+/// A client connected to the daemon's socket
+struct Client {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    next_id: u64,
+}
+
+impl Client {
+    /// Connect without authenticating
+    fn connect_anonymously(path: &PathBuf) -> Self {
+        let stream = UnixStream::connect(path).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        Self {
+            reader: BufReader::new(stream.try_clone().unwrap()),
+            writer: stream,
+            next_id: 1,
+        }
+    }
+
+    fn connect(path: &PathBuf, token: &str) -> Self {
+        let mut client = Self::connect_anonymously(path);
+        let response = client.call_with("authenticate", json!({ "token": token }));
+        assert_eq!(response["success"], true);
+        client
+    }
+
+    /// Whether the daemon closed the connection
+    fn is_closed(&mut self) -> bool {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).unwrap() == 0
+    }
+
+    fn read_message(&mut self) -> Value {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    fn send_with(&mut self, method: &str, params: Value) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        writeln!(self.writer, "{request}").unwrap();
+        id
+    }
+
+    fn send(&mut self, method: &str) -> u64 {
+        self.send_with(method, json!({}))
+    }
+
+    fn call_with(&mut self, method: &str, params: Value) -> Value {
+        let id = self.send_with(method, params);
+        let response = self.read_message();
+        assert_eq!(response["id"], id);
+        response["result"].clone()
+    }
+
+    fn call(&mut self, method: &str) -> Value {
+        self.call_with(method, json!({}))
+    }
+}
+
+#[test]
+fn test_daemon_config_from_args() {
+    let config =
+        DaemonConfig::from_args(&args(&["--socket", "/tmp/oli.sock", "--tcp", "7070"])).unwrap();
+    assert_eq!(config.socket, Some(PathBuf::from("/tmp/oli.sock")));
+    assert_eq!(config.tcp_port, Some(7070));
+
+    // TCP alone doesn't add the default socket
+    let config = DaemonConfig::from_args(&args(&["--tcp", "0"])).unwrap();
+    assert_eq!(config.socket, None);
+
+    assert_eq!(config.token_file, None);
+
+    let config = DaemonConfig::from_args(&args(&["--token-file", "/tmp/oli.token"])).unwrap();
+    assert_eq!(config.token_file, Some(PathBuf::from("/tmp/oli.token")));
+
+    assert!(DaemonConfig::from_args(&args(&["--tcp"])).is_err());
+    assert!(DaemonConfig::from_args(&args(&["--tcp", "http"])).is_err());
+    assert!(DaemonConfig::from_args(&args(&["--verbose"])).is_err());
+}
+
+#[test]
+fn test_daemon_serves_each_connection_its_own_session() {
+    let dir = TempDir::new().unwrap();
+    let socket = dir.path().join("oli.sock");
+    let daemon = Daemon::bind(&DaemonConfig {
+        socket: Some(socket.clone()),
+        tcp_port: None,
+        token_file: Some(dir.path().join("daemon.token")),
+    })
+    .unwrap();
+    assert_eq!(
+        daemon.addresses(),
+        vec![format!("unix:{}", socket.display())]
+    );
+
+    let token = daemon.token().to_string();
+    let hub = RpcServer::with_output(std::io::sink());
+    let hub_clone = hub.clone();
+    let running = std::thread::spawn(move || {
+        daemon.run(&hub_clone, |connection| {
+            let count = Arc::new(AtomicU64::new(0));
+            connection.register_method("count", move |_| {
+                Ok(json!({ "count": count.fetch_add(1, Ordering::SeqCst) + 1 }))
+            });
+        })
+    });
+
+    let mut first = Client::connect(&socket, &token);
+    let mut second = Client::connect(&socket, &token);
+    assert_eq!(first.call("count")["count"], 1);
+    assert_eq!(first.call("count")["count"], 2);
+    assert_eq!(second.call("count")["count"], 1);
+
+    // Notifications sent through the daemon reach every client
+    hub.send_notification("index_progress", json!({ "indexed": 3 }))
+        .unwrap();
+    for client in [&mut first, &mut second] {
+        let notification = client.read_message();
+        assert_eq!(notification["method"], "index_progress");
+        assert_eq!(notification["params"]["indexed"], 3);
+    }
+
+    assert_eq!(second.call("shutdown")["success"], true);
+    running.join().unwrap().unwrap();
+    assert!(!socket.exists());
+    assert!(!dir.path().join("daemon.token").exists());
+    assert!(!hub.is_running());
+}
+
+#[test]
+fn test_daemon_sends_a_runs_messages_to_its_own_client() {
+    let dir = TempDir::new().unwrap();
+    let socket = dir.path().join("oli.sock");
+    let daemon = Daemon::bind(&DaemonConfig {
+        socket: Some(socket.clone()),
+        tcp_port: None,
+        token_file: Some(dir.path().join("daemon.token")),
+    })
+    .unwrap();
+
+    let token = daemon.token().to_string();
+    let hub = RpcServer::with_output(std::io::sink());
+    let hub_clone = hub.clone();
+    let running = std::thread::spawn(move || {
+        daemon.run(&hub_clone, |connection| {
+            connection.register_method("ping", |_| Ok(json!("pong")));
+            // Like a run that streams output and asks the client for a permission
+            connection.register_background_method("run", |_| {
+                let server = get_global_rpc_server().unwrap();
+                server.send_notification("content_delta", json!({ "delta": "Hi" }))?;
+                std::thread::spawn(move || {
+                    server.request_client("permission", json!({}), Duration::from_secs(10))
+                })
+                .join()
+                .unwrap()
+            });
+        })
+    });
+
+    let mut first = Client::connect(&socket, &token);
+    let mut second = Client::connect(&socket, &token);
+    // The second client sent the latest request, which doesn't make the first's
+    // run talk to it
+    assert_eq!(second.call("ping"), "pong");
+    let id = first.send("run");
+    assert_eq!(second.call("ping"), "pong");
+
+    let delta = first.read_message();
+    assert_eq!(delta["method"], "content_delta");
+    let request = first.read_message();
+    assert_eq!(request["method"], "permission");
+    writeln!(
+        first.writer,
+        "{}",
+        json!({ "jsonrpc": "2.0", "id": request["id"], "result": { "allowed": true } })
+    )
+    .unwrap();
+    let response = first.read_message();
+    assert_eq!(response["id"], id);
+    assert_eq!(response["result"]["allowed"], true);
+
+    // Nothing of the first client's run reached the second
+    assert_eq!(second.call("ping"), "pong");
+
+    // Outside a connection's request there's no client to ask
+    assert!(hub
+        .request_client("permission", json!({}), Duration::from_secs(1))
+        .is_err());
+
+    assert_eq!(first.call("shutdown")["success"], true);
+    running.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_answers_are_read_while_a_method_waits_on_the_asking_run() {
+    let dir = TempDir::new().unwrap();
+    let socket = dir.path().join("oli.sock");
+    let daemon = Daemon::bind(&DaemonConfig {
+        socket: Some(socket.clone()),
+        tcp_port: None,
+        token_file: Some(dir.path().join("daemon.token")),
+    })
+    .unwrap();
+
+    let token = daemon.token().to_string();
+    let hub = RpcServer::with_output(std::io::sink());
+    let hub_clone = hub.clone();
+    let running = std::thread::spawn(move || {
+        daemon.run(&hub_clone, |connection| {
+            // Like a run holding the app while it asks for permission, and a method
+            // that needs the app, such as completing a file path
+            let app = Arc::new(Mutex::new(()));
+            let run_app = app.clone();
+            connection.register_background_method("run", move |_| {
+                let _app = run_app.lock().unwrap();
+                get_global_rpc_server().unwrap().request_client(
+                    "permission",
+                    json!({}),
+                    Duration::from_secs(10),
+                )
+            });
+            connection.register_method("complete", move |_| {
+                let _app = app.lock().unwrap();
+                Ok(json!("done"))
+            });
+        })
+    });
+
+    let mut client = Client::connect(&socket, &token);
+    let run = client.send("run");
+    let request = client.read_message();
+    assert_eq!(request["method"], "permission");
+
+    // The method blocks the connection's loop until the run ends, which it only
+    // does once the answer sent after it is read
+    let complete = client.send("complete");
+    std::thread::sleep(Duration::from_millis(100));
+    writeln!(
+        client.writer,
+        "{}",
+        json!({ "jsonrpc": "2.0", "id": request["id"], "result": { "allowed": true } })
+    )
+    .unwrap();
+
+    let responses = [client.read_message(), client.read_message()];
+    let response = |id: u64| responses.iter().find(|r| r["id"] == id).unwrap().clone();
+    assert_eq!(response(run)["result"]["allowed"], true);
+    assert_eq!(response(complete)["result"], "done");
+
+    assert_eq!(client.call("shutdown")["success"], true);
+    running.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_daemon_replaces_stale_socket_only() {
+    let dir = TempDir::new().unwrap();
+    let socket = dir.path().join("oli.sock");
+    let config = DaemonConfig {
+        socket: Some(socket.clone()),
+        tcp_port: None,
+        token_file: Some(dir.path().join("daemon.token")),
+    };
+
+    // A socket file whose listener is gone is left by a daemon that crashed
+    drop(UnixListener::bind(&socket).unwrap());
+    assert!(socket.exists());
+    let daemon = Daemon::bind(&config).unwrap();
+
+    // One a daemon still listens on is not taken over
+    assert!(Daemon::bind(&config).is_err());
+    drop(daemon);
+}
+
+#[test]
+fn test_daemon_listens_on_local_tcp() {
+    let dir = TempDir::new().unwrap();
+    let daemon = Daemon::bind(&DaemonConfig {
+        socket: None,
+        tcp_port: Some(0),
+        token_file: Some(dir.path().join("daemon.token")),
+    })
+    .unwrap();
+    let token = daemon.token().to_string();
+    let address = daemon.addresses().remove(0);
+    assert!(address.starts_with("tcp:127.0.0.1:"), "{address}");
+
+    let shutdown = daemon.shutdown_flag();
+    let hub = RpcServer::with_output(std::io::sink());
+    let running = std::thread::spawn(move || {
+        daemon.run(&hub, |connection| {
+            connection.register_method("ping", |_| Ok(json!("pong")));
+        })
+    });
+
+    let mut stream = std::net::TcpStream::connect(address.trim_start_matches("tcp:")).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    writeln!(
+        stream,
+        "{}",
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "authenticate", "params": { "token": token } })
+    )
+    .unwrap();
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Value>(&line).unwrap()["result"]["success"],
+        true
+    );
+
+    writeln!(
+        stream,
+        "{}",
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "ping", "params": {} })
+    )
+    .unwrap();
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Value>(&line).unwrap()["result"],
+        "pong"
+    );
+
+    // Setting the flag closes connections that are still open
+    shutdown.store(true, Ordering::SeqCst);
+    running.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_daemon_requires_its_token_first() {
+    let dir = TempDir::new().unwrap();
+    let socket = dir.path().join("oli.sock");
+    let token_file = dir.path().join("daemon.token");
+    let daemon = Daemon::bind(&DaemonConfig {
+        socket: Some(socket.clone()),
+        tcp_port: None,
+        token_file: Some(token_file.clone()),
+    })
+    .unwrap();
+
+    // The token is written for this user only
+    let token = std::fs::read_to_string(&token_file).unwrap();
+    assert_eq!(token, daemon.token());
+    assert_eq!(token.len(), 64);
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&token_file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let mode = std::fs::metadata(&socket).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    // The socket was bound in a private directory, which is gone
+    let mut entries: Vec<String> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    entries.sort();
+    assert_eq!(entries, ["daemon.token", "oli.sock"]);
+
+    let called = Arc::new(AtomicU64::new(0));
+    let called_clone = called.clone();
+    let hub = RpcServer::with_output(std::io::sink());
+    let running = std::thread::spawn(move || {
+        daemon.run(&hub, move |connection| {
+            let called = called_clone.clone();
+            connection.register_method("ping", move |_| {
+                called.fetch_add(1, Ordering::SeqCst);
+                Ok(json!("pong"))
+            });
+        })
+    });
+
+    // A method before authenticating is refused and the connection closed
+    let mut client = Client::connect_anonymously(&socket);
+    client.send("ping");
+    assert_eq!(client.read_message()["error"]["code"], -32001);
+    assert!(client.is_closed());
+
+    // So is a wrong token
+    let mut client = Client::connect_anonymously(&socket);
+    client.send_with("authenticate", json!({ "token": "0".repeat(64) }));
+    assert!(client.read_message()["error"].is_object());
+    assert!(client.is_closed());
+
+    // An endless first line is cut short and refused rather than buffered
+    let mut client = Client::connect_anonymously(&socket);
+    let _ = client.writer.write_all(&vec![b'x'; 1 << 20]);
+    assert_eq!(client.read_message()["error"]["code"], -32001);
+    assert!(client.is_closed());
+    assert_eq!(called.load(Ordering::SeqCst), 0);
+
+    // Once authenticated, a line that isn't JSON-RPC ends the connection
+    let mut client = Client::connect(&socket, &token);
+    assert_eq!(client.call("ping"), "pong");
+    writeln!(client.writer, "not json").unwrap();
+    assert_eq!(client.read_message()["error"]["code"], -32700);
+    assert!(client.is_closed());
+
+    let mut client = Client::connect(&socket, &token);
+    assert_eq!(client.call("shutdown")["success"], true);
+    running.join().unwrap().unwrap();
+}
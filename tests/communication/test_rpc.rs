@@ -1,5 +1,11 @@
-use oli_server::communication::rpc::{get_global_rpc_server, RpcServer, SubscriptionManager};
+use oli_server::communication::capabilities::{
+    ClientCapabilities, ServerCapabilities, PROTOCOL_VERSION,
+};
+use oli_server::communication::rpc::{
+    get_global_rpc_server, ResponseStream, RpcServer, SubscriptionManager,
+};
 use serde_json::json;
+use std::sync::{Arc, Mutex};
 
 #[test]
 fn test_subscription_manager() {
@@ -162,3 +168,152 @@ fn test_global_rpc_server() {
     // Due to Once initialization, we can't reset this between tests
     // In a real application this is desirable behavior
 }
+
+#[test]
+fn test_initialize_handshake() {
+    let mut server = RpcServer::new();
+    server.register_initialize_handler("1.2.3");
+
+    // Before the handshake no capabilities are known
+    let fresh = RpcServer::new();
+    assert!(fresh.client_capabilities().is_none());
+
+    let result = server
+        .call_method(
+            "initialize",
+            json!({
+                "capabilities": {
+                    "supports_notifications": true,
+                    "supports_permission_prompts": true
+                }
+            }),
+        )
+        .expect("initialize should succeed");
+
+    assert_eq!(result["server_info"]["version"], "1.2.3");
+    assert_eq!(result["protocol_version"], PROTOCOL_VERSION);
+    assert_eq!(result["capabilities"]["permission_prompts"], true);
+
+    let tools = result["capabilities"]["tools"].as_array().unwrap();
+    assert!(tools.iter().any(|t| t == "Bash"));
+
+    let caps = server.client_capabilities().expect("capabilities stored");
+    assert!(caps.supports_permission_prompts);
+}
+
+#[test]
+fn test_client_capabilities_auto_deny() {
+    // Missing fields fall back to defaults, which can't answer prompts
+    let caps = ClientCapabilities::from_params(&json!({ "capabilities": {} }));
+    assert!(caps.supports_notifications);
+    assert!(!caps.supports_permission_prompts);
+
+    assert!(caps.should_auto_deny("Bash"));
+    assert!(caps.should_auto_deny("Edit"));
+    assert!(!caps.should_auto_deny("Read"));
+
+    let server_caps = ServerCapabilities::negotiate(&caps);
+    assert!(!server_caps.tools.iter().any(|t| t == "Write"));
+    assert!(server_caps.tools.iter().any(|t| t == "Grep"));
+
+    let interactive = ClientCapabilities {
+        supports_permission_prompts: true,
+        ..ClientCapabilities::default()
+    };
+    assert!(!interactive.should_auto_deny("Bash"));
+}
+
+#[test]
+fn test_call_method_unknown() {
+    let server = RpcServer::new();
+    assert!(server.call_method("does_not_exist", json!({})).is_err());
+}
+
+#[test]
+fn test_streaming_method_notifications_carry_request_id() {
+    let mut server = RpcServer::new();
+    server.register_streaming_method("echo_stream", |params, stream| {
+        for word in params["text"].as_str().unwrap_or_default().split(' ') {
+            stream.delta(word);
+        }
+        stream.done(json!({ "output_tokens": 2 }));
+        Ok(json!({ "response": params["text"] }))
+    });
+
+    let notifications = Arc::new(Mutex::new(Vec::new()));
+    let notifications_clone = notifications.clone();
+    let stream = ResponseStream::new(Some(7), move |method, params| {
+        notifications_clone
+            .lock()
+            .unwrap()
+            .push((method.to_string(), params));
+    });
+
+    let result = server
+        .call_streaming_method("echo_stream", json!({ "text": "hello world" }), &stream)
+        .unwrap();
+    assert_eq!(result["response"], "hello world");
+
+    let notifications = notifications.lock().unwrap();
+    assert_eq!(
+        *notifications,
+        vec![
+            (
+                "content_delta".to_string(),
+                json!({ "request_id": 7, "delta": "hello" })
+            ),
+            (
+                "content_delta".to_string(),
+                json!({ "request_id": 7, "delta": "world" })
+            ),
+            (
+                "done".to_string(),
+                json!({ "request_id": 7, "usage": { "output_tokens": 2 } })
+            ),
+        ]
+    );
+
+    // Streaming methods can also be called like any other method
+    let result = server
+        .call_method("echo_stream", json!({ "text": "hi" }))
+        .unwrap();
+    assert_eq!(result["response"], "hi");
+}
+
+#[test]
+fn test_call_background_method() {
+    let mut server = RpcServer::new();
+    server.register_background_method("slow_method", |params| {
+        let value = params["value"].as_i64().unwrap_or(0);
+        Ok(json!({ "result": value + 1 }))
+    });
+
+    let result = server
+        .call_method("slow_method", json!({ "value": 41 }))
+        .unwrap();
+    assert_eq!(result["result"], 42);
+}
+
+#[test]
+fn test_client_requests_need_a_running_server() {
+    let server = RpcServer::new();
+    let result = server.request_client(
+        "workspace/applyEdit",
+        json!({ "edit": {} }),
+        std::time::Duration::from_millis(10),
+    );
+    assert!(result.is_err());
+
+    // Answers nobody is waiting for are dropped
+    assert!(!server.resolve_client_request(&json!({ "jsonrpc": "2.0", "id": 99, "result": {} })));
+}
+
+#[test]
+fn test_apply_edit_capability_is_negotiated() {
+    let caps = ClientCapabilities::from_params(&json!({
+        "capabilities": { "supports_apply_edit": true }
+    }));
+    assert!(caps.supports_apply_edit);
+    assert!(ServerCapabilities::negotiate(&caps).apply_edit);
+    assert!(!ServerCapabilities::negotiate(&ClientCapabilities::default()).apply_edit);
+}
@@ -0,0 +1,8 @@
+// Context module tests
+pub mod test_editor;
+pub mod test_file_mentions;
+pub mod test_git_history;
+pub mod test_images;
+pub mod test_piped_input;
+pub mod test_project_instructions;
+pub mod test_provider;
@@ -0,0 +1,103 @@
+//! Tests for the editor context sent by editor extensions
+
+use oli_server::context::editor::{EditorContext, EDITOR_CONTEXT_PROVIDER, MAX_SELECTION_CHARS};
+use oli_server::context::provider::{ContextAssembler, ContextRequest};
+use serde_json::json;
+use std::sync::Arc;
+
+#[test]
+fn test_section_describes_file_cursor_and_selection() {
+    let context = EditorContext::from_params(&json!({
+        "file": "/work/project/src/main.rs",
+        "cursor": { "line": 11, "character": 4 },
+        "selection": {
+            "start": { "line": 9, "character": 0 },
+            "end": { "line": 13, "character": 1 },
+            "text": "fn main() {\n    run();\n}\n"
+        },
+        "open_files": ["/work/project/src/main.rs", "/work/project/src/lib.rs", "/etc/hosts"]
+    }))
+    .unwrap();
+
+    let section = context.section(Some("/work/project")).unwrap();
+    assert!(section.starts_with("## EDITOR"));
+    assert!(section.contains("`src/main.rs` in their editor, with the cursor at line 12, column 5"));
+    assert!(section.contains("Selected: lines 10-14"));
+    assert!(section.contains("```\nfn main() {\n    run();\n}\n```"));
+    // The focused file isn't listed again; files outside the project keep their path
+    assert!(section.contains("Also open: `src/lib.rs`, `/etc/hosts`"));
+}
+
+#[test]
+fn test_empty_context_adds_nothing() {
+    let context = EditorContext::from_params(&json!({ "file": "" })).unwrap();
+    assert!(context.is_empty());
+    assert_eq!(context.section(None), None);
+    assert!(EditorContext::from_params(&json!({})).unwrap().is_empty());
+
+    // A cursor without a selection isn't a selection
+    let context = EditorContext::from_params(&json!({
+        "file": "a.rs",
+        "selection": {
+            "start": { "line": 2, "character": 3 },
+            "end": { "line": 2, "character": 3 }
+        }
+    }))
+    .unwrap();
+    assert_eq!(context.selection, None);
+
+    assert!(EditorContext::from_params(&json!({ "cursor": { "line": "one" } })).is_err());
+}
+
+#[test]
+fn test_long_selection_is_cut() {
+    let text = "x".repeat(MAX_SELECTION_CHARS + 10);
+    let context = EditorContext::from_params(&json!({
+        "file": "a.rs",
+        "selection": {
+            "start": { "line": 0, "character": 0 },
+            "end": { "line": 0, "character": text.len() },
+            "text": text
+        }
+    }))
+    .unwrap();
+
+    let section = context.section(None).unwrap();
+    assert!(section.contains(&format!(
+        "[Selection cut to {MAX_SELECTION_CHARS} of {} characters]",
+        MAX_SELECTION_CHARS + 10
+    )));
+    assert!(!section.contains(&"x".repeat(MAX_SELECTION_CHARS + 1)));
+}
+
+#[test]
+fn test_selection_containing_a_fence_stays_fenced() {
+    let context = EditorContext::from_params(&json!({
+        "file": "README.md",
+        "selection": {
+            "start": { "line": 0, "character": 0 },
+            "end": { "line": 2, "character": 3 },
+            "text": "```rust\nlet x = 1;\n```"
+        }
+    }))
+    .unwrap();
+    assert!(context
+        .section(None)
+        .unwrap()
+        .contains("````\n```rust\nlet x = 1;\n```\n````"));
+}
+
+#[test]
+fn test_editor_context_is_a_context_provider() {
+    let context = EditorContext::from_params(&json!({ "file": "/repo/src/app.ts" })).unwrap();
+    let assembler = ContextAssembler::new().with_provider(Arc::new(context));
+    assert_eq!(assembler.provider_names(), vec![EDITOR_CONTEXT_PROVIDER]);
+
+    let assembled = assembler
+        .assemble(&ContextRequest {
+            query: "what does this file do?",
+            working_directory: Some("/repo"),
+        })
+        .unwrap();
+    assert!(assembled.contains("`src/app.ts`"));
+}
@@ -0,0 +1,75 @@
+//! Tests for attaching files mentioned with @path to prompts
+
+use oli_server::context::file_mentions::{
+    complete_file_path, expand_file_mentions, file_mentions, MAX_MENTIONED_FILE_BYTES,
+};
+use std::fs;
+use tempfile::TempDir;
+
+fn project() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("src/app")).unwrap();
+    fs::write(dir.path().join("src/app/core.rs"), "pub struct App;\n").unwrap();
+    fs::write(dir.path().join("src/apis.rs"), "pub mod anthropic;\n").unwrap();
+    fs::write(dir.path().join("README.md"), "# Project\n").unwrap();
+    dir
+}
+
+#[test]
+fn test_file_mentions() {
+    assert_eq!(
+        file_mentions("@README.md compare with @src/app/core.rs, and @README.md again"),
+        vec!["README.md", "src/app/core.rs"]
+    );
+    // Email addresses and bare @ signs aren't mentions
+    assert!(file_mentions("mail me@example.com @ noon").is_empty());
+}
+
+#[test]
+fn test_expand_attaches_mentioned_files() {
+    let dir = project();
+    let prompt = expand_file_mentions("what does @src/app/core.rs define?", dir.path());
+    assert_eq!(
+        prompt,
+        "what does @src/app/core.rs define?\n\n## MENTIONED FILES\n\n<file path=\"src/app/core.rs\">\npub struct App;\n</file>"
+    );
+
+    // Mentions of people or missing files leave the prompt alone
+    let prompt = "thanks @alice, see @missing.rs";
+    assert_eq!(expand_file_mentions(prompt, dir.path()), prompt);
+}
+
+#[test]
+fn test_expand_notes_files_it_cannot_attach() {
+    let dir = project();
+    fs::write(
+        dir.path().join("big.log"),
+        "x".repeat(MAX_MENTIONED_FILE_BYTES as usize + 1),
+    )
+    .unwrap();
+    fs::write(dir.path().join("logo.png"), [0x89, b'P', b'N', b'G', 0, 0]).unwrap();
+
+    let prompt = expand_file_mentions("look at @big.log @logo.png @src/app", dir.path());
+    assert!(
+        prompt.contains("big.log is too large to attach"),
+        "{prompt}"
+    );
+    assert!(prompt.contains("use the Read tool"));
+    assert!(prompt.contains("logo.png is a binary file"));
+    assert!(prompt.contains("src/app is a directory"));
+    assert!(!prompt.contains("<file"));
+}
+
+#[test]
+fn test_complete_file_path() {
+    let dir = project();
+    let paths = complete_file_path("src/ap", dir.path(), 10);
+    assert_eq!(paths.len(), 2, "{paths:?}");
+    assert!(paths.contains(&"src/apis.rs".to_string()));
+    assert!(paths.contains(&"src/app/core.rs".to_string()));
+    assert_eq!(
+        complete_file_path("core", dir.path(), 1),
+        vec!["src/app/core.rs"]
+    );
+    assert_eq!(complete_file_path("", dir.path(), 10).len(), 3);
+}
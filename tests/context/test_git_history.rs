@@ -0,0 +1,93 @@
+//! Tests for the git history context provider
+
+use oli_server::context::git_history::GitHistory;
+use std::path::Path;
+use std::process::Command;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args([
+            "-c",
+            "user.name=Test Author",
+            "-c",
+            "user.email=test@example.com",
+        ])
+        .args(args)
+        .output()
+        .expect("git should be installed")
+        .status;
+    assert!(status.success(), "git {args:?} failed");
+}
+
+fn repo_with_history() -> tempfile::TempDir {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    git(dir, &["init", "-q"]);
+
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    for (i, subject) in [
+        "Add parser",
+        "Handle empty input",
+        "Rename tokens",
+        "Speed up lexer",
+    ]
+    .iter()
+    .enumerate()
+    {
+        std::fs::write(dir.join("src/parser.rs"), format!("// version {i}\n")).unwrap();
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-q", "-m", subject]);
+    }
+    std::fs::write(dir.join("README.md"), "untracked\n").unwrap();
+
+    temp_dir
+}
+
+#[test]
+fn test_mentioned_files() {
+    let repo = repo_with_history();
+    let history = GitHistory::new(repo.path());
+
+    let files =
+        history.mentioned_files("Refactor `src/parser.rs`, see README.md. Also fix main.rs");
+    // main.rs doesn't exist, so only real files are picked up
+    assert_eq!(files, vec!["src/parser.rs", "README.md"]);
+}
+
+#[test]
+fn test_context_lists_last_three_commits() {
+    let repo = repo_with_history();
+    let history = GitHistory::new(repo.path());
+
+    let commits = history.recent_commits("src/parser.rs");
+    assert_eq!(commits.len(), 3);
+    assert_eq!(commits[0].subject, "Speed up lexer");
+    assert_eq!(commits[0].author, "Test Author");
+
+    let context = history
+        .context_for("Why is src/parser.rs so slow?")
+        .expect("parser.rs has history");
+    assert!(context.starts_with("## RECENT GIT HISTORY"));
+    assert!(context.contains("src/parser.rs:"));
+    assert!(context.contains("Speed up lexer"));
+    assert!(!context.contains("Add parser"));
+}
+
+#[test]
+fn test_no_context_without_history() {
+    let repo = repo_with_history();
+    let history = GitHistory::new(repo.path());
+
+    // Untracked files and prompts without file names add nothing
+    assert!(history.context_for("Update README.md").is_none());
+    assert!(history.context_for("What does this project do?").is_none());
+
+    // Directories outside a git repository add nothing
+    let plain_dir = tempfile::tempdir().unwrap();
+    std::fs::write(plain_dir.path().join("notes.txt"), "hi").unwrap();
+    assert!(GitHistory::new(plain_dir.path())
+        .context_for("Read notes.txt")
+        .is_none());
+}
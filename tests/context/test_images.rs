@@ -0,0 +1,56 @@
+//! Tests for attaching images to prompts with /attach
+
+use oli_server::apis::api_client::split_images;
+use oli_server::context::images::{attach_images, ImageAttachment, MAX_ATTACHED_IMAGES};
+use tempfile::tempdir;
+
+const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+#[test]
+fn test_load_image_relative_to_working_dir() {
+    let dir = tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("shots")).unwrap();
+    std::fs::write(dir.path().join("shots/trace.png"), PNG).unwrap();
+
+    let attachment = ImageAttachment::load("shots/trace.png", dir.path()).unwrap();
+    assert_eq!(attachment.path, dir.path().join("shots/trace.png"));
+    assert_eq!(attachment.image.media_type, "image/png");
+    assert_eq!(attachment.bytes, PNG.len() as u64);
+    assert!(!attachment.image.data.is_empty());
+}
+
+#[test]
+fn test_only_images_can_be_attached() {
+    let dir = tempdir().unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "not an image").unwrap();
+
+    let error = ImageAttachment::load("notes.txt", dir.path()).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("is not a PNG, JPEG, GIF or WebP image"));
+    assert!(ImageAttachment::load("missing.png", dir.path()).is_err());
+
+    let too_many = vec!["a.png".to_string(); MAX_ATTACHED_IMAGES + 1];
+    let error = ImageAttachment::load_all(&too_many, dir.path()).unwrap_err();
+    assert!(error.to_string().contains("at most"));
+}
+
+#[test]
+fn test_attach_images_to_prompt() {
+    let dir = tempdir().unwrap();
+    std::fs::write(dir.path().join("mockup.png"), PNG).unwrap();
+    let paths = vec!["mockup.png".to_string()];
+    let images: Vec<_> = ImageAttachment::load_all(&paths, dir.path())
+        .unwrap()
+        .into_iter()
+        .map(|attachment| attachment.image)
+        .collect();
+
+    let prompt = attach_images("What is wrong with this layout?", &images);
+    let (text, found) = split_images(&prompt);
+    assert_eq!(text.trim(), "What is wrong with this layout?");
+    assert_eq!(found, images);
+
+    // Without images the prompt is unchanged
+    assert_eq!(attach_images("Hello", &[]), "Hello");
+}
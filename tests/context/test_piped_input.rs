@@ -0,0 +1,112 @@
+//! Tests for attaching piped stdin to headless prompts
+
+use oli_server::context::piped_input::{
+    attach_context_block, attach_piped_input, PipedFormat, MAX_PIPED_INPUT_BYTES,
+};
+
+#[test]
+fn test_detect_format() {
+    let diff = "diff --git a/src/lib.rs b/src/lib.rs\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-a\n+b\n";
+    assert_eq!(PipedFormat::detect(diff), PipedFormat::Diff);
+
+    assert_eq!(
+        PipedFormat::detect("{\"status\": \"failed\", \"code\": 3}"),
+        PipedFormat::Json
+    );
+
+    let log =
+        "2024-05-01 12:00:01 INFO starting server\n2024-05-01 12:00:02 ERROR connection refused\n";
+    assert_eq!(PipedFormat::detect(log), PipedFormat::Log);
+
+    assert_eq!(
+        PipedFormat::detect("Just some notes\nabout the project"),
+        PipedFormat::Text
+    );
+    // Braces alone don't make JSON
+    assert_eq!(PipedFormat::detect("{ not json"), PipedFormat::Text);
+}
+
+#[test]
+fn test_attach_piped_input() {
+    let prompt = attach_piped_input("explain this failure", "[ERROR] disk full\n");
+    assert!(prompt.starts_with("explain this failure\n\n## PIPED INPUT (log)"));
+    assert!(prompt.contains("```log\n[ERROR] disk full\n```"));
+
+    // Empty input leaves the prompt unchanged
+    assert_eq!(attach_piped_input("hello", "  \n"), "hello");
+}
+
+#[test]
+fn test_attach_truncates_large_input() {
+    // Logs keep their end, where the failure usually is
+    let log = format!(
+        "{}ERROR final failure\n",
+        "INFO routine line\n".repeat(10_000)
+    );
+    let prompt = attach_piped_input("why?", &log);
+    assert!(prompt.contains("(truncated from"));
+    assert!(prompt.contains("ERROR final failure"));
+    assert!(prompt.len() < MAX_PIPED_INPUT_BYTES + 200);
+
+    // Other input keeps its beginning
+    let text = format!("first line\n{}", "é".repeat(MAX_PIPED_INPUT_BYTES));
+    let prompt = attach_piped_input("summarize", &text);
+    assert!(prompt.contains("first line"));
+    assert!(prompt.contains("(truncated from"));
+}
+
+#[test]
+fn test_detect_stack_traces() {
+    let python = "Traceback (most recent call last):\n  File \"app.py\", line 3, in <module>\n    main()\nValueError: bad input\n";
+    assert_eq!(PipedFormat::detect(python), PipedFormat::StackTrace);
+
+    let javascript = "TypeError: x is undefined\n    at main (index.js:3:9)\n    at Object.<anonymous> (index.js:7:1)\n";
+    assert_eq!(PipedFormat::detect(javascript), PipedFormat::StackTrace);
+
+    let rust = "thread 'main' panicked at src/main.rs:2:5:\nboom\nstack backtrace:\n   0: rust_begin_unwind\n";
+    assert_eq!(PipedFormat::detect(rust), PipedFormat::StackTrace);
+}
+
+#[test]
+fn test_detect_code_language() {
+    let rust = "use std::fs;\n\nfn main() {\n    let mut x = 1;\n}\n";
+    assert_eq!(PipedFormat::detect(rust), PipedFormat::Code("rust"));
+    assert_eq!(PipedFormat::detect(rust).name(), "rust");
+
+    let python = "import os\n\ndef main():\n    print(os.getcwd())\n";
+    assert_eq!(PipedFormat::detect(python), PipedFormat::Code("python"));
+
+    let typescript = "export interface Props {\n  name: string;\n}\ninterface State {}\n";
+    assert_eq!(
+        PipedFormat::detect(typescript),
+        PipedFormat::Code("typescript")
+    );
+
+    // A single code-like line isn't enough
+    assert_eq!(
+        PipedFormat::detect("use the other branch\nthen rebase"),
+        PipedFormat::Text
+    );
+}
+
+#[test]
+fn test_attach_context_block() {
+    let prompt = attach_context_block(
+        "why does this fail?",
+        "CLIPBOARD",
+        "Traceback (most recent call last):\nKeyError: 'id'\n",
+    );
+    assert!(prompt.starts_with("why does this fail?\n\n## CLIPBOARD (stacktrace)"));
+    assert!(prompt.contains("```stacktrace\nTraceback"));
+
+    // Large stack traces keep both ends
+    let trace = format!(
+        "Traceback (most recent call last):\n{}RecursionError: maximum recursion depth exceeded\n",
+        "  File \"app.py\", line 3, in f\n".repeat(10_000)
+    );
+    let prompt = attach_context_block("why?", "CLIPBOARD", &trace);
+    assert!(prompt.contains("(truncated from"));
+    assert!(prompt.contains("Traceback (most recent call last):"));
+    assert!(prompt.contains("RecursionError"));
+    assert!(prompt.len() < MAX_PIPED_INPUT_BYTES + 200);
+}
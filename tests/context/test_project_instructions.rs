@@ -0,0 +1,134 @@
+//! Tests for reading a project's OLI.md or AGENTS.md into the agent's system prompt
+
+use oli_server::agent::core::{Agent, LLMProvider};
+use oli_server::apis::api_client::{
+    ApiClient, ApiClientEnum, CompletionOptions, Message, ToolCall, ToolResult,
+};
+use oli_server::app::init::starter_file_contents;
+use oli_server::app::memory::MemoryManager;
+use oli_server::context::project_instructions::{
+    ProjectInstructions, MAX_PROJECT_INSTRUCTIONS_BYTES,
+};
+use oli_server::prompts::add_project_instructions_to_prompt;
+use std::sync::{Arc, Mutex};
+
+// Answers straight away, keeping the system message it was sent
+#[derive(Default)]
+struct SystemPromptClient {
+    system: Mutex<Option<String>>,
+}
+
+#[async_trait::async_trait]
+impl ApiClient for SystemPromptClient {
+    async fn complete(
+        &self,
+        _messages: Vec<Message>,
+        _options: CompletionOptions,
+    ) -> anyhow::Result<String> {
+        Ok(String::new())
+    }
+
+    async fn complete_with_tools(
+        &self,
+        messages: Vec<Message>,
+        _options: CompletionOptions,
+        _tool_results: Option<Vec<ToolResult>>,
+    ) -> anyhow::Result<(String, Option<Vec<ToolCall>>)> {
+        *self.system.lock().unwrap() = messages
+            .iter()
+            .find(|msg| msg.role == "system")
+            .map(|msg| msg.content.clone());
+        Ok(("Done".to_string(), None))
+    }
+}
+
+#[test]
+fn test_oli_md_is_preferred_over_agents_md() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    assert_eq!(ProjectInstructions::load(dir), None);
+
+    std::fs::write(dir.join("AGENTS.md"), "Run `make test` before finishing.\n").unwrap();
+    let instructions = ProjectInstructions::load(dir).unwrap();
+    assert_eq!(instructions.file_name(), "AGENTS.md");
+    assert_eq!(instructions.contents, "Run `make test` before finishing.");
+
+    // A blank OLI.md, or one still holding the memory template, is passed over
+    std::fs::write(dir.join("OLI.md"), "\n  \n").unwrap();
+    assert_eq!(
+        ProjectInstructions::load(dir).unwrap().file_name(),
+        "AGENTS.md"
+    );
+    std::fs::write(dir.join("OLI.md"), MemoryManager::default_memory_template()).unwrap();
+    assert_eq!(
+        ProjectInstructions::load(dir).unwrap().file_name(),
+        "AGENTS.md"
+    );
+
+    std::fs::write(dir.join("OLI.md"), "# OLI.md\n\nUse cargo nextest.\n").unwrap();
+    let instructions = ProjectInstructions::load(dir).unwrap();
+    assert_eq!(instructions.file_name(), "OLI.md");
+    assert!(!instructions.truncated);
+}
+
+#[test]
+fn test_long_instructions_are_truncated() {
+    let contents = "é".repeat(MAX_PROJECT_INSTRUCTIONS_BYTES);
+    let instructions = ProjectInstructions::from_contents("OLI.md".into(), &contents).unwrap();
+    assert!(instructions.truncated);
+    assert!(instructions.contents.len() <= MAX_PROJECT_INSTRUCTIONS_BYTES);
+
+    let prompt = add_project_instructions_to_prompt("You are oli.", &instructions);
+    assert!(prompt.contains("read it for the rest"));
+}
+
+#[test]
+fn test_instructions_are_added_to_a_prompt_once() {
+    let instructions =
+        ProjectInstructions::from_contents("AGENTS.md".into(), "Never edit vendor/.").unwrap();
+    let prompt = add_project_instructions_to_prompt("You are oli.", &instructions);
+    assert!(prompt.starts_with("You are oli.\n\n## PROJECT INSTRUCTIONS"));
+    assert!(prompt.contains("The project's AGENTS.md"));
+    assert!(prompt.ends_with("Never edit vendor/."));
+    assert_eq!(
+        add_project_instructions_to_prompt(&prompt, &instructions),
+        prompt
+    );
+}
+
+#[tokio::test]
+async fn test_agent_adds_instructions_to_the_system_prompt() {
+    let instructions =
+        ProjectInstructions::from_contents("OLI.md".into(), "Format with `cargo fmt`.");
+
+    // Without a history, and with the session's system message as the App sends it
+    for history in [vec![], vec![Message::system("You are oli.".to_string())]] {
+        let client = Arc::new(SystemPromptClient::default());
+        let mut agent = Agent::new(LLMProvider::Anthropic)
+            .with_api_client(ApiClientEnum::custom_for_testing(client.clone()))
+            .with_working_directory("/tmp/project".to_string())
+            .with_project_instructions(instructions.clone());
+        for message in history {
+            agent.add_message(message);
+        }
+        agent.execute("Tidy up main.rs").await.unwrap();
+
+        let system = client.system.lock().unwrap().clone().unwrap();
+        assert!(system.contains("## WORKING DIRECTORY"));
+        assert!(system.contains("## PROJECT INSTRUCTIONS"));
+        assert_eq!(system.matches("Format with `cargo fmt`.").count(), 1);
+    }
+}
+
+#[test]
+fn test_starter_file_is_taken_from_the_answer() {
+    assert_eq!(
+        starter_file_contents("# OLI.md\n\n## Build Commands\n- cargo build\n").as_deref(),
+        Some("# OLI.md\n\n## Build Commands\n- cargo build\n")
+    );
+    assert_eq!(
+        starter_file_contents("```markdown\n# OLI.md\n- cargo test\n```").as_deref(),
+        Some("# OLI.md\n- cargo test\n")
+    );
+    assert_eq!(starter_file_contents("  \n"), None);
+}
@@ -0,0 +1,177 @@
+//! Tests for context providers and the context assembler
+
+use oli_server::agent::executor::AgentExecutor;
+use oli_server::apis::api_client::{
+    ApiClient, ApiClientEnum, CompletionOptions, Message, ToolCall, ToolResult,
+};
+use oli_server::context::provider::{
+    estimate_tokens, ContextAssembler, ContextProvider, ContextRequest,
+};
+use std::sync::{Arc, Mutex};
+
+struct FixedContext {
+    name: &'static str,
+    priority: i32,
+    budget: usize,
+    content: String,
+}
+
+impl FixedContext {
+    fn new(name: &'static str, priority: i32, content: &str) -> Arc<Self> {
+        Arc::new(Self {
+            name,
+            priority,
+            budget: 1_000,
+            content: content.to_string(),
+        })
+    }
+}
+
+impl ContextProvider for FixedContext {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    fn token_budget(&self) -> usize {
+        self.budget
+    }
+
+    fn provide(&self, _request: &ContextRequest) -> Option<String> {
+        (!self.content.is_empty()).then(|| self.content.clone())
+    }
+}
+
+// Echoes the query back, to check what providers receive
+struct QueryEcho;
+
+impl ContextProvider for QueryEcho {
+    fn name(&self) -> &str {
+        "query_echo"
+    }
+
+    fn provide(&self, request: &ContextRequest) -> Option<String> {
+        Some(format!("## QUERY\n{}", request.query))
+    }
+}
+
+fn request(query: &str) -> ContextRequest<'_> {
+    ContextRequest {
+        query,
+        working_directory: None,
+    }
+}
+
+#[test]
+fn test_sections_are_ordered_by_priority() {
+    let assembler = ContextAssembler::new()
+        .with_provider(FixedContext::new("memory", 1, "## MEMORY\nUse tabs"))
+        .with_provider(FixedContext::new("empty", 50, ""))
+        .with_provider(FixedContext::new(
+            "pinned",
+            5,
+            "## PINNED FILES\nsrc/lib.rs",
+        ));
+
+    assert_eq!(
+        assembler.provider_names(),
+        vec!["empty", "pinned", "memory"]
+    );
+    assert_eq!(
+        assembler.assemble(&request("hi")).unwrap(),
+        "## PINNED FILES\nsrc/lib.rs\n\n## MEMORY\nUse tabs"
+    );
+}
+
+#[test]
+fn test_providers_are_replaced_by_name() {
+    let assembler = ContextAssembler::new()
+        .with_provider(FixedContext::new("memory", 1, "old"))
+        .with_provider(FixedContext::new("memory", 1, "new"));
+    assert_eq!(assembler.assemble(&request("hi")).unwrap(), "new");
+
+    let assembler = assembler.without_provider("memory");
+    assert!(assembler.is_empty());
+    assert!(assembler.assemble(&request("hi")).is_none());
+}
+
+#[test]
+fn test_token_budgets_are_enforced() {
+    let long = "x".repeat(4_000);
+
+    // The provider budget caps its own section
+    let limited = Arc::new(FixedContext {
+        name: "limited",
+        priority: 0,
+        budget: 100,
+        content: long.clone(),
+    });
+    let context = ContextAssembler::new()
+        .with_provider(limited)
+        .assemble(&request("hi"))
+        .unwrap();
+    assert!(estimate_tokens(&context) <= 100);
+    assert!(context.ends_with("[... context truncated]"));
+
+    // The total budget is spent on higher priorities first
+    let assembler = ContextAssembler::new()
+        .with_total_budget(300)
+        .with_provider(FixedContext::new("low", 1, "## LOW"))
+        .with_provider(FixedContext::new("high", 9, &long));
+    let context = assembler.assemble(&request("hi")).unwrap();
+    assert!(context.starts_with("xxx"));
+    assert!(!context.contains("## LOW"));
+    assert!(estimate_tokens(&context) <= 300);
+}
+
+struct RecordingApiClient {
+    requests: Mutex<Vec<Vec<Message>>>,
+}
+
+#[async_trait::async_trait]
+impl ApiClient for RecordingApiClient {
+    async fn complete(
+        &self,
+        _messages: Vec<Message>,
+        _options: CompletionOptions,
+    ) -> anyhow::Result<String> {
+        Ok("Done".to_string())
+    }
+
+    async fn complete_with_tools(
+        &self,
+        messages: Vec<Message>,
+        _options: CompletionOptions,
+        _tool_results: Option<Vec<ToolResult>>,
+    ) -> anyhow::Result<(String, Option<Vec<ToolCall>>)> {
+        self.requests.lock().unwrap().push(messages);
+        Ok(("Done".to_string(), None))
+    }
+}
+
+#[tokio::test]
+async fn test_executor_adds_context_to_requests_only() {
+    let client = Arc::new(RecordingApiClient {
+        requests: Mutex::new(Vec::new()),
+    });
+    let assembler = ContextAssembler::new().with_provider(Arc::new(QueryEcho));
+
+    let mut executor = AgentExecutor::new(ApiClientEnum::custom_for_testing(client.clone()))
+        .with_context_assembler(assembler);
+    executor.add_system_message("You are helpful".to_string());
+    executor.add_user_message("Explain main.rs".to_string());
+    executor.execute().await.unwrap();
+
+    let requests = client.requests.lock().unwrap();
+    let system = &requests[0][0];
+    assert_eq!(system.role, "system");
+    assert!(system.content.starts_with("You are helpful"));
+    assert!(system.content.ends_with("## QUERY\nExplain main.rs"));
+
+    // The context is rebuilt per request rather than stored in the conversation
+    let history = executor.get_conversation_history();
+    assert!(history.iter().all(|msg| !msg.content.contains("## QUERY")));
+}
@@ -1,6 +1,8 @@
 pub mod agent;
 pub mod apis;
 pub mod app;
+pub mod client;
 pub mod communication;
+pub mod context;
 pub mod prompts;
 pub mod tools;
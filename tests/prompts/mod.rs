@@ -1 +1,2 @@
 mod test_prompts;
+mod test_templates;
@@ -0,0 +1,71 @@
+//! Tests for system prompt overrides and templates
+
+use oli_server::prompts::templates::{PromptLibrary, PromptSource};
+use oli_server::prompts::DEFAULT_SESSION_PROMPT;
+use std::fs;
+
+#[test]
+fn test_project_override_wins_over_user_override() {
+    let user = tempfile::tempdir().unwrap();
+    let project = tempfile::tempdir().unwrap();
+    let library = PromptLibrary::new(
+        Some(user.path().to_path_buf()),
+        Some(project.path().to_path_buf()),
+    );
+
+    let prompt = library.resolve(None).unwrap();
+    assert_eq!(prompt.source, PromptSource::Default);
+    assert_eq!(prompt.content, DEFAULT_SESSION_PROMPT);
+
+    fs::write(user.path().join("system_prompt.md"), "You are terse.").unwrap();
+    let prompt = library.resolve(None).unwrap();
+    assert_eq!(prompt.content, "You are terse.");
+    assert!(matches!(prompt.source, PromptSource::User { .. }));
+
+    // A blank project file is ignored
+    fs::write(project.path().join("system_prompt.md"), "  \n").unwrap();
+    assert_eq!(library.resolve(None).unwrap().content, "You are terse.");
+
+    fs::write(project.path().join("system_prompt.md"), "You review Rust.").unwrap();
+    let prompt = library.resolve(None).unwrap();
+    assert_eq!(prompt.content, "You review Rust.");
+    assert!(matches!(prompt.source, PromptSource::Project { .. }));
+
+    let sent = prompt.with_working_directory("/work");
+    assert!(sent.starts_with("You review Rust.\n\n## WORKING DIRECTORY"));
+    assert!(sent.contains("/work"));
+}
+
+#[test]
+fn test_templates_are_listed_and_chosen_by_name() {
+    let user = tempfile::tempdir().unwrap();
+    let project = tempfile::tempdir().unwrap();
+    fs::create_dir_all(user.path().join("prompts")).unwrap();
+    fs::create_dir_all(project.path().join("prompts")).unwrap();
+    fs::write(user.path().join("prompts/reviewer.md"), "User reviewer").unwrap();
+    fs::write(user.path().join("prompts/teacher.md"), "Teacher").unwrap();
+    fs::write(
+        project.path().join("prompts/reviewer.md"),
+        "Project reviewer",
+    )
+    .unwrap();
+    fs::write(project.path().join("prompts/notes.txt"), "Not a template").unwrap();
+    let library = PromptLibrary::new(
+        Some(user.path().to_path_buf()),
+        Some(project.path().to_path_buf()),
+    );
+
+    assert_eq!(library.templates(), vec!["reviewer", "teacher"]);
+
+    let prompt = library.resolve(Some("reviewer")).unwrap();
+    assert_eq!(prompt.content, "Project reviewer");
+    assert!(matches!(
+        prompt.source,
+        PromptSource::Template { ref name, .. } if name == "reviewer"
+    ));
+    assert_eq!(library.resolve(Some("teacher")).unwrap().content, "Teacher");
+
+    let error = library.resolve(Some("missing")).unwrap_err().to_string();
+    assert!(error.contains("Available: reviewer, teacher"), "{error}");
+    assert!(library.resolve(Some("../secrets")).is_err());
+}
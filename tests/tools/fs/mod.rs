@@ -1,2 +1,7 @@
 mod test_file_ops;
+mod test_fuzzy;
+mod test_highlight;
+mod test_oliignore;
+mod test_read_limits;
 mod test_search;
+mod test_workspace_index;
@@ -1,4 +1,4 @@
-use oli_server::tools::fs::file_ops::FileOps;
+use oli_server::tools::fs::file_ops::{FileOps, WriteMode};
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
@@ -74,6 +74,49 @@ fn test_write_file() {
     assert_eq!(result, content);
 }
 
+#[test]
+fn test_write_file_modes() {
+    let dir = tempdir().unwrap();
+    let log = dir.path().join("build.log");
+
+    // Appending creates the file, then adds to its end
+    FileOps::write_file_with_options(&log, "first\n", WriteMode::Append, false).unwrap();
+    let diff =
+        FileOps::write_file_with_options(&log, "second\n", WriteMode::Append, false).unwrap();
+    assert_eq!(FileOps::read_file(&log).unwrap(), "first\nsecond\n");
+    assert!(diff.contains("second"));
+    assert!(
+        !diff.contains("-first"),
+        "Appending keeps what was there: {diff}"
+    );
+
+    // create_new refuses to replace an existing file
+    let error = FileOps::write_file_with_options(&log, "third\n", WriteMode::CreateNew, false)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("already exists"), "{error}");
+    assert_eq!(FileOps::read_file(&log).unwrap(), "first\nsecond\n");
+
+    let fresh = dir.path().join("fresh.txt");
+    FileOps::write_file_with_options(&fresh, "new\n", WriteMode::CreateNew, false).unwrap();
+    assert_eq!(FileOps::read_file(&fresh).unwrap(), "new\n");
+}
+
+#[test]
+fn test_write_file_creates_dirs_only_when_asked() {
+    let dir = tempdir().unwrap();
+    let nested = dir.path().join("logs/2024/run.log");
+
+    let error = FileOps::write_file_with_options(&nested, "x", WriteMode::Overwrite, false)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("create_dirs"), "{error}");
+    assert!(!dir.path().join("logs").exists());
+
+    FileOps::write_file_with_options(&nested, "x", WriteMode::Append, true).unwrap();
+    assert_eq!(FileOps::read_file(&nested).unwrap(), "x");
+}
+
 #[test]
 fn test_edit_file() {
     let dir = tempdir().unwrap();
@@ -221,3 +264,100 @@ fn test_edit_file_with_expected_replacements() {
     let err_msg = result.unwrap_err().to_string();
     assert!(err_msg.contains("Found 3 occurrences") && err_msg.contains("expected exactly 2"));
 }
+
+#[test]
+fn test_write_many_writes_every_file() {
+    let dir = tempdir().unwrap();
+    let existing = create_test_file(dir.path(), "lib.rs", "pub mod old;");
+    let files = vec![
+        (
+            dir.path().join("parser/mod.rs"),
+            "pub mod lexer;\n".to_string(),
+        ),
+        (
+            dir.path().join("parser/lexer.rs"),
+            "pub struct Lexer;\n".to_string(),
+        ),
+        (existing.clone(), "pub mod parser;\n".to_string()),
+    ];
+
+    let preview = FileOps::generate_write_many_diff(&files, dir.path()).unwrap();
+    assert!(preview.starts_with("Writing 3 files (2 new, 1 updated)"));
+    assert!(!dir.path().join("parser").exists());
+
+    FileOps::write_many(&files, dir.path()).unwrap();
+    for (path, content) in &files {
+        assert_eq!(std::fs::read_to_string(path).unwrap(), *content);
+    }
+}
+
+#[test]
+fn test_write_many_rolls_back_when_a_file_fails() {
+    let dir = tempdir().unwrap();
+    let existing = create_test_file(dir.path(), "lib.rs", "pub mod old;");
+    let blocker = create_test_file(dir.path(), "blocker", "a file, not a directory");
+    let files = vec![
+        (existing.clone(), "pub mod parser;\n".to_string()),
+        (
+            dir.path().join("parser/mod.rs"),
+            "pub mod lexer;\n".to_string(),
+        ),
+        // Its parent is a file, so it can't be created
+        (
+            blocker.join("test.rs"),
+            "#[test]\nfn it_works() {}\n".to_string(),
+        ),
+    ];
+
+    let error = FileOps::write_many(&files, dir.path()).unwrap_err();
+    assert!(format!("{error:#}").contains("No files were written"));
+    assert_eq!(
+        std::fs::read_to_string(&existing).unwrap(),
+        "pub mod old;\n"
+    );
+    assert!(!dir.path().join("parser").exists());
+}
+
+#[test]
+fn test_write_many_keeps_to_the_workspace() {
+    let workspace = tempdir().unwrap();
+    let root = workspace.path().join("project");
+    std::fs::create_dir(&root).unwrap();
+
+    let escaping = vec![
+        (root.join("src/lib.rs"), String::new()),
+        (root.join("../outside.rs"), String::new()),
+    ];
+    let error = FileOps::write_many(&escaping, &root).unwrap_err();
+    assert!(error.to_string().contains("is outside the workspace"));
+    assert!(!root.join("src").exists());
+
+    let duplicated = vec![
+        (root.join("a.rs"), String::new()),
+        (root.join("./a.rs"), String::new()),
+    ];
+    let error = FileOps::write_many(&duplicated, &root).unwrap_err();
+    assert!(error.to_string().contains("listed more than once"));
+
+    assert!(FileOps::write_many(&[], &root).is_err());
+}
+
+#[test]
+fn test_write_many_refuses_links_to_missing_files() {
+    let workspace = tempdir().unwrap();
+    let root = workspace.path().join("project");
+    std::fs::create_dir(&root).unwrap();
+    let outside = workspace.path().join("outside.rs");
+    std::os::unix::fs::symlink(&outside, root.join("link.rs")).unwrap();
+    std::os::unix::fs::symlink(workspace.path().join("missing"), root.join("linked")).unwrap();
+
+    for path in [root.join("link.rs"), root.join("linked/lib.rs")] {
+        let files = vec![(path, "escaped\n".to_string())];
+        let error = FileOps::write_many(&files, &root).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("symbolic link to a missing file"));
+    }
+    assert!(!outside.exists());
+    assert!(!workspace.path().join("missing").exists());
+}
@@ -0,0 +1,110 @@
+use anyhow::Result;
+use oli_server::agent::tools::{FindFileParams, ToolCall};
+use oli_server::tools::fs::fuzzy::{find_files, fuzzy_score};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn setup_workspace() -> Result<TempDir> {
+    let temp_dir = tempfile::tempdir()?;
+    let files = [
+        "app/src/hooks/useScrollState.ts",
+        "app/src/components/StatusBar.tsx",
+        "src/app/session.rs",
+        "src/app/core.rs",
+        "tests/app/test_scroll.rs",
+        "docs/state-machines.md",
+        "target/debug/scroll_state.d",
+    ];
+    for file in files {
+        let path = temp_dir.path().join(file);
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, "")?;
+    }
+    fs::write(temp_dir.path().join(".gitignore"), "target/\n")?;
+    // The walker only applies .gitignore inside a repository
+    fs::create_dir(temp_dir.path().join(".git"))?;
+    Ok(temp_dir)
+}
+
+fn paths(dir: &Path, query: &str) -> Vec<PathBuf> {
+    find_files(query, dir, 10)
+        .unwrap()
+        .into_iter()
+        .map(|found| found.path)
+        .collect()
+}
+
+#[test]
+fn test_fuzzy_score_requires_every_term_in_order() {
+    assert!(fuzzy_score("scrst", "hooks/useScrollState.ts").is_some());
+    assert!(fuzzy_score("scroll state", "hooks/useScrollState.ts").is_some());
+    assert!(fuzzy_score("tsrc", "src/app/core.rs").is_none());
+    assert!(fuzzy_score("scroll missing", "hooks/useScrollState.ts").is_none());
+}
+
+#[test]
+fn test_fuzzy_score_prefers_boundaries_and_runs() {
+    let run = fuzzy_score("core", "src/app/core.rs").unwrap();
+    let scattered = fuzzy_score("core", "src/components/ChatInterface.tsx").unwrap_or(0);
+    assert!(run > scattered);
+
+    // Case doesn't matter, but a camelCase boundary scores like a separator
+    let camel = fuzzy_score("state", "useScrollState.ts").unwrap();
+    let inner = fuzzy_score("state", "restatement.ts").unwrap();
+    assert!(camel > inner);
+}
+
+#[test]
+fn test_find_files_ranks_best_match_first() -> Result<()> {
+    let workspace = setup_workspace()?;
+
+    let results = paths(workspace.path(), "scroll state");
+    assert_eq!(
+        results.first(),
+        Some(&PathBuf::from("app/src/hooks/useScrollState.ts"))
+    );
+
+    let results = paths(workspace.path(), "session");
+    assert_eq!(results.first(), Some(&PathBuf::from("src/app/session.rs")));
+    Ok(())
+}
+
+#[test]
+fn test_find_files_respects_ignore_files() -> Result<()> {
+    let workspace = setup_workspace()?;
+
+    let results = paths(workspace.path(), "scroll_state");
+    assert!(results.iter().all(|path| !path.starts_with("target")));
+    Ok(())
+}
+
+#[test]
+fn test_find_files_limit_and_errors() -> Result<()> {
+    let workspace = setup_workspace()?;
+
+    assert_eq!(find_files("s", workspace.path(), 2)?.len(), 2);
+    assert!(find_files("  ", workspace.path(), 10).is_err());
+    assert!(find_files("core", &workspace.path().join("missing"), 10).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_find_file_tool_returns_absolute_paths() -> Result<()> {
+    let workspace = setup_workspace()?;
+
+    let output = ToolCall::FindFile(FindFileParams {
+        query: "statusbar".to_string(),
+        path: Some(workspace.path().to_string_lossy().to_string()),
+        limit: Some(3),
+    })
+    .execute()?;
+
+    let expected = workspace
+        .path()
+        .join("app/src/components/StatusBar.tsx")
+        .display()
+        .to_string();
+    assert!(output.contains(&format!("1. {expected}")));
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use oli_server::tools::fs::diff::DiffTools;
+use oli_server::tools::fs::highlight::{Language, SyntaxHighlighter};
+
+#[test]
+fn test_language_detection() {
+    assert_eq!(Language::from_path("src/main.rs"), Some(Language::Rust));
+    assert_eq!(
+        Language::from_path("app/cli.TS"),
+        Some(Language::TypeScript)
+    );
+    assert_eq!(Language::from_path("script.py"), Some(Language::Python));
+    assert_eq!(Language::from_path("Cargo.toml"), Some(Language::Toml));
+    assert_eq!(Language::from_path("README"), None);
+    assert_eq!(Language::from_path("notes.txt"), None);
+}
+
+#[test]
+fn test_highlight_rust_line() {
+    let line = r#"let name = "oli"; // greeting"#;
+    let highlighted = SyntaxHighlighter::highlight_line(line, Language::Rust, "\x1b[92m");
+
+    // Keyword, string and comment are wrapped in their own colors
+    assert!(highlighted.contains("\x1b[94mlet\x1b[0m\x1b[92m"));
+    assert!(highlighted.contains("\x1b[93m\"oli\"\x1b[0m"));
+    assert!(highlighted.contains("\x1b[90m// greeting\x1b[0m"));
+
+    // Identifiers that merely contain keywords are left alone
+    let plain = SyntaxHighlighter::highlight_line("letter", Language::Rust, "");
+    assert_eq!(plain, "letter");
+
+    // Lifetimes aren't mistaken for strings
+    let lifetime = SyntaxHighlighter::highlight_line("&'a str", Language::Rust, "");
+    assert_eq!(lifetime, "&'a str");
+}
+
+#[test]
+fn test_highlight_python_comment_and_number() {
+    let highlighted =
+        SyntaxHighlighter::highlight_line("x = 42  # answer", Language::Python, "\x1b[91m");
+    assert!(highlighted.contains("\x1b[96m42\x1b[0m\x1b[91m"));
+    assert!(highlighted.contains("\x1b[90m# answer"));
+}
+
+#[test]
+fn test_format_diff_highlights_known_languages() {
+    let diff = DiffTools::generate_diff("fn old() {}\n", "fn new() {}\n");
+
+    let rust = DiffTools::format_diff(&diff, "lib.rs").unwrap();
+    assert!(rust.contains("\x1b[94mfn\x1b[0m"));
+
+    // Unknown extensions keep the plain red/green output
+    let text = DiffTools::format_diff(&diff, "notes.txt").unwrap();
+    assert!(!text.contains("\x1b[94m"));
+    assert!(text.contains("\x1b[92m"));
+}
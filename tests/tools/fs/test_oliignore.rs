@@ -0,0 +1,94 @@
+use anyhow::Result;
+use oli_server::tools::fs::file_ops::FileOps;
+use oli_server::tools::fs::oliignore::{check_path, OliIgnore};
+use oli_server::tools::fs::search::SearchTools;
+use std::fs;
+use tempfile::TempDir;
+
+// A project with secrets and generated fixtures hidden from the agent
+fn setup_project() -> Result<TempDir> {
+    let temp_dir = tempfile::tempdir()?;
+    let root = temp_dir.path();
+
+    fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n")?;
+    fs::write(
+        root.join(".oliignore"),
+        "secrets/\n*.pem\nfixtures/*.json\n!fixtures/keep.json\n",
+    )?;
+    fs::create_dir_all(root.join("src"))?;
+    fs::create_dir_all(root.join("secrets"))?;
+    fs::create_dir_all(root.join("fixtures"))?;
+
+    fs::write(
+        root.join("src/main.rs"),
+        "fn main() { let token = load(); }\n",
+    )?;
+    fs::write(root.join("secrets/prod.env"), "token = abc123\n")?;
+    fs::write(root.join("server.pem"), "token\n")?;
+    fs::write(root.join("fixtures/big.json"), "{\"token\": 1}\n")?;
+    fs::write(root.join("fixtures/keep.json"), "{\"token\": 2}\n")?;
+
+    Ok(temp_dir)
+}
+
+#[test]
+fn test_gitignore_syntax() -> Result<()> {
+    let project = setup_project()?;
+    let root = project.path();
+    let oliignore = OliIgnore::find(&root.join("src")).expect(".oliignore in an ancestor");
+
+    assert!(oliignore.is_excluded(&root.join("secrets")));
+    assert!(oliignore.is_excluded(&root.join("secrets/prod.env")));
+    assert!(oliignore.is_excluded(&root.join("server.pem")));
+    assert!(oliignore.is_excluded(&root.join("fixtures/big.json")));
+    assert!(!oliignore.is_excluded(&root.join("fixtures/keep.json")));
+    assert!(!oliignore.is_excluded(&root.join("src/main.rs")));
+    // Files that don't exist yet are matched too
+    assert!(oliignore.is_excluded(&root.join("secrets/new.env")));
+
+    assert!(OliIgnore::find(&tempfile::tempdir()?.path().join("x.rs")).is_none());
+    Ok(())
+}
+
+#[test]
+fn test_file_tools_report_exclusion() -> Result<()> {
+    let project = setup_project()?;
+    let root = project.path();
+
+    let error = FileOps::read_file_lines(&root.join("secrets/prod.env"), 0, None).unwrap_err();
+    assert!(error.to_string().ends_with("path excluded by .oliignore"));
+    assert!(FileOps::write_file_with_diff(&root.join("secrets/new.env"), "x").is_err());
+    assert!(!root.join("secrets/new.env").exists());
+    assert!(FileOps::edit_file(&root.join("server.pem"), "token", "t", None).is_err());
+    assert!(check_path(&root.join("src/main.rs")).is_ok());
+
+    let (entries, excluded) = FileOps::list_directory_with_excluded(root)?;
+    let names: Vec<String> = entries
+        .iter()
+        .map(|e| e.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(excluded, 2);
+    assert!(!names.contains(&"secrets".to_string()));
+    assert!(!names.contains(&"server.pem".to_string()));
+    assert!(names.contains(&"src".to_string()));
+    Ok(())
+}
+
+#[test]
+fn test_search_tools_skip_excluded_paths() -> Result<()> {
+    let project = setup_project()?;
+    let root = project.path();
+
+    let matches = SearchTools::grep_search("token", None, Some(root))?;
+    let mut files: Vec<String> = matches
+        .iter()
+        .map(|(path, _, _)| path.strip_prefix(root).unwrap().display().to_string())
+        .collect();
+    files.sort();
+    assert_eq!(files, vec!["fixtures/keep.json", "src/main.rs"]);
+
+    let json_files = SearchTools::glob_search_in_dir(root, "fixtures/*.json")?;
+    assert_eq!(json_files, vec![root.join("fixtures/keep.json")]);
+    assert!(SearchTools::glob_search_in_dir(root, "*.pem")?.is_empty());
+    Ok(())
+}
@@ -0,0 +1,87 @@
+//! Tests for the Read tool's size limits and its handling of binary files
+
+use oli_server::apis::api_client::split_images;
+use oli_server::apis::streaming::estimate_tokens;
+use oli_server::tools::fs::read_limits::ReadLimits;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn test_reads_numbered_lines_within_the_limits() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("notes.txt");
+    fs::write(&path, "one\r\ntwo\nthree\n").unwrap();
+
+    let output = ReadLimits::default().read(&path, 1, 10).unwrap();
+    assert_eq!(output, "   2 | two\n   3 | three");
+}
+
+#[test]
+fn test_long_files_are_cut_with_a_notice() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("build.log");
+    let lines: Vec<String> = (1..=100).map(|i| format!("line {i}")).collect();
+    fs::write(&path, lines.join("\n")).unwrap();
+
+    // Capped by lines, with where to continue
+    let limits = ReadLimits {
+        max_bytes: 100_000,
+        max_lines: 10,
+    };
+    let output = limits.read(&path, 5, 50).unwrap();
+    assert!(output.starts_with("   6 | line 6\n"));
+    assert!(output.contains("  15 | line 15\n\n[Showing lines 6-15"));
+    assert!(output.contains("offset 15"), "{output}");
+
+    // The caller's own limit needs no notice
+    let output = limits.read(&path, 0, 10).unwrap();
+    assert!(!output.contains("[Showing"));
+
+    // Capped by bytes
+    let limits = ReadLimits {
+        max_bytes: 100,
+        max_lines: 2_000,
+    };
+    let output = limits.read(&path, 0, 2_000).unwrap();
+    let (shown, notice) = output.split_once("\n\n").unwrap();
+    assert!(shown.len() <= 100);
+    assert!(notice.contains("at most 100 bytes"), "{notice}");
+
+    // One enormous line, e.g. a minified bundle, is shortened
+    let minified = dir.path().join("bundle.min.js");
+    fs::write(&minified, "x".repeat(10_000)).unwrap();
+    let output = ReadLimits::default().read(&minified, 0, 10).unwrap();
+    assert!(output.len() < 3_000);
+    assert!(output.ends_with("[8000 more characters]"));
+}
+
+#[test]
+fn test_binary_files_are_refused() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("app.bin");
+    fs::write(&path, [0x7F, b'E', b'L', b'F', 0, 0, 1, 2]).unwrap();
+
+    let error = ReadLimits::default()
+        .read(&path, 0, 10)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("binary file (8 bytes)"), "{error}");
+}
+
+#[test]
+fn test_images_are_attached() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("screenshot.png");
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    fs::write(&path, png).unwrap();
+
+    let output = ReadLimits::default().read(&path, 0, 10).unwrap();
+    let (text, images) = split_images(&output);
+    assert!(text.contains("(image/png, 16 bytes) is attached"), "{text}");
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].media_type, "image/png");
+    assert_eq!(images[0].data, "iVBORw0KGgoAAAANSUhEUg==");
+
+    // An image counts as a fixed amount of context, not the size of its data
+    assert!(estimate_tokens(&output) < 2_000);
+}
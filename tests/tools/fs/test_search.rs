@@ -1,5 +1,5 @@
 use anyhow::Result;
-use oli_server::tools::fs::search::SearchTools;
+use oli_server::tools::fs::search::{GrepOutputOptions, SearchTools};
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::Path;
@@ -405,6 +405,45 @@ fn test_grep_search_match_ordering() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_format_grep_matches_with_context() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let file = temp_dir.path().join("lib.rs");
+    write_file(&file, "a\nTODO one\nb\nc\nd\ne\nf\nTODO two\ng\n")?;
+    let results = SearchTools::grep_search("TODO", None, Some(temp_dir.path()))?;
+    let name = file.display();
+
+    // Line numbers by default, and nothing but the matches without context
+    let plain = SearchTools::format_grep_matches(&results, &GrepOutputOptions::default());
+    assert_eq!(plain, format!("{name}:2:TODO one\n{name}:8:TODO two\n"));
+
+    // Context marked with '-', and separate groups split by "--"
+    let options = GrepOutputOptions {
+        context_lines: 1,
+        show_line_numbers: true,
+    };
+    let with_context = SearchTools::format_grep_matches(&results, &options);
+    assert_eq!(
+        with_context,
+        format!(
+            "{name}-1-a\n{name}:2:TODO one\n{name}-3-b\n--\n\
+             {name}-7-f\n{name}:8:TODO two\n{name}-9-g\n"
+        )
+    );
+
+    // Overlapping context is merged into one group
+    let options = GrepOutputOptions {
+        context_lines: 3,
+        show_line_numbers: false,
+    };
+    let merged = SearchTools::format_grep_matches(&results, &options);
+    assert!(!merged.contains("--"), "{merged}");
+    assert!(merged.starts_with(&format!("{name}-a\n{name}:TODO one\n")));
+    assert_eq!(merged.lines().count(), 9);
+
+    Ok(())
+}
+
 #[test]
 fn test_glob_to_regex_conversion() -> Result<()> {
     let temp_dir = setup_test_directory()?;
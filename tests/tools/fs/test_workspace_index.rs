@@ -0,0 +1,106 @@
+use anyhow::Result;
+use oli_server::tools::fs::search::SearchTools;
+use oli_server::tools::fs::workspace_index::{
+    truncation_note, IndexStatus, LargeRepoSetting, WorkspaceIndex, DEFAULT_LARGE_REPO_FILES,
+};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tempfile::TempDir;
+
+fn setup_workspace(files: usize) -> Result<TempDir> {
+    let temp_dir = tempfile::tempdir()?;
+    for i in 0..files {
+        let path = temp_dir
+            .path()
+            .join(format!("src/mod_{}/file_{i}.rs", i % 3));
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, format!("fn item_{i}() {{}}\n// TODO item {i}\n"))?;
+    }
+    fs::create_dir_all(temp_dir.path().join("target"))?;
+    fs::write(temp_dir.path().join("target/ignored.rs"), "")?;
+    fs::write(temp_dir.path().join(".gitignore"), "target/\n")?;
+    // The walker only applies .gitignore inside a repository
+    fs::create_dir(temp_dir.path().join(".git"))?;
+    Ok(temp_dir)
+}
+
+#[test]
+fn test_large_repo_setting_parse() {
+    assert_eq!(LargeRepoSetting::parse("on"), LargeRepoSetting::On);
+    assert_eq!(LargeRepoSetting::parse("OFF"), LargeRepoSetting::Off);
+    assert_eq!(
+        LargeRepoSetting::parse("5000"),
+        LargeRepoSetting::Auto(5000)
+    );
+    assert_eq!(
+        LargeRepoSetting::parse("lots"),
+        LargeRepoSetting::Auto(DEFAULT_LARGE_REPO_FILES)
+    );
+}
+
+#[test]
+fn test_build_detects_large_repos_and_reports_progress() -> Result<()> {
+    let dir = setup_workspace(12)?;
+    let progress: Mutex<Vec<IndexStatus>> = Mutex::new(Vec::new());
+
+    let index = WorkspaceIndex::new(dir.path(), LargeRepoSetting::Auto(10));
+    index.build(&|status| progress.lock().unwrap().push(status.clone()));
+
+    let status = index.status();
+    // Hidden files and ignored target/ are skipped, as in FindFile's index
+    assert_eq!(status.files_indexed, 12);
+    assert!(status.large_repo);
+    assert!(status.complete);
+    assert_eq!(progress.lock().unwrap().last(), Some(&status));
+
+    let small = WorkspaceIndex::new(dir.path(), LargeRepoSetting::Auto(100));
+    small.build(&|_| {});
+    assert!(!small.status().large_repo);
+    Ok(())
+}
+
+#[test]
+fn test_index_lists_and_globs_files() -> Result<()> {
+    let dir = setup_workspace(6)?;
+    let index = WorkspaceIndex::new(dir.path(), LargeRepoSetting::On);
+    index.build(&|_| {});
+
+    let mut under: Vec<PathBuf> = index.files_under(&dir.path().join("src/mod_1"));
+    under.sort();
+    assert_eq!(
+        under,
+        vec![PathBuf::from("file_1.rs"), PathBuf::from("file_4.rs")]
+    );
+
+    // `*` doesn't cross directories, `**` does
+    assert!(index.glob("*.rs", dir.path())?.is_empty());
+    let all = index.glob("**/*.rs", dir.path())?;
+    assert_eq!(all.len(), 6);
+    assert!(all.iter().all(|path| path.starts_with(dir.path())));
+    assert_eq!(index.glob("src/mod_0/*.rs", dir.path())?.len(), 2);
+    assert!(index.progress_note().is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_parallel_grep_stops_at_the_limit() -> Result<()> {
+    let dir = setup_workspace(9)?;
+
+    let (all, stopped) = SearchTools::grep_search_parallel("TODO", None, Some(dir.path()), 100)?;
+    assert_eq!(all.len(), 9);
+    assert!(!stopped);
+
+    let (some, stopped) =
+        SearchTools::grep_search_parallel("TODO", Some("*.rs"), Some(dir.path()), 4)?;
+    assert_eq!(some.len(), 4);
+    assert!(stopped);
+    Ok(())
+}
+
+#[test]
+fn test_truncation_note() {
+    assert!(truncation_note(500, Some(20)).is_empty());
+    assert!(truncation_note(500, Some(900)).contains("showing 500 of 900"));
+    assert!(truncation_note(500, None).contains("stopped after the first 500"));
+}
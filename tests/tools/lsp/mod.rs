@@ -1 +1,2 @@
 pub mod test_manager;
+pub mod test_rust_analyzer;
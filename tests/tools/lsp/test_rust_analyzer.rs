@@ -0,0 +1,159 @@
+//! Tests for rust-analyzer support, run against a stand-in server that behaves like
+//! it: busy indexing at first, asking the client for things, and answering with
+//! nested symbols
+
+use oli_server::tools::lsp::{
+    Location, LspServerManager, LspServerType, Position, RUST_ANALYZER_ENV,
+};
+use serde_json::json;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use tempfile::TempDir;
+
+const FAKE_RUST_ANALYZER: &str = r#"#!/usr/bin/env python3
+import json, sys
+
+if "--version" in sys.argv:
+    print("rust-analyzer 0.0.0 (fake)")
+    sys.exit(0)
+
+ready = False
+
+def read():
+    length = None
+    while True:
+        line = sys.stdin.buffer.readline()
+        if not line:
+            sys.exit(0)
+        line = line.strip()
+        if not line and length is not None:
+            break
+        if line.startswith(b"Content-Length:"):
+            length = int(line.split(b":")[1])
+    return json.loads(sys.stdin.buffer.read(length))
+
+def send(message):
+    body = json.dumps(dict(jsonrpc="2.0", **message)).encode()
+    sys.stdout.buffer.write(b"Content-Length: %d\r\n\r\n" % len(body) + body)
+    sys.stdout.buffer.flush()
+
+def status(quiescent):
+    send({"method": "experimental/serverStatus", "params": {"health": "ok", "quiescent": quiescent}})
+
+def span(line, start, end):
+    return {"start": {"line": line, "character": start}, "end": {"line": line, "character": end}}
+
+while True:
+    message = read()
+    method = message.get("method")
+    if method == "initialize":
+        send({"id": message["id"], "result": {"capabilities": {}}})
+    elif method == "initialized":
+        status(False)
+        send({"id": "progress-1", "method": "window/workDoneProgress/create", "params": {"token": "rustAnalyzer/Indexing"}})
+        send({"id": "config-1", "method": "workspace/configuration", "params": {"items": [{"section": "rust-analyzer"}]}})
+    elif "id" in message and method is None:
+        # The client answered; indexing finishes once it has answered both requests
+        if message["id"] == "config-1" and message["result"] == [None]:
+            ready = True
+            status(True)
+    elif method == "textDocument/documentSymbol":
+        # Queries before indexing finishes would get nothing
+        symbols = [] if not ready else [{
+            "name": "Parser", "kind": 23, "range": span(0, 0, 30), "selectionRange": span(0, 11, 17),
+            "children": [{"name": "input", "detail": "String", "kind": 8, "range": span(1, 4, 20), "selectionRange": span(1, 4, 9)}],
+        }]
+        send({"method": "$/progress", "params": {"token": "rustAnalyzer/Roots Scanned", "value": {"kind": "report"}}})
+        send({"id": message["id"], "result": symbols})
+    elif method == "textDocument/references":
+        uri = message["params"]["textDocument"]["uri"]
+        send({"id": message["id"], "result": [{"uri": uri, "range": span(0, 11, 17)}, {"uri": uri, "range": span(4, 8, 14)}]})
+    elif method == "textDocument/definition":
+        uri = message["params"]["textDocument"]["uri"]
+        send({"id": message["id"], "result": {"uri": uri, "range": span(0, 11, 17)}})
+    elif method == "shutdown":
+        send({"id": message["id"], "result": None})
+    elif method == "exit":
+        sys.exit(0)
+"#;
+
+fn rust_project(dir: &Path) -> String {
+    fs::write(dir.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+    fs::create_dir_all(dir.join("src")).unwrap();
+    let file = dir.join("src/lib.rs");
+    fs::write(
+        &file,
+        "pub struct Parser {\n    input: String,\n}\n\nfn parse(parser: Parser) {}\n",
+    )
+    .unwrap();
+    file.display().to_string()
+}
+
+#[test]
+fn test_rust_analyzer_symbols_and_references() {
+    if std::process::Command::new("python3")
+        .arg("--version")
+        .output()
+        .is_err()
+    {
+        println!("Skipping test_rust_analyzer_symbols_and_references: python3 not installed");
+        return;
+    }
+
+    let dir = TempDir::new().unwrap();
+    let server = dir.path().join("rust-analyzer");
+    fs::write(&server, FAKE_RUST_ANALYZER).unwrap();
+    fs::set_permissions(&server, fs::Permissions::from_mode(0o755)).unwrap();
+    std::env::set_var(RUST_ANALYZER_ENV, &server);
+    let file = rust_project(dir.path());
+
+    let manager = LspServerManager::new();
+    let symbols = manager
+        .document_symbol(&file, &LspServerType::Rust)
+        .unwrap();
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].name, "Parser");
+    assert_eq!(symbols[0].kind_to_string(), "Struct");
+    let children = symbols[0].children.as_ref().unwrap();
+    assert_eq!(children[0].name, "input");
+    assert_eq!(children[0].kind_to_string(), "Field");
+
+    let position = Position {
+        line: 0,
+        character: 11,
+    };
+    let references = manager
+        .references(&file, &position, &LspServerType::Rust)
+        .unwrap();
+    assert_eq!(references.len(), 2);
+    assert!(references[0].uri.ends_with("src/lib.rs"));
+    assert_eq!(references[1].range.start.line, 4);
+
+    // A single Location is a list of one
+    let definition = manager
+        .definition(&file, &position, &LspServerType::Rust)
+        .unwrap();
+    assert_eq!(definition.len(), 1);
+    assert_eq!(definition[0].range.start.character, 11);
+
+    std::env::remove_var(RUST_ANALYZER_ENV);
+}
+
+#[test]
+fn test_parse_locations() {
+    assert!(Location::parse_all(json!(null)).unwrap().is_empty());
+
+    let range = |line: u32| json!({ "start": { "line": line, "character": 0 }, "end": { "line": line, "character": 4 } });
+    let links = json!([{
+        "targetUri": "file:///src/lib.rs",
+        "targetRange": range(1),
+        "targetSelectionRange": range(2)
+    }]);
+    let locations = Location::parse_all(links).unwrap();
+    assert_eq!(locations[0].uri, "file:///src/lib.rs");
+    // The symbol's name rather than its whole definition
+    assert_eq!(locations[0].range.start.line, 2);
+
+    assert!(Location::parse_all(json!([{ "uri": 3 }])).is_err());
+}
@@ -1,2 +1,14 @@
 pub mod fs;
 pub mod lsp;
+pub mod test_dependencies;
+pub mod test_editor;
+pub mod test_git;
+pub mod test_git_stage;
+pub mod test_notebook;
+#[cfg(unix)]
+pub mod test_processes;
+pub mod test_remote;
+pub mod test_rename;
+pub mod test_shell;
+pub mod test_shell_session;
+pub mod test_test_runner;
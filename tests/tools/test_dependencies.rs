@@ -0,0 +1,184 @@
+use anyhow::Result;
+use oli_server::agent::tools::{DependencyGraphParams, ToolCall};
+use oli_server::tools::dependencies::{
+    parse_cargo_manifest, parse_go_imports, parse_js_imports, parse_package_json, parse_pyproject,
+    parse_python_imports, parse_requirements, parse_rust_imports, DependencyGraph,
+};
+use std::collections::BTreeSet;
+use std::fs;
+use tempfile::TempDir;
+
+fn set(items: &[&str]) -> BTreeSet<String> {
+    items.iter().map(|item| item.to_string()).collect()
+}
+
+fn setup_workspace() -> Result<TempDir> {
+    let temp_dir = tempfile::tempdir()?;
+    let files = [
+        (
+            "Cargo.toml",
+            "[package]\nname = \"demo\"\n\n[dependencies]\nserde_json = \"1\"\ntokio = { version = \"1\" }\n",
+        ),
+        (
+            "src/main.rs",
+            "use crate::apis::api_client::Message;\nuse serde_json::json;\n",
+        ),
+        ("src/apis/api_client.rs", "use std::fmt;\n"),
+        ("src/lib.rs", "pub mod apis;\n"),
+        (
+            "app/src/App.tsx",
+            "import React from \"react\";\nimport { format } from \"./utils/format.js\";\n",
+        ),
+        ("app/src/utils/format.ts", "export const format = 1;\n"),
+        ("target/debug/build.rs", "use tokio::runtime;\n"),
+    ];
+    for (file, content) in files {
+        let path = temp_dir.path().join(file);
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, content)?;
+    }
+    fs::write(temp_dir.path().join(".gitignore"), "target/\n")?;
+    // The walker only applies .gitignore inside a repository
+    fs::create_dir(temp_dir.path().join(".git"))?;
+    Ok(temp_dir)
+}
+
+#[test]
+fn test_parse_rust_imports() {
+    let content = "use std::collections::{HashMap, HashSet};\n\
+                   pub(crate) use crate::tools::rename::*;\n\
+                   extern crate serde;\n\
+                   // use commented::out;\n";
+    assert_eq!(
+        parse_rust_imports(content),
+        set(&["crate::tools::rename", "serde", "std::collections"])
+    );
+}
+
+#[test]
+fn test_parse_python_imports() {
+    let content =
+        "import os, sys\nimport numpy as np\nfrom .models import User\nfrom pkg.sub import thing\n";
+    assert_eq!(
+        parse_python_imports(content),
+        set(&[".models", "numpy", "os", "pkg.sub", "sys"])
+    );
+}
+
+#[test]
+fn test_parse_js_imports() {
+    let content = "import React from \"react\";\n\
+                   import {\n  a,\n} from './local';\n\
+                   import './styles.css';\n\
+                   const fs = require('fs');\n\
+                   export * from \"@scope/pkg\";\n\
+                   const lazy = await import(\"./lazy.js\");\n";
+    assert_eq!(
+        parse_js_imports(content),
+        set(&[
+            "./lazy.js",
+            "./local",
+            "./styles.css",
+            "@scope/pkg",
+            "fs",
+            "react"
+        ])
+    );
+}
+
+#[test]
+fn test_parse_go_imports() {
+    let content = "package main\n\nimport \"fmt\"\n\nimport (\n\t\"os\"\n\tlog \"github.com/sirupsen/logrus\"\n)\n";
+    assert_eq!(
+        parse_go_imports(content),
+        set(&["fmt", "github.com/sirupsen/logrus", "os"])
+    );
+}
+
+#[test]
+fn test_parse_manifests() {
+    let cargo = "[package]\nname = \"x\"\nversion = \"0.1.0\"\n\n[dependencies]\nanyhow = \"1\"\n\
+                 \n[dev-dependencies]\ntempfile = \"3\"\n\n[dependencies.reqwest]\nversion = \"0.12\"\n\
+                 \n[target.'cfg(unix)'.dependencies]\nlibc = \"0.2\"\n";
+    assert_eq!(
+        parse_cargo_manifest(cargo),
+        set(&["anyhow", "libc", "reqwest", "tempfile"])
+    );
+
+    let package =
+        r#"{"name": "x", "dependencies": {"ink": "^4"}, "devDependencies": {"typescript": "^5"}}"#;
+    assert_eq!(parse_package_json(package), set(&["ink", "typescript"]));
+
+    let requirements =
+        "# pinned\nrequests==2.31\nuvicorn[standard]>=0.20 ; python_version > '3.8'\n-r dev.txt\n";
+    assert_eq!(
+        parse_requirements(requirements),
+        set(&["requests", "uvicorn"])
+    );
+
+    let pyproject =
+        "[project]\nname = \"x\"\ndependencies = [\n  \"httpx>=0.27\",\n  \"pydantic\",\n]\n\
+                     \n[project.optional-dependencies]\ndev = [\"pytest\"]\n\
+                     \n[tool.poetry.dependencies]\npython = \"^3.11\"\nrich = \"^13\"\n";
+    assert_eq!(
+        parse_pyproject(pyproject),
+        set(&["httpx", "pydantic", "pytest", "rich"])
+    );
+}
+
+#[test]
+fn test_graph_skips_ignored_files() -> Result<()> {
+    let workspace = setup_workspace()?;
+    let graph = DependencyGraph::build(workspace.path())?;
+
+    assert!(graph.adjacency.contains_key("Cargo.toml"));
+    assert!(graph.adjacency.contains_key("app/src/App.tsx"));
+    assert!(!graph.adjacency.contains_key("target/debug/build.rs"));
+    // lib.rs is scanned but imports nothing
+    assert!(!graph.adjacency.contains_key("src/lib.rs"));
+    assert_eq!(graph.files_scanned, 6);
+    Ok(())
+}
+
+#[test]
+fn test_dependents_of_matches_segments_and_files() -> Result<()> {
+    let workspace = setup_workspace()?;
+    let graph = DependencyGraph::build(workspace.path())?;
+
+    let dependents = graph.dependents_of("serde-json");
+    assert_eq!(
+        dependents.keys().collect::<Vec<_>>(),
+        vec!["Cargo.toml", "src/main.rs"]
+    );
+
+    let dependents = graph.dependents_of("src/apis/api_client.rs");
+    assert_eq!(
+        dependents.get("src/main.rs"),
+        Some(&vec!["crate::apis::api_client::Message".to_string()])
+    );
+
+    let dependents = graph.dependents_of("app/src/utils/format.ts");
+    assert_eq!(
+        dependents.keys().collect::<Vec<_>>(),
+        vec!["app/src/App.tsx"]
+    );
+
+    // "api" is a prefix of a segment, not a segment
+    assert!(graph.dependents_of("api").is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_dependency_graph_tool_returns_adjacency_json() -> Result<()> {
+    let workspace = setup_workspace()?;
+
+    let output = ToolCall::DependencyGraph(DependencyGraphParams {
+        target: Some("tokio".to_string()),
+        path: Some(workspace.path().to_string_lossy().to_string()),
+    })
+    .execute()?;
+
+    assert!(output.starts_with("1 of 6 files scanned depend on 'tokio'"));
+    assert!(output.contains("\"Cargo.toml\": [\n    \"tokio\"\n  ]"));
+    Ok(())
+}
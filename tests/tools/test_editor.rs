@@ -0,0 +1,84 @@
+//! Tests for edits applied through an attached editor
+
+use oli_server::tools::editor::{
+    edit_file, path_to_uri, position_at, replace_edit, write_edit, write_file_with_diff,
+};
+use oli_server::tools::fs::file_ops::WriteMode;
+use oli_server::tools::rename::{apply_text_edits, workspace_edit_files};
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Apply a `WorkspaceEdit` for one file the way an editor would
+fn apply(edit: &serde_json::Value, content: &str) -> String {
+    let files = workspace_edit_files(edit);
+    assert_eq!(files.len(), 1);
+    let edits = files.values().next().unwrap();
+    apply_text_edits(content, edits)
+}
+
+#[test]
+fn test_position_counts_utf16_units() {
+    let content = "fn main() {\n    let s = \"😀\"; x\n}\n";
+    let offset = content.find('x').unwrap();
+    assert_eq!(
+        position_at(content, offset),
+        json!({ "line": 1, "character": 18 })
+    );
+    assert_eq!(
+        position_at(content, 0),
+        json!({ "line": 0, "character": 0 })
+    );
+}
+
+#[test]
+fn test_replace_edit_matches_a_direct_replace() {
+    let path = Path::new("/work/src/lib.rs");
+    let content = "let a = 1;\nlet é = old;\nold(a);\n";
+    let edit = replace_edit(path, content, "old", "new_value");
+
+    let uri = "file:///work/src/lib.rs";
+    assert_eq!(edit["changes"][uri].as_array().unwrap().len(), 2);
+    assert_eq!(apply(&edit, content), content.replace("old", "new_value"));
+}
+
+#[test]
+fn test_write_edit_replaces_or_creates_the_document() {
+    let path = Path::new("/work/notes.md");
+    let old = "first\nsecond\n";
+    let edit = write_edit(path, Some(old), "rewritten\n");
+    assert_eq!(apply(&edit, old), "rewritten\n");
+
+    let created = write_edit(path, None, "hello\n");
+    let changes = created["documentChanges"].as_array().unwrap();
+    assert_eq!(changes[0]["kind"], "create");
+    assert_eq!(changes[0]["uri"], "file:///work/notes.md");
+    assert_eq!(apply(&created, ""), "hello\n");
+}
+
+#[test]
+fn test_path_to_uri_is_absolute_and_escaped() {
+    assert_eq!(
+        path_to_uri(Path::new("/my project/a.rs")),
+        "file:///my%20project/a.rs"
+    );
+    let relative = path_to_uri(Path::new("a.rs"));
+    assert!(relative.starts_with("file:///"));
+    assert!(relative.ends_with("/a.rs"));
+}
+
+#[test]
+fn test_writes_to_disk_without_an_editor() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("main.rs");
+    fs::write(&path, "fn old() {}\n").unwrap();
+
+    let diff = edit_file(&path, "old", "new", None).unwrap();
+    assert!(diff.contains("new"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "fn new() {}\n");
+
+    let created = dir.path().join("created.txt");
+    write_file_with_diff(&created, "hello\n", WriteMode::Overwrite, false).unwrap();
+    assert_eq!(fs::read_to_string(&created).unwrap(), "hello\n");
+}
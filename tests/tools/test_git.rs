@@ -0,0 +1,204 @@
+//! Tests for the read-only GitStatus, GitDiff and GitLog tools
+
+use oli_server::agent::approval::ApprovalBackend;
+use oli_server::agent::tools::{GitDiffParams, GitLogParams, ToolCall};
+use oli_server::tools::git::{split_diff, GitInspector};
+use std::path::Path;
+use std::process::Command;
+
+fn git(dir: &Path, args: &[&str]) {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args([
+            "-c",
+            "user.name=Test Author",
+            "-c",
+            "user.email=test@example.com",
+        ])
+        .args(args)
+        .output()
+        .expect("git should be installed");
+    assert!(output.status.success(), "git {args:?} failed");
+}
+
+// A repository with two commits, then a staged edit, an unstaged edit, an
+// untracked file and a change to a file hidden by .oliignore
+fn repo() -> tempfile::TempDir {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    git(dir, &["init", "-q", "-b", "main"]);
+
+    std::fs::write(dir.join("lib.rs"), "fn one() {}\n").unwrap();
+    std::fs::write(dir.join("main.rs"), "fn main() {}\n").unwrap();
+    std::fs::write(dir.join("secrets.env"), "TOKEN=old\n").unwrap();
+    std::fs::write(dir.join(".oliignore"), "secrets.env\n").unwrap();
+    git(dir, &["add", "."]);
+    git(dir, &["commit", "-q", "-m", "Add lib and main"]);
+    std::fs::write(dir.join("lib.rs"), "fn one() {}\nfn two() {}\n").unwrap();
+    git(dir, &["commit", "-q", "-am", "Add two"]);
+
+    std::fs::write(
+        dir.join("lib.rs"),
+        "fn one() {}\nfn two() {}\nfn three() {}\n",
+    )
+    .unwrap();
+    git(dir, &["add", "lib.rs"]);
+    std::fs::write(dir.join("main.rs"), "fn main() { run() }\n").unwrap();
+    std::fs::write(dir.join("secrets.env"), "TOKEN=new\n").unwrap();
+    std::fs::write(dir.join("notes.txt"), "todo\n").unwrap();
+
+    temp_dir
+}
+
+#[test]
+fn test_status_lists_changes_without_excluded_files() {
+    let repo = repo();
+    let status = GitInspector::for_path(repo.path())
+        .unwrap()
+        .status()
+        .unwrap();
+
+    assert!(status.starts_with("Branch: main\n"), "{status}");
+    assert!(status.contains("\nM  lib.rs\n"), "{status}");
+    assert!(status.contains("\n M main.rs\n"), "{status}");
+    assert!(status.contains("\n?? notes.txt\n"), "{status}");
+    assert!(!status.contains("secrets.env"), "{status}");
+    assert!(
+        status.contains("1 files excluded by .oliignore"),
+        "{status}"
+    );
+}
+
+#[test]
+fn test_diff_of_unstaged_staged_and_revision() {
+    let repo = repo();
+    let inspector = GitInspector::for_path(repo.path()).unwrap();
+
+    let unstaged = inspector.diff(false, None, None).unwrap();
+    assert!(unstaged.contains("+fn main() { run() }"), "{unstaged}");
+    assert!(!unstaged.contains("fn three"), "{unstaged}");
+    assert!(!unstaged.contains("TOKEN"), "{unstaged}");
+
+    let staged = inspector.diff(true, None, None).unwrap();
+    assert!(staged.contains("+fn three() {}"), "{staged}");
+    assert!(!staged.contains("fn main"), "{staged}");
+
+    let since_first = inspector
+        .diff(false, Some("HEAD~1"), Some(&repo.path().join("lib.rs")))
+        .unwrap();
+    assert!(since_first.contains("+fn two() {}"), "{since_first}");
+    assert!(!since_first.contains("main.rs"), "{since_first}");
+
+    // Revisions can't smuggle in options
+    assert!(inspector
+        .diff(false, Some("--output=/tmp/x"), None)
+        .is_err());
+}
+
+#[test]
+fn test_log_lists_recent_commits() {
+    let repo = repo();
+    let inspector = GitInspector::for_path(repo.path()).unwrap();
+
+    let log = inspector.log(None, None, None).unwrap();
+    assert!(log.starts_with("2 most recent commits"), "{log}");
+    let subjects: Vec<&str> = log
+        .lines()
+        .filter_map(|line| line.split_once("Test Author: ").map(|(_, subject)| subject))
+        .collect();
+    assert_eq!(subjects, vec!["Add two", "Add lib and main"]);
+
+    let main_only = inspector
+        .log(Some(5), None, Some(&repo.path().join("main.rs")))
+        .unwrap();
+    assert!(
+        main_only.starts_with("1 most recent commits"),
+        "{main_only}"
+    );
+}
+
+#[test]
+fn test_split_diff() {
+    let diff = "diff --git a/a.rs b/a.rs\n+a\ndiff --git a/b.rs b/b.rs\n+b\n";
+    assert_eq!(
+        split_diff(diff),
+        vec![
+            "diff --git a/a.rs b/a.rs\n+a\n",
+            "diff --git a/b.rs b/b.rs\n+b\n"
+        ]
+    );
+    assert!(split_diff("").is_empty());
+}
+
+#[test]
+fn test_git_tools_run_without_approval() {
+    let repo = repo();
+    for tool in ["GitStatus", "GitDiff", "GitLog"] {
+        assert!(!ApprovalBackend::requires_approval(tool));
+    }
+
+    let output = ToolCall::GitLog(GitLogParams {
+        path: Some(repo.path().to_string_lossy().to_string()),
+        limit: Some(1),
+        revision: None,
+    })
+    .execute()
+    .unwrap();
+    assert!(output.contains("Add two"), "{output}");
+
+    // Excluded paths can't be diffed directly either
+    let hidden = ToolCall::GitDiff(GitDiffParams {
+        path: Some(
+            repo.path()
+                .join("secrets.env")
+                .to_string_lossy()
+                .to_string(),
+        ),
+        staged: None,
+        revision: None,
+    })
+    .execute();
+    assert!(hidden.is_err());
+}
+
+#[test]
+fn test_lsp_tools_refuse_excluded_files() {
+    use oli_server::tools::lsp::{
+        DefinitionParams, LspServerType, ModelsCodeLensParams, ModelsDocumentSymbolParams,
+        ModelsSemanticTokensParams, Position,
+    };
+
+    let repo = repo();
+    let file_path = repo
+        .path()
+        .join("secrets.env")
+        .to_string_lossy()
+        .to_string();
+    let calls = [
+        ToolCall::DocumentSymbol(ModelsDocumentSymbolParams {
+            file_path: file_path.clone(),
+            server_type: LspServerType::Python,
+        }),
+        ToolCall::SemanticTokens(ModelsSemanticTokensParams {
+            file_path: file_path.clone(),
+            server_type: LspServerType::Python,
+        }),
+        ToolCall::CodeLens(ModelsCodeLensParams {
+            file_path: file_path.clone(),
+            server_type: LspServerType::Python,
+        }),
+        ToolCall::Definition(DefinitionParams {
+            file_path: file_path.clone(),
+            position: Position {
+                line: 0,
+                character: 0,
+            },
+            server_type: LspServerType::Python,
+        }),
+    ];
+    for call in calls {
+        let error = call.execute().unwrap_err().to_string();
+        assert!(error.contains("path excluded by .oliignore"), "{error}");
+    }
+}
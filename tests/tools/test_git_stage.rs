@@ -0,0 +1,136 @@
+//! Tests for staging changes hunk by hunk
+
+use oli_server::tools::git_stage::{parse_diff, ChangeStatus, GitStager};
+use std::path::Path;
+use std::process::Command;
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args([
+            "-c",
+            "user.name=Test Author",
+            "-c",
+            "user.email=test@example.com",
+        ])
+        .args(args)
+        .output()
+        .expect("git should be installed");
+    assert!(output.status.success(), "git {args:?} failed");
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+// A repository with a committed file of 30 lines, then two separate edits to it
+// and an untracked file
+fn repo_with_changes() -> tempfile::TempDir {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    git(dir, &["init", "-q"]);
+
+    let lines: Vec<String> = (1..=30).map(|i| format!("line {i}")).collect();
+    std::fs::write(dir.join("notes.txt"), lines.join("\n") + "\n").unwrap();
+    git(dir, &["add", "."]);
+    git(dir, &["commit", "-q", "-m", "Add notes"]);
+
+    let mut edited = lines.clone();
+    edited[1] = "line 2 (refactor)".to_string();
+    edited[27] = "line 28 (fix)".to_string();
+    std::fs::write(dir.join("notes.txt"), edited.join("\n") + "\n").unwrap();
+    std::fs::write(dir.join("new.txt"), "new\n").unwrap();
+
+    temp_dir
+}
+
+#[test]
+fn test_parse_diff() {
+    let diff = "diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,2 +1,2 @@
+-fn old() {}
++fn new() {}
+ fn keep() {}
+@@ -10,1 +10,2 @@
+ fn end() {}
++fn added() {}
+\\ No newline at end of file
+diff --git a/gone.rs b/gone.rs
+deleted file mode 100644
+index 3333333..0000000
+--- a/gone.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-fn gone() {}
+";
+    let files = parse_diff(diff);
+
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].path, "src/lib.rs");
+    assert_eq!(files[0].status, ChangeStatus::Modified);
+    assert_eq!(files[0].hunks.len(), 2);
+    assert_eq!(files[0].hunks[0].header, "@@ -1,2 +1,2 @@");
+    assert_eq!(files[0].hunks[0].added, 1);
+    assert_eq!(files[0].hunks[0].removed, 1);
+    assert_eq!(files[0].hunks[1].lines.len(), 3);
+    assert_ne!(files[0].hunks[0].id, files[0].hunks[1].id);
+
+    assert_eq!(files[1].path, "gone.rs");
+    assert_eq!(files[1].status, ChangeStatus::Deleted);
+}
+
+#[test]
+fn test_changes_lists_hunks_and_untracked_files() {
+    let repo = repo_with_changes();
+    let stager = GitStager::for_dir(repo.path()).unwrap();
+    let changes = stager.changes().unwrap();
+
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0].path, "notes.txt");
+    assert_eq!(changes[0].hunks.len(), 2);
+    assert_eq!(changes[1].path, "new.txt");
+    assert_eq!(changes[1].status, ChangeStatus::Untracked);
+    assert!(changes[1].hunks.is_empty());
+}
+
+#[test]
+fn test_stage_hunks_separately() {
+    let repo = repo_with_changes();
+    let stager = GitStager::for_dir(repo.path()).unwrap();
+    let changes = stager.changes().unwrap();
+    let first = changes[0].hunks[0].id.clone();
+    let second = changes[0].hunks[1].id.clone();
+
+    // Stage only the second hunk; the first stays unstaged
+    stager.stage_hunk("notes.txt", &second).unwrap();
+    let staged = git(repo.path(), &["diff", "--cached"]);
+    assert!(staged.contains("+line 28 (fix)"));
+    assert!(!staged.contains("line 2 (refactor)"));
+
+    // The first hunk's ID still works after the file's staged line numbers shifted
+    let remaining = stager.changes().unwrap();
+    assert_eq!(remaining[0].hunks.len(), 1);
+    assert_eq!(remaining[0].hunks[0].id, first);
+    stager.stage_hunk("notes.txt", &first).unwrap();
+    assert!(git(repo.path(), &["diff"]).is_empty());
+
+    // Staging the same hunk again fails
+    assert!(stager.stage_hunk("notes.txt", &first).is_err());
+}
+
+#[test]
+fn test_stage_untracked_file() {
+    let repo = repo_with_changes();
+    let stager = GitStager::for_dir(&repo.path().join(".")).unwrap();
+
+    stager.stage_file("new.txt").unwrap();
+    let staged = git(repo.path(), &["diff", "--cached", "--name-only"]);
+    assert_eq!(staged.trim(), "new.txt");
+}
+
+#[test]
+fn test_outside_a_repository() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    assert!(GitStager::for_dir(temp_dir.path()).is_err());
+}
@@ -0,0 +1,186 @@
+//! Tests for reading and editing Jupyter notebooks a cell at a time
+
+use oli_server::agent::tools::ToolCall;
+use oli_server::tools::notebook::{
+    edit_cell, read_notebook, CellEditMode, Notebook, NotebookEditCellParams, NotebookReadParams,
+};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn notebook(dir: &TempDir) -> PathBuf {
+    let path = dir.path().join("analysis.ipynb");
+    let json = json!({
+        "cells": [
+            {
+                "cell_type": "markdown",
+                "id": "intro",
+                "metadata": {},
+                "source": ["# Sales\n", "Monthly totals"]
+            },
+            {
+                "cell_type": "code",
+                "execution_count": 3,
+                "id": "load",
+                "metadata": { "tags": ["setup"] },
+                "outputs": [
+                    { "name": "stdout", "output_type": "stream", "text": ["loaded 12 rows\n"] },
+                    {
+                        "data": { "image/png": "iVBORw0KGgo=" },
+                        "metadata": {},
+                        "output_type": "display_data"
+                    }
+                ],
+                "source": "import pandas as pd\ndf = pd.read_csv('sales.csv')"
+            }
+        ],
+        "metadata": { "kernelspec": { "language": "python", "name": "python3" } },
+        "nbformat": 4,
+        "nbformat_minor": 5
+    });
+    fs::write(&path, serde_json::to_string_pretty(&json).unwrap()).unwrap();
+    path
+}
+
+fn edit(path: &Path) -> NotebookEditCellParams {
+    NotebookEditCellParams {
+        file_path: path.display().to_string(),
+        cell_id: None,
+        cell_index: None,
+        new_source: None,
+        cell_type: None,
+        edit_mode: None,
+    }
+}
+
+fn cells(path: &Path) -> Vec<Value> {
+    let json: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    json["cells"].as_array().unwrap().clone()
+}
+
+#[test]
+fn test_read_lists_cells_with_ids_and_outputs() {
+    let dir = TempDir::new().unwrap();
+    let path = notebook(&dir);
+
+    let output = read_notebook(&path).unwrap();
+    assert!(output.contains("with 2 cells (python)"), "{output}");
+    assert!(output.contains("Cell 0 [markdown, id: intro]\n# Sales\nMonthly totals\n"));
+    assert!(output.contains("Cell 1 [code, id: load, execution count: 3]\nimport pandas"));
+    assert!(output.contains("  loaded 12 rows\n  [image/png output]"));
+
+    let tool = ToolCall::NotebookRead(NotebookReadParams {
+        file_path: path.display().to_string(),
+    });
+    assert_eq!(tool.execute().unwrap(), output);
+}
+
+#[test]
+fn test_replace_by_id_clears_outputs_and_keeps_the_rest() {
+    let dir = TempDir::new().unwrap();
+    let path = notebook(&dir);
+
+    let diff = edit_cell(
+        &path,
+        &NotebookEditCellParams {
+            cell_id: Some("load".to_string()),
+            new_source: Some("import polars as pl\ndf = pl.read_csv('sales.csv')".to_string()),
+            ..edit(&path)
+        },
+    )
+    .unwrap();
+    assert!(diff.contains("analysis.ipynb cell 1"), "{diff}");
+    assert!(diff.contains("import polars as pl"));
+    assert!(diff.contains("import pandas as pd"));
+
+    let cells = cells(&path);
+    assert_eq!(
+        cells[1]["source"],
+        json!(["import polars as pl\n", "df = pl.read_csv('sales.csv')"])
+    );
+    assert_eq!(cells[1]["outputs"], json!([]));
+    assert_eq!(cells[1]["execution_count"], Value::Null);
+    assert_eq!(cells[1]["metadata"]["tags"], json!(["setup"]));
+    assert_eq!(cells[0]["source"], json!(["# Sales\n", "Monthly totals"]));
+    // Written the way Jupyter writes notebooks
+    assert!(fs::read_to_string(&path)
+        .unwrap()
+        .starts_with("{\n \"cells\": ["));
+}
+
+#[test]
+fn test_insert_and_delete_by_index() {
+    let dir = TempDir::new().unwrap();
+    let path = notebook(&dir);
+
+    edit_cell(
+        &path,
+        &NotebookEditCellParams {
+            cell_index: Some(1),
+            new_source: Some("## Loading".to_string()),
+            cell_type: Some("markdown".to_string()),
+            edit_mode: Some(CellEditMode::Insert),
+            ..edit(&path)
+        },
+    )
+    .unwrap();
+    let after_insert = cells(&path);
+    assert_eq!(after_insert.len(), 3);
+    assert_eq!(after_insert[1]["cell_type"], "markdown");
+    assert_eq!(after_insert[1]["source"], json!(["## Loading"]));
+    // nbformat 4.5 notebooks need an id on every cell
+    assert!(after_insert[1]["id"]
+        .as_str()
+        .is_some_and(|id| !id.is_empty()));
+    assert!(after_insert[1].get("outputs").is_none());
+
+    let diff = edit_cell(
+        &path,
+        &NotebookEditCellParams {
+            cell_index: Some(0),
+            edit_mode: Some(CellEditMode::Delete),
+            ..edit(&path)
+        },
+    )
+    .unwrap();
+    assert!(diff.contains("0 additions"), "{diff}");
+    let after_delete = cells(&path);
+    assert_eq!(after_delete.len(), 2);
+    assert_eq!(after_delete[1]["id"], "load");
+}
+
+#[test]
+fn test_bad_edits_leave_the_notebook_alone() {
+    let dir = TempDir::new().unwrap();
+    let path = notebook(&dir);
+    let before = fs::read_to_string(&path).unwrap();
+
+    let missing_id = NotebookEditCellParams {
+        cell_id: Some("nope".to_string()),
+        new_source: Some("x = 1".to_string()),
+        ..edit(&path)
+    };
+    let out_of_range = NotebookEditCellParams {
+        cell_index: Some(7),
+        new_source: Some("x = 1".to_string()),
+        ..edit(&path)
+    };
+    let wrong_index = NotebookEditCellParams {
+        cell_id: Some("load".to_string()),
+        cell_index: Some(0),
+        new_source: Some("x = 1".to_string()),
+        ..edit(&path)
+    };
+    let no_source = NotebookEditCellParams {
+        cell_index: Some(0),
+        ..edit(&path)
+    };
+    for bad in [missing_id, out_of_range, wrong_index, no_source] {
+        assert!(edit_cell(&path, &bad).is_err(), "{bad:?}");
+    }
+    assert_eq!(fs::read_to_string(&path).unwrap(), before);
+
+    fs::write(&path, "{\"not\": \"a notebook\"}").unwrap();
+    assert!(Notebook::load(&path).is_err());
+}
@@ -0,0 +1,127 @@
+//! Tests for tracking and reaping child processes
+
+use oli_server::tools::processes::{process_alive, ChildKind, ProcessSupervisor};
+use std::fs;
+use std::process::{Child, Command};
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn sleeper() -> Child {
+    Command::new("sleep").arg("30").spawn().unwrap()
+}
+
+/// Rewrite a field of a child's record, as another instance would have written it
+fn edit_record(dir: &TempDir, pid: u32, field: &str, value: serde_json::Value) {
+    let path = dir.path().join(format!("{pid}.json"));
+    let mut record: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    record[field] = value;
+    fs::write(&path, record.to_string()).unwrap();
+}
+
+#[test]
+fn test_lists_running_children_only() {
+    let dir = TempDir::new().unwrap();
+    let supervisor = ProcessSupervisor::new(dir.path().to_path_buf());
+
+    let mut running = sleeper();
+    let mut exited = Command::new("true").spawn().unwrap();
+    exited.wait().unwrap();
+    supervisor
+        .track(ChildKind::Lsp, running.id(), "sleep 30", false)
+        .unwrap();
+    supervisor
+        .track(ChildKind::Bash, exited.id(), "true", false)
+        .unwrap();
+
+    let children = supervisor.list();
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].pid, running.id());
+    assert_eq!(children[0].kind, ChildKind::Lsp);
+    assert!(!children[0].is_orphaned());
+    // The exited child's record is cleaned up
+    assert!(!dir.path().join(format!("{}.json", exited.id())).exists());
+
+    supervisor.untrack(running.id());
+    assert!(supervisor.list().is_empty());
+    running.kill().unwrap();
+    running.wait().unwrap();
+}
+
+#[test]
+fn test_kill_stops_tracked_children_only() {
+    let dir = TempDir::new().unwrap();
+    let supervisor = ProcessSupervisor::new(dir.path().to_path_buf());
+
+    let mut child = sleeper();
+    assert!(supervisor.kill(child.id()).is_err());
+
+    supervisor
+        .track(ChildKind::Bash, child.id(), "sleep 30", false)
+        .unwrap();
+    let killed = supervisor.kill(child.id()).unwrap();
+    assert_eq!(killed.command, "sleep 30");
+    assert!(!child.wait().unwrap().success());
+    assert!(supervisor.list().is_empty());
+}
+
+#[test]
+fn test_reaps_children_of_exited_instances() {
+    let dir = TempDir::new().unwrap();
+    let supervisor = ProcessSupervisor::new(dir.path().to_path_buf());
+
+    let mut dead_owner = Command::new("true").spawn().unwrap();
+    dead_owner.wait().unwrap();
+    assert!(!process_alive(dead_owner.id()));
+
+    let mut orphan = sleeper();
+    let mut own = sleeper();
+    supervisor
+        .track(ChildKind::Lsp, orphan.id(), "sleep 30", false)
+        .unwrap();
+    supervisor
+        .track(ChildKind::Lsp, own.id(), "sleep 30", false)
+        .unwrap();
+    edit_record(&dir, orphan.id(), "owner_pid", dead_owner.id().into());
+
+    let reaped = supervisor.reap_orphans();
+    assert_eq!(reaped.len(), 1);
+    assert_eq!(reaped[0].pid, orphan.id());
+    orphan.wait().unwrap();
+
+    let remaining = supervisor.list();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].pid, own.id());
+    own.kill().unwrap();
+    own.wait().unwrap();
+}
+
+#[test]
+fn test_kills_idle_children() {
+    let dir = TempDir::new().unwrap();
+    let supervisor = ProcessSupervisor::new(dir.path().to_path_buf());
+
+    let mut idle = sleeper();
+    let mut busy = sleeper();
+    supervisor
+        .track(ChildKind::Lsp, idle.id(), "sleep 30", false)
+        .unwrap();
+    supervisor
+        .track(ChildKind::Lsp, busy.id(), "sleep 30", false)
+        .unwrap();
+    edit_record(
+        &dir,
+        idle.id(),
+        "last_active_at",
+        "2020-01-01T00:00:00+00:00".into(),
+    );
+    supervisor.touch(busy.id()).unwrap();
+
+    let killed = supervisor.kill_idle(Duration::from_secs(60));
+    assert_eq!(killed.len(), 1);
+    assert_eq!(killed[0].pid, idle.id());
+    idle.wait().unwrap();
+
+    busy.kill().unwrap();
+    busy.wait().unwrap();
+}
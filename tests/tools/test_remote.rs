@@ -0,0 +1,247 @@
+//! Tests for running tools against a remote target
+
+use oli_server::agent::tools::{GlobParams, LSParams, ToolCall};
+use oli_server::tools::fs::file_ops::FileOps;
+use oli_server::tools::remote::{
+    shell_quote, ExecutionBackend, RemoteConfig, RemoteTarget, REMOTE_TARGETS_FILE_ENV,
+};
+use oli_server::tools::shell::ShellConfig;
+use serde_json::json;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+// One remote targets file for the whole test run, so tests adding their
+// workspaces to it don't race on the environment variable
+fn targets_file() -> &'static Mutex<PathBuf> {
+    static FILE: OnceLock<Mutex<PathBuf>> = OnceLock::new();
+    FILE.get_or_init(|| {
+        let dir = tempfile::tempdir().unwrap().keep();
+        let file = dir.join("remote_targets.json");
+        std::fs::write(&file, "{}").unwrap();
+        std::env::set_var(REMOTE_TARGETS_FILE_ENV, &file);
+        Mutex::new(file)
+    })
+}
+
+// Add the entry of a workspace to the remote targets file
+fn add_target(workspace: &Path, entry: serde_json::Value) {
+    let file = targets_file().lock().unwrap();
+    let mut targets: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&*file).unwrap()).unwrap();
+    targets[workspace.to_string_lossy().as_ref()] = entry;
+    std::fs::write(&*file, targets.to_string()).unwrap();
+}
+
+// A workspace whose remote target is a local directory reached through a fake
+// container runtime, which runs `exec -i -w DIR NAME sh -c SCRIPT` in DIR
+fn container_workspace(transfer_files: bool) -> (tempfile::TempDir, PathBuf, PathBuf) {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let local = temp_dir.path().join("local");
+    let remote = temp_dir.path().join("remote");
+    std::fs::create_dir_all(&local).unwrap();
+    std::fs::create_dir_all(&remote).unwrap();
+
+    let runtime = temp_dir.path().join("docker");
+    std::fs::write(
+        &runtime,
+        "#!/bin/sh\ndir=$4\nshift 5\ncd \"$dir\" && exec \"$@\"\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&runtime, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let config = json!({
+        "target": { "type": "container", "name": "dev", "runtime": runtime },
+        "remote_root": remote,
+        "transfer_files": transfer_files,
+    });
+    add_target(&local, config);
+
+    (temp_dir, local, remote)
+}
+
+fn remote_config(backend: &ExecutionBackend) -> &RemoteConfig {
+    match backend {
+        ExecutionBackend::Remote(config) => config,
+        ExecutionBackend::Local => panic!("expected a remote backend"),
+    }
+}
+
+#[test]
+fn test_config_is_found_from_nested_paths() {
+    let (_temp_dir, local, remote) = container_workspace(false);
+
+    let backend = ExecutionBackend::for_path(&local.join("src").join("main.rs"));
+    let config = remote_config(&backend);
+    assert_eq!(config.local_root, local);
+    assert_eq!(config.remote_root, remote);
+    assert!(!backend.transfers_files());
+
+    assert!(ExecutionBackend::for_path(Path::new("/")).is_local());
+}
+
+#[test]
+fn test_malformed_config_falls_back_to_local() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    add_target(temp_dir.path(), json!({ "target": 1 }));
+
+    assert!(ExecutionBackend::for_path(temp_dir.path()).is_local());
+}
+
+#[test]
+fn test_workspace_files_do_not_choose_a_target() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    targets_file();
+    std::fs::create_dir_all(temp_dir.path().join(".oli")).unwrap();
+    let config = json!({
+        "target": { "type": "container", "name": "dev" },
+        "remote_root": "/workspaces/project",
+    });
+    std::fs::write(temp_dir.path().join(".oli/remote.json"), config.to_string()).unwrap();
+
+    assert!(ExecutionBackend::for_path(temp_dir.path()).is_local());
+}
+
+#[test]
+fn test_targets_that_would_inject_options_are_ignored() {
+    for target in [
+        json!({ "type": "ssh", "host": "-oProxyCommand=touch /tmp/pwned" }),
+        json!({ "type": "ssh", "host": "build-box", "user": "-oProxyCommand=id" }),
+        json!({ "type": "container", "name": "dev", "runtime": "/bin/sh" }),
+        json!({ "type": "container", "name": "--privileged" }),
+    ] {
+        let temp_dir = tempfile::tempdir().unwrap();
+        add_target(
+            temp_dir.path(),
+            json!({ "target": target, "remote_root": "/workspaces/project" }),
+        );
+        assert!(
+            ExecutionBackend::for_path(temp_dir.path()).is_local(),
+            "{target} was accepted"
+        );
+    }
+
+    let podman: RemoteTarget =
+        serde_json::from_value(json!({ "type": "container", "name": "dev", "runtime": "podman" }))
+            .unwrap();
+    assert!(podman.validate().is_ok());
+}
+
+#[test]
+fn test_paths_are_translated_into_remote_root() {
+    let (_temp_dir, local, remote) = container_workspace(false);
+    let backend = ExecutionBackend::for_path(&local);
+
+    assert_eq!(backend.translate_path(&local), remote);
+    assert_eq!(
+        backend.translate_path(&local.join("src/lib.rs")),
+        remote.join("src/lib.rs")
+    );
+    // Outside the workspace
+    assert_eq!(
+        backend.translate_path(Path::new("/etc/hosts")),
+        PathBuf::from("/etc/hosts")
+    );
+    assert_eq!(
+        ExecutionBackend::Local.translate_path(&local),
+        local.to_path_buf()
+    );
+}
+
+#[test]
+fn test_commands_run_in_translated_directory() {
+    let (_temp_dir, local, remote) = container_workspace(false);
+    std::fs::create_dir_all(local.join("src")).unwrap();
+    std::fs::create_dir_all(remote.join("src")).unwrap();
+    let backend = ExecutionBackend::for_path(&local);
+
+    let output = backend
+        .command(&ShellConfig::default(), &local.join("src"), "pwd")
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        remote.join("src").to_string_lossy()
+    );
+
+    let output = ExecutionBackend::Local
+        .command(&ShellConfig::default(), &local, "pwd")
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        local.to_string_lossy()
+    );
+}
+
+#[test]
+fn test_ssh_command_arguments() {
+    let config: RemoteConfig = serde_json::from_value(json!({
+        "target": { "type": "ssh", "host": "build-box", "user": "dev", "port": 2222 },
+        "remote_root": "/home/dev/project",
+    }))
+    .unwrap();
+    assert!(matches!(config.target, RemoteTarget::Ssh { .. }));
+    let backend = ExecutionBackend::Remote(RemoteConfig {
+        local_root: PathBuf::from("/work/project"),
+        ..config
+    });
+
+    let command = backend.command(
+        &ShellConfig::default(),
+        Path::new("/work/project/src"),
+        "cargo test",
+    );
+    let args: Vec<String> = command
+        .get_args()
+        .map(|arg| arg.to_string_lossy().to_string())
+        .collect();
+
+    assert_eq!(command.get_program(), "ssh");
+    assert_eq!(
+        args,
+        vec![
+            "-o",
+            "BatchMode=yes",
+            "-p",
+            "2222",
+            "--",
+            "dev@build-box",
+            "cd '/home/dev/project/src' && cargo test"
+        ]
+    );
+    assert_eq!(shell_quote("it's"), r"'it'\''s'");
+}
+
+#[test]
+fn test_files_are_transferred_to_and_from_target() {
+    let (_temp_dir, local, remote) = container_workspace(true);
+    let file = local.join("src").join("notes.txt");
+
+    let diff = FileOps::write_file_with_diff(&file, "first line\n").unwrap();
+    assert!(diff.contains("first line"));
+    assert!(!file.exists());
+    assert_eq!(
+        std::fs::read_to_string(remote.join("src/notes.txt")).unwrap(),
+        "first line\n"
+    );
+
+    FileOps::edit_file(&file, "first", "only", None).unwrap();
+    assert_eq!(FileOps::read_file(&file).unwrap(), "only line\n");
+    assert!(FileOps::read_file(&local.join("missing.txt")).is_err());
+
+    let listing = ToolCall::LS(LSParams {
+        path: local.join("src").to_string_lossy().to_string(),
+        ignore: None,
+    })
+    .execute()
+    .unwrap();
+    assert!(listing.contains("[FILE] notes.txt"));
+
+    let glob = ToolCall::Glob(GlobParams {
+        pattern: "**/*.txt".to_string(),
+        path: Some(local.to_string_lossy().to_string()),
+    })
+    .execute();
+    assert!(glob.unwrap_err().to_string().contains("Use Bash"));
+}
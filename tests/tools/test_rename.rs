@@ -0,0 +1,160 @@
+//! Tests for project-wide renames
+
+use oli_server::tools::rename::{
+    apply_text_edits, rename_hunks, workspace_edit_files, RenameSource, SymbolRenamer, TextEdit,
+};
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+fn workspace() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("lib.ts"),
+        "export function loadConfig() {}\n// loadConfig reads the file\nconst loadConfigs = 1;\n",
+    )
+    .unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(
+        dir.path().join("src/main.ts"),
+        "import { loadConfig } from '../lib';\n\nloadConfig();\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("README.md"), "Nothing to see here\n").unwrap();
+    dir
+}
+
+fn read(dir: &TempDir, path: &str) -> String {
+    fs::read_to_string(dir.path().join(path)).unwrap()
+}
+
+#[test]
+fn test_plan_renames_whole_words_without_writing() {
+    let dir = workspace();
+    let plan = SymbolRenamer::new(dir.path())
+        .without_lsp()
+        .plan("loadConfig", "readConfig")
+        .unwrap();
+
+    assert_eq!(plan.source, RenameSource::Text);
+    assert_eq!(plan.occurrences(), 4);
+    let paths: Vec<&str> = plan.files.iter().map(|file| file.path.as_str()).collect();
+    assert_eq!(paths, vec!["lib.ts", "src/main.ts"]);
+
+    let hunk = &plan.files[1].hunks[0];
+    assert!(hunk
+        .lines
+        .contains(&"-import { loadConfig } from '../lib';".to_string()));
+    assert!(hunk
+        .lines
+        .contains(&"+import { readConfig } from '../lib';".to_string()));
+
+    // Nothing is written until the plan is applied
+    assert!(read(&dir, "src/main.ts").contains("loadConfig"));
+}
+
+#[test]
+fn test_apply_and_undo() {
+    let dir = workspace();
+    let plan = SymbolRenamer::new(dir.path())
+        .without_lsp()
+        .plan("loadConfig", "readConfig")
+        .unwrap();
+
+    let applied = plan.apply().unwrap();
+    assert_eq!(applied.file_count(), 2);
+    assert_eq!(
+        read(&dir, "lib.ts"),
+        "export function readConfig() {}\n// readConfig reads the file\nconst loadConfigs = 1;\n"
+    );
+    assert_eq!(
+        read(&dir, "src/main.ts"),
+        "import { readConfig } from '../lib';\n\nreadConfig();\n"
+    );
+
+    applied.undo().unwrap();
+    assert!(read(&dir, "lib.ts").starts_with("export function loadConfig()"));
+    assert!(read(&dir, "src/main.ts").contains("loadConfig();"));
+}
+
+#[test]
+fn test_apply_writes_nothing_if_a_file_changed_since_the_preview() {
+    let dir = workspace();
+    let plan = SymbolRenamer::new(dir.path())
+        .without_lsp()
+        .plan("loadConfig", "readConfig")
+        .unwrap();
+
+    fs::write(dir.path().join("src/main.ts"), "loadConfig(true);\n").unwrap();
+
+    let error = plan.apply().unwrap_err();
+    assert!(error.to_string().contains("main.ts"));
+    assert!(read(&dir, "lib.ts").contains("loadConfig"));
+}
+
+#[test]
+fn test_plan_rejects_invalid_names() {
+    let dir = workspace();
+    let renamer = SymbolRenamer::new(dir.path()).without_lsp();
+
+    assert!(renamer.plan("loadConfig", "read-config").is_err());
+    assert!(renamer.plan("loadConfig", "loadConfig").is_err());
+    assert!(renamer.plan("missingName", "other").is_err());
+}
+
+#[test]
+fn test_workspace_edits_from_changes_and_document_changes() {
+    let edit = json!({
+        "changes": {
+            "file:///tmp/a.rs": [
+                { "range": { "start": { "line": 0, "character": 3 }, "end": { "line": 0, "character": 6 } }, "newText": "bar" }
+            ]
+        },
+        "documentChanges": [
+            {
+                "textDocument": { "uri": "file:///tmp/b.rs", "version": 1 },
+                "edits": [
+                    { "range": { "start": { "line": 1, "character": 0 }, "end": { "line": 1, "character": 3 } }, "newText": "bar" }
+                ]
+            },
+            { "kind": "create", "uri": "file:///tmp/c.rs" }
+        ]
+    });
+
+    let files = workspace_edit_files(&edit);
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[&PathBuf::from("/tmp/a.rs")][0].new_text, "bar");
+    assert_eq!(files[&PathBuf::from("/tmp/b.rs")][0].start, (1, 0));
+}
+
+#[test]
+fn test_text_edits_count_utf16_units() {
+    let content = "let é = foo;\nfoo(é);\n";
+    let edits = vec![
+        TextEdit {
+            start: (0, 8),
+            end: (0, 11),
+            new_text: "bar".to_string(),
+        },
+        TextEdit {
+            start: (1, 0),
+            end: (1, 3),
+            new_text: "bar".to_string(),
+        },
+    ];
+
+    assert_eq!(apply_text_edits(content, &edits), "let é = bar;\nbar(é);\n");
+}
+
+#[test]
+fn test_hunks_group_nearby_changes() {
+    let original = "a\nfoo\nb\nc\nfoo\nd\ne\nf\ng\nh\ni\nfoo\n";
+    let updated = original.replace("foo", "bar");
+
+    let hunks = rename_hunks(original, &updated);
+    assert_eq!(hunks.len(), 2);
+    assert_eq!(hunks[0].removed, 2);
+    assert_eq!(hunks[0].header, "@@ -1,7 +1,7 @@");
+    assert_eq!(hunks[1].lines.last().unwrap(), "+bar");
+}
@@ -0,0 +1,56 @@
+//! Tests for the shell Bash commands run in
+
+use oli_server::tools::shell::{EnvSnapshot, ShellConfig};
+use std::path::PathBuf;
+
+#[test]
+fn test_command_runs_in_configured_shell() {
+    let output = ShellConfig::default().command("echo $0").output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "sh");
+
+    let args: Vec<String> = ShellConfig::default()
+        .with_shell("bash")
+        .with_login(true)
+        .command("true")
+        .get_args()
+        .map(|arg| arg.to_string_lossy().to_string())
+        .collect();
+    assert_eq!(args, vec!["-l", "-c", "true"]);
+}
+
+#[test]
+fn test_rc_file_is_sourced_before_command() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let rc_file = temp_dir.path().join("oli_rc");
+    std::fs::write(
+        &rc_file,
+        "export VIRTUAL_ENV=/work/.venv\nexport PATH=/work/.venv/bin:$PATH\n",
+    )
+    .unwrap();
+    let config = ShellConfig::default().with_rc_file(Some(rc_file.clone()));
+
+    let output = config.command("echo $VIRTUAL_ENV").output().unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "/work/.venv"
+    );
+
+    let snapshot = EnvSnapshot::capture(&config).unwrap();
+    assert_eq!(snapshot.shell.rc_file, Some(rc_file));
+    assert_eq!(snapshot.variables["VIRTUAL_ENV"], "/work/.venv");
+    assert_eq!(snapshot.path_entries()[0], "/work/.venv/bin");
+
+    let summary = snapshot.summary();
+    assert!(summary.starts_with("Shell: sh, sourcing "));
+    assert!(summary.contains("Python virtualenv: /work/.venv"));
+    assert!(summary.contains("PATH:\n  /work/.venv/bin"));
+}
+
+#[test]
+fn test_capture_fails_for_missing_shell() {
+    let config = ShellConfig::default().with_shell("/nonexistent/shell");
+    assert!(EnvSnapshot::capture(&config).is_err());
+
+    let config = ShellConfig::default().with_rc_file(Some(PathBuf::from("/nonexistent/rc")));
+    assert!(EnvSnapshot::capture(&config).is_err());
+}
@@ -0,0 +1,101 @@
+//! Tests for the persistent shell Bash commands can run in
+
+use oli_server::tools::shell::ShellConfig;
+use oli_server::tools::shell_session::{run_in_session, ShellSession, DEFAULT_COMMAND_TIMEOUT};
+use std::time::Duration;
+
+#[test]
+fn test_directory_and_variables_carry_over_between_commands() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    let mut session = ShellSession::start(&ShellConfig::default(), dir.path()).unwrap();
+
+    let cd = session
+        .run("cd sub && export GREETING=hello", DEFAULT_COMMAND_TIMEOUT)
+        .unwrap();
+    assert!(cd.success());
+    assert_eq!(cd.stdout, "");
+    assert!(session.current_dir().ends_with("sub"));
+
+    let output = session
+        .run(
+            "basename \"$PWD\"; echo \"$GREETING\"",
+            DEFAULT_COMMAND_TIMEOUT,
+        )
+        .unwrap();
+    assert_eq!(output.stdout, "sub\nhello\n");
+
+    // Output is kept exactly, without a trailing newline, and stderr separately
+    let output = session
+        .run(
+            "printf partial; echo oops >&2; false",
+            DEFAULT_COMMAND_TIMEOUT,
+        )
+        .unwrap();
+    assert_eq!(output.stdout, "partial");
+    assert_eq!(output.stderr, "oops\n");
+    assert_eq!(output.exit_code, 1);
+
+    // A syntax error fails the command but not the shell
+    let output = session.run("if then", DEFAULT_COMMAND_TIMEOUT).unwrap();
+    assert!(!output.success());
+    assert!(session.is_alive());
+    let output = session
+        .run("echo \"$GREETING\"", DEFAULT_COMMAND_TIMEOUT)
+        .unwrap();
+    assert_eq!(output.stdout, "hello\n");
+}
+
+#[test]
+fn test_large_output_arrives_whole() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut session = ShellSession::start(&ShellConfig::default(), dir.path()).unwrap();
+
+    // Many chunks, each searched for the end marker only once
+    let output = session
+        .run(
+            "head -c 20000000 /dev/zero | tr '\\0' a; echo done >&2",
+            DEFAULT_COMMAND_TIMEOUT,
+        )
+        .unwrap();
+    assert!(output.success());
+    assert_eq!(output.stdout.len(), 20_000_000);
+    assert!(output.stdout.bytes().all(|byte| byte == b'a'));
+    assert_eq!(output.stderr, "done\n");
+}
+
+#[test]
+fn test_rc_file_is_sourced_once_and_timeouts_restart_the_shell() {
+    let dir = tempfile::tempdir().unwrap();
+    let rc_file = dir.path().join("oli_rc");
+    std::fs::write(&rc_file, "export SOURCED=$((${SOURCED:-0} + 1))\n").unwrap();
+    let config = ShellConfig::default()
+        .with_rc_file(Some(rc_file))
+        .with_persistent(true);
+
+    let mut session = ShellSession::start(&config, dir.path()).unwrap();
+    for _ in 0..2 {
+        let output = session
+            .run("echo $SOURCED", DEFAULT_COMMAND_TIMEOUT)
+            .unwrap();
+        assert_eq!(output.stdout, "1\n");
+    }
+    drop(session);
+
+    run_in_session(&config, dir.path(), "export STATE=kept", None).unwrap();
+    let output = run_in_session(&config, dir.path(), "echo $STATE", None).unwrap();
+    assert_eq!(output.stdout, "kept\n");
+
+    let error = run_in_session(
+        &config,
+        dir.path(),
+        "sleep 5",
+        Some(Duration::from_millis(200)),
+    )
+    .unwrap_err();
+    assert!(format!("{error:#}").contains("timed out"), "{error:#}");
+
+    // The next command gets a fresh shell
+    let output = run_in_session(&config, dir.path(), "echo \"[$STATE]\"", None).unwrap();
+    assert_eq!(output.stdout, "[]\n");
+}
@@ -0,0 +1,166 @@
+//! Tests for the RunTests tool's framework detection and result parsing
+
+use oli_server::agent::approval::ApprovalBackend;
+use oli_server::tools::test_runner::{TestFramework, TestSummary};
+use tempfile::tempdir;
+
+#[test]
+fn test_detect_framework() {
+    let dir = tempdir().unwrap();
+    assert_eq!(TestFramework::detect(dir.path()), None);
+
+    // npm init's placeholder script isn't a test suite
+    std::fs::write(
+        dir.path().join("package.json"),
+        r#"{"scripts": {"test": "echo \"Error: no test specified\" && exit 1"}}"#,
+    )
+    .unwrap();
+    assert_eq!(TestFramework::detect(dir.path()), None);
+    std::fs::write(
+        dir.path().join("package.json"),
+        r#"{"scripts": {"test": "jest"}}"#,
+    )
+    .unwrap();
+    assert_eq!(TestFramework::detect(dir.path()), Some(TestFramework::Npm));
+
+    std::fs::write(dir.path().join("pyproject.toml"), "[project]\n").unwrap();
+    assert_eq!(
+        TestFramework::detect(dir.path()),
+        Some(TestFramework::Pytest)
+    );
+
+    std::fs::write(dir.path().join("Cargo.toml"), "[package]\n").unwrap();
+    assert_eq!(
+        TestFramework::detect(dir.path()),
+        Some(TestFramework::Cargo)
+    );
+
+    assert_eq!(
+        TestFramework::parse("PyTest").unwrap(),
+        TestFramework::Pytest
+    );
+    assert!(TestFramework::parse("maven").is_err());
+}
+
+#[test]
+fn test_commands_quote_the_filter() {
+    assert_eq!(TestFramework::Cargo.command(None), "cargo test");
+    assert_eq!(
+        TestFramework::Cargo.command(Some("config::parse")),
+        "cargo test 'config::parse'"
+    );
+    assert_eq!(
+        TestFramework::Pytest.command(Some("tests/test_api.py::test_get")),
+        "python -m pytest -q -rfE 'tests/test_api.py::test_get'"
+    );
+    assert_eq!(
+        TestFramework::Npm.command(Some("it's")),
+        r"npm test -- 'it'\''s'"
+    );
+    assert_eq!(TestFramework::Npm.command(Some("  ")), "npm test");
+}
+
+#[test]
+fn test_parse_cargo_output() {
+    let output = "\
+running 3 tests
+test config::tests::parses ... ok
+test config::tests::rejects_bad_port ... FAILED
+test config::tests::slow ... ignored
+
+failures:
+
+---- config::tests::rejects_bad_port stdout ----
+thread 'config::tests::rejects_bad_port' panicked at src/config.rs:40:9:
+assertion `left == right` failed
+  left: 8080
+ right: 0
+
+failures:
+    config::tests::rejects_bad_port
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.01s
+
+running 2 tests
+test it_works ... ok
+test it_still_works ... ok
+
+test result: ok. 2 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+";
+    let summary = TestSummary::parse(TestFramework::Cargo, "cargo test", output, 101);
+    assert!(summary.counted);
+    assert_eq!((summary.passed, summary.failed, summary.ignored), (3, 1, 1));
+    assert_eq!(summary.failing, vec!["config::tests::rejects_bad_port"]);
+    assert!(summary
+        .failure_output
+        .contains("panicked at src/config.rs:40:9"));
+    assert!(!summary.failure_output.contains("it_works"));
+
+    let report = summary.report();
+    assert!(report.contains("Result: FAILED: 3 passed, 1 failed, 1 ignored (exit code 101)"));
+    assert!(report.contains("- config::tests::rejects_bad_port"));
+}
+
+#[test]
+fn test_build_errors_keep_the_end_of_the_output() {
+    let output = "   Compiling demo v0.1.0\nerror[E0425]: cannot find value `x` in this scope\n";
+    let summary = TestSummary::parse(TestFramework::Cargo, "cargo test", output, 101);
+    assert!(!summary.counted);
+    assert!(!summary.success());
+    assert!(summary.failure_output.contains("error[E0425]"));
+    assert!(summary.report().contains("no test counts found"));
+}
+
+#[test]
+fn test_parse_pytest_output() {
+    let output = "\
+..F.s                                                                    [100%]
+=================================== FAILURES ===================================
+__________________________________ test_total __________________________________
+
+    def test_total():
+>       assert total([1, 2]) == 4
+E       assert 3 == 4
+
+tests/test_cart.py:8: AssertionError
+=========================== short test summary info ============================
+FAILED tests/test_cart.py::test_total - assert 3 == 4
+==================== 1 failed, 3 passed, 1 skipped in 0.04s ====================
+";
+    let summary = TestSummary::parse(TestFramework::Pytest, "pytest", output, 1);
+    assert_eq!((summary.passed, summary.failed, summary.ignored), (3, 1, 1));
+    assert_eq!(summary.failing, vec!["tests/test_cart.py::test_total"]);
+    assert!(summary.failure_output.contains("E       assert 3 == 4"));
+    assert!(!summary.failure_output.contains("short test summary"));
+}
+
+#[test]
+fn test_parse_npm_output() {
+    let jest = "\
+ FAIL  src/cart.test.js
+  ● cart › adds up prices
+
+    expect(received).toBe(expected)
+
+Tests:       1 failed, 5 passed, 6 total
+";
+    let summary = TestSummary::parse(TestFramework::Npm, "npm test", jest, 1);
+    assert_eq!((summary.passed, summary.failed), (5, 1));
+    assert_eq!(summary.failing, vec!["cart › adds up prices"]);
+
+    let mocha = "\
+  7 passing (20ms)
+  1 pending
+
+  0 failing
+";
+    let summary = TestSummary::parse(TestFramework::Npm, "npm test", mocha, 0);
+    assert_eq!((summary.passed, summary.failed, summary.ignored), (7, 0, 1));
+    assert!(summary.success());
+    assert!(summary.report().contains("Result: PASSED"));
+}
+
+#[test]
+fn test_run_tests_needs_no_approval() {
+    assert!(!ApprovalBackend::requires_approval("RunTests"));
+}